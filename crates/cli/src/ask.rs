@@ -0,0 +1,89 @@
+use crate::search::{print_results, KnowledgeBaseArgs};
+use anyhow::Result;
+use anyrag::{
+    context_sanitization::document_context,
+    types::{ContentType, ExecutePromptOptions},
+    PromptClientBuilder,
+};
+use clap::Parser;
+use serde_json::json;
+use tracing::info;
+
+/// The answer given when the search finds nothing to answer from.
+const NO_CONTEXT_ANSWER: &str =
+    "I could not find any relevant information to answer your question.";
+
+#[derive(Parser, Debug)]
+pub struct AskArgs {
+    /// The question to answer from the knowledge base
+    #[arg(required = true)]
+    question: String,
+    /// An instruction on how to write the answer, such as "Answer in one sentence."
+    #[arg(long)]
+    instruction: Option<String>,
+    /// The number of search results to answer from
+    #[arg(long, default_value_t = 5)]
+    limit: u32,
+    /// Print the answer as JSON
+    #[arg(long)]
+    json: bool,
+    /// Also print the retrieved chunks the answer was written from
+    #[arg(long)]
+    show_context: bool,
+    #[command(flatten)]
+    knowledge_base: KnowledgeBaseArgs,
+}
+
+pub async fn handle_ask(args: &AskArgs) -> Result<()> {
+    let knowledge_base = &args.knowledge_base;
+    info!(
+        "Answering '{}' from '{}'",
+        args.question, knowledge_base.db_path
+    );
+    let sqlite_provider = knowledge_base.open_database().await?;
+    let ai_provider = knowledge_base.ai_provider("Ask")?;
+
+    let results = knowledge_base
+        .hybrid_search(
+            sqlite_provider.clone(),
+            ai_provider.clone(),
+            &args.question,
+            args.limit,
+        )
+        .await?;
+
+    let answer = if results.is_empty() {
+        NO_CONTEXT_ANSWER.to_string()
+    } else {
+        let prompt = match args.instruction.as_deref().filter(|s| !s.is_empty()) {
+            Some(instruction) => format!("{}\n\n{instruction}", args.question),
+            None => args.question.clone(),
+        };
+        let options = ExecutePromptOptions {
+            prompt,
+            content_type: Some(ContentType::Knowledge),
+            context: Some(document_context(&results, None).context),
+            ..Default::default()
+        };
+        let client = PromptClientBuilder::new()
+            .ai_provider(Box::new(ai_provider))
+            .storage_provider(Box::new(sqlite_provider))
+            .build()?;
+        client.execute_prompt_with_options(options).await?.text
+    };
+
+    if args.json {
+        let mut output = json!({ "question": args.question, "answer": answer });
+        if args.show_context {
+            output["context"] = json!(results);
+        }
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+    if args.show_context {
+        print_results(&args.question, &results);
+        println!();
+    }
+    println!("💡 {answer}");
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use anyhow::{bail, Result};
+use anyrag::providers::db::sqlite::{
+    backup::{backup_to_file, restore_from_file},
+    SqliteProvider,
+};
+use anyrag_objectstore::client::{s3_endpoint, ObjectStoreClient, DEFAULT_REGION};
+use clap::Parser;
+use std::path::Path;
+use tracing::info;
+
+#[derive(Parser, Debug)]
+pub struct BackupArgs {
+    /// The path of the backup file to create
+    #[arg(required = true)]
+    output: String,
+    /// The path to the database file to back up
+    #[arg(long, env = "ANYRAG_DB_PATH", default_value = anyrag::constants::DEFAULT_DB_FILE)]
+    db_path: String,
+    /// Also uploads the backup to this bucket of the object store
+    #[arg(long, requires = "key")]
+    bucket: Option<String>,
+    /// The key of the uploaded backup object
+    #[arg(long, requires = "bucket")]
+    key: Option<String>,
+    #[command(flatten)]
+    object_store: ObjectStoreArgs,
+}
+
+#[derive(Parser, Debug)]
+pub struct RestoreArgs {
+    /// The path of the backup file to restore. With `--bucket`, the backup is downloaded
+    /// to this path first.
+    #[arg(required = true)]
+    input: String,
+    /// The path to the database file to restore into. It must not hold any documents.
+    #[arg(long, env = "ANYRAG_DB_PATH", default_value = anyrag::constants::DEFAULT_DB_FILE)]
+    db_path: String,
+    /// Downloads the backup from this bucket of the object store
+    #[arg(long, requires = "key")]
+    bucket: Option<String>,
+    /// The key of the backup object to download
+    #[arg(long, requires = "bucket")]
+    key: Option<String>,
+    #[command(flatten)]
+    object_store: ObjectStoreArgs,
+}
+
+/// The S3-compatible store backups are uploaded to and downloaded from.
+#[derive(Parser, Debug)]
+struct ObjectStoreArgs {
+    /// The endpoint of the object store. Defaults to the Amazon S3 endpoint of the region.
+    #[arg(long, env = "OBJECT_STORE_ENDPOINT")]
+    endpoint: Option<String>,
+    /// The region requests are signed for
+    #[arg(long, env = "OBJECT_STORE_REGION", default_value = DEFAULT_REGION)]
+    region: String,
+    /// The access key id of the object store. Requests are unsigned without it.
+    #[arg(
+        long,
+        env = "OBJECT_STORE_ACCESS_KEY_ID",
+        requires = "secret_access_key"
+    )]
+    access_key_id: Option<String>,
+    /// The secret of the object store access key
+    #[arg(long, env = "OBJECT_STORE_SECRET_ACCESS_KEY", hide_env_values = true)]
+    secret_access_key: Option<String>,
+}
+
+impl ObjectStoreArgs {
+    fn client(&self) -> ObjectStoreClient {
+        let endpoint = self
+            .endpoint
+            .clone()
+            .unwrap_or_else(|| s3_endpoint(&self.region));
+        let client = ObjectStoreClient::new(&endpoint, &self.region);
+        match (&self.access_key_id, &self.secret_access_key) {
+            (Some(access_key_id), Some(secret_access_key)) => {
+                client.with_credentials(access_key_id, secret_access_key)
+            }
+            _ => client,
+        }
+    }
+}
+
+pub async fn handle_backup(args: &BackupArgs) -> Result<()> {
+    if !Path::new(&args.db_path).exists() {
+        bail!("Database file '{}' not found.", args.db_path);
+    }
+    info!("Backing up '{}' to '{}'", args.db_path, args.output);
+    println!("💾 Backing up '{}'...", args.db_path);
+
+    let sqlite_provider = SqliteProvider::new(&args.db_path).await?;
+    let summary = backup_to_file(&sqlite_provider.db, Path::new(&args.output)).await?;
+    println!(
+        "✅ Backed up {} rows to '{}'.",
+        summary.total_rows(),
+        args.output
+    );
+
+    if let (Some(bucket), Some(key)) = (&args.bucket, &args.key) {
+        let client = args.object_store.client();
+        client
+            .put_object(bucket, key, std::fs::read(&args.output)?)
+            .await?;
+        println!(
+            "☁️  Uploaded the backup to '{}'.",
+            client.object_url(bucket, key)
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn handle_restore(args: &RestoreArgs) -> Result<()> {
+    if let (Some(bucket), Some(key)) = (&args.bucket, &args.key) {
+        if Path::new(&args.input).exists() {
+            bail!(
+                "'{}' already exists; choose a new path to download the backup to.",
+                args.input
+            );
+        }
+        let client = args.object_store.client();
+        println!("☁️  Downloading '{}'...", client.object_url(bucket, key));
+        std::fs::write(&args.input, client.get_object(bucket, key).await?)?;
+    }
+    info!("Restoring '{}' into '{}'", args.input, args.db_path);
+    println!("📦 Restoring '{}'...", args.input);
+
+    // Ensure the db directory exists before trying to create the database.
+    if let Some(parent) = Path::new(&args.db_path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let sqlite_provider = SqliteProvider::new(&args.db_path).await?;
+    let summary = restore_from_file(Path::new(&args.input), &sqlite_provider.db).await?;
+    println!(
+        "✅ Restored {} rows into '{}'.",
+        summary.total_rows(),
+        args.db_path
+    );
+
+    Ok(())
+}
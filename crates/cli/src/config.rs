@@ -0,0 +1,63 @@
+use anyhow::Result;
+use anyrag::cli_config::{config_path, CliConfig, CONFIG_KEYS, SECRET_KEYS};
+use clap::{Parser, Subcommand};
+
+#[derive(Parser, Debug)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    command: ConfigCommands,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommands {
+    /// Change a setting. API keys are stored in the system keyring. An empty value clears it.
+    Set(SetArgs),
+    /// Print a setting
+    Get(GetArgs),
+    /// Print every setting, with the environment variable that overrides it
+    List,
+}
+
+#[derive(Parser, Debug)]
+struct SetArgs {
+    /// The setting to change, such as `ai_api_url` or `db_path`
+    key: String,
+    /// The new value of the setting
+    value: String,
+}
+
+#[derive(Parser, Debug)]
+struct GetArgs {
+    /// The setting to print
+    key: String,
+}
+
+pub async fn handle_config(args: &ConfigArgs) -> Result<()> {
+    let path = config_path()?;
+    let mut config = CliConfig::load(&path)?;
+    match &args.command {
+        ConfigCommands::Set(set_args) => {
+            config.set(&set_args.key, &set_args.value)?;
+            config.save(&path)?;
+            println!("✅ Set '{}' in '{}'.", set_args.key, path.display());
+        }
+        ConfigCommands::Get(get_args) => match config.get(&get_args.key)? {
+            Some(value) => println!("{value}"),
+            None => println!("'{}' is not set.", get_args.key),
+        },
+        ConfigCommands::List => {
+            println!("# {}", path.display());
+            for (key, variable) in CONFIG_KEYS {
+                let value = match config.get(key) {
+                    // Secrets are not printed by `list`, only whether they are set.
+                    Ok(Some(_)) if SECRET_KEYS.contains(key) => "(set)".to_string(),
+                    Ok(Some(value)) => value,
+                    Ok(None) => "(not set)".to_string(),
+                    Err(e) => format!("(unavailable: {e})"),
+                };
+                println!("{key} = {value}  [{variable}]");
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use anyhow::{bail, Result};
+use anyrag::{
+    ingest::{find_unembedded_documents, store_document_embeddings, UnembeddedDocument},
+    providers::{ai::generate_embeddings_batch, db::sqlite::SqliteProvider},
+};
+use clap::Parser;
+use futures::stream::{self, StreamExt};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::Path;
+use tracing::{info, warn};
+
+#[derive(Parser, Debug)]
+pub struct EmbedArgs {
+    /// The path to the database file whose documents to embed
+    #[arg(long, visible_alias = "db", env = "ANYRAG_DB_PATH", default_value = anyrag::constants::DEFAULT_DB_FILE)]
+    db_path: String,
+    /// The most documents to embed. All documents without an embedding when omitted.
+    #[arg(long)]
+    limit: Option<usize>,
+    /// The API URL for the embedding model
+    #[arg(long, env = "EMBEDDINGS_API_URL")]
+    embedding_api_url: Option<String>,
+    /// The embedding model. Documents without an embedding of this model are embedded.
+    #[arg(long, visible_alias = "embedding-model", env = "EMBEDDINGS_MODEL")]
+    model: Option<String>,
+    /// The number of documents sent to the embedding API in one request
+    #[arg(long, default_value_t = 16)]
+    batch_size: usize,
+    /// The number of requests to the embedding API in flight at once
+    #[arg(long, default_value_t = 4)]
+    concurrency: usize,
+}
+
+pub async fn handle_embed(args: &EmbedArgs) -> Result<()> {
+    let (Some(api_url), Some(model)) = (&args.embedding_api_url, &args.model) else {
+        bail!("Embed needs `--embedding-api-url` and `--model` (or EMBEDDINGS_API_URL and EMBEDDINGS_MODEL).");
+    };
+    if !Path::new(&args.db_path).exists() {
+        bail!("Database file '{}' not found.", args.db_path);
+    }
+    let sqlite_provider = SqliteProvider::new(&args.db_path).await?;
+    sqlite_provider.initialize_schema().await?;
+    let conn = sqlite_provider.db.connect()?;
+
+    let documents = find_unembedded_documents(&conn, model, args.limit).await?;
+    if documents.is_empty() {
+        println!("No documents without a '{model}' embedding.");
+        return Ok(());
+    }
+    info!(
+        "Embedding {} documents of '{}' with '{model}'",
+        documents.len(),
+        args.db_path
+    );
+    println!(
+        "🧮 Embedding {} documents with '{model}'...",
+        documents.len()
+    );
+
+    let progress = ProgressBar::new(documents.len() as u64);
+    progress.set_style(ProgressStyle::with_template(
+        "{bar:40} {pos}/{len} documents ({eta} left)",
+    )?);
+    let api_key = std::env::var("AI_API_KEY").ok();
+    let mut requests = stream::iter(documents.chunks(args.batch_size.max(1)))
+        .map(|batch| {
+            let api_key = api_key.as_deref();
+            async move {
+                let texts: Vec<String> = batch
+                    .iter()
+                    .map(UnembeddedDocument::embedding_text)
+                    .collect();
+                let inputs: Vec<&str> = texts.iter().map(String::as_str).collect();
+                let vectors = generate_embeddings_batch(api_url, model, &inputs, api_key).await;
+                (batch, vectors)
+            }
+        })
+        .buffer_unordered(args.concurrency.max(1));
+
+    // A failed batch is reported and skipped; its documents are picked up by the next run.
+    let mut embedded = 0;
+    let mut failed = 0;
+    while let Some((batch, vectors)) = requests.next().await {
+        progress.inc(batch.len() as u64);
+        let vectors = match vectors {
+            Ok(vectors) if vectors.len() == batch.len() => Ok(vectors),
+            Ok(vectors) => Err(format!(
+                "the embedding API returned {} vectors for {} documents",
+                vectors.len(),
+                batch.len()
+            )),
+            Err(e) => Err(e.to_string()),
+        };
+        let vectors = match vectors {
+            Ok(vectors) => vectors,
+            Err(e) => {
+                warn!("Failed to embed a batch of {} documents: {e}", batch.len());
+                progress.suspend(|| eprintln!("⚠️ Failed to embed {} documents: {e}", batch.len()));
+                failed += batch.len();
+                continue;
+            }
+        };
+        let embeddings: Vec<(String, Vec<f32>)> = batch
+            .iter()
+            .map(|document| document.id.clone())
+            .zip(vectors)
+            .collect();
+        store_document_embeddings(&conn, model, &embeddings).await?;
+        embedded += batch.len();
+    }
+    progress.finish_and_clear();
+
+    if embedded == 0 {
+        bail!("No documents were embedded; {failed} failed.");
+    }
+    if failed > 0 {
+        println!("✅ Embedded {embedded} documents ({failed} failed; run again to retry them).");
+    } else {
+        println!("✅ Embedded {embedded} documents.");
+    }
+    Ok(())
+}
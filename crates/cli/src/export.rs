@@ -0,0 +1,73 @@
+use anyhow::{bail, Result};
+use anyrag::{
+    ingest::{export_knowledge_base, ExportFormat},
+    providers::db::sqlite::{
+        table_transfer::{export_table_to_file, TableFormat},
+        SqliteProvider,
+    },
+};
+use clap::Parser;
+use std::path::Path;
+use tracing::info;
+
+#[derive(Parser, Debug)]
+pub struct ExportArgs {
+    /// The path of the file to write the export to
+    #[arg(required = true)]
+    output: String,
+    /// The path to the database file to export
+    #[arg(long, env = "ANYRAG_DB_PATH", default_value = anyrag::constants::DEFAULT_DB_FILE)]
+    db_path: String,
+    /// The format of the export: `jsonl`, `parquet`, or `finetuning` for documents, and
+    /// `csv`, `jsonl` or `parquet` for a table
+    #[arg(long, default_value = "jsonl")]
+    format: String,
+    /// Only exports the documents of this owner
+    #[arg(long, conflicts_with = "table")]
+    owner_id: Option<String>,
+    /// Exports the rows of this table, such as one made by `dump`, instead of the documents
+    #[arg(long)]
+    table: Option<String>,
+}
+
+pub async fn handle_export(args: &ExportArgs) -> Result<()> {
+    if let Some(table) = &args.table {
+        return export_table(args, table).await;
+    }
+    let format: ExportFormat = args.format.parse()?;
+    if !Path::new(&args.db_path).exists() {
+        bail!("Database file '{}' not found.", args.db_path);
+    }
+    info!(
+        "Exporting '{}' as {} to '{}'",
+        args.db_path, format, args.output
+    );
+    println!("📤 Exporting '{}' as {format}...", args.db_path);
+
+    let sqlite_provider = SqliteProvider::new(&args.db_path).await?;
+    let body = export_knowledge_base(&sqlite_provider.db, format, args.owner_id.as_deref()).await?;
+    std::fs::write(&args.output, &body)?;
+    println!("✅ Exported {} bytes to '{}'.", body.len(), args.output);
+
+    Ok(())
+}
+
+/// Streams the rows of a table to the output file.
+async fn export_table(args: &ExportArgs, table: &str) -> Result<()> {
+    let format: TableFormat = args.format.parse()?;
+    if !Path::new(&args.db_path).exists() {
+        bail!("Database file '{}' not found.", args.db_path);
+    }
+    info!(
+        "Exporting table '{table}' of '{}' as {format} to '{}'",
+        args.db_path, args.output
+    );
+    println!("📤 Exporting table '{table}' as {format}...");
+
+    let sqlite_provider = SqliteProvider::new(&args.db_path).await?;
+    let rows =
+        export_table_to_file(&sqlite_provider.db, table, format, Path::new(&args.output)).await?;
+    println!("✅ Exported {rows} rows to '{}'.", args.output);
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use anyhow::Result;
+use anyrag::{
+    ingest::{import_documents, ImportFormat},
+    providers::db::sqlite::{
+        table_transfer::{import_table_from_file, TableFormat},
+        SqliteProvider,
+    },
+    types::EmbeddingConfig,
+};
+use clap::Parser;
+use std::path::Path;
+use tracing::info;
+
+#[derive(Parser, Debug)]
+pub struct ImportArgs {
+    /// The path of the export to import
+    #[arg(required = true)]
+    input: String,
+    /// The format of the export: `langchain` (Document JSONL) or `llamaindex` (docstore
+    /// JSON) for documents, and `csv`, `jsonl` or `parquet` for a table
+    #[arg(long)]
+    format: String,
+    /// The path to the database file to import into
+    #[arg(long, env = "ANYRAG_DB_PATH", default_value = anyrag::constants::DEFAULT_DB_FILE)]
+    db_path: String,
+    /// The owner of the imported documents
+    #[arg(long, conflicts_with = "table")]
+    owner_id: Option<String>,
+    /// The embedding model the vectors in the export were made with. Vectors are only
+    /// kept when it matches `--embedding-model`.
+    #[arg(long, conflicts_with = "table")]
+    source_model: Option<String>,
+    /// Imports the rows into this table instead of the documents, creating it if needed.
+    /// Rows whose key the table already holds are skipped.
+    #[arg(long)]
+    table: Option<String>,
+    /// The API URL for the embedding model (optional). If provided, documents without a
+    /// vector of this model are embedded.
+    #[arg(long, env = "EMBEDDINGS_API_URL")]
+    embedding_api_url: Option<String>,
+    /// The name of the embedding model to use (required if embedding-api-url is set).
+    #[arg(long, env = "EMBEDDINGS_MODEL", requires = "embedding_api_url")]
+    embedding_model: Option<String>,
+}
+
+pub async fn handle_import(args: &ImportArgs) -> Result<()> {
+    if let Some(table) = &args.table {
+        return import_table(args, table).await;
+    }
+    let format: ImportFormat = args.format.parse()?;
+    info!(
+        "Importing {format} export '{}' into '{}'",
+        args.input, args.db_path
+    );
+    println!("📥 Importing '{}'...", args.input);
+    let data = std::fs::read_to_string(&args.input)?;
+
+    let sqlite_provider = open_database(&args.db_path).await?;
+    sqlite_provider.initialize_schema().await?;
+
+    let embedding_config = match (&args.embedding_api_url, &args.embedding_model) {
+        (Some(url), Some(model)) => Some(EmbeddingConfig {
+            api_url: url.clone(),
+            model_name: model.clone(),
+            api_key: std::env::var("AI_API_KEY").ok(),
+        }),
+        _ => None,
+    };
+    let summary = import_documents(
+        &sqlite_provider.db,
+        format,
+        &data,
+        args.owner_id.as_deref(),
+        args.source_model.as_deref(),
+        embedding_config.as_ref(),
+    )
+    .await?;
+    println!(
+        "✅ Imported {} documents ({} duplicates skipped, {} vectors kept, {} embedded).",
+        summary.document_ids.len(),
+        summary.duplicates_skipped,
+        summary.embeddings_copied,
+        summary.embeddings_generated
+    );
+
+    Ok(())
+}
+
+/// Streams the rows of the input file into a table.
+async fn import_table(args: &ImportArgs, table: &str) -> Result<()> {
+    let format: TableFormat = args.format.parse()?;
+    info!(
+        "Importing {format} file '{}' into table '{table}' of '{}'",
+        args.input, args.db_path
+    );
+    println!("📥 Importing '{}' into table '{table}'...", args.input);
+
+    let sqlite_provider = open_database(&args.db_path).await?;
+    let summary =
+        import_table_from_file(&sqlite_provider.db, Path::new(&args.input), table, format).await?;
+    println!(
+        "✅ Imported {} rows ({} already present{}).",
+        summary.rows_inserted,
+        summary.rows_read - summary.rows_inserted,
+        if summary.table_created {
+            ", table created"
+        } else {
+            ""
+        }
+    );
+
+    Ok(())
+}
+
+async fn open_database(db_path: &str) -> Result<SqliteProvider> {
+    // Ensure the db directory exists before trying to create the database.
+    if let Some(parent) = Path::new(db_path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    Ok(SqliteProvider::new(db_path).await?)
+}
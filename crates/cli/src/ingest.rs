@@ -0,0 +1,429 @@
+use anyhow::{bail, Context, Result};
+use anyrag::ingest::{IngestionPrompts, IngestionResult, Ingestor};
+use anyrag::prompts::knowledge::{
+    KNOWLEDGE_RESTRUCTURING_SYSTEM_PROMPT, METADATA_EXTRACTION_SYSTEM_PROMPT,
+};
+use anyrag::providers::ai::local::LocalAiProvider;
+use anyrag::providers::db::sqlite::SqliteProvider;
+use anyrag::types::EmbeddingConfig;
+use anyrag_dir::{watch::watch_directory, DirectoryIngestor, FileFilter};
+use anyrag_pdf::PdfIngestor;
+use anyrag_sheets::{ingest_sheet_table, SheetsIngestor};
+use anyrag_text::{validate_chunk_config, TextIngestor, DEFAULT_CHUNK_OVERLAP, DEFAULT_CHUNK_SIZE};
+use anyrag_web::{WebIngestStrategy, WebIngestor};
+use base64::{engine::general_purpose, Engine as _};
+use clap::{Args, Parser, Subcommand};
+use serde_json::{json, Value};
+use std::path::Path;
+use tracing::info;
+
+#[derive(Parser, Debug)]
+pub struct IngestArgs {
+    #[command(subcommand)]
+    command: IngestCommands,
+}
+
+#[derive(Subcommand, Debug)]
+enum IngestCommands {
+    /// Ingest every matching file under a local directory
+    Dir(DirArgs),
+    /// Ingest a web page
+    Url(UrlArgs),
+    /// Ingest a local PDF file
+    Pdf(PdfArgs),
+    /// Ingest a local text file, chunked by paragraph
+    File(FileArgs),
+    /// Ingest a public Google Sheet
+    Sheet(SheetArgs),
+}
+
+/// The database the ingested documents are stored in.
+#[derive(Args, Debug)]
+struct DatabaseArgs {
+    /// The path to the database file to use for storage
+    #[arg(long, env = "ANYRAG_DB_PATH", default_value = anyrag::constants::DEFAULT_DB_FILE)]
+    db_path: String,
+    /// The owner of the ingested documents
+    #[arg(long)]
+    owner_id: Option<String>,
+}
+
+/// The model that restructures ingested documents and extracts their metadata.
+#[derive(Args, Debug)]
+struct AiArgs {
+    /// The API URL of an OpenAI-compatible model. Required to ingest web pages, PDF and CSV files, and sheets.
+    #[arg(long, env = "LOCAL_AI_API_URL")]
+    ai_api_url: Option<String>,
+    /// The model to use with `--ai-api-url`
+    #[arg(long, env = "AI_MODEL")]
+    ai_model: Option<String>,
+}
+
+/// The model the ingested documents are embedded with. Without it, they are left to
+/// `anyrag embed`.
+#[derive(Args, Debug)]
+struct EmbeddingArgs {
+    /// The API URL of the embedding model
+    #[arg(long, env = "EMBEDDINGS_API_URL")]
+    embedding_api_url: Option<String>,
+    /// The embedding model to use with `--embedding-api-url`
+    #[arg(long, env = "EMBEDDINGS_MODEL")]
+    embedding_model: Option<String>,
+}
+
+impl DatabaseArgs {
+    /// Opens the database, creating it and its directory when they do not exist yet.
+    async fn open(&self) -> Result<SqliteProvider> {
+        // Ensure the db directory exists before trying to create the database.
+        if let Some(parent) = Path::new(&self.db_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let sqlite_provider = SqliteProvider::new(&self.db_path).await?;
+        sqlite_provider.initialize_schema().await?;
+        Ok(sqlite_provider)
+    }
+}
+
+impl AiArgs {
+    /// Builds the model of `--ai-api-url`, if set. `AI_API_KEY` is sent when set.
+    fn ai_provider(&self) -> Result<Option<LocalAiProvider>> {
+        let Some(url) = &self.ai_api_url else {
+            return Ok(None);
+        };
+        Ok(Some(LocalAiProvider::new(
+            url.clone(),
+            std::env::var("AI_API_KEY").ok(),
+            self.ai_model.clone(),
+        )?))
+    }
+
+    /// Builds the model of `--ai-api-url`, which ingesting `source` requires.
+    fn required_ai_provider(&self, source: &str) -> Result<LocalAiProvider> {
+        match self.ai_provider()? {
+            Some(ai_provider) => Ok(ai_provider),
+            None => bail!("Ingesting {source} needs `--ai-api-url` (or LOCAL_AI_API_URL)."),
+        }
+    }
+}
+
+impl EmbeddingArgs {
+    /// The embedding model, if both its URL and name are set. `AI_API_KEY` is sent when set.
+    fn config(&self) -> Option<EmbeddingConfig> {
+        Some(EmbeddingConfig {
+            api_url: self.embedding_api_url.clone()?,
+            model_name: self.embedding_model.clone()?,
+            api_key: std::env::var("AI_API_KEY").ok(),
+        })
+    }
+}
+
+/// The prompts the CLI restructures documents and extracts their metadata with.
+fn ingestion_prompts() -> IngestionPrompts<'static> {
+    IngestionPrompts {
+        restructuring_system_prompt: KNOWLEDGE_RESTRUCTURING_SYSTEM_PROMPT,
+        metadata_extraction_system_prompt: METADATA_EXTRACTION_SYSTEM_PROMPT,
+    }
+}
+
+#[derive(Parser, Debug)]
+struct DirArgs {
+    /// The path to the directory to ingest
+    #[arg(required = true)]
+    path: String,
+    #[command(flatten)]
+    database: DatabaseArgs,
+    /// A glob of files to ingest, relative to the directory (repeatable). Defaults to all files.
+    #[arg(long)]
+    include: Vec<String>,
+    /// A glob of files to skip, relative to the directory (repeatable)
+    #[arg(long)]
+    exclude: Vec<String>,
+    /// Keep running and re-ingest files as they change
+    #[arg(long)]
+    watch: bool,
+    #[command(flatten)]
+    ai: AiArgs,
+}
+
+#[derive(Parser, Debug)]
+struct UrlArgs {
+    /// The URL of the page to ingest
+    #[arg(required = true)]
+    url: String,
+    #[command(flatten)]
+    database: DatabaseArgs,
+    /// How the page is fetched: `raw_html`, `jina`, or `headless`
+    #[arg(long, env = "WEB_INGEST_STRATEGY", default_value = "raw_html", value_parser = ["raw_html", "jina", "headless"])]
+    strategy: String,
+    /// The Jina Reader API key, used by the `jina` strategy
+    #[arg(long, env = "JINA_API_KEY")]
+    jina_api_key: Option<String>,
+    /// The DevTools endpoint of a headless Chrome, required by the `headless` strategy
+    #[arg(long, env = "HEADLESS_BROWSER_URL")]
+    headless_browser_url: Option<String>,
+    /// Also store the page's HTML tables as SQLite tables
+    #[arg(long)]
+    extract_tables: bool,
+    /// Store the page's markdown as it is, one document per section, instead of
+    /// restructuring it with the model
+    #[arg(long)]
+    no_restructure: bool,
+    #[command(flatten)]
+    ai: AiArgs,
+    #[command(flatten)]
+    embedding: EmbeddingArgs,
+}
+
+#[derive(Parser, Debug)]
+struct PdfArgs {
+    /// The path to the PDF file to ingest
+    #[arg(required = true)]
+    path: String,
+    #[command(flatten)]
+    database: DatabaseArgs,
+    /// How the text is extracted: `local`, or `gemini` to have the model read the PDF
+    #[arg(long, default_value = "local", value_parser = ["local", "gemini"])]
+    extractor: String,
+    /// Store the text of each page as it is instead of restructuring it with the model
+    #[arg(long)]
+    no_restructure: bool,
+    #[command(flatten)]
+    ai: AiArgs,
+    #[command(flatten)]
+    embedding: EmbeddingArgs,
+}
+
+#[derive(Parser, Debug)]
+struct FileArgs {
+    /// The path to the text file to ingest
+    #[arg(required = true)]
+    path: String,
+    #[command(flatten)]
+    database: DatabaseArgs,
+    /// The maximum chunk size in characters
+    #[arg(long, default_value_t = DEFAULT_CHUNK_SIZE)]
+    chunk_size: usize,
+    /// The overlap between split chunks in characters
+    #[arg(long, default_value_t = DEFAULT_CHUNK_OVERLAP)]
+    chunk_overlap: usize,
+}
+
+#[derive(Parser, Debug)]
+struct SheetArgs {
+    /// The URL of the Google Sheet to ingest
+    #[arg(required = true)]
+    url: String,
+    #[command(flatten)]
+    database: DatabaseArgs,
+    /// The `gid` of the tab to ingest. Without it, every tab is ingested as its own document.
+    #[arg(long)]
+    gid: Option<String>,
+    /// Copy the tab into a typed SQL table for text-to-SQL queries instead of
+    /// restructuring it into documents. Needs no model.
+    #[arg(long)]
+    table: bool,
+    /// Store the rows as CSV instead of restructuring them with the model
+    #[arg(long, conflicts_with = "table")]
+    no_restructure: bool,
+    #[command(flatten)]
+    ai: AiArgs,
+    #[command(flatten)]
+    embedding: EmbeddingArgs,
+}
+
+pub async fn handle_ingest(args: &IngestArgs) -> Result<()> {
+    match &args.command {
+        IngestCommands::Dir(dir_args) => handle_ingest_dir(dir_args).await,
+        IngestCommands::Url(url_args) => handle_ingest_url(url_args).await,
+        IngestCommands::Pdf(pdf_args) => handle_ingest_pdf(pdf_args).await,
+        IngestCommands::File(file_args) => handle_ingest_file(file_args).await,
+        IngestCommands::Sheet(sheet_args) => handle_ingest_sheet(sheet_args).await,
+    }
+}
+
+async fn handle_ingest_dir(args: &DirArgs) -> Result<()> {
+    info!("Ingesting directory: {}", args.path);
+    println!("📂 Ingesting directory: '{}'...", args.path);
+
+    let sqlite_provider = args.database.open().await?;
+    let ai_provider = args.ai.ai_provider()?;
+    let mut ingestor = DirectoryIngestor::new(&sqlite_provider.db);
+    if let Some(ai_provider) = &ai_provider {
+        ingestor = ingestor.with_llm(ai_provider, ingestion_prompts());
+    }
+
+    let source_json = json!({
+        "path": args.path,
+        "include": args.include,
+        "exclude": args.exclude,
+    })
+    .to_string();
+    let result = ingestor
+        .ingest(&source_json, args.database.owner_id.as_deref())
+        .await?;
+
+    let report: Value = serde_json::from_str(result.metadata.as_deref().unwrap_or("{}"))?;
+    let failed = report["failed"].as_array().cloned().unwrap_or_default();
+    for failure in &failed {
+        eprintln!(
+            "⚠️  {}: {}",
+            failure["path"].as_str().unwrap_or_default(),
+            failure["error"].as_str().unwrap_or_default()
+        );
+    }
+    println!(
+        "✅ Ingested {} chunks from {} files into '{}' ({} failed).",
+        result.documents_added,
+        report["ingested"].as_array().map_or(0, Vec::len),
+        args.database.db_path,
+        failed.len()
+    );
+
+    if !args.watch {
+        return Ok(());
+    }
+
+    println!(
+        "👀 Watching '{}' for changes. Press Ctrl+C to stop.",
+        args.path
+    );
+    let filter = FileFilter::new(&args.include, &args.exclude)?;
+    tokio::select! {
+        result = watch_directory(&ingestor, Path::new(&args.path), &filter, args.database.owner_id.as_deref()) => result?,
+        _ = tokio::signal::ctrl_c() => println!("Stopped watching."),
+    }
+    Ok(())
+}
+
+async fn handle_ingest_url(args: &UrlArgs) -> Result<()> {
+    info!("Ingesting web page: {}", args.url);
+    println!("🌐 Ingesting web page: '{}'...", args.url);
+    let strategy = match args.strategy.as_str() {
+        "jina" => WebIngestStrategy::Jina {
+            api_key: args.jina_api_key.as_deref(),
+        },
+        "headless" => WebIngestStrategy::Headless {
+            endpoint: args.headless_browser_url.as_deref().context(
+                "The 'headless' strategy needs `--headless-browser-url` (or HEADLESS_BROWSER_URL).",
+            )?,
+        },
+        _ => WebIngestStrategy::RawHtml,
+    };
+    let ai_provider = args.ai.required_ai_provider("web pages")?;
+    let embedding = args.embedding.config();
+
+    let sqlite_provider = args.database.open().await?;
+    let mut ingestor = WebIngestor::new(&sqlite_provider.db, &ai_provider, ingestion_prompts());
+    if let Some(embedding) = &embedding {
+        ingestor = ingestor.with_embedding(embedding);
+    }
+    let source_json = json!({
+        "url": args.url,
+        "strategy": strategy,
+        "extract_tables": args.extract_tables,
+        "restructure": !args.no_restructure,
+    })
+    .to_string();
+    let result = ingestor
+        .ingest(&source_json, args.database.owner_id.as_deref())
+        .await?;
+    report_ingestion(&result, &args.url, &args.database.db_path);
+    Ok(())
+}
+
+async fn handle_ingest_pdf(args: &PdfArgs) -> Result<()> {
+    info!("Ingesting PDF file: {}", args.path);
+    println!("📄 Ingesting PDF file: '{}'...", args.path);
+    let data =
+        std::fs::read(&args.path).with_context(|| format!("Failed to read '{}'", args.path))?;
+    let ai_provider = args.ai.required_ai_provider("PDF files")?;
+    let embedding = args.embedding.config();
+
+    let sqlite_provider = args.database.open().await?;
+    let mut ingestor = PdfIngestor::new(&sqlite_provider.db, &ai_provider, ingestion_prompts());
+    if let Some(embedding) = &embedding {
+        ingestor = ingestor.with_embedding(embedding);
+    }
+    let source_identifier = Path::new(&args.path)
+        .file_name()
+        .map_or(args.path.clone(), |name| name.to_string_lossy().to_string());
+    let source_json = json!({
+        "source_identifier": source_identifier,
+        "pdf_data_base64": general_purpose::STANDARD.encode(&data),
+        "extractor": args.extractor,
+        "restructure": !args.no_restructure,
+    })
+    .to_string();
+    let result = ingestor
+        .ingest(&source_json, args.database.owner_id.as_deref())
+        .await?;
+    report_ingestion(&result, &args.path, &args.database.db_path);
+    Ok(())
+}
+
+async fn handle_ingest_file(args: &FileArgs) -> Result<()> {
+    info!("Ingesting text file: {}", args.path);
+    println!("📄 Ingesting text file: '{}'...", args.path);
+    validate_chunk_config(args.chunk_size, args.chunk_overlap)?;
+    let text = std::fs::read_to_string(&args.path)
+        .with_context(|| format!("Failed to read '{}'", args.path))?;
+
+    let sqlite_provider = args.database.open().await?;
+    let ingestor = TextIngestor::new(&sqlite_provider.db);
+    let source_json = json!({
+        "text": text,
+        "source": args.path,
+        "chunk_size": args.chunk_size,
+        "chunk_overlap": args.chunk_overlap,
+    })
+    .to_string();
+    let result = ingestor
+        .ingest(&source_json, args.database.owner_id.as_deref())
+        .await?;
+    report_ingestion(&result, &args.path, &args.database.db_path);
+    Ok(())
+}
+
+async fn handle_ingest_sheet(args: &SheetArgs) -> Result<()> {
+    info!("Ingesting sheet: {}", args.url);
+    println!("📊 Ingesting sheet: '{}'...", args.url);
+    if args.table {
+        let sqlite_provider = args.database.open().await?;
+        let table = ingest_sheet_table(&sqlite_provider.db, &args.url, args.gid.as_deref()).await?;
+        println!(
+            "✅ Copied {} rows of '{}' into table '{}' in '{}'.",
+            table.rows, args.url, table.table_name, args.database.db_path
+        );
+        return Ok(());
+    }
+    let ai_provider = args.ai.required_ai_provider("sheets")?;
+    let embedding = args.embedding.config();
+
+    let sqlite_provider = args.database.open().await?;
+    let mut ingestor = SheetsIngestor::new(&sqlite_provider.db, &ai_provider, ingestion_prompts());
+    if let Some(embedding) = &embedding {
+        ingestor = ingestor.with_embedding(embedding);
+    }
+    let source_json = json!({
+        "url": args.url,
+        "gid": args.gid,
+        "restructure": !args.no_restructure,
+    })
+    .to_string();
+    let result = ingestor
+        .ingest(&source_json, args.database.owner_id.as_deref())
+        .await?;
+    report_ingestion(&result, &args.url, &args.database.db_path);
+    Ok(())
+}
+
+fn report_ingestion(result: &IngestionResult, source: &str, db_path: &str) {
+    if result.documents_added == 0 {
+        println!("✅ Nothing new to ingest from '{source}'; it may already be in '{db_path}'.");
+    } else {
+        println!(
+            "✅ Ingested {} documents from '{source}' into '{db_path}'.",
+            result.documents_added
+        );
+    }
+}
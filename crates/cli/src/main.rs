@@ -2,18 +2,30 @@
 //!
 //! This is the main entry point for the `anyrag` command-line interface.
 
+mod ask;
 mod auth;
+mod backup;
+mod config;
+mod embed;
+mod export;
 mod firebase;
+mod import;
+mod ingest;
 mod process;
+mod repl;
+mod search;
 use anyhow::{bail, Result};
 
-use anyrag::constants;
+use anyrag::{
+    cli_config::{config_path, CliConfig},
+    constants,
+};
 use anyrag_github::cli::{handle_dump_github, GithubArgs};
 use clap::{Parser, Subcommand};
 use keyring::Entry;
 use std::fs;
 use std::path::Path;
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::{fmt, EnvFilter};
 use turso::Value as TursoValue;
 
@@ -35,10 +47,30 @@ enum Commands {
     Dump(DumpArgs),
     /// Process and enrich data in the local database
     Process(process::ProcessArgs),
+    /// Embed the documents of a local database that have no embedding yet
+    Embed(embed::EmbedArgs),
+    /// Ingest local files into the local database
+    Ingest(ingest::IngestArgs),
     /// List items from a local database table
     List(ListArgs),
     /// Count items in a local database table
     Count(CountArgs),
+    /// Back up a local database, optionally uploading it to an object store
+    Backup(backup::BackupArgs),
+    /// Restore a backup into a fresh local database
+    Restore(backup::RestoreArgs),
+    /// Export the documents of a local database to JSONL or Parquet
+    Export(export::ExportArgs),
+    /// Import a LangChain or LlamaIndex export into a local database
+    Import(import::ImportArgs),
+    /// Search the knowledge base of a local database
+    Search(search::SearchArgs),
+    /// Answer a question from the knowledge base of a local database
+    Ask(ask::AskArgs),
+    /// Start an interactive session against a local database
+    Repl(repl::ReplArgs),
+    /// Read or change the settings of `~/.config/anyrag/config.toml`
+    Config(config::ConfigArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -89,6 +121,13 @@ async fn main() -> Result<()> {
         .finish();
     tracing::subscriber::set_global_default(subscriber)?;
 
+    // The configuration file fills in the environment variables that are not set, so
+    // flags override the environment, which overrides the file.
+    match config_path().and_then(|path| CliConfig::load(&path)) {
+        Ok(config) => config.apply_to_env(),
+        Err(e) => warn!("Ignoring the configuration file: {e}"),
+    }
+
     let cli = Cli::parse();
 
     // Handle the command
@@ -120,6 +159,18 @@ async fn main() -> Result<()> {
                 std::process::exit(1);
             }
         }
+        Commands::Embed(args) => {
+            if let Err(e) = embed::handle_embed(args).await {
+                eprintln!("Embed failed: {e}");
+                std::process::exit(1);
+            }
+        }
+        Commands::Ingest(args) => {
+            if let Err(e) = ingest::handle_ingest(args).await {
+                eprintln!("Ingest failed: {e}");
+                std::process::exit(1);
+            }
+        }
         Commands::List(args) => {
             if let Err(e) = handle_list(args).await {
                 eprintln!("List command failed: {e}");
@@ -132,6 +183,54 @@ async fn main() -> Result<()> {
                 std::process::exit(1);
             }
         }
+        Commands::Backup(args) => {
+            if let Err(e) = backup::handle_backup(args).await {
+                eprintln!("Backup failed: {e}");
+                std::process::exit(1);
+            }
+        }
+        Commands::Restore(args) => {
+            if let Err(e) = backup::handle_restore(args).await {
+                eprintln!("Restore failed: {e}");
+                std::process::exit(1);
+            }
+        }
+        Commands::Export(args) => {
+            if let Err(e) = export::handle_export(args).await {
+                eprintln!("Export failed: {e}");
+                std::process::exit(1);
+            }
+        }
+        Commands::Import(args) => {
+            if let Err(e) = import::handle_import(args).await {
+                eprintln!("Import failed: {e}");
+                std::process::exit(1);
+            }
+        }
+        Commands::Search(args) => {
+            if let Err(e) = search::handle_search(args).await {
+                eprintln!("Search failed: {e}");
+                std::process::exit(1);
+            }
+        }
+        Commands::Ask(args) => {
+            if let Err(e) = ask::handle_ask(args).await {
+                eprintln!("Ask failed: {e}");
+                std::process::exit(1);
+            }
+        }
+        Commands::Repl(args) => {
+            if let Err(e) = repl::handle_repl(args).await {
+                eprintln!("REPL failed: {e}");
+                std::process::exit(1);
+            }
+        }
+        Commands::Config(args) => {
+            if let Err(e) = config::handle_config(args).await {
+                eprintln!("Config failed: {e}");
+                std::process::exit(1);
+            }
+        }
     }
 
     Ok(())
@@ -1,7 +1,11 @@
-use anyhow::Result;
-use anyrag::ingest::Ingestor;
+use anyhow::{bail, Result};
+use anyrag::{
+    ingest::{reset_pipeline_progress, run_pipeline, Ingestor, PipelineDefinition},
+    providers::{ai::local::LocalAiProvider, db::sqlite::SqliteProvider},
+};
 use anyrag_markdown::{EmbeddingConfig, MarkdownIngestor, MarkdownSource};
 use clap::{Parser, Subcommand};
+use serde_json::Value;
 use std::path::Path;
 use tracing::info;
 
@@ -15,19 +19,21 @@ pub struct ProcessArgs {
 enum ProcessCommands {
     /// Process a local file for ingestion
     File(FileArgs),
+    /// Run an enrichment pipeline, defined in YAML, over the documents of the local database
+    Pipeline(PipelineArgs),
 }
 
 #[derive(Parser, Debug)]
 struct FileArgs {
-    /// The path to the local file to process
+    /// The local Markdown file to process, or a directory or quoted glob pattern of them
     #[arg(required = true)]
     path: String,
     /// The path to the database file to use for storage
-    #[arg(long, default_value = anyrag::constants::DEFAULT_DB_FILE)]
+    #[arg(long, env = "ANYRAG_DB_PATH", default_value = anyrag::constants::DEFAULT_DB_FILE)]
     db_path: String,
-    /// The separator string used to split the file content into chunks
-    #[arg(long, default_value = "\n---\n")]
-    separator: String,
+    /// Split the file on this string instead of by its headings
+    #[arg(long)]
+    separator: Option<String>,
     /// The API URL for the embedding model (optional). If provided, embeddings will be generated.
     #[arg(long, env = "EMBEDDINGS_API_URL")]
     embedding_api_url: Option<String>,
@@ -36,10 +42,89 @@ struct FileArgs {
     embedding_model: Option<String>,
 }
 
+#[derive(Parser, Debug)]
+struct PipelineArgs {
+    /// The path to the YAML file defining the pipeline
+    #[arg(required = true)]
+    path: String,
+    /// The path to the database file whose documents to enrich
+    #[arg(long, env = "ANYRAG_DB_PATH", default_value = anyrag::constants::DEFAULT_DB_FILE)]
+    db_path: String,
+    /// The API URL of an OpenAI-compatible model, which runs every stage
+    #[arg(long, env = "LOCAL_AI_API_URL")]
+    ai_api_url: Option<String>,
+    /// The model to use with `--ai-api-url`
+    #[arg(long, env = "AI_MODEL")]
+    ai_model: Option<String>,
+    /// Forget the progress of earlier runs of the pipeline and process every document again
+    #[arg(long)]
+    restart: bool,
+}
+
 pub async fn handle_process(args: &ProcessArgs) -> Result<()> {
     match &args.command {
         ProcessCommands::File(file_args) => handle_process_file(file_args).await,
+        ProcessCommands::Pipeline(pipeline_args) => handle_process_pipeline(pipeline_args).await,
+    }
+}
+
+async fn handle_process_pipeline(args: &PipelineArgs) -> Result<()> {
+    let yaml = std::fs::read_to_string(&args.path)
+        .map_err(|e| anyhow::anyhow!("Failed to read pipeline '{}': {e}", args.path))?;
+    let definition = PipelineDefinition::from_yaml(&yaml)?;
+    let Some(ai_api_url) = &args.ai_api_url else {
+        bail!("Running a pipeline needs `--ai-api-url` (or LOCAL_AI_API_URL).");
+    };
+    if !Path::new(&args.db_path).exists() {
+        bail!("Database file '{}' not found.", args.db_path);
+    }
+    let ai_provider = LocalAiProvider::new(
+        ai_api_url.clone(),
+        std::env::var("AI_API_KEY").ok(),
+        args.ai_model.clone(),
+    )?;
+    let sqlite_provider = SqliteProvider::new(&args.db_path).await?;
+    sqlite_provider.initialize_schema().await?;
+    let mut conn = sqlite_provider.db.connect()?;
+
+    if args.restart {
+        let forgotten = reset_pipeline_progress(&conn, &definition.name).await?;
+        info!(
+            "Forgot {forgotten} items of pipeline '{}'.",
+            definition.name
+        );
+    }
+    let stages: Vec<&str> = definition.stages.iter().map(|stage| stage.name()).collect();
+    println!(
+        "⚙️ Running pipeline '{}' ({})...",
+        definition.name,
+        stages.join(" → ")
+    );
+    let report = run_pipeline(&mut conn, &ai_provider, &definition).await?;
+
+    println!("Selected {} documents.", report.documents);
+    for stage in &report.stages {
+        println!(
+            "  {}: {} processed, {} already done, {} failed",
+            stage.stage,
+            stage.processed,
+            stage.skipped,
+            stage.failures.len()
+        );
+        for failure in &stage.failures {
+            eprintln!("    ⚠️ {}: {}", failure.item_id, failure.error);
+        }
     }
+    let failed = report.failed();
+    if failed > 0 {
+        println!(
+            "✅ Pipeline '{}' finished with {failed} failed items; run it again to retry them.",
+            definition.name
+        );
+    } else {
+        println!("✅ Pipeline '{}' finished.", definition.name);
+    }
+    Ok(())
 }
 
 async fn handle_process_file(args: &FileArgs) -> Result<()> {
@@ -68,6 +153,7 @@ async fn handle_process_file(args: &FileArgs) -> Result<()> {
         file_path: args.path.clone(),
         separator: args.separator.clone(),
         embedding_config,
+        chunking: None,
     };
 
     let ingestor = MarkdownIngestor;
@@ -81,6 +167,26 @@ async fn handle_process_file(args: &FileArgs) -> Result<()> {
     })?;
     let count = result.documents_added;
 
+    // A directory or glob reports the result of each file.
+    if let Some(metadata) = &result.metadata {
+        let report: Value = serde_json::from_str(metadata)?;
+        let failed = report["failed"].as_array().cloned().unwrap_or_default();
+        for failure in &failed {
+            eprintln!(
+                "⚠️  {}: {}",
+                failure["path"].as_str().unwrap_or_default(),
+                failure["error"].as_str().unwrap_or_default()
+            );
+        }
+        println!(
+            "✅ Successfully ingested {count} chunks from {} files into '{}' ({} failed).",
+            report["ingested"].as_array().map_or(0, Vec::len),
+            args.db_path,
+            failed.len()
+        );
+        return Ok(());
+    }
+
     println!(
         "✅ Successfully ingested {} chunks from '{}' into '{}'.",
         count, args.path, args.db_path
@@ -0,0 +1,263 @@
+use crate::search::{print_results, KnowledgeBaseArgs};
+use anyhow::{anyhow, Result};
+use anyrag::{
+    context_sanitization::document_context,
+    providers::{
+        ai::AiProvider,
+        db::{sqlite::SqliteProvider, storage::Storage},
+    },
+    types::{ContentType, ExecutePromptOptions},
+    PromptClientBuilder,
+};
+use clap::{Parser, ValueEnum};
+use rustyline::{error::ReadlineError, DefaultEditor};
+use std::io::Write;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+const HELP: &str = r"Type a question to answer it, ending a line with `\` to continue it on the next.
+
+  \tables          List the tables of the database that hold rows
+  \schema [TABLE]  Show the columns of a table, or of every table
+  \mode [sql|rag]  Show or switch how questions are answered
+  \help            Show this help
+  \quit            Leave the session (or Ctrl+D)";
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[clap(rename_all = "kebab_case")]
+pub enum ReplMode {
+    /// Answers from the documents found by hybrid search
+    #[default]
+    Rag,
+    /// Answers by generating and running SQL against the database
+    Sql,
+}
+
+#[derive(Parser, Debug)]
+pub struct ReplArgs {
+    /// How questions are answered at the start of the session
+    #[arg(long, value_enum, default_value_t = ReplMode::Rag)]
+    mode: ReplMode,
+    /// The number of search results RAG answers are written from
+    #[arg(long, default_value_t = 5)]
+    limit: u32,
+    /// The file the input history is kept in between sessions
+    #[arg(long, default_value = ".anyrag-cli-history")]
+    history_file: String,
+    #[command(flatten)]
+    knowledge_base: KnowledgeBaseArgs,
+}
+
+pub async fn handle_repl(args: &ReplArgs) -> Result<()> {
+    let knowledge_base = &args.knowledge_base;
+    let sqlite_provider = knowledge_base.open_database().await?;
+    let mut editor = DefaultEditor::new()?;
+    // A missing history file only means this is the first session.
+    let _ = editor.load_history(&args.history_file);
+
+    let mut mode = args.mode;
+    println!(
+        "anyrag REPL on '{}' ({} mode). Type \\help for commands.",
+        knowledge_base.db_path,
+        mode_name(mode)
+    );
+    loop {
+        let Some(input) = read_input(&mut editor, mode)? else {
+            break;
+        };
+        if input.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(input.as_str());
+
+        let result = match input.strip_prefix('\\') {
+            Some(command) => {
+                let mut parts = command.split_whitespace();
+                match (parts.next().unwrap_or_default(), parts.next()) {
+                    ("quit" | "q" | "exit", _) => break,
+                    ("help" | "?", _) => {
+                        println!("{HELP}");
+                        Ok(())
+                    }
+                    ("tables", _) => print_tables(&sqlite_provider).await,
+                    ("schema", table) => print_schema(&sqlite_provider, table).await,
+                    ("mode", None) => {
+                        println!("Answering in {} mode.", mode_name(mode));
+                        Ok(())
+                    }
+                    ("mode", Some(name)) => match ReplMode::from_str(name, true) {
+                        Ok(new_mode) => {
+                            mode = new_mode;
+                            println!("Answering in {} mode.", mode_name(mode));
+                            Ok(())
+                        }
+                        Err(_) => Err(anyhow!("Unknown mode '{name}'. Use sql or rag.")),
+                    },
+                    (other, _) => Err(anyhow!(
+                        "Unknown command '\\{other}'. Type \\help for commands."
+                    )),
+                }
+            }
+            None => match mode {
+                ReplMode::Rag => answer_from_documents(args, &sqlite_provider, &input).await,
+                ReplMode::Sql => answer_from_tables(args, &sqlite_provider, &input).await,
+            },
+        };
+        if let Err(e) = result {
+            eprintln!("❌ {e}");
+        }
+    }
+
+    if let Err(e) = editor.save_history(&args.history_file) {
+        warn!("Failed to save the REPL history: {e}");
+    }
+    Ok(())
+}
+
+fn mode_name(mode: ReplMode) -> &'static str {
+    match mode {
+        ReplMode::Rag => "rag",
+        ReplMode::Sql => "sql",
+    }
+}
+
+/// Reads the next input, joining the lines that end with `\`. Returns `None` at the
+/// end of the input.
+fn read_input(editor: &mut DefaultEditor, mode: ReplMode) -> Result<Option<String>> {
+    let mut input = String::new();
+    let mut prompt = format!("{}> ", mode_name(mode));
+    loop {
+        match editor.readline(&prompt) {
+            Ok(line) => match line.strip_suffix('\\') {
+                Some(continued) => {
+                    input.push_str(continued);
+                    input.push('\n');
+                    prompt = "... ".to_string();
+                }
+                None => {
+                    input.push_str(&line);
+                    return Ok(Some(input.trim().to_string()));
+                }
+            },
+            // Ctrl+C drops the current input without leaving the session.
+            Err(ReadlineError::Interrupted) => return Ok(Some(String::new())),
+            Err(ReadlineError::Eof) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+async fn print_tables(sqlite_provider: &SqliteProvider) -> Result<()> {
+    for table in sqlite_provider.list_tables().await? {
+        println!("{table}");
+    }
+    Ok(())
+}
+
+async fn print_schema(sqlite_provider: &SqliteProvider, table: Option<&str>) -> Result<()> {
+    let tables = match table {
+        Some(table) => vec![table.to_string()],
+        None => sqlite_provider.list_tables().await?,
+    };
+    for table in tables {
+        let schema = sqlite_provider.get_table_schema(&table).await?;
+        println!("{table}");
+        for field in &schema.fields {
+            println!("  {} {:?}", field.name, field.r#type);
+        }
+        for foreign_key in &schema.foreign_keys {
+            // A foreign key without a column refers to the primary key of the table.
+            match &foreign_key.referenced_column {
+                Some(column) => println!(
+                    "  {} -> {}({column})",
+                    foreign_key.column, foreign_key.referenced_table
+                ),
+                None => println!(
+                    "  {} -> {}",
+                    foreign_key.column, foreign_key.referenced_table
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Answers a question from the documents found by hybrid search, printing the answer
+/// as it is generated.
+async fn answer_from_documents(
+    args: &ReplArgs,
+    sqlite_provider: &SqliteProvider,
+    question: &str,
+) -> Result<()> {
+    let knowledge_base = &args.knowledge_base;
+    let ai_provider = knowledge_base.ai_provider("Answering questions")?;
+    info!("Answering '{question}' from the documents.");
+    let results = knowledge_base
+        .hybrid_search(
+            sqlite_provider.clone(),
+            ai_provider.clone(),
+            question,
+            args.limit,
+        )
+        .await?;
+    if results.is_empty() {
+        println!("I could not find any relevant information to answer your question.");
+        return Ok(());
+    }
+
+    let options = ExecutePromptOptions {
+        prompt: question.to_string(),
+        content_type: Some(ContentType::Knowledge),
+        context: Some(document_context(&results, None).context),
+        ..Default::default()
+    };
+    let client = PromptClientBuilder::new()
+        .ai_provider(Box::new(ai_provider.clone()))
+        .storage_provider(Box::new(sqlite_provider.clone()))
+        .build()?;
+    let (system_prompt, user_prompt) = client.build_prompts(&options).await?;
+
+    let (tokens, mut generated) = mpsc::unbounded_channel::<String>();
+    let generation = ai_provider.generate_stream(&system_prompt, &user_prompt, tokens);
+    let printing = async {
+        let mut stdout = std::io::stdout();
+        while let Some(text) = generated.recv().await {
+            let _ = write!(stdout, "{text}");
+            let _ = stdout.flush();
+        }
+    };
+    let (answer, ()) = tokio::join!(generation, printing);
+    answer?;
+    println!();
+    println!();
+    print_results(question, &results);
+    Ok(())
+}
+
+/// Answers a question by generating SQL over every table of the database and
+/// running it.
+async fn answer_from_tables(
+    args: &ReplArgs,
+    sqlite_provider: &SqliteProvider,
+    question: &str,
+) -> Result<()> {
+    let ai_provider = args.knowledge_base.ai_provider("Answering questions")?;
+    info!("Answering '{question}' from the tables.");
+    let client = PromptClientBuilder::new()
+        .ai_provider(Box::new(ai_provider))
+        .storage_provider(Box::new(sqlite_provider.clone()))
+        .build()?;
+    let options = ExecutePromptOptions {
+        prompt: question.to_string(),
+        // An empty table name offers every table of the database.
+        table_name: Some(String::new()),
+        ..Default::default()
+    };
+    let result = client.execute_prompt_with_options(options).await?;
+    if let Some(sql) = &result.generated_sql {
+        println!("{sql}");
+        println!();
+    }
+    println!("{}", result.text);
+    Ok(())
+}
@@ -0,0 +1,228 @@
+use anyhow::{anyhow, bail, Result};
+use anyrag::{
+    prompts::knowledge::{QUERY_ANALYSIS_SYSTEM_PROMPT, QUERY_ANALYSIS_USER_PROMPT},
+    providers::{
+        ai::{generate_embeddings_batch, local::LocalAiProvider, AiProvider},
+        db::{
+            sqlite::SqliteProvider,
+            storage::{CollectionSearch, KeywordSearch, VectorSearch},
+        },
+    },
+    search::{hybrid_search, HybridSearchOptions, HybridSearchPrompts},
+    types::SearchResult,
+};
+use clap::{Args, Parser, ValueEnum};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::info;
+
+/// The longest excerpt of a result's content that is printed.
+const EXCERPT_LENGTH: usize = 160;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+#[clap(rename_all = "kebab_case")]
+pub enum SearchType {
+    /// Metadata, keyword and vector search fused by rank, as in the server
+    #[default]
+    Hybrid,
+    /// Vector similarity search only
+    Vector,
+    /// Full-text keyword search only
+    Keyword,
+}
+
+#[derive(Parser, Debug)]
+pub struct SearchArgs {
+    /// The query to search the knowledge base for
+    #[arg(required = true)]
+    query: String,
+    /// The type of search to run
+    #[arg(long, value_enum, default_value_t = SearchType::Hybrid)]
+    mode: SearchType,
+    /// The maximum number of results to print
+    #[arg(long, default_value_t = 10)]
+    limit: u32,
+    #[command(flatten)]
+    knowledge_base: KnowledgeBaseArgs,
+}
+
+/// The options of the commands that search the knowledge base of a local database.
+#[derive(Args, Debug)]
+pub struct KnowledgeBaseArgs {
+    /// The path to the database file to search
+    #[arg(long, visible_alias = "db", env = "ANYRAG_DB_PATH", default_value = anyrag::constants::DEFAULT_DB_FILE)]
+    pub db_path: String,
+    /// Searches as this owner, who sees their own documents and those shared with them
+    #[arg(long)]
+    pub owner_id: Option<String>,
+    /// Only searches the documents of this collection, by id or name
+    #[arg(long)]
+    pub collection: Option<String>,
+    /// The API URL of an OpenAI-compatible model. Required by hybrid search to analyze the query.
+    #[arg(long, env = "LOCAL_AI_API_URL")]
+    pub ai_api_url: Option<String>,
+    /// The model to use with `--ai-api-url`
+    #[arg(long, env = "AI_MODEL")]
+    pub ai_model: Option<String>,
+    /// The API URL for the embedding model. Required by vector search, and used by hybrid search when set.
+    #[arg(long, env = "EMBEDDINGS_API_URL")]
+    pub embedding_api_url: Option<String>,
+    /// The name of the embedding model the database was embedded with (required if embedding-api-url is set).
+    #[arg(long, env = "EMBEDDINGS_MODEL", requires = "embedding_api_url")]
+    pub embedding_model: Option<String>,
+}
+
+impl KnowledgeBaseArgs {
+    /// Opens the database, bringing its schema up to date.
+    pub async fn open_database(&self) -> Result<SqliteProvider> {
+        if !Path::new(&self.db_path).exists() {
+            bail!("Database file '{}' not found.", self.db_path);
+        }
+        let sqlite_provider = SqliteProvider::new(&self.db_path).await?;
+        sqlite_provider.initialize_schema().await?;
+        Ok(sqlite_provider)
+    }
+
+    /// Builds the model of `--ai-api-url`, naming the `command` that needs it when it
+    /// is not set.
+    pub fn ai_provider(&self, command: &str) -> Result<LocalAiProvider> {
+        let Some(ai_api_url) = &self.ai_api_url else {
+            bail!("{command} needs `--ai-api-url` (or LOCAL_AI_API_URL).");
+        };
+        Ok(LocalAiProvider::new(
+            ai_api_url.clone(),
+            std::env::var("AI_API_KEY").ok(),
+            self.ai_model.clone(),
+        )?)
+    }
+
+    /// Runs the server's hybrid search pipeline, with vector search when an embedding
+    /// model is set.
+    pub async fn hybrid_search(
+        &self,
+        sqlite_provider: SqliteProvider,
+        ai_provider: LocalAiProvider,
+        query: &str,
+        limit: u32,
+    ) -> Result<Vec<SearchResult>> {
+        let api_key = std::env::var("AI_API_KEY").ok();
+        let embedding = self.embedding();
+        let options = HybridSearchOptions {
+            query_text: query.to_string(),
+            owner_id: self.owner_id.clone(),
+            org_id: None,
+            collection: self.collection.clone(),
+            limit,
+            prompts: HybridSearchPrompts {
+                analysis_system_prompt: QUERY_ANALYSIS_SYSTEM_PROMPT,
+                analysis_user_prompt_template: QUERY_ANALYSIS_USER_PROMPT,
+            },
+            use_keyword_search: true,
+            use_vector_search: embedding.is_some(),
+            embedding_api_url: embedding.map_or("", |(url, _)| url),
+            embedding_model: embedding.map_or("", |(_, model)| model),
+            embedding_api_key: api_key.as_deref(),
+            temporal_ranking_config: None,
+        };
+        let ai_provider: Arc<dyn AiProvider> = Arc::new(ai_provider);
+        Ok(hybrid_search(Arc::new(sqlite_provider), ai_provider, options).await?)
+    }
+
+    fn embedding(&self) -> Option<(&str, &str)> {
+        match (&self.embedding_api_url, &self.embedding_model) {
+            (Some(url), Some(model)) => Some((url.as_str(), model.as_str())),
+            _ => None,
+        }
+    }
+}
+
+pub async fn handle_search(args: &SearchArgs) -> Result<()> {
+    let knowledge_base = &args.knowledge_base;
+    info!(
+        "Searching '{}' for '{}' ({:?})",
+        knowledge_base.db_path, args.query, args.mode
+    );
+    let sqlite_provider = knowledge_base.open_database().await?;
+    let owner_id = knowledge_base.owner_id.as_deref();
+
+    let results = match args.mode {
+        SearchType::Hybrid => {
+            let ai_provider = knowledge_base.ai_provider("Hybrid search").map_err(|e| {
+                anyhow!("{e} Use `--mode keyword` or `--mode vector` to search without it.")
+            })?;
+            knowledge_base
+                .hybrid_search(sqlite_provider, ai_provider, &args.query, args.limit)
+                .await?
+        }
+        SearchType::Vector | SearchType::Keyword => {
+            let document_ids = match &knowledge_base.collection {
+                Some(collection) => Some(
+                    sqlite_provider
+                        .collection_document_ids(collection, owner_id, None)
+                        .await?,
+                ),
+                None => None,
+            };
+            if document_ids.as_ref().is_some_and(|ids| ids.is_empty()) {
+                Vec::new()
+            } else if matches!(args.mode, SearchType::Keyword) {
+                sqlite_provider
+                    .keyword_search(
+                        &args.query,
+                        args.limit,
+                        owner_id,
+                        None,
+                        document_ids.as_deref(),
+                    )
+                    .await?
+            } else {
+                let Some((url, model)) = knowledge_base.embedding() else {
+                    bail!("Vector search needs `--embedding-api-url` and `--embedding-model` (or EMBEDDINGS_API_URL and EMBEDDINGS_MODEL).");
+                };
+                let api_key = std::env::var("AI_API_KEY").ok();
+                let query_vector =
+                    generate_embeddings_batch(url, model, &[&args.query], api_key.as_deref())
+                        .await?
+                        .pop()
+                        .ok_or_else(|| anyhow!("Embedding API returned no vector"))?;
+                sqlite_provider
+                    .vector_search(
+                        query_vector,
+                        args.limit,
+                        owner_id,
+                        None,
+                        document_ids.as_deref(),
+                    )
+                    .await?
+            }
+        }
+    };
+
+    print_results(&args.query, &results);
+    Ok(())
+}
+
+/// Prints the results by rank, with their score, link and the start of their content.
+pub fn print_results(query: &str, results: &[SearchResult]) {
+    if results.is_empty() {
+        println!("No results found for '{query}'.");
+        return;
+    }
+    println!("🔎 {} results for '{query}':", results.len());
+    for (rank, result) in results.iter().enumerate() {
+        println!();
+        println!("{:>2}. [{:.4}] {}", rank + 1, result.score, result.title);
+        println!("    {}", result.link);
+        let excerpt: String = result
+            .description
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if excerpt.chars().count() > EXCERPT_LENGTH {
+            let truncated: String = excerpt.chars().take(EXCERPT_LENGTH - 3).collect();
+            println!("    {truncated}...");
+        } else if !excerpt.is_empty() {
+            println!("    {excerpt}");
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! # CLI Ask Command Tests
+//!
+//! This file contains tests for the `ask` command of the `anyrag-cli`.
+
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_ask_command_needs_a_model() {
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("knowledge.db");
+    std::fs::write(&db_path, "").unwrap();
+
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["ask", "How long do refunds take?", "--json"])
+        .arg("--db")
+        .arg(&db_path)
+        .env_remove("LOCAL_AI_API_URL")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Ask needs `--ai-api-url`"));
+}
+
+#[test]
+fn test_ask_command_reports_a_missing_database() {
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args([
+            "ask",
+            "How long do refunds take?",
+            "--db",
+            "missing/knowledge.db",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not found"));
+}
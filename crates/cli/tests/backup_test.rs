@@ -0,0 +1,78 @@
+//! # CLI Backup Command Tests
+//!
+//! This file contains tests for the `backup` and `restore` commands of the `anyrag-cli`.
+
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::fs;
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_backup_and_restore_commands_round_trip() {
+    // Arrange: A database with two ingested chunks.
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("source.db");
+    let fixture_path = temp_dir.path().join("sample.md");
+    fs::write(&fixture_path, "First chunk.\n---\nSecond chunk.").unwrap();
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["process", "file", fixture_path.to_str().unwrap()])
+        .arg("--db-path")
+        .arg(&db_path)
+        .assert()
+        .success();
+    let backup_path = temp_dir.path().join("backups").join("anyrag.db");
+    let restored_db_path = temp_dir.path().join("restored.db");
+
+    // Act & Assert: Back up the database.
+    Command::cargo_bin("cli")
+        .unwrap()
+        .arg("backup")
+        .arg(&backup_path)
+        .arg("--db-path")
+        .arg(&db_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Backed up"));
+    assert!(backup_path.is_file());
+
+    // Act & Assert: Restore it into a fresh database, but only once.
+    Command::cargo_bin("cli")
+        .unwrap()
+        .arg("restore")
+        .arg(&backup_path)
+        .arg("--db-path")
+        .arg(&restored_db_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored"));
+    Command::cargo_bin("cli")
+        .unwrap()
+        .arg("restore")
+        .arg(&backup_path)
+        .arg("--db-path")
+        .arg(&restored_db_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Restore failed: The database already holds documents",
+        ));
+}
+
+#[test]
+fn test_backup_command_missing_database() {
+    // Arrange
+    let temp_dir = tempdir().unwrap();
+
+    // Act & Assert
+    Command::cargo_bin("cli")
+        .unwrap()
+        .arg("backup")
+        .arg(temp_dir.path().join("backup.db"))
+        .arg("--db-path")
+        .arg(temp_dir.path().join("missing.db"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Backup failed"));
+}
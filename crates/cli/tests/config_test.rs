@@ -0,0 +1,86 @@
+//! # CLI Config Command Tests
+//!
+//! This file contains tests for the `config` command of the `anyrag-cli` and for how
+//! the other commands read the configuration file.
+
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_config_set_and_get() {
+    let temp_dir = tempdir().unwrap();
+    let config_file = temp_dir.path().join("anyrag").join("config.toml");
+
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["config", "set", "ai_model", "llama3"])
+        .env("ANYRAG_CONFIG", &config_file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Set 'ai_model'"));
+    let contents = std::fs::read_to_string(&config_file).unwrap();
+    assert!(contents.contains("ai_model = \"llama3\""), "{contents}");
+
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["config", "get", "ai_model"])
+        .env("ANYRAG_CONFIG", &config_file)
+        .assert()
+        .success()
+        .stdout("llama3\n");
+
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["config", "list"])
+        .env("ANYRAG_CONFIG", &config_file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ai_model = llama3  [AI_MODEL]"))
+        .stdout(predicate::str::contains("db_path = (not set)"));
+}
+
+#[test]
+fn test_config_rejects_an_unknown_key() {
+    let temp_dir = tempdir().unwrap();
+
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["config", "set", "favourite_colour", "blue"])
+        .env("ANYRAG_CONFIG", temp_dir.path().join("config.toml"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unknown setting 'favourite_colour'",
+        ));
+}
+
+#[test]
+fn test_commands_read_the_config_file_under_the_environment() {
+    let temp_dir = tempdir().unwrap();
+    let config_file = temp_dir.path().join("config.toml");
+    std::fs::write(&config_file, "db_path = \"from-config/knowledge.db\"\n").unwrap();
+
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["search", "refunds", "--mode", "keyword"])
+        .env("ANYRAG_CONFIG", &config_file)
+        .env_remove("ANYRAG_DB_PATH")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "'from-config/knowledge.db' not found",
+        ));
+
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["search", "refunds", "--mode", "keyword"])
+        .env("ANYRAG_CONFIG", &config_file)
+        .env("ANYRAG_DB_PATH", "from-env/knowledge.db")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "'from-env/knowledge.db' not found",
+        ));
+}
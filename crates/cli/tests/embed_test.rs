@@ -0,0 +1,57 @@
+//! # CLI Embed Command Tests
+//!
+//! This file contains tests for the `embed` command of the `anyrag-cli`.
+
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::fs;
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_embed_command_needs_an_embedding_model() {
+    let temp_dir = tempdir().unwrap();
+
+    Command::cargo_bin("cli")
+        .unwrap()
+        .arg("embed")
+        .arg("--db")
+        .arg(temp_dir.path().join("knowledge.db"))
+        .env_remove("EMBEDDINGS_API_URL")
+        .env_remove("EMBEDDINGS_MODEL")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Embed needs `--embedding-api-url`",
+        ));
+}
+
+#[test]
+fn test_embed_command_reports_failed_batches() {
+    // Arrange: A database with two documents and no embeddings.
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("knowledge.db");
+    let fixture_path = temp_dir.path().join("sample.md");
+    fs::write(&fixture_path, "First chunk.\n---\nSecond chunk.").unwrap();
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["process", "file", fixture_path.to_str().unwrap()])
+        .arg("--db-path")
+        .arg(&db_path)
+        .assert()
+        .success();
+
+    // Act & Assert: An unreachable embedding API embeds nothing, and says so.
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["embed", "--limit", "1", "--model", "mock-model"])
+        .args(["--embedding-api-url", "http://127.0.0.1:9/v1/embeddings"])
+        .arg("--db")
+        .arg(&db_path)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Embedding 1 documents"))
+        .stderr(predicate::str::contains(
+            "No documents were embedded; 1 failed.",
+        ));
+}
@@ -0,0 +1,76 @@
+//! # CLI Export Command Tests
+//!
+//! This file contains tests for the `export` command of the `anyrag-cli`.
+
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use serde_json::Value;
+use std::fs;
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_export_command_writes_jsonl() {
+    // Arrange: A database with two ingested chunks.
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("source.db");
+    let fixture_path = temp_dir.path().join("sample.md");
+    fs::write(&fixture_path, "First chunk.\n---\nSecond chunk.").unwrap();
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["process", "file", fixture_path.to_str().unwrap()])
+        .arg("--db-path")
+        .arg(&db_path)
+        .assert()
+        .success();
+    let export_path = temp_dir.path().join("export.jsonl");
+
+    // Act
+    Command::cargo_bin("cli")
+        .unwrap()
+        .arg("export")
+        .arg(&export_path)
+        .arg("--db-path")
+        .arg(&db_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported"));
+
+    // Assert: One line per document.
+    let export = fs::read_to_string(&export_path).unwrap();
+    let documents: Vec<Value> = export
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(documents.len(), 2);
+    assert!(documents.iter().all(|doc| doc["content"].is_string()));
+}
+
+#[test]
+fn test_export_command_rejects_unknown_format() {
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["export", "out.csv", "--format", "csv"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("csv"));
+}
+
+#[test]
+fn test_export_command_rejects_unknown_table_format() {
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args([
+            "export",
+            "out.txt",
+            "--table",
+            "products",
+            "--format",
+            "finetuning",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "expected 'csv', 'jsonl' or 'parquet'",
+        ));
+}
@@ -0,0 +1,104 @@
+//! # CLI Import Command Tests
+//!
+//! This file contains tests for the `import` command of the `anyrag-cli`.
+
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::fs;
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_import_command_reads_a_langchain_export() {
+    // Arrange: A LangChain export with two documents.
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("imported.db");
+    let export_path = temp_dir.path().join("documents.jsonl");
+    fs::write(
+        &export_path,
+        "{\"page_content\": \"First document.\", \"metadata\": {\"source\": \"a.md\"}}\n\
+         {\"page_content\": \"Second document.\", \"metadata\": {\"source\": \"b.md\"}}\n",
+    )
+    .unwrap();
+
+    // Act & Assert
+    Command::cargo_bin("cli")
+        .unwrap()
+        .arg("import")
+        .arg(&export_path)
+        .args(["--format", "langchain"])
+        .arg("--db-path")
+        .arg(&db_path)
+        .env_remove("EMBEDDINGS_API_URL")
+        .env_remove("EMBEDDINGS_MODEL")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 2 documents"));
+}
+
+#[test]
+fn test_import_command_reports_invalid_exports() {
+    let temp_dir = tempdir().unwrap();
+    let export_path = temp_dir.path().join("docstore.json");
+    fs::write(&export_path, "{\"not\": \"a docstore\"}").unwrap();
+
+    Command::cargo_bin("cli")
+        .unwrap()
+        .arg("import")
+        .arg(&export_path)
+        .args(["--format", "llamaindex"])
+        .arg("--db-path")
+        .arg(temp_dir.path().join("imported.db"))
+        .env_remove("EMBEDDINGS_API_URL")
+        .env_remove("EMBEDDINGS_MODEL")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Import failed"));
+}
+
+#[test]
+fn test_import_and_export_of_a_table() {
+    // Arrange: A CSV file with a header and two rows.
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("dump.db");
+    let csv_path = temp_dir.path().join("products.csv");
+    fs::write(&csv_path, "_id,name,stock\np1,Kettle,12\np2,Teapot,\n").unwrap();
+
+    // Act: Import it into a new table, then export the table as JSONL.
+    Command::cargo_bin("cli")
+        .unwrap()
+        .arg("import")
+        .arg(&csv_path)
+        .args(["--table", "products", "--format", "csv"])
+        .arg("--db-path")
+        .arg(&db_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 2 rows"))
+        .stdout(predicate::str::contains("table created"));
+    let jsonl_path = temp_dir.path().join("products.jsonl");
+    Command::cargo_bin("cli")
+        .unwrap()
+        .arg("export")
+        .arg(&jsonl_path)
+        .args(["--table", "products"])
+        .arg("--db-path")
+        .arg(&db_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 2 rows"));
+
+    // Assert: The numbers were stored as numbers, and the empty field as NULL.
+    let rows: Vec<serde_json::Value> = fs::read_to_string(&jsonl_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            serde_json::json!({"_id": "p1", "name": "Kettle", "stock": 12}),
+            serde_json::json!({"_id": "p2", "name": "Teapot", "stock": null}),
+        ]
+    );
+}
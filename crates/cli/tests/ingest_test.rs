@@ -0,0 +1,63 @@
+//! # CLI Ingest Command Tests
+//!
+//! This file contains tests for the `ingest` subcommands of the `anyrag-cli`.
+
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::fs;
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_ingest_file_command_stores_paragraphs() {
+    // Arrange
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("knowledge.db");
+    let text_path = temp_dir.path().join("refunds.txt");
+    fs::write(
+        &text_path,
+        "Refunds are paid within 5 days.\n\nShipping takes a week.",
+    )
+    .unwrap();
+
+    // Act & Assert: The file is ingested, and found by a keyword search.
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["ingest", "file"])
+        .arg(&text_path)
+        .args(["--owner-id", "alice", "--db-path"])
+        .arg(&db_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Ingested"));
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args([
+            "search",
+            "refunds",
+            "--mode",
+            "keyword",
+            "--owner-id",
+            "alice",
+        ])
+        .arg("--db-path")
+        .arg(&db_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Refunds are paid within 5 days."));
+}
+
+#[test]
+fn test_ingest_url_command_needs_a_model() {
+    let temp_dir = tempdir().unwrap();
+
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["ingest", "url", "https://example.com"])
+        .arg("--db-path")
+        .arg(temp_dir.path().join("knowledge.db"))
+        .env_remove("LOCAL_AI_API_URL")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("needs `--ai-api-url`"));
+}
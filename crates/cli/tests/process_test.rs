@@ -113,3 +113,46 @@ fn test_process_file_with_embeddings() {
             "Process failed: Embedding generation failed",
         ));
 }
+
+#[test]
+fn test_process_pipeline_rejects_an_unknown_stage() {
+    let temp_dir = tempdir().unwrap();
+    let pipeline_path = temp_dir.path().join("pipeline.yaml");
+    fs::write(
+        &pipeline_path,
+        "name: manuals\nstages:\n  - stage: metadata\n  - stage: translate\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["process", "pipeline"])
+        .arg(&pipeline_path)
+        .arg("--db-path")
+        .arg(temp_dir.path().join("knowledge.db"))
+        .env("LOCAL_AI_API_URL", "http://localhost:1/v1/chat/completions")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid pipeline definition"))
+        .stderr(predicate::str::contains("translate"));
+}
+
+#[test]
+fn test_process_pipeline_needs_an_ai_api_url() {
+    let temp_dir = tempdir().unwrap();
+    let pipeline_path = temp_dir.path().join("pipeline.yaml");
+    fs::write(&pipeline_path, "name: manuals\nstages:\n  - stage: faqs\n").unwrap();
+
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["process", "pipeline"])
+        .arg(&pipeline_path)
+        .arg("--db-path")
+        .arg(temp_dir.path().join("knowledge.db"))
+        .env_remove("LOCAL_AI_API_URL")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Running a pipeline needs `--ai-api-url`",
+        ));
+}
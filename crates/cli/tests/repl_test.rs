@@ -0,0 +1,45 @@
+//! # CLI REPL Tests
+//!
+//! This file contains tests for the meta-commands of the `repl` command of the
+//! `anyrag-cli`, with the session read from standard input.
+
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::fs;
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_repl_lists_tables_and_switches_modes() {
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("knowledge.db");
+    let text_path = temp_dir.path().join("refunds.txt");
+    fs::write(&text_path, "Refunds are paid within 5 days.").unwrap();
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["ingest", "file"])
+        .arg(&text_path)
+        .arg("--db-path")
+        .arg(&db_path)
+        .assert()
+        .success();
+
+    Command::cargo_bin("cli")
+        .unwrap()
+        .arg("repl")
+        .arg("--db-path")
+        .arg(&db_path)
+        .arg("--history-file")
+        .arg(temp_dir.path().join("history"))
+        .env_remove("LOCAL_AI_API_URL")
+        .write_stdin(
+            "\\tables\n\\schema documents\n\\mode sql\n\\mode graph\nHow many documents?\n\\quit\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("documents\n"))
+        .stdout(predicate::str::contains("  source_url "))
+        .stdout(predicate::str::contains("Answering in sql mode."))
+        .stderr(predicate::str::contains("Unknown mode 'graph'"))
+        .stderr(predicate::str::contains("needs `--ai-api-url`"));
+}
@@ -0,0 +1,71 @@
+//! # CLI Search Command Tests
+//!
+//! This file contains tests for the `search` command of the `anyrag-cli`.
+
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::fs;
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_search_command_prints_keyword_results() {
+    // Arrange: A database with two documents of one owner.
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("knowledge.db");
+    let export_path = temp_dir.path().join("documents.jsonl");
+    fs::write(
+        &export_path,
+        "{\"page_content\": \"Refunds are paid within 5 days.\", \"metadata\": {\"source\": \"refunds.md\"}}\n\
+         {\"page_content\": \"Shipping takes a week.\", \"metadata\": {\"source\": \"shipping.md\"}}\n",
+    )
+    .unwrap();
+    Command::cargo_bin("cli")
+        .unwrap()
+        .arg("import")
+        .arg(&export_path)
+        .args(["--format", "langchain", "--owner-id", "alice"])
+        .arg("--db-path")
+        .arg(&db_path)
+        .env_remove("EMBEDDINGS_API_URL")
+        .env_remove("EMBEDDINGS_MODEL")
+        .assert()
+        .success();
+
+    // Act & Assert: Only the matching document is printed, with its score.
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args([
+            "search",
+            "refunds",
+            "--mode",
+            "keyword",
+            "--owner-id",
+            "alice",
+        ])
+        .arg("--db")
+        .arg(&db_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("results for 'refunds'"))
+        .stdout(predicate::str::contains("Refunds are paid within 5 days."))
+        .stdout(predicate::str::contains("Shipping").not());
+}
+
+#[test]
+fn test_search_command_needs_an_embedding_model_for_vector_search() {
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("empty.db");
+    fs::write(&db_path, "").unwrap();
+
+    Command::cargo_bin("cli")
+        .unwrap()
+        .args(["search", "refunds", "--mode", "vector"])
+        .arg("--db-path")
+        .arg(&db_path)
+        .env_remove("EMBEDDINGS_API_URL")
+        .env_remove("EMBEDDINGS_MODEL")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Vector search needs"));
+}
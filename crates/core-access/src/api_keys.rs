@@ -0,0 +1,424 @@
+//! # API Keys
+//!
+//! API keys let services authenticate without the OAuth/JWT flow. A key acts on
+//! behalf of the user that owns it, restricted to its scopes. Only the SHA-256 hash
+//! of a key is stored; the key itself is returned once, when it is created.
+
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use turso::{Connection, Database, Error as TursoError, Row, Value, params};
+use uuid::Uuid;
+
+use crate::{CoreAccessError, User, permissions::load_role_permissions};
+
+/// The prefix of every generated key, which makes keys recognizable in logs and scanners.
+pub const API_KEY_PREFIX: &str = "ak_";
+/// The number of leading characters of a key that are stored to identify it in listings.
+const DISPLAY_PREFIX_LEN: usize = 10;
+const SCOPE_SEPARATOR: char = ',';
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const SELECT_API_KEY_COLUMNS: &str =
+    "SELECT id, owner_id, name, key_prefix, scopes, created_at, last_used_at FROM api_keys";
+
+#[derive(Error, Debug)]
+pub enum ApiKeyError {
+    #[error("Database error: {0}")]
+    Database(#[from] TursoError),
+    #[error("API key not found: {0}")]
+    NotFound(String),
+    #[error("User not found: {0}")]
+    OwnerNotFound(String),
+    #[error("Unknown API key scope: '{0}'")]
+    InvalidScope(String),
+    #[error("An API key needs at least one scope")]
+    NoScopes,
+    #[error("Invalid API key")]
+    InvalidKey,
+    #[error("Data integrity error: {0}")]
+    DataIntegrity(String),
+    #[error(transparent)]
+    CoreAccess(#[from] CoreAccessError),
+}
+
+/// The areas of the API a key may access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyScope {
+    /// Adding content to the knowledge base.
+    Ingest,
+    /// Search, RAG, and reading stored content.
+    Search,
+    /// Text-to-SQL prompts and direct database access.
+    Prompt,
+    /// Administrative endpoints. The owner also needs the matching `admin:*` permission.
+    Admin,
+}
+
+impl ApiKeyScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiKeyScope::Ingest => "ingest",
+            ApiKeyScope::Search => "search",
+            ApiKeyScope::Prompt => "prompt",
+            ApiKeyScope::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for ApiKeyScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ApiKeyScope {
+    type Err = ApiKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ingest" => Ok(ApiKeyScope::Ingest),
+            "search" => Ok(ApiKeyScope::Search),
+            "prompt" => Ok(ApiKeyScope::Prompt),
+            "admin" => Ok(ApiKeyScope::Admin),
+            other => Err(ApiKeyError::InvalidScope(other.to_string())),
+        }
+    }
+}
+
+/// A stored API key, without its secret.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiKey {
+    pub id: String,
+    /// The user the key acts as.
+    pub owner_id: String,
+    pub name: String,
+    /// The first characters of the key, to recognize it without revealing it.
+    pub key_prefix: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub created_at: DateTime<Utc>,
+    /// When the key last authenticated a request.
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    pub fn has_scope(&self, scope: ApiKeyScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+impl TryFrom<&Row> for ApiKey {
+    type Error = ApiKeyError;
+
+    fn try_from(row: &Row) -> Result<Self, Self::Error> {
+        let scopes: String = row.get(4)?;
+        let created_at: String = row.get(5)?;
+        let last_used_at = match row.get_value(6)? {
+            Value::Text(text) => Some(parse_timestamp(&text)?),
+            _ => None,
+        };
+        Ok(ApiKey {
+            id: row.get(0)?,
+            owner_id: row.get(1)?,
+            name: row.get(2)?,
+            key_prefix: row.get(3)?,
+            scopes: parse_scopes(&scopes)?,
+            created_at: parse_timestamp(&created_at)?,
+            last_used_at,
+        })
+    }
+}
+
+/// A newly created key. `key` is the secret, and is not retrievable later.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NewApiKey {
+    pub key: String,
+    pub api_key: ApiKey,
+}
+
+/// Creates a key for an existing user.
+pub async fn create_api_key(
+    db: &Database,
+    owner_id: &str,
+    name: &str,
+    scopes: &[ApiKeyScope],
+) -> Result<NewApiKey, ApiKeyError> {
+    let scopes = normalize_scopes(scopes)?;
+    let conn = db.connect()?;
+    find_user(&conn, owner_id).await?;
+
+    let id = Uuid::new_v4().to_string();
+    // Two random UUIDs give 244 bits of entropy.
+    let key = format!(
+        "{API_KEY_PREFIX}{}{}",
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    );
+    let key_prefix = key[..DISPLAY_PREFIX_LEN].to_string();
+    conn.execute(
+        "INSERT INTO api_keys (id, owner_id, name, key_hash, key_prefix, scopes, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![
+            id.clone(),
+            owner_id,
+            name,
+            hash_key(&key),
+            key_prefix,
+            join_scopes(&scopes),
+            now()
+        ],
+    )
+    .await?;
+
+    let api_key = get_api_key(db, &id).await?;
+    Ok(NewApiKey { key, api_key })
+}
+
+/// Lists every key, newest first.
+pub async fn list_api_keys(db: &Database) -> Result<Vec<ApiKey>, ApiKeyError> {
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_API_KEY_COLUMNS} ORDER BY created_at DESC"),
+            (),
+        )
+        .await?;
+    let mut keys = Vec::new();
+    while let Some(row) = rows.next().await? {
+        keys.push(ApiKey::try_from(&row)?);
+    }
+    Ok(keys)
+}
+
+pub async fn get_api_key(db: &Database, id: &str) -> Result<ApiKey, ApiKeyError> {
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_API_KEY_COLUMNS} WHERE id = ?"),
+            params![id],
+        )
+        .await?;
+    let row = rows
+        .next()
+        .await?
+        .ok_or_else(|| ApiKeyError::NotFound(id.to_string()))?;
+    ApiKey::try_from(&row)
+}
+
+/// Renames a key or replaces its scopes. Omitted fields are left unchanged.
+pub async fn update_api_key(
+    db: &Database,
+    id: &str,
+    name: Option<&str>,
+    scopes: Option<&[ApiKeyScope]>,
+) -> Result<ApiKey, ApiKeyError> {
+    let current = get_api_key(db, id).await?;
+    let name = name.unwrap_or(&current.name);
+    let scopes = match scopes {
+        Some(scopes) => normalize_scopes(scopes)?,
+        None => current.scopes,
+    };
+
+    let conn = db.connect()?;
+    conn.execute(
+        "UPDATE api_keys SET name = ?, scopes = ? WHERE id = ?",
+        params![name, join_scopes(&scopes), id],
+    )
+    .await?;
+    get_api_key(db, id).await
+}
+
+/// Revokes a key. Requests made with it are rejected from then on.
+pub async fn delete_api_key(db: &Database, id: &str) -> Result<(), ApiKeyError> {
+    let conn = db.connect()?;
+    let deleted = conn
+        .execute("DELETE FROM api_keys WHERE id = ?", params![id])
+        .await?;
+    if deleted == 0 {
+        return Err(ApiKeyError::NotFound(id.to_string()));
+    }
+    Ok(())
+}
+
+/// Resolves a key to its owner, and records that it was used.
+pub async fn authenticate_api_key(db: &Database, key: &str) -> Result<(User, ApiKey), ApiKeyError> {
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_API_KEY_COLUMNS} WHERE key_hash = ?"),
+            params![hash_key(key)],
+        )
+        .await?;
+    let row = rows.next().await?.ok_or(ApiKeyError::InvalidKey)?;
+    let mut api_key = ApiKey::try_from(&row)?;
+    // The owner may have been removed without the database cascading the delete.
+    let owner = match find_user(&conn, &api_key.owner_id).await {
+        Ok(owner) => owner,
+        Err(ApiKeyError::OwnerNotFound(_)) => return Err(ApiKeyError::InvalidKey),
+        Err(e) => return Err(e),
+    };
+
+    let used_at = now();
+    conn.execute(
+        "UPDATE api_keys SET last_used_at = ? WHERE id = ?",
+        params![used_at.clone(), api_key.id.clone()],
+    )
+    .await?;
+    api_key.last_used_at = Some(parse_timestamp(&used_at)?);
+    Ok((owner, api_key))
+}
+
+async fn find_user(conn: &Connection, user_id: &str) -> Result<User, ApiKeyError> {
+    let mut rows = conn
+        .query(
+            "SELECT id, role, created_at FROM users WHERE id = ?",
+            params![user_id],
+        )
+        .await?;
+    let row = rows
+        .next()
+        .await?
+        .ok_or_else(|| ApiKeyError::OwnerNotFound(user_id.to_string()))?;
+    let mut user = User::try_from(&row)?;
+    user.permissions = load_role_permissions(conn, &user.role)
+        .await
+        .map_err(CoreAccessError::from)?;
+    Ok(user)
+}
+
+fn hash_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Removes duplicate scopes while keeping their order, and rejects an empty list.
+fn normalize_scopes(scopes: &[ApiKeyScope]) -> Result<Vec<ApiKeyScope>, ApiKeyError> {
+    let mut normalized = Vec::new();
+    for scope in scopes {
+        if !normalized.contains(scope) {
+            normalized.push(*scope);
+        }
+    }
+    if normalized.is_empty() {
+        return Err(ApiKeyError::NoScopes);
+    }
+    Ok(normalized)
+}
+
+fn join_scopes(scopes: &[ApiKeyScope]) -> String {
+    scopes
+        .iter()
+        .map(ApiKeyScope::as_str)
+        .collect::<Vec<_>>()
+        .join(&SCOPE_SEPARATOR.to_string())
+}
+
+fn parse_scopes(scopes: &str) -> Result<Vec<ApiKeyScope>, ApiKeyError> {
+    scopes
+        .split(SCOPE_SEPARATOR)
+        .filter(|scope| !scope.is_empty())
+        .map(ApiKeyScope::from_str)
+        .collect()
+}
+
+fn now() -> String {
+    Utc::now().format(TIMESTAMP_FORMAT).to_string()
+}
+
+fn parse_timestamp(text: &str) -> Result<DateTime<Utc>, ApiKeyError> {
+    NaiveDateTime::parse_from_str(text, TIMESTAMP_FORMAT)
+        .map(|ndt| DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc))
+        .map_err(|e| ApiKeyError::DataIntegrity(format!("Failed to parse date '{text}': {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_or_create_user;
+    use anyrag::providers::db::sqlite::SqliteProvider;
+
+    #[tokio::test]
+    async fn test_api_key_lifecycle() {
+        // 1. Arrange
+        let provider = SqliteProvider::new(":memory:").await.unwrap();
+        provider.initialize_schema().await.unwrap();
+        let db = provider.db;
+        let owner = get_or_create_user(&db, "service@example.com", None)
+            .await
+            .unwrap();
+
+        // 2. Act: Create a key with a duplicated scope.
+        let created = create_api_key(
+            &db,
+            &owner.id,
+            "ingest-bot",
+            &[ApiKeyScope::Ingest, ApiKeyScope::Ingest],
+        )
+        .await
+        .unwrap();
+
+        // 3. Assert: The key is returned once, and only its prefix is listed.
+        assert!(created.key.starts_with(API_KEY_PREFIX));
+        assert_eq!(created.api_key.scopes, vec![ApiKeyScope::Ingest]);
+        assert!(created.api_key.last_used_at.is_none());
+        assert!(created.key.starts_with(&created.api_key.key_prefix));
+
+        // 4. Act & Assert: Authenticating resolves the owner and records the use.
+        let (user, api_key) = authenticate_api_key(&db, &created.key).await.unwrap();
+        assert_eq!(user.id, owner.id);
+        assert!(api_key.last_used_at.is_some());
+        let stored = get_api_key(&db, &api_key.id).await.unwrap();
+        assert!(stored.last_used_at.is_some());
+
+        // 5. Act & Assert: Updating the scopes keeps the name.
+        let updated = update_api_key(
+            &db,
+            &api_key.id,
+            None,
+            Some(&[ApiKeyScope::Search, ApiKeyScope::Prompt]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.name, "ingest-bot");
+        assert!(updated.has_scope(ApiKeyScope::Search));
+        assert!(!updated.has_scope(ApiKeyScope::Ingest));
+
+        // 6. Act & Assert: A deleted key no longer authenticates.
+        delete_api_key(&db, &api_key.id).await.unwrap();
+        assert!(matches!(
+            authenticate_api_key(&db, &created.key).await,
+            Err(ApiKeyError::InvalidKey)
+        ));
+        assert!(list_api_keys(&db).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_create_api_key_validates_input() {
+        let provider = SqliteProvider::new(":memory:").await.unwrap();
+        provider.initialize_schema().await.unwrap();
+        let db = provider.db;
+        let owner = get_or_create_user(&db, "service@example.com", None)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            create_api_key(&db, &owner.id, "empty", &[]).await,
+            Err(ApiKeyError::NoScopes)
+        ));
+        assert!(matches!(
+            create_api_key(&db, "missing-user", "orphan", &[ApiKeyScope::Search]).await,
+            Err(ApiKeyError::OwnerNotFound(_))
+        ));
+        assert!(matches!(
+            "write".parse::<ApiKeyScope>(),
+            Err(ApiKeyError::InvalidScope(_))
+        ));
+    }
+}
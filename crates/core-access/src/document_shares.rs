@@ -0,0 +1,297 @@
+//! # Document Shares
+//!
+//! The owner of a document can grant other users read access to it. A share makes
+//! the document visible to the user in searches and in the document list, as if they
+//! owned it. Users with the `admin:documents` permission can share any document.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use turso::{Connection, Database, Error as TursoError, Row, params};
+
+use crate::{User, has_permission, permissions::ADMIN_DOCUMENTS};
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const SELECT_SHARE_COLUMNS: &str =
+    "SELECT document_id, user_id, granted_by, created_at FROM document_shares";
+
+#[derive(Error, Debug)]
+pub enum ShareError {
+    #[error("Database error: {0}")]
+    Database(#[from] TursoError),
+    #[error("Document not found: {0}")]
+    DocumentNotFound(String),
+    #[error("Only the owner of document '{0}' can share it")]
+    NotOwner(String),
+    #[error("User not found: {0}")]
+    UserNotFound(String),
+    #[error("Document '{document_id}' is not shared with user '{user_id}'")]
+    ShareNotFound {
+        document_id: String,
+        user_id: String,
+    },
+    #[error("Data integrity error: {0}")]
+    DataIntegrity(String),
+}
+
+/// Read access to a document, granted to a user who doesn't own it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DocumentShare {
+    pub document_id: String,
+    pub user_id: String,
+    /// The user who shared the document.
+    pub granted_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<&Row> for DocumentShare {
+    type Error = ShareError;
+
+    fn try_from(row: &Row) -> Result<Self, Self::Error> {
+        let created_at: String = row.get(3)?;
+        Ok(DocumentShare {
+            document_id: row.get(0)?,
+            user_id: row.get(1)?,
+            granted_by: row.get(2)?,
+            created_at: parse_timestamp(&created_at)?,
+        })
+    }
+}
+
+/// Grants the users read access to a document, returning every share of the
+/// document. Users who already have access are left unchanged.
+///
+/// The shares are stored next to the document in `db`, while the users are looked up
+/// in `users_db`, the database of the access control tables. The two differ when the
+/// knowledge base is sharded.
+pub async fn share_document(
+    db: &Database,
+    users_db: &Database,
+    document_id: &str,
+    sharer: &User,
+    user_ids: &[String],
+) -> Result<Vec<DocumentShare>, ShareError> {
+    let conn = db.connect()?;
+    require_document_owner(&conn, document_id, sharer).await?;
+    let users_conn = users_db.connect()?;
+    for user_id in user_ids {
+        ensure_user_exists(&users_conn, user_id).await?;
+    }
+
+    let created_at = now();
+    for user_id in user_ids {
+        if find_share(&conn, document_id, user_id).await?.is_some() {
+            continue;
+        }
+        conn.execute(
+            "INSERT INTO document_shares (document_id, user_id, granted_by, created_at) VALUES (?, ?, ?, ?)",
+            params![
+                document_id,
+                user_id.as_str(),
+                sharer.id.as_str(),
+                created_at.as_str()
+            ],
+        )
+        .await?;
+    }
+    load_shares(&conn, document_id).await
+}
+
+/// Lists the users a document is shared with. Only its owner may see them.
+pub async fn list_document_shares(
+    db: &Database,
+    document_id: &str,
+    requester: &User,
+) -> Result<Vec<DocumentShare>, ShareError> {
+    let conn = db.connect()?;
+    require_document_owner(&conn, document_id, requester).await?;
+    load_shares(&conn, document_id).await
+}
+
+/// Revokes a user's access to a document.
+pub async fn unshare_document(
+    db: &Database,
+    document_id: &str,
+    sharer: &User,
+    user_id: &str,
+) -> Result<(), ShareError> {
+    let conn = db.connect()?;
+    require_document_owner(&conn, document_id, sharer).await?;
+    let deleted = conn
+        .execute(
+            "DELETE FROM document_shares WHERE document_id = ? AND user_id = ?",
+            params![document_id, user_id],
+        )
+        .await?;
+    if deleted == 0 {
+        return Err(ShareError::ShareNotFound {
+            document_id: document_id.to_string(),
+            user_id: user_id.to_string(),
+        });
+    }
+    Ok(())
+}
+
+async fn require_document_owner(
+    conn: &Connection,
+    document_id: &str,
+    user: &User,
+) -> Result<(), ShareError> {
+    let mut rows = conn
+        .query(
+            "SELECT owner_id FROM documents WHERE id = ?",
+            params![document_id],
+        )
+        .await?;
+    let row = rows
+        .next()
+        .await?
+        .ok_or_else(|| ShareError::DocumentNotFound(document_id.to_string()))?;
+    let owner_id: Option<String> = match row.get_value(0)? {
+        turso::Value::Text(owner_id) => Some(owner_id),
+        _ => None,
+    };
+    if owner_id.as_deref() != Some(user.id.as_str()) && !has_permission(user, ADMIN_DOCUMENTS) {
+        return Err(ShareError::NotOwner(document_id.to_string()));
+    }
+    Ok(())
+}
+
+async fn load_shares(
+    conn: &Connection,
+    document_id: &str,
+) -> Result<Vec<DocumentShare>, ShareError> {
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_SHARE_COLUMNS} WHERE document_id = ? ORDER BY created_at, user_id"),
+            params![document_id],
+        )
+        .await?;
+    let mut shares = Vec::new();
+    while let Some(row) = rows.next().await? {
+        shares.push(DocumentShare::try_from(&row)?);
+    }
+    Ok(shares)
+}
+
+async fn find_share(
+    conn: &Connection,
+    document_id: &str,
+    user_id: &str,
+) -> Result<Option<DocumentShare>, ShareError> {
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_SHARE_COLUMNS} WHERE document_id = ? AND user_id = ?"),
+            params![document_id, user_id],
+        )
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(DocumentShare::try_from(&row)?)),
+        None => Ok(None),
+    }
+}
+
+async fn ensure_user_exists(conn: &Connection, user_id: &str) -> Result<(), ShareError> {
+    let mut rows = conn
+        .query("SELECT id FROM users WHERE id = ?", params![user_id])
+        .await?;
+    if rows.next().await?.is_none() {
+        return Err(ShareError::UserNotFound(user_id.to_string()));
+    }
+    Ok(())
+}
+
+fn now() -> String {
+    Utc::now().format(TIMESTAMP_FORMAT).to_string()
+}
+
+fn parse_timestamp(text: &str) -> Result<DateTime<Utc>, ShareError> {
+    NaiveDateTime::parse_from_str(text, TIMESTAMP_FORMAT)
+        .map(|ndt| DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc))
+        .map_err(|e| ShareError::DataIntegrity(format!("Failed to parse date '{text}': {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_or_create_user;
+    use anyrag::providers::db::sqlite::SqliteProvider;
+
+    async fn insert_document(db: &Database, id: &str, owner_id: &str) {
+        let conn = db.connect().unwrap();
+        conn.execute(
+            "INSERT INTO documents (id, owner_id, source_url, title, content) VALUES (?, ?, ?, ?, ?)",
+            params![id, owner_id, "https://example.com", "Plan", "The plan."],
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_document_share_lifecycle() {
+        // 1. Arrange
+        let provider = SqliteProvider::new(":memory:").await.unwrap();
+        provider.initialize_schema().await.unwrap();
+        let db = provider.db;
+        let owner = get_or_create_user(&db, "owner@example.com", None)
+            .await
+            .unwrap();
+        let reader = get_or_create_user(&db, "reader@example.com", None)
+            .await
+            .unwrap();
+        insert_document(&db, "doc-1", &owner.id).await;
+
+        // 2. Act: Share the document twice with the same user.
+        share_document(&db, &db, "doc-1", &owner, &[reader.id.clone()])
+            .await
+            .unwrap();
+        let shares = share_document(&db, &db, "doc-1", &owner, &[reader.id.clone()])
+            .await
+            .unwrap();
+
+        // 3. Assert: The user has a single share, which only the owner manages.
+        assert_eq!(shares.len(), 1);
+        assert_eq!(shares[0].user_id, reader.id);
+        assert_eq!(shares[0].granted_by, owner.id);
+        assert!(matches!(
+            share_document(&db, &db, "doc-1", &reader, &[owner.id.clone()]).await,
+            Err(ShareError::NotOwner(_))
+        ));
+
+        // 4. Act & Assert: Revoking the share removes it.
+        unshare_document(&db, "doc-1", &owner, &reader.id)
+            .await
+            .unwrap();
+        assert!(
+            list_document_shares(&db, "doc-1", &owner)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(matches!(
+            unshare_document(&db, "doc-1", &owner, &reader.id).await,
+            Err(ShareError::ShareNotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_share_document_validates_input() {
+        let provider = SqliteProvider::new(":memory:").await.unwrap();
+        provider.initialize_schema().await.unwrap();
+        let db = provider.db;
+        let owner = get_or_create_user(&db, "owner@example.com", None)
+            .await
+            .unwrap();
+        insert_document(&db, "doc-1", &owner.id).await;
+
+        assert!(matches!(
+            share_document(&db, &db, "missing-doc", &owner, &[]).await,
+            Err(ShareError::DocumentNotFound(_))
+        ));
+        assert!(matches!(
+            share_document(&db, &db, "doc-1", &owner, &["missing-user".to_string()]).await,
+            Err(ShareError::UserNotFound(_))
+        ));
+    }
+}
@@ -3,6 +3,22 @@
 //! This crate is the central authority for all identity, authentication (AuthN),
 //! and authorization (AuthZ) logic for the `anyrag` application.
 
+pub mod api_keys;
+pub mod document_shares;
+pub mod organizations;
+pub mod permissions;
+pub mod sessions;
+pub mod usage;
+
+pub use api_keys::{ApiKey, ApiKeyError, ApiKeyScope, NewApiKey};
+pub use document_shares::{DocumentShare, ShareError};
+pub use organizations::{OrgError, OrgMember, OrgRole, Organization};
+pub use permissions::{PermissionError, has_permission, require_permission};
+pub use sessions::{NewSession, Session, SessionError};
+pub use usage::{Usage, UsageError};
+
+use permissions::load_role_permissions;
+
 pub const GUEST_USER_IDENTIFIER: &str = "::guest::";
 
 use anyhow::Result;
@@ -22,10 +38,13 @@ pub enum CoreAccessError {
     UserPersistenceFailed(String),
     #[error("Data integrity error: {0}")]
     DataIntegrity(String),
+    #[error(transparent)]
+    Permission(#[from] PermissionError),
 }
 
 /// Represents a user in the system.
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct User {
     /// The unique, deterministic ID of the user (UUIDv5 from an external identifier).
     pub id: String,
@@ -33,6 +52,9 @@ pub struct User {
     pub role: String,
     /// The timestamp when the user was first created.
     pub created_at: DateTime<Utc>,
+    /// The `resource:action` permissions granted by the user's role.
+    #[serde(default)]
+    pub permissions: Vec<String>,
 }
 
 impl TryFrom<&Row> for User {
@@ -53,6 +75,7 @@ impl TryFrom<&Row> for User {
             id: row.get(0)?,
             role: row.get(1)?,
             created_at,
+            permissions: Vec::new(),
         })
     }
 }
@@ -108,12 +131,13 @@ pub async fn get_or_create_user(
             user.role = new_role.to_string(); // Update the struct to be returned
         }
 
+        user.permissions = load_role_permissions(&conn, &user.role).await?;
         return Ok(user);
     }
 
     // info!("[core_access] User not found, creating new user.");
     // 2. User does not exist. Determine role.
-    let role = role_override.unwrap_or("user");
+    let role = role_override.unwrap_or(permissions::USER_ROLE);
     // info!("[core_access] Determined role for new user: '{role}'");
 
     // 2.5. If user doesn't exist, INSERT. If the INSERT fails due to a UNIQUE
@@ -147,7 +171,8 @@ pub async fn get_or_create_user(
         .await?
         .ok_or_else(|| CoreAccessError::UserPersistenceFailed(user_identifier.to_string()))?;
 
-    let user = User::try_from(&row)?;
+    let mut user = User::try_from(&row)?;
+    user.permissions = load_role_permissions(&conn, &user.role).await?;
     // info!("[core_access] Returning newly created user: {:?}", user);
     Ok(user)
 }
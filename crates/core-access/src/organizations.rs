@@ -0,0 +1,436 @@
+//! # Organizations
+//!
+//! An organization is a team workspace. Its members can search the documents shared
+//! with it, which are the documents whose `org_id` is set to the organization. The
+//! user who creates an organization becomes its first owner; owners manage the
+//! members.
+
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use turso::{Connection, Database, Error as TursoError, Row, Value, params};
+use uuid::Uuid;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const SELECT_ORGANIZATION_COLUMNS: &str = "SELECT o.id, o.name, o.created_at FROM organizations o";
+const SELECT_MEMBER_COLUMNS: &str = "SELECT org_id, user_id, role, joined_at FROM org_members";
+
+#[derive(Error, Debug)]
+pub enum OrgError {
+    #[error("Database error: {0}")]
+    Database(#[from] TursoError),
+    #[error("Organization not found: {0}")]
+    NotFound(String),
+    #[error("User '{user_id}' is not a member of organization '{org_id}'")]
+    NotMember { org_id: String, user_id: String },
+    #[error("Only the owners of organization '{0}' can manage its members")]
+    NotOwner(String),
+    #[error("An organization needs at least one owner")]
+    LastOwner,
+    #[error("User not found: {0}")]
+    UserNotFound(String),
+    #[error("Unknown organization role: '{0}'")]
+    InvalidRole(String),
+    #[error("Data integrity error: {0}")]
+    DataIntegrity(String),
+}
+
+/// The role of a user within an organization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum OrgRole {
+    /// Can manage the members of the organization.
+    Owner,
+    Member,
+}
+
+impl OrgRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrgRole::Owner => "owner",
+            OrgRole::Member => "member",
+        }
+    }
+}
+
+impl fmt::Display for OrgRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OrgRole {
+    type Err = OrgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "owner" => Ok(OrgRole::Owner),
+            "member" => Ok(OrgRole::Member),
+            other => Err(OrgError::InvalidRole(other.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Organization {
+    pub id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<&Row> for Organization {
+    type Error = OrgError;
+
+    fn try_from(row: &Row) -> Result<Self, Self::Error> {
+        let created_at: String = row.get(2)?;
+        Ok(Organization {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            created_at: parse_timestamp(&created_at)?,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct OrgMember {
+    pub org_id: String,
+    pub user_id: String,
+    pub role: OrgRole,
+    pub joined_at: DateTime<Utc>,
+}
+
+impl TryFrom<&Row> for OrgMember {
+    type Error = OrgError;
+
+    fn try_from(row: &Row) -> Result<Self, Self::Error> {
+        let role: String = row.get(2)?;
+        let joined_at: String = row.get(3)?;
+        Ok(OrgMember {
+            org_id: row.get(0)?,
+            user_id: row.get(1)?,
+            role: role.parse()?,
+            joined_at: parse_timestamp(&joined_at)?,
+        })
+    }
+}
+
+/// Creates an organization, with `owner_id` as its first owner.
+pub async fn create_organization(
+    db: &Database,
+    name: &str,
+    owner_id: &str,
+) -> Result<Organization, OrgError> {
+    let conn = db.connect()?;
+    ensure_user_exists(&conn, owner_id).await?;
+
+    let id = Uuid::new_v4().to_string();
+    let created_at = now();
+    conn.execute(
+        "INSERT INTO organizations (id, name, created_at) VALUES (?, ?, ?)",
+        params![id.clone(), name, created_at.clone()],
+    )
+    .await?;
+    conn.execute(
+        "INSERT INTO org_members (org_id, user_id, role, joined_at) VALUES (?, ?, ?, ?)",
+        params![id.clone(), owner_id, OrgRole::Owner.as_str(), created_at],
+    )
+    .await?;
+    get_organization(db, &id).await
+}
+
+pub async fn get_organization(db: &Database, id: &str) -> Result<Organization, OrgError> {
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_ORGANIZATION_COLUMNS} WHERE o.id = ?"),
+            params![id],
+        )
+        .await?;
+    let row = rows
+        .next()
+        .await?
+        .ok_or_else(|| OrgError::NotFound(id.to_string()))?;
+    Organization::try_from(&row)
+}
+
+/// Lists the organizations the user is a member of, by name.
+pub async fn list_user_organizations(
+    db: &Database,
+    user_id: &str,
+) -> Result<Vec<Organization>, OrgError> {
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            &format!(
+                "{SELECT_ORGANIZATION_COLUMNS} JOIN org_members m ON m.org_id = o.id WHERE m.user_id = ? ORDER BY o.name"
+            ),
+            params![user_id],
+        )
+        .await?;
+    let mut organizations = Vec::new();
+    while let Some(row) = rows.next().await? {
+        organizations.push(Organization::try_from(&row)?);
+    }
+    Ok(organizations)
+}
+
+/// Returns the user's membership, rejecting users outside the organization.
+pub async fn require_membership(
+    db: &Database,
+    org_id: &str,
+    user_id: &str,
+) -> Result<OrgMember, OrgError> {
+    let conn = db.connect()?;
+    find_member(&conn, org_id, user_id)
+        .await?
+        .ok_or_else(|| OrgError::NotMember {
+            org_id: org_id.to_string(),
+            user_id: user_id.to_string(),
+        })
+}
+
+/// Rejects users who are not owners of the organization.
+pub async fn require_owner(db: &Database, org_id: &str, user_id: &str) -> Result<(), OrgError> {
+    let member = require_membership(db, org_id, user_id).await?;
+    if member.role != OrgRole::Owner {
+        return Err(OrgError::NotOwner(org_id.to_string()));
+    }
+    Ok(())
+}
+
+/// Lists the members of an organization, in the order they joined.
+pub async fn list_members(db: &Database, org_id: &str) -> Result<Vec<OrgMember>, OrgError> {
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_MEMBER_COLUMNS} WHERE org_id = ? ORDER BY joined_at, user_id"),
+            params![org_id],
+        )
+        .await?;
+    let mut members = Vec::new();
+    while let Some(row) = rows.next().await? {
+        members.push(OrgMember::try_from(&row)?);
+    }
+    Ok(members)
+}
+
+/// Adds a user to an organization. A user who is already a member gets the new role.
+pub async fn add_member(
+    db: &Database,
+    org_id: &str,
+    user_id: &str,
+    role: OrgRole,
+) -> Result<OrgMember, OrgError> {
+    get_organization(db, org_id).await?;
+    let conn = db.connect()?;
+    ensure_user_exists(&conn, user_id).await?;
+
+    match find_member(&conn, org_id, user_id).await? {
+        Some(member) if member.role == role => return Ok(member),
+        Some(member) => {
+            if member.role == OrgRole::Owner {
+                ensure_other_owner(&conn, org_id, user_id).await?;
+            }
+            conn.execute(
+                "UPDATE org_members SET role = ? WHERE org_id = ? AND user_id = ?",
+                params![role.as_str(), org_id, user_id],
+            )
+            .await?;
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO org_members (org_id, user_id, role, joined_at) VALUES (?, ?, ?, ?)",
+                params![org_id, user_id, role.as_str(), now()],
+            )
+            .await?;
+        }
+    }
+    require_membership(db, org_id, user_id).await
+}
+
+/// Removes a user from an organization. The last owner cannot be removed.
+pub async fn remove_member(db: &Database, org_id: &str, user_id: &str) -> Result<(), OrgError> {
+    let member = require_membership(db, org_id, user_id).await?;
+    let conn = db.connect()?;
+    if member.role == OrgRole::Owner {
+        ensure_other_owner(&conn, org_id, user_id).await?;
+    }
+    conn.execute(
+        "DELETE FROM org_members WHERE org_id = ? AND user_id = ?",
+        params![org_id, user_id],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Shares documents with an organization, returning how many were updated.
+pub async fn share_documents(
+    db: &Database,
+    org_id: &str,
+    document_ids: &[String],
+) -> Result<u64, OrgError> {
+    if document_ids.is_empty() {
+        return Ok(0);
+    }
+    let conn = db.connect()?;
+    let placeholders = vec!["?"; document_ids.len()].join(", ");
+    let mut query_params: Vec<Value> = vec![org_id.to_string().into()];
+    query_params.extend(document_ids.iter().map(|id| Value::from(id.clone())));
+    let updated = conn
+        .execute(
+            &format!("UPDATE documents SET org_id = ? WHERE id IN ({placeholders})"),
+            query_params,
+        )
+        .await?;
+    Ok(updated)
+}
+
+async fn find_member(
+    conn: &Connection,
+    org_id: &str,
+    user_id: &str,
+) -> Result<Option<OrgMember>, OrgError> {
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_MEMBER_COLUMNS} WHERE org_id = ? AND user_id = ?"),
+            params![org_id, user_id],
+        )
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(OrgMember::try_from(&row)?)),
+        None => Ok(None),
+    }
+}
+
+async fn ensure_user_exists(conn: &Connection, user_id: &str) -> Result<(), OrgError> {
+    let mut rows = conn
+        .query("SELECT id FROM users WHERE id = ?", params![user_id])
+        .await?;
+    if rows.next().await?.is_none() {
+        return Err(OrgError::UserNotFound(user_id.to_string()));
+    }
+    Ok(())
+}
+
+/// Rejects changes that would leave the organization without an owner.
+async fn ensure_other_owner(
+    conn: &Connection,
+    org_id: &str,
+    user_id: &str,
+) -> Result<(), OrgError> {
+    let mut rows = conn
+        .query(
+            "SELECT user_id FROM org_members WHERE org_id = ? AND role = ? AND user_id != ?",
+            params![org_id, OrgRole::Owner.as_str(), user_id],
+        )
+        .await?;
+    if rows.next().await?.is_none() {
+        return Err(OrgError::LastOwner);
+    }
+    Ok(())
+}
+
+fn now() -> String {
+    Utc::now().format(TIMESTAMP_FORMAT).to_string()
+}
+
+fn parse_timestamp(text: &str) -> Result<DateTime<Utc>, OrgError> {
+    NaiveDateTime::parse_from_str(text, TIMESTAMP_FORMAT)
+        .map(|ndt| DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc))
+        .map_err(|e| OrgError::DataIntegrity(format!("Failed to parse date '{text}': {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_or_create_user;
+    use anyrag::providers::db::sqlite::SqliteProvider;
+
+    #[tokio::test]
+    async fn test_organization_membership_lifecycle() {
+        // 1. Arrange
+        let provider = SqliteProvider::new(":memory:").await.unwrap();
+        provider.initialize_schema().await.unwrap();
+        let db = provider.db;
+        let alice = get_or_create_user(&db, "alice@example.com", None)
+            .await
+            .unwrap();
+        let bob = get_or_create_user(&db, "bob@example.com", None)
+            .await
+            .unwrap();
+
+        // 2. Act: Alice creates an organization and adds Bob.
+        let org = create_organization(&db, "Research", &alice.id)
+            .await
+            .unwrap();
+        let member = add_member(&db, &org.id, &bob.id, OrgRole::Member)
+            .await
+            .unwrap();
+
+        // 3. Assert: Both are members, and only Alice owns the organization.
+        assert_eq!(member.role, OrgRole::Member);
+        assert_eq!(list_members(&db, &org.id).await.unwrap().len(), 2);
+        assert!(require_owner(&db, &org.id, &alice.id).await.is_ok());
+        assert!(matches!(
+            require_owner(&db, &org.id, &bob.id).await,
+            Err(OrgError::NotOwner(_))
+        ));
+        let bobs_orgs = list_user_organizations(&db, &bob.id).await.unwrap();
+        assert_eq!(bobs_orgs.len(), 1);
+        assert_eq!(bobs_orgs[0].name, "Research");
+
+        // 4. Act & Assert: The last owner can neither leave nor be demoted.
+        assert!(matches!(
+            remove_member(&db, &org.id, &alice.id).await,
+            Err(OrgError::LastOwner)
+        ));
+        assert!(matches!(
+            add_member(&db, &org.id, &alice.id, OrgRole::Member).await,
+            Err(OrgError::LastOwner)
+        ));
+
+        // 5. Act & Assert: A removed member loses access.
+        remove_member(&db, &org.id, &bob.id).await.unwrap();
+        assert!(matches!(
+            require_membership(&db, &org.id, &bob.id).await,
+            Err(OrgError::NotMember { .. })
+        ));
+        assert!(
+            list_user_organizations(&db, &bob.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_member_validates_input() {
+        let provider = SqliteProvider::new(":memory:").await.unwrap();
+        provider.initialize_schema().await.unwrap();
+        let db = provider.db;
+        let alice = get_or_create_user(&db, "alice@example.com", None)
+            .await
+            .unwrap();
+        let org = create_organization(&db, "Research", &alice.id)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            add_member(&db, &org.id, "missing-user", OrgRole::Member).await,
+            Err(OrgError::UserNotFound(_))
+        ));
+        assert!(matches!(
+            add_member(&db, "missing-org", &alice.id, OrgRole::Member).await,
+            Err(OrgError::NotFound(_))
+        ));
+        assert!(matches!(
+            "admin".parse::<OrgRole>(),
+            Err(OrgError::InvalidRole(_))
+        ));
+    }
+}
@@ -0,0 +1,239 @@
+//! # Permissions
+//!
+//! A permission is a `resource:action` string such as `ingest:write`, where either
+//! part may be the `*` wildcard (`admin:*` grants every admin action). A user has the
+//! permissions of their role. Roles are granted permissions in the `role_permissions`
+//! table, which the schema migrations seed with the built-in defaults of `root` and
+//! `user`; a role without rows there falls back to its built-in defaults.
+
+use thiserror::Error;
+use turso::{Connection, Database, Error as TursoError, params};
+
+use crate::User;
+
+pub const WILDCARD: &str = "*";
+const PERMISSION_SEPARATOR: char = ':';
+
+/// The role of administrators, which is granted every permission by default.
+pub const ROOT_ROLE: &str = "root";
+/// The role of new users, including the guest user.
+pub const USER_ROLE: &str = "user";
+
+/// Every permission.
+pub const ALL: &str = "*:*";
+/// Adding content to the knowledge base.
+pub const INGEST_WRITE: &str = "ingest:write";
+/// Search, RAG, and reading stored content.
+pub const SEARCH_READ: &str = "search:read";
+/// Text-to-SQL prompts, direct database access, experiments, and feedback.
+pub const PROMPT_EXECUTE: &str = "prompt:execute";
+/// Listing every user.
+pub const ADMIN_USERS: &str = "admin:users";
+/// Managing API keys.
+pub const ADMIN_API_KEYS: &str = "admin:api_keys";
+/// Accepting feedback corrections as few-shot examples.
+pub const ADMIN_FEEDBACK: &str = "admin:feedback";
+/// Seeing the documents of every user.
+pub const ADMIN_DOCUMENTS: &str = "admin:documents";
+/// Backing up and restoring the knowledge base.
+pub const ADMIN_BACKUPS: &str = "admin:backups";
+/// Maintaining the persistent knowledge graph.
+pub const ADMIN_GRAPH: &str = "admin:graph";
+/// Purging the answer cache.
+pub const ADMIN_CACHE: &str = "admin:cache";
+/// Reading the ingestion statistics of the knowledge base.
+pub const ADMIN_STATS: &str = "admin:stats";
+
+/// The permissions of the built-in roles, used when a role has no rows in
+/// `role_permissions`.
+pub const DEFAULT_ROLE_PERMISSIONS: &[(&str, &[&str])] = &[
+    (ROOT_ROLE, &[ALL]),
+    (USER_ROLE, &[INGEST_WRITE, SEARCH_READ, PROMPT_EXECUTE]),
+];
+
+#[derive(Error, Debug)]
+pub enum PermissionError {
+    #[error("Database error: {0}")]
+    Database(#[from] TursoError),
+    #[error("Invalid permission '{0}': expected 'resource:action'")]
+    InvalidPermission(String),
+    #[error("Missing permission '{0}'")]
+    Denied(String),
+}
+
+/// Returns whether the `granted` permission covers the `required` one.
+pub fn grants(granted: &str, required: &str) -> bool {
+    let (Some((granted_resource, granted_action)), Some((resource, action))) = (
+        granted.split_once(PERMISSION_SEPARATOR),
+        required.split_once(PERMISSION_SEPARATOR),
+    ) else {
+        return false;
+    };
+    let part_matches = |granted: &str, required: &str| granted == WILDCARD || granted == required;
+    part_matches(granted_resource, resource) && part_matches(granted_action, action)
+}
+
+pub fn has_permission(user: &User, permission: &str) -> bool {
+    user.permissions
+        .iter()
+        .any(|granted| grants(granted, permission))
+}
+
+/// Rejects users without the permission.
+pub fn require_permission(user: &User, permission: &str) -> Result<(), PermissionError> {
+    if !has_permission(user, permission) {
+        return Err(PermissionError::Denied(permission.to_string()));
+    }
+    Ok(())
+}
+
+/// Checks that a permission has the `resource:action` form.
+pub fn validate_permission(permission: &str) -> Result<(), PermissionError> {
+    match permission.split_once(PERMISSION_SEPARATOR) {
+        Some((resource, action))
+            if !resource.is_empty()
+                && !action.is_empty()
+                && !action.contains(PERMISSION_SEPARATOR) =>
+        {
+            Ok(())
+        }
+        _ => Err(PermissionError::InvalidPermission(permission.to_string())),
+    }
+}
+
+/// Returns the built-in permissions of a role, which are empty for unknown roles.
+pub fn default_permissions(role: &str) -> Vec<String> {
+    DEFAULT_ROLE_PERMISSIONS
+        .iter()
+        .find(|(default_role, _)| *default_role == role)
+        .map(|(_, permissions)| permissions.iter().map(|p| p.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Returns the effective permissions of a role.
+pub async fn role_permissions(db: &Database, role: &str) -> Result<Vec<String>, PermissionError> {
+    let conn = db.connect()?;
+    load_role_permissions(&conn, role).await
+}
+
+/// Replaces the permissions of a role. An empty list restores the built-in defaults.
+pub async fn set_role_permissions(
+    db: &Database,
+    role: &str,
+    permissions: &[String],
+) -> Result<Vec<String>, PermissionError> {
+    for permission in permissions {
+        validate_permission(permission)?;
+    }
+
+    let conn = db.connect()?;
+    conn.execute("DELETE FROM role_permissions WHERE role = ?", params![role])
+        .await?;
+    let mut inserted: Vec<&String> = Vec::new();
+    for permission in permissions {
+        if inserted.contains(&permission) {
+            continue;
+        }
+        conn.execute(
+            "INSERT INTO role_permissions (role, permission) VALUES (?, ?)",
+            params![role, permission.as_str()],
+        )
+        .await?;
+        inserted.push(permission);
+    }
+    load_role_permissions(&conn, role).await
+}
+
+pub(crate) async fn load_role_permissions(
+    conn: &Connection,
+    role: &str,
+) -> Result<Vec<String>, PermissionError> {
+    let mut rows = conn
+        .query(
+            "SELECT permission FROM role_permissions WHERE role = ? ORDER BY permission",
+            params![role],
+        )
+        .await?;
+    let mut permissions = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let permission: String = row.get(0)?;
+        permissions.push(permission);
+    }
+    if permissions.is_empty() {
+        return Ok(default_permissions(role));
+    }
+    Ok(permissions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_or_create_user;
+    use anyrag::providers::db::sqlite::SqliteProvider;
+
+    #[test]
+    fn test_grants_matches_wildcards() {
+        assert!(grants(INGEST_WRITE, INGEST_WRITE));
+        assert!(grants("admin:*", ADMIN_USERS));
+        assert!(grants(ALL, ADMIN_API_KEYS));
+        assert!(grants("*:read", SEARCH_READ));
+        assert!(!grants(SEARCH_READ, INGEST_WRITE));
+        assert!(!grants("admin:*", INGEST_WRITE));
+        assert!(!grants("admin", ADMIN_USERS));
+    }
+
+    #[test]
+    fn test_validate_permission() {
+        assert!(validate_permission("ingest:write").is_ok());
+        assert!(validate_permission("admin:*").is_ok());
+        assert!(validate_permission("ingest").is_err());
+        assert!(validate_permission(":write").is_err());
+        assert!(validate_permission("a:b:c").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_roles_use_defaults_until_overridden() {
+        // 1. Arrange
+        let provider = SqliteProvider::new(":memory:").await.unwrap();
+        provider.initialize_schema().await.unwrap();
+        let db = provider.db;
+
+        // 2. Act & Assert: Existing roles get their built-in permissions.
+        let root = get_or_create_user(&db, "root@example.com", Some(ROOT_ROLE))
+            .await
+            .unwrap();
+        let user = get_or_create_user(&db, "user@example.com", None)
+            .await
+            .unwrap();
+        assert!(require_permission(&root, ADMIN_USERS).is_ok());
+        assert!(require_permission(&user, INGEST_WRITE).is_ok());
+        assert!(matches!(
+            require_permission(&user, ADMIN_USERS),
+            Err(PermissionError::Denied(_))
+        ));
+
+        // 3. Act: Make the `user` role read-only.
+        let permissions = set_role_permissions(&db, USER_ROLE, &[SEARCH_READ.to_string()])
+            .await
+            .unwrap();
+        assert_eq!(permissions, vec![SEARCH_READ.to_string()]);
+
+        // 4. Assert: The override applies the next time the user is loaded.
+        let user = get_or_create_user(&db, "user@example.com", None)
+            .await
+            .unwrap();
+        assert!(has_permission(&user, SEARCH_READ));
+        assert!(!has_permission(&user, INGEST_WRITE));
+
+        // 5. Act & Assert: Clearing the override restores the defaults.
+        set_role_permissions(&db, USER_ROLE, &[]).await.unwrap();
+        assert_eq!(
+            role_permissions(&db, USER_ROLE).await.unwrap(),
+            default_permissions(USER_ROLE)
+        );
+        assert!(matches!(
+            set_role_permissions(&db, USER_ROLE, &["bogus".to_string()]).await,
+            Err(PermissionError::InvalidPermission(_))
+        ));
+    }
+}
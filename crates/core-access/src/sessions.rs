@@ -0,0 +1,376 @@
+//! # Sessions
+//!
+//! A session is started when a user signs in. It pairs the short-lived access tokens
+//! the server issues with a long-lived refresh token, which is exchanged for new
+//! access tokens at `/auth/refresh`. Refresh tokens are rotated on every use, and only
+//! their SHA-256 hash is stored. Revoking a session invalidates its refresh token and
+//! every access token issued for it, even before they expire.
+
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use turso::{Connection, Database, Error as TursoError, Row, Value, params};
+use uuid::Uuid;
+
+use crate::{User, has_permission, permissions::ADMIN_USERS};
+
+/// The prefix of every refresh token, which makes tokens recognizable in logs and scanners.
+pub const REFRESH_TOKEN_PREFIX: &str = "rt_";
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const SELECT_SESSION_COLUMNS: &str =
+    "SELECT id, user_id, subject, created_at, expires_at, refreshed_at, revoked_at FROM sessions";
+
+#[derive(Error, Debug)]
+pub enum SessionError {
+    #[error("Database error: {0}")]
+    Database(#[from] TursoError),
+    #[error("Session not found: {0}")]
+    NotFound(String),
+    #[error("Only the owner of session '{0}' can revoke it")]
+    NotOwner(String),
+    #[error("Invalid, expired or revoked refresh token")]
+    InvalidRefreshToken,
+    #[error("Data integrity error: {0}")]
+    DataIntegrity(String),
+}
+
+/// A stored session, without its refresh token.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Session {
+    pub id: String,
+    pub user_id: String,
+    /// The identifier the session's access tokens are issued for (their `sub`).
+    pub subject: String,
+    pub created_at: DateTime<Utc>,
+    /// When the refresh token expires, unless it is used before then.
+    pub expires_at: DateTime<Utc>,
+    /// When the refresh token was last exchanged for a new one.
+    pub refreshed_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl Session {
+    /// Whether the session is neither revoked nor expired.
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none() && self.expires_at > Utc::now()
+    }
+}
+
+impl TryFrom<&Row> for Session {
+    type Error = SessionError;
+
+    fn try_from(row: &Row) -> Result<Self, Self::Error> {
+        let created_at: String = row.get(3)?;
+        let expires_at: String = row.get(4)?;
+        Ok(Session {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            subject: row.get(2)?,
+            created_at: parse_timestamp(&created_at)?,
+            expires_at: parse_timestamp(&expires_at)?,
+            refreshed_at: parse_optional_timestamp(row.get_value(5)?)?,
+            revoked_at: parse_optional_timestamp(row.get_value(6)?)?,
+        })
+    }
+}
+
+/// A session with its current refresh token, which is not retrievable later.
+#[derive(Debug, Clone)]
+pub struct NewSession {
+    pub refresh_token: String,
+    pub session: Session,
+}
+
+/// Starts a session for the user, whose refresh token is valid for `ttl`.
+pub async fn create_session(
+    db: &Database,
+    user: &User,
+    subject: &str,
+    ttl: Duration,
+) -> Result<NewSession, SessionError> {
+    let conn = db.connect()?;
+    let id = Uuid::new_v4().to_string();
+    let refresh_token = generate_refresh_token();
+    conn.execute(
+        "INSERT INTO sessions (id, user_id, subject, refresh_token_hash, created_at, expires_at) VALUES (?, ?, ?, ?, ?, ?)",
+        params![
+            id.clone(),
+            user.id.as_str(),
+            subject,
+            hash_token(&refresh_token),
+            now(),
+            format_timestamp(Utc::now() + ttl)
+        ],
+    )
+    .await?;
+
+    let session = find_session(&conn, &id)
+        .await?
+        .ok_or_else(|| SessionError::NotFound(id.clone()))?;
+    Ok(NewSession {
+        refresh_token,
+        session,
+    })
+}
+
+/// Exchanges a refresh token for a new one, extending the session by `ttl`. The
+/// presented token can't be used again.
+pub async fn refresh_session(
+    db: &Database,
+    refresh_token: &str,
+    ttl: Duration,
+) -> Result<NewSession, SessionError> {
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_SESSION_COLUMNS} WHERE refresh_token_hash = ?"),
+            params![hash_token(refresh_token)],
+        )
+        .await?;
+    let row = rows
+        .next()
+        .await?
+        .ok_or(SessionError::InvalidRefreshToken)?;
+    let session = Session::try_from(&row)?;
+    drop(rows);
+    if !session.is_active() {
+        return Err(SessionError::InvalidRefreshToken);
+    }
+
+    // The rotation only applies while the presented token is still current, so of two
+    // concurrent refreshes with the same token, only one succeeds.
+    let new_refresh_token = generate_refresh_token();
+    let rotated = conn
+        .execute(
+            "UPDATE sessions SET refresh_token_hash = ?, refreshed_at = ?, expires_at = ? WHERE id = ? AND refresh_token_hash = ? AND revoked_at IS NULL",
+            params![
+                hash_token(&new_refresh_token),
+                now(),
+                format_timestamp(Utc::now() + ttl),
+                session.id.as_str(),
+                hash_token(refresh_token)
+            ],
+        )
+        .await?;
+    if rotated == 0 {
+        return Err(SessionError::InvalidRefreshToken);
+    }
+
+    let session = find_session(&conn, &session.id)
+        .await?
+        .ok_or(SessionError::InvalidRefreshToken)?;
+    Ok(NewSession {
+        refresh_token: new_refresh_token,
+        session,
+    })
+}
+
+/// Returns a session, active or not.
+pub async fn get_session(db: &Database, id: &str) -> Result<Session, SessionError> {
+    let conn = db.connect()?;
+    find_session(&conn, id)
+        .await?
+        .ok_or_else(|| SessionError::NotFound(id.to_string()))
+}
+
+/// Whether the session exists and is neither revoked nor expired.
+pub async fn is_session_active(db: &Database, id: &str) -> Result<bool, SessionError> {
+    let conn = db.connect()?;
+    Ok(find_session(&conn, id)
+        .await?
+        .is_some_and(|session| session.is_active()))
+}
+
+/// Lists the active sessions of a user, newest first.
+pub async fn list_user_sessions(
+    db: &Database,
+    user_id: &str,
+) -> Result<Vec<Session>, SessionError> {
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            &format!(
+                "{SELECT_SESSION_COLUMNS} WHERE user_id = ? AND revoked_at IS NULL AND expires_at > ? ORDER BY created_at DESC"
+            ),
+            params![user_id, now()],
+        )
+        .await?;
+    let mut sessions = Vec::new();
+    while let Some(row) = rows.next().await? {
+        sessions.push(Session::try_from(&row)?);
+    }
+    Ok(sessions)
+}
+
+/// Revokes a session. Users may revoke their own sessions; revoking another user's
+/// session requires the `admin:users` permission.
+pub async fn revoke_session(
+    db: &Database,
+    id: &str,
+    requester: &User,
+) -> Result<Session, SessionError> {
+    let conn = db.connect()?;
+    let session = find_session(&conn, id)
+        .await?
+        .ok_or_else(|| SessionError::NotFound(id.to_string()))?;
+    if session.user_id != requester.id && !has_permission(requester, ADMIN_USERS) {
+        return Err(SessionError::NotOwner(id.to_string()));
+    }
+    if session.revoked_at.is_some() {
+        return Ok(session);
+    }
+
+    conn.execute(
+        "UPDATE sessions SET revoked_at = ? WHERE id = ?",
+        params![now(), id],
+    )
+    .await?;
+    find_session(&conn, id)
+        .await?
+        .ok_or_else(|| SessionError::NotFound(id.to_string()))
+}
+
+/// Revokes every active session of a user, returning how many were revoked.
+pub async fn revoke_user_sessions(db: &Database, user_id: &str) -> Result<u64, SessionError> {
+    let conn = db.connect()?;
+    let revoked = conn
+        .execute(
+            "UPDATE sessions SET revoked_at = ? WHERE user_id = ? AND revoked_at IS NULL",
+            params![now(), user_id],
+        )
+        .await?;
+    Ok(revoked)
+}
+
+async fn find_session(conn: &Connection, id: &str) -> Result<Option<Session>, SessionError> {
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_SESSION_COLUMNS} WHERE id = ?"),
+            params![id],
+        )
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(Session::try_from(&row)?)),
+        None => Ok(None),
+    }
+}
+
+fn generate_refresh_token() -> String {
+    // Two random UUIDs give 244 bits of entropy.
+    format!(
+        "{REFRESH_TOKEN_PREFIX}{}{}",
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn now() -> String {
+    format_timestamp(Utc::now())
+}
+
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.format(TIMESTAMP_FORMAT).to_string()
+}
+
+fn parse_timestamp(text: &str) -> Result<DateTime<Utc>, SessionError> {
+    NaiveDateTime::parse_from_str(text, TIMESTAMP_FORMAT)
+        .map(|ndt| DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc))
+        .map_err(|e| SessionError::DataIntegrity(format!("Failed to parse date '{text}': {e}")))
+}
+
+fn parse_optional_timestamp(value: Value) -> Result<Option<DateTime<Utc>>, SessionError> {
+    match value {
+        Value::Text(text) => Ok(Some(parse_timestamp(&text)?)),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_or_create_user;
+    use anyrag::providers::db::sqlite::SqliteProvider;
+
+    #[tokio::test]
+    async fn test_refresh_token_rotation_and_revocation() {
+        // 1. Arrange
+        let provider = SqliteProvider::new(":memory:").await.unwrap();
+        provider.initialize_schema().await.unwrap();
+        let db = provider.db;
+        let user = get_or_create_user(&db, "user@example.com", None)
+            .await
+            .unwrap();
+        let started = create_session(&db, &user, "user@example.com", Duration::days(30))
+            .await
+            .unwrap();
+        assert!(started.refresh_token.starts_with(REFRESH_TOKEN_PREFIX));
+        assert!(is_session_active(&db, &started.session.id).await.unwrap());
+
+        // 2. Act: Refresh the session.
+        let refreshed = refresh_session(&db, &started.refresh_token, Duration::days(30))
+            .await
+            .unwrap();
+
+        // 3. Assert: The token was rotated, and the old one is no longer accepted.
+        assert_eq!(refreshed.session.id, started.session.id);
+        assert_ne!(refreshed.refresh_token, started.refresh_token);
+        assert!(refreshed.session.refreshed_at.is_some());
+        assert!(matches!(
+            refresh_session(&db, &started.refresh_token, Duration::days(30)).await,
+            Err(SessionError::InvalidRefreshToken)
+        ));
+
+        // 4. Act & Assert: A revoked session can't be refreshed or listed.
+        let revoked = revoke_session(&db, &started.session.id, &user)
+            .await
+            .unwrap();
+        assert!(revoked.revoked_at.is_some());
+        assert!(!is_session_active(&db, &started.session.id).await.unwrap());
+        assert!(matches!(
+            refresh_session(&db, &refreshed.refresh_token, Duration::days(30)).await,
+            Err(SessionError::InvalidRefreshToken)
+        ));
+        assert!(list_user_sessions(&db, &user.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sessions_are_revoked_by_their_owner_only() {
+        let provider = SqliteProvider::new(":memory:").await.unwrap();
+        provider.initialize_schema().await.unwrap();
+        let db = provider.db;
+        let owner = get_or_create_user(&db, "owner@example.com", None)
+            .await
+            .unwrap();
+        let other = get_or_create_user(&db, "other@example.com", None)
+            .await
+            .unwrap();
+        let root = get_or_create_user(&db, "root@example.com", Some("root"))
+            .await
+            .unwrap();
+        let started = create_session(&db, &owner, "owner@example.com", Duration::days(1))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            revoke_session(&db, &started.session.id, &other).await,
+            Err(SessionError::NotOwner(_))
+        ));
+        assert!(matches!(
+            revoke_session(&db, "missing", &owner).await,
+            Err(SessionError::NotFound(_))
+        ));
+        revoke_session(&db, &started.session.id, &root)
+            .await
+            .unwrap();
+        assert!(!is_session_active(&db, &started.session.id).await.unwrap());
+    }
+}
@@ -0,0 +1,165 @@
+//! # Usage
+//!
+//! Tracks what each user consumes per calendar month, so that the server can enforce
+//! monthly quotas. AI calls are counted as they are made; ingested documents are
+//! counted from the `documents` the user owns.
+
+use chrono::{Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use turso::{Connection, Database, Error as TursoError, Row, Value, params};
+
+/// The metric counting requests to AI-backed routes.
+pub const METRIC_AI_CALLS: &str = "ai_calls";
+const PERIOD_FORMAT: &str = "%Y-%m";
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Error, Debug)]
+pub enum UsageError {
+    #[error("Database error: {0}")]
+    Database(#[from] TursoError),
+    #[error("Data integrity error: {0}")]
+    DataIntegrity(String),
+}
+
+/// What a user consumed in a calendar month.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Usage {
+    /// The month, e.g. `2026-10`.
+    pub period: String,
+    pub ai_calls: u64,
+    pub documents_ingested: u64,
+}
+
+/// Returns the current month, as usage periods are named.
+pub fn current_period() -> String {
+    Utc::now().format(PERIOD_FORMAT).to_string()
+}
+
+/// Counts one AI call of the user in the current month, returning the new total.
+pub async fn record_ai_call(db: &Database, user_id: &str) -> Result<u64, UsageError> {
+    let conn = db.connect()?;
+    let period = current_period();
+    let updated = conn
+        .execute(
+            "UPDATE usage_counters SET count = count + 1 WHERE user_id = ? AND period = ? AND metric = ?",
+            params![user_id, period.as_str(), METRIC_AI_CALLS],
+        )
+        .await?;
+    if updated == 0 {
+        conn.execute(
+            "INSERT INTO usage_counters (user_id, period, metric, count) VALUES (?, ?, ?, 1)",
+            params![user_id, period.as_str(), METRIC_AI_CALLS],
+        )
+        .await?;
+    }
+    load_counter(&conn, user_id, &period, METRIC_AI_CALLS).await
+}
+
+/// Returns what the user consumed in the current month.
+pub async fn get_usage(db: &Database, user_id: &str) -> Result<Usage, UsageError> {
+    let conn = db.connect()?;
+    let period = current_period();
+    let ai_calls = load_counter(&conn, user_id, &period, METRIC_AI_CALLS).await?;
+    let documents_ingested = count_documents_since(&conn, user_id, &period_start()?).await?;
+    Ok(Usage {
+        period,
+        ai_calls,
+        documents_ingested,
+    })
+}
+
+async fn load_counter(
+    conn: &Connection,
+    user_id: &str,
+    period: &str,
+    metric: &str,
+) -> Result<u64, UsageError> {
+    let mut rows = conn
+        .query(
+            "SELECT count FROM usage_counters WHERE user_id = ? AND period = ? AND metric = ?",
+            params![user_id, period, metric],
+        )
+        .await?;
+    match rows.next().await? {
+        Some(row) => read_count(&row),
+        None => Ok(0),
+    }
+}
+
+async fn count_documents_since(
+    conn: &Connection,
+    owner_id: &str,
+    since: &str,
+) -> Result<u64, UsageError> {
+    let mut rows = conn
+        .query(
+            "SELECT COUNT(*) FROM documents WHERE owner_id = ? AND created_at >= ?",
+            params![owner_id, since],
+        )
+        .await?;
+    match rows.next().await? {
+        Some(row) => read_count(&row),
+        None => Ok(0),
+    }
+}
+
+/// The first second of the current month, formatted like the `created_at` columns.
+fn period_start() -> Result<String, UsageError> {
+    let today = Utc::now().date_naive();
+    NaiveDate::from_ymd_opt(today.year(), today.month(), 1)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|start| start.format(TIMESTAMP_FORMAT).to_string())
+        .ok_or_else(|| UsageError::DataIntegrity(format!("Invalid month of {today}")))
+}
+
+fn read_count(row: &Row) -> Result<u64, UsageError> {
+    match row.get_value(0)? {
+        Value::Integer(count) => u64::try_from(count)
+            .map_err(|_| UsageError::DataIntegrity(format!("Negative usage count: {count}"))),
+        other => Err(UsageError::DataIntegrity(format!(
+            "Expected an integer count, got {other:?}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_or_create_user;
+    use anyrag::providers::db::sqlite::SqliteProvider;
+
+    #[tokio::test]
+    async fn test_usage_counts_ai_calls_and_documents() {
+        // 1. Arrange
+        let provider = SqliteProvider::new(":memory:").await.unwrap();
+        provider.initialize_schema().await.unwrap();
+        let db = provider.db;
+        let user = get_or_create_user(&db, "user@example.com", None)
+            .await
+            .unwrap();
+        let conn = db.connect().unwrap();
+        conn.execute(
+            "INSERT INTO documents (id, owner_id, source_url, title, content) VALUES (?, ?, ?, ?, ?)",
+            params!["doc-1", user.id.as_str(), "https://example.com", "Plan", "The plan."],
+        )
+        .await
+        .unwrap();
+
+        // 2. Act
+        assert_eq!(record_ai_call(&db, &user.id).await.unwrap(), 1);
+        assert_eq!(record_ai_call(&db, &user.id).await.unwrap(), 2);
+        let usage = get_usage(&db, &user.id).await.unwrap();
+
+        // 3. Assert
+        assert_eq!(
+            usage,
+            Usage {
+                period: current_period(),
+                ai_calls: 2,
+                documents_ingested: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,364 @@
+//! # `anyrag-dir`: Local Directory Ingestion Plugin
+//!
+//! This crate provides the logic for ingesting the files of a local directory as a
+//! self-contained plugin for the `anyrag` ecosystem. It implements the `Ingestor`
+//! trait from the core `anyrag` library.
+//!
+//! The directory is walked recursively and every file that passes the include and
+//! exclude globs is routed by its extension to the existing ingestor for that format:
+//! PDFs to `anyrag-pdf`, CSV files to `anyrag-sheets`, and text, Markdown, and HTML to
+//! `anyrag-text`. The [`watch`] module keeps the documents in sync as files change.
+
+use anyrag::{
+    ingest::{
+        source_url_prefix_pattern, ChunkingStrategy, IngestError, IngestionPrompts,
+        IngestionResult, Ingestor,
+    },
+    providers::ai::AiProvider,
+};
+use anyrag_pdf::PdfIngestor;
+use anyrag_sheets::SheetsIngestor;
+use anyrag_text::{TextIngestor, DEFAULT_CHUNK_SIZE};
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine as _};
+use glob::Pattern;
+use serde::Deserialize;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::{info, warn};
+use turso::{params, Database};
+
+pub mod watch;
+
+/// Custom error types for the directory ingestion process.
+#[derive(Error, Debug)]
+pub enum DirIngestError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Failed to read from the file system: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Source deserialization failed: {0}")]
+    SourceDeserialization(#[from] serde_json::Error),
+    #[error("Invalid glob pattern '{0}': {1}")]
+    InvalidPattern(String, glob::PatternError),
+    #[error("Failed to watch the directory: {0}")]
+    Watch(#[from] notify::Error),
+    #[error("File '{0}' is not valid UTF-8 text")]
+    Encoding(String),
+    #[error("File '{0}' needs an AI provider to be ingested")]
+    AiProviderRequired(String),
+    #[error("{0}")]
+    Ingest(#[from] IngestError),
+}
+
+/// A helper to convert the specific `DirIngestError` into the generic `anyrag::ingest::IngestError`.
+impl From<DirIngestError> for IngestError {
+    fn from(err: DirIngestError) -> Self {
+        match err {
+            DirIngestError::Database(e) => IngestError::Database(e),
+            DirIngestError::Io(e) => IngestError::SourceNotFound(e.to_string()),
+            DirIngestError::SourceDeserialization(e) => {
+                IngestError::Parse(format!("Invalid source JSON for directory ingest: {e}"))
+            }
+            DirIngestError::Ingest(e) => e,
+            _ => IngestError::Internal(anyhow::anyhow!(err.to_string())),
+        }
+    }
+}
+
+/// Defines the structure of the JSON string passed to the `ingest` method.
+#[derive(Deserialize)]
+struct DirSource {
+    path: String,
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+/// Include and exclude globs, matched against paths relative to the ingested directory.
+///
+/// `*` also matches `/`, so `*.md` selects Markdown files at any depth.
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl FileFilter {
+    /// Compiles the globs. Every file is included when `include` is empty.
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, DirIngestError> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|p| Pattern::new(p).map_err(|e| DirIngestError::InvalidPattern(p.clone(), e)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    /// Returns whether a path, relative to the ingested directory, should be ingested.
+    pub fn matches(&self, relative_path: &Path) -> bool {
+        let included =
+            self.include.is_empty() || self.include.iter().any(|p| p.matches_path(relative_path));
+        included && !self.exclude.iter().any(|p| p.matches_path(relative_path))
+    }
+}
+
+/// The formats files are routed by, chosen from the file's extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Pdf,
+    Csv,
+    Markdown,
+    Html,
+    Text,
+}
+
+impl FileKind {
+    fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "pdf" => Some(Self::Pdf),
+            "csv" => Some(Self::Csv),
+            "md" | "markdown" => Some(Self::Markdown),
+            "html" | "htm" => Some(Self::Html),
+            "txt" => Some(Self::Text),
+            _ => None,
+        }
+    }
+}
+
+/// Returns whether a file has an extension the directory ingestor can route.
+pub fn is_supported(path: &Path) -> bool {
+    FileKind::from_path(path).is_some()
+}
+
+/// Returns the source URL of the documents made from a file.
+pub fn file_source_url(path: &Path) -> String {
+    format!("file://{}", path.display())
+}
+
+/// The `Ingestor` implementation for local directories.
+pub struct DirectoryIngestor<'a> {
+    db: &'a Database,
+    llm: Option<(&'a dyn AiProvider, IngestionPrompts<'a>)>,
+}
+
+impl<'a> DirectoryIngestor<'a> {
+    /// Creates a new `DirectoryIngestor` for text, Markdown, and HTML files.
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, llm: None }
+    }
+
+    /// Also ingests PDF and CSV files, which go through the LLM restructuring pipeline.
+    pub fn with_llm(
+        mut self,
+        ai_provider: &'a dyn AiProvider,
+        prompts: IngestionPrompts<'a>,
+    ) -> Self {
+        self.llm = Some((ai_provider, prompts));
+        self
+    }
+
+    /// Ingests one file, replacing the documents of its previous version.
+    ///
+    /// Files with an unsupported extension are ignored and add no documents.
+    pub async fn ingest_file(
+        &self,
+        path: &Path,
+        owner_id: Option<&str>,
+    ) -> Result<IngestionResult, DirIngestError> {
+        let Some(kind) = FileKind::from_path(path) else {
+            return Ok(IngestionResult::default());
+        };
+        let source_url = file_source_url(path);
+        let data = tokio::fs::read(path).await?;
+
+        let (text, chunking) = match kind {
+            FileKind::Pdf => {
+                let (ai_provider, prompts) = self.llm_for(path)?;
+                let source = json!({
+                    "source_identifier": source_url,
+                    "pdf_data_base64": general_purpose::STANDARD.encode(&data),
+                });
+                return Ok(PdfIngestor::new(self.db, ai_provider, prompts)
+                    .ingest(&source.to_string(), owner_id)
+                    .await?);
+            }
+            FileKind::Csv => {
+                let (ai_provider, prompts) = self.llm_for(path)?;
+                return Ok(SheetsIngestor::new(self.db, ai_provider, prompts)
+                    .ingest_csv(&source_url, &decode_text(path, data)?, owner_id)
+                    .await?);
+            }
+            FileKind::Html => (
+                anyrag_html::html_to_clean_markdown(&decode_text(path, data)?, None),
+                Some(markdown_chunking()),
+            ),
+            FileKind::Markdown => (decode_text(path, data)?, Some(markdown_chunking())),
+            FileKind::Text => (decode_text(path, data)?, None),
+        };
+
+        // An edited file may produce fewer chunks than before, so the chunks of its
+        // previous version for this owner are removed first.
+        let conn = self.db.connect()?;
+        conn.execute(
+            "DELETE FROM documents WHERE owner_id IS ? AND source_url LIKE ? ESCAPE '\\'",
+            params![
+                owner_id,
+                source_url_prefix_pattern(&format!("{source_url}#chunk_"))
+            ],
+        )
+        .await?;
+        let source = json!({
+            "text": text,
+            "source": source_url,
+            "chunking": chunking,
+        });
+        Ok(TextIngestor::new(self.db)
+            .ingest(&source.to_string(), owner_id)
+            .await?)
+    }
+
+    /// Deletes the documents an owner made from a file, returning how many were removed.
+    pub async fn remove_file(
+        &self,
+        path: &Path,
+        owner_id: Option<&str>,
+    ) -> Result<u64, DirIngestError> {
+        let source_url = file_source_url(path);
+        let conn = self.db.connect()?;
+        let removed = conn
+            .execute(
+                "DELETE FROM documents WHERE owner_id IS ? AND (source_url = ? OR source_url LIKE ? ESCAPE '\\')",
+                params![
+                    owner_id,
+                    source_url.clone(),
+                    source_url_prefix_pattern(&format!("{source_url}#"))
+                ],
+            )
+            .await?;
+        Ok(removed)
+    }
+
+    fn llm_for(
+        &self,
+        path: &Path,
+    ) -> Result<(&'a dyn AiProvider, IngestionPrompts<'a>), DirIngestError> {
+        self.llm
+            .ok_or_else(|| DirIngestError::AiProviderRequired(path.display().to_string()))
+    }
+}
+
+#[async_trait]
+impl Ingestor for DirectoryIngestor<'_> {
+    /// Ingests every matching file under a directory.
+    ///
+    /// The `source` argument is expected to be a JSON string with a `path` key and
+    /// optional `include` and `exclude` glob lists, for example:
+    /// `{"path": "./docs", "include": ["*.md"], "exclude": ["drafts/*"]}`.
+    /// Files that fail are listed under `failed` in the result's metadata, along with
+    /// the `ingested` and `unsupported` files, and do not stop the rest of the walk.
+    async fn ingest(
+        &self,
+        source: &str,
+        owner_id: Option<&str>,
+    ) -> Result<IngestionResult, IngestError> {
+        let dir_source: DirSource = serde_json::from_str(source).map_err(DirIngestError::from)?;
+        let filter = FileFilter::new(&dir_source.include, &dir_source.exclude)?;
+        let root = std::fs::canonicalize(&dir_source.path).map_err(DirIngestError::from)?;
+        let files = collect_files(&root, &filter).map_err(DirIngestError::from)?;
+
+        let mut documents_added = 0;
+        let mut document_ids = Vec::new();
+        let mut ingested = Vec::new();
+        let mut unsupported = Vec::new();
+        let mut failed = Vec::new();
+
+        for path in &files {
+            let relative = path
+                .strip_prefix(&root)
+                .unwrap_or(path)
+                .display()
+                .to_string();
+            if !is_supported(path) {
+                unsupported.push(relative);
+                continue;
+            }
+            match self.ingest_file(path, owner_id).await {
+                Ok(result) => {
+                    documents_added += result.documents_added;
+                    document_ids.extend(result.document_ids);
+                    ingested.push(relative);
+                }
+                Err(e) => {
+                    warn!("Failed to ingest file '{}': {e}", path.display());
+                    failed.push(json!({ "path": relative, "error": e.to_string() }));
+                }
+            }
+        }
+
+        info!(
+            "Ingested {} of {} files from '{}' ({} failed).",
+            ingested.len(),
+            files.len(),
+            root.display(),
+            failed.len()
+        );
+
+        Ok(IngestionResult {
+            source: root.display().to_string(),
+            documents_added,
+            document_ids,
+            metadata: Some(
+                json!({
+                    "ingested": ingested,
+                    "unsupported": unsupported,
+                    "failed": failed,
+                })
+                .to_string(),
+            ),
+        })
+    }
+}
+
+/// Recursively lists the files under `root` that pass the filter, in path order.
+///
+/// Symbolic links are not followed.
+pub fn collect_files(root: &Path, filter: &FileFilter) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+            if file_type.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            if file_type.is_file() && filter.matches(relative) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn decode_text(path: &Path, data: Vec<u8>) -> Result<String, DirIngestError> {
+    String::from_utf8(data).map_err(|_| DirIngestError::Encoding(path.display().to_string()))
+}
+
+/// Markdown and converted HTML are chunked by section so a chunk never spans headings.
+fn markdown_chunking() -> ChunkingStrategy {
+    ChunkingStrategy::Markdown {
+        chunk_size: DEFAULT_CHUNK_SIZE,
+    }
+}
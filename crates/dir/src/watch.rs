@@ -0,0 +1,95 @@
+//! # Directory Watching
+//!
+//! Keeps the documents of a directory in sync with its files: created and modified
+//! files are re-ingested and the documents of deleted files are removed. Events are
+//! collected until the directory has been quiet for [`DEBOUNCE`], so an editor saving
+//! a file in several writes triggers a single re-ingestion.
+
+use crate::{is_supported, DirIngestError, DirectoryIngestor, FileFilter};
+use notify::{Event, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+/// How long the directory must be quiet before a batch of changes is processed.
+pub const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Watches `root` recursively and syncs every changed file that passes `filter`.
+///
+/// This runs until the returned future is dropped, for example when the caller
+/// selects it against a shutdown signal. A file that fails to ingest is logged and
+/// does not stop the watcher.
+pub async fn watch_directory(
+    ingestor: &DirectoryIngestor<'_>,
+    root: &Path,
+    filter: &FileFilter,
+    owner_id: Option<&str>,
+) -> Result<(), DirIngestError> {
+    let root = std::fs::canonicalize(root)?;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        // The receiver only goes away when watching stops, so a failed send is harmless.
+        let _ = tx.send(event);
+    })?;
+    watcher.watch(&root, RecursiveMode::Recursive)?;
+    info!("Watching '{}' for changes.", root.display());
+
+    while let Some(event) = rx.recv().await {
+        let mut changed = BTreeSet::new();
+        collect_paths(event, &mut changed);
+        while let Ok(Some(event)) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {
+            collect_paths(event, &mut changed);
+        }
+
+        for path in changed {
+            sync_path(ingestor, &root, filter, &path, owner_id).await;
+        }
+    }
+    Ok(())
+}
+
+fn collect_paths(event: notify::Result<Event>, changed: &mut BTreeSet<PathBuf>) {
+    match event {
+        // Reading a file reports an access event, which must not trigger a re-ingestion.
+        Ok(event) if event.kind.is_access() => {}
+        Ok(event) => changed.extend(event.paths),
+        Err(e) => warn!("File watcher error: {e}"),
+    }
+}
+
+/// Re-ingests a path that still exists as a file, or removes its documents otherwise.
+async fn sync_path(
+    ingestor: &DirectoryIngestor<'_>,
+    root: &Path,
+    filter: &FileFilter,
+    path: &Path,
+    owner_id: Option<&str>,
+) {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return;
+    };
+    if !is_supported(path) || !filter.matches(relative) {
+        return;
+    }
+
+    let result = match path.is_file() {
+        true => ingestor.ingest_file(path, owner_id).await.map(|result| {
+            info!(
+                "Re-ingested '{}' ({} new documents).",
+                path.display(),
+                result.documents_added
+            )
+        }),
+        false => ingestor.remove_file(path, owner_id).await.map(|removed| {
+            info!(
+                "Removed {removed} documents of deleted file '{}'.",
+                path.display()
+            )
+        }),
+    };
+    if let Err(e) = result {
+        warn!("Failed to sync '{}': {e}", path.display());
+    }
+}
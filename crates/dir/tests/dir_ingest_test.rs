@@ -0,0 +1,180 @@
+//! # Directory Crate Tests
+//!
+//! This file contains integration tests for the `anyrag-dir` crate, ingesting and
+//! watching a temporary directory.
+
+use anyhow::Result;
+use anyrag::ingest::Ingestor;
+use anyrag_dir::{file_source_url, watch::watch_directory, DirectoryIngestor, FileFilter};
+use anyrag_test_utils::TestSetup;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use turso::{params, Database};
+
+/// Returns the contents of the documents made from a file, in chunk order.
+async fn documents_of(db: &Database, path: &Path) -> Result<Vec<String>> {
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT content FROM documents WHERE source_url LIKE ? ORDER BY source_url",
+            params![format!("{}#%", file_source_url(path))],
+        )
+        .await?;
+    let mut contents = Vec::new();
+    while let Some(row) = rows.next().await? {
+        contents.push(row.get::<String>(0)?);
+    }
+    Ok(contents)
+}
+
+#[tokio::test]
+async fn test_dir_ingestor_walks_filters_and_dispatches_files() -> Result<()> {
+    // --- Arrange ---
+    let dir = tempfile::tempdir()?;
+    let root = dir.path();
+    fs::create_dir_all(root.join("guides/drafts"))?;
+    fs::write(
+        root.join("guides/setup.md"),
+        "# Setup\n\nInstall the tools.\n\n## Verify\n\nRun the checks.",
+    )?;
+    fs::write(root.join("notes.txt"), "Rotate the keys every month.")?;
+    fs::write(
+        root.join("guides/faq.html"),
+        "<html><body><h1>FAQ</h1><p>Ask in the support channel.</p></body></html>",
+    )?;
+    fs::write(root.join("guides/drafts/wip.md"), "# Work in progress")?;
+    fs::write(root.join("report.pdf"), "not used without an AI provider")?;
+    fs::write(root.join("build.log"), "ignored by the include globs")?;
+
+    let setup = TestSetup::new().await?;
+    let ingestor = DirectoryIngestor::new(&setup.db);
+    let source = json!({
+        "path": root,
+        "include": ["*.md", "*.txt", "*.html", "*.pdf"],
+        "exclude": ["guides/drafts/*"],
+    })
+    .to_string();
+
+    // --- Act ---
+    let result = ingestor.ingest(&source, Some("dir-user")).await?;
+
+    // --- Assert ---
+    let metadata: Value = serde_json::from_str(result.metadata.as_deref().unwrap_or("{}"))?;
+    assert_eq!(
+        metadata["ingested"],
+        json!(["guides/faq.html", "guides/setup.md", "notes.txt"])
+    );
+    assert_eq!(metadata["failed"][0]["path"], "report.pdf");
+    assert!(metadata["failed"][0]["error"]
+        .as_str()
+        .unwrap_or_default()
+        .contains("AI provider"));
+    assert_eq!(result.documents_added, 4);
+
+    let root = fs::canonicalize(root)?;
+    let sections = documents_of(&setup.db, &root.join("guides/setup.md")).await?;
+    assert_eq!(sections.len(), 2);
+    assert!(sections[1].contains("Run the checks."));
+    let faq = documents_of(&setup.db, &root.join("guides/faq.html")).await?;
+    assert!(faq[0].contains("Ask in the support channel."));
+    assert!(!faq[0].contains("<p>"));
+    assert!(documents_of(&setup.db, &root.join("guides/drafts/wip.md"))
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dir_ingestor_replaces_and_removes_file_documents() -> Result<()> {
+    // --- Arrange ---
+    let dir = tempfile::tempdir()?;
+    let path = fs::canonicalize(dir.path())?.join("policy.txt");
+    fs::write(
+        &path,
+        "Laptops are replaced every three years.\n\nPhones every two.",
+    )?;
+    let setup = TestSetup::new().await?;
+    let ingestor = DirectoryIngestor::new(&setup.db);
+    ingestor.ingest_file(&path, None).await?;
+
+    // --- Act ---
+    fs::write(&path, "Laptops are replaced every four years.")?;
+    ingestor.ingest_file(&path, None).await?;
+    let edited = documents_of(&setup.db, &path).await?;
+    let removed = ingestor.remove_file(&path, None).await?;
+
+    // --- Assert ---
+    assert_eq!(edited.len(), 1);
+    assert!(edited[0].contains("every four years"));
+    assert_eq!(removed, 1);
+    assert!(documents_of(&setup.db, &path).await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dir_ingestor_keeps_the_file_documents_of_other_owners() -> Result<()> {
+    // --- Arrange: two owners ingest the same file ---
+    let dir = tempfile::tempdir()?;
+    let path = fs::canonicalize(dir.path())?.join("policy.txt");
+    fs::write(&path, "Laptops are replaced every three years.")?;
+    let setup = TestSetup::new().await?;
+    let ingestor = DirectoryIngestor::new(&setup.db);
+    ingestor.ingest_file(&path, Some("alice")).await?;
+    ingestor.ingest_file(&path, Some("bob")).await?;
+
+    // --- Act ---
+    fs::write(&path, "Laptops are replaced every four years.")?;
+    ingestor.ingest_file(&path, Some("alice")).await?;
+    let removed = ingestor.remove_file(&path, Some("alice")).await?;
+
+    // --- Assert: only the first owner's documents were replaced and removed ---
+    assert_eq!(removed, 1);
+    let documents = documents_of(&setup.db, &path).await?;
+    assert_eq!(documents.len(), 1);
+    assert!(documents[0].contains("every three years"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_watch_directory_reingests_changed_files() -> Result<()> {
+    // --- Arrange ---
+    let dir = tempfile::tempdir()?;
+    let root = fs::canonicalize(dir.path())?;
+    let path = root.join("changelog.md");
+    let setup = TestSetup::new().await?;
+    let ingestor = DirectoryIngestor::new(&setup.db);
+    let filter = FileFilter::new(&["*.md".to_string()], &[])?;
+
+    // --- Act ---
+    // The watcher runs until the assertions below finish and the select drops it.
+    let changes = async {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        fs::write(&path, "# v1.2\n\nAdded directory watching.")?;
+        fs::write(root.join("ignored.txt"), "Not matched by the filter.")?;
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let documents = documents_of(&setup.db, &path).await?;
+            if !documents.is_empty() {
+                return Ok::<_, anyhow::Error>(documents);
+            }
+        }
+        anyhow::bail!("The changed file was not re-ingested")
+    };
+    let documents = tokio::select! {
+        result = watch_directory(&ingestor, &root, &filter, None) => {
+            anyhow::bail!("The watcher stopped early: {result:?}")
+        }
+        documents = changes => documents?,
+    };
+
+    // --- Assert ---
+    assert_eq!(documents.len(), 1);
+    assert!(documents[0].contains("Added directory watching."));
+    assert!(documents_of(&setup.db, &root.join("ignored.txt"))
+        .await?
+        .is_empty());
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! # Discord REST API Client
+//!
+//! A small client for the channel endpoints the ingestor needs. It pages backwards
+//! through a channel's messages and waits out `429 Too Many Requests` responses for as
+//! long as their `Retry-After` header asks.
+
+use crate::DiscordIngestError;
+use reqwest::{
+    header::{AUTHORIZATION, RETRY_AFTER},
+    Client, Response, StatusCode,
+};
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// The base URL of the Discord REST API.
+pub const DEFAULT_DISCORD_API_URL: &str = "https://discord.com/api/v10";
+
+/// The number of messages requested per page, the maximum Discord allows.
+const PAGE_LIMIT: usize = 100;
+/// How many rate-limited responses in a row are waited out before giving up.
+const MAX_RATE_LIMIT_RETRIES: usize = 5;
+/// The wait used when a rate-limited response has no usable `Retry-After` header.
+const DEFAULT_RETRY_AFTER_SECS: f64 = 1.0;
+
+/// A channel as returned by `GET /channels/{id}`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordChannel {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub guild_id: Option<String>,
+}
+
+/// The author of a message.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordAuthor {
+    pub id: String,
+    pub username: String,
+    /// The display name, when the user set one.
+    #[serde(default)]
+    pub global_name: Option<String>,
+}
+
+impl DiscordAuthor {
+    /// Returns the name shown in the channel.
+    pub fn display_name(&self) -> &str {
+        self.global_name.as_deref().unwrap_or(&self.username)
+    }
+}
+
+/// A message as returned by `GET /channels/{id}/messages`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordMessage {
+    pub id: String,
+    pub author: DiscordAuthor,
+    #[serde(default)]
+    pub content: String,
+    /// When the message was posted, as an ISO 8601 timestamp.
+    pub timestamp: String,
+}
+
+/// A Discord REST API client authenticated with a bot token.
+#[derive(Debug, Clone)]
+pub struct DiscordClient {
+    http: Client,
+    base_url: String,
+    token: String,
+}
+
+impl DiscordClient {
+    /// Creates a client for the API at `base_url`.
+    pub fn new(base_url: &str, token: &str) -> Self {
+        Self {
+            http: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+        }
+    }
+
+    /// Sends the requests to another API base URL, keeping the token.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Fetches a channel's name and guild.
+    pub async fn channel(&self, channel_id: &str) -> Result<DiscordChannel, DiscordIngestError> {
+        let url = format!("{}/channels/{channel_id}", self.base_url);
+        let response = self.get(&url, &[]).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Fetches up to `max_messages` of a channel's most recent messages, newest first.
+    pub async fn channel_messages(
+        &self,
+        channel_id: &str,
+        max_messages: usize,
+    ) -> Result<Vec<DiscordMessage>, DiscordIngestError> {
+        let url = format!("{}/channels/{channel_id}/messages", self.base_url);
+        let mut messages: Vec<DiscordMessage> = Vec::new();
+        while messages.len() < max_messages {
+            let limit = PAGE_LIMIT.min(max_messages - messages.len());
+            let mut query = vec![("limit", limit.to_string())];
+            if let Some(oldest) = messages.last() {
+                query.push(("before", oldest.id.clone()));
+            }
+
+            let page: Vec<DiscordMessage> = self.get(&url, &query).await?.json().await?;
+            let page_len = page.len();
+            messages.extend(page);
+            if page_len < limit {
+                break;
+            }
+        }
+        info!(
+            "Fetched {} messages from Discord channel {channel_id}.",
+            messages.len()
+        );
+        Ok(messages)
+    }
+
+    /// Sends a GET request, waiting and retrying while Discord rate-limits it.
+    async fn get(
+        &self,
+        url: &str,
+        query: &[(&str, String)],
+    ) -> Result<Response, DiscordIngestError> {
+        let mut retries = 0;
+        loop {
+            let response = self
+                .http
+                .get(url)
+                .header(AUTHORIZATION, format!("Bot {}", self.token))
+                .query(query)
+                .send()
+                .await?;
+
+            if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                if retries >= MAX_RATE_LIMIT_RETRIES {
+                    return Err(DiscordIngestError::RateLimited(url.to_string()));
+                }
+                retries += 1;
+                let wait = retry_after(&response);
+                warn!("Discord rate-limited {url}, retrying in {wait:?} (attempt {retries}).");
+                tokio::time::sleep(wait).await;
+                continue;
+            }
+            if !response.status().is_success() {
+                let status = response.status().as_u16();
+                let body = response.text().await.unwrap_or_default();
+                return Err(DiscordIngestError::Api(format!(
+                    "{url} returned status {status}: {body}"
+                )));
+            }
+            return Ok(response);
+        }
+    }
+}
+
+/// Reads how long Discord asks to wait from the `Retry-After` header, in (possibly
+/// fractional) seconds.
+fn retry_after(response: &Response) -> Duration {
+    let secs = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .unwrap_or(DEFAULT_RETRY_AFTER_SECS);
+    Duration::from_secs_f64(secs)
+}
@@ -0,0 +1,390 @@
+//! # `anyrag-discord`: Discord Ingestion Plugin
+//!
+//! This crate provides the logic for ingesting the messages of Discord guild channels
+//! as a self-contained plugin for the `anyrag` ecosystem. It implements the `Ingestor`
+//! trait from the core `anyrag` library.
+//!
+//! Chat messages are too short to be useful alone, so consecutive messages are batched
+//! into conversation windows: a window ends when the channel goes quiet for longer than
+//! `window_minutes` or grows past `max_window_messages`. Each window is stored as one
+//! document, with its channel and authors in `content_metadata` for filtering.
+
+use anyrag::ingest::{
+    content_hash, find_duplicate_document, record_revision, IngestError, IngestionResult, Ingestor,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use client::{DiscordChannel, DiscordClient, DiscordMessage, DEFAULT_DISCORD_API_URL};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use thiserror::Error;
+use tracing::{info, warn};
+use turso::{params, Connection, Database};
+use uuid::Uuid;
+
+pub mod client;
+
+/// The default quiet period, in minutes, that ends a conversation window.
+pub const DEFAULT_WINDOW_MINUTES: i64 = 30;
+/// The default maximum number of messages in one conversation window.
+pub const DEFAULT_MAX_WINDOW_MESSAGES: usize = 50;
+/// The default maximum number of recent messages read from each channel.
+pub const DEFAULT_MAX_MESSAGES: usize = 1000;
+
+/// The `content_metadata` property holding the channel name of a document.
+pub const CHANNEL_PROPERTY: &str = "channel";
+/// The `content_metadata` property holding the channel ID of a document.
+pub const CHANNEL_ID_PROPERTY: &str = "channel_id";
+
+/// The base of the message links used as document source URLs.
+const CHANNELS_BASE_URL: &str = "https://discord.com/channels";
+const PROPERTY_METADATA_TYPE: &str = "PROPERTY";
+const ENTITY_METADATA_TYPE: &str = "ENTITY";
+/// The entity subtype of message authors, as used by the metadata extraction prompt.
+const PERSON_SUBTYPE: &str = "PERSON";
+
+/// Custom error types for the Discord ingestion process.
+#[derive(Error, Debug)]
+pub enum DiscordIngestError {
+    #[error("Database connection failed: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Failed to call the Discord API: {0}")]
+    Fetch(#[from] reqwest::Error),
+    #[error("Discord API returned an error: {0}")]
+    Api(String),
+    #[error("Discord kept rate-limiting {0}, giving up")]
+    RateLimited(String),
+    #[error("Source deserialization failed: {0}")]
+    SourceDeserialization(#[from] serde_json::Error),
+    #[error("Channel {0} does not belong to the requested guild")]
+    ChannelNotInGuild(String),
+}
+
+/// A helper to convert the specific `DiscordIngestError` into the generic `anyrag::ingest::IngestError`.
+impl From<DiscordIngestError> for IngestError {
+    fn from(err: DiscordIngestError) -> Self {
+        match err {
+            DiscordIngestError::Database(e) => IngestError::Database(e),
+            DiscordIngestError::Fetch(e) => IngestError::Fetch(e.to_string()),
+            DiscordIngestError::Api(e) => IngestError::Fetch(e),
+            DiscordIngestError::RateLimited(_) => IngestError::Fetch(err.to_string()),
+            DiscordIngestError::SourceDeserialization(e) => {
+                IngestError::Parse(format!("Invalid source JSON for Discord ingest: {e}"))
+            }
+            DiscordIngestError::ChannelNotInGuild(_) => {
+                IngestError::SourceNotFound(err.to_string())
+            }
+        }
+    }
+}
+
+/// Defines the structure of the JSON string passed to the `ingest` method.
+#[derive(Deserialize)]
+struct DiscordSource {
+    guild_id: String,
+    channel_ids: Vec<String>,
+    #[serde(default = "default_window_minutes")]
+    window_minutes: i64,
+    #[serde(default = "default_max_window_messages")]
+    max_window_messages: usize,
+    #[serde(default = "default_max_messages")]
+    max_messages: usize,
+}
+
+fn default_window_minutes() -> i64 {
+    DEFAULT_WINDOW_MINUTES
+}
+
+fn default_max_window_messages() -> usize {
+    DEFAULT_MAX_WINDOW_MESSAGES
+}
+
+fn default_max_messages() -> usize {
+    DEFAULT_MAX_MESSAGES
+}
+
+/// A message with its parsed timestamp.
+struct TimedMessage {
+    message: DiscordMessage,
+    posted_at: DateTime<Utc>,
+}
+
+/// A document prepared from a conversation window before it is stored.
+struct WindowDocument {
+    source_url: String,
+    title: String,
+    content: String,
+    channel: DiscordChannel,
+    authors: BTreeSet<String>,
+}
+
+/// The `Ingestor` implementation for Discord channels.
+pub struct DiscordIngestor {
+    db: Database,
+    client: DiscordClient,
+}
+
+impl DiscordIngestor {
+    /// Creates a new `DiscordIngestor` authenticated with a bot token. The bot needs the
+    /// `Read Message History` permission and the `Message Content` intent.
+    pub fn new(db: &Database, token: &str) -> Self {
+        Self {
+            db: db.clone(),
+            client: DiscordClient::new(DEFAULT_DISCORD_API_URL, token),
+        }
+    }
+
+    /// Points the ingestor at another Discord-compatible API, such as a mock server.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.client = self.client.with_base_url(base_url);
+        self
+    }
+
+    /// Fetches a channel's recent messages and batches them into window documents.
+    async fn channel_documents(
+        &self,
+        guild_id: &str,
+        channel_id: &str,
+        source: &DiscordSource,
+    ) -> Result<Vec<WindowDocument>, DiscordIngestError> {
+        let channel = self.client.channel(channel_id).await?;
+        if channel.guild_id.as_deref() != Some(guild_id) {
+            return Err(DiscordIngestError::ChannelNotInGuild(
+                channel_id.to_string(),
+            ));
+        }
+
+        let mut messages: Vec<TimedMessage> = self
+            .client
+            .channel_messages(channel_id, source.max_messages)
+            .await?
+            .into_iter()
+            .filter(|message| !message.content.trim().is_empty())
+            .filter_map(|message| {
+                let Ok(posted_at) = DateTime::parse_from_rfc3339(&message.timestamp) else {
+                    warn!(
+                        "Skipping Discord message {} with an invalid timestamp.",
+                        message.id
+                    );
+                    return None;
+                };
+                Some(TimedMessage {
+                    posted_at: posted_at.with_timezone(&Utc),
+                    message,
+                })
+            })
+            .collect();
+        messages.sort_by_key(|timed| timed.posted_at);
+
+        let windows = conversation_windows(
+            messages,
+            Duration::minutes(source.window_minutes),
+            source.max_window_messages,
+        );
+        Ok(windows
+            .into_iter()
+            .filter_map(|window| window_document(guild_id, &channel, window))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Ingestor for DiscordIngestor {
+    /// Reads the recent messages of guild channels and stores them as conversation windows.
+    ///
+    /// The `source` argument is expected to be a JSON string with the guild and its
+    /// channels, for example:
+    /// `{"guild_id": "81384788765712384", "channel_ids": ["381870553235193857"]}`.
+    /// `window_minutes`, `max_window_messages`, and `max_messages` (per channel) are
+    /// optional. A window that gained messages since the last ingestion is replaced.
+    async fn ingest(
+        &self,
+        source: &str,
+        owner_id: Option<&str>,
+    ) -> Result<IngestionResult, IngestError> {
+        let discord_source: DiscordSource =
+            serde_json::from_str(source).map_err(DiscordIngestError::from)?;
+        let guild_id = &discord_source.guild_id;
+
+        // Windows are fetched before the transaction so it is not held open over the network.
+        let mut documents = Vec::new();
+        for channel_id in &discord_source.channel_ids {
+            info!("Fetching Discord channel {channel_id} of guild {guild_id}.");
+            documents.extend(
+                self.channel_documents(guild_id, channel_id, &discord_source)
+                    .await?,
+            );
+        }
+
+        let mut conn = self.db.connect().map_err(DiscordIngestError::from)?;
+        let tx = conn.transaction().await.map_err(DiscordIngestError::from)?;
+        let mut new_document_ids = Vec::new();
+
+        for document in documents {
+            let document_id =
+                Uuid::new_v5(&Uuid::NAMESPACE_URL, document.source_url.as_bytes()).to_string();
+            let hash = content_hash(&document.content);
+            if find_duplicate_document(&tx, owner_id, &hash)
+                .await
+                .map_err(DiscordIngestError::from)?
+                .is_some()
+            {
+                info!("Skipping unchanged Discord window: {}", document.source_url);
+                continue;
+            }
+
+            // The `source_url` links to the first message of the window, so a window that
+            // gained messages replaces its previous version.
+            record_revision(&tx, &document.source_url, &document.content)
+                .await
+                .map_err(DiscordIngestError::from)?;
+            let changes = tx
+                .execute(
+                    "INSERT INTO documents (id, owner_id, source_url, title, content, content_hash)
+                     VALUES (?, ?, ?, ?, ?, ?)
+                     ON CONFLICT(source_url) DO UPDATE SET
+                     title = excluded.title,
+                     content = excluded.content,
+                     content_hash = excluded.content_hash",
+                    params![
+                        document_id.clone(),
+                        owner_id,
+                        document.source_url.clone(),
+                        document.title.clone(),
+                        document.content.clone(),
+                        hash
+                    ],
+                )
+                .await
+                .map_err(DiscordIngestError::from)?;
+            store_window_metadata(&tx, &document_id, owner_id, &document)
+                .await
+                .map_err(DiscordIngestError::from)?;
+
+            if changes > 0 {
+                new_document_ids.push(document_id);
+            }
+        }
+
+        tx.commit().await.map_err(DiscordIngestError::from)?;
+
+        info!(
+            "Ingested {} new conversation windows from guild {guild_id}.",
+            new_document_ids.len()
+        );
+
+        Ok(IngestionResult {
+            documents_added: new_document_ids.len(),
+            source: guild_id.to_string(),
+            document_ids: new_document_ids,
+            metadata: None,
+        })
+    }
+}
+
+/// Splits time-ordered messages into windows at every gap longer than `max_gap`, and
+/// every `max_messages` messages.
+fn conversation_windows(
+    messages: Vec<TimedMessage>,
+    max_gap: Duration,
+    max_messages: usize,
+) -> Vec<Vec<TimedMessage>> {
+    let mut windows: Vec<Vec<TimedMessage>> = Vec::new();
+    for message in messages {
+        let starts_window = match windows.last() {
+            Some(window) => {
+                window.len() >= max_messages
+                    || window
+                        .last()
+                        .is_none_or(|previous| message.posted_at - previous.posted_at > max_gap)
+            }
+            None => true,
+        };
+        match (starts_window, windows.last_mut()) {
+            (false, Some(window)) => window.push(message),
+            _ => windows.push(vec![message]),
+        }
+    }
+    windows
+}
+
+/// Builds the document of a conversation window.
+fn window_document(
+    guild_id: &str,
+    channel: &DiscordChannel,
+    window: Vec<TimedMessage>,
+) -> Option<WindowDocument> {
+    let first = window.first()?;
+    let channel_name = channel.name.as_deref().unwrap_or(&channel.id);
+    let source_url = format!(
+        "{CHANNELS_BASE_URL}/{guild_id}/{}/{}",
+        channel.id, first.message.id
+    );
+    let title = format!(
+        "#{channel_name} conversation, {}",
+        first.posted_at.format("%Y-%m-%d %H:%M UTC")
+    );
+    let authors = window
+        .iter()
+        .map(|timed| timed.message.author.display_name().to_string())
+        .collect();
+    let content = window
+        .iter()
+        .map(|timed| {
+            format!(
+                "[{}] {}: {}",
+                timed.posted_at.format("%Y-%m-%d %H:%M UTC"),
+                timed.message.author.display_name(),
+                timed.message.content.trim()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Some(WindowDocument {
+        source_url,
+        title,
+        content,
+        channel: channel.clone(),
+        authors,
+    })
+}
+
+/// Replaces the channel and author metadata of a window document.
+async fn store_window_metadata(
+    conn: &Connection,
+    document_id: &str,
+    owner_id: Option<&str>,
+    document: &WindowDocument,
+) -> Result<(), turso::Error> {
+    conn.execute(
+        "DELETE FROM content_metadata WHERE document_id = ?",
+        params![document_id],
+    )
+    .await?;
+
+    let channel_name = document.channel.name.clone().unwrap_or_default();
+    let mut rows = vec![
+        (PROPERTY_METADATA_TYPE, CHANNEL_PROPERTY, channel_name),
+        (
+            PROPERTY_METADATA_TYPE,
+            CHANNEL_ID_PROPERTY,
+            document.channel.id.clone(),
+        ),
+    ];
+    rows.extend(
+        document
+            .authors
+            .iter()
+            .map(|author| (ENTITY_METADATA_TYPE, PERSON_SUBTYPE, author.clone())),
+    );
+
+    for (metadata_type, subtype, value) in rows {
+        conn.execute(
+            "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_subtype, metadata_value) VALUES (?, ?, ?, ?, ?)",
+            params![document_id, owner_id, metadata_type, subtype, value],
+        )
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,157 @@
+//! # Discord Crate Tests
+//!
+//! This file contains integration tests for the `anyrag-discord` crate, running the
+//! ingestor against a mock Discord REST API.
+
+use anyhow::Result;
+use anyrag::ingest::Ingestor;
+use anyrag_discord::{DiscordIngestor, CHANNEL_PROPERTY};
+use anyrag_test_utils::TestSetup;
+use serde_json::json;
+use turso::params;
+use wiremock::matchers::{header, method, path, query_param_is_missing};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const GUILD_ID: &str = "999";
+const CHANNEL_ID: &str = "111";
+const TOKEN: &str = "bot-test-token";
+
+/// Mounts a `#support` channel with two conversations an hour and a half apart.
+async fn mount_support_channel(server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path(format!("/channels/{CHANNEL_ID}")))
+        .and(header("Authorization", format!("Bot {TOKEN}").as_str()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": CHANNEL_ID, "name": "support", "guild_id": GUILD_ID
+        })))
+        .mount(server)
+        .await;
+    // Messages are returned newest first, as Discord does.
+    Mock::given(method("GET"))
+        .and(path(format!("/channels/{CHANNEL_ID}/messages")))
+        .and(query_param_is_missing("before"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "id": "5", "author": { "id": "u2", "username": "bob" }, "content": "Thanks, that worked.", "timestamp": "2024-05-01T12:10:00+00:00" },
+            { "id": "4", "author": { "id": "u1", "username": "alice", "global_name": "Alice" }, "content": "Try clearing the cache.", "timestamp": "2024-05-01T12:00:00+00:00" },
+            { "id": "3", "author": { "id": "u2", "username": "bob" }, "content": "", "timestamp": "2024-05-01T10:06:00+00:00" },
+            { "id": "2", "author": { "id": "u1", "username": "alice", "global_name": "Alice" }, "content": "Which version are you on?", "timestamp": "2024-05-01T10:05:00+00:00" },
+            { "id": "1", "author": { "id": "u2", "username": "bob" }, "content": "The build fails on startup.", "timestamp": "2024-05-01T10:00:00+00:00" }
+        ])))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_discord_ingestor_batches_messages_into_windows() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    mount_support_channel(&server).await;
+    let setup = TestSetup::new().await?;
+    let ingestor = DiscordIngestor::new(&setup.db, TOKEN).with_base_url(&server.uri());
+    let source = json!({ "guild_id": GUILD_ID, "channel_ids": [CHANNEL_ID] }).to_string();
+
+    // --- Act ---
+    let result = ingestor.ingest(&source, Some("discord-user")).await?;
+
+    // --- Assert ---
+    // The 115-minute gap splits the channel into two windows; the empty message is skipped.
+    assert_eq!(result.documents_added, 2);
+
+    let conn = setup.db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT id, title, content FROM documents WHERE source_url = ?",
+            params!["https://discord.com/channels/999/111/1"],
+        )
+        .await?;
+    let row = rows.next().await?.expect("First window not found");
+    let document_id = row.get::<String>(0)?;
+    assert_eq!(
+        row.get::<String>(1)?,
+        "#support conversation, 2024-05-01 10:00 UTC"
+    );
+    assert_eq!(
+        row.get::<String>(2)?,
+        "[2024-05-01 10:00 UTC] bob: The build fails on startup.\n[2024-05-01 10:05 UTC] Alice: Which version are you on?"
+    );
+
+    let mut rows = conn
+        .query(
+            "SELECT metadata_type, metadata_subtype, metadata_value FROM content_metadata
+             WHERE document_id = ? ORDER BY metadata_type, metadata_subtype, metadata_value",
+            params![document_id],
+        )
+        .await?;
+    let mut metadata = Vec::new();
+    while let Some(row) = rows.next().await? {
+        metadata.push((
+            row.get::<String>(0)?,
+            row.get::<String>(1)?,
+            row.get::<String>(2)?,
+        ));
+    }
+    let expected = [
+        ("ENTITY", "PERSON", "Alice"),
+        ("ENTITY", "PERSON", "bob"),
+        ("PROPERTY", CHANNEL_PROPERTY, "support"),
+        ("PROPERTY", "channel_id", CHANNEL_ID),
+    ];
+    assert_eq!(metadata.len(), expected.len());
+    for ((t, s, v), (et, es, ev)) in metadata.iter().zip(expected) {
+        assert_eq!((t.as_str(), s.as_str(), v.as_str()), (et, es, ev));
+    }
+
+    let mut rows = conn
+        .query(
+            "SELECT content FROM documents WHERE source_url = ?",
+            params!["https://discord.com/channels/999/111/4"],
+        )
+        .await?;
+    let row = rows.next().await?.expect("Second window not found");
+    assert!(row.get::<String>(0)?.ends_with("bob: Thanks, that worked."));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_discord_ingestor_waits_out_rate_limits() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/channels/{CHANNEL_ID}/messages")))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0.01"))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    mount_support_channel(&server).await;
+    let setup = TestSetup::new().await?;
+    let ingestor = DiscordIngestor::new(&setup.db, TOKEN).with_base_url(&server.uri());
+
+    // --- Act ---
+    let source = json!({ "guild_id": GUILD_ID, "channel_ids": [CHANNEL_ID] }).to_string();
+    let result = ingestor.ingest(&source, None).await?;
+
+    // --- Assert ---
+    assert_eq!(result.documents_added, 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_discord_ingestor_rejects_channels_of_other_guilds() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    mount_support_channel(&server).await;
+    let setup = TestSetup::new().await?;
+    let ingestor = DiscordIngestor::new(&setup.db, TOKEN).with_base_url(&server.uri());
+
+    // --- Act ---
+    let source = json!({ "guild_id": "another-guild", "channel_ids": [CHANNEL_ID] }).to_string();
+    let result = ingestor.ingest(&source, None).await;
+
+    // --- Assert ---
+    let err = result.expect_err("A channel of another guild should be rejected");
+    assert!(err.to_string().contains(CHANNEL_ID));
+    Ok(())
+}
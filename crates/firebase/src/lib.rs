@@ -5,7 +5,9 @@
 //! core `anyrag` library.
 
 use anyhow::anyhow;
-use anyrag::ingest::{state_manager, IngestError as AnyragIngestError, IngestionResult, Ingestor};
+use anyrag::ingest::{
+    bulk_insert_rows, state_manager, IngestError as AnyragIngestError, IngestionResult, Ingestor,
+};
 use anyrag::providers::db::sqlite::SqliteProvider;
 use async_trait::async_trait;
 use chrono::{DateTime, TimeZone, Utc};
@@ -284,20 +286,14 @@ async fn insert_documents(
         .map(|c| format!("\"{c}\""))
         .collect::<Vec<_>>()
         .join(", ");
-    let values_placeholders = (0..snake_case_columns.len() + 1)
-        .map(|_| "?")
-        .collect::<Vec<_>>()
-        .join(", ");
     let update_set_clause = snake_case_columns
         .iter()
         .map(|c| format!("\"{c}\" = excluded.\"{c}\""))
         .collect::<Vec<_>>()
         .join(", ");
-    let insert_sql = format!(
-        "INSERT INTO \"{table_name}\" (_id, {columns_list}) VALUES ({values_placeholders})
-         ON CONFLICT(_id) DO UPDATE SET {update_set_clause};"
-    );
-    let mut stmt = conn.prepare(&insert_sql).await?;
+    let insert_sql = format!("INSERT INTO \"{table_name}\" (_id, {columns_list})");
+    let conflict_sql = format!("ON CONFLICT(_id) DO UPDATE SET {update_set_clause}");
+    let mut rows = Vec::with_capacity(documents.len());
     for doc in documents {
         let doc_id = doc
             .name
@@ -311,8 +307,9 @@ async fn insert_documents(
             let firestore_value = doc.fields.get(camel_case_name);
             params.push(convert_firestore_value_to_turso(firestore_value.cloned())?);
         }
-        stmt.execute(params).await?;
+        rows.push(params);
     }
+    bulk_insert_rows(&conn, &insert_sql, &conflict_sql, rows).await?;
     conn.execute("COMMIT", ()).await?;
     Ok(())
 }
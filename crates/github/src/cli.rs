@@ -1,6 +1,9 @@
 use crate::ingest::{
-    crawler::Crawler, extractor::Extractor, run_github_ingestion, storage::StorageManager,
-    types::IngestionTask,
+    crawler::Crawler,
+    extractor::Extractor,
+    run_github_ingestion,
+    storage::StorageManager,
+    types::{ExampleSourceType, IngestionTask},
 };
 use anyhow::Result;
 use anyrag::{constants, ingest::Ingestor};
@@ -19,17 +22,18 @@ pub enum DumpType {
     Examples,
     Src,
     Tests,
+    Docs,
 }
 
 #[derive(Parser, Debug)]
 pub struct GithubArgs {
-    /// The URL of the public GitHub repository to ingest
+    /// The URL of the repository to ingest (GitHub, GitLab, Bitbucket, or any git remote)
     #[arg(long, required = true)]
     pub url: String,
     /// An optional git version (tag, branch, commit hash) to ingest. Defaults to the latest release tag.
     #[arg(long)]
     pub version: Option<String>,
-    /// The type of content to dump (examples or all source files).
+    /// The type of content to dump (examples, tests, all source files, or documentation).
     #[arg(long, value_enum, default_value_t = DumpType::Examples)]
     pub dump_type: DumpType,
     /// A comma-separated list of directory paths to include (e.g., "examples/rust,crates/core").
@@ -51,6 +55,9 @@ pub struct GithubArgs {
     /// Extract and embed files referenced by `include_bytes!` macros.
     #[arg(long)]
     pub extract_included_files: bool,
+    /// An access token for cloning a private repository over HTTP(S).
+    #[arg(long, env = "GIT_AUTH_TOKEN", hide_env_values = true)]
+    pub auth_token: Option<String>,
 }
 
 pub async fn handle_dump_github(args: &GithubArgs) -> Result<()> {
@@ -58,6 +65,7 @@ pub async fn handle_dump_github(args: &GithubArgs) -> Result<()> {
         DumpType::Examples => handle_examples_dump(args).await,
         DumpType::Src => handle_src_dump(args).await,
         DumpType::Tests => handle_tests_dump(args).await,
+        DumpType::Docs => handle_docs_dump(args).await,
     }
 }
 
@@ -78,6 +86,7 @@ async fn handle_examples_dump(args: &GithubArgs) -> Result<()> {
         dump_type: crate::ingest::types::DumpType::Examples,
         includes: args.includes.clone(),
         excludes: args.excludes.clone(),
+        auth_token: args.auth_token.clone(),
     };
 
     let storage_manager = StorageManager::new(Some(constants::GITHUB_DB_DIR)).await?;
@@ -113,19 +122,11 @@ async fn handle_examples_dump(args: &GithubArgs) -> Result<()> {
     let example_markdown = sorted_examples
         .iter()
         .map(|ex| {
-            let path = std::path::Path::new(&ex.source_file);
-            let language = path
-                .extension()
-                .and_then(|s| s.to_str())
-                .map(|ext| match ext {
-                    "rs" => "rust",
-                    "toml" => "toml",
-                    "json" => "json",
-                    "yaml" | "yml" => "yaml",
-                    "md" => "markdown",
-                    _ => "text",
-                })
-                .unwrap_or("text");
+            // Text file examples keep their own fence, so they are wrapped as Markdown.
+            let language = match ex.source_type {
+                ExampleSourceType::TextFile => "markdown",
+                _ => ex.language.as_str(),
+            };
 
             format!(
                 "## `{}`\n\n```{}\n{}\n```\n",
@@ -167,6 +168,7 @@ async fn handle_tests_dump(args: &GithubArgs) -> Result<()> {
         dump_type: crate::ingest::types::DumpType::Tests,
         includes: args.includes.clone(),
         excludes: args.excludes.clone(),
+        auth_token: args.auth_token.clone(),
     };
 
     let storage_manager = StorageManager::new(Some(constants::GITHUB_DB_DIR)).await?;
@@ -228,6 +230,86 @@ async fn handle_tests_dump(args: &GithubArgs) -> Result<()> {
     Ok(())
 }
 
+async fn handle_docs_dump(args: &GithubArgs) -> Result<()> {
+    info!(
+        "Starting GitHub DOCS ingestion for URL: {} with version: {:?}",
+        args.url, args.version
+    );
+    println!("📥 Starting documentation ingestion for '{}'...", args.url);
+
+    let task = IngestionTask {
+        url: args.url.clone(),
+        version: args.version.clone(),
+        embedding_api_url: None,
+        embedding_model: None,
+        embedding_api_key: None,
+        extract_included_files: args.extract_included_files,
+        dump_type: crate::ingest::types::DumpType::Docs,
+        includes: args.includes.clone(),
+        excludes: args.excludes.clone(),
+        auth_token: args.auth_token.clone(),
+    };
+
+    let storage_manager = StorageManager::new(Some(constants::GITHUB_DB_DIR)).await?;
+    let (ingested_count, ingested_version) = run_github_ingestion(&storage_manager, task).await?;
+    println!(
+        "✅ Successfully ingested {} documentation sections from '{}' (version: {}).",
+        ingested_count, args.url, ingested_version
+    );
+
+    if ingested_count == 0 {
+        println!("No documentation was found to generate a markdown file.");
+        return Ok(());
+    }
+
+    println!("📝 Generating consolidated documentation file...");
+    let repo_name = StorageManager::url_to_repo_name(&args.url);
+    let sections = storage_manager
+        .get_doc_sections(&repo_name, &ingested_version)
+        .await?;
+    let links = storage_manager
+        .get_doc_example_links(&repo_name, &ingested_version)
+        .await?;
+
+    let mut markdown_content =
+        format!("# Documentation for {repo_name} (Version: {ingested_version})\n\n");
+
+    let section_markdown = sections
+        .iter()
+        .map(|section| {
+            let breadcrumb = std::iter::once(section.source_file.as_str())
+                .chain(section.heading_path.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(" > ");
+            let related = links
+                .iter()
+                .filter(|link| link.section_handle == section.section_handle)
+                .map(|link| format!("`{}`", link.example_handle))
+                .collect::<Vec<_>>();
+            let related_markdown = match related.is_empty() {
+                true => String::new(),
+                false => format!("\n\nRelated examples: {}", related.join(", ")),
+            };
+            format!("## {breadcrumb}\n\n{}{related_markdown}\n", section.content)
+        })
+        .collect::<Vec<String>>()
+        .join("---\n");
+
+    markdown_content.push_str(&section_markdown);
+
+    let safe_version = ingested_version.replace('/', "-");
+    let output_filename = format!("{repo_name}-{safe_version}-docs.md");
+    fs::write(&output_filename, markdown_content)?;
+    println!("✅ Successfully generated documentation file: '{output_filename}'");
+
+    if !args.no_process {
+        let chunk_db_dir = format!("{}/docs", constants::GITHUB_CHUNKS_DB_DIR);
+        process_markdown_file(args, &output_filename, &repo_name, &chunk_db_dir).await?;
+    }
+
+    Ok(())
+}
+
 async fn handle_src_dump(args: &GithubArgs) -> Result<()> {
     info!(
         "Starting GitHub SRC ingestion for URL: {} with version: {:?}",
@@ -245,6 +327,7 @@ async fn handle_src_dump(args: &GithubArgs) -> Result<()> {
         dump_type: crate::ingest::types::DumpType::Src,
         includes: args.includes.clone(),
         excludes: args.excludes.clone(),
+        auth_token: args.auth_token.clone(),
     };
 
     let crawl_result = Crawler::crawl(&task).await?;
@@ -344,8 +427,9 @@ async fn process_markdown_file(
     let markdown_source = MarkdownSource {
         db_path: chunk_db_path.clone(),
         file_path: output_filename.to_string(),
-        separator: "---\n".to_string(),
+        separator: Some("---\n".to_string()),
         embedding_config,
+        chunking: None,
     };
 
     let source_json = serde_json::to_string(&markdown_source)?;
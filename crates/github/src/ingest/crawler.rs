@@ -1,9 +1,13 @@
 //! # Git Repository Crawler
 //!
-//! This module provides the functionality to clone a public Git repository
-//! into a temporary local directory for analysis.
+//! This module provides the functionality to clone a Git repository from GitHub,
+//! GitLab, Bitbucket, or any other git remote into a temporary local directory for
+//! analysis. An optional access token is passed to git through its environment, so
+//! it never appears in the clone URL or in the logs.
 
+use super::remote::RepoUrl;
 use super::types::{GitHubIngestError, IngestionTask};
+use base64::{engine::general_purpose, Engine as _};
 use semver::Version;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -27,12 +31,24 @@ impl Crawler {
     /// Clones a Git repository for a given ingestion task and returns the path
     /// to the temporary directory where it was cloned.
     pub async fn crawl(task: &IngestionTask) -> Result<CrawlResult, GitHubIngestError> {
-        info!("Starting crawl for repository: {}", task.url);
+        // The clone URL never carries credentials, so it is the one that gets logged.
+        let remote = RepoUrl::parse(&task.url);
+        let clone_url = remote
+            .as_ref()
+            .map(RepoUrl::clone_url)
+            .unwrap_or_else(|| task.url.clone());
+        let remote_env = Self::remote_env(remote.as_ref(), task.auth_token.as_deref());
+
+        info!(
+            "Starting crawl for repository: {} (authenticated: {})",
+            clone_url,
+            task.auth_token.is_some()
+        );
         let temp_dir = tempdir().map_err(GitHubIngestError::Io)?;
         let repo_path = temp_dir.path().to_path_buf();
 
         // 1. Clone the repository (use sparse checkout when includes are specified)
-        let mut clone_cmd = Command::new("git");
+        let mut clone_cmd = Self::git(&remote_env);
         clone_cmd.arg("clone");
 
         if task.includes.is_some() {
@@ -43,7 +59,7 @@ impl Crawler {
         }
 
         let clone_status = clone_cmd
-            .arg(&task.url)
+            .arg(&clone_url)
             .arg(&repo_path)
             .status()
             .await
@@ -59,7 +75,7 @@ impl Crawler {
         // 2. Apply sparse checkout if includes are specified
         if let Some(includes) = &task.includes {
             info!("Applying sparse checkout for paths: {:?}", includes);
-            let sparse_status = Command::new("git")
+            let sparse_status = Self::git(&remote_env)
                 .arg("-C")
                 .arg(&repo_path)
                 .arg("sparse-checkout")
@@ -82,9 +98,9 @@ impl Crawler {
         let version = if let Some(version_spec) = &task.version {
             // 2. If a specific version is requested, fetch and check it out.
             // Fetch tags (safely handles non-shallow repos from sparse clones).
-            Self::fetch_tags_for_checkout(&repo_path).await?;
+            Self::fetch_tags_for_checkout(&repo_path, &remote_env).await?;
 
-            let checkout_status = Command::new("git")
+            let checkout_status = Self::git(&remote_env)
                 .arg("-C")
                 .arg(&repo_path)
                 .arg("checkout")
@@ -104,7 +120,7 @@ impl Crawler {
 
             // Re-apply sparse checkout after switching to a different ref
             if task.includes.is_some() {
-                Self::reapply_sparse_checkout(&repo_path).await?;
+                Self::reapply_sparse_checkout(&repo_path, &remote_env).await?;
             }
 
             version_spec.clone()
@@ -114,13 +130,13 @@ impl Crawler {
 
             // To find the latest tag, we need to fetch all tags first.
             // Fetch tags (safely handles non-shallow repos from sparse clones).
-            Self::fetch_tags_for_checkout(&repo_path).await?;
+            Self::fetch_tags_for_checkout(&repo_path, &remote_env).await?;
 
             let latest_tag = Self::get_latest_semver_tag(&repo_path).await?;
 
             if let Some(tag) = latest_tag {
                 info!("Found latest semver tag: {}. Checking it out.", tag);
-                let checkout_status = Command::new("git")
+                let checkout_status = Self::git(&remote_env)
                     .arg("-C")
                     .arg(&repo_path)
                     .arg("checkout")
@@ -142,7 +158,7 @@ impl Crawler {
 
                     // Re-apply sparse checkout after switching to a different ref
                     if task.includes.is_some() {
-                        Self::reapply_sparse_checkout(&repo_path).await?;
+                        Self::reapply_sparse_checkout(&repo_path, &remote_env).await?;
                     }
 
                     tag
@@ -165,6 +181,47 @@ impl Crawler {
         })
     }
 
+    /// Creates a git command that may talk to the remote, with the given environment.
+    /// Blobless clones also fetch file contents lazily, e.g. during checkout.
+    fn git(remote_env: &[(&str, String)]) -> Command {
+        let mut command = Command::new("git");
+        for (key, value) in remote_env {
+            command.env(key, value);
+        }
+        command
+    }
+
+    /// Builds the environment for git commands that reach the remote.
+    ///
+    /// An access token is sent as an HTTP `Authorization` header through git's
+    /// `GIT_CONFIG_*` variables, using the user name the host expects for tokens.
+    fn remote_env(
+        remote: Option<&RepoUrl>,
+        auth_token: Option<&str>,
+    ) -> Vec<(&'static str, String)> {
+        // Fail instead of waiting for credentials on a terminal.
+        let mut env = vec![("GIT_TERMINAL_PROMPT", "0".to_string())];
+        let Some(token) = auth_token else {
+            return env;
+        };
+        match remote {
+            Some(remote) if remote.is_http() => {
+                let credentials = general_purpose::STANDARD
+                    .encode(format!("{}:{token}", remote.host_kind.token_username()));
+                env.extend([
+                    ("GIT_CONFIG_COUNT", "1".to_string()),
+                    ("GIT_CONFIG_KEY_0", "http.extraHeader".to_string()),
+                    (
+                        "GIT_CONFIG_VALUE_0",
+                        format!("Authorization: Basic {credentials}"),
+                    ),
+                ]);
+            }
+            _ => warn!("An auth token is only used for HTTP(S) remotes. Cloning without it."),
+        }
+        env
+    }
+
     /// Gets the commit hash of the current HEAD.
     async fn get_head_commit(repo_path: &Path) -> Result<String, GitHubIngestError> {
         let output = Command::new("git")
@@ -242,8 +299,11 @@ impl Crawler {
     /// Re-applies the sparse checkout after switching refs (tags/branches).
     /// When checking out a different ref, git may expand the working tree
     /// beyond the sparse cone. This restores the sparse filter.
-    async fn reapply_sparse_checkout(repo_path: &Path) -> Result<(), GitHubIngestError> {
-        let reapply_status = Command::new("git")
+    async fn reapply_sparse_checkout(
+        repo_path: &Path,
+        remote_env: &[(&str, String)],
+    ) -> Result<(), GitHubIngestError> {
+        let reapply_status = Self::git(remote_env)
             .arg("-C")
             .arg(repo_path)
             .arg("sparse-checkout")
@@ -266,9 +326,12 @@ impl Crawler {
     /// Fetches tags needed for version resolution.
     /// Safely handles both shallow and non-shallow (e.g., sparse blobless) clones
     /// by tolerating the `--unshallow` failure on complete repositories.
-    async fn fetch_tags_for_checkout(repo_path: &Path) -> Result<(), GitHubIngestError> {
+    async fn fetch_tags_for_checkout(
+        repo_path: &Path,
+        remote_env: &[(&str, String)],
+    ) -> Result<(), GitHubIngestError> {
         // Try to unshallow — will fail harmlessly on non-shallow (blobless/sparse) clones.
-        let unshallow_status = Command::new("git")
+        let unshallow_status = Self::git(remote_env)
             .arg("-C")
             .arg(repo_path)
             .arg("fetch")
@@ -286,7 +349,7 @@ impl Crawler {
             );
         }
 
-        let fetch_tags_status = Command::new("git")
+        let fetch_tags_status = Self::git(remote_env)
             .arg("-C")
             .arg(repo_path)
             .arg("fetch")
@@ -0,0 +1,293 @@
+//! # Documentation Extractor
+//!
+//! This module finds the Markdown documentation of a cloned repository (READMEs,
+//! `docs/` trees such as Docusaurus sites, and mdBook sources) and splits each file
+//! into heading-level sections. Every section keeps its file path and the breadcrumb
+//! of headings above it, so the hierarchy of the documentation is preserved. Sections
+//! are then linked to the code examples they embed or whose files they mention.
+
+use super::extractor::Extractor;
+use super::types::{DocExampleLink, DocLinkType, DocSection, GeneratedExample, GitHubIngestError};
+use glob::Pattern;
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Directory names whose Markdown files are treated as documentation.
+const DOC_DIRS: [&str; 4] = ["docs", "doc", "documentation", "versioned_docs"];
+/// Directories that never contain the repository's own documentation.
+const SKIPPED_DIRS: [&str; 3] = ["node_modules", "target", "vendor"];
+/// File extensions recognized as Markdown.
+const MARKDOWN_EXTENSIONS: [&str; 3] = ["md", "mdx", "markdown"];
+/// The manifest that marks the root of an mdBook.
+const MDBOOK_MANIFEST: &str = "book.toml";
+/// The source directory of an mdBook when `book.toml` does not set one.
+const MDBOOK_DEFAULT_SRC: &str = "src";
+/// The prefix of every section handle.
+const SECTION_HANDLE_PREFIX: &str = "doc";
+
+/// The Markdown files and mdBook source directories found in a repository.
+#[derive(Default)]
+struct DiscoveredDocs {
+    markdown_files: Vec<PathBuf>,
+    book_sources: Vec<PathBuf>,
+}
+
+/// The main struct for the documentation extraction process.
+pub struct DocExtractor;
+
+impl DocExtractor {
+    /// Extracts the sections of every documentation file in a repository directory.
+    pub fn extract(
+        repo_path: &Path,
+        version: &str,
+        includes: &Option<Vec<String>>,
+        excludes: &[Pattern],
+    ) -> Result<Vec<DocSection>, GitHubIngestError> {
+        info!(
+            "Starting documentation extraction from path: {}",
+            repo_path.display()
+        );
+
+        let mut discovered = DiscoveredDocs::default();
+        Self::discover_docs_recursive(repo_path, repo_path, &mut discovered, includes, excludes)?;
+        discovered.markdown_files.sort();
+
+        let mut sections = Vec::new();
+        let mut doc_files = 0;
+        for file_path in &discovered.markdown_files {
+            let relative = file_path.strip_prefix(repo_path).unwrap_or(file_path);
+            let in_book = discovered
+                .book_sources
+                .iter()
+                .any(|src| file_path.starts_with(src));
+            if !in_book && !Self::is_doc_path(relative) {
+                continue;
+            }
+            let content = fs::read_to_string(file_path)?;
+            let relative = relative.to_string_lossy().to_string();
+            sections.extend(Self::split_sections(&relative, &content, version)?);
+            doc_files += 1;
+        }
+
+        info!(
+            "Extracted {} sections from {} documentation files ({} mdBook sources).",
+            sections.len(),
+            doc_files,
+            discovered.book_sources.len()
+        );
+        Ok(sections)
+    }
+
+    /// Links each section to the examples it embeds or whose source file it mentions.
+    ///
+    /// An example is embedded when one of the section's code blocks has the same code,
+    /// ignoring whitespace. File references only consider examples taken from code
+    /// files, since every Markdown file would otherwise match its own examples.
+    pub fn link_examples(
+        sections: &[DocSection],
+        examples: &[GeneratedExample],
+    ) -> Result<Vec<DocExampleLink>, GitHubIngestError> {
+        let code_block_re = Regex::new(r"(?s)```[^\n]*\n(.*?)\n\s*```")?;
+        let normalized_examples: Vec<String> = examples
+            .iter()
+            .map(|example| normalize_code(&example.content))
+            .collect();
+
+        let mut links = Vec::new();
+        for section in sections {
+            let code_blocks: HashSet<String> = code_block_re
+                .captures_iter(&section.content)
+                .filter_map(|cap| cap.get(1))
+                .map(|code| normalize_code(code.as_str()))
+                .filter(|code| !code.is_empty())
+                .collect();
+
+            for (example, normalized) in examples.iter().zip(&normalized_examples) {
+                let embedded = code_blocks.contains(normalized);
+                let referenced = !Self::is_markdown(Path::new(&example.source_file))
+                    && section.content.contains(&example.source_file);
+                let link_type = match (embedded, referenced) {
+                    (true, _) => DocLinkType::Embedded,
+                    (false, true) => DocLinkType::FileReference,
+                    (false, false) => continue,
+                };
+                links.push(DocExampleLink {
+                    section_handle: section.section_handle.clone(),
+                    example_handle: example.example_handle.clone(),
+                    link_type,
+                });
+            }
+        }
+
+        info!(
+            "Linked {} documentation sections to code examples.",
+            links.len()
+        );
+        Ok(links)
+    }
+
+    /// Splits a Markdown file into sections at its ATX headings (`#` to `######`).
+    ///
+    /// Headings inside fenced code blocks are ignored, and a leading YAML front
+    /// matter block (as used by Docusaurus) is dropped. Sections without any text
+    /// besides their heading are kept so the hierarchy has no gaps.
+    fn split_sections(
+        relative_path: &str,
+        content: &str,
+        version: &str,
+    ) -> Result<Vec<DocSection>, GitHubIngestError> {
+        let heading_re = Regex::new(r"^(#{1,6})\s+(.+?)\s*#*\s*$")?;
+        let body = strip_front_matter(content);
+
+        let mut sections = Vec::new();
+        let mut headings: Vec<(usize, String)> = Vec::new();
+        let mut current = String::new();
+        let mut in_fence = false;
+
+        for line in body.lines() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+            }
+            let heading = match in_fence {
+                true => None,
+                false => heading_re.captures(line),
+            };
+            let Some(heading) = heading else {
+                current.push_str(line);
+                current.push('\n');
+                continue;
+            };
+
+            Self::push_section(&mut sections, relative_path, &headings, &current, version);
+            current.clear();
+
+            let level = heading[1].len();
+            headings.retain(|(parent_level, _)| *parent_level < level);
+            headings.push((level, heading[2].to_string()));
+            current.push_str(line);
+            current.push('\n');
+        }
+        Self::push_section(&mut sections, relative_path, &headings, &current, version);
+
+        Ok(sections)
+    }
+
+    fn push_section(
+        sections: &mut Vec<DocSection>,
+        relative_path: &str,
+        headings: &[(usize, String)],
+        content: &str,
+        version: &str,
+    ) {
+        let content = content.trim();
+        if content.is_empty() {
+            return;
+        }
+        sections.push(DocSection {
+            section_handle: format!("{SECTION_HANDLE_PREFIX}:{relative_path}:{}", sections.len()),
+            source_file: relative_path.to_string(),
+            heading_path: headings.iter().map(|(_, title)| title.clone()).collect(),
+            content: content.to_string(),
+            version: version.to_string(),
+        });
+    }
+
+    /// Recursively collects Markdown files and the source directories of mdBooks.
+    fn discover_docs_recursive(
+        base_dir: &Path,
+        dir: &Path,
+        discovered: &mut DiscoveredDocs,
+        includes: &Option<Vec<String>>,
+        excludes: &[Pattern],
+    ) -> Result<(), GitHubIngestError> {
+        if !dir.is_dir() {
+            return Ok(());
+        }
+
+        let book_manifest = dir.join(MDBOOK_MANIFEST);
+        if book_manifest.is_file() {
+            let manifest = fs::read_to_string(&book_manifest)?;
+            discovered
+                .book_sources
+                .push(dir.join(mdbook_src_dir(&manifest)?));
+        }
+
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_name = entry.file_name().to_string_lossy().to_string();
+
+            if file_name.starts_with('.') {
+                continue;
+            }
+
+            if path.is_dir() {
+                if SKIPPED_DIRS.contains(&file_name.as_str())
+                    || !Extractor::path_matches_filters(base_dir, &path, includes, &[])
+                {
+                    continue;
+                }
+                Self::discover_docs_recursive(base_dir, &path, discovered, includes, excludes)?;
+            } else if Self::is_markdown(&path)
+                && Extractor::path_matches_filters(base_dir, &path, includes, excludes)
+            {
+                discovered.markdown_files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether a Markdown file is a README or lives in a documentation directory.
+    fn is_doc_path(relative: &Path) -> bool {
+        let is_readme = relative
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase().starts_with("readme"))
+            .unwrap_or(false);
+        is_readme
+            || relative.components().any(|component| {
+                let name = component.as_os_str().to_string_lossy().to_lowercase();
+                DOC_DIRS.contains(&name.as_str())
+            })
+    }
+
+    fn is_markdown(path: &Path) -> bool {
+        path.extension()
+            .map(|ext| {
+                let ext = ext.to_string_lossy().to_lowercase();
+                MARKDOWN_EXTENSIONS.contains(&ext.as_str())
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// Reads the `book.src` setting of an mdBook's `book.toml`.
+fn mdbook_src_dir(manifest: &str) -> Result<String, GitHubIngestError> {
+    let value: toml::Value = toml::from_str(manifest)
+        .map_err(|e| GitHubIngestError::Config(format!("Failed to parse book.toml: {e}")))?;
+    Ok(value
+        .get("book")
+        .and_then(|book| book.get("src"))
+        .and_then(|src| src.as_str())
+        .unwrap_or(MDBOOK_DEFAULT_SRC)
+        .to_string())
+}
+
+/// Removes a leading `---` delimited front matter block.
+fn strip_front_matter(content: &str) -> &str {
+    let Some(rest) = content.strip_prefix("---\n") else {
+        return content;
+    };
+    match rest.find("\n---\n") {
+        Some(end) => &rest[end + "\n---\n".len()..],
+        None => content,
+    }
+}
+
+/// Collapses all whitespace so formatting differences do not prevent a match.
+fn normalize_code(code: &str) -> String {
+    code.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -3,7 +3,10 @@
 //! This module is responsible for finding and extracting code examples from the
 //! files of a cloned repository. It identifies potential source files based on
 //! naming conventions and location, then parses them to extract code blocks.
+//! Rust, Python, TypeScript, and Go are supported, and each example records its
+//! language.
 
+use super::languages::{self, fenced_code_blocks, language_of_fence, language_of_path, Language};
 use super::types::{ExampleSourceType, GeneratedExample, GitHubIngestError};
 use glob::Pattern;
 use regex::Regex;
@@ -12,6 +15,21 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use tracing::info;
 
+/// Matches a single-line `#[doc = "..."]` or `#![doc = "..."]` attribute, capturing
+/// either the escaped contents of a string literal or the contents of a raw string.
+const DOC_ATTRIBUTE_PATTERN: &str =
+    r##"^\s*#!?\[\s*doc\s*=\s*(?:"((?:[^"\\]|\\.)*)"|r#*"(.*)"#*)\s*\]\s*$"##;
+/// Fence tags rustdoc accepts on a Rust code block, besides `editionNNNN`.
+const RUSTDOC_FENCE_TAGS: [&str; 6] = [
+    "rust",
+    "ignore",
+    "no_run",
+    "should_panic",
+    "compile_fail",
+    "test_harness",
+];
+const RUSTDOC_EDITION_TAG_PREFIX: &str = "edition";
+
 /// A container for all discovered source files, categorized by their type.
 #[derive(Default)]
 struct DiscoveredSources {
@@ -99,7 +117,7 @@ impl Extractor {
 
     /// Checks if a file path matches the include/exclude filters.
     /// Returns `true` if the file should be processed.
-    fn path_matches_filters(
+    pub(crate) fn path_matches_filters(
         base_dir: &Path,
         path: &Path,
         includes: &Option<Vec<String>>,
@@ -161,17 +179,19 @@ impl Extractor {
                 Self::discover_files_recursive(base_dir, &path, sources, includes, excludes)?;
             } else if Self::path_matches_filters(base_dir, &path, includes, excludes) {
                 let path_str = path.to_string_lossy();
+                let language = Language::from_path(&path);
                 if file_name == "readme.md" {
                     sources.readmes.push(path.clone());
                 } else if (path_str.contains("/tests/") || file_name.ends_with("_test.rs"))
-                    && file_name.ends_with(".rs")
+                    && language == Some(Language::Rust)
                 {
                     sources.tests.push(path.clone());
-                } else if path_str.contains("/examples/") && file_name.ends_with(".rs") {
+                } else if path_str.contains("/examples/") && language.is_some() {
                     sources.example_files.push(path.clone());
                 } else if file_name.ends_with(".md") {
                     sources.text_files.push(path.clone());
-                } else if file_name.ends_with(".rs") {
+                } else if language.is_some() {
+                    // Rustdoc, docstrings, JSDoc, and Go doc comments.
                     sources.doc_comments.push(path.clone());
                 }
             }
@@ -405,14 +425,15 @@ impl Extractor {
         Ok(())
     }
 
-    /// Parses `README.md` files to extract Rust code blocks.
+    /// Parses `README.md` files to extract code blocks tagged with a supported language
+    /// (e.g. `rust`, `py`, `ts`, or `go`).
     fn parse_readme_files(
         repo_path: &Path,
         files: &[PathBuf],
         version: &str,
     ) -> Result<Vec<GeneratedExample>, GitHubIngestError> {
         let mut examples = Vec::new();
-        let re = Regex::new(r"(?s)```rust\s*\n(.*?)\n```")?;
+        let re = Regex::new(r"(?s)```(\w+)\s*\n(.*?)\n```")?;
 
         for file_path in files {
             let content = fs::read_to_string(file_path)?;
@@ -423,7 +444,13 @@ impl Extractor {
                 .to_string();
 
             for (i, cap) in re.captures_iter(&content).enumerate() {
-                if let Some(code_match) = cap.get(1) {
+                let Some(language) = cap
+                    .get(1)
+                    .and_then(|m| Language::from_fence_tag(m.as_str()))
+                else {
+                    continue;
+                };
+                if let Some(code_match) = cap.get(2) {
                     let code_block = code_match.as_str().trim().to_string();
                     if code_block.is_empty() {
                         continue;
@@ -440,6 +467,7 @@ impl Extractor {
                         content: code_block,
                         source_file: relative_path.clone(),
                         source_type: ExampleSourceType::Readme,
+                        language: language.to_string(),
                         version: version.to_string(),
                     });
                 }
@@ -525,6 +553,7 @@ impl Extractor {
                         content: format!("```{}\n{}```", language, code_block),
                         source_file: relative_path.clone(),
                         source_type: ExampleSourceType::TextFile,
+                        language: language_of_fence(&language),
                         version: version.to_string(),
                     });
                 }
@@ -534,6 +563,7 @@ impl Extractor {
     }
 
     /// Parses files from `/examples` directories, treating each file as a single example.
+    /// `include_bytes!` is only followed in Rust files.
     fn parse_example_files(
         repo_path: &Path,
         files: &[PathBuf],
@@ -553,15 +583,17 @@ impl Extractor {
                 continue;
             }
 
+            let language = Language::from_path(file_path);
             examples.push(GeneratedExample {
                 example_handle: format!("{}:{}", ExampleSourceType::ExampleFile, relative_path),
                 content: content.clone(),
                 source_file: relative_path,
                 source_type: ExampleSourceType::ExampleFile,
+                language: language_of_path(file_path),
                 version: version.to_string(),
             });
 
-            if extract_included_files {
+            if extract_included_files && language == Some(Language::Rust) {
                 Self::add_included_bytes_examples(
                     repo_path,
                     file_path,
@@ -574,7 +606,14 @@ impl Extractor {
         Ok(examples)
     }
 
-    /// Parses Rust source files for doc comments containing Rust code blocks.
+    /// Parses source files for documentation examples. Python, TypeScript, and Go files
+    /// follow the rules in [`languages`], and Rust files are read as described below.
+    ///
+    /// Rust files are parsed for rustdoc, and the Rust code blocks it contains are extracted.
+    /// Doc comments (`///`, `//!`) and doc attributes (`#[doc = "..."]`, `#![doc = "..."]`)
+    /// are read alike. As in rustdoc, untagged fences and fences tagged only with
+    /// attributes such as `no_run` are Rust, and hidden `# ` lines are kept so each
+    /// example is complete.
     fn parse_doc_comments(
         repo_path: &Path,
         files: &[PathBuf],
@@ -582,8 +621,7 @@ impl Extractor {
         extract_included_files: bool,
     ) -> Result<Vec<GeneratedExample>, GitHubIngestError> {
         let mut examples = Vec::new();
-        let doc_block_re = Regex::new(r"(?m)((?:^\s*(?:///|//!)[^\n]*\n?)+)")?;
-        let code_block_re = Regex::new(r"(?s)```rust\s*\n(.*?)\n```")?;
+        let doc_attribute_re = Regex::new(DOC_ATTRIBUTE_PATTERN)?;
 
         for file_path in files {
             let content = fs::read_to_string(file_path)?;
@@ -593,56 +631,60 @@ impl Extractor {
                 .to_string_lossy()
                 .to_string();
 
-            for doc_cap in doc_block_re.captures_iter(&content) {
-                if let Some(doc_comment_block) = doc_cap.get(1) {
-                    let markdown_content = doc_comment_block
-                        .as_str()
-                        .lines()
-                        .map(|line| {
-                            line.trim_start()
-                                .strip_prefix("///")
-                                .or_else(|| line.trim_start().strip_prefix("//!"))
-                                .map(|s| s.trim_start())
-                                .unwrap_or(line)
-                        })
-                        .collect::<Vec<_>>()
-                        .join("\n");
-
-                    for (i, code_cap) in code_block_re.captures_iter(&markdown_content).enumerate()
-                    {
-                        if let Some(code_match) = code_cap.get(1) {
-                            let code_block = code_match.as_str().trim().to_string();
-                            if code_block.is_empty() {
-                                continue;
-                            }
-
-                            let line_number =
-                                content[..doc_comment_block.start()].lines().count() + 1;
-
-                            examples.push(GeneratedExample {
-                                example_handle: format!(
-                                    "{}:{}:{}:{}",
-                                    ExampleSourceType::DocComment,
-                                    relative_path,
-                                    line_number,
-                                    i
-                                ),
-                                content: code_block.clone(),
-                                source_file: relative_path.clone(),
-                                source_type: ExampleSourceType::DocComment,
-                                version: version.to_string(),
-                            });
-
-                            if extract_included_files {
-                                Self::add_included_bytes_examples(
-                                    repo_path,
-                                    file_path,
-                                    &code_block,
-                                    version,
-                                    &mut examples,
-                                )?;
-                            }
-                        }
+            let language = Language::from_path(file_path).unwrap_or(Language::Rust);
+            if language != Language::Rust {
+                let file_name = file_path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                for (i, (line_number, code_block)) in
+                    languages::doc_examples(language, &file_name, &content)?
+                        .into_iter()
+                        .enumerate()
+                {
+                    examples.push(GeneratedExample {
+                        example_handle: format!(
+                            "{}:{}:{}:{}",
+                            ExampleSourceType::DocComment,
+                            relative_path,
+                            line_number,
+                            i
+                        ),
+                        content: code_block,
+                        source_file: relative_path.clone(),
+                        source_type: ExampleSourceType::DocComment,
+                        language: language.to_string(),
+                        version: version.to_string(),
+                    });
+                }
+                continue;
+            }
+
+            for (line_number, markdown_content) in doc_blocks(&content, &doc_attribute_re) {
+                for (i, code_block) in rust_code_blocks(&markdown_content).into_iter().enumerate() {
+                    examples.push(GeneratedExample {
+                        example_handle: format!(
+                            "{}:{}:{}:{}",
+                            ExampleSourceType::DocComment,
+                            relative_path,
+                            line_number,
+                            i
+                        ),
+                        content: code_block.clone(),
+                        source_file: relative_path.clone(),
+                        source_type: ExampleSourceType::DocComment,
+                        language: language.to_string(),
+                        version: version.to_string(),
+                    });
+
+                    if extract_included_files {
+                        Self::add_included_bytes_examples(
+                            repo_path,
+                            file_path,
+                            &code_block,
+                            version,
+                            &mut examples,
+                        )?;
                     }
                 }
             }
@@ -721,6 +763,7 @@ impl Extractor {
                             content: code_block.clone(),
                             source_file: relative_path.clone(),
                             source_type: ExampleSourceType::Test,
+                            language: Language::Rust.to_string(),
                             version: version.to_string(),
                         });
 
@@ -804,6 +847,7 @@ impl Extractor {
                             relative_included_path
                         ),
                         content: included_content,
+                        language: language_of_path(&included_path),
                         source_file: relative_included_path,
                         source_type: ExampleSourceType::IncludedFile,
                         version: version.to_string(),
@@ -814,3 +858,114 @@ impl Extractor {
         Ok(())
     }
 }
+
+/// Groups consecutive rustdoc lines into Markdown blocks, each paired with the 1-based
+/// line number where it starts.
+fn doc_blocks(content: &str, doc_attribute_re: &Regex) -> Vec<(usize, String)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(usize, Vec<String>)> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let Some(doc_line) = doc_line(line, doc_attribute_re) else {
+            blocks.extend(
+                current
+                    .take()
+                    .map(|(start, lines)| (start, lines.join("\n"))),
+            );
+            continue;
+        };
+        current
+            .get_or_insert_with(|| (index + 1, Vec::new()))
+            .1
+            .push(doc_line);
+    }
+    blocks.extend(current.map(|(start, lines)| (start, lines.join("\n"))));
+    blocks
+}
+
+/// Returns the Markdown text of a rustdoc line, or `None` for any other line.
+fn doc_line(line: &str, doc_attribute_re: &Regex) -> Option<String> {
+    let trimmed = line.trim_start();
+    if let Some(text) = trimmed.strip_prefix("//!") {
+        return Some(strip_doc_space(text).to_string());
+    }
+    // Four or more slashes make an ordinary comment.
+    if trimmed.starts_with("////") {
+        return None;
+    }
+    if let Some(text) = trimmed.strip_prefix("///") {
+        return Some(strip_doc_space(text).to_string());
+    }
+
+    let cap = doc_attribute_re.captures(line)?;
+    let text = match (cap.get(1), cap.get(2)) {
+        (Some(escaped), _) => unescape_string_literal(escaped.as_str()),
+        (None, Some(raw)) => raw.as_str().to_string(),
+        (None, None) => return None,
+    };
+    Some(strip_doc_space(&text).to_string())
+}
+
+/// Drops the single space that conventionally follows `///` or opens a doc attribute.
+fn strip_doc_space(text: &str) -> &str {
+    text.strip_prefix(' ').unwrap_or(text)
+}
+
+/// Resolves the escapes of a (non-raw) string literal's contents.
+fn unescape_string_literal(literal: &str) -> String {
+    let mut unescaped = String::with_capacity(literal.len());
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => {}
+            Some('0') => unescaped.push('\0'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Returns the Rust code blocks of a rustdoc Markdown text.
+fn rust_code_blocks(markdown: &str) -> Vec<String> {
+    fenced_code_blocks(markdown)
+        .into_iter()
+        .filter(|(info, _)| is_rust_fence(info))
+        .map(|(_, code)| {
+            code.lines()
+                .map(unhide_line)
+                .collect::<Vec<_>>()
+                .join("\n")
+                .trim()
+                .to_string()
+        })
+        .filter(|code| !code.is_empty())
+        .collect()
+}
+
+/// Returns whether a fence's info string marks a Rust code block for rustdoc.
+fn is_rust_fence(info: &str) -> bool {
+    info.split([',', ' '])
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .all(|tag| RUSTDOC_FENCE_TAGS.contains(&tag) || tag.starts_with(RUSTDOC_EDITION_TAG_PREFIX))
+}
+
+/// Reveals a line rustdoc hides from the rendered example (`# use foo;`), and resolves
+/// the `##` escape for lines that really start with `#`.
+fn unhide_line(line: &str) -> String {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+    match trimmed {
+        "#" => String::new(),
+        _ if trimmed.starts_with("##") => format!("{indent}{}", &trimmed[1..]),
+        _ if trimmed.starts_with("# ") => format!("{indent}{}", &trimmed[2..]),
+        _ => line.to_string(),
+    }
+}
@@ -0,0 +1,361 @@
+//! # Language Rules
+//!
+//! This module detects the language of a source file or Markdown fence and holds the
+//! extraction rules for the languages besides Rust: Python docstrings (doctest
+//! sessions and fenced blocks), TypeScript JSDoc (`@example` sections and fenced
+//! blocks), and Go doc comments (indented blocks) and `Example` functions. Rust's
+//! rustdoc rules live in the extractor itself.
+
+use super::types::GitHubIngestError;
+use regex::Regex;
+use std::path::Path;
+
+/// The language of files without a recognized extension.
+pub const UNKNOWN_LANGUAGE: &str = "text";
+
+/// A programming language the extractor has rules for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Python,
+    TypeScript,
+    Go,
+}
+
+impl Language {
+    /// Detects the language of a source file from its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "rs" => Some(Self::Rust),
+            "py" | "pyi" => Some(Self::Python),
+            "ts" | "tsx" | "mts" | "cts" => Some(Self::TypeScript),
+            "go" => Some(Self::Go),
+            _ => None,
+        }
+    }
+
+    /// Detects the language of a Markdown fence from the first tag of its info string.
+    pub fn from_fence_tag(info: &str) -> Option<Self> {
+        let tag = info.split([',', ' ']).next()?.trim().to_lowercase();
+        match tag.as_str() {
+            "rust" | "rs" => Some(Self::Rust),
+            "python" | "py" | "python3" => Some(Self::Python),
+            "typescript" | "ts" | "tsx" => Some(Self::TypeScript),
+            "go" | "golang" => Some(Self::Go),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Language::Rust => write!(f, "rust"),
+            Language::Python => write!(f, "python"),
+            Language::TypeScript => write!(f, "typescript"),
+            Language::Go => write!(f, "go"),
+        }
+    }
+}
+
+/// Returns the language name stored for a file: a known [`Language`], otherwise its
+/// lowercase extension (e.g. `json`), or [`UNKNOWN_LANGUAGE`].
+pub fn language_of_path(path: &Path) -> String {
+    if let Some(language) = Language::from_path(path) {
+        return language.to_string();
+    }
+    match path.extension() {
+        Some(extension) => match extension.to_string_lossy().to_lowercase().as_str() {
+            "yml" => "yaml".to_string(),
+            other => other.to_string(),
+        },
+        None => UNKNOWN_LANGUAGE.to_string(),
+    }
+}
+
+/// Returns the language name stored for a fenced block: a known [`Language`], or the
+/// lowercase tag itself (e.g. `sql`).
+pub fn language_of_fence(info: &str) -> String {
+    match Language::from_fence_tag(info) {
+        Some(language) => language.to_string(),
+        None => info.trim().to_lowercase(),
+    }
+}
+
+/// A fenced code block that has been opened but not yet closed.
+struct OpenFence {
+    marker: char,
+    info: String,
+    lines: Vec<String>,
+}
+
+/// Returns the info string and code of every fenced block (```` ``` ```` or `~~~`) in a
+/// Markdown text.
+pub(crate) fn fenced_code_blocks(markdown: &str) -> Vec<(String, String)> {
+    let mut blocks = Vec::new();
+    let mut fence: Option<OpenFence> = None;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if fence.is_none() {
+            fence = fence_marker(trimmed).map(|marker| OpenFence {
+                marker,
+                info: trimmed.trim_start_matches(marker).trim().to_string(),
+                lines: Vec::new(),
+            });
+            continue;
+        }
+        let Some(open) = fence.as_mut() else {
+            continue;
+        };
+
+        let closes = fence_marker(trimmed) == Some(open.marker)
+            && trimmed.trim_start_matches(open.marker).trim().is_empty();
+        if !closes {
+            open.lines.push(line.to_string());
+            continue;
+        }
+        blocks.push((std::mem::take(&mut open.info), open.lines.join("\n")));
+        fence = None;
+    }
+    blocks
+}
+
+/// Returns the character of the fence a line opens or closes, if any.
+fn fence_marker(trimmed: &str) -> Option<char> {
+    match trimmed {
+        _ if trimmed.starts_with("```") => Some('`'),
+        _ if trimmed.starts_with("~~~") => Some('~'),
+        _ => None,
+    }
+}
+
+/// Extracts the examples of a Python, TypeScript, or Go source file, each paired with
+/// the 1-based line number of the docstring, comment, or function it comes from.
+/// Rust files yield nothing here.
+pub fn doc_examples(
+    language: Language,
+    file_name: &str,
+    content: &str,
+) -> Result<Vec<(usize, String)>, GitHubIngestError> {
+    match language {
+        Language::Rust => Ok(Vec::new()),
+        Language::Python => python_docstring_examples(content),
+        Language::TypeScript => jsdoc_examples(content),
+        Language::Go => go_doc_examples(file_name, content),
+    }
+}
+
+fn line_number_at(content: &str, offset: usize) -> usize {
+    content[..offset].lines().count() + 1
+}
+
+/// Extracts doctest sessions (`>>>` and `...` lines) and `python` fences from docstrings.
+fn python_docstring_examples(content: &str) -> Result<Vec<(usize, String)>, GitHubIngestError> {
+    let docstring_re = Regex::new(r#"(?s)"""(.*?)"""|'''(.*?)'''"#)?;
+    let mut examples = Vec::new();
+
+    for cap in docstring_re.captures_iter(content) {
+        let Some(docstring) = cap.get(1).or_else(|| cap.get(2)) else {
+            continue;
+        };
+        let line_number = line_number_at(content, docstring.start());
+        let text = docstring.as_str();
+
+        for session in doctest_sessions(text) {
+            examples.push((line_number, session));
+        }
+        for (info, code) in fenced_code_blocks(text) {
+            let code = dedent(&code);
+            if Language::from_fence_tag(&info) == Some(Language::Python) && !code.is_empty() {
+                examples.push((line_number, code));
+            }
+        }
+    }
+    Ok(examples)
+}
+
+/// Returns the source of each doctest session, dropping prompts and expected output.
+fn doctest_sessions(docstring: &str) -> Vec<String> {
+    let mut sessions = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+
+    for line in docstring.lines() {
+        let trimmed = line.trim_start();
+        let source = match trimmed {
+            ">>>" | "..." => Some(""),
+            _ => trimmed
+                .strip_prefix(">>> ")
+                .or_else(|| trimmed.strip_prefix("... ")),
+        };
+        match (source, trimmed.is_empty()) {
+            (Some(source), _) => current.push(source),
+            // A blank line ends the session; expected output is skipped.
+            (None, true) if !current.is_empty() => {
+                sessions.push(current.join("\n").trim().to_string());
+                current.clear();
+            }
+            (None, _) => {}
+        }
+    }
+    if !current.is_empty() {
+        sessions.push(current.join("\n").trim().to_string());
+    }
+    sessions.retain(|session| !session.is_empty());
+    sessions
+}
+
+/// Extracts `@example` sections and TypeScript or JavaScript fences from JSDoc blocks.
+fn jsdoc_examples(content: &str) -> Result<Vec<(usize, String)>, GitHubIngestError> {
+    let jsdoc_re = Regex::new(r"(?s)/\*\*(.*?)\*/")?;
+    let mut examples = Vec::new();
+
+    for cap in jsdoc_re.captures_iter(content) {
+        let Some(block) = cap.get(1) else {
+            continue;
+        };
+        let line_number = line_number_at(content, block.start());
+        let text = block
+            .as_str()
+            .lines()
+            .map(|line| {
+                let line = line.trim_start();
+                let line = line.strip_prefix('*').unwrap_or(line);
+                line.strip_prefix(' ').unwrap_or(line)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        for (tag, body) in jsdoc_sections(&text) {
+            let fences = fenced_code_blocks(&body);
+            let is_example = tag == JSDOC_EXAMPLE_TAG;
+            // An `@example` without fences is code in its entirety.
+            if is_example && fences.is_empty() {
+                let code = dedent(&body);
+                if !code.is_empty() {
+                    examples.push((line_number, code));
+                }
+                continue;
+            }
+            for (info, code) in fences {
+                let code = dedent(&code);
+                let is_script = JS_FENCE_TAGS.contains(&info.to_lowercase().as_str())
+                    || Language::from_fence_tag(&info) == Some(Language::TypeScript);
+                if (is_script || (is_example && info.is_empty())) && !code.is_empty() {
+                    examples.push((line_number, code));
+                }
+            }
+        }
+    }
+    Ok(examples)
+}
+
+const JSDOC_EXAMPLE_TAG: &str = "@example";
+/// Fence tags of JavaScript code, which TypeScript documentation often uses.
+const JS_FENCE_TAGS: [&str; 4] = ["javascript", "js", "jsx", "mjs"];
+
+/// Splits a JSDoc text into its description (with an empty tag) and block tags, each
+/// running until the next line that starts with `@`.
+fn jsdoc_sections(text: &str) -> Vec<(String, String)> {
+    let mut sections = vec![(String::new(), String::new())];
+    for line in text.lines() {
+        if let Some(tagged) = line.trim_start().strip_prefix('@') {
+            let (tag, rest) = tagged
+                .split_once(char::is_whitespace)
+                .unwrap_or((tagged, ""));
+            sections.push((format!("@{tag}"), format!("{rest}\n")));
+            continue;
+        }
+        if let Some((_, body)) = sections.last_mut() {
+            body.push_str(line);
+            body.push('\n');
+        }
+    }
+    sections
+}
+
+/// Extracts indented code blocks from `//` doc comments and the bodies of `Example`
+/// functions in `_test.go` files, which `go doc` shows as examples.
+fn go_doc_examples(
+    file_name: &str,
+    content: &str,
+) -> Result<Vec<(usize, String)>, GitHubIngestError> {
+    let mut examples = Vec::new();
+
+    if file_name.ends_with(GO_TEST_FILE_SUFFIX) {
+        let example_fn_re = Regex::new(r"(?ms)^func Example\w*\(\)\s*\{\n(.*?)^\}")?;
+        for cap in example_fn_re.captures_iter(content) {
+            let Some(body) = cap.get(1) else {
+                continue;
+            };
+            let code = dedent(body.as_str());
+            if !code.is_empty() {
+                examples.push((line_number_at(content, body.start()), code));
+            }
+        }
+    }
+
+    let mut block_start = 0;
+    let mut comment: Vec<&str> = Vec::new();
+    for (index, line) in content.lines().chain(std::iter::once("")).enumerate() {
+        let Some(text) = line.trim_start().strip_prefix("//") else {
+            examples.extend(
+                indented_blocks(&comment)
+                    .into_iter()
+                    .map(|code| (block_start, code)),
+            );
+            comment.clear();
+            continue;
+        };
+        if comment.is_empty() {
+            block_start = index + 1;
+        }
+        comment.push(text.strip_prefix(' ').unwrap_or(text));
+    }
+    Ok(examples)
+}
+
+const GO_TEST_FILE_SUFFIX: &str = "_test.go";
+
+/// Returns the runs of indented lines in a Go doc comment, which gofmt renders as code.
+fn indented_blocks(comment: &[&str]) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for line in comment {
+        let indented = line.starts_with('\t') || line.starts_with(' ');
+        match (indented, line.trim().is_empty()) {
+            (true, false) => current.push(line),
+            // Blank lines may separate the paragraphs of one block.
+            (_, true) if !current.is_empty() => current.push(line),
+            _ => {
+                let code = dedent(&current.join("\n"));
+                if !code.is_empty() {
+                    blocks.push(code);
+                }
+                current.clear();
+            }
+        }
+    }
+    let code = dedent(&current.join("\n"));
+    if !code.is_empty() {
+        blocks.push(code);
+    }
+    blocks
+}
+
+/// Removes the indentation shared by all non-blank lines, and surrounding blank lines.
+fn dedent(code: &str) -> String {
+    let indent = code
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    code.lines()
+        .map(|line| line.get(indent..).unwrap_or("").trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_string()
+}
@@ -1,24 +1,29 @@
 //! # GitHub Repository Ingestion
 //!
 //! This module contains the complete pipeline for crawling a GitHub repository,
-//! extracting versioned code examples, and storing them in a structured format
-//! for Retrieval-Augmented Generation (RAG).
+//! extracting versioned code examples and documentation, and storing them in a
+//! structured format for Retrieval-Augmented Generation (RAG).
 
 pub mod crawler;
+pub mod docs;
 pub mod extractor;
+pub mod languages;
+pub mod remote;
 pub mod search_logic;
 pub mod storage;
 pub mod types;
 
 use self::{
     crawler::Crawler,
+    docs::DocExtractor,
     extractor::Extractor,
     search_logic::search_across_repos,
     storage::StorageManager,
-    types::{GitHubIngestError, IngestionTask},
+    types::{ExampleSearchResult, GitHubIngestError, IngestionTask, TrackedRepository},
 };
-use anyrag::{providers::ai::AiProvider, SearchResult};
+use anyrag::providers::ai::AiProvider;
 use glob::Pattern;
+use std::path::Path;
 use std::sync::Arc;
 use tracing::{info, instrument};
 
@@ -31,11 +36,15 @@ use tracing::{info, instrument};
 /// 4. Extracts all code examples from the cloned repository.
 /// 5. Stores the extracted examples in the database.
 ///
+/// With `DumpType::Docs`, steps 4 and 5 store the repository's documentation
+/// sections and their links to code examples instead, and no embeddings are made.
+///
 /// # Arguments
 /// * `task`: The `IngestionTask` specifying the repository URL and version.
 ///
 /// # Returns
-/// A tuple containing the number of examples ingested and the actual version string used.
+/// A tuple containing the number of examples (or documentation sections) ingested and
+/// the actual version string used.
 #[instrument(skip(storage_manager, task), fields(url = %task.url, version = ?task.version))]
 pub async fn run_github_ingestion(
     storage_manager: &StorageManager,
@@ -90,6 +99,22 @@ pub async fn run_github_ingestion(
             info!("Src dump type selected - skipping example extraction.");
             vec![]
         }
+        types::DumpType::Docs => {
+            let count = store_docs(
+                storage_manager,
+                &tracked_repo,
+                &task,
+                &crawl_result.path,
+                &crawl_result.version,
+                &compiled_excludes,
+            )
+            .await?;
+            info!(
+                "GitHub ingestion pipeline finished successfully. Ingested {} documentation sections.",
+                count
+            );
+            return Ok((count, crawl_result.version));
+        }
     };
 
     // 5. Store
@@ -120,19 +145,48 @@ pub async fn run_github_ingestion(
     Ok((count, crawl_result.version))
 }
 
-/// Searches for examples across multiple repositories.
+/// Extracts and stores the documentation sections of a cloned repository.
+///
+/// The code examples are extracted from the same checkout only to link sections to
+/// their handles; they are stored by an `Examples` dump of the same version.
+async fn store_docs(
+    storage_manager: &StorageManager,
+    tracked_repo: &TrackedRepository,
+    task: &IngestionTask,
+    repo_path: &Path,
+    version: &str,
+    excludes: &[Pattern],
+) -> Result<usize, GitHubIngestError> {
+    let sections = DocExtractor::extract(repo_path, version, &task.includes, excludes)?;
+    let examples = Extractor::extract(
+        repo_path,
+        version,
+        task.extract_included_files,
+        &task.includes,
+        excludes,
+    )?;
+    let links = DocExtractor::link_examples(&sections, &examples)?;
+    storage_manager
+        .store_doc_sections(tracked_repo, sections, links)
+        .await
+}
+
+/// Searches for examples across multiple repositories, optionally limited to some
+/// languages (e.g. `["python"]`). An empty `languages` slice searches all of them.
 pub async fn search_examples(
     storage_manager: &StorageManager,
     query: &str,
     repos: &[String],
+    languages: &[String],
     ai_provider: Arc<dyn AiProvider>,
     embedding_api_url: &str,
     embedding_model: &str,
     embedding_api_key: Option<&str>,
-) -> Result<Vec<SearchResult>, GitHubIngestError> {
+) -> Result<Vec<ExampleSearchResult>, GitHubIngestError> {
     search_across_repos(
         query,
         repos,
+        languages,
         storage_manager,
         ai_provider,
         embedding_api_url,
@@ -0,0 +1,177 @@
+//! # Git Remote URLs
+//!
+//! This module parses the repository URLs accepted by the ingestion pipeline. Besides
+//! GitHub, it understands GitLab (including nested subgroups and self-hosted
+//! instances), Bitbucket, and any other git remote reachable over HTTP(S) or SSH.
+//! Web URLs that point inside a repository (e.g., `.../tree/main/src`) are reduced to
+//! the repository itself, and credentials embedded in the URL are dropped.
+
+/// The hosting service of a git remote, which decides how its URLs are read and
+/// which user name goes with an access token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitHost {
+    GitHub,
+    GitLab,
+    Bitbucket,
+    /// Any other git server. The whole URL path is treated as the repository path.
+    Other,
+}
+
+impl GitHost {
+    fn from_host(host: &str) -> Self {
+        match hostname(host).as_str() {
+            GITHUB_HOST => Self::GitHub,
+            name if name.starts_with("github.") => Self::GitHub,
+            name if name.contains("gitlab") => Self::GitLab,
+            name if name.contains("bitbucket") => Self::Bitbucket,
+            _ => Self::Other,
+        }
+    }
+
+    /// The user name the service expects alongside an access token over HTTP(S).
+    pub fn token_username(&self) -> &'static str {
+        match self {
+            Self::GitHub => "x-access-token",
+            Self::GitLab => "oauth2",
+            Self::Bitbucket => "x-token-auth",
+            Self::Other => "git",
+        }
+    }
+}
+
+const GITHUB_HOST: &str = "github.com";
+const GITLAB_HOST: &str = "gitlab.com";
+const BITBUCKET_HOST: &str = "bitbucket.org";
+const GIT_SUFFIX: &str = ".git";
+/// GitLab separates the project path from web routes (e.g., `/-/tree/main`) with this segment.
+const GITLAB_ROUTE_SEPARATOR: &str = "-";
+
+/// How the remote is reached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Transport {
+    /// `http://` or `https://`, holding the scheme.
+    Http(String),
+    /// `ssh://` or the scp-like `user@host:path` form, holding the original URL.
+    Ssh(String),
+}
+
+/// A parsed git remote URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoUrl {
+    /// The hosting service.
+    pub host_kind: GitHost,
+    /// The host name, with its port if the URL had one.
+    pub host: String,
+    /// The repository path segments, e.g. `["group", "subgroup", "project"]`.
+    pub path: Vec<String>,
+    transport: Transport,
+}
+
+impl RepoUrl {
+    /// Parses an HTTP(S) or SSH git remote URL.
+    ///
+    /// Returns `None` for anything else, such as local paths and `file://` URLs, which
+    /// the crawler clones as given.
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = url.trim().trim_end_matches('/');
+        let (transport, host, raw_path) = match url.split_once("://") {
+            Some((scheme, rest)) => {
+                let (authority, path) = rest.split_once('/')?;
+                // Credentials in the authority (`user:token@host`) are never kept.
+                let host = authority.rsplit('@').next().unwrap_or(authority);
+                let transport = match scheme.to_lowercase().as_str() {
+                    "http" | "https" => Transport::Http(scheme.to_lowercase()),
+                    "ssh" | "git+ssh" => Transport::Ssh(url.to_string()),
+                    _ => return None,
+                };
+                (transport, host.to_string(), path)
+            }
+            None => {
+                // The scp-like SSH form: `git@host:owner/repo.git`.
+                let (user_host, path) = url.split_once(':')?;
+                let (_, host) = user_host.split_once('@')?;
+                if host.is_empty() || user_host.contains('/') {
+                    return None;
+                }
+                (Transport::Ssh(url.to_string()), host.to_string(), path)
+            }
+        };
+
+        let host_kind = GitHost::from_host(&host);
+        let segments: Vec<String> = raw_path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_string)
+            .collect();
+        let path = Self::repository_path(host_kind, segments)?;
+
+        Some(Self {
+            host_kind,
+            host: host.to_lowercase(),
+            path,
+            transport,
+        })
+    }
+
+    /// Keeps the segments that name the repository, dropping web routes after them.
+    fn repository_path(host_kind: GitHost, segments: Vec<String>) -> Option<Vec<String>> {
+        let mut path: Vec<String> = match host_kind {
+            // `owner/repo/tree/main/...` and `workspace/repo/src/main/...`.
+            GitHost::GitHub | GitHost::Bitbucket => segments.into_iter().take(2).collect(),
+            // `group/subgroup/project/-/tree/main/...`.
+            GitHost::GitLab => segments
+                .into_iter()
+                .take_while(|segment| segment != GITLAB_ROUTE_SEPARATOR)
+                .collect(),
+            GitHost::Other => return (!segments.is_empty()).then_some(segments),
+        };
+        if path.len() < 2 {
+            return None;
+        }
+        if let Some(last) = path.last_mut() {
+            if let Some(stripped) = last.strip_suffix(GIT_SUFFIX) {
+                *last = stripped.to_string();
+            }
+        }
+        Some(path)
+    }
+
+    /// Returns whether the remote is reached over HTTP(S), where access tokens apply.
+    pub fn is_http(&self) -> bool {
+        matches!(self.transport, Transport::Http(_))
+    }
+
+    /// The URL to clone: the repository root for HTTP(S) remotes, or the original SSH URL.
+    pub fn clone_url(&self) -> String {
+        match &self.transport {
+            Transport::Http(scheme) => format!("{scheme}://{}/{}", self.host, self.path.join("/")),
+            Transport::Ssh(original) => original.clone(),
+        }
+    }
+
+    /// A filesystem-friendly name that is unique across hosts.
+    ///
+    /// Repositories on github.com keep the historical `owner-repo` form. Other hosts
+    /// are prefixed, e.g. `gitlab-group-subgroup-project`, `bitbucket-team-repo`, or
+    /// `git-example-com-team-repo` for a self-hosted server.
+    pub fn repo_name(&self) -> String {
+        let mut path = self.path.clone();
+        if let Some(last) = path.last_mut() {
+            if let Some(stripped) = last.strip_suffix(GIT_SUFFIX) {
+                *last = stripped.to_string();
+            }
+        }
+        let prefix = match hostname(&self.host).as_str() {
+            GITHUB_HOST => None,
+            GITLAB_HOST => Some("gitlab".to_string()),
+            BITBUCKET_HOST => Some("bitbucket".to_string()),
+            _ => Some(self.host.replace(['.', ':'], "-")),
+        };
+        prefix.into_iter().chain(path).collect::<Vec<_>>().join("-")
+    }
+}
+
+/// Returns the lowercase host name without its port.
+fn hostname(host: &str) -> String {
+    host.split(':').next().unwrap_or(host).to_lowercase()
+}
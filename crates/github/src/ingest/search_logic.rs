@@ -4,7 +4,11 @@
 //! multiple, isolated repository-specific databases, implementing the logic
 //! for the RAG query engine.
 
-use super::{storage::StorageManager, types::GitHubIngestError};
+use super::{
+    languages::language_of_fence,
+    storage::StorageManager,
+    types::{ExampleSearchResult, GitHubIngestError},
+};
 use anyrag::{
     ingest::knowledge::clean_llm_response,
     prompts::knowledge::{
@@ -31,12 +35,34 @@ fn parse_repo_spec(repo_spec: &str) -> (String, Option<String>) {
     (repo_spec.to_string(), None)
 }
 
+/// Adds a `column IN (...)` condition limiting the search to the given languages.
+/// Nothing is added when no language is given.
+fn push_language_filter(
+    column: &str,
+    languages: &[String],
+    conditions: &mut Vec<String>,
+    params: &mut Vec<TursoValue>,
+) {
+    if languages.is_empty() {
+        return;
+    }
+    let placeholders = languages.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+    conditions.push(format!("{column} IN ({placeholders})"));
+    // Accepts fence-style aliases such as `py` or `ts`.
+    params.extend(
+        languages
+            .iter()
+            .map(|language| TursoValue::from(language_of_fence(language))),
+    );
+}
+
 /// Performs a metadata pre-filtering search using entities.
 async fn metadata_search_for_repo(
     storage_manager: &StorageManager,
     repo_name: &str,
     version: &str,
     entities: &[String],
+    languages: &[String],
     limit: u32,
 ) -> Result<Vec<i64>, GitHubIngestError> {
     if entities.is_empty() {
@@ -47,6 +73,8 @@ async fn metadata_search_for_repo(
     let conn = provider.db.connect()?;
 
     let mut params: Vec<TursoValue> = vec![version.to_string().into()];
+    let mut filters = vec!["version = ?".to_string()];
+    push_language_filter("language", languages, &mut filters, &mut params);
     let mut conditions = Vec::new();
 
     for entity in entities {
@@ -59,7 +87,8 @@ async fn metadata_search_for_repo(
     let where_clause = conditions.join(" OR ");
 
     let sql = format!(
-        "SELECT id FROM generated_examples WHERE version = ? AND ({where_clause}) LIMIT {limit}"
+        "SELECT id FROM generated_examples WHERE {} AND ({where_clause}) LIMIT {limit}",
+        filters.join(" AND ")
     );
 
     let mut rows = conn.query(&sql, params).await?;
@@ -76,6 +105,7 @@ async fn keyword_search_for_repo(
     repo_name: &str,
     version: &str,
     query: &str,
+    languages: &[String],
     limit: u32,
     candidate_ids: &[i64],
 ) -> Result<Vec<SearchResult>, GitHubIngestError> {
@@ -92,6 +122,7 @@ async fn keyword_search_for_repo(
         pattern.clone().into(),
         pattern.into(),
     ];
+    push_language_filter("language", languages, &mut conditions, &mut query_params);
 
     if !candidate_ids.is_empty() {
         let placeholders = candidate_ids
@@ -135,6 +166,7 @@ async fn vector_search_for_repo(
     repo_name: &str,
     version: &str,
     query_vector: &[f32],
+    languages: &[String],
     limit: u32,
     candidate_ids: &[i64],
 ) -> Result<Vec<SearchResult>, GitHubIngestError> {
@@ -157,6 +189,7 @@ async fn vector_search_for_repo(
         "ee.embedding IS NOT NULL".to_string(),
     ];
     let mut query_params: Vec<TursoValue> = vec![version.to_string().into()];
+    push_language_filter("ge.language", languages, &mut conditions, &mut query_params);
 
     if !candidate_ids.is_empty() {
         let placeholders = candidate_ids
@@ -199,6 +232,90 @@ async fn vector_search_for_repo(
     Ok(results)
 }
 
+/// The shortest query word used to rank repositories; shorter words match too much.
+const MIN_RANKING_TERM_LENGTH: usize = 3;
+
+/// Counts the examples of the latest version of a repository that contain any of
+/// the terms.
+async fn keyword_hits_for_repo(
+    storage_manager: &StorageManager,
+    repo_name: &str,
+    terms: &[String],
+) -> Result<u64, GitHubIngestError> {
+    let Some(version) = storage_manager.get_latest_version(repo_name).await? else {
+        return Ok(0);
+    };
+    let provider = storage_manager.get_provider_for_repo(repo_name).await?;
+    let conn = provider.db.connect()?;
+
+    let mut params: Vec<TursoValue> = vec![version.into()];
+    let conditions = terms
+        .iter()
+        .map(|term| {
+            params.push(format!("%{term}%").into());
+            "content LIKE ?"
+        })
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    let sql =
+        format!("SELECT COUNT(*) FROM generated_examples WHERE version = ? AND ({conditions})");
+
+    let mut rows = conn.query(&sql, params).await?;
+    match rows.next().await? {
+        Some(row) => Ok(row.get::<i64>(0)?.max(0) as u64),
+        None => Ok(0),
+    }
+}
+
+/// Ranks repositories by how many of their examples contain the words of a query and
+/// keeps the `limit` best ones, as a cheap pre-filter before searching them.
+///
+/// Repositories without any match are dropped, unless none matches, in which case
+/// the first `limit` repositories are kept in their given order.
+pub async fn rank_repos_by_keywords(
+    storage_manager: &StorageManager,
+    repos: &[String],
+    query: &str,
+    limit: usize,
+) -> Result<Vec<String>, GitHubIngestError> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| word.chars().count() >= MIN_RANKING_TERM_LENGTH)
+        .map(str::to_lowercase)
+        .collect();
+    if terms.is_empty() {
+        return Ok(repos.iter().take(limit).cloned().collect());
+    }
+
+    let counts = join_all(
+        repos
+            .iter()
+            .map(|repo_name| keyword_hits_for_repo(storage_manager, repo_name, &terms)),
+    )
+    .await;
+    let mut ranked: Vec<(u64, &String)> = Vec::new();
+    for (repo_name, count) in repos.iter().zip(counts) {
+        match count {
+            Ok(0) => {}
+            Ok(hits) => ranked.push((hits, repo_name)),
+            Err(e) => warn!("Could not rank repository '{}': {}", repo_name, e),
+        }
+    }
+    if ranked.is_empty() {
+        info!("No repository matches the query keywords; keeping the first {limit}.");
+        return Ok(repos.iter().take(limit).cloned().collect());
+    }
+
+    // A stable sort keeps the given order among repositories with as many hits.
+    ranked.sort_by(|a, b| b.0.cmp(&a.0));
+    ranked.truncate(limit);
+    debug!("Repositories ranked by keyword hits: {:?}", ranked);
+    Ok(ranked
+        .into_iter()
+        .map(|(_, repo_name)| repo_name.clone())
+        .collect())
+}
+
 #[derive(Deserialize, Debug)]
 struct AnalyzedQuery {
     #[serde(default)]
@@ -237,15 +354,19 @@ async fn analyze_query(
 }
 
 /// The main entry point for searching across multiple repositories.
+///
+/// When `languages` is not empty, only examples in those languages are returned. Each
+/// result carries the repository and version it was found in.
 pub async fn search_across_repos(
     query: &str,
     repos: &[String],
+    languages: &[String],
     storage_manager: &StorageManager,
     ai_provider: Arc<dyn AiProvider>,
     embedding_api_url: &str,
     embedding_model: &str,
     embedding_api_key: Option<&str>,
-) -> Result<Vec<SearchResult>, GitHubIngestError> {
+) -> Result<Vec<ExampleSearchResult>, GitHubIngestError> {
     info!(
         "Starting multi-repo search for query: '{}' in repos: {:?}",
         query, repos
@@ -290,8 +411,9 @@ pub async fn search_across_repos(
         let query_vector_clone = query_vector.clone();
         let storage_manager_clone = storage_manager.clone();
         let entities_clone = analyzed_query.entities.clone();
+        let languages_clone = languages.to_vec();
 
-        let handle: tokio::task::JoinHandle<Result<Vec<SearchResult>, GitHubIngestError>> =
+        let handle: tokio::task::JoinHandle<Result<Vec<ExampleSearchResult>, GitHubIngestError>> =
             tokio::spawn(async move {
                 let version = match version_opt {
                     Some(v) => v,
@@ -311,6 +433,7 @@ pub async fn search_across_repos(
                     &repo_name,
                     &version,
                     &entities_clone,
+                    &languages_clone,
                     50, // Fetch more candidates for filtering
                 )
                 .await?;
@@ -321,6 +444,7 @@ pub async fn search_across_repos(
                         &repo_name,
                         &version,
                         &query_clone,
+                        &languages_clone,
                         20,
                         &candidate_ids
                     ),
@@ -329,6 +453,7 @@ pub async fn search_across_repos(
                         &repo_name,
                         &version,
                         &query_vector_clone,
+                        &languages_clone,
                         20,
                         &candidate_ids
                     )
@@ -359,10 +484,15 @@ pub async fn search_across_repos(
                     }
                 };
 
-                Ok(reciprocal_rank_fusion(vec![
-                    vector_results,
-                    keyword_results,
-                ]))
+                let fused = reciprocal_rank_fusion(vec![vector_results, keyword_results]);
+                Ok(fused
+                    .into_iter()
+                    .map(|result| ExampleSearchResult {
+                        repository: repo_name.clone(),
+                        version: version.clone(),
+                        result,
+                    })
+                    .collect())
             });
         search_handles.push(handle);
     }
@@ -379,7 +509,7 @@ pub async fn search_across_repos(
     }
 
     // A final re-ranking across all repositories to get the best overall results
-    combined_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+    combined_results.sort_by(|a, b| b.result.score.partial_cmp(&a.result.score).unwrap());
     combined_results.truncate(20);
 
     Ok(combined_results)
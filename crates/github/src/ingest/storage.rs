@@ -3,7 +3,10 @@
 //! This module handles the creation and management of SQLite databases for storing
 //! repository metadata and extracted code examples, as outlined in `PLAN.md`.
 
-use super::types::{GeneratedExample, GitHubIngestError, TrackedRepository};
+use super::remote::RepoUrl;
+use super::types::{
+    DocExampleLink, DocSection, GeneratedExample, GitHubIngestError, TrackedRepository,
+};
 use anyrag::constants;
 use anyrag::providers::db::sqlite::SqliteProvider;
 use std::fs;
@@ -63,17 +66,22 @@ impl StorageManager {
         url: &str,
     ) -> Result<TrackedRepository, GitHubIngestError> {
         let repo_name = Self::url_to_repo_name(url);
+        // Web URLs and URLs with credentials are stored as their plain clone URL.
+        let url = RepoUrl::parse(url)
+            .map(|remote| remote.clone_url())
+            .unwrap_or_else(|| url.to_string());
         let db_filename = format!("{repo_name}.db");
         let db_path = self.db_dir.join(&db_filename);
         let db_path_str = db_path.to_str().unwrap().to_string();
 
         let conn = self.meta_db_provider.db.connect()?;
 
-        // Check if the repository is already tracked.
+        // Check if the repository is already tracked. Different URLs of the same
+        // repository (e.g., HTTPS and SSH) share its name.
         let mut rows = conn
             .query(
-                "SELECT repo_name, url, db_path FROM repositories WHERE url = ?",
-                params![url],
+                "SELECT repo_name, url, db_path FROM repositories WHERE repo_name = ?",
+                params![repo_name.clone()],
             )
             .await?;
 
@@ -82,11 +90,15 @@ impl StorageManager {
                 "Repository '{}' is already tracked. Returning existing info.",
                 url
             );
-            return Ok(TrackedRepository {
+            let tracked = TrackedRepository {
                 repo_name: row.get(0)?,
                 url: row.get(1)?,
                 db_path: row.get(2)?,
-            });
+            };
+            // Databases created by older versions may lack tables added since.
+            let repo_provider = SqliteProvider::new(&tracked.db_path).await?;
+            Self::initialize_repo_db(&repo_provider).await?;
+            return Ok(tracked);
         }
 
         info!(
@@ -106,7 +118,7 @@ impl StorageManager {
 
         Ok(TrackedRepository {
             repo_name,
-            url: url.to_string(),
+            url,
             db_path: db_path_str,
         })
     }
@@ -146,8 +158,8 @@ impl StorageManager {
 
         // 2. Insert all the new examples.
         let mut stmt = conn.prepare(
-            "INSERT INTO generated_examples (example_handle, content, source_file, source_type, version)
-             VALUES (?, ?, ?, ?, ?)"
+            "INSERT INTO generated_examples (example_handle, content, source_file, source_type, language, version)
+             VALUES (?, ?, ?, ?, ?, ?)"
         ).await?;
 
         for example in &examples {
@@ -156,6 +168,7 @@ impl StorageManager {
                 example.content.clone(),
                 example.source_file.clone(),
                 example.source_type.to_string(),
+                example.language.clone(),
                 example.version.clone()
             ])
             .await?;
@@ -170,6 +183,83 @@ impl StorageManager {
         Ok(examples.len())
     }
 
+    /// Stores the documentation sections of a version and their links to code examples,
+    /// replacing any sections previously stored for that version.
+    pub async fn store_doc_sections(
+        &self,
+        repo: &TrackedRepository,
+        sections: Vec<DocSection>,
+        links: Vec<DocExampleLink>,
+    ) -> Result<usize, GitHubIngestError> {
+        if sections.is_empty() {
+            return Ok(0);
+        }
+
+        let version = &sections[0].version;
+        info!(
+            "Storing {} documentation sections and {} example links for repo '{}', version '{}'",
+            sections.len(),
+            links.len(),
+            repo.repo_name,
+            version
+        );
+
+        let provider = SqliteProvider::new(&repo.db_path).await?;
+        let conn = provider.db.connect()?;
+        conn.execute("BEGIN TRANSACTION", ()).await?;
+
+        // 1. Delete the existing sections and links of this specific version.
+        conn.execute(
+            "DELETE FROM doc_example_links WHERE version = ?",
+            params![version.clone()],
+        )
+        .await?;
+        conn.execute(
+            "DELETE FROM doc_sections WHERE version = ?",
+            params![version.clone()],
+        )
+        .await?;
+
+        // 2. Insert the new sections and links.
+        let mut stmt = conn
+            .prepare(
+                "INSERT INTO doc_sections (section_handle, source_file, heading_path, content, version)
+             VALUES (?, ?, ?, ?, ?)",
+            )
+            .await?;
+        for section in &sections {
+            let heading_path = serde_json::to_string(&section.heading_path)
+                .map_err(|e| GitHubIngestError::Internal(e.into()))?;
+            stmt.execute(params![
+                section.section_handle.clone(),
+                section.source_file.clone(),
+                heading_path,
+                section.content.clone(),
+                section.version.clone()
+            ])
+            .await?;
+        }
+
+        let mut stmt = conn
+            .prepare(
+                "INSERT INTO doc_example_links (section_handle, example_handle, link_type, version)
+             VALUES (?, ?, ?, ?)",
+            )
+            .await?;
+        for link in &links {
+            stmt.execute(params![
+                link.section_handle.clone(),
+                link.example_handle.clone(),
+                link.link_type.to_string(),
+                version.clone()
+            ])
+            .await?;
+        }
+
+        conn.execute("COMMIT", ()).await?;
+        Ok(sections.len())
+    }
+
     /// Generates and stores embeddings for examples that don't have them yet.
     pub async fn embed_and_store_examples(
         &self,
@@ -260,14 +350,15 @@ impl StorageManager {
             )));
         };
 
-        // 2. Connect to the repo-specific DB.
+        // 2. Connect to the repo-specific DB, bringing its schema up to date.
         let provider = SqliteProvider::new(&db_path).await?;
+        Self::initialize_repo_db(&provider).await?;
         let repo_conn = provider.db.connect()?;
 
         // 3. Query for examples.
         let mut stmt = repo_conn
             .prepare(
-                "SELECT example_handle, content, source_file, source_type, version FROM generated_examples WHERE version = ?",
+                "SELECT example_handle, content, source_file, source_type, language, version FROM generated_examples WHERE version = ?",
             )
             .await?;
         let mut example_rows = stmt.query(params![version]).await?;
@@ -291,13 +382,78 @@ impl StorageManager {
                 content: row.get(1)?,
                 source_file: row.get(2)?,
                 source_type,
-                version: row.get(4)?,
+                language: row.get(4)?,
+                version: row.get(5)?,
             });
         }
 
         Ok(examples)
     }
 
+    /// Retrieves all documentation sections for a specific repository and version,
+    /// in file and document order.
+    pub async fn get_doc_sections(
+        &self,
+        repo_name: &str,
+        version: &str,
+    ) -> Result<Vec<DocSection>, GitHubIngestError> {
+        let provider = self.get_provider_for_repo(repo_name).await?;
+        let conn = provider.db.connect()?;
+        let mut rows = conn
+            .query(
+                "SELECT section_handle, source_file, heading_path, content, version FROM doc_sections
+             WHERE version = ? ORDER BY id",
+                params![version],
+            )
+            .await?;
+
+        let mut sections = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let heading_path: String = row.get(2)?;
+            sections.push(DocSection {
+                section_handle: row.get(0)?,
+                source_file: row.get(1)?,
+                heading_path: serde_json::from_str(&heading_path)
+                    .map_err(|e| GitHubIngestError::Internal(e.into()))?,
+                content: row.get(3)?,
+                version: row.get(4)?,
+            });
+        }
+        Ok(sections)
+    }
+
+    /// Retrieves the links between documentation sections and code examples for a version.
+    pub async fn get_doc_example_links(
+        &self,
+        repo_name: &str,
+        version: &str,
+    ) -> Result<Vec<DocExampleLink>, GitHubIngestError> {
+        let provider = self.get_provider_for_repo(repo_name).await?;
+        let conn = provider.db.connect()?;
+        let mut rows = conn
+            .query(
+                "SELECT section_handle, example_handle, link_type FROM doc_example_links
+             WHERE version = ? ORDER BY section_handle, example_handle",
+                params![version],
+            )
+            .await?;
+
+        let mut links = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let link_type_str: String = row.get(2)?;
+            let Ok(link_type) = link_type_str.parse() else {
+                info!("Skipping link with unknown link type: {}", link_type_str);
+                continue;
+            };
+            links.push(DocExampleLink {
+                section_handle: row.get(0)?,
+                example_handle: row.get(1)?,
+                link_type,
+            });
+        }
+        Ok(links)
+    }
+
     /// Retrieves a `SqliteProvider` for a specific repository.
     pub async fn get_provider_for_repo(
         &self,
@@ -319,7 +475,9 @@ impl StorageManager {
             )));
         };
 
+        // Databases created before a schema change are brought up to date on first use.
         let provider = SqliteProvider::new(&db_path).await?;
+        Self::initialize_repo_db(&provider).await?;
         Ok(provider)
     }
 
@@ -345,6 +503,56 @@ impl StorageManager {
         }
     }
 
+    /// Lists every tracked repository, ordered by name.
+    pub async fn list_repositories(&self) -> Result<Vec<TrackedRepository>, GitHubIngestError> {
+        let conn = self.meta_db_provider.db.connect()?;
+        let mut rows = conn
+            .query(
+                "SELECT repo_name, url, db_path FROM repositories ORDER BY repo_name",
+                (),
+            )
+            .await?;
+        let mut repos = Vec::new();
+        while let Some(row) = rows.next().await? {
+            repos.push(TrackedRepository {
+                repo_name: row.get(0)?,
+                url: row.get(1)?,
+                db_path: row.get(2)?,
+            });
+        }
+        Ok(repos)
+    }
+
+    /// Checks whether examples of a version of a repository are already stored.
+    /// Untracked repositories have no stored versions; no database is created for them.
+    pub async fn is_version_ingested(
+        &self,
+        url: &str,
+        version: &str,
+    ) -> Result<bool, GitHubIngestError> {
+        let repo_name = Self::url_to_repo_name(url);
+        let conn = self.meta_db_provider.db.connect()?;
+        let mut rows = conn
+            .query(
+                "SELECT 1 FROM repositories WHERE repo_name = ?",
+                params![repo_name.clone()],
+            )
+            .await?;
+        if rows.next().await?.is_none() {
+            return Ok(false);
+        }
+
+        let repo_provider = self.get_provider_for_repo(&repo_name).await?;
+        let conn = repo_provider.db.connect()?;
+        let mut rows = conn
+            .query(
+                "SELECT 1 FROM generated_examples WHERE version = ? LIMIT 1",
+                params![version],
+            )
+            .await?;
+        Ok(rows.next().await?.is_some())
+    }
+
     // --- Private Helper Functions ---
 
     /// Creates the `repositories` table in the main metadata database if it doesn't exist.
@@ -364,8 +572,8 @@ impl StorageManager {
         Ok(())
     }
 
-    /// Creates the necessary tables (`generated_examples`, `example_embeddings`)
-    /// in a repository-specific database.
+    /// Creates the necessary tables (`generated_examples`, `example_embeddings`,
+    /// `doc_sections`, `doc_example_links`) in a repository-specific database.
     async fn initialize_repo_db(provider: &SqliteProvider) -> Result<(), GitHubIngestError> {
         let conn = provider.db.connect()?;
         conn.execute(
@@ -375,6 +583,7 @@ impl StorageManager {
                 content TEXT NOT NULL,
                 source_file TEXT NOT NULL,
                 source_type TEXT NOT NULL,
+                language TEXT NOT NULL DEFAULT 'rust',
                 version TEXT NOT NULL,
                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP
             )",
@@ -382,6 +591,24 @@ impl StorageManager {
         )
         .await?;
 
+        // Examples stored before multi-language extraction are all Rust.
+        let mut rows = conn
+            .query("PRAGMA table_info(generated_examples)", ())
+            .await?;
+        let mut has_language = false;
+        while let Some(row) = rows.next().await? {
+            let name: String = row.get(1)?;
+            has_language |= name == "language";
+        }
+        if !has_language {
+            info!("Adding the 'language' column to the 'generated_examples' table.");
+            conn.execute(
+                "ALTER TABLE generated_examples ADD COLUMN language TEXT NOT NULL DEFAULT 'rust'",
+                (),
+            )
+            .await?;
+        }
+
         conn.execute(
             "CREATE TABLE IF NOT EXISTS example_embeddings (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -393,27 +620,49 @@ impl StorageManager {
             (),
         )
         .await?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS doc_sections (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                section_handle TEXT NOT NULL,
+                source_file TEXT NOT NULL,
+                heading_path TEXT NOT NULL,
+                content TEXT NOT NULL,
+                version TEXT NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE (section_handle, version)
+            )",
+            (),
+        )
+        .await?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS doc_example_links (
+                section_handle TEXT NOT NULL,
+                example_handle TEXT NOT NULL,
+                link_type TEXT NOT NULL,
+                version TEXT NOT NULL,
+                PRIMARY KEY (section_handle, example_handle, version)
+            )",
+            (),
+        )
+        .await?;
         Ok(())
     }
 
-    /// Sanitizes a GitHub URL or local path to create a filesystem-friendly repository name.
+    /// Sanitizes a git remote URL or local path to create a filesystem-friendly repository name.
     /// e.g., "https://github.com/tursodatabase/turso" -> "tursodatabase-turso"
+    /// e.g., "https://gitlab.com/group/subgroup/project" -> "gitlab-group-subgroup-project"
     /// e.g., "/var/folders/xyz/T/.sometempdir" -> "sometempdir"
     pub fn url_to_repo_name(url: &str) -> String {
-        let name = if url.starts_with("http") || url.starts_with("git@") {
-            // Handle URLs: take the last two path components.
-            let trimmed = url.trim_end_matches('/');
-            let no_git = trimmed.strip_suffix(".git").unwrap_or(trimmed);
-            let mut parts: Vec<&str> = no_git.split('/').rev().take(2).collect();
-            parts.reverse();
-            parts.join("-")
-        } else {
+        let name = match RepoUrl::parse(url) {
+            Some(remote) => remote.repo_name(),
             // Handle local paths: take the final component (the directory name).
             // This is more robust for temporary directories used in tests.
-            Path::new(url)
+            None => Path::new(url)
                 .file_name()
                 .map(|s| s.to_string_lossy().to_string())
-                .unwrap_or_else(|| url.to_string()) // Fallback to the whole string
+                .unwrap_or_else(|| url.to_string()), // Fallback to the whole string
         };
 
         // Final sanitization for any other invalid characters, including leading dots.
@@ -423,10 +672,8 @@ impl StorageManager {
             .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
             .collect();
 
-        info!(
-            "Sanitized URL or path '{}' to repo name '{}'",
-            url, sanitized_name
-        );
+        // The URL itself is not logged since it may embed credentials.
+        info!("Sanitized URL or path to repo name '{}'", sanitized_name);
         sanitized_name
     }
 }
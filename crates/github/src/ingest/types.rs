@@ -1,4 +1,4 @@
-use anyrag::PromptError;
+use anyrag::{PromptError, SearchResult};
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 use thiserror::Error;
@@ -85,10 +85,84 @@ pub struct GeneratedExample {
     pub source_file: String,
     /// The type of source, used for prioritization during conflict resolution.
     pub source_type: ExampleSourceType,
+    /// The language of the code (e.g. `rust`, `python`, `typescript`, `go`), used to
+    /// filter searches.
+    pub language: String,
     /// The version (Git tag, release, or hash) of the repository.
     pub version: String,
 }
 
+/// Represents one heading-level section of a Markdown documentation file.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DocSection {
+    /// A unique, deterministic handle for the section (e.g., "doc:docs/guide.md:3").
+    pub section_handle: String,
+    /// The Markdown file the section belongs to, relative to the repo root.
+    pub source_file: String,
+    /// The headings leading to the section, outermost first (e.g., `["Guide", "Setup"]`).
+    /// Text before the first heading of a file has an empty path.
+    pub heading_path: Vec<String>,
+    /// The Markdown content of the section, including its own heading line.
+    pub content: String,
+    /// The version (Git tag, release, or hash) of the repository.
+    pub version: String,
+}
+
+/// How a documentation section refers to a code example.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DocLinkType {
+    /// The section contains the example's code in one of its code blocks.
+    Embedded,
+    /// The section mentions the file the example was extracted from.
+    FileReference,
+}
+
+impl std::fmt::Display for DocLinkType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocLinkType::Embedded => write!(f, "embedded"),
+            DocLinkType::FileReference => write!(f, "file_reference"),
+        }
+    }
+}
+
+impl FromStr for DocLinkType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "embedded" => Ok(DocLinkType::Embedded),
+            "file_reference" => Ok(DocLinkType::FileReference),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Links a documentation section to a code example it references.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DocExampleLink {
+    /// The handle of the referencing `DocSection`.
+    pub section_handle: String,
+    /// The handle of the referenced `GeneratedExample`.
+    pub example_handle: String,
+    /// How the section refers to the example.
+    pub link_type: DocLinkType,
+}
+
+/// A code example found by an example search, with the repository and version it
+/// was ingested from. The fields of the underlying [`SearchResult`] are serialized
+/// alongside them.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ExampleSearchResult {
+    /// The name of the repository (e.g., "tursodatabase-turso").
+    pub repository: String,
+    /// The ingested version the example belongs to.
+    pub version: String,
+    #[serde(flatten)]
+    pub result: SearchResult,
+}
+
 /// Represents a tracked repository in the main metadata database.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TrackedRepository {
@@ -110,6 +184,8 @@ pub enum DumpType {
     Tests,
     /// Flattens all source code files into a single markdown file.
     Src,
+    /// Ingests the repository's Markdown documentation as heading-level sections.
+    Docs,
 }
 
 impl std::fmt::Display for DumpType {
@@ -118,12 +194,13 @@ impl std::fmt::Display for DumpType {
             DumpType::Examples => write!(f, "examples"),
             DumpType::Tests => write!(f, "tests"),
             DumpType::Src => write!(f, "src"),
+            DumpType::Docs => write!(f, "docs"),
         }
     }
 }
 
-/// Represents a task to ingest a specific version of a GitHub repository.
-#[derive(Debug, Clone)]
+/// Represents a task to ingest a specific version of a git repository.
+#[derive(Clone)]
 pub struct IngestionTask {
     /// The URL of the repository to clone.
     pub url: String,
@@ -146,4 +223,32 @@ pub struct IngestionTask {
     /// Optional list of glob patterns to exclude (e.g., `["*.lock", "benches/**"]`).
     /// When set, files matching these patterns are skipped during extraction.
     pub excludes: Option<Vec<String>>,
+    /// An optional access token for cloning private repositories over HTTP(S), such as
+    /// a personal access token or a GitHub App installation token. It is never logged
+    /// or stored.
+    pub auth_token: Option<String>,
+}
+
+/// Shows whether an access token is set without revealing it.
+impl std::fmt::Debug for IngestionTask {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IngestionTask")
+            .field("url", &self.url)
+            .field("version", &self.version)
+            .field("embedding_api_url", &self.embedding_api_url)
+            .field("embedding_model", &self.embedding_model)
+            .field(
+                "embedding_api_key",
+                &self.embedding_api_key.as_ref().map(|_| REDACTED),
+            )
+            .field("extract_included_files", &self.extract_included_files)
+            .field("dump_type", &self.dump_type)
+            .field("includes", &self.includes)
+            .field("excludes", &self.excludes)
+            .field("auth_token", &self.auth_token.as_ref().map(|_| REDACTED))
+            .finish()
+    }
 }
+
+/// Printed in place of secrets.
+const REDACTED: &str = "<redacted>";
@@ -0,0 +1,308 @@
+//! # GitHub GraphQL API Client
+//!
+//! A small client for reading the issues, pull requests, and discussions of a
+//! repository from the GitHub GraphQL API. Each kind is paged with `endCursor`, most
+//! recently updated first, up to a caller-supplied limit.
+
+use super::types::{GitHubIssuesError, Thread, ThreadComment, ThreadKind};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+
+/// The GraphQL endpoint of github.com. GitHub Enterprise Server uses
+/// `https://<host>/api/graphql`.
+pub const GITHUB_GRAPHQL_ENDPOINT: &str = "https://api.github.com/graphql";
+/// The number of threads requested per page.
+const PAGE_SIZE: usize = 50;
+/// The GitHub API rejects requests without a `User-Agent`.
+const USER_AGENT: &str = "anyrag-github";
+
+// Each query aliases its connection as `threads` so all kinds share one response type.
+// Up to 20 labels and the first 50 comments of each thread are fetched.
+
+const ISSUES_QUERY: &str = r#"
+query($owner: String!, $name: String!, $first: Int!, $after: String) {
+  repository(owner: $owner, name: $name) {
+    threads: issues(first: $first, after: $after, orderBy: {field: UPDATED_AT, direction: DESC}) {
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        number title body url state
+        author { login }
+        labels(first: 20) { nodes { name } }
+        comments(first: 50) { nodes { author { login } body createdAt } }
+      }
+    }
+  }
+}"#;
+
+const PULL_REQUESTS_QUERY: &str = r#"
+query($owner: String!, $name: String!, $first: Int!, $after: String) {
+  repository(owner: $owner, name: $name) {
+    threads: pullRequests(first: $first, after: $after, orderBy: {field: UPDATED_AT, direction: DESC}) {
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        number title body url state
+        author { login }
+        labels(first: 20) { nodes { name } }
+      }
+    }
+  }
+}"#;
+
+const DISCUSSIONS_QUERY: &str = r#"
+query($owner: String!, $name: String!, $first: Int!, $after: String) {
+  repository(owner: $owner, name: $name) {
+    threads: discussions(first: $first, after: $after, orderBy: {field: UPDATED_AT, direction: DESC}) {
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        number title body url closed
+        author { login }
+        category { name }
+        labels(first: 20) { nodes { name } }
+        answer { author { login } body createdAt }
+        comments(first: 50) { nodes { author { login } body createdAt } }
+      }
+    }
+  }
+}"#;
+
+#[derive(Deserialize)]
+struct GraphQlResponse {
+    #[serde(default)]
+    data: Option<RepositoryData>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct RepositoryData {
+    repository: Option<Repository>,
+}
+
+#[derive(Deserialize)]
+struct Repository {
+    threads: ThreadConnection,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThreadConnection {
+    page_info: PageInfo,
+    #[serde(default)]
+    nodes: Vec<ThreadNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Nodes<T> {
+    #[serde(default)]
+    nodes: Vec<T>,
+}
+
+impl<T> Default for Nodes<T> {
+    fn default() -> Self {
+        Self { nodes: Vec::new() }
+    }
+}
+
+#[derive(Deserialize)]
+struct Actor {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct Named {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CommentNode {
+    #[serde(default)]
+    author: Option<Actor>,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    created_at: String,
+}
+
+impl From<CommentNode> for ThreadComment {
+    fn from(node: CommentNode) -> Self {
+        Self {
+            author: node.author.map(|author| author.login),
+            body: node.body,
+            created_at: node.created_at,
+        }
+    }
+}
+
+/// The fields of an issue, pull request, or discussion node. Fields a kind does not
+/// query are left at their defaults.
+#[derive(Deserialize)]
+struct ThreadNode {
+    number: u64,
+    title: String,
+    #[serde(default)]
+    body: String,
+    url: String,
+    #[serde(default)]
+    state: Option<String>,
+    #[serde(default)]
+    closed: Option<bool>,
+    #[serde(default)]
+    author: Option<Actor>,
+    #[serde(default)]
+    category: Option<Named>,
+    #[serde(default)]
+    labels: Option<Nodes<Named>>,
+    #[serde(default)]
+    answer: Option<CommentNode>,
+    #[serde(default)]
+    comments: Option<Nodes<CommentNode>>,
+}
+
+impl ThreadNode {
+    fn into_thread(self, kind: ThreadKind) -> Thread {
+        // Discussions report `closed` instead of a state.
+        let state = match (self.state, self.closed) {
+            (Some(state), _) => state,
+            (None, Some(true)) => "CLOSED".to_string(),
+            (None, _) => "OPEN".to_string(),
+        };
+        Thread {
+            kind,
+            number: self.number,
+            title: self.title,
+            body: self.body,
+            url: self.url,
+            state,
+            author: self.author.map(|author| author.login),
+            labels: self
+                .labels
+                .unwrap_or_default()
+                .nodes
+                .into_iter()
+                .map(|label| label.name)
+                .collect(),
+            category: self.category.map(|category| category.name),
+            answer: self.answer.map(ThreadComment::from),
+            comments: self
+                .comments
+                .unwrap_or_default()
+                .nodes
+                .into_iter()
+                .map(ThreadComment::from)
+                .collect(),
+        }
+    }
+}
+
+/// A GitHub GraphQL API client authenticated with an access token.
+#[derive(Clone)]
+pub struct GitHubGraphQlClient {
+    http: Client,
+    endpoint: String,
+    token: String,
+}
+
+impl GitHubGraphQlClient {
+    /// Creates a client for github.com. The GraphQL API requires a token, such as a
+    /// personal access token or a GitHub App installation token.
+    pub fn new(token: &str) -> Self {
+        Self {
+            http: Client::new(),
+            endpoint: GITHUB_GRAPHQL_ENDPOINT.to_string(),
+            token: token.to_string(),
+        }
+    }
+
+    /// Sends requests to another GraphQL endpoint, e.g. a GitHub Enterprise Server.
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.to_string();
+        self
+    }
+
+    /// Fetches up to `limit` threads of one kind, most recently updated first.
+    pub async fn threads(
+        &self,
+        owner: &str,
+        name: &str,
+        kind: ThreadKind,
+        limit: usize,
+    ) -> Result<Vec<Thread>, GitHubIssuesError> {
+        let query = match kind {
+            ThreadKind::Issue => ISSUES_QUERY,
+            ThreadKind::PullRequest => PULL_REQUESTS_QUERY,
+            ThreadKind::Discussion => DISCUSSIONS_QUERY,
+        };
+
+        let mut threads = Vec::new();
+        let mut after: Option<String> = None;
+        while threads.len() < limit {
+            let first = PAGE_SIZE.min(limit - threads.len());
+            let body = json!({
+                "query": query,
+                "variables": { "owner": owner, "name": name, "first": first, "after": after },
+            });
+            let connection = self.post(&body).await?;
+            threads.extend(
+                connection
+                    .nodes
+                    .into_iter()
+                    .map(|node| node.into_thread(kind)),
+            );
+            after = connection.page_info.end_cursor;
+            if !connection.page_info.has_next_page || after.is_none() {
+                break;
+            }
+        }
+
+        info!(
+            "Fetched {} GitHub {kind} threads from {owner}/{name}.",
+            threads.len()
+        );
+        Ok(threads)
+    }
+
+    async fn post(&self, body: &serde_json::Value) -> Result<ThreadConnection, GitHubIssuesError> {
+        let response = self
+            .http
+            .post(&self.endpoint)
+            .bearer_auth(&self.token)
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .json(body)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            return Err(GitHubIssuesError::Api(format!(
+                "{} returned status {status}: {body}",
+                self.endpoint
+            )));
+        }
+
+        let response: GraphQlResponse = response.json().await?;
+        if !response.errors.is_empty() {
+            let messages: Vec<String> = response.errors.into_iter().map(|e| e.message).collect();
+            return Err(GitHubIssuesError::Api(messages.join("; ")));
+        }
+        response
+            .data
+            .and_then(|data| data.repository)
+            .map(|repository| repository.threads)
+            .ok_or_else(|| GitHubIssuesError::Api("Repository not found".to_string()))
+    }
+}
@@ -0,0 +1,273 @@
+//! # Issues Ingestor
+//!
+//! Stores each issue, pull request, or discussion as one document keyed by its web URL,
+//! so re-ingesting a repository updates the threads that changed. The kind, state,
+//! category, and labels of each thread are stored in `content_metadata` so results can
+//! be filtered by them.
+
+use super::client::GitHubGraphQlClient;
+use super::types::{GitHubIssuesError, Thread, ThreadComment, ThreadKind};
+use crate::ingest::remote::{GitHost, RepoUrl};
+use anyrag::ingest::{
+    content_hash, find_duplicate_document, record_revision, IngestError, IngestionResult, Ingestor,
+};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use tracing::info;
+use turso::{params, Connection, Database};
+use uuid::Uuid;
+
+/// The `content_metadata` property holding the kind of a thread (`issue`,
+/// `pull_request`, or `discussion`).
+pub const KIND_PROPERTY: &str = "kind";
+/// The `content_metadata` property holding the state of a thread.
+pub const STATE_PROPERTY: &str = "state";
+/// The `content_metadata` property holding the category of a discussion.
+pub const CATEGORY_PROPERTY: &str = "category";
+/// The `content_metadata` property holding a label of a thread, one row per label.
+pub const LABEL_PROPERTY: &str = "label";
+
+/// The number of threads of each kind fetched when a source sets no `max_items`.
+pub const DEFAULT_MAX_ITEMS: usize = 200;
+const PROPERTY_METADATA_TYPE: &str = "PROPERTY";
+
+/// Defines the structure of the JSON string passed to the `ingest` method.
+#[derive(Deserialize)]
+struct IssuesSource {
+    url: String,
+    /// The kinds of threads to ingest. All kinds when empty.
+    #[serde(default)]
+    kinds: Vec<ThreadKind>,
+    #[serde(default)]
+    max_items: Option<usize>,
+}
+
+/// A document prepared from a thread before it is stored.
+struct ThreadDocument {
+    source_url: String,
+    title: String,
+    content: String,
+    /// `(property, value)` pairs stored in `content_metadata`.
+    properties: Vec<(&'static str, String)>,
+}
+
+/// The `Ingestor` implementation for GitHub issues, pull requests, and discussions.
+pub struct GitHubIssuesIngestor {
+    db: Database,
+    client: GitHubGraphQlClient,
+}
+
+impl GitHubIssuesIngestor {
+    /// Creates a new `GitHubIssuesIngestor` that reads threads with `client`.
+    pub fn new(db: &Database, client: GitHubGraphQlClient) -> Self {
+        Self {
+            db: db.clone(),
+            client,
+        }
+    }
+}
+
+#[async_trait]
+impl Ingestor for GitHubIssuesIngestor {
+    /// Fetches the threads of a repository and stores each as a document.
+    ///
+    /// The `source` argument is expected to be a JSON string with the repository `url`
+    /// and optional `kinds` and `max_items` (per kind, most recently updated first), for
+    /// example: `{"url": "https://github.com/owner/repo", "kinds": ["issue", "discussion"]}`.
+    async fn ingest(
+        &self,
+        source: &str,
+        owner_id: Option<&str>,
+    ) -> Result<IngestionResult, IngestError> {
+        let issues_source: IssuesSource =
+            serde_json::from_str(source).map_err(GitHubIssuesError::from)?;
+        let (owner, name) = repository_of(&issues_source.url)?;
+        let kinds = match issues_source.kinds.is_empty() {
+            true => ThreadKind::ALL.to_vec(),
+            false => issues_source.kinds,
+        };
+        let max_items = issues_source.max_items.unwrap_or(DEFAULT_MAX_ITEMS);
+
+        let mut fetched = BTreeMap::new();
+        let mut documents = Vec::new();
+        for kind in kinds {
+            let threads = self.client.threads(&owner, &name, kind, max_items).await?;
+            fetched.insert(kind.to_string(), threads.len());
+            documents.extend(
+                threads
+                    .iter()
+                    .map(|thread| thread_document(&owner, &name, thread)),
+            );
+        }
+
+        let mut conn = self.db.connect().map_err(GitHubIssuesError::from)?;
+        let tx = conn.transaction().await.map_err(GitHubIssuesError::from)?;
+        let mut new_document_ids = Vec::new();
+
+        for document in documents {
+            let document_id =
+                Uuid::new_v5(&Uuid::NAMESPACE_URL, document.source_url.as_bytes()).to_string();
+            let hash = content_hash(&document.content);
+            if find_duplicate_document(&tx, owner_id, &hash)
+                .await
+                .map_err(GitHubIssuesError::from)?
+                .is_some()
+            {
+                info!("Skipping unchanged GitHub thread: {}", document.source_url);
+                continue;
+            }
+
+            // The `source_url` is the thread's web URL, so an updated thread replaces its
+            // previous version.
+            record_revision(&tx, &document.source_url, &document.content)
+                .await
+                .map_err(GitHubIssuesError::from)?;
+            let changes = tx
+                .execute(
+                    "INSERT INTO documents (id, owner_id, source_url, title, content, content_hash)
+                     VALUES (?, ?, ?, ?, ?, ?)
+                     ON CONFLICT(source_url) DO UPDATE SET
+                     title = excluded.title,
+                     content = excluded.content,
+                     content_hash = excluded.content_hash",
+                    params![
+                        document_id.clone(),
+                        owner_id,
+                        document.source_url.clone(),
+                        document.title.clone(),
+                        document.content.clone(),
+                        hash
+                    ],
+                )
+                .await
+                .map_err(GitHubIssuesError::from)?;
+            store_thread_metadata(&tx, &document_id, owner_id, &document.properties)
+                .await
+                .map_err(GitHubIssuesError::from)?;
+
+            if changes > 0 {
+                new_document_ids.push(document_id);
+            }
+        }
+
+        tx.commit().await.map_err(GitHubIssuesError::from)?;
+
+        info!(
+            "Ingested {} new or updated GitHub threads from {owner}/{name}.",
+            new_document_ids.len()
+        );
+
+        Ok(IngestionResult {
+            source: format!("https://github.com/{owner}/{name}"),
+            documents_added: new_document_ids.len(),
+            document_ids: new_document_ids,
+            metadata: Some(json!({ "fetched": fetched }).to_string()),
+        })
+    }
+}
+
+/// Reads the owner and name of a GitHub repository from its URL.
+fn repository_of(url: &str) -> Result<(String, String), GitHubIssuesError> {
+    let invalid = || GitHubIssuesError::InvalidRepository(url.to_string());
+    let remote = RepoUrl::parse(url).ok_or_else(invalid)?;
+    match (remote.host_kind, remote.path.as_slice()) {
+        (GitHost::GitHub, [owner, name]) => Ok((owner.clone(), name.clone())),
+        _ => Err(invalid()),
+    }
+}
+
+/// Builds the document of a thread: its title, fields, body, answer, and comments.
+fn thread_document(owner: &str, name: &str, thread: &Thread) -> ThreadDocument {
+    let kind_label = match thread.kind {
+        ThreadKind::Issue => "Issue",
+        ThreadKind::PullRequest => "Pull request",
+        ThreadKind::Discussion => "Discussion",
+    };
+    let title = format!("{owner}/{name}#{}: {}", thread.number, thread.title);
+
+    let mut content = format!(
+        "{title}\nType: {kind_label}\nState: {}\nAuthor: {}\nLabels: {}",
+        thread.state,
+        thread.author.as_deref().unwrap_or("Unknown"),
+        thread.labels.join(", ")
+    );
+    if let Some(category) = &thread.category {
+        content.push_str(&format!("\nCategory: {category}"));
+    }
+    let body = thread.body.trim();
+    if !body.is_empty() {
+        content.push_str(&format!("\n\n{body}"));
+    }
+    if let Some(answer) = &thread.answer {
+        content.push_str(&format!("\n\nAccepted answer:\n{}", comment_line(answer)));
+    }
+    if !thread.comments.is_empty() {
+        content.push_str("\n\nComments:");
+        for comment in &thread.comments {
+            content.push_str(&format!("\n{}", comment_line(comment)));
+        }
+    }
+
+    let mut properties = vec![
+        (KIND_PROPERTY, thread.kind.to_string()),
+        (STATE_PROPERTY, thread.state.clone()),
+    ];
+    properties.extend(
+        thread
+            .category
+            .clone()
+            .map(|category| (CATEGORY_PROPERTY, category)),
+    );
+    properties.extend(
+        thread
+            .labels
+            .iter()
+            .map(|label| (LABEL_PROPERTY, label.clone())),
+    );
+
+    ThreadDocument {
+        source_url: thread.url.clone(),
+        title,
+        content,
+        properties,
+    }
+}
+
+fn comment_line(comment: &ThreadComment) -> String {
+    let created = comment.created_at.get(..10).unwrap_or(&comment.created_at);
+    format!(
+        "[{created}] {}: {}",
+        comment.author.as_deref().unwrap_or("Unknown"),
+        comment.body.trim()
+    )
+}
+
+/// Replaces the property metadata of a thread document.
+async fn store_thread_metadata(
+    conn: &Connection,
+    document_id: &str,
+    owner_id: Option<&str>,
+    properties: &[(&'static str, String)],
+) -> Result<(), turso::Error> {
+    conn.execute(
+        "DELETE FROM content_metadata WHERE document_id = ?",
+        params![document_id],
+    )
+    .await?;
+    for (property, value) in properties {
+        conn.execute(
+            "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_subtype, metadata_value) VALUES (?, ?, ?, ?, ?)",
+            params![
+                document_id,
+                owner_id,
+                PROPERTY_METADATA_TYPE,
+                *property,
+                value.clone()
+            ],
+        )
+        .await?;
+    }
+    Ok(())
+}
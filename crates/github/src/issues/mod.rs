@@ -0,0 +1,8 @@
+//! # GitHub Issues and Discussions Ingestion
+//!
+//! This module ingests the issues, pull request descriptions, and discussions of a
+//! GitHub repository through the GraphQL API, storing each thread as a document.
+
+pub mod client;
+pub mod ingestor;
+pub mod types;
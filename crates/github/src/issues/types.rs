@@ -0,0 +1,94 @@
+//! # Issues Types
+//!
+//! The error type and the thread structures shared by the GraphQL client and the ingestor.
+
+use anyrag::ingest::IngestError;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Custom error types for the issues and discussions ingestion process.
+#[derive(Error, Debug)]
+pub enum GitHubIssuesError {
+    #[error("Database connection failed: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Failed to call the GitHub GraphQL API: {0}")]
+    Fetch(#[from] reqwest::Error),
+    #[error("GitHub GraphQL API returned an error: {0}")]
+    Api(String),
+    #[error("Source deserialization failed: {0}")]
+    SourceDeserialization(#[from] serde_json::Error),
+    #[error("Not a GitHub repository URL: {0}")]
+    InvalidRepository(String),
+}
+
+/// A helper to convert the specific `GitHubIssuesError` into the generic `anyrag::ingest::IngestError`.
+impl From<GitHubIssuesError> for IngestError {
+    fn from(err: GitHubIssuesError) -> Self {
+        match err {
+            GitHubIssuesError::Database(e) => IngestError::Database(e),
+            GitHubIssuesError::Fetch(e) => IngestError::Fetch(e.to_string()),
+            GitHubIssuesError::Api(e) => IngestError::Fetch(e),
+            GitHubIssuesError::SourceDeserialization(e) => {
+                IngestError::Parse(format!("Invalid source JSON for GitHub issues ingest: {e}"))
+            }
+            GitHubIssuesError::InvalidRepository(e) => {
+                IngestError::Parse(format!("Not a GitHub repository URL: {e}"))
+            }
+        }
+    }
+}
+
+/// The kinds of conversation threads that can be ingested from a repository.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ThreadKind {
+    Issue,
+    PullRequest,
+    Discussion,
+}
+
+impl ThreadKind {
+    /// Every kind, in the order they are fetched when a source names none.
+    pub const ALL: [ThreadKind; 3] = [Self::Issue, Self::PullRequest, Self::Discussion];
+}
+
+impl std::fmt::Display for ThreadKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThreadKind::Issue => write!(f, "issue"),
+            ThreadKind::PullRequest => write!(f, "pull_request"),
+            ThreadKind::Discussion => write!(f, "discussion"),
+        }
+    }
+}
+
+/// A comment on a thread, or the accepted answer of a discussion.
+#[derive(Debug, Clone)]
+pub struct ThreadComment {
+    pub author: Option<String>,
+    pub body: String,
+    /// When the comment was created, e.g. `2024-05-01T10:00:00Z`.
+    pub created_at: String,
+}
+
+/// An issue, pull request, or discussion with the fields stored in its document.
+#[derive(Debug, Clone)]
+pub struct Thread {
+    pub kind: ThreadKind,
+    pub number: u64,
+    pub title: String,
+    pub body: String,
+    /// The web URL of the thread, used as the document's `source_url`.
+    pub url: String,
+    /// `OPEN`, `CLOSED`, or, for pull requests, `MERGED`.
+    pub state: String,
+    pub author: Option<String>,
+    pub labels: Vec<String>,
+    /// The category of a discussion.
+    pub category: Option<String>,
+    /// The accepted answer of a discussion.
+    pub answer: Option<ThreadComment>,
+    /// The first comments of the thread. Pull request comments are not fetched.
+    pub comments: Vec<ThreadComment>,
+}
@@ -1,11 +1,12 @@
 //! # anyrag-github: GitHub Ingestion and Search Crate
 //!
 //! This crate contains all functionality related to ingesting code examples
-//! from public GitHub repositories and searching them for Retrieval-Augmented
+//! from git repositories (GitHub, GitLab, Bitbucket, or any git remote) and searching them for Retrieval-Augmented
 //! Generation (RAG).
 
 pub mod cli;
 pub mod ingest;
+pub mod issues;
 
 // Re-export the main functions for easy access from other crates.
 pub use ingest::{run_github_ingestion, search_examples, types};
@@ -39,16 +40,20 @@ struct IngestSource {
     includes: Option<Vec<String>>,
     #[serde(default)]
     excludes: Option<Vec<String>>,
+    /// Overrides the ingestor's default access token for this request.
+    #[serde(default)]
+    auth_token: Option<String>,
 }
 
 use std::sync::Arc;
 
-/// The Ingestor implementation for public GitHub repositories.
+/// The Ingestor implementation for git repositories.
 pub struct GithubIngestor {
     storage_manager: Arc<StorageManager>,
     embedding_api_url: Option<String>,
     embedding_model: Option<String>,
     embedding_api_key: Option<String>,
+    auth_token: Option<String>,
 }
 
 impl GithubIngestor {
@@ -66,8 +71,16 @@ impl GithubIngestor {
             embedding_api_url,
             embedding_model,
             embedding_api_key,
+            auth_token: None,
         }
     }
+
+    /// Sets the default access token used to clone private repositories, such as a
+    /// personal access token or a GitHub App installation token.
+    pub fn with_auth_token(mut self, auth_token: Option<String>) -> Self {
+        self.auth_token = auth_token;
+        self
+    }
 }
 
 #[async_trait]
@@ -75,7 +88,8 @@ impl Ingestor for GithubIngestor {
     /// Ingests a GitHub repository.
     ///
     /// # Arguments
-    /// * `source`: A JSON string containing the `url` and optional `version`.
+    /// * `source`: A JSON string containing the `url` and optional `version`. An optional
+    ///   `auth_token` overrides the ingestor's default token for private repositories.
     ///   Example: `{"url": "https://github.com/user/repo", "version": "v1.0.0"}`
     /// * `_owner_id`: The owner ID (not used in this implementation).
     async fn ingest(
@@ -99,6 +113,7 @@ impl Ingestor for GithubIngestor {
             dump_type: ingest_source.dump_type,
             includes: ingest_source.includes,
             excludes: ingest_source.excludes,
+            auth_token: ingest_source.auth_token.or_else(|| self.auth_token.clone()),
         };
 
         // 3. Run the ingestion pipeline.
@@ -44,10 +44,15 @@ assert_eq!(x + y, 15);
 
 ```
 
-This is not a rust block:
+A Python block is extracted too:
 ```python
 print("hello")
 ```
+
+This block has no supported language:
+```console
+$ cargo run
+```
 "#;
     create_test_file(&readme_path, readme_content);
 
@@ -55,7 +60,11 @@ print("hello")
     let examples = Extractor::extract(repo_path, "v1.0.0", false, &None, &[]).unwrap();
 
     // Assert
-    assert_eq!(examples.len(), 2, "Expected to find 2 Rust code blocks.");
+    assert_eq!(
+        examples.len(),
+        3,
+        "Expected to find 2 Rust code blocks and 1 Python code block."
+    );
 
     let expected_code_1 = r#"fn main() {
     println!("Hello, from README!");
@@ -74,6 +83,17 @@ assert_eq!(x + y, 15);"#;
         "The 'x + y' example was not found"
     );
 
+    let python_example = examples
+        .iter()
+        .find(|e| e.content == "print(\"hello\")")
+        .expect("The Python example was not found");
+    assert_eq!(python_example.language, "python");
+    assert_eq!(
+        examples.iter().filter(|e| e.language == "rust").count(),
+        2,
+        "The Rust examples should be tagged as Rust."
+    );
+
     // Verify all examples from this test are from the README.
     assert!(examples
         .iter()
@@ -234,6 +254,62 @@ assert_eq!(b, 2);"#;
     );
 }
 
+#[test]
+fn test_extract_from_doc_attributes_and_rustdoc_fences() {
+    // Arrange
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let repo_path = temp_dir.path();
+    let lib_rs_path = repo_path.join("src").join("client.rs");
+
+    let lib_rs_content = r##"
+#![doc = "Client helpers."]
+#![doc = ""]
+#![doc = "```"]
+#![doc = "let client = Client::new(\"token\");"]
+#![doc = "```"]
+
+/// Connects to the server.
+///
+/// ```no_run
+/// # use my_crate::Client;
+/// let client = Client::new("token");
+/// client.connect()?;
+/// ```
+///
+/// ```text
+/// not rust
+/// ```
+pub fn connect() {}
+
+#[doc = r#"Closes the connection."#]
+pub fn close() {}
+
+//// Not a doc comment.
+//// ```
+//// ignored();
+//// ```
+"##;
+    create_test_file(&lib_rs_path, lib_rs_content);
+
+    // Act
+    let examples = Extractor::extract(repo_path, "v1.0.0", false, &None, &[]).unwrap();
+
+    // Assert
+    let mut contents: Vec<&str> = examples.iter().map(|e| e.content.as_str()).collect();
+    contents.sort();
+    assert_eq!(
+        contents,
+        vec![
+            "let client = Client::new(\"token\");",
+            "use my_crate::Client;\nlet client = Client::new(\"token\");\nclient.connect()?;",
+        ],
+        "Expected the doc attribute and no_run examples, with hidden lines revealed."
+    );
+    assert!(examples
+        .iter()
+        .all(|e| e.source_type == ExampleSourceType::DocComment));
+}
+
 #[test]
 fn test_extract_from_test_files() {
     // Arrange
@@ -564,3 +640,88 @@ fn test_rstest_simple() {
         "rstest test should include parameters in handle"
     );
 }
+
+#[test]
+fn test_extract_python_typescript_and_go_examples() {
+    // Arrange
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let repo_path = temp_dir.path();
+
+    create_test_file(
+        &repo_path.join("pkg").join("client.py"),
+        r#"
+def connect(url):
+    """Opens a connection.
+
+    >>> conn = connect("file:local.db")
+    >>> conn.execute(
+    ...     "SELECT 1")
+    1
+
+    ```python
+    with connect("file:local.db") as conn:
+        conn.close()
+    ```
+    """
+"#,
+    );
+    create_test_file(
+        &repo_path.join("src").join("client.ts"),
+        r#"
+/**
+ * Opens a connection.
+ *
+ * @example
+ * const conn = connect("file:local.db");
+ * await conn.close();
+ * @returns The connection.
+ */
+export function connect(url: string) {}
+"#,
+    );
+    create_test_file(
+        &repo_path.join("client").join("doc.go"),
+        "// Package client opens connections.\n//\n//\tconn := client.Connect(\"file:local.db\")\n//\tdefer conn.Close()\npackage client\n",
+    );
+    create_test_file(
+        &repo_path.join("client").join("example_test.go"),
+        "package client_test\n\nfunc ExampleConnect() {\n\tconn := client.Connect(\"file:local.db\")\n\tfmt.Println(conn.Ping())\n\t// Output: true\n}\n",
+    );
+    create_test_file(
+        &repo_path.join("examples").join("quickstart.py"),
+        "print(connect(\"file:local.db\"))\n",
+    );
+
+    // Act
+    let examples = Extractor::extract(repo_path, "v1.0.0", false, &None, &[]).unwrap();
+
+    // Assert
+    let find = |content: &str| {
+        examples
+            .iter()
+            .find(|e| e.content == content)
+            .unwrap_or_else(|| panic!("Example not found: {content}"))
+    };
+
+    let doctest = find("conn = connect(\"file:local.db\")\nconn.execute(\n    \"SELECT 1\")");
+    assert_eq!(doctest.language, "python");
+    assert_eq!(doctest.source_type, ExampleSourceType::DocComment);
+    let fenced = find("with connect(\"file:local.db\") as conn:\n    conn.close()");
+    assert_eq!(fenced.language, "python");
+
+    let jsdoc = find("const conn = connect(\"file:local.db\");\nawait conn.close();");
+    assert_eq!(jsdoc.language, "typescript");
+
+    let go_doc = find("conn := client.Connect(\"file:local.db\")\ndefer conn.Close()");
+    assert_eq!(go_doc.language, "go");
+    let go_example = find(
+        "conn := client.Connect(\"file:local.db\")\nfmt.Println(conn.Ping())\n// Output: true",
+    );
+    assert_eq!(go_example.language, "go");
+
+    let example_file = find("print(connect(\"file:local.db\"))\n");
+    assert_eq!(example_file.language, "python");
+    assert_eq!(example_file.source_type, ExampleSourceType::ExampleFile);
+
+    assert_eq!(examples.len(), 6, "Unexpected examples: {examples:#?}");
+}
@@ -4,7 +4,10 @@
 //! interaction between the Extractor and the StorageManager without a real Git remote.
 
 use anyrag_github::ingest::{
-    extractor::Extractor, storage::StorageManager, types::ExampleSourceType,
+    docs::DocExtractor,
+    extractor::Extractor,
+    storage::StorageManager,
+    types::{DocLinkType, DumpType, ExampleSourceType, IngestionTask},
 };
 use std::fs;
 use std::path::Path;
@@ -110,3 +113,188 @@ async fn test_extractor_and_storage_integration() {
     assert_eq!(test_example.content.trim(), common_code);
     assert_eq!(test_example.source_file, "tests/test.rs");
 }
+
+#[tokio::test]
+async fn test_is_version_ingested_reports_stored_versions_only() {
+    // --- 1. Arrange ---
+    let repo_dir = tempdir().expect("Failed to create repo temp dir");
+    let repo_path = repo_dir.path();
+    let db_dir = tempdir().expect("Failed to create db temp dir");
+    let db_path_str = db_dir.path().to_str().unwrap();
+    create_file(
+        repo_path,
+        "examples/main.rs",
+        "fn main() { println!(\"cached\"); }",
+    );
+    let examples = Extractor::extract(repo_path, "1.2.0", false, &None, &[]).unwrap();
+    let storage = StorageManager::new(Some(db_path_str)).await.unwrap();
+    let repo_url = "https://github.com/user/cached-repo";
+
+    // --- 2. Act ---
+    let before_tracking = storage
+        .is_version_ingested(repo_url, "1.2.0")
+        .await
+        .unwrap();
+    let tracked_repo = storage.track_repository(repo_url).await.unwrap();
+    let before_storing = storage
+        .is_version_ingested(repo_url, "1.2.0")
+        .await
+        .unwrap();
+    storage
+        .store_examples(&tracked_repo, examples)
+        .await
+        .unwrap();
+
+    // --- 3. Assert ---
+    assert!(!before_tracking, "An untracked repository has no versions.");
+    assert!(
+        !before_storing,
+        "A tracked repository without examples has no versions."
+    );
+    assert!(storage
+        .is_version_ingested(repo_url, "1.2.0")
+        .await
+        .unwrap());
+    // The SSH URL of the same repository shares its stored versions.
+    assert!(storage
+        .is_version_ingested("git@github.com:user/cached-repo.git", "1.2.0")
+        .await
+        .unwrap());
+    assert!(!storage
+        .is_version_ingested(repo_url, "1.3.0")
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn test_doc_extraction_preserves_hierarchy_and_links_examples() {
+    // --- 1. Arrange ---
+    let repo_dir = tempdir().expect("Failed to create repo temp dir");
+    let repo_path = repo_dir.path();
+    let db_dir = tempdir().expect("Failed to create db temp dir");
+    let db_path_str = db_dir.path().to_str().unwrap();
+    let version = "v2.0.0";
+
+    create_file(
+        repo_path,
+        "README.md",
+        "# Demo\n\nIntro text.\n\n## Quick start\n\n```rust\nlet client = demo::Client::new();\n```\n",
+    );
+    // A Docusaurus page with front matter and a comment inside a code block.
+    create_file(
+        repo_path,
+        "docs/guide/setup.mdx",
+        "---\ntitle: Setup\n---\n# Setup\n\n## Install\n\nSee `examples/basic.rs` for a full program.\n\n```bash\n# not a heading\ncargo add demo\n```\n",
+    );
+    create_file(repo_path, "examples/basic.rs", "fn main() { demo::run(); }");
+    // An mdBook whose sources live outside any `docs/` directory.
+    create_file(
+        repo_path,
+        "book/book.toml",
+        "[book]\ntitle = \"Demo Book\"\nsrc = \"pages\"\n",
+    );
+    create_file(
+        repo_path,
+        "book/pages/intro.md",
+        "# Introduction\n\nWelcome to the book.\n",
+    );
+    // Markdown outside the documentation trees is not ingested.
+    create_file(repo_path, "notes/todo.md", "# Todo\n\nNot documentation.");
+    create_file(
+        repo_path,
+        "node_modules/pkg/README.md",
+        "# Vendored\n\nNot ours.",
+    );
+
+    // --- 2. Act ---
+    let sections = DocExtractor::extract(repo_path, version, &None, &[]).unwrap();
+    let examples = Extractor::extract(repo_path, version, false, &None, &[]).unwrap();
+    let links = DocExtractor::link_examples(&sections, &examples).unwrap();
+
+    let storage = StorageManager::new(Some(db_path_str)).await.unwrap();
+    let repo_url = "http://mock.com/user/docs-repo";
+    let repo_name = StorageManager::url_to_repo_name(repo_url);
+    let tracked_repo = storage.track_repository(repo_url).await.unwrap();
+    let stored = storage
+        .store_doc_sections(&tracked_repo, sections, links)
+        .await
+        .unwrap();
+
+    // --- 3. Assert ---
+    assert_eq!(stored, 4, "Expected 2 README, 2 guide, and 1 book section.");
+    let sections = storage.get_doc_sections(&repo_name, version).await.unwrap();
+    let section = |handle: &str| {
+        sections
+            .iter()
+            .find(|s| s.section_handle == handle)
+            .unwrap_or_else(|| panic!("Section '{handle}' not found"))
+    };
+
+    assert_eq!(section("doc:README.md:0").heading_path, vec!["Demo"]);
+    assert_eq!(
+        section("doc:README.md:1").heading_path,
+        vec!["Demo", "Quick start"]
+    );
+    let install = section("doc:docs/guide/setup.mdx:1");
+    assert_eq!(install.source_file, "docs/guide/setup.mdx");
+    assert_eq!(install.heading_path, vec!["Setup", "Install"]);
+    assert!(install.content.contains("# not a heading\ncargo add demo"));
+    assert!(!section("doc:docs/guide/setup.mdx:0")
+        .content
+        .contains("title: Setup"));
+    assert_eq!(
+        section("doc:book/pages/intro.md:0").heading_path,
+        vec!["Introduction"]
+    );
+    assert!(sections.iter().all(
+        |s| !s.source_file.starts_with("notes/") && !s.source_file.starts_with("node_modules/")
+    ));
+
+    let handle_of = |source_file: &str| {
+        examples
+            .iter()
+            .find(|ex| ex.source_file == source_file)
+            .map(|ex| ex.example_handle.clone())
+            .unwrap_or_else(|| panic!("No example from '{source_file}'"))
+    };
+    let links = storage
+        .get_doc_example_links(&repo_name, version)
+        .await
+        .unwrap();
+    assert_eq!(links.len(), 2, "Unexpected links: {links:?}");
+    let readme_link = links
+        .iter()
+        .find(|l| l.section_handle == "doc:README.md:1")
+        .expect("README section should link to its code block");
+    assert_eq!(readme_link.example_handle, handle_of("README.md"));
+    assert_eq!(readme_link.link_type, DocLinkType::Embedded);
+    let guide_link = links
+        .iter()
+        .find(|l| l.section_handle == "doc:docs/guide/setup.mdx:1")
+        .expect("Guide section should link to the referenced example file");
+    assert_eq!(guide_link.example_handle, handle_of("examples/basic.rs"));
+    assert_eq!(guide_link.link_type, DocLinkType::FileReference);
+}
+
+#[test]
+fn test_ingestion_task_debug_redacts_auth_token() {
+    let task = IngestionTask {
+        url: "https://github.com/acme/private-repo".to_string(),
+        version: None,
+        embedding_api_url: None,
+        embedding_model: None,
+        embedding_api_key: Some("embedding-secret".to_string()),
+        extract_included_files: false,
+        dump_type: DumpType::Examples,
+        includes: None,
+        excludes: None,
+        auth_token: Some("ghp_secret".to_string()),
+    };
+
+    let debug = format!("{task:?}");
+
+    assert!(debug.contains("https://github.com/acme/private-repo"));
+    assert!(!debug.contains("ghp_secret"));
+    assert!(!debug.contains("embedding-secret"));
+    assert!(debug.contains("<redacted>"));
+}
@@ -8,6 +8,7 @@ mod common;
 
 use anyrag_github::{
     ingest::{
+        search_logic::rank_repos_by_keywords,
         storage::StorageManager,
         types::{ExampleSourceType, GeneratedExample},
     },
@@ -44,6 +45,7 @@ async fn setup_database_with_mock_data() -> (StorageManager, String, TempDir) {
         content: "let db = Client::open(\"file:local.db\"); // turso client".to_string(),
         source_file: "tests/turso_test.rs".to_string(),
         source_type: ExampleSourceType::Test,
+        language: "rust".to_string(),
         version: version.to_string(),
     };
     let other_example = GeneratedExample {
@@ -51,6 +53,7 @@ async fn setup_database_with_mock_data() -> (StorageManager, String, TempDir) {
         content: "let x = 1 + 1;".to_string(),
         source_file: "tests/other_test.rs".to_string(),
         source_type: ExampleSourceType::Test,
+        language: "rust".to_string(),
         version: version.to_string(),
     };
     let examples = vec![turso_example, other_example];
@@ -95,7 +98,8 @@ async fn test_github_search_with_metadata_prefiltering() {
     let search_results = search_examples(
         &storage_manager,
         user_query,
-        &[repo_name],
+        std::slice::from_ref(&repo_name),
+        &[],
         ai_provider,
         &embedding_api_url,
         "mock-model",
@@ -114,13 +118,16 @@ async fn test_github_search_with_metadata_prefiltering() {
 
     let result = &search_results[0];
     assert!(
-        result.description.contains("turso"),
+        result.result.description.contains("turso"),
         "The returned result should be the turso example."
     );
     assert_eq!(
-        result.title, "test:tests/turso_test.rs:connect",
+        result.result.title, "test:tests/turso_test.rs:connect",
         "The wrong example was returned."
     );
+    // The result names the repository and the latest version it was found in.
+    assert_eq!(result.repository, repo_name);
+    assert_eq!(result.version, "v1.0.0");
 
     // Verify that the query analysis was called as expected.
     let history = call_history.read().unwrap();
@@ -136,3 +143,55 @@ async fn test_github_search_with_metadata_prefiltering() {
         "The user prompt for analysis did not contain the original query."
     );
 }
+
+#[tokio::test]
+async fn test_rank_repos_by_keywords_keeps_the_most_relevant_repos() {
+    // --- 1. Arrange ---
+    setup_tracing();
+    let (storage_manager, turso_repo, _db_dir) = setup_database_with_mock_data().await;
+    let other_repo = storage_manager
+        .track_repository("http://mock.com/user/other-repo")
+        .await
+        .expect("Failed to track repo");
+    let other_example = GeneratedExample {
+        example_handle: "example:examples/server.rs".to_string(),
+        content: "let server = Server::bind(addr); // http server".to_string(),
+        source_file: "examples/server.rs".to_string(),
+        source_type: ExampleSourceType::ExampleFile,
+        language: "rust".to_string(),
+        version: "v0.3.0".to_string(),
+    };
+    storage_manager
+        .store_examples(&other_repo, vec![other_example])
+        .await
+        .expect("Failed to store examples");
+
+    // --- 2. Act ---
+    let tracked: Vec<String> = storage_manager
+        .list_repositories()
+        .await
+        .expect("Failed to list repositories")
+        .into_iter()
+        .map(|repo| repo.repo_name)
+        .collect();
+    let for_turso = rank_repos_by_keywords(&storage_manager, &tracked, "open a turso client", 1)
+        .await
+        .expect("Ranking failed");
+    let for_server = rank_repos_by_keywords(&storage_manager, &tracked, "bind an HTTP server", 5)
+        .await
+        .expect("Ranking failed");
+    let unmatched = rank_repos_by_keywords(&storage_manager, &tracked, "quantum", 5)
+        .await
+        .expect("Ranking failed");
+
+    // --- 3. Assert ---
+    assert_eq!(
+        tracked,
+        vec![other_repo.repo_name.clone(), turso_repo.clone()]
+    );
+    assert_eq!(for_turso, vec![turso_repo]);
+    // Repositories without a matching example are dropped.
+    assert_eq!(for_server, vec![other_repo.repo_name]);
+    // When nothing matches, every repository is kept.
+    assert_eq!(unmatched, tracked);
+}
@@ -0,0 +1,229 @@
+//! # GitHub Issues Ingestion Tests
+//!
+//! These tests run the issues and discussions ingestor against a mock GitHub GraphQL API.
+
+use anyhow::Result;
+use anyrag::ingest::Ingestor;
+use anyrag_github::issues::client::GitHubGraphQlClient;
+use anyrag_github::issues::ingestor::{
+    GitHubIssuesIngestor, CATEGORY_PROPERTY, KIND_PROPERTY, LABEL_PROPERTY, STATE_PROPERTY,
+};
+use anyrag_test_utils::TestSetup;
+use serde_json::json;
+use turso::params;
+use wiremock::matchers::{body_string_contains, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const GRAPHQL_PATH: &str = "/graphql";
+const REPO_URL: &str = "https://github.com/acme/widgets";
+
+fn client(server: &MockServer) -> GitHubGraphQlClient {
+    GitHubGraphQlClient::new("test-token").with_endpoint(&format!("{}{GRAPHQL_PATH}", server.uri()))
+}
+
+/// Mounts two pages of issues and one page of discussions.
+async fn mount_threads(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path(GRAPHQL_PATH))
+        .and(header("authorization", "Bearer test-token"))
+        .and(body_string_contains("threads: issues("))
+        .and(body_string_contains("\"after\":null"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "repository": { "threads": {
+                "pageInfo": { "hasNextPage": true, "endCursor": "cursor-1" },
+                "nodes": [{
+                    "number": 1,
+                    "title": "Crash on startup",
+                    "body": "The app panics when the config is missing.",
+                    "url": "https://github.com/acme/widgets/issues/1",
+                    "state": "OPEN",
+                    "author": { "login": "alice" },
+                    "labels": { "nodes": [{ "name": "bug" }, { "name": "p1" }] },
+                    "comments": { "nodes": [
+                        { "author": { "login": "bob" }, "body": "Reproduced on 1.2.", "createdAt": "2024-05-01T10:00:00Z" }
+                    ] }
+                }]
+            } } }
+        })))
+        .expect(1)
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(GRAPHQL_PATH))
+        .and(body_string_contains("threads: issues("))
+        .and(body_string_contains("\"after\":\"cursor-1\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "repository": { "threads": {
+                "pageInfo": { "hasNextPage": false, "endCursor": "cursor-2" },
+                "nodes": [{
+                    "number": 2,
+                    "title": "Document the config file",
+                    "body": "",
+                    "url": "https://github.com/acme/widgets/issues/2",
+                    "state": "CLOSED",
+                    "author": null,
+                    "labels": { "nodes": [] },
+                    "comments": { "nodes": [] }
+                }]
+            } } }
+        })))
+        .expect(1)
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(GRAPHQL_PATH))
+        .and(body_string_contains("threads: discussions("))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "repository": { "threads": {
+                "pageInfo": { "hasNextPage": false, "endCursor": null },
+                "nodes": [{
+                    "number": 3,
+                    "title": "How do I configure retries?",
+                    "body": "Is there a setting for it?",
+                    "url": "https://github.com/acme/widgets/discussions/3",
+                    "closed": true,
+                    "author": { "login": "carol" },
+                    "category": { "name": "Q&A" },
+                    "labels": { "nodes": [] },
+                    "answer": { "author": { "login": "alice" }, "body": "Set `retries` in widgets.toml.", "createdAt": "2024-05-02T08:30:00Z" },
+                    "comments": { "nodes": [] }
+                }]
+            } } }
+        })))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+async fn properties_of(setup: &TestSetup, source_url: &str) -> Result<Vec<(String, String)>> {
+    let conn = setup.db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT cm.metadata_subtype, cm.metadata_value FROM content_metadata cm
+             JOIN documents d ON d.id = cm.document_id
+             WHERE d.source_url = ? AND cm.metadata_type = 'PROPERTY'
+             ORDER BY cm.metadata_subtype, cm.metadata_value",
+            params![source_url],
+        )
+        .await?;
+    let mut properties = Vec::new();
+    while let Some(row) = rows.next().await? {
+        properties.push((row.get::<String>(0)?, row.get::<String>(1)?));
+    }
+    Ok(properties)
+}
+
+async fn content_of(setup: &TestSetup, source_url: &str) -> Result<(String, String)> {
+    let conn = setup.db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT title, content FROM documents WHERE source_url = ?",
+            params![source_url],
+        )
+        .await?;
+    let row = rows.next().await?.expect("Thread document not found");
+    Ok((row.get::<String>(0)?, row.get::<String>(1)?))
+}
+
+#[tokio::test]
+async fn test_issues_ingestor_pages_and_stores_threads_with_metadata() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    mount_threads(&server).await;
+    let setup = TestSetup::new().await?;
+    let ingestor = GitHubIssuesIngestor::new(&setup.db, client(&server));
+    let source = json!({ "url": REPO_URL, "kinds": ["issue", "discussion"] }).to_string();
+
+    // --- Act ---
+    let result = ingestor.ingest(&source, Some("github-user")).await?;
+
+    // --- Assert ---
+    assert_eq!(result.documents_added, 3);
+    let metadata: serde_json::Value =
+        serde_json::from_str(result.metadata.as_deref().expect("metadata missing"))?;
+    assert_eq!(metadata["fetched"]["issue"], 2);
+    assert_eq!(metadata["fetched"]["discussion"], 1);
+
+    let (title, content) = content_of(&setup, "https://github.com/acme/widgets/issues/1").await?;
+    assert_eq!(title, "acme/widgets#1: Crash on startup");
+    assert_eq!(
+        content,
+        "acme/widgets#1: Crash on startup\nType: Issue\nState: OPEN\nAuthor: alice\nLabels: bug, p1\n\n\
+         The app panics when the config is missing.\n\n\
+         Comments:\n[2024-05-01] bob: Reproduced on 1.2."
+    );
+    let properties = properties_of(&setup, "https://github.com/acme/widgets/issues/1").await?;
+    let expected = vec![
+        (KIND_PROPERTY.to_string(), "issue".to_string()),
+        (LABEL_PROPERTY.to_string(), "bug".to_string()),
+        (LABEL_PROPERTY.to_string(), "p1".to_string()),
+        (STATE_PROPERTY.to_string(), "OPEN".to_string()),
+    ];
+    assert_eq!(properties, expected);
+
+    let (_, content) = content_of(&setup, "https://github.com/acme/widgets/issues/2").await?;
+    assert_eq!(
+        content,
+        "acme/widgets#2: Document the config file\nType: Issue\nState: CLOSED\nAuthor: Unknown\nLabels: "
+    );
+
+    let discussion_url = "https://github.com/acme/widgets/discussions/3";
+    let (_, content) = content_of(&setup, discussion_url).await?;
+    assert_eq!(
+        content,
+        "acme/widgets#3: How do I configure retries?\nType: Discussion\nState: CLOSED\nAuthor: carol\nLabels: \nCategory: Q&A\n\n\
+         Is there a setting for it?\n\n\
+         Accepted answer:\n[2024-05-02] alice: Set `retries` in widgets.toml."
+    );
+    let properties = properties_of(&setup, discussion_url).await?;
+    let expected = vec![
+        (CATEGORY_PROPERTY.to_string(), "Q&A".to_string()),
+        (KIND_PROPERTY.to_string(), "discussion".to_string()),
+        (STATE_PROPERTY.to_string(), "CLOSED".to_string()),
+    ];
+    assert_eq!(properties, expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_issues_ingestor_reports_graphql_errors() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(GRAPHQL_PATH))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "repository": null },
+            "errors": [{ "message": "Could not resolve to a Repository with the name 'acme/widgets'." }]
+        })))
+        .mount(&server)
+        .await;
+    let setup = TestSetup::new().await?;
+    let ingestor = GitHubIssuesIngestor::new(&setup.db, client(&server));
+
+    // --- Act ---
+    let source = json!({ "url": REPO_URL, "kinds": ["issue"] }).to_string();
+    let result = ingestor.ingest(&source, None).await;
+
+    // --- Assert ---
+    let err = result.expect_err("A GraphQL error should fail the ingestion");
+    assert!(err.to_string().contains("Could not resolve"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_issues_ingestor_rejects_non_github_urls() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    let setup = TestSetup::new().await?;
+    let ingestor = GitHubIssuesIngestor::new(&setup.db, client(&server));
+
+    // --- Act ---
+    let source = json!({ "url": "https://gitlab.com/group/project" }).to_string();
+    let result = ingestor.ingest(&source, None).await;
+
+    // --- Assert ---
+    let err = result.expect_err("Only GitHub repositories have issues to ingest");
+    assert!(err.to_string().contains("Not a GitHub repository URL"));
+    Ok(())
+}
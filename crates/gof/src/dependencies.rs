@@ -0,0 +1,251 @@
+//! # Dependency Discovery
+//!
+//! This module reads the dependencies of a Cargo project for `gof example`: the regular,
+//! dev, and build dependencies of a manifest (including target-specific tables),
+//! optionally those of every workspace member, with versions optionally pinned to the
+//! ones recorded in `Cargo.lock`. Crates are deduplicated by name.
+
+use anyhow::{Context, Result};
+use semver::{Version, VersionReq};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+const DEPENDENCIES: &str = "dependencies";
+const DEV_DEPENDENCIES: &str = "dev-dependencies";
+const BUILD_DEPENDENCIES: &str = "build-dependencies";
+const MANIFEST_FILE: &str = "Cargo.toml";
+const LOCK_FILE: &str = "Cargo.lock";
+/// The version requirement of a dependency that does not specify one.
+const ANY_VERSION: &str = "*";
+
+/// Selects which dependencies [`collect_dependencies`] reads.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DependencyOptions {
+    /// Also read `[dev-dependencies]`.
+    pub dev: bool,
+    /// Also read `[build-dependencies]`.
+    pub build: bool,
+    /// Also read the manifests of the workspace members. Members are not reported as
+    /// dependencies of each other.
+    pub workspace: bool,
+    /// Replace version requirements with the highest matching version in the
+    /// `Cargo.lock` next to the manifest.
+    pub lockfile: bool,
+}
+
+/// Parses a `Cargo.toml` file and extracts a list of (name, version) for its
+/// `[dependencies]`.
+pub fn parse_dependencies(path: &Path) -> Result<Vec<(String, String)>> {
+    collect_dependencies(path, &DependencyOptions::default())
+}
+
+/// Collects the (name, version) of the dependencies of a project, sorted by name.
+pub fn collect_dependencies(
+    path: &Path,
+    options: &DependencyOptions,
+) -> Result<Vec<(String, String)>> {
+    let root = read_toml(path, MANIFEST_FILE)?;
+    let root_dir = path.parent().unwrap_or(Path::new("."));
+    let workspace_dependencies = root
+        .get("workspace")
+        .and_then(|workspace| workspace.get(DEPENDENCIES))
+        .and_then(|dependencies| dependencies.as_table())
+        .cloned()
+        .unwrap_or_default();
+
+    let mut manifests = vec![root.clone()];
+    if options.workspace {
+        for member_path in workspace_member_manifests(root_dir, &root)? {
+            manifests.push(read_toml(&member_path, MANIFEST_FILE)?);
+        }
+    }
+    // Workspace members depend on each other by path; they are not crates to fetch.
+    let members: HashSet<String> = match options.workspace {
+        true => manifests.iter().filter_map(package_name).collect(),
+        false => HashSet::new(),
+    };
+
+    let mut sections = vec![DEPENDENCIES];
+    if options.dev {
+        sections.push(DEV_DEPENDENCIES);
+    }
+    if options.build {
+        sections.push(BUILD_DEPENDENCIES);
+    }
+
+    let mut deps: BTreeMap<String, String> = BTreeMap::new();
+    for manifest in &manifests {
+        for table in dependency_tables(manifest, &sections) {
+            for (key, value) in table {
+                let Some((name, version)) = dependency_spec(key, value, &workspace_dependencies)
+                else {
+                    warn!("Dependency '{key}' inherits from the workspace, which does not declare it. Skipping.");
+                    continue;
+                };
+                if members.contains(&name) {
+                    continue;
+                }
+                // The first concrete requirement wins over a bare `*`.
+                let existing = deps.entry(name).or_insert_with(|| version.clone());
+                if *existing == ANY_VERSION {
+                    *existing = version;
+                }
+            }
+        }
+    }
+
+    if options.lockfile {
+        pin_to_lockfile(&root_dir.join(LOCK_FILE), &mut deps)?;
+    }
+
+    Ok(deps.into_iter().collect())
+}
+
+fn read_toml(path: &Path, description: &str) -> Result<toml::Table> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {description} at '{}'", path.display()))?;
+    content
+        .parse()
+        .with_context(|| format!("Failed to parse TOML from '{}'", path.display()))
+}
+
+fn package_name(manifest: &toml::Table) -> Option<String> {
+    manifest
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(|name| name.as_str())
+        .map(str::to_string)
+}
+
+/// Returns the requested dependency tables of a manifest, including the
+/// target-specific ones (`[target.'cfg(unix)'.dependencies]`).
+fn dependency_tables<'a>(manifest: &'a toml::Table, sections: &[&str]) -> Vec<&'a toml::Table> {
+    let targets = manifest
+        .get("target")
+        .and_then(|target| target.as_table())
+        .into_iter()
+        .flat_map(|targets| targets.values())
+        .filter_map(|target| target.as_table());
+    std::iter::once(manifest)
+        .chain(targets)
+        .flat_map(|table| {
+            sections
+                .iter()
+                .filter_map(move |section| table.get(*section).and_then(|deps| deps.as_table()))
+        })
+        .collect()
+}
+
+/// Reads the crate name and version requirement of one dependency entry, resolving
+/// renames (`package = "..."`) and `workspace = true` inheritance.
+fn dependency_spec(
+    key: &str,
+    value: &toml::Value,
+    workspace_dependencies: &toml::Table,
+) -> Option<(String, String)> {
+    let Some(table) = value.as_table() else {
+        let version = value.as_str().unwrap_or(ANY_VERSION);
+        return Some((key.to_string(), version.to_string()));
+    };
+    if table.get("workspace").and_then(|w| w.as_bool()) == Some(true) {
+        let inherited = workspace_dependencies.get(key)?;
+        return dependency_spec(key, inherited, &toml::Table::new());
+    }
+    let name = table
+        .get("package")
+        .and_then(|package| package.as_str())
+        .unwrap_or(key);
+    let version = table
+        .get("version")
+        .and_then(|version| version.as_str())
+        .unwrap_or(ANY_VERSION);
+    Some((name.to_string(), version.to_string()))
+}
+
+/// Expands the `members` globs of a `[workspace]` into member manifest paths,
+/// leaving out `exclude`d directories.
+fn workspace_member_manifests(root_dir: &Path, root: &toml::Table) -> Result<Vec<PathBuf>> {
+    let Some(workspace) = root.get("workspace").and_then(|w| w.as_table()) else {
+        info!("The manifest has no [workspace] section; reading it alone.");
+        return Ok(Vec::new());
+    };
+    let strings = |key: &str| -> Vec<String> {
+        workspace
+            .get(key)
+            .and_then(|values| values.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|value| value.as_str().map(str::to_string))
+            .collect()
+    };
+    let excluded: HashSet<PathBuf> = strings("exclude")
+        .iter()
+        .map(|dir| root_dir.join(dir))
+        .collect();
+
+    let mut manifests = Vec::new();
+    for pattern in strings("members") {
+        let pattern = root_dir.join(&pattern).to_string_lossy().to_string();
+        let dirs = glob::glob(&pattern)
+            .with_context(|| format!("Invalid workspace member pattern '{pattern}'"))?;
+        for dir in dirs {
+            let dir = dir?;
+            let manifest = dir.join(MANIFEST_FILE);
+            if excluded.contains(&dir) || !manifest.is_file() {
+                continue;
+            }
+            manifests.push(manifest);
+        }
+    }
+    info!("Found {} workspace members.", manifests.len());
+    Ok(manifests)
+}
+
+/// Replaces each requirement with the highest version `Cargo.lock` resolved for it.
+/// Requirements without a matching locked version are kept.
+fn pin_to_lockfile(lock_path: &Path, deps: &mut BTreeMap<String, String>) -> Result<()> {
+    if !lock_path.is_file() {
+        warn!(
+            "No Cargo.lock at '{}'; keeping the version requirements.",
+            lock_path.display()
+        );
+        return Ok(());
+    }
+    let lock = read_toml(lock_path, LOCK_FILE)?;
+
+    // Only packages from a registry or git source; path packages are local crates.
+    let mut locked: HashMap<String, Vec<Version>> = HashMap::new();
+    for package in lock
+        .get("package")
+        .and_then(|packages| packages.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|package| package.get("source").is_some())
+    {
+        let name = package.get("name").and_then(|name| name.as_str());
+        let version = package
+            .get("version")
+            .and_then(|version| version.as_str())
+            .and_then(|version| Version::parse(version).ok());
+        if let (Some(name), Some(version)) = (name, version) {
+            locked.entry(name.to_string()).or_default().push(version);
+        }
+    }
+
+    for (name, requirement) in deps.iter_mut() {
+        let Some(versions) = locked.get(name) else {
+            continue;
+        };
+        let req = VersionReq::parse(requirement).ok();
+        let best = versions
+            .iter()
+            .filter(|version| req.as_ref().is_none_or(|req| req.matches(version)))
+            .max();
+        if let Some(best) = best {
+            *requirement = best.to_string();
+        }
+    }
+    Ok(())
+}
@@ -3,19 +3,22 @@
 //! This crate contains the core logic for the `gof` CLI tool, which automates
 //! the creation of a RAG knowledge base from a Rust project's dependencies.
 
+pub mod dependencies;
+pub mod serve;
+
 use anyhow::{anyhow, Context, Result};
 
-use anyrag::{
-    providers::ai::{local::LocalAiProvider, AiProvider},
-    SearchResult,
-};
+use anyrag::providers::ai::{local::LocalAiProvider, AiProvider};
+use anyrag_github::ingest::types::ExampleSearchResult;
 use clap::{Parser, Subcommand};
 use crates_io_api::AsyncClient as CratesClient;
 use futures::future::join_all;
 use serde::{Deserialize, Serialize};
-use std::{env, fs, path::PathBuf, sync::Arc};
+use std::{env, path::PathBuf, sync::Arc};
 use tracing::{error, info, warn};
 
+pub use dependencies::{collect_dependencies, parse_dependencies, DependencyOptions};
+
 // --- CLI Argument Structs ---
 
 #[derive(Parser, Debug)]
@@ -32,6 +35,8 @@ pub enum Commands {
     Example(ExampleArgs),
     /// Search the ingested code examples using RAG
     Mcp(McpArgs),
+    /// Serve the ingested code examples as MCP tools over stdio (JSON-RPC)
+    Serve,
 }
 
 #[derive(Parser, Debug)]
@@ -50,6 +55,22 @@ pub struct ExampleArgs {
     /// providing comprehensive context.
     #[arg(long)]
     all: bool,
+    /// Also ingest `[dev-dependencies]`.
+    #[arg(long)]
+    dev: bool,
+    /// Also ingest `[build-dependencies]`.
+    #[arg(long)]
+    build: bool,
+    /// Also read the dependencies of every workspace member listed in the manifest.
+    #[arg(long)]
+    workspace: bool,
+    /// Use the exact versions recorded in the `Cargo.lock` next to the manifest.
+    #[arg(long)]
+    lockfile: bool,
+    /// Re-ingest dependency versions that are already stored instead of reporting them
+    /// as cached.
+    #[arg(long)]
+    force: bool,
 }
 
 #[derive(Parser, Debug)]
@@ -60,6 +81,14 @@ pub struct McpArgs {
     /// If omitted, all ingested repositories will be searched.
     #[arg(long, value_delimiter = ',')]
     repos: Option<Vec<String>>,
+    /// Limits the search to examples in these languages (e.g., "rust,python").
+    /// If omitted, examples in every language are searched.
+    #[arg(long, value_delimiter = ',')]
+    languages: Option<Vec<String>>,
+    /// When --repos is omitted, searches only the N repositories with the most examples
+    /// containing the query's words instead of every ingested repository.
+    #[arg(long)]
+    max_repos: Option<usize>,
 }
 
 // --- MCP Protocol Structs ---
@@ -71,21 +100,25 @@ pub struct McpSuccessResponse {
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct McpSearchResult {
-    // Note: repository and version are not available in the standard SearchResult.
-    // This can be added later if needed. For now, we return what's available.
+    /// The repository the example comes from (e.g., "tursodatabase-turso").
+    pub repository: String,
+    /// The ingested version of the repository the example comes from.
+    pub version: String,
     pub source_file: String,
     pub handle: String,
     pub content: String,
     pub score: f64,
 }
 
-impl From<SearchResult> for McpSearchResult {
-    fn from(value: SearchResult) -> Self {
+impl From<ExampleSearchResult> for McpSearchResult {
+    fn from(value: ExampleSearchResult) -> Self {
         Self {
-            source_file: value.link,
-            handle: value.title,
-            content: value.description,
-            score: value.score,
+            repository: value.repository,
+            version: value.version,
+            source_file: value.result.link,
+            handle: value.result.title,
+            content: value.result.description,
+            score: value.result.score,
         }
     }
 }
@@ -108,6 +141,7 @@ pub async fn run(cli: Cli) -> Result<()> {
     match cli.command {
         Commands::Example(args) => handle_example(args).await,
         Commands::Mcp(args) => handle_mcp(args).await,
+        Commands::Serve => handle_serve().await,
     }
 }
 
@@ -121,8 +155,14 @@ async fn handle_example(args: ExampleArgs) -> Result<()> {
         args.path.display()
     );
 
-    // 1. Parse Cargo.toml
-    let dependencies = parse_dependencies(&args.path)?;
+    // 1. Parse Cargo.toml (and the workspace members and Cargo.lock, if requested)
+    let options = DependencyOptions {
+        dev: args.dev,
+        build: args.build,
+        workspace: args.workspace,
+        lockfile: args.lockfile,
+    };
+    let dependencies = collect_dependencies(&args.path, &options)?;
     if dependencies.is_empty() {
         println!(
             "🤷 No dependencies found in '{}'. Nothing to do.",
@@ -167,13 +207,43 @@ async fn handle_example(args: ExampleArgs) -> Result<()> {
         return Ok(());
     }
 
+    // Crates published from the same repository (e.g. `tokio` and `tokio-macros`)
+    // share one ingestion per version.
+    repo_tasks.sort();
+    repo_tasks.dedup();
+
+    let storage_manager = anyrag_github::ingest::storage::StorageManager::new(None).await?;
+
+    // 3. Skip the versions that are already ingested, unless --force is set.
+    let mut cached_count = 0;
+    if !args.force {
+        let mut pending = Vec::with_capacity(repo_tasks.len());
+        for (url, version) in repo_tasks {
+            match storage_manager.is_version_ingested(&url, &version).await {
+                Ok(true) => {
+                    println!("  💾 Cached {url}@{version}: already ingested.");
+                    cached_count += 1;
+                }
+                Ok(false) => pending.push((url, version)),
+                Err(e) => {
+                    warn!("Could not check the stored versions of {url}: {e}. Ingesting it.");
+                    pending.push((url, version));
+                }
+            }
+        }
+        repo_tasks = pending;
+    }
+    if repo_tasks.is_empty() {
+        println!("\n✨ All {cached_count} repositories are cached. Use --force to re-ingest them.");
+        return Ok(());
+    }
+
     println!(
         "\n🚀 Starting parallel ingestion for {} repositories...",
         repo_tasks.len()
     );
 
-    // 3. Parallel Ingestion
-    let storage_manager = anyrag_github::ingest::storage::StorageManager::new(None).await?;
+    // 4. Parallel Ingestion
     let mut handles = vec![];
     let ingest_all = args.all;
 
@@ -213,6 +283,7 @@ async fn handle_example(args: ExampleArgs) -> Result<()> {
                     dump_type,
                     includes: None,
                     excludes: None,
+                    auth_token: None,
                 };
 
                 println!(
@@ -258,6 +329,9 @@ async fn handle_example(args: ExampleArgs) -> Result<()> {
 
     println!("\n✨ Ingestion complete.");
     println!("   - {success_count} repositories succeeded.");
+    if cached_count > 0 {
+        println!("   - {cached_count} repositories were cached.");
+    }
     if fail_count > 0 {
         println!("   - {fail_count} repositories failed.");
     }
@@ -265,38 +339,6 @@ async fn handle_example(args: ExampleArgs) -> Result<()> {
     Ok(())
 }
 
-/// Parses a `Cargo.toml` file and extracts a list of (name, version) for dependencies.
-pub fn parse_dependencies(path: &PathBuf) -> Result<Vec<(String, String)>> {
-    let content = fs::read_to_string(path)
-        .with_context(|| format!("Failed to read Cargo.toml at '{}'", path.display()))?;
-    let table: toml::Table = content
-        .parse()
-        .with_context(|| format!("Failed to parse TOML from '{}'", path.display()))?;
-
-    let mut deps = Vec::new();
-
-    if let Some(dependencies) = table.get("dependencies").and_then(|d| d.as_table()) {
-        for (name, val) in dependencies {
-            let version = if val.is_str() {
-                val.as_str().unwrap().to_string()
-            } else if let Some(table) = val.as_table() {
-                table
-                    .get("version")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("*") // Default if version is not specified
-                    .to_string()
-            } else {
-                "*".to_string()
-            };
-            deps.push((name.clone(), version));
-        }
-    }
-
-    // Optionally, you could also parse [dev-dependencies], [build-dependencies], etc.
-
-    Ok(deps)
-}
-
 /// Handles the `gof mcp` command logic.
 async fn handle_mcp(args: McpArgs) -> Result<()> {
     info!("Starting 'mcp' command with args: {:?}", args);
@@ -324,17 +366,35 @@ async fn handle_mcp(args: McpArgs) -> Result<()> {
     }
 }
 
+/// Handles the `gof serve` command logic.
+async fn handle_serve() -> Result<()> {
+    let storage_manager = anyrag_github::ingest::storage::StorageManager::new(None).await?;
+    let server = serve::McpServer::new(storage_manager);
+    server
+        .run(
+            tokio::io::BufReader::new(tokio::io::stdin()),
+            tokio::io::stdout(),
+        )
+        .await
+}
+
 /// The core logic for the MCP search, designed to return a JSON string on success
 /// or an `anyhow::Error` on failure.
 async fn run_mcp_search(args: McpArgs) -> Result<String> {
-    // 1. Get repository list. For now, we require it to be specified.
-    let repos_to_search = args.repos.ok_or_else(|| {
-        anyhow!("The --repos flag must be provided with a list of repository names to search.")
-    })?;
+    let storage_manager = anyrag_github::ingest::storage::StorageManager::new(None).await?;
+    mcp_search(&storage_manager, args).await
+}
 
-    if repos_to_search.is_empty() {
-        return Err(anyhow!("The --repos list cannot be empty."));
-    }
+/// Runs an MCP search against the repositories of a `StorageManager`.
+async fn mcp_search(
+    storage_manager: &anyrag_github::ingest::storage::StorageManager,
+    args: McpArgs,
+) -> Result<String> {
+    // 1. Get the repository list, defaulting to every ingested repository.
+    let repos_to_search = match args.repos {
+        Some(repos) if !repos.is_empty() => repos,
+        _ => default_repos(storage_manager, &args.query, args.max_repos).await?,
+    };
 
     // 2. Get embedding configuration from environment.
     let embedding_api_url = env::var("EMBEDDINGS_API_URL")
@@ -343,8 +403,7 @@ async fn run_mcp_search(args: McpArgs) -> Result<String> {
         env::var("EMBEDDINGS_MODEL").context("EMBEDDINGS_MODEL environment variable is not set")?;
     let embedding_api_key = env::var("AI_API_KEY").ok();
 
-    // 3. Create dependencies (StorageManager, AiProvider).
-    let storage_manager = anyrag_github::ingest::storage::StorageManager::new(None).await?;
+    // 3. Create the AI provider.
     let local_ai_url = env::var("LOCAL_AI_API_URL")
         .or_else(|_| env::var("AI_API_URL"))
         .context("LOCAL_AI_API_URL or AI_API_URL must be set for local provider")?;
@@ -362,9 +421,10 @@ async fn run_mcp_search(args: McpArgs) -> Result<String> {
         args.query, repos_to_search
     );
     let search_results = anyrag_github::search_examples(
-        &storage_manager,
+        storage_manager,
         &args.query,
         &repos_to_search,
+        &args.languages.unwrap_or_default(),
         ai_provider,
         &embedding_api_url,
         &embedding_model,
@@ -377,8 +437,48 @@ async fn run_mcp_search(args: McpArgs) -> Result<String> {
     format_mcp_response(search_results)
 }
 
-/// Formats a vector of `SearchResult` into the MCP JSON string.
-pub fn format_mcp_response(search_results: Vec<SearchResult>) -> Result<String> {
+/// Returns the names of all tracked repositories, or the `max_repos` most relevant
+/// ones to the query when a limit is given.
+async fn default_repos(
+    storage_manager: &anyrag_github::ingest::storage::StorageManager,
+    query: &str,
+    max_repos: Option<usize>,
+) -> Result<Vec<String>> {
+    let repos: Vec<String> = storage_manager
+        .list_repositories()
+        .await?
+        .into_iter()
+        .map(|repo| repo.repo_name)
+        .collect();
+    if repos.is_empty() {
+        return Err(anyhow!(
+            "No repositories have been ingested yet. Run `gof example` first."
+        ));
+    }
+    let Some(limit) = max_repos else {
+        info!(
+            "No --repos given; searching all {} repositories.",
+            repos.len()
+        );
+        return Ok(repos);
+    };
+    let ranked = anyrag_github::ingest::search_logic::rank_repos_by_keywords(
+        storage_manager,
+        &repos,
+        query,
+        limit,
+    )
+    .await?;
+    info!(
+        "No --repos given; searching the {} most relevant of {} repositories.",
+        ranked.len(),
+        repos.len()
+    );
+    Ok(ranked)
+}
+
+/// Formats a vector of `ExampleSearchResult` into the MCP JSON string.
+pub fn format_mcp_response(search_results: Vec<ExampleSearchResult>) -> Result<String> {
     let mcp_results: Vec<McpSearchResult> = search_results
         .into_iter()
         .map(McpSearchResult::from)
@@ -5,8 +5,10 @@
 //! All logic is delegated to the `gof` library crate.
 
 use anyhow::Result;
+use anyrag::cli_config::{config_path, CliConfig};
 use clap::Parser;
 use gof::{run, Cli};
+use tracing::warn;
 use tracing_subscriber::{fmt, EnvFilter};
 
 // --- Main Application Entry ---
@@ -17,13 +19,21 @@ async fn main() -> Result<()> {
     let subscriber = fmt::Subscriber::builder()
         .with_env_filter(EnvFilter::from_default_env().add_directive("gof=info".parse()?))
         .with_ansi(false) // Make logs clean for file output or CI
+        // Keep stdout for command output, which `gof mcp` and `gof serve` emit as JSON.
+        .with_writer(std::io::stderr)
         .finish();
     tracing::subscriber::set_global_default(subscriber)?;
 
-    // 2. Parse CLI arguments
+    // 2. Fill in the environment variables that are not set from the configuration file
+    match config_path().and_then(|path| CliConfig::load(&path)) {
+        Ok(config) => config.apply_to_env(),
+        Err(e) => warn!("Ignoring the configuration file: {e}"),
+    }
+
+    // 3. Parse CLI arguments
     let cli = Cli::parse();
 
-    // 3. Call the library's run function and handle the final result
+    // 4. Call the library's run function and handle the final result
     if let Err(e) = run(cli).await {
         // Use debug formatting for more detailed error context
         eprintln!("[gof error] Failed to execute command: {e:?}");
@@ -0,0 +1,292 @@
+//! # MCP Stdio Server
+//!
+//! This module implements `gof serve`: a Model Context Protocol server that reads
+//! newline-delimited JSON-RPC 2.0 messages from stdin and writes the responses to
+//! stdout. It exposes the ingested examples as two tools, `search_examples` and
+//! `get_examples`, so an editor can keep one process running instead of spawning
+//! `gof mcp` for each query.
+
+use crate::{mcp_search, McpArgs};
+use anyhow::{anyhow, Context, Result};
+use anyrag_github::ingest::storage::StorageManager;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use tracing::{info, warn};
+
+const JSONRPC_VERSION: &str = "2.0";
+/// The protocol revision answered to clients that do not request one.
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+const SERVER_NAME: &str = "gof";
+
+const METHOD_INITIALIZE: &str = "initialize";
+const METHOD_PING: &str = "ping";
+const METHOD_TOOLS_LIST: &str = "tools/list";
+const METHOD_TOOLS_CALL: &str = "tools/call";
+
+pub const TOOL_SEARCH_EXAMPLES: &str = "search_examples";
+pub const TOOL_GET_EXAMPLES: &str = "get_examples";
+
+// JSON-RPC 2.0 error codes.
+pub const PARSE_ERROR: i64 = -32700;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// A JSON-RPC request, or a notification when it has no `id`.
+#[derive(Deserialize)]
+struct JsonRpcRequest {
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// A JSON-RPC error to return instead of a result.
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ToolCall {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Deserialize)]
+struct SearchExamplesArguments {
+    query: String,
+    #[serde(default)]
+    repos: Option<Vec<String>>,
+    #[serde(default)]
+    languages: Option<Vec<String>>,
+    #[serde(default)]
+    max_repos: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct GetExamplesArguments {
+    repo: String,
+    #[serde(default)]
+    version: Option<String>,
+}
+
+/// Answers MCP requests with the examples of a `StorageManager`.
+pub struct McpServer {
+    storage_manager: StorageManager,
+}
+
+impl McpServer {
+    pub fn new(storage_manager: StorageManager) -> Self {
+        Self { storage_manager }
+    }
+
+    /// Reads one JSON-RPC message per line until the reader closes, writing one
+    /// response line for each request. Notifications get no response.
+    pub async fn run<R, W>(&self, reader: R, mut writer: W) -> Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        info!("MCP server listening on stdio.");
+        let mut lines = reader.lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Some(response) = self.handle_message(&line).await else {
+                continue;
+            };
+            let mut output = serde_json::to_string(&response)?;
+            output.push('\n');
+            writer.write_all(output.as_bytes()).await?;
+            writer.flush().await?;
+        }
+        info!("MCP client closed the connection.");
+        Ok(())
+    }
+
+    /// Handles one JSON-RPC message and returns its response, or `None` for a
+    /// notification.
+    pub async fn handle_message(&self, message: &str) -> Option<Value> {
+        let request: JsonRpcRequest = match serde_json::from_str(message) {
+            Ok(request) => request,
+            Err(e) => {
+                warn!("Received an invalid JSON-RPC message: {e}");
+                let error = RpcError::new(PARSE_ERROR, format!("Invalid JSON-RPC message: {e}"));
+                return Some(error_response(Value::Null, error));
+            }
+        };
+        let Some(id) = request.id else {
+            info!("Received notification '{}'.", request.method);
+            return None;
+        };
+
+        let response = match self.dispatch(&request.method, request.params).await {
+            Ok(result) => json!({ "jsonrpc": JSONRPC_VERSION, "id": id, "result": result }),
+            Err(error) => error_response(id, error),
+        };
+        Some(response)
+    }
+
+    async fn dispatch(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            METHOD_INITIALIZE => {
+                let protocol_version = params
+                    .get("protocolVersion")
+                    .and_then(|version| version.as_str())
+                    .unwrap_or(PROTOCOL_VERSION);
+                Ok(json!({
+                    "protocolVersion": protocol_version,
+                    "capabilities": { "tools": {} },
+                    "serverInfo": { "name": SERVER_NAME, "version": env!("CARGO_PKG_VERSION") },
+                }))
+            }
+            METHOD_PING => Ok(json!({})),
+            METHOD_TOOLS_LIST => Ok(json!({ "tools": tool_definitions() })),
+            METHOD_TOOLS_CALL => self.call_tool(params).await,
+            other => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method '{other}'"),
+            )),
+        }
+    }
+
+    /// Runs a tool. Failures of the tool itself are reported in the result with
+    /// `isError` so the model can see them; malformed calls are JSON-RPC errors.
+    async fn call_tool(&self, params: Value) -> Result<Value, RpcError> {
+        let call: ToolCall = serde_json::from_value(params)
+            .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid tool call: {e}")))?;
+        info!("Calling tool '{}'.", call.name);
+
+        let outcome = match call.name.as_str() {
+            TOOL_SEARCH_EXAMPLES => {
+                let arguments: SearchExamplesArguments = parse_arguments(call.arguments)?;
+                let args = McpArgs {
+                    query: arguments.query,
+                    repos: arguments.repos,
+                    languages: arguments.languages,
+                    max_repos: arguments.max_repos,
+                };
+                mcp_search(&self.storage_manager, args).await
+            }
+            TOOL_GET_EXAMPLES => {
+                let arguments: GetExamplesArguments = parse_arguments(call.arguments)?;
+                self.get_examples(arguments).await
+            }
+            other => {
+                return Err(RpcError::new(
+                    INVALID_PARAMS,
+                    format!("Unknown tool '{other}'"),
+                ))
+            }
+        };
+
+        let (text, is_error) = match outcome {
+            Ok(text) => (text, false),
+            Err(e) => (format!("{e:#}"), true),
+        };
+        Ok(json!({
+            "content": [{ "type": "text", "text": text }],
+            "isError": is_error,
+        }))
+    }
+
+    /// Returns the stored examples of a repository version (the latest ingested one
+    /// if none is given) as a JSON string.
+    async fn get_examples(&self, arguments: GetExamplesArguments) -> Result<String> {
+        let repo = arguments.repo;
+        let version = match arguments.version {
+            Some(version) => version,
+            None => self
+                .storage_manager
+                .get_latest_version(&repo)
+                .await?
+                .ok_or_else(|| anyhow!("Repository '{repo}' has no ingested examples."))?,
+        };
+        let examples = self.storage_manager.get_examples(&repo, &version).await?;
+        serde_json::to_string_pretty(&json!({
+            "repo": repo,
+            "version": version,
+            "examples": examples,
+        }))
+        .context("Failed to serialize the examples to JSON")
+    }
+}
+
+fn parse_arguments<T: for<'de> Deserialize<'de>>(arguments: Value) -> Result<T, RpcError> {
+    serde_json::from_value(arguments)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid tool arguments: {e}")))
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": JSONRPC_VERSION,
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+/// The tools listed by `tools/list`, with the JSON Schema of their arguments.
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": TOOL_SEARCH_EXAMPLES,
+            "description": "Search the code examples ingested from the project's dependencies.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "What the example should show, in natural language."
+                    },
+                    "repos": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Repository names to search (e.g. \"tursodatabase-turso\"). Defaults to every ingested repository."
+                    },
+                    "languages": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Limits the search to these languages (e.g. \"rust\")."
+                    },
+                    "max_repos": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Without repos, searches only this many repositories, those with the most examples containing the query's words."
+                    }
+                },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": TOOL_GET_EXAMPLES,
+            "description": "List every code example ingested for a repository version.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "repo": {
+                        "type": "string",
+                        "description": "The repository name (e.g. \"tursodatabase-turso\")."
+                    },
+                    "version": {
+                        "type": "string",
+                        "description": "The ingested version. Defaults to the latest one."
+                    }
+                },
+                "required": ["repo"]
+            }
+        }
+    ])
+}
@@ -1,9 +1,14 @@
 //! # `gof` Crate Integration Tests
 
 use anyhow::Result;
-use gof::{format_mcp_response, parse_dependencies, McpSearchResult, McpSuccessResponse};
+use anyrag_github::ingest::types::ExampleSearchResult;
+use gof::{
+    collect_dependencies, format_mcp_response, parse_dependencies, DependencyOptions,
+    McpSearchResult, McpSuccessResponse,
+};
 use std::fs::File;
 use std::io::Write;
+use std::path::Path;
 use tempfile::tempdir;
 
 #[test]
@@ -102,21 +107,211 @@ edition = "2021"
     Ok(())
 }
 
+/// Writes a file, creating its parent directories.
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+#[test]
+fn test_collect_dependencies_reads_dev_build_and_target_tables() -> Result<()> {
+    // Arrange
+    let dir = tempdir()?;
+    let file_path = dir.path().join("Cargo.toml");
+    write_file(
+        &file_path,
+        r#"[package]
+name = "test-project"
+version = "0.1.0"
+
+[dependencies]
+serde = "1.0"
+json = { package = "serde_json", version = "1.0.100" }
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+
+[dev-dependencies]
+tempfile = "3.8"
+serde = "1.0.190"
+
+[build-dependencies]
+cc = "1.0"
+"#,
+    )?;
+
+    // Act
+    let regular = collect_dependencies(&file_path, &DependencyOptions::default())?;
+    let all = collect_dependencies(
+        &file_path,
+        &DependencyOptions {
+            dev: true,
+            build: true,
+            ..Default::default()
+        },
+    )?;
+
+    // Assert
+    let pairs = |deps: &[(&str, &str)]| -> Vec<(String, String)> {
+        deps.iter()
+            .map(|(name, version)| (name.to_string(), version.to_string()))
+            .collect()
+    };
+    assert_eq!(
+        regular,
+        pairs(&[("libc", "0.2"), ("serde", "1.0"), ("serde_json", "1.0.100")])
+    );
+    // `serde` appears twice but is ingested once, with the first requirement.
+    assert_eq!(
+        all,
+        pairs(&[
+            ("cc", "1.0"),
+            ("libc", "0.2"),
+            ("serde", "1.0"),
+            ("serde_json", "1.0.100"),
+            ("tempfile", "3.8"),
+        ])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_collect_dependencies_walks_workspace_members_and_pins_lockfile() -> Result<()> {
+    // Arrange
+    let dir = tempdir()?;
+    let root = dir.path();
+    write_file(
+        &root.join("Cargo.toml"),
+        r#"[workspace]
+members = ["crates/*"]
+exclude = ["crates/experimental"]
+
+[workspace.dependencies]
+tokio = { version = "1.35", features = ["full"] }
+"#,
+    )?;
+    write_file(
+        &root.join("crates/core/Cargo.toml"),
+        r#"[package]
+name = "core"
+version = "0.1.0"
+
+[dependencies]
+tokio = { workspace = true }
+anyhow = "1.0"
+"#,
+    )?;
+    write_file(
+        &root.join("crates/cli/Cargo.toml"),
+        r#"[package]
+name = "cli"
+version = "0.1.0"
+
+[dependencies]
+core = { path = "../core" }
+clap = "4"
+"#,
+    )?;
+    write_file(
+        &root.join("crates/experimental/Cargo.toml"),
+        r#"[package]
+name = "experimental"
+version = "0.1.0"
+
+[dependencies]
+rand = "0.8"
+"#,
+    )?;
+    write_file(
+        &root.join("Cargo.lock"),
+        r#"version = 3
+
+[[package]]
+name = "anyhow"
+version = "1.0.79"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "clap"
+version = "3.2.25"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "clap"
+version = "4.4.18"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "core"
+version = "0.1.0"
+
+[[package]]
+name = "tokio"
+version = "1.35.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#,
+    )?;
+    let manifest = root.join("Cargo.toml");
+
+    // Act
+    let requirements = collect_dependencies(
+        &manifest,
+        &DependencyOptions {
+            workspace: true,
+            ..Default::default()
+        },
+    )?;
+    let locked = collect_dependencies(
+        &manifest,
+        &DependencyOptions {
+            workspace: true,
+            lockfile: true,
+            ..Default::default()
+        },
+    )?;
+
+    // Assert
+    let expected_requirements = vec![
+        ("anyhow".to_string(), "1.0".to_string()),
+        ("clap".to_string(), "4".to_string()),
+        ("tokio".to_string(), "1.35".to_string()),
+    ];
+    assert_eq!(requirements, expected_requirements);
+    let expected_locked = vec![
+        ("anyhow".to_string(), "1.0.79".to_string()),
+        ("clap".to_string(), "4.4.18".to_string()),
+        ("tokio".to_string(), "1.35.1".to_string()),
+    ];
+    assert_eq!(locked, expected_locked);
+    Ok(())
+}
+
 #[test]
 fn test_mcp_json_formatting() -> Result<()> {
     // Arrange
     let search_results = vec![
-        anyrag::SearchResult {
-            title: "handle1".to_string(),
-            link: "file1.rs".to_string(),
-            description: "content1".to_string(),
-            score: 0.9,
+        ExampleSearchResult {
+            repository: "tokio-rs-tokio".to_string(),
+            version: "1.35.1".to_string(),
+            result: anyrag::SearchResult {
+                title: "handle1".to_string(),
+                link: "file1.rs".to_string(),
+                description: "content1".to_string(),
+                score: 0.9,
+            },
         },
-        anyrag::SearchResult {
-            title: "handle2".to_string(),
-            link: "file2.rs".to_string(),
-            description: "content2".to_string(),
-            score: 0.8,
+        ExampleSearchResult {
+            repository: "serde-rs-serde".to_string(),
+            version: "1.0.190".to_string(),
+            result: anyrag::SearchResult {
+                title: "handle2".to_string(),
+                link: "file2.rs".to_string(),
+                description: "content2".to_string(),
+                score: 0.8,
+            },
         },
     ];
 
@@ -129,6 +324,8 @@ fn test_mcp_json_formatting() -> Result<()> {
     assert_eq!(
         parsed.results[0],
         McpSearchResult {
+            repository: "tokio-rs-tokio".to_string(),
+            version: "1.35.1".to_string(),
             source_file: "file1.rs".to_string(),
             handle: "handle1".to_string(),
             content: "content1".to_string(),
@@ -138,6 +335,8 @@ fn test_mcp_json_formatting() -> Result<()> {
     assert_eq!(
         parsed.results[1],
         McpSearchResult {
+            repository: "serde-rs-serde".to_string(),
+            version: "1.0.190".to_string(),
             source_file: "file2.rs".to_string(),
             handle: "handle2".to_string(),
             content: "content2".to_string(),
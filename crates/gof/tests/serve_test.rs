@@ -0,0 +1,147 @@
+//! # `gof serve` MCP Server Tests
+//!
+//! These tests drive the MCP server over in-memory streams, with examples stored in a
+//! temporary `StorageManager`. The `search_examples` tool needs an embedding and AI
+//! service, so only its argument validation is covered here.
+
+use anyhow::Result;
+use anyrag_github::ingest::{
+    storage::StorageManager,
+    types::{ExampleSourceType, GeneratedExample},
+};
+use gof::serve::{McpServer, INVALID_PARAMS, METHOD_NOT_FOUND, PARSE_ERROR, PROTOCOL_VERSION};
+use serde_json::{json, Value};
+use tempfile::tempdir;
+
+const REPO_URL: &str = "https://github.com/user/demo";
+const REPO_NAME: &str = "user-demo";
+
+/// Creates a server whose storage holds one example of `REPO_URL` at version `1.0.0`.
+async fn server_with_example(db_dir: &str) -> Result<McpServer> {
+    let storage = StorageManager::new(Some(db_dir)).await?;
+    let repo = storage.track_repository(REPO_URL).await?;
+    let example = GeneratedExample {
+        example_handle: "example:examples/main.rs".to_string(),
+        content: "fn main() { demo::run(); }".to_string(),
+        source_file: "examples/main.rs".to_string(),
+        source_type: ExampleSourceType::ExampleFile,
+        language: "rust".to_string(),
+        version: "1.0.0".to_string(),
+    };
+    storage.store_examples(&repo, vec![example]).await?;
+    Ok(McpServer::new(storage))
+}
+
+/// Sends one message and parses the response.
+async fn request(server: &McpServer, message: Value) -> Value {
+    server
+        .handle_message(&message.to_string())
+        .await
+        .expect("A request with an id must get a response.")
+}
+
+#[tokio::test]
+async fn test_serve_handshake_and_tool_listing_over_stdio() -> Result<()> {
+    // Arrange
+    let db_dir = tempdir()?;
+    let server = server_with_example(db_dir.path().to_str().unwrap()).await?;
+    let input = [
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+        json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }),
+    ]
+    .iter()
+    .map(|message| format!("{message}\n"))
+    .collect::<String>();
+    let mut output = Vec::new();
+
+    // Act
+    server.run(input.as_bytes(), &mut output).await?;
+
+    // Assert
+    let responses: Vec<Value> = String::from_utf8(output)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    // The notification gets no response.
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["id"], 1);
+    assert_eq!(responses[0]["result"]["protocolVersion"], PROTOCOL_VERSION);
+    assert_eq!(responses[0]["result"]["serverInfo"]["name"], "gof");
+    let tools: Vec<&str> = responses[1]["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(tools, vec!["search_examples", "get_examples"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_serve_get_examples_tool_returns_stored_examples() -> Result<()> {
+    // Arrange
+    let db_dir = tempdir()?;
+    let server = server_with_example(db_dir.path().to_str().unwrap()).await?;
+
+    // Act
+    let latest = request(
+        &server,
+        json!({
+            "jsonrpc": "2.0", "id": 7, "method": "tools/call",
+            "params": { "name": "get_examples", "arguments": { "repo": REPO_NAME } }
+        }),
+    )
+    .await;
+    let missing = request(
+        &server,
+        json!({
+            "jsonrpc": "2.0", "id": 8, "method": "tools/call",
+            "params": { "name": "get_examples", "arguments": { "repo": "user-unknown" } }
+        }),
+    )
+    .await;
+
+    // Assert
+    assert_eq!(latest["id"], 7);
+    assert_eq!(latest["result"]["isError"], false);
+    let text = latest["result"]["content"][0]["text"].as_str().unwrap();
+    let payload: Value = serde_json::from_str(text)?;
+    assert_eq!(payload["version"], "1.0.0");
+    assert_eq!(payload["examples"][0]["source_file"], "examples/main.rs");
+    assert_eq!(payload["examples"][0]["language"], "rust");
+    // A failing tool is reported in its result so the client can show it.
+    assert_eq!(missing["result"]["isError"], true);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_serve_reports_protocol_errors() -> Result<()> {
+    // Arrange
+    let db_dir = tempdir()?;
+    let server = server_with_example(db_dir.path().to_str().unwrap()).await?;
+
+    // Act
+    let unknown_method = request(
+        &server,
+        json!({ "jsonrpc": "2.0", "id": "a", "method": "resources/list" }),
+    )
+    .await;
+    let malformed = server.handle_message("{not json").await.unwrap();
+    let search_without_query = request(
+        &server,
+        json!({
+            "jsonrpc": "2.0", "id": 3, "method": "tools/call",
+            "params": { "name": "search_examples", "arguments": { "repos": [REPO_NAME] } }
+        }),
+    )
+    .await;
+
+    // Assert
+    assert_eq!(unknown_method["id"], "a");
+    assert_eq!(unknown_method["error"]["code"], METHOD_NOT_FOUND);
+    assert_eq!(malformed["id"], Value::Null);
+    assert_eq!(malformed["error"]["code"], PARSE_ERROR);
+    assert_eq!(search_without_query["error"]["code"], INVALID_PARAMS);
+    Ok(())
+}
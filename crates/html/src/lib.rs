@@ -1,9 +1,16 @@
 use regex::Regex;
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html, Selector};
 use std::error::Error;
 use std::fmt;
 use std::fs;
 
+/// The tags removed by `clean_html` when no tags are given.
+pub const DEFAULT_REMOVE_TAGS: &[&str] = &["script", "style", "meta", "link", "a", "img"];
+
+/// The most columns a single table cell is repeated across by `extract_tables`. Larger
+/// `colspan` values are clamped, so a hostile page cannot make a cell fill memory.
+pub const MAX_COLSPAN: usize = 1000;
+
 /// Cleans specified HTML tags from a string.
 ///
 /// # Arguments
@@ -16,8 +23,7 @@ use std::fs;
 ///
 /// A `String` with the specified HTML tags removed.
 pub fn clean_html(html: &str, remove_tags: Option<&[&str]>) -> String {
-    let default_tags = &["script", "style", "meta", "link", "a", "img"];
-    let tags_to_remove = remove_tags.unwrap_or(default_tags);
+    let tags_to_remove = remove_tags.unwrap_or(DEFAULT_REMOVE_TAGS);
 
     let mut cleaned_html = html.to_string();
     for tag in tags_to_remove {
@@ -185,12 +191,180 @@ pub async fn url_to_clean_markdown(
         return Ok(clean_markdown_content(&markdown));
     }
 
+    let html_raw = fetch_html(url).await?;
+    Ok(html_to_clean_markdown(&html_raw, remove_tags))
+}
+
+/// Fetches a URL and returns its raw HTML body.
+pub async fn fetch_html(url: &str) -> Result<String, FetchError> {
     let response = reqwest::get(url).await?;
     if !response.status().is_success() {
         let status = response.status().as_u16();
         let body = response.text().await.unwrap_or_default();
         return Err(FetchError::Status { status, body });
     }
-    let html_raw = response.text().await?;
-    Ok(html_to_clean_markdown(&html_raw, remove_tags))
+    Ok(response.text().await?)
+}
+
+/// Returns the `href` of every `<a>` element, in document order.
+pub fn extract_links(html: &str) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let link_selector = Selector::parse("a[href]").unwrap();
+    document
+        .select(&link_selector)
+        .filter_map(|a| a.value().attr("href"))
+        .map(|href| href.trim().to_string())
+        .filter(|href| !href.is_empty())
+        .collect()
+}
+
+/// A `<table>` element parsed into a header row and data rows.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HtmlTable {
+    /// The text of the table's `<caption>`, if any.
+    pub caption: Option<String>,
+    /// One header per column. Columns without a header are named `column_N`.
+    pub headers: Vec<String>,
+    /// The data rows, each padded to the number of headers.
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Extracts every `<table>` in an HTML document that has at least one data row.
+///
+/// The first row is used as the header row when it is inside `<thead>` or made of
+/// `<th>` cells only. Cells spanning several columns (`colspan`) are repeated so
+/// each value stays under its header, up to `MAX_COLSPAN` times.
+pub fn extract_tables(html: &str) -> Vec<HtmlTable> {
+    let document = Html::parse_document(html);
+    let table_selector = Selector::parse("table").unwrap();
+    let caption_selector = Selector::parse("caption").unwrap();
+    let row_selector = Selector::parse("tr").unwrap();
+
+    let mut tables = Vec::new();
+    for table in document.select(&table_selector) {
+        let caption = table
+            .select(&caption_selector)
+            .next()
+            .map(|c| element_text(&c))
+            .filter(|c| !c.is_empty());
+
+        let mut header_row: Option<Vec<String>> = None;
+        let mut rows = Vec::new();
+        for (index, row) in table.select(&row_selector).enumerate() {
+            let cells: Vec<ElementRef> = row
+                .children()
+                .filter_map(ElementRef::wrap)
+                .filter(|c| matches!(c.value().name(), "th" | "td"))
+                .collect();
+            if cells.is_empty() {
+                continue;
+            }
+
+            let in_thead = row
+                .parent()
+                .and_then(ElementRef::wrap)
+                .is_some_and(|p| p.value().name() == "thead");
+            let all_th = cells.iter().all(|c| c.value().name() == "th");
+
+            let mut values = Vec::new();
+            for cell in &cells {
+                let span = cell
+                    .value()
+                    .attr("colspan")
+                    .and_then(|s| s.trim().parse::<usize>().ok())
+                    .unwrap_or(1)
+                    .clamp(1, MAX_COLSPAN);
+                let text = element_text(cell);
+                values.extend(std::iter::repeat_n(text, span));
+            }
+
+            if index == 0 && (in_thead || all_th) {
+                header_row = Some(values);
+            } else {
+                rows.push(values);
+            }
+        }
+
+        if rows.is_empty() {
+            continue;
+        }
+
+        let width = rows
+            .iter()
+            .map(Vec::len)
+            .chain(header_row.iter().map(Vec::len))
+            .max()
+            .unwrap_or(0);
+        let mut headers = header_row.unwrap_or_default();
+        headers.resize(width, String::new());
+        for (i, header) in headers.iter_mut().enumerate() {
+            if header.is_empty() {
+                *header = format!("column_{}", i + 1);
+            }
+        }
+        for row in &mut rows {
+            row.resize(width, String::new());
+        }
+
+        tables.push(HtmlTable {
+            caption,
+            headers,
+            rows,
+        });
+    }
+    tables
+}
+
+/// An `<img>` element with the text describing it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HtmlImage {
+    /// The `src` attribute, as written in the document.
+    pub src: String,
+    /// The `alt` text, if any.
+    pub alt: Option<String>,
+    /// The `<figcaption>` of the `<figure>` holding the image, if any.
+    pub caption: Option<String>,
+}
+
+/// Extracts every `<img>` with a `src` in an HTML document, in document order.
+pub fn extract_images(html: &str) -> Vec<HtmlImage> {
+    let document = Html::parse_document(html);
+    let image_selector = Selector::parse("img[src]").unwrap();
+    let caption_selector = Selector::parse("figcaption").unwrap();
+
+    document
+        .select(&image_selector)
+        .filter_map(|img| {
+            let src = img.value().attr("src")?.trim();
+            if src.is_empty() {
+                return None;
+            }
+            let alt = img
+                .value()
+                .attr("alt")
+                .map(|alt| alt.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|alt| !alt.is_empty());
+            let caption = img
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .find(|ancestor| ancestor.value().name() == "figure")
+                .and_then(|figure| figure.select(&caption_selector).next())
+                .map(|caption| element_text(&caption))
+                .filter(|caption| !caption.is_empty());
+            Some(HtmlImage {
+                src: src.to_string(),
+                alt,
+                caption,
+            })
+        })
+        .collect()
+}
+
+/// Returns the whitespace-normalized text of an element.
+fn element_text(element: &ElementRef) -> String {
+    element
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
 }
@@ -5,7 +5,10 @@
 
 #[cfg(test)]
 mod tests {
-    use anyrag_html::{clean_html, html_to_clean_markdown, url_to_md};
+    use anyrag_html::{
+        clean_html, extract_images, extract_links, extract_tables, html_to_clean_markdown,
+        url_to_md, HtmlImage, HtmlTable, MAX_COLSPAN,
+    };
 
     #[test]
     fn test_clean_html() {
@@ -72,4 +75,103 @@ mod tests {
         let markdown = html_to_clean_markdown(html_content, None);
         assert_eq!(markdown.trim(), expected_markdown);
     }
+
+    #[test]
+    fn test_extract_tables_with_headers() {
+        let html_content = r#"
+        <html><body>
+            <table>
+                <caption>Largest cities</caption>
+                <thead><tr><th>City</th><th>Population</th></tr></thead>
+                <tbody>
+                    <tr><td>Bangkok</td><td>8,305,218</td></tr>
+                    <tr><td colspan="2">Data unavailable</td></tr>
+                </tbody>
+            </table>
+            <table><tr><td>1</td><td>2</td><td>3</td></tr></table>
+            <table><tr><th>Header only</th></tr></table>
+        </body></html>
+        "#;
+
+        let tables = extract_tables(html_content);
+
+        // The header-only table has no data rows and is skipped.
+        assert_eq!(tables.len(), 2);
+        assert_eq!(
+            tables[0],
+            HtmlTable {
+                caption: Some("Largest cities".to_string()),
+                headers: vec!["City".to_string(), "Population".to_string()],
+                rows: vec![
+                    vec!["Bangkok".to_string(), "8,305,218".to_string()],
+                    vec![
+                        "Data unavailable".to_string(),
+                        "Data unavailable".to_string()
+                    ],
+                ],
+            }
+        );
+        // Without a header row, columns get generated names.
+        assert_eq!(tables[1].headers, vec!["column_1", "column_2", "column_3"]);
+        assert_eq!(tables[1].rows, vec![vec!["1", "2", "3"]]);
+    }
+
+    #[test]
+    fn test_extract_tables_clamps_colspan() {
+        let html_content = r#"<table><tr><td colspan="4294967295">Wide</td></tr></table>"#;
+
+        let tables = extract_tables(html_content);
+
+        assert_eq!(tables[0].rows[0].len(), MAX_COLSPAN);
+        assert_eq!(tables[0].headers.len(), MAX_COLSPAN);
+    }
+
+    #[test]
+    fn test_extract_links() {
+        let html_content = r##"
+        <html><body>
+            <a href="/docs">Docs</a>
+            <a href=" https://example.com/about ">About</a>
+            <a href="">Empty</a>
+            <a name="anchor">No href</a>
+            <a href="#top">Top</a>
+        </body></html>
+        "##;
+
+        assert_eq!(
+            extract_links(html_content),
+            vec!["/docs", "https://example.com/about", "#top"]
+        );
+    }
+
+    #[test]
+    fn test_extract_images() {
+        let html_content = r#"
+        <html><body>
+            <figure>
+                <img src="/img/architecture.png" alt="  System
+                    architecture ">
+                <figcaption>Figure 1: How requests flow</figcaption>
+            </figure>
+            <img src="logo.svg">
+            <img src="" alt="Empty">
+        </body></html>
+        "#;
+
+        assert_eq!(
+            extract_images(html_content),
+            vec![
+                HtmlImage {
+                    src: "/img/architecture.png".to_string(),
+                    alt: Some("System architecture".to_string()),
+                    caption: Some("Figure 1: How requests flow".to_string()),
+                },
+                HtmlImage {
+                    src: "logo.svg".to_string(),
+                    alt: None,
+                    caption: None,
+                },
+            ]
+        );
+    }
 }
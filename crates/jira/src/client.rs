@@ -0,0 +1,182 @@
+//! # Jira REST API Client
+//!
+//! A small client for the Jira Cloud search and comment endpoints. It pages through
+//! JQL results with `nextPageToken` and loads the remaining comments of issues whose
+//! embedded comment list was truncated.
+
+use crate::JiraIngestError;
+use reqwest::{Client, Response};
+use serde::Deserialize;
+use tracing::info;
+
+/// The number of issues or comments requested per page.
+const PAGE_SIZE: usize = 100;
+/// The issue fields requested from the search endpoint.
+const ISSUE_FIELDS: &str = "summary,description,status,assignee,labels,updated,comment";
+
+/// An issue as returned by `GET /rest/api/2/search/jql`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JiraIssue {
+    pub key: String,
+    pub fields: JiraIssueFields,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JiraIssueFields {
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub status: Option<JiraNamed>,
+    #[serde(default)]
+    pub assignee: Option<JiraUser>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// When the issue was last updated, e.g. `2024-05-01T10:00:00.000+0000`.
+    #[serde(default)]
+    pub updated: Option<String>,
+    #[serde(default)]
+    pub comment: Option<JiraCommentPage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JiraNamed {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JiraUser {
+    pub display_name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JiraComment {
+    #[serde(default)]
+    pub author: Option<JiraUser>,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub created: String,
+}
+
+/// A page of comments, as embedded in an issue or returned by the comment endpoint.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JiraCommentPage {
+    #[serde(default)]
+    pub comments: Vec<JiraComment>,
+    #[serde(default)]
+    pub total: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResponse {
+    #[serde(default)]
+    issues: Vec<JiraIssue>,
+    #[serde(default)]
+    next_page_token: Option<String>,
+}
+
+/// A Jira Cloud client authenticated with an account email and API token.
+#[derive(Debug, Clone)]
+pub struct JiraClient {
+    http: Client,
+    base_url: String,
+    email: String,
+    api_token: String,
+}
+
+impl JiraClient {
+    /// Creates a client for the Jira site at `base_url`, e.g. `https://acme.atlassian.net`.
+    pub fn new(base_url: &str, email: &str, api_token: &str) -> Self {
+        Self {
+            http: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            email: email.to_string(),
+            api_token: api_token.to_string(),
+        }
+    }
+
+    /// The base URL of the Jira site.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Fetches every issue matching `jql`, with all of its comments.
+    pub async fn search(&self, jql: &str) -> Result<Vec<JiraIssue>, JiraIngestError> {
+        let url = format!("{}/rest/api/2/search/jql", self.base_url);
+        let mut issues = Vec::new();
+        let mut next_page_token: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("jql", jql.to_string()),
+                ("fields", ISSUE_FIELDS.to_string()),
+                ("maxResults", PAGE_SIZE.to_string()),
+            ];
+            if let Some(token) = &next_page_token {
+                query.push(("nextPageToken", token.clone()));
+            }
+
+            let page: SearchResponse = self.get(&url, &query).await?.json().await?;
+            issues.extend(page.issues);
+            next_page_token = page.next_page_token.filter(|token| !token.is_empty());
+            if next_page_token.is_none() {
+                break;
+            }
+        }
+
+        for issue in &mut issues {
+            let Some(embedded) = &issue.fields.comment else {
+                continue;
+            };
+            if embedded.comments.len() < embedded.total {
+                let comments = self.comments(&issue.key).await?;
+                issue.fields.comment = Some(comments);
+            }
+        }
+
+        info!("Fetched {} Jira issues for JQL: {jql}", issues.len());
+        Ok(issues)
+    }
+
+    /// Fetches every comment of an issue.
+    async fn comments(&self, issue_key: &str) -> Result<JiraCommentPage, JiraIngestError> {
+        let url = format!("{}/rest/api/2/issue/{issue_key}/comment", self.base_url);
+        let mut all = JiraCommentPage::default();
+        loop {
+            let query = vec![
+                ("startAt", all.comments.len().to_string()),
+                ("maxResults", PAGE_SIZE.to_string()),
+            ];
+            let page: JiraCommentPage = self.get(&url, &query).await?.json().await?;
+            all.total = page.total;
+            let page_len = page.comments.len();
+            all.comments.extend(page.comments);
+            if page_len == 0 || all.comments.len() >= all.total {
+                break;
+            }
+        }
+        Ok(all)
+    }
+
+    async fn get(&self, url: &str, query: &[(&str, String)]) -> Result<Response, JiraIngestError> {
+        let response = self
+            .http
+            .get(url)
+            .basic_auth(&self.email, Some(&self.api_token))
+            .query(query)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            return Err(JiraIngestError::Api(format!(
+                "{url} returned status {status}: {body}"
+            )));
+        }
+        Ok(response)
+    }
+}
@@ -0,0 +1,334 @@
+//! # `anyrag-jira`: Jira Ingestion Plugin
+//!
+//! This crate provides the logic for ingesting Jira issues as a self-contained plugin
+//! for the `anyrag` ecosystem. It implements the `Ingestor` trait from the core
+//! `anyrag` library.
+//!
+//! Each issue matching a JQL query is stored as one document together with its
+//! comments, and its status, assignee, and labels are stored in `content_metadata` so
+//! results can be filtered by them.
+
+use anyhow::anyhow;
+use anyrag::ingest::{
+    content_hash, find_duplicate_document, record_revision, state_manager, IngestError,
+    IngestionResult, Ingestor,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use client::{JiraClient, JiraIssue};
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::info;
+use turso::{params, Connection, Database};
+use uuid::Uuid;
+
+pub mod client;
+
+/// The `content_metadata` property holding the status of an issue.
+pub const STATUS_PROPERTY: &str = "status";
+/// The `content_metadata` property holding the assignee of an issue.
+pub const ASSIGNEE_PROPERTY: &str = "assignee";
+/// The `content_metadata` property holding a label of an issue, one row per label.
+pub const LABEL_PROPERTY: &str = "label";
+
+/// The `project_id` under which `state_manager` keeps the last seen update time of each
+/// JQL query.
+const SYNC_STATE_PROJECT: &str = "jira";
+const PROPERTY_METADATA_TYPE: &str = "PROPERTY";
+/// The format of the `updated` field of Jira issues.
+const JIRA_DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f%z";
+/// The date format JQL accepts in `updated >=` clauses.
+const JQL_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Custom error types for the Jira ingestion process.
+#[derive(Error, Debug)]
+pub enum JiraIngestError {
+    #[error("Database connection failed: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Failed to call the Jira API: {0}")]
+    Fetch(#[from] reqwest::Error),
+    #[error("Jira API returned an error: {0}")]
+    Api(String),
+    #[error("Source deserialization failed: {0}")]
+    SourceDeserialization(#[from] serde_json::Error),
+    #[error("Failed to access the sync state: {0}")]
+    State(String),
+}
+
+/// A helper to convert the specific `JiraIngestError` into the generic `anyrag::ingest::IngestError`.
+impl From<JiraIngestError> for IngestError {
+    fn from(err: JiraIngestError) -> Self {
+        match err {
+            JiraIngestError::Database(e) => IngestError::Database(e),
+            JiraIngestError::Fetch(e) => IngestError::Fetch(e.to_string()),
+            JiraIngestError::Api(e) => IngestError::Fetch(e),
+            JiraIngestError::SourceDeserialization(e) => {
+                IngestError::Parse(format!("Invalid source JSON for Jira ingest: {e}"))
+            }
+            JiraIngestError::State(e) => IngestError::Internal(anyhow!(e)),
+        }
+    }
+}
+
+/// Defines the structure of the JSON string passed to the `ingest` method.
+#[derive(Deserialize)]
+struct JiraSource {
+    jql: String,
+    /// Fetches only the issues updated since the last incremental sync of this query.
+    #[serde(default)]
+    incremental: bool,
+}
+
+/// A document prepared from a Jira issue before it is stored.
+struct IssueDocument {
+    source_url: String,
+    title: String,
+    content: String,
+    /// `(property, value)` pairs stored in `content_metadata`.
+    properties: Vec<(&'static str, String)>,
+}
+
+/// The `Ingestor` implementation for Jira issues.
+pub struct JiraIngestor {
+    db: Database,
+    client: JiraClient,
+}
+
+impl JiraIngestor {
+    /// Creates a new `JiraIngestor` for the Jira site at `base_url`, authenticated with
+    /// an account email and API token.
+    pub fn new(db: &Database, base_url: &str, email: &str, api_token: &str) -> Self {
+        Self {
+            db: db.clone(),
+            client: JiraClient::new(base_url, email, api_token),
+        }
+    }
+}
+
+#[async_trait]
+impl Ingestor for JiraIngestor {
+    /// Fetches the issues matching a JQL query and stores each as a document.
+    ///
+    /// The `source` argument is expected to be a JSON string with a `jql` key, for
+    /// example: `{"jql": "project = OPS ORDER BY updated"}`. With `"incremental": true`,
+    /// an `updated >=` clause limits the query to issues updated since the last
+    /// incremental sync of the same JQL.
+    async fn ingest(
+        &self,
+        source: &str,
+        owner_id: Option<&str>,
+    ) -> Result<IngestionResult, IngestError> {
+        let jira_source: JiraSource =
+            serde_json::from_str(source).map_err(JiraIngestError::from)?;
+        let jql = &jira_source.jql;
+
+        let last_updated = match jira_source.incremental {
+            true => state_manager::read_last_timestamp(SYNC_STATE_PROJECT, jql)
+                .map_err(|e| JiraIngestError::State(e.to_string()))?,
+            false => None,
+        };
+        let effective_jql = match &last_updated {
+            Some(since) => incremental_jql(jql, since),
+            None => jql.clone(),
+        };
+
+        info!("Searching Jira issues with JQL: {effective_jql}");
+        let issues = self.client.search(&effective_jql).await?;
+        let newest_update = issues
+            .iter()
+            .filter_map(|issue| issue.fields.updated.as_deref())
+            .filter_map(parse_jira_datetime)
+            .max();
+
+        let documents: Vec<IssueDocument> = issues
+            .iter()
+            .map(|issue| issue_document(self.client.base_url(), issue))
+            .collect();
+
+        let mut conn = self.db.connect().map_err(JiraIngestError::from)?;
+        let tx = conn.transaction().await.map_err(JiraIngestError::from)?;
+        let mut new_document_ids = Vec::new();
+
+        for document in documents {
+            let document_id =
+                Uuid::new_v5(&Uuid::NAMESPACE_URL, document.source_url.as_bytes()).to_string();
+            let hash = content_hash(&document.content);
+            if find_duplicate_document(&tx, owner_id, &hash)
+                .await
+                .map_err(JiraIngestError::from)?
+                .is_some()
+            {
+                info!("Skipping unchanged Jira issue: {}", document.source_url);
+                continue;
+            }
+
+            // The `source_url` is the issue's browse link, so an updated issue replaces
+            // its previous version.
+            record_revision(&tx, &document.source_url, &document.content)
+                .await
+                .map_err(JiraIngestError::from)?;
+            let changes = tx
+                .execute(
+                    "INSERT INTO documents (id, owner_id, source_url, title, content, content_hash)
+                     VALUES (?, ?, ?, ?, ?, ?)
+                     ON CONFLICT(source_url) DO UPDATE SET
+                     title = excluded.title,
+                     content = excluded.content,
+                     content_hash = excluded.content_hash",
+                    params![
+                        document_id.clone(),
+                        owner_id,
+                        document.source_url.clone(),
+                        document.title.clone(),
+                        document.content.clone(),
+                        hash
+                    ],
+                )
+                .await
+                .map_err(JiraIngestError::from)?;
+            store_issue_metadata(&tx, &document_id, owner_id, &document.properties)
+                .await
+                .map_err(JiraIngestError::from)?;
+
+            if changes > 0 {
+                new_document_ids.push(document_id);
+            }
+        }
+
+        tx.commit().await.map_err(JiraIngestError::from)?;
+
+        if let (true, Some(newest)) = (jira_source.incremental, newest_update) {
+            state_manager::write_last_timestamp(SYNC_STATE_PROJECT, jql, &newest.to_rfc3339())
+                .map_err(|e| JiraIngestError::State(e.to_string()))?;
+        }
+
+        info!(
+            "Ingested {} new or updated Jira issues.",
+            new_document_ids.len()
+        );
+
+        Ok(IngestionResult {
+            documents_added: new_document_ids.len(),
+            source: jql.to_string(),
+            document_ids: new_document_ids,
+            metadata: None,
+        })
+    }
+}
+
+/// Restricts a JQL query to issues updated at or after `since`, an RFC 3339 timestamp.
+///
+/// Any `ORDER BY` clause is kept at the end, where JQL requires it. JQL compares dates
+/// in the timezone of the Jira account, so the account should use UTC.
+fn incremental_jql(jql: &str, since: &str) -> String {
+    let Some(since) = DateTime::parse_from_rfc3339(since)
+        .ok()
+        .map(|since| since.with_timezone(&Utc).format(JQL_DATETIME_FORMAT))
+    else {
+        return jql.to_string();
+    };
+    let lowercase = jql.to_ascii_lowercase();
+    let (filter, order_by) = match lowercase.rfind("order by") {
+        Some(index) => (jql[..index].trim(), Some(jql[index..].trim())),
+        None => (jql.trim(), None),
+    };
+    let filtered = match filter.is_empty() {
+        true => format!("updated >= \"{since}\""),
+        false => format!("({filter}) AND updated >= \"{since}\""),
+    };
+    match order_by {
+        Some(order_by) => format!("{filtered} {order_by}"),
+        None => filtered,
+    }
+}
+
+fn parse_jira_datetime(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_str(value, JIRA_DATETIME_FORMAT)
+        .ok()
+        .map(|datetime| datetime.with_timezone(&Utc))
+}
+
+/// Builds the document of an issue: its summary, fields, description, and comments.
+fn issue_document(base_url: &str, issue: &JiraIssue) -> IssueDocument {
+    let fields = &issue.fields;
+    let title = format!("{}: {}", issue.key, fields.summary);
+    let status = fields.status.as_ref().map(|status| status.name.clone());
+    let assignee = fields
+        .assignee
+        .as_ref()
+        .map(|assignee| assignee.display_name.clone());
+
+    let mut content = format!(
+        "{title}\nStatus: {}\nAssignee: {}\nLabels: {}",
+        status.as_deref().unwrap_or("None"),
+        assignee.as_deref().unwrap_or("Unassigned"),
+        fields.labels.join(", ")
+    );
+    if let Some(description) = fields.description.as_deref().map(str::trim) {
+        if !description.is_empty() {
+            content.push_str(&format!("\n\n{description}"));
+        }
+    }
+    let comments = fields
+        .comment
+        .as_ref()
+        .map(|page| page.comments.as_slice())
+        .unwrap_or_default();
+    if !comments.is_empty() {
+        content.push_str("\n\nComments:");
+        for comment in comments {
+            let author = comment
+                .author
+                .as_ref()
+                .map(|author| author.display_name.as_str())
+                .unwrap_or("Unknown");
+            let created = comment.created.get(..10).unwrap_or(&comment.created);
+            content.push_str(&format!("\n[{created}] {author}: {}", comment.body.trim()));
+        }
+    }
+
+    let mut properties = Vec::new();
+    properties.extend(status.map(|status| (STATUS_PROPERTY, status)));
+    properties.extend(assignee.map(|assignee| (ASSIGNEE_PROPERTY, assignee)));
+    properties.extend(
+        fields
+            .labels
+            .iter()
+            .map(|label| (LABEL_PROPERTY, label.clone())),
+    );
+
+    IssueDocument {
+        source_url: format!("{base_url}/browse/{}", issue.key),
+        title,
+        content,
+        properties,
+    }
+}
+
+/// Replaces the property metadata of an issue document.
+async fn store_issue_metadata(
+    conn: &Connection,
+    document_id: &str,
+    owner_id: Option<&str>,
+    properties: &[(&'static str, String)],
+) -> Result<(), turso::Error> {
+    conn.execute(
+        "DELETE FROM content_metadata WHERE document_id = ?",
+        params![document_id],
+    )
+    .await?;
+    for (property, value) in properties {
+        conn.execute(
+            "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_subtype, metadata_value) VALUES (?, ?, ?, ?, ?)",
+            params![
+                document_id,
+                owner_id,
+                PROPERTY_METADATA_TYPE,
+                *property,
+                value.clone()
+            ],
+        )
+        .await?;
+    }
+    Ok(())
+}
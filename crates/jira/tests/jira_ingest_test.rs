@@ -0,0 +1,202 @@
+//! # Jira Crate Tests
+//!
+//! This file contains integration tests for the `anyrag-jira` crate, running the
+//! ingestor against a mock Jira REST API.
+
+use anyhow::Result;
+use anyrag::ingest::Ingestor;
+use anyrag_jira::{JiraIngestor, ASSIGNEE_PROPERTY, LABEL_PROPERTY, STATUS_PROPERTY};
+use anyrag_test_utils::TestSetup;
+use serde_json::json;
+use turso::params;
+use wiremock::matchers::{method, path, query_param, query_param_is_missing};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const SEARCH_PATH: &str = "/rest/api/2/search/jql";
+const JQL: &str = "project = OPS ORDER BY updated";
+/// The file `state_manager` writes the Jira sync state to.
+const SYNC_STATE_FILE: &str = ".anyrag_sync_state_jira.json";
+
+/// Mounts a two-page search result. The first issue has more comments than the search
+/// embeds, so they are loaded from the comment endpoint.
+async fn mount_search(server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path(SEARCH_PATH))
+        .and(query_param("jql", JQL))
+        .and(query_param_is_missing("nextPageToken"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "issues": [{
+                "key": "OPS-1",
+                "fields": {
+                    "summary": "Database failover runbook",
+                    "description": "Promote the replica, then repoint the app.",
+                    "status": { "name": "Done" },
+                    "assignee": { "displayName": "Alice" },
+                    "labels": ["runbook", "database"],
+                    "updated": "2024-05-01T10:00:00.000+0000",
+                    "comment": {
+                        "comments": [
+                            { "author": { "displayName": "Bob" }, "body": "Tested in staging.", "created": "2024-04-30T09:00:00.000+0000" }
+                        ],
+                        "total": 2
+                    }
+                }
+            }],
+            "nextPageToken": "page-2"
+        })))
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(SEARCH_PATH))
+        .and(query_param("nextPageToken", "page-2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "issues": [{
+                "key": "OPS-2",
+                "fields": {
+                    "summary": "Rotate TLS certificates",
+                    "status": { "name": "To Do" },
+                    "assignee": null,
+                    "labels": [],
+                    "updated": "2024-05-02T08:30:00.000+0000"
+                }
+            }],
+            "isLast": true
+        })))
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/rest/api/2/issue/OPS-1/comment"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "comments": [
+                { "author": { "displayName": "Bob" }, "body": "Tested in staging.", "created": "2024-04-30T09:00:00.000+0000" },
+                { "author": { "displayName": "Alice" }, "body": "Rolled out to production.", "created": "2024-05-01T10:00:00.000+0000" }
+            ],
+            "total": 2
+        })))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_jira_ingestor_stores_issues_with_comments_and_metadata() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    mount_search(&server).await;
+    let setup = TestSetup::new().await?;
+    let ingestor = JiraIngestor::new(&setup.db, &server.uri(), "bot@example.com", "token");
+    let source = json!({ "jql": JQL }).to_string();
+
+    // --- Act ---
+    let result = ingestor.ingest(&source, Some("jira-user")).await?;
+
+    // --- Assert ---
+    assert_eq!(result.documents_added, 2);
+
+    let conn = setup.db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT id, title, content FROM documents WHERE source_url = ?",
+            params![format!("{}/browse/OPS-1", server.uri())],
+        )
+        .await?;
+    let row = rows.next().await?.expect("OPS-1 not found");
+    let document_id = row.get::<String>(0)?;
+    assert_eq!(row.get::<String>(1)?, "OPS-1: Database failover runbook");
+    assert_eq!(
+        row.get::<String>(2)?,
+        "OPS-1: Database failover runbook\nStatus: Done\nAssignee: Alice\nLabels: runbook, database\n\n\
+         Promote the replica, then repoint the app.\n\n\
+         Comments:\n[2024-04-30] Bob: Tested in staging.\n[2024-05-01] Alice: Rolled out to production."
+    );
+
+    let mut rows = conn
+        .query(
+            "SELECT metadata_subtype, metadata_value FROM content_metadata
+             WHERE document_id = ? AND metadata_type = 'PROPERTY'
+             ORDER BY metadata_subtype, metadata_value",
+            params![document_id],
+        )
+        .await?;
+    let mut properties = Vec::new();
+    while let Some(row) = rows.next().await? {
+        properties.push((row.get::<String>(0)?, row.get::<String>(1)?));
+    }
+    let expected = vec![
+        (ASSIGNEE_PROPERTY.to_string(), "Alice".to_string()),
+        (LABEL_PROPERTY.to_string(), "database".to_string()),
+        (LABEL_PROPERTY.to_string(), "runbook".to_string()),
+        (STATUS_PROPERTY.to_string(), "Done".to_string()),
+    ];
+    assert_eq!(properties, expected);
+
+    let mut rows = conn
+        .query(
+            "SELECT content FROM documents WHERE source_url = ?",
+            params![format!("{}/browse/OPS-2", server.uri())],
+        )
+        .await?;
+    let row = rows.next().await?.expect("OPS-2 not found");
+    assert_eq!(
+        row.get::<String>(0)?,
+        "OPS-2: Rotate TLS certificates\nStatus: To Do\nAssignee: Unassigned\nLabels: "
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_jira_incremental_sync_adds_updated_clause() -> Result<()> {
+    // --- Arrange ---
+    let _ = std::fs::remove_file(SYNC_STATE_FILE);
+    let server = MockServer::start().await;
+    mount_search(&server).await;
+    // The second sync must only ask for issues updated since the newest one seen.
+    Mock::given(method("GET"))
+        .and(path(SEARCH_PATH))
+        .and(query_param(
+            "jql",
+            "(project = OPS) AND updated >= \"2024-05-02 08:30\" ORDER BY updated",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "issues": [] })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let setup = TestSetup::new().await?;
+    let ingestor = JiraIngestor::new(&setup.db, &server.uri(), "bot@example.com", "token");
+    let source = json!({ "jql": JQL, "incremental": true }).to_string();
+
+    // --- Act ---
+    let first = ingestor.ingest(&source, None).await?;
+    let second = ingestor.ingest(&source, None).await?;
+    std::fs::remove_file(SYNC_STATE_FILE)?;
+
+    // --- Assert ---
+    assert_eq!(first.documents_added, 2);
+    assert_eq!(second.documents_added, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_jira_ingestor_reports_api_errors() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(SEARCH_PATH))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "errorMessages": ["Field 'projekt' does not exist."]
+        })))
+        .mount(&server)
+        .await;
+    let setup = TestSetup::new().await?;
+    let ingestor = JiraIngestor::new(&setup.db, &server.uri(), "bot@example.com", "token");
+
+    // --- Act ---
+    let source = json!({ "jql": "projekt = OPS" }).to_string();
+    let result = ingestor.ingest(&source, None).await;
+
+    // --- Assert ---
+    let err = result.expect_err("An invalid JQL should fail the ingestion");
+    assert!(err.to_string().contains("does not exist"));
+    Ok(())
+}
@@ -225,6 +225,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let search_options = HybridSearchOptions {
         query_text: question.to_string(),
         owner_id: Some(user.id.clone()),
+        org_id: None,
+        collection: None,
         limit: 5,
         prompts: HybridSearchPrompts {
             analysis_system_prompt: QUERY_ANALYSIS_SYSTEM_PROMPT,
@@ -0,0 +1,201 @@
+//! # Answer Cache
+//!
+//! Support teams ask the same questions over and over. This module stores synthesized
+//! knowledge answers in the `answer_cache` table together with the embedding of their
+//! question and the version of the documents they were answered from. A later question
+//! whose embedding is close enough to a cached one is answered from the cache, as long
+//! as the documents visible to the asker are unchanged and the entry has not expired.
+
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::info;
+use turso::{params, Database, Value as TursoValue};
+
+/// Custom error types for the answer cache.
+#[derive(Error, Debug)]
+pub enum AnswerCacheError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+}
+
+/// Configuration for caching knowledge answers.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct AnswerCacheConfig {
+    /// The cosine similarity a question's embedding must reach with a cached question's
+    /// to be served its answer.
+    #[serde(default = "default_similarity_threshold")]
+    pub similarity_threshold: f32,
+    /// How long an answer is served from the cache, in seconds.
+    #[serde(default = "default_ttl_seconds")]
+    pub ttl_seconds: u64,
+}
+
+impl Default for AnswerCacheConfig {
+    fn default() -> Self {
+        Self {
+            similarity_threshold: default_similarity_threshold(),
+            ttl_seconds: default_ttl_seconds(),
+        }
+    }
+}
+
+fn default_similarity_threshold() -> f32 {
+    0.95
+}
+
+fn default_ttl_seconds() -> u64 {
+    24 * 60 * 60
+}
+
+/// The answers a question may be served: those given to the same owner and
+/// organization, from the same database, under the same instruction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnswerCacheScope {
+    pub owner_id: Option<String>,
+    pub org_id: Option<String>,
+    /// The project database the answer was searched in.
+    pub db: Option<String>,
+    pub instruction: Option<String>,
+}
+
+impl AnswerCacheScope {
+    fn columns(&self) -> [&str; 4] {
+        [
+            self.owner_id.as_deref().unwrap_or_default(),
+            self.org_id.as_deref().unwrap_or_default(),
+            self.db.as_deref().unwrap_or_default(),
+            self.instruction.as_deref().unwrap_or_default(),
+        ]
+    }
+}
+
+/// An answer served from the cache.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CachedAnswer {
+    pub id: i64,
+    /// The question the answer was synthesized for.
+    pub question: String,
+    pub answer: String,
+    /// The cosine similarity of the asked question with `question`.
+    pub similarity: f32,
+    pub created_at: String,
+}
+
+/// Returns the closest cached answer to a question, if it reaches the configured
+/// similarity.
+///
+/// Only entries of the `scope` that were answered from `corpus_version` and have not
+/// expired are considered. The other entries of the scope are deleted on the way, since
+/// they can no longer be served.
+pub async fn find_cached_answer(
+    db: &Database,
+    scope: &AnswerCacheScope,
+    corpus_version: &str,
+    embedding: &[f32],
+    config: &AnswerCacheConfig,
+) -> Result<Option<CachedAnswer>, AnswerCacheError> {
+    let conn = db.connect()?;
+    let [owner_id, org_id, db_name, instruction] = scope.columns();
+    let now = Utc::now().to_rfc3339();
+
+    let deleted = conn
+        .execute(
+            "DELETE FROM answer_cache WHERE owner_id = ? AND org_id = ? AND db = ? AND (corpus_version != ? OR expires_at <= ?)",
+            params![owner_id, org_id, db_name, corpus_version, now.as_str()],
+        )
+        .await?;
+    if deleted > 0 {
+        info!("Evicted {deleted} stale answers from the answer cache.");
+    }
+
+    let mut rows = conn
+        .query(
+            "SELECT id, question, answer, embedding, created_at FROM answer_cache WHERE owner_id = ? AND org_id = ? AND db = ? AND instruction = ?",
+            params![owner_id, org_id, db_name, instruction],
+        )
+        .await?;
+    let mut best: Option<CachedAnswer> = None;
+    while let Some(row) = rows.next().await? {
+        let TursoValue::Blob(bytes) = row.get_value(3)? else {
+            continue;
+        };
+        let similarity = cosine_similarity(embedding, &vector_from_bytes(&bytes));
+        if similarity < config.similarity_threshold
+            || best.as_ref().is_some_and(|b| b.similarity >= similarity)
+        {
+            continue;
+        }
+        best = Some(CachedAnswer {
+            id: row.get(0)?,
+            question: row.get(1)?,
+            answer: row.get(2)?,
+            similarity,
+            created_at: row.get(4)?,
+        });
+    }
+    Ok(best)
+}
+
+/// Stores an answer to be served to near-duplicate questions of the same scope until
+/// the documents change or the configured time to live passes.
+pub async fn store_answer(
+    db: &Database,
+    scope: &AnswerCacheScope,
+    corpus_version: &str,
+    question: &str,
+    embedding: &[f32],
+    answer: &str,
+    config: &AnswerCacheConfig,
+) -> Result<(), AnswerCacheError> {
+    let conn = db.connect()?;
+    let [owner_id, org_id, db_name, instruction] = scope.columns();
+    let created_at = Utc::now();
+    let expires_at = created_at + Duration::seconds(config.ttl_seconds as i64);
+    let embedding_bytes: Vec<u8> = embedding.iter().flat_map(|x| x.to_ne_bytes()).collect();
+    conn.execute(
+        "INSERT INTO answer_cache (owner_id, org_id, db, instruction, question, embedding, answer, corpus_version, created_at, expires_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            owner_id,
+            org_id,
+            db_name,
+            instruction,
+            question,
+            embedding_bytes.as_slice(),
+            answer,
+            corpus_version,
+            created_at.to_rfc3339(),
+            expires_at.to_rfc3339()
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Deletes every cached answer, returning how many were deleted.
+pub async fn purge_answer_cache(db: &Database) -> Result<u64, AnswerCacheError> {
+    let conn = db.connect()?;
+    let deleted = conn.execute("DELETE FROM answer_cache", ()).await?;
+    info!("Purged {deleted} answers from the answer cache.");
+    Ok(deleted)
+}
+
+fn vector_from_bytes(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
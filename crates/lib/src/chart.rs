@@ -0,0 +1,170 @@
+//! # Chart Output
+//!
+//! Dashboards plot query results rather than read prose answers. With `output: "chart"`,
+//! the formatting call writes the results as a `ChartSpec`: the labels of the x-axis (or
+//! of the slices of a pie) and one or more named series of numbers aligned with them.
+//! The spec is validated, and repaired once, like any output schema.
+
+use crate::prompts::tasks::{
+    CHART_OUTPUT_SYSTEM_PROMPT, STRUCTURED_OUTPUT_REPAIR_USER_PROMPT, STRUCTURED_OUTPUT_USER_PROMPT,
+};
+use crate::structured_output::parse_structured_output;
+use crate::types::{ExecutePromptOptions, PipelineStage, PipelineStep};
+use crate::{PromptClient, PromptError};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// The instruction the results are charted with, when the caller gives none.
+const DEFAULT_CHART_INSTRUCTION: &str =
+    "Chart the input data in the way that best answers the prompt.";
+
+/// The kind of chart a spec is drawn as.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartType {
+    Bar,
+    Line,
+    Area,
+    Pie,
+}
+
+/// A named series of values, one for each label of the chart.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ChartSeries {
+    pub name: String,
+    pub data: Vec<f64>,
+}
+
+/// A chart of query results, ready to be plotted.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ChartSpec {
+    pub chart_type: ChartType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The categories or x-axis values, in order.
+    pub labels: Vec<String>,
+    pub series: Vec<ChartSeries>,
+}
+
+/// The JSON Schema a chart spec is generated against.
+pub fn chart_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "chart_type": { "type": "string", "enum": ["bar", "line", "area", "pie"] },
+            "title": { "type": "string" },
+            "labels": { "type": "array", "items": { "type": "string" } },
+            "series": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "data": { "type": "array", "items": { "type": "number" } }
+                    },
+                    "required": ["name", "data"],
+                    "additionalProperties": false
+                }
+            }
+        },
+        "required": ["chart_type", "labels", "series"],
+        "additionalProperties": false
+    })
+}
+
+/// Parses an AI response as a chart spec.
+///
+/// Besides conforming to `chart_schema`, the spec must have at least one series, every
+/// series must have one value per label, and a pie chart must have a single series. On
+/// failure, the returned list describes every problem found.
+pub fn parse_chart_spec(raw: &str) -> Result<ChartSpec, Vec<String>> {
+    let value = parse_structured_output(raw, &chart_schema())?;
+    let spec: ChartSpec =
+        serde_json::from_value(value).map_err(|e| vec![format!("Invalid chart spec: {e}")])?;
+
+    let mut errors = Vec::new();
+    if spec.series.is_empty() {
+        errors.push("$.series: at least one series is required".to_string());
+    }
+    if spec.chart_type == ChartType::Pie && spec.series.len() > 1 {
+        errors.push("$.series: a pie chart has a single series".to_string());
+    }
+    for (i, series) in spec.series.iter().enumerate() {
+        if series.data.len() != spec.labels.len() {
+            errors.push(format!(
+                "$.series[{i}].data: expected {} values, one for each label, found {}",
+                spec.labels.len(),
+                series.data.len()
+            ));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(spec)
+}
+
+impl PromptClient {
+    /// Formats the content into a chart spec, retrying once with the validation errors
+    /// when the first response is not a valid spec. Returns the spec as JSON.
+    pub(crate) async fn format_chart_response(
+        &self,
+        content: &str,
+        options: &ExecutePromptOptions,
+        trace: &mut Vec<PipelineStep>,
+    ) -> Result<String, PromptError> {
+        let schema_str = serde_json::to_string_pretty(&chart_schema())?;
+        let instruction = options
+            .instruction
+            .as_deref()
+            .unwrap_or(DEFAULT_CHART_INSTRUCTION);
+
+        let system_prompt = CHART_OUTPUT_SYSTEM_PROMPT;
+        let user_prompt = STRUCTURED_OUTPUT_USER_PROMPT
+            .replace("{prompt}", &options.prompt)
+            .replace("{instruction}", instruction)
+            .replace("{schema}", &schema_str)
+            .replace("{content}", content);
+
+        info!(system_prompt = %system_prompt, user_prompt = %user_prompt, "--> Sending prompts to AI Provider for chart formatting");
+        let started = Instant::now();
+        let response = self
+            .ai_provider
+            .generate(system_prompt, &user_prompt)
+            .await?;
+        trace.push(PipelineStep::finished(
+            PipelineStage::FormattingPrompt,
+            started,
+            json!({ "system_prompt": system_prompt, "user_prompt": user_prompt, "response": response }),
+        ));
+
+        let errors = match parse_chart_spec(&response) {
+            Ok(spec) => return Ok(serde_json::to_string(&spec)?),
+            Err(errors) => errors,
+        };
+
+        warn!(
+            "[format_chart_response] Output is not a valid chart spec, retrying once: {errors:?}"
+        );
+        let repair_prompt = STRUCTURED_OUTPUT_REPAIR_USER_PROMPT
+            .replace("{schema}", &schema_str)
+            .replace("{output}", &response)
+            .replace("{errors}", &errors.join("\n"));
+        let started = Instant::now();
+        let repaired = self
+            .ai_provider
+            .generate(system_prompt, &repair_prompt)
+            .await?;
+        trace.push(PipelineStep::finished(
+            PipelineStage::FormattingPrompt,
+            started,
+            json!({ "system_prompt": system_prompt, "user_prompt": repair_prompt, "response": repaired }),
+        ));
+
+        let spec = parse_chart_spec(&repaired)
+            .map_err(|errors| PromptError::OutputSchemaViolation(errors.join("; ")))?;
+        Ok(serde_json::to_string(&spec)?)
+    }
+}
@@ -0,0 +1,143 @@
+//! # Conversation Sessions
+//!
+//! This module provides the `ChatClient`, which persists conversation turns in the
+//! `conversations` table and rewrites follow-up questions (e.g., "what about last month?")
+//! into standalone queries so they can be used for retrieval.
+
+use crate::{
+    errors::PromptError,
+    providers::{ai::AiProvider, db::sqlite::SqliteProvider},
+    types::{ChatMessage, ChatRole},
+};
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{debug, info};
+use turso::params;
+
+/// Custom error types for conversation sessions.
+#[derive(Error, Debug)]
+pub enum ChatError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Follow-up rewriting failed: {0}")]
+    Rewrite(#[from] PromptError),
+    #[error("Unknown chat role stored for session: {0}")]
+    InvalidRole(String),
+}
+
+/// Formats conversation history into a plain-text transcript for prompts.
+pub fn format_chat_history(history: &[ChatMessage]) -> String {
+    history
+        .iter()
+        .map(|message| format!("{}: {}", message.role.as_str(), message.content))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A client for storing and resolving multi-turn conversations.
+pub struct ChatClient {
+    ai_provider: Box<dyn AiProvider>,
+    sqlite_provider: Arc<SqliteProvider>,
+}
+
+impl ChatClient {
+    pub fn new(ai_provider: Box<dyn AiProvider>, sqlite_provider: Arc<SqliteProvider>) -> Self {
+        Self {
+            ai_provider,
+            sqlite_provider,
+        }
+    }
+
+    /// Loads the most recent messages of a session, oldest first.
+    pub async fn load_history(
+        &self,
+        session_id: &str,
+        owner_id: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<ChatMessage>, ChatError> {
+        let conn = self.sqlite_provider.db.connect()?;
+        let mut params: Vec<turso::Value> = vec![session_id.into()];
+
+        let mut sql = "SELECT role, content FROM conversations WHERE session_id = ?".to_string();
+        if let Some(id) = owner_id {
+            sql.push_str(" AND owner_id = ?");
+            params.push(id.into());
+        } else {
+            sql.push_str(" AND owner_id IS NULL");
+        }
+        sql.push_str(&format!(" ORDER BY id DESC LIMIT {limit}"));
+
+        let mut rows = conn.query(&sql, params).await?;
+        let mut history = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let role: String = row.get(0)?;
+            let content: String = row.get(1)?;
+            let role = match role.as_str() {
+                "user" => ChatRole::User,
+                "assistant" => ChatRole::Assistant,
+                _ => return Err(ChatError::InvalidRole(role)),
+            };
+            history.push(ChatMessage { role, content });
+        }
+        history.reverse();
+
+        debug!(
+            "Loaded {} message(s) for session '{}'.",
+            history.len(),
+            session_id
+        );
+        Ok(history)
+    }
+
+    /// Appends a message to a session.
+    pub async fn append_message(
+        &self,
+        session_id: &str,
+        owner_id: Option<&str>,
+        message: &ChatMessage,
+    ) -> Result<(), ChatError> {
+        let conn = self.sqlite_provider.db.connect()?;
+        conn.execute(
+            "INSERT INTO conversations (session_id, owner_id, role, content) VALUES (?, ?, ?, ?)",
+            params![
+                session_id,
+                owner_id.map(|s| s.to_string()),
+                message.role.as_str(),
+                message.content.as_str()
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Rewrites a follow-up message into a standalone query using the conversation history.
+    ///
+    /// If there is no history, the message is already standalone and is returned as-is.
+    pub async fn rewrite_follow_up(
+        &self,
+        history: &[ChatMessage],
+        message: &str,
+        system_prompt: &str,
+        user_prompt_template: &str,
+    ) -> Result<String, ChatError> {
+        if history.is_empty() {
+            return Ok(message.to_string());
+        }
+
+        let user_prompt = user_prompt_template
+            .replace("{history}", &format_chat_history(history))
+            .replace("{prompt}", message);
+        let response = self
+            .ai_provider
+            .generate(system_prompt, &user_prompt)
+            .await?;
+        let standalone_query = response.trim();
+
+        if standalone_query.is_empty() {
+            return Ok(message.to_string());
+        }
+
+        info!("Rewrote follow-up '{message}' into standalone query '{standalone_query}'.");
+        Ok(standalone_query.to_string())
+    }
+}
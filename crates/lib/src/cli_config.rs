@@ -0,0 +1,171 @@
+//! # Command-Line Configuration
+//!
+//! `anyrag-cli` and `gof` read their settings from a TOML file, by default
+//! `~/.config/anyrag/config.toml`, so that the model endpoints and database paths do not
+//! have to be exported before every command. API keys are kept in the system keyring
+//! instead of the file.
+//!
+//! The environment still wins: `apply_to_env` only sets the variables that are not set
+//! yet, and the command-line flags read from those variables override both.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::info;
+
+/// The environment variable naming a configuration file to use instead of the default.
+pub const CONFIG_FILE_ENV: &str = "ANYRAG_CONFIG";
+
+/// The keyring service the secret settings are stored under.
+const KEYRING_SERVICE: &str = "anyrag-cli";
+
+/// The settings, each with the environment variable that overrides it.
+pub const CONFIG_KEYS: &[(&str, &str)] = &[
+    ("db_path", "ANYRAG_DB_PATH"),
+    ("ai_api_url", "LOCAL_AI_API_URL"),
+    ("ai_model", "AI_MODEL"),
+    ("ai_api_key", "AI_API_KEY"),
+    ("embedding_api_url", "EMBEDDINGS_API_URL"),
+    ("embedding_model", "EMBEDDINGS_MODEL"),
+];
+
+/// The settings kept in the keyring rather than in the file.
+pub const SECRET_KEYS: &[&str] = &["ai_api_key"];
+
+/// Custom error types for the command-line configuration.
+#[derive(Error, Debug)]
+pub enum CliConfigError {
+    #[error("Failed to access the configuration file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid configuration file: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("Failed to write the configuration file: {0}")]
+    Serialize(#[from] toml::ser::Error),
+    #[error("Keyring error: {0}")]
+    Keyring(#[from] keyring::Error),
+    #[error("Unknown setting '{0}'. Known settings: {known}", known = known_keys())]
+    UnknownKey(String),
+    #[error("Could not locate the configuration directory. Set HOME or {CONFIG_FILE_ENV}.")]
+    NoConfigDir,
+}
+
+/// The settings stored in the configuration file.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CliConfig {
+    /// The database commands use when no `--db-path` is given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub db_path: Option<String>,
+    /// The API URL of an OpenAI-compatible chat model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_api_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_model: Option<String>,
+    /// The API URL of the embedding model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_api_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+}
+
+/// Returns the path of the configuration file: the one named by `ANYRAG_CONFIG`, or
+/// `anyrag/config.toml` in `XDG_CONFIG_HOME`, which defaults to `~/.config`.
+pub fn config_path() -> Result<PathBuf, CliConfigError> {
+    if let Some(path) = std::env::var_os(CONFIG_FILE_ENV) {
+        return Ok(PathBuf::from(path));
+    }
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .ok_or(CliConfigError::NoConfigDir)?;
+    Ok(config_dir.join("anyrag").join("config.toml"))
+}
+
+impl CliConfig {
+    /// Reads the configuration file. A missing file is an empty configuration.
+    pub fn load(path: &Path) -> Result<Self, CliConfigError> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Ok(toml::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the configuration file, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> Result<(), CliConfigError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Reads a setting, from the keyring for secret ones.
+    pub fn get(&self, key: &str) -> Result<Option<String>, CliConfigError> {
+        if SECRET_KEYS.contains(&key) {
+            return match keyring::Entry::new(KEYRING_SERVICE, key)?.get_password() {
+                Ok(secret) => Ok(Some(secret)),
+                Err(keyring::Error::NoEntry) => Ok(None),
+                Err(e) => Err(e.into()),
+            };
+        }
+        let value = match key {
+            "db_path" => &self.db_path,
+            "ai_api_url" => &self.ai_api_url,
+            "ai_model" => &self.ai_model,
+            "embedding_api_url" => &self.embedding_api_url,
+            "embedding_model" => &self.embedding_model,
+            _ => return Err(CliConfigError::UnknownKey(key.to_string())),
+        };
+        Ok(value.clone())
+    }
+
+    /// Changes a setting, storing secret ones in the keyring. An empty value clears it.
+    ///
+    /// The file is not written; call `save` for the settings kept in it.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), CliConfigError> {
+        let value = Some(value.to_string()).filter(|value| !value.is_empty());
+        if SECRET_KEYS.contains(&key) {
+            let entry = keyring::Entry::new(KEYRING_SERVICE, key)?;
+            return match value {
+                Some(secret) => Ok(entry.set_password(&secret)?),
+                None => match entry.delete_credential() {
+                    Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+                    Err(e) => Err(e.into()),
+                },
+            };
+        }
+        let field = match key {
+            "db_path" => &mut self.db_path,
+            "ai_api_url" => &mut self.ai_api_url,
+            "ai_model" => &mut self.ai_model,
+            "embedding_api_url" => &mut self.embedding_api_url,
+            "embedding_model" => &mut self.embedding_model,
+            _ => return Err(CliConfigError::UnknownKey(key.to_string())),
+        };
+        *field = value;
+        Ok(())
+    }
+
+    /// Exports every setting as its environment variable, unless that variable is
+    /// already set. Call it at startup, before other threads read the environment.
+    pub fn apply_to_env(&self) {
+        for (key, variable) in CONFIG_KEYS {
+            if std::env::var_os(variable).is_some() {
+                continue;
+            }
+            // A keyring that cannot be reached only leaves the secret unset.
+            if let Ok(Some(value)) = self.get(key) {
+                info!("Using '{key}' from the configuration file as {variable}.");
+                std::env::set_var(variable, value);
+            }
+        }
+    }
+}
+
+fn known_keys() -> String {
+    CONFIG_KEYS
+        .iter()
+        .map(|(key, _)| *key)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
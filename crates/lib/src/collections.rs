@@ -0,0 +1,256 @@
+//! # Document Collections
+//!
+//! A collection is a named group of documents, such as "API docs" or "HR policies",
+//! that a user can scope their questions to without keeping a separate database. Each
+//! user has collections of their own, stored in the `collections` table, and a document
+//! can belong to any number of them through `document_collections`.
+//!
+//! Search endpoints take a `collection`, by id or name, and only search the documents
+//! of that collection that the user can see. An unknown collection matches nothing.
+
+use serde::Serialize;
+use thiserror::Error;
+use tracing::info;
+use turso::{params, Connection, Database, Row};
+use uuid::Uuid;
+
+const SELECT_COLLECTION_SQL: &str = "SELECT c.id, c.owner_id, c.name, c.description, c.created_at, (SELECT COUNT(*) FROM document_collections dc JOIN documents d ON d.id = dc.document_id WHERE dc.collection_id = c.id AND d.deleted_at IS NULL) FROM collections c";
+
+/// Custom error types for document collections.
+#[derive(Error, Debug)]
+pub enum CollectionError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Collection not found: {0}")]
+    NotFound(String),
+    #[error("A collection named '{0}' already exists.")]
+    NameTaken(String),
+    #[error("A collection needs a name.")]
+    MissingName,
+    #[error("Document not found: {0}")]
+    DocumentNotFound(String),
+}
+
+/// A named group of documents.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Collection {
+    pub id: String,
+    pub owner_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// The number of documents in the collection, leaving out those in the trash.
+    pub document_count: i64,
+    pub created_at: String,
+}
+
+/// Creates a collection for `owner_id`. Names are unique per owner.
+pub async fn create_collection(
+    db: &Database,
+    owner_id: &str,
+    name: &str,
+    description: Option<&str>,
+) -> Result<Collection, CollectionError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(CollectionError::MissingName);
+    }
+    let conn = db.connect()?;
+    if find_collection(&conn, owner_id, name).await?.is_some() {
+        return Err(CollectionError::NameTaken(name.to_string()));
+    }
+
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO collections (id, owner_id, name, description) VALUES (?, ?, ?, ?)",
+        params![id.as_str(), owner_id, name, description],
+    )
+    .await?;
+    info!("User '{owner_id}' created collection '{name}' ({id}).");
+    get_collection(db, owner_id, &id).await
+}
+
+/// Lists the collections of `owner_id` by name.
+pub async fn list_collections(
+    db: &Database,
+    owner_id: &str,
+) -> Result<Vec<Collection>, CollectionError> {
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_COLLECTION_SQL} WHERE c.owner_id = ? ORDER BY c.name"),
+            params![owner_id],
+        )
+        .await?;
+    let mut collections = Vec::new();
+    while let Some(row) = rows.next().await? {
+        collections.push(collection_from_row(&row)?);
+    }
+    Ok(collections)
+}
+
+/// Reads a collection of `owner_id` by id or name.
+pub async fn get_collection(
+    db: &Database,
+    owner_id: &str,
+    collection: &str,
+) -> Result<Collection, CollectionError> {
+    let conn = db.connect()?;
+    find_collection(&conn, owner_id, collection)
+        .await?
+        .ok_or_else(|| CollectionError::NotFound(collection.to_string()))
+}
+
+/// Renames a collection or changes its description, leaving out the unset fields.
+pub async fn update_collection(
+    db: &Database,
+    owner_id: &str,
+    collection: &str,
+    name: Option<&str>,
+    description: Option<&str>,
+) -> Result<Collection, CollectionError> {
+    let existing = get_collection(db, owner_id, collection).await?;
+    let conn = db.connect()?;
+    let name = match name.map(str::trim) {
+        Some("") => return Err(CollectionError::MissingName),
+        Some(name) if name != existing.name => {
+            if find_collection(&conn, owner_id, name).await?.is_some() {
+                return Err(CollectionError::NameTaken(name.to_string()));
+            }
+            name
+        }
+        _ => existing.name.as_str(),
+    };
+    let description = description.or(existing.description.as_deref());
+    conn.execute(
+        "UPDATE collections SET name = ?, description = ? WHERE id = ?",
+        params![name, description, existing.id.as_str()],
+    )
+    .await?;
+    get_collection(db, owner_id, &existing.id).await
+}
+
+/// Deletes a collection. Its documents are kept.
+pub async fn delete_collection(
+    db: &Database,
+    owner_id: &str,
+    collection: &str,
+) -> Result<(), CollectionError> {
+    let existing = get_collection(db, owner_id, collection).await?;
+    let conn = db.connect()?;
+    conn.execute(
+        "DELETE FROM document_collections WHERE collection_id = ?",
+        params![existing.id.as_str()],
+    )
+    .await?;
+    conn.execute(
+        "DELETE FROM collections WHERE id = ?",
+        params![existing.id.as_str()],
+    )
+    .await?;
+    info!("User '{owner_id}' deleted collection '{}'.", existing.name);
+    Ok(())
+}
+
+/// Adds documents to a collection. Documents already in it are left as they are.
+///
+/// Only documents of `owner_id`, or shared with them, can be added. Fails with
+/// `DocumentNotFound`, without adding any document, when one of them is not such a
+/// document or is in the trash.
+pub async fn add_documents_to_collection(
+    db: &Database,
+    owner_id: &str,
+    collection: &str,
+    document_ids: &[String],
+) -> Result<Collection, CollectionError> {
+    let existing = get_collection(db, owner_id, collection).await?;
+    let conn = db.connect()?;
+    for document_id in document_ids {
+        let mut rows = conn
+            .query(
+                "SELECT id FROM documents WHERE id = ? AND deleted_at IS NULL AND (owner_id = ? OR id IN (SELECT document_id FROM document_shares WHERE user_id = ?))",
+                params![document_id.as_str(), owner_id, owner_id],
+            )
+            .await?;
+        if rows.next().await?.is_none() {
+            return Err(CollectionError::DocumentNotFound(document_id.clone()));
+        }
+    }
+    for document_id in document_ids {
+        conn.execute(
+            "INSERT OR IGNORE INTO document_collections (collection_id, document_id) VALUES (?, ?)",
+            params![existing.id.as_str(), document_id.as_str()],
+        )
+        .await?;
+    }
+    get_collection(db, owner_id, &existing.id).await
+}
+
+/// Takes a document out of a collection, returning whether it was in it.
+pub async fn remove_document_from_collection(
+    db: &Database,
+    owner_id: &str,
+    collection: &str,
+    document_id: &str,
+) -> Result<bool, CollectionError> {
+    let existing = get_collection(db, owner_id, collection).await?;
+    let conn = db.connect()?;
+    let removed = conn
+        .execute(
+            "DELETE FROM document_collections WHERE collection_id = ? AND document_id = ?",
+            params![existing.id.as_str(), document_id],
+        )
+        .await?;
+    Ok(removed > 0)
+}
+
+/// Lists the ids of the documents in a collection, most recently added first, leaving
+/// out those in the trash.
+pub async fn collection_documents(
+    db: &Database,
+    owner_id: &str,
+    collection: &str,
+) -> Result<Vec<String>, CollectionError> {
+    let existing = get_collection(db, owner_id, collection).await?;
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT dc.document_id FROM document_collections dc JOIN documents d ON d.id = dc.document_id WHERE dc.collection_id = ? AND d.deleted_at IS NULL ORDER BY dc.added_at DESC, dc.document_id",
+            params![existing.id.as_str()],
+        )
+        .await?;
+    let mut document_ids = Vec::new();
+    while let Some(row) = rows.next().await? {
+        document_ids.push(row.get(0)?);
+    }
+    Ok(document_ids)
+}
+
+/// Finds a collection of `owner_id` by id or name.
+async fn find_collection(
+    conn: &Connection,
+    owner_id: &str,
+    collection: &str,
+) -> Result<Option<Collection>, turso::Error> {
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_COLLECTION_SQL} WHERE c.owner_id = ? AND (c.id = ? OR c.name = ?)"),
+            params![owner_id, collection, collection],
+        )
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(collection_from_row(&row)?)),
+        None => Ok(None),
+    }
+}
+
+fn collection_from_row(row: &Row) -> Result<Collection, turso::Error> {
+    Ok(Collection {
+        id: row.get(0)?,
+        owner_id: row.get(1)?,
+        name: row.get(2)?,
+        description: row.get(3)?,
+        created_at: row.get(4)?,
+        document_count: row.get(5)?,
+    })
+}
@@ -15,3 +15,40 @@ pub const GITHUB_CHUNKS_DB_DIR: &str = "db/github_chunks";
 
 /// The default path for the main application SQLite database.
 pub const DEFAULT_DB_FILE: &str = "db/anyrag.db";
+
+/// The default maximum execution time of a single storage query, in seconds.
+pub const DEFAULT_QUERY_TIMEOUT_SECS: u64 = 30;
+
+/// The default maximum number of rows returned by a single storage query.
+pub const DEFAULT_QUERY_MAX_ROWS: usize = 10_000;
+
+/// The default time a SQLite connection waits for a lock before failing as busy, in
+/// milliseconds.
+pub const DEFAULT_SQLITE_BUSY_TIMEOUT_MS: u64 = 5_000;
+
+/// The default number of idle SQLite read connections kept for reuse.
+pub const DEFAULT_SQLITE_READ_POOL_SIZE: usize = 8;
+
+/// The default number of prepared statements cached per SQLite connection.
+pub const DEFAULT_SQLITE_STATEMENT_CACHE_SIZE: usize = 32;
+
+/// The default number of times a SQLite operation is retried after failing as busy.
+pub const DEFAULT_SQLITE_BUSY_RETRIES: u32 = 5;
+
+/// The default number of LLM calls an ingestion runs at once for independent chunks.
+pub const DEFAULT_INGEST_CONCURRENCY: usize = 4;
+
+/// The default maximum number of pages a crawl of the server may ingest.
+pub const DEFAULT_MAX_CRAWL_PAGES: usize = 100;
+
+/// The default maximum number of pages the server may ingest from one sitemap.
+pub const DEFAULT_MAX_SITEMAP_URLS: usize = 1000;
+
+/// The default number of times the LLM is asked to fix restructured YAML that is invalid.
+pub const DEFAULT_RESTRUCTURING_REPAIR_ATTEMPTS: usize = 2;
+
+/// The default number of documents sent to the embedding API in one request.
+pub const DEFAULT_EMBEDDING_BATCH_SIZE: usize = 64;
+
+/// The default directory of a persistent knowledge graph.
+pub const DEFAULT_GRAPH_DIR: &str = "db/graph";
@@ -0,0 +1,174 @@
+//! # Context Budgeting
+//!
+//! This module fits retrieved search results into a model's context window before
+//! they are handed to the RAG synthesis step. Results are packed in their ranked
+//! order; a result that does not fit is truncated at a sentence boundary, or skipped
+//! when not even its first sentence fits, and the smaller results ranked below it are
+//! still packed into the budget that is left. Everything that was cut is recorded so
+//! it can be surfaced in debug output instead of degrading generation silently.
+
+use crate::types::{AppConfig, SearchResult};
+use serde::Serialize;
+use tracing::{info, warn};
+
+/// The average number of characters per token used for estimation.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Characters that end a sentence for the purpose of truncation.
+const SENTENCE_TERMINATORS: &[char] = &['.', '!', '?', '\n', '。', '！', '？'];
+
+/// The reason a search result did not make it into the context in full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DropReason {
+    /// The result was cut at a sentence boundary to fit the remaining budget.
+    Truncated,
+    /// Not even the first sentence of the result fit the remaining budget.
+    OverBudget,
+}
+
+/// A record of a search result that was truncated or dropped.
+#[derive(Debug, Clone, Serialize)]
+pub struct DroppedContext {
+    pub link: String,
+    pub title: String,
+    /// The estimated token count of the original description.
+    pub original_tokens: usize,
+    /// The estimated token count that was kept (0 when dropped entirely).
+    pub kept_tokens: usize,
+    pub reason: DropReason,
+}
+
+/// The outcome of applying a `ContextBudget` to a set of search results.
+#[derive(Debug, Clone, Default)]
+pub struct BudgetedContext {
+    /// The results that fit the budget, in their original order.
+    pub results: Vec<SearchResult>,
+    /// The estimated number of tokens used by `results`.
+    pub used_tokens: usize,
+    /// The results that were truncated or dropped.
+    pub dropped: Vec<DroppedContext>,
+}
+
+/// A token budget for the retrieved context of a RAG prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextBudget {
+    pub max_tokens: usize,
+}
+
+impl ContextBudget {
+    pub fn new(max_tokens: usize) -> Self {
+        Self { max_tokens }
+    }
+
+    /// Estimates the number of tokens in a text.
+    ///
+    /// This is a tokenizer-free heuristic (about four characters per token), which
+    /// is accurate enough to keep prompts inside a model's context window.
+    pub fn estimate_tokens(text: &str) -> usize {
+        text.chars().count().div_ceil(CHARS_PER_TOKEN)
+    }
+
+    /// The budget of the provider named `provider_name`, when its `max_context_tokens`
+    /// is configured.
+    pub fn for_provider(config: &AppConfig, provider_name: &str) -> Option<Self> {
+        config
+            .providers
+            .get(provider_name)
+            .and_then(|provider| provider.max_context_tokens)
+            .map(Self::new)
+    }
+
+    /// Trims the ranked search results so their descriptions fit within the budget.
+    pub fn apply(&self, results: Vec<SearchResult>) -> BudgetedContext {
+        let mut budgeted = BudgetedContext::default();
+
+        for mut result in results {
+            let original_tokens = Self::estimate_tokens(&result.description);
+            let remaining = self.max_tokens.saturating_sub(budgeted.used_tokens);
+
+            if original_tokens <= remaining {
+                budgeted.used_tokens += original_tokens;
+                budgeted.results.push(result);
+                continue;
+            }
+
+            // A result that does not fit is cut or skipped, and the results ranked below
+            // it may still fill the budget that is left.
+            match truncate_at_sentence(&result.description, remaining) {
+                Some(text) => {
+                    let kept_tokens = Self::estimate_tokens(&text);
+                    budgeted.dropped.push(DroppedContext {
+                        link: result.link.clone(),
+                        title: result.title.clone(),
+                        original_tokens,
+                        kept_tokens,
+                        reason: DropReason::Truncated,
+                    });
+                    result.description = text;
+                    budgeted.used_tokens += kept_tokens;
+                    budgeted.results.push(result);
+                }
+                None => budgeted.dropped.push(DroppedContext {
+                    link: result.link,
+                    title: result.title,
+                    original_tokens,
+                    kept_tokens: 0,
+                    reason: DropReason::OverBudget,
+                }),
+            }
+        }
+
+        if !budgeted.dropped.is_empty() {
+            warn!(
+                "Context budget of {} tokens exceeded: {} result(s) truncated or dropped.",
+                self.max_tokens,
+                budgeted.dropped.len()
+            );
+        }
+        info!(
+            "Context budget: kept {} result(s) using ~{} of {} tokens.",
+            budgeted.results.len(),
+            budgeted.used_tokens,
+            self.max_tokens
+        );
+
+        budgeted
+    }
+}
+
+/// Fits the ranked search results into `budget`, or keeps them all when the model has
+/// no configured context window.
+pub fn fit_context(budget: Option<ContextBudget>, results: Vec<SearchResult>) -> BudgetedContext {
+    match budget {
+        Some(budget) => budget.apply(results),
+        None => BudgetedContext {
+            results,
+            ..Default::default()
+        },
+    }
+}
+
+/// Returns the longest prefix of `text` that ends on a sentence boundary and fits
+/// within `max_tokens`, or `None` if not even the first sentence fits.
+fn truncate_at_sentence(text: &str, max_tokens: usize) -> Option<String> {
+    let max_chars = max_tokens * CHARS_PER_TOKEN;
+    let mut last_boundary = None;
+
+    for (char_count, (byte_idx, c)) in text.char_indices().enumerate() {
+        if char_count >= max_chars {
+            break;
+        }
+        if SENTENCE_TERMINATORS.contains(&c) {
+            last_boundary = Some(byte_idx + c.len_utf8());
+        }
+    }
+
+    let end = last_boundary?;
+    let truncated = text[..end].trim_end();
+    if truncated.is_empty() {
+        return None;
+    }
+
+    Some(truncated.to_string())
+}
@@ -0,0 +1,209 @@
+//! # Context Sanitization
+//!
+//! Retrieved documents are untrusted input: an ingested web page can say "ignore
+//! previous instructions" as easily as it can describe a product. With a
+//! `context_sanitization` configuration, each document is scanned for instruction-like
+//! text before it is put into a RAG prompt. The offending lines are stripped, or kept
+//! and the document flagged, and every document is wrapped in `<document>` delimiters
+//! that the system prompt tells the model to treat as data only.
+//!
+//! Every detection is recorded so it can be surfaced in debug output.
+
+use crate::prompts::tasks::UNTRUSTED_CONTEXT_SYSTEM_INSTRUCTION;
+use crate::types::SearchResult;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::warn;
+
+/// The separator of documents in a context without sanitization.
+const DOCUMENT_SEPARATOR: &str = "\n\n---\n\n";
+/// The line an instruction-like line is replaced with when stripped.
+const STRIPPED_LINE: &str = "[removed: instruction-like text]";
+
+/// Phrases that address the model rather than the reader, matched case-insensitively.
+const BUILTIN_PATTERNS: &[&str] = &[
+    r"\b(ignore|disregard|forget|override)\b.{0,40}\b(previous|prior|above|earlier|preceding|all|your)\b.{0,20}\b(instructions?|prompts?|rules|directions|guidelines)\b",
+    r"\b(reveal|print|show|repeat|output)\b.{0,20}\b(system prompt|hidden prompt|your instructions)\b",
+    r"\byou are now\b",
+    r"\bnew instructions\s*:",
+    r"^\s*(system|assistant|developer)\s*:",
+    r"<\|?(im_start|im_end|system|endoftext)\|?>",
+    r"\[/?INST\]",
+];
+
+/// Matches the delimiters of the wrapped context, so a document cannot close its own.
+const DELIMITER_PATTERN: &str = r"(?i)<(/?)document";
+
+/// Custom error types for context sanitization.
+#[derive(Error, Debug)]
+pub enum SanitizationError {
+    #[error("Invalid injection pattern '{pattern}': {source}")]
+    InvalidPattern {
+        pattern: String,
+        #[source]
+        source: regex::Error,
+    },
+}
+
+/// What is done with the instruction-like lines of a document.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectionAction {
+    /// The lines are replaced with a placeholder.
+    #[default]
+    Strip,
+    /// The lines are kept, and the document is marked `flagged="true"`.
+    Flag,
+}
+
+/// Configuration for sanitizing the retrieved context of RAG prompts.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct ContextSanitizationConfig {
+    #[serde(default)]
+    pub action: InjectionAction,
+    /// Additional regular expressions of instruction-like text, matched
+    /// case-insensitively like the built-in ones.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
+/// A line of a retrieved document that looks like an instruction to the model.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct InjectionDetection {
+    pub link: String,
+    pub title: String,
+    /// The text that matched.
+    pub matched: String,
+    pub action: InjectionAction,
+}
+
+/// The context built from retrieved documents, with the detections made in them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentContext {
+    pub context: String,
+    pub detections: Vec<InjectionDetection>,
+}
+
+/// Scans retrieved documents for instruction-like text and wraps them in delimiters.
+#[derive(Debug, Clone)]
+pub struct ContextSanitizer {
+    patterns: Vec<Regex>,
+    action: InjectionAction,
+    delimiter: Regex,
+}
+
+impl ContextSanitizer {
+    /// Compiles the built-in and configured patterns.
+    pub fn new(config: &ContextSanitizationConfig) -> Result<Self, SanitizationError> {
+        let patterns = BUILTIN_PATTERNS
+            .iter()
+            .map(|pattern| pattern.to_string())
+            .chain(config.patterns.iter().cloned())
+            .map(|pattern| {
+                Regex::new(&format!("(?i){pattern}"))
+                    .map_err(|source| SanitizationError::InvalidPattern { pattern, source })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            patterns,
+            action: config.action,
+            delimiter: Regex::new(DELIMITER_PATTERN).expect("valid delimiter pattern"),
+        })
+    }
+
+    /// Sanitizes the documents and wraps each of them in `<document>` delimiters.
+    pub fn sanitize(&self, results: &[SearchResult]) -> DocumentContext {
+        let mut detections = Vec::new();
+        let documents: Vec<String> = results
+            .iter()
+            .enumerate()
+            .map(|(i, result)| {
+                let mut flagged = false;
+                let content: Vec<String> = result
+                    .description
+                    .lines()
+                    .map(|line| {
+                        let Some(matched) = self.find(line) else {
+                            return line.to_string();
+                        };
+                        detections.push(InjectionDetection {
+                            link: result.link.clone(),
+                            title: result.title.clone(),
+                            matched: matched.to_string(),
+                            action: self.action,
+                        });
+                        match self.action {
+                            InjectionAction::Strip => STRIPPED_LINE.to_string(),
+                            InjectionAction::Flag => {
+                                flagged = true;
+                                line.to_string()
+                            }
+                        }
+                    })
+                    .collect();
+                format!(
+                    "<document index=\"{}\" title=\"{}\" source=\"{}\"{}>\n{}\n</document>",
+                    i + 1,
+                    escape_attribute(&result.title),
+                    escape_attribute(&result.link),
+                    if flagged { " flagged=\"true\"" } else { "" },
+                    self.delimiter
+                        .replace_all(&content.join("\n"), "&lt;${1}document")
+                )
+            })
+            .collect();
+
+        if !detections.is_empty() {
+            warn!(
+                "Found {} instruction-like lines in the retrieved context.",
+                detections.len()
+            );
+        }
+        DocumentContext {
+            context: documents.join("\n\n"),
+            detections,
+        }
+    }
+
+    fn find<'a>(&self, line: &'a str) -> Option<&'a str> {
+        self.patterns
+            .iter()
+            .find_map(|pattern| pattern.find(line))
+            .map(|m| m.as_str())
+    }
+}
+
+/// Builds the context of a RAG prompt from retrieved documents. With a sanitizer, the
+/// documents are sanitized and delimited; without one, they are separated by rules.
+pub fn document_context(
+    results: &[SearchResult],
+    sanitizer: Option<&ContextSanitizer>,
+) -> DocumentContext {
+    match sanitizer {
+        Some(sanitizer) => sanitizer.sanitize(results),
+        None => DocumentContext {
+            context: results
+                .iter()
+                .map(|result| result.description.as_str())
+                .collect::<Vec<&str>>()
+                .join(DOCUMENT_SEPARATOR),
+            detections: Vec::new(),
+        },
+    }
+}
+
+/// Appends the instruction to treat the delimited documents as data to a system prompt.
+pub fn guard_system_prompt(system_prompt: &str) -> String {
+    format!(
+        "{}\n\n{UNTRUSTED_CONTEXT_SYSTEM_INSTRUCTION}",
+        system_prompt.trim_end()
+    )
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
@@ -29,6 +29,19 @@ pub enum PromptError {
     Regex(#[from] regex::Error),
     #[error("Failed to serialize result to JSON: {0}")]
     JsonSerialization(#[from] serde_json::Error),
+    #[error("AI response does not conform to the output schema: {0}")]
+    OutputSchemaViolation(String),
+    #[error("The AI provider does not accept images.")]
+    ImagesNotSupported,
+    #[error("Query exceeded the execution timeout of {0} seconds")]
+    QueryTimeout(u64),
+    #[error("Query would scan an estimated {estimated_bytes} bytes, above the limit of {max_bytes} bytes. Set `confirm_expensive_query` to run it anyway.")]
+    QueryCostExceeded {
+        estimated_bytes: u64,
+        max_bytes: u64,
+    },
+    #[error("The prompt did not result in a valid query.")]
+    NoQueryGenerated,
 }
 
 #[cfg(feature = "firebase")]
@@ -7,11 +7,17 @@
 
 use crate::{
     constants,
+    context_sanitization::guard_system_prompt,
+    experiments::{assign_variant, find_experiment, record_experiment_outcome, ExperimentOutcome},
+    feedback::find_few_shot_examples,
+    planning::PlanningOptions,
     providers::{
         ai::AiProvider,
         db::{sqlite::SqliteProvider, storage::Storage},
         factory::create_dynamic_provider,
     },
+    routing::{Route, RouteChoice, RouteRetriever, RoutingConfig, RoutingOptions},
+    schema_annotations::get_schema_annotations,
     types::{
         AppConfig, ContentType, ExecutePromptOptions as LibExecutePromptOptions,
         HttpRequestPromptOptions, ResolvedTask,
@@ -20,16 +26,32 @@ use crate::{
 };
 use std::collections::HashMap;
 use std::sync::Arc;
-use tracing::info;
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// The maximum number of accepted corrections shown to the model as examples.
+const FEW_SHOT_EXAMPLE_LIMIT: usize = 3;
+
+/// The task whose prompts classify a prompt between routes.
+const ANSWER_ROUTING_TASK: &str = "answer_routing";
+
+/// The task whose prompts decompose a prompt into sub-queries.
+const QUERY_PLANNING_TASK: &str = "query_planning";
 
 /// A struct that holds all the dependencies required to execute `anyrag`'s core logic.
 /// This decouples the business logic from the server's `AppState` or any other
 /// specific application container.
+#[derive(Clone)]
 pub struct AnyragExecutor {
     pub ai_providers: Arc<HashMap<String, Box<dyn AiProvider>>>,
     pub sqlite_provider: Arc<SqliteProvider>,
     pub config: Arc<AppConfig>,
     pub tasks: Arc<HashMap<String, ResolvedTask>>,
+    /// Retrieves the context of the knowledge and graph routes when prompts are routed.
+    pub route_retriever: Option<Arc<dyn RouteRetriever>>,
+    /// The user prompts are executed for. Only the corrections to their own feedback
+    /// are shown to the model as few-shot examples.
+    pub owner_id: Option<String>,
 }
 
 impl AnyragExecutor {
@@ -45,9 +67,23 @@ impl AnyragExecutor {
             sqlite_provider,
             config,
             tasks,
+            route_retriever: None,
+            owner_id: None,
         }
     }
 
+    /// Sets the retriever of the knowledge and graph routes.
+    pub fn with_route_retriever(mut self, route_retriever: Arc<dyn RouteRetriever>) -> Self {
+        self.route_retriever = Some(route_retriever);
+        self
+    }
+
+    /// Sets the user prompts are executed for.
+    pub fn with_owner_id(mut self, owner_id: Option<String>) -> Self {
+        self.owner_id = owner_id;
+        self
+    }
+
     /// Orchestrates the execution of a prompt originating from an HTTP request.
     /// This is the primary entry point for the `server` crate into the `lib`'s core logic.
     /// It encapsulates business logic such as shorthand command parsing, dynamic provider
@@ -59,7 +95,8 @@ impl AnyragExecutor {
         info!("Executor received prompt payload: '{}'", options.prompt);
 
         // --- Shorthand "ls" command: Always targets a local DB ---
-        if options.prompt.starts_with("ls ") {
+        let is_shorthand = options.prompt.starts_with("ls ");
+        if is_shorthand {
             info!("Shorthand 'ls' command detected. Overriding to local DB query.");
             let parts: Vec<&str> = options.prompt.split_whitespace().collect();
             let table_name = match parts.get(1) {
@@ -114,9 +151,45 @@ impl AnyragExecutor {
         };
         info!("Selected task '{task_name}' based on request payload.");
 
-        let task_config = self.tasks.get(task_name).ok_or_else(|| {
+        // --- Answer Routing ---
+        // Prompts without a content type are routed by a classification call when
+        // routing is configured.
+        let routing = match &self.config.routing {
+            Some(routing) if options.content_type.is_none() && !is_shorthand => {
+                Some(self.routing_options(routing)?)
+            }
+            _ => None,
+        };
+        if routing.is_some()
+            && options.table_name.is_none()
+            && (options.db.is_some() || options.project_id.is_some())
+        {
+            // Offer text-to-SQL over all the tables of the database.
+            options.table_name = Some(String::new());
+        }
+
+        // --- A/B Experiment Routing ---
+        // An experiment on the selected task swaps in one of its variant configurations.
+        let experiment = find_experiment(&self.config.experiments, task_name).map(
+            |(name, experiment_config)| {
+                let variant = assign_variant(experiment_config);
+                (name, variant, experiment_config.variant_task(variant))
+            },
+        );
+        let config_task_name = match experiment {
+            Some((name, variant, variant_task)) => {
+                info!(
+                    "Experiment '{name}' routed task '{task_name}' to variant {} ('{variant_task}').",
+                    variant.as_str()
+                );
+                variant_task
+            }
+            None => task_name,
+        };
+
+        let task_config = self.tasks.get(config_task_name).ok_or_else(|| {
             PromptError::StorageOperationFailed(format!(
-                "Configuration for task '{task_name}' not found."
+                "Configuration for task '{config_task_name}' not found."
             ))
         })?;
 
@@ -138,12 +211,15 @@ impl AnyragExecutor {
             (provider, provider_config.model_name.clone())
         };
 
-        // Apply task's default prompts if not overridden in the request.
-        if options.system_prompt_template.is_none() {
-            options.system_prompt_template = Some(task_config.system_prompt.clone());
-        }
-        if options.user_prompt_template.is_none() {
-            options.user_prompt_template = Some(task_config.user_prompt.clone());
+        // Apply task's default prompts if not overridden in the request. Routed prompts
+        // use the prompts of the route they are answered on instead.
+        if routing.is_none() {
+            if options.system_prompt_template.is_none() {
+                options.system_prompt_template = Some(task_config.system_prompt.clone());
+            }
+            if options.user_prompt_template.is_none() {
+                options.user_prompt_template = Some(task_config.user_prompt.clone());
+            }
         }
 
         // --- Storage Provider Selection ---
@@ -155,7 +231,12 @@ impl AnyragExecutor {
             {
                 let bq_provider =
                     crate::providers::db::bigquery::BigQueryProvider::new(_project_id.to_string())
-                        .await?;
+                        .await?
+                        .with_query_limits(self.config.query_limits())
+                        .with_cost_guard(
+                            self.config.bigquery_max_bytes_scanned,
+                            options.confirm_expensive_query,
+                        );
                 Box::new(bq_provider)
             }
             #[cfg(not(feature = "bigquery"))]
@@ -168,7 +249,10 @@ impl AnyragExecutor {
                 db_name
             );
             let db_path = format!("{}/{db_name}.db", constants::DB_DIR);
-            let provider = SqliteProvider::new(&db_path).await?;
+            let provider = SqliteProvider::new(&db_path)
+                .await?
+                .with_query_limits(self.config.query_limits())
+                .with_sqlite_settings(self.config.sqlite_settings());
             provider.initialize_schema().await?;
             Box::new(provider)
         } else {
@@ -178,12 +262,160 @@ impl AnyragExecutor {
         };
 
         // --- Final Execution ---
-        let client = PromptClientBuilder::new()
+        let mut builder = PromptClientBuilder::new()
             .ai_provider(ai_provider)
-            .storage_provider(storage_provider)
-            .build()?;
+            .storage_provider(storage_provider);
+        if routing.is_some() {
+            builder = builder.routing_provider(self.task_provider(ANSWER_ROUTING_TASK)?);
+            if let Some(route_retriever) = &self.route_retriever {
+                builder = builder.route_retriever(route_retriever.clone());
+            }
+        }
+        let client = builder.build()?;
+
+        let db_name = options.db.clone();
+        let mut lib_options: LibExecutePromptOptions = options.into();
 
-        let lib_options: LibExecutePromptOptions = options.into();
-        client.execute_prompt_with_options(lib_options).await
+        // Show the model accepted corrections to similar prompts.
+        if matches!(task_name, "direct_generation" | "rag_synthesis") {
+            let examples = find_few_shot_examples(
+                &self.sqlite_provider.db,
+                self.owner_id.as_deref(),
+                &lib_options.prompt,
+                FEW_SHOT_EXAMPLE_LIMIT,
+            )
+            .await
+            .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+            lib_options.few_shot_examples = Some(examples);
+        }
+
+        // Merge user-supplied table and column descriptions into the schema context.
+        let offers_sql = routing
+            .as_ref()
+            .is_some_and(|r| r.routes.iter().any(|c| c.route == Route::Sql));
+        if task_name == "query_generation" || offers_sql {
+            let annotations =
+                get_schema_annotations(&self.sqlite_provider.db, db_name.as_deref(), None)
+                    .await
+                    .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+            lib_options.schema_annotations = Some(annotations);
+        }
+        lib_options.routing = routing;
+
+        // Prompts answered with text-to-SQL are planned when planning is configured.
+        if let Some(planning) = &self.config.planning {
+            if (task_name == "query_generation" || offers_sql) && !is_shorthand {
+                let task_config = self.tasks.get(QUERY_PLANNING_TASK).ok_or_else(|| {
+                    PromptError::StorageOperationFailed(format!(
+                        "Configuration for task '{QUERY_PLANNING_TASK}' not found."
+                    ))
+                })?;
+                lib_options.planning = Some(PlanningOptions {
+                    system_prompt: task_config.system_prompt.clone(),
+                    user_prompt_template: task_config.user_prompt.clone(),
+                    max_steps: planning.max_steps,
+                });
+            }
+        }
+
+        let started = Instant::now();
+        let result = client.execute_prompt_with_options(lib_options).await;
+
+        let Some((name, variant, variant_task)) = experiment else {
+            return result;
+        };
+        let (prompt_tokens, completion_tokens) = match &result {
+            Ok(r) => ExperimentOutcome::estimate_tokens(
+                &[r.system_prompt.as_deref(), r.user_prompt.as_deref()],
+                &r.text,
+            ),
+            Err(_) => (0, 0),
+        };
+        let outcome = ExperimentOutcome {
+            experiment: name.to_string(),
+            variant,
+            task: variant_task.to_string(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            prompt_tokens,
+            completion_tokens,
+            success: result.is_ok(),
+        };
+        // Failing to record an outcome must not fail the request itself.
+        let run = match record_experiment_outcome(&self.sqlite_provider.db, &outcome).await {
+            Ok(run) => Some(run),
+            Err(e) => {
+                warn!("Failed to record outcome of experiment '{name}': {e}");
+                None
+            }
+        };
+
+        result.map(|mut r| {
+            r.experiment_run = run;
+            r
+        })
+    }
+
+    /// Builds the routing stage from the configuration. Each route is answered with the
+    /// prompts of its task.
+    fn routing_options(&self, routing: &RoutingConfig) -> Result<RoutingOptions, PromptError> {
+        let task_config = self.tasks.get(ANSWER_ROUTING_TASK).ok_or_else(|| {
+            PromptError::StorageOperationFailed(format!(
+                "Configuration for task '{ANSWER_ROUTING_TASK}' not found."
+            ))
+        })?;
+        let routes = routing
+            .routes
+            .iter()
+            .map(|route_config| {
+                let route = route_config.route;
+                let answer_task = self.tasks.get(route.task_name());
+                // The knowledge route answers from retrieved documents, which are
+                // delimited as untrusted data when context sanitization is configured.
+                let guarded =
+                    route == Route::Knowledge && self.config.context_sanitization.is_some();
+                RouteChoice {
+                    route,
+                    description: route_config
+                        .description
+                        .clone()
+                        .unwrap_or_else(|| route.default_description().to_string()),
+                    system_prompt_template: answer_task.map(|t| {
+                        if guarded {
+                            guard_system_prompt(&t.system_prompt)
+                        } else {
+                            t.system_prompt.clone()
+                        }
+                    }),
+                    user_prompt_template: answer_task.map(|t| t.user_prompt.clone()),
+                }
+            })
+            .collect();
+        Ok(RoutingOptions {
+            routes,
+            fallback: routing.fallback,
+            system_prompt: task_config.system_prompt.clone(),
+            user_prompt_template: task_config.user_prompt.clone(),
+        })
+    }
+
+    /// Returns the AI provider configured for a task.
+    fn task_provider(&self, task_name: &str) -> Result<Box<dyn AiProvider>, PromptError> {
+        let provider_name = self
+            .tasks
+            .get(task_name)
+            .map(|t| t.provider.as_str())
+            .ok_or_else(|| {
+                PromptError::StorageOperationFailed(format!(
+                    "Configuration for task '{task_name}' not found."
+                ))
+            })?;
+        self.ai_providers
+            .get(provider_name)
+            .cloned()
+            .ok_or_else(|| {
+                PromptError::MissingAiProvider(format!(
+                    "Provider '{provider_name}' for task '{task_name}' not found in providers map."
+                ))
+            })
     }
 }
@@ -0,0 +1,225 @@
+//! # A/B Experiments
+//!
+//! This module routes requests for a task between two task configurations and records
+//! the outcome of every request in the `experiments` table, so prompt and model
+//! changes can be compared on latency, token usage, and user feedback.
+
+use crate::{
+    context_budget::ContextBudget,
+    types::{ExperimentConfig, ExperimentRun, ExperimentVariant},
+};
+use serde::Serialize;
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::BuildHasher,
+    time::SystemTime,
+};
+use thiserror::Error;
+use tracing::info;
+use turso::{params, Database, Value as TursoValue};
+
+/// Custom error types for A/B experiments.
+#[derive(Error, Debug)]
+pub enum ExperimentError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Unknown experiment variant stored: {0}")]
+    InvalidVariant(String),
+    #[error("Experiment '{0}' is not configured.")]
+    UnknownExperiment(String),
+    #[error("Experiment run {0} not found.")]
+    RunNotFound(i64),
+}
+
+/// The measured outcome of a single request served by an experiment.
+#[derive(Debug, Clone)]
+pub struct ExperimentOutcome {
+    pub experiment: String,
+    pub variant: ExperimentVariant,
+    /// The task configuration that served the request.
+    pub task: String,
+    pub latency_ms: u64,
+    /// The estimated number of tokens sent to the model.
+    pub prompt_tokens: usize,
+    /// The estimated number of tokens in the final result.
+    pub completion_tokens: usize,
+    pub success: bool,
+}
+
+impl ExperimentOutcome {
+    /// Estimates token usage from the prompts sent and the result returned.
+    pub fn estimate_tokens(prompts: &[Option<&str>], result: &str) -> (usize, usize) {
+        let prompt_tokens = prompts
+            .iter()
+            .flatten()
+            .map(|p| ContextBudget::estimate_tokens(p))
+            .sum();
+        (prompt_tokens, ContextBudget::estimate_tokens(result))
+    }
+}
+
+/// Aggregated outcomes of one variant of an experiment.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct VariantSummary {
+    pub variant: ExperimentVariant,
+    pub task: String,
+    pub runs: i64,
+    /// The fraction of runs that completed without an error.
+    pub success_rate: f64,
+    pub avg_latency_ms: f64,
+    pub avg_prompt_tokens: f64,
+    pub avg_completion_tokens: f64,
+    pub positive_feedback: i64,
+    pub negative_feedback: i64,
+}
+
+/// Aggregated outcomes of an experiment, one entry per variant and task configuration.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ExperimentSummary {
+    pub experiment: String,
+    pub variants: Vec<VariantSummary>,
+}
+
+/// Returns the experiment that splits the given task, if any.
+///
+/// If several experiments target the same task, the first by name wins so the
+/// choice is stable across requests.
+pub fn find_experiment<'a>(
+    experiments: &'a HashMap<String, ExperimentConfig>,
+    task: &str,
+) -> Option<(&'a str, &'a ExperimentConfig)> {
+    experiments
+        .iter()
+        .filter(|(_, config)| config.task == task)
+        .min_by_key(|(name, _)| name.as_str())
+        .map(|(name, config)| (name.as_str(), config))
+}
+
+/// Picks the variant for a roll in `0..100`; rolls below `traffic_percent_b` go to B.
+pub fn variant_for_roll(config: &ExperimentConfig, roll: u8) -> ExperimentVariant {
+    if roll < config.traffic_percent_b {
+        return ExperimentVariant::B;
+    }
+    ExperimentVariant::A
+}
+
+/// Randomly assigns a request to a variant according to the configured traffic split.
+pub fn assign_variant(config: &ExperimentConfig) -> ExperimentVariant {
+    // `RandomState` is randomly seeded, which is enough entropy for traffic splitting
+    // without pulling in a random number generator.
+    let roll = RandomState::new().hash_one(SystemTime::now()) % 100;
+    variant_for_roll(config, roll as u8)
+}
+
+/// Records the outcome of a request and returns the run it was stored as.
+pub async fn record_experiment_outcome(
+    db: &Database,
+    outcome: &ExperimentOutcome,
+) -> Result<ExperimentRun, ExperimentError> {
+    let conn = db.connect()?;
+    conn.execute(
+        "INSERT INTO experiments (experiment, variant, task, latency_ms, prompt_tokens, completion_tokens, success) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![
+            outcome.experiment.as_str(),
+            outcome.variant.as_str(),
+            outcome.task.as_str(),
+            outcome.latency_ms as i64,
+            outcome.prompt_tokens as i64,
+            outcome.completion_tokens as i64,
+            outcome.success as i64
+        ],
+    )
+    .await?;
+
+    let mut rows = conn.query("SELECT last_insert_rowid()", ()).await?;
+    let id: i64 = match rows.next().await? {
+        Some(row) => row.get(0)?,
+        None => 0,
+    };
+
+    info!(
+        "Recorded run {} of experiment '{}' (variant {}).",
+        id,
+        outcome.experiment,
+        outcome.variant.as_str()
+    );
+    Ok(ExperimentRun {
+        id,
+        experiment: outcome.experiment.clone(),
+        variant: outcome.variant,
+    })
+}
+
+/// Attaches user feedback to a recorded run.
+pub async fn record_experiment_feedback(
+    db: &Database,
+    run_id: i64,
+    positive: bool,
+) -> Result<(), ExperimentError> {
+    let conn = db.connect()?;
+    let feedback: i64 = if positive { 1 } else { -1 };
+    let affected = conn
+        .execute(
+            "UPDATE experiments SET feedback = ? WHERE id = ?",
+            params![feedback, run_id],
+        )
+        .await?;
+    if affected == 0 {
+        return Err(ExperimentError::RunNotFound(run_id));
+    }
+    Ok(())
+}
+
+/// Aggregates the recorded outcomes of an experiment per variant.
+pub async fn summarize_experiment(
+    db: &Database,
+    experiment: &str,
+) -> Result<ExperimentSummary, ExperimentError> {
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT variant, task, COUNT(*), AVG(success), AVG(latency_ms), AVG(prompt_tokens), AVG(completion_tokens),
+                    SUM(CASE WHEN feedback = 1 THEN 1 ELSE 0 END), SUM(CASE WHEN feedback = -1 THEN 1 ELSE 0 END)
+             FROM experiments WHERE experiment = ? GROUP BY variant, task ORDER BY variant, task",
+            params![experiment],
+        )
+        .await?;
+
+    let mut variants = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let variant: String = row.get(0)?;
+        let variant = match variant.as_str() {
+            "a" => ExperimentVariant::A,
+            "b" => ExperimentVariant::B,
+            _ => return Err(ExperimentError::InvalidVariant(variant)),
+        };
+
+        variants.push(VariantSummary {
+            variant,
+            task: row.get(1)?,
+            runs: row.get(2)?,
+            success_rate: as_f64(row.get_value(3)?),
+            avg_latency_ms: as_f64(row.get_value(4)?),
+            avg_prompt_tokens: as_f64(row.get_value(5)?),
+            avg_completion_tokens: as_f64(row.get_value(6)?),
+            positive_feedback: as_f64(row.get_value(7)?) as i64,
+            negative_feedback: as_f64(row.get_value(8)?) as i64,
+        });
+    }
+
+    Ok(ExperimentSummary {
+        experiment: experiment.to_string(),
+        variants,
+    })
+}
+
+/// Reads a numeric aggregate, which SQLite may return as an integer or a real.
+fn as_f64(value: TursoValue) -> f64 {
+    match value {
+        TursoValue::Integer(i) => i as f64,
+        TursoValue::Real(f) => f,
+        _ => 0.0,
+    }
+}
@@ -0,0 +1,170 @@
+//! # Answer Feedback
+//!
+//! This module stores thumbs up/down ratings of answers in the `feedback` table, and
+//! turns accepted corrections into few-shot examples. The examples most similar to a
+//! new prompt are shown to the model, so reviewed corrections improve later answers.
+
+use crate::types::FewShotExample;
+use std::collections::HashSet;
+use thiserror::Error;
+use tracing::info;
+use turso::{params, Database};
+
+/// The number of recent few-shot examples of an owner considered when picking the most
+/// similar ones.
+const FEW_SHOT_CANDIDATE_LIMIT: u32 = 200;
+
+/// Custom error types for answer feedback.
+#[derive(Error, Debug)]
+pub enum FeedbackError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Feedback {0} not found.")]
+    NotFound(i64),
+    #[error("Feedback {0} has no correction to accept.")]
+    MissingCorrection(i64),
+}
+
+/// A rating of a single prompt result.
+#[derive(Debug, Clone)]
+pub struct NewFeedback {
+    pub owner_id: Option<String>,
+    /// The client-supplied id of the rated prompt result.
+    pub result_id: String,
+    /// The prompt that produced the rated answer.
+    pub prompt: String,
+    pub positive: bool,
+    /// The answer the user expected instead, if any.
+    pub correction: Option<String>,
+}
+
+/// Stores a rating and returns its id.
+pub async fn record_feedback(db: &Database, feedback: &NewFeedback) -> Result<i64, FeedbackError> {
+    let conn = db.connect()?;
+    conn.execute(
+        "INSERT INTO feedback (owner_id, result_id, prompt, positive, correction) VALUES (?, ?, ?, ?, ?)",
+        params![
+            feedback.owner_id.clone(),
+            feedback.result_id.as_str(),
+            feedback.prompt.as_str(),
+            feedback.positive as i64,
+            feedback.correction.clone()
+        ],
+    )
+    .await?;
+
+    let mut rows = conn.query("SELECT last_insert_rowid()", ()).await?;
+    let id: i64 = match rows.next().await? {
+        Some(row) => row.get(0)?,
+        None => 0,
+    };
+    info!(
+        "Recorded {} feedback {} for result '{}'.",
+        if feedback.positive {
+            "positive"
+        } else {
+            "negative"
+        },
+        id,
+        feedback.result_id
+    );
+    Ok(id)
+}
+
+/// Accepts the correction of a feedback entry into the few-shot example store. The
+/// example belongs to the owner of the feedback.
+pub async fn accept_correction(
+    db: &Database,
+    feedback_id: i64,
+) -> Result<FewShotExample, FeedbackError> {
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT prompt, correction, owner_id FROM feedback WHERE id = ?",
+            params![feedback_id],
+        )
+        .await?;
+    let Some(row) = rows.next().await? else {
+        return Err(FeedbackError::NotFound(feedback_id));
+    };
+    let prompt: String = row.get(0)?;
+    let correction: Option<String> = row.get(1)?;
+    let owner_id: Option<String> = row.get(2)?;
+    let Some(answer) = correction.filter(|c| !c.trim().is_empty()) else {
+        return Err(FeedbackError::MissingCorrection(feedback_id));
+    };
+
+    conn.execute(
+        "INSERT INTO few_shot_examples (prompt, answer, feedback_id, owner_id) VALUES (?, ?, ?, ?)",
+        params![prompt.as_str(), answer.as_str(), feedback_id, owner_id],
+    )
+    .await?;
+    conn.execute(
+        "UPDATE feedback SET accepted = 1 WHERE id = ?",
+        params![feedback_id],
+    )
+    .await?;
+
+    info!("Accepted the correction of feedback {feedback_id} as a few-shot example.");
+    Ok(FewShotExample { prompt, answer })
+}
+
+/// Returns up to `limit` of the few-shot examples of `owner_id` whose prompts share the
+/// most words with `prompt`.
+///
+/// Examples with no words in common are never returned, so unrelated corrections
+/// do not leak into the prompt, and those of other owners are never considered.
+pub async fn find_few_shot_examples(
+    db: &Database,
+    owner_id: Option<&str>,
+    prompt: &str,
+    limit: usize,
+) -> Result<Vec<FewShotExample>, FeedbackError> {
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT prompt, answer FROM few_shot_examples WHERE owner_id IS ? ORDER BY id DESC LIMIT {FEW_SHOT_CANDIDATE_LIMIT}"
+            ),
+            params![owner_id.map(str::to_string)],
+        )
+        .await?;
+
+    let prompt_words = words(prompt);
+    let mut scored = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let example = FewShotExample {
+            prompt: row.get(0)?,
+            answer: row.get(1)?,
+        };
+        let overlap = words(&example.prompt).intersection(&prompt_words).count();
+        if overlap > 0 {
+            scored.push((overlap, example));
+        }
+    }
+
+    // A stable sort keeps the most recent example first among equal scores.
+    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    Ok(scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, example)| example)
+        .collect())
+}
+
+/// Formats few-shot examples into a prompt block.
+pub fn format_few_shot_examples(examples: &[FewShotExample]) -> String {
+    examples
+        .iter()
+        .map(|example| format!("Q: {}\nA: {}", example.prompt, example.answer))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Splits text into a set of lowercase words, ignoring very short ones.
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() > 2)
+        .map(str::to_lowercase)
+        .collect()
+}
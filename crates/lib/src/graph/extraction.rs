@@ -0,0 +1,132 @@
+//! # Fact Extraction
+//!
+//! Asks an AI provider for the facts a document states, as (subject, predicate, object)
+//! triples with an optional validity period, and adds them to a `KnowledgeGraph`
+//! linked back to the document they came from.
+
+use super::types::{KnowledgeGraph, KnowledgeGraphError};
+use crate::ingest::knowledge::clean_llm_response;
+use crate::providers::ai::AiProvider;
+use chrono::{DateTime, NaiveDate, Utc};
+use indradb::Datastore;
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+/// The end of the validity period of facts that state none.
+const TIMELESS_END: &str = "9999-12-31T23:59:59Z";
+
+/// A fact stated by a document.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct ExtractedFact {
+    pub subject: String,
+    pub predicate: String,
+    pub object: String,
+    /// When the fact starts to hold, as an RFC 3339 timestamp or a `YYYY-MM-DD` date.
+    #[serde(default)]
+    pub valid_from: Option<String>,
+    /// When the fact stops holding, in the same formats as `valid_from`.
+    #[serde(default)]
+    pub valid_to: Option<String>,
+}
+
+impl ExtractedFact {
+    /// The predicate as a graph edge type: lowercase words joined by underscores.
+    pub fn normalized_predicate(&self) -> String {
+        normalize_predicate(&self.predicate)
+    }
+
+    /// The period the fact holds in. A missing or unreadable bound leaves that side of
+    /// the period open.
+    pub fn validity(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let start = self
+            .valid_from
+            .as_deref()
+            .and_then(parse_time)
+            .unwrap_or(DateTime::UNIX_EPOCH);
+        let end = self
+            .valid_to
+            .as_deref()
+            .and_then(parse_time)
+            .unwrap_or_else(timeless_end);
+        (start, end)
+    }
+}
+
+/// Turns a predicate into a graph edge type: lowercase words joined by underscores.
+pub(crate) fn normalize_predicate(predicate: &str) -> String {
+    predicate
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+pub(crate) fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| time.and_utc())
+}
+
+fn timeless_end() -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(TIMELESS_END)
+        .expect("the timeless end is a valid timestamp")
+        .with_timezone(&Utc)
+}
+
+/// Asks the AI provider for the facts `content` states.
+///
+/// Returns no facts when the response is not a JSON array of facts, so one
+/// unreadable response does not fail an ingestion.
+pub async fn extract_facts(
+    ai_provider: &dyn AiProvider,
+    content: &str,
+    system_prompt: &str,
+    user_prompt_template: &str,
+) -> Result<Vec<ExtractedFact>, KnowledgeGraphError> {
+    let user_prompt = user_prompt_template.replace("{content}", content);
+    let response = ai_provider.generate(system_prompt, &user_prompt).await?;
+    debug!("LLM fact extraction response: {response}");
+    let cleaned_response = clean_llm_response(&response);
+
+    match serde_json::from_str::<Vec<ExtractedFact>>(&cleaned_response) {
+        Ok(facts) => Ok(facts),
+        Err(e) => {
+            warn!("Failed to parse extracted facts, skipping. Error: {e}");
+            Ok(Vec::new())
+        }
+    }
+}
+
+impl<D: Datastore> KnowledgeGraph<D> {
+    /// Adds the facts extracted from a document, linked to its id. Facts with an empty
+    /// part, or that the graph rejects, are skipped. Returns the number of facts added.
+    pub fn add_document_facts(&mut self, document_id: &str, facts: &[ExtractedFact]) -> usize {
+        let mut added = 0;
+        for fact in facts {
+            let predicate = fact.normalized_predicate();
+            let subject = fact.subject.trim();
+            let object = fact.object.trim();
+            if subject.is_empty() || predicate.is_empty() || object.is_empty() {
+                continue;
+            }
+            let (start_time, end_time) = fact.validity();
+            match self.add_fact_from_document(
+                subject,
+                &predicate,
+                object,
+                start_time,
+                end_time,
+                document_id,
+            ) {
+                Ok(()) => added += 1,
+                Err(e) => warn!("Skipping fact '{subject} {predicate} {object}': {e}"),
+            }
+        }
+        added
+    }
+}
@@ -6,16 +6,23 @@
 //! a specific moment. This entire module is compiled only when the `graph_db`
 //! feature is enabled.
 
+pub mod extraction;
+pub mod nl_query;
+pub mod persistent;
+pub mod query;
+pub mod resolution;
+pub mod store;
 pub mod types;
 
+use self::query::FactVisibility;
 use self::types::{
     KnowledgeGraph, KnowledgeGraphError, MemoryKnowledgeGraph, RocksdbKnowledgeGraph,
     TimeConstraint,
 };
 use chrono::{DateTime, Utc};
 use indradb::{
-    Datastore, Edge, Identifier, Json, MemoryDatastore, QueryExt, RocksdbDatastore,
-    SpecificVertexQuery, Transaction, Vertex,
+    Datastore, Edge, EdgeProperties, Identifier, Json, MemoryDatastore, QueryExt, RocksdbDatastore,
+    SpecificEdgeQuery, SpecificVertexQuery, Transaction, Vertex,
 };
 use serde_json::json;
 use std::collections::HashMap;
@@ -24,6 +31,9 @@ use uuid::Uuid;
 
 const TIME_PROPERTY_NAME: &str = "time";
 const NAME_PROPERTY_NAME: &str = "name";
+const SOURCE_DOCUMENT_PROPERTY_NAME: &str = "source_document";
+const SOURCE_DOCUMENTS_PROPERTY_NAME: &str = "source_documents";
+const ALIASES_PROPERTY_NAME: &str = "aliases";
 
 impl MemoryKnowledgeGraph {
     /// Creates a new in-memory `KnowledgeGraph`.
@@ -95,6 +105,53 @@ impl<D: Datastore> KnowledgeGraph<D> {
         start_time: DateTime<Utc>,
         end_time: DateTime<Utc>,
     ) -> Result<(), KnowledgeGraphError> {
+        self.insert_fact(subject, predicate, object, start_time, end_time, None)
+    }
+
+    /// Adds a fact like `add_fact`, recording the id of the document it was extracted
+    /// from so it can be traced back with `get_fact_source_as_of`. A fact stated by
+    /// several documents keeps the ids of all of them, so it stays readable to whoever
+    /// may read one of them (see `query::FactVisibility`).
+    pub fn add_fact_from_document(
+        &mut self,
+        subject: &str,
+        predicate: &str,
+        object: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        document_id: &str,
+    ) -> Result<(), KnowledgeGraphError> {
+        self.insert_fact(
+            subject,
+            predicate,
+            object,
+            start_time,
+            end_time,
+            Some(document_id),
+        )
+    }
+
+    fn insert_fact(
+        &mut self,
+        subject: &str,
+        predicate: &str,
+        object: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        document_id: Option<&str>,
+    ) -> Result<(), KnowledgeGraphError> {
+        let source_documents = match document_id {
+            Some(document_id) => {
+                let mut source_documents =
+                    self.stored_source_documents(subject, predicate, object)?;
+                if !source_documents.iter().any(|id| id == document_id) {
+                    source_documents.push(document_id.to_string());
+                }
+                source_documents
+            }
+            None => Vec::new(),
+        };
+
         let mut transaction = self.db.datastore.transaction();
         let subject_id =
             Self::get_or_create_vertex(&mut self.entity_map, &mut transaction, subject)?;
@@ -111,10 +168,24 @@ impl<D: Datastore> KnowledgeGraph<D> {
         let time_prop_name = Identifier::new(TIME_PROPERTY_NAME)?;
 
         transaction.set_edge_properties(
-            vec![edge],
+            vec![edge.clone()],
             time_prop_name,
             &Json::new(json!(time_constraint)),
         )?;
+        if let Some(document_id) = document_id {
+            let source_prop_name = Identifier::new(SOURCE_DOCUMENT_PROPERTY_NAME)?;
+            transaction.set_edge_properties(
+                vec![edge.clone()],
+                source_prop_name,
+                &Json::new(json!(document_id)),
+            )?;
+            let sources_prop_name = Identifier::new(SOURCE_DOCUMENTS_PROPERTY_NAME)?;
+            transaction.set_edge_properties(
+                vec![edge],
+                sources_prop_name,
+                &Json::new(json!(source_documents)),
+            )?;
+        }
 
         // The transaction is automatically committed/rolled back when it goes
         // out of scope (RAII), as the `Transaction` trait does not define a
@@ -122,14 +193,91 @@ impl<D: Datastore> KnowledgeGraph<D> {
         Ok(())
     }
 
-    /// Retrieves the object of a fact that is valid at a specific point in time.
+    /// Returns the ids of the documents an existing fact was extracted from.
+    fn stored_source_documents(
+        &self,
+        subject: &str,
+        predicate: &str,
+        object: &str,
+    ) -> Result<Vec<String>, KnowledgeGraphError> {
+        let edge = Edge::new(
+            query::entity_id(subject),
+            Identifier::new(predicate)?,
+            query::entity_id(object),
+        );
+        let results = self.db.get(SpecificEdgeQuery::single(edge).properties()?)?;
+        Ok(indradb::util::extract_edge_properties(results)
+            .unwrap_or_default()
+            .into_iter()
+            .next()
+            .map(|properties| query::source_documents(&properties))
+            .unwrap_or_default())
+    }
+
+    /// Retrieves the object of a visible fact that is valid at a specific point in time.
     pub fn get_fact_as_of(
         &self,
         subject: &str,
         predicate: &str,
         as_of: DateTime<Utc>,
+        visibility: &FactVisibility,
     ) -> Result<Option<String>, KnowledgeGraphError> {
-        let subject_id = Uuid::new_v5(&Uuid::NAMESPACE_DNS, subject.as_bytes());
+        let Some(edge_properties) = self.valid_edge_as_of(subject, predicate, as_of, visibility)?
+        else {
+            return Ok(None);
+        };
+
+        // Found a valid edge. Now get the object's name property.
+        let object_id = edge_properties.edge.inbound_id;
+        let name_prop = Identifier::new(NAME_PROPERTY_NAME)?;
+        let prop_query = SpecificVertexQuery::single(object_id)
+            .properties()?
+            .name(name_prop);
+
+        let prop_results = self.db.get(prop_query)?;
+        let vertex_props = indradb::util::extract_vertex_properties(prop_results)
+            .ok_or(KnowledgeGraphError::NotFound)?;
+
+        if let Some(v_prop) = vertex_props.into_iter().next() {
+            if let Some(named_prop) = v_prop.props.into_iter().next() {
+                if let serde_json::Value::String(s) = named_prop.value.0.as_ref() {
+                    return Ok(Some(s.clone()));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Retrieves the id of the latest visible document a fact valid at a specific point
+    /// in time was extracted from. Facts added with `add_fact` have none.
+    pub fn get_fact_source_as_of(
+        &self,
+        subject: &str,
+        predicate: &str,
+        as_of: DateTime<Utc>,
+        visibility: &FactVisibility,
+    ) -> Result<Option<String>, KnowledgeGraphError> {
+        let Some(edge_properties) = self.valid_edge_as_of(subject, predicate, as_of, visibility)?
+        else {
+            return Ok(None);
+        };
+        let source_documents = visibility
+            .visible_sources(query::source_documents(&edge_properties))
+            .unwrap_or_default();
+        Ok(source_documents.last().cloned())
+    }
+
+    /// Finds the properties of the first visible `predicate` edge of `subject` that is
+    /// valid at `as_of`.
+    fn valid_edge_as_of(
+        &self,
+        subject: &str,
+        predicate: &str,
+        as_of: DateTime<Utc>,
+        visibility: &FactVisibility,
+    ) -> Result<Option<EdgeProperties>, KnowledgeGraphError> {
+        let subject_id = self.resolve_entity(subject);
         let predicate_id = Identifier::new(predicate)?;
 
         // Build a query that gets all properties for the outbound edges of a specific type from the subject.
@@ -145,30 +293,20 @@ impl<D: Datastore> KnowledgeGraph<D> {
         let time_prop_name = Identifier::new(TIME_PROPERTY_NAME)?;
 
         for prop in edge_properties {
-            if let Some(time_json) = prop.props.iter().find(|p| p.name == time_prop_name) {
-                let time_constraint: TimeConstraint =
-                    serde_json::from_value((*time_json.value.0).clone())?;
-
-                if as_of >= time_constraint.start_time && as_of < time_constraint.end_time {
-                    // Found a valid edge. Now get the object's name property.
-                    let object_id = prop.edge.inbound_id;
-                    let name_prop = Identifier::new(NAME_PROPERTY_NAME)?;
-                    let prop_query = SpecificVertexQuery::single(object_id)
-                        .properties()?
-                        .name(name_prop);
-
-                    let prop_results = self.db.get(prop_query)?;
-                    let vertex_props = indradb::util::extract_vertex_properties(prop_results)
-                        .ok_or(KnowledgeGraphError::NotFound)?;
-
-                    if let Some(v_prop) = vertex_props.into_iter().next() {
-                        if let Some(named_prop) = v_prop.props.into_iter().next() {
-                            if let serde_json::Value::String(s) = named_prop.value.0.as_ref() {
-                                return Ok(Some(s.clone()));
-                            }
-                        }
-                    }
-                }
+            if visibility
+                .visible_sources(query::source_documents(&prop))
+                .is_none()
+            {
+                continue;
+            }
+            let Some(time_json) = prop.props.iter().find(|p| p.name == time_prop_name) else {
+                continue;
+            };
+            let time_constraint: TimeConstraint =
+                serde_json::from_value((*time_json.value.0).clone())?;
+
+            if as_of >= time_constraint.start_time && as_of < time_constraint.end_time {
+                return Ok(Some(prop));
             }
         }
 
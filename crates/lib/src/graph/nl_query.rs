@@ -0,0 +1,199 @@
+//! # Natural Language Graph Queries
+//!
+//! The knowledge graph counterpart of the text-to-SQL pipeline: an AI provider turns a
+//! question into a structured `GraphQuery` (entity, predicate, point in time), which is
+//! run against the graph. When it matches facts, they are formatted into the answer;
+//! when it matches none, the caller falls back to RAG.
+
+use super::extraction::{normalize_predicate, parse_time};
+use super::query::{FactVisibility, GraphFact};
+use super::types::{KnowledgeGraph, KnowledgeGraphError};
+use crate::ingest::knowledge::clean_llm_response;
+use crate::types::{ExecutePromptOptions, PipelineStage, PipelineStep, PromptResult};
+use crate::{PromptClient, PromptError};
+use chrono::{DateTime, Utc};
+use indradb::Datastore;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Instant;
+use tracing::{info, instrument, warn};
+
+/// The instruction the matched facts are formatted into an answer with, when the
+/// caller gives none.
+const DEFAULT_GRAPH_ANSWER_INSTRUCTION: &str =
+    "Answer the prompt in one or two sentences, using only the facts in the input data.";
+
+/// A structured knowledge graph query.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct GraphQuery {
+    /// The entity the question is about, as the subject of its facts.
+    pub entity: String,
+    /// The relationship asked for. All of the entity's facts match without one.
+    #[serde(default)]
+    pub predicate: Option<String>,
+    /// When the facts must hold, as an RFC 3339 timestamp or a `YYYY-MM-DD` date. The
+    /// present when unset.
+    #[serde(default)]
+    pub as_of: Option<String>,
+}
+
+/// The response of the query generation task, whose entity is null for questions
+/// that are not about one.
+#[derive(Deserialize)]
+struct GeneratedGraphQuery {
+    entity: Option<String>,
+    #[serde(default)]
+    predicate: Option<String>,
+    #[serde(default)]
+    as_of: Option<String>,
+}
+
+impl GraphQuery {
+    /// The predicate as a graph edge type: lowercase words joined by underscores.
+    pub fn normalized_predicate(&self) -> Option<String> {
+        let predicate = normalize_predicate(self.predicate.as_deref()?);
+        (!predicate.is_empty()).then_some(predicate)
+    }
+
+    /// The time the facts must hold at. An unreadable `as_of` is treated as the present.
+    pub fn as_of_time(&self) -> DateTime<Utc> {
+        self.as_of
+            .as_deref()
+            .and_then(parse_time)
+            .unwrap_or_else(Utc::now)
+    }
+}
+
+/// Parses the response of the query generation task. Returns `None` when it is not a
+/// query about an entity.
+pub fn parse_graph_query(response: &str) -> Option<GraphQuery> {
+    let cleaned_response = clean_llm_response(response);
+    let generated: GeneratedGraphQuery = match serde_json::from_str(&cleaned_response) {
+        Ok(generated) => generated,
+        Err(e) => {
+            warn!("Failed to parse the generated graph query: {e}");
+            return None;
+        }
+    };
+    let entity = generated.entity?.trim().to_string();
+    if entity.is_empty() {
+        return None;
+    }
+    Some(GraphQuery {
+        entity,
+        predicate: generated.predicate.filter(|p| !p.trim().is_empty()),
+        as_of: generated.as_of.filter(|a| !a.trim().is_empty()),
+    })
+}
+
+impl<D: Datastore> KnowledgeGraph<D> {
+    /// Runs a graph query over the visible facts. An entity the graph does not hold
+    /// matches no facts.
+    pub fn execute_graph_query(
+        &self,
+        query: &GraphQuery,
+        visibility: &FactVisibility,
+    ) -> Result<Vec<GraphFact>, KnowledgeGraphError> {
+        let facts = match self.facts_about_as_of(&query.entity, query.as_of_time(), visibility) {
+            Ok(facts) => facts,
+            Err(KnowledgeGraphError::EntityNotFound(_)) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        Ok(match query.normalized_predicate() {
+            Some(predicate) => facts
+                .into_iter()
+                .filter(|fact| fact.predicate == predicate)
+                .collect(),
+            None => facts,
+        })
+    }
+}
+
+impl PromptClient {
+    /// Answers a question from the knowledge graph.
+    ///
+    /// 1.  It calls the AI provider to turn the question into a `GraphQuery`, with the
+    ///     prompts of the graph query generation task.
+    /// 2.  It runs the query with `run_query`, which reads the graph the caller holds.
+    /// 3.  It calls the AI provider again to answer the question from the matched facts,
+    ///     guided by the `instruction`.
+    ///
+    /// Returns `None` when the question is not about an entity or matches no facts, so
+    /// the caller can fall back to RAG.
+    #[instrument(name = "prompt.graph", skip_all)]
+    pub async fn execute_graph_prompt<F>(
+        &self,
+        question: &str,
+        system_prompt: &str,
+        user_prompt_template: &str,
+        instruction: Option<&str>,
+        run_query: F,
+    ) -> Result<Option<PromptResult>, PromptError>
+    where
+        F: FnOnce(&GraphQuery) -> Result<Vec<GraphFact>, KnowledgeGraphError>,
+    {
+        let mut trace = Vec::new();
+        let user_prompt = user_prompt_template
+            .replace("{today}", &Utc::now().to_rfc3339())
+            .replace("{prompt}", question);
+
+        let started = Instant::now();
+        let response = self
+            .ai_provider
+            .generate(system_prompt, &user_prompt)
+            .await?;
+        trace.push(PipelineStep::finished(
+            PipelineStage::AiResponse,
+            started,
+            Value::String(response.clone()),
+        ));
+
+        let started = Instant::now();
+        let Some(query) = parse_graph_query(&response) else {
+            info!("[execute_graph_prompt] The question is not about an entity of the graph.");
+            return Ok(None);
+        };
+        trace.push(PipelineStep::finished(
+            PipelineStage::GeneratedGraphQuery,
+            started,
+            json!(query),
+        ));
+
+        let started = Instant::now();
+        let facts =
+            run_query(&query).map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+        trace.push(PipelineStep::finished(
+            PipelineStage::FactsReturned,
+            started,
+            json!({ "facts": facts.len() }),
+        ));
+        if facts.is_empty() {
+            info!("[execute_graph_prompt] The graph query matched no facts.");
+            return Ok(None);
+        }
+
+        let facts_json = serde_json::to_string_pretty(&facts)?;
+        let options = ExecutePromptOptions {
+            prompt: question.to_string(),
+            instruction: Some(
+                instruction
+                    .filter(|i| !i.is_empty())
+                    .unwrap_or(DEFAULT_GRAPH_ANSWER_INSTRUCTION)
+                    .to_string(),
+            ),
+            ..Default::default()
+        };
+        let text = self
+            .format_response(&facts_json, &options, &mut trace)
+            .await?;
+
+        Ok(Some(PromptResult {
+            text,
+            database_result: Some(facts_json),
+            system_prompt: Some(system_prompt.to_string()),
+            user_prompt: Some(user_prompt),
+            trace,
+            ..Default::default()
+        }))
+    }
+}
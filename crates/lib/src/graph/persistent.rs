@@ -0,0 +1,240 @@
+//! # Persistent Knowledge Graph
+//!
+//! Keeps a RocksDB-backed knowledge graph in a directory of its own, so the graph
+//! survives restarts. The RocksDB store lives in a generation subdirectory (`gen-<n>`)
+//! named by the directory's `CURRENT` file. Compaction rewrites the live graph into
+//! the next generation and switches `CURRENT` to it, so the directory always holds one
+//! complete store, even if the process stops halfway through.
+
+use super::types::{KnowledgeGraph, KnowledgeGraphError, RocksdbKnowledgeGraph};
+use super::{ALIASES_PROPERTY_NAME, NAME_PROPERTY_NAME};
+use indradb::{AllEdgeQuery, AllVertexQuery, BulkInsertItem, Datastore, Identifier, QueryExt};
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::{info, warn};
+
+/// The file naming the generation in use.
+const CURRENT_FILE: &str = "CURRENT";
+const GENERATION_PREFIX: &str = "gen-";
+
+/// The size of a graph after compaction.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct CompactionStats {
+    pub entities: usize,
+    pub facts: usize,
+}
+
+/// A RocksDB-backed knowledge graph in a directory of generations.
+pub struct PersistentKnowledgeGraph {
+    dir: PathBuf,
+    generation: u64,
+    graph: RocksdbKnowledgeGraph,
+}
+
+impl PersistentKnowledgeGraph {
+    /// Opens the graph in `dir`, creating it if needed. Generations left behind by an
+    /// interrupted compaction are removed.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, KnowledgeGraphError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let generation = read_current(&dir)?;
+        remove_stale_generations(&dir, generation)?;
+
+        let mut graph = RocksdbKnowledgeGraph::new_rocksdb(generation_dir(&dir, generation))?;
+        graph.load_entity_map()?;
+        write_current(&dir, generation)?;
+        info!(
+            "Opened the knowledge graph at '{}' with {} entities.",
+            dir.display(),
+            graph.entity_map.len()
+        );
+        Ok(Self {
+            dir,
+            generation,
+            graph,
+        })
+    }
+
+    /// The graph, for reads.
+    pub fn graph(&self) -> &RocksdbKnowledgeGraph {
+        &self.graph
+    }
+
+    /// The graph, for writes.
+    pub fn graph_mut(&mut self) -> &mut RocksdbKnowledgeGraph {
+        &mut self.graph
+    }
+
+    /// The directory of the RocksDB store in use.
+    pub fn store_dir(&self) -> PathBuf {
+        generation_dir(&self.dir, self.generation)
+    }
+
+    /// Flushes the graph to disk.
+    pub fn sync(&self) -> Result<(), KnowledgeGraphError> {
+        self.graph.db.sync()?;
+        Ok(())
+    }
+
+    /// Removes every entity and fact.
+    pub fn clear(&mut self) -> Result<(), KnowledgeGraphError> {
+        self.graph.db.delete(AllVertexQuery)?;
+        self.graph.entity_map.clear();
+        Ok(())
+    }
+
+    /// Rewrites the live graph into a new store, dropping the space deleted and
+    /// overwritten data still takes up, and removes the old store.
+    pub fn compact(&mut self) -> Result<CompactionStats, KnowledgeGraphError> {
+        let (items, stats) = self.graph.bulk_items()?;
+        let next = self.generation + 1;
+        let next_dir = generation_dir(&self.dir, next);
+        if next_dir.exists() {
+            fs::remove_dir_all(&next_dir)?;
+        }
+
+        let mut compacted = RocksdbKnowledgeGraph::new_rocksdb(&next_dir)?;
+        compacted.db.bulk_insert(items)?;
+        compacted.db.sync()?;
+        compacted.load_entity_map()?;
+        write_current(&self.dir, next)?;
+
+        // Replacing the graph closes the old store, so its directory can be removed.
+        self.graph = compacted;
+        let previous_dir = generation_dir(&self.dir, self.generation);
+        self.generation = next;
+        if let Err(e) = fs::remove_dir_all(&previous_dir) {
+            warn!(
+                "Could not remove the compacted store at '{}': {e}",
+                previous_dir.display()
+            );
+        }
+        info!(
+            "Compacted the knowledge graph into '{}': {} entities, {} facts.",
+            self.store_dir().display(),
+            stats.entities,
+            stats.facts
+        );
+        Ok(stats)
+    }
+}
+
+impl<D: Datastore> KnowledgeGraph<D> {
+    /// Fills the entity cache from the names and aliases stored on the graph's vertices.
+    pub fn load_entity_map(&mut self) -> Result<(), KnowledgeGraphError> {
+        let name_prop = Identifier::new(NAME_PROPERTY_NAME)?;
+        let aliases_prop = Identifier::new(ALIASES_PROPERTY_NAME)?;
+        let results = self.db.get(AllVertexQuery.properties()?)?;
+        let vertex_properties =
+            indradb::util::extract_vertex_properties(results).unwrap_or_default();
+        for vertex_property in vertex_properties {
+            let id = vertex_property.vertex.id;
+            for prop in vertex_property.props {
+                if prop.name == name_prop {
+                    if let Some(name) = prop.value.0.as_str() {
+                        self.entity_map.insert(name.to_string(), id);
+                    }
+                } else if prop.name == aliases_prop {
+                    let aliases: Vec<String> =
+                        serde_json::from_value((*prop.value.0).clone()).unwrap_or_default();
+                    for alias in aliases {
+                        self.entity_map.insert(alias, id);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Lists every vertex, edge and property of the graph, in the order a bulk insert
+    /// needs them, along with the number of entities and facts.
+    fn bulk_items(&self) -> Result<(Vec<BulkInsertItem>, CompactionStats), KnowledgeGraphError> {
+        let vertices =
+            indradb::util::extract_vertices(self.db.get(AllVertexQuery)?).unwrap_or_default();
+        let edges = indradb::util::extract_edges(self.db.get(AllEdgeQuery)?).unwrap_or_default();
+        let vertex_properties =
+            indradb::util::extract_vertex_properties(self.db.get(AllVertexQuery.properties()?)?)
+                .unwrap_or_default();
+        let edge_properties =
+            indradb::util::extract_edge_properties(self.db.get(AllEdgeQuery.properties()?)?)
+                .unwrap_or_default();
+        let stats = CompactionStats {
+            entities: vertices.len(),
+            facts: edges.len(),
+        };
+
+        let mut items: Vec<BulkInsertItem> =
+            vertices.into_iter().map(BulkInsertItem::Vertex).collect();
+        items.extend(edges.into_iter().map(BulkInsertItem::Edge));
+        for vertex_property in vertex_properties {
+            let id = vertex_property.vertex.id;
+            items.extend(
+                vertex_property
+                    .props
+                    .into_iter()
+                    .map(|p| BulkInsertItem::VertexProperty(id, p.name, p.value)),
+            );
+        }
+        for edge_property in edge_properties {
+            let edge = edge_property.edge;
+            items.extend(
+                edge_property
+                    .props
+                    .into_iter()
+                    .map(|p| BulkInsertItem::EdgeProperty(edge.clone(), p.name, p.value)),
+            );
+        }
+        Ok((items, stats))
+    }
+}
+
+fn generation_dir(dir: &Path, generation: u64) -> PathBuf {
+    dir.join(format!("{GENERATION_PREFIX}{generation}"))
+}
+
+/// Reads the generation in use, which is the first one when there is none yet.
+fn read_current(dir: &Path) -> Result<u64, KnowledgeGraphError> {
+    let path = dir.join(CURRENT_FILE);
+    if !path.exists() {
+        return Ok(0);
+    }
+    let current = fs::read_to_string(&path)?;
+    let generation = current
+        .trim()
+        .strip_prefix(GENERATION_PREFIX)
+        .and_then(|n| n.parse().ok());
+    match generation {
+        Some(generation) => Ok(generation),
+        None => Err(KnowledgeGraphError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Unreadable '{}': '{}'", path.display(), current.trim()),
+        ))),
+    }
+}
+
+/// Points `CURRENT` at a generation. The file is replaced by a rename, so it is never
+/// seen half written.
+fn write_current(dir: &Path, generation: u64) -> Result<(), KnowledgeGraphError> {
+    let staged = dir.join(format!("{CURRENT_FILE}.tmp"));
+    fs::write(&staged, format!("{GENERATION_PREFIX}{generation}\n"))?;
+    fs::rename(&staged, dir.join(CURRENT_FILE))?;
+    Ok(())
+}
+
+/// Removes every generation directory other than the one in use.
+fn remove_stale_generations(dir: &Path, current: u64) -> Result<(), KnowledgeGraphError> {
+    let current_name = format!("{GENERATION_PREFIX}{current}");
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.starts_with(GENERATION_PREFIX) || name == current_name {
+            continue;
+        }
+        warn!("Removing stale knowledge graph store '{name}'.");
+        fs::remove_dir_all(entry.path())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,370 @@
+//! # Graph Queries
+//!
+//! Reads beyond a single subject/predicate lookup: the neighborhood of an entity, the
+//! shortest path between two entities, and every fact about an entity as of a point in
+//! time. Results are returned as nodes and edges, the shape graph visualization UIs
+//! expect.
+//!
+//! Every read takes a `FactVisibility`, so a reader only sees the facts of the
+//! documents they may read.
+
+use super::types::{KnowledgeGraph, KnowledgeGraphError, TimeConstraint};
+use super::{
+    NAME_PROPERTY_NAME, SOURCE_DOCUMENTS_PROPERTY_NAME, SOURCE_DOCUMENT_PROPERTY_NAME,
+    TIME_PROPERTY_NAME,
+};
+use chrono::{DateTime, Utc};
+use indradb::{
+    Datastore, EdgeProperties, Identifier, QueryExt, QueryOutputValue, SpecificVertexQuery,
+};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use uuid::Uuid;
+
+/// An entity of the graph.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct GraphNode {
+    pub id: Uuid,
+    pub name: String,
+}
+
+/// A fact of the graph, from the `source` entity to the `target` entity.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct GraphEdge {
+    pub source: Uuid,
+    pub target: Uuid,
+    pub predicate: String,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    /// The id of the latest document the fact was extracted from, if any.
+    pub source_document: Option<String>,
+    /// The ids of every document the fact was extracted from.
+    pub source_documents: Vec<String>,
+}
+
+/// A subgraph: the entities and the facts between them.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct GraphView {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// A fact about an entity, with its parts named.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct GraphFact {
+    pub subject: String,
+    pub predicate: String,
+    pub object: String,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub source_document: Option<String>,
+    pub source_documents: Vec<String>,
+}
+
+/// Which facts a reader may see. A fact extracted from documents is visible when one
+/// of them is; facts that were not extracted from a document, such as those built from
+/// a database table, are visible to everyone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FactVisibility {
+    /// Every fact.
+    All,
+    /// The facts of these documents, and those of no document.
+    Documents(HashSet<String>),
+}
+
+impl FactVisibility {
+    /// Keeps the ids among a fact's `source_documents` that the reader may see.
+    /// Returns `None` when the fact is hidden from them.
+    pub fn visible_sources(&self, source_documents: Vec<String>) -> Option<Vec<String>> {
+        match self {
+            Self::All => Some(source_documents),
+            Self::Documents(_) if source_documents.is_empty() => Some(source_documents),
+            Self::Documents(visible) => {
+                let visible_sources: Vec<String> = source_documents
+                    .into_iter()
+                    .filter(|id| visible.contains(id))
+                    .collect();
+                (!visible_sources.is_empty()).then_some(visible_sources)
+            }
+        }
+    }
+}
+
+/// The id of an entity's vertex, which is derived from its name.
+pub(crate) fn entity_id(name: &str) -> Uuid {
+    Uuid::new_v5(&Uuid::NAMESPACE_DNS, name.as_bytes())
+}
+
+impl<D: Datastore> KnowledgeGraph<D> {
+    /// Returns an entity with the facts it is the subject or object of, and the entities
+    /// on their other end. With `as_of`, only the facts valid at that time are included.
+    pub fn neighbors(
+        &self,
+        entity: &str,
+        as_of: Option<DateTime<Utc>>,
+        visibility: &FactVisibility,
+    ) -> Result<GraphView, KnowledgeGraphError> {
+        let id = self.visible_entity(entity, visibility)?;
+        let edges = self.incident_edges(id, as_of, visibility)?;
+        let mut node_ids = vec![id];
+        for edge in &edges {
+            for neighbor in [edge.source, edge.target] {
+                if !node_ids.contains(&neighbor) {
+                    node_ids.push(neighbor);
+                }
+            }
+        }
+        Ok(GraphView {
+            nodes: self.nodes(&node_ids)?,
+            edges,
+        })
+    }
+
+    /// Finds the shortest chain of facts linking two entities, following facts in either
+    /// direction, up to `max_depth` facts long. With `as_of`, only the facts valid at that
+    /// time are followed. Returns `None` when the entities are not linked.
+    pub fn shortest_path(
+        &self,
+        from: &str,
+        to: &str,
+        max_depth: usize,
+        as_of: Option<DateTime<Utc>>,
+        visibility: &FactVisibility,
+    ) -> Result<Option<GraphView>, KnowledgeGraphError> {
+        let start = self.visible_entity(from, visibility)?;
+        let goal = self.visible_entity(to, visibility)?;
+        if start == goal {
+            return Ok(Some(GraphView {
+                nodes: self.nodes(&[start])?,
+                edges: Vec::new(),
+            }));
+        }
+
+        // A breadth-first search, remembering the edge each vertex was first reached by.
+        let mut reached_by: HashMap<Uuid, (Uuid, GraphEdge)> = HashMap::new();
+        let mut visited = HashSet::from([start]);
+        let mut frontier = VecDeque::from([(start, 0)]);
+        while let Some((id, depth)) = frontier.pop_front() {
+            if depth >= max_depth {
+                continue;
+            }
+            for edge in self.incident_edges(id, as_of, visibility)? {
+                let next = if edge.source == id {
+                    edge.target
+                } else {
+                    edge.source
+                };
+                if !visited.insert(next) {
+                    continue;
+                }
+                reached_by.insert(next, (id, edge));
+                if next == goal {
+                    return self.path_view(start, goal, reached_by).map(Some);
+                }
+                frontier.push_back((next, depth + 1));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns every fact whose subject is `entity` and that is valid at `as_of`.
+    pub fn facts_about_as_of(
+        &self,
+        entity: &str,
+        as_of: DateTime<Utc>,
+        visibility: &FactVisibility,
+    ) -> Result<Vec<GraphFact>, KnowledgeGraphError> {
+        let id = self.visible_entity(entity, visibility)?;
+        let query = SpecificVertexQuery::single(id).outbound()?.properties()?;
+        let edges = graph_edges(self.db.get(query)?, Some(as_of), visibility)?;
+
+        let object_ids: Vec<Uuid> = edges.iter().map(|edge| edge.target).collect();
+        let names = self.names(&object_ids)?;
+        Ok(edges
+            .into_iter()
+            .map(|edge| GraphFact {
+                subject: entity.to_string(),
+                predicate: edge.predicate,
+                object: names.get(&edge.target).cloned().unwrap_or_default(),
+                start_time: edge.start_time,
+                end_time: edge.end_time,
+                source_document: edge.source_document,
+                source_documents: edge.source_documents,
+            })
+            .collect())
+    }
+
+    /// Returns the id of an entity by its name or one of its aliases, failing when the
+    /// graph does not hold it.
+    pub(super) fn existing_entity(&self, name: &str) -> Result<Uuid, KnowledgeGraphError> {
+        let id = self.resolve_entity(name);
+        let vertices =
+            indradb::util::extract_vertices(self.db.get(SpecificVertexQuery::single(id))?)
+                .unwrap_or_default();
+        if vertices.is_empty() {
+            return Err(KnowledgeGraphError::EntityNotFound(name.to_string()));
+        }
+        Ok(id)
+    }
+
+    /// Returns the id of an entity like `existing_entity`, failing as well when none of
+    /// its facts is visible, so a reader cannot tell the entities of others exist.
+    fn visible_entity(
+        &self,
+        name: &str,
+        visibility: &FactVisibility,
+    ) -> Result<Uuid, KnowledgeGraphError> {
+        let id = self.existing_entity(name)?;
+        if *visibility != FactVisibility::All
+            && self.incident_edges(id, None, visibility)?.is_empty()
+        {
+            return Err(KnowledgeGraphError::EntityNotFound(name.to_string()));
+        }
+        Ok(id)
+    }
+
+    /// Returns the visible facts an entity is the subject or object of.
+    fn incident_edges(
+        &self,
+        id: Uuid,
+        as_of: Option<DateTime<Utc>>,
+        visibility: &FactVisibility,
+    ) -> Result<Vec<GraphEdge>, KnowledgeGraphError> {
+        let outbound = SpecificVertexQuery::single(id).outbound()?.properties()?;
+        let inbound = SpecificVertexQuery::single(id).inbound()?.properties()?;
+        let mut edges = graph_edges(self.db.get(outbound)?, as_of, visibility)?;
+        edges.extend(graph_edges(self.db.get(inbound)?, as_of, visibility)?);
+        Ok(edges)
+    }
+
+    /// Walks the edges a breadth-first search reached `goal` by back to `start`.
+    fn path_view(
+        &self,
+        start: Uuid,
+        goal: Uuid,
+        mut reached_by: HashMap<Uuid, (Uuid, GraphEdge)>,
+    ) -> Result<GraphView, KnowledgeGraphError> {
+        let mut node_ids = vec![goal];
+        let mut edges = Vec::new();
+        let mut current = goal;
+        while current != start {
+            let (previous, edge) = reached_by
+                .remove(&current)
+                .ok_or(KnowledgeGraphError::NotFound)?;
+            edges.push(edge);
+            node_ids.push(previous);
+            current = previous;
+        }
+        node_ids.reverse();
+        edges.reverse();
+        Ok(GraphView {
+            nodes: self.nodes(&node_ids)?,
+            edges,
+        })
+    }
+
+    /// Returns the nodes of the given vertices, in the same order.
+    fn nodes(&self, ids: &[Uuid]) -> Result<Vec<GraphNode>, KnowledgeGraphError> {
+        let names = self.names(ids)?;
+        Ok(ids
+            .iter()
+            .map(|id| GraphNode {
+                id: *id,
+                name: names.get(id).cloned().unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    /// Looks up the names stored on the given vertices.
+    fn names(&self, ids: &[Uuid]) -> Result<HashMap<Uuid, String>, KnowledgeGraphError> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let name_prop = Identifier::new(NAME_PROPERTY_NAME)?;
+        let query = SpecificVertexQuery::new(ids.to_vec())
+            .properties()?
+            .name(name_prop);
+        let vertex_properties =
+            indradb::util::extract_vertex_properties(self.db.get(query)?).unwrap_or_default();
+        Ok(vertex_properties
+            .into_iter()
+            .filter_map(|vertex_property| {
+                let name = vertex_property
+                    .props
+                    .iter()
+                    .find_map(|p| p.value.0.as_str().map(str::to_string))?;
+                Some((vertex_property.vertex.id, name))
+            })
+            .collect())
+    }
+}
+
+/// Converts the output of an edge properties query, keeping the visible edges that are
+/// valid at `as_of` when it is given.
+fn graph_edges(
+    results: Vec<QueryOutputValue>,
+    as_of: Option<DateTime<Utc>>,
+    visibility: &FactVisibility,
+) -> Result<Vec<GraphEdge>, KnowledgeGraphError> {
+    let edge_properties = indradb::util::extract_edge_properties(results).unwrap_or_default();
+    let mut edges = Vec::new();
+    for properties in edge_properties {
+        let Some(edge) = graph_edge(properties, visibility)? else {
+            continue;
+        };
+        let valid = match (as_of, edge.start_time, edge.end_time) {
+            (None, _, _) => true,
+            (Some(as_of), Some(start), Some(end)) => as_of >= start && as_of < end,
+            (Some(_), _, _) => false,
+        };
+        if valid {
+            edges.push(edge);
+        }
+    }
+    Ok(edges)
+}
+
+/// Reads the validity period and source documents of an edge. Returns `None` when the
+/// edge is hidden from the reader; otherwise only its visible source documents are kept.
+fn graph_edge(
+    properties: EdgeProperties,
+    visibility: &FactVisibility,
+) -> Result<Option<GraphEdge>, KnowledgeGraphError> {
+    let Some(source_documents) = visibility.visible_sources(source_documents(&properties)) else {
+        return Ok(None);
+    };
+    let time_prop_name = Identifier::new(TIME_PROPERTY_NAME)?;
+    let mut edge = GraphEdge {
+        source: properties.edge.outbound_id,
+        target: properties.edge.inbound_id,
+        predicate: properties.edge.t.as_str().to_string(),
+        start_time: None,
+        end_time: None,
+        source_document: source_documents.last().cloned(),
+        source_documents,
+    };
+    for prop in properties.props {
+        if prop.name == time_prop_name {
+            let time_constraint: TimeConstraint = serde_json::from_value((*prop.value.0).clone())?;
+            edge.start_time = Some(time_constraint.start_time);
+            edge.end_time = Some(time_constraint.end_time);
+        }
+    }
+    Ok(Some(edge))
+}
+
+/// Reads the ids of the documents an edge was extracted from. Facts added before every
+/// source was kept only name their latest document.
+pub(super) fn source_documents(properties: &EdgeProperties) -> Vec<String> {
+    let mut source_document = None;
+    for prop in &properties.props {
+        if prop.name.as_str() == SOURCE_DOCUMENTS_PROPERTY_NAME {
+            if let Ok(ids) = serde_json::from_value::<Vec<String>>((*prop.value.0).clone()) {
+                return ids;
+            }
+        } else if prop.name.as_str() == SOURCE_DOCUMENT_PROPERTY_NAME {
+            source_document = prop.value.0.as_str().map(str::to_string);
+        }
+    }
+    source_document.into_iter().collect()
+}
@@ -0,0 +1,432 @@
+//! # Entity Resolution
+//!
+//! Facts extracted from different documents often name the same entity differently
+//! ("ACME Inc." and "ACME Corporation"), which splits its facts across vertices that
+//! queries look up one at a time. This module finds such duplicates in three passes,
+//! and merges each into one canonical vertex that records the other names as aliases:
+//!
+//! 1.  **Exact**: names that are equal once case, punctuation and company suffixes
+//!     are removed.
+//! 2.  **Embedding**: names whose embeddings are at least `merge_threshold` similar.
+//! 3.  **LLM**: names whose similarity falls between `adjudication_threshold` and
+//!     `merge_threshold`, which an AI provider judges to be the same entity.
+//!
+//! Planning the merges only reads entity names, so it can run without holding the
+//! graph; applying them is a separate, synchronous step.
+
+use super::query::entity_id;
+use super::types::{KnowledgeGraph, KnowledgeGraphError};
+use super::{ALIASES_PROPERTY_NAME, NAME_PROPERTY_NAME};
+use crate::ingest::knowledge::clean_llm_response;
+use crate::providers::ai::{generate_embeddings_batch, AiProvider};
+use crate::PromptError;
+use indradb::{
+    AllVertexQuery, BulkInsertItem, Datastore, Edge, Identifier, Json, QueryExt,
+    SpecificVertexQuery,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// The similarity at or above which names are merged without asking the AI provider.
+pub const DEFAULT_MERGE_THRESHOLD: f32 = 0.92;
+/// The similarity at or above which the AI provider is asked whether names match.
+pub const DEFAULT_ADJUDICATION_THRESHOLD: f32 = 0.8;
+
+/// Words that distinguish the legal form of a company rather than the company itself.
+const COMPANY_SUFFIXES: &[&str] = &[
+    "inc",
+    "incorporated",
+    "corp",
+    "corporation",
+    "co",
+    "company",
+    "ltd",
+    "limited",
+    "llc",
+    "plc",
+    "gmbh",
+    "ag",
+    "sa",
+];
+
+/// How a duplicate entity was found.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeMethod {
+    Exact,
+    Embedding,
+    Llm,
+}
+
+/// A duplicate entity to merge into its canonical entity.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct EntityMerge {
+    pub canonical: String,
+    pub duplicate: String,
+    pub method: MergeMethod,
+    /// The similarity of the two names' embeddings, for the embedding and LLM passes.
+    pub similarity: Option<f32>,
+}
+
+/// The embedding model the embedding pass compares names with.
+#[derive(Debug, Clone, Copy)]
+pub struct ResolutionEmbedding<'a> {
+    pub api_url: &'a str,
+    pub model: &'a str,
+    pub api_key: Option<&'a str>,
+}
+
+/// The AI provider and prompts the LLM pass judges uncertain pairs with.
+#[derive(Clone, Copy)]
+pub struct ResolutionAdjudicator<'a> {
+    pub ai_provider: &'a dyn AiProvider,
+    pub system_prompt: &'a str,
+    /// A template with `{first}` and `{second}` placeholders for the two names.
+    pub user_prompt_template: &'a str,
+}
+
+/// The passes an entity resolution runs, and their thresholds.
+#[derive(Clone, Copy)]
+pub struct EntityResolutionOptions<'a> {
+    /// Runs the embedding pass when set.
+    pub embedding: Option<ResolutionEmbedding<'a>>,
+    /// Runs the LLM pass when set, which needs the embedding pass.
+    pub adjudicator: Option<ResolutionAdjudicator<'a>>,
+    /// Names at least this similar are merged without asking the AI provider.
+    pub merge_threshold: f32,
+    /// Names at least this similar, but below `merge_threshold`, are judged by the AI
+    /// provider.
+    pub adjudication_threshold: f32,
+}
+
+#[derive(Deserialize)]
+struct Adjudication {
+    same_entity: bool,
+}
+
+/// Reduces an entity name to the part that identifies it: lowercase words, without
+/// punctuation or company suffixes.
+pub fn normalize_entity_name(name: &str) -> String {
+    let words: Vec<String> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let significant: Vec<&str> = words
+        .iter()
+        .map(String::as_str)
+        .filter(|word| !COMPANY_SUFFIXES.contains(word))
+        .collect();
+    // A name made only of suffixes ("The Company") is kept as it is.
+    if significant.is_empty() {
+        words.join(" ")
+    } else {
+        significant.join(" ")
+    }
+}
+
+/// Plans the merges that resolve duplicates among `names`.
+///
+/// Within each group of duplicates, the longest name is kept as the canonical one.
+pub async fn plan_entity_merges(
+    names: &[String],
+    options: &EntityResolutionOptions<'_>,
+) -> Result<Vec<EntityMerge>, PromptError> {
+    // --- 1. Exact pass: group the names by their normalized form. ---
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for name in names {
+        let key = normalize_entity_name(name);
+        if !key.is_empty() {
+            groups.entry(key).or_default().push(name.clone());
+        }
+    }
+    let mut merges = Vec::new();
+    let mut canonicals = Vec::new();
+    for members in groups.into_values() {
+        let canonical = canonical_name(&members).to_string();
+        for member in members.iter().filter(|m| **m != canonical) {
+            merges.push(EntityMerge {
+                canonical: canonical.clone(),
+                duplicate: member.clone(),
+                method: MergeMethod::Exact,
+                similarity: None,
+            });
+        }
+        canonicals.push(canonical);
+    }
+
+    // --- 2. Embedding and LLM passes: compare the remaining names pairwise. ---
+    let Some(embedding) = options.embedding else {
+        return Ok(merges);
+    };
+    if canonicals.len() < 2 {
+        return Ok(merges);
+    }
+    let inputs: Vec<&str> = canonicals.iter().map(String::as_str).collect();
+    let vectors = generate_embeddings_batch(
+        embedding.api_url,
+        embedding.model,
+        &inputs,
+        embedding.api_key,
+    )
+    .await?;
+    if vectors.len() != canonicals.len() {
+        warn!(
+            "The embedding API returned {} vectors for {} entity names, skipping the embedding pass.",
+            vectors.len(),
+            canonicals.len()
+        );
+        return Ok(merges);
+    }
+
+    // Each name is merged at most once, into the first name it matches.
+    let mut merged_into: HashMap<usize, usize> = HashMap::new();
+    for i in 0..canonicals.len() {
+        if merged_into.contains_key(&i) {
+            continue;
+        }
+        for j in (i + 1)..canonicals.len() {
+            if merged_into.contains_key(&j) {
+                continue;
+            }
+            let similarity = cosine_similarity(&vectors[i], &vectors[j]);
+            let method = if similarity >= options.merge_threshold {
+                MergeMethod::Embedding
+            } else if similarity >= options.adjudication_threshold {
+                let Some(adjudicator) = options.adjudicator else {
+                    continue;
+                };
+                if !adjudicate(&adjudicator, &canonicals[i], &canonicals[j]).await? {
+                    continue;
+                }
+                MergeMethod::Llm
+            } else {
+                continue;
+            };
+            merged_into.insert(j, i);
+            let pair = [canonicals[i].clone(), canonicals[j].clone()];
+            let canonical = canonical_name(&pair).to_string();
+            let duplicate = if canonical == pair[0] {
+                pair[1].clone()
+            } else {
+                pair[0].clone()
+            };
+            debug!("Resolved '{duplicate}' as '{canonical}' by {method:?} ({similarity:.3}).");
+            merges.push(EntityMerge {
+                canonical,
+                duplicate,
+                method,
+                similarity: Some(similarity),
+            });
+        }
+    }
+    Ok(chain_merges(merges))
+}
+
+/// Picks the name a group of duplicates is merged into: the longest, then the first in
+/// alphabetical order.
+fn canonical_name(names: &[String]) -> &str {
+    names
+        .iter()
+        .max_by(|a, b| a.len().cmp(&b.len()).then_with(|| b.cmp(a)))
+        .map(String::as_str)
+        .unwrap_or_default()
+}
+
+/// Points merges into a name that is itself merged away at that name's canonical name,
+/// so that applying them in order never targets a removed entity.
+fn chain_merges(merges: Vec<EntityMerge>) -> Vec<EntityMerge> {
+    let targets: HashMap<String, String> = merges
+        .iter()
+        .map(|m| (m.duplicate.clone(), m.canonical.clone()))
+        .collect();
+    merges
+        .into_iter()
+        .filter_map(|mut merge| {
+            let mut seen = 0;
+            while let Some(next) = targets.get(&merge.canonical) {
+                merge.canonical = next.clone();
+                seen += 1;
+                if seen > targets.len() {
+                    return None;
+                }
+            }
+            (merge.canonical != merge.duplicate).then_some(merge)
+        })
+        .collect()
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Asks the AI provider whether two names refer to the same entity. An unreadable
+/// answer counts as "no", so that nothing is merged on a guess.
+async fn adjudicate(
+    adjudicator: &ResolutionAdjudicator<'_>,
+    first: &str,
+    second: &str,
+) -> Result<bool, PromptError> {
+    let user_prompt = adjudicator
+        .user_prompt_template
+        .replace("{first}", first)
+        .replace("{second}", second);
+    let response = adjudicator
+        .ai_provider
+        .generate(adjudicator.system_prompt, &user_prompt)
+        .await?;
+    match serde_json::from_str::<Adjudication>(&clean_llm_response(&response)) {
+        Ok(adjudication) => Ok(adjudication.same_entity),
+        Err(e) => {
+            warn!("Failed to parse the entity adjudication for '{first}' and '{second}': {e}");
+            Ok(false)
+        }
+    }
+}
+
+impl<D: Datastore> KnowledgeGraph<D> {
+    /// Returns the name of every entity of the graph, without its aliases.
+    pub fn entity_names(&self) -> Result<Vec<String>, KnowledgeGraphError> {
+        let name_prop = Identifier::new(NAME_PROPERTY_NAME)?;
+        let results = self.db.get(AllVertexQuery.properties()?.name(name_prop))?;
+        let vertex_properties =
+            indradb::util::extract_vertex_properties(results).unwrap_or_default();
+        let mut names: Vec<String> = vertex_properties
+            .iter()
+            .filter_map(|vertex_property| {
+                vertex_property
+                    .props
+                    .iter()
+                    .find_map(|p| p.value.0.as_str().map(str::to_string))
+            })
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Returns the other names an entity has been merged from.
+    pub fn aliases(&self, name: &str) -> Result<Vec<String>, KnowledgeGraphError> {
+        let id = self.resolve_entity(name);
+        self.vertex_aliases(id)
+    }
+
+    /// Merges the `duplicate` entity into the `canonical` one: its facts are moved to
+    /// the canonical entity, its vertex is removed, and its name and aliases are recorded
+    /// as aliases of the canonical entity, so lookups by any of them find the merged facts.
+    pub fn merge_entities(
+        &mut self,
+        canonical: &str,
+        duplicate: &str,
+    ) -> Result<(), KnowledgeGraphError> {
+        let canonical_id = self.existing_entity(canonical)?;
+        let duplicate_id = self.existing_entity(duplicate)?;
+        if canonical_id == duplicate_id {
+            return Ok(());
+        }
+
+        let outbound = self.db.get(
+            SpecificVertexQuery::single(duplicate_id)
+                .outbound()?
+                .properties()?,
+        )?;
+        let inbound = self.db.get(
+            SpecificVertexQuery::single(duplicate_id)
+                .inbound()?
+                .properties()?,
+        )?;
+        let mut edge_properties =
+            indradb::util::extract_edge_properties(outbound).unwrap_or_default();
+        edge_properties.extend(indradb::util::extract_edge_properties(inbound).unwrap_or_default());
+
+        let moved = |id: Uuid| if id == duplicate_id { canonical_id } else { id };
+        let mut items = Vec::new();
+        for properties in edge_properties {
+            let edge = Edge::new(
+                moved(properties.edge.outbound_id),
+                properties.edge.t.clone(),
+                moved(properties.edge.inbound_id),
+            );
+            items.push(BulkInsertItem::Edge(edge.clone()));
+            for prop in properties.props {
+                items.push(BulkInsertItem::EdgeProperty(
+                    edge.clone(),
+                    prop.name,
+                    prop.value,
+                ));
+            }
+        }
+
+        let mut aliases = self.vertex_aliases(canonical_id)?;
+        let duplicate_names =
+            std::iter::once(duplicate.to_string()).chain(self.vertex_aliases(duplicate_id)?);
+        for name in duplicate_names {
+            if name != canonical && !aliases.contains(&name) {
+                aliases.push(name);
+            }
+        }
+
+        // Removing the vertex removes its edges, which are recreated on the canonical one.
+        self.db.delete(SpecificVertexQuery::single(duplicate_id))?;
+        self.db.bulk_insert(items)?;
+        self.db.set_properties(
+            SpecificVertexQuery::single(canonical_id),
+            Identifier::new(ALIASES_PROPERTY_NAME)?,
+            &Json::new(json!(aliases)),
+        )?;
+
+        for alias in aliases {
+            self.entity_map.insert(alias, canonical_id);
+        }
+        Ok(())
+    }
+
+    /// Applies planned merges in order, skipping those whose entities are gone. Returns
+    /// the merges that were applied.
+    pub fn apply_entity_merges(&mut self, merges: &[EntityMerge]) -> Vec<EntityMerge> {
+        let mut applied = Vec::new();
+        for merge in merges {
+            match self.merge_entities(&merge.canonical, &merge.duplicate) {
+                Ok(()) => applied.push(merge.clone()),
+                Err(e) => warn!(
+                    "Could not merge '{}' into '{}': {e}",
+                    merge.duplicate, merge.canonical
+                ),
+            }
+        }
+        info!("Merged {} duplicate entities.", applied.len());
+        applied
+    }
+
+    /// The id of an entity by its name or one of its aliases.
+    pub(crate) fn resolve_entity(&self, name: &str) -> Uuid {
+        self.entity_map
+            .get(name)
+            .copied()
+            .unwrap_or_else(|| entity_id(name))
+    }
+
+    fn vertex_aliases(&self, id: Uuid) -> Result<Vec<String>, KnowledgeGraphError> {
+        let aliases_prop = Identifier::new(ALIASES_PROPERTY_NAME)?;
+        let results = self.db.get(
+            SpecificVertexQuery::single(id)
+                .properties()?
+                .name(aliases_prop),
+        )?;
+        let aliases = indradb::util::extract_vertex_properties(results)
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|vertex_property| vertex_property.props)
+            .find_map(|p| serde_json::from_value::<Vec<String>>((*p.value.0).clone()).ok())
+            .unwrap_or_default();
+        Ok(aliases)
+    }
+}
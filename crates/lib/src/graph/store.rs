@@ -0,0 +1,206 @@
+//! # Knowledge Graph Store
+//!
+//! The knowledge graph a server keeps, in memory or persisted to RocksDB as selected
+//! by the `graph` section of the configuration.
+
+use super::{
+    extraction::ExtractedFact,
+    nl_query::GraphQuery,
+    persistent::{CompactionStats, PersistentKnowledgeGraph},
+    query::{FactVisibility, GraphFact, GraphView},
+    resolution::EntityMerge,
+    types::{KnowledgeGraphError, MemoryKnowledgeGraph},
+};
+use crate::types::{GraphBackend, GraphConfig};
+use chrono::{DateTime, Utc};
+
+/// A knowledge graph of either backend.
+pub enum KnowledgeGraphStore {
+    /// A graph that is lost when the process stops.
+    Memory(MemoryKnowledgeGraph),
+    /// A graph persisted to a RocksDB directory.
+    Persistent(PersistentKnowledgeGraph),
+}
+
+impl KnowledgeGraphStore {
+    /// Creates an empty in-memory graph.
+    pub fn new_memory() -> Self {
+        Self::Memory(MemoryKnowledgeGraph::new_memory())
+    }
+
+    /// Opens the graph selected by the configuration, loading a persisted one from its
+    /// directory. Without configuration, the graph is kept in memory.
+    pub fn open(config: Option<&GraphConfig>) -> Result<Self, KnowledgeGraphError> {
+        let Some(config) = config else {
+            return Ok(Self::new_memory());
+        };
+        match config.backend {
+            GraphBackend::Memory => Ok(Self::new_memory()),
+            GraphBackend::Rocksdb => Ok(Self::Persistent(PersistentKnowledgeGraph::open(
+                &config.path,
+            )?)),
+        }
+    }
+
+    /// Adds a fact with a specified validity period. See `KnowledgeGraph::add_fact`.
+    pub fn add_fact(
+        &mut self,
+        subject: &str,
+        predicate: &str,
+        object: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<(), KnowledgeGraphError> {
+        match self {
+            Self::Memory(kg) => kg.add_fact(subject, predicate, object, start_time, end_time),
+            Self::Persistent(kg) => kg
+                .graph_mut()
+                .add_fact(subject, predicate, object, start_time, end_time),
+        }
+    }
+
+    /// Adds the facts extracted from a document. See `KnowledgeGraph::add_document_facts`.
+    pub fn add_document_facts(&mut self, document_id: &str, facts: &[ExtractedFact]) -> usize {
+        match self {
+            Self::Memory(kg) => kg.add_document_facts(document_id, facts),
+            Self::Persistent(kg) => kg.graph_mut().add_document_facts(document_id, facts),
+        }
+    }
+
+    /// Retrieves the object of a visible fact valid at `as_of`.
+    pub fn get_fact_as_of(
+        &self,
+        subject: &str,
+        predicate: &str,
+        as_of: DateTime<Utc>,
+        visibility: &FactVisibility,
+    ) -> Result<Option<String>, KnowledgeGraphError> {
+        match self {
+            Self::Memory(kg) => kg.get_fact_as_of(subject, predicate, as_of, visibility),
+            Self::Persistent(kg) => kg
+                .graph()
+                .get_fact_as_of(subject, predicate, as_of, visibility),
+        }
+    }
+
+    /// Retrieves the id of the latest visible document a fact valid at `as_of` was
+    /// extracted from.
+    pub fn get_fact_source_as_of(
+        &self,
+        subject: &str,
+        predicate: &str,
+        as_of: DateTime<Utc>,
+        visibility: &FactVisibility,
+    ) -> Result<Option<String>, KnowledgeGraphError> {
+        match self {
+            Self::Memory(kg) => kg.get_fact_source_as_of(subject, predicate, as_of, visibility),
+            Self::Persistent(kg) => kg
+                .graph()
+                .get_fact_source_as_of(subject, predicate, as_of, visibility),
+        }
+    }
+
+    /// Returns an entity with its facts and neighbors. See `KnowledgeGraph::neighbors`.
+    pub fn neighbors(
+        &self,
+        entity: &str,
+        as_of: Option<DateTime<Utc>>,
+        visibility: &FactVisibility,
+    ) -> Result<GraphView, KnowledgeGraphError> {
+        match self {
+            Self::Memory(kg) => kg.neighbors(entity, as_of, visibility),
+            Self::Persistent(kg) => kg.graph().neighbors(entity, as_of, visibility),
+        }
+    }
+
+    /// Finds the shortest chain of facts linking two entities. See
+    /// `KnowledgeGraph::shortest_path`.
+    pub fn shortest_path(
+        &self,
+        from: &str,
+        to: &str,
+        max_depth: usize,
+        as_of: Option<DateTime<Utc>>,
+        visibility: &FactVisibility,
+    ) -> Result<Option<GraphView>, KnowledgeGraphError> {
+        match self {
+            Self::Memory(kg) => kg.shortest_path(from, to, max_depth, as_of, visibility),
+            Self::Persistent(kg) => kg
+                .graph()
+                .shortest_path(from, to, max_depth, as_of, visibility),
+        }
+    }
+
+    /// Returns every visible fact about an entity valid at `as_of`.
+    pub fn facts_about_as_of(
+        &self,
+        entity: &str,
+        as_of: DateTime<Utc>,
+        visibility: &FactVisibility,
+    ) -> Result<Vec<GraphFact>, KnowledgeGraphError> {
+        match self {
+            Self::Memory(kg) => kg.facts_about_as_of(entity, as_of, visibility),
+            Self::Persistent(kg) => kg.graph().facts_about_as_of(entity, as_of, visibility),
+        }
+    }
+
+    /// Runs a graph query. See `KnowledgeGraph::execute_graph_query`.
+    pub fn execute_graph_query(
+        &self,
+        query: &GraphQuery,
+        visibility: &FactVisibility,
+    ) -> Result<Vec<GraphFact>, KnowledgeGraphError> {
+        match self {
+            Self::Memory(kg) => kg.execute_graph_query(query, visibility),
+            Self::Persistent(kg) => kg.graph().execute_graph_query(query, visibility),
+        }
+    }
+
+    /// Returns the name of every entity. See `KnowledgeGraph::entity_names`.
+    pub fn entity_names(&self) -> Result<Vec<String>, KnowledgeGraphError> {
+        match self {
+            Self::Memory(kg) => kg.entity_names(),
+            Self::Persistent(kg) => kg.graph().entity_names(),
+        }
+    }
+
+    /// Returns the other names an entity has been merged from.
+    pub fn aliases(&self, entity: &str) -> Result<Vec<String>, KnowledgeGraphError> {
+        match self {
+            Self::Memory(kg) => kg.aliases(entity),
+            Self::Persistent(kg) => kg.graph().aliases(entity),
+        }
+    }
+
+    /// Applies planned entity merges. See `KnowledgeGraph::apply_entity_merges`.
+    pub fn apply_entity_merges(&mut self, merges: &[EntityMerge]) -> Vec<EntityMerge> {
+        match self {
+            Self::Memory(kg) => kg.apply_entity_merges(merges),
+            Self::Persistent(kg) => kg.graph_mut().apply_entity_merges(merges),
+        }
+    }
+
+    /// Removes every entity and fact.
+    pub fn clear(&mut self) -> Result<(), KnowledgeGraphError> {
+        match self {
+            Self::Memory(kg) => kg.clear(),
+            Self::Persistent(kg) => kg.clear(),
+        }
+    }
+
+    /// Flushes a persistent graph to disk. An in-memory graph has nothing to flush.
+    pub fn sync(&self) -> Result<(), KnowledgeGraphError> {
+        match self {
+            Self::Memory(_) => Ok(()),
+            Self::Persistent(kg) => kg.sync(),
+        }
+    }
+
+    /// Compacts a persistent graph. See `PersistentKnowledgeGraph::compact`.
+    pub fn compact(&mut self) -> Result<CompactionStats, KnowledgeGraphError> {
+        match self {
+            Self::Memory(_) => Err(KnowledgeGraphError::NotPersistent),
+            Self::Persistent(kg) => kg.compact(),
+        }
+    }
+}
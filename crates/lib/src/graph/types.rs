@@ -1,3 +1,4 @@
+use crate::PromptError;
 use chrono::{DateTime, Utc};
 use indradb::{Datastore, MemoryDatastore, RocksdbDatastore, ValidationError};
 use serde::{Deserialize, Serialize};
@@ -17,6 +18,12 @@ pub enum KnowledgeGraphError {
     EntityNotFound(String),
     #[error("Required data was not found in the graph response")]
     NotFound,
+    #[error("Fact extraction failed: {0}")]
+    Llm(#[from] PromptError),
+    #[error("Graph storage I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The knowledge graph is kept in memory; only a persistent graph can be compacted")]
+    NotPersistent,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -0,0 +1,203 @@
+//! # Bulk Inserts
+//!
+//! Inserting one row per statement costs a round trip through the SQLite engine for
+//! every row, which dominates large ingestions. These helpers insert many rows per
+//! statement with multi-row `VALUES` lists, as many as the limit on bound parameters
+//! allows, and check the content of many documents for duplicates at once.
+
+use crate::ingest::dedup::{content_hash, find_duplicate_hashes};
+use crate::ingest::revisions::record_revision;
+use crate::providers::db::sqlite::{pool::write_scoped, statements::StatementCache};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+use tracing::info;
+use turso::{Connection, Value};
+
+/// The most parameters bound to one statement; SQLite before 3.32 allows no more.
+pub const MAX_BOUND_PARAMETERS: usize = 999;
+
+const INSERT_DOCUMENTS_SQL: &str =
+    "INSERT INTO documents (id, owner_id, source_url, title, content, content_hash)";
+const UPDATE_DOCUMENT_SQL: &str =
+    "UPDATE documents SET title = ?, content = ?, content_hash = ? WHERE id = ?";
+/// A bulk insert prepares two statements: one for full batches and one for the last.
+const BULK_STATEMENT_CACHE_SIZE: usize = 2;
+
+/// A document to store with `bulk_insert_documents`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewDocument {
+    pub id: String,
+    /// The unique origin of the document. A document the owner stored with the same
+    /// one is replaced, and keeps its id.
+    pub source_url: String,
+    pub title: String,
+    pub content: String,
+}
+
+/// Inserts rows with multi-row `VALUES` lists, in as few statements as possible.
+///
+/// # Arguments
+///
+/// * `insert`: The statement up to its `VALUES`, e.g. `INSERT INTO "t" ("a", "b")`.
+/// * `conflict`: The clause after the values, e.g. `ON CONFLICT("a") DO NOTHING`, or
+///   an empty string.
+/// * `rows`: The values of the rows, one for each column named by `insert`.
+///
+/// The rows are not inserted atomically unless the connection is in a transaction.
+/// Returns the number of rows changed.
+pub async fn bulk_insert_rows(
+    conn: &Connection,
+    insert: &str,
+    conflict: &str,
+    rows: Vec<Vec<Value>>,
+) -> Result<u64, turso::Error> {
+    let Some(width) = rows.first().map(Vec::len).filter(|width| *width > 0) else {
+        return Ok(0);
+    };
+    let rows_per_statement = (MAX_BOUND_PARAMETERS / width).max(1);
+    let row_placeholders = format!("({})", vec!["?"; width].join(", "));
+
+    let mut statements = StatementCache::new(BULK_STATEMENT_CACHE_SIZE);
+    let mut changed = 0;
+    let mut rows = rows.into_iter().peekable();
+    while rows.peek().is_some() {
+        let batch: Vec<Vec<Value>> = rows.by_ref().take(rows_per_statement).collect();
+        let sql = format!(
+            "{insert} VALUES {} {conflict}",
+            vec![row_placeholders.as_str(); batch.len()].join(", ")
+        );
+        let params: Vec<Value> = batch.into_iter().flatten().collect();
+        changed += statements
+            .prepare(conn, &sql)
+            .await?
+            .execute(params)
+            .await?;
+    }
+    Ok(changed)
+}
+
+/// Stores documents in the `documents` table in a single transaction.
+///
+/// Documents whose content the owner already has are skipped, as are repeats of the same
+/// content within `documents`. When several documents share a source URL, the last one
+/// is stored. A document the owner already stored at a source URL is updated in place,
+/// and the version replaced is kept as a revision. Returns the ids of the stored
+/// documents, which are the existing ids of the updated ones.
+///
+/// The transaction is retried while the database is busy, and holds the database's write
+/// permit when run inside `WriteLock::scope`.
+pub async fn bulk_insert_documents(
+    conn: &mut Connection,
+    owner_id: Option<&str>,
+    documents: Vec<NewDocument>,
+) -> Result<Vec<String>, turso::Error> {
+    if documents.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Every attempt borrows the connection again.
+    let conn = &Mutex::new(conn);
+    let documents = &documents;
+    write_scoped(|| async move {
+        let mut conn = conn.lock().await;
+        insert_documents(&mut conn, owner_id, documents.clone()).await
+    })
+    .await
+}
+
+/// Stores documents in one transaction; see `bulk_insert_documents`.
+async fn insert_documents(
+    conn: &mut Connection,
+    owner_id: Option<&str>,
+    documents: Vec<NewDocument>,
+) -> Result<Vec<String>, turso::Error> {
+    let tx = conn.transaction().await?;
+    let hashes: Vec<String> = documents
+        .iter()
+        .map(|document| content_hash(&document.content))
+        .collect();
+    let mut seen_hashes = find_duplicate_hashes(&tx, owner_id, &hashes).await?;
+
+    let owner = match owner_id {
+        Some(owner) => Value::Text(owner.to_string()),
+        None => Value::Null,
+    };
+    let mut document_ids = Vec::new();
+    let mut rows: Vec<Vec<Value>> = Vec::new();
+    // One statement cannot upsert the same source URL twice.
+    let mut row_of_source_url: HashMap<String, usize> = HashMap::new();
+    for (document, hash) in documents.into_iter().zip(hashes) {
+        if !seen_hashes.insert(hash.clone()) {
+            info!("Skipping duplicate document '{}'.", document.source_url);
+            continue;
+        }
+        let row = vec![
+            Value::Text(document.id.clone()),
+            owner.clone(),
+            Value::Text(document.source_url.clone()),
+            Value::Text(document.title),
+            Value::Text(document.content),
+            Value::Text(hash),
+        ];
+        match row_of_source_url.get(&document.source_url) {
+            Some(&index) => {
+                rows[index] = row;
+                document_ids[index] = document.id;
+            }
+            None => {
+                row_of_source_url.insert(document.source_url, rows.len());
+                rows.push(row);
+                document_ids.push(document.id);
+            }
+        }
+    }
+
+    let source_urls: Vec<String> = row_of_source_url.into_keys().collect();
+    let stored = stored_document_ids(&tx, &owner, &source_urls).await?;
+    let mut new_rows = Vec::new();
+    for (row, document_id) in rows.into_iter().zip(document_ids.iter_mut()) {
+        let (Value::Text(source_url), Value::Text(content)) = (&row[2], &row[4]) else {
+            continue;
+        };
+        record_revision(&tx, source_url, content).await?;
+        match stored.get(source_url) {
+            Some(stored_id) => {
+                // The title, content and hash of the row, then the id to update.
+                let mut params: Vec<Value> = row.into_iter().skip(3).collect();
+                params.push(Value::Text(stored_id.clone()));
+                tx.execute(UPDATE_DOCUMENT_SQL, params).await?;
+                *document_id = stored_id.clone();
+            }
+            None => new_rows.push(row),
+        }
+    }
+    bulk_insert_rows(&tx, INSERT_DOCUMENTS_SQL, "", new_rows).await?;
+    tx.commit().await?;
+    Ok(document_ids)
+}
+
+/// Returns the ids of the documents `owner` stored at each of `source_urls`, by source
+/// URL.
+async fn stored_document_ids(
+    conn: &Connection,
+    owner: &Value,
+    source_urls: &[String],
+) -> Result<HashMap<String, String>, turso::Error> {
+    let mut stored = HashMap::new();
+    for batch in source_urls.chunks(MAX_BOUND_PARAMETERS - 1) {
+        let sql = format!(
+            "SELECT source_url, id FROM documents WHERE owner_id IS ? AND source_url IN ({})",
+            vec!["?"; batch.len()].join(", ")
+        );
+        let params: Vec<Value> = std::iter::once(owner.clone())
+            .chain(batch.iter().map(|url| Value::Text(url.clone())))
+            .collect();
+        let mut rows = conn.query(&sql, params).await?;
+        while let Some(row) = rows.next().await? {
+            stored
+                .entry(row.get::<String>(0)?)
+                .or_insert(row.get::<String>(1)?);
+        }
+    }
+    Ok(stored)
+}
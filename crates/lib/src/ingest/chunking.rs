@@ -0,0 +1,442 @@
+//! # Chunking Strategies
+//!
+//! This module defines the `Chunker` trait used by ingestors to split a document into
+//! pieces small enough to embed and retrieve, along with its implementations. An
+//! ingestor selects one through the `chunking` field of its source JSON, for example:
+//! `{"strategy": "markdown", "chunk_size": 2000}`.
+//!
+//! All sizes are measured in characters, not bytes, so multi-byte text is never split
+//! inside a character.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The default target maximum size for a single chunk in characters.
+pub const DEFAULT_CHUNK_SIZE: usize = 4096;
+/// The default character overlap between consecutive fixed-size chunks.
+pub const DEFAULT_CHUNK_OVERLAP: usize = 200;
+
+/// Characters that end a sentence.
+const SENTENCE_TERMINATORS: &[char] = &['.', '!', '?', '。', '！', '？'];
+
+/// A chunking strategy whose parameters cannot produce chunks.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid chunking configuration: {0}")]
+pub struct InvalidChunkingError(pub String);
+
+/// Splits text into chunks for ingestion.
+pub trait Chunker: Send + Sync {
+    /// Splits `text` into trimmed, non-empty chunks.
+    fn chunk(&self, text: &str) -> Vec<String>;
+}
+
+/// Selects a `Chunker` and its parameters, as found in an ingestor's source JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum ChunkingStrategy {
+    /// One chunk per paragraph; oversized paragraphs are split by size.
+    Paragraph {
+        #[serde(default = "default_chunk_size")]
+        chunk_size: usize,
+        #[serde(default = "default_chunk_overlap")]
+        chunk_overlap: usize,
+    },
+    /// Windows of `chunk_size` characters that overlap by `chunk_overlap`.
+    FixedSize {
+        #[serde(default = "default_chunk_size")]
+        chunk_size: usize,
+        #[serde(default = "default_chunk_overlap")]
+        chunk_overlap: usize,
+    },
+    /// Splits on the coarsest separator that works and merges pieces up to `chunk_size`.
+    Recursive {
+        #[serde(default = "default_chunk_size")]
+        chunk_size: usize,
+        #[serde(default = "default_separators")]
+        separators: Vec<String>,
+    },
+    /// One chunk per Markdown section, so a chunk never spans two headings.
+    Markdown {
+        #[serde(default = "default_chunk_size")]
+        chunk_size: usize,
+    },
+    /// Groups whole sentences up to `chunk_size`, never cutting a sentence in half.
+    Sentence {
+        #[serde(default = "default_chunk_size")]
+        chunk_size: usize,
+    },
+}
+
+impl Default for ChunkingStrategy {
+    fn default() -> Self {
+        ChunkingStrategy::Paragraph {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            chunk_overlap: DEFAULT_CHUNK_OVERLAP,
+        }
+    }
+}
+
+/// Checks that a chunk size and overlap can produce chunks. An overlap as large as the
+/// chunk would never move past the first window.
+pub fn validate_chunk_sizes(
+    chunk_size: usize,
+    chunk_overlap: usize,
+) -> Result<(), InvalidChunkingError> {
+    if chunk_size == 0 {
+        return Err(InvalidChunkingError(
+            "chunk_size must be greater than 0".to_string(),
+        ));
+    }
+    if chunk_overlap >= chunk_size {
+        return Err(InvalidChunkingError(format!(
+            "chunk_overlap ({chunk_overlap}) must be smaller than chunk_size ({chunk_size})"
+        )));
+    }
+    Ok(())
+}
+
+impl ChunkingStrategy {
+    /// Builds the `Chunker` for this strategy, rejecting parameters that cannot produce
+    /// chunks.
+    pub fn chunker(&self) -> Result<Box<dyn Chunker>, InvalidChunkingError> {
+        match self {
+            ChunkingStrategy::Paragraph {
+                chunk_size,
+                chunk_overlap,
+            }
+            | ChunkingStrategy::FixedSize {
+                chunk_size,
+                chunk_overlap,
+            } => validate_chunk_sizes(*chunk_size, *chunk_overlap)?,
+            ChunkingStrategy::Recursive { chunk_size, .. }
+            | ChunkingStrategy::Markdown { chunk_size }
+            | ChunkingStrategy::Sentence { chunk_size } => validate_chunk_sizes(*chunk_size, 0)?,
+        }
+        Ok(match self {
+            ChunkingStrategy::Paragraph {
+                chunk_size,
+                chunk_overlap,
+            } => Box::new(ParagraphChunker {
+                chunk_size: *chunk_size,
+                chunk_overlap: *chunk_overlap,
+            }),
+            ChunkingStrategy::FixedSize {
+                chunk_size,
+                chunk_overlap,
+            } => Box::new(FixedSizeChunker {
+                chunk_size: *chunk_size,
+                chunk_overlap: *chunk_overlap,
+            }),
+            ChunkingStrategy::Recursive {
+                chunk_size,
+                separators,
+            } => Box::new(RecursiveChunker {
+                chunk_size: *chunk_size,
+                separators: separators.clone(),
+            }),
+            ChunkingStrategy::Markdown { chunk_size } => Box::new(MarkdownChunker {
+                chunk_size: *chunk_size,
+            }),
+            ChunkingStrategy::Sentence { chunk_size } => Box::new(SentenceChunker {
+                chunk_size: *chunk_size,
+            }),
+        })
+    }
+}
+
+fn default_chunk_size() -> usize {
+    DEFAULT_CHUNK_SIZE
+}
+
+fn default_chunk_overlap() -> usize {
+    DEFAULT_CHUNK_OVERLAP
+}
+
+fn default_separators() -> Vec<String> {
+    ["\n\n", "\n", ". ", " "]
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+// --- Fixed-Size ---
+
+/// Splits text into windows of `chunk_size` characters overlapping by `chunk_overlap`.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedSizeChunker {
+    pub chunk_size: usize,
+    pub chunk_overlap: usize,
+}
+
+impl Chunker for FixedSizeChunker {
+    fn chunk(&self, text: &str) -> Vec<String> {
+        let chars: Vec<char> = text.trim().chars().collect();
+        let chunk_size = self.chunk_size.max(1);
+        let mut chunks = Vec::new();
+        let mut start = 0;
+
+        while start < chars.len() {
+            let end = std::cmp::min(start + chunk_size, chars.len());
+            chunks.push(chars[start..end].iter().collect());
+
+            // Move the start for the next chunk, considering the overlap. An overlap of
+            // the whole chunk is clamped so that the window still advances.
+            let next_start = start + chunk_size - self.chunk_overlap.min(chunk_size - 1);
+            if next_start >= chars.len() || next_start <= start {
+                break;
+            }
+            start = next_start;
+        }
+
+        chunks
+    }
+}
+
+// --- Paragraph ---
+
+/// Emits each paragraph as its own chunk, splitting oversized paragraphs by size.
+#[derive(Debug, Clone, Copy)]
+pub struct ParagraphChunker {
+    pub chunk_size: usize,
+    pub chunk_overlap: usize,
+}
+
+impl Default for ParagraphChunker {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            chunk_overlap: DEFAULT_CHUNK_OVERLAP,
+        }
+    }
+}
+
+impl Chunker for ParagraphChunker {
+    fn chunk(&self, text: &str) -> Vec<String> {
+        let fixed = FixedSizeChunker {
+            chunk_size: self.chunk_size,
+            chunk_overlap: self.chunk_overlap,
+        };
+        let mut chunks = Vec::new();
+
+        for paragraph in text.trim().split("\n\n") {
+            let paragraph = paragraph.trim();
+            if paragraph.is_empty() {
+                continue;
+            }
+            if paragraph.chars().count() <= self.chunk_size {
+                chunks.push(paragraph.to_string());
+            } else {
+                chunks.extend(fixed.chunk(paragraph));
+            }
+        }
+
+        chunks
+    }
+}
+
+// --- Recursive Separator ---
+
+/// Splits text on the first separator that occurs in it, merges adjacent pieces while
+/// they fit in `chunk_size`, and recurses with finer separators into pieces that do not.
+#[derive(Debug, Clone)]
+pub struct RecursiveChunker {
+    pub chunk_size: usize,
+    /// Separators from coarsest to finest, e.g. paragraphs, lines, sentences, words.
+    pub separators: Vec<String>,
+}
+
+impl Default for RecursiveChunker {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            separators: default_separators(),
+        }
+    }
+}
+
+impl RecursiveChunker {
+    fn split(&self, text: &str, separators: &[String]) -> Vec<String> {
+        if text.chars().count() <= self.chunk_size {
+            return vec![text.to_string()];
+        }
+
+        let Some(position) = separators.iter().position(|s| text.contains(s.as_str())) else {
+            // No separator left to split on, so fall back to a hard split.
+            let fixed = FixedSizeChunker {
+                chunk_size: self.chunk_size,
+                chunk_overlap: 0,
+            };
+            return fixed.chunk(text);
+        };
+        let separator = &separators[position];
+        let finer = &separators[position + 1..];
+
+        let mut chunks = Vec::new();
+        let mut current = String::new();
+        for piece in text.split(separator.as_str()) {
+            let candidate_len = match current.is_empty() {
+                true => piece.chars().count(),
+                false => {
+                    current.chars().count() + separator.chars().count() + piece.chars().count()
+                }
+            };
+            if candidate_len <= self.chunk_size {
+                if !current.is_empty() {
+                    current.push_str(separator);
+                }
+                current.push_str(piece);
+                continue;
+            }
+
+            if !current.is_empty() {
+                chunks.push(std::mem::take(&mut current));
+            }
+            if piece.chars().count() <= self.chunk_size {
+                current.push_str(piece);
+            } else {
+                chunks.extend(self.split(piece, finer));
+            }
+        }
+        if !current.is_empty() {
+            chunks.push(current);
+        }
+
+        chunks
+    }
+}
+
+impl Chunker for RecursiveChunker {
+    fn chunk(&self, text: &str) -> Vec<String> {
+        self.split(text.trim(), &self.separators)
+            .into_iter()
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty())
+            .collect()
+    }
+}
+
+// --- Markdown ---
+
+/// Emits one chunk per Markdown section. A heading with no body of its own stays with
+/// the section that follows it, and headings inside fenced code blocks are ignored.
+/// Sections longer than `chunk_size` are split with a `RecursiveChunker`.
+#[derive(Debug, Clone, Copy)]
+pub struct MarkdownChunker {
+    pub chunk_size: usize,
+}
+
+impl Chunker for MarkdownChunker {
+    fn chunk(&self, text: &str) -> Vec<String> {
+        let mut sections = Vec::new();
+        let mut current = String::new();
+        let mut has_body = false;
+        let mut in_code_block = false;
+
+        for line in text.lines() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_code_block = !in_code_block;
+            }
+
+            if !in_code_block && is_markdown_heading(trimmed) {
+                if has_body {
+                    sections.push(std::mem::take(&mut current));
+                    has_body = false;
+                }
+            } else if !line.trim().is_empty() {
+                has_body = true;
+            }
+
+            current.push_str(line);
+            current.push('\n');
+        }
+        sections.push(current);
+
+        let recursive = RecursiveChunker {
+            chunk_size: self.chunk_size,
+            ..Default::default()
+        };
+        sections
+            .iter()
+            .flat_map(|section| recursive.chunk(section))
+            .collect()
+    }
+}
+
+/// Returns whether a line is an ATX heading (`#` to `######` followed by a space).
+fn is_markdown_heading(line: &str) -> bool {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&level) && line[level..].starts_with(' ')
+}
+
+// --- Sentence ---
+
+/// Groups consecutive sentences into chunks of up to `chunk_size` characters, so no
+/// sentence is cut in half. A single sentence longer than `chunk_size` is split by size.
+#[derive(Debug, Clone, Copy)]
+pub struct SentenceChunker {
+    pub chunk_size: usize,
+}
+
+impl Chunker for SentenceChunker {
+    fn chunk(&self, text: &str) -> Vec<String> {
+        let fixed = FixedSizeChunker {
+            chunk_size: self.chunk_size,
+            chunk_overlap: 0,
+        };
+        let mut chunks = Vec::new();
+        let mut current = String::new();
+
+        for sentence in split_sentences(text) {
+            let sentence_len = sentence.chars().count();
+            if sentence_len > self.chunk_size {
+                if !current.is_empty() {
+                    chunks.push(std::mem::take(&mut current));
+                }
+                chunks.extend(fixed.chunk(&sentence));
+                continue;
+            }
+
+            if !current.is_empty() && current.chars().count() + 1 + sentence_len > self.chunk_size {
+                chunks.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(&sentence);
+        }
+        if !current.is_empty() {
+            chunks.push(current);
+        }
+
+        chunks
+    }
+}
+
+/// Splits text into trimmed sentences. A terminator only ends a sentence when followed
+/// by whitespace or the end of the text, so decimals like "3.5" stay intact. Blank
+/// lines always end a sentence.
+fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\n' && chars.peek() == Some(&'\n') {
+            sentences.push(std::mem::take(&mut current));
+            continue;
+        }
+        current.push(c);
+        if SENTENCE_TERMINATORS.contains(&c) && chars.peek().is_none_or(|n| n.is_whitespace()) {
+            sentences.push(std::mem::take(&mut current));
+        }
+    }
+    sentences.push(current);
+
+    sentences
+        .into_iter()
+        .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|s| !s.is_empty())
+        .collect()
+}
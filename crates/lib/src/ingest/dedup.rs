@@ -0,0 +1,129 @@
+//! # Content Deduplication
+//!
+//! Ingestors store the hash of every document's content in `documents.content_hash`
+//! and skip a chunk when the same owner already has a document with the same hash,
+//! so re-ingesting the same text under a new source does not create duplicates.
+//!
+//! A document in the trash still counts as a duplicate, so that its content is not
+//! stored twice, but ingesting that content again restores it from the trash: asking
+//! for the content again means the document is wanted back.
+
+use crate::ingest::bulk::MAX_BOUND_PARAMETERS;
+use std::collections::{HashMap, HashSet};
+use tracing::info;
+use turso::{params, Connection, Value};
+
+/// Returns the hash used to detect duplicate document content.
+///
+/// Surrounding whitespace is ignored so chunks that differ only in padding match.
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", md5::compute(content.trim().as_bytes()))
+}
+
+/// Returns the id of a document owned by `owner_id` with the given content hash, if any.
+/// A document found only in the trash is restored.
+///
+/// Public documents (without an owner) are only compared with other public documents.
+pub async fn find_duplicate_document(
+    conn: &Connection,
+    owner_id: Option<&str>,
+    content_hash: &str,
+) -> Result<Option<String>, turso::Error> {
+    // Documents outside the trash come first.
+    let mut rows = match owner_id {
+        Some(owner) => {
+            conn.query(
+                "SELECT id, deleted_at IS NOT NULL FROM documents WHERE owner_id = ? AND content_hash = ? ORDER BY deleted_at IS NOT NULL LIMIT 1",
+                params![owner, content_hash],
+            )
+            .await?
+        }
+        None => {
+            conn.query(
+                "SELECT id, deleted_at IS NOT NULL FROM documents WHERE owner_id IS NULL AND content_hash = ? ORDER BY deleted_at IS NOT NULL LIMIT 1",
+                params![content_hash],
+            )
+            .await?
+        }
+    };
+
+    let Some(row) = rows.next().await? else {
+        return Ok(None);
+    };
+    let document_id: String = row.get(0)?;
+    let trashed: i64 = row.get(1)?;
+    drop(rows);
+    if trashed != 0 {
+        restore_trashed(conn, &[document_id.clone()]).await?;
+    }
+    Ok(Some(document_id))
+}
+
+/// Returns which of the given content hashes `owner_id` already has documents with,
+/// looking them up in as few queries as possible. For a hash only found in the trash,
+/// one of its documents is restored.
+pub async fn find_duplicate_hashes(
+    conn: &Connection,
+    owner_id: Option<&str>,
+    content_hashes: &[String],
+) -> Result<HashSet<String>, turso::Error> {
+    let mut duplicates = HashSet::new();
+    let mut trashed: HashMap<String, String> = HashMap::new();
+    // One parameter is left for the owner.
+    for batch in content_hashes.chunks(MAX_BOUND_PARAMETERS - 1) {
+        let placeholders = vec!["?"; batch.len()].join(", ");
+        let mut params: Vec<Value> = batch.iter().cloned().map(Value::Text).collect();
+        let owner_condition = match owner_id {
+            Some(owner) => {
+                params.push(Value::Text(owner.to_string()));
+                "owner_id = ?"
+            }
+            None => "owner_id IS NULL",
+        };
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT content_hash, id, deleted_at IS NOT NULL FROM documents WHERE content_hash IN ({placeholders}) AND {owner_condition}"
+                ),
+                params,
+            )
+            .await?;
+        while let Some(row) = rows.next().await? {
+            let hash: String = row.get(0)?;
+            if row.get::<i64>(2)? == 0 {
+                duplicates.insert(hash);
+            } else {
+                trashed.insert(hash, row.get(1)?);
+            }
+        }
+    }
+
+    let restored: Vec<String> = trashed
+        .into_iter()
+        .filter(|(hash, _)| !duplicates.contains(hash))
+        .map(|(hash, document_id)| {
+            duplicates.insert(hash);
+            document_id
+        })
+        .collect();
+    restore_trashed(conn, &restored).await?;
+    Ok(duplicates)
+}
+
+/// Takes the given documents out of the trash.
+async fn restore_trashed(conn: &Connection, document_ids: &[String]) -> Result<(), turso::Error> {
+    for batch in document_ids.chunks(MAX_BOUND_PARAMETERS) {
+        let placeholders = vec!["?"; batch.len()].join(", ");
+        let params: Vec<Value> = batch.iter().cloned().map(Value::Text).collect();
+        conn.execute(
+            &format!("UPDATE documents SET deleted_at = NULL WHERE id IN ({placeholders})"),
+            params,
+        )
+        .await?;
+        info!(
+            "Restored {} re-ingested documents from the trash.",
+            batch.len()
+        );
+    }
+    Ok(())
+}
@@ -3,11 +3,27 @@
 //! This module provides the logic for generating vector embeddings for data
 //! that has been ingested, such as articles from an RSS feed. This is a key
 //! step in preparing the data for semantic search.
+//!
+//! Ingestors given an [`EmbeddingConfig`] embed the documents they store right away
+//! with [`embed_ingested_documents`]; the others are left to `/embed/new`.
 
-use crate::providers::ai::generate_embeddings_batch;
+use crate::{
+    constants::DEFAULT_EMBEDDING_BATCH_SIZE, providers::ai::generate_embeddings_batch,
+    types::EmbeddingConfig,
+};
 use thiserror::Error;
-use tracing::info;
-use turso::{params, Database, Value as TursoValue};
+use tracing::{info, instrument, warn};
+use turso::{params, Connection, Database, Row, Value as TursoValue};
+
+const SELECT_UNEMBEDDED_DOCUMENTS_SQL: &str = "SELECT d.id, d.title, d.content FROM documents d
+     LEFT JOIN document_embeddings de ON d.id = de.document_id AND de.model_name = ?
+     WHERE de.id IS NULL AND d.deleted_at IS NULL
+     ORDER BY d.created_at, d.id";
+const SELECT_UNEMBEDDED_DOCUMENT_SQL: &str = "SELECT d.id, d.title, d.content FROM documents d
+     LEFT JOIN document_embeddings de ON d.id = de.document_id AND de.model_name = ?
+     WHERE d.id = ? AND de.id IS NULL AND d.deleted_at IS NULL";
+const INSERT_DOCUMENT_EMBEDDING_SQL: &str =
+    "INSERT INTO document_embeddings (document_id, model_name, embedding) VALUES (?, ?, ?)";
 
 /// Custom error types for the embedding process.
 #[derive(Error, Debug)]
@@ -33,6 +49,7 @@ pub enum EmbeddingError {
 /// * `embeddings_api_url`: The URL of the embeddings API endpoint.
 /// * `embeddings_model`: The name of the model to use for generating embeddings.
 /// * `article_id`: The ID of the article to process.
+#[instrument(name = "ingest.embed_article", skip_all, fields(article_id = article_id))]
 pub async fn embed_article(
     db: &Database,
     embeddings_api_url: &str,
@@ -96,3 +113,171 @@ pub async fn embed_article(
     info!("Successfully embedded and updated article ID: {article_id}");
     Ok(())
 }
+
+/// A document that has no embedding of a model yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnembeddedDocument {
+    pub id: String,
+    pub title: String,
+    pub content: String,
+}
+
+impl UnembeddedDocument {
+    /// The text embedded for the document: its title, then its content, as `/embed/new`
+    /// embeds them.
+    pub fn embedding_text(&self) -> String {
+        format!("{}. {}", self.title, self.content)
+    }
+}
+
+/// Finds the documents outside the trash that have no embedding of `model_name`, oldest
+/// first, up to `limit` of them when it is set.
+pub async fn find_unembedded_documents(
+    conn: &Connection,
+    model_name: &str,
+    limit: Option<usize>,
+) -> Result<Vec<UnembeddedDocument>, turso::Error> {
+    let sql = match limit {
+        Some(limit) => format!("{SELECT_UNEMBEDDED_DOCUMENTS_SQL} LIMIT {limit}"),
+        None => SELECT_UNEMBEDDED_DOCUMENTS_SQL.to_string(),
+    };
+    let mut rows = conn.query(&sql, params![model_name]).await?;
+    let mut documents = Vec::new();
+    while let Some(row) = rows.next().await? {
+        documents.push(unembedded_document(&row)?);
+    }
+    Ok(documents)
+}
+
+fn unembedded_document(row: &Row) -> Result<UnembeddedDocument, turso::Error> {
+    let text = |value: TursoValue| match value {
+        TursoValue::Text(text) => text,
+        _ => String::new(),
+    };
+    Ok(UnembeddedDocument {
+        id: text(row.get_value(0)?),
+        title: text(row.get_value(1)?),
+        content: text(row.get_value(2)?),
+    })
+}
+
+/// Embeds those of the given documents that have no embedding of the configured model
+/// yet, `batch_size` of them per request to the embedding API, and returns how many
+/// were embedded. Each batch is stored as soon as its vectors arrive, so a failed batch
+/// keeps the ones before it.
+pub async fn embed_documents(
+    conn: &Connection,
+    config: &EmbeddingConfig,
+    document_ids: &[String],
+    batch_size: usize,
+) -> Result<usize, EmbeddingError> {
+    let mut documents = Vec::new();
+    for document_id in document_ids {
+        let mut rows = conn
+            .query(
+                SELECT_UNEMBEDDED_DOCUMENT_SQL,
+                params![config.model_name.as_str(), document_id.as_str()],
+            )
+            .await?;
+        if let Some(row) = rows.next().await? {
+            documents.push(unembedded_document(&row)?);
+        }
+    }
+
+    let mut embedded = 0;
+    for batch in documents.chunks(batch_size.max(1)) {
+        let texts: Vec<String> = batch
+            .iter()
+            .map(UnembeddedDocument::embedding_text)
+            .collect();
+        let inputs: Vec<&str> = texts.iter().map(String::as_str).collect();
+        let vectors = generate_embeddings_batch(
+            &config.api_url,
+            &config.model_name,
+            &inputs,
+            config.api_key.as_deref(),
+        )
+        .await?;
+        if vectors.len() != batch.len() {
+            return Err(EmbeddingError::Embedding(
+                crate::errors::PromptError::AiApi(format!(
+                    "Embedding API returned {} vectors for {} documents",
+                    vectors.len(),
+                    batch.len()
+                )),
+            ));
+        }
+        let embeddings: Vec<(String, Vec<f32>)> = batch
+            .iter()
+            .map(|document| document.id.clone())
+            .zip(vectors)
+            .collect();
+        store_document_embeddings(conn, &config.model_name, &embeddings).await?;
+        embedded += embeddings.len();
+    }
+    Ok(embedded)
+}
+
+/// Embeds the documents an ingestion just stored, in batches of
+/// [`DEFAULT_EMBEDDING_BATCH_SIZE`], and returns how many were embedded. A failure is
+/// logged and never fails the ingestion; the documents left without a vector are
+/// embedded by the next `/embed/new` call.
+#[instrument(name = "ingest.embed", skip_all, fields(documents = document_ids.len()))]
+pub async fn embed_ingested_documents(
+    db: &Database,
+    config: &EmbeddingConfig,
+    document_ids: &[String],
+) -> usize {
+    if document_ids.is_empty() {
+        return 0;
+    }
+    let conn = match db.connect() {
+        Ok(conn) => conn,
+        Err(e) => {
+            warn!("Could not connect to embed the ingested documents: {e}");
+            return 0;
+        }
+    };
+    match embed_documents(&conn, config, document_ids, DEFAULT_EMBEDDING_BATCH_SIZE).await {
+        Ok(embedded) => {
+            info!(
+                "Embedded {embedded} ingested documents with '{}'.",
+                config.model_name
+            );
+            embedded
+        }
+        Err(e) => {
+            warn!("Failed to embed the ingested documents: {e}");
+            0
+        }
+    }
+}
+
+/// Stores the embeddings of `model_name` for the given documents in one transaction.
+pub async fn store_document_embeddings(
+    conn: &Connection,
+    model_name: &str,
+    embeddings: &[(String, Vec<f32>)],
+) -> Result<(), turso::Error> {
+    conn.execute("BEGIN TRANSACTION", ()).await?;
+    let result = async {
+        let mut stmt = conn.prepare(INSERT_DOCUMENT_EMBEDDING_SQL).await?;
+        for (document_id, vector) in embeddings {
+            let vector_bytes: &[u8] = unsafe {
+                std::slice::from_raw_parts(vector.as_ptr() as *const u8, vector.len() * 4)
+            };
+            stmt.execute(params![document_id.as_str(), model_name, vector_bytes])
+                .await?;
+        }
+        Ok::<_, turso::Error>(())
+    }
+    .await;
+    match result {
+        Ok(()) => conn.execute("COMMIT", ()).await?,
+        Err(e) => {
+            conn.execute("ROLLBACK", ()).await?;
+            return Err(e);
+        }
+    };
+    Ok(())
+}
@@ -0,0 +1,408 @@
+//! # Knowledge Base Export
+//!
+//! This module exports the stored documents, with their metadata and embeddings, to
+//! formats other tools read: JSON Lines with one document per line, and Parquet with
+//! one row per document (behind the `parquet` feature). The OpenAI chat format
+//! fine-tuning JSONL of `export_finetuning_dataset` is available through the same
+//! entry point.
+
+use crate::ingest::finetuning::{export_finetuning_dataset, FinetuningExportOptions};
+use crate::ingest::knowledge::KnowledgeError;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt, str::FromStr};
+use thiserror::Error;
+use tracing::info;
+use turso::{Database, Value as TursoValue};
+
+const SELECT_DOCUMENTS_SQL: &str = "SELECT d.id, d.owner_id, d.org_id, d.source_url, d.title, d.content, d.created_at FROM documents d";
+const SELECT_METADATA_SQL: &str = "SELECT m.document_id, m.metadata_type, m.metadata_subtype, m.metadata_value FROM content_metadata m JOIN documents d ON d.id = m.document_id";
+const SELECT_EMBEDDINGS_SQL: &str = "SELECT e.document_id, e.model_name, e.embedding FROM document_embeddings e JOIN documents d ON d.id = e.document_id";
+
+/// Custom error types for the knowledge base export.
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Failed to serialize a document: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("{0}")]
+    Knowledge(#[from] KnowledgeError),
+    #[error("Unknown export format '{0}'; expected 'jsonl', 'parquet' or 'finetuning'.")]
+    UnknownFormat(String),
+    #[error("The 'parquet' feature is not enabled.")]
+    ParquetFeatureNotEnabled,
+    #[cfg(feature = "parquet")]
+    #[error("Failed to write Parquet: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    #[cfg(feature = "parquet")]
+    #[error("Failed to build the Parquet columns: {0}")]
+    Arrow(#[from] arrow::error::ArrowError),
+}
+
+/// The formats the knowledge base can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One JSON document per line, with its metadata and embeddings.
+    #[default]
+    Jsonl,
+    /// One row per document, with the metadata and embeddings as list columns.
+    Parquet,
+    /// The FAQs of the knowledge base as OpenAI chat fine-tuning JSONL.
+    Finetuning,
+}
+
+impl ExportFormat {
+    /// The MIME type of an export in this format.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Jsonl | ExportFormat::Finetuning => "application/x-ndjson",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+
+    /// The file extension of an export in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Jsonl | ExportFormat::Finetuning => "jsonl",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = ExportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonl" => Ok(ExportFormat::Jsonl),
+            "parquet" => Ok(ExportFormat::Parquet),
+            "finetuning" => Ok(ExportFormat::Finetuning),
+            other => Err(ExportError::UnknownFormat(other.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::Finetuning => "finetuning",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A stored document with its metadata and embeddings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedDocument {
+    pub id: String,
+    pub owner_id: Option<String>,
+    pub org_id: Option<String>,
+    pub source_url: Option<String>,
+    pub title: Option<String>,
+    pub content: String,
+    pub created_at: Option<String>,
+    pub metadata: Vec<ExportedMetadata>,
+    pub embeddings: Vec<ExportedEmbedding>,
+}
+
+/// An entity or keyphrase extracted from a document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedMetadata {
+    pub metadata_type: String,
+    pub metadata_subtype: Option<String>,
+    pub metadata_value: String,
+}
+
+/// The embedding of a document by one model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedEmbedding {
+    pub model_name: String,
+    pub vector: Vec<f32>,
+}
+
+/// Exports the documents owned by `owner_id`, or every document when it is `None`,
+/// in the given format.
+pub async fn export_knowledge_base(
+    db: &Database,
+    format: ExportFormat,
+    owner_id: Option<&str>,
+) -> Result<Vec<u8>, ExportError> {
+    export_knowledge_bases(&[db], format, owner_id).await
+}
+
+/// Exports the documents owned by `owner_id`, or every document when it is `None`,
+/// from several databases, such as the shards of a knowledge base, as one file.
+pub async fn export_knowledge_bases(
+    databases: &[&Database],
+    format: ExportFormat,
+    owner_id: Option<&str>,
+) -> Result<Vec<u8>, ExportError> {
+    info!(
+        "Exporting {} database(s) as {format} (owner: {owner_id:?}).",
+        databases.len()
+    );
+    match format {
+        ExportFormat::Jsonl | ExportFormat::Parquet => {
+            let mut documents = Vec::new();
+            for db in databases {
+                documents.extend(export_documents(db, owner_id).await?);
+            }
+            documents.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+            match format {
+                ExportFormat::Parquet => to_parquet(&documents),
+                _ => to_jsonl(&documents),
+            }
+        }
+        ExportFormat::Finetuning => {
+            let options = FinetuningExportOptions {
+                owner_id: owner_id.map(str::to_string),
+                ..Default::default()
+            };
+            let mut output = String::new();
+            for db in databases {
+                output.push_str(&export_finetuning_dataset(db, &options).await?);
+            }
+            Ok(output.into_bytes())
+        }
+    }
+}
+
+/// Reads the documents owned by `owner_id`, or every document when it is `None`, with
+/// their metadata and embeddings, ordered by creation time. Documents in the trash are
+/// left out.
+pub async fn export_documents(
+    db: &Database,
+    owner_id: Option<&str>,
+) -> Result<Vec<ExportedDocument>, ExportError> {
+    let conn = db.connect()?;
+    let (condition, params) = match owner_id {
+        Some(owner_id) => (
+            " WHERE d.deleted_at IS NULL AND d.owner_id = ?",
+            vec![TursoValue::Text(owner_id.to_string())],
+        ),
+        None => (" WHERE d.deleted_at IS NULL", Vec::new()),
+    };
+
+    let mut documents = Vec::new();
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_DOCUMENTS_SQL}{condition} ORDER BY d.created_at, d.id"),
+            params.clone(),
+        )
+        .await?;
+    while let Some(row) = rows.next().await? {
+        documents.push(ExportedDocument {
+            id: text(row.get_value(0)?).unwrap_or_default(),
+            owner_id: text(row.get_value(1)?),
+            org_id: text(row.get_value(2)?),
+            source_url: text(row.get_value(3)?),
+            title: text(row.get_value(4)?),
+            content: text(row.get_value(5)?).unwrap_or_default(),
+            created_at: text(row.get_value(6)?),
+            metadata: Vec::new(),
+            embeddings: Vec::new(),
+        });
+    }
+
+    let positions: HashMap<String, usize> = documents
+        .iter()
+        .enumerate()
+        .map(|(position, document)| (document.id.clone(), position))
+        .collect();
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_METADATA_SQL}{condition} ORDER BY m.id"),
+            params.clone(),
+        )
+        .await?;
+    while let Some(row) = rows.next().await? {
+        let Some(&position) = text(row.get_value(0)?).and_then(|id| positions.get(&id)) else {
+            continue;
+        };
+        documents[position].metadata.push(ExportedMetadata {
+            metadata_type: text(row.get_value(1)?).unwrap_or_default(),
+            metadata_subtype: text(row.get_value(2)?),
+            metadata_value: text(row.get_value(3)?).unwrap_or_default(),
+        });
+    }
+
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_EMBEDDINGS_SQL}{condition} ORDER BY e.id"),
+            params,
+        )
+        .await?;
+    while let Some(row) = rows.next().await? {
+        let Some(&position) = text(row.get_value(0)?).and_then(|id| positions.get(&id)) else {
+            continue;
+        };
+        let TursoValue::Blob(bytes) = row.get_value(2)? else {
+            continue;
+        };
+        documents[position].embeddings.push(ExportedEmbedding {
+            model_name: text(row.get_value(1)?).unwrap_or_default(),
+            vector: bytes
+                .chunks_exact(4)
+                .map(|chunk| f32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect(),
+        });
+    }
+
+    info!("Read {} documents for export.", documents.len());
+    Ok(documents)
+}
+
+/// Serializes documents as JSON Lines, one document per line.
+pub fn to_jsonl(documents: &[ExportedDocument]) -> Result<Vec<u8>, ExportError> {
+    let mut output = Vec::new();
+    for document in documents {
+        serde_json::to_writer(&mut output, document)?;
+        output.push(b'\n');
+    }
+    Ok(output)
+}
+
+/// Serializes documents as a Parquet file with one row per document. `metadata` is a
+/// list of structs, and `embeddings` a list of `{model_name, vector}` structs.
+#[cfg(feature = "parquet")]
+pub fn to_parquet(documents: &[ExportedDocument]) -> Result<Vec<u8>, ExportError> {
+    use arrow::{
+        array::{ArrayRef, Float32Array, ListArray, StringArray, StructArray},
+        buffer::OffsetBuffer,
+        datatypes::{DataType, Field, Fields, Schema},
+        record_batch::RecordBatch,
+    };
+    use parquet::arrow::ArrowWriter;
+    use std::sync::Arc;
+
+    let strings = |values: Vec<Option<&str>>| Arc::new(StringArray::from(values)) as ArrayRef;
+
+    let metadata_fields = Fields::from(vec![
+        Field::new("metadata_type", DataType::Utf8, false),
+        Field::new("metadata_subtype", DataType::Utf8, true),
+        Field::new("metadata_value", DataType::Utf8, false),
+    ]);
+    let all_metadata: Vec<&ExportedMetadata> = documents.iter().flat_map(|d| &d.metadata).collect();
+    let metadata_values = StructArray::try_new(
+        metadata_fields.clone(),
+        vec![
+            strings(
+                all_metadata
+                    .iter()
+                    .map(|m| Some(m.metadata_type.as_str()))
+                    .collect(),
+            ),
+            strings(
+                all_metadata
+                    .iter()
+                    .map(|m| m.metadata_subtype.as_deref())
+                    .collect(),
+            ),
+            strings(
+                all_metadata
+                    .iter()
+                    .map(|m| Some(m.metadata_value.as_str()))
+                    .collect(),
+            ),
+        ],
+        None,
+    )?;
+    let metadata_item = Arc::new(Field::new("item", DataType::Struct(metadata_fields), false));
+    let metadata = ListArray::try_new(
+        metadata_item.clone(),
+        OffsetBuffer::from_lengths(documents.iter().map(|d| d.metadata.len())),
+        Arc::new(metadata_values),
+        None,
+    )?;
+
+    let all_embeddings: Vec<&ExportedEmbedding> =
+        documents.iter().flat_map(|d| &d.embeddings).collect();
+    let vector_item = Arc::new(Field::new("item", DataType::Float32, false));
+    let vectors = ListArray::try_new(
+        vector_item.clone(),
+        OffsetBuffer::from_lengths(all_embeddings.iter().map(|e| e.vector.len())),
+        Arc::new(Float32Array::from(
+            all_embeddings
+                .iter()
+                .flat_map(|e| e.vector.iter().copied())
+                .collect::<Vec<f32>>(),
+        )),
+        None,
+    )?;
+    let embedding_fields = Fields::from(vec![
+        Field::new("model_name", DataType::Utf8, false),
+        Field::new("vector", DataType::List(vector_item), false),
+    ]);
+    let embedding_values = StructArray::try_new(
+        embedding_fields.clone(),
+        vec![
+            strings(
+                all_embeddings
+                    .iter()
+                    .map(|e| Some(e.model_name.as_str()))
+                    .collect(),
+            ),
+            Arc::new(vectors),
+        ],
+        None,
+    )?;
+    let embedding_item = Arc::new(Field::new(
+        "item",
+        DataType::Struct(embedding_fields),
+        false,
+    ));
+    let embeddings = ListArray::try_new(
+        embedding_item.clone(),
+        OffsetBuffer::from_lengths(documents.iter().map(|d| d.embeddings.len())),
+        Arc::new(embedding_values),
+        None,
+    )?;
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("owner_id", DataType::Utf8, true),
+        Field::new("org_id", DataType::Utf8, true),
+        Field::new("source_url", DataType::Utf8, true),
+        Field::new("title", DataType::Utf8, true),
+        Field::new("content", DataType::Utf8, false),
+        Field::new("created_at", DataType::Utf8, true),
+        Field::new("metadata", DataType::List(metadata_item), false),
+        Field::new("embeddings", DataType::List(embedding_item), false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            strings(documents.iter().map(|d| Some(d.id.as_str())).collect()),
+            strings(documents.iter().map(|d| d.owner_id.as_deref()).collect()),
+            strings(documents.iter().map(|d| d.org_id.as_deref()).collect()),
+            strings(documents.iter().map(|d| d.source_url.as_deref()).collect()),
+            strings(documents.iter().map(|d| d.title.as_deref()).collect()),
+            strings(documents.iter().map(|d| Some(d.content.as_str())).collect()),
+            strings(documents.iter().map(|d| d.created_at.as_deref()).collect()),
+            Arc::new(metadata),
+            Arc::new(embeddings),
+        ],
+    )?;
+
+    let mut output = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut output, schema, None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(output)
+}
+
+#[cfg(not(feature = "parquet"))]
+pub fn to_parquet(_documents: &[ExportedDocument]) -> Result<Vec<u8>, ExportError> {
+    Err(ExportError::ParquetFeatureNotEnabled)
+}
+
+fn text(value: TursoValue) -> Option<String> {
+    match value {
+        TursoValue::Text(text) => Some(text),
+        _ => None,
+    }
+}
@@ -0,0 +1,268 @@
+//! # Fine-Tuning Dataset Export
+//!
+//! This module exports the question and answer pairs of the knowledge base as a
+//! fine-tuning dataset: the FAQs of the documents restructured to YAML, followed by the
+//! FAQs distilled into `faq_items`. Each pair becomes one JSON line in the OpenAI chat
+//! format, the Gemini tuning format, or the Alpaca format.
+//!
+//! The pairs can be filtered by the owner, link prefix and creation date of their
+//! document, and by the feedback their question received. The feedback score of a
+//! question is the number of positive ratings of prompts asking it minus the number of
+//! negative ones; questions nobody rated score 0.
+
+use crate::ingest::knowledge::{normalize_question, KnowledgeError, YamlContent};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{collections::HashMap, fmt, str::FromStr};
+use tracing::{info, warn};
+use turso::{Connection, Database, Value as TursoValue};
+
+/// The system prompt of the OpenAI and Gemini formats.
+const FINETUNING_SYSTEM_PROMPT: &str =
+    "You are a helpful assistant. Provide clear, accurate answers based on the retrieved context.";
+/// The format of the document creation dates the date range is compared with.
+const CREATED_AT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// The formats a fine-tuning dataset can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum FinetuningFormat {
+    /// `{"messages": [...]}` with system, user and assistant messages.
+    #[default]
+    Openai,
+    /// `{"systemInstruction": ..., "contents": [...]}` with user and model turns.
+    Gemini,
+    /// `{"instruction": ..., "input": "", "output": ...}`.
+    Alpaca,
+}
+
+impl FromStr for FinetuningFormat {
+    type Err = KnowledgeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "openai" => Ok(FinetuningFormat::Openai),
+            "gemini" => Ok(FinetuningFormat::Gemini),
+            "alpaca" => Ok(FinetuningFormat::Alpaca),
+            other => Err(KnowledgeError::UnknownFinetuningFormat(other.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for FinetuningFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FinetuningFormat::Openai => "openai",
+            FinetuningFormat::Gemini => "gemini",
+            FinetuningFormat::Alpaca => "alpaca",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The format and filters of a fine-tuning dataset export.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FinetuningExportOptions {
+    pub format: FinetuningFormat,
+    /// Only exports the pairs of this user's documents.
+    pub owner_id: Option<String>,
+    /// Only exports the pairs of documents whose link starts with this prefix.
+    pub source_prefix: Option<String>,
+    /// Only exports the pairs of documents created at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Only exports the pairs of documents created before this time.
+    pub until: Option<DateTime<Utc>>,
+    /// Only exports the pairs whose question has at least this feedback score.
+    pub min_feedback_score: Option<i64>,
+}
+
+/// A question and answer pair of the dataset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TrainingPair {
+    question: String,
+    answer: String,
+}
+
+/// Exports the question and answer pairs matching `options` as fine-tuning JSONL.
+pub async fn export_finetuning_dataset(
+    db: &Database,
+    options: &FinetuningExportOptions,
+) -> Result<String, KnowledgeError> {
+    info!(
+        "Exporting the fine-tuning dataset as {} (owner: {:?}, source prefix: {:?}).",
+        options.format, options.owner_id, options.source_prefix
+    );
+    let conn = db.connect()?;
+    let mut pairs = yaml_pairs(&conn, options).await?;
+    pairs.extend(faq_item_pairs(&conn, options).await?);
+
+    if let Some(min_score) = options.min_feedback_score {
+        let scores = feedback_scores(&conn, options.owner_id.as_deref()).await?;
+        pairs.retain(|pair| {
+            let score = scores
+                .get(&normalize_question(&pair.question))
+                .copied()
+                .unwrap_or(0);
+            score >= min_score
+        });
+    }
+
+    let mut jsonl_output = String::new();
+    for pair in &pairs {
+        jsonl_output.push_str(&serde_json::to_string(&entry(options.format, pair))?);
+        jsonl_output.push('\n');
+    }
+    info!("Generated fine-tuning data with {} entries.", pairs.len());
+    Ok(jsonl_output)
+}
+
+/// Formats a pair as one entry of the dataset.
+fn entry(format: FinetuningFormat, pair: &TrainingPair) -> Value {
+    match format {
+        FinetuningFormat::Openai => json!({
+            "messages": [
+                { "role": "system", "content": FINETUNING_SYSTEM_PROMPT },
+                { "role": "user", "content": pair.question },
+                { "role": "assistant", "content": pair.answer },
+            ]
+        }),
+        FinetuningFormat::Gemini => json!({
+            "systemInstruction": {
+                "role": "system",
+                "parts": [{ "text": FINETUNING_SYSTEM_PROMPT }]
+            },
+            "contents": [
+                { "role": "user", "parts": [{ "text": pair.question }] },
+                { "role": "model", "parts": [{ "text": pair.answer }] },
+            ]
+        }),
+        FinetuningFormat::Alpaca => json!({
+            "instruction": pair.question,
+            "input": "",
+            "output": pair.answer,
+        }),
+    }
+}
+
+/// Builds the `WHERE` conditions on the documents `d` selected by `options`, which
+/// leave out the documents in the trash.
+fn document_conditions(options: &FinetuningExportOptions) -> (String, Vec<TursoValue>) {
+    let mut conditions = vec!["d.deleted_at IS NULL"];
+    let mut params: Vec<TursoValue> = Vec::new();
+    if let Some(owner_id) = &options.owner_id {
+        conditions.push("d.owner_id = ?");
+        params.push(owner_id.as_str().into());
+    }
+    if let Some(prefix) = &options.source_prefix {
+        conditions.push("substr(d.source_url, 1, length(?)) = ?");
+        params.push(prefix.as_str().into());
+        params.push(prefix.as_str().into());
+    }
+    if let Some(since) = options.since {
+        conditions.push("d.created_at >= ?");
+        params.push(since.format(CREATED_AT_FORMAT).to_string().into());
+    }
+    if let Some(until) = options.until {
+        conditions.push("d.created_at < ?");
+        params.push(until.format(CREATED_AT_FORMAT).to_string().into());
+    }
+    let conditions = conditions
+        .iter()
+        .map(|condition| format!(" AND {condition}"))
+        .collect();
+    (conditions, params)
+}
+
+/// Reads the FAQs of the documents restructured to YAML.
+async fn yaml_pairs(
+    conn: &Connection,
+    options: &FinetuningExportOptions,
+) -> Result<Vec<TrainingPair>, KnowledgeError> {
+    let (conditions, params) = document_conditions(options);
+    let sql = format!(
+        "SELECT d.content FROM documents d WHERE d.content IS NOT NULL AND d.content != ''{conditions} ORDER BY d.rowid"
+    );
+    let mut rows = conn.query(&sql, params).await?;
+
+    let mut pairs = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let yaml_content = if let Ok(TursoValue::Text(s)) = row.get_value(0) {
+            s
+        } else {
+            continue;
+        };
+
+        // Only process documents that appear to be our structured YAML format.
+        if !yaml_content.trim().starts_with("sections:") {
+            continue;
+        }
+
+        let parsed_yaml: YamlContent = match serde_yaml::from_str(&yaml_content) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Failed to parse YAML content for fine-tuning export, skipping document. Error: {}", e);
+                continue;
+            }
+        };
+
+        for section in parsed_yaml.sections {
+            for faq in section.faqs {
+                pairs.push(TrainingPair {
+                    question: faq.question,
+                    answer: faq.answer,
+                });
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+/// Reads the FAQs distilled into `faq_items` from the selected documents.
+async fn faq_item_pairs(
+    conn: &Connection,
+    options: &FinetuningExportOptions,
+) -> Result<Vec<TrainingPair>, KnowledgeError> {
+    let (conditions, params) = document_conditions(options);
+    let sql = format!(
+        "SELECT f.question, f.answer FROM faq_items f JOIN documents d ON d.id = f.document_id WHERE 1 = 1{conditions} ORDER BY f.id"
+    );
+    let mut rows = conn.query(&sql, params).await?;
+
+    let mut pairs = Vec::new();
+    while let Some(row) = rows.next().await? {
+        pairs.push(TrainingPair {
+            question: row.get(0)?,
+            answer: row.get(1)?,
+        });
+    }
+    Ok(pairs)
+}
+
+/// Sums the ratings of the prompts of `owner_id`, or of every user when it is `None`,
+/// by normalized question: +1 for a positive rating and -1 for a negative one.
+async fn feedback_scores(
+    conn: &Connection,
+    owner_id: Option<&str>,
+) -> Result<HashMap<String, i64>, KnowledgeError> {
+    let (condition, params): (&str, Vec<TursoValue>) = match owner_id {
+        Some(owner_id) => (" WHERE owner_id = ?", vec![owner_id.into()]),
+        None => ("", Vec::new()),
+    };
+    let mut rows = conn
+        .query(
+            &format!("SELECT prompt, positive FROM feedback{condition}"),
+            params,
+        )
+        .await?;
+
+    let mut scores = HashMap::new();
+    while let Some(row) = rows.next().await? {
+        let prompt: String = row.get(0)?;
+        let positive: i64 = row.get(1)?;
+        *scores.entry(normalize_question(&prompt)).or_insert(0) +=
+            if positive != 0 { 1 } else { -1 };
+    }
+    Ok(scores)
+}
@@ -0,0 +1,358 @@
+//! # Import from LangChain and LlamaIndex
+//!
+//! This module reads the document exports of common Python RAG stacks and stores them
+//! as `documents`, with their vectors in `document_embeddings`:
+//!
+//! - LangChain: JSON Lines of `Document`s, either plain (`{"page_content", "metadata"}`)
+//!   or serialized with `dumpd` (`{"lc": 1, "kwargs": {...}}`).
+//! - LlamaIndex: the JSON of a persisted docstore (`docstore.json`), whose nodes may
+//!   carry their `embedding`. The `embedding_dict` of a persisted vector store in the
+//!   same file is used for nodes without one.
+//!
+//! Exported vectors are only kept when they were made by the configured embedding
+//! model; otherwise the documents are re-embedded with it.
+
+use crate::{
+    constants::{DEFAULT_EMBEDDING_BATCH_SIZE, DEFAULT_SQLITE_STATEMENT_CACHE_SIZE},
+    errors::PromptError,
+    ingest::dedup::{content_hash, find_duplicate_document},
+    providers::{ai::generate_embeddings_batch, db::sqlite::statements::StatementCache},
+    types::EmbeddingConfig,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+use tracing::info;
+use turso::{params, Database};
+use uuid::Uuid;
+
+const INSERT_DOCUMENT_SQL: &str =
+    "INSERT INTO documents (id, owner_id, source_url, title, content, content_hash) VALUES (?, ?, ?, ?, ?, ?)";
+const INSERT_EMBEDDING_SQL: &str =
+    "INSERT INTO document_embeddings (document_id, model_name, embedding) VALUES (?, ?, ?)";
+/// The metadata keys LangChain and LlamaIndex loaders record the origin of a document in.
+const SOURCE_METADATA_KEYS: &[&str] = &["source", "url", "file_path", "file_name"];
+const TITLE_METADATA_KEY: &str = "title";
+const LLAMAINDEX_DOCSTORE_KEY: &str = "docstore/data";
+const LLAMAINDEX_EMBEDDINGS_KEY: &str = "embedding_dict";
+/// The number of characters of the content used as the title when the export has none.
+const TITLE_LENGTH: usize = 80;
+
+/// Custom error types for the import of RAG exports.
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Failed to parse the export: {0}")]
+    Parse(String),
+    #[error("Embedding generation failed: {0}")]
+    Embedding(#[from] PromptError),
+    #[error("Unknown import format '{0}'; expected 'langchain' or 'llamaindex'.")]
+    UnknownFormat(String),
+}
+
+/// The export formats that can be imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    /// JSON Lines of LangChain `Document`s.
+    Langchain,
+    /// The JSON of a persisted LlamaIndex docstore.
+    Llamaindex,
+}
+
+impl FromStr for ImportFormat {
+    type Err = ImportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "langchain" => Ok(ImportFormat::Langchain),
+            "llamaindex" => Ok(ImportFormat::Llamaindex),
+            other => Err(ImportError::UnknownFormat(other.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportFormat::Langchain => write!(f, "langchain"),
+            ImportFormat::Llamaindex => write!(f, "llamaindex"),
+        }
+    }
+}
+
+/// A document read from an export, before it is stored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedDocument {
+    /// The id the document had in the export, if it had one.
+    pub id: Option<String>,
+    pub content: String,
+    pub metadata: Map<String, Value>,
+    pub embedding: Option<Vec<f32>>,
+}
+
+/// What an import stored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImportSummary {
+    /// The ids of the documents created.
+    pub document_ids: Vec<String>,
+    /// Documents skipped because the owner already has one with the same content.
+    pub duplicates_skipped: usize,
+    /// Vectors stored as they were in the export.
+    pub embeddings_copied: usize,
+    /// Vectors generated with the configured embedding model.
+    pub embeddings_generated: usize,
+}
+
+/// Reads the documents of an export.
+pub fn parse_export(
+    format: ImportFormat,
+    data: &str,
+) -> Result<Vec<ImportedDocument>, ImportError> {
+    match format {
+        ImportFormat::Langchain => parse_langchain(data),
+        ImportFormat::Llamaindex => parse_llamaindex(data),
+    }
+}
+
+/// Imports the documents of an export for `owner_id`.
+///
+/// # Arguments
+///
+/// * `source_model`: The embedding model the vectors in the export were made with, if
+///   known. Neither format records it.
+/// * `embedding`: The model the knowledge base is embedded with. Exported vectors of
+///   another (or an unknown) model are replaced by new ones from this model. Without
+///   it, exported vectors are stored under `source_model`, and no vectors are made.
+pub async fn import_documents(
+    db: &Database,
+    format: ImportFormat,
+    data: &str,
+    owner_id: Option<&str>,
+    source_model: Option<&str>,
+    embedding: Option<&EmbeddingConfig>,
+) -> Result<ImportSummary, ImportError> {
+    let documents = parse_export(format, data)?;
+    info!("Importing {} {format} documents.", documents.len());
+
+    let conn = db.connect()?;
+    let mut statements = StatementCache::new(DEFAULT_SQLITE_STATEMENT_CACHE_SIZE);
+    let mut summary = ImportSummary::default();
+    let mut to_embed: Vec<(String, String)> = Vec::new();
+
+    conn.execute("BEGIN", ()).await?;
+    let result = async {
+        for (index, document) in documents.into_iter().enumerate() {
+            let hash = content_hash(&document.content);
+            if find_duplicate_document(&conn, owner_id, &hash)
+                .await?
+                .is_some()
+            {
+                summary.duplicates_skipped += 1;
+                continue;
+            }
+
+            let document_id = Uuid::new_v4().to_string();
+            statements
+                .prepare(&conn, INSERT_DOCUMENT_SQL)
+                .await?
+                .execute(params![
+                    document_id.clone(),
+                    owner_id,
+                    source_url(format, &document, index),
+                    title(&document),
+                    document.content.clone(),
+                    hash
+                ])
+                .await?;
+
+            let copy_model = match (embedding, source_model) {
+                (Some(config), Some(model)) if config.model_name == model => Some(model),
+                (Some(_), _) => None,
+                (None, model) => model,
+            };
+            match (document.embedding, copy_model) {
+                (Some(vector), Some(model)) => {
+                    insert_embedding(&mut statements, &conn, &document_id, model, &vector).await?;
+                    summary.embeddings_copied += 1;
+                }
+                _ if embedding.is_some() => {
+                    to_embed.push((document_id.clone(), document.content));
+                }
+                _ => {}
+            }
+            summary.document_ids.push(document_id);
+        }
+        Ok::<_, turso::Error>(())
+    }
+    .await;
+    match result {
+        Ok(()) => conn.execute("COMMIT", ()).await?,
+        Err(e) => {
+            conn.execute("ROLLBACK", ()).await?;
+            return Err(e.into());
+        }
+    };
+
+    let Some(config) = embedding else {
+        return Ok(summary);
+    };
+    for batch in to_embed.chunks(DEFAULT_EMBEDDING_BATCH_SIZE) {
+        let texts: Vec<&str> = batch.iter().map(|(_, content)| content.as_str()).collect();
+        let vectors = generate_embeddings_batch(
+            &config.api_url,
+            &config.model_name,
+            &texts,
+            config.api_key.as_deref(),
+        )
+        .await?;
+        for ((document_id, _), vector) in batch.iter().zip(vectors) {
+            insert_embedding(
+                &mut statements,
+                &conn,
+                document_id,
+                &config.model_name,
+                &vector,
+            )
+            .await?;
+            summary.embeddings_generated += 1;
+        }
+    }
+    info!(
+        "Imported {} documents ({} duplicates skipped, {} vectors copied, {} generated).",
+        summary.document_ids.len(),
+        summary.duplicates_skipped,
+        summary.embeddings_copied,
+        summary.embeddings_generated
+    );
+    Ok(summary)
+}
+
+async fn insert_embedding(
+    statements: &mut StatementCache,
+    conn: &turso::Connection,
+    document_id: &str,
+    model_name: &str,
+    vector: &[f32],
+) -> Result<(), turso::Error> {
+    let vector_bytes: &[u8] =
+        unsafe { std::slice::from_raw_parts(vector.as_ptr() as *const u8, vector.len() * 4) };
+    statements
+        .prepare(conn, INSERT_EMBEDDING_SQL)
+        .await?
+        .execute(params![document_id, model_name, vector_bytes])
+        .await?;
+    Ok(())
+}
+
+/// The origin of the document, made unique per document so chunks of the same
+/// source can be told apart.
+fn source_url(format: ImportFormat, document: &ImportedDocument, index: usize) -> String {
+    let source = SOURCE_METADATA_KEYS
+        .iter()
+        .find_map(|key| document.metadata.get(*key).and_then(Value::as_str))
+        .map_or_else(|| format!("{format}-import"), str::to_string);
+    match &document.id {
+        Some(id) => format!("{source}#{id}"),
+        None => format!("{source}#{index}"),
+    }
+}
+
+fn title(document: &ImportedDocument) -> String {
+    match document
+        .metadata
+        .get(TITLE_METADATA_KEY)
+        .and_then(Value::as_str)
+    {
+        Some(title) => title.to_string(),
+        None => document.content.chars().take(TITLE_LENGTH).collect(),
+    }
+}
+
+fn parse_langchain(data: &str) -> Result<Vec<ImportedDocument>, ImportError> {
+    let mut documents = Vec::new();
+    for (line_number, line) in data.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value = serde_json::from_str(line)
+            .map_err(|e| ImportError::Parse(format!("line {}: {e}", line_number + 1)))?;
+        // `dumpd` wraps the fields of the document in `kwargs`.
+        let fields = value.get("kwargs").unwrap_or(&value);
+        let Some(content) = fields.get("page_content").and_then(Value::as_str) else {
+            return Err(ImportError::Parse(format!(
+                "line {}: the document has no 'page_content'.",
+                line_number + 1
+            )));
+        };
+        documents.push(ImportedDocument {
+            id: fields.get("id").and_then(Value::as_str).map(str::to_string),
+            content: content.to_string(),
+            metadata: object(fields.get("metadata")),
+            embedding: vector(fields.get("embedding")),
+        });
+    }
+    Ok(documents)
+}
+
+fn parse_llamaindex(data: &str) -> Result<Vec<ImportedDocument>, ImportError> {
+    let value: Value = serde_json::from_str(data).map_err(|e| ImportError::Parse(e.to_string()))?;
+    let Some(nodes) = value
+        .get(LLAMAINDEX_DOCSTORE_KEY)
+        .and_then(Value::as_object)
+    else {
+        return Err(ImportError::Parse(format!(
+            "the export has no '{LLAMAINDEX_DOCSTORE_KEY}'."
+        )));
+    };
+    let embeddings = value.get(LLAMAINDEX_EMBEDDINGS_KEY);
+
+    let mut documents = Vec::new();
+    for (node_id, node) in nodes {
+        // Older versions store the node's fields as a JSON string.
+        let data = match node.get("__data__") {
+            Some(Value::String(json)) => {
+                serde_json::from_str(json).map_err(|e| ImportError::Parse(e.to_string()))?
+            }
+            Some(data) => data.clone(),
+            None => node.clone(),
+        };
+        let Some(content) = data.get("text").and_then(Value::as_str) else {
+            continue;
+        };
+        if content.trim().is_empty() {
+            continue;
+        }
+        let embedding = vector(data.get("embedding"))
+            .or_else(|| vector(embeddings.and_then(|embeddings| embeddings.get(node_id))));
+        documents.push(ImportedDocument {
+            id: Some(node_id.clone()),
+            content: content.to_string(),
+            metadata: object(data.get("metadata")),
+            embedding,
+        });
+    }
+    Ok(documents)
+}
+
+fn object(value: Option<&Value>) -> Map<String, Value> {
+    match value {
+        Some(Value::Object(map)) => map.clone(),
+        _ => Map::new(),
+    }
+}
+
+fn vector(value: Option<&Value>) -> Option<Vec<f32>> {
+    let values = value?.as_array()?;
+    if values.is_empty() {
+        return None;
+    }
+    values
+        .iter()
+        .map(|v| v.as_f64().map(|f| f as f32))
+        .collect()
+}
@@ -1,16 +1,25 @@
 //! # Knowledge Base Utilities
 //!
 //! This module provides shared utilities for the knowledge base, such as helper
-//! functions for cleaning LLM responses and logic for exporting data for fine-tuning.
-//! The core ingestion pipelines are now located in their respective plugin crates
-//! (e.g., `anyrag-web`, `anyrag-pdf`).
+//! functions for cleaning LLM responses, logic for exporting data for fine-tuning, and
+//! the distillation of documents into `faq_items`.
+//! It also holds the [`KnowledgePipeline`], the LLM restructuring and metadata
+//! extraction that the document ingestors of the plugin crates (e.g., `anyrag-web`,
+//! `anyrag-pdf`, `anyrag-sheets`) run on the content they fetch.
 
+use crate::constants::{DEFAULT_INGEST_CONCURRENCY, DEFAULT_RESTRUCTURING_REPAIR_ATTEMPTS};
+use crate::ingest::chunking::{ChunkingStrategy, InvalidChunkingError, DEFAULT_CHUNK_SIZE};
+use crate::ingest::finetuning::{export_finetuning_dataset, FinetuningExportOptions};
+use crate::ingest::traits::IngestionPrompts;
 use crate::ingest::types::{ContentMetadata, MetadataResponse};
+use crate::prompts::knowledge::{KNOWLEDGE_REPAIR_SYSTEM_PROMPT, KNOWLEDGE_REPAIR_USER_PROMPT};
 use crate::providers::ai::AiProvider;
 use crate::PromptError;
+use futures::stream::{self, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use thiserror::Error;
-use tracing::{debug, info, warn};
+use tracing::{debug, info, instrument, warn};
 use turso::{params, Connection, Database};
 
 // --- Data Structures for YAML Parsing ---
@@ -44,6 +53,12 @@ pub enum KnowledgeError {
     Parse(#[from] serde_json::Error),
     #[error("LLM processing failed: {0}")]
     Llm(#[from] PromptError),
+    #[error("Failed to serialize YAML: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("Document not found: {0}")]
+    NotFound(String),
+    #[error("Unknown fine-tuning format '{0}'; expected 'openai', 'gemini' or 'alpaca'.")]
+    UnknownFinetuningFormat(String),
 }
 
 // --- Helper Functions ---
@@ -62,100 +77,456 @@ pub fn clean_llm_response(response: &str) -> String {
 
 // --- Fine-Tuning Export ---
 
-#[derive(Serialize, Debug)]
-struct FinetuningEntry<'a> {
-    messages: Vec<FinetuningMessage<'a>>,
+/// Exports the structured knowledge base into a JSONL file suitable for fine-tuning models.
+///
+/// This is the OpenAI chat format export of every document; see
+/// [`export_finetuning_dataset`] for the other formats and the filters.
+pub async fn export_for_finetuning(db: &Database) -> Result<String, KnowledgeError> {
+    export_finetuning_dataset(db, &FinetuningExportOptions::default()).await
 }
 
-#[derive(Serialize, Debug)]
-struct FinetuningMessage<'a> {
-    role: &'a str,
-    content: &'a str,
+// --- Core Ingestion Pipeline Functions ---
+
+#[instrument(name = "ingest.restructure", skip_all)]
+pub async fn restructure_with_llm(
+    ai_provider: &dyn AiProvider,
+    markdown_content: &str,
+    system_prompt: &str,
+) -> Result<String, KnowledgeError> {
+    let user_prompt = format!("# Markdown Content to Process:\n{markdown_content}");
+    let llm_response = ai_provider.generate(system_prompt, &user_prompt).await?;
+    Ok(strip_yaml_fence(&llm_response))
 }
 
-/// Exports the structured knowledge base into a JSONL file suitable for fine-tuning models.
-pub async fn export_for_finetuning(db: &Database) -> Result<String, KnowledgeError> {
-    info!("Exporting knowledge base for fine-tuning from structured YAML.");
-    let conn = db.connect()?;
-    let mut stmt = conn
-        .prepare("SELECT content FROM documents WHERE content IS NOT NULL AND content != ''")
+/// Restructures a document that was split into chunks, one LLM call per chunk, with
+/// up to `concurrency` calls in flight at once.
+///
+/// The sections of every chunk are merged into a single YAML document, in chunk order.
+/// Chunks whose response is not valid YAML are skipped with a warning. A single chunk is
+/// passed through `restructure_with_llm` unchanged.
+#[instrument(name = "ingest.restructure_chunks", skip_all, fields(chunks = chunks.len()))]
+pub async fn restructure_chunks_with_llm(
+    ai_provider: &dyn AiProvider,
+    chunks: &[String],
+    system_prompt: &str,
+    concurrency: usize,
+) -> Result<String, KnowledgeError> {
+    if let [chunk] = chunks {
+        return restructure_with_llm(ai_provider, chunk, system_prompt).await;
+    }
+
+    let responses =
+        restructure_each_with_llm(ai_provider, chunks, system_prompt, concurrency).await?;
+    merge_restructured_chunks(&responses)
+}
+
+/// Restructures every chunk on its own, with up to `concurrency` LLM calls in flight at
+/// once. Returns the YAML of each chunk, in the order of `chunks`.
+pub async fn restructure_each_with_llm(
+    ai_provider: &dyn AiProvider,
+    chunks: &[String],
+    system_prompt: &str,
+    concurrency: usize,
+) -> Result<Vec<String>, KnowledgeError> {
+    let mut responses: Vec<(usize, String)> = stream::iter(chunks.iter().enumerate())
+        .map(|(index, chunk)| async move {
+            let structured_yaml = restructure_with_llm(ai_provider, chunk, system_prompt).await?;
+            Ok::<_, KnowledgeError>((index, structured_yaml))
+        })
+        .buffer_unordered(concurrency.max(1))
+        .try_collect()
         .await?;
-    let mut rows = stmt.query(()).await?;
-    let system_prompt =
-        "You are a helpful assistant. Provide clear, accurate answers based on the retrieved context.";
-    let mut jsonl_output = String::new();
+    responses.sort_by_key(|(index, _)| *index);
+    Ok(responses
+        .into_iter()
+        .map(|(_, structured_yaml)| structured_yaml)
+        .collect())
+}
 
-    while let Some(row) = rows.next().await? {
-        let yaml_content = if let Ok(turso::Value::Text(s)) = row.get_value(0) {
-            s
-        } else {
-            continue;
-        };
+/// Merges the sections of the restructured YAML of several chunks into one document.
+/// Returns an empty string when no chunk has a section.
+pub fn merge_restructured_chunks(responses: &[String]) -> Result<String, KnowledgeError> {
+    let mut merged = YamlContent { sections: vec![] };
+    for (index, structured_yaml) in responses.iter().enumerate() {
+        match serde_yaml::from_str::<YamlContent>(structured_yaml) {
+            Ok(content) => merged.sections.extend(content.sections),
+            Err(e) => warn!("Failed to parse YAML for chunk {index}, skipping. Error: {e}"),
+        }
+    }
 
-        // Only process documents that appear to be our structured YAML format.
-        if !yaml_content.trim().starts_with("sections:") {
-            continue;
+    if merged.sections.is_empty() {
+        return Ok(String::new());
+    }
+    Ok(serde_yaml::to_string(&merged)?)
+}
+
+// --- Knowledge Pipeline ---
+
+/// The title of a document that holds an LLM response which is not valid knowledge YAML.
+pub const UNPARSED_CONTENT_TITLE: &str = "Unparsed Content";
+/// The `content_metadata` property recording how a document's YAML was obtained; see
+/// [`RestructuringOutcome`].
+pub const RESTRUCTURING_PROPERTY: &str = "restructuring";
+
+/// How the restructured YAML of a document was obtained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestructuringOutcome {
+    /// The LLM's response was valid on the first try.
+    Valid,
+    /// The LLM fixed its invalid response when asked to.
+    Repaired,
+    /// No valid YAML could be obtained, so the raw response was stored.
+    Unparsed,
+    /// The source asked for no restructuring, so the content was stored as fetched.
+    Skipped,
+}
+
+impl RestructuringOutcome {
+    /// The value stored under [`RESTRUCTURING_PROPERTY`].
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Valid => "valid",
+            Self::Repaired => "repaired",
+            Self::Unparsed => "unparsed",
+            Self::Skipped => "skipped",
         }
+    }
+}
+
+/// The outcome of restructuring content with the LLM.
+#[derive(Debug)]
+pub enum Restructured {
+    /// The sections of the content, with the YAML to store: the LLM's response as-is
+    /// for a single chunk, or the merged sections of several.
+    Sections {
+        content: YamlContent,
+        yaml: String,
+        /// Whether any of the YAML had to be repaired.
+        repaired: bool,
+    },
+    /// No response is valid knowledge YAML, even after repair. The responses are kept as
+    /// they are, so the content is stored rather than lost.
+    Unparsed(String),
+    /// The LLM returned no sections at all.
+    Empty,
+}
 
-        let parsed_yaml: YamlContent = match serde_yaml::from_str(&yaml_content) {
-            Ok(data) => data,
-            Err(e) => {
-                warn!("Failed to parse YAML content for fine-tuning export, skipping document. Error: {}", e);
-                continue;
+impl Restructured {
+    /// Merges the restructured chunks of one document.
+    ///
+    /// The sections of the valid chunks are merged, and the unparsed ones are skipped
+    /// with a warning. The raw responses are only kept when no chunk is valid.
+    pub fn merge(parts: Vec<Restructured>) -> Result<Self, KnowledgeError> {
+        if parts.len() == 1 {
+            return Ok(parts.into_iter().next().unwrap_or(Self::Empty));
+        }
+        let mut merged = YamlContent { sections: vec![] };
+        let mut any_repaired = false;
+        let mut unparsed = Vec::new();
+        for (index, part) in parts.into_iter().enumerate() {
+            match part {
+                Self::Sections {
+                    content, repaired, ..
+                } => {
+                    merged.sections.extend(content.sections);
+                    any_repaired |= repaired;
+                }
+                Self::Unparsed(raw) => unparsed.push((index, raw)),
+                Self::Empty => {}
             }
-        };
+        }
 
-        for section in parsed_yaml.sections {
-            for faq in section.faqs {
-                let entry = FinetuningEntry {
-                    messages: vec![
-                        FinetuningMessage {
-                            role: "system",
-                            content: system_prompt,
-                        },
-                        FinetuningMessage {
-                            role: "user",
-                            content: &faq.question,
-                        },
-                        FinetuningMessage {
-                            role: "assistant",
-                            content: &faq.answer,
-                        },
-                    ],
-                };
-                let line = serde_json::to_string(&entry)?;
-                jsonl_output.push_str(&line);
-                jsonl_output.push('\n');
+        if !merged.sections.is_empty() {
+            for (index, _) in &unparsed {
+                warn!("Skipping chunk {index}, whose restructured YAML could not be parsed.");
             }
+            let yaml = serde_yaml::to_string(&merged)?;
+            return Ok(Self::Sections {
+                content: merged,
+                yaml,
+                repaired: any_repaired,
+            });
         }
+        if unparsed.is_empty() {
+            return Ok(Self::Empty);
+        }
+        let raw: Vec<String> = unparsed.into_iter().map(|(_, raw)| raw).collect();
+        Ok(Self::Unparsed(raw.join("\n\n")))
     }
 
-    info!(
-        "Generated fine-tuning data with {} entries.",
-        jsonl_output.lines().count()
-    );
-    Ok(jsonl_output)
+    /// How the YAML was obtained, or `None` when there is none.
+    pub fn outcome(&self) -> Option<RestructuringOutcome> {
+        match self {
+            Self::Sections { repaired: true, .. } => Some(RestructuringOutcome::Repaired),
+            Self::Sections { .. } => Some(RestructuringOutcome::Valid),
+            Self::Unparsed(_) => Some(RestructuringOutcome::Unparsed),
+            Self::Empty => None,
+        }
+    }
+
+    /// The text to store as the document's content, or `None` when there is none.
+    pub fn into_text(self) -> Option<String> {
+        match self {
+            Self::Sections { yaml, .. } => Some(yaml),
+            Self::Unparsed(raw) => Some(raw),
+            Self::Empty => None,
+        }
+    }
 }
 
-// --- Core Ingestion Pipeline Functions ---
+/// Checks restructured YAML against the restructuring schema. Returns the parsed
+/// content, `None` for a document without sections, or the problem found.
+pub fn validate_restructured_yaml(yaml: &str) -> Result<Option<YamlContent>, String> {
+    if yaml.trim().is_empty() {
+        return Ok(None);
+    }
+    let content: YamlContent = serde_yaml::from_str(yaml).map_err(|e| e.to_string())?;
+    for (index, section) in content.sections.iter().enumerate() {
+        if section.title.trim().is_empty() {
+            return Err(format!("Section {index} has an empty `title`."));
+        }
+        for (faq_index, faq) in section.faqs.iter().enumerate() {
+            if faq.question.trim().is_empty() || faq.answer.trim().is_empty() {
+                return Err(format!(
+                    "FAQ {faq_index} of section '{}' has an empty `question` or `answer`.",
+                    section.title
+                ));
+            }
+        }
+    }
+    Ok((!content.sections.is_empty()).then_some(content))
+}
 
-pub async fn restructure_with_llm(
-    ai_provider: &dyn AiProvider,
-    markdown_content: &str,
-    system_prompt: &str,
-) -> Result<String, KnowledgeError> {
-    let user_prompt = format!("# Markdown Content to Process:\n{markdown_content}");
-    let llm_response = ai_provider.generate(system_prompt, &user_prompt).await?;
-    let cleaned_yaml = llm_response
+/// Splits fetched content into the documents stored for a source that asked for no
+/// restructuring, with the source's chunking strategy or, by default, one chunk per
+/// markdown section. Fails if the strategy's parameters cannot produce chunks.
+pub fn chunk_without_restructuring(
+    content: &str,
+    chunking: Option<&ChunkingStrategy>,
+) -> Result<Vec<String>, InvalidChunkingError> {
+    let default_strategy = ChunkingStrategy::Markdown {
+        chunk_size: DEFAULT_CHUNK_SIZE,
+    };
+    Ok(chunking
+        .unwrap_or(&default_strategy)
+        .chunker()?
+        .chunk(content))
+}
+
+/// The text of the first markdown heading of a chunk outside fenced code, used as the
+/// title of a document stored without restructuring.
+pub fn chunk_title(chunk: &str) -> Option<String> {
+    let mut in_code_block = false;
+    for line in chunk.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if !in_code_block && (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            let title = trimmed[level..].trim().trim_end_matches('#').trim();
+            if !title.is_empty() {
+                return Some(title.to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Strips the markdown code fence an LLM may wrap YAML in.
+fn strip_yaml_fence(response: &str) -> String {
+    response
         .trim()
         .strip_prefix("```yaml")
-        .unwrap_or(&llm_response)
+        .unwrap_or(response)
         .strip_suffix("```")
-        .unwrap_or(&llm_response)
-        .trim();
-    Ok(cleaned_yaml.to_string())
+        .unwrap_or(response)
+        .trim()
+        .to_string()
+}
+
+/// Turns fetched content into knowledge documents with the LLM: restructures it into
+/// YAML sections of FAQs, then extracts the metadata of the documents stored from them.
+///
+/// Every ingestor that stores LLM-restructured documents (web pages, PDFs and sheets)
+/// goes through this pipeline, so all of them treat the LLM's responses alike. Each
+/// response is checked against the restructuring schema, and an invalid one is sent
+/// back to the LLM to be fixed, up to the set number of attempts. Valid sections are
+/// stored, a response that stays invalid is stored as-is under
+/// [`UNPARSED_CONTENT_TITLE`], and an empty one adds no document. Which of these
+/// happened is recorded with [`KnowledgePipeline::record_outcome`].
+///
+/// A source can opt out of the restructuring with `"restructure": false`, for content
+/// that is already well structured or must be stored word for word. Its content is
+/// then stored as it is, split with [`chunk_without_restructuring`] by the web and PDF
+/// ingestors and by rows by the sheets one, and only the metadata is extracted by the
+/// LLM. Such documents are recorded as [`RestructuringOutcome::Skipped`].
+#[derive(Clone, Copy)]
+pub struct KnowledgePipeline<'a> {
+    ai_provider: &'a dyn AiProvider,
+    prompts: IngestionPrompts<'a>,
+    concurrency: usize,
+    repair_attempts: usize,
 }
 
+impl<'a> KnowledgePipeline<'a> {
+    pub fn new(ai_provider: &'a dyn AiProvider, prompts: IngestionPrompts<'a>) -> Self {
+        Self {
+            ai_provider,
+            prompts,
+            concurrency: DEFAULT_INGEST_CONCURRENCY,
+            repair_attempts: DEFAULT_RESTRUCTURING_REPAIR_ATTEMPTS,
+        }
+    }
+
+    /// Sets how many LLM calls are in flight at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets how many times the LLM is asked to fix invalid YAML before it is stored
+    /// unparsed; `0` never asks.
+    pub fn with_repair_attempts(mut self, repair_attempts: usize) -> Self {
+        self.repair_attempts = repair_attempts;
+        self
+    }
+
+    /// Restructures the chunks of one document, one LLM call per chunk.
+    pub async fn restructure(&self, chunks: &[String]) -> Result<Restructured, KnowledgeError> {
+        Restructured::merge(self.restructure_each(chunks).await?)
+    }
+
+    /// Restructures every chunk on its own, in order, for ingestors that store several
+    /// documents from one source. Combine the chunks of a document with
+    /// [`Restructured::merge`].
+    pub async fn restructure_each(
+        &self,
+        chunks: &[String],
+    ) -> Result<Vec<Restructured>, KnowledgeError> {
+        let responses = restructure_each_with_llm(
+            self.ai_provider,
+            chunks,
+            self.prompts.restructuring_system_prompt,
+            self.concurrency,
+        )
+        .await?;
+        stream::iter(responses)
+            .map(|response| self.validate_or_repair(response))
+            .buffered(self.concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Checks a response against the schema, asking the LLM to fix it while it is
+    /// invalid and attempts are left.
+    async fn validate_or_repair(&self, response: String) -> Result<Restructured, KnowledgeError> {
+        let mut error = match validate_restructured_yaml(&response) {
+            Ok(Some(content)) => {
+                return Ok(Restructured::Sections {
+                    content,
+                    yaml: response,
+                    repaired: false,
+                })
+            }
+            Ok(None) => return Ok(Restructured::Empty),
+            Err(error) => error,
+        };
+
+        let mut candidate = response.clone();
+        for attempt in 1..=self.repair_attempts {
+            debug!("Repairing restructured YAML (attempt {attempt}): {error}");
+            let user_prompt = KNOWLEDGE_REPAIR_USER_PROMPT
+                .replace("{error}", &error)
+                .replace("{yaml}", &candidate);
+            candidate = match self
+                .ai_provider
+                .generate(KNOWLEDGE_REPAIR_SYSTEM_PROMPT, &user_prompt)
+                .await
+            {
+                Ok(repaired) => strip_yaml_fence(&repaired),
+                Err(e) => {
+                    warn!("Failed to ask the LLM to repair restructured YAML: {e}");
+                    break;
+                }
+            };
+            match validate_restructured_yaml(&candidate) {
+                Ok(Some(content)) => {
+                    info!("Repaired restructured YAML after {attempt} attempts.");
+                    return Ok(Restructured::Sections {
+                        content,
+                        yaml: candidate,
+                        repaired: true,
+                    });
+                }
+                // A repair must not drop the content of the document.
+                Ok(None) => error = "The document has no sections.".to_string(),
+                Err(e) => error = e,
+            }
+        }
+
+        warn!("Restructured YAML is still invalid, storing it unparsed: {error}");
+        Ok(Restructured::Unparsed(response))
+    }
+
+    /// Extracts and stores the metadata of a document, replacing what it had.
+    pub async fn extract_metadata(
+        &self,
+        conn: &Connection,
+        document_id: &str,
+        owner_id: Option<&str>,
+        content: &str,
+    ) -> Result<(), KnowledgeError> {
+        extract_and_store_metadata(
+            conn,
+            self.ai_provider,
+            document_id,
+            owner_id,
+            content,
+            self.prompts.metadata_extraction_system_prompt,
+        )
+        .await
+    }
+
+    /// Extracts and stores the metadata of several documents, given as their id and
+    /// content, with the LLM calls running concurrently.
+    pub async fn extract_metadata_of_each(
+        &self,
+        conn: &Connection,
+        documents: &[(String, String)],
+        owner_id: Option<&str>,
+    ) -> Result<(), KnowledgeError> {
+        extract_and_store_metadata_concurrently(
+            conn,
+            self.ai_provider,
+            documents,
+            owner_id,
+            self.prompts.metadata_extraction_system_prompt,
+            self.concurrency,
+        )
+        .await
+    }
+
+    /// Records how a document's YAML was obtained as its [`RESTRUCTURING_PROPERTY`].
+    /// Metadata extraction replaces all rows of a document, so this runs after it.
+    pub async fn record_outcome(
+        &self,
+        conn: &Connection,
+        document_id: &str,
+        owner_id: Option<&str>,
+        outcome: RestructuringOutcome,
+    ) -> Result<(), KnowledgeError> {
+        conn.execute(
+            "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_subtype, metadata_value) VALUES (?, ?, 'PROPERTY', ?, ?)",
+            params![document_id, owner_id, RESTRUCTURING_PROPERTY, outcome.as_str()],
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+#[instrument(name = "ingest.extract_metadata", skip_all, fields(document_id = %document_id))]
 pub async fn extract_and_store_metadata(
     conn: &Connection,
     ai_provider: &dyn AiProvider,
@@ -164,24 +535,74 @@ pub async fn extract_and_store_metadata(
     content: &str,
     system_prompt: &str,
 ) -> Result<(), KnowledgeError> {
+    let Some(metadata_items) = extract_metadata(ai_provider, content, system_prompt).await? else {
+        return Ok(());
+    };
+    store_metadata(conn, document_id, owner_id, &metadata_items).await
+}
+
+/// Extracts the metadata of several documents, with up to `concurrency` LLM calls in
+/// flight at once, and stores it.
+///
+/// `documents` holds the id and content of each document. Only the LLM calls overlap;
+/// the metadata is written on `conn` one document at a time as the calls complete.
+#[instrument(name = "ingest.extract_metadata_batch", skip_all, fields(documents = documents.len()))]
+pub async fn extract_and_store_metadata_concurrently(
+    conn: &Connection,
+    ai_provider: &dyn AiProvider,
+    documents: &[(String, String)],
+    owner_id: Option<&str>,
+    system_prompt: &str,
+    concurrency: usize,
+) -> Result<(), KnowledgeError> {
+    let mut extractions = stream::iter(documents)
+        .map(|(document_id, content)| async move {
+            let metadata_items = extract_metadata(ai_provider, content, system_prompt).await?;
+            Ok::<_, KnowledgeError>((document_id, metadata_items))
+        })
+        .buffer_unordered(concurrency.max(1));
+
+    while let Some((document_id, metadata_items)) = extractions.try_next().await? {
+        let Some(metadata_items) = metadata_items else {
+            continue;
+        };
+        store_metadata(conn, document_id, owner_id, &metadata_items).await?;
+    }
+    Ok(())
+}
+
+/// Asks the LLM for the metadata of `content`. Returns `None` when its response cannot
+/// be parsed.
+pub async fn extract_metadata(
+    ai_provider: &dyn AiProvider,
+    content: &str,
+    system_prompt: &str,
+) -> Result<Option<Vec<ContentMetadata>>, KnowledgeError> {
     let user_prompt = content;
     let llm_response = ai_provider.generate(system_prompt, user_prompt).await?;
     debug!("LLM metadata response: {}", llm_response);
     let cleaned_response = clean_llm_response(&llm_response);
 
-    let metadata_items: Vec<ContentMetadata> =
-        if let Ok(items) = serde_json::from_str(&cleaned_response) {
-            items
-        } else if let Ok(response) = serde_json::from_str::<MetadataResponse>(&cleaned_response) {
-            response.metadata
-        } else {
-            warn!(
-                "Failed to parse metadata response, skipping. Raw response: '{}'",
-                &cleaned_response
-            );
-            return Ok(());
-        };
+    if let Ok(items) = serde_json::from_str(&cleaned_response) {
+        return Ok(Some(items));
+    }
+    if let Ok(response) = serde_json::from_str::<MetadataResponse>(&cleaned_response) {
+        return Ok(Some(response.metadata));
+    }
+    warn!(
+        "Failed to parse metadata response, skipping. Raw response: '{}'",
+        &cleaned_response
+    );
+    Ok(None)
+}
 
+/// Replaces the stored metadata of a document with `metadata_items`.
+pub async fn store_metadata(
+    conn: &Connection,
+    document_id: &str,
+    owner_id: Option<&str>,
+    metadata_items: &[ContentMetadata],
+) -> Result<(), KnowledgeError> {
     conn.execute(
         "DELETE FROM content_metadata WHERE document_id = ?",
         params![document_id],
@@ -195,7 +616,7 @@ pub async fn extract_and_store_metadata(
     conn.execute("BEGIN TRANSACTION", ()).await?;
     let mut stmt = conn.prepare("INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_subtype, metadata_value) VALUES (?, ?, ?, ?, ?)")
         .await?;
-    for item in &metadata_items {
+    for item in metadata_items {
         stmt.execute(params![
             document_id.to_string(),
             owner_id.map(|s| s.to_string()),
@@ -208,3 +629,143 @@ pub async fn extract_and_store_metadata(
     conn.execute("COMMIT", ()).await?;
     Ok(())
 }
+
+// --- FAQ Generation ---
+
+const SELECT_FAQ_SOURCE_SQL: &str =
+    "SELECT owner_id, source_url, content FROM documents WHERE id = ?";
+const INSERT_FAQ_ITEM_SQL: &str = "INSERT INTO faq_items (document_id, owner_id, question, answer, source_url, is_explicit) VALUES (?, ?, ?, ?, ?, ?)";
+
+/// A question and answer pair distilled from a document.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct DistilledFaq {
+    pub question: String,
+    pub answer: String,
+    /// Whether the document asks the question itself, rather than only answering it.
+    #[serde(default)]
+    pub is_explicit: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct DistillationResponse {
+    #[serde(default)]
+    faqs: Vec<DistilledFaq>,
+}
+
+/// The outcome of generating the FAQs of a document.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FaqGeneration {
+    pub document_id: String,
+    /// The number of pairs stored in `faq_items`.
+    pub stored: usize,
+    /// The number of pairs skipped because their question was already asked.
+    pub duplicates: usize,
+}
+
+/// Distills the question and answer pairs of a document into `faq_items`.
+///
+/// The document's content fills the `{markdown_content}` placeholder of the user
+/// prompt, and the LLM answers with the `faqs` of the knowledge distillation prompt.
+/// Each pair is stored with its provenance: the document it was distilled from, the
+/// document's link, and whether the document asks the question explicitly. Pairs whose
+/// question the document's owner already has an FAQ for, compared regardless of case
+/// and whitespace, are skipped.
+#[instrument(name = "knowledge.generate_faqs", skip_all, fields(document_id = %document_id))]
+pub async fn generate_faqs(
+    conn: &Connection,
+    ai_provider: &dyn AiProvider,
+    document_id: &str,
+    system_prompt: &str,
+    user_prompt_template: &str,
+) -> Result<FaqGeneration, KnowledgeError> {
+    let mut rows = conn
+        .query(SELECT_FAQ_SOURCE_SQL, params![document_id])
+        .await?;
+    let Some(row) = rows.next().await? else {
+        return Err(KnowledgeError::NotFound(document_id.to_string()));
+    };
+    let owner_id: Option<String> = row.get(0)?;
+    let source_url: Option<String> = row.get(1)?;
+    let content: String = row.get(2)?;
+    drop(rows);
+
+    let user_prompt = user_prompt_template.replace("{markdown_content}", &content);
+    let llm_response = ai_provider.generate(system_prompt, &user_prompt).await?;
+    debug!("LLM FAQ distillation response: {}", llm_response);
+    let response: DistillationResponse = serde_json::from_str(&clean_llm_response(&llm_response))?;
+
+    let mut asked_questions = owner_questions(conn, owner_id.as_deref()).await?;
+    let mut generation = FaqGeneration {
+        document_id: document_id.to_string(),
+        ..Default::default()
+    };
+    conn.execute("BEGIN TRANSACTION", ()).await?;
+    for faq in response.faqs {
+        let (question, answer) = (faq.question.trim(), faq.answer.trim());
+        if question.is_empty() || answer.is_empty() {
+            continue;
+        }
+        if !asked_questions.insert(normalize_question(question)) {
+            generation.duplicates += 1;
+            continue;
+        }
+        conn.execute(
+            INSERT_FAQ_ITEM_SQL,
+            params![
+                document_id,
+                owner_id.clone(),
+                question,
+                answer,
+                source_url.clone(),
+                i64::from(faq.is_explicit)
+            ],
+        )
+        .await?;
+        generation.stored += 1;
+    }
+    conn.execute("COMMIT", ()).await?;
+
+    info!(
+        "Stored {} FAQs for document '{document_id}', skipped {} duplicates.",
+        generation.stored, generation.duplicates
+    );
+    Ok(generation)
+}
+
+/// Returns the normalized questions of the FAQs an owner already has.
+async fn owner_questions(
+    conn: &Connection,
+    owner_id: Option<&str>,
+) -> Result<HashSet<String>, turso::Error> {
+    let mut rows = match owner_id {
+        Some(owner) => {
+            conn.query(
+                "SELECT question FROM faq_items WHERE owner_id = ?",
+                params![owner],
+            )
+            .await?
+        }
+        None => {
+            conn.query("SELECT question FROM faq_items WHERE owner_id IS NULL", ())
+                .await?
+        }
+    };
+    let mut questions = HashSet::new();
+    while let Some(row) = rows.next().await? {
+        questions.insert(normalize_question(&row.get::<String>(0)?));
+    }
+    Ok(questions)
+}
+
+/// Normalizes a question for deduplication: lowercased, with runs of whitespace
+/// collapsed and trailing question marks removed.
+pub(crate) fn normalize_question(question: &str) -> String {
+    question
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches('?')
+        .trim_end()
+        .to_lowercase()
+}
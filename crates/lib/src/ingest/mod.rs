@@ -4,22 +4,74 @@
 //! such as RSS feeds, text, and knowledge bases, and storing it in a local
 //! database for later use in RAG.
 
+pub mod bulk;
+
+pub mod chunking;
+
+pub mod dedup;
+
 pub mod embedding;
 
+pub mod export;
+
+pub mod finetuning;
+
+pub mod import;
+
 pub mod knowledge;
 
+pub mod pipeline;
+
+pub mod revisions;
+
 #[cfg(feature = "sheets")]
 pub mod shared;
 
+pub mod sources;
+
 pub mod state_manager;
 
 pub mod traits;
 
+pub mod trash;
+
 pub mod types;
 
-pub use embedding::{embed_article, EmbeddingError};
+pub use bulk::{bulk_insert_documents, bulk_insert_rows, NewDocument};
+
+pub use chunking::{Chunker, ChunkingStrategy, InvalidChunkingError};
+
+pub use dedup::{content_hash, find_duplicate_document, find_duplicate_hashes};
+
+pub use embedding::{
+    embed_article, embed_documents, embed_ingested_documents, find_unembedded_documents,
+    store_document_embeddings, EmbeddingError, UnembeddedDocument,
+};
+
+pub use export::{export_knowledge_base, export_knowledge_bases, ExportError, ExportFormat};
+
+pub use finetuning::{export_finetuning_dataset, FinetuningExportOptions, FinetuningFormat};
+
+pub use import::{import_documents, ImportError, ImportFormat, ImportSummary};
+
+pub use knowledge::{
+    chunk_without_restructuring, export_for_finetuning, generate_faqs, FaqGeneration,
+    KnowledgeError, KnowledgePipeline, Restructured,
+};
+
+pub use pipeline::{
+    reset_pipeline_progress, run_pipeline, PipelineDefinition, PipelineError, PipelineReport,
+    PipelineSelection, PipelineStage, StageFailure, StageReport,
+};
+
+pub use revisions::{document_history, record_revision, DocumentHistory, RevisionError};
+
+pub use sources::source_url_prefix_pattern;
 
-pub use knowledge::{export_for_finetuning, KnowledgeError};
+pub use trash::{
+    list_trash, purge_trash, restore_document, soft_delete_document, TrashConfig, TrashError,
+    TrashedDocument,
+};
 
 pub use traits::{IngestError, IngestionPrompts, IngestionResult, Ingestor};
 pub use types::{ContentMetadata, MetadataResponse};
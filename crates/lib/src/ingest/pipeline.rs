@@ -0,0 +1,471 @@
+//! # Enrichment Pipelines
+//!
+//! An enrichment pipeline runs stages over the documents already in a database: metadata
+//! extraction, source summarization, FAQ generation and knowledge graph building. It is
+//! defined in YAML, with the documents it applies to and the stages in the order they
+//! run:
+//!
+//! ```yaml
+//! name: manuals
+//! select:
+//!   sources: ["https://docs.example.com/"]
+//!   limit: 500
+//! stages:
+//!   - stage: metadata
+//!   - stage: summarize
+//!     min_source_chars: 4000
+//!   - stage: faqs
+//!   - stage: graph
+//!     path: db/graph
+//! ```
+//!
+//! The outcome of every item of every stage is recorded in `pipeline_progress`, under
+//! the pipeline's name. A run skips the items a previous run of the same pipeline
+//! finished, so an interrupted run resumes where it stopped. An item that fails is
+//! recorded with its error and the run goes on; the next run retries it.
+
+use crate::ingest::knowledge::{extract_and_store_metadata, generate_faqs};
+use crate::prompts::{
+    knowledge::METADATA_EXTRACTION_SYSTEM_PROMPT,
+    tasks::{
+        DOCUMENT_SUMMARIZATION_SYSTEM_PROMPT, DOCUMENT_SUMMARIZATION_USER_PROMPT,
+        KNOWLEDGE_DISTILLATION_SYSTEM_PROMPT, KNOWLEDGE_DISTILLATION_USER_PROMPT,
+    },
+};
+use crate::providers::ai::AiProvider;
+use crate::summarization::{is_summary_link, source_of, summarize_source, SummarizationConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+use turso::{params, Connection, Value as TursoValue};
+
+#[cfg(feature = "graph_db")]
+use crate::{
+    graph::{extraction::extract_facts, persistent::PersistentKnowledgeGraph},
+    prompts::tasks::{
+        KNOWLEDGE_GRAPH_EXTRACTION_SYSTEM_PROMPT, KNOWLEDGE_GRAPH_EXTRACTION_USER_PROMPT,
+    },
+};
+
+const SELECT_DONE_ITEMS_SQL: &str =
+    "SELECT item_id FROM pipeline_progress WHERE pipeline = ? AND stage = ? AND status = 'done'";
+const RECORD_PROGRESS_SQL: &str =
+    "INSERT INTO pipeline_progress (pipeline, stage, item_id, status, error) VALUES (?, ?, ?, ?, ?)
+     ON CONFLICT(pipeline, stage, item_id) DO UPDATE SET
+        status = excluded.status,
+        error = excluded.error,
+        attempts = pipeline_progress.attempts + 1,
+        updated_at = CURRENT_TIMESTAMP";
+
+/// Custom error types for enrichment pipelines.
+#[derive(Error, Debug)]
+pub enum PipelineError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Invalid pipeline definition: {0}")]
+    Definition(String),
+    #[error("Failed to open the knowledge graph: {0}")]
+    Graph(String),
+}
+
+/// A pipeline: the documents it applies to and the stages run over them.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PipelineDefinition {
+    /// The name progress is recorded under. Runs of the same name resume each other.
+    pub name: String,
+    #[serde(default)]
+    pub select: PipelineSelection,
+    pub stages: Vec<PipelineStage>,
+}
+
+/// The documents a pipeline applies to. Trashed documents and source summaries are
+/// never selected.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct PipelineSelection {
+    /// Prefixes of the `source_url` of the selected documents. All sources when empty.
+    #[serde(default)]
+    pub sources: Vec<String>,
+    /// Only the documents of this owner. The documents of every owner when omitted.
+    #[serde(default)]
+    pub owner_id: Option<String>,
+    /// Only the documents of this collection id.
+    #[serde(default)]
+    pub collection: Option<String>,
+    /// The most documents selected, oldest first.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// A stage of a pipeline.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum PipelineStage {
+    /// Extracts the entities and keyphrases of each document into `content_metadata`.
+    Metadata,
+    /// Summarizes each source the selected documents are chunks of.
+    Summarize(SummarizationConfig),
+    /// Distills the question and answer pairs of each document into `faq_items`.
+    Faqs,
+    /// Extracts the facts of each document into the RocksDB knowledge graph at `path`.
+    Graph { path: String },
+}
+
+impl PipelineStage {
+    /// The name the stage's progress is recorded under.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Metadata => "metadata",
+            Self::Summarize(_) => "summarize",
+            Self::Faqs => "faqs",
+            Self::Graph { .. } => "graph",
+        }
+    }
+}
+
+impl PipelineDefinition {
+    /// Parses a pipeline from YAML and checks it.
+    pub fn from_yaml(yaml: &str) -> Result<Self, PipelineError> {
+        let definition: Self =
+            serde_yaml::from_str(yaml).map_err(|e| PipelineError::Definition(e.to_string()))?;
+        if definition.name.trim().is_empty() {
+            return Err(PipelineError::Definition(
+                "`name` must not be empty".to_string(),
+            ));
+        }
+        if definition.stages.is_empty() {
+            return Err(PipelineError::Definition(
+                "`stages` must list at least one stage".to_string(),
+            ));
+        }
+        let mut names = HashSet::new();
+        for stage in &definition.stages {
+            if !names.insert(stage.name()) {
+                return Err(PipelineError::Definition(format!(
+                    "stage '{}' is listed more than once",
+                    stage.name()
+                )));
+            }
+        }
+        if cfg!(not(feature = "graph_db")) && names.contains("graph") {
+            return Err(PipelineError::Definition(
+                "the `graph` stage needs the `graph_db` feature".to_string(),
+            ));
+        }
+        Ok(definition)
+    }
+}
+
+/// An item of a stage that failed.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct StageFailure {
+    /// The document id, or the source for the `summarize` stage.
+    pub item_id: String,
+    pub error: String,
+}
+
+/// The outcome of one stage of a run.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct StageReport {
+    pub stage: String,
+    /// The items processed by this run.
+    pub processed: usize,
+    /// The items skipped because a previous run finished them.
+    pub skipped: usize,
+    pub failures: Vec<StageFailure>,
+}
+
+/// The outcome of a run, stage by stage.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct PipelineReport {
+    pub pipeline: String,
+    /// The number of documents selected.
+    pub documents: usize,
+    pub stages: Vec<StageReport>,
+}
+
+impl PipelineReport {
+    /// The number of items that failed over all stages.
+    pub fn failed(&self) -> usize {
+        self.stages.iter().map(|stage| stage.failures.len()).sum()
+    }
+}
+
+/// A selected document.
+#[derive(Debug, Clone)]
+struct PipelineDocument {
+    id: String,
+    owner_id: Option<String>,
+    source_url: Option<String>,
+    content: String,
+}
+
+/// Runs the stages of a pipeline, in order, over the documents it selects.
+///
+/// Items a previous run of the pipeline finished are skipped. An item that fails is
+/// recorded in `pipeline_progress` and in the report, and does not stop the run; only
+/// a failure to read the selection or to record progress does.
+#[instrument(name = "ingest.pipeline", skip_all, fields(pipeline = %definition.name))]
+pub async fn run_pipeline(
+    conn: &mut Connection,
+    ai_provider: &dyn AiProvider,
+    definition: &PipelineDefinition,
+) -> Result<PipelineReport, PipelineError> {
+    let documents = select_documents(conn, &definition.select).await?;
+    info!(
+        "Running pipeline '{}' over {} documents.",
+        definition.name,
+        documents.len()
+    );
+    let mut report = PipelineReport {
+        pipeline: definition.name.clone(),
+        documents: documents.len(),
+        stages: Vec::new(),
+    };
+    for stage in &definition.stages {
+        let stage_report =
+            run_stage(conn, ai_provider, &definition.name, stage, &documents).await?;
+        info!(
+            "Stage '{}': {} processed, {} skipped, {} failed.",
+            stage_report.stage,
+            stage_report.processed,
+            stage_report.skipped,
+            stage_report.failures.len()
+        );
+        report.stages.push(stage_report);
+    }
+    Ok(report)
+}
+
+/// Forgets the progress of a pipeline, so that its next run processes every item again.
+/// Returns the number of items forgotten.
+pub async fn reset_pipeline_progress(
+    conn: &Connection,
+    pipeline: &str,
+) -> Result<u64, PipelineError> {
+    Ok(conn
+        .execute(
+            "DELETE FROM pipeline_progress WHERE pipeline = ?",
+            params![pipeline],
+        )
+        .await?)
+}
+
+async fn run_stage(
+    conn: &mut Connection,
+    ai_provider: &dyn AiProvider,
+    pipeline: &str,
+    stage: &PipelineStage,
+    documents: &[PipelineDocument],
+) -> Result<StageReport, PipelineError> {
+    let done = done_items(conn, pipeline, stage.name()).await?;
+    let mut report = StageReport {
+        stage: stage.name().to_string(),
+        ..Default::default()
+    };
+
+    #[cfg(feature = "graph_db")]
+    let mut graph = match stage {
+        PipelineStage::Graph { path } => Some(
+            PersistentKnowledgeGraph::open(path)
+                .map_err(|e| PipelineError::Graph(e.to_string()))?,
+        ),
+        _ => None,
+    };
+
+    for (item_id, owner_id, content) in stage_items(stage, documents) {
+        if done.contains(&item_id) {
+            report.skipped += 1;
+            continue;
+        }
+        let result: Result<(), String> = match stage {
+            PipelineStage::Metadata => extract_and_store_metadata(
+                conn,
+                ai_provider,
+                &item_id,
+                owner_id,
+                content,
+                METADATA_EXTRACTION_SYSTEM_PROMPT,
+            )
+            .await
+            .map_err(|e| e.to_string()),
+            PipelineStage::Summarize(config) => summarize_source(
+                conn,
+                ai_provider,
+                owner_id,
+                &item_id,
+                config,
+                DOCUMENT_SUMMARIZATION_SYSTEM_PROMPT,
+                DOCUMENT_SUMMARIZATION_USER_PROMPT,
+            )
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+            PipelineStage::Faqs => generate_faqs(
+                conn,
+                ai_provider,
+                &item_id,
+                KNOWLEDGE_DISTILLATION_SYSTEM_PROMPT,
+                KNOWLEDGE_DISTILLATION_USER_PROMPT,
+            )
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+            #[cfg(feature = "graph_db")]
+            PipelineStage::Graph { .. } => match graph.as_mut() {
+                Some(graph) => add_graph_facts(graph, ai_provider, &item_id, content).await,
+                None => Err("the knowledge graph is not open".to_string()),
+            },
+            #[cfg(not(feature = "graph_db"))]
+            PipelineStage::Graph { .. } => {
+                Err("the `graph` stage needs the `graph_db` feature".to_string())
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                record_progress(conn, pipeline, stage.name(), &item_id, None).await?;
+                report.processed += 1;
+            }
+            Err(error) => {
+                warn!("Stage '{}' failed for '{item_id}': {error}", stage.name());
+                record_progress(conn, pipeline, stage.name(), &item_id, Some(&error)).await?;
+                report.failures.push(StageFailure { item_id, error });
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// The items of a stage: each document, or each source for `summarize`. An item is its
+/// id, its owner and the content the stage reads.
+fn stage_items<'a>(
+    stage: &PipelineStage,
+    documents: &'a [PipelineDocument],
+) -> Vec<(String, Option<&'a str>, &'a str)> {
+    match stage {
+        PipelineStage::Summarize(_) => {
+            let mut seen = BTreeSet::new();
+            documents
+                .iter()
+                .filter_map(|document| {
+                    let source = source_of(document.source_url.as_deref()?).to_string();
+                    seen.insert((document.owner_id.clone(), source.clone()))
+                        .then_some((source, document.owner_id.as_deref(), ""))
+                })
+                .collect()
+        }
+        _ => documents
+            .iter()
+            .map(|document| {
+                (
+                    document.id.clone(),
+                    document.owner_id.as_deref(),
+                    document.content.as_str(),
+                )
+            })
+            .collect(),
+    }
+}
+
+/// Extracts the facts of a document into the graph, flushed to disk before the
+/// document is recorded as done.
+#[cfg(feature = "graph_db")]
+async fn add_graph_facts(
+    graph: &mut PersistentKnowledgeGraph,
+    ai_provider: &dyn AiProvider,
+    document_id: &str,
+    content: &str,
+) -> Result<(), String> {
+    let facts = extract_facts(
+        ai_provider,
+        content,
+        KNOWLEDGE_GRAPH_EXTRACTION_SYSTEM_PROMPT,
+        KNOWLEDGE_GRAPH_EXTRACTION_USER_PROMPT,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    graph.graph_mut().add_document_facts(document_id, &facts);
+    graph.sync().map_err(|e| e.to_string())
+}
+
+async fn select_documents(
+    conn: &Connection,
+    selection: &PipelineSelection,
+) -> Result<Vec<PipelineDocument>, PipelineError> {
+    let mut conditions = vec!["deleted_at IS NULL".to_string()];
+    let mut query_params: Vec<TursoValue> = Vec::new();
+    if !selection.sources.is_empty() {
+        let prefixes = vec!["source_url LIKE ?"; selection.sources.len()].join(" OR ");
+        conditions.push(format!("({prefixes})"));
+        for source in &selection.sources {
+            query_params.push(format!("{source}%").into());
+        }
+    }
+    if let Some(owner_id) = &selection.owner_id {
+        conditions.push("owner_id = ?".to_string());
+        query_params.push(owner_id.clone().into());
+    }
+    if let Some(collection) = &selection.collection {
+        conditions.push(
+            "id IN (SELECT document_id FROM document_collections WHERE collection_id = ?)"
+                .to_string(),
+        );
+        query_params.push(collection.clone().into());
+    }
+    let mut sql = format!(
+        "SELECT id, owner_id, source_url, content FROM documents WHERE {} ORDER BY rowid",
+        conditions.join(" AND ")
+    );
+    if let Some(limit) = selection.limit {
+        sql.push_str(&format!(" LIMIT {limit}"));
+    }
+
+    let mut rows = conn.query(&sql, query_params).await?;
+    let mut documents = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let document = PipelineDocument {
+            id: row.get(0)?,
+            owner_id: row.get(1)?,
+            source_url: row.get(2)?,
+            content: row.get(3)?,
+        };
+        if document.source_url.as_deref().is_some_and(is_summary_link) {
+            continue;
+        }
+        documents.push(document);
+    }
+    Ok(documents)
+}
+
+async fn done_items(
+    conn: &Connection,
+    pipeline: &str,
+    stage: &str,
+) -> Result<HashSet<String>, turso::Error> {
+    let mut rows = conn
+        .query(SELECT_DONE_ITEMS_SQL, params![pipeline, stage])
+        .await?;
+    let mut items = HashSet::new();
+    while let Some(row) = rows.next().await? {
+        items.insert(row.get::<String>(0)?);
+    }
+    Ok(items)
+}
+
+/// Records an item as done, or as failed with `error`.
+async fn record_progress(
+    conn: &Connection,
+    pipeline: &str,
+    stage: &str,
+    item_id: &str,
+    error: Option<&str>,
+) -> Result<(), turso::Error> {
+    let status = if error.is_some() { "failed" } else { "done" };
+    conn.execute(
+        RECORD_PROGRESS_SQL,
+        params![pipeline, stage, item_id, status, error.map(str::to_string)],
+    )
+    .await?;
+    Ok(())
+}
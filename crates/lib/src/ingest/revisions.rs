@@ -0,0 +1,207 @@
+//! # Document Revisions
+//!
+//! Ingestors upsert documents by `source_url`, so ingesting a source again replaces the
+//! stored content. Before the upsert, `record_revision` copies the stored version into
+//! the `document_revisions` table, which keeps the history of every document.
+//!
+//! The embedding of a document is only dropped, for `/embed/new` to compute it again,
+//! when the content changed materially: when the share of its word pairs that differ
+//! reaches `MATERIAL_CHANGE_RATIO`. A fixed typo or whitespace keeps the embedding.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use thiserror::Error;
+use tracing::info;
+use turso::{params, Connection};
+
+/// The share of changed word pairs from which a new version needs a new embedding.
+pub const MATERIAL_CHANGE_RATIO: f64 = 0.05;
+
+const SELECT_STORED_VERSION_SQL: &str =
+    "SELECT id, owner_id, title, content, content_hash FROM documents WHERE source_url = ?";
+const SELECT_LAST_REVISION_SQL: &str =
+    "SELECT COALESCE(MAX(revision), 0) FROM document_revisions WHERE document_id = ?";
+const INSERT_REVISION_SQL: &str = "INSERT INTO document_revisions (document_id, revision, owner_id, title, content, content_hash, change_ratio) VALUES (?, ?, ?, ?, ?, ?, ?)";
+const DELETE_EMBEDDINGS_SQL: &str = "DELETE FROM document_embeddings WHERE document_id = ?";
+
+/// Custom error types for document revisions.
+#[derive(Error, Debug)]
+pub enum RevisionError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Document not found: {0}")]
+    DocumentNotFound(String),
+}
+
+/// A version of a document that was replaced by a later one.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DocumentRevision {
+    /// The number of the version, starting at 1 for the first one stored.
+    pub revision: i64,
+    pub title: Option<String>,
+    pub content: String,
+    pub content_hash: Option<String>,
+    /// How much the next version changed this one, from 0 to 1.
+    pub change_ratio: f64,
+    /// When the next version replaced this one.
+    pub replaced_at: String,
+}
+
+/// The history of a document: its current version number and the versions it replaced.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DocumentHistory {
+    pub document_id: String,
+    pub owner_id: Option<String>,
+    pub source_url: Option<String>,
+    /// The number of the stored version.
+    pub current_revision: i64,
+    /// The replaced versions, newest first.
+    pub revisions: Vec<DocumentRevision>,
+}
+
+/// A revision recorded before an upsert.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedRevision {
+    pub document_id: String,
+    /// The number of the replaced version.
+    pub revision: i64,
+    pub change_ratio: f64,
+    /// Whether the change was material, in which case the embedding was dropped.
+    pub reembed: bool,
+}
+
+/// Measures how much a text changed, from 0 for the same words in the same order to 1
+/// for no word pair in common. Case and whitespace are ignored.
+pub fn change_ratio(old: &str, new: &str) -> f64 {
+    let (old, new) = (word_pairs(old), word_pairs(new));
+    let union = old.union(&new).count();
+    if union == 0 {
+        return 0.0;
+    }
+    1.0 - old.intersection(&new).count() as f64 / union as f64
+}
+
+/// The consecutive word pairs of a text, or its only word.
+fn word_pairs(text: &str) -> HashSet<String> {
+    let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+    if words.len() == 1 {
+        return words.into_iter().collect();
+    }
+    words
+        .windows(2)
+        .map(|pair| format!("{} {}", pair[0], pair[1]))
+        .collect()
+}
+
+/// Copies the stored version of the document at `source_url` into `document_revisions`
+/// before an upsert replaces its content with `new_content`, and drops its embedding
+/// when the change is material.
+///
+/// Returns `None` when no document is stored at `source_url`, or when its content is
+/// already `new_content`. Call it on the connection or transaction of the upsert.
+pub async fn record_revision(
+    conn: &Connection,
+    source_url: &str,
+    new_content: &str,
+) -> Result<Option<RecordedRevision>, turso::Error> {
+    let mut rows = conn
+        .query(SELECT_STORED_VERSION_SQL, params![source_url])
+        .await?;
+    let Some(row) = rows.next().await? else {
+        return Ok(None);
+    };
+    let document_id: String = row.get(0)?;
+    let owner_id: Option<String> = row.get(1)?;
+    let title: Option<String> = row.get(2)?;
+    let content: String = row.get(3)?;
+    let content_hash: Option<String> = row.get(4)?;
+    drop(rows);
+    if content == new_content {
+        return Ok(None);
+    }
+
+    let mut rows = conn
+        .query(SELECT_LAST_REVISION_SQL, params![document_id.as_str()])
+        .await?;
+    let last_revision: i64 = match rows.next().await? {
+        Some(row) => row.get(0)?,
+        None => 0,
+    };
+    drop(rows);
+
+    let revision = last_revision + 1;
+    let ratio = change_ratio(&content, new_content);
+    conn.execute(
+        INSERT_REVISION_SQL,
+        params![
+            document_id.as_str(),
+            revision,
+            owner_id,
+            title,
+            content,
+            content_hash,
+            ratio
+        ],
+    )
+    .await?;
+    let reembed = ratio >= MATERIAL_CHANGE_RATIO;
+    if reembed {
+        conn.execute(DELETE_EMBEDDINGS_SQL, params![document_id.as_str()])
+            .await?;
+    }
+    info!(
+        "Recorded revision {revision} of '{source_url}' (change ratio {ratio:.3}, re-embed: {reembed})."
+    );
+    Ok(Some(RecordedRevision {
+        document_id,
+        revision,
+        change_ratio: ratio,
+        reembed,
+    }))
+}
+
+/// Reads the history of a document.
+pub async fn document_history(
+    conn: &Connection,
+    document_id: &str,
+) -> Result<DocumentHistory, RevisionError> {
+    let mut rows = conn
+        .query(
+            "SELECT owner_id, source_url FROM documents WHERE id = ?",
+            params![document_id],
+        )
+        .await?;
+    let Some(row) = rows.next().await? else {
+        return Err(RevisionError::DocumentNotFound(document_id.to_string()));
+    };
+    let owner_id: Option<String> = row.get(0)?;
+    let source_url: Option<String> = row.get(1)?;
+    drop(rows);
+
+    let mut rows = conn
+        .query(
+            "SELECT revision, title, content, content_hash, change_ratio, replaced_at FROM document_revisions WHERE document_id = ? ORDER BY revision DESC",
+            params![document_id],
+        )
+        .await?;
+    let mut revisions = Vec::new();
+    while let Some(row) = rows.next().await? {
+        revisions.push(DocumentRevision {
+            revision: row.get(0)?,
+            title: row.get(1)?,
+            content: row.get(2)?,
+            content_hash: row.get(3)?,
+            change_ratio: row.get(4)?,
+            replaced_at: row.get(5)?,
+        });
+    }
+    Ok(DocumentHistory {
+        document_id: document_id.to_string(),
+        owner_id,
+        source_url,
+        current_revision: revisions.first().map_or(1, |latest| latest.revision + 1),
+        revisions,
+    })
+}
@@ -3,6 +3,7 @@
 //! This module provides functionality for ingesting Q&A pairs directly
 //! from a public Google Sheet into the `faq_kb` knowledge base table.
 
+use crate::ingest::revisions::record_revision;
 use crate::ingest::shared::{construct_export_url_and_table_name, download_csv, SheetError};
 use thiserror::Error;
 use tracing::{info, warn};
@@ -52,6 +53,7 @@ pub async fn ingest_faq_from_google_sheet(
     // Use INSERT ... ON CONFLICT DO NOTHING to avoid errors on re-ingestion.
     // We only create the parent document if it doesn't exist. The FAQs themselves
     // will be overwritten by the `store_faq_items` logic.
+    record_revision(&conn, sheet_url, &csv_data).await?;
     conn.execute(
         "INSERT INTO documents (id, owner_id, source_url, title, content)
          VALUES (?, ?, ?, ?, ?)
@@ -0,0 +1,24 @@
+//! # Source URL Patterns
+//!
+//! A source ingested again replaces the documents it produced before, which are found
+//! by their `source_url`: the source's own URL, or the URL with a fragment such as
+//! `#chunk_3`. Those prefixes are matched with `LIKE`, so the wildcards a URL may
+//! contain (`%` and `_`) must be escaped, or the ingestion would replace the documents
+//! of other sources too.
+
+/// The escape character of the patterns built by [`source_url_prefix_pattern`].
+/// Conditions using them are written `source_url LIKE ? ESCAPE '\'`.
+pub const LIKE_ESCAPE_CHAR: char = '\\';
+
+/// Returns the `LIKE` pattern of the source URLs that start with `prefix`.
+pub fn source_url_prefix_pattern(prefix: &str) -> String {
+    let mut pattern = String::with_capacity(prefix.len() + 1);
+    for c in prefix.chars() {
+        if matches!(c, '%' | '_' | LIKE_ESCAPE_CHAR) {
+            pattern.push(LIKE_ESCAPE_CHAR);
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
@@ -1,4 +1,5 @@
 use async_trait::async_trait;
+use serde::Serialize;
 use thiserror::Error;
 
 /// A generic error type for all ingestion plugins.
@@ -28,7 +29,8 @@ pub enum IngestError {
 ///
 /// This struct provides a standardized summary of what was accomplished during an
 /// ingestion task, which can be returned to the user or used for logging.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct IngestionResult {
     /// The original source identifier (e.g., URL, file path) that was processed.
     pub source: String,
@@ -0,0 +1,225 @@
+//! # Document Trash
+//!
+//! Deleting a document only moves it to the trash: its `deleted_at` is set, and every
+//! search, listing and export skips it from then on. A trashed document keeps its
+//! embeddings, metadata and FAQs, so restoring it makes it searchable again at once.
+//! Ingesting its content again restores it as well.
+//!
+//! `purge_trash` deletes for good the documents trashed before a cutoff, along with the
+//! rows that refer to them. The server runs it periodically with the retention of the
+//! `trash` configuration.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::info;
+use turso::{params, Connection, Value as TursoValue};
+
+/// The format `deleted_at` is stored in, which the purge cutoff is compared with.
+const DELETED_AT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+const SELECT_TRASHED_SQL: &str = "SELECT id, owner_id, source_url, title, deleted_at FROM documents WHERE deleted_at IS NOT NULL";
+
+/// The tables whose rows belong to a document and are purged with it.
+const DOCUMENT_TABLES: &[&str] = &[
+    "document_embeddings",
+    "content_metadata",
+    "document_collections",
+    "document_shares",
+    "faq_items",
+    "document_revisions",
+];
+
+/// Custom error types for the document trash.
+#[derive(Error, Debug)]
+pub enum TrashError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Document not found: {0}")]
+    DocumentNotFound(String),
+}
+
+/// Configuration for purging the trash.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct TrashConfig {
+    /// How long a deleted document can be restored, in days, before it is purged.
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+    /// How often the trash is purged, in seconds.
+    #[serde(default = "default_purge_interval_secs")]
+    pub purge_interval_secs: u64,
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: default_retention_days(),
+            purge_interval_secs: default_purge_interval_secs(),
+        }
+    }
+}
+
+fn default_retention_days() -> u32 {
+    30
+}
+
+fn default_purge_interval_secs() -> u64 {
+    3_600
+}
+
+/// A document in the trash.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TrashedDocument {
+    pub id: String,
+    pub owner_id: Option<String>,
+    pub source_url: Option<String>,
+    pub title: Option<String>,
+    /// When the document was deleted.
+    pub deleted_at: String,
+}
+
+/// Moves a document to the trash. With an `owner_id`, only a document of that owner is
+/// deleted.
+///
+/// Fails with `DocumentNotFound` when no such document exists or it is already in the
+/// trash.
+pub async fn soft_delete_document(
+    conn: &Connection,
+    document_id: &str,
+    owner_id: Option<&str>,
+) -> Result<TrashedDocument, TrashError> {
+    let (owner_filter, mut params) = owner_filter(owner_id);
+    params.insert(0, document_id.into());
+    let updated = conn
+        .execute(
+            &format!("UPDATE documents SET deleted_at = CURRENT_TIMESTAMP WHERE id = ? AND deleted_at IS NULL{owner_filter}"),
+            params,
+        )
+        .await?;
+    if updated == 0 {
+        return Err(TrashError::DocumentNotFound(document_id.to_string()));
+    }
+    info!("Moved document '{document_id}' to the trash.");
+    trashed_document(conn, document_id).await
+}
+
+/// Takes a document out of the trash. With an `owner_id`, only a document of that
+/// owner is restored.
+///
+/// Fails with `DocumentNotFound` when no such document is in the trash.
+pub async fn restore_document(
+    conn: &Connection,
+    document_id: &str,
+    owner_id: Option<&str>,
+) -> Result<TrashedDocument, TrashError> {
+    let trashed = trashed_document(conn, document_id).await?;
+    if owner_id.is_some() && trashed.owner_id.as_deref() != owner_id {
+        return Err(TrashError::DocumentNotFound(document_id.to_string()));
+    }
+    conn.execute(
+        "UPDATE documents SET deleted_at = NULL WHERE id = ?",
+        params![document_id],
+    )
+    .await?;
+    info!("Restored document '{document_id}' from the trash.");
+    Ok(trashed)
+}
+
+/// Lists the documents in the trash of `owner_id`, or of every owner when it is
+/// `None`, most recently deleted first.
+pub async fn list_trash(
+    conn: &Connection,
+    owner_id: Option<&str>,
+) -> Result<Vec<TrashedDocument>, TrashError> {
+    let (owner_filter, params) = owner_filter(owner_id);
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_TRASHED_SQL}{owner_filter} ORDER BY deleted_at DESC, id"),
+            params,
+        )
+        .await?;
+    let mut documents = Vec::new();
+    while let Some(row) = rows.next().await? {
+        documents.push(TrashedDocument {
+            id: row.get(0)?,
+            owner_id: row.get(1)?,
+            source_url: row.get(2)?,
+            title: row.get(3)?,
+            deleted_at: row.get(4)?,
+        });
+    }
+    Ok(documents)
+}
+
+/// Deletes for good the documents moved to the trash before `deleted_before`, with the
+/// rows that refer to them. Returns the number of documents purged.
+pub async fn purge_trash(
+    conn: &Connection,
+    deleted_before: DateTime<Utc>,
+) -> Result<u64, TrashError> {
+    let cutoff = deleted_before.format(DELETED_AT_FORMAT).to_string();
+    let purged_ids = "SELECT id FROM documents WHERE deleted_at IS NOT NULL AND deleted_at < ?";
+
+    conn.execute("BEGIN", ()).await?;
+    let result = async {
+        for table in DOCUMENT_TABLES {
+            conn.execute(
+                &format!("DELETE FROM {table} WHERE document_id IN ({purged_ids})"),
+                params![cutoff.as_str()],
+            )
+            .await?;
+        }
+        conn.execute(
+            "DELETE FROM documents WHERE deleted_at IS NOT NULL AND deleted_at < ?",
+            params![cutoff.as_str()],
+        )
+        .await
+    }
+    .await;
+
+    match result {
+        Ok(purged) => {
+            conn.execute("COMMIT", ()).await?;
+            if purged > 0 {
+                info!("Purged {purged} documents deleted before {cutoff}.");
+            }
+            Ok(purged)
+        }
+        Err(e) => {
+            conn.execute("ROLLBACK", ()).await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Reads a document in the trash.
+async fn trashed_document(
+    conn: &Connection,
+    document_id: &str,
+) -> Result<TrashedDocument, TrashError> {
+    let mut rows = conn
+        .query(
+            &format!("{SELECT_TRASHED_SQL} AND id = ?"),
+            params![document_id],
+        )
+        .await?;
+    let Some(row) = rows.next().await? else {
+        return Err(TrashError::DocumentNotFound(document_id.to_string()));
+    };
+    Ok(TrashedDocument {
+        id: row.get(0)?,
+        owner_id: row.get(1)?,
+        source_url: row.get(2)?,
+        title: row.get(3)?,
+        deleted_at: row.get(4)?,
+    })
+}
+
+/// The condition limiting `documents` to those of `owner_id`, if any.
+fn owner_filter(owner_id: Option<&str>) -> (&'static str, Vec<TursoValue>) {
+    match owner_id {
+        Some(owner_id) => (" AND owner_id = ?", vec![owner_id.into()]),
+        None => ("", Vec::new()),
+    }
+}
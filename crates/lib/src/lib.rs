@@ -9,35 +9,63 @@ pub mod graph;
 pub mod errors;
 pub mod executor;
 
+pub mod answer_cache;
+pub mod chart;
+pub mod chat;
+#[cfg(feature = "cli-config")]
+pub mod cli_config;
+pub mod collections;
 pub mod constants;
+pub mod context_budget;
+pub mod context_sanitization;
 pub mod curator;
+pub mod experiments;
+pub mod feedback;
 pub mod ingest;
+pub mod metrics;
+pub mod moderation;
+pub mod planning;
 pub mod prompts;
 pub mod providers;
+pub mod redaction;
 pub mod rerank;
+pub mod routing;
+pub mod schema_annotations;
 pub mod search;
+pub mod structured_output;
+pub mod summarization;
+pub mod temporal;
 pub mod types;
 
+pub use chat::{ChatClient, ChatError};
+pub use context_budget::ContextBudget;
 pub use errors::PromptError;
 pub use executor::AnyragExecutor;
 pub use rerank::{RerankError, Rerankable};
+pub use routing::{Route, RouteDecision, RouteRetriever};
 pub use search::{SearchError, SearchMode};
 pub use types::{
-    ExecutePromptOptions, HttpRequestPromptOptions, PromptClient, PromptClientBuilder,
-    PromptResult, SearchResult,
+    ChatMessage, ChatRole, ExecutePromptOptions, HttpRequestPromptOptions, PromptClient,
+    PromptClientBuilder, PromptResult, SearchResult,
 };
 
+use crate::chat::format_chat_history;
+use crate::feedback::format_few_shot_examples;
 use crate::prompts::{
     core::{get_alias_instruction, get_select_instruction, QUERY_CONSTRUCTION_RULES},
     tasks::{
         QUERY_GENERATION_SYSTEM_PROMPT, QUERY_GENERATION_USER_PROMPT,
         RESPONSE_FORMATTING_SYSTEM_PROMPT, RESPONSE_FORMATTING_USER_PROMPT,
+        STRUCTURED_OUTPUT_REPAIR_USER_PROMPT, STRUCTURED_OUTPUT_SYSTEM_PROMPT,
+        STRUCTURED_OUTPUT_USER_PROMPT,
     },
 };
-use crate::types::TableSchema;
+use crate::structured_output::parse_structured_output;
+use crate::types::{OutputFormat, PipelineStage, PipelineStep, SchemaAnnotation, TableSchema};
 use chrono::Utc;
-use serde_json::Value;
-use tracing::{error, info};
+use serde_json::{json, Value};
+use std::time::Instant;
+use tracing::{error, info, info_span, instrument, warn, Instrument};
 
 /// Represents the result of a prompt that could be either a query or a direct answer.
 pub enum QueryOrAnswer {
@@ -56,24 +84,60 @@ impl PromptClient {
     /// 2.  It executes the generated query against the configured storage provider.
     /// 3.  It optionally calls the AI provider again to format the raw query results into a
     ///     natural language response, guided by the `instruction`.
+    ///
+    /// With `routing` options, a classification call first decides whether the prompt is
+    /// answered with text-to-SQL, a knowledge search, a graph lookup or directly, and the
+    /// decision is returned in the result's `route`. With `planning` options, a prompt
+    /// answered with text-to-SQL that needs several queries is answered by
+    /// `execute_planned_prompt` instead.
+    ///
+    /// Each stage runs in its own span under a `prompt` span, and is recorded with its
+    /// timing in the result's `trace`.
+    ///
+    /// When the AI provider returns neither a query nor an answer, the prompt fails with
+    /// `PromptError::NoQueryGenerated`, as no result could match the requested output.
+    #[instrument(name = "prompt", skip_all, fields(db = self.storage_provider.name()))]
     pub async fn execute_prompt_with_options(
         &self,
-        options: ExecutePromptOptions,
+        mut options: ExecutePromptOptions,
     ) -> Result<PromptResult, PromptError> {
         info!("[execute_prompt] Starting query generation pipeline.");
-        let (query_or_answer, system_prompt, user_prompt) =
-            self.get_query_from_prompt_internal(&options).await?;
+        let mut trace = Vec::new();
+        let route = match options.routing.take() {
+            Some(routing) => {
+                let mut decision = self.route_prompt(&options, &routing, &mut trace).await?;
+                self.apply_route(&mut options, &routing, &mut decision, &mut trace)
+                    .await?;
+                Some(decision)
+            }
+            None => None,
+        };
+        if let Some(planning) = options.planning.take() {
+            if options.content_type.is_none() && options.table_name.is_some() {
+                if let Some(result) = self
+                    .execute_planned_prompt(&options, &planning, &mut trace)
+                    .await?
+                {
+                    return Ok(PromptResult { route, ..result });
+                }
+            }
+        }
+        let (query_or_answer, system_prompt, user_prompt) = self
+            .get_query_from_prompt_internal(&options, &mut trace)
+            .await?;
 
         match query_or_answer {
             QueryOrAnswer::Query(query) => {
                 if query.trim().is_empty() {
-                    return Ok(PromptResult {
-                        text: "The prompt did not result in a valid query.".to_string(),
-                        ..Default::default()
-                    });
+                    return Err(PromptError::NoQueryGenerated);
                 }
 
-                let database_result = self.storage_provider.execute_query(&query).await;
+                let started = Instant::now();
+                let database_result = self
+                    .storage_provider
+                    .execute_query(&query)
+                    .instrument(info_span!("prompt.execute_query"))
+                    .await;
                 if let Err(e) = &database_result {
                     error!("[execute_prompt] Query execution error: {e:?}");
                 }
@@ -81,8 +145,26 @@ impl PromptClient {
 
                 // Pre-process the JSON to make it more readable for the model.
                 let json_data: serde_json::Value = serde_json::from_str(&database_result)?;
+                trace.push(PipelineStep::finished(
+                    PipelineStage::RowsReturned,
+                    started,
+                    json!({ "rows": json_data.as_array().map(Vec::len) }),
+                ));
                 let pretty_json = serde_json::to_string_pretty(&json_data)?;
-                let final_result = self.format_response(&pretty_json, &options).await?;
+                let final_result = match (options.output, &options.output_schema) {
+                    (Some(OutputFormat::Chart), _) => {
+                        self.format_chart_response(&pretty_json, &options, &mut trace)
+                            .await?
+                    }
+                    (_, Some(schema)) => {
+                        self.format_structured_response(&pretty_json, schema, &options, &mut trace)
+                            .await?
+                    }
+                    _ => {
+                        self.format_response(&pretty_json, &options, &mut trace)
+                            .await?
+                    }
+                };
 
                 Ok(PromptResult {
                     text: final_result,
@@ -90,19 +172,32 @@ impl PromptClient {
                     database_result: Some(database_result),
                     system_prompt: Some(system_prompt),
                     user_prompt: Some(user_prompt),
+                    trace,
+                    route,
+                    ..Default::default()
                 })
             }
             QueryOrAnswer::Answer(answer) => {
                 if answer.trim().is_empty() {
-                    return Ok(PromptResult {
-                        text: "The prompt did not result in a valid query.".to_string(),
-                        ..Default::default()
-                    });
+                    return Err(PromptError::NoQueryGenerated);
                 }
+                let text = match (options.output, &options.output_schema) {
+                    (Some(OutputFormat::Chart), _) => {
+                        self.format_chart_response(&answer, &options, &mut trace)
+                            .await?
+                    }
+                    (_, Some(schema)) => {
+                        self.format_structured_response(&answer, schema, &options, &mut trace)
+                            .await?
+                    }
+                    _ => answer,
+                };
                 Ok(PromptResult {
-                    text: answer,
+                    text,
                     system_prompt: Some(system_prompt),
                     user_prompt: Some(user_prompt),
+                    trace,
+                    route,
                     ..Default::default()
                 })
             }
@@ -147,13 +242,16 @@ impl PromptClient {
         &self,
         options: &ExecutePromptOptions,
     ) -> Result<PromptResult, PromptError> {
-        let (query_or_answer, system_prompt, user_prompt) =
-            self.get_query_from_prompt_internal(options).await?;
+        let mut trace = Vec::new();
+        let (query_or_answer, system_prompt, user_prompt) = self
+            .get_query_from_prompt_internal(options, &mut trace)
+            .await?;
         match query_or_answer {
             QueryOrAnswer::Query(q) => Ok(PromptResult {
                 text: q,
                 system_prompt: Some(system_prompt),
                 user_prompt: Some(user_prompt),
+                trace,
                 ..Default::default()
             }),
             // For backward compatibility and simple testing, return empty string for non-queries.
@@ -161,16 +259,18 @@ impl PromptClient {
                 text: answer,
                 system_prompt: Some(system_prompt),
                 user_prompt: Some(user_prompt),
+                trace,
                 ..Default::default()
             }),
         }
     }
 
-    /// Internal version of `get_query_from_prompt` that distinguishes between queries and direct answers.
-    async fn get_query_from_prompt_internal(
+    /// Renders the system and user prompts sent to the AI provider for a prompt,
+    /// with the date, history, few-shot examples, and schema or content context.
+    pub async fn build_prompts(
         &self,
         options: &ExecutePromptOptions,
-    ) -> Result<(QueryOrAnswer, String, String), PromptError> {
+    ) -> Result<(String, String), PromptError> {
         info!(
             "[get_query_from_prompt] received prompt: {:?}",
             options.prompt
@@ -181,6 +281,22 @@ impl PromptClient {
         let today_iso8601 = now.to_rfc3339();
 
         let mut context = format!("# TODAY\nRFC2822: {today_rfc2822}\nUTC: {today_iso8601}\n\n");
+        if let Some(history) = options.history.as_deref().filter(|h| !h.is_empty()) {
+            context.push_str(&format!(
+                "# CONVERSATION HISTORY\n{}\n\n",
+                format_chat_history(history)
+            ));
+        }
+        if let Some(examples) = options
+            .few_shot_examples
+            .as_deref()
+            .filter(|e| !e.is_empty())
+        {
+            context.push_str(&format!(
+                "# EXAMPLES OF ACCEPTED ANSWERS\n{}\n\n",
+                format_few_shot_examples(examples)
+            ));
+        }
         let language = self.storage_provider.language();
 
         let alias_instruction = get_alias_instruction(options.answer_key.as_deref());
@@ -239,11 +355,37 @@ impl PromptClient {
             // --- Logic for Query Generation ---
             info!("[get_query_from_prompt] Using table-based query generation.");
 
-            // If a specific table is named, get its schema.
+            let annotations = options.schema_annotations.as_deref().unwrap_or_default();
+
+            // If a specific table is named, get its schema, and the schemas of the tables
+            // it can be joined with.
             if let Some(table) = options.table_name.as_deref().filter(|s| !s.is_empty()) {
                 let schema = self.storage_provider.get_table_schema(table).await?;
-                let schema_str = Self::format_schema_for_prompt(&schema);
+                let schema_str = Self::format_schema_for_prompt(table, &schema, annotations);
                 context.push_str(&format!("# Schema for `{table}`\n{schema_str}\n\n"));
+
+                let related_tables = Self::related_tables(
+                    table,
+                    &schema,
+                    annotations,
+                    options.related_tables.as_deref(),
+                );
+                for related in related_tables {
+                    match self.storage_provider.get_table_schema(&related).await {
+                        Ok(schema) => {
+                            let schema_str =
+                                Self::format_schema_for_prompt(&related, &schema, annotations);
+                            context
+                                .push_str(&format!("# Schema for `{related}`\n{schema_str}\n\n"));
+                        }
+                        Err(e) => {
+                            warn!(
+                                "[get_query_from_prompt] Failed to get schema for related table '{}': {}",
+                                related, e
+                            );
+                        }
+                    }
+                }
             } else {
                 // If no specific table is named, but a DB is context, get all table schemas.
                 info!("[get_query_from_prompt] No table_name provided; fetching all schemas for the current DB.");
@@ -253,7 +395,8 @@ impl PromptClient {
                     // We'll log the error but continue, so the AI gets as much context as possible.
                     match self.storage_provider.get_table_schema(&table).await {
                         Ok(schema) => {
-                            let schema_str = Self::format_schema_for_prompt(&schema);
+                            let schema_str =
+                                Self::format_schema_for_prompt(&table, &schema, annotations);
                             context.push_str(&format!("# Schema for `{table}`\n{schema_str}\n\n"));
                         }
                         Err(e) => {
@@ -316,15 +459,40 @@ impl PromptClient {
             };
             (system_prompt, user_prompt)
         };
+        Ok((system_prompt, user_prompt))
+    }
+
+    /// Internal version of `get_query_from_prompt` that distinguishes between queries and direct answers.
+    /// The prompts, the AI response and the generated query are recorded in `trace`.
+    #[instrument(name = "prompt.generate_query", skip_all)]
+    async fn get_query_from_prompt_internal(
+        &self,
+        options: &ExecutePromptOptions,
+        trace: &mut Vec<PipelineStep>,
+    ) -> Result<(QueryOrAnswer, String, String), PromptError> {
+        let started = Instant::now();
+        let (system_prompt, user_prompt) = self.build_prompts(options).await?;
+        trace.push(PipelineStep::finished(
+            PipelineStage::PromptSent,
+            started,
+            json!({ "system_prompt": system_prompt, "user_prompt": user_prompt }),
+        ));
 
         info!(system_prompt = %system_prompt, user_prompt = %user_prompt, "--> Sending prompts to AI Provider");
 
+        let started = Instant::now();
         let raw_response = self
             .ai_provider
             .generate(&system_prompt, &user_prompt)
             .await?;
+        trace.push(PipelineStep::finished(
+            PipelineStage::AiResponse,
+            started,
+            Value::String(raw_response.clone()),
+        ));
 
         info!("<-- Raw response from AI: {}", &raw_response);
+        let started = Instant::now();
 
         // --- FINAL, ROBUST LOGIC ---
         // This logic robustly handles AI responses that are either raw SQL
@@ -371,44 +539,138 @@ impl PromptClient {
             query = query.replace("`your_table_name`", &format!("`{table}`"));
             query = query.replace("your_table_name", table);
         }
+        trace.push(PipelineStep::finished(
+            PipelineStage::GeneratedSql,
+            started,
+            Value::String(query.clone()),
+        ));
 
         Ok((QueryOrAnswer::Query(query), system_prompt, user_prompt))
     }
 
+    /// Lists the tables whose schemas are shown along with `table`'s: the `requested`
+    /// ones, then those `table` references through its foreign keys or the `references`
+    /// of its column annotations.
+    fn related_tables(
+        table: &str,
+        schema: &TableSchema,
+        annotations: &[SchemaAnnotation],
+        requested: Option<&[String]>,
+    ) -> Vec<String> {
+        let referenced = schema
+            .foreign_keys
+            .iter()
+            .map(|fk| fk.referenced_table.clone())
+            .chain(
+                annotations
+                    .iter()
+                    .filter(|a| a.table_name == table)
+                    .filter_map(|a| a.references.as_deref())
+                    .filter_map(|r| r.split_once('.').map(|(t, _)| t.to_string())),
+            );
+        let mut related: Vec<String> = Vec::new();
+        for candidate in requested
+            .unwrap_or_default()
+            .iter()
+            .cloned()
+            .chain(referenced)
+        {
+            if !candidate.is_empty() && candidate != table && !related.contains(&candidate) {
+                related.push(candidate);
+            }
+        }
+        related
+    }
+
     /// Formats a `TableSchema` into a markdown-like string for the AI prompt.
-    fn format_schema_for_prompt(schema: &TableSchema) -> String {
+    ///
+    /// Annotations for the table and its columns are merged in, so the model sees what
+    /// each column means and what its values look like instead of guessing from names.
+    /// The columns that reference other tables are marked, so the model can join them.
+    fn format_schema_for_prompt(
+        table: &str,
+        schema: &TableSchema,
+        annotations: &[SchemaAnnotation],
+    ) -> String {
         // This regex is static and simple, so unwrap is safe.
         let simple_identifier_re = regex::Regex::new(r"^[a-zA-Z0-9_]+$").unwrap();
-        schema
-            .fields
+        let table_annotations: Vec<&SchemaAnnotation> = annotations
             .iter()
-            .map(|field| {
-                // If a column name contains special characters (like spaces, parens, or CJK chars),
-                // wrap it in backticks to teach the AI the correct quoting syntax.
-                let field_name = if simple_identifier_re.is_match(&field.name) {
-                    field.name.clone()
-                } else {
-                    format!("`{}`", field.name)
-                };
+            .filter(|a| a.table_name == table)
+            .collect();
 
-                let mut field_str =
-                    format!("- {field_name}: {field_type:?}", field_type = field.r#type);
-                if let Some(desc) = &field.description {
-                    if !desc.is_empty() {
-                        field_str.push_str(&format!(" ({desc})"));
-                    }
-                }
-                field_str
-            })
-            .collect::<Vec<String>>()
-            .join("\n")
+        let mut lines = Vec::new();
+        if let Some(desc) = table_annotations
+            .iter()
+            .find(|a| a.column_name.is_none())
+            .and_then(|a| a.description.as_deref())
+            .filter(|d| !d.is_empty())
+        {
+            lines.push(format!("Description: {desc}"));
+        }
+
+        for field in &schema.fields {
+            // If a column name contains special characters (like spaces, parens, or CJK chars),
+            // wrap it in backticks to teach the AI the correct quoting syntax.
+            let field_name = if simple_identifier_re.is_match(&field.name) {
+                field.name.clone()
+            } else {
+                format!("`{}`", field.name)
+            };
+            let annotation = table_annotations
+                .iter()
+                .find(|a| a.column_name.as_deref() == Some(field.name.as_str()));
+
+            let mut notes: Vec<String> = [
+                field.description.as_deref(),
+                annotation.and_then(|a| a.description.as_deref()),
+            ]
+            .into_iter()
+            .flatten()
+            .filter(|d| !d.is_empty())
+            .map(str::to_string)
+            .collect();
+            if let Some(a) = annotation.filter(|a| !a.examples.is_empty()) {
+                let examples = a
+                    .examples
+                    .iter()
+                    .map(|e| format!("'{e}'"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                notes.push(format!("e.g. {examples}"));
+            }
+            let foreign_key = schema
+                .foreign_keys
+                .iter()
+                .find(|fk| fk.column == field.name);
+            if let Some(reference) = foreign_key
+                .map(|fk| match &fk.referenced_column {
+                    Some(column) => format!("{}.{column}", fk.referenced_table),
+                    None => fk.referenced_table.clone(),
+                })
+                .or_else(|| annotation.and_then(|a| a.references.clone()))
+            {
+                notes.push(format!("references {reference}"));
+            }
+
+            let mut field_str =
+                format!("- {field_name}: {field_type:?}", field_type = field.r#type);
+            if !notes.is_empty() {
+                field_str.push_str(&format!(" ({})", notes.join("; ")));
+            }
+            lines.push(field_str);
+        }
+
+        lines.join("\n")
     }
 
     /// Formats the raw query result using the AI provider if an instruction is given.
+    #[instrument(name = "prompt.format_response", skip_all)]
     async fn format_response(
         &self,
         content: &str,
         options: &ExecutePromptOptions,
+        trace: &mut Vec<PipelineStep>,
     ) -> Result<String, PromptError> {
         let instruction = match &options.instruction {
             Some(inst) => inst,
@@ -436,8 +698,79 @@ impl PromptClient {
 
         info!(system_prompt = %system_prompt, user_prompt = %user_prompt, "--> Sending prompts to AI Provider for formatting");
 
-        self.ai_provider
+        let started = Instant::now();
+        let response = self
+            .ai_provider
             .generate(&system_prompt, &user_prompt)
-            .await
+            .await?;
+        trace.push(PipelineStep::finished(
+            PipelineStage::FormattingPrompt,
+            started,
+            json!({ "system_prompt": system_prompt, "user_prompt": user_prompt, "response": response }),
+        ));
+        Ok(response)
+    }
+
+    /// Formats content as JSON conforming to `schema` using the AI provider.
+    ///
+    /// The response is validated against the schema. If it does not conform, the model
+    /// is asked once to repair it using the validation errors before giving up.
+    #[instrument(name = "prompt.format_structured_response", skip_all)]
+    async fn format_structured_response(
+        &self,
+        content: &str,
+        schema: &Value,
+        options: &ExecutePromptOptions,
+        trace: &mut Vec<PipelineStep>,
+    ) -> Result<String, PromptError> {
+        let schema_str = serde_json::to_string_pretty(schema)?;
+        let instruction = options
+            .instruction
+            .as_deref()
+            .unwrap_or("Answer the prompt using the input data.");
+
+        let system_prompt = STRUCTURED_OUTPUT_SYSTEM_PROMPT;
+        let user_prompt = STRUCTURED_OUTPUT_USER_PROMPT
+            .replace("{prompt}", &options.prompt)
+            .replace("{instruction}", instruction)
+            .replace("{schema}", &schema_str)
+            .replace("{content}", content);
+
+        info!(system_prompt = %system_prompt, user_prompt = %user_prompt, "--> Sending prompts to AI Provider for structured formatting");
+        let started = Instant::now();
+        let response = self
+            .ai_provider
+            .generate(system_prompt, &user_prompt)
+            .await?;
+        trace.push(PipelineStep::finished(
+            PipelineStage::FormattingPrompt,
+            started,
+            json!({ "system_prompt": system_prompt, "user_prompt": user_prompt, "response": response }),
+        ));
+
+        let errors = match parse_structured_output(&response, schema) {
+            Ok(value) => return Ok(serde_json::to_string(&value)?),
+            Err(errors) => errors,
+        };
+
+        warn!("[format_structured_response] Output does not conform to schema, retrying once: {errors:?}");
+        let repair_prompt = STRUCTURED_OUTPUT_REPAIR_USER_PROMPT
+            .replace("{schema}", &schema_str)
+            .replace("{output}", &response)
+            .replace("{errors}", &errors.join("\n"));
+        let started = Instant::now();
+        let repaired = self
+            .ai_provider
+            .generate(system_prompt, &repair_prompt)
+            .await?;
+        trace.push(PipelineStep::finished(
+            PipelineStage::FormattingPrompt,
+            started,
+            json!({ "system_prompt": system_prompt, "user_prompt": repair_prompt, "response": repaired }),
+        ));
+
+        let value = parse_structured_output(&repaired, schema)
+            .map_err(|errors| PromptError::OutputSchemaViolation(errors.join("; ")))?;
+        Ok(serde_json::to_string(&value)?)
     }
 }
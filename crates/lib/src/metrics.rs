@@ -0,0 +1,29 @@
+//! # Metrics
+//!
+//! The names and labels of the metrics the library records through the `metrics`
+//! facade. Nothing is exported unless the application installs a recorder, as the
+//! server does for its Prometheus endpoint.
+
+/// Requests to AI providers, labelled by `provider` and `status`.
+pub const AI_REQUESTS_TOTAL: &str = "anyrag_ai_requests_total";
+/// The latency of AI provider requests, labelled by `provider`.
+pub const AI_REQUEST_DURATION_SECONDS: &str = "anyrag_ai_request_duration_seconds";
+/// Requests to embedding APIs, labelled by `model` and `status`.
+pub const EMBEDDING_REQUESTS_TOTAL: &str = "anyrag_embedding_requests_total";
+/// The texts embedded successfully, labelled by `model`.
+pub const EMBEDDING_INPUTS_TOTAL: &str = "anyrag_embedding_inputs_total";
+/// The latency of embedding API requests, labelled by `model`.
+pub const EMBEDDING_REQUEST_DURATION_SECONDS: &str = "anyrag_embedding_request_duration_seconds";
+
+/// The `status` label of a successful call.
+pub const STATUS_OK: &str = "ok";
+/// The `status` label of a failed call.
+pub const STATUS_ERROR: &str = "error";
+
+/// Returns the `status` label of a call's result.
+pub fn status_label<T, E>(result: &Result<T, E>) -> &'static str {
+    match result {
+        Ok(_) => STATUS_OK,
+        Err(_) => STATUS_ERROR,
+    }
+}
@@ -0,0 +1,125 @@
+//! # Content Moderation
+//!
+//! A public-facing chatbot must not return whatever the model writes. With a
+//! `moderation` configuration, final answers (and optionally ingested documents) are
+//! checked by a `ModerationProvider`, and the deployment's policy decides what happens
+//! to the flagged ones: they are blocked and replaced with a fixed message, returned
+//! with the verdict attached, or only logged.
+
+use crate::providers::moderation::{ModerationProvider, ModerationVerdict};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// The answer returned in place of a blocked one, when none is configured.
+const DEFAULT_BLOCKED_MESSAGE: &str = "Sorry, I can't help with that.";
+
+/// The moderation provider to check texts with.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ModerationProviderConfig {
+    /// OpenAI's moderation endpoint, or an API compatible with it.
+    #[serde(rename = "openai")]
+    OpenAi {
+        api_key: String,
+        #[serde(default)]
+        api_url: Option<String>,
+        #[serde(default)]
+        model: Option<String>,
+    },
+    /// A classifier model prompted with the `content_moderation` task.
+    Classifier,
+}
+
+/// What is done with a flagged text.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationAction {
+    /// The text is replaced with the blocked message, or an ingested document deleted.
+    /// A text that cannot be checked is blocked too.
+    #[default]
+    Block,
+    /// The text is kept, and the verdict is returned with it or recorded as metadata.
+    Flag,
+    /// The text is kept, and the verdict is only logged.
+    Log,
+}
+
+/// Configuration for moderating answers and ingested documents.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ModerationConfig {
+    pub provider: ModerationProviderConfig,
+    #[serde(default)]
+    pub action: ModerationAction,
+    /// The answer returned in place of a blocked one.
+    #[serde(default = "default_blocked_message")]
+    pub blocked_message: String,
+    /// Whether ingested documents are moderated too.
+    #[serde(default)]
+    pub moderate_ingested: bool,
+}
+
+fn default_blocked_message() -> String {
+    DEFAULT_BLOCKED_MESSAGE.to_string()
+}
+
+/// The outcome of moderating a text.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ModerationReport {
+    pub flagged: bool,
+    pub categories: Vec<String>,
+    pub action: ModerationAction,
+    /// Whether the text was withheld.
+    pub blocked: bool,
+    /// Why the text could not be checked, if it could not.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ModerationReport {
+    /// Whether the report is returned to the caller with the answer. Logged verdicts
+    /// are only shown in debug output.
+    pub fn is_reported(&self) -> bool {
+        (self.flagged || self.blocked) && self.action != ModerationAction::Log
+    }
+}
+
+/// Checks a text with the provider and applies the policy of `config` to it.
+///
+/// Returns the text to pass on, which is the blocked message when the text is
+/// blocked, and the report of what was done. When the provider fails, the text is
+/// blocked if the action is `block`, and passed on otherwise.
+pub async fn moderate_text(
+    provider: &dyn ModerationProvider,
+    config: &ModerationConfig,
+    text: String,
+) -> (String, ModerationReport) {
+    let (verdict, error) = match provider.moderate(&text).await {
+        Ok(verdict) => (verdict, None),
+        Err(e) => {
+            warn!("Moderation failed: {e}");
+            (ModerationVerdict::default(), Some(e.to_string()))
+        }
+    };
+    let blocked = config.action == ModerationAction::Block && (verdict.flagged || error.is_some());
+    if verdict.flagged {
+        warn!(
+            categories = ?verdict.categories,
+            action = ?config.action,
+            "Moderation flagged a text."
+        );
+    }
+    let report = ModerationReport {
+        flagged: verdict.flagged,
+        categories: verdict.categories,
+        action: config.action,
+        blocked,
+        error,
+    };
+    if blocked {
+        (config.blocked_message.clone(), report)
+    } else {
+        (text, report)
+    }
+}
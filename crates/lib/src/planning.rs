@@ -0,0 +1,222 @@
+//! # Query Planning
+//!
+//! Single-shot text-to-SQL fails on questions that need several queries, such as
+//! "compare Q1 vs Q2 revenue and summarize the drivers". A planner call decomposes such
+//! a prompt into sub-questions, each is turned into a query and run against the storage
+//! provider in turn, and the final answer is written from all of their results.
+
+use crate::ingest::knowledge::clean_llm_response;
+use crate::types::{ExecutePromptOptions, OutputFormat, PipelineStage, PipelineStep, PromptResult};
+use crate::{PromptClient, PromptError, QueryOrAnswer};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Instant;
+use tracing::{info, info_span, instrument, warn, Instrument};
+
+/// The instruction the results of a plan are formatted into an answer with, when the
+/// caller gives none.
+const DEFAULT_PLAN_ANSWER_INSTRUCTION: &str =
+    "Answer the prompt using the results of every step, comparing them where the prompt asks to.";
+
+/// Configuration for planning prompts that need several queries.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PlanningConfig {
+    /// The most sub-queries a prompt is decomposed into.
+    #[serde(default = "default_max_plan_steps")]
+    pub max_steps: usize,
+}
+
+impl Default for PlanningConfig {
+    fn default() -> Self {
+        Self {
+            max_steps: default_max_plan_steps(),
+        }
+    }
+}
+
+fn default_max_plan_steps() -> usize {
+    4
+}
+
+/// The planning stage of a prompt execution.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PlanningOptions {
+    /// The system prompt of the planner call.
+    pub system_prompt: String,
+    /// The user prompt of the planner call, with `{prompt}` and `{max_steps}`
+    /// placeholders.
+    pub user_prompt_template: String,
+    /// The most sub-queries a prompt is decomposed into.
+    pub max_steps: usize,
+}
+
+/// A step of an executed plan.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PlanStepResult {
+    /// The sub-question the step answers.
+    pub question: String,
+    /// The query generated for the sub-question. `None` when the model answered it
+    /// directly.
+    #[serde(default)]
+    pub query: Option<String>,
+    /// The rows the query returned, or the model's direct answer.
+    pub result: Value,
+}
+
+#[derive(Deserialize)]
+struct GeneratedPlan {
+    #[serde(default)]
+    steps: Vec<String>,
+}
+
+/// Parses the planner's response into at most `max_steps` sub-questions. Returns `None`
+/// when it cannot be read.
+pub fn parse_query_plan(response: &str, max_steps: usize) -> Option<Vec<String>> {
+    let plan: GeneratedPlan = match serde_json::from_str(&clean_llm_response(response)) {
+        Ok(plan) => plan,
+        Err(e) => {
+            warn!("Failed to parse the query plan: {e}");
+            return None;
+        }
+    };
+    Some(
+        plan.steps
+            .into_iter()
+            .map(|step| step.trim().to_string())
+            .filter(|step| !step.is_empty())
+            .take(max_steps)
+            .collect(),
+    )
+}
+
+impl PromptClient {
+    /// Answers a prompt with several queries.
+    ///
+    /// 1.  It calls the AI provider to decompose the prompt into sub-questions.
+    /// 2.  It generates a query for each sub-question and runs it against the storage
+    ///     provider, one after the other.
+    /// 3.  It calls the AI provider again to answer the prompt from all of the results.
+    ///
+    /// The plan and the result of each step are recorded in `trace`. Returns `None`
+    /// when the planner finds the prompt needs a single query, so the caller can answer
+    /// it in one shot.
+    #[instrument(name = "prompt.plan", skip_all)]
+    pub(crate) async fn execute_planned_prompt(
+        &self,
+        options: &ExecutePromptOptions,
+        planning: &PlanningOptions,
+        trace: &mut Vec<PipelineStep>,
+    ) -> Result<Option<PromptResult>, PromptError> {
+        let started = Instant::now();
+        let user_prompt = planning
+            .user_prompt_template
+            .replace("{prompt}", &options.prompt)
+            .replace("{max_steps}", &planning.max_steps.to_string());
+        let response = self
+            .ai_provider
+            .generate(&planning.system_prompt, &user_prompt)
+            .await?;
+        let steps = parse_query_plan(&response, planning.max_steps).unwrap_or_default();
+        trace.push(PipelineStep::finished(
+            PipelineStage::QueryPlanned,
+            started,
+            json!({ "steps": steps }),
+        ));
+        if steps.len() < 2 {
+            info!("[execute_planned_prompt] The prompt needs a single query.");
+            return Ok(None);
+        }
+        info!(
+            "[execute_planned_prompt] Planned {} sub-queries.",
+            steps.len()
+        );
+
+        let mut results = Vec::with_capacity(steps.len());
+        for question in steps {
+            let step_options = ExecutePromptOptions {
+                prompt: question.clone(),
+                instruction: None,
+                answer_key: None,
+                output_schema: None,
+                output: None,
+                ..options.clone()
+            };
+            let (query_or_answer, _, _) = self
+                .get_query_from_prompt_internal(&step_options, trace)
+                .await?;
+            let started = Instant::now();
+            let step = match query_or_answer {
+                QueryOrAnswer::Query(query) => {
+                    let rows = self
+                        .storage_provider
+                        .execute_query(&query)
+                        .instrument(info_span!("prompt.execute_query"))
+                        .await?;
+                    PlanStepResult {
+                        question,
+                        query: Some(query),
+                        result: serde_json::from_str(&rows)?,
+                    }
+                }
+                QueryOrAnswer::Answer(answer) => PlanStepResult {
+                    question,
+                    query: None,
+                    result: Value::String(answer),
+                },
+            };
+            trace.push(PipelineStep::finished(
+                PipelineStage::PlanStepExecuted,
+                started,
+                json!(step),
+            ));
+            results.push(step);
+        }
+
+        let content = results
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                let query = step
+                    .query
+                    .as_deref()
+                    .map(|q| format!("```sql\n{q}\n```\n"))
+                    .unwrap_or_default();
+                let result = serde_json::to_string_pretty(&step.result).unwrap_or_default();
+                format!("## Step {}: {}\n{query}{result}", i + 1, step.question)
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let format_options = ExecutePromptOptions {
+            instruction: Some(
+                options
+                    .instruction
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_PLAN_ANSWER_INSTRUCTION.to_string()),
+            ),
+            ..options.clone()
+        };
+        let text = match (options.output, &options.output_schema) {
+            (Some(OutputFormat::Chart), _) => {
+                self.format_chart_response(&content, &format_options, trace)
+                    .await?
+            }
+            (_, Some(schema)) => {
+                self.format_structured_response(&content, schema, &format_options, trace)
+                    .await?
+            }
+            _ => {
+                self.format_response(&content, &format_options, trace)
+                    .await?
+            }
+        };
+
+        let queries: Vec<&str> = results.iter().filter_map(|s| s.query.as_deref()).collect();
+        Ok(Some(PromptResult {
+            text,
+            generated_sql: (!queries.is_empty()).then(|| queries.join(";\n\n")),
+            database_result: Some(serde_json::to_string(&results)?),
+            trace: std::mem::take(trace),
+            ..Default::default()
+        }))
+    }
+}
@@ -118,6 +118,36 @@ sections:
 ```
 "#;
 
+/// The system prompt for repairing a restructured document that is not valid YAML of
+/// the restructuring schema.
+pub const KNOWLEDGE_REPAIR_SYSTEM_PROMPT: &str = r#"You are a meticulous YAML editor. You are given a YAML document that was meant to follow the schema below, and the error found when it was checked.
+
+# Instructions
+1.  Fix the document so it is valid YAML that follows the schema exactly.
+2.  Keep all of its content. Do NOT add, remove, translate or summarize any question, answer or title.
+3.  Quote strings that contain a colon, and use `|` block scalars for multi-line answers.
+4.  **Crucial Output Rule**: Your response MUST be the corrected YAML document only. Do not include any other text, explanations, or markdown code fences like ` ```yaml `.
+
+# YAML Schema
+```yaml
+sections:
+  - title: "The title of the section"
+    faqs:
+      - question: "A question from the section."
+        answer: |
+          The answer to the question.
+```
+"#;
+
+/// The user prompt for repairing a restructured document.
+/// Placeholders: {error}, {yaml}
+pub const KNOWLEDGE_REPAIR_USER_PROMPT: &str = r#"# Error
+{error}
+
+# Document to Fix
+{yaml}
+"#;
+
 // --- Metadata Extraction ---
 
 /// System prompt for extracting structured metadata (Entities and Keyphrases) from content.
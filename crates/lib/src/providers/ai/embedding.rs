@@ -3,10 +3,12 @@
 //! This module provides functionality for generating vector embeddings by calling
 //! an external, OpenAI-compatible embeddings API.
 
-use crate::errors::PromptError;
+use crate::{errors::PromptError, metrics};
+use ::metrics::{counter, histogram};
 use reqwest::Client as ReqwestClient;
 use serde::{Deserialize, Serialize};
-use tracing::debug;
+use std::time::Instant;
+use tracing::{debug, instrument};
 
 // --- OpenAI-compatible request and response structures ---
 
@@ -62,7 +64,9 @@ struct GeminiEmbeddingValue {
 /// Generates vector embeddings for a given batch of text inputs using an external API.
 ///
 /// This function dynamically constructs the correct JSON payload based on whether
-/// the `api_url` is for a Gemini or an OpenAI-compatible endpoint.
+/// the `api_url` is for a Gemini or an OpenAI-compatible endpoint. Every request is
+/// recorded in the embedding metrics.
+#[instrument(name = "embedding.batch", skip_all, fields(model = %model, inputs = inputs.len()))]
 pub async fn generate_embeddings_batch(
     api_url: &str,
     model: &str,
@@ -73,6 +77,26 @@ pub async fn generate_embeddings_batch(
         return Ok(Vec::new());
     }
 
+    let started = Instant::now();
+    let result = request_embeddings_batch(api_url, model, inputs, api_key).await;
+    let status = metrics::status_label(&result);
+    histogram!(metrics::EMBEDDING_REQUEST_DURATION_SECONDS, "model" => model.to_string())
+        .record(started.elapsed().as_secs_f64());
+    counter!(metrics::EMBEDDING_REQUESTS_TOTAL, "model" => model.to_string(), "status" => status)
+        .increment(1);
+    if result.is_ok() {
+        counter!(metrics::EMBEDDING_INPUTS_TOTAL, "model" => model.to_string())
+            .increment(inputs.len() as u64);
+    }
+    result
+}
+
+async fn request_embeddings_batch(
+    api_url: &str,
+    model: &str,
+    inputs: &[&str],
+    api_key: Option<&str>,
+) -> Result<Vec<Vec<f32>>, PromptError> {
     let client = ReqwestClient::new();
     // The Gemini batch endpoint is different.
     let final_api_url = if api_url.ends_with(":embedContent") {
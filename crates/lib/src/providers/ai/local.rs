@@ -1,9 +1,10 @@
 use crate::{errors::PromptError, providers::ai::AiProvider};
 use async_trait::async_trait;
-use reqwest::Client as ReqwestClient;
+use reqwest::{header::CONTENT_TYPE, Client as ReqwestClient};
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::fmt::Debug;
+use tokio::sync::mpsc::UnboundedSender;
 use tracing::{debug, info};
 
 // --- OpenAI-compatible request and response structures ---
@@ -24,6 +25,36 @@ struct LocalAiMessage {
     content: String,
 }
 
+/// A completion request whose user message holds an image, for vision models.
+#[derive(Serialize, Debug)]
+struct LocalAiImageRequest<'a> {
+    messages: Vec<LocalAiImageMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<&'a str>,
+    temperature: f32,
+    max_tokens: i32,
+    stream: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct LocalAiImageMessage {
+    role: &'static str,
+    content: Vec<LocalAiContentPart>,
+}
+
+/// A part of a message's content in the OpenAI chat format.
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum LocalAiContentPart {
+    Text { text: String },
+    ImageUrl { image_url: LocalAiImageUrl },
+}
+
+#[derive(Serialize, Debug)]
+struct LocalAiImageUrl {
+    url: String,
+}
+
 #[derive(Deserialize, Debug)]
 struct LocalAiResponse {
     choices: Vec<LocalAiChoice>,
@@ -34,6 +65,31 @@ struct LocalAiChoice {
     message: LocalAiMessage,
 }
 
+/// One server-sent event of a streamed completion.
+#[derive(Deserialize, Debug)]
+struct LocalAiStreamChunk {
+    choices: Vec<LocalAiStreamChoice>,
+}
+
+#[derive(Deserialize, Debug)]
+struct LocalAiStreamChoice {
+    #[serde(default)]
+    delta: LocalAiDelta,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct LocalAiDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// The content type of a streamed completion.
+const EVENT_STREAM_CONTENT_TYPE: &str = "text/event-stream";
+/// The prefix of the data lines of a server-sent event.
+const EVENT_DATA_PREFIX: &str = "data:";
+/// The data of the event that ends a streamed completion.
+const STREAM_DONE: &str = "[DONE]";
+
 // --- Local Provider implementation ---
 
 /// A provider for interacting with a local or OpenAI-compatible API.
@@ -64,6 +120,29 @@ impl LocalAiProvider {
     }
 }
 
+/// Builds the messages of a single-turn completion.
+fn chat_messages(system_prompt: &str, user_prompt: &str) -> Vec<LocalAiMessage> {
+    vec![
+        LocalAiMessage {
+            role: "system".to_string(),
+            content: system_prompt.to_string(),
+        },
+        LocalAiMessage {
+            role: "user".to_string(),
+            content: user_prompt.to_string(),
+        },
+    ]
+}
+
+/// Returns the content of the first choice of a complete (non-streamed) response.
+fn first_choice_content(response: LocalAiResponse) -> String {
+    response
+        .choices
+        .first()
+        .map(|c| c.message.content.clone())
+        .unwrap_or_default()
+}
+
 #[async_trait]
 impl AiProvider for LocalAiProvider {
     /// Generates a response from a given system and user prompt.
@@ -72,19 +151,8 @@ impl AiProvider for LocalAiProvider {
         system_prompt: &str,
         user_prompt: &str,
     ) -> Result<String, PromptError> {
-        let messages = vec![
-            LocalAiMessage {
-                role: "system".to_string(),
-                content: system_prompt.to_string(),
-            },
-            LocalAiMessage {
-                role: "user".to_string(),
-                content: user_prompt.to_string(),
-            },
-        ];
-
         let request_body = LocalAiRequest {
-            messages,
+            messages: chat_messages(system_prompt, user_prompt),
             model: self.model.as_deref(),
             temperature: 0.0,
             max_tokens: 8192,
@@ -120,12 +188,154 @@ impl AiProvider for LocalAiProvider {
             .await
             .map_err(PromptError::AiDeserialization)?;
 
-        let raw_response = local_ai_response
-            .choices
-            .first()
-            .map(|c| c.message.content.clone())
-            .unwrap_or_default();
+        Ok(first_choice_content(local_ai_response))
+    }
+
+    /// Sends the image as an `image_url` part of the user message, which
+    /// OpenAI-compatible vision models accept.
+    async fn describe_image(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        image_url: &str,
+    ) -> Result<String, PromptError> {
+        let text = |text: &str| LocalAiContentPart::Text {
+            text: text.to_string(),
+        };
+        let request_body = LocalAiImageRequest {
+            messages: vec![
+                LocalAiImageMessage {
+                    role: "system",
+                    content: vec![text(system_prompt)],
+                },
+                LocalAiImageMessage {
+                    role: "user",
+                    content: vec![
+                        text(user_prompt),
+                        LocalAiContentPart::ImageUrl {
+                            image_url: LocalAiImageUrl {
+                                url: image_url.to_string(),
+                            },
+                        },
+                    ],
+                },
+            ],
+            model: self.model.as_deref(),
+            temperature: 0.0,
+            max_tokens: 1024,
+            stream: false,
+        };
+
+        debug!(payload = ?request_body, "--> Sending image request to Local AI");
+        let mut request_builder = self.client.post(&self.api_url);
+        if let Some(key) = &self.api_key {
+            request_builder = request_builder.bearer_auth(key);
+        }
+
+        let response = request_builder
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(PromptError::AiRequest)?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(PromptError::AiApi(error_text));
+        }
+
+        let local_ai_response: LocalAiResponse = response
+            .json()
+            .await
+            .map_err(PromptError::AiDeserialization)?;
+
+        Ok(first_choice_content(local_ai_response))
+    }
+
+    /// Streams the response from the server-sent events of an OpenAI-compatible API.
+    /// Servers that ignore `stream` and answer at once are handled too.
+    async fn generate_stream(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        tokens: UnboundedSender<String>,
+    ) -> Result<String, PromptError> {
+        let request_body = LocalAiRequest {
+            messages: chat_messages(system_prompt, user_prompt),
+            model: self.model.as_deref(),
+            temperature: 0.0,
+            max_tokens: 8192,
+            stream: true,
+        };
+
+        debug!(payload = ?request_body, "--> Sending streaming request to Local AI");
+        info!(
+            "--> Local AI Provider streaming from API URL: {}",
+            self.api_url
+        );
+        let mut request_builder = self.client.post(&self.api_url);
+
+        if let Some(key) = &self.api_key {
+            request_builder = request_builder.bearer_auth(key);
+        }
+
+        let mut response = request_builder
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(PromptError::AiRequest)?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(PromptError::AiApi(error_text));
+        }
+
+        let is_event_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with(EVENT_STREAM_CONTENT_TYPE));
+        if !is_event_stream {
+            let local_ai_response: LocalAiResponse = response
+                .json()
+                .await
+                .map_err(PromptError::AiDeserialization)?;
+            let text = first_choice_content(local_ai_response);
+            let _ = tokens.send(text.clone());
+            return Ok(text);
+        }
+
+        // Events may be split across network chunks, so bytes are buffered until a
+        // full line (and a full UTF-8 sequence) has arrived.
+        let mut text = String::new();
+        let mut buffer: Vec<u8> = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(PromptError::AiRequest)? {
+            buffer.extend_from_slice(&chunk);
+            while let Some(end) = buffer.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                let Some(data) = line.trim().strip_prefix(EVENT_DATA_PREFIX) else {
+                    continue;
+                };
+                let data = data.trim();
+                if data == STREAM_DONE {
+                    return Ok(text);
+                }
+                let event: LocalAiStreamChunk = serde_json::from_str(data)
+                    .map_err(|e| PromptError::AiApi(format!("Invalid stream event: {e}")))?;
+                let Some(content) = event
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.delta.content)
+                    .filter(|content| !content.is_empty())
+                else {
+                    continue;
+                };
+                text.push_str(&content);
+                let _ = tokens.send(content);
+            }
+        }
 
-        Ok(raw_response)
+        Ok(text)
     }
 }
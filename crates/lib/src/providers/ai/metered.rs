@@ -0,0 +1,87 @@
+//! # Metered AI Provider
+//!
+//! This module provides a wrapper that records the latency and outcome of every
+//! request to the AI provider it wraps.
+
+use super::AiProvider;
+use crate::{errors::PromptError, metrics};
+use ::metrics::{counter, histogram};
+use async_trait::async_trait;
+use std::time::Instant;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::instrument;
+
+/// An `AiProvider` that records metrics for the provider it wraps, labelled by the
+/// provider's name from the configuration.
+#[derive(Debug, Clone)]
+pub struct MeteredAiProvider {
+    name: String,
+    inner: Box<dyn AiProvider>,
+}
+
+impl MeteredAiProvider {
+    pub fn new(name: impl Into<String>, inner: Box<dyn AiProvider>) -> Self {
+        Self {
+            name: name.into(),
+            inner,
+        }
+    }
+
+    fn record<T>(&self, started: Instant, result: &Result<T, PromptError>) {
+        histogram!(metrics::AI_REQUEST_DURATION_SECONDS, "provider" => self.name.clone())
+            .record(started.elapsed().as_secs_f64());
+        counter!(
+            metrics::AI_REQUESTS_TOTAL,
+            "provider" => self.name.clone(),
+            "status" => metrics::status_label(result)
+        )
+        .increment(1);
+    }
+}
+
+#[async_trait]
+impl AiProvider for MeteredAiProvider {
+    #[instrument(name = "ai.generate", skip_all, fields(provider = %self.name))]
+    async fn generate(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+    ) -> Result<String, PromptError> {
+        let started = Instant::now();
+        let result = self.inner.generate(system_prompt, user_prompt).await;
+        self.record(started, &result);
+        result
+    }
+
+    #[instrument(name = "ai.generate_stream", skip_all, fields(provider = %self.name))]
+    async fn generate_stream(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        tokens: UnboundedSender<String>,
+    ) -> Result<String, PromptError> {
+        let started = Instant::now();
+        let result = self
+            .inner
+            .generate_stream(system_prompt, user_prompt, tokens)
+            .await;
+        self.record(started, &result);
+        result
+    }
+
+    #[instrument(name = "ai.describe_image", skip_all, fields(provider = %self.name))]
+    async fn describe_image(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        image_url: &str,
+    ) -> Result<String, PromptError> {
+        let started = Instant::now();
+        let result = self
+            .inner
+            .describe_image(system_prompt, user_prompt, image_url)
+            .await;
+        self.record(started, &result);
+        result
+    }
+}
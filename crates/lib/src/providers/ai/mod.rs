@@ -1,13 +1,18 @@
 pub mod embedding;
 pub mod gemini;
 pub mod local;
+pub mod metered;
+pub mod redacting;
 pub mod zai;
 
 use crate::errors::PromptError;
 use async_trait::async_trait;
 use dyn_clone::DynClone;
 pub use embedding::generate_embeddings_batch;
+pub use metered::MeteredAiProvider;
+pub use redacting::RedactingAiProvider;
 use std::fmt::Debug;
+use tokio::sync::mpsc::UnboundedSender;
 
 /// A trait for interacting with an AI provider.
 ///
@@ -20,6 +25,38 @@ pub trait AiProvider: Send + Sync + Debug + DynClone {
     /// The result should be a string containing the AI's response.
     async fn generate(&self, system_prompt: &str, user_prompt: &str)
         -> Result<String, PromptError>;
+
+    /// Generates a response like `generate`, sending each piece of text to `tokens`
+    /// as it is produced, and returns the complete response.
+    ///
+    /// The default implementation sends the whole response as one piece. Providers
+    /// whose API can stream override it. Sending stops silently if the receiver is
+    /// dropped, since the caller is no longer interested in the tokens.
+    async fn generate_stream(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        tokens: UnboundedSender<String>,
+    ) -> Result<String, PromptError> {
+        let response = self.generate(system_prompt, user_prompt).await?;
+        let _ = tokens.send(response.clone());
+        Ok(response)
+    }
+
+    /// Describes the image at `image_url` with a model that accepts images, following
+    /// the system prompt, with `user_prompt` sent alongside the image.
+    ///
+    /// The default implementation fails with `ImagesNotSupported`. Providers whose API
+    /// takes images override it.
+    async fn describe_image(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        image_url: &str,
+    ) -> Result<String, PromptError> {
+        let _ = (system_prompt, user_prompt, image_url);
+        Err(PromptError::ImagesNotSupported)
+    }
 }
 
 dyn_clone::clone_trait_object!(AiProvider);
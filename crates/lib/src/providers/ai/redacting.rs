@@ -0,0 +1,73 @@
+//! # Redacting AI Provider
+//!
+//! This module provides a wrapper that masks personal data in the user prompts sent
+//! to the AI provider it wraps, for the tasks configured with `redact: true`.
+
+use super::AiProvider;
+use crate::{errors::PromptError, redaction::Redactor};
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::debug;
+
+/// An `AiProvider` that redacts the user prompt before forwarding it to the provider
+/// it wraps.
+///
+/// Only the regular expressions of the `Redactor` are applied: asking a model to find
+/// the personal data would send it to a model in the first place. System prompts come
+/// from the configuration and are forwarded as they are.
+#[derive(Debug, Clone)]
+pub struct RedactingAiProvider {
+    redactor: Arc<Redactor>,
+    inner: Box<dyn AiProvider>,
+}
+
+impl RedactingAiProvider {
+    pub fn new(redactor: Arc<Redactor>, inner: Box<dyn AiProvider>) -> Self {
+        Self { redactor, inner }
+    }
+
+    fn redact(&self, user_prompt: &str) -> String {
+        let redaction = self.redactor.redact(user_prompt);
+        if redaction.is_redacted() {
+            debug!(detected = ?redaction.detected, "Redacted PII from a prompt.");
+        }
+        redaction.text
+    }
+}
+
+#[async_trait]
+impl AiProvider for RedactingAiProvider {
+    async fn generate(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+    ) -> Result<String, PromptError> {
+        self.inner
+            .generate(system_prompt, &self.redact(user_prompt))
+            .await
+    }
+
+    async fn generate_stream(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        tokens: UnboundedSender<String>,
+    ) -> Result<String, PromptError> {
+        self.inner
+            .generate_stream(system_prompt, &self.redact(user_prompt), tokens)
+            .await
+    }
+
+    /// The image itself is sent as it is; only the text sent with it is redacted.
+    async fn describe_image(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        image_url: &str,
+    ) -> Result<String, PromptError> {
+        self.inner
+            .describe_image(system_prompt, &self.redact(user_prompt), image_url)
+            .await
+    }
+}
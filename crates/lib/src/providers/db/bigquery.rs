@@ -1,4 +1,6 @@
-use crate::types::{FieldType as AnyragFieldType, TableField, TableSchema as AnyragTableSchema};
+use crate::types::{
+    FieldType as AnyragFieldType, QueryLimits, TableField, TableSchema as AnyragTableSchema,
+};
 use crate::{errors::PromptError, providers::db::storage::Storage};
 use async_trait::async_trait;
 use gcp_bigquery_client::{
@@ -15,7 +17,7 @@ use std::{
     sync::Arc,
 };
 use tokio::sync::RwLock;
-use tracing::debug;
+use tracing::{debug, info, warn};
 
 /// A provider for interacting with Google BigQuery.
 #[derive(Clone)]
@@ -23,6 +25,11 @@ pub struct BigQueryProvider {
     client: Client,
     project_id: String,
     schema_cache: Arc<RwLock<HashMap<String, Arc<AnyragTableSchema>>>>,
+    query_limits: QueryLimits,
+    /// Queries estimated to scan more bytes than this are refused unless confirmed.
+    max_bytes_scanned: Option<u64>,
+    /// Whether queries above `max_bytes_scanned` were explicitly confirmed by the caller.
+    expensive_query_confirmed: bool,
 }
 
 impl BigQueryProvider {
@@ -35,9 +42,58 @@ impl BigQueryProvider {
             client,
             project_id,
             schema_cache: Arc::new(RwLock::new(HashMap::new())),
+            query_limits: QueryLimits::default(),
+            max_bytes_scanned: None,
+            expensive_query_confirmed: false,
         })
     }
 
+    /// Sets the execution limits enforced by `execute_query`.
+    pub fn with_query_limits(mut self, query_limits: QueryLimits) -> Self {
+        self.query_limits = query_limits;
+        self
+    }
+
+    /// Sets the cost guard: queries estimated to scan more than `max_bytes_scanned` bytes
+    /// are refused unless `confirmed` is true.
+    pub fn with_cost_guard(mut self, max_bytes_scanned: Option<u64>, confirmed: bool) -> Self {
+        self.max_bytes_scanned = max_bytes_scanned;
+        self.expensive_query_confirmed = confirmed;
+        self
+    }
+
+    /// Runs a dry-run of the query and returns the estimated number of bytes it would scan.
+    pub async fn estimate_bytes_scanned(&self, query: &str) -> Result<u64, PromptError> {
+        let mut req = QueryRequest::new(query.to_string());
+        req.use_legacy_sql = false;
+        req.dry_run = Some(true);
+
+        let response = self
+            .client
+            .job()
+            .query(&self.project_id, req)
+            .await
+            .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+
+        let bytes = response
+            .total_bytes_processed
+            .as_deref()
+            .and_then(|b| b.parse::<u64>().ok())
+            .unwrap_or(0);
+        Ok(bytes)
+    }
+
+    /// Refuses the query if its dry-run estimate exceeds the configured threshold.
+    async fn check_query_cost(&self, query: &str) -> Result<(), PromptError> {
+        let Some(max_bytes) = self.max_bytes_scanned else {
+            return Ok(());
+        };
+
+        let estimated_bytes = self.estimate_bytes_scanned(query).await?;
+        info!("BigQuery dry-run estimates {estimated_bytes} bytes scanned (limit: {max_bytes}).");
+        check_bytes_scanned(estimated_bytes, max_bytes, self.expensive_query_confirmed)
+    }
+
     /// Converts a BigQuery-specific schema to the provider-agnostic `AnyragTableSchema`.
     fn convert_schema(bq_schema: &BqTableSchema) -> AnyragTableSchema {
         let fields = bq_schema
@@ -63,10 +119,34 @@ impl BigQueryProvider {
             })
             .collect();
 
-        AnyragTableSchema { fields }
+        AnyragTableSchema {
+            fields,
+            foreign_keys: Vec::new(),
+        }
     }
 }
 
+/// Refuses a query estimated to scan more than `max_bytes` bytes, unless the caller
+/// `confirmed` it.
+pub fn check_bytes_scanned(
+    estimated_bytes: u64,
+    max_bytes: u64,
+    confirmed: bool,
+) -> Result<(), PromptError> {
+    if estimated_bytes <= max_bytes {
+        return Ok(());
+    }
+    if confirmed {
+        warn!("Running query above the cost limit because it was explicitly confirmed.");
+        return Ok(());
+    }
+
+    Err(PromptError::QueryCostExceeded {
+        estimated_bytes,
+        max_bytes,
+    })
+}
+
 impl Debug for BigQueryProvider {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("BigQueryProvider")
@@ -85,10 +165,16 @@ impl Storage for BigQueryProvider {
         "SQL"
     }
 
+    fn query_limits(&self) -> QueryLimits {
+        self.query_limits
+    }
+
     /// Executes a query on BigQuery and returns the result as a JSON string.
     async fn execute_query(&self, query: &str) -> Result<String, PromptError> {
         debug!(query = %query, "--> Executing BigQuery query");
 
+        self.check_query_cost(query).await?;
+
         // The query job is always run in the provider's configured project,
         // which has the necessary billing and permissions. The query string itself
         // (e.g., "SELECT * FROM `bigquery-public-data.samples.shakespeare`")
@@ -96,21 +182,33 @@ impl Storage for BigQueryProvider {
         // By explicitly setting `use_legacy_sql` to false, we ensure Standard SQL
         // is used, which is generally required for modern queries and syntax. This can
         // also prevent the client from making incorrect assumptions about default datasets.
+        let limits = self.query_limits;
         let mut req = QueryRequest::new(query.to_string());
         req.use_legacy_sql = false;
+        // Ask BigQuery to enforce the limits server-side as well.
+        req.timeout_ms = i32::try_from(limits.timeout.as_millis()).ok();
+        req.max_results = i32::try_from(limits.max_rows).ok();
 
-        let response = self
-            .client
-            .job()
-            .query(&self.project_id, req)
-            .await
-            .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+        let response = tokio::time::timeout(
+            limits.timeout,
+            self.client.job().query(&self.project_id, req),
+        )
+        .await
+        .map_err(|_| PromptError::QueryTimeout(limits.timeout.as_secs()))?
+        .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
 
         let mut results = ResultSet::new_from_query_response(response);
         let mut json_results: Vec<Value> = Vec::new();
         let column_names = results.column_names();
 
         while results.next_row() {
+            if json_results.len() >= limits.max_rows {
+                warn!(
+                    "Query returned more than {} rows; the result was truncated.",
+                    limits.max_rows
+                );
+                break;
+            }
             let mut row_map = serde_json::Map::new();
             for name in &column_names {
                 let value = results
@@ -0,0 +1,248 @@
+//! # SQLite Backup and Restore
+//!
+//! A backup is a copy of every table of the knowledge base, including the embeddings
+//! and metadata, written to a new SQLite file at the latest schema version. The source
+//! is read in a single transaction, so the copy is consistent while the database keeps
+//! serving requests.
+//!
+//! Restoring copies the tables of a backup into a database that holds no documents yet,
+//! such as the database of a fresh instance. Rows that already exist there, e.g. the
+//! user performing the restore, are kept.
+
+use super::migrations;
+use crate::errors::PromptError;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+use tracing::info;
+use turso::{Connection, Database, Value as TursoValue};
+
+const LIST_TABLES_SQL: &str = "SELECT name, sql FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name";
+const COUNT_DOCUMENTS_SQL: &str = "SELECT COUNT(*) FROM documents";
+const CHECKPOINT_SQL: &str = "PRAGMA wal_checkpoint(TRUNCATE)";
+/// The table of applied migrations, which each database keeps for itself.
+const SCHEMA_MIGRATIONS_TABLE: &str = "schema_migrations";
+
+/// Custom error types for backups and restores.
+#[derive(Error, Debug)]
+pub enum BackupError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+    #[error("{0}")]
+    Migration(#[from] PromptError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The backup file '{}' already exists.", .0.display())]
+    AlreadyExists(PathBuf),
+    #[error("The backup file '{}' does not exist.", .0.display())]
+    NotFound(PathBuf),
+    #[error("The database already holds documents; restore into a fresh instance.")]
+    NotEmpty,
+    #[error("The backup is at schema version {backup_version}, but this build only knows migrations up to version {latest_version}.")]
+    NewerSchema {
+        backup_version: i64,
+        latest_version: i64,
+    },
+    #[error("'{}' is not a valid UTF-8 path.", .0.display())]
+    InvalidPath(PathBuf),
+}
+
+/// The rows copied by a backup or a restore.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CopySummary {
+    /// The number of rows copied, by table.
+    pub rows: BTreeMap<String, u64>,
+}
+
+impl CopySummary {
+    /// The number of rows copied across all tables.
+    pub fn total_rows(&self) -> u64 {
+        self.rows.values().sum()
+    }
+}
+
+/// Writes a backup of `source` to a new file at `path`. Fails if the file exists.
+pub async fn backup_to_file(source: &Database, path: &Path) -> Result<CopySummary, BackupError> {
+    if path.exists() {
+        return Err(BackupError::AlreadyExists(path.to_path_buf()));
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let destination = open(path).await?;
+    let destination_conn = destination.connect()?;
+    migrations::run_migrations(&destination_conn).await?;
+    let summary = copy_database(source, &destination_conn).await?;
+
+    // Fold the write-ahead log into the file, so the backup is a single file.
+    destination_conn.query(CHECKPOINT_SQL, ()).await?;
+    info!(
+        "Backed up {} rows to '{}'.",
+        summary.total_rows(),
+        path.display()
+    );
+    Ok(summary)
+}
+
+/// Restores the backup at `path` into `target`, which must not hold any documents.
+pub async fn restore_from_file(path: &Path, target: &Database) -> Result<CopySummary, BackupError> {
+    if !path.is_file() {
+        return Err(BackupError::NotFound(path.to_path_buf()));
+    }
+
+    let backup = open(path).await?;
+    let backup_version = migrations::schema_version(&backup.connect()?).await?;
+    let latest_version = migrations::MIGRATIONS
+        .last()
+        .map_or(0, |migration| migration.version);
+    if backup_version > latest_version {
+        return Err(BackupError::NewerSchema {
+            backup_version,
+            latest_version,
+        });
+    }
+
+    let target_conn = target.connect()?;
+    migrations::run_migrations(&target_conn).await?;
+    if count_documents(&target_conn).await? > 0 {
+        return Err(BackupError::NotEmpty);
+    }
+
+    let summary = copy_database(&backup, &target_conn).await?;
+    info!(
+        "Restored {} rows from '{}'.",
+        summary.total_rows(),
+        path.display()
+    );
+    Ok(summary)
+}
+
+async fn open(path: &Path) -> Result<Database, BackupError> {
+    let Some(path_str) = path.to_str() else {
+        return Err(BackupError::InvalidPath(path.to_path_buf()));
+    };
+    Ok(turso::Builder::new_local(path_str).build().await?)
+}
+
+async fn count_documents(conn: &Connection) -> Result<i64, turso::Error> {
+    let mut rows = conn.query(COUNT_DOCUMENTS_SQL, ()).await?;
+    match rows.next().await? {
+        Some(row) => match row.get_value(0)? {
+            TursoValue::Integer(count) => Ok(count),
+            _ => Ok(0),
+        },
+        None => Ok(0),
+    }
+}
+
+/// Copies every table of `source` into `destination` in one transaction on each side.
+async fn copy_database(
+    source: &Database,
+    destination: &Connection,
+) -> Result<CopySummary, turso::Error> {
+    let source_conn = source.connect()?;
+    // The read transaction pins one snapshot of the source for every table.
+    source_conn.execute("BEGIN", ()).await?;
+    destination.execute("BEGIN", ()).await?;
+
+    let result = copy_tables(&source_conn, destination).await;
+    let end_destination = match result {
+        Ok(_) => "COMMIT",
+        Err(_) => "ROLLBACK",
+    };
+    destination.execute(end_destination, ()).await?;
+    source_conn.execute("COMMIT", ()).await?;
+    result
+}
+
+async fn copy_tables(
+    source: &Connection,
+    destination: &Connection,
+) -> Result<CopySummary, turso::Error> {
+    let mut tables = Vec::new();
+    let mut rows = source.query(LIST_TABLES_SQL, ()).await?;
+    while let Some(row) = rows.next().await? {
+        if let (TursoValue::Text(name), TursoValue::Text(create_sql)) =
+            (row.get_value(0)?, row.get_value(1)?)
+        {
+            tables.push((name, create_sql));
+        }
+    }
+
+    let mut summary = CopySummary::default();
+    for (table, create_sql) in tables {
+        if table == SCHEMA_MIGRATIONS_TABLE {
+            continue;
+        }
+
+        // Tables the migrations do not create, e.g. those made by ingestors, are
+        // created from their definition in the source.
+        let mut destination_columns = table_columns(destination, &table).await?;
+        if destination_columns.is_empty() {
+            destination.execute(&create_sql, ()).await?;
+            destination_columns = table_columns(destination, &table).await?;
+        }
+        // Only the columns both sides have are copied, so a backup made before a
+        // column was added still restores, with the column at its default.
+        let columns: Vec<String> = table_columns(source, &table)
+            .await?
+            .into_iter()
+            .filter(|column| destination_columns.contains(column))
+            .collect();
+        if columns.is_empty() {
+            continue;
+        }
+
+        let column_list = columns
+            .iter()
+            .map(|column| quote_identifier(column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let quoted_table = quote_identifier(&table);
+        let mut insert = destination
+            .prepare(&format!(
+                "INSERT INTO {quoted_table} ({column_list}) VALUES ({placeholders}) ON CONFLICT DO NOTHING"
+            ))
+            .await?;
+
+        let mut copied = 0;
+        let mut rows = source
+            .query(&format!("SELECT {column_list} FROM {quoted_table}"), ())
+            .await?;
+        while let Some(row) = rows.next().await? {
+            let values = (0..columns.len())
+                .map(|i| row.get_value(i))
+                .collect::<Result<Vec<TursoValue>, _>>()?;
+            copied += insert.execute(values).await?;
+        }
+        summary.rows.insert(table, copied);
+    }
+    Ok(summary)
+}
+
+async fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>, turso::Error> {
+    let mut rows = conn
+        .query(
+            &format!("PRAGMA table_info({})", quote_identifier(table)),
+            (),
+        )
+        .await?;
+    let mut columns = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let TursoValue::Text(name) = row.get_value(1)? {
+            columns.push(name);
+        }
+    }
+    Ok(columns)
+}
+
+/// Quotes a table or column name read from the schema for use in a statement.
+pub(super) fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
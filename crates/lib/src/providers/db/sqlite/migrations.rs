@@ -0,0 +1,235 @@
+//! # SQLite Schema Migrations
+//!
+//! The schema is versioned by numbered migrations. Each migration is applied once, in
+//! a transaction, and recorded in the `schema_migrations` table, so a schema change
+//! such as a new column ships as a new migration rather than an edit to an existing one.
+//!
+//! Databases created before migrations were introduced have no recorded version. Their
+//! `documents` table is brought up to date first, after which the baseline migration
+//! only creates the tables they are missing.
+
+use super::sql;
+use crate::errors::PromptError;
+use tracing::info;
+use turso::{params, Connection, Value as TursoValue};
+
+/// A versioned schema change.
+#[derive(Debug)]
+pub struct Migration {
+    /// The version the schema is at once the migration is applied. Versions start at 1
+    /// and increase by one.
+    pub version: i64,
+    pub name: &'static str,
+    /// The statements that apply the migration, in order.
+    pub up: &'static [&'static str],
+}
+
+/// All migrations, in order. Append new migrations; never edit an applied one.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "baseline",
+        up: &[
+            sql::CREATE_USERS_TABLE_SQL,
+            sql::CREATE_DOCUMENTS_TABLE_SQL,
+            sql::CREATE_DOCUMENT_EMBEDDINGS_TABLE_SQL,
+            sql::CREATE_CONTENT_METADATA_TABLE_SQL,
+            sql::CREATE_CONVERSATIONS_TABLE_SQL,
+            sql::CREATE_SCHEMA_ANNOTATIONS_TABLE_SQL,
+            sql::CREATE_EXPERIMENTS_TABLE_SQL,
+            sql::CREATE_FEEDBACK_TABLE_SQL,
+            sql::CREATE_FEW_SHOT_EXAMPLES_TABLE_SQL,
+            sql::CREATE_WEB_SOURCES_TABLE_SQL,
+            sql::CREATE_OBJECT_STORE_MANIFEST_TABLE_SQL,
+            sql::CREATE_API_KEYS_TABLE_SQL,
+            sql::CREATE_ROLE_PERMISSIONS_TABLE_SQL,
+            sql::CREATE_ORGANIZATIONS_TABLE_SQL,
+            sql::CREATE_DOCUMENT_SHARES_TABLE_SQL,
+            sql::CREATE_SESSIONS_TABLE_SQL,
+            sql::CREATE_USAGE_COUNTERS_TABLE_SQL,
+        ],
+    },
+    Migration {
+        version: 2,
+        name: "schema_annotation_references",
+        up: &[sql::ADD_SCHEMA_ANNOTATIONS_REFERENCES_SQL],
+    },
+    Migration {
+        version: 3,
+        name: "answer_cache",
+        up: &[
+            sql::CREATE_ANSWER_CACHE_TABLE_SQL,
+            sql::CREATE_ANSWER_CACHE_INDEX_SQL,
+        ],
+    },
+    Migration {
+        version: 4,
+        name: "faq_items",
+        up: &[
+            sql::CREATE_FAQ_ITEMS_TABLE_SQL,
+            sql::CREATE_FAQ_ITEMS_INDEX_SQL,
+        ],
+    },
+    Migration {
+        version: 5,
+        name: "document_revisions",
+        up: &[
+            sql::CREATE_DOCUMENT_REVISIONS_TABLE_SQL,
+            sql::CREATE_DOCUMENT_REVISIONS_INDEX_SQL,
+        ],
+    },
+    Migration {
+        version: 6,
+        name: "document_trash",
+        up: &[
+            sql::ADD_DOCUMENTS_DELETED_AT_SQL,
+            sql::CREATE_DOCUMENTS_DELETED_AT_INDEX_SQL,
+        ],
+    },
+    Migration {
+        version: 7,
+        name: "collections",
+        up: &[
+            sql::CREATE_COLLECTIONS_TABLE_SQL,
+            sql::CREATE_DOCUMENT_COLLECTIONS_TABLE_SQL,
+        ],
+    },
+    Migration {
+        version: 8,
+        name: "pipeline_progress",
+        up: &[sql::CREATE_PIPELINE_PROGRESS_TABLE_SQL],
+    },
+    Migration {
+        version: 9,
+        name: "seed_role_permissions",
+        up: &[sql::SEED_ROLE_PERMISSIONS_SQL],
+    },
+    Migration {
+        version: 10,
+        name: "few_shot_example_owners",
+        up: &[
+            sql::ADD_FEW_SHOT_EXAMPLES_OWNER_ID_SQL,
+            sql::BACKFILL_FEW_SHOT_EXAMPLES_OWNER_ID_SQL,
+            sql::CREATE_FEW_SHOT_EXAMPLES_OWNER_ID_INDEX_SQL,
+        ],
+    },
+];
+
+/// Applies the migrations the database has not applied yet, returning the versions
+/// applied. Fails if the database was migrated by a newer build.
+pub async fn run_migrations(conn: &Connection) -> Result<Vec<i64>, PromptError> {
+    conn.execute(sql::CREATE_SCHEMA_MIGRATIONS_TABLE_SQL, ())
+        .await
+        .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+
+    let current_version = schema_version(conn).await?;
+    let latest_version = MIGRATIONS.last().map_or(0, |migration| migration.version);
+    if current_version > latest_version {
+        return Err(PromptError::StorageOperationFailed(format!(
+            "The database schema is at version {current_version}, but this build only knows migrations up to version {latest_version}."
+        )));
+    }
+    if current_version == 0 {
+        upgrade_legacy_documents(conn).await?;
+    }
+
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > current_version)
+    {
+        info!(
+            "Applying schema migration {} ({}).",
+            migration.version, migration.name
+        );
+        apply(conn, migration).await.map_err(|e| {
+            PromptError::StorageOperationFailed(format!(
+                "Schema migration {} ({}) failed: {e}",
+                migration.version, migration.name
+            ))
+        })?;
+        applied.push(migration.version);
+    }
+    Ok(applied)
+}
+
+/// Returns the version of the last migration applied, or 0 if none was.
+pub async fn schema_version(conn: &Connection) -> Result<i64, PromptError> {
+    let mut rows = conn
+        .query("SELECT MAX(version) FROM schema_migrations", ())
+        .await
+        .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+    let row = rows
+        .next()
+        .await
+        .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+    match row.map(|row| row.get_value(0)).transpose() {
+        Ok(Some(TursoValue::Integer(version))) => Ok(version),
+        Ok(_) => Ok(0),
+        Err(e) => Err(PromptError::StorageOperationFailed(e.to_string())),
+    }
+}
+
+/// Applies a migration and records it, rolling back if any statement fails.
+async fn apply(conn: &Connection, migration: &Migration) -> Result<(), turso::Error> {
+    conn.execute("BEGIN", ()).await?;
+    let result = async {
+        for statement in migration.up {
+            conn.execute(statement, ()).await?;
+        }
+        conn.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?, ?)",
+            params![migration.version, migration.name],
+        )
+        .await?;
+        Ok(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            conn.execute("COMMIT", ()).await?;
+            Ok(())
+        }
+        Err(e) => {
+            conn.execute("ROLLBACK", ()).await?;
+            Err(e)
+        }
+    }
+}
+
+/// Adds the `documents` columns that databases created before migrations may lack,
+/// and that the baseline's indexes need.
+async fn upgrade_legacy_documents(conn: &Connection) -> Result<(), PromptError> {
+    let mut rows = conn
+        .query("PRAGMA table_info(documents);", ())
+        .await
+        .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+    let mut columns = Vec::new();
+    while let Some(row) = rows
+        .next()
+        .await
+        .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?
+    {
+        if let Ok(TursoValue::Text(name)) = row.get_value(1) {
+            columns.push(name);
+        }
+    }
+    if columns.is_empty() {
+        return Ok(());
+    }
+
+    if !columns.iter().any(|c| c == "content_hash") {
+        info!("Adding the 'content_hash' column to the 'documents' table.");
+        conn.execute(sql::ADD_DOCUMENTS_CONTENT_HASH_SQL, ())
+            .await
+            .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+    }
+    if !columns.iter().any(|c| c == "org_id") {
+        info!("Adding the 'org_id' column to the 'documents' table.");
+        conn.execute(sql::ADD_DOCUMENTS_ORG_ID_SQL, ())
+            .await
+            .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+    }
+    Ok(())
+}
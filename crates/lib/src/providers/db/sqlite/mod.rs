@@ -1,4 +1,4 @@
-use crate::types::{FieldType, TableField, TableSchema};
+use crate::types::{FieldType, ForeignKey, QueryLimits, SqliteSettings, TableField, TableSchema};
 use crate::{
     errors::PromptError,
     providers::db::storage::{KeywordSearch, MetadataSearch, Storage, VectorSearch},
@@ -8,22 +8,30 @@ use crate::{
 use async_trait::async_trait;
 #[cfg(feature = "core-access")]
 use core_access::GUEST_USER_IDENTIFIER;
+use pool::{retry_on_busy, ConnectionPool, PooledConnection, WriteConnection, WriteLock};
 use serde_json::Value;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::{self, Debug},
     sync::Arc,
 };
-use tokio::sync::RwLock;
-use tracing::{debug, info};
+use tokio::sync::{OwnedSemaphorePermit, RwLock};
+use tracing::{debug, info, warn};
 use turso::{Database, Value as TursoValue};
 
 #[cfg(feature = "core-access")]
 use uuid::Uuid;
 
-use crate::providers::db::storage::TemporalSearch;
+use crate::providers::db::storage::{CollectionSearch, SummarySearch, TemporalSearch};
+use crate::summarization::SUMMARY_METADATA_TYPE;
 
+pub mod backup;
+pub mod migrations;
+pub mod pool;
 pub mod sql;
+pub mod statements;
+#[cfg(feature = "table-transfer")]
+pub mod table_transfer;
 
 /// Represents a search result from the `faq_kb` table, used for RAG context.
 #[derive(Debug)]
@@ -34,14 +42,17 @@ pub struct FaqSearchResult {
 
 /// A provider for interacting with a local SQLite database using Turso.
 ///
-/// This provider holds a `Database` instance, which manages a connection pool.
-/// When cloned, it shares the same underlying database, allowing for concurrent and
-/// shared access to the same database file or in-memory instance.
+/// This provider holds a `Database` instance and a `ConnectionPool` of its connections.
+/// When cloned, it shares the same underlying database and pool, allowing for concurrent
+/// and shared access to the same database file or in-memory instance. Reads reuse pooled
+/// connections, and writes through the provider are serialized by the pool's write permit.
 #[derive(Clone)]
 pub struct SqliteProvider {
     /// The Turso database instance. It's cloneable and thread-safe.
     pub db: Database,
+    pool: Arc<ConnectionPool>,
     schema_cache: Arc<RwLock<HashMap<String, Arc<TableSchema>>>>,
+    query_limits: QueryLimits,
 }
 
 impl SqliteProvider {
@@ -73,18 +84,172 @@ impl SqliteProvider {
             .map_err(|e| PromptError::StorageConnection(e.to_string()))?;
 
         Ok(Self {
+            pool: Arc::new(ConnectionPool::new(db.clone(), SqliteSettings::default())),
             db,
             schema_cache: Arc::new(RwLock::new(HashMap::new())),
+            query_limits: QueryLimits::default(),
         })
     }
 
+    /// Sets the execution limits enforced by `execute_query`.
+    pub fn with_query_limits(mut self, query_limits: QueryLimits) -> Self {
+        self.query_limits = query_limits;
+        self
+    }
+
+    /// Sets the busy timeout, read pool size and busy retries of the connections.
+    pub fn with_sqlite_settings(mut self, settings: SqliteSettings) -> Self {
+        self.pool = Arc::new(ConnectionPool::new(self.db.clone(), settings));
+        self
+    }
+
+    /// Returns a pooled connection for reading.
+    pub async fn read_connection(&self) -> Result<PooledConnection, PromptError> {
+        self.pool
+            .read()
+            .await
+            .map_err(|e| PromptError::StorageConnection(e.to_string()))
+    }
+
+    /// Returns a connection holding the write permit, waiting for other writers to finish.
+    pub async fn write_connection(&self) -> Result<WriteConnection, PromptError> {
+        self.pool
+            .write()
+            .await
+            .map_err(|e| PromptError::StorageConnection(e.to_string()))
+    }
+
+    /// Waits for the write permit, to hold while writing through connections of one's
+    /// own.
+    pub async fn write_permit(&self) -> OwnedSemaphorePermit {
+        self.pool.write_permit().await
+    }
+
+    /// The write lock to run an ingestion in, whose writes take the write permit only
+    /// while they run.
+    pub fn write_lock(&self) -> WriteLock {
+        self.pool.write_lock()
+    }
+
+    /// Returns a hash of the documents visible to the owner and organization, which
+    /// changes whenever one of them is added, edited or deleted.
+    pub async fn corpus_version(
+        &self,
+        owner_id: Option<&str>,
+        org_id: Option<&str>,
+    ) -> Result<String, PromptError> {
+        let (condition, params) = visibility_condition(owner_id, org_id);
+        let conn = self.read_connection().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT d.id, d.content_hash, d.created_at FROM documents d WHERE {condition} ORDER BY d.id"
+                ),
+                params,
+            )
+            .await
+            .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+
+        let mut context = md5::Context::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?
+        {
+            for i in 0..3 {
+                let value = row
+                    .get_value(i)
+                    .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+                if let TursoValue::Text(text) = value {
+                    context.consume(text.as_bytes());
+                }
+                context.consume(b"\x1f");
+            }
+            context.consume(b"\n");
+        }
+        Ok(format!("{:x}", context.compute()))
+    }
+
+    /// Returns the ids of the documents visible to the owner and organization.
+    pub async fn visible_document_ids(
+        &self,
+        owner_id: Option<&str>,
+        org_id: Option<&str>,
+    ) -> Result<HashSet<String>, PromptError> {
+        let (condition, params) = visibility_condition(owner_id, org_id);
+        let conn = self.read_connection().await?;
+        let mut rows = conn
+            .query(
+                &format!("SELECT d.id FROM documents d WHERE {condition}"),
+                params,
+            )
+            .await
+            .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+        let mut document_ids = HashSet::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?
+        {
+            let id: String = row
+                .get(0)
+                .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+            document_ids.insert(id);
+        }
+        Ok(document_ids)
+    }
+
+    /// Runs a query and collects at most `max_rows` rows as JSON objects.
+    async fn collect_query_rows(
+        &self,
+        query: &str,
+        max_rows: usize,
+    ) -> Result<Vec<Value>, PromptError> {
+        let conn = self.read_connection().await?;
+
+        let mut stmt = conn
+            .prepare(query)
+            .await
+            .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+
+        let column_names: Vec<String> = stmt
+            .columns()
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect();
+
+        let mut rows = stmt
+            .query(())
+            .await
+            .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+
+        let mut json_results: Vec<Value> = Vec::new();
+
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?
+        {
+            if json_results.len() >= max_rows {
+                warn!("Query returned more than {max_rows} rows; the result was truncated.");
+                break;
+            }
+            let mut row_map = serde_json::Map::new();
+            for (i, name) in column_names.iter().enumerate() {
+                let value = row
+                    .get_value(i)
+                    .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+                row_map.insert(name.clone(), turso_value_to_json(value));
+            }
+            json_results.push(Value::Object(row_map));
+        }
+
+        Ok(json_results)
+    }
+
     /// A helper for tests to pre-populate data by executing multiple SQL statements.
     pub async fn initialize_with_data(&self, init_sql: &str) -> Result<(), PromptError> {
-        // Get a new connection for this operation.
-        let conn = self
-            .db
-            .connect()
-            .map_err(|e| PromptError::StorageConnection(e.to_string()))?;
+        let conn = self.write_connection().await?;
 
         for statement in init_sql.split(';').filter(|s| !s.trim().is_empty()) {
             conn.execute(statement, ())
@@ -94,19 +259,11 @@ impl SqliteProvider {
         Ok(())
     }
 
-    /// Ensures that all required application tables and indexes exist.
+    /// Brings the schema up to date by applying the pending migrations.
     /// This function is idempotent and safe to call on every application startup.
     pub async fn initialize_schema(&self) -> Result<(), PromptError> {
-        let conn = self
-            .db
-            .connect()
-            .map_err(|e| PromptError::StorageConnection(e.to_string()))?;
-
-        for statement in sql::ALL_TABLE_CREATION_SQL {
-            conn.execute(statement, ())
-                .await
-                .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
-        }
+        let conn = self.write_connection().await?;
+        migrations::run_migrations(&conn).await?;
         Ok(())
     }
 }
@@ -124,6 +281,35 @@ impl AsRef<Database> for SqliteProvider {
 }
 
 /// Converts a Turso value to a serde_json::Value.
+/// Reads the foreign keys of a table.
+async fn foreign_keys(
+    conn: &turso::Connection,
+    table_name: &str,
+) -> Result<Vec<ForeignKey>, turso::Error> {
+    // PRAGMA foreign_key_list columns: id, seq, table, from, to, on_update, on_delete, match
+    let mut rows = conn
+        .query(&format!("PRAGMA foreign_key_list({table_name});"), ())
+        .await?;
+    let mut foreign_keys = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let (TursoValue::Text(referenced_table), TursoValue::Text(column)) =
+            (row.get_value(2)?, row.get_value(3)?)
+        {
+            // `to` is NULL when the key references the primary key.
+            let referenced_column = match row.get_value(4)? {
+                TursoValue::Text(to) => Some(to),
+                _ => None,
+            };
+            foreign_keys.push(ForeignKey {
+                column,
+                referenced_table,
+                referenced_column,
+            });
+        }
+    }
+    Ok(foreign_keys)
+}
+
 fn turso_value_to_json(v: TursoValue) -> Value {
     match v {
         TursoValue::Null => Value::Null,
@@ -146,48 +332,23 @@ impl Storage for SqliteProvider {
         "SQL"
     }
 
+    fn query_limits(&self) -> QueryLimits {
+        self.query_limits
+    }
+
     /// Executes a query on SQLite and returns the result as a JSON string.
     async fn execute_query(&self, query: &str) -> Result<String, PromptError> {
         debug!(query = %query, "--> Executing SQLite query");
 
-        // Get a new connection for this query.
-        let conn = self
-            .db
-            .connect()
-            .map_err(|e| PromptError::StorageConnection(e.to_string()))?;
-
-        let mut stmt = conn
-            .prepare(query)
-            .await
-            .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
-
-        let column_names: Vec<String> = stmt
-            .columns()
-            .iter()
-            .map(|c| c.name().to_string())
-            .collect();
-
-        let mut rows = stmt
-            .query(())
-            .await
-            .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
-
-        let mut json_results: Vec<Value> = Vec::new();
-
-        while let Some(row) = rows
-            .next()
-            .await
-            .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?
-        {
-            let mut row_map = serde_json::Map::new();
-            for (i, name) in column_names.iter().enumerate() {
-                let value = row
-                    .get_value(i)
-                    .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
-                row_map.insert(name.clone(), turso_value_to_json(value));
-            }
-            json_results.push(Value::Object(row_map));
-        }
+        let limits = self.query_limits;
+        let json_results = tokio::time::timeout(
+            limits.timeout,
+            retry_on_busy(self.pool.settings().busy_retries, || {
+                self.collect_query_rows(query, limits.max_rows)
+            }),
+        )
+        .await
+        .map_err(|_| PromptError::QueryTimeout(limits.timeout.as_secs()))??;
 
         Ok(serde_json::to_string(&json_results)?)
     }
@@ -200,10 +361,7 @@ impl Storage for SqliteProvider {
         }
         debug!(table_name = %table_name, "Schema not in cache. Fetching from DB.");
 
-        let conn = self
-            .db
-            .connect()
-            .map_err(|e| PromptError::StorageConnection(e.to_string()))?;
+        let conn = self.read_connection().await?;
 
         let query = format!("PRAGMA table_info({table_name});");
         let mut rows = conn
@@ -246,9 +404,21 @@ impl Storage for SqliteProvider {
             )));
         }
 
-        info!(table_name = %table_name, "Successfully fetched schema with {} columns.", fields.len());
+        // Foreign keys only add join hints, so failing to read them doesn't fail the schema.
+        let foreign_keys = match foreign_keys(&conn, table_name).await {
+            Ok(foreign_keys) => foreign_keys,
+            Err(e) => {
+                warn!(table_name = %table_name, "Failed to read foreign keys: {e}");
+                Vec::new()
+            }
+        };
+
+        info!(table_name = %table_name, "Successfully fetched schema with {} columns and {} foreign keys.", fields.len(), foreign_keys.len());
 
-        let schema = Arc::new(TableSchema { fields });
+        let schema = Arc::new(TableSchema {
+            fields,
+            foreign_keys,
+        });
 
         self.schema_cache
             .write()
@@ -260,10 +430,7 @@ impl Storage for SqliteProvider {
 
     async fn list_tables(&self) -> Result<Vec<String>, PromptError> {
         info!("Listing all non-empty tables in SQLite database.");
-        let conn = self
-            .db
-            .connect()
-            .map_err(|e| PromptError::StorageConnection(e.to_string()))?;
+        let conn = self.read_connection().await?;
 
         let mut rows = conn
             .query(
@@ -305,6 +472,59 @@ impl Storage for SqliteProvider {
     }
 }
 
+/// Matches the documents shared with a user through `document_shares`.
+const SHARED_WITH_USER_CONDITION: &str =
+    "d.id IN (SELECT document_id FROM document_shares WHERE user_id = ?)";
+
+/// Matches the documents that are not in the trash.
+const NOT_DELETED_CONDITION: &str = "d.deleted_at IS NULL";
+
+/// Builds the condition that limits `documents d` to those visible to the owner,
+/// including the documents shared with them. With an organization, the documents
+/// shared with it are visible too. Documents in the trash are never visible.
+fn visibility_condition(owner_id: Option<&str>, org_id: Option<&str>) -> (String, Vec<TursoValue>) {
+    let (condition, mut params) = owner_condition(owner_id);
+    let Some(org) = org_id else {
+        return (format!("{NOT_DELETED_CONDITION} AND {condition}"), params);
+    };
+    params.push(org.to_string().into());
+    (
+        format!("{NOT_DELETED_CONDITION} AND ({condition} OR d.org_id = ?)"),
+        params,
+    )
+}
+
+#[cfg(feature = "core-access")]
+fn owner_condition(owner_id: Option<&str>) -> (String, Vec<TursoValue>) {
+    let guest_user_id =
+        Uuid::new_v5(&Uuid::NAMESPACE_URL, GUEST_USER_IDENTIFIER.as_bytes()).to_string();
+    match owner_id {
+        // An authenticated user sees their own content, guest content, and content
+        // shared with them.
+        Some(owner) if owner != guest_user_id => (
+            format!("(d.owner_id = ? OR d.owner_id = ? OR {SHARED_WITH_USER_CONDITION})"),
+            vec![
+                owner.to_string().into(),
+                guest_user_id.into(),
+                owner.to_string().into(),
+            ],
+        ),
+        // The guest user, or no owner, sees only guest content.
+        _ => ("d.owner_id = ?".to_string(), vec![guest_user_id.into()]),
+    }
+}
+
+#[cfg(not(feature = "core-access"))]
+fn owner_condition(owner_id: Option<&str>) -> (String, Vec<TursoValue>) {
+    match owner_id {
+        Some(owner) => (
+            format!("(d.owner_id = ? OR {SHARED_WITH_USER_CONDITION})"),
+            vec![owner.to_string().into(), owner.to_string().into()],
+        ),
+        None => ("d.owner_id IS NULL".to_string(), Vec::new()),
+    }
+}
+
 #[async_trait]
 impl VectorSearch for SqliteProvider {
     /// Performs a vector similarity search using SQLite with the vss-lite extension.
@@ -313,6 +533,7 @@ impl VectorSearch for SqliteProvider {
         query_vector: Vec<f32>,
         limit: u32,
         owner_id: Option<&str>,
+        org_id: Option<&str>,
         document_ids: Option<&[String]>,
     ) -> Result<Vec<SearchResult>, SearchError> {
         info!("Executing SQLite vector search on documents.");
@@ -323,7 +544,7 @@ impl VectorSearch for SqliteProvider {
             }
         }
 
-        let conn = self.db.connect()?;
+        let mut conn = self.pool.read().await?;
 
         let vector_numbers_str = query_vector
             .iter()
@@ -331,81 +552,40 @@ impl VectorSearch for SqliteProvider {
             .collect::<Vec<_>>()
             .join(", ");
 
-        let vector_str = format!("vector('[{}]')", &vector_numbers_str);
-
-        let distance_calculation =
-            format!("(1.0 - (vector_distance_cos(de.embedding, {vector_str}) / 2.0))");
-
-        let mut sql = format!(
-            "SELECT d.title, d.source_url, d.content, {distance_calculation} AS similarity
+        // The vector and the limit are bound as parameters, so the prepared statement
+        // is reused by every search with the same filters.
+        let mut sql = "SELECT d.title, d.source_url, d.content,
+                    (1.0 - (vector_distance_cos(de.embedding, vector(?)) / 2.0)) AS similarity
              FROM document_embeddings de
              JOIN documents d ON d.id = de.document_id"
-        );
+            .to_string();
 
         let mut conditions: Vec<String> = vec!["de.embedding IS NOT NULL".to_string()];
-        let mut query_params: Vec<TursoValue> = Vec::new();
+        let mut query_params: Vec<TursoValue> = vec![format!("[{vector_numbers_str}]").into()];
 
         // Only apply the owner_id filter if we are not already filtering by a specific set of document IDs.
         // The document_ids are pre-filtered by owner in the metadata search step.
         if document_ids.is_none() {
-            // This block needs the GUEST_USER_IDENTIFIER, so it's conditionally compiled.
-            #[cfg(feature = "core-access")]
-            {
-                let guest_user_id =
-                    Uuid::new_v5(&Uuid::NAMESPACE_URL, GUEST_USER_IDENTIFIER.as_bytes())
-                        .to_string();
-
-                if let Some(owner) = owner_id {
-                    if owner == guest_user_id {
-                        // It's the guest user, they only see guest content.
-                        conditions.push("d.owner_id = ?".to_string());
-                        query_params.push(guest_user_id.into());
-                    } else {
-                        // It's an authenticated user, they see their own content and guest content.
-                        conditions.push("(d.owner_id = ? OR d.owner_id = ?)".to_string());
-                        query_params.push(owner.to_string().into());
-                        query_params.push(guest_user_id.into());
-                    }
-                } else {
-                    // No owner provided, default to only showing guest content.
-                    conditions.push("d.owner_id = ?".to_string());
-                    query_params.push(guest_user_id.into());
-                }
-            }
-            #[cfg(not(feature = "core-access"))]
-            {
-                if let Some(owner) = owner_id {
-                    conditions.push("d.owner_id = ?".to_string());
-                    query_params.push(owner.to_string().into());
-                } else {
-                    conditions.push("d.owner_id IS NULL".to_string());
-                }
-            }
+            let (condition, params) = visibility_condition(owner_id, org_id);
+            conditions.push(condition);
+            query_params.extend(params);
         }
 
         if let Some(ids) = document_ids {
             let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
             let condition = format!("de.document_id IN ({placeholders})");
             conditions.push(condition);
+            conditions.push(NOT_DELETED_CONDITION.to_string());
             for id in ids {
                 query_params.push(id.clone().into());
             }
         }
 
         sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
-        sql.push_str(&format!(" ORDER BY similarity DESC LIMIT {limit};"));
-
-        let _log_sql = if vector_numbers_str.len() > 256 {
-            sql.replace(&vector_numbers_str, "[...vector truncated...]")
-        } else {
-            sql.clone()
-        };
+        sql.push_str(" ORDER BY similarity DESC LIMIT ?;");
+        query_params.push(TursoValue::Integer(i64::from(limit)));
 
-        let mut results = if query_params.is_empty() {
-            conn.query(&sql, ()).await?
-        } else {
-            conn.query(&sql, query_params).await?
-        };
+        let mut results = conn.prepare_cached(&sql).await?.query(query_params).await?;
         let mut search_results = Vec::new();
 
         while let Some(row) = results.next().await? {
@@ -444,10 +624,11 @@ impl KeywordSearch for SqliteProvider {
         query: &str,
         limit: u32,
         owner_id: Option<&str>,
+        org_id: Option<&str>,
         document_ids: Option<&[String]>,
     ) -> Result<Vec<SearchResult>, SearchError> {
         info!("Executing keyword search for: '{query}' for owner: {owner_id:?}");
-        let conn = self.db.connect()?;
+        let mut conn = self.pool.read().await?;
         let mut search_results = Vec::new();
         let keywords: Vec<_> = query.split_whitespace().filter(|s| !s.is_empty()).collect();
         if keywords.is_empty() {
@@ -472,6 +653,7 @@ impl KeywordSearch for SqliteProvider {
             if !ids.is_empty() {
                 let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
                 doc_conditions.push(format!("d.id IN ({placeholders})"));
+                doc_conditions.push(NOT_DELETED_CONDITION.to_string());
                 for id in ids {
                     doc_params.push(TursoValue::Text(id.to_string()));
                 }
@@ -483,42 +665,22 @@ impl KeywordSearch for SqliteProvider {
 
         // Only apply owner_id filter if not already filtering by specific doc IDs.
         if document_ids.is_none() {
-            #[cfg(feature = "core-access")]
-            {
-                let guest_user_id =
-                    Uuid::new_v5(&Uuid::NAMESPACE_URL, GUEST_USER_IDENTIFIER.as_bytes())
-                        .to_string();
-                if let Some(owner) = owner_id {
-                    if owner == guest_user_id {
-                        doc_conditions.push("d.owner_id = ?".to_string());
-                        doc_params.push(TursoValue::Text(guest_user_id.clone()));
-                    } else {
-                        doc_conditions.push("(d.owner_id = ? OR d.owner_id = ?)".to_string());
-                        doc_params.push(TursoValue::Text(owner.to_string()));
-                        doc_params.push(TursoValue::Text(guest_user_id.clone()));
-                    }
-                } else {
-                    doc_conditions.push("d.owner_id = ?".to_string());
-                    doc_params.push(TursoValue::Text(guest_user_id.clone()));
-                }
-            }
-            #[cfg(not(feature = "core-access"))]
-            {
-                if let Some(owner) = owner_id {
-                    doc_conditions.push("d.owner_id = ?".to_string());
-                    doc_params.push(TursoValue::Text(owner.to_string()));
-                } else {
-                    doc_conditions.push("d.owner_id IS NULL".to_string());
-                }
-            }
+            let (condition, params) = visibility_condition(owner_id, org_id);
+            doc_conditions.push(condition);
+            doc_params.extend(params);
         }
 
         let doc_where = doc_conditions.join(" AND ");
         let doc_sql = format!(
-            "SELECT d.title, d.source_url, d.content FROM documents d WHERE {doc_where} LIMIT {limit}"
+            "SELECT d.title, d.source_url, d.content FROM documents d WHERE {doc_where} LIMIT ?"
         );
+        doc_params.push(TursoValue::Integer(i64::from(limit)));
 
-        let mut doc_rows = conn.query(&doc_sql, doc_params).await?;
+        let mut doc_rows = conn
+            .prepare_cached(&doc_sql)
+            .await?
+            .query(doc_params)
+            .await?;
         while let Some(row) = doc_rows.next().await? {
             search_results.push(SearchResult {
                 title: row.get::<String>(0)?,
@@ -539,42 +701,22 @@ impl MetadataSearch for SqliteProvider {
         entities: &[String],
         keyphrases: &[String],
         owner_id: Option<&str>,
+        org_id: Option<&str>,
         limit: u32,
+        document_ids: Option<&[String]>,
     ) -> Result<Vec<SearchResult>, SearchError> {
         info!("Executing metadata search for entities: {entities:?}, keyphrases: {keyphrases:?}");
-        let conn = self.db.connect()?;
-
-        let mut conditions = Vec::new();
-        let mut params: Vec<turso::Value> = Vec::new();
-
-        #[cfg(feature = "core-access")]
-        {
-            let guest_user_id =
-                Uuid::new_v5(&Uuid::NAMESPACE_URL, GUEST_USER_IDENTIFIER.as_bytes()).to_string();
-
-            if let Some(owner) = owner_id {
-                if owner == guest_user_id {
-                    // Guest user sees only guest content.
-                    conditions.push("d.owner_id = ?".to_string());
-                    params.push(guest_user_id.into());
-                } else {
-                    conditions.push("(d.owner_id = ? OR d.owner_id = ?)".to_string());
-                    params.push(owner.to_string().into());
-                    params.push(guest_user_id.into());
-                }
-            } else {
-                conditions.push("d.owner_id = ?".to_string());
-                params.push(guest_user_id.into());
-            }
+        if document_ids.is_some_and(|ids| ids.is_empty()) {
+            return Ok(Vec::new());
         }
-        #[cfg(not(feature = "core-access"))]
-        {
-            if let Some(owner) = owner_id {
-                conditions.push("d.owner_id = ?".to_string());
-                params.push(owner.to_string().into());
-            } else {
-                conditions.push("d.owner_id IS NULL".to_string());
-            }
+        let conn = self.pool.read().await?;
+
+        let (condition, mut params) = visibility_condition(owner_id, org_id);
+        let mut conditions = vec![condition];
+        if let Some(ids) = document_ids {
+            let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+            conditions.push(format!("d.id IN ({placeholders})"));
+            params.extend(ids.iter().map(|id| TursoValue::from(id.clone())));
         }
 
         let mut metadata_conditions = Vec::new();
@@ -655,42 +797,109 @@ impl MetadataSearch for SqliteProvider {
 
 #[async_trait]
 impl TemporalSearch for SqliteProvider {
-    async fn get_string_properties_for_documents(
+    async fn get_properties_for_links(
         &self,
-        doc_ids: &[&str],
-        property_name: &str,
+        links: &[&str],
         owner_id: Option<&str>,
-    ) -> Result<HashMap<String, String>, turso::Error> {
-        if doc_ids.is_empty() {
+    ) -> Result<HashMap<String, Vec<(String, String)>>, turso::Error> {
+        if links.is_empty() {
             return Ok(HashMap::new());
         }
 
-        let conn = self.db.connect()?;
-        let mut params: Vec<turso::Value> = vec![];
+        let conn = self.pool.read().await?;
+        let (owner_filter, mut params) = owner_condition(owner_id);
+        let placeholders = links.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let sql = format!(
+            "SELECT d.source_url, COALESCE(m.metadata_subtype, ''), m.metadata_value
+             FROM content_metadata m JOIN documents d ON d.id = m.document_id
+             WHERE m.metadata_type = 'PROPERTY' AND {NOT_DELETED_CONDITION} AND {owner_filter} AND d.source_url IN ({placeholders})"
+        );
+        for link in links {
+            params.push((*link).into());
+        }
+
+        let mut result_set = conn.query(&sql, params).await?;
+        let mut results: HashMap<String, Vec<(String, String)>> = HashMap::new();
 
-        let mut sql = "SELECT document_id, metadata_value FROM content_metadata WHERE metadata_type = 'PROPERTY' AND metadata_subtype = ?".to_string();
-        params.push(property_name.into());
+        while let Some(row) = result_set.next().await? {
+            let link: String = row.get(0)?;
+            let name: String = row.get(1)?;
+            let value: String = row.get(2)?;
+            results.entry(link).or_default().push((name, value));
+        }
+
+        Ok(results)
+    }
+}
 
-        if let Some(id) = owner_id {
-            sql.push_str(" AND owner_id = ?");
-            params.push(id.into());
+#[async_trait]
+impl SummarySearch for SqliteProvider {
+    async fn get_summarized_chunk_ids(
+        &self,
+        links: &[&str],
+        owner_id: Option<&str>,
+        org_id: Option<&str>,
+    ) -> Result<HashMap<String, Vec<String>>, turso::Error> {
+        if links.is_empty() {
+            return Ok(HashMap::new());
         }
 
-        let placeholders = doc_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
-        sql.push_str(&format!(" AND document_id IN ({placeholders})"));
-        for id in doc_ids {
-            params.push((*id).into());
+        let conn = self.pool.read().await?;
+        let (visibility, mut params) = visibility_condition(owner_id, org_id);
+        let placeholders = links.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let sql = format!(
+            "SELECT d.source_url, m.metadata_value
+             FROM content_metadata m JOIN documents d ON d.id = m.document_id
+             WHERE m.metadata_type = '{SUMMARY_METADATA_TYPE}' AND {visibility} AND d.source_url IN ({placeholders})
+             ORDER BY m.id"
+        );
+        for link in links {
+            params.push((*link).into());
         }
 
         let mut result_set = conn.query(&sql, params).await?;
-        let mut results = HashMap::new();
+        let mut results: HashMap<String, Vec<String>> = HashMap::new();
 
         while let Some(row) = result_set.next().await? {
-            let doc_id: String = row.get(0)?;
-            let value: String = row.get(1)?;
-            results.insert(doc_id, value);
+            let link: String = row.get(0)?;
+            let chunk_id: String = row.get(1)?;
+            results.entry(link).or_default().push(chunk_id);
         }
 
         Ok(results)
     }
 }
+
+#[async_trait]
+impl CollectionSearch for SqliteProvider {
+    async fn collection_document_ids(
+        &self,
+        collection: &str,
+        owner_id: Option<&str>,
+        org_id: Option<&str>,
+    ) -> Result<Vec<String>, turso::Error> {
+        let conn = self.pool.read().await?;
+        let mut params: Vec<TursoValue> = vec![collection.into(), collection.into()];
+        let mut collection_condition = "(c.id = ? OR c.name = ?)".to_string();
+        if let Some(owner) = owner_id {
+            collection_condition.push_str(" AND c.owner_id = ?");
+            params.push(owner.into());
+        }
+        let (visibility, visibility_params) = visibility_condition(owner_id, org_id);
+        params.extend(visibility_params);
+        let sql = format!(
+            "SELECT DISTINCT d.id
+             FROM collections c
+             JOIN document_collections dc ON dc.collection_id = c.id
+             JOIN documents d ON d.id = dc.document_id
+             WHERE {collection_condition} AND {visibility}"
+        );
+
+        let mut result_set = conn.query(&sql, params).await?;
+        let mut document_ids = Vec::new();
+        while let Some(row) = result_set.next().await? {
+            document_ids.push(row.get(0)?);
+        }
+        Ok(document_ids)
+    }
+}
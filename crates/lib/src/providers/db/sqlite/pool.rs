@@ -0,0 +1,253 @@
+//! # SQLite Connection Pool
+//!
+//! SQLite serves many readers at once but only one writer. This module keeps a pool of
+//! read connections for reuse, lets a single writer at a time hold the database's write
+//! permit, and retries operations that fail because the database is busy.
+//!
+//! Ingestors write through connections of their own and may spend most of their time
+//! fetching content or calling an LLM. They run inside `WriteLock::scope` instead of
+//! holding the permit, and their writes take it for as long as each one runs, through
+//! `write_scoped`.
+//!
+//! Every connection opened here waits up to `busy_timeout` for a lock before failing.
+//! Pooled read connections keep their cache of prepared statements between uses.
+
+use super::statements::StatementCache;
+use crate::types::SqliteSettings;
+use std::{
+    fmt::Display,
+    future::Future,
+    ops::Deref,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::warn;
+use turso::{Connection, Database, Statement};
+
+/// The delay before the first retry of a busy operation; it doubles with every retry.
+const BUSY_RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+/// The parts of the messages SQLite fails with while another connection holds a lock.
+const BUSY_ERROR_MARKERS: &[&str] = &["database is locked", "database is busy", "sqlite_busy"];
+
+/// The connections of a database, shared by the clones of its `SqliteProvider`.
+pub struct ConnectionPool {
+    db: Database,
+    settings: SqliteSettings,
+    idle: Mutex<Vec<(Connection, StatementCache)>>,
+    writer: Arc<Semaphore>,
+}
+
+impl ConnectionPool {
+    pub(crate) fn new(db: Database, settings: SqliteSettings) -> Self {
+        Self {
+            db,
+            settings,
+            idle: Mutex::new(Vec::new()),
+            writer: Arc::new(Semaphore::new(1)),
+        }
+    }
+
+    /// The settings the pool was created with.
+    pub fn settings(&self) -> SqliteSettings {
+        self.settings
+    }
+
+    /// Returns an idle read connection, or opens a new one if none is left. The
+    /// connection goes back to the pool when dropped.
+    pub async fn read(self: &Arc<Self>) -> Result<PooledConnection, turso::Error> {
+        let idle = self
+            .idle
+            .lock()
+            .expect("connection pool lock poisoned")
+            .pop();
+        let (conn, statements) = match idle {
+            Some(idle) => idle,
+            None => (
+                self.connect().await?,
+                StatementCache::new(self.settings.statement_cache_size),
+            ),
+        };
+        Ok(PooledConnection {
+            conn: Some(conn),
+            statements,
+            pool: self.clone(),
+        })
+    }
+
+    /// Waits for the write permit and returns a connection to write with. Other writers
+    /// through this pool wait until it is dropped.
+    pub async fn write(&self) -> Result<WriteConnection, turso::Error> {
+        let permit = self.write_permit().await;
+        let conn = self.connect().await?;
+        Ok(WriteConnection {
+            conn,
+            _permit: permit,
+        })
+    }
+
+    /// The write permit and retry settings of the database, to run ingestions with.
+    pub fn write_lock(&self) -> WriteLock {
+        WriteLock {
+            writer: self.writer.clone(),
+            busy_retries: self.settings.busy_retries,
+        }
+    }
+
+    /// Waits for the write permit, for callers that write through connections of their
+    /// own.
+    pub async fn write_permit(&self) -> OwnedSemaphorePermit {
+        self.writer
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the write semaphore is never closed")
+    }
+
+    async fn connect(&self) -> Result<Connection, turso::Error> {
+        let conn = self.db.connect()?;
+        let busy_timeout = format!(
+            "PRAGMA busy_timeout = {};",
+            self.settings.busy_timeout.as_millis()
+        );
+        // Use `query` for PRAGMA statements that return a value to avoid "unexpected row" errors.
+        if let Err(e) = conn.query(&busy_timeout, ()).await {
+            warn!("Could not set the busy timeout of a connection: {e}");
+        }
+        Ok(conn)
+    }
+
+    fn release(&self, conn: Connection, statements: StatementCache) {
+        let mut idle = self.idle.lock().expect("connection pool lock poisoned");
+        if idle.len() < self.settings.read_pool_size {
+            idle.push((conn, statements));
+        }
+    }
+}
+
+/// A read connection borrowed from a `ConnectionPool`.
+pub struct PooledConnection {
+    conn: Option<Connection>,
+    statements: StatementCache,
+    pool: Arc<ConnectionPool>,
+}
+
+impl PooledConnection {
+    /// Returns the prepared statement for `sql` from the connection's cache, preparing
+    /// it on first use.
+    pub async fn prepare_cached(&mut self, sql: &str) -> Result<&mut Statement, turso::Error> {
+        let conn = self
+            .conn
+            .as_ref()
+            .expect("connection is present until dropped");
+        self.statements.prepare(conn, sql).await
+    }
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+            .as_ref()
+            .expect("connection is present until dropped")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            let statements = std::mem::replace(&mut self.statements, StatementCache::new(0));
+            self.pool.release(conn, statements);
+        }
+    }
+}
+
+/// A connection holding the write permit of its database.
+pub struct WriteConnection {
+    conn: Connection,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Deref for WriteConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+tokio::task_local! {
+    /// The write lock of the database the running ingestion writes to.
+    static WRITE_LOCK: WriteLock;
+}
+
+/// The write permit of a database, taken by the writes of an ingestion only while they
+/// run. See `WriteLock::scope`.
+#[derive(Clone)]
+pub struct WriteLock {
+    writer: Arc<Semaphore>,
+    busy_retries: u32,
+}
+
+impl WriteLock {
+    /// Runs `future` with the lock in scope, so that its `write_scoped` writes wait for
+    /// the database's write permit and retry while the database is busy.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        WRITE_LOCK.scope(self, future).await
+    }
+}
+
+/// Runs a write, retrying it while the database is busy. Inside `WriteLock::scope`, the
+/// write holds the database's write permit while it runs; outside of one, it is retried
+/// as often as the default settings allow.
+pub async fn write_scoped<T, E, F, Fut>(operation: F) -> Result<T, E>
+where
+    E: Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    match WRITE_LOCK.try_with(WriteLock::clone) {
+        Ok(lock) => {
+            let _permit = lock
+                .writer
+                .acquire()
+                .await
+                .expect("the write semaphore is never closed");
+            retry_on_busy(lock.busy_retries, operation).await
+        }
+        Err(_) => retry_on_busy(SqliteSettings::default().busy_retries, operation).await,
+    }
+}
+
+/// Whether an error means another connection held a lock for longer than the busy
+/// timeout.
+pub fn is_busy(error: &impl Display) -> bool {
+    let message = error.to_string().to_lowercase();
+    BUSY_ERROR_MARKERS
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+/// Runs an operation, retrying it up to `retries` times with exponential backoff while it
+/// fails because the database is busy. A busy operation has not changed the database,
+/// so running it again is safe.
+pub async fn retry_on_busy<T, E, F, Fut>(retries: u32, mut operation: F) -> Result<T, E>
+where
+    E: Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut attempt = 0;
+    loop {
+        match operation().await {
+            Err(e) if attempt < retries && is_busy(&e) => {
+                let delay = BUSY_RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt));
+                attempt += 1;
+                warn!("Database busy ({e}); retry {attempt} of {retries} in {delay:?}.");
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
@@ -5,6 +5,15 @@
 
 // --- Core Schema Definitions (V2 - Normalized) ---
 
+/// SQL to create the `schema_migrations` table, which records the migrations applied.
+pub const CREATE_SCHEMA_MIGRATIONS_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS schema_migrations (
+        version INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    );
+";
+
 /// SQL to create the `users` table.
 pub const CREATE_USERS_TABLE_SQL: &str = "
     CREATE TABLE IF NOT EXISTS users (
@@ -22,12 +31,22 @@ pub const CREATE_DOCUMENTS_TABLE_SQL: &str = "
         source_url TEXT,
         title TEXT,
         content TEXT NOT NULL,
+        content_hash TEXT, -- Used to skip duplicate chunks of the same owner
+        org_id TEXT, -- Nullable; shares the document with the members of an organization
         created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
         expires_at DATETIME,
         FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE CASCADE
     );
+    CREATE INDEX IF NOT EXISTS idx_documents_owner_content_hash ON documents(owner_id, content_hash);
 ";
 
+/// SQL to add the `content_hash` column to a `documents` table created before it existed.
+pub const ADD_DOCUMENTS_CONTENT_HASH_SQL: &str =
+    "ALTER TABLE documents ADD COLUMN content_hash TEXT";
+
+/// SQL to add the `org_id` column to a `documents` table created before it existed.
+pub const ADD_DOCUMENTS_ORG_ID_SQL: &str = "ALTER TABLE documents ADD COLUMN org_id TEXT";
+
 /// SQL to create the `document_embeddings` table, optimized for vector search.
 pub const CREATE_DOCUMENT_EMBEDDINGS_TABLE_SQL: &str = "
     CREATE TABLE IF NOT EXISTS document_embeddings (
@@ -56,11 +75,353 @@ pub const CREATE_CONTENT_METADATA_TABLE_SQL: &str = "
     CREATE INDEX IF NOT EXISTS idx_metadata_owner_id ON content_metadata(owner_id);
 ";
 
-/// An array containing all the schema creation SQL statements.
-/// This allows them to be executed in order to set up a new database.
-pub const ALL_TABLE_CREATION_SQL: &[&str] = &[
-    CREATE_USERS_TABLE_SQL,
-    CREATE_DOCUMENTS_TABLE_SQL,
-    CREATE_DOCUMENT_EMBEDDINGS_TABLE_SQL,
-    CREATE_CONTENT_METADATA_TABLE_SQL,
-];
+/// SQL to create the `conversations` table, which stores chat messages by session.
+pub const CREATE_CONVERSATIONS_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS conversations (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        session_id TEXT NOT NULL,
+        owner_id TEXT, -- Nullable for guest sessions
+        role TEXT NOT NULL, -- 'user', 'assistant'
+        content TEXT NOT NULL,
+        created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE CASCADE
+    );
+    CREATE INDEX IF NOT EXISTS idx_conversations_session_id ON conversations(session_id);
+";
+
+/// SQL to create the `schema_annotations` table, which stores user-supplied descriptions
+/// and example values for tables and columns. An empty `db` refers to the default
+/// database, and an empty `column_name` annotates the table itself.
+pub const CREATE_SCHEMA_ANNOTATIONS_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS schema_annotations (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        db TEXT NOT NULL DEFAULT '',
+        table_name TEXT NOT NULL,
+        column_name TEXT NOT NULL DEFAULT '',
+        description TEXT,
+        examples TEXT NOT NULL DEFAULT '[]', -- JSON array of example values
+        updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE INDEX IF NOT EXISTS idx_schema_annotations_table ON schema_annotations(db, table_name);
+";
+
+/// SQL to add the `references_column` column to the `schema_annotations` table. It holds
+/// the `table.column` an annotated column references, declaring a relationship the
+/// database has no foreign key for.
+pub const ADD_SCHEMA_ANNOTATIONS_REFERENCES_SQL: &str =
+    "ALTER TABLE schema_annotations ADD COLUMN references_column TEXT";
+
+/// SQL to create the `answer_cache` table, which stores synthesized knowledge answers
+/// with the embedding of their question, to serve near-duplicate questions while the
+/// documents they were answered from are unchanged. Empty `owner_id`, `org_id`, `db`
+/// and `instruction` values mean none was given.
+pub const CREATE_ANSWER_CACHE_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS answer_cache (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        owner_id TEXT NOT NULL DEFAULT '',
+        org_id TEXT NOT NULL DEFAULT '',
+        db TEXT NOT NULL DEFAULT '',
+        instruction TEXT NOT NULL DEFAULT '',
+        question TEXT NOT NULL,
+        embedding BLOB NOT NULL,
+        answer TEXT NOT NULL,
+        corpus_version TEXT NOT NULL, -- The hash of the documents visible when answered
+        created_at TEXT NOT NULL,
+        expires_at TEXT NOT NULL
+    )
+";
+
+/// SQL to index the `answer_cache` table by the scope answers are looked up in.
+pub const CREATE_ANSWER_CACHE_INDEX_SQL: &str =
+    "CREATE INDEX IF NOT EXISTS idx_answer_cache_scope ON answer_cache(owner_id, org_id, db)";
+
+/// SQL to create the `faq_items` table, which stores the question and answer pairs
+/// distilled from documents, along with the document each pair was distilled from.
+pub const CREATE_FAQ_ITEMS_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS faq_items (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        document_id TEXT NOT NULL,
+        owner_id TEXT,
+        question TEXT NOT NULL,
+        answer TEXT NOT NULL,
+        source_url TEXT, -- The link of the document when the pair was distilled
+        is_explicit INTEGER NOT NULL DEFAULT 0, -- 1 when the document states the question itself
+        created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE CASCADE
+    )
+";
+
+/// SQL to index the `faq_items` table by the document each pair was distilled from.
+pub const CREATE_FAQ_ITEMS_INDEX_SQL: &str =
+    "CREATE INDEX IF NOT EXISTS idx_faq_items_document_id ON faq_items(document_id)";
+
+/// SQL to create the `document_revisions` table, which keeps the versions of documents
+/// replaced when their `source_url` was ingested again.
+pub const CREATE_DOCUMENT_REVISIONS_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS document_revisions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        document_id TEXT NOT NULL,
+        revision INTEGER NOT NULL, -- 1 for the first version of the document
+        owner_id TEXT,
+        title TEXT,
+        content TEXT NOT NULL,
+        content_hash TEXT,
+        change_ratio REAL NOT NULL, -- How much the next version changed it, from 0 to 1
+        replaced_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE CASCADE
+    )
+";
+
+/// SQL to index the `document_revisions` table by document and version.
+pub const CREATE_DOCUMENT_REVISIONS_INDEX_SQL: &str = "CREATE UNIQUE INDEX IF NOT EXISTS idx_document_revisions_document_revision ON document_revisions(document_id, revision)";
+
+/// SQL to add the `deleted_at` column to the `documents` table. Documents with a
+/// `deleted_at` are in the trash, and hidden from search until restored or purged.
+pub const ADD_DOCUMENTS_DELETED_AT_SQL: &str =
+    "ALTER TABLE documents ADD COLUMN deleted_at DATETIME";
+
+/// SQL to index the `documents` table by deletion time, for listing and purging the trash.
+pub const CREATE_DOCUMENTS_DELETED_AT_INDEX_SQL: &str =
+    "CREATE INDEX IF NOT EXISTS idx_documents_deleted_at ON documents(deleted_at)";
+
+/// SQL to create the `collections` table, which holds the named collections users
+/// group their documents in, such as "API docs" or "HR policies".
+pub const CREATE_COLLECTIONS_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS collections (
+        id TEXT PRIMARY KEY,
+        owner_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        description TEXT,
+        created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE UNIQUE INDEX IF NOT EXISTS idx_collections_owner_name ON collections(owner_id, name);
+";
+
+/// SQL to create the `document_collections` table, which puts documents in collections.
+pub const CREATE_DOCUMENT_COLLECTIONS_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS document_collections (
+        collection_id TEXT NOT NULL,
+        document_id TEXT NOT NULL,
+        added_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (collection_id, document_id),
+        FOREIGN KEY (collection_id) REFERENCES collections(id) ON DELETE CASCADE,
+        FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE CASCADE
+    );
+    CREATE INDEX IF NOT EXISTS idx_document_collections_document_id ON document_collections(document_id);
+";
+
+/// SQL to create the `pipeline_progress` table, which records the outcome of each item
+/// of each stage of an enrichment pipeline so that a run can resume an earlier one.
+pub const CREATE_PIPELINE_PROGRESS_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS pipeline_progress (
+        pipeline TEXT NOT NULL,
+        stage TEXT NOT NULL,
+        item_id TEXT NOT NULL, -- The document id, or the source for the `summarize` stage
+        status TEXT NOT NULL, -- 'done' or 'failed'
+        error TEXT,
+        attempts INTEGER NOT NULL DEFAULT 1,
+        updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (pipeline, stage, item_id)
+    )
+";
+
+/// SQL to create the `experiments` table, which records one row per request served
+/// by an A/B experiment along with its outcome.
+pub const CREATE_EXPERIMENTS_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS experiments (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        experiment TEXT NOT NULL,
+        variant TEXT NOT NULL, -- 'a', 'b'
+        task TEXT NOT NULL, -- The task configuration that served the request
+        latency_ms INTEGER NOT NULL,
+        prompt_tokens INTEGER NOT NULL,
+        completion_tokens INTEGER NOT NULL,
+        success INTEGER NOT NULL,
+        feedback INTEGER, -- 1 for positive, -1 for negative, NULL when not given
+        created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE INDEX IF NOT EXISTS idx_experiments_experiment ON experiments(experiment);
+";
+
+/// SQL to create the `feedback` table, which stores user ratings of answers.
+pub const CREATE_FEEDBACK_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS feedback (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        owner_id TEXT, -- Nullable for guest feedback
+        result_id TEXT NOT NULL, -- The client-supplied id of the rated prompt result
+        prompt TEXT NOT NULL,
+        positive INTEGER NOT NULL,
+        correction TEXT,
+        accepted INTEGER NOT NULL DEFAULT 0,
+        created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE CASCADE
+    );
+    CREATE INDEX IF NOT EXISTS idx_feedback_owner_id ON feedback(owner_id);
+";
+
+/// SQL to create the `few_shot_examples` table, which stores accepted corrections
+/// that are shown to the model as examples for similar prompts.
+pub const CREATE_FEW_SHOT_EXAMPLES_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS few_shot_examples (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        prompt TEXT NOT NULL,
+        answer TEXT NOT NULL,
+        feedback_id INTEGER,
+        created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (feedback_id) REFERENCES feedback(id) ON DELETE SET NULL
+    );
+";
+
+/// SQL to add the `owner_id` column to the `few_shot_examples` table, so each user is
+/// only shown the corrections to their own feedback.
+pub const ADD_FEW_SHOT_EXAMPLES_OWNER_ID_SQL: &str =
+    "ALTER TABLE few_shot_examples ADD COLUMN owner_id TEXT";
+
+/// SQL to give the examples accepted before `owner_id` existed the owner of their feedback.
+pub const BACKFILL_FEW_SHOT_EXAMPLES_OWNER_ID_SQL: &str = "
+    UPDATE few_shot_examples
+    SET owner_id = (SELECT f.owner_id FROM feedback f WHERE f.id = few_shot_examples.feedback_id)
+    WHERE owner_id IS NULL
+";
+
+/// SQL to index the `few_shot_examples` table by owner.
+pub const CREATE_FEW_SHOT_EXAMPLES_OWNER_ID_INDEX_SQL: &str =
+    "CREATE INDEX IF NOT EXISTS idx_few_shot_examples_owner_id ON few_shot_examples(owner_id)";
+
+/// SQL to create the `web_sources` table, which remembers the HTTP validators and the
+/// content hash of each ingested web page so unchanged pages are not re-processed.
+/// An empty `owner_id` refers to public content.
+pub const CREATE_WEB_SOURCES_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS web_sources (
+        source_url TEXT NOT NULL,
+        owner_id TEXT NOT NULL DEFAULT '',
+        etag TEXT,
+        last_modified TEXT,
+        content_hash TEXT,
+        fetched_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (source_url, owner_id)
+    );
+";
+
+/// SQL to create the `object_store_manifest` table, which remembers the ETag of each
+/// ingested bucket object so unchanged objects are skipped on the next run.
+/// An empty `owner_id` refers to public content.
+pub const CREATE_OBJECT_STORE_MANIFEST_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS object_store_manifest (
+        object_url TEXT NOT NULL,
+        owner_id TEXT NOT NULL DEFAULT '',
+        etag TEXT NOT NULL,
+        ingested_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (object_url, owner_id)
+    );
+";
+
+/// SQL to create the `api_keys` table, which stores the keys services authenticate
+/// with instead of a JWT. Only the SHA-256 hash of each key is kept.
+pub const CREATE_API_KEYS_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS api_keys (
+        id TEXT PRIMARY KEY,
+        owner_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        key_hash TEXT NOT NULL,
+        key_prefix TEXT NOT NULL, -- The first characters of the key, for display
+        scopes TEXT NOT NULL, -- Comma-separated: 'ingest', 'search', 'prompt', 'admin'
+        created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        last_used_at DATETIME,
+        FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE CASCADE
+    );
+    CREATE INDEX IF NOT EXISTS idx_api_keys_key_hash ON api_keys(key_hash);
+";
+
+/// SQL to create the `role_permissions` table, which grants `resource:action`
+/// permissions to roles. Roles without rows use their built-in permissions.
+pub const CREATE_ROLE_PERMISSIONS_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS role_permissions (
+        role TEXT NOT NULL,
+        permission TEXT NOT NULL, -- e.g., 'ingest:write', 'admin:*'
+        PRIMARY KEY (role, permission)
+    );
+";
+
+/// SQL to seed `role_permissions` with the built-in permissions of the `root` and
+/// `user` roles, for the roles that have no rows yet. Roles with rows keep them.
+pub const SEED_ROLE_PERMISSIONS_SQL: &str = "
+    INSERT INTO role_permissions (role, permission)
+    SELECT defaults.role, defaults.permission FROM (
+        SELECT 'root' AS role, '*:*' AS permission
+        UNION ALL SELECT 'user', 'ingest:write'
+        UNION ALL SELECT 'user', 'search:read'
+        UNION ALL SELECT 'user', 'prompt:execute'
+    ) AS defaults
+    WHERE NOT EXISTS (
+        SELECT 1 FROM role_permissions existing WHERE existing.role = defaults.role
+    )
+";
+
+/// SQL to create the `organizations` and `org_members` tables, which let documents be
+/// shared with a team through `documents.org_id`.
+pub const CREATE_ORGANIZATIONS_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS organizations (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE TABLE IF NOT EXISTS org_members (
+        org_id TEXT NOT NULL,
+        user_id TEXT NOT NULL,
+        role TEXT NOT NULL DEFAULT 'member', -- 'owner', 'member'
+        joined_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (org_id, user_id),
+        FOREIGN KEY (org_id) REFERENCES organizations(id) ON DELETE CASCADE,
+        FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+    );
+    CREATE INDEX IF NOT EXISTS idx_org_members_user_id ON org_members(user_id);
+    CREATE INDEX IF NOT EXISTS idx_documents_org_id ON documents(org_id);
+";
+
+/// SQL to create the `document_shares` table, which grants individual users read
+/// access to a document they don't own.
+pub const CREATE_DOCUMENT_SHARES_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS document_shares (
+        document_id TEXT NOT NULL,
+        user_id TEXT NOT NULL,
+        granted_by TEXT NOT NULL,
+        created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (document_id, user_id),
+        FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE CASCADE,
+        FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+    );
+    CREATE INDEX IF NOT EXISTS idx_document_shares_user_id ON document_shares(user_id);
+";
+
+/// SQL to create the `sessions` table. A session is started at sign-in and holds the
+/// SHA-256 hash of its refresh token. Access tokens name their session, so revoking it
+/// invalidates them before they expire.
+pub const CREATE_SESSIONS_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS sessions (
+        id TEXT PRIMARY KEY,
+        user_id TEXT NOT NULL,
+        subject TEXT NOT NULL, -- The `sub` of the access tokens issued for the session
+        refresh_token_hash TEXT NOT NULL,
+        created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+        expires_at DATETIME NOT NULL, -- When the refresh token expires
+        refreshed_at DATETIME,
+        revoked_at DATETIME,
+        FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+    );
+    CREATE INDEX IF NOT EXISTS idx_sessions_refresh_token_hash ON sessions(refresh_token_hash);
+    CREATE INDEX IF NOT EXISTS idx_sessions_user_id ON sessions(user_id);
+";
+
+/// SQL to create the `usage_counters` table, which counts what each user consumed per
+/// calendar month for their quotas.
+pub const CREATE_USAGE_COUNTERS_TABLE_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS usage_counters (
+        user_id TEXT NOT NULL,
+        period TEXT NOT NULL, -- The month, e.g. '2026-10'
+        metric TEXT NOT NULL, -- e.g. 'ai_calls'
+        count INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (user_id, period, metric),
+        FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+    );
+";
@@ -0,0 +1,80 @@
+//! # Prepared Statement Cache
+//!
+//! Preparing a statement parses and plans its SQL, which dominates loops that run the
+//! same few statements for thousands of rows. A `StatementCache` keeps the prepared
+//! statements of one connection by SQL text, dropping the least recently used one once
+//! it holds `capacity` of them.
+//!
+//! A cached statement belongs to the connection it was prepared on, so a cache must only
+//! ever be used with a single connection.
+
+use std::collections::HashMap;
+use turso::{Connection, Statement};
+
+/// The prepared statements of a connection, keyed by SQL text.
+pub struct StatementCache {
+    capacity: usize,
+    statements: HashMap<String, (Statement, u64)>,
+    tick: u64,
+}
+
+impl StatementCache {
+    /// Creates a cache holding at most `capacity` statements, and at least one.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            statements: HashMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// Returns the prepared statement for `sql`, preparing it on `conn` if it is not
+    /// cached yet.
+    pub async fn prepare(
+        &mut self,
+        conn: &Connection,
+        sql: &str,
+    ) -> Result<&mut Statement, turso::Error> {
+        self.tick += 1;
+        if !self.statements.contains_key(sql) {
+            let statement = conn.prepare(sql).await?;
+            if self.statements.len() >= self.capacity {
+                self.evict_least_recently_used();
+            }
+            self.statements
+                .insert(sql.to_string(), (statement, self.tick));
+        }
+        let (statement, last_used) = self
+            .statements
+            .get_mut(sql)
+            .expect("the statement was just cached");
+        *last_used = self.tick;
+        Ok(statement)
+    }
+
+    /// Whether the statement for `sql` is cached.
+    pub fn contains(&self, sql: &str) -> bool {
+        self.statements.contains_key(sql)
+    }
+
+    /// The number of cached statements.
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    /// Whether no statement is cached.
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let least_recently_used = self
+            .statements
+            .iter()
+            .min_by_key(|(_, (_, last_used))| *last_used)
+            .map(|(sql, _)| sql.clone());
+        if let Some(sql) = least_recently_used {
+            self.statements.remove(&sql);
+        }
+    }
+}
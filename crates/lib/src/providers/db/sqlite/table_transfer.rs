@@ -0,0 +1,843 @@
+//! # Table Export and Import
+//!
+//! Copies a single table of a local database, such as one made by `dump firebase`, to
+//! or from a CSV, JSON Lines or Parquet file (the latter behind the `parquet` feature).
+//! Rows are streamed: an export writes each row as it is read, and an import inserts
+//! the rows in batches as they are parsed, so tables larger than memory can be copied.
+//!
+//! Blobs are written to CSV and JSON Lines as `\x` followed by their bytes in
+//! hexadecimal, and read back as blobs. In CSV, an empty field is `NULL`.
+
+use super::backup::quote_identifier;
+use crate::ingest::bulk::bulk_insert_rows;
+use serde::Serialize;
+use std::{
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Cursor, Read, Write},
+    path::Path,
+    str::FromStr,
+};
+use thiserror::Error;
+use tracing::info;
+use turso::{Connection, Database, Value as TursoValue};
+
+/// The number of rows an import inserts at once, and a Parquet export writes per batch.
+const BATCH_ROWS: usize = 1024;
+
+/// Custom error types for table exports and imports.
+#[derive(Error, Debug)]
+pub enum TableTransferError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid CSV: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("Invalid JSON on line {line}: {message}")]
+    Json { line: usize, message: String },
+    #[error("Unknown table format '{0}'; expected 'csv', 'jsonl' or 'parquet'.")]
+    UnknownFormat(String),
+    #[error("Table '{0}' does not exist.")]
+    TableNotFound(String),
+    #[error("Table '{table}' has no column {}.", .columns.join(", "))]
+    UnknownColumns { table: String, columns: Vec<String> },
+    #[error("The file has no columns to import.")]
+    NoColumns,
+    #[error("Column '{column}' holds a value that is not {expected}.")]
+    UnexpectedValue {
+        column: String,
+        expected: &'static str,
+    },
+    #[error("The 'parquet' feature is not enabled.")]
+    ParquetFeatureNotEnabled,
+    #[cfg(feature = "parquet")]
+    #[error("Invalid Parquet: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    #[cfg(feature = "parquet")]
+    #[error("Failed to convert the Parquet columns: {0}")]
+    Arrow(#[from] arrow::error::ArrowError),
+}
+
+/// The file formats a table can be exported to and imported from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableFormat {
+    /// A header row with the column names, then one record per row.
+    Csv,
+    /// One JSON object per row, keyed by column name.
+    #[default]
+    Jsonl,
+    /// One Parquet column per table column, typed by its declared type.
+    Parquet,
+}
+
+impl TableFormat {
+    /// The file extension of a table file in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TableFormat::Csv => "csv",
+            TableFormat::Jsonl => "jsonl",
+            TableFormat::Parquet => "parquet",
+        }
+    }
+}
+
+impl FromStr for TableFormat {
+    type Err = TableTransferError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(TableFormat::Csv),
+            "jsonl" => Ok(TableFormat::Jsonl),
+            "parquet" => Ok(TableFormat::Parquet),
+            other => Err(TableTransferError::UnknownFormat(other.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for TableFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+/// The rows read and stored by an import.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TableImportSummary {
+    /// The number of rows in the file.
+    pub rows_read: u64,
+    /// The number of rows inserted. Rows whose key the table already holds are skipped.
+    pub rows_inserted: u64,
+    /// Whether the table was created by the import.
+    pub table_created: bool,
+}
+
+/// Writes every row of `table` to a new file at `path`. Returns the number of rows
+/// written.
+pub async fn export_table_to_file(
+    db: &Database,
+    table: &str,
+    format: TableFormat,
+    path: &Path,
+) -> Result<u64, TableTransferError> {
+    let conn = db.connect()?;
+    let columns = table_columns(&conn, table).await?;
+    if columns.is_empty() {
+        return Err(TableTransferError::TableNotFound(table.to_string()));
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut writer = RowWriter::new(format, BufWriter::new(File::create(path)?), &columns)?;
+
+    let column_list = columns
+        .iter()
+        .map(|column| quote_identifier(&column.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut rows = conn
+        .query(
+            &format!("SELECT {column_list} FROM {}", quote_identifier(table)),
+            (),
+        )
+        .await?;
+    let mut exported = 0;
+    while let Some(row) = rows.next().await? {
+        let values = (0..columns.len())
+            .map(|i| row.get_value(i))
+            .collect::<Result<Vec<TursoValue>, _>>()?;
+        writer.write_row(values)?;
+        exported += 1;
+    }
+    writer.finish()?;
+    info!(
+        "Exported {exported} rows of '{table}' as {format} to '{}'.",
+        path.display()
+    );
+    Ok(exported)
+}
+
+/// Inserts the rows of the file at `path` into `table` in one transaction, creating the
+/// table when it does not exist. The types of a new table's columns are inferred from
+/// the first rows.
+pub async fn import_table_from_file(
+    db: &Database,
+    path: &Path,
+    table: &str,
+    format: TableFormat,
+) -> Result<TableImportSummary, TableTransferError> {
+    let file = File::open(path)?;
+    let (columns, rows) = match format {
+        TableFormat::Csv => csv_rows(BufReader::new(file))?,
+        TableFormat::Jsonl => jsonl_rows(file)?,
+        TableFormat::Parquet => parquet_rows(file)?,
+    };
+    let summary = import_in_transaction(db, table, columns, rows).await?;
+    info!(
+        "Imported {} of {} rows from '{}' into '{table}'.",
+        summary.rows_inserted,
+        summary.rows_read,
+        path.display()
+    );
+    Ok(summary)
+}
+
+/// Inserts the rows of CSV content already in memory, such as a downloaded sheet, into
+/// `table` as [`import_table_from_file`] does for a CSV file.
+pub async fn import_table_from_csv(
+    db: &Database,
+    csv_content: &str,
+    table: &str,
+) -> Result<TableImportSummary, TableTransferError> {
+    let (columns, rows) = csv_rows(Cursor::new(csv_content.as_bytes().to_vec()))?;
+    let summary = import_in_transaction(db, table, columns, rows).await?;
+    info!(
+        "Imported {} of {} CSV rows into '{table}'.",
+        summary.rows_inserted, summary.rows_read
+    );
+    Ok(summary)
+}
+
+async fn import_in_transaction(
+    db: &Database,
+    table: &str,
+    columns: Vec<String>,
+    rows: RowIter,
+) -> Result<TableImportSummary, TableTransferError> {
+    if columns.is_empty() {
+        return Err(TableTransferError::NoColumns);
+    }
+
+    let conn = db.connect()?;
+    conn.execute("BEGIN", ()).await?;
+    let result = import_rows(&conn, table, columns, rows).await;
+    let end = match result {
+        Ok(_) => "COMMIT",
+        Err(_) => "ROLLBACK",
+    };
+    conn.execute(end, ()).await?;
+    result
+}
+
+/// The rows of an import file, as the values of its columns in order.
+type RowIter = Box<dyn Iterator<Item = Result<Vec<TursoValue>, TableTransferError>> + Send>;
+
+async fn import_rows(
+    conn: &Connection,
+    table: &str,
+    columns: Vec<String>,
+    mut rows: RowIter,
+) -> Result<TableImportSummary, TableTransferError> {
+    let first_rows = rows
+        .by_ref()
+        .take(BATCH_ROWS)
+        .map(|row| row.map(|row| row.into_iter().map(decode_blob).collect::<Vec<_>>()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut summary = TableImportSummary::default();
+
+    let mut existing = table_columns(conn, table).await?;
+    if existing.is_empty() {
+        let definitions = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let sql_type = infer_sql_type(first_rows.iter().map(|row| &row[i]));
+                format!("{} {sql_type}", quote_identifier(column))
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        conn.execute(
+            &format!("CREATE TABLE {} ({definitions})", quote_identifier(table)),
+            (),
+        )
+        .await?;
+        summary.table_created = true;
+        existing = table_columns(conn, table).await?;
+    }
+    let unknown: Vec<String> = columns
+        .iter()
+        .filter(|column| !existing.iter().any(|c| &c.name == *column))
+        .cloned()
+        .collect();
+    if !unknown.is_empty() {
+        return Err(TableTransferError::UnknownColumns {
+            table: table.to_string(),
+            columns: unknown,
+        });
+    }
+    let affinities: Vec<Affinity> = columns
+        .iter()
+        .map(|column| {
+            existing
+                .iter()
+                .find(|c| &c.name == column)
+                .map_or(Affinity::Blob, |c| affinity(&c.declared_type))
+        })
+        .collect();
+
+    let insert = format!(
+        "INSERT INTO {} ({})",
+        quote_identifier(table),
+        columns
+            .iter()
+            .map(|column| quote_identifier(column))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let mut batch = Vec::with_capacity(BATCH_ROWS);
+    for row in first_rows.into_iter().map(Ok).chain(rows) {
+        let row = row?
+            .into_iter()
+            .zip(&affinities)
+            .map(|(value, affinity)| apply_affinity(decode_blob(value), *affinity))
+            .collect();
+        batch.push(row);
+        summary.rows_read += 1;
+        if batch.len() == BATCH_ROWS {
+            summary.rows_inserted += bulk_insert_rows(
+                conn,
+                &insert,
+                "ON CONFLICT DO NOTHING",
+                std::mem::take(&mut batch),
+            )
+            .await?;
+        }
+    }
+    summary.rows_inserted +=
+        bulk_insert_rows(conn, &insert, "ON CONFLICT DO NOTHING", batch).await?;
+    Ok(summary)
+}
+
+fn csv_rows(
+    input: impl Read + Send + 'static,
+) -> Result<(Vec<String>, RowIter), TableTransferError> {
+    let mut reader = csv::Reader::from_reader(input);
+    let columns: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+    let rows = reader.into_records().map(|record| {
+        Ok(record?
+            .iter()
+            .map(|field| match field {
+                "" => TursoValue::Null,
+                text => TursoValue::Text(text.to_string()),
+            })
+            .collect())
+    });
+    Ok((columns, Box::new(rows)))
+}
+
+fn jsonl_rows(file: File) -> Result<(Vec<String>, RowIter), TableTransferError> {
+    let mut objects = BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|(index, line)| {
+            let line_number = index + 1;
+            let json_error = |message: String| TableTransferError::Json {
+                line: line_number,
+                message,
+            };
+            match serde_json::from_str(&line?) {
+                Ok(serde_json::Value::Object(object)) => Ok((line_number, object)),
+                Ok(_) => Err(json_error("expected an object".to_string())),
+                Err(e) => Err(json_error(e.to_string())),
+            }
+        });
+    // The columns are the keys of the first row; later rows may leave some out.
+    let first = objects.next().transpose()?;
+    let columns: Vec<String> = first
+        .as_ref()
+        .map(|(_, object)| object.keys().cloned().collect())
+        .unwrap_or_default();
+    let row_columns = columns.clone();
+    let rows = first.map(Ok).into_iter().chain(objects).map(move |object| {
+        let (line, mut object) = object?;
+        let row = row_columns
+            .iter()
+            .map(|column| {
+                object
+                    .remove(column)
+                    .map_or(TursoValue::Null, json_to_value)
+            })
+            .collect();
+        if !object.is_empty() {
+            let extra: Vec<String> = object.keys().cloned().collect();
+            return Err(TableTransferError::Json {
+                line,
+                message: format!("columns not in the first row: {}", extra.join(", ")),
+            });
+        }
+        Ok(row)
+    });
+    Ok((columns, Box::new(rows)))
+}
+
+#[cfg(feature = "parquet")]
+fn parquet_rows(file: File) -> Result<(Vec<String>, RowIter), TableTransferError> {
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
+    let columns: Vec<String> = builder
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().clone())
+        .collect();
+    let reader = builder.with_batch_size(BATCH_ROWS).build()?;
+    let rows = reader.flat_map(|batch| {
+        match batch
+            .map_err(TableTransferError::from)
+            .and_then(|batch| parquet_file::batch_rows(&batch))
+        {
+            Ok(rows) => rows.into_iter().map(Ok).collect::<Vec<_>>(),
+            Err(e) => vec![Err(e)],
+        }
+    });
+    Ok((columns, Box::new(rows)))
+}
+
+#[cfg(not(feature = "parquet"))]
+fn parquet_rows(_file: File) -> Result<(Vec<String>, RowIter), TableTransferError> {
+    Err(TableTransferError::ParquetFeatureNotEnabled)
+}
+
+/// Writes the rows of an export in its format.
+enum RowWriter {
+    Csv(csv::Writer<BufWriter<File>>),
+    Jsonl(BufWriter<File>, Vec<String>),
+    #[cfg(feature = "parquet")]
+    Parquet(parquet_file::ParquetRowWriter),
+}
+
+impl RowWriter {
+    fn new(
+        format: TableFormat,
+        output: BufWriter<File>,
+        columns: &[Column],
+    ) -> Result<Self, TableTransferError> {
+        let names: Vec<String> = columns.iter().map(|c| c.name.clone()).collect();
+        match format {
+            TableFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(output);
+                writer.write_record(&names)?;
+                Ok(RowWriter::Csv(writer))
+            }
+            TableFormat::Jsonl => Ok(RowWriter::Jsonl(output, names)),
+            #[cfg(feature = "parquet")]
+            TableFormat::Parquet => Ok(RowWriter::Parquet(parquet_file::ParquetRowWriter::new(
+                output, columns,
+            )?)),
+            #[cfg(not(feature = "parquet"))]
+            TableFormat::Parquet => Err(TableTransferError::ParquetFeatureNotEnabled),
+        }
+    }
+
+    fn write_row(&mut self, values: Vec<TursoValue>) -> Result<(), TableTransferError> {
+        match self {
+            RowWriter::Csv(writer) => {
+                let fields: Vec<String> = values.into_iter().map(value_to_text).collect();
+                writer.write_record(&fields)?;
+            }
+            RowWriter::Jsonl(output, names) => {
+                let object: serde_json::Map<String, serde_json::Value> = names
+                    .iter()
+                    .cloned()
+                    .zip(values.into_iter().map(value_to_json))
+                    .collect();
+                serde_json::to_writer(&mut *output, &object).map_err(std::io::Error::from)?;
+                output.write_all(b"\n")?;
+            }
+            #[cfg(feature = "parquet")]
+            RowWriter::Parquet(writer) => writer.write_row(values)?,
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), TableTransferError> {
+        match self {
+            RowWriter::Csv(mut writer) => writer.flush()?,
+            RowWriter::Jsonl(mut output, _) => output.flush()?,
+            #[cfg(feature = "parquet")]
+            RowWriter::Parquet(writer) => writer.finish()?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_file {
+    //! The Parquet side of table transfers, with one Arrow array per column.
+
+    use super::{affinity, blob_to_text, Affinity, Column, TableTransferError, BATCH_ROWS};
+    use arrow::{
+        array::{
+            Array, ArrayRef, AsArray, BinaryBuilder, Float64Builder, Int64Builder, StringBuilder,
+        },
+        compute::cast,
+        datatypes::{DataType, Field, Float64Type, Int64Type, Schema, SchemaRef},
+        record_batch::RecordBatch,
+    };
+    use parquet::arrow::ArrowWriter;
+    use std::{fs::File, io::BufWriter, sync::Arc};
+    use turso::Value as TursoValue;
+
+    /// Buffers rows and writes them as record batches of `BATCH_ROWS` rows.
+    pub(super) struct ParquetRowWriter {
+        writer: ArrowWriter<BufWriter<File>>,
+        schema: SchemaRef,
+        pending: Vec<Vec<TursoValue>>,
+    }
+
+    impl ParquetRowWriter {
+        pub(super) fn new(
+            output: BufWriter<File>,
+            columns: &[Column],
+        ) -> Result<Self, TableTransferError> {
+            let schema = Arc::new(Schema::new(
+                columns
+                    .iter()
+                    .map(|column| {
+                        // Columns without a type, or of `NUMERIC` type, may hold any
+                        // value, so they are written as strings.
+                        let data_type = match affinity(&column.declared_type) {
+                            Affinity::Integer => DataType::Int64,
+                            Affinity::Real => DataType::Float64,
+                            Affinity::Blob if !column.declared_type.is_empty() => DataType::Binary,
+                            _ => DataType::Utf8,
+                        };
+                        Field::new(&column.name, data_type, true)
+                    })
+                    .collect::<Vec<_>>(),
+            ));
+            Ok(Self {
+                writer: ArrowWriter::try_new(output, schema.clone(), None)?,
+                schema,
+                pending: Vec::with_capacity(BATCH_ROWS),
+            })
+        }
+
+        pub(super) fn write_row(
+            &mut self,
+            values: Vec<TursoValue>,
+        ) -> Result<(), TableTransferError> {
+            self.pending.push(values);
+            if self.pending.len() == BATCH_ROWS {
+                self.flush()?;
+            }
+            Ok(())
+        }
+
+        pub(super) fn finish(mut self) -> Result<(), TableTransferError> {
+            self.flush()?;
+            self.writer.close()?;
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), TableTransferError> {
+            if self.pending.is_empty() {
+                return Ok(());
+            }
+            let rows = std::mem::take(&mut self.pending);
+            let arrays = (0..self.schema.fields().len())
+                .map(|i| self.column_array(&rows, i))
+                .collect::<Result<Vec<ArrayRef>, _>>()?;
+            self.writer
+                .write(&RecordBatch::try_new(self.schema.clone(), arrays)?)?;
+            Ok(())
+        }
+
+        fn column_array(
+            &self,
+            rows: &[Vec<TursoValue>],
+            index: usize,
+        ) -> Result<ArrayRef, TableTransferError> {
+            let unexpected = |expected| TableTransferError::UnexpectedValue {
+                column: self.schema.field(index).name().clone(),
+                expected,
+            };
+            let values = rows.iter().map(|row| &row[index]);
+            Ok(match self.schema.field(index).data_type() {
+                DataType::Int64 => {
+                    let mut builder = Int64Builder::with_capacity(rows.len());
+                    for value in values {
+                        match value {
+                            TursoValue::Null => builder.append_null(),
+                            TursoValue::Integer(integer) => builder.append_value(*integer),
+                            TursoValue::Real(real) if real.fract() == 0.0 => {
+                                builder.append_value(*real as i64)
+                            }
+                            TursoValue::Text(text) => builder.append_value(
+                                text.trim().parse().map_err(|_| unexpected("an integer"))?,
+                            ),
+                            _ => return Err(unexpected("an integer")),
+                        }
+                    }
+                    Arc::new(builder.finish())
+                }
+                DataType::Float64 => {
+                    let mut builder = Float64Builder::with_capacity(rows.len());
+                    for value in values {
+                        match value {
+                            TursoValue::Null => builder.append_null(),
+                            TursoValue::Integer(integer) => builder.append_value(*integer as f64),
+                            TursoValue::Real(real) => builder.append_value(*real),
+                            TursoValue::Text(text) => builder.append_value(
+                                text.trim().parse().map_err(|_| unexpected("a number"))?,
+                            ),
+                            TursoValue::Blob(_) => return Err(unexpected("a number")),
+                        }
+                    }
+                    Arc::new(builder.finish())
+                }
+                DataType::Binary => {
+                    let mut builder = BinaryBuilder::new();
+                    for value in values {
+                        match value {
+                            TursoValue::Null => builder.append_null(),
+                            TursoValue::Blob(bytes) => builder.append_value(bytes),
+                            TursoValue::Text(text) => builder.append_value(text.as_bytes()),
+                            _ => return Err(unexpected("a blob")),
+                        }
+                    }
+                    Arc::new(builder.finish())
+                }
+                _ => {
+                    let mut builder = StringBuilder::new();
+                    for value in values {
+                        match value {
+                            TursoValue::Null => builder.append_null(),
+                            TursoValue::Text(text) => builder.append_value(text),
+                            TursoValue::Integer(integer) => {
+                                builder.append_value(integer.to_string())
+                            }
+                            TursoValue::Real(real) => builder.append_value(real.to_string()),
+                            TursoValue::Blob(bytes) => builder.append_value(blob_to_text(bytes)),
+                        }
+                    }
+                    Arc::new(builder.finish())
+                }
+            })
+        }
+    }
+
+    /// Reads the rows of a record batch, with integers, floats, binaries and strings
+    /// kept as such and other columns converted to strings.
+    pub(super) fn batch_rows(
+        batch: &RecordBatch,
+    ) -> Result<Vec<Vec<TursoValue>>, TableTransferError> {
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|column| {
+                let data_type = column.data_type();
+                let target = if data_type.is_integer() || *data_type == DataType::Boolean {
+                    DataType::Int64
+                } else if data_type.is_floating() {
+                    DataType::Float64
+                } else if matches!(
+                    data_type,
+                    DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_)
+                ) {
+                    DataType::Binary
+                } else {
+                    DataType::Utf8
+                };
+                cast(column, &target)
+            })
+            .collect::<Result<Vec<ArrayRef>, _>>()?;
+
+        Ok((0..batch.num_rows())
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|column| {
+                        if column.is_null(row) {
+                            return TursoValue::Null;
+                        }
+                        match column.data_type() {
+                            DataType::Int64 => {
+                                TursoValue::Integer(column.as_primitive::<Int64Type>().value(row))
+                            }
+                            DataType::Float64 => {
+                                TursoValue::Real(column.as_primitive::<Float64Type>().value(row))
+                            }
+                            DataType::Binary => {
+                                TursoValue::Blob(column.as_binary::<i32>().value(row).to_vec())
+                            }
+                            _ => TursoValue::Text(column.as_string::<i32>().value(row).to_string()),
+                        }
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+}
+
+/// A column of a table with its declared type.
+struct Column {
+    name: String,
+    declared_type: String,
+}
+
+/// How SQLite converts the values stored in a column, from its declared type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Affinity {
+    Integer,
+    Real,
+    Numeric,
+    Text,
+    /// No conversion, for columns declared `BLOB` or without a type.
+    Blob,
+}
+
+/// Follows SQLite's rules for the affinity of a declared type.
+fn affinity(declared_type: &str) -> Affinity {
+    let declared_type = declared_type.to_ascii_uppercase();
+    if declared_type.contains("INT") {
+        Affinity::Integer
+    } else if ["CHAR", "CLOB", "TEXT"]
+        .iter()
+        .any(|t| declared_type.contains(t))
+    {
+        Affinity::Text
+    } else if declared_type.is_empty() || declared_type.contains("BLOB") {
+        Affinity::Blob
+    } else if ["REAL", "FLOA", "DOUB"]
+        .iter()
+        .any(|t| declared_type.contains(t))
+    {
+        Affinity::Real
+    } else {
+        Affinity::Numeric
+    }
+}
+
+/// Converts a value read from a file as SQLite would when storing it in a column of
+/// the given affinity, so text read from CSV lands in numeric columns as numbers.
+fn apply_affinity(value: TursoValue, affinity: Affinity) -> TursoValue {
+    match (affinity, value) {
+        (Affinity::Integer | Affinity::Numeric, TursoValue::Text(text)) => {
+            match (text.trim().parse::<i64>(), text.trim().parse::<f64>()) {
+                (Ok(integer), _) => TursoValue::Integer(integer),
+                (_, Ok(real)) => TursoValue::Real(real),
+                _ => TursoValue::Text(text),
+            }
+        }
+        (Affinity::Real, TursoValue::Text(text)) => match text.trim().parse::<f64>() {
+            Ok(real) => TursoValue::Real(real),
+            Err(_) => TursoValue::Text(text),
+        },
+        (Affinity::Real, TursoValue::Integer(integer)) => TursoValue::Real(integer as f64),
+        (Affinity::Text, TursoValue::Integer(integer)) => TursoValue::Text(integer.to_string()),
+        (Affinity::Text, TursoValue::Real(real)) => TursoValue::Text(real.to_string()),
+        (_, value) => value,
+    }
+}
+
+/// Picks the type of a new column from its first values: `INTEGER` or `REAL` when they
+/// all read as such, `BLOB` when they are all blobs, and `TEXT` otherwise.
+fn infer_sql_type<'a>(values: impl Iterator<Item = &'a TursoValue>) -> &'static str {
+    let mut sql_type = None;
+    for value in values {
+        let value_type = match value {
+            TursoValue::Null => continue,
+            TursoValue::Integer(_) => "INTEGER",
+            TursoValue::Real(_) => "REAL",
+            TursoValue::Blob(_) => "BLOB",
+            TursoValue::Text(text) if text.trim().parse::<i64>().is_ok() => "INTEGER",
+            TursoValue::Text(text) if text.trim().parse::<f64>().is_ok() => "REAL",
+            TursoValue::Text(_) => return "TEXT",
+        };
+        sql_type = match (sql_type, value_type) {
+            (None, value_type) => Some(value_type),
+            (Some(current), value_type) if current == value_type => Some(current),
+            (Some("INTEGER" | "REAL"), "INTEGER" | "REAL") => Some("REAL"),
+            _ => return "TEXT",
+        };
+    }
+    sql_type.unwrap_or("TEXT")
+}
+
+async fn table_columns(conn: &Connection, table: &str) -> Result<Vec<Column>, turso::Error> {
+    let mut rows = conn
+        .query(
+            &format!("PRAGMA table_info({})", quote_identifier(table)),
+            (),
+        )
+        .await?;
+    let mut columns = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let TursoValue::Text(name) = row.get_value(1)? {
+            let declared_type = match row.get_value(2)? {
+                TursoValue::Text(declared_type) => declared_type,
+                _ => String::new(),
+            };
+            columns.push(Column {
+                name,
+                declared_type,
+            });
+        }
+    }
+    Ok(columns)
+}
+
+fn value_to_text(value: TursoValue) -> String {
+    match value {
+        TursoValue::Null => String::new(),
+        TursoValue::Integer(integer) => integer.to_string(),
+        TursoValue::Real(real) => real.to_string(),
+        TursoValue::Text(text) => text,
+        TursoValue::Blob(bytes) => blob_to_text(&bytes),
+    }
+}
+
+fn value_to_json(value: TursoValue) -> serde_json::Value {
+    match value {
+        TursoValue::Null => serde_json::Value::Null,
+        TursoValue::Integer(integer) => integer.into(),
+        TursoValue::Real(real) => serde_json::Number::from_f64(real)
+            .map_or(serde_json::Value::Null, serde_json::Value::Number),
+        TursoValue::Text(text) => text.into(),
+        TursoValue::Blob(bytes) => blob_to_text(&bytes).into(),
+    }
+}
+
+fn json_to_value(value: serde_json::Value) -> TursoValue {
+    match value {
+        serde_json::Value::Null => TursoValue::Null,
+        serde_json::Value::Bool(boolean) => TursoValue::Integer(boolean.into()),
+        serde_json::Value::Number(number) => match number.as_i64() {
+            Some(integer) => TursoValue::Integer(integer),
+            None => TursoValue::Real(number.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(text) => TursoValue::Text(text),
+        // Arrays and objects are stored as their JSON text.
+        other => TursoValue::Text(other.to_string()),
+    }
+}
+
+/// The prefix of a blob written as text, as in PostgreSQL.
+const BLOB_TEXT_PREFIX: &str = "\\x";
+
+/// Writes a blob as `\x` followed by its bytes in hexadecimal.
+fn blob_to_text(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("{BLOB_TEXT_PREFIX}{hex}")
+}
+
+/// Reads back a blob written by `blob_to_text`, leaving other values as they are.
+fn decode_blob(value: TursoValue) -> TursoValue {
+    let TursoValue::Text(text) = &value else {
+        return value;
+    };
+    let Some(hex) = text.strip_prefix(BLOB_TEXT_PREFIX) else {
+        return value;
+    };
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return value;
+    }
+    let bytes: Option<Vec<u8>> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect();
+    bytes.map_or(value, TursoValue::Blob)
+}
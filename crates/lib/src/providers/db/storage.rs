@@ -1,7 +1,7 @@
 use crate::{
     errors::PromptError,
     search::SearchError,
-    types::{SearchResult, TableSchema},
+    types::{QueryLimits, SearchResult, TableSchema},
 };
 use async_trait::async_trait;
 use dyn_clone::DynClone;
@@ -21,9 +21,15 @@ pub trait Storage: Send + Sync + DynClone + Debug {
     /// Returns the query language used by the storage provider (e.g., "SQL").
     fn language(&self) -> &str;
 
+    /// Returns the execution limits enforced by `execute_query`.
+    fn query_limits(&self) -> QueryLimits {
+        QueryLimits::default()
+    }
+
     /// Executes a query against the storage provider.
     ///
-    /// The result should be a JSON formatted string.
+    /// The result should be a JSON formatted string. Implementations must abort the query
+    /// after `query_limits().timeout` and return at most `query_limits().max_rows` rows.
     async fn execute_query(&self, query: &str) -> Result<String, PromptError>;
 
     /// Retrieves the schema for a given table.
@@ -38,12 +44,14 @@ dyn_clone::clone_trait_object!(Storage);
 /// A trait for providers that support vector similarity search.
 #[async_trait]
 pub trait VectorSearch: Send + Sync + DynClone + Debug {
-    /// Performs a vector similarity search.
+    /// Performs a vector similarity search. With an `org_id`, the documents shared with
+    /// the organization are searched too.
     async fn vector_search(
         &self,
         query_vector: Vec<f32>,
         limit: u32,
         owner_id: Option<&str>,
+        org_id: Option<&str>,
         document_ids: Option<&[String]>,
     ) -> Result<Vec<SearchResult>, SearchError>;
 }
@@ -53,12 +61,14 @@ dyn_clone::clone_trait_object!(VectorSearch);
 /// A trait for providers that support keyword search.
 #[async_trait]
 pub trait KeywordSearch: Send + Sync + DynClone + Debug {
-    /// Performs a keyword search.
+    /// Performs a keyword search. With an `org_id`, the documents shared with the
+    /// organization are searched too.
     async fn keyword_search(
         &self,
         query: &str,
         limit: u32,
         owner_id: Option<&str>,
+        org_id: Option<&str>,
         document_ids: Option<&[String]>,
     ) -> Result<Vec<SearchResult>, SearchError>;
 }
@@ -68,13 +78,16 @@ dyn_clone::clone_trait_object!(KeywordSearch);
 /// A trait for providers that support metadata search.
 #[async_trait]
 pub trait MetadataSearch: Send + Sync + DynClone + Debug {
-    /// Performs a search on the metadata table for documents.
+    /// Performs a search on the metadata table for documents. With `document_ids`, only
+    /// those documents are searched.
     async fn metadata_search(
         &self,
         entities: &[String],
         keyphrases: &[String],
         owner_id: Option<&str>,
+        org_id: Option<&str>,
         limit: u32,
+        document_ids: Option<&[String]>,
     ) -> Result<Vec<SearchResult>, SearchError>;
 }
 
@@ -83,13 +96,45 @@ dyn_clone::clone_trait_object!(MetadataSearch);
 /// A trait for providers that support temporal property searches.
 #[async_trait]
 pub trait TemporalSearch: Send + Sync + DynClone + Debug {
-    /// Fetches a specific string property for a set of document IDs.
-    async fn get_string_properties_for_documents(
+    /// Fetches the `PROPERTY` metadata of the documents with the given links, as
+    /// `(name, value)` pairs keyed by link. Links without properties are left out.
+    async fn get_properties_for_links(
         &self,
-        doc_ids: &[&str],
-        property_name: &str,
+        links: &[&str],
         owner_id: Option<&str>,
-    ) -> Result<HashMap<String, String>, turso::Error>;
+    ) -> Result<HashMap<String, Vec<(String, String)>>, turso::Error>;
 }
 
 dyn_clone::clone_trait_object!(TemporalSearch);
+
+/// A trait for providers that store summary documents of long sources.
+#[async_trait]
+pub trait SummarySearch: Send + Sync + DynClone + Debug {
+    /// Fetches the ids of the chunks each of the summary documents with the given links
+    /// summarizes, keyed by link. With an `org_id`, the summaries shared with the
+    /// organization are expanded too.
+    async fn get_summarized_chunk_ids(
+        &self,
+        links: &[&str],
+        owner_id: Option<&str>,
+        org_id: Option<&str>,
+    ) -> Result<HashMap<String, Vec<String>>, turso::Error>;
+}
+
+dyn_clone::clone_trait_object!(SummarySearch);
+
+/// A trait for providers that group documents into collections.
+#[async_trait]
+pub trait CollectionSearch: Send + Sync + DynClone + Debug {
+    /// Fetches the ids of the documents in a collection, given by id or name, that are
+    /// visible to the owner. With an owner, only their own collections are matched. An
+    /// unknown collection has no documents.
+    async fn collection_document_ids(
+        &self,
+        collection: &str,
+        owner_id: Option<&str>,
+        org_id: Option<&str>,
+    ) -> Result<Vec<String>, turso::Error>;
+}
+
+dyn_clone::clone_trait_object!(CollectionSearch);
@@ -1,3 +1,4 @@
 pub mod ai;
 pub mod db;
 pub mod factory;
+pub mod moderation;
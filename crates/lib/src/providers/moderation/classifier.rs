@@ -0,0 +1,46 @@
+use super::{ModerationProvider, ModerationVerdict};
+use crate::{
+    errors::PromptError, ingest::knowledge::clean_llm_response, providers::ai::AiProvider,
+};
+use async_trait::async_trait;
+use tracing::debug;
+
+/// A moderation provider that prompts a classifier model, such as a local model,
+/// with the `content_moderation` task.
+///
+/// The model must answer with a JSON object like `{"flagged": true, "categories":
+/// ["harassment"]}`. Any other answer is an error, so that the moderation policy
+/// decides what happens to the text.
+#[derive(Clone, Debug)]
+pub struct ClassifierModerationProvider {
+    ai_provider: Box<dyn AiProvider>,
+    system_prompt: String,
+    user_prompt_template: String,
+}
+
+impl ClassifierModerationProvider {
+    pub fn new(
+        ai_provider: Box<dyn AiProvider>,
+        system_prompt: impl Into<String>,
+        user_prompt_template: impl Into<String>,
+    ) -> Self {
+        Self {
+            ai_provider,
+            system_prompt: system_prompt.into(),
+            user_prompt_template: user_prompt_template.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl ModerationProvider for ClassifierModerationProvider {
+    async fn moderate(&self, text: &str) -> Result<ModerationVerdict, PromptError> {
+        let user_prompt = self.user_prompt_template.replace("{content}", text);
+        let response = self
+            .ai_provider
+            .generate(&self.system_prompt, &user_prompt)
+            .await?;
+        debug!("LLM moderation response: {response}");
+        Ok(serde_json::from_str(&clean_llm_response(&response))?)
+    }
+}
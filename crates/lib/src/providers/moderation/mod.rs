@@ -0,0 +1,30 @@
+pub mod classifier;
+pub mod openai;
+
+use crate::errors::PromptError;
+use async_trait::async_trait;
+pub use classifier::ClassifierModerationProvider;
+pub use openai::OpenAiModerationProvider;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+/// The judgement of a moderation provider on a text.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ModerationVerdict {
+    /// Whether the text violates the provider's policy.
+    pub flagged: bool,
+    /// The policy categories the text violates (e.g., "harassment", "self-harm").
+    #[serde(default)]
+    pub categories: Vec<String>,
+}
+
+/// A trait for checking texts against a content policy.
+///
+/// Implementations wrap a dedicated moderation API (e.g., OpenAI's moderation
+/// endpoint) or a classifier model prompted through an `AiProvider`.
+#[async_trait]
+pub trait ModerationProvider: Send + Sync + Debug {
+    /// Returns whether `text` violates the policy, and which categories it violates.
+    async fn moderate(&self, text: &str) -> Result<ModerationVerdict, PromptError>;
+}
@@ -0,0 +1,107 @@
+use super::{ModerationProvider, ModerationVerdict};
+use crate::errors::PromptError;
+use async_trait::async_trait;
+use reqwest::Client as ReqwestClient;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::debug;
+
+/// The moderation endpoint of the OpenAI API.
+pub const DEFAULT_OPENAI_MODERATION_URL: &str = "https://api.openai.com/v1/moderations";
+/// The moderation model used when none is configured.
+pub const DEFAULT_OPENAI_MODERATION_MODEL: &str = "omni-moderation-latest";
+
+// --- OpenAI moderation request and response structures ---
+
+#[derive(Serialize, Debug)]
+struct ModerationRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+}
+
+#[derive(Deserialize, Debug)]
+struct ModerationResponse {
+    results: Vec<ModerationResultResponse>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ModerationResultResponse {
+    flagged: bool,
+    #[serde(default)]
+    categories: BTreeMap<String, bool>,
+}
+
+// --- OpenAI Moderation Provider implementation ---
+
+/// A moderation provider for OpenAI's moderation endpoint, or any API compatible
+/// with it.
+#[derive(Clone, Debug)]
+pub struct OpenAiModerationProvider {
+    client: ReqwestClient,
+    api_url: String,
+    api_key: String,
+    model: String,
+}
+
+impl OpenAiModerationProvider {
+    /// Creates a new `OpenAiModerationProvider`. The OpenAI endpoint and model are used
+    /// when `api_url` or `model` are not given.
+    pub fn new(
+        api_key: String,
+        api_url: Option<String>,
+        model: Option<String>,
+    ) -> Result<Self, PromptError> {
+        let client = ReqwestClient::builder()
+            .build()
+            .map_err(PromptError::ReqwestClientBuild)?;
+        Ok(Self {
+            client,
+            api_url: api_url.unwrap_or_else(|| DEFAULT_OPENAI_MODERATION_URL.to_string()),
+            api_key,
+            model: model.unwrap_or_else(|| DEFAULT_OPENAI_MODERATION_MODEL.to_string()),
+        })
+    }
+}
+
+#[async_trait]
+impl ModerationProvider for OpenAiModerationProvider {
+    async fn moderate(&self, text: &str) -> Result<ModerationVerdict, PromptError> {
+        let request_body = ModerationRequest {
+            model: &self.model,
+            input: text,
+        };
+        debug!("--> Sending moderation request to {}", self.api_url);
+        let response = self
+            .client
+            .post(&self.api_url)
+            .bearer_auth(&self.api_key)
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(PromptError::AiRequest)?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(PromptError::AiApi(error_text));
+        }
+
+        let moderation: ModerationResponse = response
+            .json()
+            .await
+            .map_err(PromptError::AiDeserialization)?;
+        let Some(result) = moderation.results.into_iter().next() else {
+            return Err(PromptError::AiApi(
+                "The moderation response has no results.".to_string(),
+            ));
+        };
+        Ok(ModerationVerdict {
+            flagged: result.flagged,
+            categories: result
+                .categories
+                .into_iter()
+                .filter(|(_, violated)| *violated)
+                .map(|(category, _)| category)
+                .collect(),
+        })
+    }
+}
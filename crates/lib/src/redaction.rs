@@ -0,0 +1,297 @@
+//! # PII Redaction
+//!
+//! Support tickets and chat logs are full of emails, phone numbers and account ids
+//! that must not be stored or sent to a model as they are. A `Redactor` masks them
+//! with `[REDACTED_<TYPE>]` placeholders: the built-in detectors and the configured
+//! patterns find them with regular expressions, and the optional `pii_detection` task
+//! asks a model for those the expressions miss (e.g., names and street addresses).
+//!
+//! The redaction reports how many values of each type it masked, so they can be
+//! recorded with the document they were found in.
+
+use crate::errors::PromptError;
+use crate::ingest::knowledge::clean_llm_response;
+use crate::providers::ai::AiProvider;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+use tracing::{debug, warn};
+
+/// Custom error types for redaction.
+#[derive(Error, Debug)]
+pub enum RedactionError {
+    #[error("Invalid redaction pattern '{name}': {source}")]
+    InvalidPattern {
+        name: String,
+        #[source]
+        source: regex::Error,
+    },
+}
+
+/// A kind of personal data the redactor detects without configuration.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiType {
+    Email,
+    Phone,
+    /// Card numbers that pass the Luhn checksum.
+    CreditCard,
+    IpAddress,
+}
+
+impl PiiType {
+    pub const ALL: [PiiType; 4] = [
+        PiiType::Email,
+        PiiType::CreditCard,
+        PiiType::IpAddress,
+        PiiType::Phone,
+    ];
+
+    /// The name the type is recorded and masked with, e.g. `EMAIL`.
+    pub fn label(&self) -> &'static str {
+        match self {
+            PiiType::Email => "EMAIL",
+            PiiType::Phone => "PHONE",
+            PiiType::CreditCard => "CREDIT_CARD",
+            PiiType::IpAddress => "IP_ADDRESS",
+        }
+    }
+
+    fn pattern(&self) -> &'static str {
+        match self {
+            PiiType::Email => r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+            PiiType::Phone => {
+                r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{2,4}\)|\b\d{2,4})[\s.-]\d{3,4}[\s.-]\d{3,4}\b"
+            }
+            PiiType::CreditCard => r"\b(?:\d[ -]?){12,18}\d\b",
+            PiiType::IpAddress => {
+                r"\b(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)\b"
+            }
+        }
+    }
+}
+
+/// A named regular expression for data specific to a deployment, such as account ids.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct RedactionPattern {
+    /// The type the matches are recorded as, e.g. `account_id`.
+    pub name: String,
+    pub pattern: String,
+}
+
+/// Configuration for redacting personal data.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct RedactionConfig {
+    /// The built-in detectors to run. All of them by default.
+    #[serde(default = "default_detectors")]
+    pub detectors: Vec<PiiType>,
+    /// Additional patterns to redact, matched before the built-in detectors.
+    #[serde(default)]
+    pub patterns: Vec<RedactionPattern>,
+    /// Whether the content of ingested documents is redacted before it is searched.
+    #[serde(default = "default_redact_ingested")]
+    pub redact_ingested: bool,
+    /// Whether ingested documents are also sent to the `pii_detection` task, after the
+    /// regular expressions have masked what they found.
+    #[serde(default)]
+    pub llm_detection: bool,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            detectors: default_detectors(),
+            patterns: Vec::new(),
+            redact_ingested: default_redact_ingested(),
+            llm_detection: false,
+        }
+    }
+}
+
+fn default_detectors() -> Vec<PiiType> {
+    PiiType::ALL.to_vec()
+}
+
+fn default_redact_ingested() -> bool {
+    true
+}
+
+/// A value the `pii_detection` task found in a text.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct DetectedPii {
+    #[serde(rename = "type")]
+    pub pii_type: String,
+    pub text: String,
+}
+
+/// A redacted text, with the number of values masked for each type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Redaction {
+    pub text: String,
+    pub detected: BTreeMap<String, usize>,
+}
+
+impl Redaction {
+    /// Whether anything was masked.
+    pub fn is_redacted(&self) -> bool {
+        !self.detected.is_empty()
+    }
+
+    /// Masks every occurrence of the values found by the `pii_detection` task.
+    pub fn mask_detected(&mut self, found: &[DetectedPii]) {
+        for pii in found {
+            let value = pii.text.trim();
+            let label = label_of(&pii.pii_type);
+            if value.is_empty() || label.is_empty() {
+                continue;
+            }
+            let occurrences = self.text.matches(value).count();
+            if occurrences == 0 {
+                continue;
+            }
+            self.text = self.text.replace(value, &placeholder(&label));
+            *self.detected.entry(label).or_default() += occurrences;
+        }
+    }
+}
+
+/// A regular expression and the type of its matches.
+#[derive(Debug, Clone)]
+struct Matcher {
+    label: String,
+    regex: Regex,
+    /// Whether matches must pass the Luhn checksum, as card numbers do.
+    luhn_checked: bool,
+}
+
+/// Masks personal data in texts with the configured detectors.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    /// The expressions to apply, in order.
+    matchers: Vec<Matcher>,
+}
+
+impl Redactor {
+    /// Compiles the detectors and patterns of the configuration.
+    pub fn new(config: &RedactionConfig) -> Result<Self, RedactionError> {
+        let mut matchers = Vec::new();
+        for custom in &config.patterns {
+            let regex =
+                Regex::new(&custom.pattern).map_err(|source| RedactionError::InvalidPattern {
+                    name: custom.name.clone(),
+                    source,
+                })?;
+            matchers.push(Matcher {
+                label: label_of(&custom.name),
+                regex,
+                luhn_checked: false,
+            });
+        }
+        // Card numbers are matched before phone numbers, whose pattern would match
+        // their first groups of digits.
+        for pii_type in PiiType::ALL {
+            if config.detectors.contains(&pii_type) {
+                matchers.push(Matcher {
+                    label: pii_type.label().to_string(),
+                    regex: Regex::new(pii_type.pattern()).expect("built-in patterns are valid"),
+                    luhn_checked: pii_type == PiiType::CreditCard,
+                });
+            }
+        }
+        Ok(Self { matchers })
+    }
+
+    /// Masks the values the regular expressions match in `text`.
+    pub fn redact(&self, text: &str) -> Redaction {
+        let mut redaction = Redaction {
+            text: text.to_string(),
+            detected: BTreeMap::new(),
+        };
+        for matcher in &self.matchers {
+            let mut count = 0;
+            let masked = matcher
+                .regex
+                .replace_all(&redaction.text, |caps: &Captures| {
+                    let value = &caps[0];
+                    if matcher.luhn_checked && !passes_luhn(value) {
+                        return value.to_string();
+                    }
+                    count += 1;
+                    placeholder(&matcher.label)
+                });
+            if count > 0 {
+                redaction.text = masked.into_owned();
+                *redaction.detected.entry(matcher.label.clone()).or_default() += count;
+            }
+        }
+        redaction
+    }
+
+    /// Masks the values the regular expressions match, then asks the AI provider for
+    /// the personal data left in the masked text and masks it too.
+    pub async fn redact_with_llm(
+        &self,
+        text: &str,
+        ai_provider: &dyn AiProvider,
+        system_prompt: &str,
+        user_prompt_template: &str,
+    ) -> Result<Redaction, PromptError> {
+        let mut redaction = self.redact(text);
+        let found = detect_pii(
+            ai_provider,
+            &redaction.text,
+            system_prompt,
+            user_prompt_template,
+        )
+        .await?;
+        redaction.mask_detected(&found);
+        Ok(redaction)
+    }
+}
+
+/// Asks the AI provider for the personal data in `content`.
+///
+/// Returns nothing when the response is not a JSON array of detected values, so one
+/// unreadable response does not fail an ingestion.
+pub async fn detect_pii(
+    ai_provider: &dyn AiProvider,
+    content: &str,
+    system_prompt: &str,
+    user_prompt_template: &str,
+) -> Result<Vec<DetectedPii>, PromptError> {
+    let user_prompt = user_prompt_template.replace("{content}", content);
+    let response = ai_provider.generate(system_prompt, &user_prompt).await?;
+    debug!("LLM PII detection response: {response}");
+
+    match serde_json::from_str::<Vec<DetectedPii>>(&clean_llm_response(&response)) {
+        Ok(found) => Ok(found),
+        Err(e) => {
+            warn!("Failed to parse detected PII, skipping. Error: {e}");
+            Ok(Vec::new())
+        }
+    }
+}
+
+fn label_of(name: &str) -> String {
+    name.trim().to_uppercase().replace([' ', '-'], "_")
+}
+
+fn placeholder(label: &str) -> String {
+    format!("[REDACTED_{label}]")
+}
+
+fn passes_luhn(value: &str) -> bool {
+    let digits: Vec<u32> = value.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2 == 1, d * 2) {
+            (true, doubled) if doubled > 9 => doubled - 9,
+            (true, doubled) => doubled,
+            (false, _) => d,
+        })
+        .sum();
+    sum % 10 == 0
+}
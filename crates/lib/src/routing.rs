@@ -0,0 +1,340 @@
+//! # Answer Routing
+//!
+//! A cheap classification call that decides how a question is answered: with text-to-SQL
+//! over a table, with a knowledge search over documents, with a knowledge graph lookup,
+//! or directly by the model. `RoutingConfig` selects the routes offered and how they are
+//! described to the classifier.
+
+use crate::ingest::knowledge::clean_llm_response;
+use crate::types::{ContentType, ExecutePromptOptions, PipelineStage, PipelineStep};
+use crate::{PromptClient, PromptError};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt::Debug;
+use std::time::Instant;
+use tracing::{info, instrument, warn};
+
+/// A way of answering a question.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Route {
+    /// Text-to-SQL over the tables of the storage provider.
+    Sql,
+    /// A knowledge search over the ingested documents.
+    Knowledge,
+    /// A lookup of facts in the knowledge graph.
+    Graph,
+    /// An answer from the model alone.
+    Direct,
+}
+
+impl Route {
+    pub const ALL: [Route; 4] = [Route::Sql, Route::Knowledge, Route::Graph, Route::Direct];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Route::Sql => "sql",
+            Route::Knowledge => "knowledge",
+            Route::Graph => "graph",
+            Route::Direct => "direct",
+        }
+    }
+
+    /// How the route is described to the classifier when the configuration gives no
+    /// description.
+    pub fn default_description(&self) -> &'static str {
+        match self {
+            Route::Sql => "Questions that count, filter, aggregate or list rows of the database tables.",
+            Route::Knowledge => "Questions answered by the ingested documents, such as how-tos, policies and explanations.",
+            Route::Graph => "Questions about a specific entity's relationships or attributes, possibly at a point in time (e.g., who someone worked for in 2021).",
+            Route::Direct => "General questions, greetings and tasks that need no stored data.",
+        }
+    }
+
+    /// The task whose prompts write the answer on this route.
+    pub fn task_name(&self) -> &'static str {
+        match self {
+            Route::Sql => "query_generation",
+            Route::Knowledge | Route::Graph => "rag_synthesis",
+            Route::Direct => "direct_generation",
+        }
+    }
+}
+
+/// A route offered to the classifier.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct RouteConfig {
+    pub route: Route,
+    /// Tells the classifier which questions belong on the route. Defaults to
+    /// `Route::default_description`.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Configuration for routing prompts between answering strategies.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct RoutingConfig {
+    /// The routes offered, all of them by default.
+    #[serde(default = "default_routes")]
+    pub routes: Vec<RouteConfig>,
+    /// The route taken when the classifier's answer cannot be used.
+    #[serde(default = "default_fallback_route")]
+    pub fallback: Route,
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            routes: default_routes(),
+            fallback: default_fallback_route(),
+        }
+    }
+}
+
+fn default_routes() -> Vec<RouteConfig> {
+    Route::ALL
+        .into_iter()
+        .map(|route| RouteConfig {
+            route,
+            description: None,
+        })
+        .collect()
+}
+
+fn default_fallback_route() -> Route {
+    Route::Direct
+}
+
+/// A route offered to the classifier, with the prompts that write its answer.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct RouteChoice {
+    pub route: Route,
+    pub description: String,
+    /// Replaces the default system prompt of the route's answer.
+    #[serde(default)]
+    pub system_prompt_template: Option<String>,
+    /// Replaces the default user prompt of the route's answer.
+    #[serde(default)]
+    pub user_prompt_template: Option<String>,
+}
+
+/// The routing stage of a prompt execution.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct RoutingOptions {
+    pub routes: Vec<RouteChoice>,
+    pub fallback: Route,
+    /// The system prompt of the classification call.
+    pub system_prompt: String,
+    /// The user prompt of the classification call, with `{routes}` and `{prompt}`
+    /// placeholders.
+    pub user_prompt_template: String,
+}
+
+/// The route a prompt was answered on, and why.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct RouteDecision {
+    pub route: Route,
+    /// The classifier's reason for the route.
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Whether the classifier chose the route. It is not asked when a single route is
+    /// available, and its answer is replaced by the fallback when it cannot be used.
+    pub classified: bool,
+    /// The route chosen first, when it found nothing to answer from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_from: Option<Route>,
+}
+
+/// Retrieves the context of the routes that need more than the storage provider: the
+/// documents of a knowledge search and the facts of a graph lookup.
+#[async_trait]
+pub trait RouteRetriever: Send + Sync + Debug {
+    /// Whether the retriever can serve `route`.
+    fn supports(&self, route: Route) -> bool;
+
+    /// Retrieves the context `question` is answered from on `route`. A knowledge search
+    /// is limited to `collection`, when given. Returns `None` when nothing relevant is
+    /// found.
+    async fn retrieve(
+        &self,
+        route: Route,
+        question: &str,
+        collection: Option<&str>,
+    ) -> Result<Option<String>, PromptError>;
+}
+
+#[derive(Deserialize)]
+struct ClassifiedRoute {
+    route: String,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Parses the classifier's response. Returns `None` when it is not one of the
+/// `available` routes.
+pub fn parse_route_decision(response: &str, available: &[Route]) -> Option<RouteDecision> {
+    let classified: ClassifiedRoute = match serde_json::from_str(&clean_llm_response(response)) {
+        Ok(classified) => classified,
+        Err(e) => {
+            warn!("Failed to parse the route classification: {e}");
+            return None;
+        }
+    };
+    let name = classified.route.trim().to_lowercase();
+    let route = available.iter().find(|route| route.as_str() == name)?;
+    Some(RouteDecision {
+        route: *route,
+        reason: classified.reason,
+        classified: true,
+        fallback_from: None,
+    })
+}
+
+impl PromptClient {
+    /// Decides the route of a prompt among the available ones. SQL is available when the
+    /// options name a table, and the knowledge and graph routes when the client's route
+    /// retriever supports them.
+    #[instrument(name = "prompt.route", skip_all)]
+    pub async fn route_prompt(
+        &self,
+        options: &ExecutePromptOptions,
+        routing: &RoutingOptions,
+        trace: &mut Vec<PipelineStep>,
+    ) -> Result<RouteDecision, PromptError> {
+        let started = Instant::now();
+        let available: Vec<&RouteChoice> = routing
+            .routes
+            .iter()
+            .filter(|choice| self.route_available(choice.route, options))
+            .collect();
+        let fallback = if available.iter().any(|c| c.route == routing.fallback) {
+            routing.fallback
+        } else {
+            available.first().map_or(Route::Direct, |c| c.route)
+        };
+
+        let decision = if available.len() <= 1 {
+            RouteDecision {
+                route: fallback,
+                reason: None,
+                classified: false,
+                fallback_from: None,
+            }
+        } else {
+            let routes = available
+                .iter()
+                .map(|choice| format!("- `{}`: {}", choice.route.as_str(), choice.description))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let user_prompt = routing
+                .user_prompt_template
+                .replace("{routes}", &routes)
+                .replace("{prompt}", &options.prompt);
+            let routing_provider = self
+                .routing_provider
+                .as_deref()
+                .unwrap_or(self.ai_provider.as_ref());
+            let response = routing_provider
+                .generate(&routing.system_prompt, &user_prompt)
+                .await?;
+            let routes: Vec<Route> = available.iter().map(|c| c.route).collect();
+            parse_route_decision(&response, &routes).unwrap_or(RouteDecision {
+                route: fallback,
+                reason: None,
+                classified: false,
+                fallback_from: None,
+            })
+        };
+
+        info!(
+            "[route_prompt] Routed the prompt to '{}'.",
+            decision.route.as_str()
+        );
+        trace.push(PipelineStep::finished(
+            PipelineStage::RouteSelected,
+            started,
+            json!(decision),
+        ));
+        Ok(decision)
+    }
+
+    /// Prepares the options to answer on the decided route. The knowledge and graph
+    /// routes answer from their retrieved context, and fall back to a direct answer
+    /// when they find none.
+    pub(crate) async fn apply_route(
+        &self,
+        options: &mut ExecutePromptOptions,
+        routing: &RoutingOptions,
+        decision: &mut RouteDecision,
+        trace: &mut Vec<PipelineStep>,
+    ) -> Result<(), PromptError> {
+        if matches!(decision.route, Route::Knowledge | Route::Graph) {
+            let started = Instant::now();
+            let context = match &self.route_retriever {
+                Some(retriever) => {
+                    retriever
+                        .retrieve(
+                            decision.route,
+                            &options.prompt,
+                            options.collection.as_deref(),
+                        )
+                        .await?
+                }
+                None => None,
+            };
+            match context.filter(|c| !c.trim().is_empty()) {
+                Some(context) => {
+                    trace.push(PipelineStep::finished(
+                        PipelineStage::ContextRetrieved,
+                        started,
+                        json!({ "route": decision.route, "characters": context.len() }),
+                    ));
+                    options.content_type = Some(ContentType::Knowledge);
+                    options.context = Some(context);
+                    options.table_name = None;
+                }
+                None => {
+                    info!(
+                        "[apply_route] The '{}' route found nothing, answering directly.",
+                        decision.route.as_str()
+                    );
+                    decision.fallback_from = Some(decision.route);
+                    decision.route = Route::Direct;
+                }
+            }
+        }
+        match decision.route {
+            Route::Sql | Route::Direct => {
+                options.content_type = None;
+                options.context = None;
+            }
+            Route::Knowledge | Route::Graph => {}
+        }
+        if decision.route == Route::Direct {
+            options.table_name = None;
+        }
+
+        // The prompts given with the request take precedence over the route's.
+        if let Some(choice) = routing.routes.iter().find(|c| c.route == decision.route) {
+            if options.system_prompt_template.is_none() {
+                options.system_prompt_template = choice.system_prompt_template.clone();
+            }
+            if options.user_prompt_template.is_none() {
+                options.user_prompt_template = choice.user_prompt_template.clone();
+            }
+        }
+        Ok(())
+    }
+
+    fn route_available(&self, route: Route, options: &ExecutePromptOptions) -> bool {
+        match route {
+            Route::Sql => options.table_name.is_some(),
+            Route::Knowledge | Route::Graph => self
+                .route_retriever
+                .as_ref()
+                .is_some_and(|retriever| retriever.supports(route)),
+            Route::Direct => true,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! # Schema Annotations
+//!
+//! SQLite's `PRAGMA table_info` carries no column comments, so the model has to guess
+//! what a column like `status` or `amt` means. This module stores user-supplied
+//! descriptions and example values for tables and columns in the `schema_annotations`
+//! table so they can be merged into query generation prompts. A column annotation can
+//! also declare the column it references, for joins the database has no foreign key for.
+
+use crate::types::SchemaAnnotation;
+use thiserror::Error;
+use tracing::info;
+use turso::{params, Database};
+
+/// Custom error types for schema annotations.
+#[derive(Error, Debug)]
+pub enum SchemaAnnotationError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Failed to (de)serialize annotation examples: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("A table name is required to annotate a schema.")]
+    MissingTableName,
+}
+
+/// Creates or replaces the annotation for a table or column.
+pub async fn upsert_schema_annotation(
+    db: &Database,
+    annotation: &SchemaAnnotation,
+) -> Result<(), SchemaAnnotationError> {
+    if annotation.table_name.trim().is_empty() {
+        return Err(SchemaAnnotationError::MissingTableName);
+    }
+
+    let db_name = annotation.db.clone().unwrap_or_default();
+    let column_name = annotation.column_name.clone().unwrap_or_default();
+    let examples = serde_json::to_string(&annotation.examples)?;
+
+    let conn = db.connect()?;
+    conn.execute(
+        "DELETE FROM schema_annotations WHERE db = ? AND table_name = ? AND column_name = ?",
+        params![
+            db_name.as_str(),
+            annotation.table_name.as_str(),
+            column_name.as_str()
+        ],
+    )
+    .await?;
+    conn.execute(
+        "INSERT INTO schema_annotations (db, table_name, column_name, description, examples, references_column) VALUES (?, ?, ?, ?, ?, ?)",
+        params![
+            db_name.as_str(),
+            annotation.table_name.as_str(),
+            column_name.as_str(),
+            annotation.description.clone(),
+            examples,
+            annotation.references.clone()
+        ],
+    )
+    .await?;
+
+    info!(
+        "Saved schema annotation for '{}.{}' in db '{}'.",
+        annotation.table_name, column_name, db_name
+    );
+    Ok(())
+}
+
+/// Deletes the annotation for a table or column, returning whether one existed.
+pub async fn delete_schema_annotation(
+    db: &Database,
+    db_name: Option<&str>,
+    table_name: &str,
+    column_name: Option<&str>,
+) -> Result<bool, SchemaAnnotationError> {
+    let conn = db.connect()?;
+    let affected = conn
+        .execute(
+            "DELETE FROM schema_annotations WHERE db = ? AND table_name = ? AND column_name = ?",
+            params![
+                db_name.unwrap_or_default(),
+                table_name,
+                column_name.unwrap_or_default()
+            ],
+        )
+        .await?;
+    Ok(affected > 0)
+}
+
+/// Lists the annotations of a database, optionally restricted to a single table.
+pub async fn get_schema_annotations(
+    db: &Database,
+    db_name: Option<&str>,
+    table_name: Option<&str>,
+) -> Result<Vec<SchemaAnnotation>, SchemaAnnotationError> {
+    let conn = db.connect()?;
+    let mut params: Vec<turso::Value> = vec![db_name.unwrap_or_default().into()];
+
+    let mut sql = "SELECT table_name, column_name, description, examples, references_column FROM schema_annotations WHERE db = ?".to_string();
+    if let Some(table) = table_name {
+        sql.push_str(" AND table_name = ?");
+        params.push(table.into());
+    }
+    sql.push_str(" ORDER BY table_name, column_name");
+
+    let mut rows = conn.query(&sql, params).await?;
+    let mut annotations = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let table_name: String = row.get(0)?;
+        let column_name: String = row.get(1)?;
+        let description: Option<String> = row.get(2)?;
+        let examples: String = row.get(3)?;
+        let references: Option<String> = row.get(4)?;
+
+        annotations.push(SchemaAnnotation {
+            db: db_name.map(str::to_string),
+            table_name,
+            column_name: Some(column_name).filter(|c| !c.is_empty()),
+            description,
+            examples: serde_json::from_str(&examples)?,
+            references,
+        });
+    }
+
+    Ok(annotations)
+}
@@ -5,27 +5,37 @@
 //! 1.  **Query Analysis**: An LLM extracts key entities and concepts from the user's query.
 //! 2.  **Parallel Retrieval**: Metadata, keyword, and vector searches are run concurrently to gather a wide set of candidate documents.
 //! 3.  **Re-ranking**: The results from all sources are combined and re-ranked using Reciprocal Rank Fusion to produce the final, most relevant results.
+//! 4.  **Expansion**: Structured documents are split into their sections, and summary documents are followed by the chunks of their source that best match the query.
 
 use crate::ingest::knowledge::clean_llm_response;
 use crate::{
     providers::{
         ai::{generate_embeddings_batch, AiProvider},
-        db::storage::{KeywordSearch, MetadataSearch, TemporalSearch, VectorSearch},
+        db::storage::{
+            CollectionSearch, KeywordSearch, MetadataSearch, SummarySearch, TemporalSearch,
+            VectorSearch,
+        },
     },
     rerank::reciprocal_rank_fusion,
+    summarization::is_summary_link,
+    temporal::{
+        apply_temporal_constraint, document_date, parse_temporal_constraint, TemporalConstraint,
+    },
     types::SearchResult,
     PromptError,
 };
-use chrono::NaiveDate;
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use serde_yaml;
 
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use thiserror::Error;
-use tracing::{debug, info, warn};
+use tracing::{debug, info, instrument, warn};
 
 /// Defines the re-ranking strategy for hybrid search.
 #[derive(Default, Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum SearchMode {
     /// Uses a Large Language Model to intelligently re-rank candidates. (Default)
@@ -33,8 +43,14 @@ pub enum SearchMode {
     LlmReRank,
     /// Uses the fast Reciprocal Rank Fusion algorithm.
     Rrf,
+    /// Embeds an LLM-written hypothetical answer instead of the raw query (HyDE),
+    /// then fuses the candidates with Reciprocal Rank Fusion.
+    Hyde,
 }
 
+/// The most chunks a summary document found by hybrid search is expanded to.
+pub const SUMMARY_EXPANSION_LIMIT: u32 = 3;
+
 /// A struct to hold the prompts for the hybrid search query analysis step.
 pub struct HybridSearchPrompts<'a> {
     pub analysis_system_prompt: &'a str,
@@ -45,9 +61,10 @@ pub struct HybridSearchPrompts<'a> {
 /// Configuration for temporal ranking.
 #[derive(Clone, Copy)]
 pub struct TemporalRankingConfig<'a> {
-    /// Keywords that trigger temporal ranking (e.g., "newest", "latest").
+    /// Keywords that ask for the newest document (e.g., "newest", "latest").
     pub keywords: &'a [&'a str],
-    /// The name of the metadata property that holds the date/timestamp.
+    /// The metadata property preferred as a document's date. Documents without it are
+    /// dated by any other `PROPERTY` that holds a date.
     pub property_name: &'a str,
 }
 
@@ -55,6 +72,10 @@ pub struct TemporalRankingConfig<'a> {
 pub struct HybridSearchOptions<'a> {
     pub query_text: String,
     pub owner_id: Option<String>,
+    /// An organization whose shared documents are searched along with the owner's.
+    pub org_id: Option<String>,
+    /// A collection, by id or name, that the search is limited to.
+    pub collection: Option<String>,
     pub limit: u32,
     pub prompts: HybridSearchPrompts<'a>,
     pub use_keyword_search: bool,
@@ -65,6 +86,15 @@ pub struct HybridSearchOptions<'a> {
     pub temporal_ranking_config: Option<TemporalRankingConfig<'a>>,
 }
 
+/// The results of a hybrid search, with the details of how they were selected.
+#[derive(Debug, Default)]
+pub struct HybridSearchOutput {
+    pub results: Vec<SearchResult>,
+    /// The time constraint parsed from the query, when temporal ranking is configured
+    /// and the query has one.
+    pub temporal_constraint: Option<TemporalConstraint>,
+}
+
 // --- Query Analysis ---
 
 #[derive(Deserialize, Debug)]
@@ -134,25 +164,59 @@ async fn analyze_query(
     }
 }
 
-/// Filters and re-ranks a list of search results based on a date property.
-/// If any documents with a valid date are found, it returns only the single most recent one.
+/// Uses an LLM to write a hypothetical document that answers the user query.
+///
+/// The returned text is meant to be embedded in place of the raw query, which
+/// places short, keyword-like queries closer to the answers stored in the knowledge base.
+pub async fn generate_hypothetical_document(
+    ai_provider: &dyn AiProvider,
+    query_text: &str,
+    system_prompt: &str,
+    user_prompt_template: &str,
+) -> Result<String, PromptError> {
+    let user_prompt = user_prompt_template.replace("{prompt}", query_text);
+    let llm_response = ai_provider.generate(system_prompt, &user_prompt).await?;
+    let hypothetical_document = llm_response.trim().to_string();
+
+    debug!("LLM hypothetical document: {}", hypothetical_document);
+    if hypothetical_document.is_empty() {
+        warn!("LLM returned an empty hypothetical document, falling back to the raw query.");
+        return Ok(query_text.to_string());
+    }
+
+    Ok(hypothetical_document)
+}
+
+/// Filters and re-ranks search results by the time constraint of the query, using the
+/// dates stored as `PROPERTY` metadata of their documents.
 async fn temporally_rank_results<P>(
     provider: Arc<P>,
     results: Vec<SearchResult>,
     config: &TemporalRankingConfig<'_>,
+    constraint: &TemporalConstraint,
     owner_id: Option<&str>,
 ) -> Vec<SearchResult>
 where
     P: TemporalSearch + Send + Sync + 'static,
 {
-    let doc_links: Vec<&str> = results.iter().map(|r| r.link.as_str()).collect();
-    if doc_links.is_empty() {
+    // The chunks of a structured document link to it with a `#section` suffix.
+    let document_link = |link: &str| -> String {
+        link.rsplit_once('#')
+            .map_or(link, |(document, _)| document)
+            .to_string()
+    };
+    let mut links: Vec<String> = results.iter().map(|r| r.link.clone()).collect();
+    links.extend(results.iter().map(|r| document_link(&r.link)));
+    links.sort();
+    links.dedup();
+    if links.is_empty() {
         return results;
     }
+    let link_refs: Vec<&str> = links.iter().map(String::as_str).collect();
 
-    // Fetch date properties for all candidate documents.
+    // Fetch the properties of all candidate documents.
     let properties = match provider
-        .get_string_properties_for_documents(&doc_links, config.property_name, owner_id)
+        .get_properties_for_links(&link_refs, owner_id)
         .await
     {
         Ok(props) => props,
@@ -165,36 +229,109 @@ where
         }
     };
 
-    let mut dated_candidates = Vec::new();
-    let mut undated_candidates = Vec::new();
+    let dates: HashMap<String, _> = results
+        .iter()
+        .filter_map(|result| {
+            let document_properties = properties
+                .get(&result.link)
+                .or_else(|| properties.get(&document_link(&result.link)))?;
+            let date = document_date(document_properties, config.property_name)?;
+            Some((result.link.clone(), date))
+        })
+        .collect();
+    info!(
+        "Found {} dated documents among {} candidates for {:?}.",
+        dates.len(),
+        results.len(),
+        constraint
+    );
+    apply_temporal_constraint(results, &dates, constraint)
+}
 
-    // Partition the original results into two groups.
+/// Follows each summary document among the results with the chunks of its source that
+/// best match the query: by vector similarity when the query was embedded, and by
+/// keywords otherwise. Expanded chunks take the rank and score of their summary, unless
+/// they were already among the results.
+async fn expand_summaries<P>(
+    provider: &P,
+    results: Vec<SearchResult>,
+    query_vector: Option<&[f32]>,
+    keyword_query: &str,
+    options: &HybridSearchOptions<'_>,
+) -> Vec<SearchResult>
+where
+    P: VectorSearch + KeywordSearch + SummarySearch,
+{
+    let summary_links: Vec<&str> = results
+        .iter()
+        .map(|result| result.link.as_str())
+        .filter(|link| is_summary_link(link))
+        .collect();
+    if summary_links.is_empty() {
+        return results;
+    }
+    let owner_id = options.owner_id.as_deref();
+    let org_id = options.org_id.as_deref();
+    let chunk_ids = match provider
+        .get_summarized_chunk_ids(&summary_links, owner_id, org_id)
+        .await
+    {
+        Ok(chunk_ids) => chunk_ids,
+        Err(e) => {
+            warn!("Failed to fetch the chunks of summaries, they will not be expanded: {e}");
+            return results;
+        }
+    };
+
+    let mut seen: HashSet<String> = results.iter().map(|result| result.link.clone()).collect();
+    let mut expanded = Vec::with_capacity(results.len());
     for result in results {
-        if let Some(date_str) = properties.get(&result.link) {
-            if let Ok(date) = NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-                dated_candidates.push((result, date));
-                continue;
+        let Some(ids) = chunk_ids.get(&result.link) else {
+            expanded.push(result);
+            continue;
+        };
+        let chunks = match query_vector {
+            Some(query_vector) => {
+                provider
+                    .vector_search(
+                        query_vector.to_vec(),
+                        SUMMARY_EXPANSION_LIMIT,
+                        owner_id,
+                        org_id,
+                        Some(ids),
+                    )
+                    .await
+            }
+            None => {
+                provider
+                    .keyword_search(
+                        keyword_query,
+                        SUMMARY_EXPANSION_LIMIT,
+                        owner_id,
+                        org_id,
+                        Some(ids),
+                    )
+                    .await
+            }
+        };
+        let chunks = chunks.unwrap_or_else(|e| {
+            warn!("Failed to expand summary '{}': {e}", result.link);
+            Vec::new()
+        });
+        debug!(
+            "Expanded summary '{}' to {} chunks.",
+            result.link,
+            chunks.len()
+        );
+        let score = result.score;
+        expanded.push(result);
+        for chunk in chunks {
+            if seen.insert(chunk.link.clone()) {
+                expanded.push(SearchResult { score, ..chunk });
             }
         }
-        undated_candidates.push(result);
-    }
-
-    if dated_candidates.is_empty() {
-        // If no documents had a valid date, return the original (now filtered) undated results.
-        return undated_candidates;
     }
-
-    // Sort the dated candidates to find the newest one.
-    dated_candidates.sort_by(|a, b| b.1.cmp(&a.1));
-
-    // For a temporal query like "newest", we return only the single most recent document for precision.
-    info!(
-        "Found {} dated documents. Returning the newest one: '{}' with date {}",
-        dated_candidates.len(),
-        dated_candidates[0].0.title,
-        dated_candidates[0].1
-    );
-    vec![dated_candidates.remove(0).0]
+    expanded
 }
 
 /// Performs a multi-stage hybrid search.
@@ -204,9 +341,57 @@ pub async fn hybrid_search<P>(
     options: HybridSearchOptions<'_>,
 ) -> Result<Vec<SearchResult>, SearchError>
 where
-    P: MetadataSearch + VectorSearch + KeywordSearch + TemporalSearch + Send + Sync + 'static,
+    P: MetadataSearch
+        + VectorSearch
+        + KeywordSearch
+        + TemporalSearch
+        + SummarySearch
+        + CollectionSearch
+        + Send
+        + Sync
+        + 'static,
+{
+    hybrid_search_with_details(provider, ai_provider, options)
+        .await
+        .map(|output| output.results)
+}
+
+/// Performs a multi-stage hybrid search like `hybrid_search`, also returning how the
+/// results were selected.
+#[instrument(name = "search.hybrid", skip_all)]
+pub async fn hybrid_search_with_details<P>(
+    provider: Arc<P>,
+    ai_provider: Arc<dyn AiProvider>,
+    options: HybridSearchOptions<'_>,
+) -> Result<HybridSearchOutput, SearchError>
+where
+    P: MetadataSearch
+        + VectorSearch
+        + KeywordSearch
+        + TemporalSearch
+        + SummarySearch
+        + CollectionSearch
+        + Send
+        + Sync
+        + 'static,
 {
     info!(query = %options.query_text, "Starting hybrid search");
+    let collection_ids = match &options.collection {
+        Some(collection) => Some(
+            provider
+                .collection_document_ids(
+                    collection,
+                    options.owner_id.as_deref(),
+                    options.org_id.as_deref(),
+                )
+                .await?,
+        ),
+        None => None,
+    };
+    if collection_ids.as_ref().is_some_and(|ids| ids.is_empty()) {
+        info!("Collection has no documents to search.");
+        return Ok(HybridSearchOutput::default());
+    }
     let analyzed_query = analyze_query(
         ai_provider.as_ref(),
         &options.query_text,
@@ -248,7 +433,9 @@ where
             &analyzed_query.entities,
             &keyphrases_meta,
             options.owner_id.as_deref(),
+            options.org_id.as_deref(),
             options.limit * 2,
+            collection_ids.as_deref(),
         )
         .await
     {
@@ -276,7 +463,8 @@ where
                 &filtered_keyword_query,
                 options.limit * 2,
                 options.owner_id.as_deref(),
-                None,
+                options.org_id.as_deref(),
+                collection_ids.as_deref(),
             )
             .await
         {
@@ -300,7 +488,7 @@ where
         Vec::new()
     };
 
-    let vector_candidates = if options.use_vector_search {
+    let query_vector = if options.use_vector_search {
         let query_vector_result = generate_embeddings_batch(
             options.embedding_api_url,
             options.embedding_model,
@@ -318,40 +506,46 @@ where
         });
 
         match query_vector_result {
-            Ok(query_vector) => {
-                match provider
-                    .vector_search(
-                        query_vector,
-                        options.limit * 2,
-                        options.owner_id.as_deref(),
-                        None,
-                    )
-                    .await
-                {
-                    Ok(res) => {
-                        info!(
-                            "[hybrid_search] Vector search returned {} candidates.",
-                            res.len()
-                        );
-                        debug!(
-                            "Vector candidates: {:?}",
-                            res.iter().map(|r| r.title.clone()).collect::<Vec<_>>()
-                        );
-                        res
-                    }
-                    Err(e) => {
-                        warn!("Vector search task failed: {}", e);
-                        Vec::new()
-                    }
-                }
-            }
+            Ok(query_vector) => Some(query_vector),
             Err(e) => {
                 warn!("Vector embedding generation failed: {}", e);
-                Vec::new()
+                None
             }
         }
     } else {
-        Vec::new()
+        None
+    };
+
+    let vector_candidates = match &query_vector {
+        Some(query_vector) => {
+            match provider
+                .vector_search(
+                    query_vector.clone(),
+                    options.limit * 2,
+                    options.owner_id.as_deref(),
+                    options.org_id.as_deref(),
+                    collection_ids.as_deref(),
+                )
+                .await
+            {
+                Ok(res) => {
+                    info!(
+                        "[hybrid_search] Vector search returned {} candidates.",
+                        res.len()
+                    );
+                    debug!(
+                        "Vector candidates: {:?}",
+                        res.iter().map(|r| r.title.clone()).collect::<Vec<_>>()
+                    );
+                    res
+                }
+                Err(e) => {
+                    warn!("Vector search task failed: {}", e);
+                    Vec::new()
+                }
+            }
+        }
+        None => Vec::new(),
     };
 
     let ranked_parent_documents = reciprocal_rank_fusion(vec![
@@ -414,28 +608,42 @@ where
         }
     }
 
-    // --- Step 5: Final Ranking and Truncation ---
+    // --- Step 5: Expand Summaries into the Chunks of their Source ---
+    let contextual_chunks = expand_summaries(
+        provider.as_ref(),
+        contextual_chunks,
+        query_vector.as_deref(),
+        &filtered_keyword_query,
+        &options,
+    )
+    .await;
+
+    // --- Step 6: Final Ranking and Truncation ---
     let mut final_results = contextual_chunks;
 
     // --- Temporal Ranking Step ---
+    let mut temporal_constraint = None;
     if let Some(config) = &options.temporal_ranking_config {
-        let is_temporal_query = analyzed_query
-            .keyphrases
-            .iter()
-            .any(|phrase| config.keywords.iter().any(|kw| phrase.contains(*kw)));
-
-        if is_temporal_query && !final_results.is_empty() {
-            info!(
-                "Temporal keyword detected. Re-ranking results by '{}'.",
-                config.property_name
-            );
-            final_results = temporally_rank_results(
-                Arc::clone(&provider),
-                final_results,
-                config,
-                options.owner_id.as_deref(),
-            )
-            .await;
+        temporal_constraint = parse_temporal_constraint(
+            &options.query_text,
+            config.keywords,
+            Utc::now().date_naive(),
+        );
+        if let Some(constraint) = &temporal_constraint {
+            if !final_results.is_empty() {
+                info!(
+                    "Temporal constraint detected: {:?}. Re-ranking results by date, preferring '{}'.",
+                    constraint, config.property_name
+                );
+                final_results = temporally_rank_results(
+                    Arc::clone(&provider),
+                    final_results,
+                    config,
+                    constraint,
+                    options.owner_id.as_deref(),
+                )
+                .await;
+            }
         }
     }
 
@@ -448,5 +656,8 @@ where
         );
     }
 
-    Ok(final_results)
+    Ok(HybridSearchOutput {
+        results: final_results,
+        temporal_constraint,
+    })
 }
@@ -0,0 +1,113 @@
+//! # Structured Output
+//!
+//! This module validates AI responses against a caller-supplied JSON Schema so that
+//! prompt results can be consumed by machines. Only the commonly used subset of
+//! JSON Schema is supported: `type`, `properties`, `required`, `additionalProperties: false`,
+//! `items`, and `enum`. Unknown keywords are ignored.
+
+use crate::ingest::knowledge::clean_llm_response;
+use serde_json::Value;
+
+/// Parses an AI response as JSON and validates it against the schema.
+///
+/// Markdown code fences around the JSON are tolerated. On failure, the returned list
+/// describes every problem found so it can be fed back to the model for repair.
+pub fn parse_structured_output(raw: &str, schema: &Value) -> Result<Value, Vec<String>> {
+    let cleaned = clean_llm_response(raw);
+    let cleaned = cleaned
+        .strip_prefix("```")
+        .and_then(|s| s.strip_suffix("```"))
+        .unwrap_or(&cleaned)
+        .trim();
+
+    let value: Value = serde_json::from_str(cleaned)
+        .map_err(|e| vec![format!("Response is not valid JSON: {e}")])?;
+
+    let errors = validate_against_schema(&value, schema);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(value)
+}
+
+/// Validates a JSON value against a JSON Schema, returning a list of violations.
+pub fn validate_against_schema(value: &Value, schema: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at("$", value, schema, &mut errors);
+    errors
+}
+
+fn validate_at(path: &str, value: &Value, schema: &Value, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            errors.push(format!("{path}: value {value} is not one of {allowed:?}"));
+        }
+    }
+
+    if let Some(expected) = schema.get("type") {
+        let matches = match expected {
+            Value::String(t) => matches_type(value, t),
+            Value::Array(types) => types
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|t| matches_type(value, t)),
+            _ => true,
+        };
+        if !matches {
+            errors.push(format!("{path}: expected type {expected}, found {value}"));
+            return;
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for key in required.iter().filter_map(Value::as_str) {
+                    if !map.contains_key(key) {
+                        errors.push(format!("{path}: missing required property '{key}'"));
+                    }
+                }
+            }
+
+            for (key, child) in map {
+                match properties.and_then(|p| p.get(key)) {
+                    Some(child_schema) => {
+                        validate_at(&format!("{path}.{key}"), child, child_schema, errors)
+                    }
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        errors.push(format!("{path}: unexpected property '{key}'"));
+                    }
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_at(&format!("{path}[{i}]"), item, item_schema, errors);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
@@ -0,0 +1,303 @@
+//! # Source Summarization
+//!
+//! Long manuals are ingested as many chunks, and each chunk loses the context of the
+//! document it was cut from. With a `summarization` configuration, every ingested source
+//! long enough gets a summary document, stored under the source's link with a
+//! `#summary` suffix and linked to the chunks it summarizes by `SUMMARY` metadata.
+//!
+//! The summary is embedded and searched like any other document. When hybrid search
+//! hits one, it expands it to the chunks of its source that best match the query
+//! (the parent-document retriever pattern).
+
+use crate::errors::PromptError;
+use crate::ingest::{bulk::bulk_insert_rows, dedup::content_hash};
+use crate::providers::ai::AiProvider;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, info};
+use turso::{params, Connection, Value as TursoValue};
+use uuid::Uuid;
+
+/// The suffix of the link of a summary document, appended to its source's link.
+pub const SUMMARY_LINK_SUFFIX: &str = "#summary";
+/// The metadata type that links a summary document to the chunks it summarizes.
+pub const SUMMARY_METADATA_TYPE: &str = "SUMMARY";
+
+const INSERT_SUMMARY_LINKS_SQL: &str = "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_subtype, metadata_value)";
+
+/// Custom error types for source summarization.
+#[derive(Error, Debug)]
+pub enum SummarizationError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Summarization failed: {0}")]
+    Prompt(#[from] PromptError),
+}
+
+/// Configuration for summarizing long ingested sources.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct SummarizationConfig {
+    /// The number of characters a source must have, over all of its chunks, to be
+    /// summarized.
+    #[serde(default = "default_min_source_chars")]
+    pub min_source_chars: usize,
+    /// The most characters sent to the model at once. Longer sources are summarized in
+    /// parts, and the partial summaries are merged into one.
+    #[serde(default = "default_max_input_chars")]
+    pub max_input_chars: usize,
+}
+
+impl Default for SummarizationConfig {
+    fn default() -> Self {
+        Self {
+            min_source_chars: default_min_source_chars(),
+            max_input_chars: default_max_input_chars(),
+        }
+    }
+}
+
+fn default_min_source_chars() -> usize {
+    8_000
+}
+
+fn default_max_input_chars() -> usize {
+    24_000
+}
+
+/// A chunk of an ingested source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceChunk {
+    pub id: String,
+    pub content: String,
+}
+
+/// The source a document link belongs to: the link without its `#fragment`, which
+/// chunked ingestors use to number the chunks of a source.
+pub fn source_of(link: &str) -> &str {
+    link.rsplit_once('#').map_or(link, |(source, _)| source)
+}
+
+/// The link of the summary document of a source.
+pub fn summary_link(source: &str) -> String {
+    format!("{source}{SUMMARY_LINK_SUFFIX}")
+}
+
+/// Whether a link is the link of a summary document.
+pub fn is_summary_link(link: &str) -> bool {
+    link.ends_with(SUMMARY_LINK_SUFFIX)
+}
+
+/// Fetches the chunks of a source stored for an owner, in the order they were stored.
+/// The source's summary document is not one of them.
+pub async fn source_chunks(
+    conn: &Connection,
+    owner_id: Option<&str>,
+    source: &str,
+) -> Result<Vec<SourceChunk>, turso::Error> {
+    let (owner_filter, mut params) = owner_filter(owner_id);
+    let sql = format!(
+        "SELECT id, content FROM documents
+         WHERE (source_url = ? OR source_url LIKE ?) AND source_url != ? AND {owner_filter}
+         ORDER BY rowid"
+    );
+    let mut query_params: Vec<TursoValue> = vec![
+        source.into(),
+        format!("{source}#%").into(),
+        summary_link(source).into(),
+    ];
+    query_params.append(&mut params);
+
+    let mut rows = conn.query(&sql, query_params).await?;
+    let mut chunks = Vec::new();
+    while let Some(row) = rows.next().await? {
+        chunks.push(SourceChunk {
+            id: row.get(0)?,
+            content: row.get(1)?,
+        });
+    }
+    Ok(chunks)
+}
+
+/// Asks the AI provider for a summary of the chunks of a source.
+///
+/// The chunks are sent in parts of at most `max_input_chars` characters. When there is
+/// more than one part, the partial summaries are merged with a last request.
+pub async fn summarize_chunks(
+    ai_provider: &dyn AiProvider,
+    chunks: &[&str],
+    config: &SummarizationConfig,
+    system_prompt: &str,
+    user_prompt_template: &str,
+) -> Result<String, PromptError> {
+    let max_chars = config.max_input_chars.max(1);
+    let mut parts: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for chunk in chunks {
+        let chunk: String = chunk.chars().take(max_chars).collect();
+        let length = current.chars().count() + chunk.chars().count() + 2;
+        if !current.is_empty() && length > max_chars {
+            parts.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(&chunk);
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+
+    let mut summaries = Vec::new();
+    for part in &parts {
+        let user_prompt = user_prompt_template.replace("{content}", part);
+        summaries.push(
+            ai_provider
+                .generate(system_prompt, &user_prompt)
+                .await?
+                .trim()
+                .to_string(),
+        );
+    }
+    if summaries.len() <= 1 {
+        return Ok(summaries.pop().unwrap_or_default());
+    }
+
+    debug!("Merging {} partial summaries.", summaries.len());
+    let merged: String = summaries.join("\n\n").chars().take(max_chars).collect();
+    let user_prompt = user_prompt_template.replace("{content}", &merged);
+    Ok(ai_provider
+        .generate(system_prompt, &user_prompt)
+        .await?
+        .trim()
+        .to_string())
+}
+
+/// Stores the summary document of a source, replacing the previous one, and links it
+/// to the chunks it summarizes. Returns the id of the summary document.
+pub async fn store_summary(
+    conn: &mut Connection,
+    owner_id: Option<&str>,
+    source: &str,
+    summary: &str,
+    chunk_ids: &[String],
+) -> Result<String, turso::Error> {
+    let link = summary_link(source);
+    let tx = conn.transaction().await?;
+
+    let (owner_filter, mut params) = owner_filter(owner_id);
+    let mut query_params: Vec<TursoValue> = vec![link.as_str().into()];
+    query_params.append(&mut params);
+    let mut rows = tx
+        .query(
+            &format!("SELECT id FROM documents WHERE source_url = ? AND {owner_filter}"),
+            query_params,
+        )
+        .await?;
+    let mut previous_ids: Vec<String> = Vec::new();
+    while let Some(row) = rows.next().await? {
+        previous_ids.push(row.get(0)?);
+    }
+    drop(rows);
+    for previous_id in &previous_ids {
+        tx.execute(
+            "DELETE FROM content_metadata WHERE document_id = ?",
+            params![previous_id.as_str()],
+        )
+        .await?;
+        tx.execute(
+            "DELETE FROM document_embeddings WHERE document_id = ?",
+            params![previous_id.as_str()],
+        )
+        .await?;
+        tx.execute(
+            "DELETE FROM documents WHERE id = ?",
+            params![previous_id.as_str()],
+        )
+        .await?;
+    }
+
+    let summary_id = Uuid::new_v4().to_string();
+    let owner = match owner_id {
+        Some(owner) => TursoValue::Text(owner.to_string()),
+        None => TursoValue::Null,
+    };
+    tx.execute(
+        "INSERT INTO documents (id, owner_id, source_url, title, content, content_hash) VALUES (?, ?, ?, ?, ?, ?)",
+        vec![
+            summary_id.as_str().into(),
+            owner.clone(),
+            link.as_str().into(),
+            format!("Summary of {source}").into(),
+            summary.into(),
+            content_hash(summary).into(),
+        ],
+    )
+    .await?;
+    let links = chunk_ids
+        .iter()
+        .map(|chunk_id| {
+            vec![
+                summary_id.as_str().into(),
+                owner.clone(),
+                SUMMARY_METADATA_TYPE.into(),
+                "CHUNK".into(),
+                chunk_id.as_str().into(),
+            ]
+        })
+        .collect();
+    bulk_insert_rows(&tx, INSERT_SUMMARY_LINKS_SQL, "", links).await?;
+    tx.commit().await?;
+
+    info!(
+        "Stored the summary of '{source}' over {} chunks.",
+        chunk_ids.len()
+    );
+    Ok(summary_id)
+}
+
+/// Summarizes a source stored for an owner, if it is long enough, and stores the
+/// summary document. Returns the id of the summary document, or `None` when the source
+/// is too short to be summarized.
+pub async fn summarize_source(
+    conn: &mut Connection,
+    ai_provider: &dyn AiProvider,
+    owner_id: Option<&str>,
+    source: &str,
+    config: &SummarizationConfig,
+    system_prompt: &str,
+    user_prompt_template: &str,
+) -> Result<Option<String>, SummarizationError> {
+    let chunks = source_chunks(conn, owner_id, source).await?;
+    let total_chars: usize = chunks
+        .iter()
+        .map(|chunk| chunk.content.chars().count())
+        .sum();
+    if chunks.is_empty() || total_chars < config.min_source_chars {
+        debug!("Source '{source}' has {total_chars} characters, not summarizing it.");
+        return Ok(None);
+    }
+
+    let contents: Vec<&str> = chunks.iter().map(|chunk| chunk.content.as_str()).collect();
+    let summary = summarize_chunks(
+        ai_provider,
+        &contents,
+        config,
+        system_prompt,
+        user_prompt_template,
+    )
+    .await?;
+    if summary.is_empty() {
+        return Ok(None);
+    }
+    let chunk_ids: Vec<String> = chunks.into_iter().map(|chunk| chunk.id).collect();
+    Ok(Some(
+        store_summary(conn, owner_id, source, &summary, &chunk_ids).await?,
+    ))
+}
+
+fn owner_filter(owner_id: Option<&str>) -> (&'static str, Vec<TursoValue>) {
+    match owner_id {
+        Some(owner) => ("owner_id = ?", vec![owner.into()]),
+        None => ("owner_id IS NULL", Vec::new()),
+    }
+}
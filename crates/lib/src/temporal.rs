@@ -0,0 +1,342 @@
+//! # Temporal Reasoning
+//!
+//! Reads the time constraint of a search query, such as "the latest release", "reports
+//! before June" or "between 2023-01-01 and 2023-03-31", and applies it to search results
+//! using the dates stored as `PROPERTY` metadata of their documents.
+
+use crate::types::SearchResult;
+use chrono::{DateTime, Datelike, Months, NaiveDate};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Keywords that ask for the oldest document, the counterpart of the configured keywords
+/// that ask for the newest.
+const OLDEST_KEYWORDS: &[&str] = &["oldest", "earliest", "least recent"];
+
+const MONTHS: &[(&str, u32)] = &[
+    ("january", 1),
+    ("jan", 1),
+    ("february", 2),
+    ("feb", 2),
+    ("march", 3),
+    ("mar", 3),
+    ("april", 4),
+    ("apr", 4),
+    ("may", 5),
+    ("june", 6),
+    ("jun", 6),
+    ("july", 7),
+    ("jul", 7),
+    ("august", 8),
+    ("aug", 8),
+    ("september", 9),
+    ("sept", 9),
+    ("sep", 9),
+    ("october", 10),
+    ("oct", 10),
+    ("november", 11),
+    ("nov", 11),
+    ("december", 12),
+    ("dec", 12),
+];
+
+/// Which end of the time range a query asks for.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TemporalOrder {
+    Newest,
+    Oldest,
+}
+
+/// The time constraint of a query.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct TemporalConstraint {
+    /// Keeps only the newest or oldest matching document.
+    pub order: Option<TemporalOrder>,
+    /// The earliest date a document may have, inclusive.
+    pub from: Option<NaiveDate>,
+    /// The latest date a document may have, inclusive.
+    pub to: Option<NaiveDate>,
+}
+
+impl TemporalConstraint {
+    /// Whether a document dated `date` falls within the range.
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.from.is_none_or(|from| date >= from) && self.to.is_none_or(|to| date <= to)
+    }
+
+    fn narrow_from(&mut self, date: NaiveDate) {
+        self.from = Some(self.from.map_or(date, |from| from.max(date)));
+    }
+
+    fn narrow_to(&mut self, date: NaiveDate) {
+        self.to = Some(self.to.map_or(date, |to| to.min(date)));
+    }
+}
+
+/// The days a date expression covers, such as the whole month for "June".
+#[derive(Debug, Clone, Copy)]
+struct Period {
+    start: NaiveDate,
+    end: NaiveDate,
+}
+
+impl Period {
+    fn day(date: NaiveDate) -> Self {
+        Self {
+            start: date,
+            end: date,
+        }
+    }
+
+    fn month(year: i32, month: u32) -> Option<Self> {
+        let start = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let end = start.checked_add_months(Months::new(1))?.pred_opt()?;
+        Some(Self { start, end })
+    }
+
+    fn year(year: i32) -> Option<Self> {
+        Some(Self {
+            start: NaiveDate::from_ymd_opt(year, 1, 1)?,
+            end: NaiveDate::from_ymd_opt(year, 12, 31)?,
+        })
+    }
+}
+
+/// Parses the time constraint of a query.
+///
+/// -   `newest_keywords` (e.g., "latest") ask for the newest document, and "oldest" or
+///     "earliest" for the oldest.
+/// -   "before", "after", "since", "until", "in", "between ... and ..." and
+///     "from ... to ..." bound the dates, followed by a date (`2024-06-01`), a month
+///     (`2024-06`, "June", "June 2024"), a year (`2024`), or "this"/"last" month or year.
+///     A month without a year is in the year of `today`.
+///
+/// Returns `None` when the query has no time constraint.
+pub fn parse_temporal_constraint(
+    query: &str,
+    newest_keywords: &[&str],
+    today: NaiveDate,
+) -> Option<TemporalConstraint> {
+    let lowered = query.to_lowercase();
+    let mut constraint = TemporalConstraint::default();
+    if newest_keywords
+        .iter()
+        .any(|kw| !kw.is_empty() && lowered.contains(&kw.to_lowercase()))
+    {
+        constraint.order = Some(TemporalOrder::Newest);
+    } else if OLDEST_KEYWORDS.iter().any(|kw| lowered.contains(kw)) {
+        constraint.order = Some(TemporalOrder::Oldest);
+    }
+
+    let tokens: Vec<&str> = lowered
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | '?' | '!' | ';' | '(' | ')'))
+        .map(|token| token.trim_matches(|c: char| matches!(c, '.' | ':' | '"' | '\'')))
+        .filter(|token| !token.is_empty())
+        .collect();
+    let mut i = 0;
+    while i < tokens.len() {
+        let rest = &tokens[i + 1..];
+        let consumed = match tokens[i] {
+            "before" | "prior" => parse_period(skip_word(rest, "to"), today).map(|(p, n)| {
+                if let Some(day) = p.start.pred_opt() {
+                    constraint.narrow_to(day);
+                }
+                n + usize::from(rest.first() == Some(&"to"))
+            }),
+            "until" | "till" | "through" => parse_period(rest, today).map(|(p, n)| {
+                constraint.narrow_to(p.end);
+                n
+            }),
+            "after" => parse_period(rest, today).map(|(p, n)| {
+                if let Some(day) = p.end.succ_opt() {
+                    constraint.narrow_from(day);
+                }
+                n
+            }),
+            "since" => parse_period(rest, today).map(|(p, n)| {
+                constraint.narrow_from(p.start);
+                n
+            }),
+            "in" | "during" | "on" => parse_period(rest, today).map(|(p, n)| {
+                constraint.narrow_from(p.start);
+                constraint.narrow_to(p.end);
+                n
+            }),
+            "between" | "from" => parse_period(rest, today).map(|(first, n)| {
+                constraint.narrow_from(first.start);
+                let separator = rest.get(n).copied();
+                let second = match separator {
+                    Some("and" | "to" | "until" | "through" | "-") => {
+                        parse_period(&rest[n + 1..], today)
+                    }
+                    _ => None,
+                };
+                match second {
+                    Some((second, m)) => {
+                        constraint.narrow_to(second.end);
+                        n + 1 + m
+                    }
+                    // "between June and ..." without a second date is a single period.
+                    None if tokens[i] == "between" => {
+                        constraint.narrow_to(first.end);
+                        n
+                    }
+                    None => n,
+                }
+            }),
+            _ => None,
+        };
+        i += 1 + consumed.unwrap_or(0);
+    }
+
+    (constraint != TemporalConstraint::default()).then_some(constraint)
+}
+
+fn skip_word<'a, 'b>(tokens: &'a [&'b str], word: &str) -> &'a [&'b str] {
+    match tokens.first() {
+        Some(first) if *first == word => &tokens[1..],
+        _ => tokens,
+    }
+}
+
+/// Parses the date expression the tokens start with. Returns its period and the number
+/// of tokens it spans.
+fn parse_period(tokens: &[&str], today: NaiveDate) -> Option<(Period, usize)> {
+    let first = *tokens.first()?;
+    match (first, tokens.get(1).copied()) {
+        ("today", _) => return Some((Period::day(today), 1)),
+        ("yesterday", _) => return Some((Period::day(today.pred_opt()?), 1)),
+        ("this", Some("year")) => return Some((Period::year(today.year())?, 2)),
+        ("last", Some("year")) => return Some((Period::year(today.year() - 1)?, 2)),
+        ("this", Some("month")) => {
+            return Some((Period::month(today.year(), today.month())?, 2));
+        }
+        ("last", Some("month")) => {
+            let last_month = today.checked_sub_months(Months::new(1))?;
+            return Some((Period::month(last_month.year(), last_month.month())?, 2));
+        }
+        _ => {}
+    }
+
+    for format in ["%Y-%m-%d", "%Y/%m/%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(first, format) {
+            return Some((Period::day(date), 1));
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&format!("{first}-01"), "%Y-%m-%d") {
+        return Some((Period::month(date.year(), date.month())?, 1));
+    }
+    if let Some(year) = parse_year(first) {
+        return Some((Period::year(year)?, 1));
+    }
+
+    // A month name, optionally followed by a day and a year ("june 5th 2024").
+    let month = MONTHS
+        .iter()
+        .find(|(name, _)| *name == first)
+        .map(|(_, month)| *month)?;
+    let mut consumed = 1;
+    let day = tokens.get(consumed).and_then(|token| parse_day(token));
+    if day.is_some() {
+        consumed += 1;
+    }
+    let year = match tokens.get(consumed).and_then(|token| parse_year(token)) {
+        Some(year) => {
+            consumed += 1;
+            year
+        }
+        None => today.year(),
+    };
+    let period = match day {
+        Some(day) => Period::day(NaiveDate::from_ymd_opt(year, month, day)?),
+        None => Period::month(year, month)?,
+    };
+    Some((period, consumed))
+}
+
+fn parse_year(token: &str) -> Option<i32> {
+    if token.len() != 4 || !token.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok().filter(|year| *year >= 1000)
+}
+
+fn parse_day(token: &str) -> Option<u32> {
+    let digits = token.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    if digits.is_empty() || digits.len() > 2 {
+        return None;
+    }
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+/// Reads a metadata value as a date: a `YYYY-MM-DD` or `YYYY/MM/DD` date, an RFC 3339
+/// timestamp, or a timestamp that starts with a `YYYY-MM-DD` date.
+pub fn parse_metadata_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp.date_naive());
+    }
+    value
+        .get(..10)
+        .and_then(|prefix| {
+            NaiveDate::parse_from_str(prefix, "%Y-%m-%d")
+                .or_else(|_| NaiveDate::parse_from_str(prefix, "%Y/%m/%d"))
+                .ok()
+        })
+        .or_else(|| NaiveDate::parse_from_str(value, "%B %d %Y").ok())
+        .or_else(|| NaiveDate::parse_from_str(value, "%B %d, %Y").ok())
+}
+
+/// Picks the date of a document from its `PROPERTY` metadata, as `(name, value)` pairs:
+/// the `preferred` property when it holds a date, otherwise the first property, by name,
+/// that does.
+pub fn document_date(properties: &[(String, String)], preferred: &str) -> Option<NaiveDate> {
+    let preferred_date = properties
+        .iter()
+        .filter(|(name, _)| name == preferred)
+        .find_map(|(_, value)| parse_metadata_date(value));
+    if preferred_date.is_some() {
+        return preferred_date;
+    }
+    let mut dated: Vec<(&str, NaiveDate)> = properties
+        .iter()
+        .filter_map(|(name, value)| Some((name.as_str(), parse_metadata_date(value)?)))
+        .collect();
+    dated.sort_by(|a, b| a.0.cmp(b.0).then(a.1.cmp(&b.1)));
+    dated.first().map(|(_, date)| *date)
+}
+
+/// Applies a time constraint to search results, given the date of each result by link.
+///
+/// When no result has a date, the constraint cannot be applied and the results are
+/// returned unchanged. Otherwise, only the dated results within the range are kept, in
+/// their order of relevance; with an order, only the newest or oldest of them is kept.
+pub fn apply_temporal_constraint(
+    results: Vec<SearchResult>,
+    dates: &HashMap<String, NaiveDate>,
+    constraint: &TemporalConstraint,
+) -> Vec<SearchResult> {
+    if !results
+        .iter()
+        .any(|result| dates.contains_key(&result.link))
+    {
+        return results;
+    }
+    let mut dated: Vec<(SearchResult, NaiveDate)> = results
+        .into_iter()
+        .filter_map(|result| {
+            let date = *dates.get(&result.link)?;
+            constraint.contains(date).then_some((result, date))
+        })
+        .collect();
+    match constraint.order {
+        Some(TemporalOrder::Newest) => dated.sort_by(|a, b| b.1.cmp(&a.1)),
+        Some(TemporalOrder::Oldest) => dated.sort_by(|a, b| a.1.cmp(&b.1)),
+        None => {}
+    }
+    if constraint.order.is_some() {
+        dated.truncate(1);
+    }
+    dated.into_iter().map(|(result, _)| result).collect()
+}
@@ -3,19 +3,29 @@ use crate::prompts::tasks::{RSS_SUMMARIZATION_SYSTEM_PROMPT, RSS_SUMMARIZATION_U
 #[cfg(feature = "bigquery")]
 use crate::providers::db::bigquery::BigQueryProvider;
 use crate::{
+    answer_cache::AnswerCacheConfig,
     constants,
+    context_sanitization::ContextSanitizationConfig,
     errors::PromptError,
+    ingest::TrashConfig,
+    moderation::ModerationConfig,
+    planning::{PlanningConfig, PlanningOptions},
     prompts::{
         core::DEFAULT_QUERY_SYSTEM_PROMPT,
         knowledge::{KNOWLEDGE_RAG_SYSTEM_PROMPT, KNOWLEDGE_RAG_USER_PROMPT},
         tasks::QUERY_GENERATION_USER_PROMPT,
     },
     providers::{ai::AiProvider, db::storage::Storage},
+    redaction::RedactionConfig,
     rerank::Rerankable,
+    routing::{RouteDecision, RouteRetriever, RoutingConfig, RoutingOptions},
+    summarization::SummarizationConfig,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::{self, Debug};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// A client for executing natural language prompts against a storage provider.
 ///
@@ -25,6 +35,10 @@ use std::fmt::{self, Debug};
 pub struct PromptClient {
     pub ai_provider: Box<dyn AiProvider>,
     pub(crate) storage_provider: Box<dyn Storage>,
+    /// Retrieves the context of the knowledge and graph routes.
+    pub(crate) route_retriever: Option<Arc<dyn RouteRetriever>>,
+    /// Classifies prompts between routes. The AI provider is used without it.
+    pub(crate) routing_provider: Option<Box<dyn AiProvider>>,
 }
 
 impl Debug for PromptClient {
@@ -32,6 +46,8 @@ impl Debug for PromptClient {
         f.debug_struct("PromptClient")
             .field("ai_provider", &self.ai_provider)
             .field("storage_provider", &self.storage_provider)
+            .field("route_retriever", &self.route_retriever)
+            .field("routing_provider", &self.routing_provider)
             .finish()
     }
 }
@@ -107,6 +123,105 @@ pub struct ExecutePromptOptions {
     /// Available placeholders: `{prompt}`, `{instruction}`, `{content}`
     #[serde(default)]
     pub format_user_prompt_template: Option<String>,
+    /// Prior turns of a conversation, oldest first, so follow-up questions can be resolved.
+    #[serde(default)]
+    pub history: Option<Vec<ChatMessage>>,
+    /// A JSON Schema the final result must conform to. When set, the result `text`
+    /// is a JSON document validated against this schema.
+    #[serde(default)]
+    pub output_schema: Option<serde_json::Value>,
+    /// The form of the final result. With `OutputFormat::Chart`, the result `text` is a
+    /// `ChartSpec` as JSON, and `output_schema` is ignored.
+    #[serde(default)]
+    pub output: Option<OutputFormat>,
+    /// Descriptions and example values for tables and columns, merged into the
+    /// schema section of query generation prompts.
+    #[serde(default)]
+    pub schema_annotations: Option<Vec<SchemaAnnotation>>,
+    /// Worked examples of good answers to similar prompts, shown to the model as guidance.
+    #[serde(default)]
+    pub few_shot_examples: Option<Vec<FewShotExample>>,
+    /// Tables whose schemas are shown to the model along with `table_name`'s, so it can
+    /// join them. The tables `table_name` references are shown without being listed.
+    #[serde(default)]
+    pub related_tables: Option<Vec<String>>,
+    /// Routes the prompt to text-to-SQL, knowledge search, graph lookup or a direct
+    /// answer with a classification call, instead of by `table_name` and `content_type`.
+    #[serde(default)]
+    pub routing: Option<RoutingOptions>,
+    /// A collection, by id or name, that knowledge searches are limited to.
+    #[serde(default)]
+    pub collection: Option<String>,
+    /// Decomposes a prompt that needs several queries into sub-queries, run one after
+    /// the other, before the answer is written from their results.
+    #[serde(default)]
+    pub planning: Option<PlanningOptions>,
+}
+
+/// The form the final result of a prompt is returned in.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    /// A text answer, or a JSON document when an output schema is given.
+    #[default]
+    Text,
+    /// A chart spec of the query results, ready to be plotted.
+    Chart,
+}
+
+/// A prompt paired with an answer that a reviewer accepted as correct.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FewShotExample {
+    pub prompt: String,
+    pub answer: String,
+}
+
+/// A natural-language description and example values attached to a table or column.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SchemaAnnotation {
+    /// The database the table belongs to. `None` refers to the default database.
+    #[serde(default)]
+    pub db: Option<String>,
+    pub table_name: String,
+    /// The annotated column. `None` annotates the table itself.
+    #[serde(default)]
+    pub column_name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Representative values, which teach the model the format of the data.
+    #[serde(default)]
+    pub examples: Vec<String>,
+    /// The column this column references, as `table.column`, for relationships the
+    /// database does not declare with a foreign key.
+    #[serde(default)]
+    pub references: Option<String>,
+}
+
+/// The author of a message in a conversation.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatRole {
+    User,
+    Assistant,
+}
+
+impl ChatRole {
+    /// Returns the role as stored in the `conversations` table.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatRole::User => "user",
+            ChatRole::Assistant => "assistant",
+        }
+    }
+}
+
+/// A single message in a conversation.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
 }
 
 /// The result of a successful prompt execution, including debug information.
@@ -126,6 +241,92 @@ pub struct PromptResult {
     /// The user prompt sent to the AI for query generation.
     #[serde(default)]
     pub user_prompt: Option<String>,
+    /// The A/B experiment run this result was recorded as, if any.
+    #[serde(default)]
+    pub experiment_run: Option<ExperimentRun>,
+    /// Each stage of the pipeline that produced this result, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trace: Vec<PipelineStep>,
+    /// The route the prompt was answered on, when it was routed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route: Option<RouteDecision>,
+}
+
+/// A stage of the prompt pipeline.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStage {
+    /// The prompts were rendered and sent to the AI provider.
+    PromptSent,
+    /// The AI provider responded to the prompts.
+    AiResponse,
+    /// A query was extracted from the AI response.
+    GeneratedSql,
+    /// The query was executed against the storage provider.
+    RowsReturned,
+    /// The result was sent to the AI provider to be formatted.
+    FormattingPrompt,
+    /// A knowledge graph query was extracted from the AI response.
+    GeneratedGraphQuery,
+    /// The knowledge graph query was executed against the graph.
+    FactsReturned,
+    /// The route the prompt is answered on was decided.
+    RouteSelected,
+    /// The context of the route was retrieved.
+    ContextRetrieved,
+    /// The prompt was decomposed into sub-queries.
+    QueryPlanned,
+    /// A sub-query of the plan was executed against the storage provider.
+    PlanStepExecuted,
+}
+
+/// A stage of the prompt pipeline as it ran, for debugging a result.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PipelineStep {
+    pub stage: PipelineStage,
+    /// How long the stage took, in milliseconds.
+    pub duration_ms: u64,
+    /// What the stage sent or produced, e.g. the prompts or the generated SQL.
+    pub detail: serde_json::Value,
+}
+
+impl PipelineStep {
+    /// Records a stage that started at `started` and has just finished.
+    pub fn finished(stage: PipelineStage, started: Instant, detail: serde_json::Value) -> Self {
+        Self {
+            stage,
+            duration_ms: started.elapsed().as_millis() as u64,
+            detail,
+        }
+    }
+}
+
+/// An arm of an A/B experiment.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ExperimentVariant {
+    A,
+    B,
+}
+
+impl ExperimentVariant {
+    /// Returns the variant as stored in the `experiments` table.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExperimentVariant::A => "a",
+            ExperimentVariant::B => "b",
+        }
+    }
+}
+
+/// Identifies a recorded request within an A/B experiment, so feedback can be attached to it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ExperimentRun {
+    pub id: i64,
+    pub experiment: String,
+    pub variant: ExperimentVariant,
 }
 
 /// A builder for creating `PromptClient` instances.
@@ -136,6 +337,8 @@ pub struct PromptResult {
 pub struct PromptClientBuilder {
     ai_provider: Option<Box<dyn AiProvider>>,
     storage_provider: Option<Box<dyn Storage>>,
+    route_retriever: Option<Arc<dyn RouteRetriever>>,
+    routing_provider: Option<Box<dyn AiProvider>>,
 }
 
 impl PromptClientBuilder {
@@ -156,6 +359,18 @@ impl PromptClientBuilder {
         self
     }
 
+    /// Sets the retriever of the knowledge and graph routes.
+    pub fn route_retriever(mut self, route_retriever: Arc<dyn RouteRetriever>) -> Self {
+        self.route_retriever = Some(route_retriever);
+        self
+    }
+
+    /// Sets the AI provider that classifies prompts between routes.
+    pub fn routing_provider(mut self, routing_provider: Box<dyn AiProvider>) -> Self {
+        self.routing_provider = Some(routing_provider);
+        self
+    }
+
     /// A helper to build and set a `BigQueryProvider` as the storage provider.
     #[cfg(feature = "bigquery")]
     pub async fn bigquery_storage(mut self, project_id: String) -> Result<Self, PromptError> {
@@ -197,12 +412,15 @@ impl PromptClientBuilder {
         Ok(PromptClient {
             ai_provider,
             storage_provider,
+            route_retriever: self.route_retriever,
+            routing_provider: self.routing_provider,
         })
     }
 }
 
 /// A search result from any search provider (vector, keyword, etc.).
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct SearchResult {
     pub title: String,
     pub link: String,
@@ -251,6 +469,19 @@ pub struct TableField {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TableSchema {
     pub fields: Vec<TableField>,
+    /// The columns that reference columns of other tables.
+    #[serde(default)]
+    pub foreign_keys: Vec<ForeignKey>,
+}
+
+/// A column that references a column of another table.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ForeignKey {
+    pub column: String,
+    pub referenced_table: String,
+    /// The referenced column. `None` refers to the primary key of the table.
+    #[serde(default)]
+    pub referenced_column: Option<String>,
 }
 
 /// Represents the full set of options that can be received in an HTTP request
@@ -280,12 +511,25 @@ pub struct HttpRequestPromptOptions {
     pub format_system_prompt_template: Option<String>,
     #[serde(default)]
     pub format_user_prompt_template: Option<String>,
+    #[serde(default)]
+    pub history: Option<Vec<ChatMessage>>,
+    #[serde(default)]
+    pub output_schema: Option<serde_json::Value>,
+    #[serde(default)]
+    pub output: Option<OutputFormat>,
+    #[serde(default)]
+    pub related_tables: Option<Vec<String>>,
+    #[serde(default)]
+    pub collection: Option<String>,
 
     // Server-specific fields
     #[serde(default)]
     pub db: Option<String>,
     #[serde(default)]
     pub model: Option<String>,
+    /// Confirms that a BigQuery query above `bigquery_max_bytes_scanned` should run anyway.
+    #[serde(default)]
+    pub confirm_expensive_query: bool,
 }
 
 /// Converts the HTTP request options into the library's internal `ExecutePromptOptions`.
@@ -305,6 +549,15 @@ impl From<HttpRequestPromptOptions> for ExecutePromptOptions {
             user_prompt_template: options.user_prompt_template,
             format_system_prompt_template: options.format_system_prompt_template,
             format_user_prompt_template: options.format_user_prompt_template,
+            history: options.history,
+            output_schema: options.output_schema,
+            output: options.output,
+            schema_annotations: None,
+            few_shot_examples: None,
+            related_tables: options.related_tables,
+            routing: None,
+            collection: options.collection,
+            planning: None,
         }
     }
 }
@@ -332,6 +585,124 @@ pub struct TemporalReasoningConfig {
     pub property_name: String,
 }
 
+/// Configuration for verifying tokens issued by an external OpenID Connect provider,
+/// such as Keycloak, Auth0 or Google.
+#[derive(Debug, Deserialize, Clone)]
+pub struct OidcConfig {
+    /// The issuer URL of the provider. Its discovery document is fetched from
+    /// `{issuer_url}/.well-known/openid-configuration`, and tokens must carry it as `iss`.
+    pub issuer_url: String,
+    /// The audience (`aud`) access tokens must be issued for. ID tokens from the login
+    /// flow are issued for the `client_id` instead, which is accepted as well.
+    #[serde(default)]
+    pub audience: Option<String>,
+    /// The client ID of the server, required for the `/auth/login/oidc` flow.
+    #[serde(default)]
+    pub client_id: Option<String>,
+    /// The client secret used to exchange authorization codes.
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    /// The URL the provider redirects to after sign-in, i.e. `/auth/callback/oidc`.
+    #[serde(default)]
+    pub redirect_url: Option<String>,
+    /// The scopes requested during sign-in.
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: String,
+}
+
+fn default_oidc_scopes() -> String {
+    "openid email profile".to_string()
+}
+
+/// Configuration for limiting how fast each user or API key may call the server.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RateLimitConfig {
+    /// The sustained number of requests allowed per minute.
+    pub requests_per_minute: u32,
+    /// How many requests may be made at once before the limit applies. Defaults to
+    /// `requests_per_minute`.
+    #[serde(default)]
+    pub burst: Option<u32>,
+}
+
+/// Configuration for the monthly usage quotas of each user. Unset quotas are unlimited.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct QuotaConfig {
+    /// The number of requests to AI-backed routes (prompts, chat, generation, embedding
+    /// and semantic search) a user may make per calendar month.
+    #[serde(default)]
+    pub ai_calls_per_month: Option<u64>,
+    /// The number of documents a user may ingest per calendar month.
+    #[serde(default)]
+    pub documents_per_month: Option<u64>,
+}
+
+/// Configuration for storing the documents of each owner and organization in a SQLite
+/// file of their own, instead of in the database at `db_url`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ShardingConfig {
+    /// The directory the shard files are kept in. Defaults to a `shards` directory next
+    /// to the database file.
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// The number of shards kept open at once. The least recently used shard is closed
+    /// when another one is opened.
+    #[serde(default = "default_max_open_shards")]
+    pub max_open: usize,
+}
+
+fn default_max_open_shards() -> usize {
+    64
+}
+
+/// The storage backend of the knowledge graph.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphBackend {
+    /// The graph is kept in memory and lost when the server stops.
+    #[default]
+    Memory,
+    /// The graph is persisted to a RocksDB store and loaded on startup.
+    Rocksdb,
+}
+
+/// Configuration for where the knowledge graph is stored.
+#[derive(Debug, Deserialize, Clone)]
+pub struct GraphConfig {
+    /// The storage backend, `memory` or `rocksdb`.
+    #[serde(default)]
+    pub backend: GraphBackend,
+    /// The directory of the RocksDB store when `backend` is `rocksdb`.
+    #[serde(default = "default_graph_path")]
+    pub path: String,
+}
+
+fn default_graph_path() -> String {
+    constants::DEFAULT_GRAPH_DIR.to_string()
+}
+
+/// Configuration for exporting OpenTelemetry traces over OTLP.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TelemetryConfig {
+    /// The OTLP/gRPC endpoint of the collector, e.g. `http://localhost:4317`.
+    pub otlp_endpoint: String,
+    /// The `service.name` the traces are reported under.
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+    /// The fraction of new traces to sample, from `0.0` to `1.0`. Traces continued
+    /// from an incoming `traceparent` header follow the caller's decision.
+    #[serde(default = "default_sample_ratio")]
+    pub sample_ratio: f64,
+}
+
+fn default_service_name() -> String {
+    "anyrag-server".to_string()
+}
+
+fn default_sample_ratio() -> f64 {
+    1.0
+}
+
 /// Configuration for the embedding model provider.
 #[derive(Debug, Deserialize, Clone)]
 #[allow(dead_code)]
@@ -341,6 +712,48 @@ pub struct EmbeddingConfig {
     pub api_key: Option<String>,
 }
 
+/// Execution limits enforced by storage providers on every query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryLimits {
+    /// The maximum time a query may run before it is aborted.
+    pub timeout: Duration,
+    /// The maximum number of rows returned; extra rows are discarded.
+    pub max_rows: usize,
+}
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(constants::DEFAULT_QUERY_TIMEOUT_SECS),
+            max_rows: constants::DEFAULT_QUERY_MAX_ROWS,
+        }
+    }
+}
+
+/// Connection settings of a SQLite database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SqliteSettings {
+    /// How long a connection waits for a lock before failing as busy.
+    pub busy_timeout: Duration,
+    /// The number of idle read connections kept for reuse.
+    pub read_pool_size: usize,
+    /// How many times an operation is retried after failing as busy.
+    pub busy_retries: u32,
+    /// The number of prepared statements cached per pooled connection.
+    pub statement_cache_size: usize,
+}
+
+impl Default for SqliteSettings {
+    fn default() -> Self {
+        Self {
+            busy_timeout: Duration::from_millis(constants::DEFAULT_SQLITE_BUSY_TIMEOUT_MS),
+            read_pool_size: constants::DEFAULT_SQLITE_READ_POOL_SIZE,
+            busy_retries: constants::DEFAULT_SQLITE_BUSY_RETRIES,
+            statement_cache_size: constants::DEFAULT_SQLITE_STATEMENT_CACHE_SIZE,
+        }
+    }
+}
+
 /// A reusable configuration for a specific AI provider instance.
 #[derive(Debug, Deserialize, Clone)]
 #[allow(dead_code)]
@@ -352,6 +765,10 @@ pub struct ProviderConfig {
     /// The API key, which can be null for local providers.
     pub api_key: Option<String>,
     pub model_name: String,
+    /// The maximum number of tokens of retrieved context to send to this model.
+    /// When unset, retrieved context is not trimmed.
+    #[serde(default)]
+    pub max_context_tokens: Option<usize>,
 }
 
 /// Defines the prompts and provider for a specific application task from `config.yml`.
@@ -365,6 +782,25 @@ pub struct TaskConfig {
     pub system_prompt: Option<String>,
     #[serde(default)]
     pub user_prompt: Option<String>,
+    /// Whether personal data is masked in the user prompts of this task before they
+    /// are sent to the provider, with the detectors of the `redaction` configuration.
+    #[serde(default)]
+    pub redact: bool,
+}
+
+/// Defines an A/B experiment that splits the requests for one task between two
+/// task configurations (e.g., a new prompt or a different model).
+#[derive(Debug, Deserialize, Clone)]
+pub struct ExperimentConfig {
+    /// The task whose requests are split (e.g., "query_generation").
+    pub task: String,
+    /// The task configuration served to the control group.
+    pub variant_a: String,
+    /// The task configuration served to the treatment group.
+    pub variant_b: String,
+    /// The percentage of requests (0-100) routed to `variant_b`.
+    #[serde(default = "default_experiment_traffic_percent_b")]
+    pub traffic_percent_b: u8,
 }
 
 fn default_temporal_keywords() -> Vec<String> {
@@ -379,6 +815,21 @@ fn default_temporal_property_name() -> String {
     "release_date".to_string()
 }
 
+impl ExperimentConfig {
+    /// Returns the name of the task configuration that serves a variant.
+    pub fn variant_task(&self, variant: ExperimentVariant) -> &str {
+        match variant {
+            ExperimentVariant::A => &self.variant_a,
+            ExperimentVariant::B => &self.variant_b,
+        }
+    }
+}
+
+/// Provides a default value for the `traffic_percent_b` field, an even split.
+fn default_experiment_traffic_percent_b() -> u8 {
+    50
+}
+
 /// Provides a default value for the `port` field if not set in the environment.
 fn default_port() -> u16 {
     9090
@@ -389,6 +840,51 @@ fn default_db_url() -> String {
     constants::DEFAULT_DB_FILE.to_string()
 }
 
+/// Provides a default value for the `query_timeout_secs` field.
+fn default_query_timeout_secs() -> u64 {
+    constants::DEFAULT_QUERY_TIMEOUT_SECS
+}
+
+/// Provides a default value for the `query_max_rows` field.
+fn default_query_max_rows() -> usize {
+    constants::DEFAULT_QUERY_MAX_ROWS
+}
+
+/// Provides a default value for the `sqlite_busy_timeout_ms` field.
+fn default_sqlite_busy_timeout_ms() -> u64 {
+    constants::DEFAULT_SQLITE_BUSY_TIMEOUT_MS
+}
+
+/// Provides a default value for the `sqlite_read_pool_size` field.
+fn default_sqlite_read_pool_size() -> usize {
+    constants::DEFAULT_SQLITE_READ_POOL_SIZE
+}
+
+/// Provides a default value for the `sqlite_busy_retries` field.
+fn default_sqlite_busy_retries() -> u32 {
+    constants::DEFAULT_SQLITE_BUSY_RETRIES
+}
+
+/// Provides a default value for the `sqlite_statement_cache_size` field.
+fn default_sqlite_statement_cache_size() -> usize {
+    constants::DEFAULT_SQLITE_STATEMENT_CACHE_SIZE
+}
+
+/// Provides a default value for the `ingest_concurrency` field.
+fn default_ingest_concurrency() -> usize {
+    constants::DEFAULT_INGEST_CONCURRENCY
+}
+
+/// Provides a default value for the `max_crawl_pages` field.
+fn default_max_crawl_pages() -> usize {
+    constants::DEFAULT_MAX_CRAWL_PAGES
+}
+
+/// Provides a default value for the `max_sitemap_urls` field.
+fn default_max_sitemap_urls() -> usize {
+    constants::DEFAULT_MAX_SITEMAP_URLS
+}
+
 /// Provides a default value for the `web_ingest_strategy` field.
 fn default_web_ingest_strategy() -> String {
     "raw_html".to_string()
@@ -404,24 +900,202 @@ pub struct AppConfig {
     /// The path to the SQLite database file. Loaded from `DB_URL` env var.
     #[serde(default = "default_db_url")]
     pub db_url: String,
+    /// The directory `POST /admin/backups` writes backups to. Defaults to a `backups`
+    /// directory next to the database file. Loaded from `BACKUP_DIR` env var.
+    #[serde(default)]
+    pub backup_dir: Option<String>,
     /// The directory for storing GitHub ingestion databases. Optional.
     #[serde(default)]
     pub github_db_dir: Option<String>,
+    /// The access token `/ingest/github` clones private repositories with when a request
+    /// has no `auth_token` of its own, e.g. a personal access token or a GitHub App
+    /// installation token. Loaded from `GITHUB_TOKEN` env var.
+    #[serde(default)]
+    pub github_token: Option<String>,
     /// An optional API key for the Jina Reader service. Loaded from `JINA_API_KEY` env var.
     #[serde(default)]
     pub jina_api_key: Option<String>,
-    /// The web ingestion strategy to use ("raw_html" or "jina"). Loaded from `WEB_INGEST_STRATEGY` env var.
+    /// The web ingestion strategy to use ("raw_html", "jina", or "headless"). Loaded from `WEB_INGEST_STRATEGY` env var.
     #[serde(default = "default_web_ingest_strategy")]
     pub web_ingest_strategy: String,
+    /// The most pages a web crawl may ingest; a larger `crawl.max_pages` is lowered to
+    /// it. Loaded from `MAX_CRAWL_PAGES` env var.
+    #[serde(default = "default_max_crawl_pages")]
+    pub max_crawl_pages: usize,
+    /// The most pages ingested from one sitemap; a larger `sitemap.max_urls` is lowered
+    /// to it. Loaded from `MAX_SITEMAP_URLS` env var.
+    #[serde(default = "default_max_sitemap_urls")]
+    pub max_sitemap_urls: usize,
+    /// How many LLM calls the web, PDF and sheet ingestors run at once for independent
+    /// chunks. Loaded from `INGEST_CONCURRENCY` env var.
+    #[serde(default = "default_ingest_concurrency")]
+    pub ingest_concurrency: usize,
+    /// Whether the web, PDF and generic ingestion endpoints extract the facts of new
+    /// documents into the knowledge graph. Loaded from `KNOWLEDGE_GRAPH_EXTRACTION` env var.
+    #[serde(default)]
+    pub knowledge_graph_extraction: bool,
+    /// The DevTools address of the headless Chrome used by the "headless" web ingestion
+    /// strategy. Loaded from `HEADLESS_BROWSER_URL` env var.
+    #[serde(default)]
+    pub headless_browser_url: Option<String>,
+    /// The Whisper-compatible endpoint used to transcribe podcast episodes during RSS
+    /// ingestion. Podcasts are not transcribed when unset. Loaded from
+    /// `TRANSCRIPTION_API_URL` env var.
+    #[serde(default)]
+    pub transcription_api_url: Option<String>,
+    /// The API key sent to the transcription endpoint. Loaded from
+    /// `TRANSCRIPTION_API_KEY` env var.
+    #[serde(default)]
+    pub transcription_api_key: Option<String>,
+    /// The transcription model, `whisper-1` when unset. Loaded from
+    /// `TRANSCRIPTION_MODEL` env var.
+    #[serde(default)]
+    pub transcription_model: Option<String>,
+    /// The bot token (`xoxb-...`) used by `/ingest/slack`. Loaded from
+    /// `SLACK_BOT_TOKEN` env var.
+    #[serde(default)]
+    pub slack_bot_token: Option<String>,
+    /// The bot token used by `/ingest/discord`. Loaded from `DISCORD_BOT_TOKEN` env var.
+    #[serde(default)]
+    pub discord_bot_token: Option<String>,
+    /// The Jira site used by `/ingest/jira`, e.g. `https://acme.atlassian.net`. Loaded
+    /// from `JIRA_BASE_URL` env var.
+    #[serde(default)]
+    pub jira_base_url: Option<String>,
+    /// The account email Jira API requests authenticate as. Loaded from `JIRA_EMAIL` env var.
+    #[serde(default)]
+    pub jira_email: Option<String>,
+    /// The API token of the Jira account. Loaded from `JIRA_API_TOKEN` env var.
+    #[serde(default)]
+    pub jira_api_token: Option<String>,
+    /// The S3-compatible endpoint used by `/ingest/objectstore`, e.g.
+    /// `https://storage.googleapis.com` for Cloud Storage. Defaults to the Amazon S3
+    /// endpoint of the region. Loaded from `OBJECT_STORE_ENDPOINT` env var.
+    #[serde(default)]
+    pub object_store_endpoint: Option<String>,
+    /// The region requests to the object store are signed for. Defaults to `us-east-1`.
+    /// Loaded from `OBJECT_STORE_REGION` env var.
+    #[serde(default)]
+    pub object_store_region: Option<String>,
+    /// The access key id, or Cloud Storage HMAC key id, of the object store. Requests
+    /// are unsigned when unset. Loaded from `OBJECT_STORE_ACCESS_KEY_ID` env var.
+    #[serde(default)]
+    pub object_store_access_key_id: Option<String>,
+    /// The secret of the object store access key. Loaded from
+    /// `OBJECT_STORE_SECRET_ACCESS_KEY` env var.
+    #[serde(default)]
+    pub object_store_secret_access_key: Option<String>,
+    /// The maximum execution time of a storage query, in seconds.
+    #[serde(default = "default_query_timeout_secs")]
+    pub query_timeout_secs: u64,
+    /// The maximum number of rows a storage query may return.
+    #[serde(default = "default_query_max_rows")]
+    pub query_max_rows: usize,
+    /// How long a SQLite connection waits for a lock before failing as busy, in
+    /// milliseconds.
+    #[serde(default = "default_sqlite_busy_timeout_ms")]
+    pub sqlite_busy_timeout_ms: u64,
+    /// The number of idle SQLite read connections kept for reuse.
+    #[serde(default = "default_sqlite_read_pool_size")]
+    pub sqlite_read_pool_size: usize,
+    /// How many times a SQLite operation is retried after failing as busy.
+    #[serde(default = "default_sqlite_busy_retries")]
+    pub sqlite_busy_retries: u32,
+    /// The number of prepared statements cached per SQLite read connection.
+    #[serde(default = "default_sqlite_statement_cache_size")]
+    pub sqlite_statement_cache_size: usize,
+    /// BigQuery queries estimated (via dry-run) to scan more bytes than this are refused
+    /// unless the request sets `confirm_expensive_query`. No limit when unset.
+    #[serde(default)]
+    pub bigquery_max_bytes_scanned: Option<u64>,
 
     /// Configuration for temporal reasoning.
     #[serde(default)]
     pub temporal_reasoning: Option<TemporalReasoningConfig>,
 
+    /// Configuration for OpenID Connect authentication. Without it, only tokens signed
+    /// with the shared `JWT_SECRET` are accepted.
+    #[serde(default)]
+    pub oidc: Option<OidcConfig>,
+    /// Configuration for per-user rate limiting. Requests are not rate limited without it.
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Configuration for monthly per-user usage quotas.
+    #[serde(default)]
+    pub quotas: Option<QuotaConfig>,
+    /// Configuration for exporting traces to an OpenTelemetry collector. Spans are
+    /// only logged without it.
+    #[serde(default)]
+    pub telemetry: Option<TelemetryConfig>,
+    /// Configuration for sharding the knowledge base by owner and organization. All
+    /// documents share the database at `db_url` without it.
+    #[serde(default)]
+    pub sharding: Option<ShardingConfig>,
+    /// Configuration for storing the knowledge graph. The graph is kept in memory
+    /// without it.
+    #[serde(default)]
+    pub graph: Option<GraphConfig>,
+    /// Configuration for routing prompts with a classification call. Prompts are routed
+    /// by their `table_name` and `content_type` without it.
+    #[serde(default)]
+    pub routing: Option<RoutingConfig>,
+    /// Configuration for planning prompts that need several queries. Every prompt is
+    /// answered with a single query without it.
+    #[serde(default)]
+    pub planning: Option<PlanningConfig>,
+    /// Configuration for serving cached answers to near-duplicate knowledge questions.
+    /// Every question is answered afresh without it.
+    #[serde(default)]
+    pub answer_cache: Option<AnswerCacheConfig>,
+    /// Configuration for masking personal data in ingested documents and in the prompts
+    /// of the tasks configured with `redact: true`. Ingested documents are stored as
+    /// they are without it, and those tasks use the built-in detectors.
+    #[serde(default)]
+    pub redaction: Option<RedactionConfig>,
+    /// Configuration for scanning retrieved documents for instruction-like text before
+    /// they are put into RAG prompts. Documents are used as they are without it.
+    #[serde(default)]
+    pub context_sanitization: Option<ContextSanitizationConfig>,
+    /// Configuration for moderating final answers, and optionally ingested documents.
+    /// Nothing is moderated without it.
+    #[serde(default)]
+    pub moderation: Option<ModerationConfig>,
+    /// Configuration for storing a summary document for each long ingested source,
+    /// which search expands to the source's chunks. No summaries are made without it.
+    #[serde(default)]
+    pub summarization: Option<SummarizationConfig>,
+    /// Configuration for purging deleted documents from the trash. Deleted documents
+    /// stay in the trash, restorable, until purged by hand without it.
+    #[serde(default)]
+    pub trash: Option<TrashConfig>,
+
     /// Configuration for the text embedding model.
     pub embedding: EmbeddingConfig,
     /// A map of named, reusable AI provider configurations.
     pub providers: HashMap<String, ProviderConfig>,
     /// A map of tasks, each specifying a provider and prompts.
     pub tasks: HashMap<String, TaskConfig>,
+    /// A map of named A/B experiments over the tasks.
+    #[serde(default)]
+    pub experiments: HashMap<String, ExperimentConfig>,
+}
+
+impl AppConfig {
+    /// Returns the storage query limits configured for this application.
+    pub fn query_limits(&self) -> QueryLimits {
+        QueryLimits {
+            timeout: Duration::from_secs(self.query_timeout_secs),
+            max_rows: self.query_max_rows,
+        }
+    }
+
+    /// Returns the SQLite connection settings configured for this application.
+    pub fn sqlite_settings(&self) -> SqliteSettings {
+        SqliteSettings {
+            busy_timeout: Duration::from_millis(self.sqlite_busy_timeout_ms),
+            read_pool_size: self.sqlite_read_pool_size,
+            busy_retries: self.sqlite_busy_retries,
+            statement_cache_size: self.sqlite_statement_cache_size,
+        }
+    }
 }
@@ -0,0 +1,168 @@
+//! # Answer Cache Tests
+//!
+//! This file contains tests for serving cached answers to near-duplicate questions,
+//! and for invalidating them when the documents change, they expire, or the cache is
+//! purged.
+
+mod common;
+
+use anyrag::{
+    answer_cache::{
+        find_cached_answer, purge_answer_cache, store_answer, AnswerCacheConfig, AnswerCacheScope,
+    },
+    providers::db::sqlite::SqliteProvider,
+};
+use common::setup_tracing;
+
+fn scope(instruction: Option<&str>) -> AnswerCacheScope {
+    AnswerCacheScope {
+        owner_id: Some("alice".to_string()),
+        instruction: instruction.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_near_duplicate_questions_are_served_while_documents_are_unchanged() {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await.unwrap();
+    provider.initialize_schema().await.unwrap();
+    provider
+        .initialize_with_data(
+            "INSERT INTO documents (id, owner_id, title, content) VALUES ('doc-a', 'alice', 'Refunds', 'Refunds take 5 days.')",
+        )
+        .await
+        .unwrap();
+    let config = AnswerCacheConfig::default();
+    let version = provider.corpus_version(Some("alice"), None).await.unwrap();
+
+    store_answer(
+        &provider.db,
+        &scope(None),
+        &version,
+        "How long do refunds take?",
+        &[1.0, 0.0, 0.0],
+        "Refunds take 5 days.",
+        &config,
+    )
+    .await
+    .unwrap();
+
+    // 1. A near-duplicate question is served the cached answer.
+    let cached = find_cached_answer(
+        &provider.db,
+        &scope(None),
+        &version,
+        &[0.99, 0.05, 0.0],
+        &config,
+    )
+    .await
+    .unwrap()
+    .expect("The near-duplicate question should be served from the cache.");
+    assert_eq!(cached.answer, "Refunds take 5 days.");
+    assert_eq!(cached.question, "How long do refunds take?");
+
+    // 2. A different question, or the same one under another instruction, is not.
+    assert!(find_cached_answer(
+        &provider.db,
+        &scope(None),
+        &version,
+        &[0.0, 1.0, 0.0],
+        &config
+    )
+    .await
+    .unwrap()
+    .is_none());
+    assert!(find_cached_answer(
+        &provider.db,
+        &scope(Some("Answer in French.")),
+        &version,
+        &[1.0, 0.0, 0.0],
+        &config
+    )
+    .await
+    .unwrap()
+    .is_none());
+
+    // 3. Once a document is added, the corpus version changes and the answer is evicted.
+    provider
+        .initialize_with_data(
+            "INSERT INTO documents (id, owner_id, title, content) VALUES ('doc-b', 'alice', 'Refunds', 'Refunds now take 3 days.')",
+        )
+        .await
+        .unwrap();
+    let new_version = provider.corpus_version(Some("alice"), None).await.unwrap();
+    assert_ne!(version, new_version);
+    assert!(find_cached_answer(
+        &provider.db,
+        &scope(None),
+        &new_version,
+        &[1.0, 0.0, 0.0],
+        &config
+    )
+    .await
+    .unwrap()
+    .is_none());
+    assert!(
+        find_cached_answer(
+            &provider.db,
+            &scope(None),
+            &version,
+            &[1.0, 0.0, 0.0],
+            &config
+        )
+        .await
+        .unwrap()
+        .is_none(),
+        "The stale answer should have been deleted."
+    );
+}
+
+#[tokio::test]
+async fn test_expired_and_purged_answers_are_not_served() {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await.unwrap();
+    provider.initialize_schema().await.unwrap();
+    let expired = AnswerCacheConfig {
+        ttl_seconds: 0,
+        ..Default::default()
+    };
+    let config = AnswerCacheConfig::default();
+
+    store_answer(
+        &provider.db,
+        &scope(None),
+        "v1",
+        "Q",
+        &[1.0, 0.0],
+        "Old",
+        &expired,
+    )
+    .await
+    .unwrap();
+    assert!(
+        find_cached_answer(&provider.db, &scope(None), "v1", &[1.0, 0.0], &config)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    store_answer(
+        &provider.db,
+        &scope(None),
+        "v1",
+        "Q",
+        &[1.0, 0.0],
+        "New",
+        &config,
+    )
+    .await
+    .unwrap();
+    assert_eq!(purge_answer_cache(&provider.db).await.unwrap(), 1);
+    assert!(
+        find_cached_answer(&provider.db, &scope(None), "v1", &[1.0, 0.0], &config)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
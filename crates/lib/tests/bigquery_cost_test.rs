@@ -0,0 +1,28 @@
+//! # BigQuery Cost Guard Tests
+//!
+//! This file contains tests for refusing BigQuery queries whose dry-run estimate is
+//! above the configured number of bytes scanned, unless the caller confirmed them.
+
+#[cfg(feature = "bigquery")]
+use anyrag::{providers::db::bigquery::check_bytes_scanned, PromptError};
+
+#[cfg(feature = "bigquery")]
+#[test]
+fn test_queries_over_the_cost_limit_are_refused() {
+    let result = check_bytes_scanned(2_000_000, 1_000_000, false);
+
+    assert!(matches!(
+        result,
+        Err(PromptError::QueryCostExceeded {
+            estimated_bytes: 2_000_000,
+            max_bytes: 1_000_000,
+        })
+    ));
+}
+
+#[cfg(feature = "bigquery")]
+#[test]
+fn test_confirmed_or_cheap_queries_are_run() {
+    assert!(check_bytes_scanned(2_000_000, 1_000_000, true).is_ok());
+    assert!(check_bytes_scanned(1_000_000, 1_000_000, false).is_ok());
+}
@@ -0,0 +1,144 @@
+//! # Bulk Insert Tests
+//!
+//! This file contains tests for the batched inserts shared by the ingestors, checking
+//! that rows beyond one statement's parameter limit are all stored and that duplicate
+//! content is skipped.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::{
+    ingest::{bulk_insert_documents, bulk_insert_rows, NewDocument},
+    providers::db::sqlite::SqliteProvider,
+};
+use common::setup_tracing;
+use turso::Value;
+
+fn document(id: &str, source_url: &str, content: &str) -> NewDocument {
+    NewDocument {
+        id: id.to_string(),
+        source_url: source_url.to_string(),
+        title: id.to_string(),
+        content: content.to_string(),
+    }
+}
+
+async fn count(provider: &SqliteProvider, table: &str) -> Result<i64> {
+    let conn = provider.db.connect()?;
+    let mut rows = conn
+        .query(&format!("SELECT COUNT(*) FROM {table}"), ())
+        .await?;
+    let row = rows.next().await?.expect("COUNT(*) returns a row");
+    Ok(row.get(0)?)
+}
+
+#[tokio::test]
+async fn test_bulk_insert_rows_spans_several_statements() -> Result<()> {
+    // --- Arrange ---
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider
+        .initialize_with_data("CREATE TABLE pairs (a INTEGER, b TEXT)")
+        .await?;
+    // 2,000 rows of 2 values need 3 statements of at most 999 parameters.
+    let rows: Vec<Vec<Value>> = (0..2_000i64)
+        .map(|n| vec![Value::Integer(n), Value::Text(format!("row {n}"))])
+        .collect();
+
+    // --- Act ---
+    let conn = provider.db.connect()?;
+    let changed = bulk_insert_rows(&conn, "INSERT INTO pairs (a, b)", "", rows).await?;
+
+    // --- Assert ---
+    assert_eq!(changed, 2_000);
+    assert_eq!(count(&provider, "pairs").await?, 2_000);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bulk_insert_documents_skips_duplicate_content() -> Result<()> {
+    // --- Arrange ---
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let mut conn = provider.db.connect()?;
+    bulk_insert_documents(
+        &mut conn,
+        Some("alice"),
+        vec![document("existing", "a.md#0", "Already stored.")],
+    )
+    .await?;
+
+    // --- Act ---
+    let ids = bulk_insert_documents(
+        &mut conn,
+        Some("alice"),
+        vec![
+            document("repeat", "b.md#0", "Already stored."),
+            document("first", "b.md#1", "New content."),
+            document("copy", "b.md#2", "New content."),
+            document("second", "b.md#3", "Other content."),
+        ],
+    )
+    .await?;
+    // Another owner may store the same content.
+    let other_ids = bulk_insert_documents(
+        &mut conn,
+        Some("bob"),
+        vec![document("bobs", "c.md#0", "Already stored.")],
+    )
+    .await?;
+
+    // --- Assert ---
+    assert_eq!(ids, vec!["first".to_string(), "second".to_string()]);
+    assert_eq!(other_ids, vec!["bobs".to_string()]);
+    assert_eq!(count(&provider, "documents").await?, 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bulk_insert_documents_reingest_returns_the_stored_ids() -> Result<()> {
+    // --- Arrange ---
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let mut conn = provider.db.connect()?;
+    let first_ids = bulk_insert_documents(
+        &mut conn,
+        Some("alice"),
+        vec![document("first", "a.md#0", "First version.")],
+    )
+    .await?;
+    let bobs_ids = bulk_insert_documents(
+        &mut conn,
+        Some("bob"),
+        vec![document("bobs", "a.md#0", "Bob's version.")],
+    )
+    .await?;
+
+    // --- Act: Re-ingest the source with a new id ---
+    let ids = bulk_insert_documents(
+        &mut conn,
+        Some("alice"),
+        vec![document("second", "a.md#0", "Second version.")],
+    )
+    .await?;
+
+    // --- Assert: The stored document is updated under its id ---
+    assert_eq!(first_ids, vec!["first".to_string()]);
+    assert_eq!(ids, first_ids);
+    assert_eq!(count(&provider, "documents").await?, 2);
+    let mut rows = conn
+        .query(
+            "SELECT id, content FROM documents WHERE source_url = 'a.md#0' ORDER BY id",
+            (),
+        )
+        .await?;
+    let bobs = rows.next().await?.expect("Bob's document is kept");
+    assert_eq!(bobs.get::<String>(0)?, bobs_ids[0]);
+    assert_eq!(bobs.get::<String>(1)?, "Bob's version.");
+    let alices = rows.next().await?.expect("Alice's document is stored");
+    assert_eq!(alices.get::<String>(0)?, "first");
+    assert_eq!(alices.get::<String>(1)?, "Second version.");
+    Ok(())
+}
@@ -0,0 +1,68 @@
+//! # Chart Output Tests
+//!
+//! This file contains tests for charting query results: the validation of chart specs
+//! and the `output: "chart"` option of the prompt pipeline.
+
+mod common;
+
+use anyrag::{
+    chart::{parse_chart_spec, ChartType},
+    types::OutputFormat,
+    ExecutePromptOptions, PromptClientBuilder,
+};
+use common::{setup_tracing, MockAiProvider, MockStorageProvider};
+use serde_json::{json, Value};
+
+#[test]
+fn test_parse_chart_spec_checks_series_against_labels() {
+    let spec = parse_chart_spec(
+        r#"{"chart_type": "bar", "labels": ["Q1", "Q2"], "series": [{"name": "Revenue", "data": [100, 150.5]}]}"#,
+    )
+    .unwrap();
+    assert_eq!(spec.chart_type, ChartType::Bar);
+    assert_eq!(spec.series[0].data, vec![100.0, 150.5]);
+
+    let errors = parse_chart_spec(
+        r#"{"chart_type": "pie", "labels": ["Q1", "Q2"], "series": [{"name": "A", "data": [1]}, {"name": "B", "data": [1, 2]}]}"#,
+    )
+    .unwrap_err();
+    assert_eq!(errors.len(), 2, "Unexpected errors: {errors:?}");
+    assert!(errors.iter().any(|e| e.contains("a single series")));
+    assert!(errors.iter().any(|e| e.starts_with("$.series[0].data")));
+
+    assert!(parse_chart_spec(r#"{"chart_type": "radar", "labels": [], "series": []}"#).is_err());
+}
+
+#[tokio::test]
+async fn test_chart_output_is_repaired_after_one_retry() {
+    setup_tracing();
+
+    let mock_ai_provider = MockAiProvider::new(vec![
+        "SELECT quarter, SUM(amount) AS revenue FROM orders GROUP BY quarter".to_string(),
+        r#"{"chart_type": "bar", "labels": ["Q1", "Q2"], "series": [{"name": "revenue", "data": [100]}]}"#.to_string(),
+        r#"{"chart_type": "bar", "labels": ["Q1", "Q2"], "series": [{"name": "revenue", "data": [100, 150]}]}"#.to_string(),
+    ]);
+    let call_history = mock_ai_provider.call_history.clone();
+    let client = PromptClientBuilder::new()
+        .ai_provider(Box::new(mock_ai_provider))
+        .storage_provider(Box::new(MockStorageProvider))
+        .build()
+        .unwrap();
+
+    let options = ExecutePromptOptions {
+        prompt: "Revenue by quarter".to_string(),
+        table_name: Some("orders".to_string()),
+        output: Some(OutputFormat::Chart),
+        ..Default::default()
+    };
+    let result = client.execute_prompt_with_options(options).await.unwrap();
+
+    let chart: Value = serde_json::from_str(&result.text).unwrap();
+    assert_eq!(chart["labels"], json!(["Q1", "Q2"]));
+    assert_eq!(chart["series"][0]["data"], json!([100.0, 150.0]));
+
+    let history = call_history.read().unwrap();
+    assert_eq!(history.len(), 3);
+    assert!(history[1].1.contains("\"chart_type\""));
+    assert!(history[2].1.contains("expected 2 values"));
+}
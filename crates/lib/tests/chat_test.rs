@@ -0,0 +1,139 @@
+//! # Chat Session Tests
+//!
+//! This file contains tests for the `ChatClient`, verifying that conversation
+//! turns are persisted per session and that follow-ups are rewritten using history.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::{
+    providers::db::sqlite::SqliteProvider, ChatClient, ChatMessage, ChatRole, ExecutePromptOptions,
+};
+use common::MockAiProvider;
+use std::sync::Arc;
+
+async fn setup_provider() -> Result<Arc<SqliteProvider>> {
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    Ok(Arc::new(provider))
+}
+
+fn message(role: ChatRole, content: &str) -> ChatMessage {
+    ChatMessage {
+        role,
+        content: content.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_chat_history_is_persisted_per_session_and_owner() -> Result<()> {
+    // --- Arrange ---
+    let sqlite_provider = setup_provider().await?;
+    let chat_client = ChatClient::new(
+        Box::new(MockAiProvider::new(vec![])),
+        sqlite_provider.clone(),
+    );
+
+    // --- Act ---
+    chat_client
+        .append_message(
+            "s1",
+            Some("alice"),
+            &message(ChatRole::User, "Sales in May?"),
+        )
+        .await?;
+    chat_client
+        .append_message(
+            "s1",
+            Some("alice"),
+            &message(ChatRole::Assistant, "42 units."),
+        )
+        .await?;
+    chat_client
+        .append_message(
+            "s2",
+            Some("alice"),
+            &message(ChatRole::User, "Other session"),
+        )
+        .await?;
+
+    // --- Assert ---
+    let history = chat_client.load_history("s1", Some("alice"), 10).await?;
+    assert_eq!(
+        history,
+        vec![
+            message(ChatRole::User, "Sales in May?"),
+            message(ChatRole::Assistant, "42 units."),
+        ]
+    );
+
+    // Another owner cannot read the session.
+    let foreign_history = chat_client.load_history("s1", Some("bob"), 10).await?;
+    assert!(foreign_history.is_empty());
+
+    // The limit keeps only the most recent messages, still oldest first.
+    let recent = chat_client.load_history("s1", Some("alice"), 1).await?;
+    assert_eq!(recent, vec![message(ChatRole::Assistant, "42 units.")]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_follow_up_is_rewritten_with_history() -> Result<()> {
+    // --- Arrange ---
+    let sqlite_provider = setup_provider().await?;
+    let mock_ai_provider = MockAiProvider::new(vec!["What were the sales in April?".to_string()]);
+    let call_history = mock_ai_provider.call_history.clone();
+    let chat_client = ChatClient::new(Box::new(mock_ai_provider), sqlite_provider);
+    let history = vec![
+        message(ChatRole::User, "What were the sales in May?"),
+        message(ChatRole::Assistant, "42 units."),
+    ];
+
+    // --- Act ---
+    let standalone = chat_client
+        .rewrite_follow_up(
+            &history,
+            "what about last month?",
+            "SYSTEM",
+            "{history}\n{prompt}",
+        )
+        .await?;
+    let first_turn = chat_client
+        .rewrite_follow_up(
+            &[],
+            "What were the sales in May?",
+            "SYSTEM",
+            "{history}\n{prompt}",
+        )
+        .await?;
+
+    // --- Assert ---
+    assert_eq!(standalone, "What were the sales in April?");
+    assert_eq!(first_turn, "What were the sales in May?");
+
+    // Only the follow-up needed the AI, and it received the formatted history.
+    let calls = call_history.read().unwrap();
+    assert_eq!(calls.len(), 1);
+    assert!(calls[0].1.contains("user: What were the sales in May?"));
+    assert!(calls[0].1.contains("assistant: 42 units."));
+    assert!(calls[0].1.ends_with("what about last month?"));
+
+    Ok(())
+}
+
+#[test]
+fn test_history_deserializes_in_prompt_options() {
+    let options: ExecutePromptOptions = serde_json::from_value(serde_json::json!({
+        "prompt": "what about last month?",
+        "history": [
+            { "role": "user", "content": "Sales in May?" },
+            { "role": "assistant", "content": "42 units." }
+        ]
+    }))
+    .unwrap();
+
+    let history = options.history.unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[1].role, ChatRole::Assistant);
+}
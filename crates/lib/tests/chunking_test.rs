@@ -0,0 +1,109 @@
+//! # Chunking Strategy Tests
+//!
+//! This file contains tests for the `Chunker` implementations and for selecting
+//! them through the `chunking` JSON accepted by ingestors.
+
+use anyrag::ingest::{
+    chunking::{
+        FixedSizeChunker, MarkdownChunker, ParagraphChunker, RecursiveChunker, SentenceChunker,
+    },
+    Chunker, ChunkingStrategy,
+};
+
+#[test]
+fn test_paragraph_chunker_keeps_legacy_behavior() {
+    let text = "First paragraph.\n\n\n\nSecond paragraph.";
+    let chunks = ParagraphChunker::default().chunk(text);
+    assert_eq!(chunks, vec!["First paragraph.", "Second paragraph."]);
+
+    let long_text = "a".repeat(5000);
+    let chunks = ParagraphChunker::default().chunk(&long_text);
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0].len(), 4096);
+    assert_eq!(chunks[1].len(), 5000 - (4096 - 200));
+}
+
+#[test]
+fn test_fixed_size_chunker_overlaps_windows() {
+    let chunker = FixedSizeChunker {
+        chunk_size: 4,
+        chunk_overlap: 1,
+    };
+    assert_eq!(chunker.chunk("abcdefghi"), vec!["abcd", "defg", "ghi"]);
+}
+
+#[test]
+fn test_recursive_chunker_prefers_coarse_separators() {
+    let chunker = RecursiveChunker {
+        chunk_size: 20,
+        ..Default::default()
+    };
+    let chunks = chunker.chunk("aaaa bbbb cccc dddd eeee\n\nffff gggg");
+    assert_eq!(chunks, vec!["aaaa bbbb cccc dddd", "eeee", "ffff gggg"]);
+    assert!(chunks.iter().all(|c| c.chars().count() <= 20));
+}
+
+#[test]
+fn test_markdown_chunker_splits_on_headings() {
+    let markdown = "# Title\n\nIntro.\n\n## Setup\n\n```sh\n# a comment, not a heading\n```\nRun it.\n\n## Usage\n### Basics\nCall it.";
+    let chunks = MarkdownChunker { chunk_size: 4096 }.chunk(markdown);
+
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(chunks[0], "# Title\n\nIntro.");
+    assert!(chunks[1].starts_with("## Setup") && chunks[1].contains("# a comment"));
+    // A heading without a body stays with the section that follows it.
+    assert_eq!(chunks[2], "## Usage\n### Basics\nCall it.");
+}
+
+#[test]
+fn test_sentence_chunker_never_cuts_sentences() {
+    let chunker = SentenceChunker { chunk_size: 30 };
+    let chunks = chunker.chunk("Hello world. Pi is 3.14 today! Is it? Yes.");
+    assert_eq!(
+        chunks,
+        vec!["Hello world. Pi is 3.14 today!", "Is it? Yes."]
+    );
+}
+
+#[test]
+fn test_chunking_strategy_from_json() {
+    let strategy: ChunkingStrategy =
+        serde_json::from_str(r#"{"strategy": "sentence", "chunk_size": 30}"#).unwrap();
+    assert_eq!(strategy, ChunkingStrategy::Sentence { chunk_size: 30 });
+
+    // Omitted parameters fall back to the defaults.
+    let strategy: ChunkingStrategy = serde_json::from_str(r#"{"strategy": "fixed_size"}"#).unwrap();
+    assert_eq!(
+        strategy,
+        ChunkingStrategy::FixedSize {
+            chunk_size: 4096,
+            chunk_overlap: 200
+        }
+    );
+
+    let chunks = ChunkingStrategy::Markdown { chunk_size: 4096 }
+        .chunker()
+        .unwrap()
+        .chunk("# A\nOne.\n# B\nTwo.");
+    assert_eq!(chunks, vec!["# A\nOne.", "# B\nTwo."]);
+
+    assert!(serde_json::from_str::<ChunkingStrategy>(r#"{"strategy": "unknown"}"#).is_err());
+}
+
+#[test]
+fn test_chunking_strategy_rejects_sizes_that_cannot_produce_chunks() {
+    let overlapping = ChunkingStrategy::FixedSize {
+        chunk_size: 4,
+        chunk_overlap: 4,
+    };
+    let empty = ChunkingStrategy::Sentence { chunk_size: 0 };
+    assert!(overlapping.chunker().is_err());
+    assert!(empty.chunker().is_err());
+
+    // A chunker built directly still advances through the whole text.
+    let chunker = FixedSizeChunker {
+        chunk_size: 4,
+        chunk_overlap: 4,
+    };
+    assert_eq!(chunker.chunk("abcdef"), vec!["abcd", "bcde", "cdef"]);
+}
@@ -0,0 +1,180 @@
+//! # Document Collection Tests
+//!
+//! This file contains tests for creating and managing collections of documents, and for
+//! limiting searches to the documents of a collection.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::{
+    collections::{
+        add_documents_to_collection, collection_documents, create_collection, delete_collection,
+        list_collections, remove_document_from_collection, update_collection, CollectionError,
+    },
+    ingest::soft_delete_document,
+    providers::db::{
+        sqlite::SqliteProvider,
+        storage::{CollectionSearch, KeywordSearch, MetadataSearch},
+    },
+};
+use common::setup_tracing;
+use turso::{params, Connection};
+
+async fn insert_document(conn: &Connection, id: &str, owner_id: &str, content: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO documents (id, owner_id, source_url, title, content) VALUES (?, ?, ?, ?, ?)",
+        params![
+            id,
+            owner_id,
+            format!("https://example.com/{id}"),
+            id,
+            content
+        ],
+    )
+    .await?;
+    conn.execute(
+        "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_value) VALUES (?, ?, 'KEYPHRASE', 'leave')",
+        params![id, owner_id],
+    )
+    .await?;
+    Ok(())
+}
+
+async fn setup() -> Result<SqliteProvider> {
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let conn = provider.db.connect()?;
+    insert_document(
+        &conn,
+        "api-auth",
+        "alice",
+        "Request leave of the API token.",
+    )
+    .await?;
+    insert_document(&conn, "hr-leave", "alice", "Annual leave is 25 days.").await?;
+    insert_document(&conn, "bob-leave", "bob", "Bob's leave notes.").await?;
+    Ok(provider)
+}
+
+#[tokio::test]
+async fn test_collection_crud() -> Result<()> {
+    setup_tracing();
+    let provider = setup().await?;
+    let db = &provider.db;
+
+    // 1. Names are required and unique per owner.
+    let hr = create_collection(db, "alice", "HR policies", Some("Leave and benefits")).await?;
+    assert!(matches!(
+        create_collection(db, "alice", "HR policies", None).await,
+        Err(CollectionError::NameTaken(_))
+    ));
+    assert!(matches!(
+        create_collection(db, "alice", "  ", None).await,
+        Err(CollectionError::MissingName)
+    ));
+    create_collection(db, "bob", "HR policies", None).await?;
+    create_collection(db, "alice", "API docs", None).await?;
+    let names: Vec<String> = list_collections(db, "alice")
+        .await?
+        .into_iter()
+        .map(|collection| collection.name)
+        .collect();
+    assert_eq!(names, vec!["API docs", "HR policies"]);
+
+    // 2. Documents are added by id, and only those of the owner can be.
+    let hr = add_documents_to_collection(db, "alice", &hr.id, &["hr-leave".to_string()]).await?;
+    assert_eq!(hr.document_count, 1);
+    assert!(matches!(
+        add_documents_to_collection(db, "alice", "HR policies", &["bob-leave".to_string()]).await,
+        Err(CollectionError::DocumentNotFound(_))
+    ));
+    assert_eq!(
+        collection_documents(db, "alice", "HR policies").await?,
+        vec!["hr-leave"]
+    );
+
+    // 3. Collections are renamed, and found by their new name.
+    let renamed = update_collection(db, "alice", "HR policies", Some("People"), None).await?;
+    assert_eq!(renamed.id, hr.id);
+    assert_eq!(renamed.description.as_deref(), Some("Leave and benefits"));
+    assert!(matches!(
+        update_collection(db, "alice", "People", Some("API docs"), None).await,
+        Err(CollectionError::NameTaken(_))
+    ));
+
+    // 4. Removing a document or deleting the collection keeps the documents.
+    assert!(remove_document_from_collection(db, "alice", "People", "hr-leave").await?);
+    assert!(!remove_document_from_collection(db, "alice", "People", "hr-leave").await?);
+    delete_collection(db, "alice", "People").await?;
+    assert!(matches!(
+        collection_documents(db, "alice", "People").await,
+        Err(CollectionError::NotFound(_))
+    ));
+    let conn = provider.db.connect()?;
+    let mut rows = conn.query("SELECT COUNT(*) FROM documents", ()).await?;
+    assert_eq!(rows.next().await?.unwrap().get::<i64>(0)?, 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_search_is_limited_to_the_collection() -> Result<()> {
+    setup_tracing();
+    let provider = setup().await?;
+    let db = &provider.db;
+    create_collection(db, "alice", "HR policies", None).await?;
+    add_documents_to_collection(db, "alice", "HR policies", &["hr-leave".to_string()]).await?;
+
+    // 1. The collection resolves by name to the documents visible to the owner.
+    let ids = provider
+        .collection_document_ids("HR policies", Some("alice"), None)
+        .await?;
+    assert_eq!(ids, vec!["hr-leave"]);
+    // Another user's collection of the same name is not theirs.
+    assert!(provider
+        .collection_document_ids("HR policies", Some("bob"), None)
+        .await?
+        .is_empty());
+
+    // 2. Keyword and metadata searches only find the documents of the collection.
+    let keyword = provider
+        .keyword_search("leave", 10, Some("alice"), None, Some(&ids))
+        .await?;
+    assert_eq!(
+        keyword.iter().map(|r| r.link.as_str()).collect::<Vec<_>>(),
+        vec!["https://example.com/hr-leave"]
+    );
+    let metadata = provider
+        .metadata_search(
+            &[],
+            &["leave".to_string()],
+            Some("alice"),
+            None,
+            10,
+            Some(&ids),
+        )
+        .await?;
+    assert_eq!(
+        metadata.iter().map(|r| r.link.as_str()).collect::<Vec<_>>(),
+        vec!["https://example.com/hr-leave"]
+    );
+
+    // 3. A document in the trash leaves the collection's search.
+    let conn = provider.db.connect()?;
+    soft_delete_document(&conn, "hr-leave", Some("alice")).await?;
+    assert!(provider
+        .collection_document_ids("HR policies", Some("alice"), None)
+        .await?
+        .is_empty());
+    assert!(provider
+        .metadata_search(
+            &[],
+            &["leave".to_string()],
+            Some("alice"),
+            None,
+            10,
+            Some([].as_slice())
+        )
+        .await?
+        .is_empty());
+    Ok(())
+}
@@ -0,0 +1,107 @@
+//! # Context Budget Tests
+//!
+//! This file contains tests for `ContextBudget` to ensure that ranked search
+//! results are trimmed to fit a token budget and that cuts are recorded.
+
+use anyrag::{
+    context_budget::{fit_context, ContextBudget, DropReason},
+    types::SearchResult,
+};
+
+fn result(link: &str, description: &str) -> SearchResult {
+    SearchResult {
+        title: link.to_string(),
+        link: link.to_string(),
+        description: description.to_string(),
+        score: 0.0,
+    }
+}
+
+#[test]
+fn test_budget_keeps_everything_when_it_fits() {
+    let results = vec![result("a", "Short one."), result("b", "Short two.")];
+
+    let budgeted = ContextBudget::new(100).apply(results);
+
+    assert_eq!(budgeted.results.len(), 2);
+    assert!(budgeted.dropped.is_empty());
+    assert_eq!(budgeted.used_tokens, 6); // 10 chars each -> 3 tokens each
+}
+
+#[test]
+fn test_budget_truncates_at_sentence_boundary_and_drops_the_rest() {
+    // The first result uses 10 tokens, leaving room for only the first sentence of the second.
+    let first = "This first result fits entirely here...";
+    let second = "Second result sentence one. Sentence two is much longer text.";
+    let results = vec![
+        result("a", first),
+        result("b", second),
+        result("c", "Never included."),
+    ];
+
+    let budgeted = ContextBudget::new(18).apply(results);
+
+    // --- Assert kept results ---
+    assert_eq!(budgeted.results.len(), 2);
+    assert_eq!(budgeted.results[0].description, first);
+    assert_eq!(
+        budgeted.results[1].description,
+        "Second result sentence one."
+    );
+    assert!(budgeted.used_tokens <= 18);
+
+    // --- Assert recorded cuts ---
+    assert_eq!(budgeted.dropped.len(), 2);
+    assert_eq!(budgeted.dropped[0].link, "b");
+    assert_eq!(budgeted.dropped[0].reason, DropReason::Truncated);
+    assert_eq!(budgeted.dropped[1].link, "c");
+    assert_eq!(budgeted.dropped[1].reason, DropReason::OverBudget);
+    assert_eq!(budgeted.dropped[1].kept_tokens, 0);
+}
+
+#[test]
+fn test_budget_drops_result_without_a_fitting_sentence() {
+    let results = vec![result(
+        "a",
+        "One very long sentence without any break at all",
+    )];
+
+    let budgeted = ContextBudget::new(2).apply(results);
+
+    assert!(budgeted.results.is_empty());
+    assert_eq!(budgeted.dropped.len(), 1);
+    assert_eq!(budgeted.dropped[0].reason, DropReason::OverBudget);
+}
+
+#[test]
+fn test_budget_keeps_packing_smaller_results_after_a_skipped_one() {
+    let results = vec![
+        result("a", "Short one."),
+        result("b", "One very long sentence without any break at all"),
+        result("c", "Short two."),
+    ];
+
+    let budgeted = ContextBudget::new(8).apply(results);
+
+    // The long result is skipped, but the smaller one ranked below it still fits.
+    assert_eq!(budgeted.results.len(), 2);
+    assert_eq!(budgeted.results[0].link, "a");
+    assert_eq!(budgeted.results[1].link, "c");
+    assert_eq!(budgeted.used_tokens, 6);
+    assert_eq!(budgeted.dropped.len(), 1);
+    assert_eq!(budgeted.dropped[0].link, "b");
+    assert_eq!(budgeted.dropped[0].reason, DropReason::OverBudget);
+}
+
+#[test]
+fn test_fit_context_keeps_everything_without_a_budget() {
+    let results = vec![result(
+        "a",
+        "One very long sentence without any break at all",
+    )];
+
+    let budgeted = fit_context(None, results);
+
+    assert_eq!(budgeted.results.len(), 1);
+    assert!(budgeted.dropped.is_empty());
+}
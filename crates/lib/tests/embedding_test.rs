@@ -0,0 +1,113 @@
+//! # Ingestion Embedding Tests
+//!
+//! This file contains tests for embedding the documents an ingestion stored, in
+//! batches, skipping those that already have a vector of the model.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::{
+    ingest::{
+        bulk_insert_documents, embed_documents, embed_ingested_documents,
+        find_unembedded_documents, EmbeddingError, NewDocument,
+    },
+    providers::db::sqlite::SqliteProvider,
+    types::EmbeddingConfig,
+};
+use common::{setup_mock_embedding_server, setup_tracing};
+
+/// Sets up a database with three documents, returning it and their ids.
+async fn setup_documents() -> Result<(SqliteProvider, Vec<String>)> {
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let mut conn = provider.db.connect()?;
+    let documents = ["Rust", "Tokio", "Axum"]
+        .into_iter()
+        .map(|title| NewDocument {
+            id: format!("doc-{title}"),
+            source_url: format!("https://example.com/{title}"),
+            title: title.to_string(),
+            content: format!("{title} is part of the Rust ecosystem."),
+        })
+        .collect();
+    let document_ids = bulk_insert_documents(&mut conn, Some("alice"), documents).await?;
+    Ok((provider, document_ids))
+}
+
+fn embedding_config(api_url: String) -> EmbeddingConfig {
+    EmbeddingConfig {
+        api_url,
+        model_name: "mock-model".to_string(),
+        api_key: None,
+    }
+}
+
+#[tokio::test]
+async fn test_only_the_given_unembedded_documents_are_embedded() -> Result<()> {
+    // --- Arrange ---
+    setup_tracing();
+    let (provider, document_ids) = setup_documents().await?;
+    let mock_server = setup_mock_embedding_server().await;
+    let config = embedding_config(format!("{}/v1/embeddings", mock_server.uri()));
+    let conn = provider.db.connect()?;
+
+    // --- Act ---
+    let first = embed_documents(&conn, &config, &document_ids[..2], 1).await?;
+    let again = embed_documents(&conn, &config, &document_ids, 1).await?;
+
+    // --- Assert ---
+    assert_eq!(first, 2);
+    assert_eq!(again, 1);
+    assert!(find_unembedded_documents(&conn, "mock-model", None)
+        .await?
+        .is_empty());
+    assert_eq!(mock_server.received_requests().await.unwrap().len(), 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_a_short_batch_of_vectors_stores_nothing_of_it() -> Result<()> {
+    // --- Arrange ---
+    setup_tracing();
+    let (provider, document_ids) = setup_documents().await?;
+    // The mock returns one vector per request, whatever the batch size.
+    let mock_server = setup_mock_embedding_server().await;
+    let config = embedding_config(format!("{}/v1/embeddings", mock_server.uri()));
+    let conn = provider.db.connect()?;
+
+    // --- Act ---
+    let result = embed_documents(&conn, &config, &document_ids, 2).await;
+
+    // --- Assert ---
+    assert!(matches!(result, Err(EmbeddingError::Embedding(_))));
+    assert_eq!(
+        find_unembedded_documents(&conn, "mock-model", None)
+            .await?
+            .len(),
+        3
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_a_failed_ingestion_embedding_keeps_the_documents() -> Result<()> {
+    // --- Arrange ---
+    setup_tracing();
+    let (provider, document_ids) = setup_documents().await?;
+    let mock_server = setup_mock_embedding_server().await;
+    let config = embedding_config(format!("{}/v1/missing", mock_server.uri()));
+
+    // --- Act ---
+    let embedded = embed_ingested_documents(&provider.db, &config, &document_ids).await;
+
+    // --- Assert ---
+    assert_eq!(embedded, 0);
+    let conn = provider.db.connect()?;
+    assert_eq!(
+        find_unembedded_documents(&conn, "mock-model", None)
+            .await?
+            .len(),
+        3
+    );
+    Ok(())
+}
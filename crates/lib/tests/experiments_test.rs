@@ -0,0 +1,111 @@
+//! # A/B Experiment Tests
+//!
+//! This file contains tests for routing requests between experiment variants and for
+//! recording and summarizing their outcomes.
+
+mod common;
+
+use anyrag::{
+    experiments::{
+        find_experiment, record_experiment_feedback, record_experiment_outcome,
+        summarize_experiment, variant_for_roll, ExperimentError, ExperimentOutcome,
+    },
+    providers::db::sqlite::SqliteProvider,
+    types::{ExperimentConfig, ExperimentVariant},
+};
+use common::setup_tracing;
+use std::collections::HashMap;
+
+fn experiment_config(traffic_percent_b: u8) -> ExperimentConfig {
+    ExperimentConfig {
+        task: "query_generation".to_string(),
+        variant_a: "query_generation".to_string(),
+        variant_b: "query_generation_v2".to_string(),
+        traffic_percent_b,
+    }
+}
+
+fn outcome(variant: ExperimentVariant, latency_ms: u64, success: bool) -> ExperimentOutcome {
+    ExperimentOutcome {
+        experiment: "sql_prompt".to_string(),
+        variant,
+        task: experiment_config(50).variant_task(variant).to_string(),
+        latency_ms,
+        prompt_tokens: 100,
+        completion_tokens: 10,
+        success,
+    }
+}
+
+#[test]
+fn test_variant_assignment_follows_traffic_split() {
+    let config = experiment_config(20);
+    assert_eq!(variant_for_roll(&config, 0), ExperimentVariant::B);
+    assert_eq!(variant_for_roll(&config, 19), ExperimentVariant::B);
+    assert_eq!(variant_for_roll(&config, 20), ExperimentVariant::A);
+    assert_eq!(variant_for_roll(&config, 99), ExperimentVariant::A);
+
+    // A split of 0 keeps all traffic on the control group.
+    let control_only = experiment_config(0);
+    assert!((0..100).all(|roll| variant_for_roll(&control_only, roll) == ExperimentVariant::A));
+}
+
+#[test]
+fn test_find_experiment_matches_task() {
+    let mut experiments = HashMap::new();
+    experiments.insert("sql_prompt".to_string(), experiment_config(50));
+
+    let (name, config) = find_experiment(&experiments, "query_generation").unwrap();
+    assert_eq!(name, "sql_prompt");
+    assert_eq!(
+        config.variant_task(ExperimentVariant::B),
+        "query_generation_v2"
+    );
+    assert!(find_experiment(&experiments, "rag_synthesis").is_none());
+}
+
+#[tokio::test]
+async fn test_experiment_outcomes_are_summarized_per_variant() {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await.unwrap();
+    provider.initialize_schema().await.unwrap();
+
+    let run_a = record_experiment_outcome(&provider.db, &outcome(ExperimentVariant::A, 100, true))
+        .await
+        .unwrap();
+    record_experiment_outcome(&provider.db, &outcome(ExperimentVariant::A, 300, false))
+        .await
+        .unwrap();
+    let run_b = record_experiment_outcome(&provider.db, &outcome(ExperimentVariant::B, 50, true))
+        .await
+        .unwrap();
+    assert_ne!(run_a.id, run_b.id);
+
+    record_experiment_feedback(&provider.db, run_a.id, false)
+        .await
+        .unwrap();
+    record_experiment_feedback(&provider.db, run_b.id, true)
+        .await
+        .unwrap();
+    let missing = record_experiment_feedback(&provider.db, 9999, true).await;
+    assert!(matches!(missing, Err(ExperimentError::RunNotFound(9999))));
+
+    let summary = summarize_experiment(&provider.db, "sql_prompt")
+        .await
+        .unwrap();
+    assert_eq!(summary.variants.len(), 2);
+
+    let a = &summary.variants[0];
+    assert_eq!(a.variant, ExperimentVariant::A);
+    assert_eq!(a.task, "query_generation");
+    assert_eq!(a.runs, 2);
+    assert_eq!(a.success_rate, 0.5);
+    assert_eq!(a.avg_latency_ms, 200.0);
+    assert_eq!((a.positive_feedback, a.negative_feedback), (0, 1));
+
+    let b = &summary.variants[1];
+    assert_eq!(b.variant, ExperimentVariant::B);
+    assert_eq!(b.task, "query_generation_v2");
+    assert_eq!(b.runs, 1);
+    assert_eq!((b.positive_feedback, b.negative_feedback), (1, 0));
+}
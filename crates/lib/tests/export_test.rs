@@ -0,0 +1,98 @@
+//! # Knowledge Base Export Tests
+//!
+//! This file contains tests for exporting documents, with their metadata and
+//! embeddings, to JSONL and Parquet.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::{
+    ingest::{
+        export::{export_documents, ExportedDocument},
+        export_knowledge_base, ExportFormat,
+    },
+    providers::db::sqlite::SqliteProvider,
+};
+use common::setup_tracing;
+
+/// Sets up a database with a document of `alice`, with metadata and an embedding, and
+/// a document of `bob`.
+async fn setup_database() -> Result<SqliteProvider> {
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    provider
+        .initialize_with_data(
+            "INSERT INTO documents (id, owner_id, title, content, created_at) VALUES ('doc-a', 'alice', 'Roadmap', 'Ship exports', '2025-01-01 00:00:00');
+             INSERT INTO documents (id, owner_id, title, content, created_at) VALUES ('doc-b', 'bob', 'Notes', 'Private notes', '2025-01-02 00:00:00');
+             INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_subtype, metadata_value) VALUES ('doc-a', 'alice', 'ENTITY', 'PRODUCT', 'anyrag');
+             INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_value) VALUES ('doc-a', 'alice', 'KEYPHRASE', 'exports');
+             INSERT INTO document_embeddings (document_id, model_name, embedding) VALUES ('doc-a', 'mock-model', X'0000803F00000040');",
+        )
+        .await?;
+    Ok(provider)
+}
+
+#[tokio::test]
+async fn test_export_documents_is_scoped_to_the_owner() -> Result<()> {
+    // --- Arrange ---
+    setup_tracing();
+    let provider = setup_database().await?;
+
+    // --- Act ---
+    let all = export_documents(&provider.db, None).await?;
+    let alice = export_documents(&provider.db, Some("alice")).await?;
+
+    // --- Assert ---
+    assert_eq!(
+        all.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(),
+        vec!["doc-a", "doc-b"]
+    );
+    assert_eq!(alice.len(), 1);
+    let document = &alice[0];
+    assert_eq!(document.title.as_deref(), Some("Roadmap"));
+    assert_eq!(document.metadata.len(), 2);
+    assert_eq!(
+        document.metadata[0].metadata_subtype.as_deref(),
+        Some("PRODUCT")
+    );
+    assert_eq!(document.metadata[1].metadata_value, "exports");
+    assert_eq!(document.embeddings.len(), 1);
+    assert_eq!(document.embeddings[0].model_name, "mock-model");
+    assert_eq!(document.embeddings[0].vector, vec![1.0, 2.0]);
+    assert!(all[1].metadata.is_empty() && all[1].embeddings.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_jsonl_round_trips() -> Result<()> {
+    // --- Arrange ---
+    setup_tracing();
+    let provider = setup_database().await?;
+
+    // --- Act ---
+    let output = export_knowledge_base(&provider.db, ExportFormat::Jsonl, None).await?;
+
+    // --- Assert: Each line is one document ---
+    let documents = String::from_utf8(output)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<ExportedDocument>, _>>()?;
+    assert_eq!(documents, export_documents(&provider.db, None).await?);
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+#[tokio::test]
+async fn test_export_parquet_writes_a_parquet_file() -> Result<()> {
+    // --- Arrange ---
+    setup_tracing();
+    let provider = setup_database().await?;
+
+    // --- Act ---
+    let output = export_knowledge_base(&provider.db, ExportFormat::Parquet, Some("alice")).await?;
+
+    // --- Assert: The file starts and ends with the Parquet magic number ---
+    assert!(output.starts_with(b"PAR1"));
+    assert!(output.ends_with(b"PAR1"));
+    Ok(())
+}
@@ -0,0 +1,152 @@
+//! # FAQ Generation Tests
+//!
+//! This file contains tests for distilling the question and answer pairs of a document
+//! into `faq_items`, with their provenance and without repeating questions.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::{
+    ingest::{generate_faqs, KnowledgeError},
+    prompts::tasks::{KNOWLEDGE_DISTILLATION_SYSTEM_PROMPT, KNOWLEDGE_DISTILLATION_USER_PROMPT},
+    providers::db::sqlite::SqliteProvider,
+};
+use common::{setup_tracing, MockAiProvider};
+use serde_json::json;
+use turso::{params, Connection};
+
+async fn insert_document(conn: &Connection, id: &str, owner_id: &str, content: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO documents (id, owner_id, source_url, title, content) VALUES (?, ?, ?, ?, ?)",
+        params![
+            id,
+            owner_id,
+            format!("https://example.com/{id}"),
+            id,
+            content
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+fn distillation(faqs: serde_json::Value) -> String {
+    format!(
+        "```json\n{}\n```",
+        json!({ "faqs": faqs, "content_chunks": [] })
+    )
+}
+
+#[tokio::test]
+async fn test_generate_faqs_stores_pairs_with_provenance() -> Result<()> {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let conn = provider.db.connect()?;
+    insert_document(&conn, "refunds", "alice", "Refunds take 5 days.").await?;
+    let ai_provider = MockAiProvider::new(vec![distillation(json!([
+        { "question": "How long do refunds take?", "answer": "5 days.", "is_explicit": true },
+        { "question": "Are refunds slow?", "answer": "They take 5 days.", "is_explicit": false }
+    ]))]);
+
+    let generation = generate_faqs(
+        &conn,
+        &ai_provider,
+        "refunds",
+        KNOWLEDGE_DISTILLATION_SYSTEM_PROMPT,
+        KNOWLEDGE_DISTILLATION_USER_PROMPT,
+    )
+    .await?;
+
+    assert_eq!((generation.stored, generation.duplicates), (2, 0));
+    assert!(ai_provider.call_history.read().unwrap()[0]
+        .1
+        .contains("Refunds take 5 days."));
+    let mut rows = conn
+        .query(
+            "SELECT document_id, owner_id, question, source_url, is_explicit FROM faq_items ORDER BY id",
+            (),
+        )
+        .await?;
+    let row = rows.next().await?.expect("The first FAQ should be stored.");
+    assert_eq!(row.get::<String>(0)?, "refunds");
+    assert_eq!(row.get::<String>(1)?, "alice");
+    assert_eq!(row.get::<String>(2)?, "How long do refunds take?");
+    assert_eq!(row.get::<String>(3)?, "https://example.com/refunds");
+    assert_eq!(row.get::<i64>(4)?, 1);
+    let row = rows
+        .next()
+        .await?
+        .expect("The second FAQ should be stored.");
+    assert_eq!(row.get::<i64>(4)?, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_generate_faqs_skips_questions_the_owner_already_has() -> Result<()> {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let conn = provider.db.connect()?;
+    insert_document(&conn, "refunds", "alice", "Refunds take 5 days.").await?;
+    insert_document(&conn, "policy", "alice", "Refunds take five days.").await?;
+    insert_document(&conn, "bob_policy", "bob", "Refunds take a week.").await?;
+    let ai_provider = MockAiProvider::new(vec![
+        distillation(json!([{ "question": "How long do refunds take?", "answer": "5 days." }])),
+        distillation(json!([
+            { "question": "how long do  refunds take", "answer": "Five days." },
+            { "question": "Who pays for returns?", "answer": "We do." },
+            { "question": "Who pays for returns?", "answer": "The store." }
+        ])),
+        distillation(json!([{ "question": "How long do refunds take?", "answer": "A week." }])),
+    ]);
+
+    for (document_id, expected) in [
+        ("refunds", (1, 0)),
+        ("policy", (1, 2)),
+        ("bob_policy", (1, 0)),
+    ] {
+        let generation = generate_faqs(
+            &conn,
+            &ai_provider,
+            document_id,
+            KNOWLEDGE_DISTILLATION_SYSTEM_PROMPT,
+            KNOWLEDGE_DISTILLATION_USER_PROMPT,
+        )
+        .await?;
+        assert_eq!(
+            (generation.stored, generation.duplicates),
+            expected,
+            "{document_id}"
+        );
+    }
+
+    // Another owner's questions are not duplicates.
+    let mut rows = conn
+        .query("SELECT COUNT(*) FROM faq_items WHERE owner_id = 'bob'", ())
+        .await?;
+    assert_eq!(rows.next().await?.unwrap().get::<i64>(0)?, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_generate_faqs_for_a_missing_document_fails() -> Result<()> {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let conn = provider.db.connect()?;
+    let ai_provider = MockAiProvider::new(vec![]);
+
+    let result = generate_faqs(
+        &conn,
+        &ai_provider,
+        "missing",
+        KNOWLEDGE_DISTILLATION_SYSTEM_PROMPT,
+        KNOWLEDGE_DISTILLATION_USER_PROMPT,
+    )
+    .await;
+
+    assert!(matches!(result, Err(KnowledgeError::NotFound(id)) if id == "missing"));
+    assert!(ai_provider.call_history.read().unwrap().is_empty());
+    Ok(())
+}
@@ -0,0 +1,141 @@
+//! # Answer Feedback Tests
+//!
+//! This file contains tests for recording feedback, accepting corrections into the
+//! few-shot example store, and showing those examples to the model.
+
+mod common;
+
+use anyrag::{
+    feedback::{
+        accept_correction, find_few_shot_examples, record_feedback, FeedbackError, NewFeedback,
+    },
+    providers::db::sqlite::SqliteProvider,
+    types::FewShotExample,
+    ExecutePromptOptions, PromptClientBuilder,
+};
+use common::{setup_tracing, MockAiProvider, MockStorageProvider};
+
+fn feedback(prompt: &str, correction: Option<&str>) -> NewFeedback {
+    NewFeedback {
+        owner_id: None,
+        result_id: "result-1".to_string(),
+        prompt: prompt.to_string(),
+        positive: false,
+        correction: correction.map(str::to_string),
+    }
+}
+
+#[tokio::test]
+async fn test_accepted_corrections_become_few_shot_examples() {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await.unwrap();
+    provider.initialize_schema().await.unwrap();
+
+    let refund_id = record_feedback(
+        &provider.db,
+        &feedback(
+            "How long do refunds take?",
+            Some("Refunds take 5 business days."),
+        ),
+    )
+    .await
+    .unwrap();
+    let shipping_id = record_feedback(
+        &provider.db,
+        &feedback("Where is my parcel?", Some("Track it on the orders page.")),
+    )
+    .await
+    .unwrap();
+    let rating_only_id = record_feedback(&provider.db, &feedback("Hello?", None))
+        .await
+        .unwrap();
+
+    // Nothing is shown to the model until a correction is accepted.
+    let examples = find_few_shot_examples(&provider.db, None, "How long will my refund take?", 3)
+        .await
+        .unwrap();
+    assert!(examples.is_empty());
+
+    accept_correction(&provider.db, refund_id).await.unwrap();
+    accept_correction(&provider.db, shipping_id).await.unwrap();
+    let missing = accept_correction(&provider.db, rating_only_id).await;
+    assert!(matches!(missing, Err(FeedbackError::MissingCorrection(_))));
+    let unknown = accept_correction(&provider.db, 9999).await;
+    assert!(matches!(unknown, Err(FeedbackError::NotFound(9999))));
+
+    // Only the example that shares words with the prompt is returned.
+    let examples = find_few_shot_examples(&provider.db, None, "How long will my refund take?", 3)
+        .await
+        .unwrap();
+    assert_eq!(
+        examples,
+        vec![FewShotExample {
+            prompt: "How long do refunds take?".to_string(),
+            answer: "Refunds take 5 business days.".to_string(),
+        }]
+    );
+}
+
+#[tokio::test]
+async fn test_few_shot_examples_are_only_shown_to_their_owner() {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await.unwrap();
+    provider.initialize_schema().await.unwrap();
+    provider
+        .initialize_with_data("INSERT INTO users (id) VALUES ('alice'), ('bob')")
+        .await
+        .unwrap();
+
+    let alice_feedback = NewFeedback {
+        owner_id: Some("alice".to_string()),
+        ..feedback(
+            "How long do refunds take?",
+            Some("Refunds take 5 business days."),
+        )
+    };
+    let feedback_id = record_feedback(&provider.db, &alice_feedback)
+        .await
+        .unwrap();
+    accept_correction(&provider.db, feedback_id).await.unwrap();
+
+    let prompt = "How long will my refund take?";
+    let for_alice = find_few_shot_examples(&provider.db, Some("alice"), prompt, 3)
+        .await
+        .unwrap();
+    let for_bob = find_few_shot_examples(&provider.db, Some("bob"), prompt, 3)
+        .await
+        .unwrap();
+    let for_anonymous = find_few_shot_examples(&provider.db, None, prompt, 3)
+        .await
+        .unwrap();
+    assert_eq!(for_alice.len(), 1);
+    assert!(for_bob.is_empty());
+    assert!(for_anonymous.is_empty());
+}
+
+#[tokio::test]
+async fn test_few_shot_examples_are_added_to_the_prompt() {
+    setup_tracing();
+    let mock_ai_provider = MockAiProvider::new(vec!["Refunds take 5 business days.".to_string()]);
+    let call_history = mock_ai_provider.call_history.clone();
+    let client = PromptClientBuilder::new()
+        .ai_provider(Box::new(mock_ai_provider))
+        .storage_provider(Box::new(MockStorageProvider))
+        .build()
+        .unwrap();
+
+    let options = ExecutePromptOptions {
+        prompt: "How long will my refund take?".to_string(),
+        few_shot_examples: Some(vec![FewShotExample {
+            prompt: "How long do refunds take?".to_string(),
+            answer: "Refunds take 5 business days.".to_string(),
+        }]),
+        ..Default::default()
+    };
+    client.execute_prompt_with_options(options).await.unwrap();
+
+    let history = call_history.read().unwrap();
+    assert!(history[0].1.contains(
+        "# EXAMPLES OF ACCEPTED ANSWERS\nQ: How long do refunds take?\nA: Refunds take 5 business days."
+    ));
+}
@@ -0,0 +1,208 @@
+//! # Fine-Tuning Export Tests
+//!
+//! This file contains tests for exporting the FAQs of the knowledge base in the OpenAI,
+//! Gemini and Alpaca formats, and for filtering them by owner, link prefix, creation
+//! date and feedback score.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::{
+    ingest::{export_finetuning_dataset, FinetuningExportOptions, FinetuningFormat},
+    providers::db::sqlite::SqliteProvider,
+};
+use chrono::{TimeZone, Utc};
+use common::setup_tracing;
+use serde_json::Value;
+use turso::{params, Connection};
+
+const REFUNDS_YAML: &str = r#"
+sections:
+  - title: "Refunds"
+    faqs:
+      - question: "How long do refunds take?"
+        answer: "Refunds take 5 days."
+"#;
+
+async fn insert_document(
+    conn: &Connection,
+    id: &str,
+    owner_id: &str,
+    source_url: &str,
+    content: &str,
+    created_at: &str,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO documents (id, owner_id, source_url, title, content, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+        params![id, owner_id, source_url, id, content, created_at],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Stores a structured document and a document with a distilled FAQ for `alice`, and a
+/// structured document for `bob`.
+async fn setup_database() -> Result<SqliteProvider> {
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let conn = provider.db.connect()?;
+    insert_document(
+        &conn,
+        "refunds",
+        "alice",
+        "https://docs.example.com/refunds",
+        REFUNDS_YAML,
+        "2024-01-10 09:00:00",
+    )
+    .await?;
+    insert_document(
+        &conn,
+        "shipping",
+        "alice",
+        "https://blog.example.com/shipping",
+        "Shipping is free over $50.",
+        "2024-03-10 09:00:00",
+    )
+    .await?;
+    conn.execute(
+        "INSERT INTO faq_items (document_id, owner_id, question, answer) VALUES (?, ?, ?, ?)",
+        params![
+            "shipping",
+            "alice",
+            "Is shipping free?",
+            "Shipping is free over $50."
+        ],
+    )
+    .await?;
+    insert_document(
+        &conn,
+        "bob_refunds",
+        "bob",
+        "https://docs.example.com/bob",
+        REFUNDS_YAML,
+        "2024-01-10 09:00:00",
+    )
+    .await?;
+    Ok(provider)
+}
+
+fn entries(jsonl: &str) -> Result<Vec<Value>> {
+    Ok(jsonl
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?)
+}
+
+fn alice(format: FinetuningFormat) -> FinetuningExportOptions {
+    FinetuningExportOptions {
+        format,
+        owner_id: Some("alice".to_string()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_finetuning_export_formats() -> Result<()> {
+    setup_tracing();
+    let provider = setup_database().await?;
+
+    // 1. OpenAI: chat messages, with the structured FAQs before the distilled ones.
+    let openai =
+        entries(&export_finetuning_dataset(&provider.db, &alice(FinetuningFormat::Openai)).await?)?;
+    assert_eq!(openai.len(), 2);
+    assert_eq!(openai[0]["messages"][0]["role"], "system");
+    assert_eq!(
+        openai[0]["messages"][1]["content"],
+        "How long do refunds take?"
+    );
+    assert_eq!(openai[1]["messages"][2]["role"], "assistant");
+    assert_eq!(
+        openai[1]["messages"][2]["content"],
+        "Shipping is free over $50."
+    );
+
+    // 2. Gemini: a system instruction, and user and model turns.
+    let gemini =
+        entries(&export_finetuning_dataset(&provider.db, &alice(FinetuningFormat::Gemini)).await?)?;
+    assert!(gemini[0]["systemInstruction"]["parts"][0]["text"].is_string());
+    assert_eq!(gemini[0]["contents"][0]["role"], "user");
+    assert_eq!(
+        gemini[0]["contents"][0]["parts"][0]["text"],
+        "How long do refunds take?"
+    );
+    assert_eq!(gemini[0]["contents"][1]["role"], "model");
+
+    // 3. Alpaca: an instruction and its output.
+    let alpaca =
+        entries(&export_finetuning_dataset(&provider.db, &alice(FinetuningFormat::Alpaca)).await?)?;
+    assert_eq!(alpaca[1]["instruction"], "Is shipping free?");
+    assert_eq!(alpaca[1]["input"], "");
+    assert_eq!(alpaca[1]["output"], "Shipping is free over $50.");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_finetuning_export_filters_by_source_and_date() -> Result<()> {
+    setup_tracing();
+    let provider = setup_database().await?;
+
+    // 1. Without an owner, every owner's FAQs are exported.
+    let all = export_finetuning_dataset(&provider.db, &FinetuningExportOptions::default()).await?;
+    assert_eq!(all.lines().count(), 3);
+
+    // 2. Only the documents under the link prefix.
+    let options = FinetuningExportOptions {
+        source_prefix: Some("https://docs.example.com/".to_string()),
+        ..alice(FinetuningFormat::Alpaca)
+    };
+    let docs = entries(&export_finetuning_dataset(&provider.db, &options).await?)?;
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0]["instruction"], "How long do refunds take?");
+
+    // 3. Only the documents created in the range.
+    let options = FinetuningExportOptions {
+        since: Some(Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap()),
+        until: Some(Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap()),
+        ..alice(FinetuningFormat::Alpaca)
+    };
+    let recent = entries(&export_finetuning_dataset(&provider.db, &options).await?)?;
+    assert_eq!(recent.len(), 1);
+    assert_eq!(recent[0]["instruction"], "Is shipping free?");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_finetuning_export_filters_by_feedback_score() -> Result<()> {
+    setup_tracing();
+    let provider = setup_database().await?;
+    let conn = provider.db.connect()?;
+    for (prompt, positive) in [
+        ("how long do refunds take", 1),
+        ("How long do refunds take?", 1),
+        ("Is shipping free?", 0),
+    ] {
+        conn.execute(
+            "INSERT INTO feedback (owner_id, result_id, prompt, positive) VALUES (?, ?, ?, ?)",
+            params!["alice", "result", prompt, positive],
+        )
+        .await?;
+    }
+
+    for (min_feedback_score, expected) in [
+        (2, vec!["How long do refunds take?"]),
+        (0, vec!["How long do refunds take?"]),
+        (-1, vec!["How long do refunds take?", "Is shipping free?"]),
+    ] {
+        let options = FinetuningExportOptions {
+            min_feedback_score: Some(min_feedback_score),
+            ..alice(FinetuningFormat::Alpaca)
+        };
+        let exported = entries(&export_finetuning_dataset(&provider.db, &options).await?)?;
+        let questions: Vec<&str> = exported
+            .iter()
+            .map(|entry| entry["instruction"].as_str().unwrap())
+            .collect();
+        assert_eq!(questions, expected, "min score {min_feedback_score}");
+    }
+    Ok(())
+}
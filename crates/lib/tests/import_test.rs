@@ -0,0 +1,152 @@
+//! # LangChain and LlamaIndex Import Tests
+//!
+//! This file contains tests for importing the document exports of LangChain and
+//! LlamaIndex, keeping their vectors only when they match the configured model.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::{
+    ingest::{import::parse_export, import_documents, ImportFormat},
+    providers::db::sqlite::SqliteProvider,
+    types::EmbeddingConfig,
+};
+use common::{setup_mock_embedding_server, setup_tracing};
+use serde_json::json;
+use turso::Value as TursoValue;
+
+const LANGCHAIN_EXPORT: &str = r#"{"page_content": "Rust is a systems language.", "metadata": {"source": "docs/rust.md"}, "embedding": [1.0, 0.0, 0.0, 0.0]}
+{"lc": 1, "type": "constructor", "id": ["langchain", "schema", "document", "Document"], "kwargs": {"page_content": "Tokio is an async runtime.", "metadata": {"source": "docs/tokio.md", "title": "Tokio"}}}
+{"page_content": "Rust is a systems language.", "metadata": {"source": "docs/copy.md"}}
+"#;
+
+async fn embeddings(provider: &SqliteProvider) -> Result<Vec<(String, Vec<f32>)>> {
+    let conn = provider.db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT model_name, embedding FROM document_embeddings ORDER BY id",
+            (),
+        )
+        .await?;
+    let mut embeddings = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let (TursoValue::Text(model), TursoValue::Blob(bytes)) =
+            (row.get_value(0)?, row.get_value(1)?)
+        {
+            let vector = bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+            embeddings.push((model, vector));
+        }
+    }
+    Ok(embeddings)
+}
+
+#[test]
+fn test_parse_langchain_plain_and_dumpd_documents() -> Result<()> {
+    let documents = parse_export(ImportFormat::Langchain, LANGCHAIN_EXPORT)?;
+
+    assert_eq!(documents.len(), 3);
+    assert_eq!(documents[0].embedding, Some(vec![1.0, 0.0, 0.0, 0.0]));
+    assert_eq!(documents[1].content, "Tokio is an async runtime.");
+    assert_eq!(documents[1].metadata["title"], "Tokio");
+    assert_eq!(documents[1].embedding, None);
+    Ok(())
+}
+
+#[test]
+fn test_parse_llamaindex_docstore_with_vector_store() -> Result<()> {
+    let export = json!({
+        "docstore/data": {
+            "node-1": {"__type__": "1", "__data__": {"id_": "node-1", "text": "First node.", "embedding": [0.5, 0.5], "metadata": {"file_name": "a.txt"}}},
+            "node-2": {"__type__": "1", "__data__": "{\"id_\": \"node-2\", \"text\": \"Second node.\", \"embedding\": null, \"metadata\": {}}"}
+        },
+        "embedding_dict": {"node-2": [0.25, 0.75]}
+    });
+
+    let documents = parse_export(ImportFormat::Llamaindex, &export.to_string())?;
+
+    assert_eq!(documents.len(), 2);
+    let second = documents
+        .iter()
+        .find(|d| d.id.as_deref() == Some("node-2"))
+        .unwrap();
+    assert_eq!(second.content, "Second node.");
+    assert_eq!(second.embedding, Some(vec![0.25, 0.75]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_import_keeps_vectors_of_the_configured_model() -> Result<()> {
+    // --- Arrange ---
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let mock_server = setup_mock_embedding_server().await;
+    let config = EmbeddingConfig {
+        api_url: format!("{}/v1/embeddings", mock_server.uri()),
+        model_name: "mock-model".to_string(),
+        api_key: None,
+    };
+
+    // --- Act ---
+    let summary = import_documents(
+        &provider.db,
+        ImportFormat::Langchain,
+        LANGCHAIN_EXPORT,
+        Some("alice"),
+        Some("mock-model"),
+        Some(&config),
+    )
+    .await?;
+
+    // --- Assert ---
+    assert_eq!(summary.document_ids.len(), 2);
+    assert_eq!(summary.duplicates_skipped, 1);
+    assert_eq!(summary.embeddings_copied, 1);
+    assert_eq!(summary.embeddings_generated, 1);
+    let embeddings = embeddings(&provider).await?;
+    assert_eq!(
+        embeddings[0],
+        ("mock-model".to_string(), vec![1.0, 0.0, 0.0, 0.0])
+    );
+    assert_eq!(embeddings[1].1, vec![0.99, 0.01, 0.0, 0.0]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_import_re_embeds_vectors_of_another_model() -> Result<()> {
+    // --- Arrange ---
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let mock_server = setup_mock_embedding_server().await;
+    let config = EmbeddingConfig {
+        api_url: format!("{}/v1/embeddings", mock_server.uri()),
+        model_name: "mock-model".to_string(),
+        api_key: None,
+    };
+    let export = r#"{"page_content": "Only document.", "metadata": {}, "embedding": [1.0, 0.0]}"#;
+
+    // --- Act ---
+    let summary = import_documents(
+        &provider.db,
+        ImportFormat::Langchain,
+        export,
+        None,
+        Some("text-embedding-3-small"),
+        Some(&config),
+    )
+    .await?;
+
+    // --- Assert ---
+    assert_eq!(summary.embeddings_copied, 0);
+    assert_eq!(summary.embeddings_generated, 1);
+    let embeddings = embeddings(&provider).await?;
+    assert_eq!(
+        embeddings,
+        vec![("mock-model".to_string(), vec![0.99, 0.01, 0.0, 0.0])]
+    );
+    Ok(())
+}
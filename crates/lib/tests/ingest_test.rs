@@ -3,7 +3,7 @@
 //! This file contains integration tests for the text chunking logic
 //! provided in the `anyrag` library.
 
-use anyrag_text::{chunk_text, TextIngestError};
+use anyrag_text::{chunk_text, TextIngestError, DEFAULT_CHUNK_OVERLAP, DEFAULT_CHUNK_SIZE};
 
 // It's good practice to define constants used in tests, especially if they
 // mirror constants in the implementation, to catch accidental changes.
@@ -14,7 +14,7 @@ const CHUNK_OVERLAP: usize = 200;
 #[test]
 fn test_chunk_text_simple() {
     let text = "This is a short text.\n\nIt has two paragraphs.";
-    let chunks = chunk_text(text).unwrap();
+    let chunks = chunk_text(text, DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_OVERLAP).unwrap();
     assert_eq!(chunks.len(), 2);
     assert_eq!(chunks[0], "This is a short text.");
     assert_eq!(chunks[1], "It has two paragraphs.");
@@ -24,7 +24,7 @@ fn test_chunk_text_simple() {
 #[test]
 fn test_chunk_text_empty_input() {
     let text = "";
-    let result = chunk_text(text);
+    let result = chunk_text(text, DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_OVERLAP);
     assert!(matches!(result, Err(TextIngestError::EmptyContent)));
 }
 
@@ -33,7 +33,7 @@ fn test_chunk_text_empty_input() {
 #[test]
 fn test_chunk_text_whitespace_input() {
     let text = "   \t\n  ";
-    let result = chunk_text(text);
+    let result = chunk_text(text, DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_OVERLAP);
     assert!(matches!(result, Err(TextIngestError::EmptyContent)));
 }
 
@@ -42,7 +42,7 @@ fn test_chunk_text_whitespace_input() {
 #[test]
 fn test_long_paragraph_gets_split() {
     let long_paragraph = "a".repeat(5000);
-    let chunks = chunk_text(&long_paragraph).unwrap();
+    let chunks = chunk_text(&long_paragraph, DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_OVERLAP).unwrap();
 
     // The logic should be:
     // Chunk 1: Chars 0..4096
@@ -72,7 +72,7 @@ fn test_mixed_length_paragraphs() {
     let long_paragraph = "b".repeat(6000);
     let text = format!("{short_paragraph}\n\n{long_paragraph}");
 
-    let chunks = chunk_text(&text).unwrap();
+    let chunks = chunk_text(&text, DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_OVERLAP).unwrap();
 
     // Expect 1 chunk for the short paragraph and 2 for the long one.
     // Chunk 1: short_paragraph
@@ -94,7 +94,7 @@ fn test_no_infinite_loop_on_overlap() {
     // Create a text that is just slightly longer than the step size (limit - overlap).
     // An incorrect loop condition might fail to terminate here.
     let text = "c".repeat(CHUNK_SIZE_LIMIT - CHUNK_OVERLAP + 1);
-    let chunks = chunk_text(&text).unwrap();
+    let chunks = chunk_text(&text, DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_OVERLAP).unwrap();
 
     // It should produce only one chunk because the next starting point would be <= the current one.
     assert_eq!(chunks.len(), 1);
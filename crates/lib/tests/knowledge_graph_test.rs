@@ -1,5 +1,8 @@
 #[cfg(feature = "graph_db")]
-use anyrag::graph::types::{MemoryKnowledgeGraph, RocksdbKnowledgeGraph};
+use anyrag::graph::{
+    query::FactVisibility,
+    types::{MemoryKnowledgeGraph, RocksdbKnowledgeGraph},
+};
 use chrono::{DateTime, Duration, Utc};
 use tempfile::{tempdir, TempDir};
 
@@ -48,7 +51,7 @@ impl KnowledgeGraphTest for MemoryKnowledgeGraph {
         predicate: &str,
         as_of: DateTime<Utc>,
     ) -> Option<String> {
-        self.get_fact_as_of(subject, predicate, as_of)
+        self.get_fact_as_of(subject, predicate, as_of, &FactVisibility::All)
             .expect("Failed to get fact in MemoryKnowledgeGraph")
     }
 }
@@ -86,7 +89,7 @@ impl KnowledgeGraphTest for RocksDbTestHarness {
         as_of: DateTime<Utc>,
     ) -> Option<String> {
         self.kg
-            .get_fact_as_of(subject, predicate, as_of)
+            .get_fact_as_of(subject, predicate, as_of, &FactVisibility::All)
             .expect("Failed to get fact in RocksdbKnowledgeGraph")
     }
 }
@@ -177,3 +180,329 @@ fn test_rocksdb_knowledge_graph_suite() {
     };
     run_test_time_constrained_fact_retrieval(&mut harness2);
 }
+
+#[test]
+#[cfg(feature = "graph_db")]
+fn test_document_facts_link_back_to_their_document() {
+    use anyrag::graph::extraction::ExtractedFact;
+
+    let mut kg = MemoryKnowledgeGraph::new_memory();
+    let facts = vec![
+        ExtractedFact {
+            subject: "Alice".to_string(),
+            predicate: "Works At".to_string(),
+            object: "Acme".to_string(),
+            valid_from: Some("2020-01-01".to_string()),
+            valid_to: None,
+        },
+        ExtractedFact {
+            subject: " ".to_string(),
+            predicate: "role".to_string(),
+            object: "Developer".to_string(),
+            valid_from: None,
+            valid_to: None,
+        },
+    ];
+
+    let added = kg.add_document_facts("doc-1", &facts);
+    assert_eq!(added, 1, "The fact without a subject should be skipped.");
+
+    let now = Utc::now();
+    assert_eq!(
+        kg.get_fact_as_of("Alice", "works_at", now, &FactVisibility::All)
+            .unwrap(),
+        Some("Acme".to_string())
+    );
+    assert_eq!(
+        kg.get_fact_source_as_of("Alice", "works_at", now, &FactVisibility::All)
+            .unwrap(),
+        Some("doc-1".to_string())
+    );
+    let before = DateTime::parse_from_rfc3339("2019-06-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    assert_eq!(
+        kg.get_fact_as_of("Alice", "works_at", before, &FactVisibility::All)
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+#[cfg(feature = "graph_db")]
+fn test_document_facts_are_only_visible_with_one_of_their_documents() {
+    use anyrag::graph::{extraction::ExtractedFact, types::KnowledgeGraphError};
+    use std::collections::HashSet;
+
+    let fact = |subject: &str, predicate: &str, object: &str| ExtractedFact {
+        subject: subject.to_string(),
+        predicate: predicate.to_string(),
+        object: object.to_string(),
+        valid_from: None,
+        valid_to: None,
+    };
+    let mut kg = MemoryKnowledgeGraph::new_memory();
+    kg.add_document_facts("doc-a", &[fact("Alice", "works_at", "Acme")]);
+    kg.add_document_facts(
+        "doc-b",
+        &[
+            fact("Alice", "works_at", "Acme"),
+            fact("Bob", "salary", "100k"),
+        ],
+    );
+    let now = Utc::now();
+    let reader_of = |id: &str| FactVisibility::Documents(HashSet::from([id.to_string()]));
+
+    // A fact stated by both documents is visible with either, naming only that one.
+    let facts = kg
+        .facts_about_as_of("Alice", now, &reader_of("doc-a"))
+        .unwrap();
+    assert_eq!(facts.len(), 1);
+    assert_eq!(facts[0].source_documents, vec!["doc-a"]);
+    let facts = kg
+        .facts_about_as_of("Alice", now, &FactVisibility::All)
+        .unwrap();
+    assert_eq!(facts[0].source_documents, vec!["doc-a", "doc-b"]);
+
+    // The facts of documents the reader may not see are hidden, and so are their entities.
+    assert_eq!(
+        kg.get_fact_as_of("Bob", "salary", now, &reader_of("doc-a"))
+            .unwrap(),
+        None
+    );
+    assert!(matches!(
+        kg.neighbors("Bob", None, &reader_of("doc-a")),
+        Err(KnowledgeGraphError::EntityNotFound(_))
+    ));
+    assert_eq!(
+        kg.neighbors("Bob", None, &reader_of("doc-b"))
+            .unwrap()
+            .edges
+            .len(),
+        1
+    );
+}
+
+#[test]
+#[cfg(feature = "graph_db")]
+fn test_persistent_knowledge_graph_survives_reopen_and_compaction() {
+    use anyrag::graph::persistent::PersistentKnowledgeGraph;
+
+    let dir = tempdir().unwrap();
+    let start = DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let end = DateTime::parse_from_rfc3339("9999-12-31T23:59:59Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let now = Utc::now();
+
+    {
+        let mut graph = PersistentKnowledgeGraph::open(dir.path()).unwrap();
+        graph
+            .graph_mut()
+            .add_fact("Alice", "role", "Engineer", start, end)
+            .unwrap();
+        graph.sync().unwrap();
+    }
+
+    let mut graph = PersistentKnowledgeGraph::open(dir.path()).unwrap();
+    assert_eq!(
+        graph
+            .graph()
+            .get_fact_as_of("Alice", "role", now, &FactVisibility::All)
+            .unwrap(),
+        Some("Engineer".to_string()),
+        "The fact should be loaded from disk."
+    );
+    assert!(graph.graph().entity_map.contains_key("Alice"));
+
+    let previous_store = graph.store_dir();
+    let stats = graph.compact().unwrap();
+    assert_eq!(stats.entities, 2);
+    assert_eq!(stats.facts, 1);
+    assert!(!previous_store.exists(), "The old store should be removed.");
+    drop(graph);
+
+    let graph = PersistentKnowledgeGraph::open(dir.path()).unwrap();
+    assert_eq!(
+        graph
+            .graph()
+            .get_fact_as_of("Alice", "role", now, &FactVisibility::All)
+            .unwrap(),
+        Some("Engineer".to_string()),
+        "The compacted store should be the one reopened."
+    );
+}
+
+#[test]
+#[cfg(feature = "graph_db")]
+fn test_graph_neighbors_paths_and_facts_as_of() {
+    use anyrag::graph::types::KnowledgeGraphError;
+
+    let mut kg = MemoryKnowledgeGraph::new_memory();
+    let now = Utc::now();
+    let past = (now - Duration::days(10), now - Duration::days(5));
+    let current = (now - Duration::days(1), now + Duration::days(1));
+    kg.add_fact("Alice", "works_at", "Acme", current.0, current.1)
+        .unwrap();
+    kg.add_fact("Acme", "located_in", "Berlin", current.0, current.1)
+        .unwrap();
+    kg.add_fact("Bob", "works_at", "Acme", past.0, past.1)
+        .unwrap();
+
+    let neighbors = kg.neighbors("Acme", None, &FactVisibility::All).unwrap();
+    assert_eq!(neighbors.nodes.len(), 4, "Acme and its three neighbors.");
+    assert_eq!(neighbors.edges.len(), 3);
+    let current_neighbors = kg
+        .neighbors("Acme", Some(now), &FactVisibility::All)
+        .unwrap();
+    assert_eq!(current_neighbors.edges.len(), 2, "Bob's fact has expired.");
+
+    let path = kg
+        .shortest_path("Alice", "Berlin", 4, None, &FactVisibility::All)
+        .unwrap()
+        .expect("Alice and Berlin are linked through Acme.");
+    let names: Vec<&str> = path.nodes.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(names, vec!["Alice", "Acme", "Berlin"]);
+    assert_eq!(path.edges.len(), 2);
+    assert!(kg
+        .shortest_path("Alice", "Berlin", 1, None, &FactVisibility::All)
+        .unwrap()
+        .is_none());
+    assert!(kg
+        .shortest_path("Bob", "Berlin", 4, Some(now), &FactVisibility::All)
+        .unwrap()
+        .is_none());
+
+    let facts = kg
+        .facts_about_as_of("Alice", now, &FactVisibility::All)
+        .unwrap();
+    assert_eq!(facts.len(), 1);
+    assert_eq!(facts[0].predicate, "works_at");
+    assert_eq!(facts[0].object, "Acme");
+    assert!(kg
+        .facts_about_as_of("Bob", now, &FactVisibility::All)
+        .unwrap()
+        .is_empty());
+
+    assert!(matches!(
+        kg.neighbors("Carol", None, &FactVisibility::All),
+        Err(KnowledgeGraphError::EntityNotFound(_))
+    ));
+}
+
+#[test]
+#[cfg(feature = "graph_db")]
+fn test_graph_queries_parse_and_match_facts() {
+    use anyrag::graph::nl_query::parse_graph_query;
+
+    let response = "```json\n{\"entity\": \"Alice\", \"predicate\": \"Works At\", \"as_of\": \"2021-03-01\"}\n```";
+    let query = parse_graph_query(response).expect("The response is a graph query.");
+    assert_eq!(query.entity, "Alice");
+    assert_eq!(query.normalized_predicate().as_deref(), Some("works_at"));
+    assert_eq!(query.as_of_time().to_rfc3339(), "2021-03-01T00:00:00+00:00");
+
+    assert!(parse_graph_query(r#"{"entity": null, "predicate": "role"}"#).is_none());
+    assert!(parse_graph_query("not json").is_none());
+
+    let mut kg = MemoryKnowledgeGraph::new_memory();
+    let start = DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let end = DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    kg.add_fact("Alice", "works_at", "Acme", start, end)
+        .unwrap();
+    kg.add_fact("Alice", "role", "Engineer", start, end)
+        .unwrap();
+
+    let facts = kg
+        .execute_graph_query(&query, &FactVisibility::All)
+        .unwrap();
+    assert_eq!(facts.len(), 1);
+    assert_eq!(facts[0].object, "Acme");
+
+    let unknown = parse_graph_query(r#"{"entity": "Carol", "predicate": null}"#).unwrap();
+    assert!(
+        kg.execute_graph_query(&unknown, &FactVisibility::All)
+            .unwrap()
+            .is_empty(),
+        "An unknown entity matches no facts."
+    );
+}
+
+#[tokio::test]
+#[cfg(feature = "graph_db")]
+async fn test_entity_resolution_merges_duplicates_into_aliases() {
+    use anyrag::graph::{
+        persistent::PersistentKnowledgeGraph,
+        resolution::{
+            normalize_entity_name, plan_entity_merges, EntityResolutionOptions, MergeMethod,
+            DEFAULT_ADJUDICATION_THRESHOLD, DEFAULT_MERGE_THRESHOLD,
+        },
+    };
+
+    assert_eq!(normalize_entity_name("ACME, Inc."), "acme");
+    assert_eq!(normalize_entity_name("Acme Corporation"), "acme");
+    assert_eq!(normalize_entity_name("The Company"), "the company");
+
+    let dir = tempdir().unwrap();
+    let mut graph = PersistentKnowledgeGraph::open(dir.path()).unwrap();
+    let now = Utc::now();
+    let (start, end) = (now - Duration::days(1), now + Duration::days(1));
+    let kg = graph.graph_mut();
+    kg.add_fact("Alice", "works_at", "ACME Inc.", start, end)
+        .unwrap();
+    kg.add_fact("ACME Corporation", "located_in", "Berlin", start, end)
+        .unwrap();
+    kg.add_fact("ACME Inc.", "founded_by", "Bob", start, end)
+        .unwrap();
+
+    let options = EntityResolutionOptions {
+        embedding: None,
+        adjudicator: None,
+        merge_threshold: DEFAULT_MERGE_THRESHOLD,
+        adjudication_threshold: DEFAULT_ADJUDICATION_THRESHOLD,
+    };
+    let names = kg.entity_names().unwrap();
+    let merges = plan_entity_merges(&names, &options).await.unwrap();
+    assert_eq!(merges.len(), 1, "Only the two ACME names are duplicates.");
+    assert_eq!(merges[0].canonical, "ACME Corporation");
+    assert_eq!(merges[0].duplicate, "ACME Inc.");
+    assert_eq!(merges[0].method, MergeMethod::Exact);
+
+    assert_eq!(kg.apply_entity_merges(&merges).len(), 1);
+    assert_eq!(kg.aliases("ACME Corporation").unwrap(), vec!["ACME Inc."]);
+    assert!(!kg
+        .entity_names()
+        .unwrap()
+        .contains(&"ACME Inc.".to_string()));
+
+    let facts = kg
+        .facts_about_as_of("ACME Inc.", now, &FactVisibility::All)
+        .unwrap();
+    assert_eq!(
+        facts.len(),
+        2,
+        "The alias finds the facts of both entities."
+    );
+    assert_eq!(
+        kg.get_fact_as_of("Alice", "works_at", now, &FactVisibility::All)
+            .unwrap(),
+        Some("ACME Corporation".to_string())
+    );
+
+    graph.sync().unwrap();
+    drop(graph);
+    let reopened = PersistentKnowledgeGraph::open(dir.path()).unwrap();
+    assert_eq!(
+        reopened
+            .graph()
+            .get_fact_as_of("ACME Inc.", "located_in", now, &FactVisibility::All)
+            .unwrap(),
+        Some("Berlin".to_string()),
+        "Aliases are reloaded with the graph."
+    );
+}
@@ -148,6 +148,8 @@ async fn test_hybrid_search_logic_is_correct() -> Result<()> {
         query_text: user_query.to_string(),
         // Call with `owner_id: None` to simulate a guest user request.
         owner_id: None,
+        org_id: None,
+        collection: None,
         limit: 5,
         prompts: HybridSearchPrompts {
             analysis_system_prompt: "You are an expert query analyst.",
@@ -185,6 +185,8 @@ async fn run_hybrid_search(
     let search_options = HybridSearchOptions {
         query_text: question.to_string(),
         owner_id: Some(user_id.to_string()),
+        org_id: None,
+        collection: None,
         limit: 5,
         prompts: HybridSearchPrompts {
             analysis_system_prompt: "Analyze this query.",
@@ -0,0 +1,130 @@
+//! # Content Moderation Tests
+//!
+//! This file contains tests for moderating texts with a classifier model, and for the
+//! `block`, `flag` and `log` policies applied to its verdicts.
+
+mod common;
+
+use anyrag::{
+    moderation::{moderate_text, ModerationAction, ModerationConfig, ModerationProviderConfig},
+    prompts::tasks::{CONTENT_MODERATION_SYSTEM_PROMPT, CONTENT_MODERATION_USER_PROMPT},
+    providers::moderation::{ClassifierModerationProvider, ModerationProvider},
+};
+use common::{setup_tracing, MockAiProvider};
+
+const FLAGGED_RESPONSE: &str = r#"```json
+{"flagged": true, "categories": ["harassment"]}
+```"#;
+
+fn classifier(response: &str) -> (ClassifierModerationProvider, MockAiProvider) {
+    let ai_provider = MockAiProvider::new(vec![response.to_string()]);
+    let provider = ClassifierModerationProvider::new(
+        Box::new(ai_provider.clone()),
+        CONTENT_MODERATION_SYSTEM_PROMPT,
+        CONTENT_MODERATION_USER_PROMPT,
+    );
+    (provider, ai_provider)
+}
+
+fn config(action: ModerationAction) -> ModerationConfig {
+    ModerationConfig {
+        provider: ModerationProviderConfig::Classifier,
+        action,
+        blocked_message: "Blocked.".to_string(),
+        moderate_ingested: false,
+    }
+}
+
+#[tokio::test]
+async fn test_classifier_prompts_with_the_text_and_parses_the_verdict() {
+    setup_tracing();
+    let (provider, ai_provider) = classifier(FLAGGED_RESPONSE);
+
+    let verdict = provider.moderate("You are an idiot.").await.unwrap();
+
+    assert!(verdict.flagged);
+    assert_eq!(verdict.categories, vec!["harassment".to_string()]);
+    let history = ai_provider.call_history.read().unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].0, CONTENT_MODERATION_SYSTEM_PROMPT);
+    assert!(history[0].1.contains("You are an idiot."));
+}
+
+#[tokio::test]
+async fn test_block_replaces_a_flagged_text() {
+    setup_tracing();
+    let (provider, _) = classifier(FLAGGED_RESPONSE);
+
+    let (text, report) = moderate_text(
+        &provider,
+        &config(ModerationAction::Block),
+        "You are an idiot.".to_string(),
+    )
+    .await;
+
+    assert_eq!(text, "Blocked.");
+    assert!(report.flagged && report.blocked);
+    assert!(report.is_reported());
+}
+
+#[tokio::test]
+async fn test_flag_and_log_keep_a_flagged_text() {
+    setup_tracing();
+    for (action, reported) in [
+        (ModerationAction::Flag, true),
+        (ModerationAction::Log, false),
+    ] {
+        let (provider, _) = classifier(FLAGGED_RESPONSE);
+
+        let (text, report) =
+            moderate_text(&provider, &config(action), "You are an idiot.".to_string()).await;
+
+        assert_eq!(text, "You are an idiot.", "{action:?}");
+        assert!(report.flagged && !report.blocked, "{action:?}");
+        assert_eq!(report.categories, vec!["harassment".to_string()]);
+        assert_eq!(report.is_reported(), reported, "{action:?}");
+    }
+}
+
+#[tokio::test]
+async fn test_a_text_passing_moderation_is_kept_and_not_reported() {
+    setup_tracing();
+    let (provider, _) = classifier(r#"{"flagged": false, "categories": []}"#);
+
+    let (text, report) = moderate_text(
+        &provider,
+        &config(ModerationAction::Block),
+        "Refunds take 5 days.".to_string(),
+    )
+    .await;
+
+    assert_eq!(text, "Refunds take 5 days.");
+    assert!(!report.flagged && !report.blocked);
+    assert!(!report.is_reported());
+}
+
+#[tokio::test]
+async fn test_block_fails_closed_when_the_text_cannot_be_checked() {
+    setup_tracing();
+    // 1. With `block`, a text whose verdict cannot be read is withheld.
+    let (provider, _) = classifier("I cannot decide.");
+    let (text, report) = moderate_text(
+        &provider,
+        &config(ModerationAction::Block),
+        "Refunds take 5 days.".to_string(),
+    )
+    .await;
+    assert_eq!(text, "Blocked.");
+    assert!(report.blocked && report.error.is_some());
+
+    // 2. With the other actions, the text is passed on.
+    let (provider, _) = classifier("I cannot decide.");
+    let (text, report) = moderate_text(
+        &provider,
+        &config(ModerationAction::Flag),
+        "Refunds take 5 days.".to_string(),
+    )
+    .await;
+    assert_eq!(text, "Refunds take 5 days.");
+    assert!(!report.blocked && report.error.is_some());
+}
@@ -0,0 +1,146 @@
+//! # Enrichment Pipeline Tests
+//!
+//! This file contains tests for parsing enrichment pipelines and for running them over
+//! the documents of a database, resuming earlier runs and tolerating failed documents.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::{
+    ingest::{
+        reset_pipeline_progress, run_pipeline, PipelineDefinition, PipelineError, PipelineStage,
+    },
+    providers::db::sqlite::SqliteProvider,
+};
+use common::{setup_tracing, MockAiProvider};
+use serde_json::json;
+use turso::{params, Connection};
+
+async fn insert_document(
+    conn: &Connection,
+    id: &str,
+    source_url: &str,
+    content: &str,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO documents (id, owner_id, source_url, title, content) VALUES (?, ?, ?, ?, ?)",
+        params![id, "alice", source_url, id, content],
+    )
+    .await?;
+    Ok(())
+}
+
+async fn count(conn: &Connection, sql: &str) -> Result<i64> {
+    let mut rows = conn.query(sql, ()).await?;
+    Ok(rows.next().await?.unwrap().get(0)?)
+}
+
+fn distillation(question: &str) -> String {
+    json!({ "faqs": [{ "question": question, "answer": "Yes.", "is_explicit": true }] }).to_string()
+}
+
+#[test]
+fn test_pipeline_definition_parses_stages() -> Result<()> {
+    let definition = PipelineDefinition::from_yaml(
+        "name: manuals
+select:
+  sources: [\"https://docs.example.com/\"]
+  limit: 10
+stages:
+  - stage: metadata
+  - stage: summarize
+    min_source_chars: 100
+  - stage: faqs
+",
+    )?;
+
+    assert_eq!(definition.name, "manuals");
+    assert_eq!(definition.select.sources, ["https://docs.example.com/"]);
+    assert_eq!(definition.select.limit, Some(10));
+    let stages: Vec<&str> = definition.stages.iter().map(PipelineStage::name).collect();
+    assert_eq!(stages, ["metadata", "summarize", "faqs"]);
+    assert!(matches!(
+        &definition.stages[1],
+        PipelineStage::Summarize(config) if config.min_source_chars == 100
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_pipeline_definition_rejects_unknown_and_repeated_stages() {
+    let unknown = PipelineDefinition::from_yaml("name: p\nstages:\n  - stage: translate\n");
+    let repeated =
+        PipelineDefinition::from_yaml("name: p\nstages:\n  - stage: faqs\n  - stage: faqs\n");
+    let empty = PipelineDefinition::from_yaml("name: p\nstages: []\n");
+
+    assert!(matches!(unknown, Err(PipelineError::Definition(_))));
+    assert!(
+        matches!(repeated, Err(PipelineError::Definition(ref e)) if e.contains("more than once"))
+    );
+    assert!(matches!(empty, Err(PipelineError::Definition(_))));
+}
+
+#[tokio::test]
+async fn test_pipeline_records_failures_and_resumes() -> Result<()> {
+    // --- Arrange ---
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let mut conn = provider.db.connect()?;
+    insert_document(
+        &conn,
+        "a",
+        "https://docs.example.com/a",
+        "Refunds are possible.",
+    )
+    .await?;
+    insert_document(
+        &conn,
+        "b",
+        "https://docs.example.com/b",
+        "Returns are free.",
+    )
+    .await?;
+    insert_document(&conn, "c", "https://blog.example.com/c", "Not selected.").await?;
+    let definition = PipelineDefinition::from_yaml(
+        "name: docs\nselect:\n  sources: [\"https://docs.example.com/\"]\nstages:\n  - stage: faqs\n",
+    )?;
+
+    // --- Act ---
+    // The response for the second document cannot be parsed, so only it fails.
+    let first_ai = MockAiProvider::new(vec![
+        distillation("Are refunds possible?"),
+        "not json".to_string(),
+    ]);
+    let first_run = run_pipeline(&mut conn, &first_ai, &definition).await?;
+    let second_ai = MockAiProvider::new(vec![distillation("Are returns free?")]);
+    let second_run = run_pipeline(&mut conn, &second_ai, &definition).await?;
+
+    // --- Assert ---
+    assert_eq!(first_run.documents, 2);
+    assert_eq!(first_run.stages[0].processed, 1);
+    assert_eq!(first_run.failed(), 1);
+    assert_eq!(first_run.stages[0].failures[0].item_id, "b");
+    // The second run only retries the document that failed.
+    assert_eq!(second_run.stages[0].processed, 1);
+    assert_eq!(second_run.stages[0].skipped, 1);
+    assert_eq!(second_run.failed(), 0);
+    assert_eq!(second_ai.call_history.read().unwrap().len(), 1);
+    assert_eq!(count(&conn, "SELECT COUNT(*) FROM faq_items").await?, 2);
+    assert_eq!(
+        count(
+            &conn,
+            "SELECT attempts FROM pipeline_progress WHERE pipeline = 'docs' AND item_id = 'b'"
+        )
+        .await?,
+        2
+    );
+
+    // Restarting forgets the progress, so every document is processed again.
+    assert_eq!(reset_pipeline_progress(&conn, "docs").await?, 2);
+    let third_ai = MockAiProvider::new(Vec::new());
+    let third_run = run_pipeline(&mut conn, &third_ai, &definition).await?;
+    assert_eq!(third_run.stages[0].skipped, 0);
+    assert_eq!(third_ai.call_history.read().unwrap().len(), 2);
+    Ok(())
+}
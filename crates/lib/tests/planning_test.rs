@@ -0,0 +1,119 @@
+//! # Query Planning Tests
+//!
+//! This file contains tests for decomposing prompts that need several queries into
+//! sub-queries, and for answering them from all of their results.
+
+mod common;
+
+use anyrag::{
+    planning::{parse_query_plan, PlanningOptions},
+    types::PipelineStage,
+    ExecutePromptOptions, PromptClientBuilder,
+};
+use common::{setup_tracing, MockAiProvider, MockStorageProvider};
+
+fn planning_options() -> PlanningOptions {
+    PlanningOptions {
+        system_prompt: "Plan the queries.".to_string(),
+        user_prompt_template: "At most {max_steps} steps for: {prompt}".to_string(),
+        max_steps: 3,
+    }
+}
+
+#[test]
+fn test_parse_query_plan() {
+    assert_eq!(
+        parse_query_plan(
+            "```json\n{\"steps\": [\"Q1 revenue?\", \" \", \"Q2 revenue?\"]}\n```",
+            3
+        ),
+        Some(vec!["Q1 revenue?".to_string(), "Q2 revenue?".to_string()])
+    );
+    assert_eq!(
+        parse_query_plan(r#"{"steps": ["a", "b", "c", "d"]}"#, 2).map(|s| s.len()),
+        Some(2),
+        "The plan is cut to the maximum number of steps."
+    );
+    assert_eq!(parse_query_plan("First, query Q1.", 3), None);
+}
+
+#[tokio::test]
+async fn test_planned_prompt_runs_each_step_and_synthesizes() {
+    setup_tracing();
+
+    let mock_ai_provider = MockAiProvider::new(vec![
+        r#"{"steps": ["What was the revenue in Q1?", "What was the revenue in Q2?"]}"#.to_string(),
+        "SELECT SUM(amount) FROM orders WHERE quarter = 1".to_string(),
+        "SELECT SUM(amount) FROM orders WHERE quarter = 2".to_string(),
+        "Revenue grew from Q1 to Q2.".to_string(),
+    ]);
+    let call_history = mock_ai_provider.call_history.clone();
+    let client = PromptClientBuilder::new()
+        .ai_provider(Box::new(mock_ai_provider))
+        .storage_provider(Box::new(MockStorageProvider))
+        .build()
+        .unwrap();
+
+    let options = ExecutePromptOptions {
+        prompt: "Compare Q1 vs Q2 revenue.".to_string(),
+        table_name: Some("orders".to_string()),
+        planning: Some(planning_options()),
+        ..Default::default()
+    };
+    let result = client.execute_prompt_with_options(options).await.unwrap();
+
+    assert_eq!(result.text, "Revenue grew from Q1 to Q2.");
+    let generated_sql = result.generated_sql.unwrap();
+    assert!(generated_sql.contains("quarter = 1"));
+    assert!(generated_sql.contains("quarter = 2"));
+
+    let stages: Vec<PipelineStage> = result.trace.iter().map(|s| s.stage).collect();
+    assert_eq!(stages[0], PipelineStage::QueryPlanned);
+    assert_eq!(
+        stages
+            .iter()
+            .filter(|s| **s == PipelineStage::PlanStepExecuted)
+            .count(),
+        2
+    );
+
+    let history = call_history.read().unwrap();
+    assert_eq!(history.len(), 4);
+    assert!(history[0].1.contains("At most 3 steps"));
+    assert!(history[1].1.contains("What was the revenue in Q1?"));
+    let synthesis_prompt = &history[3].1;
+    assert!(synthesis_prompt.contains("Step 1: What was the revenue in Q1?"));
+    assert!(synthesis_prompt.contains("Step 2: What was the revenue in Q2?"));
+    assert!(synthesis_prompt.contains("Compare Q1 vs Q2 revenue."));
+}
+
+#[tokio::test]
+async fn test_single_step_plan_answers_in_one_shot() {
+    setup_tracing();
+
+    let mock_ai_provider = MockAiProvider::new(vec![
+        r#"{"steps": ["What was the total revenue?"]}"#.to_string(),
+        "SELECT SUM(amount) FROM orders".to_string(),
+    ]);
+    let call_history = mock_ai_provider.call_history.clone();
+    let client = PromptClientBuilder::new()
+        .ai_provider(Box::new(mock_ai_provider))
+        .storage_provider(Box::new(MockStorageProvider))
+        .build()
+        .unwrap();
+
+    let options = ExecutePromptOptions {
+        prompt: "What was the total revenue?".to_string(),
+        table_name: Some("orders".to_string()),
+        planning: Some(planning_options()),
+        ..Default::default()
+    };
+    let result = client.execute_prompt_with_options(options).await.unwrap();
+
+    assert_eq!(
+        result.generated_sql.as_deref(),
+        Some("SELECT SUM(amount) FROM orders")
+    );
+    assert_eq!(result.trace[0].stage, PipelineStage::QueryPlanned);
+    assert_eq!(call_history.read().unwrap().len(), 2);
+}
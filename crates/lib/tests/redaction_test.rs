@@ -0,0 +1,129 @@
+//! # PII Redaction Tests
+//!
+//! This file contains tests for masking personal data: with the built-in detectors
+//! and configured patterns, with the LLM-assisted pass, and in the prompts sent through
+//! a `RedactingAiProvider`.
+
+mod common;
+
+use anyrag::{
+    prompts::tasks::{PII_DETECTION_SYSTEM_PROMPT, PII_DETECTION_USER_PROMPT},
+    providers::ai::{AiProvider, RedactingAiProvider},
+    redaction::{PiiType, RedactionConfig, RedactionPattern, Redactor},
+};
+use common::{setup_tracing, MockAiProvider};
+use std::sync::Arc;
+
+fn support_redactor() -> Redactor {
+    Redactor::new(&RedactionConfig {
+        patterns: vec![RedactionPattern {
+            name: "account_id".to_string(),
+            pattern: r"ACC-\d{8}".to_string(),
+        }],
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_redact_masks_builtin_types_and_custom_patterns() {
+    let redactor = support_redactor();
+    let redaction = redactor.redact(
+        "Customer jane.doe@example.com (ACC-12345678) called from +1 (555) 123-4567 \
+         on 2024-01-15, paid with 4111 1111 1111 1111 from 192.168.1.10. \
+         Order 1234567890123456 is not a card number.",
+    );
+
+    assert_eq!(
+        redaction.text,
+        "Customer [REDACTED_EMAIL] ([REDACTED_ACCOUNT_ID]) called from [REDACTED_PHONE] \
+         on 2024-01-15, paid with [REDACTED_CREDIT_CARD] from [REDACTED_IP_ADDRESS]. \
+         Order 1234567890123456 is not a card number."
+    );
+    for pii_type in ["EMAIL", "ACCOUNT_ID", "PHONE", "CREDIT_CARD", "IP_ADDRESS"] {
+        assert_eq!(redaction.detected.get(pii_type), Some(&1), "{pii_type}");
+    }
+}
+
+#[test]
+fn test_redact_only_runs_the_configured_detectors() {
+    let redactor = Redactor::new(&RedactionConfig {
+        detectors: vec![PiiType::Email],
+        ..Default::default()
+    })
+    .unwrap();
+    let redaction = redactor.redact("Mail a@b.io or b@c.io, or call 555-123-4567.");
+
+    assert_eq!(
+        redaction.text,
+        "Mail [REDACTED_EMAIL] or [REDACTED_EMAIL], or call 555-123-4567."
+    );
+    assert_eq!(redaction.detected.get("EMAIL"), Some(&2));
+    assert!(!redactor.redact("Nothing to see here.").is_redacted());
+
+    let invalid = Redactor::new(&RedactionConfig {
+        patterns: vec![RedactionPattern {
+            name: "broken".to_string(),
+            pattern: "(".to_string(),
+        }],
+        ..Default::default()
+    });
+    assert!(invalid.is_err());
+}
+
+#[tokio::test]
+async fn test_llm_detection_masks_what_the_patterns_miss() {
+    setup_tracing();
+    let mock_ai_provider = MockAiProvider::new(vec![r#"```json
+[{"type": "person_name", "text": "Jane Doe"}, {"type": "address", "text": "12 Elm Street"}]
+```"#
+        .to_string()]);
+    let call_history = mock_ai_provider.call_history.clone();
+
+    let redaction = support_redactor()
+        .redact_with_llm(
+            "Jane Doe (jane@example.com) moved to 12 Elm Street. Jane Doe asked for a refund.",
+            &mock_ai_provider,
+            PII_DETECTION_SYSTEM_PROMPT,
+            PII_DETECTION_USER_PROMPT,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        redaction.text,
+        "[REDACTED_PERSON_NAME] ([REDACTED_EMAIL]) moved to [REDACTED_ADDRESS]. [REDACTED_PERSON_NAME] asked for a refund."
+    );
+    assert_eq!(redaction.detected.get("PERSON_NAME"), Some(&2));
+    assert_eq!(redaction.detected.get("ADDRESS"), Some(&1));
+
+    // The model only sees the text the patterns already masked.
+    let history = call_history.read().unwrap();
+    assert!(history[0].1.contains("[REDACTED_EMAIL]"));
+    assert!(!history[0].1.contains("jane@example.com"));
+}
+
+#[tokio::test]
+async fn test_redacting_provider_masks_user_prompts() {
+    setup_tracing();
+    let mock_ai_provider = MockAiProvider::new(vec!["Done.".to_string()]);
+    let call_history = mock_ai_provider.call_history.clone();
+    let provider =
+        RedactingAiProvider::new(Arc::new(support_redactor()), Box::new(mock_ai_provider));
+
+    let response = provider
+        .generate(
+            "Reply to support@example.com tickets.",
+            "Ticket from bob@example.com about ACC-87654321.",
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response, "Done.");
+    let history = call_history.read().unwrap();
+    assert_eq!(history[0].0, "Reply to support@example.com tickets.");
+    assert_eq!(
+        history[0].1,
+        "Ticket from [REDACTED_EMAIL] about [REDACTED_ACCOUNT_ID]."
+    );
+}
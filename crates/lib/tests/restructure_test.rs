@@ -0,0 +1,233 @@
+//! # Concurrent Restructuring Tests
+//!
+//! Verifies that chunks restructured by concurrent LLM calls are merged in chunk order,
+//! however the calls interleave, how the knowledge pipeline validates, repairs and
+//! merges responses that are not valid YAML, and how content that is not restructured
+//! is chunked.
+
+mod common;
+
+use anyrag::{
+    ingest::{
+        knowledge::{
+            restructure_chunks_with_llm, restructure_each_with_llm, validate_restructured_yaml,
+            RestructuringOutcome, YamlContent,
+        },
+        IngestionPrompts, KnowledgePipeline, Restructured,
+    },
+    providers::ai::AiProvider,
+    PromptError,
+};
+use async_trait::async_trait;
+use common::MockAiProvider;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// An AI provider that restructures a chunk into a section titled after it. Earlier
+/// chunks take longer, so their responses arrive last.
+#[derive(Clone, Debug, Default)]
+struct SlowEchoProvider {
+    in_flight: Arc<AtomicUsize>,
+    max_in_flight: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl AiProvider for SlowEchoProvider {
+    async fn generate(
+        &self,
+        _system_prompt: &str,
+        user_prompt: &str,
+    ) -> Result<String, PromptError> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+
+        let chunk = user_prompt.lines().last().unwrap_or_default();
+        let index: u64 = chunk.trim_start_matches("chunk ").parse().unwrap_or(0);
+        tokio::time::sleep(Duration::from_millis(40 - index * 10)).await;
+
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        Ok(format!(
+            "sections:\n  - title: {chunk}\n    faqs:\n      - question: q\n        answer: a\n"
+        ))
+    }
+}
+
+fn chunks() -> Vec<String> {
+    (0..4).map(|index| format!("chunk {index}")).collect()
+}
+
+#[tokio::test]
+async fn test_restructure_chunks_keeps_chunk_order() {
+    let provider = SlowEchoProvider::default();
+
+    let yaml = restructure_chunks_with_llm(&provider, &chunks(), "system", 4)
+        .await
+        .unwrap();
+
+    let content: YamlContent = serde_yaml::from_str(&yaml).unwrap();
+    let titles: Vec<String> = content.sections.into_iter().map(|s| s.title).collect();
+    assert_eq!(titles, chunks());
+    assert_eq!(provider.max_in_flight.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn test_restructure_each_respects_concurrency_limit() {
+    let provider = SlowEchoProvider::default();
+
+    let responses = restructure_each_with_llm(&provider, &chunks(), "system", 2)
+        .await
+        .unwrap();
+
+    assert_eq!(responses.len(), 4);
+    assert!(responses[0].contains("chunk 0"));
+    assert!(responses[3].contains("chunk 3"));
+    assert_eq!(provider.max_in_flight.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_knowledge_pipeline_merges_sections_in_chunk_order() {
+    let provider = SlowEchoProvider::default();
+
+    let restructured = KnowledgePipeline::new(&provider, prompts())
+        .with_concurrency(3)
+        .restructure(&chunks())
+        .await
+        .unwrap();
+
+    let Restructured::Sections { content, yaml, .. } = restructured else {
+        panic!("Expected sections, got {restructured:?}");
+    };
+    let titles: Vec<String> = content.sections.into_iter().map(|s| s.title).collect();
+    assert_eq!(titles, chunks());
+    assert!(yaml.contains("chunk 3"));
+    assert_eq!(provider.max_in_flight.load(Ordering::SeqCst), 3);
+}
+
+const VALID_YAML: &str =
+    "sections:\n  - title: Intro\n    faqs:\n      - question: q\n        answer: a\n";
+const INVALID_YAML: &str = "sections:\n  - title: Intro\n    faqs:\n      - question: Why: not\n";
+
+fn prompts() -> IngestionPrompts<'static> {
+    IngestionPrompts {
+        restructuring_system_prompt: "system",
+        metadata_extraction_system_prompt: "system",
+    }
+}
+
+fn sections(yaml: &str) -> Restructured {
+    let content = validate_restructured_yaml(yaml).unwrap().unwrap();
+    Restructured::Sections {
+        content,
+        yaml: yaml.to_string(),
+        repaired: false,
+    }
+}
+
+#[test]
+fn test_validation_reports_what_breaks_the_schema() {
+    assert!(validate_restructured_yaml(VALID_YAML).unwrap().is_some());
+    assert!(validate_restructured_yaml("sections: []")
+        .unwrap()
+        .is_none());
+    assert!(validate_restructured_yaml("  ").unwrap().is_none());
+    assert!(validate_restructured_yaml(INVALID_YAML).is_err());
+
+    let error = validate_restructured_yaml(
+        "sections:\n  - title: Intro\n    faqs:\n      - question: q\n        answer: ''\n",
+    )
+    .unwrap_err();
+    assert!(error.contains("'Intro'"), "{error}");
+}
+
+#[tokio::test]
+async fn test_invalid_yaml_is_repaired_by_the_llm() {
+    let provider = MockAiProvider::new(vec![INVALID_YAML.to_string(), VALID_YAML.to_string()]);
+
+    let restructured = KnowledgePipeline::new(&provider, prompts())
+        .restructure(&["chunk".to_string()])
+        .await
+        .unwrap();
+
+    assert_eq!(restructured.outcome(), Some(RestructuringOutcome::Repaired));
+    assert!(matches!(
+        restructured,
+        Restructured::Sections { ref yaml, repaired: true, .. } if yaml == VALID_YAML.trim()
+    ));
+    let calls = provider.call_history.read().unwrap();
+    assert_eq!(calls.len(), 2);
+    assert!(calls[1].1.contains("question: Why: not"));
+}
+
+#[tokio::test]
+async fn test_yaml_that_stays_invalid_is_kept_unparsed() {
+    let provider =
+        MockAiProvider::new(vec![INVALID_YAML.to_string(), "still: [broken".to_string()]);
+
+    let restructured = KnowledgePipeline::new(&provider, prompts())
+        .with_repair_attempts(1)
+        .restructure(&["chunk".to_string()])
+        .await
+        .unwrap();
+
+    assert_eq!(restructured.outcome(), Some(RestructuringOutcome::Unparsed));
+    assert!(matches!(restructured, Restructured::Unparsed(ref raw) if raw == INVALID_YAML.trim()));
+    assert_eq!(provider.call_history.read().unwrap().len(), 2);
+}
+
+#[test]
+fn test_merge_skips_unparsed_chunks_when_others_are_valid() {
+    let parts = vec![
+        sections(VALID_YAML),
+        Restructured::Unparsed("Sorry, I cannot help with that: [".to_string()),
+        Restructured::Empty,
+    ];
+
+    let restructured = Restructured::merge(parts).unwrap();
+
+    assert_eq!(restructured.outcome(), Some(RestructuringOutcome::Valid));
+    assert!(matches!(
+        restructured,
+        Restructured::Sections { ref content, .. } if content.sections.len() == 1
+    ));
+}
+
+#[test]
+fn test_merge_keeps_unparsed_chunks_when_none_are_valid() {
+    let parts = vec![
+        Restructured::Unparsed("Not: [yaml".to_string()),
+        Restructured::Empty,
+    ];
+
+    let restructured = Restructured::merge(parts).unwrap();
+
+    assert!(matches!(restructured, Restructured::Unparsed(ref raw) if raw == "Not: [yaml"));
+    assert!(matches!(
+        Restructured::merge(vec![Restructured::Empty, Restructured::Empty]).unwrap(),
+        Restructured::Empty
+    ));
+    assert_eq!(Restructured::merge(vec![]).unwrap().outcome(), None);
+}
+
+#[test]
+fn test_unrestructured_content_is_chunked_by_markdown_section() {
+    let markdown = "# Install\n\nRun the installer.\n\n```sh\n# not a heading\n```\n\n## Configure\n\nEdit the file.";
+
+    let chunks = chunk_without_restructuring(markdown, None).unwrap();
+
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunk_title(&chunks[0]).as_deref(), Some("Install"));
+    assert_eq!(chunk_title(&chunks[1]).as_deref(), Some("Configure"));
+    assert_eq!(chunk_title("```\n# comment\n```\nNo heading."), None);
+
+    let sentences = chunk_without_restructuring(
+        "One. Two. Three.",
+        Some(&ChunkingStrategy::Sentence { chunk_size: 6 }),
+    )
+    .unwrap();
+    assert_eq!(sentences, vec!["One.", "Two.", "Three."]);
+}
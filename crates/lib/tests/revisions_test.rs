@@ -0,0 +1,144 @@
+//! # Document Revision Tests
+//!
+//! This file contains tests for keeping the replaced versions of a document as
+//! revisions, for dropping its embedding only when its content changed materially, and
+//! for reading its history.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::{
+    ingest::{document_history, record_revision, revisions::change_ratio, RevisionError},
+    providers::db::sqlite::SqliteProvider,
+};
+use common::setup_tracing;
+use turso::{params, Connection};
+
+const SOURCE_URL: &str = "https://example.com/refunds";
+const FIRST_VERSION: &str = "Refunds are processed within five business days of the return.";
+
+async fn setup_document(provider: &SqliteProvider, content: &str) -> Result<Connection> {
+    let conn = provider.db.connect()?;
+    conn.execute(
+        "INSERT INTO documents (id, owner_id, source_url, title, content) VALUES (?, ?, ?, ?, ?)",
+        params!["refunds", "alice", SOURCE_URL, "Refunds", content],
+    )
+    .await?;
+    conn.execute(
+        "INSERT INTO document_embeddings (document_id, model_name, embedding) VALUES (?, ?, ?)",
+        params!["refunds", "mock-model", [0u8; 16].as_slice()],
+    )
+    .await?;
+    Ok(conn)
+}
+
+/// Records a revision and replaces the content, as an upsert of the ingestors does.
+async fn replace_content(conn: &Connection, content: &str) -> Result<()> {
+    record_revision(conn, SOURCE_URL, content).await?;
+    conn.execute(
+        "UPDATE documents SET content = ? WHERE source_url = ?",
+        params![content, SOURCE_URL],
+    )
+    .await?;
+    Ok(())
+}
+
+async fn embedding_count(conn: &Connection) -> Result<i64> {
+    let mut rows = conn
+        .query(
+            "SELECT COUNT(*) FROM document_embeddings WHERE document_id = 'refunds'",
+            (),
+        )
+        .await?;
+    Ok(rows.next().await?.unwrap().get(0)?)
+}
+
+#[test]
+fn test_change_ratio_ignores_case_and_whitespace() {
+    assert_eq!(
+        change_ratio("Refunds take  5 days.", "refunds take 5\ndays."),
+        0.0
+    );
+    assert_eq!(
+        change_ratio("Refunds take 5 days.", "Shipping is free."),
+        1.0
+    );
+    let ratio = change_ratio("Refunds take 5 days.", "Refunds take 7 days.");
+    assert!(ratio > 0.0 && ratio < 1.0);
+}
+
+#[tokio::test]
+async fn test_minor_change_is_recorded_and_keeps_the_embedding() -> Result<()> {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    // A long policy of 120 distinct words, in which one word is then corrected.
+    let words: Vec<String> = (0..120).map(|n| format!("clause{n}")).collect();
+    let policy = words.join(" ");
+    let conn = setup_document(&provider, &policy).await?;
+
+    // 1. The same content is not a new version.
+    assert!(record_revision(&conn, SOURCE_URL, &policy).await?.is_none());
+
+    // 2. A one-word correction is a new version that keeps the embedding.
+    let corrected = policy.replace("clause60", "corrected60");
+    let revision = record_revision(&conn, SOURCE_URL, &corrected)
+        .await?
+        .expect("A changed content is a new version.");
+    assert_eq!(revision.revision, 1);
+    assert!(!revision.reembed, "ratio {}", revision.change_ratio);
+    assert_eq!(embedding_count(&conn).await?, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_material_change_is_recorded_and_drops_the_embedding() -> Result<()> {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let conn = setup_document(&provider, FIRST_VERSION).await?;
+
+    let revision = record_revision(
+        &conn,
+        SOURCE_URL,
+        "Refunds are no longer offered; store credit is issued instead.",
+    )
+    .await?
+    .expect("A changed content is a new version.");
+
+    assert_eq!(revision.document_id, "refunds");
+    assert_eq!(revision.revision, 1);
+    assert!(revision.reembed);
+    assert_eq!(embedding_count(&conn).await?, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_document_history_lists_replaced_versions_newest_first() -> Result<()> {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let conn = setup_document(&provider, FIRST_VERSION).await?;
+    replace_content(&conn, "Refunds take seven days.").await?;
+    replace_content(&conn, "Refunds take ten days.").await?;
+
+    let history = document_history(&conn, "refunds").await?;
+
+    assert_eq!(history.owner_id.as_deref(), Some("alice"));
+    assert_eq!(history.source_url.as_deref(), Some(SOURCE_URL));
+    assert_eq!(history.current_revision, 3);
+    let contents: Vec<(i64, &str)> = history
+        .revisions
+        .iter()
+        .map(|revision| (revision.revision, revision.content.as_str()))
+        .collect();
+    assert_eq!(
+        contents,
+        vec![(2, "Refunds take seven days."), (1, FIRST_VERSION)]
+    );
+    assert!(matches!(
+        document_history(&conn, "missing").await,
+        Err(RevisionError::DocumentNotFound(_))
+    ));
+    Ok(())
+}
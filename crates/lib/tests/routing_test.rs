@@ -0,0 +1,177 @@
+//! # Answer Routing Tests
+//!
+//! This file contains tests for routing prompts between text-to-SQL, knowledge search,
+//! graph lookup and direct answers with a classification call.
+
+mod common;
+
+use anyrag::{
+    routing::{parse_route_decision, RouteChoice, RoutingOptions},
+    types::PipelineStage,
+    ExecutePromptOptions, PromptClientBuilder, PromptError, Route, RouteRetriever,
+};
+use async_trait::async_trait;
+use common::{setup_tracing, MockAiProvider, MockStorageProvider};
+use std::sync::Arc;
+
+/// A retriever that finds the same context for every question, or none.
+#[derive(Debug)]
+struct MockRouteRetriever {
+    context: Option<String>,
+}
+
+#[async_trait]
+impl RouteRetriever for MockRouteRetriever {
+    fn supports(&self, route: Route) -> bool {
+        route == Route::Knowledge
+    }
+
+    async fn retrieve(
+        &self,
+        _route: Route,
+        _question: &str,
+        _collection: Option<&str>,
+    ) -> Result<Option<String>, PromptError> {
+        Ok(self.context.clone())
+    }
+}
+
+fn routing_options(routes: &[Route]) -> RoutingOptions {
+    RoutingOptions {
+        routes: routes
+            .iter()
+            .map(|route| RouteChoice {
+                route: *route,
+                description: route.default_description().to_string(),
+                system_prompt_template: None,
+                user_prompt_template: None,
+            })
+            .collect(),
+        fallback: Route::Direct,
+        system_prompt: "Choose a route.".to_string(),
+        user_prompt_template: "# Routes\n{routes}\n\n# Question\n{prompt}".to_string(),
+    }
+}
+
+#[test]
+fn test_parse_route_decision() {
+    let available = [Route::Sql, Route::Direct];
+
+    let decision = parse_route_decision(
+        "```json\n{\"route\": \"SQL\", \"reason\": \"It counts rows.\"}\n```",
+        &available,
+    )
+    .unwrap();
+    assert_eq!(decision.route, Route::Sql);
+    assert_eq!(decision.reason.as_deref(), Some("It counts rows."));
+    assert!(decision.classified);
+
+    assert_eq!(
+        parse_route_decision(r#"{"route": "graph"}"#, &available),
+        None,
+        "A route that is not available is rejected."
+    );
+    assert_eq!(parse_route_decision("sql", &available), None);
+}
+
+#[tokio::test]
+async fn test_knowledge_route_answers_from_retrieved_context() {
+    setup_tracing();
+
+    let mock_ai_provider = MockAiProvider::new(vec![
+        r#"{"route": "knowledge", "reason": "It asks about a policy."}"#.to_string(),
+        "Refunds take 5 days.".to_string(),
+    ]);
+    let call_history = mock_ai_provider.call_history.clone();
+    let client = PromptClientBuilder::new()
+        .ai_provider(Box::new(mock_ai_provider))
+        .storage_provider(Box::new(MockStorageProvider))
+        .route_retriever(Arc::new(MockRouteRetriever {
+            context: Some("Refunds are processed within 5 business days.".to_string()),
+        }))
+        .build()
+        .unwrap();
+
+    let options = ExecutePromptOptions {
+        prompt: "How long do refunds take?".to_string(),
+        table_name: Some("orders".to_string()),
+        routing: Some(routing_options(&Route::ALL)),
+        ..Default::default()
+    };
+    let result = client.execute_prompt_with_options(options).await.unwrap();
+
+    assert_eq!(result.text, "Refunds take 5 days.");
+    assert_eq!(result.generated_sql, None);
+    let route = result.route.unwrap();
+    assert_eq!(route.route, Route::Knowledge);
+    assert_eq!(route.reason.as_deref(), Some("It asks about a policy."));
+
+    let history = call_history.read().unwrap();
+    assert_eq!(history.len(), 2);
+    let routing_prompt = &history[0].1;
+    assert!(routing_prompt.contains("`sql`"));
+    assert!(routing_prompt.contains("`knowledge`"));
+    assert!(
+        !routing_prompt.contains("`graph`"),
+        "The graph route is not offered without a retriever for it."
+    );
+    assert!(history[1]
+        .1
+        .contains("Refunds are processed within 5 business days."));
+    assert_eq!(result.trace[0].stage, PipelineStage::RouteSelected);
+}
+
+#[tokio::test]
+async fn test_route_without_context_falls_back_to_direct_answer() {
+    setup_tracing();
+
+    let mock_ai_provider = MockAiProvider::new(vec![
+        r#"{"route": "knowledge"}"#.to_string(),
+        "I don't know.".to_string(),
+    ]);
+    let client = PromptClientBuilder::new()
+        .ai_provider(Box::new(mock_ai_provider))
+        .storage_provider(Box::new(MockStorageProvider))
+        .route_retriever(Arc::new(MockRouteRetriever { context: None }))
+        .build()
+        .unwrap();
+
+    let options = ExecutePromptOptions {
+        prompt: "How long do refunds take?".to_string(),
+        routing: Some(routing_options(&Route::ALL)),
+        ..Default::default()
+    };
+    let result = client.execute_prompt_with_options(options).await.unwrap();
+
+    assert_eq!(result.text, "I don't know.");
+    let route = result.route.unwrap();
+    assert_eq!(route.route, Route::Direct);
+    assert_eq!(route.fallback_from, Some(Route::Knowledge));
+}
+
+#[tokio::test]
+async fn test_single_available_route_skips_classification() {
+    setup_tracing();
+
+    let mock_ai_provider = MockAiProvider::new(vec!["Hello!".to_string()]);
+    let call_history = mock_ai_provider.call_history.clone();
+    let client = PromptClientBuilder::new()
+        .ai_provider(Box::new(mock_ai_provider))
+        .storage_provider(Box::new(MockStorageProvider))
+        .build()
+        .unwrap();
+
+    // Without a table or a retriever, only the direct route is available.
+    let options = ExecutePromptOptions {
+        prompt: "Say hello.".to_string(),
+        routing: Some(routing_options(&Route::ALL)),
+        ..Default::default()
+    };
+    let result = client.execute_prompt_with_options(options).await.unwrap();
+
+    assert_eq!(result.text, "Hello!");
+    let route = result.route.unwrap();
+    assert_eq!(route.route, Route::Direct);
+    assert!(!route.classified);
+    assert_eq!(call_history.read().unwrap().len(), 1);
+}
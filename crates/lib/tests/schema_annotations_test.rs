@@ -0,0 +1,176 @@
+//! # Schema Annotation Tests
+//!
+//! This file contains tests for storing table and column annotations and for
+//! merging them into the schema section of query generation prompts.
+
+mod common;
+
+use anyrag::{
+    providers::db::sqlite::SqliteProvider,
+    schema_annotations::{
+        delete_schema_annotation, get_schema_annotations, upsert_schema_annotation,
+    },
+    types::SchemaAnnotation,
+    ExecutePromptOptions, PromptClientBuilder,
+};
+use common::{setup_tracing, MockAiProvider};
+
+fn status_annotation(description: &str) -> SchemaAnnotation {
+    SchemaAnnotation {
+        db: None,
+        table_name: "orders".to_string(),
+        column_name: Some("status".to_string()),
+        description: Some(description.to_string()),
+        examples: vec!["shipped".to_string(), "pending".to_string()],
+        references: None,
+    }
+}
+
+#[tokio::test]
+async fn test_schema_annotations_are_upserted_and_deleted() {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await.unwrap();
+    provider.initialize_schema().await.unwrap();
+
+    upsert_schema_annotation(&provider.db, &status_annotation("Old description"))
+        .await
+        .unwrap();
+    upsert_schema_annotation(&provider.db, &status_annotation("Order lifecycle state"))
+        .await
+        .unwrap();
+
+    let annotations = get_schema_annotations(&provider.db, None, Some("orders"))
+        .await
+        .unwrap();
+    assert_eq!(
+        annotations,
+        vec![status_annotation("Order lifecycle state")]
+    );
+
+    // Annotations are scoped to their database.
+    let other_db = get_schema_annotations(&provider.db, Some("sales"), None)
+        .await
+        .unwrap();
+    assert!(other_db.is_empty());
+
+    let deleted = delete_schema_annotation(&provider.db, None, "orders", Some("status"))
+        .await
+        .unwrap();
+    assert!(deleted);
+    let annotations = get_schema_annotations(&provider.db, None, None)
+        .await
+        .unwrap();
+    assert!(annotations.is_empty());
+}
+
+#[tokio::test]
+async fn test_schema_annotations_are_merged_into_query_prompt() {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await.unwrap();
+    provider
+        .initialize_with_data(
+            "CREATE TABLE orders (id INTEGER, status TEXT); INSERT INTO orders VALUES (1, 'shipped');",
+        )
+        .await
+        .unwrap();
+
+    let mock_ai_provider = MockAiProvider::new(vec!["SELECT status FROM orders".to_string()]);
+    let call_history = mock_ai_provider.call_history.clone();
+    let client = PromptClientBuilder::new()
+        .ai_provider(Box::new(mock_ai_provider))
+        .storage_provider(Box::new(provider))
+        .build()
+        .unwrap();
+
+    let table_annotation = SchemaAnnotation {
+        db: None,
+        table_name: "orders".to_string(),
+        column_name: None,
+        description: Some("One row per customer order.".to_string()),
+        examples: vec![],
+        references: None,
+    };
+    let options = ExecutePromptOptions {
+        prompt: "Which orders have shipped?".to_string(),
+        table_name: Some("orders".to_string()),
+        schema_annotations: Some(vec![
+            table_annotation,
+            status_annotation("Order lifecycle state"),
+        ]),
+        ..Default::default()
+    };
+
+    client.execute_prompt_with_options(options).await.unwrap();
+
+    let history = call_history.read().unwrap();
+    let user_prompt = &history[0].1;
+    assert!(user_prompt.contains("Description: One row per customer order."));
+    assert!(user_prompt.contains("- id: Integer\n"));
+    assert!(
+        user_prompt.contains("- status: String (Order lifecycle state; e.g. 'shipped', 'pending')"),
+        "Annotation not found in prompt: {user_prompt}"
+    );
+}
+
+#[tokio::test]
+async fn test_related_tables_are_merged_into_query_prompt() {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await.unwrap();
+    provider
+        .initialize_with_data(
+            "CREATE TABLE customers (id INTEGER PRIMARY KEY, name TEXT);
+             CREATE TABLE products (code TEXT, title TEXT);
+             CREATE TABLE regions (id INTEGER, label TEXT);
+             CREATE TABLE orders (id INTEGER, customer_id INTEGER REFERENCES customers(id), product_code TEXT);",
+        )
+        .await
+        .unwrap();
+
+    // The database declares no foreign key for `product_code`, so it is annotated.
+    let product_reference = SchemaAnnotation {
+        db: None,
+        table_name: "orders".to_string(),
+        column_name: Some("product_code".to_string()),
+        description: None,
+        examples: vec![],
+        references: Some("products.code".to_string()),
+    };
+    upsert_schema_annotation(&provider.db, &product_reference)
+        .await
+        .unwrap();
+    let annotations = get_schema_annotations(&provider.db, None, Some("orders"))
+        .await
+        .unwrap();
+    assert_eq!(annotations, vec![product_reference]);
+
+    let mock_ai_provider = MockAiProvider::new(vec!["SELECT 1".to_string()]);
+    let call_history = mock_ai_provider.call_history.clone();
+    let client = PromptClientBuilder::new()
+        .ai_provider(Box::new(mock_ai_provider))
+        .storage_provider(Box::new(provider))
+        .build()
+        .unwrap();
+
+    let options = ExecutePromptOptions {
+        prompt: "Which customers ordered which products, by region?".to_string(),
+        table_name: Some("orders".to_string()),
+        related_tables: Some(vec!["regions".to_string()]),
+        schema_annotations: Some(annotations),
+        ..Default::default()
+    };
+    client.execute_prompt_with_options(options).await.unwrap();
+
+    let history = call_history.read().unwrap();
+    let user_prompt = &history[0].1;
+    assert!(
+        user_prompt.contains("- customer_id: Integer (references customers.id)"),
+        "Foreign key not found in prompt: {user_prompt}"
+    );
+    assert!(user_prompt.contains("- product_code: String (references products.code)"));
+    for table in ["customers", "products", "regions"] {
+        assert!(
+            user_prompt.contains(&format!("# Schema for `{table}`")),
+            "Schema of related table '{table}' not found in prompt: {user_prompt}"
+        );
+    }
+}
@@ -0,0 +1,56 @@
+//! # Source URL Pattern Tests
+//!
+//! This file contains tests for the `LIKE` patterns ingestors find the earlier
+//! documents of a source with, checking that the wildcards of a URL only match
+//! themselves.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::{ingest::source_url_prefix_pattern, providers::db::sqlite::SqliteProvider};
+use common::setup_tracing;
+use turso::params;
+
+#[test]
+fn test_source_url_prefix_pattern_escapes_wildcards() {
+    assert_eq!(
+        source_url_prefix_pattern("https://example.com/a_b#chunk_"),
+        "https://example.com/a\\_b#chunk\\_%"
+    );
+    assert_eq!(
+        source_url_prefix_pattern("file:///100%\\done"),
+        "file:///100\\%\\\\done%"
+    );
+}
+
+#[tokio::test]
+async fn test_source_url_prefix_pattern_matches_only_its_source() -> Result<()> {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let conn = provider.db.connect()?;
+    for (id, source_url) in [
+        ("own", "https://example.com/a_b#chunk_0"),
+        ("other", "https://example.com/aXb#chunk_0"),
+        ("percent", "https://example.com/a_b%#chunk_0"),
+    ] {
+        conn.execute(
+            "INSERT INTO documents (id, source_url, title, content) VALUES (?, ?, 'Title', 'content')",
+            params![id, source_url],
+        )
+        .await?;
+    }
+
+    let mut rows = conn
+        .query(
+            "SELECT id FROM documents WHERE source_url LIKE ? ESCAPE '\\' ORDER BY id",
+            params![source_url_prefix_pattern("https://example.com/a_b#chunk_")],
+        )
+        .await?;
+    let mut ids = Vec::new();
+    while let Some(row) = rows.next().await? {
+        ids.push(row.get::<String>(0)?);
+    }
+    assert_eq!(ids, vec!["own"]);
+    Ok(())
+}
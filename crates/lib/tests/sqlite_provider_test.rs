@@ -12,8 +12,15 @@
 mod common;
 
 use crate::common::setup_tracing;
-use anyrag::providers::db::sqlite::SqliteProvider;
+use anyrag::providers::db::sqlite::{
+    backup::{backup_to_file, restore_from_file},
+    migrations::{run_migrations, schema_version, MIGRATIONS},
+    pool::{is_busy, retry_on_busy, write_scoped},
+    statements::StatementCache,
+    SqliteProvider,
+};
 use anyrag::providers::db::storage::Storage;
+use anyrag::types::QueryLimits;
 use anyrag::PromptError;
 use chrono::Utc;
 use serde_json::json;
@@ -221,3 +228,353 @@ async fn test_sqlite_provider_versioning_logic() {
         "There should be two documents in the table."
     );
 }
+
+/// Verifies that `execute_query` enforces the configured row limit.
+#[tokio::test]
+async fn test_sqlite_provider_enforces_query_limits() {
+    setup_tracing();
+
+    // 1. Setup: A provider that returns at most 2 rows.
+    let provider = SqliteProvider::new(":memory:")
+        .await
+        .expect("Failed to create SqliteProvider")
+        .with_query_limits(QueryLimits {
+            max_rows: 2,
+            ..Default::default()
+        });
+    provider
+        .initialize_with_data(
+            "
+        CREATE TABLE numbers (n INTEGER);
+        INSERT INTO numbers (n) VALUES (1);
+        INSERT INTO numbers (n) VALUES (2);
+        INSERT INTO numbers (n) VALUES (3);
+    ",
+        )
+        .await
+        .expect("Failed to initialize database with test data");
+
+    // 2. Act & Assert: Extra rows are discarded.
+    let result_json = provider
+        .execute_query("SELECT n FROM numbers ORDER BY n ASC")
+        .await
+        .expect("Failed to execute query");
+    assert_eq!(result_json, json!([{"n": 1}, {"n": 2}]).to_string());
+}
+
+/// Verifies that concurrent writers take turns and all their writes land.
+#[tokio::test]
+async fn test_sqlite_provider_serializes_concurrent_writes() {
+    setup_tracing();
+
+    // 1. Setup
+    let provider = SqliteProvider::new(":memory:")
+        .await
+        .expect("Failed to create SqliteProvider");
+    provider
+        .initialize_with_data("CREATE TABLE events (n INTEGER)")
+        .await
+        .expect("Failed to create table");
+
+    // 2. Act: Write from many tasks at once, each in a transaction of its own.
+    let writers = (0..16i64).map(|n| {
+        let provider = provider.clone();
+        tokio::spawn(async move {
+            let conn = provider.write_connection().await?;
+            conn.execute("BEGIN", ()).await?;
+            conn.execute("INSERT INTO events (n) VALUES (?)", turso::params![n])
+                .await?;
+            tokio::task::yield_now().await;
+            conn.execute("COMMIT", ()).await?;
+            Ok::<_, anyhow::Error>(())
+        })
+    });
+    for writer in futures::future::join_all(writers).await {
+        writer.expect("writer panicked").expect("write failed");
+    }
+
+    // 3. Assert: Every write landed, and reads reuse pooled connections.
+    for _ in 0..3 {
+        let result_json = provider
+            .execute_query("SELECT COUNT(*) AS count FROM events")
+            .await
+            .expect("Failed to count events");
+        assert_eq!(result_json, json!([{"count": 16}]).to_string());
+    }
+}
+
+/// Verifies that busy failures are retried and other failures are returned at once.
+#[tokio::test]
+async fn test_retry_on_busy() {
+    setup_tracing();
+
+    // 1. Act: An operation that is busy twice before it succeeds.
+    let mut attempts = 0;
+    let result = retry_on_busy(3, || {
+        attempts += 1;
+        let attempt = attempts;
+        async move {
+            match attempt {
+                1 | 2 => Err("database is locked".to_string()),
+                _ => Ok(attempt),
+            }
+        }
+    })
+    .await;
+
+    // 2. Assert
+    assert_eq!(result, Ok(3));
+    assert!(is_busy(&"SQLITE_BUSY: database is busy"));
+    assert!(!is_busy(&"no such table: events"));
+
+    let mut attempts = 0;
+    let result: Result<(), String> = retry_on_busy(3, || {
+        attempts += 1;
+        async { Err("no such table: events".to_string()) }
+    })
+    .await;
+    assert!(result.is_err());
+    assert_eq!(attempts, 1, "Other errors must not be retried.");
+}
+
+/// Verifies that an ingestion in a write lock's scope holds the write permit only while
+/// one of its writes runs.
+#[tokio::test]
+async fn test_write_lock_scope_takes_the_permit_only_for_writes() {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await.unwrap();
+    let wait = std::time::Duration::from_millis(100);
+
+    provider
+        .write_lock()
+        .scope(async {
+            // 1. Between writes, other writers are not blocked.
+            let permit = tokio::time::timeout(wait, provider.write_permit())
+                .await
+                .expect("The permit must be free between writes.");
+
+            // 2. A write waits for the permit held by another writer.
+            let write = write_scoped(|| async { Ok::<_, String>(()) });
+            tokio::pin!(write);
+            assert!(tokio::time::timeout(wait, &mut write).await.is_err());
+            drop(permit);
+            assert_eq!(write.await, Ok(()));
+
+            // 3. The write released the permit when it finished.
+            assert!(tokio::time::timeout(wait, provider.write_permit())
+                .await
+                .is_ok());
+        })
+        .await;
+}
+
+/// Verifies that cached statements are reused and the least recently used is evicted.
+#[tokio::test]
+async fn test_statement_cache_evicts_least_recently_used() {
+    setup_tracing();
+
+    // 1. Setup
+    let provider = SqliteProvider::new(":memory:")
+        .await
+        .expect("Failed to create SqliteProvider");
+    provider
+        .initialize_with_data("CREATE TABLE numbers (n INTEGER)")
+        .await
+        .expect("Failed to create table");
+    let conn = provider.db.connect().expect("Failed to connect");
+    let mut statements = StatementCache::new(2);
+    let insert = "INSERT INTO numbers (n) VALUES (?)";
+    let count = "SELECT COUNT(*) FROM numbers";
+
+    // 2. Act: Run the same insert many times through the cache.
+    for n in 0..100i64 {
+        statements
+            .prepare(&conn, insert)
+            .await
+            .expect("Failed to prepare insert")
+            .execute(turso::params![n])
+            .await
+            .expect("Failed to insert");
+    }
+    let mut rows = statements
+        .prepare(&conn, count)
+        .await
+        .expect("Failed to prepare count")
+        .query(())
+        .await
+        .expect("Failed to count");
+    let row = rows.next().await.expect("Failed to read count").unwrap();
+    assert_eq!(row.get::<i64>(0).expect("Failed to read count"), 100);
+    drop(rows);
+    assert_eq!(statements.len(), 2);
+
+    // 3. Assert: A third statement evicts the insert, which was used least recently.
+    statements
+        .prepare(&conn, "SELECT n FROM numbers")
+        .await
+        .expect("Failed to prepare select");
+    statements
+        .prepare(&conn, count)
+        .await
+        .expect("Failed to prepare count");
+    assert_eq!(statements.len(), 2);
+    assert!(!statements.contains(insert));
+    assert!(statements.contains(count));
+}
+
+/// Verifies that migrations are applied once, in order, and recorded.
+#[tokio::test]
+async fn test_sqlite_migrations_are_applied_once() {
+    setup_tracing();
+
+    // 1. Arrange
+    let provider = SqliteProvider::new(":memory:")
+        .await
+        .expect("Failed to create SqliteProvider");
+    let conn = provider.db.connect().unwrap();
+
+    // 2. Act
+    let first_run = run_migrations(&conn).await.expect("Migrations failed");
+    let second_run = run_migrations(&conn).await.expect("Migrations failed");
+
+    // 3. Assert
+    let all_versions: Vec<i64> = MIGRATIONS.iter().map(|m| m.version).collect();
+    assert_eq!(first_run, all_versions);
+    assert!(second_run.is_empty());
+    assert_eq!(
+        schema_version(&conn).await.unwrap(),
+        *all_versions.last().unwrap()
+    );
+}
+
+/// Verifies that a database created before migrations existed is brought up to date.
+#[tokio::test]
+async fn test_sqlite_migrations_upgrade_legacy_database() {
+    setup_tracing();
+
+    // 1. Arrange: A `documents` table from before `content_hash` and `org_id`.
+    let provider = SqliteProvider::new(":memory:")
+        .await
+        .expect("Failed to create SqliteProvider");
+    provider
+        .initialize_with_data(
+            "CREATE TABLE documents (id TEXT PRIMARY KEY, owner_id TEXT, source_url TEXT, title TEXT, content TEXT NOT NULL, created_at DATETIME DEFAULT CURRENT_TIMESTAMP, expires_at DATETIME);
+             INSERT INTO documents (id, content) VALUES ('doc1', 'Kept content')",
+        )
+        .await
+        .expect("Failed to create the legacy table");
+
+    // 2. Act
+    provider
+        .initialize_schema()
+        .await
+        .expect("Failed to migrate the legacy database");
+
+    // 3. Assert: The data is kept and the new columns are usable.
+    let result_json = provider
+        .execute_query("SELECT id, content, content_hash, org_id FROM documents")
+        .await
+        .expect("Failed to query documents table");
+    let result: serde_json::Value = serde_json::from_str(&result_json).unwrap();
+    assert_eq!(
+        result,
+        json!([{ "id": "doc1", "content": "Kept content", "content_hash": null, "org_id": null }])
+    );
+}
+
+/// Verifies that the built-in role permissions are seeded, without replacing the
+/// permissions a role was already granted.
+#[tokio::test]
+async fn test_sqlite_migrations_seed_role_permissions() {
+    setup_tracing();
+
+    // 1. Arrange: The `user` role was restricted before the seeding migration.
+    let provider = SqliteProvider::new(":memory:")
+        .await
+        .expect("Failed to create SqliteProvider");
+    provider
+        .initialize_with_data(
+            "CREATE TABLE role_permissions (role TEXT NOT NULL, permission TEXT NOT NULL, PRIMARY KEY (role, permission));
+             INSERT INTO role_permissions (role, permission) VALUES ('user', 'search:read')",
+        )
+        .await
+        .expect("Failed to create the role permissions");
+
+    // 2. Act
+    provider
+        .initialize_schema()
+        .await
+        .expect("Failed to migrate the database");
+
+    // 3. Assert
+    let result_json = provider
+        .execute_query("SELECT role, permission FROM role_permissions ORDER BY role, permission")
+        .await
+        .expect("Failed to query role_permissions table");
+    let result: serde_json::Value = serde_json::from_str(&result_json).unwrap();
+    assert_eq!(
+        result,
+        json!([
+            { "role": "root", "permission": "*:*" },
+            { "role": "user", "permission": "search:read" }
+        ])
+    );
+}
+
+/// Verifies that a backup restores into a fresh database with its embeddings and
+/// tables created outside the migrations, and that a backup is never overwritten.
+#[tokio::test]
+async fn test_sqlite_backup_and_restore_round_trip() {
+    setup_tracing();
+
+    // 1. Arrange
+    let temp_dir = tempfile::tempdir().unwrap();
+    let backup_path = temp_dir.path().join("backups").join("anyrag.db");
+    let source = SqliteProvider::new(":memory:")
+        .await
+        .expect("Failed to create SqliteProvider");
+    source
+        .initialize_schema()
+        .await
+        .expect("Failed to initialize schema");
+    source
+        .initialize_with_data(
+            "INSERT INTO documents (id, title, content) VALUES ('doc1', 'Roadmap', 'Ship backups');
+             INSERT INTO document_embeddings (document_id, model_name, embedding) VALUES ('doc1', 'mock-model', X'0000803F');
+             CREATE TABLE faq_kb (id INTEGER PRIMARY KEY, answer TEXT);
+             INSERT INTO faq_kb (answer) VALUES ('Nightly');",
+        )
+        .await
+        .expect("Failed to insert test data");
+
+    // 2. Act
+    let backup_summary = backup_to_file(&source.db, &backup_path)
+        .await
+        .expect("Backup failed");
+    let target = SqliteProvider::new(":memory:")
+        .await
+        .expect("Failed to create SqliteProvider");
+    let restore_summary = restore_from_file(&backup_path, &target.db)
+        .await
+        .expect("Restore failed");
+
+    // 3. Assert
+    assert_eq!(backup_summary.rows["documents"], 1);
+    assert_eq!(backup_summary.rows["document_embeddings"], 1);
+    assert_eq!(backup_summary.rows["faq_kb"], 1);
+    assert_eq!(restore_summary, backup_summary);
+    let result_json = target
+        .execute_query(
+            "SELECT d.title, e.model_name, hex(e.embedding) AS embedding, f.answer FROM documents d JOIN document_embeddings e ON e.document_id = d.id, faq_kb f",
+        )
+        .await
+        .expect("Failed to query the restored database");
+    let result: serde_json::Value = serde_json::from_str(&result_json).unwrap();
+    assert_eq!(
+        result,
+        json!([{ "title": "Roadmap", "model_name": "mock-model", "embedding": "0000803F", "answer": "Nightly" }])
+    );
+
+    assert!(backup_to_file(&source.db, &backup_path).await.is_err());
+    assert!(restore_from_file(&backup_path, &target.db).await.is_err());
+}
@@ -0,0 +1,134 @@
+//! # Structured Output Tests
+//!
+//! This file contains tests for output schema enforcement, covering the schema
+//! validator and the validate-and-repair loop in the prompt pipeline.
+
+mod common;
+
+use anyrag::{
+    structured_output::validate_against_schema, ExecutePromptOptions, PromptClientBuilder,
+    PromptError,
+};
+use common::{setup_tracing, MockAiProvider, MockStorageProvider};
+use serde_json::{json, Value};
+
+fn answer_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "answer": { "type": "string" },
+            "confidence": { "type": "string", "enum": ["low", "high"] },
+            "sources": { "type": "array", "items": { "type": "string" } }
+        },
+        "required": ["answer", "confidence"],
+        "additionalProperties": false
+    })
+}
+
+#[test]
+fn test_validate_against_schema_reports_violations() {
+    let schema = answer_schema();
+
+    let valid = json!({ "answer": "42", "confidence": "high", "sources": ["a"] });
+    assert!(validate_against_schema(&valid, &schema).is_empty());
+
+    let invalid = json!({ "confidence": "maybe", "sources": [1], "extra": true });
+    let errors = validate_against_schema(&invalid, &schema);
+    assert_eq!(errors.len(), 4, "Unexpected errors: {errors:?}");
+    assert!(errors
+        .iter()
+        .any(|e| e.contains("missing required property 'answer'")));
+    assert!(errors.iter().any(|e| e.starts_with("$.confidence")));
+    assert!(errors.iter().any(|e| e.starts_with("$.sources[0]")));
+    assert!(errors
+        .iter()
+        .any(|e| e.contains("unexpected property 'extra'")));
+}
+
+#[tokio::test]
+async fn test_output_schema_is_repaired_after_one_retry() {
+    setup_tracing();
+
+    // 1. Direct answer, 2. non-conforming structured output, 3. repaired output.
+    let mock_ai_provider = MockAiProvider::new(vec![
+        "The answer is 42.".to_string(),
+        r#"{"answer": "42"}"#.to_string(),
+        "```json\n{\"answer\": \"42\", \"confidence\": \"high\"}\n```".to_string(),
+    ]);
+    let call_history = mock_ai_provider.call_history.clone();
+    let client = PromptClientBuilder::new()
+        .ai_provider(Box::new(mock_ai_provider))
+        .storage_provider(Box::new(MockStorageProvider))
+        .build()
+        .unwrap();
+
+    let options = ExecutePromptOptions {
+        prompt: "What is the answer?".to_string(),
+        output_schema: Some(answer_schema()),
+        ..Default::default()
+    };
+
+    let result = client.execute_prompt_with_options(options).await.unwrap();
+
+    let value: Value = serde_json::from_str(&result.text).unwrap();
+    assert_eq!(value, json!({ "answer": "42", "confidence": "high" }));
+
+    let history = call_history.read().unwrap();
+    assert_eq!(history.len(), 3);
+    assert!(history[2]
+        .1
+        .contains("missing required property 'confidence'"));
+}
+
+#[tokio::test]
+async fn test_output_schema_fails_when_repair_does_not_conform() {
+    setup_tracing();
+
+    let mock_ai_provider = MockAiProvider::new(vec![
+        "The answer is 42.".to_string(),
+        "not json".to_string(),
+        "still not json".to_string(),
+    ]);
+    let client = PromptClientBuilder::new()
+        .ai_provider(Box::new(mock_ai_provider))
+        .storage_provider(Box::new(MockStorageProvider))
+        .build()
+        .unwrap();
+
+    let options = ExecutePromptOptions {
+        prompt: "What is the answer?".to_string(),
+        output_schema: Some(answer_schema()),
+        ..Default::default()
+    };
+
+    let result = client.execute_prompt_with_options(options).await;
+
+    assert!(matches!(result, Err(PromptError::OutputSchemaViolation(_))));
+}
+
+#[tokio::test]
+async fn test_prompt_without_a_query_fails_instead_of_returning_unstructured_text() {
+    setup_tracing();
+
+    let mock_ai_provider = MockAiProvider::new(vec!["   ".to_string()]);
+    let call_history = mock_ai_provider.call_history.clone();
+    let client = PromptClientBuilder::new()
+        .ai_provider(Box::new(mock_ai_provider))
+        .storage_provider(Box::new(MockStorageProvider))
+        .build()
+        .unwrap();
+
+    let options = ExecutePromptOptions {
+        prompt: "What is the answer?".to_string(),
+        output_schema: Some(answer_schema()),
+        ..Default::default()
+    };
+
+    let result = client.execute_prompt_with_options(options).await;
+
+    assert!(
+        matches!(result, Err(PromptError::NoQueryGenerated)),
+        "Unexpected result: {result:?}"
+    );
+    assert_eq!(call_history.read().unwrap().len(), 1);
+}
@@ -0,0 +1,277 @@
+//! # Source Summarization Tests
+//!
+//! This file contains tests for storing a summary document per long ingested source,
+//! for summarizing sources in parts, and for expanding summaries found by hybrid search
+//! to the chunks of their source.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::{
+    providers::db::{sqlite::SqliteProvider, storage::SummarySearch},
+    search::{hybrid_search, HybridSearchOptions, HybridSearchPrompts},
+    summarization::{source_chunks, summarize_chunks, summarize_source, SummarizationConfig},
+};
+use common::{setup_mock_embedding_server, setup_tracing, MockAiProvider};
+use serde_json::json;
+use std::sync::Arc;
+use turso::{params, Connection};
+
+const OWNER: &str = "alice";
+
+async fn insert_document(
+    conn: &Connection,
+    id: &str,
+    source_url: &str,
+    content: &str,
+    embedding: Option<[f32; 4]>,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO documents (id, owner_id, source_url, title, content) VALUES (?, ?, ?, ?, ?)",
+        params![id, OWNER, source_url, content, content],
+    )
+    .await?;
+    if let Some(embedding) = embedding {
+        let bytes: Vec<u8> = embedding.iter().flat_map(|x| x.to_ne_bytes()).collect();
+        conn.execute(
+            "INSERT INTO document_embeddings (document_id, model_name, embedding) VALUES (?, ?, ?)",
+            params![id, "mock-model", bytes.as_slice()],
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Stores a manual of three chunks.
+async fn setup_manual(provider: &SqliteProvider) -> Result<()> {
+    let conn = provider.db.connect()?;
+    insert_document(&conn, "c0", "manual#chunk_0", "Unpack the device.", None).await?;
+    insert_document(&conn, "c1", "manual#chunk_1", "Plug it in.", None).await?;
+    insert_document(&conn, "c2", "manual#chunk_2", "Clean the filter.", None).await?;
+    insert_document(&conn, "faq", "faq", "Shipping is free.", None).await?;
+    Ok(())
+}
+
+fn config(min_source_chars: usize) -> SummarizationConfig {
+    SummarizationConfig {
+        min_source_chars,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_long_source_is_summarized_and_linked_to_its_chunks() -> Result<()> {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    setup_manual(&provider).await?;
+    let mut conn = provider.db.connect()?;
+    let ai_provider = MockAiProvider::new(vec![
+        "A manual on setting up the device.".to_string(),
+        "A manual on setting up and cleaning the device.".to_string(),
+    ]);
+
+    // 1. The summary is stored under the source's link, linked to its chunks in order.
+    let summary_id = summarize_source(
+        &mut conn,
+        &ai_provider,
+        Some(OWNER),
+        "manual",
+        &config(10),
+        "Summarize.",
+        "{content}",
+    )
+    .await?
+    .expect("The manual is long enough to be summarized.");
+    let chunk_ids = provider
+        .get_summarized_chunk_ids(&["manual#summary"], Some(OWNER), None)
+        .await?;
+    assert_eq!(
+        chunk_ids.get("manual#summary"),
+        Some(&vec!["c0".to_string(), "c1".to_string(), "c2".to_string()])
+    );
+    assert_eq!(
+        ai_provider.call_history.read().unwrap()[0].1,
+        "Unpack the device.\n\nPlug it in.\n\nClean the filter."
+    );
+
+    // 2. The summary is not a chunk of the source, and summarizing again replaces it.
+    assert_eq!(source_chunks(&conn, Some(OWNER), "manual").await?.len(), 3);
+    let new_summary_id = summarize_source(
+        &mut conn,
+        &ai_provider,
+        Some(OWNER),
+        "manual",
+        &config(10),
+        "Summarize.",
+        "{content}",
+    )
+    .await?
+    .unwrap();
+    assert_ne!(summary_id, new_summary_id);
+    let mut rows = conn
+        .query(
+            "SELECT id, content FROM documents WHERE source_url = 'manual#summary'",
+            (),
+        )
+        .await?;
+    let row = rows.next().await?.expect("The summary should be stored.");
+    assert_eq!(row.get::<String>(0)?, new_summary_id);
+    assert_eq!(
+        row.get::<String>(1)?,
+        "A manual on setting up and cleaning the device."
+    );
+    assert!(
+        rows.next().await?.is_none(),
+        "The old summary should be gone."
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_short_source_is_not_summarized() -> Result<()> {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    setup_manual(&provider).await?;
+    let mut conn = provider.db.connect()?;
+    let ai_provider = MockAiProvider::new(vec![]);
+
+    let summary_id = summarize_source(
+        &mut conn,
+        &ai_provider,
+        Some(OWNER),
+        "manual",
+        &SummarizationConfig::default(),
+        "Summarize.",
+        "{content}",
+    )
+    .await?;
+
+    assert!(summary_id.is_none());
+    assert!(ai_provider.call_history.read().unwrap().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_long_sources_are_summarized_in_parts_and_merged() -> Result<()> {
+    setup_tracing();
+    let ai_provider = MockAiProvider::new(vec![
+        "Part A".to_string(),
+        "Part B".to_string(),
+        "Whole".to_string(),
+    ]);
+    let config = SummarizationConfig {
+        max_input_chars: 12,
+        ..Default::default()
+    };
+
+    let summary = summarize_chunks(
+        &ai_provider,
+        &["aaaaaaaaaa", "bbbbbbbbbb"],
+        &config,
+        "Summarize.",
+        "{content}",
+    )
+    .await?;
+
+    assert_eq!(summary, "Whole");
+    let history = ai_provider.call_history.read().unwrap();
+    let prompts: Vec<&str> = history.iter().map(|(_, user)| user.as_str()).collect();
+    assert_eq!(
+        prompts,
+        vec!["aaaaaaaaaa", "bbbbbbbbbb", "Part A\n\nPart B"]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_hybrid_search_expands_summaries_to_matching_chunks() -> Result<()> {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let conn = provider.db.connect()?;
+    // The query embeds close to the summary and the other documents, not to the chunks.
+    insert_document(
+        &conn,
+        "c0",
+        "manual#chunk_0",
+        "Unpack.",
+        Some([0.0, 0.0, 1.0, 0.0]),
+    )
+    .await?;
+    insert_document(
+        &conn,
+        "c1",
+        "manual#chunk_1",
+        "Plug in.",
+        Some([0.3, 0.7, 0.0, 0.0]),
+    )
+    .await?;
+    insert_document(
+        &conn,
+        "c2",
+        "manual#chunk_2",
+        "Clean.",
+        Some([0.0, 0.0, 0.0, 1.0]),
+    )
+    .await?;
+    for id in ["u1", "u2", "u3"] {
+        insert_document(&conn, id, id, "Unrelated.", Some([0.95, 0.05, 0.0, 0.0])).await?;
+    }
+    let mut write_conn = provider.db.connect()?;
+    let summary_ai = MockAiProvider::new(vec!["Device manual.".to_string()]);
+    let summary_id = summarize_source(
+        &mut write_conn,
+        &summary_ai,
+        Some(OWNER),
+        "manual",
+        &config(1),
+        "Summarize.",
+        "{content}",
+    )
+    .await?
+    .unwrap();
+    let bytes: Vec<u8> = [1.0f32, 0.0, 0.0, 0.0]
+        .iter()
+        .flat_map(|x| x.to_ne_bytes())
+        .collect();
+    conn.execute(
+        "INSERT INTO document_embeddings (document_id, model_name, embedding) VALUES (?, ?, ?)",
+        params![summary_id, "mock-model", bytes.as_slice()],
+    )
+    .await?;
+
+    let mock_embedding_server = setup_mock_embedding_server().await;
+    let embedding_api_url = format!("{}/v1/embeddings", mock_embedding_server.uri());
+    let analysis_ai =
+        MockAiProvider::new(vec![json!({ "entities": [], "keyphrases": [] }).to_string()]);
+    let results = hybrid_search(
+        Arc::new(provider.clone()),
+        Arc::new(analysis_ai),
+        HybridSearchOptions {
+            query_text: "device".to_string(),
+            owner_id: Some(OWNER.to_string()),
+            org_id: None,
+            collection: None,
+            limit: 2,
+            prompts: HybridSearchPrompts {
+                analysis_system_prompt: "Analyze.",
+                analysis_user_prompt_template: "{prompt}",
+            },
+            use_keyword_search: false,
+            use_vector_search: true,
+            embedding_api_url: &embedding_api_url,
+            embedding_model: "mock-model",
+            embedding_api_key: None,
+            temporal_ranking_config: None,
+        },
+    )
+    .await?;
+
+    // The summary is followed by the chunk of its source closest to the query.
+    let links: Vec<&str> = results.iter().map(|r| r.link.as_str()).collect();
+    assert_eq!(links, vec!["manual#summary", "manual#chunk_1"]);
+    assert_eq!(results[1].score, results[0].score);
+    Ok(())
+}
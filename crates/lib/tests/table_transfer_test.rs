@@ -0,0 +1,184 @@
+//! # Table Export and Import Tests
+//!
+//! This file contains tests for copying a single table of a local database to and from
+//! CSV, JSON Lines and Parquet files.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::providers::db::sqlite::{
+    table_transfer::{
+        export_table_to_file, import_table_from_csv, import_table_from_file, TableFormat,
+        TableTransferError,
+    },
+    SqliteProvider,
+};
+use common::setup_tracing;
+use tempfile::tempdir;
+use turso::Value as TursoValue;
+
+/// Sets up a database with a `products` table holding a value of every storage class.
+async fn setup_database() -> Result<SqliteProvider> {
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider
+        .initialize_with_data(
+            "CREATE TABLE products (_id TEXT PRIMARY KEY, name TEXT, stock INTEGER, price REAL, thumbnail BLOB);
+             INSERT INTO products VALUES ('p1', 'Kettle, steel', 12, 19.5, X'CAFE');
+             INSERT INTO products VALUES ('p2', 'Teapot', NULL, 7.25, NULL);",
+        )
+        .await?;
+    Ok(provider)
+}
+
+async fn read_rows(provider: &SqliteProvider, table: &str) -> Result<Vec<Vec<TursoValue>>> {
+    let conn = provider.db.connect()?;
+    let mut rows = conn
+        .query(
+            &format!("SELECT _id, name, stock, price, thumbnail FROM {table} ORDER BY _id"),
+            (),
+        )
+        .await?;
+    let mut values = Vec::new();
+    while let Some(row) = rows.next().await? {
+        values.push((0..5).map(|i| row.get_value(i)).collect::<Result<_, _>>()?);
+    }
+    Ok(values)
+}
+
+async fn assert_round_trip(format: TableFormat) -> Result<()> {
+    // --- Arrange ---
+    setup_tracing();
+    let source = setup_database().await?;
+    let temp_dir = tempdir()?;
+    let path = temp_dir
+        .path()
+        .join(format!("products.{}", format.extension()));
+
+    // --- Act ---
+    let exported = export_table_to_file(&source.db, "products", format, &path).await?;
+    let target = SqliteProvider::new(":memory:").await?;
+    let summary = import_table_from_file(&target.db, &path, "products", format).await?;
+
+    // --- Assert ---
+    assert_eq!(exported, 2);
+    assert_eq!(summary.rows_read, 2);
+    assert_eq!(summary.rows_inserted, 2);
+    assert!(summary.table_created);
+    assert_eq!(
+        read_rows(&target, "products").await?,
+        read_rows(&source, "products").await?
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_table_round_trips_through_csv() -> Result<()> {
+    assert_round_trip(TableFormat::Csv).await
+}
+
+#[tokio::test]
+async fn test_table_round_trips_through_jsonl() -> Result<()> {
+    assert_round_trip(TableFormat::Jsonl).await
+}
+
+#[cfg(feature = "parquet")]
+#[tokio::test]
+async fn test_table_round_trips_through_parquet() -> Result<()> {
+    assert_round_trip(TableFormat::Parquet).await
+}
+
+#[tokio::test]
+async fn test_import_into_an_existing_table_skips_existing_keys() -> Result<()> {
+    // --- Arrange ---
+    setup_tracing();
+    let provider = setup_database().await?;
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("products.jsonl");
+    std::fs::write(
+        &path,
+        "{\"_id\": \"p2\", \"name\": \"Teapot\"}\n\n{\"_id\": \"p3\", \"name\": \"Mug\", \"stock\": 40}\n",
+    )?;
+
+    // --- Act ---
+    let summary =
+        import_table_from_file(&provider.db, &path, "products", TableFormat::Jsonl).await?;
+
+    // --- Assert ---
+    assert_eq!(summary.rows_read, 2);
+    assert_eq!(summary.rows_inserted, 1);
+    assert!(!summary.table_created);
+    let rows = read_rows(&provider, "products").await?;
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[2][1], TursoValue::Text("Mug".to_string()));
+    assert_eq!(rows[2][2], TursoValue::Integer(40));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_import_rejects_columns_the_table_does_not_have() -> Result<()> {
+    // --- Arrange ---
+    setup_tracing();
+    let provider = setup_database().await?;
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("products.csv");
+    std::fs::write(&path, "_id,colour\np9,red\n")?;
+
+    // --- Act ---
+    let result = import_table_from_file(&provider.db, &path, "products", TableFormat::Csv).await;
+
+    // --- Assert ---
+    assert!(matches!(
+        result,
+        Err(TableTransferError::UnknownColumns { ref columns, .. }) if columns == &["colour"]
+    ));
+    assert_eq!(read_rows(&provider, "products").await?.len(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_of_a_missing_table_fails() -> Result<()> {
+    setup_tracing();
+    let provider = setup_database().await?;
+    let temp_dir = tempdir()?;
+
+    let result = export_table_to_file(
+        &provider.db,
+        "orders",
+        TableFormat::Csv,
+        &temp_dir.path().join("orders.csv"),
+    )
+    .await;
+
+    assert!(matches!(result, Err(TableTransferError::TableNotFound(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_csv_content_is_imported_into_a_typed_table() -> Result<()> {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+
+    let summary = import_table_from_csv(
+        &provider.db,
+        "sku,stock,note\nA1,4,\nA2,12,restocked\n",
+        "stock",
+    )
+    .await?;
+
+    assert_eq!(summary.rows_inserted, 2);
+    assert!(summary.table_created);
+    let conn = provider.db.connect()?;
+    let row = conn
+        .query(
+            "SELECT SUM(stock), typeof(stock), count(note) FROM stock",
+            (),
+        )
+        .await?
+        .next()
+        .await?
+        .expect("Expected a row");
+    assert_eq!(row.get::<i64>(0)?, 16);
+    assert_eq!(row.get::<String>(1)?, "integer");
+    assert_eq!(row.get::<i64>(2)?, 1);
+    Ok(())
+}
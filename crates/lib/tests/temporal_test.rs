@@ -0,0 +1,156 @@
+//! # Temporal Reasoning Tests
+//!
+//! This file contains tests for parsing the time constraints of search queries and
+//! applying them to search results dated by their `PROPERTY` metadata.
+
+use anyrag::{
+    temporal::{
+        apply_temporal_constraint, document_date, parse_metadata_date, parse_temporal_constraint,
+        TemporalConstraint, TemporalOrder,
+    },
+    types::SearchResult,
+};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+const NEWEST_KEYWORDS: &[&str] = &["newest", "latest", "most recent"];
+
+fn date(value: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+}
+
+fn result(link: &str) -> SearchResult {
+    SearchResult {
+        title: link.to_string(),
+        link: link.to_string(),
+        description: String::new(),
+        score: 0.0,
+    }
+}
+
+fn parse(query: &str) -> Option<TemporalConstraint> {
+    parse_temporal_constraint(query, NEWEST_KEYWORDS, date("2024-09-15"))
+}
+
+#[test]
+fn test_queries_without_time_constraint_parse_to_none() {
+    assert_eq!(parse("What is in the product catalog?"), None);
+    assert_eq!(parse("Tell me about the release from the team"), None);
+}
+
+#[test]
+fn test_order_keywords_parse() {
+    let newest = parse("What is the latest release?").unwrap();
+    assert_eq!(newest.order, Some(TemporalOrder::Newest));
+    assert_eq!((newest.from, newest.to), (None, None));
+
+    let oldest = parse("Which is the earliest report?").unwrap();
+    assert_eq!(oldest.order, Some(TemporalOrder::Oldest));
+}
+
+#[test]
+fn test_date_ranges_parse() {
+    let before = parse("Releases before June").unwrap();
+    assert_eq!((before.from, before.to), (None, Some(date("2024-05-31"))));
+
+    let after = parse("reports after 2023").unwrap();
+    assert_eq!((after.from, after.to), (Some(date("2024-01-01")), None));
+
+    let between = parse("changes between 2023-01-15 and 2023-03").unwrap();
+    assert_eq!(
+        (between.from, between.to),
+        (Some(date("2023-01-15")), Some(date("2023-03-31")))
+    );
+
+    let month = parse("What shipped in March 2022?").unwrap();
+    assert_eq!(
+        (month.from, month.to),
+        (Some(date("2022-03-01")), Some(date("2022-03-31")))
+    );
+
+    let combined = parse("the newest post since last month").unwrap();
+    assert_eq!(combined.order, Some(TemporalOrder::Newest));
+    assert_eq!(
+        (combined.from, combined.to),
+        (Some(date("2024-08-01")), None)
+    );
+}
+
+#[test]
+fn test_documents_are_dated_by_any_date_property() {
+    assert_eq!(
+        parse_metadata_date("2024-02-01T10:30:00Z"),
+        Some(date("2024-02-01"))
+    );
+    assert_eq!(
+        parse_metadata_date("2024-02-01T10:30:00.000+0000"),
+        Some(date("2024-02-01"))
+    );
+    assert_eq!(parse_metadata_date("support"), None);
+
+    let properties = vec![
+        ("channel".to_string(), "support".to_string()),
+        ("updated_at".to_string(), "2024-03-01".to_string()),
+        ("created_at".to_string(), "2024-01-01".to_string()),
+    ];
+    assert_eq!(
+        document_date(&properties, "updated_at"),
+        Some(date("2024-03-01")),
+        "The preferred property is used when it holds a date."
+    );
+    assert_eq!(
+        document_date(&properties, "release_date"),
+        Some(date("2024-01-01")),
+        "Otherwise, the first date property by name is used."
+    );
+}
+
+#[test]
+fn test_constraints_filter_and_rank_results() {
+    let results = vec![result("a"), result("b"), result("c"), result("undated")];
+    let dates = HashMap::from([
+        ("a".to_string(), date("2024-01-10")),
+        ("b".to_string(), date("2024-06-10")),
+        ("c".to_string(), date("2024-03-10")),
+    ]);
+    let links = |results: Vec<SearchResult>| -> Vec<String> {
+        results.into_iter().map(|r| r.link).collect()
+    };
+
+    let before_june = parse("before June").unwrap();
+    assert_eq!(
+        links(apply_temporal_constraint(
+            results.clone(),
+            &dates,
+            &before_june
+        )),
+        vec!["a", "c"],
+        "Matching results keep their order of relevance."
+    );
+
+    let latest = parse("the latest").unwrap();
+    assert_eq!(
+        links(apply_temporal_constraint(results.clone(), &dates, &latest)),
+        vec!["b"]
+    );
+
+    let latest_before_june = parse("the latest before June").unwrap();
+    assert_eq!(
+        links(apply_temporal_constraint(
+            results.clone(),
+            &dates,
+            &latest_before_june
+        )),
+        vec!["c"]
+    );
+
+    assert_eq!(
+        links(apply_temporal_constraint(
+            results,
+            &HashMap::new(),
+            &before_june
+        )),
+        vec!["a", "b", "c", "undated"],
+        "Without dates, the results are left unchanged."
+    );
+}
@@ -0,0 +1,210 @@
+//! # Document Trash Tests
+//!
+//! This file contains tests for moving documents to the trash, which hides them from
+//! search, for restoring them, by hand or by ingesting their content again, and for
+//! purging the documents deleted before a cutoff.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::{
+    ingest::{
+        bulk_insert_documents, content_hash, find_duplicate_document, list_trash, purge_trash,
+        restore_document, soft_delete_document, NewDocument, TrashError,
+    },
+    providers::db::{sqlite::SqliteProvider, storage::KeywordSearch},
+};
+use chrono::{TimeZone, Utc};
+use common::setup_tracing;
+use turso::{params, Connection};
+
+async fn insert_document(conn: &Connection, id: &str, owner_id: &str, content: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO documents (id, owner_id, source_url, title, content) VALUES (?, ?, ?, ?, ?)",
+        params![
+            id,
+            owner_id,
+            format!("https://example.com/{id}"),
+            id,
+            content
+        ],
+    )
+    .await?;
+    conn.execute(
+        "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_value) VALUES (?, ?, 'KEYPHRASE', 'refunds')",
+        params![id, owner_id],
+    )
+    .await?;
+    Ok(())
+}
+
+async fn count(conn: &Connection, sql: &str) -> Result<i64> {
+    let mut rows = conn.query(sql, ()).await?;
+    Ok(rows.next().await?.unwrap().get(0)?)
+}
+
+async fn search_links(provider: &SqliteProvider, owner_id: &str) -> Result<Vec<String>> {
+    let results = provider
+        .keyword_search("refunds", 10, Some(owner_id), None, None)
+        .await?;
+    Ok(results.into_iter().map(|result| result.link).collect())
+}
+
+#[tokio::test]
+async fn test_deleted_document_is_hidden_until_restored() -> Result<()> {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let conn = provider.db.connect()?;
+    insert_document(&conn, "refunds", "alice", "Refunds take 5 days.").await?;
+    let version = provider.corpus_version(Some("alice"), None).await?;
+
+    // 1. A deleted document is in the trash and no longer found.
+    let trashed = soft_delete_document(&conn, "refunds", Some("alice")).await?;
+    assert_eq!(trashed.owner_id.as_deref(), Some("alice"));
+    assert!(search_links(&provider, "alice").await?.is_empty());
+    assert_ne!(provider.corpus_version(Some("alice"), None).await?, version);
+    let trash = list_trash(&conn, Some("alice")).await?;
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].id, "refunds");
+
+    // 2. It cannot be deleted twice.
+    assert!(matches!(
+        soft_delete_document(&conn, "refunds", Some("alice")).await,
+        Err(TrashError::DocumentNotFound(_))
+    ));
+
+    // 3. A restored document is found again, with its metadata.
+    restore_document(&conn, "refunds", Some("alice")).await?;
+    assert_eq!(
+        search_links(&provider, "alice").await?,
+        vec!["https://example.com/refunds"]
+    );
+    assert!(list_trash(&conn, Some("alice")).await?.is_empty());
+    assert_eq!(provider.corpus_version(Some("alice"), None).await?, version);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_only_the_owner_can_delete_and_restore() -> Result<()> {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let conn = provider.db.connect()?;
+    insert_document(&conn, "refunds", "alice", "Refunds take 5 days.").await?;
+
+    assert!(matches!(
+        soft_delete_document(&conn, "refunds", Some("bob")).await,
+        Err(TrashError::DocumentNotFound(_))
+    ));
+    // Without an owner, as for administrators, any document can be deleted.
+    soft_delete_document(&conn, "refunds", None).await?;
+    assert!(list_trash(&conn, Some("bob")).await?.is_empty());
+    assert!(matches!(
+        restore_document(&conn, "refunds", Some("bob")).await,
+        Err(TrashError::DocumentNotFound(_))
+    ));
+    assert_eq!(list_trash(&conn, None).await?.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_purge_deletes_documents_trashed_before_the_cutoff() -> Result<()> {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let conn = provider.db.connect()?;
+    insert_document(&conn, "old", "alice", "Refunds took 10 days.").await?;
+    insert_document(&conn, "recent", "alice", "Refunds take 7 days.").await?;
+    insert_document(&conn, "kept", "alice", "Refunds take 5 days.").await?;
+    conn.execute(
+        "UPDATE documents SET deleted_at = '2024-01-01 00:00:00' WHERE id = 'old'",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "UPDATE documents SET deleted_at = '2024-03-01 00:00:00' WHERE id = 'recent'",
+        (),
+    )
+    .await?;
+
+    let purged = purge_trash(&conn, Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap()).await?;
+
+    assert_eq!(purged, 1);
+    assert_eq!(
+        count(&conn, "SELECT COUNT(*) FROM documents WHERE id = 'old'").await?,
+        0
+    );
+    assert_eq!(
+        count(
+            &conn,
+            "SELECT COUNT(*) FROM content_metadata WHERE document_id = 'old'"
+        )
+        .await?,
+        0
+    );
+    assert_eq!(count(&conn, "SELECT COUNT(*) FROM documents").await?, 2);
+    let trash = list_trash(&conn, Some("alice")).await?;
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].id, "recent");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reingesting_a_deleted_document_restores_it() -> Result<()> {
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let mut conn = provider.db.connect()?;
+    let new_document = |id: &str, content: &str| NewDocument {
+        id: id.to_string(),
+        source_url: format!("https://example.com/{id}"),
+        title: id.to_string(),
+        content: content.to_string(),
+    };
+    bulk_insert_documents(
+        &mut conn,
+        Some("alice"),
+        vec![
+            new_document("refunds", "Refunds take 5 days."),
+            new_document("returns", "Returns are free for refunds."),
+        ],
+    )
+    .await?;
+    for id in ["refunds", "returns"] {
+        conn.execute(
+            "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_value) VALUES (?, 'alice', 'KEYPHRASE', 'refunds')",
+            params![id],
+        )
+        .await?;
+        soft_delete_document(&conn, id, Some("alice")).await?;
+    }
+    assert!(search_links(&provider, "alice").await?.is_empty());
+
+    // 1. Ingesting the same content again restores the trashed document, once.
+    let stored = bulk_insert_documents(
+        &mut conn,
+        Some("alice"),
+        vec![new_document("refunds", "Refunds take 5 days.")],
+    )
+    .await?;
+    assert!(stored.is_empty());
+    let duplicate = find_duplicate_document(
+        &conn,
+        Some("alice"),
+        &content_hash("Returns are free for refunds."),
+    )
+    .await?;
+    assert_eq!(duplicate.as_deref(), Some("returns"));
+
+    // 2. Both are found again, and the trash is empty.
+    let mut links = search_links(&provider, "alice").await?;
+    links.sort();
+    assert_eq!(
+        links,
+        vec!["https://example.com/refunds", "https://example.com/returns"]
+    );
+    assert!(list_trash(&conn, Some("alice")).await?.is_empty());
+    assert_eq!(count(&conn, "SELECT COUNT(*) FROM documents").await?, 2);
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! # Front Matter
+//!
+//! Parses the YAML block that docs sites put between `---` lines at the top of a
+//! Markdown file. Only the `title`, `date` and `tags` keys are kept.
+
+use serde_yaml::Value as YamlValue;
+use tracing::warn;
+
+/// The fields kept from the front matter of a file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    /// The date as written, e.g. `2024-05-01`.
+    pub date: Option<String>,
+    /// The tags, from either a YAML list or a comma-separated string.
+    pub tags: Vec<String>,
+}
+
+/// Splits the front matter off the start of `content`, returning it and the rest of the
+/// file. A block that is not a YAML mapping is left in the content, as it is more likely
+/// a horizontal rule than front matter.
+pub fn split_front_matter(content: &str) -> (Option<FrontMatter>, &str) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (None, content);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            let yaml = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return match serde_yaml::from_str::<YamlValue>(yaml) {
+                Ok(YamlValue::Mapping(mapping)) => (Some(front_matter_of(&mapping)), body),
+                Ok(_) => (None, content),
+                Err(e) => {
+                    warn!("Ignoring front matter that is not valid YAML: {e}");
+                    (None, content)
+                }
+            };
+        }
+        offset += line.len();
+    }
+    (None, content)
+}
+
+fn front_matter_of(mapping: &serde_yaml::Mapping) -> FrontMatter {
+    let tags = match mapping.get("tags") {
+        Some(YamlValue::Sequence(tags)) => tags.iter().filter_map(scalar_text).collect(),
+        Some(YamlValue::String(tags)) => tags
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    };
+    FrontMatter {
+        title: mapping.get("title").and_then(scalar_text),
+        date: mapping.get("date").and_then(scalar_text),
+        tags,
+    }
+}
+
+/// The text of a scalar YAML value; `None` for empty strings, nulls and collections.
+fn scalar_text(value: &YamlValue) -> Option<String> {
+    let text = match value {
+        YamlValue::String(text) => text.trim().to_string(),
+        YamlValue::Number(number) => number.to_string(),
+        YamlValue::Bool(flag) => flag.to_string(),
+        _ => return None,
+    };
+    (!text.is_empty()).then_some(text)
+}
@@ -3,20 +3,50 @@
 //! This crate provides the logic for ingesting local Markdown files as a self-contained
 //! plugin for the `anyrag` ecosystem. It implements the `Ingestor` trait from the
 //! core `anyrag` library.
+//!
+//! Files are chunked by their heading outline, with each chunk titled by its heading
+//! path, and the `title`, `date` and `tags` of their YAML front matter are stored as
+//! `PROPERTY` metadata of every chunk.
 
 use anyhow::anyhow;
-use anyrag::ingest::{IngestError as AnyragIngestError, IngestionResult, Ingestor};
+use anyrag::ingest::{
+    bulk_insert_documents, bulk_insert_rows, ChunkingStrategy, IngestError as AnyragIngestError,
+    IngestionResult, Ingestor, NewDocument,
+};
 use anyrag::{
+    constants::DEFAULT_INGEST_CONCURRENCY,
     providers::{ai::generate_embeddings_batch, db::sqlite::SqliteProvider},
     PromptError,
 };
 use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
-use tracing::info;
-use turso::params;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+use turso::{params, Connection, Database, Value};
 use uuid::Uuid;
 
+pub mod front_matter;
+pub mod sections;
+
+pub use front_matter::{split_front_matter, FrontMatter};
+pub use sections::{split_sections, Section};
+
+/// The `content_metadata` property holding the `title` of a file's front matter.
+pub const TITLE_PROPERTY: &str = "title";
+/// The `content_metadata` property holding the `date` of a file's front matter.
+pub const DATE_PROPERTY: &str = "date";
+/// The `content_metadata` property holding a tag of a file's front matter, one row per tag.
+pub const TAG_PROPERTY: &str = "tag";
+
+const PROPERTY_METADATA_TYPE: &str = "PROPERTY";
+
 // --- Error Definitions ---
 
 #[derive(Error, Debug)]
@@ -31,6 +61,8 @@ pub enum MarkdownIngestError {
     Embedding(PromptError),
     #[error("Source deserialization failed: {0}")]
     SourceDeserialization(#[from] serde_json::Error),
+    #[error("Invalid glob pattern '{0}': {1}")]
+    InvalidPattern(String, glob::PatternError),
 }
 
 impl From<MarkdownIngestError> for AnyragIngestError {
@@ -41,6 +73,7 @@ impl From<MarkdownIngestError> for AnyragIngestError {
             MarkdownIngestError::SourceDeserialization(e) => {
                 AnyragIngestError::Parse(e.to_string())
             }
+            MarkdownIngestError::InvalidPattern(..) => AnyragIngestError::Parse(err.to_string()),
             _ => AnyragIngestError::Internal(anyhow!(err.to_string())),
         }
     }
@@ -58,20 +91,305 @@ pub struct EmbeddingConfig {
 #[derive(Deserialize, Serialize, Debug)]
 pub struct MarkdownSource {
     pub db_path: String,
+    /// A Markdown file, a directory of them, or a glob pattern such as `docs/**/*.md`.
     pub file_path: String,
-    pub separator: String,
+    /// Splits the file on this string instead of by its headings.
+    #[serde(default)]
+    pub separator: Option<String>,
     pub embedding_config: Option<EmbeddingConfig>,
+    /// Splits each heading section further with this strategy, e.g. to bound the size
+    /// of long sections. Ignored when `separator` is set.
+    #[serde(default)]
+    pub chunking: Option<ChunkingStrategy>,
+}
+
+/// A chunk of a Markdown file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownChunk {
+    pub title: String,
+    pub content: String,
+}
+
+/// Chunks the body of a Markdown file, its front matter already removed.
+///
+/// Without a `separator`, each heading section is a chunk (or several, when `chunking`
+/// is given) titled by its heading path. Text before the first heading, and every chunk
+/// split on a `separator`, is titled `fallback_title`, else its first 80 characters.
+pub fn chunk_markdown(
+    body: &str,
+    fallback_title: Option<&str>,
+    separator: Option<&str>,
+    chunking: Option<&ChunkingStrategy>,
+) -> Vec<MarkdownChunk> {
+    let title_of = |content: &str| match fallback_title {
+        Some(title) => title.to_string(),
+        None => content.chars().take(80).collect(),
+    };
+    if let Some(separator) = separator {
+        return body
+            .split(separator)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|content| MarkdownChunk {
+                title: title_of(content),
+                content: content.to_string(),
+            })
+            .collect();
+    }
+
+    let chunker = chunking.map(ChunkingStrategy::chunker);
+    split_sections(body)
+        .into_iter()
+        .flat_map(|section| {
+            let title = if section.heading_path.is_empty() {
+                title_of(&section.content)
+            } else {
+                section.title()
+            };
+            let pieces = match &chunker {
+                Some(chunker) => chunker.chunk(&section.content),
+                None => vec![section.content],
+            };
+            pieces
+                .into_iter()
+                .map(|piece| piece.trim().to_string())
+                .filter(|piece| !piece.is_empty())
+                .map(move |content| MarkdownChunk {
+                    title: title.clone(),
+                    content,
+                })
+        })
+        .collect()
+}
+
+/// The front matter fields stored as `PROPERTY` metadata of each chunk.
+fn front_matter_properties(front_matter: &FrontMatter) -> Vec<(&'static str, String)> {
+    let mut properties = Vec::new();
+    properties.extend(
+        front_matter
+            .title
+            .clone()
+            .map(|title| (TITLE_PROPERTY, title)),
+    );
+    properties.extend(front_matter.date.clone().map(|date| (DATE_PROPERTY, date)));
+    properties.extend(
+        front_matter
+            .tags
+            .iter()
+            .map(|tag| (TAG_PROPERTY, tag.clone())),
+    );
+    properties
+}
+
+/// Replaces the property metadata of a chunk document.
+async fn store_chunk_metadata(
+    conn: &Connection,
+    document_id: &str,
+    owner_id: Option<&str>,
+    properties: &[(&'static str, String)],
+) -> Result<(), turso::Error> {
+    conn.execute(
+        "DELETE FROM content_metadata WHERE document_id = ?",
+        params![document_id],
+    )
+    .await?;
+    for (property, value) in properties {
+        conn.execute(
+            "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_subtype, metadata_value) VALUES (?, ?, ?, ?, ?)",
+            params![
+                document_id,
+                owner_id,
+                PROPERTY_METADATA_TYPE,
+                *property,
+                value.clone()
+            ],
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+// --- File Selection ---
+
+/// Returns whether a `file_path` is a glob pattern rather than a path.
+fn is_glob(file_path: &str) -> bool {
+    file_path.contains(['*', '?', '['])
+}
+
+/// Returns whether a file found in a directory is Markdown.
+fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            matches!(extension.to_ascii_lowercase().as_str(), "md" | "markdown")
+        })
+}
+
+/// Lists the files a glob pattern or directory selects, in path order. A directory
+/// selects the Markdown files under it at any depth; symbolic links are not followed.
+pub fn markdown_files(file_path: &str) -> Result<Vec<PathBuf>, MarkdownIngestError> {
+    let mut files = Vec::new();
+    if is_glob(file_path) {
+        let paths = glob::glob(file_path)
+            .map_err(|e| MarkdownIngestError::InvalidPattern(file_path.to_string(), e))?;
+        for path in paths {
+            let path = path.map_err(glob::GlobError::into_error)?;
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+    } else {
+        let mut pending = vec![PathBuf::from(file_path)];
+        while let Some(dir) = pending.pop() {
+            for entry in std::fs::read_dir(&dir)? {
+                let entry = entry?;
+                let file_type = entry.file_type()?;
+                let path = entry.path();
+                if file_type.is_dir() {
+                    pending.push(path);
+                } else if file_type.is_file() && is_markdown(&path) {
+                    files.push(path);
+                }
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
 }
 
 // --- Ingestor Implementation ---
 
+/// Ingests one Markdown file as the chunks `{source_url}#chunk_N`, returning the ids of
+/// the chunks added. `writes` is held while writing, as the database takes one writer
+/// at a time; reading, chunking and embedding run outside of it.
+async fn ingest_file(
+    db: &Database,
+    writes: &Mutex<()>,
+    path: &Path,
+    source_url: &str,
+    source: &MarkdownSource,
+    owner_id: Option<&str>,
+) -> Result<Vec<String>, AnyragIngestError> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(MarkdownIngestError::from)?;
+    let (front_matter, body) = split_front_matter(&content);
+    let front_matter = front_matter.unwrap_or_default();
+    let chunks = chunk_markdown(
+        body,
+        front_matter.title.as_deref(),
+        source.separator.as_deref(),
+        source.chunking.as_ref(),
+    );
+
+    if chunks.is_empty() {
+        info!("No non-empty chunks found in '{source_url}'.");
+        return Ok(Vec::new());
+    }
+    info!(
+        "Found {} non-empty chunks to ingest from '{source_url}'.",
+        chunks.len()
+    );
+
+    // --- Ingest Chunks ---
+    let documents: Vec<NewDocument> = chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let source_url = format!("{source_url}#chunk_{i}");
+            NewDocument {
+                id: Uuid::new_v5(&Uuid::NAMESPACE_URL, source_url.as_bytes()).to_string(),
+                source_url,
+                title: chunk.title,
+                content: chunk.content,
+            }
+        })
+        .collect();
+    let content_of: HashMap<String, String> = documents
+        .iter()
+        .map(|document| (document.id.clone(), document.content.clone()))
+        .collect();
+    let mut conn = db.connect().map_err(MarkdownIngestError::from)?;
+    let ingested_ids = {
+        let _write = writes.lock().await;
+        let ingested_ids = bulk_insert_documents(&mut conn, owner_id, documents).await?;
+
+        // --- Front Matter ---
+        let properties = front_matter_properties(&front_matter);
+        if !properties.is_empty() {
+            for document_id in &ingested_ids {
+                store_chunk_metadata(&conn, document_id, owner_id, &properties).await?;
+            }
+        }
+        ingested_ids
+    };
+
+    // --- Embedding Generation ---
+    if let Some(config) = &source.embedding_config {
+        if !ingested_ids.is_empty() {
+            println!(
+                "Generating embeddings for {} new chunks",
+                ingested_ids.len()
+            );
+            info!(
+                "Generating embeddings for {} new chunks using model '{}'...",
+                ingested_ids.len(),
+                config.model
+            );
+            let texts_to_embed: Vec<&str> = ingested_ids
+                .iter()
+                .map(|id| content_of[id].as_str())
+                .collect();
+
+            let embeddings = generate_embeddings_batch(
+                &config.api_url,
+                &config.model,
+                &texts_to_embed,
+                config.api_key.as_deref(),
+            )
+            .await
+            .map_err(MarkdownIngestError::Embedding)?;
+
+            let rows: Vec<Vec<Value>> = ingested_ids
+                .iter()
+                .zip(embeddings)
+                .map(|(doc_id, vector)| {
+                    let vector_bytes: &[u8] = unsafe {
+                        std::slice::from_raw_parts(vector.as_ptr() as *const u8, vector.len() * 4)
+                    };
+                    vec![
+                        Value::Text(doc_id.clone()),
+                        Value::Text(config.model.to_string()),
+                        Value::Blob(vector_bytes.to_vec()),
+                    ]
+                })
+                .collect();
+            let _write = writes.lock().await;
+            let embedded_count = bulk_insert_rows(
+                &conn,
+                "INSERT INTO document_embeddings (document_id, model_name, embedding)",
+                "",
+                rows,
+            )
+            .await?;
+            info!("Successfully generated and stored embeddings for {embedded_count} chunks.");
+        }
+    }
+
+    Ok(ingested_ids)
+}
+
 pub struct MarkdownIngestor;
 
 #[async_trait]
 impl Ingestor for MarkdownIngestor {
-    /// Ingests a Markdown file.
+    /// Ingests a Markdown file, or every file a glob pattern or directory selects.
     ///
     /// The `source` argument is expected to be a JSON string matching the `MarkdownSource` struct.
+    /// The chunks of each file are stored under the file's path as matched, e.g.
+    /// `docs/guide/install.md#chunk_0` for `docs/**/*.md`. Files are processed in
+    /// parallel; those that fail are listed under `failed` in the result's metadata,
+    /// along with the `ingested` files and their chunk counts, and do not stop the rest.
     async fn ingest(
         &self,
         source: &str,
@@ -82,21 +400,15 @@ impl Ingestor for MarkdownIngestor {
 
         let file_path = &source_payload.file_path;
         let db_path = &source_payload.db_path;
+        let single_file = !is_glob(file_path) && !Path::new(file_path).is_dir();
 
-        info!("Ingesting markdown file '{file_path}' into database '{db_path}'");
-        let content = std::fs::read_to_string(file_path).map_err(MarkdownIngestError::from)?;
-        let chunks: Vec<String> = content
-            .split(&source_payload.separator)
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
-
-        if chunks.is_empty() {
-            info!("No non-empty chunks found in '{file_path}'.");
-            return Ok(Default::default());
-        }
-        info!("Found {} non-empty chunks to ingest.", chunks.len());
-
+        info!("Ingesting markdown '{file_path}' into database '{db_path}'");
+        let files = if single_file {
+            std::fs::metadata(file_path).map_err(MarkdownIngestError::from)?;
+            Vec::new()
+        } else {
+            markdown_files(file_path)?
+        };
         let provider = SqliteProvider::new(db_path)
             .await
             .map_err(MarkdownIngestError::from)?;
@@ -104,83 +416,67 @@ impl Ingestor for MarkdownIngestor {
             .initialize_schema()
             .await
             .map_err(MarkdownIngestError::from)?;
-        let mut conn = provider.db.connect()?;
-
-        // --- Ingest Chunks ---
-        let tx = conn.transaction().await?;
-        let mut ingested_ids = Vec::new();
-
-        for (i, chunk) in chunks.iter().enumerate() {
-            let document_id = Uuid::new_v4().to_string();
-            let source_url = format!("{file_path}#chunk_{i}");
-            let title: String = chunk.chars().take(80).collect();
-
-            tx.execute(
-                "INSERT INTO documents (id, owner_id, source_url, title, content)
-                 VALUES (?, ?, ?, ?, ?)
-                 ON CONFLICT(source_url) DO UPDATE SET
-                 title = excluded.title,
-                 content = excluded.content",
-                params![
-                    document_id.clone(),
-                    owner_id,
-                    source_url,
-                    title,
-                    chunk.clone()
-                ],
+        let db = &provider.db;
+        let writes = &Mutex::new(());
+        let source_payload = &source_payload;
+
+        if single_file {
+            let document_ids = ingest_file(
+                db,
+                writes,
+                Path::new(file_path),
+                file_path,
+                source_payload,
+                owner_id,
             )
             .await?;
-            ingested_ids.push(document_id);
+            return Ok(IngestionResult {
+                documents_added: document_ids.len(),
+                source: file_path.to_string(),
+                document_ids,
+                metadata: None,
+            });
         }
-        tx.commit().await?;
-
-        let documents_added = ingested_ids.len();
-
-        // --- Embedding Generation ---
-        if let Some(config) = source_payload.embedding_config {
-            if !ingested_ids.is_empty() {
-                println!(
-                    "Generating embeddings for {} new chunks",
-                    ingested_ids.len()
-                );
-                info!(
-                    "Generating embeddings for {} new chunks using model '{}'...",
-                    ingested_ids.len(),
-                    config.model
-                );
-                let texts_to_embed: Vec<&str> = chunks.iter().map(AsRef::as_ref).collect();
-
-                let embeddings = generate_embeddings_batch(
-                    &config.api_url,
-                    &config.model,
-                    &texts_to_embed,
-                    config.api_key.as_deref(),
-                )
-                .await
-                .map_err(MarkdownIngestError::Embedding)?;
-
-                let mut embedded_count = 0;
-                for (doc_id, vector) in ingested_ids.iter().zip(embeddings) {
-                    let vector_bytes: &[u8] = unsafe {
-                        std::slice::from_raw_parts(vector.as_ptr() as *const u8, vector.len() * 4)
-                    };
 
-                    conn.execute(
-                        "INSERT INTO document_embeddings (document_id, model_name, embedding) VALUES (?, ?, ?)",
-                        params![doc_id.clone(), config.model.to_string(), vector_bytes],
-                    )
-                    .await?;
-                    embedded_count += 1;
+        info!("Found {} markdown files in '{file_path}'.", files.len());
+        let results: Vec<_> = stream::iter(&files)
+            .map(|path| async move {
+                let source_url = path.display().to_string();
+                let result =
+                    ingest_file(db, writes, path, &source_url, source_payload, owner_id).await;
+                (source_url, result)
+            })
+            .buffered(DEFAULT_INGEST_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut document_ids = Vec::new();
+        let mut ingested = Vec::new();
+        let mut failed = Vec::new();
+        for (path, result) in results {
+            match result {
+                Ok(ids) => {
+                    ingested.push(json!({ "path": path, "documents_added": ids.len() }));
+                    document_ids.extend(ids);
+                }
+                Err(e) => {
+                    warn!("Failed to ingest markdown file '{path}': {e}");
+                    failed.push(json!({ "path": path, "error": e.to_string() }));
                 }
-                info!("Successfully generated and stored embeddings for {embedded_count} chunks.");
             }
         }
+        info!(
+            "Ingested {} of {} markdown files from '{file_path}' ({} failed).",
+            ingested.len(),
+            files.len(),
+            failed.len()
+        );
 
         Ok(IngestionResult {
-            documents_added,
+            documents_added: document_ids.len(),
             source: file_path.to_string(),
-            document_ids: ingested_ids,
-            metadata: None,
+            document_ids,
+            metadata: Some(json!({ "ingested": ingested, "failed": failed }).to_string()),
         })
     }
 }
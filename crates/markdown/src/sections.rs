@@ -0,0 +1,94 @@
+//! # Heading Sections
+//!
+//! Splits Markdown into the sections under its ATX (`#`) headings, so each chunk is one
+//! topic of a document and knows where it sits in the document's outline. Lines inside
+//! fenced code blocks are never taken for headings.
+
+/// The text under one heading, up to the next heading of any level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// The headings from the top of the outline down to this section's own, empty for
+    /// text before the first heading.
+    pub heading_path: Vec<String>,
+    /// The section's heading line and text.
+    pub content: String,
+}
+
+impl Section {
+    /// The heading path joined for display, e.g. `Guide > Install > Linux`.
+    pub fn title(&self) -> String {
+        self.heading_path.join(" > ")
+    }
+}
+
+/// Splits `content` into its heading sections. Sections with no text besides their
+/// heading are dropped, though their heading stays in the path of the ones below it.
+pub fn split_sections(content: &str) -> Vec<Section> {
+    let mut sections = Vec::new();
+    // The open headings as (level, text), outermost first.
+    let mut outline: Vec<(usize, String)> = Vec::new();
+    let mut current = String::new();
+    let mut fence: Option<&str> = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+        } else if trimmed.starts_with("```") {
+            fence = Some("```");
+        } else if trimmed.starts_with("~~~") {
+            fence = Some("~~~");
+        } else if let Some((level, text)) = parse_heading(line) {
+            push_section(&mut sections, &outline, &current);
+            current.clear();
+            outline.retain(|(open_level, _)| *open_level < level);
+            outline.push((level, text));
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    push_section(&mut sections, &outline, &current);
+    sections
+}
+
+fn push_section(sections: &mut Vec<Section>, outline: &[(usize, String)], content: &str) {
+    let content = content.trim();
+    // A section of only its heading line has nothing to index.
+    let has_text = if outline.is_empty() {
+        !content.is_empty()
+    } else {
+        content.lines().skip(1).any(|line| !line.trim().is_empty())
+    };
+    if has_text {
+        sections.push(Section {
+            heading_path: outline.iter().map(|(_, text)| text.clone()).collect(),
+            content: content.to_string(),
+        });
+    }
+}
+
+/// Parses an ATX heading line into its level and text.
+fn parse_heading(line: &str) -> Option<(usize, String)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let line = &line[indent..];
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    // Drop the optional closing sequence of `#`s.
+    let text = rest.trim();
+    let text = match text.trim_end_matches('#') {
+        stripped if stripped.is_empty() || stripped.ends_with([' ', '\t']) => stripped.trim(),
+        _ => text,
+    };
+    (!text.is_empty()).then(|| text.to_string())
+}
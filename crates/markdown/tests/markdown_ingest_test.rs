@@ -0,0 +1,232 @@
+//! # Markdown Crate Tests
+//!
+//! This file contains tests for the `anyrag-markdown` crate: front matter parsing,
+//! heading-aware chunking, and the ingestion of docs files, one at a time or by glob,
+//! into the database.
+
+use anyhow::Result;
+use anyrag::{ingest::Ingestor, providers::db::sqlite::SqliteProvider};
+use anyrag_markdown::{
+    markdown_files, split_front_matter, split_sections, FrontMatter, MarkdownIngestor,
+    MarkdownSource,
+};
+use tempfile::tempdir;
+
+const GUIDE: &str = "---
+title: Getting Started
+date: 2024-05-01
+tags: [setup, linux]
+---
+Read this first.
+
+# Guide
+
+## Install
+
+```sh
+# not a heading
+make install
+```
+
+### Linux
+
+Use the package manager.
+
+---
+
+## Configure
+
+Edit the config file.
+";
+
+#[test]
+fn test_front_matter_is_split_off() {
+    let (front_matter, body) = split_front_matter(GUIDE);
+
+    assert_eq!(
+        front_matter,
+        Some(FrontMatter {
+            title: Some("Getting Started".to_string()),
+            date: Some("2024-05-01".to_string()),
+            tags: vec!["setup".to_string(), "linux".to_string()],
+        })
+    );
+    assert!(body.starts_with("Read this first."));
+}
+
+#[test]
+fn test_front_matter_accepts_comma_separated_tags() {
+    let (front_matter, _) = split_front_matter("---\ntags: api, sdk\n---\nBody");
+
+    assert_eq!(front_matter.unwrap().tags, vec!["api", "sdk"]);
+}
+
+#[test]
+fn test_leading_horizontal_rule_is_not_front_matter() {
+    let content = "---\nJust some text.\n---\nMore text.";
+
+    let (front_matter, body) = split_front_matter(content);
+
+    assert_eq!(front_matter, None);
+    assert_eq!(body, content);
+}
+
+#[test]
+fn test_sections_follow_the_heading_hierarchy() {
+    let (_, body) = split_front_matter(GUIDE);
+
+    let sections = split_sections(body);
+
+    let titles: Vec<String> = sections.iter().map(|section| section.title()).collect();
+    assert_eq!(
+        titles,
+        vec![
+            "",
+            "Guide > Install",
+            "Guide > Install > Linux",
+            "Guide > Configure"
+        ]
+    );
+    assert!(sections[1].content.contains("# not a heading"));
+    assert!(sections[2].content.ends_with("---"));
+}
+
+#[tokio::test]
+async fn test_ingest_stores_heading_paths_and_front_matter() -> Result<()> {
+    // --- Arrange ---
+    let temp_dir = tempdir()?;
+    let file_path = temp_dir.path().join("guide.md");
+    std::fs::write(&file_path, GUIDE)?;
+    let db_path = temp_dir.path().join("guide.db");
+    let source = MarkdownSource {
+        db_path: db_path.to_string_lossy().to_string(),
+        file_path: file_path.to_string_lossy().to_string(),
+        separator: None,
+        embedding_config: None,
+        chunking: None,
+    };
+
+    // --- Act ---
+    let result = MarkdownIngestor
+        .ingest(&serde_json::to_string(&source)?, None)
+        .await?;
+
+    // --- Assert ---
+    assert_eq!(result.documents_added, 4);
+    let provider = SqliteProvider::new(db_path.to_str().unwrap()).await?;
+    let conn = provider.db.connect()?;
+    let mut rows = conn
+        .query("SELECT title FROM documents ORDER BY source_url", ())
+        .await?;
+    let mut titles = Vec::new();
+    while let Some(row) = rows.next().await? {
+        titles.push(row.get::<String>(0)?);
+    }
+    assert_eq!(
+        titles,
+        vec![
+            "Getting Started",
+            "Guide > Install",
+            "Guide > Install > Linux",
+            "Guide > Configure"
+        ]
+    );
+
+    let mut rows = conn
+        .query(
+            "SELECT m.metadata_subtype, m.metadata_value FROM content_metadata m
+             JOIN documents d ON d.id = m.document_id
+             WHERE d.source_url LIKE '%#chunk_3' AND m.metadata_type = 'PROPERTY'
+             ORDER BY m.metadata_subtype, m.metadata_value",
+            (),
+        )
+        .await?;
+    let mut properties = Vec::new();
+    while let Some(row) = rows.next().await? {
+        properties.push((row.get::<String>(0)?, row.get::<String>(1)?));
+    }
+    assert_eq!(
+        properties,
+        vec![
+            ("date".to_string(), "2024-05-01".to_string()),
+            ("tag".to_string(), "linux".to_string()),
+            ("tag".to_string(), "setup".to_string()),
+            ("title".to_string(), "Getting Started".to_string()),
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ingest_of_a_glob_reports_each_file() -> Result<()> {
+    // --- Arrange ---
+    let temp_dir = tempdir()?;
+    let docs = temp_dir.path().join("docs");
+    std::fs::create_dir_all(docs.join("guide"))?;
+    std::fs::write(docs.join("index.md"), "# Home\n\nWelcome.")?;
+    std::fs::write(docs.join("guide/install.md"), "# Install\n\nRun it.")?;
+    std::fs::write(docs.join("guide/notes.txt"), "Not markdown.")?;
+    std::fs::write(docs.join("guide/broken.md"), [0xff, 0xfe])?;
+    let db_path = temp_dir.path().join("docs.db");
+    let source = MarkdownSource {
+        db_path: db_path.to_string_lossy().to_string(),
+        file_path: format!("{}/**/*.md", docs.display()),
+        separator: None,
+        embedding_config: None,
+        chunking: None,
+    };
+
+    // --- Act ---
+    let result = MarkdownIngestor
+        .ingest(&serde_json::to_string(&source)?, None)
+        .await?;
+
+    // --- Assert ---
+    assert_eq!(result.documents_added, 2);
+    let report: serde_json::Value = serde_json::from_str(&result.metadata.unwrap())?;
+    assert_eq!(report["ingested"].as_array().unwrap().len(), 2);
+    assert_eq!(report["ingested"][0]["documents_added"], 1);
+    assert_eq!(report["failed"].as_array().unwrap().len(), 1);
+    assert!(report["failed"][0]["path"]
+        .as_str()
+        .unwrap()
+        .ends_with("guide/broken.md"));
+
+    let provider = SqliteProvider::new(db_path.to_str().unwrap()).await?;
+    let conn = provider.db.connect()?;
+    let source_url: String = conn
+        .query(
+            "SELECT source_url FROM documents WHERE title = 'Install'",
+            (),
+        )
+        .await?
+        .next()
+        .await?
+        .expect("Expected the install chunk")
+        .get(0)?;
+    assert_eq!(
+        source_url,
+        format!("{}#chunk_0", docs.join("guide/install.md").display())
+    );
+    Ok(())
+}
+
+#[test]
+fn test_directory_selects_markdown_files_at_any_depth() -> Result<()> {
+    let temp_dir = tempdir()?;
+    std::fs::create_dir_all(temp_dir.path().join("a/b"))?;
+    std::fs::write(temp_dir.path().join("top.md"), "Top")?;
+    std::fs::write(temp_dir.path().join("a/b/deep.markdown"), "Deep")?;
+    std::fs::write(temp_dir.path().join("a/skip.txt"), "Skip")?;
+
+    let files = markdown_files(temp_dir.path().to_str().unwrap())?;
+
+    assert_eq!(
+        files,
+        vec![
+            temp_dir.path().join("a/b/deep.markdown"),
+            temp_dir.path().join("top.md")
+        ]
+    );
+    Ok(())
+}
@@ -3,20 +3,35 @@
 //! This crate provides the logic for ingesting data from Notion databases as a self-contained
 //! plugin for the `anyrag` ecosystem. It implements the `Ingestor` trait from the
 //! core `anyrag` library.
+//!
+//! A database is ingested in one of two modes. The default `table` mode copies its
+//! pages into a table of a dedicated SQLite file for NL-to-SQL queries. The
+//! `knowledge` mode stores each page as a YAML document in `documents`, with its
+//! metadata extracted by the LLM, so that Notion content is found by hybrid search.
 
 use anyhow::anyhow;
-use anyrag::ingest::traits::{IngestError, IngestionResult, Ingestor};
+use anyrag::{
+    ingest::{
+        bulk_insert_rows, content_hash, embed_ingested_documents, find_duplicate_document,
+        knowledge::extract_and_store_metadata,
+        record_revision,
+        traits::{IngestError, IngestionPrompts, IngestionResult, Ingestor},
+    },
+    providers::ai::AiProvider,
+    types::EmbeddingConfig,
+};
 use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
 
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
 use serde::Deserialize;
 use serde_json::json;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use thiserror::Error;
 use tracing::{info, warn};
-use turso::{params, Connection, Value};
+use turso::{params, Connection, Database, Value};
+use uuid::Uuid;
 
 // --- Error Definitions ---
 
@@ -32,6 +47,8 @@ pub enum NotionError {
     MissingEnvVar(String),
     #[error("No data sources found for the given database")]
     NoDataSource,
+    #[error("The knowledge mode needs a database and an AI provider; build the ingestor with `with_knowledge`")]
+    KnowledgeNotConfigured,
 }
 
 impl From<reqwest::Error> for NotionError {
@@ -53,6 +70,9 @@ impl From<NotionError> for IngestError {
             NotionError::NoDataSource => {
                 IngestError::SourceNotFound("No data sources found for database".into())
             }
+            NotionError::KnowledgeNotConfigured => {
+                IngestError::Internal(anyhow!(NotionError::KnowledgeNotConfigured))
+            }
         }
     }
 }
@@ -82,17 +102,55 @@ enum PropertyValue {
     Date {
         date: Option<DateValue>,
     },
+    Number {
+        number: Option<serde_json::Number>,
+    },
+    Select {
+        select: Option<SelectOption>,
+    },
+    MultiSelect {
+        multi_select: Vec<SelectOption>,
+    },
+    Status {
+        status: Option<SelectOption>,
+    },
+    Checkbox {
+        checkbox: bool,
+    },
+    Url {
+        url: Option<String>,
+    },
+    Email {
+        email: Option<String>,
+    },
+    PhoneNumber {
+        phone_number: Option<String>,
+    },
     #[serde(other)]
     Other,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+struct SelectOption {
+    name: String,
+}
+
 #[derive(Deserialize, Debug)]
 struct Page {
-    #[allow(unused)]
     id: String,
+    /// The link of the page in Notion.
+    #[serde(default)]
+    url: Option<String>,
     properties: HashMap<String, PropertyValue>,
 }
 
+#[derive(Deserialize, Debug)]
+struct BlockChildrenResponse {
+    results: Vec<serde_json::Value>,
+    next_cursor: Option<String>,
+    has_more: bool,
+}
+
 #[derive(Deserialize, Debug)]
 struct QueryResponse {
     results: Vec<Page>,
@@ -114,43 +172,105 @@ struct DatabaseResponse {
 
 // --- Ingestor Implementation ---
 
+/// How the pages of a Notion database are stored.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum NotionMode {
+    /// One row per page, in a table of a dedicated SQLite file.
+    #[default]
+    Table,
+    /// One YAML document per page, in `documents`, with extracted metadata.
+    Knowledge,
+}
+
 /// Defines the structure of the JSON string passed to the `ingest` method.
 #[derive(Deserialize)]
 struct NotionSource {
     database_id: String,
+    #[serde(default)]
+    mode: NotionMode,
+    /// In `knowledge` mode, also stores the text of each page's blocks.
+    #[serde(default)]
+    include_content: bool,
+}
+
+/// What the `knowledge` mode stores pages with.
+struct KnowledgeTarget<'a> {
+    db: &'a Database,
+    ai_provider: &'a dyn AiProvider,
+    prompts: IngestionPrompts<'a>,
 }
 
 /// The `Ingestor` implementation for Notion.
-pub struct NotionIngestor;
+pub struct NotionIngestor<'a> {
+    knowledge: Option<KnowledgeTarget<'a>>,
+    embedding: Option<&'a EmbeddingConfig>,
+}
 
-impl NotionIngestor {
-    /// Creates a new `NotionIngestor`.
+impl<'a> NotionIngestor<'a> {
+    /// Creates a new `NotionIngestor`, which can ingest in `table` mode.
     pub fn new() -> Self {
-        Self {}
+        Self {
+            knowledge: None,
+            embedding: None,
+        }
+    }
+
+    /// Enables the `knowledge` mode, which stores pages in the `documents` of `db` and
+    /// extracts their metadata with `ai_provider`.
+    pub fn with_knowledge(
+        mut self,
+        db: &'a Database,
+        ai_provider: &'a dyn AiProvider,
+        prompts: IngestionPrompts<'a>,
+    ) -> Self {
+        self.knowledge = Some(KnowledgeTarget {
+            db,
+            ai_provider,
+            prompts,
+        });
+        self
+    }
+
+    /// Embeds the documents of each `knowledge` mode ingestion with the given model once
+    /// they are stored.
+    pub fn with_embedding(mut self, config: &'a EmbeddingConfig) -> Self {
+        self.embedding = Some(config);
+        self
     }
 }
 
-impl Default for NotionIngestor {
+impl Default for NotionIngestor<'_> {
     fn default() -> Self {
         Self::new()
     }
 }
 
 #[async_trait]
-impl Ingestor for NotionIngestor {
+impl Ingestor for NotionIngestor<'_> {
     /// Ingests a Notion Database.
     ///
     /// The `source` argument is expected to be a JSON string with a `database_id` key,
     /// for example:
-    /// `{"database_id": "276fdc98-..."}`.
+    /// `{"database_id": "276fdc98-..."}`. With `"mode": "knowledge"`, each page is
+    /// stored as a document instead of a table row, and `"include_content": true` adds
+    /// the text of the page to its properties.
     async fn ingest(
         &self,
         source: &str,
-        _owner_id: Option<&str>,
+        owner_id: Option<&str>,
     ) -> Result<IngestionResult, IngestError> {
         let notion_source: NotionSource =
             serde_json::from_str(source).map_err(|e| NotionError::InvalidSource(e.to_string()))?;
         let db_id = notion_source.database_id;
+        let knowledge = match notion_source.mode {
+            NotionMode::Table => None,
+            NotionMode::Knowledge => Some(
+                self.knowledge
+                    .as_ref()
+                    .ok_or(NotionError::KnowledgeNotConfigured)?,
+            ),
+        };
 
         info!("Starting ingestion for Notion database: {}", db_id);
 
@@ -187,6 +307,42 @@ impl Ingestor for NotionIngestor {
             });
         }
 
+        if let Some(target) = knowledge {
+            let contents = match notion_source.include_content {
+                true => {
+                    let mut contents = HashMap::new();
+                    for page in &pages {
+                        let content = fetch_page_content(&client, &headers, &page.id).await?;
+                        contents.insert(page.id.clone(), content);
+                    }
+                    contents
+                }
+                false => HashMap::new(),
+            };
+            let document_ids =
+                store_page_documents(target, &db_id, &pages, &contents, owner_id).await?;
+            if let Some(config) = self.embedding {
+                embed_ingested_documents(target.db, config, &document_ids).await;
+            }
+            info!(
+                "Ingested {} new or updated Notion pages as documents.",
+                document_ids.len()
+            );
+            return Ok(IngestionResult {
+                documents_added: document_ids.len(),
+                source: db_id,
+                document_ids,
+                metadata: Some(
+                    json!({
+                        "mode": "knowledge",
+                        "data_source_id": data_source_id,
+                        "pages": pages_count,
+                    })
+                    .to_string(),
+                ),
+            });
+        }
+
         // 3. Define a unique table name.
         let table_name = format!(
             "notion_{:x}",
@@ -328,6 +484,196 @@ async fn query_all_pages(
     Ok(all_pages)
 }
 
+/// Fetches the text of the top-level blocks of a page, one line per block.
+async fn fetch_page_content(
+    client: &reqwest::Client,
+    headers: &HeaderMap,
+    page_id: &str,
+) -> Result<String, NotionError> {
+    let base_url = get_base_url();
+    let url = format!("{base_url}/v1/blocks/{page_id}/children");
+    let mut lines = Vec::new();
+    let mut next_cursor: Option<String> = None;
+
+    loop {
+        let mut request = client
+            .get(&url)
+            .headers(headers.clone())
+            .query(&[("page_size", "100")]);
+        if let Some(cursor) = &next_cursor {
+            request = request.query(&[("start_cursor", cursor)]);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let err_text = response.text().await.unwrap_or_default();
+            return Err(NotionError::ApiError(format!(
+                "Failed to fetch page content: {err_text}"
+            )));
+        }
+
+        let children = response.json::<BlockChildrenResponse>().await?;
+        lines.extend(children.results.iter().filter_map(block_text));
+        if children.has_more {
+            next_cursor = children.next_cursor;
+        } else {
+            break;
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+/// The plain text of a block, for the block types that hold rich text.
+fn block_text(block: &serde_json::Value) -> Option<String> {
+    let block_type = block["type"].as_str()?;
+    let text: String = block[block_type]["rich_text"]
+        .as_array()?
+        .iter()
+        .filter_map(|part| part["plain_text"].as_str())
+        .collect();
+    (!text.trim().is_empty()).then_some(text)
+}
+
+/// Converts a page to the YAML document stored for it: its title, its link, its
+/// properties by name and, when fetched, its content. Properties are sorted by name so
+/// that an unchanged page converts to the same document.
+fn page_to_yaml(page: &Page, content: Option<&str>) -> Result<(String, String), NotionError> {
+    let title = page
+        .properties
+        .values()
+        .find(|property| matches!(property, PropertyValue::Title { .. }))
+        .map(extract_text_from_property)
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| format!("Notion page {}", page.id));
+
+    let properties: BTreeMap<&str, serde_yaml::Value> = page
+        .properties
+        .iter()
+        .filter_map(|(name, property)| Some((name.as_str(), property_to_yaml(property)?)))
+        .collect();
+    let mut document = serde_yaml::Mapping::new();
+    document.insert("title".into(), title.clone().into());
+    if let Some(url) = &page.url {
+        document.insert("url".into(), url.clone().into());
+    }
+    document.insert(
+        "properties".into(),
+        serde_yaml::to_value(properties).map_err(|e| NotionError::InvalidSource(e.to_string()))?,
+    );
+    if let Some(content) = content.filter(|content| !content.is_empty()) {
+        document.insert("content".into(), content.into());
+    }
+    let yaml =
+        serde_yaml::to_string(&document).map_err(|e| NotionError::InvalidSource(e.to_string()))?;
+    Ok((title, yaml))
+}
+
+/// The YAML value of a property, or `None` when it is empty or of a type not stored.
+fn property_to_yaml(property: &PropertyValue) -> Option<serde_yaml::Value> {
+    let value: serde_yaml::Value = match property {
+        PropertyValue::Title { .. } | PropertyValue::RichText { .. } => {
+            extract_text_from_property(property).into()
+        }
+        PropertyValue::Date { date } => {
+            let date = date.as_ref()?;
+            match &date.end {
+                Some(end) => format!("{} → {end}", date.start).into(),
+                None => date.start.clone().into(),
+            }
+        }
+        PropertyValue::Number { number } => serde_yaml::to_value(number.as_ref()?).ok()?,
+        PropertyValue::Select { select: option } | PropertyValue::Status { status: option } => {
+            option.as_ref()?.name.clone().into()
+        }
+        PropertyValue::MultiSelect { multi_select } => multi_select
+            .iter()
+            .map(|option| serde_yaml::Value::from(option.name.clone()))
+            .collect::<Vec<_>>()
+            .into(),
+        PropertyValue::Checkbox { checkbox } => (*checkbox).into(),
+        PropertyValue::Url { url: text }
+        | PropertyValue::Email { email: text }
+        | PropertyValue::PhoneNumber { phone_number: text } => text.clone()?.into(),
+        PropertyValue::Other => return None,
+    };
+    match &value {
+        serde_yaml::Value::String(text) if text.is_empty() => None,
+        serde_yaml::Value::Sequence(items) if items.is_empty() => None,
+        _ => Some(value),
+    }
+}
+
+/// Stores each page as a document, skipping the pages whose document is unchanged, and
+/// extracts the metadata of the new and updated ones. Returns their document ids.
+async fn store_page_documents(
+    target: &KnowledgeTarget<'_>,
+    db_id: &str,
+    pages: &[Page],
+    contents: &HashMap<String, String>,
+    owner_id: Option<&str>,
+) -> Result<Vec<String>, IngestError> {
+    let mut conn = target.db.connect()?;
+    let tx = conn.transaction().await?;
+    let mut stored = Vec::new();
+
+    for page in pages {
+        let (title, yaml) = page_to_yaml(page, contents.get(&page.id).map(String::as_str))?;
+        let hash = content_hash(&yaml);
+        if find_duplicate_document(&tx, owner_id, &hash)
+            .await?
+            .is_some()
+        {
+            info!("Skipping unchanged Notion page: {}", page.id);
+            continue;
+        }
+        let source_url = page
+            .url
+            .clone()
+            .unwrap_or_else(|| format!("notion://{db_id}/{}", page.id));
+        let document_id = Uuid::new_v5(&Uuid::NAMESPACE_URL, source_url.as_bytes()).to_string();
+
+        // The `source_url` is the page's link, so an updated page replaces its previous
+        // version.
+        record_revision(&tx, &source_url, &yaml).await?;
+        tx.execute(
+            "INSERT INTO documents (id, owner_id, source_url, title, content, content_hash)
+             VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT(source_url) DO UPDATE SET
+             title = excluded.title,
+             content = excluded.content,
+             content_hash = excluded.content_hash",
+            params![
+                document_id.clone(),
+                owner_id,
+                source_url,
+                title,
+                yaml.clone(),
+                hash
+            ],
+        )
+        .await?;
+        stored.push((document_id, yaml));
+    }
+    tx.commit().await?;
+
+    // The LLM is only called once the documents are committed.
+    for (document_id, yaml) in &stored {
+        extract_and_store_metadata(
+            &conn,
+            target.ai_provider,
+            document_id,
+            owner_id,
+            yaml,
+            target.prompts.metadata_extraction_system_prompt,
+        )
+        .await
+        .map_err(|e| IngestError::Internal(anyhow!("Metadata extraction failed: {e}")))?;
+    }
+    Ok(stored
+        .into_iter()
+        .map(|(document_id, _)| document_id)
+        .collect())
+}
+
 fn extract_text_from_property(property: &PropertyValue) -> String {
     match property {
         PropertyValue::Title { title } => title
@@ -394,15 +740,9 @@ async fn process_and_store_pages(
     info!("Created table `{}`", table_name);
 
     // Prepare for insertion
-    let placeholders = columns.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
-    let insert_sql = format!(
-        "INSERT INTO `{}` ({}) VALUES ({})",
-        table_name,
-        columns.join(", "),
-        placeholders
-    );
+    let insert_sql = format!("INSERT INTO `{}` ({})", table_name, columns.join(", "));
 
-    let tx = conn.transaction().await?;
+    let mut rows: Vec<Vec<Value>> = Vec::new();
     for page in pages {
         let mut base_row_data: HashMap<String, Value> = HashMap::new();
         let mut current_date_prop: Option<PropertyValue> = None;
@@ -445,8 +785,7 @@ async fn process_and_store_pages(
                             row_params.push(base_row_data.get(col).cloned().unwrap_or(Value::Null));
                         }
                     }
-                    tx.execute(&insert_sql, params::Params::Positional(row_params))
-                        .await?;
+                    rows.push(row_params);
                     current_dt += Duration::hours(1);
                 }
             }
@@ -456,10 +795,12 @@ async fn process_and_store_pages(
             for col in &columns {
                 row_params.push(base_row_data.get(col).cloned().unwrap_or(Value::Null));
             }
-            tx.execute(&insert_sql, params::Params::Positional(row_params))
-                .await?;
+            rows.push(row_params);
         }
     }
+
+    let tx = conn.transaction().await?;
+    bulk_insert_rows(&tx, &insert_sql, "", rows).await?;
     tx.commit().await?;
 
     Ok(())
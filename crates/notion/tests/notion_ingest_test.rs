@@ -1,8 +1,14 @@
 //! # Notion Ingestor Integration Tests
 
 use anyhow::Result;
-use anyrag::ingest::Ingestor;
+use anyrag::{
+    ingest::{IngestionPrompts, Ingestor},
+    prompts::knowledge::{
+        KNOWLEDGE_RESTRUCTURING_SYSTEM_PROMPT, METADATA_EXTRACTION_SYSTEM_PROMPT,
+    },
+};
 use anyrag_notion::NotionIngestor;
+use anyrag_test_utils::{MockAiProvider, TestSetup};
 use httpmock::{Method, MockServer};
 use serial_test::serial;
 
@@ -314,3 +320,168 @@ async fn test_notion_ingestion_hourly_expansion() -> Result<()> {
 
     Ok(())
 }
+
+/// Mocks a Notion database of two pages, the first with a paragraph of content.
+fn mock_knowledge_database(mock_server: &MockServer, db_id: &str, data_source_id: &str) {
+    mock_server.mock(|when, then| {
+        when.method(Method::GET)
+            .path(format!("/v1/databases/{db_id}"));
+        then.status(200)
+            .json_body(json!({ "id": db_id, "data_sources": [{ "id": data_source_id }] }));
+    });
+    mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(format!("/v1/data_sources/{data_source_id}/query"));
+        then.status(200).json_body(json!({
+            "results": [
+                {
+                    "id": "page-refunds",
+                    "url": "https://www.notion.so/Refund-policy-page-refunds",
+                    "properties": {
+                        "Name": { "type": "title", "title": [{ "plain_text": "Refund policy" }] },
+                        "Status": { "type": "status", "status": { "name": "Published" } },
+                        "Tags": { "type": "multi_select", "multi_select": [{ "name": "billing" }, { "name": "support" }] },
+                        "Days": { "type": "number", "number": 14 }
+                    }
+                },
+                {
+                    "id": "page-shipping",
+                    "properties": {
+                        "Name": { "type": "title", "title": [{ "plain_text": "Shipping" }] },
+                        "Status": { "type": "status", "status": null }
+                    }
+                }
+            ],
+            "has_more": false,
+            "next_cursor": null
+        }));
+    });
+    mock_server.mock(|when, then| {
+        when.method(Method::GET)
+            .path("/v1/blocks/page-refunds/children");
+        then.status(200).json_body(json!({
+            "results": [
+                { "type": "paragraph", "paragraph": { "rich_text": [{ "plain_text": "Refunds are paid within 14 days." }] } },
+                { "type": "divider", "divider": {} }
+            ],
+            "has_more": false,
+            "next_cursor": null
+        }));
+    });
+    mock_server.mock(|when, then| {
+        when.method(Method::GET)
+            .path("/v1/blocks/page-shipping/children");
+        then.status(200)
+            .json_body(json!({ "results": [], "has_more": false, "next_cursor": null }));
+    });
+}
+
+fn knowledge_prompts() -> IngestionPrompts<'static> {
+    IngestionPrompts {
+        restructuring_system_prompt: KNOWLEDGE_RESTRUCTURING_SYSTEM_PROMPT,
+        metadata_extraction_system_prompt: METADATA_EXTRACTION_SYSTEM_PROMPT,
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_notion_knowledge_mode_stores_pages_as_documents() -> Result<()> {
+    // --- 1. Arrange ---
+    let mock_server = MockServer::start();
+    env::set_var(
+        "NOTION_API_BASE_URL_OVERRIDE_FOR_TESTING",
+        mock_server.base_url(),
+    );
+    env::set_var("NOTION_TOKEN", "test_token");
+    env::set_var("NOTION_VERSION", "2022-06-28");
+    mock_knowledge_database(&mock_server, "mock-db-knowledge", "mock-ds-knowledge");
+
+    let setup = TestSetup::new().await?;
+    let ai_provider = MockAiProvider::new();
+    ai_provider.add_response(
+        "metadata",
+        r#"[{"type": "KEYPHRASE", "subtype": "CONCEPT", "value": "refund policy"}]"#,
+    );
+    ai_provider.add_response("metadata", "[]");
+    let ingestor =
+        NotionIngestor::new().with_knowledge(&setup.db, &ai_provider, knowledge_prompts());
+    let source = json!({
+        "database_id": "mock-db-knowledge",
+        "mode": "knowledge",
+        "include_content": true
+    })
+    .to_string();
+
+    // --- 2. Act ---
+    let first = ingestor.ingest(&source, Some("notion-user")).await?;
+    let second = ingestor.ingest(&source, Some("notion-user")).await?;
+
+    // --- 3. Assert ---
+    assert_eq!(first.documents_added, 2);
+    // Unchanged pages are skipped, without calling the LLM again.
+    assert_eq!(second.documents_added, 0);
+    assert_eq!(ai_provider.get_calls().len(), 2);
+
+    let conn = setup.db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT id, owner_id, source_url, title, content FROM documents ORDER BY title",
+            (),
+        )
+        .await?;
+    let refunds = rows.next().await?.expect("Expected the refund page");
+    let refunds_id: String = refunds.get(0)?;
+    assert_eq!(refunds.get::<String>(1)?, "notion-user");
+    assert_eq!(
+        refunds.get::<String>(2)?,
+        "https://www.notion.so/Refund-policy-page-refunds"
+    );
+    assert_eq!(refunds.get::<String>(3)?, "Refund policy");
+    let content: String = refunds.get(4)?;
+    assert!(content.contains("Status: Published"), "{content}");
+    assert!(content.contains("- billing"), "{content}");
+    assert!(content.contains("Days: 14"), "{content}");
+    assert!(
+        content.contains("content: Refunds are paid within 14 days."),
+        "{content}"
+    );
+    let shipping = rows.next().await?.expect("Expected the shipping page");
+    assert_eq!(
+        shipping.get::<String>(2)?,
+        "notion://mock-db-knowledge/page-shipping"
+    );
+    assert!(!shipping.get::<String>(4)?.contains("Status"));
+    drop(rows);
+
+    let mut rows = conn
+        .query(
+            "SELECT metadata_value FROM content_metadata WHERE document_id = ?",
+            params![refunds_id],
+        )
+        .await?;
+    let metadata = rows.next().await?.expect("Expected extracted metadata");
+    assert_eq!(metadata.get::<String>(0)?, "refund policy");
+
+    env::remove_var("NOTION_API_BASE_URL_OVERRIDE_FOR_TESTING");
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_notion_knowledge_mode_needs_a_knowledge_target() -> Result<()> {
+    let mock_server = MockServer::start();
+    env::set_var(
+        "NOTION_API_BASE_URL_OVERRIDE_FOR_TESTING",
+        mock_server.base_url(),
+    );
+    env::set_var("NOTION_TOKEN", "test_token");
+    env::set_var("NOTION_VERSION", "2022-06-28");
+
+    let source = json!({ "database_id": "mock-db", "mode": "knowledge" }).to_string();
+    let result = NotionIngestor::new().ingest(&source, None).await;
+
+    let error = result.expect_err("The knowledge mode should need `with_knowledge`");
+    assert!(error.to_string().contains("with_knowledge"), "{error}");
+    env::remove_var("NOTION_API_BASE_URL_OVERRIDE_FOR_TESTING");
+    Ok(())
+}
@@ -0,0 +1,289 @@
+//! # `anyrag-objectstore`: S3 and GCS Bucket Ingestion Plugin
+//!
+//! This crate provides the logic for ingesting the objects of a bucket as a
+//! self-contained plugin for the `anyrag` ecosystem. It implements the `Ingestor`
+//! trait from the core `anyrag` library.
+//!
+//! Every object under a prefix is routed by its extension to the existing ingestor for
+//! that format: PDFs to `anyrag-pdf`, CSV files to `anyrag-sheets`, and text, Markdown,
+//! and HTML to `anyrag-text`. The ETag of each ingested object is kept in a manifest so
+//! re-runs only process new and modified objects, and an object that fails is reported
+//! without aborting the rest of the batch.
+
+use anyhow::anyhow;
+use anyrag::{
+    ingest::{
+        source_url_prefix_pattern, ChunkingStrategy, IngestError, IngestionPrompts,
+        IngestionResult, Ingestor,
+    },
+    providers::ai::AiProvider,
+};
+use anyrag_pdf::PdfIngestor;
+use anyrag_sheets::SheetsIngestor;
+use anyrag_text::{TextIngestor, DEFAULT_CHUNK_SIZE};
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine as _};
+use client::{ObjectEntry, ObjectStoreClient};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::Path;
+use thiserror::Error;
+use tracing::{info, warn};
+use turso::{params, Database};
+
+pub mod client;
+pub mod manifest;
+pub mod signing;
+
+/// Custom error types for the object-store ingestion process.
+#[derive(Error, Debug)]
+pub enum ObjectStoreIngestError {
+    #[error("Database error: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Failed to call the object store: {0}")]
+    Fetch(#[from] reqwest::Error),
+    #[error("Object store returned an error: {0}")]
+    Api(String),
+    #[error("Invalid object store configuration: {0}")]
+    Config(String),
+    #[error("Source deserialization failed: {0}")]
+    SourceDeserialization(#[from] serde_json::Error),
+    #[error("Object '{0}' is not valid UTF-8 text")]
+    Encoding(String),
+    #[error("{0}")]
+    Ingest(#[from] IngestError),
+}
+
+/// A helper to convert the specific `ObjectStoreIngestError` into the generic `anyrag::ingest::IngestError`.
+impl From<ObjectStoreIngestError> for IngestError {
+    fn from(err: ObjectStoreIngestError) -> Self {
+        match err {
+            ObjectStoreIngestError::Database(e) => IngestError::Database(e),
+            ObjectStoreIngestError::Fetch(e) => IngestError::Fetch(e.to_string()),
+            ObjectStoreIngestError::Api(e) => IngestError::Fetch(e),
+            ObjectStoreIngestError::Config(e) => IngestError::Internal(anyhow!(e)),
+            ObjectStoreIngestError::SourceDeserialization(e) => {
+                IngestError::Parse(format!("Invalid source JSON for object store ingest: {e}"))
+            }
+            ObjectStoreIngestError::Encoding(key) => {
+                IngestError::Parse(format!("Object '{key}' is not valid UTF-8 text"))
+            }
+            ObjectStoreIngestError::Ingest(e) => e,
+        }
+    }
+}
+
+/// Defines the structure of the JSON string passed to the `ingest` method.
+#[derive(Deserialize)]
+struct ObjectStoreSource {
+    bucket: String,
+    #[serde(default)]
+    prefix: String,
+    /// Re-ingests every object, even those whose ETag is unchanged.
+    #[serde(default)]
+    force: bool,
+}
+
+/// The formats objects are routed by, chosen from the object key's extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ObjectKind {
+    Pdf,
+    Csv,
+    Markdown,
+    Html,
+    Text,
+}
+
+impl ObjectKind {
+    fn from_key(key: &str) -> Option<Self> {
+        let extension = Path::new(key).extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "pdf" => Some(Self::Pdf),
+            "csv" => Some(Self::Csv),
+            "md" | "markdown" => Some(Self::Markdown),
+            "html" | "htm" => Some(Self::Html),
+            "txt" => Some(Self::Text),
+            _ => None,
+        }
+    }
+}
+
+/// The `Ingestor` implementation for S3 and GCS buckets.
+pub struct ObjectStoreIngestor<'a> {
+    db: &'a Database,
+    ai_provider: &'a dyn AiProvider,
+    prompts: IngestionPrompts<'a>,
+    client: ObjectStoreClient,
+}
+
+impl<'a> ObjectStoreIngestor<'a> {
+    /// Creates a new `ObjectStoreIngestor` reading from the store behind `client`.
+    ///
+    /// The AI provider and prompts are used for PDF and CSV objects, which go through
+    /// the LLM restructuring pipeline.
+    pub fn new(
+        db: &'a Database,
+        ai_provider: &'a dyn AiProvider,
+        prompts: IngestionPrompts<'a>,
+        client: ObjectStoreClient,
+    ) -> Self {
+        Self {
+            db,
+            ai_provider,
+            prompts,
+            client,
+        }
+    }
+
+    /// Downloads one object and hands it to the ingestor for its format.
+    async fn ingest_object(
+        &self,
+        bucket: &str,
+        object: &ObjectEntry,
+        kind: ObjectKind,
+        object_url: &str,
+        owner_id: Option<&str>,
+    ) -> Result<IngestionResult, ObjectStoreIngestError> {
+        let data = self.client.get_object(bucket, &object.key).await?;
+        let (text, chunking) = match kind {
+            ObjectKind::Pdf => {
+                let source = json!({
+                    "source_identifier": object_url,
+                    "pdf_data_base64": general_purpose::STANDARD.encode(&data),
+                });
+                return Ok(PdfIngestor::new(self.db, self.ai_provider, self.prompts)
+                    .ingest(&source.to_string(), owner_id)
+                    .await?);
+            }
+            ObjectKind::Csv => {
+                return Ok(SheetsIngestor::new(self.db, self.ai_provider, self.prompts)
+                    .ingest_csv(object_url, &decode_text(object, data)?, owner_id)
+                    .await?);
+            }
+            ObjectKind::Html => (
+                anyrag_html::html_to_clean_markdown(&decode_text(object, data)?, None),
+                Some(markdown_chunking()),
+            ),
+            ObjectKind::Markdown => (decode_text(object, data)?, Some(markdown_chunking())),
+            ObjectKind::Text => (decode_text(object, data)?, None),
+        };
+
+        // A modified object may produce fewer chunks than before, so the chunks of
+        // its previous version for this owner are removed first.
+        let conn = self.db.connect()?;
+        conn.execute(
+            "DELETE FROM documents WHERE owner_id IS ? AND source_url LIKE ? ESCAPE '\\'",
+            params![
+                owner_id,
+                source_url_prefix_pattern(&format!("{object_url}#chunk_"))
+            ],
+        )
+        .await?;
+        let source = json!({
+            "text": text,
+            "source": object_url,
+            "chunking": chunking,
+        });
+        Ok(TextIngestor::new(self.db)
+            .ingest(&source.to_string(), owner_id)
+            .await?)
+    }
+}
+
+#[async_trait]
+impl Ingestor for ObjectStoreIngestor<'_> {
+    /// Ingests every supported object under a bucket prefix.
+    ///
+    /// The `source` argument is expected to be a JSON string with a `bucket` key and an
+    /// optional `prefix` key, for example: `{"bucket": "docs", "prefix": "handbook/"}`.
+    /// Objects whose ETag matches the manifest are skipped unless `"force": true` is
+    /// set. Failed objects are listed under `failed` in the result's metadata, along
+    /// with the `ingested`, `unchanged`, and `unsupported` object keys.
+    async fn ingest(
+        &self,
+        source: &str,
+        owner_id: Option<&str>,
+    ) -> Result<IngestionResult, IngestError> {
+        let store_source: ObjectStoreSource =
+            serde_json::from_str(source).map_err(ObjectStoreIngestError::from)?;
+        let bucket = &store_source.bucket;
+        let objects = self
+            .client
+            .list_objects(bucket, &store_source.prefix)
+            .await?;
+
+        let mut documents_added = 0;
+        let mut document_ids = Vec::new();
+        let mut ingested = Vec::new();
+        let mut unchanged = Vec::new();
+        let mut unsupported = Vec::new();
+        let mut failed = Vec::new();
+
+        for object in &objects {
+            let Some(kind) = ObjectKind::from_key(&object.key) else {
+                unsupported.push(object.key.clone());
+                continue;
+            };
+            let object_url = self.client.object_url(bucket, &object.key);
+            let stored_etag = manifest::stored_etag(self.db, &object_url, owner_id)
+                .await
+                .map_err(ObjectStoreIngestError::from)?;
+            if !store_source.force && stored_etag.as_deref() == Some(object.etag.as_str()) {
+                info!("Skipping unchanged object: {object_url}");
+                unchanged.push(object.key.clone());
+                continue;
+            }
+
+            let result = self
+                .ingest_object(bucket, object, kind, &object_url, owner_id)
+                .await;
+            match result {
+                Ok(result) => {
+                    manifest::save_etag(self.db, &object_url, owner_id, &object.etag)
+                        .await
+                        .map_err(ObjectStoreIngestError::from)?;
+                    documents_added += result.documents_added;
+                    document_ids.extend(result.document_ids);
+                    ingested.push(object.key.clone());
+                }
+                Err(e) => {
+                    warn!("Failed to ingest object '{object_url}': {e}");
+                    failed.push(json!({ "key": object.key, "error": e.to_string() }));
+                }
+            }
+        }
+
+        info!(
+            "Ingested {} of {} objects from bucket '{bucket}' ({} failed).",
+            ingested.len(),
+            objects.len(),
+            failed.len()
+        );
+
+        Ok(IngestionResult {
+            source: format!("{bucket}/{}", store_source.prefix),
+            documents_added,
+            document_ids,
+            metadata: Some(
+                json!({
+                    "ingested": ingested,
+                    "unchanged": unchanged,
+                    "unsupported": unsupported,
+                    "failed": failed,
+                })
+                .to_string(),
+            ),
+        })
+    }
+}
+
+fn decode_text(object: &ObjectEntry, data: Vec<u8>) -> Result<String, ObjectStoreIngestError> {
+    String::from_utf8(data).map_err(|_| ObjectStoreIngestError::Encoding(object.key.clone()))
+}
+
+/// Markdown and converted HTML are chunked by section so a chunk never spans headings.
+fn markdown_chunking() -> ChunkingStrategy {
+    ChunkingStrategy::Markdown {
+        chunk_size: DEFAULT_CHUNK_SIZE,
+    }
+}
@@ -0,0 +1,59 @@
+//! # Object Manifest
+//!
+//! Remembers the ETag of every ingested object in the `object_store_manifest` table.
+//! A re-run skips objects whose ETag has not changed, so only new and modified
+//! objects are downloaded and processed again.
+
+use anyrag::ingest::source_url_prefix_pattern;
+use turso::{params, Database};
+
+/// Returns the ETag an object had when it was last ingested for an owner.
+///
+/// Nothing is returned once the object's documents are gone, so a deleted object is
+/// processed again instead of being reported as unchanged.
+pub async fn stored_etag(
+    db: &Database,
+    object_url: &str,
+    owner_id: Option<&str>,
+) -> Result<Option<String>, turso::Error> {
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT etag FROM object_store_manifest m
+             WHERE m.object_url = ? AND m.owner_id = ?
+             AND EXISTS (
+                SELECT 1 FROM documents d
+                WHERE (d.source_url = m.object_url OR d.source_url LIKE ? ESCAPE '\\')
+                AND COALESCE(d.owner_id, '') = m.owner_id
+             )",
+            params![
+                object_url,
+                owner_id.unwrap_or_default(),
+                source_url_prefix_pattern(&format!("{object_url}#"))
+            ],
+        )
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(row.get(0)?)),
+        None => Ok(None),
+    }
+}
+
+/// Remembers the ETag of an object that was ingested for an owner.
+pub async fn save_etag(
+    db: &Database,
+    object_url: &str,
+    owner_id: Option<&str>,
+    etag: &str,
+) -> Result<(), turso::Error> {
+    let conn = db.connect()?;
+    conn.execute(
+        "INSERT INTO object_store_manifest (object_url, owner_id, etag) VALUES (?, ?, ?)
+         ON CONFLICT(object_url, owner_id) DO UPDATE SET
+            etag = excluded.etag,
+            ingested_at = CURRENT_TIMESTAMP",
+        params![object_url, owner_id.unwrap_or_default(), etag],
+    )
+    .await?;
+    Ok(())
+}
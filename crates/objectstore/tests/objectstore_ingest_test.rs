@@ -0,0 +1,302 @@
+//! # Object Store Crate Tests
+//!
+//! This file contains integration tests for the `anyrag-objectstore` crate, running
+//! the ingestor against a mock S3-compatible bucket.
+
+use anyhow::Result;
+use anyrag::ingest::{IngestionPrompts, Ingestor};
+use anyrag_objectstore::{
+    client::{ObjectStoreClient, DEFAULT_REGION},
+    ObjectStoreIngestor,
+};
+use anyrag_test_utils::{MockAiProvider, TestSetup};
+use serde_json::Value;
+use turso::params;
+use wiremock::matchers::{
+    body_bytes, header, header_regex, method, path, query_param, query_param_is_missing,
+};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const BUCKET: &str = "docs";
+const PROMPTS: IngestionPrompts<'static> = IngestionPrompts {
+    restructuring_system_prompt: "Restructure the content.",
+    metadata_extraction_system_prompt: "Extract metadata.",
+};
+
+/// Renders a `ListObjectsV2` response for `(key, etag)` pairs.
+fn listing(objects: &[(&str, &str)], next_token: Option<&str>) -> String {
+    let contents: String = objects
+        .iter()
+        .map(|(key, etag)| {
+            format!("<Contents><Key>{key}</Key><ETag>&quot;{etag}&quot;</ETag><Size>10</Size></Contents>")
+        })
+        .collect();
+    let truncation = match next_token {
+        Some(token) => {
+            format!("<IsTruncated>true</IsTruncated><NextContinuationToken>{token}</NextContinuationToken>")
+        }
+        None => "<IsTruncated>false</IsTruncated>".to_string(),
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/"><Name>{BUCKET}</Name>{truncation}{contents}</ListBucketResult>"#
+    )
+}
+
+async fn mount_object(server: &MockServer, key: &str, body: &str) {
+    Mock::given(method("GET"))
+        .and(path(format!("/{BUCKET}/{key}")))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+/// Reads the string array stored under `field` in the result's metadata.
+fn keys(metadata: &Value, field: &str) -> Vec<String> {
+    metadata[field]
+        .as_array()
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|value| value.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn test_objectstore_routes_objects_and_reports_failures() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/{BUCKET}")))
+        .and(query_param("list-type", "2"))
+        .and(query_param("prefix", "handbook/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(listing(
+            &[
+                ("handbook/", "folder"),
+                ("handbook/intro.md", "e1"),
+                ("handbook/notes.txt", "e2"),
+                ("handbook/page.html", "e3"),
+                ("handbook/logo.png", "e4"),
+                ("handbook/broken.txt", "e5"),
+            ],
+            None,
+        )))
+        .mount(&server)
+        .await;
+    mount_object(
+        &server,
+        "handbook/intro.md",
+        "# Intro\n\nWelcome aboard.\n\n## Setup\n\nInstall the tools.",
+    )
+    .await;
+    mount_object(&server, "handbook/notes.txt", "Remember to rotate keys.").await;
+    mount_object(
+        &server,
+        "handbook/page.html",
+        "<html><body><h1>Holidays</h1><p>The office closes in August.</p></body></html>",
+    )
+    .await;
+    Mock::given(method("GET"))
+        .and(path(format!("/{BUCKET}/handbook/broken.txt")))
+        .respond_with(ResponseTemplate::new(500).set_body_string("InternalError"))
+        .mount(&server)
+        .await;
+
+    let setup = TestSetup::new().await?;
+    let ai_provider = MockAiProvider::new();
+    let client = ObjectStoreClient::new(&server.uri(), DEFAULT_REGION);
+    let ingestor = ObjectStoreIngestor::new(&setup.db, &ai_provider, PROMPTS, client);
+    let source = serde_json::json!({ "bucket": BUCKET, "prefix": "handbook/" }).to_string();
+
+    // --- Act ---
+    let result = ingestor.ingest(&source, None).await?;
+
+    // --- Assert ---
+    let metadata: Value = serde_json::from_str(result.metadata.as_deref().unwrap_or("{}"))?;
+    assert_eq!(
+        keys(&metadata, "ingested"),
+        vec![
+            "handbook/intro.md",
+            "handbook/notes.txt",
+            "handbook/page.html"
+        ]
+    );
+    assert_eq!(keys(&metadata, "unsupported"), vec!["handbook/logo.png"]);
+    let failed = metadata["failed"].as_array().expect("failed list");
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0]["key"], "handbook/broken.txt");
+    assert!(failed[0]["error"]
+        .as_str()
+        .unwrap_or_default()
+        .contains("500"));
+
+    // The Markdown object is chunked by section.
+    assert_eq!(result.documents_added, 4);
+    let conn = setup.db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT content FROM documents WHERE source_url LIKE ? ORDER BY source_url",
+            params![format!("{}/{BUCKET}/handbook/intro.md#%", server.uri())],
+        )
+        .await?;
+    let mut sections = Vec::new();
+    while let Some(row) = rows.next().await? {
+        sections.push(row.get::<String>(0)?);
+    }
+    assert_eq!(sections.len(), 2);
+    assert!(sections[1].contains("Install the tools."));
+
+    let mut rows = conn
+        .query(
+            "SELECT content FROM documents WHERE source_url LIKE ?",
+            params![format!("{}/{BUCKET}/handbook/page.html#%", server.uri())],
+        )
+        .await?;
+    let row = rows.next().await?.expect("HTML document not found");
+    let content = row.get::<String>(0)?;
+    assert!(content.contains("The office closes in August."));
+    assert!(!content.contains("<p>"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_objectstore_skips_objects_with_unchanged_etags() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/{BUCKET}")))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_string(listing(&[("faq.txt", "v1")], None)),
+        )
+        .mount(&server)
+        .await;
+    // The object is downloaded by the first run only.
+    mount_object(&server, "faq.txt", "Support is open on weekdays.").await;
+
+    let setup = TestSetup::new().await?;
+    let ai_provider = MockAiProvider::new();
+    let client = ObjectStoreClient::new(&server.uri(), DEFAULT_REGION);
+    let ingestor = ObjectStoreIngestor::new(&setup.db, &ai_provider, PROMPTS, client);
+    let source = serde_json::json!({ "bucket": BUCKET }).to_string();
+
+    // --- Act ---
+    let first = ingestor.ingest(&source, Some("bucket-user")).await?;
+    let second = ingestor.ingest(&source, Some("bucket-user")).await?;
+
+    // --- Assert ---
+    assert_eq!(first.documents_added, 1);
+    assert_eq!(second.documents_added, 0);
+    let metadata: Value = serde_json::from_str(second.metadata.as_deref().unwrap_or("{}"))?;
+    assert_eq!(keys(&metadata, "unchanged"), vec!["faq.txt"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_objectstore_keeps_the_documents_of_other_owners() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/{BUCKET}")))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_string(listing(&[("faq.txt", "v1")], None)),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!("/{BUCKET}/faq.txt")))
+        .respond_with(ResponseTemplate::new(200).set_body_string("Support is open on weekdays."))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let setup = TestSetup::new().await?;
+    let ai_provider = MockAiProvider::new();
+    let client = ObjectStoreClient::new(&server.uri(), DEFAULT_REGION);
+    let ingestor = ObjectStoreIngestor::new(&setup.db, &ai_provider, PROMPTS, client);
+    let source = serde_json::json!({ "bucket": BUCKET }).to_string();
+
+    // --- Act: two owners ingest the same object ---
+    ingestor.ingest(&source, Some("alice")).await?;
+    ingestor.ingest(&source, Some("bob")).await?;
+
+    // --- Assert: the second ingestion did not replace the first owner's chunks ---
+    let conn = setup.db.connect()?;
+    let mut rows = conn
+        .query("SELECT owner_id FROM documents ORDER BY owner_id", ())
+        .await?;
+    let mut owners = Vec::new();
+    while let Some(row) = rows.next().await? {
+        owners.push(row.get::<String>(0)?);
+    }
+    assert_eq!(owners, vec!["alice", "bob"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_objectstore_pages_listings_with_signed_requests() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    let signature = r"^AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/\d{8}/us-east-1/s3/aws4_request, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature=[0-9a-f]{64}$";
+    Mock::given(method("GET"))
+        .and(path(format!("/{BUCKET}")))
+        .and(query_param_is_missing("continuation-token"))
+        .and(header_regex("Authorization", signature))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(listing(&[("a.txt", "a1")], Some("token/2"))),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!("/{BUCKET}")))
+        .and(query_param("continuation-token", "token/2"))
+        .and(header_regex("Authorization", signature))
+        .respond_with(ResponseTemplate::new(200).set_body_string(listing(&[("b.txt", "b1")], None)))
+        .mount(&server)
+        .await;
+    mount_object(&server, "a.txt", "First object.").await;
+    mount_object(&server, "b.txt", "Second object.").await;
+
+    let setup = TestSetup::new().await?;
+    let ai_provider = MockAiProvider::new();
+    let client = ObjectStoreClient::new(&server.uri(), DEFAULT_REGION)
+        .with_credentials("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY");
+    let ingestor = ObjectStoreIngestor::new(&setup.db, &ai_provider, PROMPTS, client);
+
+    // --- Act ---
+    let source = serde_json::json!({ "bucket": BUCKET }).to_string();
+    let result = ingestor.ingest(&source, None).await?;
+
+    // --- Assert ---
+    assert_eq!(result.documents_added, 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_objectstore_client_uploads_signed_objects() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    // The SHA-256 digest of "backup", which the signature covers.
+    let payload_sha256 = "54d00d867758cef816bc4685f58e327b949712b07ebd17c3485f3ffc9e9f5133";
+    Mock::given(method("PUT"))
+        .and(path(format!("/{BUCKET}/backups/anyrag.db")))
+        .and(body_bytes(b"backup".to_vec()))
+        .and(header("x-amz-content-sha256", payload_sha256))
+        .and(header_regex("Authorization", "^AWS4-HMAC-SHA256 "))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = ObjectStoreClient::new(&server.uri(), DEFAULT_REGION)
+        .with_credentials("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY");
+
+    // --- Act & Assert ---
+    client
+        .put_object(BUCKET, "backups/anyrag.db", b"backup".to_vec())
+        .await?;
+    Ok(())
+}
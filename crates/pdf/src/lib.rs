@@ -4,11 +4,16 @@
 //! for the `anyrag` ecosystem. It implements the `Ingestor` trait from `anyrag-lib`.
 
 use anyrag::{
+    constants::DEFAULT_INGEST_CONCURRENCY,
     ingest::{
-        knowledge::{extract_and_store_metadata, restructure_with_llm, YamlContent},
-        IngestError, IngestionPrompts, IngestionResult, Ingestor,
+        chunk_without_restructuring, content_hash, embed_ingested_documents,
+        find_duplicate_document,
+        knowledge::{chunk_title, RestructuringOutcome, YamlContent, UNPARSED_CONTENT_TITLE},
+        record_revision, ChunkingStrategy, IngestError, IngestionPrompts, IngestionResult,
+        Ingestor, InvalidChunkingError, KnowledgePipeline, Restructured,
     },
     providers::ai::AiProvider,
+    types::EmbeddingConfig,
     PromptError,
 };
 use async_trait::async_trait;
@@ -36,6 +41,8 @@ pub enum PdfIngestError {
     Internal(#[from] anyhow::Error),
     #[error("Knowledge pipeline failed: {0}")]
     Knowledge(#[from] anyrag::ingest::knowledge::KnowledgeError),
+    #[error(transparent)]
+    InvalidChunking(#[from] InvalidChunkingError),
 }
 
 impl From<PdfIngestError> for IngestError {
@@ -43,6 +50,7 @@ impl From<PdfIngestError> for IngestError {
         match err {
             PdfIngestError::Database(e) => IngestError::Database(e),
             PdfIngestError::PdfParse(s) => IngestError::Parse(s),
+            PdfIngestError::InvalidChunking(e) => IngestError::Parse(e.to_string()),
             _ => IngestError::Internal(anyhow::anyhow!(err.to_string())),
         }
     }
@@ -64,53 +72,149 @@ struct IngestSource<'a> {
     pdf_data_base64: &'a str,
     #[serde(default)]
     extractor: PdfExtractor,
+    /// Splits the extracted text before restructuring, one LLM call per chunk.
+    #[serde(default)]
+    chunking: Option<ChunkingStrategy>,
+    /// With `false`, the text is not restructured by the LLM: each chunk of a page is
+    /// stored as its own document, as extracted.
+    #[serde(default = "default_restructure")]
+    restructure: bool,
+}
+
+fn default_restructure() -> bool {
+    true
 }
 
 // --- Core Pipeline Logic ---
 
-/// Extracts text from all pages of a PDF synchronously.
-fn extract_text_from_pdf(pdf_data: &[u8]) -> Result<String, PdfIngestError> {
+/// The `content_metadata` type of document properties.
+const PROPERTY_METADATA_TYPE: &str = "PROPERTY";
+/// The property holding the 1-based page a PDF document was extracted from.
+pub const PAGE_NUMBER_PROPERTY: &str = "page_number";
+
+/// Extracts the text of each page of a PDF synchronously, one string per page.
+fn extract_pages_from_pdf(pdf_data: &[u8]) -> Result<Vec<String>, PdfIngestError> {
     let file = FileOptions::cached()
         .load(pdf_data)
         .map_err(|e| PdfIngestError::PdfParse(e.to_string()))?;
     let resolver = file.resolver();
-    let mut full_text = String::new();
+    let mut pages = Vec::new();
 
     for page_num in 0..file.num_pages() {
         let page = file
             .get_page(page_num)
             .map_err(|e| PdfIngestError::PdfParse(e.to_string()))?;
+        let mut page_text = String::new();
         if let Some(content) = &page.contents {
             let operations = content
                 .operations(&resolver)
                 .map_err(|e| PdfIngestError::PdfParse(e.to_string()))?;
             for op in operations.iter() {
                 if let pdf::content::Op::TextDraw { text } = op {
-                    full_text.push_str(&text.to_string_lossy());
+                    page_text.push_str(&text.to_string_lossy());
                 }
             }
         }
+        pages.push(page_text);
+    }
+    Ok(pages)
+}
+
+/// Returns the source URL of a section, using the `#page=N` fragment PDF viewers
+/// open at, so a citation links to the exact page.
+fn section_source_url(source_identifier: &str, page_number: usize, index: usize) -> String {
+    format!("{source_identifier}#page={page_number}&section={index}")
+}
+
+/// The page number of a page's documents, how the page's content was obtained, and the
+/// title and content of each of its documents.
+type PageSections = (usize, RestructuringOutcome, Vec<(String, String)>);
+
+/// Restructures the text of each page with the LLM into the sections stored for it.
+///
+/// Each page is restructured on its own so every section knows its page. The chunks
+/// of all pages are independent, so they are sent to the LLM together.
+async fn restructure_pages(
+    pipeline: &KnowledgePipeline<'_>,
+    pages: Vec<String>,
+    source_identifier: &str,
+    chunking: Option<&ChunkingStrategy>,
+) -> Result<Vec<PageSections>, PdfIngestError> {
+    let mut page_chunk_counts = Vec::new();
+    let mut chunks = Vec::new();
+    for (page_index, page_text) in pages.into_iter().enumerate() {
+        if page_text.trim().is_empty() {
+            continue;
+        }
+        let page_chunks = match chunking {
+            Some(strategy) => strategy.chunker()?.chunk(&page_text),
+            None => vec![page_text],
+        };
+        page_chunk_counts.push((page_index + 1, page_chunks.len()));
+        chunks.extend(page_chunks);
+    }
+    let restructured_chunks = pipeline.restructure_each(&chunks).await?;
+
+    let mut page_sections = Vec::new();
+    let mut restructured_chunks = restructured_chunks.into_iter();
+    for (page_number, chunk_count) in page_chunk_counts {
+        let restructured =
+            Restructured::merge(restructured_chunks.by_ref().take(chunk_count).collect())?;
+        let Some(outcome) = restructured.outcome() else {
+            warn!(
+                "LLM restructuring of page {page_number} of '{source_identifier}' resulted in empty YAML."
+            );
+            continue;
+        };
+        let sections = match restructured {
+            Restructured::Sections { content, .. } => content
+                .sections
+                .into_iter()
+                .filter_map(|section| {
+                    let title = section.title.clone();
+                    let chunk_yaml_content = YamlContent {
+                        sections: vec![section],
+                    };
+                    match serde_yaml::to_string(&chunk_yaml_content) {
+                        Ok(yaml) => Some((title, yaml)),
+                        Err(e) => {
+                            warn!("Failed to serialize section '{title}' of page {page_number} to YAML, skipping. Error: {e}");
+                            None
+                        }
+                    }
+                })
+                .collect(),
+            Restructured::Unparsed(raw) => {
+                warn!(
+                    "Failed to obtain valid YAML from LLM for page {page_number} of '{source_identifier}', storing it as-is."
+                );
+                vec![(UNPARSED_CONTENT_TITLE.to_string(), raw)]
+            }
+            Restructured::Empty => continue,
+        };
+        page_sections.push((page_number, outcome, sections));
     }
-    Ok(full_text)
+    Ok(page_sections)
 }
 
-#[instrument(skip(db, ai_provider, pdf_data))]
+#[instrument(skip(db, pipeline, pdf_data, source), fields(source_identifier = %source.source_identifier))]
 async fn run_pdf_ingestion_pipeline(
     db: &Database,
-    ai_provider: &dyn AiProvider,
+    pipeline: &KnowledgePipeline<'_>,
     pdf_data: Vec<u8>,
-    source_identifier: &str,
+    source: &IngestSource<'_>,
     owner_id: Option<&str>,
-    extractor: PdfExtractor,
-    prompts: IngestionPrompts<'_>,
-) -> Result<usize, PdfIngestError> {
+) -> Result<Vec<String>, PdfIngestError> {
+    let source_identifier = source.source_identifier;
+    let extractor = source.extractor;
+    let chunking = source.chunking.as_ref();
     info!(
         "Starting PDF ingestion pipeline for '{}' using '{:?}' extractor.",
         source_identifier, extractor
     );
 
-    let refined_markdown = match extractor {
-        PdfExtractor::Local => extract_text_from_pdf(&pdf_data)?,
+    let pages = match extractor {
+        PdfExtractor::Local => extract_pages_from_pdf(&pdf_data)?,
         PdfExtractor::Gemini => {
             return Err(PdfIngestError::Internal(anyhow::anyhow!(
                 "Gemini PDF extractor is not yet implemented."
@@ -118,42 +222,42 @@ async fn run_pdf_ingestion_pipeline(
         }
     };
 
-    if refined_markdown.trim().is_empty() {
-        warn!(
-            "PDF processing for '{}' resulted in empty content. Aborting.",
-            source_identifier
-        );
-        return Ok(0);
-    }
-
-    let structured_yaml = restructure_with_llm(
-        ai_provider,
-        &refined_markdown,
-        prompts.restructuring_system_prompt,
-    )
-    .await?;
+    let page_sections = if source.restructure {
+        restructure_pages(pipeline, pages, source_identifier, chunking).await?
+    } else {
+        pages
+            .into_iter()
+            .enumerate()
+            .filter(|(_, page_text)| !page_text.trim().is_empty())
+            .map(
+                |(page_index, page_text)| -> Result<PageSections, PdfIngestError> {
+                    let page_number = page_index + 1;
+                    let sections = chunk_without_restructuring(&page_text, chunking)?
+                        .into_iter()
+                        .map(|chunk| {
+                            let title = chunk_title(&chunk).unwrap_or_else(|| {
+                                format!("{source_identifier} (page {page_number})")
+                            });
+                            (title, chunk)
+                        })
+                        .collect();
+                    Ok((page_number, RestructuringOutcome::Skipped, sections))
+                },
+            )
+            .collect::<Result<_, _>>()?
+    };
 
-    if structured_yaml.trim().is_empty() {
+    if page_sections.is_empty() {
         warn!(
-            "LLM restructuring of PDF content for '{}' resulted in empty YAML.",
+            "PDF processing for '{}' resulted in empty content. Aborting.",
             source_identifier
         );
-        return Ok(0);
+        return Ok(vec![]);
     }
 
-    let parsed_yaml: YamlContent = match serde_yaml::from_str(&structured_yaml) {
-        Ok(data) => data,
-        Err(e) => {
-            warn!(
-                "Failed to parse YAML from LLM for '{}', aborting. Error: {}",
-                source_identifier, e
-            );
-            return Ok(0);
-        }
-    };
-
     let conn = db.connect()?;
-    let mut documents_added = 0;
+    // The id, content, page number and restructuring outcome of every stored section.
+    let mut stored_sections = Vec::new();
 
     // Before creating new chunks, delete any existing chunks for this source.
     // This ensures that if the PDF is re-ingested with fewer sections, the old,
@@ -171,58 +275,81 @@ async fn run_pdf_ingestion_pipeline(
     )
     .await?;
 
-    for (index, section) in parsed_yaml.sections.iter().enumerate() {
-        let chunk_source_url = format!("{source_identifier}#section_{index}");
-        let chunk_document_id =
-            Uuid::new_v5(&Uuid::NAMESPACE_URL, chunk_source_url.as_bytes()).to_string();
-
-        let chunk_yaml_content = YamlContent {
-            sections: vec![section.clone()],
-        };
-
-        let chunk_yaml_string = match serde_yaml::to_string(&chunk_yaml_content) {
-            Ok(s) => s,
-            Err(e) => {
-                warn!("Failed to serialize chunk to YAML, skipping section {index}. Error: {e}");
+    for (page_number, outcome, sections) in page_sections {
+        for (index, (title, chunk_yaml_string)) in sections.into_iter().enumerate() {
+            let chunk_source_url = section_source_url(source_identifier, page_number, index);
+            let chunk_document_id =
+                Uuid::new_v5(&Uuid::NAMESPACE_URL, chunk_source_url.as_bytes()).to_string();
+
+            let hash = content_hash(&chunk_yaml_string);
+            if find_duplicate_document(&conn, owner_id, &hash)
+                .await?
+                .is_some()
+            {
+                info!("Skipping duplicate section {index} of page {page_number} of '{source_identifier}'.");
                 continue;
             }
-        };
 
+            record_revision(&conn, &chunk_source_url, &chunk_yaml_string).await?;
+            conn.execute(
+                "INSERT INTO documents (id, owner_id, source_url, title, content, content_hash)
+                 VALUES (?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(source_url) DO UPDATE SET
+                 title = excluded.title,
+                 content = excluded.content,
+                 content_hash = excluded.content_hash",
+                params![
+                    chunk_document_id.clone(),
+                    owner_id,
+                    chunk_source_url,
+                    title,
+                    chunk_yaml_string.clone(),
+                    hash
+                ],
+            )
+            .await?;
+
+            stored_sections.push((chunk_document_id, chunk_yaml_string, page_number, outcome));
+        }
+    }
+
+    let documents: Vec<(String, String)> = stored_sections
+        .iter()
+        .map(|(document_id, content, _, _)| (document_id.clone(), content.clone()))
+        .collect();
+    pipeline
+        .extract_metadata_of_each(&conn, &documents, owner_id)
+        .await?;
+
+    // Stored after the extracted metadata, which replaces all rows of the document.
+    for (document_id, _, page_number, outcome) in &stored_sections {
         conn.execute(
-            "INSERT INTO documents (id, owner_id, source_url, title, content)
-             VALUES (?, ?, ?, ?, ?)
-             ON CONFLICT(source_url) DO UPDATE SET
-             title = excluded.title,
-             content = excluded.content",
+            "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_subtype, metadata_value) VALUES (?, ?, ?, ?, ?)",
             params![
-                chunk_document_id.clone(),
+                document_id.clone(),
                 owner_id,
-                chunk_source_url,
-                section.title.clone(),
-                chunk_yaml_string.clone()
+                PROPERTY_METADATA_TYPE,
+                PAGE_NUMBER_PROPERTY,
+                page_number.to_string()
             ],
         )
         .await?;
-
-        extract_and_store_metadata(
-            &conn,
-            ai_provider,
-            &chunk_document_id,
-            owner_id,
-            &chunk_yaml_string,
-            prompts.metadata_extraction_system_prompt,
-        )
-        .await?;
-
-        documents_added += 1;
+        pipeline
+            .record_outcome(&conn, document_id, owner_id, *outcome)
+            .await?;
     }
+    let document_ids: Vec<String> = stored_sections
+        .into_iter()
+        .map(|(document_id, _, _, _)| document_id)
+        .collect();
 
     info!(
         "PDF ingestion for '{}' complete. Added {} document chunks.",
-        source_identifier, documents_added
+        source_identifier,
+        document_ids.len()
     );
 
-    Ok(documents_added)
+    Ok(document_ids)
 }
 
 // --- Ingestor Implementation ---
@@ -232,6 +359,8 @@ pub struct PdfIngestor<'a> {
     db: &'a Database,
     ai_provider: &'a dyn AiProvider,
     prompts: IngestionPrompts<'a>,
+    concurrency: usize,
+    embedding: Option<&'a EmbeddingConfig>,
 }
 
 impl<'a> PdfIngestor<'a> {
@@ -244,8 +373,22 @@ impl<'a> PdfIngestor<'a> {
             db,
             ai_provider,
             prompts,
+            concurrency: DEFAULT_INGEST_CONCURRENCY,
+            embedding: None,
         }
     }
+
+    /// Sets how many pages and sections are sent to the LLM at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Embeds the documents of each PDF with the given model once they are stored.
+    pub fn with_embedding(mut self, config: &'a EmbeddingConfig) -> Self {
+        self.embedding = Some(config);
+        self
+    }
 }
 
 #[async_trait]
@@ -262,20 +405,19 @@ impl<'a> Ingestor for PdfIngestor<'a> {
             .decode(ingest_source.pdf_data_base64)
             .map_err(PdfIngestError::from)?;
 
-        let documents_added = run_pdf_ingestion_pipeline(
-            self.db,
-            self.ai_provider,
-            pdf_data,
-            ingest_source.source_identifier,
-            owner_id,
-            ingest_source.extractor,
-            self.prompts,
-        )
-        .await?;
+        let pipeline = KnowledgePipeline::new(self.ai_provider, self.prompts)
+            .with_concurrency(self.concurrency);
+        let document_ids =
+            run_pdf_ingestion_pipeline(self.db, &pipeline, pdf_data, &ingest_source, owner_id)
+                .await?;
+        if let Some(config) = self.embedding {
+            embed_ingested_documents(self.db, config, &document_ids).await;
+        }
 
         Ok(IngestionResult {
             source: ingest_source.source_identifier.to_string(),
-            documents_added,
+            documents_added: document_ids.len(),
+            document_ids,
             ..Default::default()
         })
     }
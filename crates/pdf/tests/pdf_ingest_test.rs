@@ -7,8 +7,11 @@ use anyrag::{
         KNOWLEDGE_RESTRUCTURING_SYSTEM_PROMPT, METADATA_EXTRACTION_SYSTEM_PROMPT,
     },
 };
-use anyrag_pdf::PdfIngestor;
-use anyrag_test_utils::{helpers::generate_test_pdf, MockAiProvider, TestSetup};
+use anyrag_pdf::{PdfIngestor, PAGE_NUMBER_PROPERTY};
+use anyrag_test_utils::{
+    helpers::{generate_multi_page_test_pdf, generate_test_pdf},
+    MockAiProvider, TestSetup,
+};
 use base64::{engine::general_purpose, Engine as _};
 use serde_json::json;
 use turso::params;
@@ -87,7 +90,7 @@ sections:
     let source_url_1 = row1.get::<String>(0)?;
     let content_1 = row1.get::<String>(1)?;
     let id_1 = row1.get::<String>(2)?;
-    assert_eq!(source_url_1, "test.pdf#section_0");
+    assert_eq!(source_url_1, "test.pdf#page=1&section=0");
     let expected_content_1 = r#"
 sections:
 - title: First Section
@@ -102,7 +105,7 @@ sections:
     let source_url_2 = row2.get::<String>(0)?;
     let content_2 = row2.get::<String>(1)?;
     let id_2 = row2.get::<String>(2)?;
-    assert_eq!(source_url_2, "test.pdf#section_1");
+    assert_eq!(source_url_2, "test.pdf#page=1&section=1");
     let expected_content_2 = r#"
 sections:
 - title: Second Section
@@ -120,7 +123,7 @@ sections:
     // C. Check metadata for each chunk
     // Metadata for Chunk 1
     let mut stmt_meta_1 = conn
-        .prepare("SELECT metadata_value FROM content_metadata WHERE document_id = ?")
+        .prepare("SELECT metadata_value FROM content_metadata WHERE document_id = ? AND metadata_type != 'PROPERTY'")
         .await?;
     let mut rows_meta_1 = stmt_meta_1.query(params![id_1]).await?;
     let meta_value_1 = rows_meta_1.next().await?.unwrap().get::<String>(0)?;
@@ -129,14 +132,19 @@ sections:
 
     // Metadata for Chunk 2
     let mut stmt_meta_2 = conn
-        .prepare("SELECT metadata_value FROM content_metadata WHERE document_id = ?")
+        .prepare("SELECT metadata_value FROM content_metadata WHERE document_id = ? AND metadata_type != 'PROPERTY'")
         .await?;
     let mut rows_meta_2 = stmt_meta_2.query(params![id_2]).await?;
     let meta_value_2 = rows_meta_2.next().await?.unwrap().get::<String>(0)?;
     assert_eq!(meta_value_2, "everything");
     assert!(rows_meta_2.next().await?.is_none());
 
-    // D. Assert that the AI provider was called correctly
+    // D. Check that both chunks record the page they came from
+    for id in [&id_1, &id_2] {
+        assert_eq!(page_number_of(&conn, id).await?, "1");
+    }
+
+    // E. Assert that the AI provider was called correctly
     assert_eq!(
         ai_provider.get_calls().len(),
         3,
@@ -145,3 +153,161 @@ sections:
 
     Ok(())
 }
+
+/// Reads the `page_number` property stored for a document.
+async fn page_number_of(conn: &turso::Connection, document_id: &str) -> Result<String> {
+    let mut rows = conn
+        .query(
+            "SELECT metadata_value FROM content_metadata WHERE document_id = ? AND metadata_type = 'PROPERTY' AND metadata_subtype = ?",
+            params![document_id, PAGE_NUMBER_PROPERTY],
+        )
+        .await?;
+    let row = rows.next().await?.expect("page_number property not found");
+    Ok(row.get::<String>(0)?)
+}
+
+#[tokio::test]
+async fn test_pdf_ingestion_creates_documents_per_page() -> Result<()> {
+    // --- 1. Arrange ---
+    let setup = TestSetup::new().await?;
+    let ai_provider = MockAiProvider::new();
+    let owner_id = "pdf-pages-user-001";
+    let source_identifier = "manual.pdf";
+
+    let pdf_data = generate_multi_page_test_pdf(&[
+        "Installation: plug the device in.",
+        "",
+        "Troubleshooting: restart the device.",
+    ])?;
+    let pdf_base64 = general_purpose::STANDARD.encode(&pdf_data);
+
+    // Each non-empty page is restructured on its own, then each section gets metadata.
+    ai_provider.add_response(
+        "expert document analyst and editor",
+        r#"
+sections:
+  - title: "Installation"
+    faqs:
+      - question: "How do I install the device?"
+        answer: "Plug it in."
+"#,
+    );
+    ai_provider.add_response(
+        "expert document analyst and editor",
+        r#"
+sections:
+  - title: "Troubleshooting"
+    faqs:
+      - question: "What if the device stops working?"
+        answer: "Restart it."
+"#,
+    );
+    ai_provider.add_response("extract two types of metadata", "[]");
+    ai_provider.add_response("extract two types of metadata", "[]");
+
+    // --- 2. Act ---
+    let prompts = IngestionPrompts {
+        restructuring_system_prompt: KNOWLEDGE_RESTRUCTURING_SYSTEM_PROMPT,
+        metadata_extraction_system_prompt: METADATA_EXTRACTION_SYSTEM_PROMPT,
+    };
+    let ingestor = PdfIngestor::new(&setup.db, &ai_provider, prompts);
+    let source = json!({
+        "source_identifier": source_identifier,
+        "pdf_data_base64": pdf_base64,
+    })
+    .to_string();
+    let result = ingestor.ingest(&source, Some(owner_id)).await?;
+
+    // --- 3. Assert ---
+    assert_eq!(result.documents_added, 2);
+
+    let conn = setup.db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT source_url, title, id FROM documents WHERE source_url LIKE ? ORDER BY source_url",
+            params![format!("{source_identifier}#%")],
+        )
+        .await?;
+    let mut documents = Vec::new();
+    while let Some(row) = rows.next().await? {
+        documents.push((
+            row.get::<String>(0)?,
+            row.get::<String>(1)?,
+            row.get::<String>(2)?,
+        ));
+    }
+    assert_eq!(documents.len(), 2);
+
+    let (url_1, title_1, id_1) = &documents[0];
+    assert_eq!(url_1, "manual.pdf#page=1&section=0");
+    assert_eq!(title_1, "Installation");
+    assert_eq!(page_number_of(&conn, id_1).await?, "1");
+
+    // The blank second page produces no document, so the next one is page 3.
+    let (url_2, title_2, id_2) = &documents[1];
+    assert_eq!(url_2, "manual.pdf#page=3&section=0");
+    assert_eq!(title_2, "Troubleshooting");
+    assert_eq!(page_number_of(&conn, id_2).await?, "3");
+
+    assert_eq!(
+        ai_provider.get_calls().len(),
+        4,
+        "Expected 4 AI calls (2 restructure, 2 metadata)"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pdf_ingestion_without_restructuring_stores_page_text() -> Result<()> {
+    // --- 1. Arrange ---
+    let setup = TestSetup::new().await?;
+    let ai_provider = MockAiProvider::new();
+    let source_identifier = "leaflet.pdf";
+
+    let pdf_data = generate_multi_page_test_pdf(&["Keep the device dry.", "Recycle the battery."])?;
+    // Only the metadata of each page is extracted by the LLM.
+    ai_provider.add_response("extract two types of metadata", "[]");
+    ai_provider.add_response("extract two types of metadata", "[]");
+
+    // --- 2. Act ---
+    let prompts = IngestionPrompts {
+        restructuring_system_prompt: KNOWLEDGE_RESTRUCTURING_SYSTEM_PROMPT,
+        metadata_extraction_system_prompt: METADATA_EXTRACTION_SYSTEM_PROMPT,
+    };
+    let ingestor = PdfIngestor::new(&setup.db, &ai_provider, prompts);
+    let source = json!({
+        "source_identifier": source_identifier,
+        "pdf_data_base64": general_purpose::STANDARD.encode(&pdf_data),
+        "restructure": false,
+    })
+    .to_string();
+    let result = ingestor.ingest(&source, None).await?;
+
+    // --- 3. Assert ---
+    assert_eq!(result.documents_added, 2);
+    let calls = ai_provider.get_calls();
+    assert_eq!(calls.len(), 2);
+    assert!(calls
+        .iter()
+        .all(|(system_prompt, _)| system_prompt == METADATA_EXTRACTION_SYSTEM_PROMPT));
+
+    let conn = setup.db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT d.source_url, d.title, d.content, m.metadata_value FROM documents d
+             JOIN content_metadata m ON m.document_id = d.id AND m.metadata_subtype = 'restructuring'
+             ORDER BY d.source_url",
+            (),
+        )
+        .await?;
+    let first = rows.next().await?.expect("Expected the first page");
+    assert_eq!(first.get::<String>(0)?, "leaflet.pdf#page=1&section=0");
+    assert_eq!(first.get::<String>(1)?, "leaflet.pdf (page 1)");
+    assert_eq!(first.get::<String>(2)?, "Keep the device dry.");
+    assert_eq!(first.get::<String>(3)?, "skipped");
+    let second = rows.next().await?.expect("Expected the second page");
+    assert_eq!(second.get::<String>(2)?, "Recycle the battery.");
+    assert!(rows.next().await?.is_none());
+    Ok(())
+}
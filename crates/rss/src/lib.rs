@@ -5,15 +5,25 @@
 //! core `anyrag` library.
 
 use anyhow::anyhow;
-use anyrag::ingest::{IngestError, IngestionResult, Ingestor};
+use anyrag::{
+    ingest::{
+        bulk_insert_documents, embed_ingested_documents, IngestError, IngestionResult, Ingestor,
+        NewDocument,
+    },
+    types::EmbeddingConfig,
+};
+use anyrag_web::{fetch_web_content, WebIngestStrategy};
 use async_trait::async_trait;
-use rss::Channel;
+use rss::{Channel, Item};
 use serde::Deserialize;
 use thiserror::Error;
-use tracing::info;
-use turso::{params, Database};
+use tracing::{info, warn};
+use transcription::{chunk_transcript, is_audio_enclosure, transcribe_audio, TranscriptionConfig};
+use turso::Database;
 use uuid::Uuid;
 
+pub mod transcription;
+
 /// Custom error types for the RSS ingestion process.
 #[derive(Error, Debug)]
 pub enum RssIngestError {
@@ -25,6 +35,8 @@ pub enum RssIngestError {
     Parse(#[from] rss::Error),
     #[error("Source deserialization failed: {0}")]
     SourceDeserialization(#[from] serde_json::Error),
+    #[error("Transcription failed: {0}")]
+    Transcription(String),
 }
 
 /// A helper to convert the specific `RssIngestError` into the generic `anyrag::ingest::IngestError`.
@@ -37,6 +49,7 @@ impl From<RssIngestError> for IngestError {
             RssIngestError::SourceDeserialization(e) => {
                 IngestError::Internal(anyhow!("Failed to deserialize source JSON: {e}"))
             }
+            RssIngestError::Transcription(e) => IngestError::Fetch(e),
         }
     }
 }
@@ -45,17 +58,86 @@ impl From<RssIngestError> for IngestError {
 #[derive(Deserialize)]
 struct RssSource {
     url: String,
+    /// Follows each item's link and stores the full cleaned article instead of the
+    /// (usually truncated) description.
+    #[serde(default)]
+    fetch_full_content: bool,
+}
+
+/// A document prepared from an RSS item before it is stored.
+struct ItemDocument {
+    source_url: String,
+    title: String,
+    content: String,
 }
 
 /// The `Ingestor` implementation for RSS feeds.
 pub struct RssIngestor {
     db: Database,
+    transcription: Option<TranscriptionConfig>,
+    embedding: Option<EmbeddingConfig>,
 }
 
 impl RssIngestor {
     /// Creates a new `RssIngestor`.
     pub fn new(db: &Database) -> Self {
-        Self { db: db.clone() }
+        Self {
+            db: db.clone(),
+            transcription: None,
+            embedding: None,
+        }
+    }
+
+    /// Transcribes the audio enclosures of podcast items with the given endpoint and
+    /// stores the transcript, split into chunks, instead of the item's description.
+    pub fn with_transcription(mut self, config: TranscriptionConfig) -> Self {
+        self.transcription = Some(config);
+        self
+    }
+
+    /// Embeds the new documents of each feed with the given model once they are stored.
+    pub fn with_embedding(mut self, config: EmbeddingConfig) -> Self {
+        self.embedding = Some(config);
+        self
+    }
+
+    /// Transcribes an item's audio enclosure into one document per transcript chunk.
+    ///
+    /// Returns `None` when there is nothing to transcribe or transcription fails, so
+    /// the item is stored like any other.
+    async fn transcript_documents(&self, item: &Item, title: &str) -> Option<Vec<ItemDocument>> {
+        let config = self.transcription.as_ref()?;
+        let enclosure = item
+            .enclosure()
+            .filter(|e| is_audio_enclosure(e.mime_type()))?;
+        let audio_url = enclosure.url();
+
+        let transcript = match transcribe_audio(config, audio_url, enclosure.mime_type()).await {
+            Ok(transcript) => transcript,
+            Err(e) => {
+                warn!("Failed to transcribe {audio_url}: {e}. Keeping the RSS description.");
+                return None;
+            }
+        };
+        let chunks = chunk_transcript(&transcript);
+        if chunks.is_empty() {
+            warn!("Transcript of {audio_url} is empty, keeping the RSS description.");
+            return None;
+        }
+
+        let total = chunks.len();
+        Some(
+            chunks
+                .into_iter()
+                .enumerate()
+                .map(|(i, chunk)| ItemDocument {
+                    // Each chunk needs its own unique `source_url`.
+                    source_url: format!("{audio_url}#part-{}", i + 1),
+                    title: format!("{title} (transcript {}/{total})", i + 1),
+                    content: format!("{title}\n\n{chunk}"),
+                })
+                .collect(),
+        )
     }
 }
 
@@ -63,8 +145,10 @@ impl RssIngestor {
 impl Ingestor for RssIngestor {
     /// Fetches an RSS feed, parses its items, and stores them as documents in the database.
     ///
-    /// The `source` argument is expected to be a JSON string with a single `url` key,
-    /// for example: `{"url": "https://example.com/feed.xml"}`.
+    /// The `source` argument is expected to be a JSON string with a `url` key, for
+    /// example: `{"url": "https://example.com/feed.xml"}`. With
+    /// `"fetch_full_content": true`, each item's article is fetched from its link; an
+    /// item whose article cannot be fetched falls back to its description.
     async fn ingest(
         &self,
         source: &str,
@@ -94,50 +178,53 @@ impl Ingestor for RssIngestor {
             });
         }
 
-        let tx = conn.transaction().await.map_err(RssIngestError::from)?;
-        let mut new_document_ids = Vec::new();
-
+        // Articles and transcripts are fetched before the transaction so it is not held
+        // open over the network.
+        let mut documents = Vec::new();
         for item in channel.items() {
-            if let (Some(title), Some(link)) = (item.title(), item.link()) {
-                let document_id = Uuid::new_v5(&Uuid::NAMESPACE_URL, link.as_bytes()).to_string();
-                let description = item.description().unwrap_or_default();
-                let content = format!("{title}\n\n{description}");
-
-                // The `source_url` is the unique link of the RSS item itself.
-                let mut stmt = tx
-                    .prepare(
-                        "INSERT INTO documents (id, owner_id, source_url, title, content)
-                         VALUES (?, ?, ?, ?, ?)
-                         ON CONFLICT(source_url) DO UPDATE SET
-                         title = excluded.title,
-                         content = excluded.content",
-                    )
-                    .await
-                    .map_err(RssIngestError::from)?;
-
-                let changes = stmt
-                    .execute(params![
-                        document_id.clone(),
-                        owner_id,
-                        link.to_string(),
-                        title.to_string(),
-                        content
-                    ])
-                    .await
-                    .map_err(RssIngestError::from)?;
-
-                if changes > 0 {
-                    new_document_ids.push(document_id);
-                }
+            let Some(title) = item.title() else {
+                continue;
+            };
+            if let Some(transcript) = self.transcript_documents(item, title).await {
+                documents.extend(transcript);
+                continue;
             }
+            let Some(link) = item.link() else {
+                continue;
+            };
+            let body = match rss_source.fetch_full_content {
+                true => fetch_article(item, link).await,
+                false => item.description().unwrap_or_default().to_string(),
+            };
+            documents.push(ItemDocument {
+                source_url: link.to_string(),
+                title: title.to_string(),
+                content: format!("{title}\n\n{body}"),
+            });
         }
 
-        tx.commit().await.map_err(RssIngestError::from)?;
+        // The id is derived from the `source_url`, the unique link of the RSS item or of a
+        // transcript chunk, so a re-ingested item keeps its id.
+        let documents = documents
+            .into_iter()
+            .map(|document| NewDocument {
+                id: Uuid::new_v5(&Uuid::NAMESPACE_URL, document.source_url.as_bytes()).to_string(),
+                source_url: document.source_url,
+                title: document.title,
+                content: document.content,
+            })
+            .collect();
+        let new_document_ids = bulk_insert_documents(&mut conn, owner_id, documents)
+            .await
+            .map_err(RssIngestError::from)?;
 
         info!(
             "Transaction committed. Ingested {} new documents from RSS feed.",
             new_document_ids.len()
         );
+        if let Some(config) = &self.embedding {
+            embed_ingested_documents(&self.db, config, &new_document_ids).await;
+        }
 
         Ok(IngestionResult {
             documents_added: new_document_ids.len(),
@@ -147,3 +234,20 @@ impl Ingestor for RssIngestor {
         })
     }
 }
+
+/// Fetches the full article behind an item's link as cleaned markdown, falling back to
+/// the item's description if the article cannot be fetched or is empty.
+async fn fetch_article(item: &Item, link: &str) -> String {
+    let description = item.description().unwrap_or_default();
+    match fetch_web_content(link, WebIngestStrategy::RawHtml).await {
+        Ok(article) if !article.trim().is_empty() => article,
+        Ok(_) => {
+            warn!("Article at {link} is empty, keeping the RSS description.");
+            description.to_string()
+        }
+        Err(e) => {
+            warn!("Failed to fetch article at {link}: {e}. Keeping the RSS description.");
+            description.to_string()
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! # Podcast Transcription
+//!
+//! This module turns the audio enclosures of podcast feeds into text through a
+//! Whisper-compatible transcription endpoint, such as OpenAI's
+//! `/v1/audio/transcriptions` or a self-hosted `faster-whisper` server.
+
+use crate::RssIngestError;
+use anyrag::ingest::chunking::{Chunker, SentenceChunker, DEFAULT_CHUNK_SIZE};
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+use tracing::info;
+
+/// The model requested when none is configured.
+pub const DEFAULT_TRANSCRIPTION_MODEL: &str = "whisper-1";
+/// The largest audio file sent for transcription, the upload limit of the Whisper API.
+pub const MAX_AUDIO_BYTES: usize = 25 * 1024 * 1024;
+
+/// The MIME type prefix of audio enclosures.
+const AUDIO_MIME_PREFIX: &str = "audio/";
+/// The file name sent when the enclosure URL has none.
+const FALLBACK_FILE_NAME: &str = "episode.mp3";
+
+/// Where and how to transcribe audio enclosures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptionConfig {
+    /// The full URL of the transcription endpoint.
+    pub endpoint: String,
+    pub api_key: Option<String>,
+    pub model: String,
+}
+
+impl TranscriptionConfig {
+    /// Creates a configuration for `endpoint` using the default model and no API key.
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            api_key: None,
+            model: DEFAULT_TRANSCRIPTION_MODEL.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TranscriptionResponse {
+    text: String,
+}
+
+/// Returns whether an enclosure of this MIME type is audio.
+pub fn is_audio_enclosure(mime_type: &str) -> bool {
+    mime_type
+        .trim()
+        .to_lowercase()
+        .starts_with(AUDIO_MIME_PREFIX)
+}
+
+/// Downloads an audio file and returns its transcript.
+pub async fn transcribe_audio(
+    config: &TranscriptionConfig,
+    audio_url: &str,
+    mime_type: &str,
+) -> Result<String, RssIngestError> {
+    info!("Downloading audio for transcription: {audio_url}");
+    let audio = reqwest::get(audio_url)
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    if audio.len() > MAX_AUDIO_BYTES {
+        return Err(RssIngestError::Transcription(format!(
+            "{audio_url} is {} bytes, more than the {MAX_AUDIO_BYTES} bytes the endpoint accepts.",
+            audio.len()
+        )));
+    }
+
+    let file_name = audio_url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .unwrap_or(FALLBACK_FILE_NAME)
+        .to_string();
+    let file = Part::bytes(audio.to_vec())
+        .file_name(file_name)
+        .mime_str(mime_type)
+        .map_err(|e| RssIngestError::Transcription(format!("Invalid MIME type: {e}")))?;
+    let form = Form::new()
+        .part("file", file)
+        .text("model", config.model.clone());
+
+    let mut request = reqwest::Client::new()
+        .post(&config.endpoint)
+        .multipart(form);
+    if let Some(key) = config.api_key.as_deref().filter(|k| !k.is_empty()) {
+        request = request.bearer_auth(key);
+    }
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        let status = response.status().as_u16();
+        let body = response.text().await.unwrap_or_default();
+        return Err(RssIngestError::Transcription(format!(
+            "Endpoint returned status {status}: {body}"
+        )));
+    }
+
+    let transcript: TranscriptionResponse = response.json().await?;
+    info!(
+        "Transcribed {audio_url} into {} characters.",
+        transcript.text.len()
+    );
+    Ok(transcript.text)
+}
+
+/// Splits a transcript into chunks of whole sentences.
+pub fn chunk_transcript(transcript: &str) -> Vec<String> {
+    SentenceChunker {
+        chunk_size: DEFAULT_CHUNK_SIZE,
+    }
+    .chunk(transcript)
+}
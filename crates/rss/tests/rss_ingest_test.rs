@@ -5,11 +5,17 @@
 //! independent of the main server.
 
 use anyhow::Result;
-use anyrag::ingest::{IngestError, Ingestor};
-use anyrag_rss::RssIngestor;
+use anyrag::{
+    ingest::{IngestError, Ingestor},
+    types::EmbeddingConfig,
+};
+use anyrag_rss::{
+    transcription::{is_audio_enclosure, TranscriptionConfig},
+    RssIngestor,
+};
 use anyrag_test_utils::TestSetup;
 use serde_json::json;
-use wiremock::matchers::{method, path};
+use wiremock::matchers::{header, method, path};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 /// Helper function to create a mock RSS feed.
@@ -92,6 +98,57 @@ async fn test_rss_ingestor_success() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_rss_ingestor_embeds_new_documents() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/feed.xml"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(mock_rss_feed_content())
+                .insert_header("Content-Type", "application/rss+xml"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [{ "embedding": [0.1, 0.2] }, { "embedding": [0.3, 0.4] }]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let setup = TestSetup::new().await?;
+    let ingestor = RssIngestor::new(&setup.db).with_embedding(EmbeddingConfig {
+        api_url: server.uri() + "/v1/embeddings",
+        model_name: "mock-embedding-model".to_string(),
+        api_key: None,
+    });
+    let source = json!({ "url": server.uri() + "/feed.xml" }).to_string();
+
+    // --- Act ---
+    let result = ingestor.ingest(&source, None).await?;
+
+    // --- Assert ---
+    assert_eq!(result.documents_added, 2);
+    let conn = setup.db.connect()?;
+    let count: i64 = conn
+        .query(
+            "SELECT COUNT(*) FROM document_embeddings WHERE model_name = 'mock-embedding-model'",
+            (),
+        )
+        .await?
+        .next()
+        .await?
+        .unwrap()
+        .get(0)?;
+    assert_eq!(count, 2);
+
+    Ok(())
+}
+
 #[tokio::test]
 #[ignore]
 async fn test_rss_ingestor_idempotency() -> Result<()> {
@@ -187,3 +244,163 @@ async fn test_rss_ingestor_parse_error() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_rss_ingestor_fetch_full_content() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    let base = server.uri();
+    let feed = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+        <rss version="2.0">
+        <channel>
+            <title>Test Feed</title>
+            <link>{base}</link>
+            <description>A feed with truncated descriptions.</description>
+            <item>
+                <title>Full Article</title>
+                <link>{base}/articles/full</link>
+                <description>The beginning of...</description>
+            </item>
+            <item>
+                <title>Missing Article</title>
+                <link>{base}/articles/missing</link>
+                <description>Only the description survives.</description>
+            </item>
+        </channel>
+        </rss>"#
+    );
+    Mock::given(method("GET"))
+        .and(path("/feed.xml"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(feed))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/articles/full"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            "<html><body><h1>Full Article</h1><p>The beginning of the story and its whole ending.</p></body></html>",
+        ))
+        .mount(&server)
+        .await;
+
+    let setup = TestSetup::new().await?;
+    let ingestor = RssIngestor::new(&setup.db);
+    let source =
+        json!({ "url": base.clone() + "/feed.xml", "fetch_full_content": true }).to_string();
+
+    // --- Act ---
+    let result = ingestor.ingest(&source, None).await?;
+
+    // --- Assert ---
+    // The missing article does not fail the feed; it keeps its description.
+    assert_eq!(result.documents_added, 2);
+
+    let conn = setup.db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT source_url, content FROM documents ORDER BY source_url",
+            (),
+        )
+        .await?;
+    let full = rows.next().await?.unwrap();
+    assert_eq!(full.get::<String>(0)?, format!("{base}/articles/full"));
+    assert!(full.get::<String>(1)?.contains("its whole ending"));
+    let missing = rows.next().await?.unwrap();
+    assert_eq!(
+        missing.get::<String>(0)?,
+        format!("{base}/articles/missing")
+    );
+    assert!(missing
+        .get::<String>(1)?
+        .contains("Only the description survives."));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rss_ingestor_transcribes_podcast_enclosures() -> Result<()> {
+    // --- Arrange ---
+    let server = MockServer::start().await;
+    let base = server.uri();
+    let feed = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+        <rss version="2.0">
+        <channel>
+            <title>Test Podcast</title>
+            <link>{base}</link>
+            <description>A podcast feed.</description>
+            <item>
+                <title>Episode One</title>
+                <link>{base}/episodes/1</link>
+                <description>Show notes.</description>
+                <enclosure url="{base}/audio/episode1.mp3" length="4" type="audio/mpeg"/>
+            </item>
+            <item>
+                <title>Text Post</title>
+                <link>{base}/posts/1</link>
+                <description>Not an episode.</description>
+            </item>
+        </channel>
+        </rss>"#
+    );
+    Mock::given(method("GET"))
+        .and(path("/feed.xml"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(feed))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/audio/episode1.mp3"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(b"ID3\x03".to_vec()))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/audio/transcriptions"))
+        .and(header("Authorization", "Bearer test-key"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "text": "Welcome to the show. Today we talk about RAG." })),
+        )
+        .mount(&server)
+        .await;
+
+    let setup = TestSetup::new().await?;
+    let mut transcription = TranscriptionConfig::new(format!("{base}/v1/audio/transcriptions"));
+    transcription.api_key = Some("test-key".to_string());
+    let ingestor = RssIngestor::new(&setup.db).with_transcription(transcription);
+    let source = json!({ "url": base.clone() + "/feed.xml" }).to_string();
+
+    // --- Act ---
+    let result = ingestor.ingest(&source, None).await?;
+
+    // --- Assert ---
+    assert_eq!(result.documents_added, 2);
+
+    let conn = setup.db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT source_url, title, content FROM documents ORDER BY source_url",
+            (),
+        )
+        .await?;
+    let episode = rows.next().await?.unwrap();
+    assert_eq!(
+        episode.get::<String>(0)?,
+        format!("{base}/audio/episode1.mp3#part-1")
+    );
+    assert_eq!(episode.get::<String>(1)?, "Episode One (transcript 1/1)");
+    assert!(episode
+        .get::<String>(2)?
+        .contains("Today we talk about RAG."));
+    let post = rows.next().await?.unwrap();
+    assert_eq!(post.get::<String>(0)?, format!("{base}/posts/1"));
+
+    Ok(())
+}
+
+#[test]
+fn test_is_audio_enclosure() {
+    assert!(is_audio_enclosure("audio/mpeg"));
+    assert!(is_audio_enclosure(" Audio/x-m4a"));
+    assert!(!is_audio_enclosure("video/mp4"));
+    assert!(!is_audio_enclosure("application/pdf"));
+}
@@ -63,6 +63,7 @@ async fn ask_question(
         limit: Some(5), // How many KB entries to use for context
         mode: Default::default(),
         use_knowledge_graph: Some(true),
+        collection: None,
     };
 
     let result = handlers::knowledge_search_handler(
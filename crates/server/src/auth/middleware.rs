@@ -1,12 +1,14 @@
 //! # Authentication Middleware
 //!
-//! This module provides the Axum middleware for handling JWT-based authentication.
-//! It defines an `AuthenticatedUser` extractor that can be used in handlers to
-//! ensure a valid user is present and to get their identity.
+//! This module provides the Axum middleware for handling authentication with either a
+//! JWT or an API key. It defines an `AuthenticatedUser` extractor that can be used in
+//! handlers to ensure a valid user is present and to get their identity, and the
+//! `require_route_access` layer that enforces the access rules of every route.
 
 use axum::{
-    extract::FromRequestParts,
-    http::{request::Parts, StatusCode},
+    extract::{FromRequestParts, Request, State},
+    http::{request::Parts, Method, StatusCode},
+    middleware::Next,
     response::{IntoResponse, Response},
     Json,
 };
@@ -14,14 +16,158 @@ use axum_extra::{
     headers::{authorization::Bearer, Authorization},
     TypedHeader,
 };
-use core_access::{get_or_create_user, User, GUEST_USER_IDENTIFIER};
-use jsonwebtoken::{decode, DecodingKey, Validation};
+use core_access::{
+    api_keys::authenticate_api_key,
+    get_or_create_user, has_permission,
+    permissions::{
+        ADMIN_API_KEYS, ADMIN_BACKUPS, ADMIN_CACHE, ADMIN_GRAPH, ADMIN_STATS, ADMIN_USERS,
+        INGEST_WRITE, PROMPT_EXECUTE, SEARCH_READ,
+    },
+    sessions::is_session_active,
+    usage::{get_usage, record_ai_call},
+    ApiKey, ApiKeyError, ApiKeyScope, UsageError, User, GUEST_USER_IDENTIFIER,
+};
+use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::{error, info, warn};
 
-use crate::state::AppState;
+use crate::{
+    auth::{
+        oidc::{is_shared_secret_algorithm, OidcError},
+        session::jwt_secret,
+    },
+    state::AppState,
+};
+
+/// The header carrying an API key, as an alternative to `Authorization: Bearer`.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// What a caller needs to access the routes under a path prefix.
+struct RouteAccess {
+    prefix: &'static str,
+    /// The permission the user needs.
+    permission: &'static str,
+    /// The scope an API key additionally needs.
+    scope: ApiKeyScope,
+    /// The permission and scope that writes (`POST`, `PUT`, `PATCH` and `DELETE`
+    /// requests) need instead, for routes that are both read and written.
+    write: Option<(&'static str, ApiKeyScope)>,
+}
+
+const fn route(prefix: &'static str, permission: &'static str, scope: ApiKeyScope) -> RouteAccess {
+    RouteAccess {
+        prefix,
+        permission,
+        scope,
+        write: None,
+    }
+}
+
+/// A route that is read with `permission` and `scope`, and written with `INGEST_WRITE`
+/// and the `ingest` scope.
+const fn writable_route(
+    prefix: &'static str,
+    permission: &'static str,
+    scope: ApiKeyScope,
+) -> RouteAccess {
+    RouteAccess {
+        prefix,
+        permission,
+        scope,
+        write: Some((INGEST_WRITE, ApiKeyScope::Ingest)),
+    }
+}
+
+/// The access rules of the routes. Routes that are not listed, such as `/health` or
+/// `/auth/me`, are open to every caller. The first rule whose prefix matches a path
+/// applies.
+const ROUTE_ACCESS: &[RouteAccess] = &[
+    route("/admin/api-keys", ADMIN_API_KEYS, ApiKeyScope::Admin),
+    route("/admin/backups", ADMIN_BACKUPS, ApiKeyScope::Admin),
+    route("/admin/graph", ADMIN_GRAPH, ApiKeyScope::Admin),
+    route("/admin/answer-cache", ADMIN_CACHE, ApiKeyScope::Admin),
+    route("/admin/stats", ADMIN_STATS, ApiKeyScope::Admin),
+    route("/users", ADMIN_USERS, ApiKeyScope::Admin),
+    route("/ingest", INGEST_WRITE, ApiKeyScope::Ingest),
+    route("/embed", INGEST_WRITE, ApiKeyScope::Ingest),
+    route("/graph/build", INGEST_WRITE, ApiKeyScope::Ingest),
+    route("/graph", SEARCH_READ, ApiKeyScope::Search),
+    route("/prompt", PROMPT_EXECUTE, ApiKeyScope::Prompt),
+    route("/db", PROMPT_EXECUTE, ApiKeyScope::Prompt),
+    route("/experiments", PROMPT_EXECUTE, ApiKeyScope::Prompt),
+    route("/feedback", PROMPT_EXECUTE, ApiKeyScope::Prompt),
+    route("/search", SEARCH_READ, ApiKeyScope::Search),
+    route("/chat", SEARCH_READ, ApiKeyScope::Search),
+    route("/ws", SEARCH_READ, ApiKeyScope::Search),
+    route("/gen", SEARCH_READ, ApiKeyScope::Search),
+    route("/knowledge/faq/generate", INGEST_WRITE, ApiKeyScope::Ingest),
+    route("/knowledge", SEARCH_READ, ApiKeyScope::Search),
+    writable_route("/documents", SEARCH_READ, ApiKeyScope::Search),
+    writable_route("/collections", SEARCH_READ, ApiKeyScope::Search),
+    route("/examples", SEARCH_READ, ApiKeyScope::Search),
+    writable_route("/orgs", SEARCH_READ, ApiKeyScope::Search),
+];
+
+/// The routes that count against the monthly AI call quota.
+const AI_CALL_ROUTES: &[&str] = &[
+    "/prompt",
+    "/chat",
+    "/ws",
+    "/gen",
+    "/embed",
+    "/search/vector",
+    "/search/hybrid",
+    "/search/knowledge",
+    "/search/graph",
+    "/knowledge/faq/generate",
+];
+
+/// The routes that are refused once the monthly document quota is used up.
+const INGEST_ROUTES: &[&str] = &["/ingest"];
+
+/// Whether `path` is `prefix` or one of the routes below it.
+fn matches_prefix(path: &str, prefix: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+fn matches_any_prefix(path: &str, prefixes: &[&str]) -> bool {
+    prefixes.iter().any(|prefix| matches_prefix(path, prefix))
+}
+
+fn route_access(path: &str) -> Option<&'static RouteAccess> {
+    ROUTE_ACCESS
+        .iter()
+        .find(|access| matches_prefix(path, access.prefix))
+}
+
+fn is_write(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    )
+}
+
+/// Returns the permission and scope a `method` request to `path` needs.
+fn route_requirement(method: &Method, path: &str) -> Option<(&'static str, ApiKeyScope)> {
+    let access = route_access(path)?;
+    match access.write {
+        Some(write) if is_write(method) => Some(write),
+        _ => Some((access.permission, access.scope)),
+    }
+}
+
+/// Returns the scope an API key needs to call the route at `path` with `method`.
+pub fn required_scope(method: &Method, path: &str) -> Option<ApiKeyScope> {
+    route_requirement(method, path).map(|(_, scope)| scope)
+}
+
+/// Returns the permission a user needs to call the route at `path` with `method`.
+pub fn required_permission(method: &Method, path: &str) -> Option<&'static str> {
+    route_requirement(method, path).map(|(permission, _)| permission)
+}
 
 /// Represents the claims we expect to find in the JWT.
 #[derive(Debug, Serialize, Deserialize)]
@@ -33,6 +179,9 @@ pub struct Claims {
     /// The user's database ID (UUID). This is optional and mainly for testing.
     #[serde(default)]
     pub user_id: String,
+    /// The session the token was issued for. Tokens of a revoked session are rejected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
 }
 
 /// An Axum extractor that provides the currently authenticated user.
@@ -42,6 +191,16 @@ pub struct Claims {
 /// 2.  **Valid Token Present**: Resolves to the authenticated user.
 /// 3.  **Invalid/Expired Token Present**: Rejects the request with a `401 Unauthorized`.
 ///
+/// Bearer tokens are either signed with the shared `JWT_SECRET` or, when OpenID
+/// Connect is configured, by the provider's keys (see `auth::oidc`). Tokens the
+/// server issued for a session are rejected once the session is revoked.
+///
+/// An `X-Api-Key` header takes precedence over the `Authorization` header and resolves
+/// to the owner of the key. A key without the scope the route requires is rejected
+/// with a `403 Forbidden`, as is a user whose role lacks the route's permission.
+/// Callers over their rate limit or monthly quota are rejected with a `429 Too Many
+/// Requests`.
+///
 /// This ensures that handlers always receive a valid `User` object (either
 /// guest or authenticated), simplifying the application logic.
 #[derive(Debug, Clone)]
@@ -66,6 +225,26 @@ impl FromRequestParts<AppState> for AuthenticatedUser {
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
+        // The `require_route_access` layer already authenticated the request.
+        if let Some(user) = parts.extensions.get::<AuthenticatedUser>() {
+            return Ok(user.clone());
+        }
+
+        let path = parts.uri.path().to_string();
+        let method = parts.method.clone();
+        if let Some(api_key) = parts.headers.get(API_KEY_HEADER) {
+            let api_key = api_key.to_str().map_err(|_| {
+                AuthError(
+                    StatusCode::BAD_REQUEST,
+                    "Invalid X-Api-Key header format.".to_string(),
+                )
+            })?;
+            let (user, api_key) = authenticate_with_api_key(state, api_key, &method, &path).await?;
+            let user = authorize(user, &method, &path)?;
+            enforce_limits(state, &user.0, &format!("api_key:{}", api_key.id), &path).await?;
+            return Ok(user);
+        }
+
         // Attempt to extract the token from the `Authorization: Bearer <token>` header.
         // This is now optional.
         let bearer_header =
@@ -83,50 +262,12 @@ impl FromRequestParts<AppState> for AuthenticatedUser {
         let user = if let Some(TypedHeader(Authorization(bearer))) = bearer_header {
             // Case 1: Token is present. Validate it.
             info!("Authorization header found, attempting to validate JWT.");
-            let jwt_secret =
-                std::env::var("JWT_SECRET").unwrap_or_else(|_| "a-secure-secret-key".to_string());
+            let identifier = verify_bearer_token(state, bearer.token()).await?;
 
-            let token_data = decode::<Claims>(
-                bearer.token(),
-                &DecodingKey::from_secret(jwt_secret.as_ref()),
-                &Validation::default(),
-            )
-            .map_err(|e| {
-                warn!("JWT validation failed: {}", e);
-                AuthError(
-                    StatusCode::UNAUTHORIZED,
-                    "Invalid or expired token.".to_string(),
-                )
-            })?;
-
-            // Manually verify the expiration to be absolutely sure.
-            // The `jsonwebtoken` crate should handle this, but adding an explicit
-            // check makes the logic more robust against subtle configuration issues.
-            let current_timestamp = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .map_err(|_| {
-                    AuthError(
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "System time is before UNIX EPOCH.".to_string(),
-                    )
-                })?
-                .as_secs();
-
-            if token_data.claims.exp < current_timestamp as usize {
-                warn!(
-                    "Token has expired. exp: {}, current: {}",
-                    token_data.claims.exp, current_timestamp
-                );
-                return Err(AuthError(
-                    StatusCode::UNAUTHORIZED,
-                    "Invalid or expired token.".to_string(),
-                ));
-            }
-
-            // The claims `sub` is the source of truth for the user's identity.
+            // The token's subject is the source of truth for the user's identity.
             // We use it to deterministically find or create the user in our database,
             // which holds the authoritative role.
-            get_or_create_user(&state.sqlite_provider.db, &token_data.claims.sub, None).await
+            get_or_create_user(&state.sqlite_provider.db, &identifier, None).await
         } else {
             // Case 2: No token is present. Use the guest user.
             info!("No Authorization header found, using guest user.");
@@ -142,6 +283,265 @@ impl FromRequestParts<AppState> for AuthenticatedUser {
         })?;
 
         // If all checks pass, return the authenticated user (either real or guest).
-        Ok(AuthenticatedUser(user))
+        let user = authorize(user, &method, &path)?;
+        enforce_limits(state, &user.0, &format!("user:{}", user.0.id), &path).await?;
+        Ok(user)
+    }
+}
+
+/// Middleware enforcing `ROUTE_ACCESS`, including on routes whose handler does not
+/// take the `AuthenticatedUser` extractor. The user is resolved as the extractor does,
+/// and kept in the request's extensions for the handler. Routes that are not listed
+/// pass through untouched.
+pub async fn require_route_access(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if route_access(request.uri().path()).is_none() {
+        return next.run(request).await;
     }
+    let (mut parts, body) = request.into_parts();
+    match AuthenticatedUser::from_request_parts(&mut parts, &state).await {
+        Ok(user) => {
+            parts.extensions.insert(user);
+            next.run(Request::from_parts(parts, body)).await
+        }
+        Err(rejection) => rejection.into_response(),
+    }
+}
+
+/// Validates a bearer token and returns the identifier of its user. Tokens signed
+/// with the shared `JWT_SECRET` (HS256) are checked locally; any other token must be
+/// signed by the configured OpenID Connect provider.
+async fn verify_bearer_token(state: &AppState, token: &str) -> Result<String, AuthError> {
+    let unauthorized = || {
+        AuthError(
+            StatusCode::UNAUTHORIZED,
+            "Invalid or expired token.".to_string(),
+        )
+    };
+    let header = decode_header(token).map_err(|e| {
+        warn!("JWT validation failed: {}", e);
+        unauthorized()
+    })?;
+
+    let oidc = match state.oidc.as_ref() {
+        Some(oidc) if !is_shared_secret_algorithm(header.alg) => oidc,
+        _ => return verify_shared_secret_token(state, token).await,
+    };
+
+    match oidc.verify(token).await {
+        Ok(claims) => Ok(claims.sub),
+        Err(OidcError::InvalidToken(e)) => {
+            warn!("OpenID Connect token validation failed: {}", e);
+            Err(unauthorized())
+        }
+        Err(e) => {
+            error!("Failed to verify OpenID Connect token: {}", e);
+            Err(AuthError(
+                StatusCode::BAD_GATEWAY,
+                format!("Could not verify token: {e}"),
+            ))
+        }
+    }
+}
+
+/// Validates a token signed with the shared `JWT_SECRET` and returns its subject.
+/// The token must be issued for a session, and is only valid while it is active.
+async fn verify_shared_secret_token(state: &AppState, token: &str) -> Result<String, AuthError> {
+    let token_data = decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(jwt_secret().as_ref()),
+        &Validation::default(),
+    )
+    .map_err(|e| {
+        warn!("JWT validation failed: {}", e);
+        AuthError(
+            StatusCode::UNAUTHORIZED,
+            "Invalid or expired token.".to_string(),
+        )
+    })?;
+
+    // Manually verify the expiration to be absolutely sure.
+    // The `jsonwebtoken` crate should handle this, but adding an explicit
+    // check makes the logic more robust against subtle configuration issues.
+    let current_timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| {
+            AuthError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "System time is before UNIX EPOCH.".to_string(),
+            )
+        })?
+        .as_secs();
+
+    if token_data.claims.exp < current_timestamp as usize {
+        warn!(
+            "Token has expired. exp: {}, current: {}",
+            token_data.claims.exp, current_timestamp
+        );
+        return Err(AuthError(
+            StatusCode::UNAUTHORIZED,
+            "Invalid or expired token.".to_string(),
+        ));
+    }
+
+    let Some(session_id) = &token_data.claims.sid else {
+        warn!("Rejecting a token signed with the shared secret without a session.");
+        return Err(AuthError(
+            StatusCode::UNAUTHORIZED,
+            "Invalid or expired token.".to_string(),
+        ));
+    };
+    let active = is_session_active(&state.sqlite_provider.db, session_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to look up session '{}': {}", session_id, e);
+            AuthError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Could not validate session: {e}"),
+            )
+        })?;
+    if !active {
+        warn!(
+            "Rejecting a token of the revoked or expired session '{}'.",
+            session_id
+        );
+        return Err(AuthError(
+            StatusCode::UNAUTHORIZED,
+            "The session of this token has been revoked or has expired.".to_string(),
+        ));
+    }
+    Ok(token_data.claims.sub)
+}
+
+/// Checks that the user's role grants the permission the request requires.
+fn authorize(user: User, method: &Method, path: &str) -> Result<AuthenticatedUser, AuthError> {
+    let missing_permission =
+        required_permission(method, path).filter(|p| !has_permission(&user, p));
+    if let Some(permission) = missing_permission {
+        warn!(
+            "User '{}' with role '{}' lacks the '{}' permission for '{} {}'.",
+            user.id, user.role, permission, method, path
+        );
+        return Err(AuthError(
+            StatusCode::FORBIDDEN,
+            format!("Forbidden: missing the '{permission}' permission."),
+        ));
+    }
+    Ok(AuthenticatedUser(user))
+}
+
+/// Resolves an API key to its owner, checking that it grants access to the request.
+async fn authenticate_with_api_key(
+    state: &AppState,
+    api_key: &str,
+    method: &Method,
+    path: &str,
+) -> Result<(User, ApiKey), AuthError> {
+    info!("X-Api-Key header found, attempting to validate API key.");
+    let (user, api_key) = authenticate_api_key(&state.sqlite_provider.db, api_key)
+        .await
+        .map_err(|e| match e {
+            ApiKeyError::InvalidKey => {
+                warn!("API key validation failed.");
+                AuthError(StatusCode::UNAUTHORIZED, "Invalid API key.".to_string())
+            }
+            e => {
+                error!("Failed to validate API key: {}", e);
+                AuthError(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Could not validate API key: {e}"),
+                )
+            }
+        })?;
+
+    let missing_scope = required_scope(method, path).filter(|scope| !api_key.has_scope(*scope));
+    if let Some(scope) = missing_scope {
+        warn!(
+            "API key '{}' lacks the '{}' scope for '{} {}'.",
+            api_key.id, scope, method, path
+        );
+        return Err(AuthError(
+            StatusCode::FORBIDDEN,
+            format!("This API key does not have the '{scope}' scope."),
+        ));
+    }
+
+    Ok((user, api_key))
+}
+
+/// Applies the rate limit of the caller, identified by `rate_limit_key`, and the
+/// monthly quotas of the user to a request. Exceeding either is a `429 Too Many
+/// Requests`.
+async fn enforce_limits(
+    state: &AppState,
+    user: &User,
+    rate_limit_key: &str,
+    path: &str,
+) -> Result<(), AuthError> {
+    if let Some(rate_limiter) = &state.rate_limiter {
+        if let Err(retry_after) = rate_limiter.try_acquire(rate_limit_key) {
+            warn!("Rate limit exceeded by '{}' on '{}'.", rate_limit_key, path);
+            return Err(AuthError(
+                StatusCode::TOO_MANY_REQUESTS,
+                format!(
+                    "Rate limit exceeded. Retry in {} seconds.",
+                    retry_after.as_secs().max(1)
+                ),
+            ));
+        }
+    }
+
+    let Some(quotas) = &state.config.quotas else {
+        return Ok(());
+    };
+    let is_ai_call = matches_any_prefix(path, AI_CALL_ROUTES);
+    let is_ingest = matches_any_prefix(path, INGEST_ROUTES);
+    if !is_ai_call && !is_ingest {
+        return Ok(());
+    }
+
+    let db = &state.sqlite_provider.db;
+    let usage = get_usage(db, &user.id).await.map_err(quota_lookup_error)?;
+    let exceeded = match (is_ai_call, is_ingest) {
+        (true, _) => quotas
+            .ai_calls_per_month
+            .filter(|quota| usage.ai_calls >= *quota)
+            .map(|quota| format!("{quota} AI calls")),
+        (_, true) => quotas
+            .documents_per_month
+            .filter(|quota| usage.documents_ingested >= *quota)
+            .map(|quota| format!("{quota} ingested documents")),
+        _ => None,
+    };
+    if let Some(quota) = exceeded {
+        warn!(
+            "User '{}' exceeded the monthly quota of {} in {}.",
+            user.id, quota, usage.period
+        );
+        return Err(AuthError(
+            StatusCode::TOO_MANY_REQUESTS,
+            format!(
+                "Monthly quota of {quota} exceeded for {}. See /me/usage.",
+                usage.period
+            ),
+        ));
+    }
+
+    if is_ai_call {
+        record_ai_call(db, &user.id)
+            .await
+            .map_err(quota_lookup_error)?;
+    }
+    Ok(())
+}
+
+fn quota_lookup_error(e: UsageError) -> AuthError {
+    error!("Failed to look up usage: {}", e);
+    AuthError(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!("Could not check the usage quota: {e}"),
+    )
 }
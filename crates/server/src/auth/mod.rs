@@ -1 +1,5 @@
 pub mod middleware;
+pub mod oidc;
+pub mod org;
+pub mod rate_limit;
+pub mod session;
@@ -0,0 +1,306 @@
+//! # OpenID Connect
+//!
+//! This module verifies tokens issued by an external OpenID Connect provider, such as
+//! Keycloak, Auth0 or Google. The provider's discovery document is fetched on first
+//! use, and tokens are checked against the signing keys it publishes (its JWKS), the
+//! configured issuer, and the configured audience. It also implements the server side
+//! of the authorization code flow behind `/auth/login/oidc`.
+
+use anyrag::types::OidcConfig;
+use jsonwebtoken::{
+    decode, decode_header,
+    jwk::{Jwk, JwkSet},
+    Algorithm, DecodingKey, Validation,
+};
+use reqwest::Url;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tokio::sync::{Mutex, OnceCell, RwLock};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// The path of the discovery document, relative to the issuer URL.
+pub const DISCOVERY_PATH: &str = "/.well-known/openid-configuration";
+/// How long a login started with `/auth/login/oidc` may take to complete.
+const LOGIN_STATE_TTL: Duration = Duration::from_secs(600);
+/// The key under which a JWK without a `kid` is cached.
+const NO_KEY_ID: &str = "";
+
+/// An error from verifying a token or signing a user in with the provider.
+#[derive(Debug)]
+pub enum OidcError {
+    /// OpenID Connect is not configured, or lacks a setting the operation needs.
+    NotConfigured(String),
+    /// The provider could not be reached or returned an unexpected response.
+    Provider(String),
+    /// The token is malformed, expired, or was not issued for this server.
+    InvalidToken(String),
+    /// The login callback carries a state this server did not issue, or it expired.
+    InvalidState,
+}
+
+impl std::fmt::Display for OidcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OidcError::NotConfigured(msg) => write!(f, "OpenID Connect is not configured: {msg}"),
+            OidcError::Provider(msg) => write!(f, "OpenID Connect provider error: {msg}"),
+            OidcError::InvalidToken(msg) => write!(f, "Invalid token: {msg}"),
+            OidcError::InvalidState => write!(f, "Unknown or expired login state."),
+        }
+    }
+}
+
+impl std::error::Error for OidcError {}
+
+impl From<reqwest::Error> for OidcError {
+    fn from(err: reqwest::Error) -> Self {
+        OidcError::Provider(err.to_string())
+    }
+}
+
+/// The parts of the provider's discovery document the server uses.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub jwks_uri: String,
+    #[serde(default)]
+    pub authorization_endpoint: Option<String>,
+    #[serde(default)]
+    pub token_endpoint: Option<String>,
+}
+
+/// The claims read from a verified token.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OidcClaims {
+    /// The subject of the token, which identifies the user at the provider.
+    pub sub: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    /// The nonce of the login that issued an ID token.
+    #[serde(default)]
+    pub nonce: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+/// A client for one OpenID Connect provider, shared through the `AppState`.
+pub struct OidcClient {
+    config: OidcConfig,
+    http: reqwest::Client,
+    metadata: OnceCell<ProviderMetadata>,
+    /// The provider's signing keys, keyed by their `kid`.
+    keys: RwLock<HashMap<String, DecodingKey>>,
+    /// The states of logins in progress, with the time they were started.
+    pending_logins: Mutex<HashMap<String, Instant>>,
+}
+
+impl OidcClient {
+    /// Creates a client for the configured provider. The provider is only contacted
+    /// when the first token is verified.
+    pub fn new(config: OidcConfig) -> Result<Self, OidcError> {
+        if config.audience.is_none() && config.client_id.is_none() {
+            return Err(OidcError::NotConfigured(
+                "either 'audience' or 'client_id' is required".to_string(),
+            ));
+        }
+        Ok(Self {
+            config,
+            http: reqwest::Client::new(),
+            metadata: OnceCell::new(),
+            keys: RwLock::new(HashMap::new()),
+            pending_logins: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Returns the provider's discovery document, fetching it on first use.
+    pub async fn metadata(&self) -> Result<&ProviderMetadata, OidcError> {
+        self.metadata
+            .get_or_try_init(|| async {
+                let issuer_url = self.config.issuer_url.trim_end_matches('/');
+                let url = format!("{issuer_url}{DISCOVERY_PATH}");
+                info!("Fetching the OpenID Connect discovery document from '{url}'.");
+                let metadata: ProviderMetadata = self
+                    .http
+                    .get(&url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                if metadata.issuer.trim_end_matches('/') != issuer_url {
+                    return Err(OidcError::Provider(format!(
+                        "the discovery document is for issuer '{}', not '{issuer_url}'",
+                        metadata.issuer
+                    )));
+                }
+                Ok(metadata)
+            })
+            .await
+    }
+
+    /// Verifies the signature, issuer, audience and expiry of a token.
+    pub async fn verify(&self, token: &str) -> Result<OidcClaims, OidcError> {
+        let header = decode_header(token).map_err(|e| OidcError::InvalidToken(e.to_string()))?;
+        if is_shared_secret_algorithm(header.alg) {
+            return Err(OidcError::InvalidToken(format!(
+                "'{:?}' tokens are not signed by the provider",
+                header.alg
+            )));
+        }
+        let key_id = header.kid.unwrap_or_else(|| NO_KEY_ID.to_string());
+        let key = self.decoding_key(&key_id).await?;
+
+        let metadata = self.metadata().await?;
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[metadata.issuer.as_str()]);
+        let audiences: Vec<&str> = [&self.config.audience, &self.config.client_id]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        validation.set_audience(&audiences);
+
+        decode::<OidcClaims>(token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| OidcError::InvalidToken(e.to_string()))
+    }
+
+    /// Starts a login, returning the provider URL to redirect the user to.
+    pub async fn authorization_url(&self) -> Result<String, OidcError> {
+        let client_id = required(&self.config.client_id, "client_id")?;
+        let redirect_url = required(&self.config.redirect_url, "redirect_url")?;
+        let metadata = self.metadata().await?;
+        let endpoint = metadata.authorization_endpoint.as_deref().ok_or_else(|| {
+            OidcError::Provider("the provider has no authorization endpoint".to_string())
+        })?;
+
+        // The state also serves as the nonce of the ID token issued for the login.
+        let state = Uuid::new_v4().to_string();
+        let url = Url::parse_with_params(
+            endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", client_id),
+                ("redirect_uri", redirect_url),
+                ("scope", self.config.scopes.as_str()),
+                ("state", state.as_str()),
+                ("nonce", state.as_str()),
+            ],
+        )
+        .map_err(|e| OidcError::Provider(format!("invalid authorization endpoint: {e}")))?;
+
+        let mut pending_logins = self.pending_logins.lock().await;
+        pending_logins.retain(|_, started| started.elapsed() < LOGIN_STATE_TTL);
+        pending_logins.insert(state, Instant::now());
+        Ok(url.to_string())
+    }
+
+    /// Completes a login: checks the state, exchanges the code for an ID token at
+    /// the provider, and verifies it. Returns the claims of the ID token.
+    pub async fn complete_login(&self, code: &str, state: &str) -> Result<OidcClaims, OidcError> {
+        let started = self.pending_logins.lock().await.remove(state);
+        if !started.is_some_and(|started| started.elapsed() < LOGIN_STATE_TTL) {
+            return Err(OidcError::InvalidState);
+        }
+
+        let client_id = required(&self.config.client_id, "client_id")?;
+        let redirect_url = required(&self.config.redirect_url, "redirect_url")?;
+        let metadata = self.metadata().await?;
+        let endpoint = metadata
+            .token_endpoint
+            .as_deref()
+            .ok_or_else(|| OidcError::Provider("the provider has no token endpoint".to_string()))?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_url),
+            ("client_id", client_id),
+        ];
+        if let Some(client_secret) = &self.config.client_secret {
+            form.push(("client_secret", client_secret.as_str()));
+        }
+        let response: TokenResponse = self
+            .http
+            .post(endpoint)
+            .form(&form)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let id_token = response.id_token.ok_or_else(|| {
+            OidcError::Provider("the token response has no 'id_token'".to_string())
+        })?;
+
+        let claims = self.verify(&id_token).await?;
+        if claims.nonce.as_deref() != Some(state) {
+            return Err(OidcError::InvalidToken(
+                "the ID token was not issued for this login".to_string(),
+            ));
+        }
+        Ok(claims)
+    }
+
+    /// Returns the signing key with the ID, refreshing the cached JWKS once when the
+    /// key is unknown, e.g. after the provider rotated its keys.
+    async fn decoding_key(&self, key_id: &str) -> Result<DecodingKey, OidcError> {
+        if let Some(key) = self.keys.read().await.get(key_id) {
+            return Ok(key.clone());
+        }
+
+        let metadata = self.metadata().await?;
+        info!(
+            "Fetching the OpenID Connect signing keys from '{}'.",
+            metadata.jwks_uri
+        );
+        let jwks: JwkSet = self
+            .http
+            .get(&metadata.jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let mut keys = self.keys.write().await;
+        keys.clear();
+        for jwk in &jwks.keys {
+            match DecodingKey::from_jwk(jwk) {
+                Ok(key) => {
+                    keys.insert(jwk_id(jwk), key);
+                }
+                Err(e) => warn!("Skipping unsupported signing key '{}': {}", jwk_id(jwk), e),
+            }
+        }
+        keys.get(key_id)
+            .cloned()
+            .ok_or_else(|| OidcError::InvalidToken(format!("unknown signing key '{key_id}'")))
+    }
+}
+
+/// Returns a setting the login flow needs.
+fn required<'a>(value: &'a Option<String>, name: &str) -> Result<&'a str, OidcError> {
+    value
+        .as_deref()
+        .ok_or_else(|| OidcError::NotConfigured(format!("'{name}' is required to sign in")))
+}
+
+fn jwk_id(jwk: &Jwk) -> String {
+    jwk.common
+        .key_id
+        .clone()
+        .unwrap_or_else(|| NO_KEY_ID.to_string())
+}
+
+/// Whether tokens signed with the algorithm are verified with the shared `JWT_SECRET`
+/// rather than a key of the provider.
+pub fn is_shared_secret_algorithm(alg: Algorithm) -> bool {
+    matches!(alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512)
+}
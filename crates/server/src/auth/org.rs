@@ -0,0 +1,32 @@
+//! # Organization Context
+//!
+//! Search and ingest requests can act within an organization by naming it in the
+//! `X-Org-Id` header. Searches then also see the documents shared with the
+//! organization, and ingested documents are shared with it. Only members of the
+//! organization may use it as their context.
+
+use axum::http::HeaderMap;
+use core_access::{organizations::require_membership, OrgError, User};
+
+use crate::{errors::AppError, state::AppState};
+
+/// The header naming the organization a request acts within.
+pub const ORG_ID_HEADER: &str = "x-org-id";
+
+/// Returns the organization named by the `X-Org-Id` header, after checking that the
+/// user is one of its members. Requests without the header have no organization.
+pub async fn org_context(
+    app_state: &AppState,
+    user: &User,
+    headers: &HeaderMap,
+) -> Result<Option<String>, AppError> {
+    let Some(org_id) = headers.get(ORG_ID_HEADER) else {
+        return Ok(None);
+    };
+    // A header that is not text cannot name an organization.
+    let org_id = org_id
+        .to_str()
+        .map_err(|_| OrgError::NotFound(String::from_utf8_lossy(org_id.as_bytes()).into_owned()))?;
+    require_membership(&app_state.sqlite_provider.db, org_id, &user.id).await?;
+    Ok(Some(org_id.to_string()))
+}
@@ -0,0 +1,81 @@
+//! # Rate Limiting
+//!
+//! This module implements an in-memory token bucket per caller. Each bucket holds up to
+//! `burst` tokens and refills at `requests_per_minute`; a request takes one token, and
+//! is rejected while the bucket is empty. Buckets are kept per process, so each server
+//! instance enforces the limit on its own.
+
+use anyrag::types::RateLimitConfig;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Above this many buckets, full ones are dropped, as they are equivalent to new ones.
+const MAX_IDLE_BUCKETS: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// Token buckets keyed by caller, e.g. a user or an API key.
+pub struct RateLimiter {
+    requests_per_minute: u32,
+    burst: u32,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            requests_per_minute: config.requests_per_minute,
+            burst: config.burst.unwrap_or(config.requests_per_minute),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn requests_per_minute(&self) -> u32 {
+        self.requests_per_minute
+    }
+
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// Takes a token from the caller's bucket. When the bucket is empty, returns how
+    /// long until the next token is available.
+    pub fn try_acquire(&self, key: &str) -> Result<(), Duration> {
+        let capacity = f64::from(self.burst);
+        let refill_per_sec = f64::from(self.requests_per_minute) / 60.0;
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() > MAX_IDLE_BUCKETS {
+            buckets.retain(|_, bucket| {
+                let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+                bucket.tokens + elapsed * refill_per_sec < capacity
+            });
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill_per_sec).min(capacity);
+        bucket.updated_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        if refill_per_sec <= 0.0 {
+            return Err(Duration::MAX);
+        }
+        Err(Duration::from_secs_f64(
+            (1.0 - bucket.tokens) / refill_per_sec,
+        ))
+    }
+}
@@ -0,0 +1,100 @@
+//! # Sessions
+//!
+//! This module issues the tokens of a sign-in session: a short-lived access token,
+//! signed with the shared `JWT_SECRET` and naming its session in the `sid` claim, and
+//! a long-lived refresh token that `/auth/refresh` exchanges for new tokens. The
+//! middleware rejects access tokens whose session was revoked.
+
+use crate::{auth::middleware::Claims, errors::AppError, state::AppState};
+use chrono::Duration;
+use core_access::{
+    sessions::{create_session, refresh_session},
+    NewSession, User,
+};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// How long an access token is valid. Clients refresh it with their refresh token.
+pub const ACCESS_TOKEN_TTL_SECS: u64 = 15 * 60;
+/// How long a refresh token is valid. Each refresh issues a new one.
+pub const REFRESH_TOKEN_TTL_DAYS: i64 = 30;
+pub const TOKEN_TYPE: &str = "Bearer";
+const DEFAULT_JWT_SECRET: &str = "a-secure-secret-key";
+
+/// The tokens of a session.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionTokens {
+    /// Sent as `Authorization: Bearer` until it expires.
+    pub access_token: String,
+    /// Exchanged at `/auth/refresh` for new tokens. It can only be used once.
+    pub refresh_token: String,
+    pub token_type: String,
+    /// The lifetime of the access token, in seconds.
+    pub expires_in: u64,
+    pub session_id: String,
+}
+
+/// Returns the secret tokens issued by the server are signed with.
+pub fn jwt_secret() -> String {
+    std::env::var("JWT_SECRET").unwrap_or_else(|_| DEFAULT_JWT_SECRET.to_string())
+}
+
+/// Starts a session for a user who just signed in as `subject`.
+pub async fn start_session(
+    app_state: &AppState,
+    user: &User,
+    subject: &str,
+) -> Result<SessionTokens, AppError> {
+    let session = create_session(
+        &app_state.sqlite_provider.db,
+        user,
+        subject,
+        Duration::days(REFRESH_TOKEN_TTL_DAYS),
+    )
+    .await?;
+    issue_tokens(session)
+}
+
+/// Exchanges a refresh token for new tokens of the same session.
+pub async fn refresh(app_state: &AppState, refresh_token: &str) -> Result<SessionTokens, AppError> {
+    let session = refresh_session(
+        &app_state.sqlite_provider.db,
+        refresh_token,
+        Duration::days(REFRESH_TOKEN_TTL_DAYS),
+    )
+    .await?;
+    issue_tokens(session)
+}
+
+fn issue_tokens(new_session: NewSession) -> Result<SessionTokens, AppError> {
+    let NewSession {
+        refresh_token,
+        session,
+    } = new_session;
+    let issued_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("System time is before UNIX EPOCH: {e}")))?
+        .as_secs();
+    let claims = Claims {
+        sub: session.subject,
+        exp: (issued_at + ACCESS_TOKEN_TTL_SECS) as usize,
+        user_id: session.user_id,
+        sid: Some(session.id.clone()),
+    };
+    let access_token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(jwt_secret().as_ref()),
+    )
+    .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to sign access token: {e}")))?;
+
+    Ok(SessionTokens {
+        access_token,
+        refresh_token,
+        token_type: TOKEN_TYPE.to_string(),
+        expires_in: ACCESS_TOKEN_TTL_SECS,
+        session_id: session.id,
+    })
+}
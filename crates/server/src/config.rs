@@ -81,6 +81,14 @@ fn build_default_tasks() -> HashMap<String, ConfigValue> {
                 tasks::KNOWLEDGE_DISTILLATION_USER_PROMPT,
             ),
         ),
+        (
+            "faq_generation",
+            (
+                "gemini_default",
+                tasks::KNOWLEDGE_DISTILLATION_SYSTEM_PROMPT,
+                tasks::KNOWLEDGE_DISTILLATION_USER_PROMPT,
+            ),
+        ),
         (
             "query_analysis",
             (
@@ -97,6 +105,14 @@ fn build_default_tasks() -> HashMap<String, ConfigValue> {
                 tasks::LLM_RERANK_USER_PROMPT,
             ),
         ),
+        (
+            "hyde_generation",
+            (
+                "gemini_default",
+                tasks::HYDE_GENERATION_SYSTEM_PROMPT,
+                tasks::HYDE_GENERATION_USER_PROMPT,
+            ),
+        ),
         (
             "knowledge_augmentation",
             (
@@ -113,6 +129,78 @@ fn build_default_tasks() -> HashMap<String, ConfigValue> {
                 tasks::KNOWLEDGE_METADATA_EXTRACTION_USER_PROMPT,
             ),
         ),
+        (
+            "knowledge_graph_extraction",
+            (
+                "gemini_default",
+                tasks::KNOWLEDGE_GRAPH_EXTRACTION_SYSTEM_PROMPT,
+                tasks::KNOWLEDGE_GRAPH_EXTRACTION_USER_PROMPT,
+            ),
+        ),
+        (
+            "pii_detection",
+            (
+                "gemini_default",
+                tasks::PII_DETECTION_SYSTEM_PROMPT,
+                tasks::PII_DETECTION_USER_PROMPT,
+            ),
+        ),
+        (
+            "content_moderation",
+            (
+                "gemini_default",
+                tasks::CONTENT_MODERATION_SYSTEM_PROMPT,
+                tasks::CONTENT_MODERATION_USER_PROMPT,
+            ),
+        ),
+        (
+            "document_summarization",
+            (
+                "gemini_default",
+                tasks::DOCUMENT_SUMMARIZATION_SYSTEM_PROMPT,
+                tasks::DOCUMENT_SUMMARIZATION_USER_PROMPT,
+            ),
+        ),
+        (
+            "image_captioning",
+            (
+                "gemini_default",
+                tasks::IMAGE_CAPTIONING_SYSTEM_PROMPT,
+                tasks::IMAGE_CAPTIONING_USER_PROMPT,
+            ),
+        ),
+        (
+            "graph_query_generation",
+            (
+                "gemini_default",
+                tasks::GRAPH_QUERY_GENERATION_SYSTEM_PROMPT,
+                tasks::GRAPH_QUERY_GENERATION_USER_PROMPT,
+            ),
+        ),
+        (
+            "entity_resolution",
+            (
+                "gemini_default",
+                tasks::ENTITY_RESOLUTION_SYSTEM_PROMPT,
+                tasks::ENTITY_RESOLUTION_USER_PROMPT,
+            ),
+        ),
+        (
+            "query_planning",
+            (
+                "gemini_default",
+                tasks::QUERY_PLANNING_SYSTEM_PROMPT,
+                tasks::QUERY_PLANNING_USER_PROMPT,
+            ),
+        ),
+        (
+            "answer_routing",
+            (
+                "gemini_default",
+                tasks::ANSWER_ROUTING_SYSTEM_PROMPT,
+                tasks::ANSWER_ROUTING_USER_PROMPT,
+            ),
+        ),
         (
             "context_agent",
             (
@@ -121,6 +209,14 @@ fn build_default_tasks() -> HashMap<String, ConfigValue> {
                 tasks::CONTEXT_AGENT_USER_PROMPT,
             ),
         ),
+        (
+            "chat_query_rewrite",
+            (
+                "gemini_default",
+                tasks::CHAT_QUERY_REWRITE_SYSTEM_PROMPT,
+                tasks::CHAT_QUERY_REWRITE_USER_PROMPT,
+            ),
+        ),
         (
             "query_deconstruction",
             (
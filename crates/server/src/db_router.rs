@@ -0,0 +1,273 @@
+//! # Database Router
+//!
+//! This module routes each request to the SQLite database that holds its documents.
+//! Without a `sharding` section in the configuration, every document lives in the
+//! primary database at `db_url`. With it, the documents of each owner, and those
+//! ingested within an organization, are kept in a file of their own in the shard
+//! directory, e.g. `db/shards/owner-<user id>.db`.
+//!
+//! Users, API keys, sessions, organizations and the other access control tables always
+//! stay in the primary database. The shares of a document are kept next to it, in the
+//! database of its owner. Project databases (`db/{project_id}.db`), such as those
+//! filled by `/ingest/firebase`, are opened through the router as well.
+//!
+//! Databases are opened on first use and kept open, up to `max_open` of them; the
+//! least recently used one is closed when another is opened.
+
+use crate::errors::AppError;
+use anyrag::{
+    constants,
+    providers::db::sqlite::SqliteProvider,
+    types::{AppConfig, QueryLimits, SqliteSettings},
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::sync::Mutex;
+use tracing::info;
+
+/// The directory shards are kept in when `sharding.dir` is not configured, next to the
+/// primary database.
+const DEFAULT_SHARD_DIR_NAME: &str = "shards";
+const OWNER_SHARD_PREFIX: &str = "owner-";
+const ORG_SHARD_PREFIX: &str = "org-";
+const DB_FILE_EXTENSION: &str = "db";
+
+/// Whose documents a database holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tenant<'a> {
+    /// The documents of a user.
+    Owner(&'a str),
+    /// The documents ingested within an organization.
+    Org(&'a str),
+    /// A project database in the `db` directory, named by the `db` field of a request.
+    Project(&'a str),
+}
+
+/// Maps owners, organizations and projects to their SQLite databases.
+pub struct DatabaseRouter {
+    primary: Arc<SqliteProvider>,
+    /// The directory of the shards, if the knowledge base is sharded.
+    shard_dir: Option<PathBuf>,
+    max_open: usize,
+    query_limits: QueryLimits,
+    sqlite_settings: SqliteSettings,
+    open: Mutex<OpenDatabases>,
+}
+
+/// The databases opened by the router, with the tick they were last used at.
+#[derive(Default)]
+struct OpenDatabases {
+    providers: HashMap<PathBuf, (Arc<SqliteProvider>, u64)>,
+    tick: u64,
+}
+
+impl DatabaseRouter {
+    /// Creates a router over the primary database, sharding as configured.
+    pub fn new(primary: Arc<SqliteProvider>, config: &AppConfig) -> Self {
+        let shard_dir = config
+            .sharding
+            .as_ref()
+            .map(|sharding| match &sharding.dir {
+                Some(dir) => PathBuf::from(dir),
+                None => Path::new(&config.db_url)
+                    .parent()
+                    .unwrap_or(Path::new(""))
+                    .join(DEFAULT_SHARD_DIR_NAME),
+            });
+        let max_open = config
+            .sharding
+            .as_ref()
+            .map_or(1, |sharding| sharding.max_open.max(1));
+        Self {
+            primary,
+            shard_dir,
+            max_open,
+            query_limits: config.query_limits(),
+            sqlite_settings: config.sqlite_settings(),
+            open: Mutex::new(OpenDatabases::default()),
+        }
+    }
+
+    /// The primary database, which holds the access control tables.
+    pub fn primary(&self) -> Arc<SqliteProvider> {
+        self.primary.clone()
+    }
+
+    /// Whether documents are stored in per-owner and per-organization shards.
+    pub fn is_sharded(&self) -> bool {
+        self.shard_dir.is_some()
+    }
+
+    /// Returns the database of a request: the organization's when the request acts
+    /// within one, and the user's otherwise.
+    pub async fn for_user(
+        &self,
+        user_id: &str,
+        org_id: Option<&str>,
+    ) -> Result<Arc<SqliteProvider>, AppError> {
+        match org_id {
+            Some(org_id) => self.route(Tenant::Org(org_id)).await,
+            None => self.route(Tenant::Owner(user_id)).await,
+        }
+    }
+
+    /// Returns the database of a tenant, opening it if needed. Owners and organizations
+    /// share the primary database unless the knowledge base is sharded.
+    pub async fn route(&self, tenant: Tenant<'_>) -> Result<Arc<SqliteProvider>, AppError> {
+        let path = match (tenant, &self.shard_dir) {
+            (Tenant::Project(name), _) => Path::new(constants::DB_DIR).join(file_name("", name)?),
+            (_, None) => return Ok(self.primary.clone()),
+            (Tenant::Owner(id), Some(dir)) => dir.join(file_name(OWNER_SHARD_PREFIX, id)?),
+            (Tenant::Org(id), Some(dir)) => dir.join(file_name(ORG_SHARD_PREFIX, id)?),
+        };
+        self.open(path).await
+    }
+
+    /// Returns every database holding documents: the primary one and, when the knowledge
+    /// base is sharded, each shard in the shard directory, opening them if needed.
+    pub async fn knowledge_bases(&self) -> Result<Vec<Arc<SqliteProvider>>, AppError> {
+        let mut databases = vec![self.primary.clone()];
+        databases.extend(self.shards().await?.into_iter().map(|(_, shard)| shard));
+        Ok(databases)
+    }
+
+    /// Returns each shard in the shard directory with its file name, ordered by name and
+    /// opening them if needed. There are none unless the knowledge base is sharded.
+    pub async fn shards(&self) -> Result<Vec<(String, Arc<SqliteProvider>)>, AppError> {
+        let Some(dir) = &self.shard_dir else {
+            return Ok(Vec::new());
+        };
+        let mut entries = match tokio::fs::read_dir(dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(AppError::Internal(e.into())),
+        };
+        let mut paths = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| AppError::Internal(e.into()))?
+        {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some(DB_FILE_EXTENSION) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut shards = Vec::with_capacity(paths.len());
+        for path in paths {
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default()
+                .to_string();
+            shards.push((name, self.open(path).await?));
+        }
+        Ok(shards)
+    }
+
+    /// Returns the shard kept in the file `name` of the shard directory, as listed by
+    /// [`DatabaseRouter::shards`], opening it if needed.
+    pub async fn shard(&self, name: &str) -> Result<Arc<SqliteProvider>, AppError> {
+        let Some(dir) = &self.shard_dir else {
+            return Err(AppError::BadRequest(
+                "The knowledge base is not sharded.".to_string(),
+            ));
+        };
+        let stem = name
+            .strip_suffix(&format!(".{DB_FILE_EXTENSION}"))
+            .ok_or_else(|| AppError::BadRequest(format!("'{name}' is not a shard.")))?;
+        self.open(dir.join(file_name("", stem)?)).await
+    }
+
+    /// Returns the database holding a document, wherever its owner's shard is. Falls back
+    /// to the primary database when no database holds it.
+    pub async fn for_document(&self, document_id: &str) -> Result<Arc<SqliteProvider>, AppError> {
+        if !self.is_sharded() {
+            return Ok(self.primary.clone());
+        }
+        for database in self.knowledge_bases().await? {
+            let mut rows = database
+                .db
+                .connect()?
+                .query(
+                    "SELECT 1 FROM documents WHERE id = ?",
+                    turso::params![document_id],
+                )
+                .await?;
+            if rows.next().await?.is_some() {
+                return Ok(database);
+            }
+        }
+        Ok(self.primary.clone())
+    }
+
+    /// The number of databases the router holds open, besides the primary one.
+    pub async fn open_count(&self) -> usize {
+        self.open.lock().await.providers.len()
+    }
+
+    async fn open(&self, path: PathBuf) -> Result<Arc<SqliteProvider>, AppError> {
+        let mut guard = self.open.lock().await;
+        let open = &mut *guard;
+        open.tick += 1;
+        if let Some((provider, last_used)) = open.providers.get_mut(&path) {
+            *last_used = open.tick;
+            return Ok(provider.clone());
+        }
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| AppError::Internal(e.into()))?;
+        }
+        let Some(path_str) = path.to_str() else {
+            return Err(AppError::BadRequest(format!(
+                "'{}' is not a valid database path.",
+                path.display()
+            )));
+        };
+        let provider = SqliteProvider::new(path_str)
+            .await?
+            .with_query_limits(self.query_limits)
+            .with_sqlite_settings(self.sqlite_settings);
+        provider.initialize_schema().await?;
+        info!("Opened database '{}'.", path.display());
+
+        if open.providers.len() >= self.max_open {
+            let least_recently_used = open
+                .providers
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(path, _)| path.clone());
+            if let Some(least_recently_used) = least_recently_used {
+                // Requests still using the database keep it open until they finish.
+                open.providers.remove(&least_recently_used);
+                info!("Closed database '{}'.", least_recently_used.display());
+            }
+        }
+        let provider = Arc::new(provider);
+        open.providers.insert(path, (provider.clone(), open.tick));
+        Ok(provider)
+    }
+}
+
+/// The file name of a database, accepting only names that cannot reach outside its
+/// directory.
+fn file_name(prefix: &str, name: &str) -> Result<String, AppError> {
+    let is_valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !is_valid {
+        return Err(AppError::BadRequest(format!(
+            "'{name}' is not a valid database name."
+        )));
+    }
+    Ok(format!("{prefix}{name}.{DB_FILE_EXTENSION}"))
+}
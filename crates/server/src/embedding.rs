@@ -0,0 +1,27 @@
+//! # Ingestion Embedding
+//!
+//! The documents an ingestion stored are embedded with the configured embedding model
+//! once it is done, in batches, so that they are found by vector and hybrid search
+//! without a separate `/embed/new` call. A request opts out with `?embed=false`.
+//!
+//! This runs after redaction and moderation, so that no vector is made of the data they
+//! mask or of a document they delete. A failure is logged and never fails the
+//! ingestion; the documents left without a vector are embedded by the next
+//! `/embed/new` call.
+
+use crate::{state::AppState, types::EmbedParams};
+use anyrag::{ingest::embed_ingested_documents, providers::db::sqlite::SqliteProvider};
+
+/// Embeds the given documents, unless the request opted out. Returns the number of
+/// documents embedded.
+pub async fn embed_ingested(
+    app_state: &AppState,
+    db: &SqliteProvider,
+    document_ids: &[String],
+    params: &EmbedParams,
+) -> usize {
+    if !params.embed.unwrap_or(true) {
+        return 0;
+    }
+    embed_ingested_documents(&db.db, &app_state.config.embedding, document_ids).await
+}
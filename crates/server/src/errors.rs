@@ -1,5 +1,13 @@
+use crate::{auth::oidc::OidcError, types::ErrorResponse};
 use anyrag::{
-    ingest::{EmbeddingError, KnowledgeError},
+    chat::ChatError,
+    collections::CollectionError,
+    experiments::ExperimentError,
+    feedback::FeedbackError,
+    graph::types::KnowledgeGraphError,
+    ingest::{EmbeddingError, ExportError, IngestError, KnowledgeError, RevisionError, TrashError},
+    providers::db::sqlite::backup::BackupError,
+    schema_annotations::SchemaAnnotationError,
     search::SearchError,
     PromptError,
 };
@@ -18,7 +26,7 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use core_access::{ApiKeyError, OrgError, PermissionError, SessionError, ShareError};
 use tracing::error;
 use turso::Error as TursoError;
 
@@ -51,8 +59,46 @@ pub enum AppError {
     Knowledge(KnowledgeError),
     /// Errors from the search process.
     Search(SearchError),
+    /// Errors from exporting documents.
+    Export(ExportError),
+    /// Errors from conversation sessions.
+    Chat(ChatError),
+    /// Errors from A/B experiments.
+    Experiment(ExperimentError),
+    /// Errors from answer feedback.
+    Feedback(FeedbackError),
+    /// Errors from schema annotations.
+    SchemaAnnotation(SchemaAnnotationError),
+    /// Errors from API key management.
+    ApiKey(ApiKeyError),
+    /// Errors from organizations and their membership checks.
+    Org(OrgError),
+    /// Errors from sharing documents with other users.
+    Share(ShareError),
+    /// Errors from reading the revisions of documents.
+    Revision(RevisionError),
+    /// Errors from moving documents to the trash and restoring them.
+    Trash(TrashError),
+    /// Errors from managing document collections.
+    Collection(CollectionError),
+    /// Errors from sign-in sessions and their refresh tokens.
+    Session(SessionError),
+    /// Errors from signing in with the OpenID Connect provider.
+    Oidc(OidcError),
+    /// Errors from backing up or restoring the knowledge base.
+    Backup(BackupError),
+    /// Errors from querying or maintaining the knowledge graph.
+    Graph(KnowledgeGraphError),
+    /// The request is malformed in a way its JSON schema cannot express.
+    BadRequest(String),
+    /// The user is authenticated but not allowed to perform the operation.
+    Forbidden(String),
     /// Errors from database operations.
     Database(TursoError),
+    /// Errors from an ingestion plugin called through `POST /ingest`.
+    Ingest(IngestError),
+    /// A `POST /ingest` request for a source type without a registered plugin.
+    UnknownSourceType(String),
     /// Errors from parsing JSON.
     JsonParse(serde_json::Error),
     /// Generic internal server errors.
@@ -111,6 +157,115 @@ impl From<SearchError> for AppError {
     }
 }
 
+/// Conversion from `ChatError` to `AppError`.
+impl From<ChatError> for AppError {
+    fn from(err: ChatError) -> Self {
+        AppError::Chat(err)
+    }
+}
+
+/// Conversion from `ExperimentError` to `AppError`.
+impl From<ExperimentError> for AppError {
+    fn from(err: ExperimentError) -> Self {
+        AppError::Experiment(err)
+    }
+}
+
+/// Conversion from `FeedbackError` to `AppError`.
+impl From<FeedbackError> for AppError {
+    fn from(err: FeedbackError) -> Self {
+        AppError::Feedback(err)
+    }
+}
+
+/// Conversion from `SchemaAnnotationError` to `AppError`.
+impl From<SchemaAnnotationError> for AppError {
+    fn from(err: SchemaAnnotationError) -> Self {
+        AppError::SchemaAnnotation(err)
+    }
+}
+
+/// Conversion from `ApiKeyError` to `AppError`.
+impl From<ApiKeyError> for AppError {
+    fn from(err: ApiKeyError) -> Self {
+        AppError::ApiKey(err)
+    }
+}
+
+/// Conversion from `OrgError` to `AppError`.
+impl From<OrgError> for AppError {
+    fn from(err: OrgError) -> Self {
+        AppError::Org(err)
+    }
+}
+
+/// Conversion from `ShareError` to `AppError`.
+impl From<ShareError> for AppError {
+    fn from(err: ShareError) -> Self {
+        AppError::Share(err)
+    }
+}
+
+/// Conversion from `RevisionError` to `AppError`.
+impl From<RevisionError> for AppError {
+    fn from(err: RevisionError) -> Self {
+        AppError::Revision(err)
+    }
+}
+
+/// Conversion from `TrashError` to `AppError`.
+impl From<TrashError> for AppError {
+    fn from(err: TrashError) -> Self {
+        AppError::Trash(err)
+    }
+}
+
+/// Conversion from `CollectionError` to `AppError`.
+impl From<CollectionError> for AppError {
+    fn from(err: CollectionError) -> Self {
+        AppError::Collection(err)
+    }
+}
+
+/// Conversion from `SessionError` to `AppError`.
+impl From<SessionError> for AppError {
+    fn from(err: SessionError) -> Self {
+        AppError::Session(err)
+    }
+}
+
+/// Conversion from `OidcError` to `AppError`.
+impl From<OidcError> for AppError {
+    fn from(err: OidcError) -> Self {
+        AppError::Oidc(err)
+    }
+}
+
+/// Conversion from `ExportError` to `AppError`.
+impl From<ExportError> for AppError {
+    fn from(err: ExportError) -> Self {
+        AppError::Export(err)
+    }
+}
+
+/// Conversion from `BackupError` to `AppError`.
+impl From<BackupError> for AppError {
+    fn from(err: BackupError) -> Self {
+        AppError::Backup(err)
+    }
+}
+
+/// Conversion from `PermissionError` to `AppError`. A missing permission is a
+/// `Forbidden` error; failing to look permissions up is an internal one.
+impl From<PermissionError> for AppError {
+    fn from(err: PermissionError) -> Self {
+        match err {
+            PermissionError::Denied(_) => AppError::Forbidden(format!("Forbidden: {err}")),
+            err => AppError::Internal(err.into()),
+        }
+    }
+}
+
 /// Conversion from `GitHubIngestError` to `AppError`.
 #[cfg(feature = "github")]
 impl From<GitHubIngestError> for AppError {
@@ -127,6 +282,13 @@ impl From<WebIngestError> for AppError {
     }
 }
 
+/// Conversion from `KnowledgeGraphError` to `AppError`.
+impl From<KnowledgeGraphError> for AppError {
+    fn from(err: KnowledgeGraphError) -> Self {
+        AppError::Graph(err)
+    }
+}
+
 /// Conversion from `PromptError` to `AppError`.
 impl From<PromptError> for AppError {
     fn from(err: PromptError) -> Self {
@@ -198,6 +360,10 @@ impl IntoResponse for AppError {
                         StatusCode::INTERNAL_SERVER_ERROR,
                         format!("Failed to parse data: {e}"),
                     ),
+                    KnowledgeError::NotFound(_) => (StatusCode::NOT_FOUND, err.to_string()),
+                    KnowledgeError::UnknownFinetuningFormat(_) => {
+                        (StatusCode::BAD_REQUEST, err.to_string())
+                    }
                     _ => (
                         StatusCode::INTERNAL_SERVER_ERROR,
                         format!("Knowledge base operation failed: {err}"),
@@ -223,6 +389,180 @@ impl IntoResponse for AppError {
                     format!("Search operation failed: {err}"),
                 )
             }
+            AppError::Chat(err) => {
+                error!("ChatError: {:?}", err);
+                let status_code = match err {
+                    ChatError::Rewrite(_) => StatusCode::BAD_GATEWAY,
+                    ChatError::Database(_) | ChatError::InvalidRole(_) => {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    }
+                };
+                (status_code, format!("Chat operation failed: {err}"))
+            }
+            AppError::Experiment(err) => {
+                error!("ExperimentError: {:?}", err);
+                let status_code = match err {
+                    ExperimentError::UnknownExperiment(_) | ExperimentError::RunNotFound(_) => {
+                        StatusCode::NOT_FOUND
+                    }
+                    ExperimentError::Database(_) | ExperimentError::InvalidVariant(_) => {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    }
+                };
+                (status_code, format!("Experiment operation failed: {err}"))
+            }
+            AppError::Feedback(err) => {
+                error!("FeedbackError: {:?}", err);
+                let status_code = match err {
+                    FeedbackError::NotFound(_) => StatusCode::NOT_FOUND,
+                    FeedbackError::MissingCorrection(_) => StatusCode::BAD_REQUEST,
+                    FeedbackError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status_code, format!("Feedback operation failed: {err}"))
+            }
+            AppError::SchemaAnnotation(err) => {
+                error!("SchemaAnnotationError: {:?}", err);
+                let status_code = match err {
+                    SchemaAnnotationError::MissingTableName => StatusCode::BAD_REQUEST,
+                    SchemaAnnotationError::Database(_)
+                    | SchemaAnnotationError::Serialization(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status_code, format!("Schema annotation failed: {err}"))
+            }
+            AppError::ApiKey(err) => {
+                error!("ApiKeyError: {:?}", err);
+                let status_code = match err {
+                    ApiKeyError::NotFound(_) => StatusCode::NOT_FOUND,
+                    ApiKeyError::OwnerNotFound(_)
+                    | ApiKeyError::InvalidScope(_)
+                    | ApiKeyError::NoScopes => StatusCode::BAD_REQUEST,
+                    ApiKeyError::InvalidKey => StatusCode::UNAUTHORIZED,
+                    ApiKeyError::Database(_)
+                    | ApiKeyError::DataIntegrity(_)
+                    | ApiKeyError::CoreAccess(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status_code, format!("API key operation failed: {err}"))
+            }
+            AppError::Org(err) => {
+                error!("OrgError: {:?}", err);
+                let status_code = match err {
+                    OrgError::NotFound(_) => StatusCode::NOT_FOUND,
+                    OrgError::NotMember { .. } | OrgError::NotOwner(_) => StatusCode::FORBIDDEN,
+                    OrgError::LastOwner | OrgError::UserNotFound(_) | OrgError::InvalidRole(_) => {
+                        StatusCode::BAD_REQUEST
+                    }
+                    OrgError::Database(_) | OrgError::DataIntegrity(_) => {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    }
+                };
+                (status_code, format!("Organization operation failed: {err}"))
+            }
+            AppError::Share(err) => {
+                error!("ShareError: {:?}", err);
+                let status_code = match err {
+                    ShareError::DocumentNotFound(_) | ShareError::ShareNotFound { .. } => {
+                        StatusCode::NOT_FOUND
+                    }
+                    ShareError::NotOwner(_) => StatusCode::FORBIDDEN,
+                    ShareError::UserNotFound(_) => StatusCode::BAD_REQUEST,
+                    ShareError::Database(_) | ShareError::DataIntegrity(_) => {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    }
+                };
+                (status_code, format!("Document sharing failed: {err}"))
+            }
+            AppError::Revision(err) => {
+                error!("RevisionError: {:?}", err);
+                let status_code = match err {
+                    RevisionError::DocumentNotFound(_) => StatusCode::NOT_FOUND,
+                    RevisionError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status_code, format!("Document history failed: {err}"))
+            }
+            AppError::Trash(err) => {
+                error!("TrashError: {:?}", err);
+                let status_code = match err {
+                    TrashError::DocumentNotFound(_) => StatusCode::NOT_FOUND,
+                    TrashError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (
+                    status_code,
+                    format!("Document trash operation failed: {err}"),
+                )
+            }
+            AppError::Collection(err) => {
+                error!("CollectionError: {:?}", err);
+                let status_code = match err {
+                    CollectionError::NotFound(_) | CollectionError::DocumentNotFound(_) => {
+                        StatusCode::NOT_FOUND
+                    }
+                    CollectionError::NameTaken(_) => StatusCode::CONFLICT,
+                    CollectionError::MissingName => StatusCode::BAD_REQUEST,
+                    CollectionError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status_code, format!("Collection operation failed: {err}"))
+            }
+            AppError::Session(err) => {
+                error!("SessionError: {:?}", err);
+                let status_code = match err {
+                    SessionError::NotFound(_) => StatusCode::NOT_FOUND,
+                    SessionError::NotOwner(_) => StatusCode::FORBIDDEN,
+                    SessionError::InvalidRefreshToken => StatusCode::UNAUTHORIZED,
+                    SessionError::Database(_) | SessionError::DataIntegrity(_) => {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    }
+                };
+                (status_code, format!("Session operation failed: {err}"))
+            }
+            AppError::Oidc(err) => {
+                error!("OidcError: {:?}", err);
+                let status_code = match err {
+                    OidcError::NotConfigured(_) => StatusCode::NOT_FOUND,
+                    OidcError::InvalidState => StatusCode::BAD_REQUEST,
+                    OidcError::InvalidToken(_) => StatusCode::UNAUTHORIZED,
+                    OidcError::Provider(_) => StatusCode::BAD_GATEWAY,
+                };
+                (status_code, format!("Sign-in failed: {err}"))
+            }
+            AppError::Export(err) => {
+                error!("ExportError: {:?}", err);
+                let status_code = match err {
+                    ExportError::UnknownFormat(_) | ExportError::ParquetFeatureNotEnabled => {
+                        StatusCode::BAD_REQUEST
+                    }
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status_code, format!("Export failed: {err}"))
+            }
+            AppError::Backup(err) => {
+                error!("BackupError: {:?}", err);
+                let status_code = match err {
+                    BackupError::NotFound(_) => StatusCode::NOT_FOUND,
+                    BackupError::AlreadyExists(_) | BackupError::NotEmpty => StatusCode::CONFLICT,
+                    BackupError::NewerSchema { .. } | BackupError::InvalidPath(_) => {
+                        StatusCode::BAD_REQUEST
+                    }
+                    BackupError::Database(_) | BackupError::Migration(_) | BackupError::Io(_) => {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    }
+                };
+                (status_code, err.to_string())
+            }
+            AppError::Graph(err) => {
+                error!("KnowledgeGraphError: {:?}", err);
+                let status_code = match err {
+                    KnowledgeGraphError::EntityNotFound(_) => StatusCode::NOT_FOUND,
+                    KnowledgeGraphError::IdentifierValidation(_)
+                    | KnowledgeGraphError::NotPersistent => StatusCode::BAD_REQUEST,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (
+                    status_code,
+                    format!("Knowledge graph operation failed: {err}"),
+                )
+            }
+            AppError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            AppError::Forbidden(message) => (StatusCode::FORBIDDEN, message),
             AppError::Database(err) => {
                 error!("Database error: {:?}", err);
                 (
@@ -230,6 +570,19 @@ impl IntoResponse for AppError {
                     format!("Database operation failed: {err}"),
                 )
             }
+            AppError::Ingest(err) => {
+                error!("IngestError: {:?}", err);
+                let status_code = match err {
+                    IngestError::SourceNotFound(_) => StatusCode::NOT_FOUND,
+                    IngestError::Parse(_) => StatusCode::BAD_REQUEST,
+                    IngestError::Fetch(_) => StatusCode::BAD_GATEWAY,
+                    IngestError::Database(_) | IngestError::Internal(_) => {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    }
+                };
+                (status_code, format!("Ingestion failed: {err}"))
+            }
+            AppError::UnknownSourceType(message) => (StatusCode::BAD_REQUEST, message),
             AppError::JsonParse(err) => {
                 error!("JsonParseError: {:?}", err);
                 (
@@ -276,6 +629,29 @@ impl IntoResponse for AppError {
                         StatusCode::INTERNAL_SERVER_ERROR,
                         format!("Failed to build HTTP client: {e}"),
                     ),
+                    PromptError::OutputSchemaViolation(e) => (
+                        StatusCode::BAD_GATEWAY,
+                        format!("AI response does not conform to the output schema: {e}"),
+                    ),
+                    PromptError::ImagesNotSupported => (
+                        StatusCode::BAD_REQUEST,
+                        "The configured AI provider does not accept images.".to_string(),
+                    ),
+                    PromptError::QueryTimeout(secs) => (
+                        StatusCode::GATEWAY_TIMEOUT,
+                        format!("Query exceeded the execution timeout of {secs} seconds"),
+                    ),
+                    PromptError::QueryCostExceeded {
+                        estimated_bytes,
+                        max_bytes,
+                    } => (
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        format!("Query would scan an estimated {estimated_bytes} bytes, above the limit of {max_bytes} bytes. Set `confirm_expensive_query` to run it anyway."),
+                    ),
+                    PromptError::NoQueryGenerated => (
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        "The prompt did not result in a valid query.".to_string(),
+                    ),
                     PromptError::BigQueryFeatureNotEnabled => (
                         StatusCode::INTERNAL_SERVER_ERROR,
                         "The server is not configured for BigQuery. The 'bigquery' feature is not enabled.".to_string()
@@ -291,9 +667,9 @@ impl IntoResponse for AppError {
             }
         };
 
-        let body = Json(json!({
-            "error": error_message,
-        }));
+        let body = Json(ErrorResponse {
+            error: error_message,
+        });
 
         (status_code, body).into_response()
     }
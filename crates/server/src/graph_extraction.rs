@@ -0,0 +1,128 @@
+//! # Knowledge Graph Extraction
+//!
+//! With `knowledge_graph_extraction` enabled, the documents an ingestion stored are
+//! sent to the `knowledge_graph_extraction` task, which extracts the facts they state.
+//! The facts are added to the shared knowledge graph, linked to their document ids.
+//!
+//! Extraction runs after the ingestion has stored its documents, so a failure here is
+//! logged and never fails the ingestion.
+//!
+//! Because the graph is shared, readers only see the extracted facts of documents
+//! they can see themselves; see [`fact_visibility`].
+
+use crate::state::AppState;
+use anyrag::{
+    graph::{
+        extraction::{extract_facts, ExtractedFact},
+        query::FactVisibility,
+    },
+    providers::db::sqlite::SqliteProvider,
+    PromptError,
+};
+use futures::stream::{self, StreamExt};
+use tracing::{info, instrument, warn};
+use turso::params;
+
+/// The task facts are extracted with.
+const EXTRACTION_TASK: &str = "knowledge_graph_extraction";
+const SELECT_CONTENT_SQL: &str =
+    "SELECT content FROM documents WHERE id = ? AND content IS NOT NULL";
+
+/// Extracts the facts of the given documents into the knowledge graph, if enabled.
+/// Returns the number of facts added.
+#[instrument(name = "ingest.extract_facts", skip_all, fields(documents = document_ids.len()))]
+pub async fn extract_document_facts(
+    app_state: &AppState,
+    db: &SqliteProvider,
+    document_ids: &[String],
+) -> usize {
+    if !app_state.config.knowledge_graph_extraction || document_ids.is_empty() {
+        return 0;
+    }
+    let Some(task) = app_state.tasks.get(EXTRACTION_TASK) else {
+        warn!("Task '{EXTRACTION_TASK}' not found in config, skipping fact extraction.");
+        return 0;
+    };
+    let Some(ai_provider) = app_state.ai_providers.get(&task.provider) else {
+        warn!(
+            "Provider '{}' not found, skipping fact extraction.",
+            task.provider
+        );
+        return 0;
+    };
+
+    let documents = match document_contents(db, document_ids).await {
+        Ok(documents) => documents,
+        Err(e) => {
+            warn!("Could not load the documents to extract facts from: {e}");
+            return 0;
+        }
+    };
+
+    let extracted: Vec<(String, Vec<ExtractedFact>)> = stream::iter(documents)
+        .map(|(document_id, content)| async move {
+            let facts = extract_facts(
+                ai_provider.as_ref(),
+                &content,
+                &task.system_prompt,
+                &task.user_prompt,
+            )
+            .await;
+            (document_id, facts)
+        })
+        .buffer_unordered(app_state.config.ingest_concurrency.max(1))
+        .filter_map(|(document_id, facts)| async move {
+            match facts {
+                Ok(facts) => Some((document_id, facts)),
+                Err(e) => {
+                    warn!("Fact extraction failed for document '{document_id}': {e}");
+                    None
+                }
+            }
+        })
+        .collect()
+        .await;
+
+    let Ok(mut kg) = app_state.knowledge_graph.write() else {
+        warn!("Failed to acquire KG write lock, skipping fact extraction.");
+        return 0;
+    };
+    let facts_added = extracted
+        .iter()
+        .map(|(document_id, facts)| kg.add_document_facts(document_id, facts))
+        .sum();
+    info!("Added {facts_added} extracted facts to the Knowledge Graph.");
+    facts_added
+}
+
+/// The graph facts a reader may see: those linked to a document visible to
+/// `owner_id` (and `org_id`) in `db`, plus the facts that are linked to no document.
+pub async fn fact_visibility(
+    db: &SqliteProvider,
+    owner_id: Option<&str>,
+    org_id: Option<&str>,
+) -> Result<FactVisibility, PromptError> {
+    Ok(FactVisibility::Documents(
+        db.visible_document_ids(owner_id, org_id).await?,
+    ))
+}
+
+/// Loads the content of the documents that exist among `document_ids`.
+async fn document_contents(
+    db: &SqliteProvider,
+    document_ids: &[String],
+) -> Result<Vec<(String, String)>, anyhow::Error> {
+    let mut conn = db.read_connection().await?;
+    let mut documents = Vec::new();
+    for document_id in document_ids {
+        let mut rows = conn
+            .prepare_cached(SELECT_CONTENT_SQL)
+            .await?
+            .query(params![document_id.as_str()])
+            .await?;
+        if let Some(row) = rows.next().await? {
+            documents.push((document_id.clone(), row.get::<String>(0)?));
+        }
+    }
+    Ok(documents)
+}
@@ -1,23 +1,33 @@
 //! # Admin Route Handlers
 //!
-//! This module contains handlers for endpoints that require administrative (root) privileges.
+//! This module contains handlers for endpoints that require administrative (`admin:*`) permissions.
 
 use crate::{
     auth::middleware::AuthenticatedUser,
     errors::AppError,
     handlers::{wrap_response, ApiResponse, DebugParams},
+    metrics::database_size_bytes,
     state::AppState,
 };
+use anyrag::answer_cache::purge_answer_cache;
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
     Json,
 };
-use serde::Serialize;
-use serde_json::json;
+use core_access::{
+    api_keys::{create_api_key, delete_api_key, get_api_key, list_api_keys, update_api_key},
+    permissions::{ADMIN_API_KEYS, ADMIN_CACHE, ADMIN_STATS, ADMIN_USERS},
+    require_permission, ApiKey, ApiKeyScope, NewApiKey,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
 use tracing::info;
+use turso::Connection;
+use utoipa::ToSchema;
 
 /// A response item for the user list.
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct UserListResponse {
     id: String,
     role: String,
@@ -26,7 +36,14 @@ pub struct UserListResponse {
 
 /// Handler for retrieving a list of all users.
 ///
-/// **Authorization**: This endpoint is protected and only accessible by users with the 'root' role.
+/// **Authorization**: Requires the `admin:users` permission.
+#[utoipa::path(
+    get,
+    path = "/users",
+    tag = "admin",
+    params(DebugParams),
+    responses((status = 200, description = "Every user. Requires the `admin:users` permission.", body = ApiResponse<Vec<UserListResponse>>))
+)]
 pub async fn get_users_handler(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
@@ -39,14 +56,7 @@ pub async fn get_users_handler(
     );
 
     // --- Authorization Check ---
-    if current_user.role != "root" {
-        // This is not ideal as it will result in a 500 status code, but it's the
-        // only suitable error variant available right now. This should be improved
-        // by adding an `AppError::Forbidden` variant.
-        return Err(AppError::Internal(anyhow::anyhow!(
-            "Forbidden: You do not have permission to access this resource."
-        )));
-    }
+    require_permission(&current_user, ADMIN_USERS)?;
 
     let conn = app_state.sqlite_provider.db.connect()?;
     let mut rows = conn
@@ -68,3 +78,374 @@ pub async fn get_users_handler(
     let debug_info = json!({ "requesting_user_id": current_user.id, "user_count": users.len() });
     Ok(wrap_response(users, debug_params, Some(debug_info)))
 }
+
+// --- API Key Management ---
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    /// A label to recognize the key by, such as the service that uses it.
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    /// The user the key acts as. Defaults to the requesting user.
+    #[serde(default)]
+    pub owner_id: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateApiKeyRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Replaces the scopes of the key.
+    #[serde(default)]
+    pub scopes: Option<Vec<ApiKeyScope>>,
+}
+
+/// Handler for creating an API key. The key is only returned in this response.
+///
+/// **Authorization**: Requires the `admin:api_keys` permission.
+#[utoipa::path(
+    post,
+    path = "/admin/api-keys",
+    tag = "admin",
+    params(DebugParams),
+    request_body = CreateApiKeyRequest,
+    responses((status = 200, description = "The new key and its secret. Requires the `admin:api_keys` permission.", body = ApiResponse<NewApiKey>))
+)]
+pub async fn create_api_key_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<Json<ApiResponse<NewApiKey>>, AppError> {
+    let current_user = user.0;
+    require_permission(&current_user, ADMIN_API_KEYS)?;
+
+    let owner_id = payload.owner_id.unwrap_or_else(|| current_user.id.clone());
+    info!(
+        "User '{}' creating API key '{}' for user '{}'.",
+        current_user.id, payload.name, owner_id
+    );
+    let new_key = create_api_key(
+        &app_state.sqlite_provider.db,
+        &owner_id,
+        &payload.name,
+        &payload.scopes,
+    )
+    .await?;
+
+    let debug_info =
+        json!({ "requesting_user_id": current_user.id, "api_key_id": new_key.api_key.id });
+    Ok(wrap_response(new_key, debug_params, Some(debug_info)))
+}
+
+/// Handler for listing every API key, without their secrets.
+///
+/// **Authorization**: Requires the `admin:api_keys` permission.
+#[utoipa::path(
+    get,
+    path = "/admin/api-keys",
+    tag = "admin",
+    params(DebugParams),
+    responses((status = 200, description = "Every API key. Requires the `admin:api_keys` permission.", body = ApiResponse<Vec<ApiKey>>))
+)]
+pub async fn list_api_keys_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+) -> Result<Json<ApiResponse<Vec<ApiKey>>>, AppError> {
+    let current_user = user.0;
+    require_permission(&current_user, ADMIN_API_KEYS)?;
+
+    let keys = list_api_keys(&app_state.sqlite_provider.db).await?;
+    let debug_info = json!({ "requesting_user_id": current_user.id, "api_key_count": keys.len() });
+    Ok(wrap_response(keys, debug_params, Some(debug_info)))
+}
+
+/// Handler for retrieving an API key, without its secret.
+///
+/// **Authorization**: Requires the `admin:api_keys` permission.
+#[utoipa::path(
+    get,
+    path = "/admin/api-keys/{id}",
+    tag = "admin",
+    params(DebugParams, ("id" = String, Path, description = "The API key.")),
+    responses((status = 200, description = "The API key. Requires the `admin:api_keys` permission.", body = ApiResponse<ApiKey>))
+)]
+pub async fn get_api_key_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<ApiKey>>, AppError> {
+    require_permission(&user.0, ADMIN_API_KEYS)?;
+    let key = get_api_key(&app_state.sqlite_provider.db, &id).await?;
+    Ok(wrap_response(key, debug_params, None))
+}
+
+/// Handler for renaming an API key or replacing its scopes.
+///
+/// **Authorization**: Requires the `admin:api_keys` permission.
+#[utoipa::path(
+    put,
+    path = "/admin/api-keys/{id}",
+    tag = "admin",
+    params(DebugParams, ("id" = String, Path, description = "The API key.")),
+    request_body = UpdateApiKeyRequest,
+    responses((status = 200, description = "The updated API key. Requires the `admin:api_keys` permission.", body = ApiResponse<ApiKey>))
+)]
+pub async fn update_api_key_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateApiKeyRequest>,
+) -> Result<Json<ApiResponse<ApiKey>>, AppError> {
+    let current_user = user.0;
+    require_permission(&current_user, ADMIN_API_KEYS)?;
+
+    info!("User '{}' updating API key '{}'.", current_user.id, id);
+    let key = update_api_key(
+        &app_state.sqlite_provider.db,
+        &id,
+        payload.name.as_deref(),
+        payload.scopes.as_deref(),
+    )
+    .await?;
+    Ok(wrap_response(key, debug_params, None))
+}
+
+/// Handler for revoking an API key.
+///
+/// **Authorization**: Requires the `admin:api_keys` permission.
+#[utoipa::path(
+    delete,
+    path = "/admin/api-keys/{id}",
+    tag = "admin",
+    params(DebugParams, ("id" = String, Path, description = "The API key.")),
+    responses((status = 200, description = "The id of the revoked key. Requires the `admin:api_keys` permission.", body = ApiResponse<Value>))
+)]
+pub async fn delete_api_key_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Value>>, AppError> {
+    let current_user = user.0;
+    require_permission(&current_user, ADMIN_API_KEYS)?;
+
+    info!("User '{}' revoking API key '{}'.", current_user.id, id);
+    delete_api_key(&app_state.sqlite_provider.db, &id).await?;
+    Ok(wrap_response(json!({ "id": id }), debug_params, None))
+}
+
+/// Handler for purging the answer cache, so that every knowledge question is answered
+/// afresh, e.g. after changing the synthesis prompts.
+///
+/// **Authorization**: Requires the `admin:cache` permission.
+#[utoipa::path(
+    delete,
+    path = "/admin/answer-cache",
+    tag = "admin",
+    params(DebugParams),
+    responses((status = 200, description = "The number of purged answers. Requires the `admin:cache` permission.", body = ApiResponse<Value>))
+)]
+pub async fn purge_answer_cache_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+) -> Result<Json<ApiResponse<Value>>, AppError> {
+    let current_user = user.0;
+    require_permission(&current_user, ADMIN_CACHE)?;
+
+    info!("User '{}' purging the answer cache.", current_user.id);
+    let purged = purge_answer_cache(&app_state.sqlite_provider.db)
+        .await
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Answer cache purge failed: {e}")))?;
+    Ok(wrap_response(
+        json!({ "purged": purged }),
+        debug_params,
+        None,
+    ))
+}
+
+// --- Knowledge Base Statistics ---
+
+/// The source type of a document: the host of a web page, or the scheme of any other
+/// `source_url`, such as `file` or `db`. Documents without one are of type `other`.
+const SOURCE_TYPE_SQL: &str = "CASE
+    WHEN d.source_url IS NULL OR instr(d.source_url, '://') = 0 THEN 'other'
+    WHEN d.source_url LIKE 'http://%' OR d.source_url LIKE 'https://%' THEN substr(
+        substr(d.source_url, instr(d.source_url, '://') + 3),
+        1,
+        instr(substr(d.source_url, instr(d.source_url, '://') + 3) || '/', '/') - 1
+    )
+    ELSE substr(d.source_url, 1, instr(d.source_url, '://') - 1)
+END";
+
+/// The ingestion statistics of the documents of a source type or an owner.
+#[derive(Serialize, ToSchema, Debug, Clone, Default)]
+pub struct IngestionStats {
+    /// The source type or owner. Documents without an owner are grouped under `null`.
+    pub group: Option<String>,
+    pub documents: i64,
+    /// The documents with an embedding, which vector search can find.
+    pub embedded_documents: i64,
+    /// The documents with extracted metadata, which metadata search can find.
+    pub documents_with_metadata: i64,
+    /// When the most recent document was ingested.
+    pub last_ingested_at: Option<String>,
+}
+
+impl IngestionStats {
+    fn merge(&mut self, other: IngestionStats) {
+        self.documents += other.documents;
+        self.embedded_documents += other.embedded_documents;
+        self.documents_with_metadata += other.documents_with_metadata;
+        self.last_ingested_at = self.last_ingested_at.take().max(other.last_ingested_at);
+    }
+}
+
+/// The state of the knowledge base. Documents in the trash are only counted in
+/// `trashed_documents`.
+#[derive(Serialize, ToSchema, Debug)]
+pub struct KnowledgeBaseStats {
+    pub documents: i64,
+    pub trashed_documents: i64,
+    pub embedded_documents: i64,
+    /// The share of documents with an embedding, from 0 to 1.
+    pub embedding_coverage: f64,
+    pub documents_with_metadata: i64,
+    /// The share of documents with extracted metadata, from 0 to 1.
+    pub metadata_coverage: f64,
+    pub last_ingested_at: Option<String>,
+    /// The size of the databases holding documents, when they report it.
+    pub database_size_bytes: Option<i64>,
+    /// The statistics per source type, largest first.
+    pub sources: Vec<IngestionStats>,
+    /// The statistics per owner, largest first.
+    pub owners: Vec<IngestionStats>,
+}
+
+/// Computes the ingestion statistics of the documents of one database, grouped by the
+/// SQL expression `group`.
+async fn group_stats(conn: &Connection, group: &str) -> Result<Vec<IngestionStats>, AppError> {
+    let sql = format!(
+        "SELECT {group} AS stats_group,
+            COUNT(*),
+            SUM(CASE WHEN d.id IN (SELECT document_id FROM document_embeddings) THEN 1 ELSE 0 END),
+            SUM(CASE WHEN d.id IN (SELECT document_id FROM content_metadata) THEN 1 ELSE 0 END),
+            MAX(d.created_at)
+         FROM documents d
+         WHERE d.deleted_at IS NULL
+         GROUP BY stats_group"
+    );
+    let mut rows = conn.query(&sql, ()).await?;
+    let mut stats = Vec::new();
+    while let Some(row) = rows.next().await? {
+        stats.push(IngestionStats {
+            group: row.get(0)?,
+            documents: row.get(1)?,
+            embedded_documents: row.get(2)?,
+            documents_with_metadata: row.get(3)?,
+            last_ingested_at: row.get(4)?,
+        });
+    }
+    Ok(stats)
+}
+
+/// Adds the statistics of one database to those of the others, by group.
+fn merge_stats(merged: &mut HashMap<Option<String>, IngestionStats>, stats: Vec<IngestionStats>) {
+    for group_stats in stats {
+        merged
+            .entry(group_stats.group.clone())
+            .or_insert_with(|| IngestionStats {
+                group: group_stats.group.clone(),
+                ..Default::default()
+            })
+            .merge(group_stats);
+    }
+}
+
+/// Orders the statistics of the groups, largest first.
+fn sorted_stats(merged: HashMap<Option<String>, IngestionStats>) -> Vec<IngestionStats> {
+    let mut stats: Vec<IngestionStats> = merged.into_values().collect();
+    stats.sort_by(|a, b| b.documents.cmp(&a.documents).then(a.group.cmp(&b.group)));
+    stats
+}
+
+fn coverage(count: i64, total: i64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+/// Handler for the ingestion statistics of the knowledge base: the number of documents,
+/// how many of them are embedded and have metadata, and when each source type and owner
+/// last ingested. With a sharded knowledge base, every shard is counted.
+///
+/// **Authorization**: Requires the `admin:stats` permission.
+#[utoipa::path(
+    get,
+    path = "/admin/stats",
+    tag = "admin",
+    params(DebugParams),
+    responses((status = 200, description = "The statistics of the knowledge base. Requires the `admin:stats` permission.", body = ApiResponse<KnowledgeBaseStats>))
+)]
+pub async fn knowledge_base_stats_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+) -> Result<Json<ApiResponse<KnowledgeBaseStats>>, AppError> {
+    let current_user = user.0;
+    require_permission(&current_user, ADMIN_STATS)?;
+    info!(
+        "User '{}' reading the knowledge base statistics.",
+        current_user.id
+    );
+
+    let databases = app_state.db_router.knowledge_bases().await?;
+    let mut sources = HashMap::new();
+    let mut owners = HashMap::new();
+    let mut trashed_documents = 0;
+    let mut database_size = Some(0);
+    for db in &databases {
+        let conn = db.db.connect()?;
+        merge_stats(&mut sources, group_stats(&conn, SOURCE_TYPE_SQL).await?);
+        merge_stats(&mut owners, group_stats(&conn, "d.owner_id").await?);
+        let mut rows = conn
+            .query(
+                "SELECT COUNT(*) FROM documents WHERE deleted_at IS NOT NULL",
+                (),
+            )
+            .await?;
+        if let Some(row) = rows.next().await? {
+            trashed_documents += row.get::<i64>(0)?;
+        }
+        database_size = match (database_size, database_size_bytes(db).await) {
+            (Some(total), Some(size)) => Some(total + size),
+            _ => None,
+        };
+    }
+
+    let mut total = IngestionStats::default();
+    for owner_stats in owners.values() {
+        total.merge(owner_stats.clone());
+    }
+    let stats = KnowledgeBaseStats {
+        documents: total.documents,
+        trashed_documents,
+        embedded_documents: total.embedded_documents,
+        embedding_coverage: coverage(total.embedded_documents, total.documents),
+        documents_with_metadata: total.documents_with_metadata,
+        metadata_coverage: coverage(total.documents_with_metadata, total.documents),
+        last_ingested_at: total.last_ingested_at,
+        database_size_bytes: database_size,
+        sources: sorted_stats(sources),
+        owners: sorted_stats(owners),
+    };
+
+    let debug_info = json!({ "requesting_user_id": current_user.id, "databases": databases.len() });
+    Ok(wrap_response(stats, debug_params, Some(debug_info)))
+}
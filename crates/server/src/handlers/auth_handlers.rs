@@ -1,16 +1,31 @@
 //! # Authentication Route Handlers
 //!
 //! This module contains the Axum handlers for the OAuth 2.0 authentication flow.
-//! NOTE: This is a placeholder implementation to allow the application to compile.
+//! NOTE: The Google handlers are placeholders. Sign-in with any OpenID Connect
+//! provider, Google included, is available through `/auth/login/oidc`.
 
-use crate::{auth::middleware::AuthenticatedUser, errors::AppError};
+use crate::{
+    auth::{
+        middleware::AuthenticatedUser,
+        oidc::{OidcClient, OidcError},
+        session::{refresh, start_session, SessionTokens},
+    },
+    errors::AppError,
+    state::AppState,
+};
 use axum::{
-    extract::Query,
+    extract::{Path, Query, State},
     response::{IntoResponse, Json, Redirect},
 };
-use core_access::User;
+use core_access::{
+    get_or_create_user,
+    sessions::{list_user_sessions, revoke_session, revoke_user_sessions},
+    Session, User,
+};
 use serde::{Deserialize, Serialize};
-use tracing::warn;
+use serde_json::{json, Value};
+use tracing::{info, warn};
+use utoipa::{IntoParams, ToSchema};
 
 /// The claims for our application-specific JWT.
 #[derive(Debug, Serialize, Deserialize)]
@@ -22,7 +37,8 @@ pub struct Claims {
 }
 
 // Placeholder structs to satisfy the router's handler signatures.
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct AuthRequest {
     #[allow(dead_code)]
     pub cli_port: u16,
@@ -34,6 +50,13 @@ pub struct AuthCallback {
 }
 
 /// Initiates the Google OAuth 2.0 login flow (Placeholder).
+#[utoipa::path(
+    get,
+    path = "/auth/login/google",
+    tag = "auth",
+    params(AuthRequest),
+    responses((status = 303, description = "Redirects to the Google sign-in page."))
+)]
 pub async fn google_login_handler(Query(_query): Query<AuthRequest>) -> impl IntoResponse {
     warn!("google_login_handler is a placeholder and is not functional.");
     // Redirect to Google's main page as a non-functional placeholder.
@@ -41,13 +64,167 @@ pub async fn google_login_handler(Query(_query): Query<AuthRequest>) -> impl Int
 }
 
 /// Handles the callback from Google after the user has authenticated (Placeholder).
+#[utoipa::path(
+    get,
+    path = "/auth/callback/google",
+    tag = "auth",
+    responses((status = 303, description = "Redirects to the login success page."))
+)]
 pub async fn google_auth_callback_handler(Query(_query): Query<AuthCallback>) -> impl IntoResponse {
     warn!("google_auth_callback_handler is a placeholder and is not functional.");
     Redirect::to("http://localhost:8080/login_success.html") // A generic success-like page
 }
 
 /// Returns the details of the currently authenticated user.
+#[utoipa::path(
+    get,
+    path = "/auth/me",
+    tag = "auth",
+    responses((status = 200, description = "The current user, or the guest user without a token.", body = User))
+)]
 pub async fn get_me_handler(user: AuthenticatedUser) -> Result<Json<User>, AppError> {
     // This handler can remain functional as it just returns the user from the extractor.
     Ok(Json(user.0))
 }
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OidcCallback {
+    /// The authorization code issued by the provider.
+    pub code: String,
+    /// The state of the login, as sent by `/auth/login/oidc`.
+    pub state: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LoginResponse {
+    #[serde(flatten)]
+    pub session: SessionTokens,
+    pub user: User,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+fn oidc_client(app_state: &AppState) -> Result<&OidcClient, AppError> {
+    app_state.oidc.as_deref().ok_or_else(|| {
+        AppError::Oidc(OidcError::NotConfigured(
+            "the server has no 'oidc' configuration".to_string(),
+        ))
+    })
+}
+
+/// Initiates sign-in with the configured OpenID Connect provider.
+#[utoipa::path(
+    get,
+    path = "/auth/login/oidc",
+    tag = "auth",
+    responses(
+        (status = 303, description = "Redirects to the sign-in page of the provider."),
+        (status = 404, description = "OpenID Connect is not configured.")
+    )
+)]
+pub async fn oidc_login_handler(State(app_state): State<AppState>) -> Result<Redirect, AppError> {
+    let url = oidc_client(&app_state)?.authorization_url().await?;
+    Ok(Redirect::to(&url))
+}
+
+/// Handles the redirect from the OpenID Connect provider after sign-in. The code is
+/// exchanged for an ID token, which is verified, and a session is started for its user.
+#[utoipa::path(
+    get,
+    path = "/auth/callback/oidc",
+    tag = "auth",
+    params(OidcCallback),
+    responses(
+        (status = 200, description = "The tokens of the new session and the signed-in user.", body = LoginResponse),
+        (status = 400, description = "The login state is unknown or expired.")
+    )
+)]
+pub async fn oidc_callback_handler(
+    State(app_state): State<AppState>,
+    Query(callback): Query<OidcCallback>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let claims = oidc_client(&app_state)?
+        .complete_login(&callback.code, &callback.state)
+        .await?;
+    info!("User '{}' signed in with OpenID Connect.", claims.sub);
+    let user = get_or_create_user(&app_state.sqlite_provider.db, &claims.sub, None)
+        .await
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get or create user: {e}")))?;
+    let session = start_session(&app_state, &user, &claims.sub).await?;
+    Ok(Json(LoginResponse { session, user }))
+}
+
+/// Exchanges a refresh token for a new access token and refresh token. The presented
+/// refresh token can't be used again.
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    tag = "auth",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "The new tokens of the session.", body = SessionTokens),
+        (status = 401, description = "The refresh token is invalid, expired or revoked.")
+    )
+)]
+pub async fn refresh_handler(
+    State(app_state): State<AppState>,
+    Json(payload): Json<RefreshRequest>,
+) -> Result<Json<SessionTokens>, AppError> {
+    Ok(Json(refresh(&app_state, &payload.refresh_token).await?))
+}
+
+/// Lists the active sessions of the current user.
+#[utoipa::path(
+    get,
+    path = "/auth/sessions",
+    tag = "auth",
+    responses((status = 200, description = "The active sessions, newest first.", body = Vec<Session>))
+)]
+pub async fn list_sessions_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+) -> Result<Json<Vec<Session>>, AppError> {
+    let sessions = list_user_sessions(&app_state.sqlite_provider.db, &user.0.id).await?;
+    Ok(Json(sessions))
+}
+
+/// Revokes a session. Its refresh token and access tokens are rejected from then on.
+///
+/// **Authorization**: Users may revoke their own sessions; other sessions require the
+/// `admin:users` permission.
+#[utoipa::path(
+    delete,
+    path = "/auth/sessions/{session_id}",
+    tag = "auth",
+    params(("session_id" = String, Path, description = "The session to revoke.")),
+    responses((status = 200, description = "The revoked session.", body = Session))
+)]
+pub async fn revoke_session_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(session_id): Path<String>,
+) -> Result<Json<Session>, AppError> {
+    info!("User '{}' revoking session '{}'.", user.0.id, session_id);
+    let session = revoke_session(&app_state.sqlite_provider.db, &session_id, &user.0).await?;
+    Ok(Json(session))
+}
+
+/// Revokes every session of the current user, signing them out everywhere.
+#[utoipa::path(
+    delete,
+    path = "/auth/sessions",
+    tag = "auth",
+    responses((status = 200, description = "The number of revoked sessions.", body = Value))
+)]
+pub async fn revoke_all_sessions_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+) -> Result<Json<Value>, AppError> {
+    info!("User '{}' revoking all of their sessions.", user.0.id);
+    let revoked = revoke_user_sessions(&app_state.sqlite_provider.db, &user.0.id).await?;
+    Ok(Json(json!({ "revoked": revoked })))
+}
@@ -0,0 +1,356 @@
+//! # Backup Route Handlers
+//!
+//! This module contains the handlers that back up the knowledge base to the backup
+//! directory, optionally uploading the backup to the configured object store, and that
+//! restore a backup into a fresh instance.
+//!
+//! When the knowledge base is sharded, each shard is backed up next to the primary
+//! database, into a directory named after the backup, e.g. `anyrag-<timestamp>/`.
+
+use crate::{
+    auth::middleware::AuthenticatedUser,
+    errors::AppError,
+    handlers::{wrap_response, ApiResponse, DebugParams},
+    state::AppState,
+};
+use anyrag::{
+    providers::db::sqlite::backup::{backup_to_file, restore_from_file, CopySummary},
+    types::AppConfig,
+};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::Utc;
+use core_access::{permissions::ADMIN_BACKUPS, require_permission};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+use tracing::info;
+use utoipa::ToSchema;
+
+/// The directory backups are kept in when `backup_dir` is not configured, next to the
+/// database file.
+const DEFAULT_BACKUP_DIR_NAME: &str = "backups";
+const BACKUP_FILE_PREFIX: &str = "anyrag-";
+#[cfg(feature = "objectstore")]
+const DOWNLOADED_FILE_PREFIX: &str = "downloaded-";
+const BACKUP_FILE_EXTENSION: &str = ".db";
+/// Backup names sort by the time they were made, e.g. `anyrag-20251015T093000123Z.db`.
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%3fZ";
+
+#[derive(Deserialize, ToSchema)]
+pub struct BackupRequest {
+    /// Also uploads the backup to a bucket of the configured object store.
+    #[serde(default)]
+    pub upload: Option<BackupObject>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RestoreRequest {
+    /// The name of a backup in the backup directory, as returned by `POST /admin/backups`.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Downloads the backup from a bucket of the configured object store instead. Only
+    /// the primary database is restored from a download; shards are restored by name.
+    #[serde(default)]
+    pub download: Option<BackupObject>,
+}
+
+/// The location of a backup in the object store.
+#[derive(Deserialize, ToSchema)]
+pub struct BackupObject {
+    pub bucket: String,
+    /// The key of the backup object when downloading. When uploading, the prefix the
+    /// backup's name is appended to, e.g. `backups/`.
+    #[serde(default)]
+    pub key: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BackupResponse {
+    pub message: String,
+    /// The name of the backup file in the backup directory.
+    pub name: String,
+    /// The number of rows copied, by table, across the primary database and the shards.
+    pub rows: BTreeMap<String, u64>,
+    /// The shards backed up into the directory named after the backup.
+    pub shards: Vec<String>,
+    /// The URL of the uploaded backup, if it was uploaded.
+    pub object_url: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RestoreResponse {
+    pub message: String,
+    /// The number of rows restored, by table, across the primary database and the shards.
+    pub rows: BTreeMap<String, u64>,
+    /// The shards restored from the directory named after the backup.
+    pub shards: Vec<String>,
+}
+
+/// Handler for backing up the knowledge base, including embeddings and metadata.
+///
+/// The backup is consistent while the server keeps serving requests.
+///
+/// **Authorization**: Requires the `admin:backups` permission.
+#[utoipa::path(
+    post,
+    path = "/admin/backups",
+    tag = "admin",
+    params(DebugParams),
+    request_body = BackupRequest,
+    responses((status = 200, description = "The backup was written. Requires the `admin:backups` permission.", body = ApiResponse<BackupResponse>))
+)]
+pub async fn create_backup_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Json(payload): Json<BackupRequest>,
+) -> Result<Json<ApiResponse<BackupResponse>>, AppError> {
+    let current_user = user.0;
+    require_permission(&current_user, ADMIN_BACKUPS)?;
+
+    let name = format!(
+        "{BACKUP_FILE_PREFIX}{}{BACKUP_FILE_EXTENSION}",
+        Utc::now().format(BACKUP_TIMESTAMP_FORMAT)
+    );
+    let path = backup_dir(&app_state.config).join(&name);
+    info!(
+        "User '{}' is backing up the knowledge base to '{}'.",
+        current_user.id,
+        path.display()
+    );
+    let mut summary = backup_to_file(&app_state.sqlite_provider.db, &path).await?;
+    let shard_dir = shard_backup_dir(&path);
+    let mut shards = Vec::new();
+    for (shard_name, shard) in app_state.db_router.shards().await? {
+        let shard_summary = backup_to_file(&shard.db, &shard_dir.join(&shard_name)).await?;
+        add_rows(&mut summary, shard_summary);
+        shards.push(shard_name);
+    }
+
+    let object_url = match &payload.upload {
+        Some(upload) => {
+            let object_url = upload_backup(&app_state.config, &path, &name, upload).await?;
+            let shard_prefix = BackupObject {
+                bucket: upload.bucket.clone(),
+                key: format!("{}{}/", upload.key, backup_stem(&name)),
+            };
+            for shard_name in &shards {
+                upload_backup(
+                    &app_state.config,
+                    &shard_dir.join(shard_name),
+                    shard_name,
+                    &shard_prefix,
+                )
+                .await?;
+            }
+            Some(object_url)
+        }
+        None => None,
+    };
+
+    let debug_info = json!({ "path": path, "total_rows": summary.total_rows() });
+    let response = BackupResponse {
+        message: format!("Backed up {} rows to '{name}'.", summary.total_rows()),
+        name,
+        rows: summary.rows,
+        shards,
+        object_url,
+    };
+    Ok(wrap_response(response, debug_params, Some(debug_info)))
+}
+
+/// Handler for restoring a backup into this instance, which must hold no documents.
+///
+/// **Authorization**: Requires the `admin:backups` permission.
+#[utoipa::path(
+    post,
+    path = "/admin/backups/restore",
+    tag = "admin",
+    params(DebugParams),
+    request_body = RestoreRequest,
+    responses((status = 200, description = "The backup was restored into this instance. Requires the `admin:backups` permission.", body = ApiResponse<RestoreResponse>))
+)]
+pub async fn restore_backup_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Json(payload): Json<RestoreRequest>,
+) -> Result<Json<ApiResponse<RestoreResponse>>, AppError> {
+    let current_user = user.0;
+    require_permission(&current_user, ADMIN_BACKUPS)?;
+
+    let path = match (&payload.name, &payload.download) {
+        (Some(name), None) => backup_dir(&app_state.config).join(validate_backup_name(name)?),
+        (None, Some(download)) => download_backup(&app_state.config, download).await?,
+        _ => {
+            return Err(AppError::BadRequest(
+                "Exactly one of 'name' and 'download' must be given.".to_string(),
+            ))
+        }
+    };
+    info!(
+        "User '{}' is restoring the knowledge base from '{}'.",
+        current_user.id,
+        path.display()
+    );
+    let mut summary = restore_from_file(&path, &app_state.sqlite_provider.db).await?;
+    let mut shards = Vec::new();
+    for shard_path in shard_backups(&shard_backup_dir(&path)).await? {
+        let Some(shard_name) = shard_path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let shard = app_state.db_router.shard(shard_name).await?;
+        add_rows(
+            &mut summary,
+            restore_from_file(&shard_path, &shard.db).await?,
+        );
+        shards.push(shard_name.to_string());
+    }
+
+    let debug_info = json!({ "path": path, "total_rows": summary.total_rows() });
+    let response = RestoreResponse {
+        message: format!("Restored {} rows.", summary.total_rows()),
+        rows: summary.rows,
+        shards,
+    };
+    Ok(wrap_response(response, debug_params, Some(debug_info)))
+}
+
+/// The configured backup directory, or a `backups` directory next to the database.
+fn backup_dir(config: &AppConfig) -> PathBuf {
+    match &config.backup_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => Path::new(&config.db_url)
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(DEFAULT_BACKUP_DIR_NAME),
+    }
+}
+
+/// The directory the shards of the backup at `path` are kept in, named after it.
+fn shard_backup_dir(path: &Path) -> PathBuf {
+    path.with_extension("")
+}
+
+/// The name of a backup without its extension, e.g. `anyrag-20251015T093000123Z`.
+fn backup_stem(name: &str) -> &str {
+    name.strip_suffix(BACKUP_FILE_EXTENSION).unwrap_or(name)
+}
+
+/// The shard backups in `dir`, ordered by name. There are none when the backed up
+/// knowledge base was not sharded.
+async fn shard_backups(dir: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(AppError::Internal(e.into())),
+    };
+    let mut paths = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| AppError::Internal(e.into()))?
+    {
+        let path = entry.path();
+        if path
+            .to_str()
+            .is_some_and(|p| p.ends_with(BACKUP_FILE_EXTENSION))
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Adds the rows copied from a shard to those of the whole backup.
+fn add_rows(summary: &mut CopySummary, shard: CopySummary) {
+    for (table, rows) in shard.rows {
+        *summary.rows.entry(table).or_default() += rows;
+    }
+}
+
+/// Accepts only the plain file names of backups, so a request cannot reach files
+/// outside the backup directory.
+fn validate_backup_name(name: &str) -> Result<&str, AppError> {
+    let is_file_name = Path::new(name).file_name().and_then(|n| n.to_str()) == Some(name);
+    if !is_file_name || name.starts_with('.') {
+        return Err(AppError::BadRequest(format!(
+            "'{name}' is not the name of a backup."
+        )));
+    }
+    Ok(name)
+}
+
+#[cfg(feature = "objectstore")]
+async fn upload_backup(
+    config: &AppConfig,
+    path: &Path,
+    name: &str,
+    upload: &BackupObject,
+) -> Result<String, AppError> {
+    use anyrag::providers::db::sqlite::backup::BackupError;
+
+    let client = crate::handlers::ingest::objectstore::object_store_client(config);
+    let key = format!("{}{name}", upload.key);
+    let body = tokio::fs::read(path).await.map_err(BackupError::from)?;
+    client
+        .put_object(&upload.bucket, &key, body)
+        .await
+        .map_err(|e| AppError::Ingest(e.into()))?;
+    Ok(client.object_url(&upload.bucket, &key))
+}
+
+#[cfg(not(feature = "objectstore"))]
+async fn upload_backup(
+    _config: &AppConfig,
+    _path: &Path,
+    _name: &str,
+    _upload: &BackupObject,
+) -> Result<String, AppError> {
+    Err(object_store_disabled())
+}
+
+/// Downloads a backup into the backup directory, returning its path.
+#[cfg(feature = "objectstore")]
+async fn download_backup(config: &AppConfig, download: &BackupObject) -> Result<PathBuf, AppError> {
+    use anyrag::providers::db::sqlite::backup::BackupError;
+
+    let client = crate::handlers::ingest::objectstore::object_store_client(config);
+    let body = client
+        .get_object(&download.bucket, &download.key)
+        .await
+        .map_err(|e| AppError::Ingest(e.into()))?;
+
+    let dir = backup_dir(config);
+    let path = dir.join(format!(
+        "{DOWNLOADED_FILE_PREFIX}{}{BACKUP_FILE_EXTENSION}",
+        Utc::now().format(BACKUP_TIMESTAMP_FORMAT)
+    ));
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(BackupError::from)?;
+    tokio::fs::write(&path, body)
+        .await
+        .map_err(BackupError::from)?;
+    Ok(path)
+}
+
+#[cfg(not(feature = "objectstore"))]
+async fn download_backup(
+    _config: &AppConfig,
+    _download: &BackupObject,
+) -> Result<PathBuf, AppError> {
+    Err(object_store_disabled())
+}
+
+#[cfg(not(feature = "objectstore"))]
+fn object_store_disabled() -> AppError {
+    AppError::BadRequest("The server was built without the 'objectstore' feature.".to_string())
+}
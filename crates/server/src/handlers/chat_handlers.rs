@@ -0,0 +1,228 @@
+//! # Chat Route Handlers
+//!
+//! This module contains the Axum handler for the `/chat` endpoint, which runs the
+//! knowledge RAG pipeline over a persisted conversation so follow-up questions
+//! resolve against prior turns.
+
+use super::{wrap_response, ApiResponse, AppError, AppState, DebugParams};
+use crate::{auth::middleware::AuthenticatedUser, moderation::moderate_answer};
+use anyrag::{
+    context_budget::{fit_context, ContextBudget},
+    context_sanitization::{document_context, guard_system_prompt},
+    moderation::ModerationReport,
+    providers::ai::AiProvider,
+    search::{hybrid_search, HybridSearchOptions, HybridSearchPrompts},
+    types::{ContentType, ExecutePromptOptions, PromptClientBuilder, ResolvedTask},
+    ChatClient, ChatMessage, ChatRole,
+};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// The number of prior messages loaded as context for a new turn.
+pub(crate) const CHAT_HISTORY_LIMIT: u32 = 20;
+/// The task that rewrites a follow-up message into a standalone query.
+pub(crate) const CHAT_QUERY_REWRITE_TASK: &str = "chat_query_rewrite";
+/// The task that extracts the search terms of a query.
+pub(crate) const QUERY_ANALYSIS_TASK: &str = "query_analysis";
+/// The task that answers a query from the retrieved context.
+pub(crate) const RAG_SYNTHESIS_TASK: &str = "rag_synthesis";
+
+// --- API Payloads for Chat ---
+
+#[derive(Deserialize, ToSchema)]
+pub struct ChatRequest {
+    /// The session to continue. A new session is started when omitted.
+    #[serde(default)]
+    pub session_id: Option<String>,
+    pub message: String,
+    pub limit: Option<u32>,
+    /// A collection, by id or name, that the retrieval is limited to.
+    #[serde(default)]
+    pub collection: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ChatResponse {
+    pub session_id: String,
+    pub text: String,
+    /// The follow-up message rewritten as a standalone query for retrieval.
+    pub standalone_query: String,
+    /// The moderation verdict, when the answer was flagged or blocked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moderation: Option<ModerationReport>,
+}
+
+// --- Chat Handlers ---
+
+/// Returns a configured task and a handle to the AI provider it runs on.
+pub(crate) fn task_with_provider<'a>(
+    app_state: &'a AppState,
+    task_name: &str,
+) -> anyhow::Result<(&'a ResolvedTask, Box<dyn AiProvider>)> {
+    let task = app_state
+        .tasks
+        .get(task_name)
+        .ok_or_else(|| anyhow::anyhow!("Task '{task_name}' not found in config"))?;
+    let provider_name = &task.provider;
+    let provider = app_state
+        .ai_providers
+        .get(provider_name)
+        .ok_or_else(|| anyhow::anyhow!("Provider '{provider_name}' not found"))?;
+    Ok((task, provider.clone()))
+}
+
+/// Handler for a single turn of a conversation over the knowledge base.
+#[utoipa::path(
+    post,
+    path = "/chat",
+    tag = "prompt",
+    params(DebugParams),
+    request_body = ChatRequest,
+    responses((status = 200, description = "The answer and the session to continue the conversation in.", body = ApiResponse<ChatResponse>))
+)]
+pub async fn chat_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Json(payload): Json<ChatRequest>,
+) -> Result<Json<ApiResponse<ChatResponse>>, AppError> {
+    let db = app_state.db_router.for_user(&user.0.id, None).await?;
+    let owner_id = Some(user.0.id);
+    let limit = payload.limit.unwrap_or(5);
+    let session_id = payload
+        .session_id
+        .clone()
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    info!(
+        "User '{:?}' sending chat message in session '{}': '{}'",
+        owner_id, session_id, payload.message
+    );
+
+    // --- Resolve the follow-up against the conversation history ---
+    let (rewrite_task, rewrite_provider) =
+        task_with_provider(&app_state, CHAT_QUERY_REWRITE_TASK).map_err(AppError::Internal)?;
+
+    let chat_client = ChatClient::new(rewrite_provider, db.clone());
+    let history = chat_client
+        .load_history(&session_id, owner_id.as_deref(), CHAT_HISTORY_LIMIT)
+        .await?;
+    let standalone_query = chat_client
+        .rewrite_follow_up(
+            &history,
+            &payload.message,
+            &rewrite_task.system_prompt,
+            &rewrite_task.user_prompt,
+        )
+        .await?;
+
+    // --- Retrieve context for the standalone query ---
+    let (analysis_task, analysis_provider) =
+        task_with_provider(&app_state, QUERY_ANALYSIS_TASK).map_err(AppError::Internal)?;
+
+    let search_options = HybridSearchOptions {
+        query_text: standalone_query.clone(),
+        owner_id: owner_id.clone(),
+        org_id: None,
+        collection: payload.collection.clone(),
+        limit,
+        prompts: HybridSearchPrompts {
+            analysis_system_prompt: &analysis_task.system_prompt,
+            analysis_user_prompt_template: &analysis_task.user_prompt,
+        },
+        use_keyword_search: true,
+        use_vector_search: true,
+        embedding_api_url: &app_state.config.embedding.api_url,
+        embedding_model: &app_state.config.embedding.model_name,
+        embedding_api_key: app_state.config.embedding.api_key.as_deref(),
+        temporal_ranking_config: None,
+    };
+    let search_results =
+        hybrid_search(db.clone(), Arc::from(analysis_provider), search_options).await?;
+
+    // --- Fit the retrieved context into the synthesis model's context window ---
+    let (synthesis_task, synthesis_provider) =
+        task_with_provider(&app_state, RAG_SYNTHESIS_TASK).map_err(AppError::Internal)?;
+    let budgeted = fit_context(
+        ContextBudget::for_provider(&app_state.config, &synthesis_task.provider),
+        search_results,
+    );
+    let search_results = budgeted.results;
+
+    let sanitizer = app_state.context_sanitizer.as_deref();
+    let documents = document_context(&search_results, sanitizer);
+    let context = documents.context;
+
+    // --- Synthesize the answer with the conversation history ---
+    let options = ExecutePromptOptions {
+        prompt: standalone_query.clone(),
+        content_type: Some(ContentType::Knowledge),
+        context: Some(context.clone()),
+        history: Some(history.clone()),
+        system_prompt_template: Some(match sanitizer {
+            Some(_) => guard_system_prompt(&synthesis_task.system_prompt),
+            None => synthesis_task.system_prompt.clone(),
+        }),
+        user_prompt_template: Some(synthesis_task.user_prompt.clone()),
+        ..Default::default()
+    };
+
+    let client = PromptClientBuilder::new()
+        .ai_provider(synthesis_provider)
+        .storage_provider(Box::new(db.as_ref().clone()))
+        .build()?;
+    let prompt_result = client.execute_prompt_with_options(options).await?;
+    // The moderated answer is the one persisted, so a blocked answer is not replayed
+    // as history in later turns.
+    let (answer, moderation) = moderate_answer(&app_state, prompt_result.text).await;
+
+    // --- Persist the turn ---
+    chat_client
+        .append_message(
+            &session_id,
+            owner_id.as_deref(),
+            &ChatMessage {
+                role: ChatRole::User,
+                content: payload.message.clone(),
+            },
+        )
+        .await?;
+    chat_client
+        .append_message(
+            &session_id,
+            owner_id.as_deref(),
+            &ChatMessage {
+                role: ChatRole::Assistant,
+                content: answer.clone(),
+            },
+        )
+        .await?;
+
+    let debug_info = json!({
+        "history_length": history.len(),
+        "retrieved_context": context,
+        "injection_detections": documents.detections,
+        "moderation": moderation,
+        "final_candidate_count": search_results.len(),
+        "context_tokens": budgeted.used_tokens,
+        "dropped_context": budgeted.dropped,
+        "owner_id": owner_id,
+    });
+    Ok(wrap_response(
+        ChatResponse {
+            session_id,
+            text: answer,
+            standalone_query,
+            moderation: moderation.filter(ModerationReport::is_reported),
+        },
+        debug_params,
+        Some(debug_info),
+    ))
+}
@@ -0,0 +1,260 @@
+//! # Collection Route Handlers
+//!
+//! This module contains handlers for managing the document collections of the
+//! requesting user. Search requests, chat messages and prompts take a `collection`, by
+//! id or name, to search only the documents of one of them.
+
+use crate::{
+    auth::middleware::AuthenticatedUser,
+    errors::AppError,
+    handlers::{wrap_response, ApiResponse, DebugParams},
+    state::AppState,
+};
+use anyrag::collections::{
+    add_documents_to_collection, collection_documents, create_collection, delete_collection,
+    get_collection, list_collections, remove_document_from_collection, update_collection,
+    Collection,
+};
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::info;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateCollectionRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateCollectionRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AddCollectionDocumentsRequest {
+    pub document_ids: Vec<String>,
+}
+
+/// Handler for creating a collection.
+#[utoipa::path(
+    post,
+    path = "/collections",
+    tag = "collections",
+    params(DebugParams),
+    request_body = CreateCollectionRequest,
+    responses((status = 200, description = "The new collection.", body = ApiResponse<Collection>))
+)]
+pub async fn create_collection_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Json(payload): Json<CreateCollectionRequest>,
+) -> Result<Json<ApiResponse<Collection>>, AppError> {
+    let current_user = user.0;
+    info!(
+        "User '{}' creating collection '{}'.",
+        current_user.id, payload.name
+    );
+    let db = app_state.db_router.for_user(&current_user.id, None).await?;
+    let collection = create_collection(
+        &db.db,
+        &current_user.id,
+        &payload.name,
+        payload.description.as_deref(),
+    )
+    .await?;
+
+    let debug_info = json!({ "requesting_user_id": current_user.id });
+    Ok(wrap_response(collection, debug_params, Some(debug_info)))
+}
+
+/// Handler for listing the collections of the requesting user by name.
+#[utoipa::path(
+    get,
+    path = "/collections",
+    tag = "collections",
+    params(DebugParams),
+    responses((status = 200, description = "The collections of the current user.", body = ApiResponse<Vec<Collection>>))
+)]
+pub async fn list_collections_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+) -> Result<Json<ApiResponse<Vec<Collection>>>, AppError> {
+    let current_user = user.0;
+    let db = app_state.db_router.for_user(&current_user.id, None).await?;
+    let collections = list_collections(&db.db, &current_user.id).await?;
+    let debug_info =
+        json!({ "requesting_user_id": current_user.id, "collection_count": collections.len() });
+    Ok(wrap_response(collections, debug_params, Some(debug_info)))
+}
+
+/// Handler for reading a collection.
+#[utoipa::path(
+    get,
+    path = "/collections/{id}",
+    tag = "collections",
+    params(DebugParams, ("id" = String, Path, description = "The collection, by id or name.")),
+    responses((status = 200, description = "The collection.", body = ApiResponse<Collection>))
+)]
+pub async fn get_collection_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Collection>>, AppError> {
+    let current_user = user.0;
+    let db = app_state.db_router.for_user(&current_user.id, None).await?;
+    let collection = get_collection(&db.db, &current_user.id, &id).await?;
+    Ok(wrap_response(collection, debug_params, None))
+}
+
+/// Handler for renaming a collection or changing its description.
+#[utoipa::path(
+    put,
+    path = "/collections/{id}",
+    tag = "collections",
+    params(DebugParams, ("id" = String, Path, description = "The collection, by id or name.")),
+    request_body = UpdateCollectionRequest,
+    responses((status = 200, description = "The updated collection.", body = ApiResponse<Collection>))
+)]
+pub async fn update_collection_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateCollectionRequest>,
+) -> Result<Json<ApiResponse<Collection>>, AppError> {
+    let current_user = user.0;
+    info!("User '{}' updating collection '{}'.", current_user.id, id);
+    let db = app_state.db_router.for_user(&current_user.id, None).await?;
+    let collection = update_collection(
+        &db.db,
+        &current_user.id,
+        &id,
+        payload.name.as_deref(),
+        payload.description.as_deref(),
+    )
+    .await?;
+    Ok(wrap_response(collection, debug_params, None))
+}
+
+/// Handler for deleting a collection. The documents in it are kept.
+#[utoipa::path(
+    delete,
+    path = "/collections/{id}",
+    tag = "collections",
+    params(DebugParams, ("id" = String, Path, description = "The collection, by id or name.")),
+    responses((status = 200, description = "The deleted collection.", body = ApiResponse<Value>))
+)]
+pub async fn delete_collection_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Value>>, AppError> {
+    let current_user = user.0;
+    info!("User '{}' deleting collection '{}'.", current_user.id, id);
+    let db = app_state.db_router.for_user(&current_user.id, None).await?;
+    delete_collection(&db.db, &current_user.id, &id).await?;
+    Ok(wrap_response(
+        json!({ "collection": id }),
+        debug_params,
+        None,
+    ))
+}
+
+/// Handler for listing the ids of the documents in a collection, most recently added
+/// first.
+#[utoipa::path(
+    get,
+    path = "/collections/{id}/documents",
+    tag = "collections",
+    params(DebugParams, ("id" = String, Path, description = "The collection, by id or name.")),
+    responses((status = 200, description = "The ids of the documents in the collection.", body = ApiResponse<Vec<String>>))
+)]
+pub async fn list_collection_documents_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<String>>>, AppError> {
+    let current_user = user.0;
+    let db = app_state.db_router.for_user(&current_user.id, None).await?;
+    let document_ids = collection_documents(&db.db, &current_user.id, &id).await?;
+    Ok(wrap_response(document_ids, debug_params, None))
+}
+
+/// Handler for adding documents to a collection.
+///
+/// **Authorization**: Only documents of the requesting user, or shared with them, can
+/// be added.
+#[utoipa::path(
+    post,
+    path = "/collections/{id}/documents",
+    tag = "collections",
+    params(DebugParams, ("id" = String, Path, description = "The collection, by id or name.")),
+    request_body = AddCollectionDocumentsRequest,
+    responses((status = 200, description = "The collection with the documents added.", body = ApiResponse<Collection>))
+)]
+pub async fn add_collection_documents_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(id): Path<String>,
+    Json(payload): Json<AddCollectionDocumentsRequest>,
+) -> Result<Json<ApiResponse<Collection>>, AppError> {
+    let current_user = user.0;
+    info!(
+        "User '{}' adding {} documents to collection '{}'.",
+        current_user.id,
+        payload.document_ids.len(),
+        id
+    );
+    let db = app_state.db_router.for_user(&current_user.id, None).await?;
+    let collection =
+        add_documents_to_collection(&db.db, &current_user.id, &id, &payload.document_ids).await?;
+    Ok(wrap_response(collection, debug_params, None))
+}
+
+/// Handler for taking a document out of a collection. The document itself is kept.
+#[utoipa::path(
+    delete,
+    path = "/collections/{id}/documents/{document_id}",
+    tag = "collections",
+    params(
+        DebugParams,
+        ("id" = String, Path, description = "The collection, by id or name."),
+        ("document_id" = String, Path, description = "The document to take out.")
+    ),
+    responses((status = 200, description = "Whether the document was in the collection.", body = ApiResponse<Value>))
+)]
+pub async fn remove_collection_document_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path((id, document_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<Value>>, AppError> {
+    let current_user = user.0;
+    info!(
+        "User '{}' removing document '{}' from collection '{}'.",
+        current_user.id, document_id, id
+    );
+    let db = app_state.db_router.for_user(&current_user.id, None).await?;
+    let removed =
+        remove_document_from_collection(&db.db, &current_user.id, &id, &document_id).await?;
+    Ok(wrap_response(
+        json!({ "collection": id, "document_id": document_id, "removed": removed }),
+        debug_params,
+        None,
+    ))
+}
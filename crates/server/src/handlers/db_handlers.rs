@@ -3,10 +3,14 @@
 //! This module contains handlers for direct database interaction endpoints.
 
 use super::{wrap_response, ApiResponse, AppError, DebugParams};
-use crate::state::AppState;
+use crate::{db_router::Tenant, state::AppState};
 use anyrag::{
-    constants,
-    providers::db::{sqlite::SqliteProvider, storage::Storage},
+    providers::db::storage::Storage,
+    schema_annotations::{
+        delete_schema_annotation, get_schema_annotations, upsert_schema_annotation,
+        SchemaAnnotationError,
+    },
+    types::SchemaAnnotation,
 };
 use axum::{
     extract::{Query, State},
@@ -15,20 +19,38 @@ use axum::{
 use serde::Deserialize;
 use serde_json::{json, Value};
 use tracing::info;
+use utoipa::{IntoParams, ToSchema};
 
 // --- API Payloads for DB Handlers ---
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct DbQueryRequest {
     pub db: String,
     pub query: String,
 }
 
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SchemaAnnotationQuery {
+    /// The database the table belongs to. The default database is used when omitted.
+    pub db: Option<String>,
+    pub table_name: Option<String>,
+    pub column_name: Option<String>,
+}
+
 // --- DB Handlers ---
 
 /// Handler for executing a raw, read-only SQL query against a specific project's database.
+#[utoipa::path(
+    post,
+    path = "/db/query",
+    tag = "db",
+    params(DebugParams),
+    request_body = DbQueryRequest,
+    responses((status = 200, description = "The rows returned by the query.", body = ApiResponse<Value>))
+)]
 pub async fn db_query_handler(
-    State(_app_state): State<AppState>,
+    State(app_state): State<AppState>,
     debug_params: Query<DebugParams>,
     Json(payload): Json<DbQueryRequest>,
 ) -> Result<Json<ApiResponse<Value>>, AppError> {
@@ -42,10 +64,11 @@ pub async fn db_query_handler(
         )));
     }
 
-    // Dynamically create a provider for the requested project's database.
-    let db_path = format!("{}/{}.db", constants::DB_DIR, payload.db);
-    let sqlite_provider = SqliteProvider::new(&db_path).await?;
-    sqlite_provider.initialize_schema().await?; // Ensure tables exist
+    // Open the requested project's database, with the configured query limits.
+    let sqlite_provider = app_state
+        .db_router
+        .route(Tenant::Project(&payload.db))
+        .await?;
 
     let result_json_str = sqlite_provider.execute_query(&payload.query).await?;
     let result_value: Value =
@@ -58,3 +81,83 @@ pub async fn db_query_handler(
 
     Ok(wrap_response(result_value, debug_params, Some(debug_info)))
 }
+
+/// Handler for listing the schema annotations of a database, optionally for one table.
+#[utoipa::path(
+    get,
+    path = "/db/annotations",
+    tag = "db",
+    params(DebugParams, SchemaAnnotationQuery),
+    responses((status = 200, description = "The matching schema annotations.", body = ApiResponse<Vec<SchemaAnnotation>>))
+)]
+pub async fn list_schema_annotations_handler(
+    State(app_state): State<AppState>,
+    debug_params: Query<DebugParams>,
+    Query(params): Query<SchemaAnnotationQuery>,
+) -> Result<Json<ApiResponse<Vec<SchemaAnnotation>>>, AppError> {
+    let annotations = get_schema_annotations(
+        &app_state.sqlite_provider.db,
+        params.db.as_deref(),
+        params.table_name.as_deref(),
+    )
+    .await?;
+
+    let debug_info = json!({ "db": params.db, "table_name": params.table_name });
+    Ok(wrap_response(annotations, debug_params, Some(debug_info)))
+}
+
+/// Handler for creating or replacing the annotation of a table or column.
+#[utoipa::path(
+    post,
+    path = "/db/annotations",
+    tag = "db",
+    params(DebugParams),
+    request_body = SchemaAnnotation,
+    responses((status = 200, description = "The stored schema annotation.", body = ApiResponse<SchemaAnnotation>))
+)]
+pub async fn upsert_schema_annotation_handler(
+    State(app_state): State<AppState>,
+    debug_params: Query<DebugParams>,
+    Json(payload): Json<SchemaAnnotation>,
+) -> Result<Json<ApiResponse<SchemaAnnotation>>, AppError> {
+    info!(
+        "Annotating '{}.{}' in db '{:?}'.",
+        payload.table_name,
+        payload.column_name.as_deref().unwrap_or_default(),
+        payload.db
+    );
+    upsert_schema_annotation(&app_state.sqlite_provider.db, &payload).await?;
+    Ok(wrap_response(payload, debug_params, None))
+}
+
+/// Handler for deleting the annotation of a table or column.
+#[utoipa::path(
+    delete,
+    path = "/db/annotations",
+    tag = "db",
+    params(DebugParams, SchemaAnnotationQuery),
+    responses((status = 200, description = "The number of deleted annotations.", body = ApiResponse<Value>))
+)]
+pub async fn delete_schema_annotation_handler(
+    State(app_state): State<AppState>,
+    debug_params: Query<DebugParams>,
+    Query(params): Query<SchemaAnnotationQuery>,
+) -> Result<Json<ApiResponse<Value>>, AppError> {
+    let table_name = params
+        .table_name
+        .as_deref()
+        .ok_or(SchemaAnnotationError::MissingTableName)?;
+    let deleted = delete_schema_annotation(
+        &app_state.sqlite_provider.db,
+        params.db.as_deref(),
+        table_name,
+        params.column_name.as_deref(),
+    )
+    .await?;
+
+    Ok(wrap_response(
+        json!({ "deleted": deleted }),
+        debug_params,
+        None,
+    ))
+}
@@ -8,21 +8,36 @@ use crate::{
     handlers::{wrap_response, ApiResponse, DebugParams},
     state::AppState,
 };
+use anyrag::ingest::{
+    document_history, export_knowledge_bases, list_trash, restore_document, soft_delete_document,
+    DocumentHistory, ExportFormat, TrashedDocument,
+};
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
+    http::header,
+    response::IntoResponse,
     Json,
 };
-use core_access::GUEST_USER_IDENTIFIER;
-use serde::Serialize;
-use serde_json::json;
+use core_access::{
+    document_shares::{list_document_shares, share_document, unshare_document},
+    has_permission,
+    organizations::list_user_organizations,
+    permissions::ADMIN_DOCUMENTS,
+    DocumentShare, User, GUEST_USER_IDENTIFIER,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use tracing::info;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 /// A response item for the document list.
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct DocumentListResponse {
     pub id: String,
     pub owner_id: String,
+    /// The organization the document is shared with.
+    pub org_id: Option<String>,
     pub source_url: String,
     pub title: String,
     pub created_at: String,
@@ -31,9 +46,17 @@ pub struct DocumentListResponse {
 /// Handler for retrieving a list of documents.
 ///
 /// **Authorization**: This endpoint is protected.
-/// - Users with the 'root' role can see all documents.
-/// - Regular users can see their own documents and documents owned by the guest user.
+/// - Users with the `admin:documents` permission can see all documents.
+/// - Regular users can see their own documents, documents owned by the guest user,
+///   documents shared with them, and documents shared with their organizations.
 /// - Guest users can only see guest-owned documents.
+#[utoipa::path(
+    get,
+    path = "/documents",
+    tag = "documents",
+    params(DebugParams),
+    responses((status = 200, description = "The documents visible to the current user.", body = ApiResponse<Vec<DocumentListResponse>>))
+)]
 pub async fn get_documents_handler(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
@@ -45,40 +68,379 @@ pub async fn get_documents_handler(
         current_user.id, current_user.role
     );
 
-    let conn = app_state.sqlite_provider.db.connect()?;
     let guest_user_id =
         Uuid::new_v5(&Uuid::NAMESPACE_URL, GUEST_USER_IDENTIFIER.as_bytes()).to_string();
 
-    let (query_sql, params) = if current_user.role == "root" {
+    let (query_sql, params) = if has_permission(&current_user, ADMIN_DOCUMENTS) {
         (
-            "SELECT id, owner_id, source_url, title, created_at FROM documents ORDER BY created_at DESC",
+            "SELECT id, owner_id, source_url, title, created_at, org_id FROM documents WHERE deleted_at IS NULL".to_string(),
             vec![],
         )
     } else if current_user.id == guest_user_id {
         (
-            "SELECT id, owner_id, source_url, title, created_at FROM documents WHERE owner_id = ? ORDER BY created_at DESC",
+            "SELECT id, owner_id, source_url, title, created_at, org_id FROM documents WHERE owner_id = ? AND deleted_at IS NULL".to_string(),
             vec![turso::Value::Text(guest_user_id)],
         )
     } else {
+        // Memberships are kept in the primary database, which shards don't share.
+        let org_ids: Vec<String> =
+            list_user_organizations(&app_state.db_router.primary().db, &current_user.id)
+                .await?
+                .into_iter()
+                .map(|org| org.id)
+                .collect();
+        let org_condition = if org_ids.is_empty() {
+            String::new()
+        } else {
+            format!(" OR org_id IN ({})", vec!["?"; org_ids.len()].join(", "))
+        };
+        let mut params = vec![
+            turso::Value::Text(current_user.id.clone()),
+            turso::Value::Text(guest_user_id),
+            turso::Value::Text(current_user.id.clone()),
+        ];
+        params.extend(org_ids.into_iter().map(turso::Value::Text));
         (
-            "SELECT id, owner_id, source_url, title, created_at FROM documents WHERE owner_id = ? OR owner_id = ? ORDER BY created_at DESC",
-            vec![turso::Value::Text(current_user.id.clone()), turso::Value::Text(guest_user_id)],
+            format!("SELECT id, owner_id, source_url, title, created_at, org_id FROM documents WHERE deleted_at IS NULL AND (owner_id = ? OR owner_id = ? OR id IN (SELECT document_id FROM document_shares WHERE user_id = ?){org_condition})"),
+            params,
         )
     };
 
-    let mut rows = conn.query(query_sql, params).await?;
+    // The documents visible to a user may live in any shard: those of the guest user,
+    // of their organizations, and those shared with them by other owners.
     let mut documents = Vec::new();
-    while let Some(row) = rows.next().await? {
-        documents.push(DocumentListResponse {
-            id: row.get(0)?,
-            owner_id: row.get(1).unwrap_or_default(),
-            source_url: row.get(2).unwrap_or_default(),
-            title: row.get(3).unwrap_or_default(),
-            created_at: row.get(4).unwrap_or_default(),
-        });
+    for db in app_state.db_router.knowledge_bases().await? {
+        let conn = db.db.connect()?;
+        let mut rows = conn.query(&query_sql, params.clone()).await?;
+        while let Some(row) = rows.next().await? {
+            documents.push(DocumentListResponse {
+                id: row.get(0)?,
+                owner_id: row.get(1).unwrap_or_default(),
+                source_url: row.get(2).unwrap_or_default(),
+                title: row.get(3).unwrap_or_default(),
+                created_at: row.get(4).unwrap_or_default(),
+                org_id: match row.get_value(5)? {
+                    turso::Value::Text(org_id) => Some(org_id),
+                    _ => None,
+                },
+            });
+        }
+    }
+    documents.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+    let debug_info =
+        json!({ "requesting_user_id": current_user.id, "document_count": documents.len() });
+    Ok(wrap_response(documents, debug_params, Some(debug_info)))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ShareDocumentRequest {
+    /// The users to grant read access to.
+    pub user_ids: Vec<String>,
+}
+
+/// Handler for sharing a document with other users.
+///
+/// **Authorization**: Only the owner of the document, or users with the
+/// `admin:documents` permission, can share it.
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/share",
+    tag = "documents",
+    params(DebugParams, ("id" = String, Path, description = "The document.")),
+    request_body = ShareDocumentRequest,
+    responses((status = 200, description = "Every user the document is shared with.", body = ApiResponse<Vec<DocumentShare>>))
+)]
+pub async fn share_document_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(id): Path<String>,
+    Json(payload): Json<ShareDocumentRequest>,
+) -> Result<Json<ApiResponse<Vec<DocumentShare>>>, AppError> {
+    let current_user = user.0;
+    // Guest content is visible to everyone, so sharing with the guest user is meaningless.
+    let guest_user_id =
+        Uuid::new_v5(&Uuid::NAMESPACE_URL, GUEST_USER_IDENTIFIER.as_bytes()).to_string();
+    if payload.user_ids.contains(&guest_user_id) {
+        return Err(AppError::Forbidden(
+            "Forbidden: documents cannot be shared with the guest user.".to_string(),
+        ));
+    }
+
+    info!(
+        "User '{}' sharing document '{}' with {:?}.",
+        current_user.id, id, payload.user_ids
+    );
+    let db = app_state.db_router.for_document(&id).await?;
+    let shares = share_document(
+        &db.db,
+        &app_state.db_router.primary().db,
+        &id,
+        &current_user,
+        &payload.user_ids,
+    )
+    .await?;
+
+    let debug_info = json!({ "requesting_user_id": current_user.id, "share_count": shares.len() });
+    Ok(wrap_response(shares, debug_params, Some(debug_info)))
+}
+
+/// Handler for listing the users a document is shared with.
+///
+/// **Authorization**: Only the owner of the document, or users with the
+/// `admin:documents` permission, can see its shares.
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/share",
+    tag = "documents",
+    params(DebugParams, ("id" = String, Path, description = "The document.")),
+    responses((status = 200, description = "Every user the document is shared with.", body = ApiResponse<Vec<DocumentShare>>))
+)]
+pub async fn list_document_shares_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<DocumentShare>>>, AppError> {
+    let db = app_state.db_router.for_document(&id).await?;
+    let shares = list_document_shares(&db.db, &id, &user.0).await?;
+    Ok(wrap_response(shares, debug_params, None))
+}
+
+/// Handler for revoking a user's access to a document.
+///
+/// **Authorization**: Only the owner of the document, or users with the
+/// `admin:documents` permission, can revoke its shares.
+#[utoipa::path(
+    delete,
+    path = "/documents/{id}/share/{user_id}",
+    tag = "documents",
+    params(
+        DebugParams,
+        ("id" = String, Path, description = "The document."),
+        ("user_id" = String, Path, description = "The user to revoke access from.")
+    ),
+    responses((status = 200, description = "The revoked share.", body = ApiResponse<Value>))
+)]
+pub async fn unshare_document_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path((id, user_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<Value>>, AppError> {
+    let current_user = user.0;
+    info!(
+        "User '{}' revoking the share of document '{}' with '{}'.",
+        current_user.id, id, user_id
+    );
+    let db = app_state.db_router.for_document(&id).await?;
+    unshare_document(&db.db, &id, &current_user, &user_id).await?;
+    Ok(wrap_response(
+        json!({ "document_id": id, "user_id": user_id }),
+        debug_params,
+        None,
+    ))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportDocumentsQuery {
+    /// `jsonl` (default), `parquet`, or `finetuning`.
+    #[serde(default)]
+    #[param(inline)]
+    pub format: ExportFormat,
+    /// Exports the documents of this user. Defaults to the current user, or to every
+    /// user for callers with the `admin:documents` permission.
+    pub owner_id: Option<String>,
+}
+
+/// Handler for exporting documents, with their metadata and embeddings, as a file.
+///
+/// **Authorization**: Users can export their own documents. Users with the
+/// `admin:documents` permission can export the documents of any user, or all of them.
+#[utoipa::path(
+    get,
+    path = "/documents/export",
+    tag = "documents",
+    params(ExportDocumentsQuery),
+    responses((status = 200, description = "The documents as JSONL or Parquet.", body = Vec<u8>))
+)]
+pub async fn export_documents_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(query): Query<ExportDocumentsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let current_user = user.0;
+    let is_admin = has_permission(&current_user, ADMIN_DOCUMENTS);
+    let owner_id = match (query.owner_id, is_admin) {
+        (Some(owner_id), true) => Some(owner_id),
+        (None, true) => None,
+        (Some(owner_id), false) if owner_id != current_user.id => {
+            return Err(AppError::Forbidden(
+                "Forbidden: only your own documents can be exported.".to_string(),
+            ))
+        }
+        (_, false) => Some(current_user.id.clone()),
+    };
+    info!(
+        "User '{}' is exporting the documents of {:?} as {}.",
+        current_user.id, owner_id, query.format
+    );
+
+    // Every owner has a shard of their own when the knowledge base is sharded; an
+    // export of all owners reads every shard.
+    let databases = match &owner_id {
+        Some(owner_id) => vec![app_state.db_router.for_user(owner_id, None).await?],
+        None => app_state.db_router.knowledge_bases().await?,
+    };
+    let databases: Vec<&turso::Database> = databases.iter().map(|db| &db.db).collect();
+    let body = export_knowledge_bases(&databases, query.format, owner_id.as_deref()).await?;
+    let disposition = format!(
+        "attachment; filename=\"documents.{}\"",
+        query.format.extension()
+    );
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                query.format.content_type().to_string(),
+            ),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    ))
+}
+
+/// Handler for the history of a document: the versions replaced when its source was
+/// ingested again.
+///
+/// **Authorization**: Only the owner of the document, or users with the
+/// `admin:documents` permission, can see its history.
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/history",
+    tag = "documents",
+    params(DebugParams, ("id" = String, Path, description = "The document.")),
+    responses((status = 200, description = "The replaced versions of the document, newest first.", body = ApiResponse<DocumentHistory>))
+)]
+pub async fn document_history_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<DocumentHistory>>, AppError> {
+    let current_user = user.0;
+    let db = app_state.db_router.for_user(&current_user.id, None).await?;
+    let conn = db.db.connect()?;
+    let history = document_history(&conn, &id).await?;
+    if history.owner_id.as_deref() != Some(current_user.id.as_str())
+        && !has_permission(&current_user, ADMIN_DOCUMENTS)
+    {
+        return Err(AppError::Forbidden(
+            "Forbidden: only the owner of a document can see its history.".to_string(),
+        ));
     }
 
+    let debug_info =
+        json!({ "requesting_user_id": current_user.id, "revision_count": history.revisions.len() });
+    Ok(wrap_response(history, debug_params, Some(debug_info)))
+}
+
+/// Handler for deleting a document. The document is moved to the trash, from which it
+/// can be restored until the trash is purged.
+///
+/// **Authorization**: Only the owner of the document, or users with the
+/// `admin:documents` permission, can delete it.
+#[utoipa::path(
+    delete,
+    path = "/documents/{id}",
+    tag = "documents",
+    params(DebugParams, ("id" = String, Path, description = "The document.")),
+    responses((status = 200, description = "The document moved to the trash.", body = ApiResponse<TrashedDocument>))
+)]
+pub async fn delete_document_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<TrashedDocument>>, AppError> {
+    let current_user = user.0;
+    info!(
+        "User '{}' is moving document '{}' to the trash.",
+        current_user.id, id
+    );
+    let db = app_state.db_router.for_user(&current_user.id, None).await?;
+    let conn = db.db.connect()?;
+    let owner_id = trash_owner(&current_user);
+    let document = soft_delete_document(&conn, &id, owner_id).await?;
+
+    let debug_info = json!({ "requesting_user_id": current_user.id });
+    Ok(wrap_response(document, debug_params, Some(debug_info)))
+}
+
+/// Handler for listing the documents in the trash, most recently deleted first.
+///
+/// **Authorization**: Users see their own deleted documents. Users with the
+/// `admin:documents` permission see those of every user.
+#[utoipa::path(
+    get,
+    path = "/documents/trash",
+    tag = "documents",
+    params(DebugParams),
+    responses((status = 200, description = "The documents in the trash.", body = ApiResponse<Vec<TrashedDocument>>))
+)]
+pub async fn list_trash_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+) -> Result<Json<ApiResponse<Vec<TrashedDocument>>>, AppError> {
+    let current_user = user.0;
+    let db = app_state.db_router.for_user(&current_user.id, None).await?;
+    let conn = db.db.connect()?;
+    let documents = list_trash(&conn, trash_owner(&current_user)).await?;
+
     let debug_info =
         json!({ "requesting_user_id": current_user.id, "document_count": documents.len() });
     Ok(wrap_response(documents, debug_params, Some(debug_info)))
 }
+
+/// Handler for restoring a document from the trash.
+///
+/// **Authorization**: Only the owner of the document, or users with the
+/// `admin:documents` permission, can restore it.
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/restore",
+    tag = "documents",
+    params(DebugParams, ("id" = String, Path, description = "The document.")),
+    responses((status = 200, description = "The restored document.", body = ApiResponse<TrashedDocument>))
+)]
+pub async fn restore_document_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<TrashedDocument>>, AppError> {
+    let current_user = user.0;
+    info!(
+        "User '{}' is restoring document '{}' from the trash.",
+        current_user.id, id
+    );
+    let db = app_state.db_router.for_user(&current_user.id, None).await?;
+    let conn = db.db.connect()?;
+    let document = restore_document(&conn, &id, trash_owner(&current_user)).await?;
+
+    let debug_info = json!({ "requesting_user_id": current_user.id });
+    Ok(wrap_response(document, debug_params, Some(debug_info)))
+}
+
+/// The owner whose trash a user acts on: their own, or every owner's for users with
+/// the `admin:documents` permission.
+fn trash_owner(user: &User) -> Option<&str> {
+    if has_permission(user, ADMIN_DOCUMENTS) {
+        None
+    } else {
+        Some(user.id.as_str())
+    }
+}
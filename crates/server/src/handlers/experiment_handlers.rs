@@ -0,0 +1,89 @@
+//! # Experiment Route Handlers
+//!
+//! This module contains the Axum handlers for A/B experiments: a summary of the
+//! recorded outcomes per variant, and feedback on an individual experiment run.
+
+use super::{wrap_response, ApiResponse, AppError, AppState, DebugParams};
+use anyrag::experiments::{
+    record_experiment_feedback, summarize_experiment, ExperimentError, ExperimentSummary,
+};
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::info;
+use utoipa::ToSchema;
+
+// --- API Payloads for Experiments ---
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct ExperimentFeedbackRequest {
+    /// `true` for a thumbs up, `false` for a thumbs down.
+    pub positive: bool,
+}
+
+// --- Experiment Handlers ---
+
+/// Handler for summarizing the recorded outcomes of an experiment per variant.
+#[utoipa::path(
+    get,
+    path = "/experiments/{name}/summary",
+    tag = "experiments",
+    params(DebugParams, ("name" = String, Path, description = "The experiment name.")),
+    responses((status = 200, description = "The outcomes of each variant.", body = ApiResponse<ExperimentSummary>))
+)]
+pub async fn experiment_summary_handler(
+    State(app_state): State<AppState>,
+    debug_params: Query<DebugParams>,
+    Path(name): Path<String>,
+) -> Result<Json<ApiResponse<ExperimentSummary>>, AppError> {
+    let config = app_state
+        .config
+        .experiments
+        .get(&name)
+        .ok_or_else(|| ExperimentError::UnknownExperiment(name.clone()))?;
+
+    let summary = summarize_experiment(&app_state.sqlite_provider.db, &name).await?;
+
+    let debug_info = json!({
+        "task": config.task,
+        "variant_a": config.variant_a,
+        "variant_b": config.variant_b,
+        "traffic_percent_b": config.traffic_percent_b,
+    });
+    Ok(wrap_response(summary, debug_params, Some(debug_info)))
+}
+
+/// Handler for attaching user feedback to a single experiment run.
+#[utoipa::path(
+    post,
+    path = "/experiments/runs/{run_id}/feedback",
+    tag = "experiments",
+    params(DebugParams, ("run_id" = i64, Path, description = "The experiment run to rate.")),
+    request_body = ExperimentFeedbackRequest,
+    responses((status = 200, description = "The feedback was recorded.", body = ApiResponse<Value>))
+)]
+pub async fn experiment_feedback_handler(
+    State(app_state): State<AppState>,
+    debug_params: Query<DebugParams>,
+    Path(run_id): Path<i64>,
+    Json(payload): Json<ExperimentFeedbackRequest>,
+) -> Result<Json<ApiResponse<Value>>, AppError> {
+    info!(
+        "Recording {} feedback for experiment run {run_id}.",
+        if payload.positive {
+            "positive"
+        } else {
+            "negative"
+        }
+    );
+    record_experiment_feedback(&app_state.sqlite_provider.db, run_id, payload.positive).await?;
+
+    Ok(wrap_response(
+        json!({ "run_id": run_id, "positive": payload.positive }),
+        debug_params,
+        None,
+    ))
+}
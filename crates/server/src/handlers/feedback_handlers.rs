@@ -0,0 +1,127 @@
+//! # Feedback Route Handlers
+//!
+//! This module contains the Axum handlers for rating answers and for accepting user
+//! corrections into the few-shot example store.
+
+use super::{wrap_response, ApiResponse, AppError, AppState, DebugParams};
+use crate::auth::middleware::AuthenticatedUser;
+use anyrag::{
+    experiments::record_experiment_feedback,
+    feedback::{accept_correction, record_feedback, NewFeedback},
+    types::FewShotExample,
+};
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use core_access::{permissions::ADMIN_FEEDBACK, require_permission};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+use utoipa::ToSchema;
+
+// --- API Payloads for Feedback ---
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct FeedbackRequest {
+    /// The client-side id of the rated prompt result.
+    pub result_id: String,
+    /// The prompt that produced the rated answer.
+    pub prompt: String,
+    /// `true` for a thumbs up, `false` for a thumbs down.
+    pub positive: bool,
+    /// The answer the user expected instead.
+    #[serde(default)]
+    pub correction: Option<String>,
+    /// The `experiment_run` returned with the answer, if it was served by an experiment.
+    #[serde(default)]
+    pub experiment_run_id: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct FeedbackResponse {
+    pub feedback_id: i64,
+}
+
+// --- Feedback Handlers ---
+
+/// Handler for rating an answer, optionally with a correction.
+#[utoipa::path(
+    post,
+    path = "/feedback",
+    tag = "feedback",
+    params(DebugParams),
+    request_body = FeedbackRequest,
+    responses((status = 200, description = "The recorded feedback.", body = ApiResponse<FeedbackResponse>))
+)]
+pub async fn feedback_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Json(payload): Json<FeedbackRequest>,
+) -> Result<Json<ApiResponse<FeedbackResponse>>, AppError> {
+    let owner_id = Some(user.0.id);
+    info!(
+        "User '{:?}' rating result '{}' as {}.",
+        owner_id,
+        payload.result_id,
+        if payload.positive {
+            "positive"
+        } else {
+            "negative"
+        }
+    );
+
+    let feedback = NewFeedback {
+        owner_id: owner_id.clone(),
+        result_id: payload.result_id.clone(),
+        prompt: payload.prompt,
+        positive: payload.positive,
+        correction: payload.correction,
+    };
+    let feedback_id = record_feedback(&app_state.sqlite_provider.db, &feedback).await?;
+
+    if let Some(run_id) = payload.experiment_run_id {
+        record_experiment_feedback(&app_state.sqlite_provider.db, run_id, payload.positive).await?;
+    }
+
+    let debug_info = json!({
+        "owner_id": owner_id,
+        "result_id": payload.result_id,
+        "experiment_run_id": payload.experiment_run_id,
+    });
+    Ok(wrap_response(
+        FeedbackResponse { feedback_id },
+        debug_params,
+        Some(debug_info),
+    ))
+}
+
+/// Handler for accepting the correction of a feedback entry as a few-shot example.
+///
+/// **Authorization**: Requires the `admin:feedback` permission.
+#[utoipa::path(
+    post,
+    path = "/feedback/{feedback_id}/accept",
+    tag = "feedback",
+    params(DebugParams, ("feedback_id" = i64, Path, description = "The feedback whose correction to accept.")),
+    responses((status = 200, description = "The few-shot example created from the correction.", body = ApiResponse<FewShotExample>))
+)]
+pub async fn accept_feedback_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(feedback_id): Path<i64>,
+) -> Result<Json<ApiResponse<FewShotExample>>, AppError> {
+    let current_user = user.0;
+    info!(
+        "User '{}' with role '{}' is accepting feedback {feedback_id}.",
+        current_user.id, current_user.role
+    );
+
+    // --- Authorization Check ---
+    require_permission(&current_user, ADMIN_FEEDBACK)?;
+
+    let example = accept_correction(&app_state.sqlite_provider.db, feedback_id).await?;
+    Ok(wrap_response(example, debug_params, None))
+}
@@ -1,40 +1,97 @@
 //! # General Route Handlers
 //!
 //! This module contains the general-purpose Axum handlers for the `anyrag-server`,
-//! including the root, health check, and the main Text-to-SQL prompt endpoint.
+//! including the root, health check, metrics, and the main Text-to-SQL prompt endpoint.
 
-use super::{wrap_response, ApiResponse, AppError, AppState, DebugParams};
-use anyrag::HttpRequestPromptOptions;
+use super::{wrap_response, ApiResponse, AppError, AppState, DebugParams, OrgHeader};
+use crate::auth::{middleware::AuthenticatedUser, org::org_context};
+use crate::metrics::{record_database_stats, PROMETHEUS_CONTENT_TYPE};
+use crate::moderation::moderate_answer;
+use crate::route_retriever::ServerRouteRetriever;
+use anyrag::{
+    chart::parse_chart_spec,
+    moderation::ModerationReport,
+    types::{ExperimentRun, OutputFormat},
+    HttpRequestPromptOptions,
+};
 use axum::{
     extract::{Query, State},
+    http::{header, HeaderMap},
+    response::IntoResponse,
     Json,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::sync::Arc;
 use tracing::info;
+use utoipa::ToSchema;
 
 // --- API Payloads for General Handlers ---
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct PromptResponse {
     pub text: Value,
+    /// The A/B experiment run that served this request, for attaching feedback.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experiment_run: Option<ExperimentRun>,
+    /// The moderation verdict on the answer, when it was flagged or blocked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moderation: Option<ModerationReport>,
 }
 
 // --- General-Purpose Handlers ---
 
 /// The handler for the root (`/`) endpoint.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "general",
+    responses((status = 200, description = "The server is running.", body = String))
+)]
 pub async fn root() -> &'static str {
     "anyrag server is running."
 }
 
 /// The handler for the health check (`/health`) endpoint.
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "general",
+    responses((status = 200, description = "The server is healthy.", body = String))
+)]
 pub async fn health_check() -> &'static str {
     "OK"
 }
 
+/// The handler for the Prometheus metrics (`/metrics`) endpoint. Like `/health`, it
+/// requires no authentication, so that scrapers can reach it.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "general",
+    responses((status = 200, description = "The metrics, in the Prometheus text format.", body = String))
+)]
+pub async fn metrics_handler(State(app_state): State<AppState>) -> impl IntoResponse {
+    record_database_stats(&app_state.sqlite_provider).await;
+    (
+        [(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+        app_state.metrics.render(),
+    )
+}
+
 /// The primary handler for the `/prompt` endpoint.
+#[utoipa::path(
+    post,
+    path = "/prompt",
+    tag = "prompt",
+    params(DebugParams, OrgHeader),
+    request_body = Value,
+    responses((status = 200, description = "The answer to the prompt.", body = ApiResponse<PromptResponse>))
+)]
 pub async fn prompt_handler(
     State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    headers: HeaderMap,
     debug_params: Query<DebugParams>,
     Json(payload): Json<Value>,
 ) -> Result<Json<ApiResponse<PromptResponse>>, AppError> {
@@ -42,13 +99,28 @@ pub async fn prompt_handler(
     let server_options: HttpRequestPromptOptions =
         serde_json::from_value(payload).map_err(anyrag::PromptError::from)?;
 
+    // Routed prompts answer from the documents the caller can see, in the database
+    // the caller's requests are routed to.
+    let org_id = org_context(&app_state, &user.0, &headers).await?;
+    let db = app_state
+        .db_router
+        .for_user(&user.0.id, org_id.as_deref())
+        .await?;
+    let route_retriever =
+        ServerRouteRetriever::for_caller(&app_state, db, Some(user.0.id.clone()), org_id);
+
     // All business logic is now delegated to the library crate.
     // The library will handle shorthand commands, dynamic provider creation, etc.
     // All business logic is now delegated to the library's executor.
     let prompt_result = app_state
         .executor
+        .as_ref()
+        .clone()
+        .with_route_retriever(Arc::new(route_retriever))
+        .with_owner_id(Some(user.0.id.clone()))
         .execute_http_prompt(server_options.clone())
         .await?;
+    let (answer, moderation) = moderate_answer(&app_state, prompt_result.text).await;
 
     let debug_info = if debug_params.debug.unwrap_or(false) {
         Some(json!({
@@ -56,14 +128,33 @@ pub async fn prompt_handler(
             // "model_used" is now determined within the lib crate.
             "generated_sql": prompt_result.generated_sql,
             "database_result": prompt_result.database_result,
+            "route": prompt_result.route,
+            "trace": prompt_result.trace,
+            "moderation": moderation,
         }))
     } else {
         None
     };
 
+    // A chart spec or a schema-conforming result is returned as JSON rather than as a
+    // string. A chart spec is checked once more before it reaches the dashboard. A
+    // blocked answer is replaced with the blocked message in any format.
+    let blocked = moderation.as_ref().is_some_and(|report| report.blocked);
+    let text = match (server_options.output, &server_options.output_schema) {
+        _ if blocked => Value::String(answer),
+        (Some(OutputFormat::Chart), _) => serde_json::to_value(
+            parse_chart_spec(&answer)
+                .map_err(|errors| anyrag::PromptError::OutputSchemaViolation(errors.join("; ")))?,
+        )?,
+        (_, Some(_)) => serde_json::from_str(&answer)?,
+        _ => Value::String(answer),
+    };
+
     Ok(wrap_response(
         PromptResponse {
-            text: Value::String(prompt_result.text),
+            text,
+            experiment_run: prompt_result.experiment_run,
+            moderation: moderation.filter(ModerationReport::is_reported),
         },
         debug_params,
         debug_info,
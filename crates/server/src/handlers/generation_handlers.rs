@@ -5,9 +5,10 @@
 //! that decides the best method to retrieve context for generation.
 
 use super::{wrap_response, ApiResponse, AppError, AppState, DebugParams, PromptResponse};
-use crate::auth::middleware::AuthenticatedUser;
+use crate::{auth::middleware::AuthenticatedUser, db_router::Tenant, moderation::moderate_answer};
 use anyrag::{
-    providers::{db::sqlite::SqliteProvider, factory::create_dynamic_provider},
+    moderation::ModerationReport,
+    providers::factory::create_dynamic_provider,
     search::{hybrid_search, HybridSearchOptions, HybridSearchPrompts},
     types::{ExecutePromptOptions as LibExecutePromptOptions, PromptClientBuilder},
 };
@@ -32,6 +33,14 @@ use super::generation_types::{AgentDecision, DeconstructedQuery, GenTextRequest}
 ///     retrieve relevant data from the specified database (`db`).
 /// 2.  **Content Generation**: The retrieved data is used as context for a second
 ///     LLM call, which uses the `generation_prompt` to create the final text output.
+#[utoipa::path(
+    post,
+    path = "/gen/text",
+    tag = "generation",
+    params(DebugParams),
+    request_body = GenTextRequest,
+    responses((status = 200, description = "The generated text.", body = ApiResponse<PromptResponse>))
+)]
 pub async fn gen_text_handler(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
@@ -44,17 +53,18 @@ pub async fn gen_text_handler(
     // use the default provider from the application state.
     let (sqlite_provider, db_name) = if let Some(db_name_str) = payload.db.clone() {
         info!(
-            "Request specified db: '{}'. Creating dynamic SQLite provider.",
+            "Request specified db: '{}'. Using its project database.",
             db_name_str
         );
-        let db_path = format!("{}/{db_name_str}.db", anyrag::constants::DB_DIR);
-        let provider = SqliteProvider::new(&db_path).await?;
-        provider.initialize_schema().await?;
-        (Arc::new(provider), db_name_str)
+        let provider = app_state
+            .db_router
+            .route(Tenant::Project(&db_name_str))
+            .await?;
+        (provider, db_name_str)
     } else {
-        info!("No db specified in request. Using default SQLite provider.");
+        info!("No db specified in request. Using the database of the user.");
         (
-            app_state.sqlite_provider.clone(),
+            app_state.db_router.for_user(&user.0.id, None).await?,
             std::path::Path::new(&app_state.config.db_url)
                 .file_stem()
                 .and_then(std::ffi::OsStr::to_str)
@@ -197,6 +207,7 @@ pub async fn gen_text_handler(
                 let context_result = client.execute_prompt_with_options(options).await?;
                 retrieved_context = context_result.database_result.unwrap_or_default();
                 debug_context["generated_sql"] = json!(context_result.generated_sql);
+                debug_context["trace"] = json!(context_result.trace);
             }
             "knowledge_search" => {
                 let analysis_task_config = app_state.tasks.get("query_analysis").unwrap();
@@ -208,6 +219,8 @@ pub async fn gen_text_handler(
                 let search_options = HybridSearchOptions {
                     query_text: agent_decision.query,
                     owner_id: Some(user.0.id.clone()),
+                    org_id: None,
+                    collection: None,
                     limit: payload.rerank_limit.unwrap_or(10),
                     prompts: HybridSearchPrompts {
                         analysis_system_prompt: &analysis_task_config.system_prompt,
@@ -249,6 +262,8 @@ pub async fn gen_text_handler(
                 "Failed to generate content: No relevant context was found for your request."
                     .to_string(),
             ),
+            experiment_run: None,
+            moderation: None,
         };
         let debug_info = json!({
             "status": "Aborted due to no context",
@@ -302,9 +317,15 @@ pub async fn gen_text_handler(
         .unwrap_or(&raw_response)
         .trim();
 
-    let final_value = match serde_json::from_str(cleaned_response) {
-        Ok(json_value) => json_value,
-        Err(_) => Value::String(raw_response.clone()),
+    // The raw response is moderated, so that a structured one is checked as a whole.
+    let (moderated, moderation) = moderate_answer(&app_state, raw_response.clone()).await;
+    let final_value = if moderation.as_ref().is_some_and(|report| report.blocked) {
+        Value::String(moderated)
+    } else {
+        match serde_json::from_str(cleaned_response) {
+            Ok(json_value) => json_value,
+            Err(_) => Value::String(raw_response.clone()),
+        }
     };
 
     let debug_info = json!({
@@ -321,10 +342,15 @@ pub async fn gen_text_handler(
         "raw_ai_response": raw_response,
         "model_override": payload.model,
         "model_used": model_used_name,
+        "moderation": moderation,
     });
 
     Ok(wrap_response(
-        PromptResponse { text: final_value },
+        PromptResponse {
+            text: final_value,
+            experiment_run: None,
+            moderation: moderation.filter(ModerationReport::is_reported),
+        },
         debug_params,
         Some(debug_info),
     ))
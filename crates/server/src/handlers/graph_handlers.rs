@@ -1,35 +1,166 @@
 //! # Knowledge Graph Route Handlers
 //!
-//! This module contains handlers for endpoints that interact with the in-memory
-//! Knowledge Graph, such as building it from a local database.
+//! This module contains handlers for endpoints that interact with the Knowledge
+//! Graph, such as building it from a local database, merging its duplicate entities
+//! and compacting its store.
 
-use super::{wrap_response, ApiResponse, AppError, AppState, DebugParams};
-use crate::auth::middleware::AuthenticatedUser;
+use super::{
+    knowledge_search_handler, search::SearchRequest, wrap_response, ApiResponse, AppError,
+    AppState, DebugParams, OrgHeader, PromptResponse,
+};
+use crate::{
+    auth::{middleware::AuthenticatedUser, org::org_context},
+    graph_extraction::fact_visibility,
+    moderation::moderate_answer,
+};
+use anyrag::{
+    graph::{
+        query::{FactVisibility, GraphEdge, GraphFact, GraphNode, GraphView},
+        resolution::{
+            plan_entity_merges, EntityMerge, EntityResolutionOptions, ResolutionAdjudicator,
+            ResolutionEmbedding, DEFAULT_ADJUDICATION_THRESHOLD, DEFAULT_MERGE_THRESHOLD,
+        },
+    },
+    moderation::ModerationReport,
+    types::PromptClientBuilder,
+};
 use axum::{
     extract::{Query, State},
+    http::HeaderMap,
     Json,
 };
 use chrono::{DateTime, Utc};
+use core_access::{permissions::ADMIN_GRAPH, require_permission};
 use serde::Deserialize;
 use serde_json::json;
 use std::path::Path;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use turso::Value as TursoValue;
+use utoipa::ToSchema;
 
 // --- API Payloads for Graph Handlers ---
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct GraphBuildRequest {
     pub db: String,
     pub table_name: String,
 }
 
-#[derive(serde::Serialize, Debug)]
+#[derive(serde::Serialize, Debug, ToSchema)]
 pub struct GraphBuildResponse {
     pub message: String,
     pub facts_added: usize,
 }
 
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct GraphNeighborsRequest {
+    pub entity: String,
+    /// Only the facts valid at this time are included. All facts are included when unset.
+    #[serde(default)]
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct GraphPathRequest {
+    pub from: String,
+    pub to: String,
+    /// The longest chain of facts searched. Defaults to 4.
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    /// Only the facts valid at this time are followed. All facts are followed when unset.
+    #[serde(default)]
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct GraphFactsRequest {
+    pub entity: String,
+    /// The time the facts must be valid at. Defaults to now.
+    #[serde(default)]
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+/// A subgraph, as nodes and edges for graph visualization.
+#[derive(serde::Serialize, Debug, Default, ToSchema)]
+pub struct GraphViewResponse {
+    /// The entities, each with an `id` and a `name`.
+    #[schema(value_type = Vec<Object>)]
+    pub nodes: Vec<GraphNode>,
+    /// The facts, each from a `source` to a `target` node, with its `predicate`, validity
+    /// period, `source_document` and the `source_documents` it was extracted from.
+    #[schema(value_type = Vec<Object>)]
+    pub edges: Vec<GraphEdge>,
+}
+
+impl From<GraphView> for GraphViewResponse {
+    fn from(view: GraphView) -> Self {
+        Self {
+            nodes: view.nodes,
+            edges: view.edges,
+        }
+    }
+}
+
+#[derive(serde::Serialize, Debug, ToSchema)]
+pub struct GraphPathResponse {
+    /// Whether the entities are linked within `max_depth` facts.
+    pub found: bool,
+    /// The entities and facts of the path, in order from `from` to `to`.
+    pub path: GraphViewResponse,
+}
+
+#[derive(serde::Serialize, Debug, ToSchema)]
+pub struct GraphFactsResponse {
+    pub entity: String,
+    pub as_of: DateTime<Utc>,
+    #[schema(value_type = Vec<Object>)]
+    pub facts: Vec<GraphFact>,
+}
+
+#[derive(serde::Serialize, Debug, ToSchema)]
+pub struct GraphCompactResponse {
+    pub message: String,
+    /// The number of entities in the compacted graph.
+    pub entities: usize,
+    /// The number of facts in the compacted graph.
+    pub facts: usize,
+}
+
+#[derive(Deserialize, Debug, Default, ToSchema)]
+pub struct GraphResolveRequest {
+    /// Plans the merges without applying them.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// The name similarity at or above which entities are merged. Defaults to 0.92.
+    #[serde(default)]
+    pub merge_threshold: Option<f32>,
+    /// The name similarity at or above which the AI provider judges whether entities
+    /// match. Defaults to 0.8.
+    #[serde(default)]
+    pub adjudication_threshold: Option<f32>,
+    /// Skips the AI provider, merging only exact and very similar names.
+    #[serde(default)]
+    pub skip_adjudication: bool,
+}
+
+#[derive(serde::Serialize, Debug, ToSchema)]
+pub struct GraphResolveResponse {
+    pub message: String,
+    /// Whether the merges were applied, which a dry run does not.
+    pub applied: bool,
+    /// The merges, each of a `duplicate` entity into its `canonical` one, with the
+    /// `method` that found it.
+    #[schema(value_type = Vec<Object>)]
+    pub merges: Vec<EntityMerge>,
+}
+
+/// The task questions are turned into graph queries with.
+const GRAPH_QUERY_TASK: &str = "graph_query_generation";
+/// The task uncertain entity matches are judged with.
+const ENTITY_RESOLUTION_TASK: &str = "entity_resolution";
+/// The longest chain of facts `/graph/path` searches when the request sets none.
+const DEFAULT_PATH_MAX_DEPTH: usize = 4;
+
 // --- Graph Handlers ---
 
 // This struct is updated to handle potential nulls from the AI response.
@@ -42,6 +173,14 @@ struct AiFactMapping {
 
 /// Handler for building or updating the in-memory Knowledge Graph from a local database table.
 /// This handler intelligently maps columns from the source table to graph facts using an AI.
+#[utoipa::path(
+    post,
+    path = "/graph/build",
+    tag = "graph",
+    params(DebugParams),
+    request_body = GraphBuildRequest,
+    responses((status = 200, description = "The number of facts added to the graph.", body = ApiResponse<GraphBuildResponse>))
+)]
 pub async fn graph_build_handler(
     State(app_state): State<AppState>,
     _user: AuthenticatedUser, // Ensures the endpoint is protected
@@ -214,3 +353,386 @@ pub async fn graph_build_handler(
     });
     Ok(wrap_response(response, debug_params, Some(debug_info)))
 }
+
+/// Handler for compacting the persistent knowledge graph.
+///
+/// The live graph is rewritten into a fresh RocksDB store, which drops the space that
+/// deleted and overwritten facts still take up. Requests that use the graph wait until
+/// the compaction finishes.
+///
+/// **Authorization**: Requires the `admin:graph` permission.
+#[utoipa::path(
+    post,
+    path = "/admin/graph/compact",
+    tag = "admin",
+    params(DebugParams),
+    responses((status = 200, description = "The graph was compacted. Requires the `admin:graph` permission.", body = ApiResponse<GraphCompactResponse>))
+)]
+pub async fn graph_compact_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+) -> Result<Json<ApiResponse<GraphCompactResponse>>, AppError> {
+    let current_user = user.0;
+    require_permission(&current_user, ADMIN_GRAPH)?;
+    info!(
+        "User '{}' is compacting the knowledge graph.",
+        current_user.id
+    );
+
+    let knowledge_graph = app_state.knowledge_graph.clone();
+    let stats = tokio::task::spawn_blocking(move || {
+        let mut kg = knowledge_graph
+            .write()
+            .map_err(|_| AppError::Internal(anyhow::anyhow!("Failed to acquire KG write lock")))?;
+        kg.compact().map_err(AppError::from)
+    })
+    .await
+    .map_err(anyhow::Error::from)??;
+
+    let response = GraphCompactResponse {
+        message: "Knowledge Graph compaction completed.".to_string(),
+        entities: stats.entities,
+        facts: stats.facts,
+    };
+    Ok(wrap_response(response, debug_params, None))
+}
+
+/// Handler for merging the duplicate entities of the knowledge graph.
+///
+/// Entities whose names match once normalized, whose name embeddings are very similar,
+/// or that the `entity_resolution` task judges to be the same are merged into one,
+/// which keeps the other names as aliases. Lookups by an alias then find the facts of
+/// every merged entity.
+///
+/// **Authorization**: Requires the `admin:graph` permission.
+#[utoipa::path(
+    post,
+    path = "/admin/graph/resolve",
+    tag = "admin",
+    params(DebugParams),
+    request_body = GraphResolveRequest,
+    responses((status = 200, description = "The merged entities. Requires the `admin:graph` permission.", body = ApiResponse<GraphResolveResponse>))
+)]
+pub async fn graph_resolve_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Json(payload): Json<GraphResolveRequest>,
+) -> Result<Json<ApiResponse<GraphResolveResponse>>, AppError> {
+    let current_user = user.0;
+    require_permission(&current_user, ADMIN_GRAPH)?;
+    info!(
+        "User '{}' is resolving the knowledge graph's entities.",
+        current_user.id
+    );
+
+    let names = {
+        let kg = app_state
+            .knowledge_graph
+            .read()
+            .map_err(|_| AppError::Internal(anyhow::anyhow!("Failed to acquire KG read lock")))?;
+        kg.entity_names()?
+    };
+
+    let adjudicator = if payload.skip_adjudication {
+        None
+    } else {
+        let task_config = app_state.tasks.get(ENTITY_RESOLUTION_TASK).ok_or_else(|| {
+            AppError::Internal(anyhow::anyhow!(
+                "Task '{ENTITY_RESOLUTION_TASK}' not found in config"
+            ))
+        })?;
+        let ai_provider = app_state
+            .ai_providers
+            .get(&task_config.provider)
+            .ok_or_else(|| {
+                AppError::Internal(anyhow::anyhow!(
+                    "Provider '{}' not found",
+                    task_config.provider
+                ))
+            })?;
+        Some((ai_provider, task_config))
+    };
+    let embedding = &app_state.config.embedding;
+    let options = EntityResolutionOptions {
+        embedding: Some(ResolutionEmbedding {
+            api_url: &embedding.api_url,
+            model: &embedding.model_name,
+            api_key: embedding.api_key.as_deref(),
+        }),
+        adjudicator: adjudicator.map(|(ai_provider, task_config)| ResolutionAdjudicator {
+            ai_provider: ai_provider.as_ref(),
+            system_prompt: &task_config.system_prompt,
+            user_prompt_template: &task_config.user_prompt,
+        }),
+        merge_threshold: payload.merge_threshold.unwrap_or(DEFAULT_MERGE_THRESHOLD),
+        adjudication_threshold: payload
+            .adjudication_threshold
+            .unwrap_or(DEFAULT_ADJUDICATION_THRESHOLD),
+    };
+    let planned = plan_entity_merges(&names, &options).await?;
+
+    let merges = if payload.dry_run {
+        planned
+    } else {
+        let mut kg = app_state
+            .knowledge_graph
+            .write()
+            .map_err(|_| AppError::Internal(anyhow::anyhow!("Failed to acquire KG write lock")))?;
+        kg.apply_entity_merges(&planned)
+    };
+
+    let response = GraphResolveResponse {
+        message: format!(
+            "{} {} duplicate entities.",
+            if payload.dry_run { "Found" } else { "Merged" },
+            merges.len()
+        ),
+        applied: !payload.dry_run,
+        merges,
+    };
+    let debug_info = json!({ "entities": names.len() });
+    Ok(wrap_response(response, debug_params, Some(debug_info)))
+}
+
+/// Handler for the neighborhood of an entity: the facts it is the subject or object of,
+/// and the entities on their other end. Facts extracted from documents the caller
+/// cannot see are left out, and an entity with no visible facts is not found.
+///
+/// **Authorization**: Requires the `search:read` permission.
+#[utoipa::path(
+    post,
+    path = "/graph/neighbors",
+    tag = "graph",
+    params(DebugParams, OrgHeader),
+    request_body = GraphNeighborsRequest,
+    responses((status = 200, description = "The entity and its neighbors.", body = ApiResponse<GraphViewResponse>))
+)]
+pub async fn graph_neighbors_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    headers: HeaderMap,
+    Json(payload): Json<GraphNeighborsRequest>,
+) -> Result<Json<ApiResponse<GraphViewResponse>>, AppError> {
+    info!(
+        "User '{}' queried the graph neighbors of '{}'",
+        user.0.id, payload.entity
+    );
+    let visibility = caller_fact_visibility(&app_state, &user, &headers).await?;
+    let view = {
+        let kg = app_state
+            .knowledge_graph
+            .read()
+            .map_err(|_| AppError::Internal(anyhow::anyhow!("Failed to acquire KG read lock")))?;
+        kg.neighbors(&payload.entity, payload.as_of, &visibility)?
+    };
+    let debug_info = json!({
+        "entity": payload.entity,
+        "as_of": payload.as_of,
+        "nodes": view.nodes.len(),
+        "edges": view.edges.len(),
+    });
+    Ok(wrap_response(
+        GraphViewResponse::from(view),
+        debug_params,
+        Some(debug_info),
+    ))
+}
+
+/// Handler for the shortest chain of facts between two entities, following facts in
+/// either direction. Only the facts visible to the caller are followed.
+///
+/// **Authorization**: Requires the `search:read` permission.
+#[utoipa::path(
+    post,
+    path = "/graph/path",
+    tag = "graph",
+    params(DebugParams, OrgHeader),
+    request_body = GraphPathRequest,
+    responses((status = 200, description = "The shortest path, if the entities are linked.", body = ApiResponse<GraphPathResponse>))
+)]
+pub async fn graph_path_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    headers: HeaderMap,
+    Json(payload): Json<GraphPathRequest>,
+) -> Result<Json<ApiResponse<GraphPathResponse>>, AppError> {
+    info!(
+        "User '{}' queried the graph path from '{}' to '{}'",
+        user.0.id, payload.from, payload.to
+    );
+    let max_depth = payload.max_depth.unwrap_or(DEFAULT_PATH_MAX_DEPTH);
+    let visibility = caller_fact_visibility(&app_state, &user, &headers).await?;
+    let path = {
+        let kg = app_state
+            .knowledge_graph
+            .read()
+            .map_err(|_| AppError::Internal(anyhow::anyhow!("Failed to acquire KG read lock")))?;
+        kg.shortest_path(
+            &payload.from,
+            &payload.to,
+            max_depth,
+            payload.as_of,
+            &visibility,
+        )?
+    };
+    let response = GraphPathResponse {
+        found: path.is_some(),
+        path: path.map(GraphViewResponse::from).unwrap_or_default(),
+    };
+    let debug_info = json!({
+        "from": payload.from,
+        "to": payload.to,
+        "max_depth": max_depth,
+        "as_of": payload.as_of,
+    });
+    Ok(wrap_response(response, debug_params, Some(debug_info)))
+}
+
+/// Handler for every fact about an entity that is valid at a point in time and visible
+/// to the caller.
+///
+/// **Authorization**: Requires the `search:read` permission.
+#[utoipa::path(
+    post,
+    path = "/graph/facts",
+    tag = "graph",
+    params(DebugParams, OrgHeader),
+    request_body = GraphFactsRequest,
+    responses((status = 200, description = "The facts whose subject is the entity.", body = ApiResponse<GraphFactsResponse>))
+)]
+pub async fn graph_facts_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    headers: HeaderMap,
+    Json(payload): Json<GraphFactsRequest>,
+) -> Result<Json<ApiResponse<GraphFactsResponse>>, AppError> {
+    let as_of = payload.as_of.unwrap_or_else(Utc::now);
+    info!(
+        "User '{}' queried the graph facts about '{}' as of {as_of}",
+        user.0.id, payload.entity
+    );
+    let visibility = caller_fact_visibility(&app_state, &user, &headers).await?;
+    let facts = {
+        let kg = app_state
+            .knowledge_graph
+            .read()
+            .map_err(|_| AppError::Internal(anyhow::anyhow!("Failed to acquire KG read lock")))?;
+        kg.facts_about_as_of(&payload.entity, as_of, &visibility)?
+    };
+    let debug_info = json!({ "facts": facts.len() });
+    let response = GraphFactsResponse {
+        entity: payload.entity,
+        as_of,
+        facts,
+    };
+    Ok(wrap_response(response, debug_params, Some(debug_info)))
+}
+
+/// Handler for answering a question from the knowledge graph.
+///
+/// The question is turned into a graph query (entity, predicate, point in time) by the
+/// `graph_query_generation` task. When the query matches facts the caller can see, the
+/// answer is written from them; otherwise the question is answered by the knowledge base RAG search, as
+/// `/search/knowledge` would.
+#[utoipa::path(
+    post,
+    path = "/search/graph",
+    tag = "search",
+    params(DebugParams, OrgHeader),
+    request_body = SearchRequest,
+    responses((status = 200, description = "The answer from the knowledge graph, or from the knowledge base when the graph holds no matching facts.", body = ApiResponse<PromptResponse>))
+)]
+pub async fn graph_prompt_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    headers: HeaderMap,
+    Json(payload): Json<SearchRequest>,
+) -> Result<Json<ApiResponse<PromptResponse>>, AppError> {
+    info!("Received graph question: '{}'", payload.query);
+    let task_config = app_state.tasks.get(GRAPH_QUERY_TASK).ok_or_else(|| {
+        AppError::Internal(anyhow::anyhow!(
+            "Task '{GRAPH_QUERY_TASK}' not found in config"
+        ))
+    })?;
+    let ai_provider = app_state
+        .ai_providers
+        .get(&task_config.provider)
+        .ok_or_else(|| {
+            AppError::Internal(anyhow::anyhow!(
+                "Provider '{}' not found",
+                task_config.provider
+            ))
+        })?;
+    let client = PromptClientBuilder::new()
+        .ai_provider(ai_provider.clone())
+        .storage_provider(Box::new(app_state.sqlite_provider.as_ref().clone()))
+        .build()?;
+
+    let visibility = caller_fact_visibility(&app_state, &user, &headers).await?;
+    let knowledge_graph = &app_state.knowledge_graph;
+    let prompt_result = client
+        .execute_graph_prompt(
+            &payload.query,
+            &task_config.system_prompt,
+            &task_config.user_prompt,
+            payload.instruction.as_deref(),
+            |query| match knowledge_graph.read() {
+                Ok(kg) => kg.execute_graph_query(query, &visibility),
+                Err(_) => {
+                    warn!("Failed to acquire KG read lock, answering without the graph.");
+                    Ok(Vec::new())
+                }
+            },
+        )
+        .await?;
+
+    let Some(prompt_result) = prompt_result else {
+        info!("The knowledge graph holds no matching facts, falling back to RAG.");
+        return knowledge_search_handler(
+            State(app_state),
+            user,
+            debug_params,
+            headers,
+            Json(payload),
+        )
+        .await;
+    };
+
+    let (answer, moderation) = moderate_answer(&app_state, prompt_result.text).await;
+    let debug_info = json!({
+        "source": "knowledge_graph",
+        "facts": prompt_result.database_result,
+        "trace": prompt_result.trace,
+        "moderation": moderation,
+    });
+    Ok(wrap_response(
+        PromptResponse {
+            text: serde_json::Value::String(answer),
+            experiment_run: None,
+            moderation: moderation.filter(ModerationReport::is_reported),
+        },
+        debug_params,
+        Some(debug_info),
+    ))
+}
+
+/// The graph facts the caller may see, from the documents visible to them in their
+/// organization, or their own database.
+async fn caller_fact_visibility(
+    app_state: &AppState,
+    user: &AuthenticatedUser,
+    headers: &HeaderMap,
+) -> Result<FactVisibility, AppError> {
+    let org_id = org_context(app_state, &user.0, headers).await?;
+    let db = app_state
+        .db_router
+        .for_user(&user.0.id, org_id.as_deref())
+        .await?;
+    Ok(fact_visibility(&db, Some(&user.0.id), org_id.as_deref()).await?)
+}
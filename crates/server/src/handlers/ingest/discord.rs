@@ -0,0 +1,76 @@
+use crate::auth::middleware::AuthenticatedUser;
+use crate::handlers::ingest::generic::run_ingest;
+use crate::handlers::{
+    wrap_response, ApiResponse, AppError, AppState, DebugParams, EmbedParams, OrgHeader,
+};
+use crate::ingestors::SOURCE_DISCORD;
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// The request body, forwarded as the plugin's source JSON. Unset options are left out
+/// so the plugin's defaults apply.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct IngestDiscordRequest {
+    pub guild_id: String,
+    pub channel_ids: Vec<String>,
+    /// The quiet period, in minutes, that ends a conversation window.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_minutes: Option<i64>,
+    /// The maximum number of messages in one conversation window.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_window_messages: Option<usize>,
+    /// The maximum number of recent messages read from each channel.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_messages: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IngestDiscordResponse {
+    pub message: String,
+    pub ingested_windows: usize,
+}
+
+/// Handler for ingesting Discord guild channels using the `anyrag-discord` plugin.
+#[utoipa::path(
+    post,
+    path = "/ingest/discord",
+    tag = "ingest",
+    params(DebugParams, EmbedParams, OrgHeader),
+    request_body = IngestDiscordRequest,
+    responses((status = 200, description = "The number of conversation windows stored.", body = ApiResponse<IngestDiscordResponse>))
+)]
+pub async fn ingest_discord_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Query(embed_params): Query<EmbedParams>,
+    headers: HeaderMap,
+    Json(payload): Json<IngestDiscordRequest>,
+) -> Result<Json<ApiResponse<IngestDiscordResponse>>, AppError> {
+    // 1. Ingest the source through the plugin registry.
+    let source = serde_json::to_value(&payload)?;
+    let (result, debug_info) = run_ingest(
+        &app_state,
+        &user.0,
+        &headers,
+        &embed_params,
+        SOURCE_DISCORD,
+        source,
+    )
+    .await?;
+
+    // 2. Construct the final HTTP response.
+    let response = IngestDiscordResponse {
+        message: format!(
+            "Successfully ingested {} conversation windows from Discord.",
+            result.documents_added
+        ),
+        ingested_windows: result.documents_added,
+    };
+    Ok(wrap_response(response, debug_params, Some(debug_info)))
+}
@@ -1,11 +1,12 @@
 use crate::auth::middleware::AuthenticatedUser;
+use crate::db_router::Tenant;
 use crate::handlers::ingest::firebase_types::{IngestFirebaseRequest, IngestFirebaseResponse};
 use crate::handlers::{
     graph_handlers, wrap_response, ApiResponse, AppError, AppState, DebugParams,
 };
 use anyhow::anyhow;
 use anyrag::ingest::knowledge::extract_and_store_metadata;
-use anyrag::ingest::Ingestor;
+use anyrag::ingest::{content_hash, find_duplicate_document, record_revision, Ingestor};
 use anyrag::providers::factory::create_dynamic_provider;
 use anyrag_firebase::{sanitize_table_name, FirebaseIngestor, FirebaseSource};
 use axum::{
@@ -31,6 +32,14 @@ impl From<&IngestFirebaseRequest> for FirebaseSource {
 }
 
 /// Handler for ingesting a Firestore collection into a local project database.
+#[utoipa::path(
+    post,
+    path = "/ingest/firebase",
+    tag = "ingest",
+    params(DebugParams),
+    request_body = IngestFirebaseRequest,
+    responses((status = 200, description = "The number of Firestore documents stored.", body = ApiResponse<IngestFirebaseResponse>))
+)]
 pub async fn ingest_firebase_handler(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
@@ -43,9 +52,10 @@ pub async fn ingest_firebase_handler(
         payload.project_id, payload.collection
     );
 
-    let db_path = format!("{}/{}.db", anyrag::constants::DB_DIR, payload.project_id);
-    let sqlite_provider = anyrag::providers::db::sqlite::SqliteProvider::new(&db_path).await?;
-    sqlite_provider.initialize_schema().await?;
+    let sqlite_provider = app_state
+        .db_router
+        .route(Tenant::Project(&payload.project_id))
+        .await?;
 
     let firebase_source = FirebaseSource::from(&payload);
     let source_str = serde_json::to_string(&firebase_source).map_err(|e| {
@@ -57,6 +67,7 @@ pub async fn ingest_firebase_handler(
     })?;
 
     let ingestor = FirebaseIngestor::new(&sqlite_provider);
+    let _write_permit = sqlite_provider.write_permit().await;
     let ingestion_result = ingestor
         .ingest(&source_str, owner_id.as_deref())
         .await
@@ -171,18 +182,30 @@ pub async fn ingest_firebase_handler(
         let source_url = format!("db://{}/{}/{}", payload.project_id, table_name, pk_val);
         let document_id = Uuid::new_v5(&Uuid::NAMESPACE_URL, source_url.as_bytes()).to_string();
 
+        let hash = content_hash(&document_content);
+        if find_duplicate_document(&conn, owner_id.as_deref(), &hash)
+            .await?
+            .is_some()
+        {
+            info!("Skipping duplicate content for {source_url}.");
+            continue;
+        }
+
+        record_revision(&conn, &source_url, &document_content).await?;
         conn.execute(
-            "INSERT INTO documents (id, owner_id, source_url, title, content)
-             VALUES (?, ?, ?, ?, ?)
+            "INSERT INTO documents (id, owner_id, source_url, title, content, content_hash)
+             VALUES (?, ?, ?, ?, ?, ?)
              ON CONFLICT(source_url) DO UPDATE SET
              title = excluded.title,
-             content = excluded.content",
+             content = excluded.content,
+             content_hash = excluded.content_hash",
             turso::params![
                 document_id.clone(),
                 owner_id.clone(),
                 source_url,
                 title,
-                document_content.clone()
+                document_content.clone(),
+                hash
             ],
         )
         .await?;
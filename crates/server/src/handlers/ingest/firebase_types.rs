@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct IngestFirebaseRequest {
     pub project_id: String,
     pub collection: String,
@@ -15,7 +16,7 @@ pub struct IngestFirebaseRequest {
     pub model: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct IngestFirebaseResponse {
     pub message: String,
     pub ingested_documents: usize,
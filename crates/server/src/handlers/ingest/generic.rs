@@ -0,0 +1,162 @@
+use crate::auth::{middleware::AuthenticatedUser, org::org_context};
+use crate::embedding::embed_ingested;
+use crate::graph_extraction::extract_document_facts;
+use crate::handlers::{
+    wrap_response, ApiResponse, AppError, AppState, DebugParams, EmbedParams, OrgHeader,
+};
+use crate::metrics::record_ingest;
+use crate::moderation::moderate_documents;
+use crate::redaction::redact_documents;
+use crate::summarization::summarize_sources;
+use anyrag::ingest::IngestionResult;
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    Json,
+};
+use core_access::{organizations::share_documents, usage::get_usage, User};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Instant;
+use tracing::info;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct IngestRequest {
+    /// The registered plugin to ingest with (e.g., `"rss"` or `"notion"`).
+    pub source_type: String,
+    /// The plugin's source description, as its `Ingestor` expects it.
+    pub source: Value,
+}
+
+/// Handler for ingesting from any registered plugin. The `source_type` selects the
+/// plugin from the `IngestorRegistry`, and `source` is passed to its `Ingestor`. With
+/// an `X-Org-Id` header, the ingested documents are shared with the organization.
+#[utoipa::path(
+    post,
+    path = "/ingest",
+    tag = "ingest",
+    params(DebugParams, EmbedParams, OrgHeader),
+    request_body = IngestRequest,
+    responses((status = 200, description = "The result reported by the plugin.", body = ApiResponse<IngestionResult>))
+)]
+pub async fn ingest_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Query(embed_params): Query<EmbedParams>,
+    headers: HeaderMap,
+    Json(payload): Json<IngestRequest>,
+) -> Result<Json<ApiResponse<IngestionResult>>, AppError> {
+    let (result, debug_info) = run_ingest(
+        &app_state,
+        &user.0,
+        &headers,
+        &embed_params,
+        &payload.source_type,
+        payload.source,
+    )
+    .await?;
+    Ok(wrap_response(result, debug_params, Some(debug_info)))
+}
+
+/// Ingests `source` with the plugin registered for `source_type`, and processes the
+/// new documents as every ingestion does: they are shared with the organization of
+/// the request, redacted, moderated, embedded, summarized, and their facts extracted.
+/// Every ingest endpoint runs through here, so that each run is routed and recorded
+/// in the metrics the same way. Returns the result reported by the plugin and the
+/// debug information of the run.
+pub(crate) async fn run_ingest(
+    app_state: &AppState,
+    user: &User,
+    headers: &HeaderMap,
+    embed_params: &EmbedParams,
+    source_type: &str,
+    source: Value,
+) -> Result<(IngestionResult, Value), AppError> {
+    let org_id = org_context(app_state, user, headers).await?;
+    let db = app_state
+        .db_router
+        .for_user(&user.id, org_id.as_deref())
+        .await?;
+    let owner_id = Some(user.id.as_str());
+    info!("User '{:?}' initiating '{}' ingest.", owner_id, source_type);
+
+    // 1. Resolve the plugin of the source type.
+    let Some(plugin) = app_state.ingestors.get(source_type) else {
+        return Err(AppError::UnknownSourceType(format!(
+            "Unknown source type '{}'. Available source types: {}.",
+            source_type,
+            app_state.ingestors.source_types().join(", ")
+        )));
+    };
+
+    // 2. Complete the source with the server's settings and build the ingestor.
+    let remaining_documents = remaining_document_quota(app_state, user).await?;
+    let source = plugin
+        .prepare_source(app_state, source, remaining_documents)
+        .map_err(AppError::Internal)?;
+    let ingestor = plugin
+        .build(app_state, &db.db, &source)
+        .map_err(AppError::Internal)?;
+
+    // 3. Call the generic ingest method from the trait, recording the run's metrics.
+    // Its writes take the database's write permit only while they run, not while the
+    // ingestor fetches content or calls the LLM.
+    let started = Instant::now();
+    let result = db
+        .write_lock()
+        .scope(ingestor.ingest(&source.to_string(), owner_id))
+        .await;
+    record_ingest(
+        source_type,
+        started,
+        result.as_ref().map(|result| result.documents_added),
+    );
+    let result = result.map_err(AppError::Ingest)?;
+
+    // 4. Share the new documents with the organization of the request.
+    if let Some(org_id) = &org_id {
+        share_documents(&db.db, org_id, &result.document_ids).await?;
+    }
+    let documents_redacted = redact_documents(app_state, &db, &result.document_ids).await;
+    let documents_moderated = moderate_documents(app_state, &db, &result.document_ids).await;
+    let documents_embedded =
+        embed_ingested(app_state, &db, &result.document_ids, embed_params).await;
+    let sources_summarized = summarize_sources(app_state, &db, &result.document_ids).await;
+    let facts_extracted = extract_document_facts(app_state, &db, &result.document_ids).await;
+
+    let debug_info = json!({
+        "source_type": source_type,
+        "source": result.source,
+        "owner_id": owner_id,
+        "org_id": org_id,
+        "document_ids": result.document_ids,
+        "documents_redacted": documents_redacted,
+        "documents_moderated": documents_moderated,
+        "documents_embedded": documents_embedded,
+        "sources_summarized": sources_summarized,
+        "facts_extracted": facts_extracted,
+    });
+    Ok((result, debug_info))
+}
+
+/// The number of documents the user may still ingest this month, when the server
+/// limits it with a quota.
+async fn remaining_document_quota(
+    app_state: &AppState,
+    user: &User,
+) -> Result<Option<u64>, AppError> {
+    let Some(quota) = app_state
+        .config
+        .quotas
+        .as_ref()
+        .and_then(|quotas| quotas.documents_per_month)
+    else {
+        return Ok(None);
+    };
+    let usage = get_usage(&app_state.sqlite_provider.db, &user.id)
+        .await
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to load usage: {e}")))?;
+    Ok(Some(quota.saturating_sub(usage.documents_ingested)))
+}
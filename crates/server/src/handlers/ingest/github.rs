@@ -1,67 +1,127 @@
 use super::github_types::*;
 use crate::auth::middleware::AuthenticatedUser;
-use crate::handlers::{wrap_response, ApiResponse, AppError, AppState, DebugParams};
-use anyrag::ingest::Ingestor;
+use crate::handlers::ingest::generic::run_ingest;
+use crate::handlers::{
+    wrap_response, ApiResponse, AppError, AppState, DebugParams, EmbedParams, OrgHeader,
+};
+use crate::ingestors::{SOURCE_GITHUB, SOURCE_GITHUB_ISSUES};
 use anyrag_github::ingest::search_examples;
-use anyrag_github::GithubIngestor;
 use axum::{
     extract::{Path, Query, State},
+    http::HeaderMap,
     Json,
 };
 use serde_json::json;
 use tracing::info;
 
-/// Handler for ingesting code examples from a public GitHub repository.
-/// This handler acts as a thin web layer, orchestrating the call to the
-/// `anyrag-github` crate through the generic `Ingestor` trait.
+/// Handler for ingesting code examples from a GitHub repository, which may be private
+/// when the request or the server configuration provides an access token.
+/// This handler acts as a thin web layer, ingesting the repository through the
+/// `anyrag-github` plugin of the registry.
+#[utoipa::path(
+    post,
+    path = "/ingest/github",
+    tag = "examples",
+    params(DebugParams, EmbedParams, OrgHeader),
+    request_body = IngestGitHubRequest,
+    responses((status = 200, description = "The number of examples stored.", body = ApiResponse<IngestGitHubResponse>))
+)]
 pub async fn ingest_github_handler(
     State(app_state): State<AppState>,
-    _user: AuthenticatedUser,
+    user: AuthenticatedUser,
     debug_params: Query<DebugParams>,
+    Query(embed_params): Query<EmbedParams>,
+    headers: HeaderMap,
     Json(payload): Json<IngestGitHubRequest>,
 ) -> Result<Json<ApiResponse<IngestGitHubResponse>>, AppError> {
-    info!("Received GitHub ingest request for URL: {}", payload.url);
-
-    // 1. Instantiate the ingestor with configuration from the app state.
-    // This decouples the server from the implementation details of the plugin.
-    let ingestor = GithubIngestor::new(
-        app_state.storage_manager.clone(),
-        Some(app_state.config.embedding.api_url.clone()),
-        Some(app_state.config.embedding.model_name.clone()),
-        app_state.config.embedding.api_key.clone(),
-    );
-
-    // 2. Serialize the source information into a JSON string for the generic ingest method.
-    let source_json = json!({
-        "url": payload.url.clone(),
-        "version": payload.version.clone()
-    })
-    .to_string();
-
-    // 3. Call the generic ingest method from the trait.
-    let ingest_result = ingestor
-        .ingest(&source_json, None) // owner_id is not used for github ingestion
-        .await
-        .map_err(|e| AppError::Internal(anyhow::anyhow!("GitHub ingestion failed: {e}")))?;
+    // 1. Ingest the repository through the plugin registry.
+    let source = json!({
+        "url": payload.url,
+        "version": payload.version,
+        "auth_token": payload.auth_token
+    });
+    let (ingest_result, debug_info) = run_ingest(
+        &app_state,
+        &user.0,
+        &headers,
+        &embed_params,
+        SOURCE_GITHUB,
+        source,
+    )
+    .await?;
 
-    // 4. Parse the version from the result source for the response.
+    // 2. Parse the version from the result source for the response.
     let ingested_version = ingest_result
         .source
         .rsplit_once('#')
         .map(|(_, v)| v.to_string())
         .unwrap_or_else(|| "unknown".to_string());
 
-    // 5. Construct the final HTTP response.
+    // 3. Construct the final HTTP response.
     let response = IngestGitHubResponse {
         message: "GitHub ingestion pipeline completed successfully.".to_string(),
         ingested_examples: ingest_result.documents_added,
         version: ingested_version,
     };
-    let debug_info = json!({ "url": payload.url, "version": payload.version });
+    Ok(wrap_response(response, debug_params, Some(debug_info)))
+}
+
+/// Handler for ingesting the issues, pull requests, and discussions of a GitHub
+/// repository as documents. The GraphQL API always requires an access token, taken from
+/// the request or the server's `GITHUB_TOKEN`.
+#[utoipa::path(
+    post,
+    path = "/ingest/github/issues",
+    tag = "ingest",
+    params(DebugParams, EmbedParams, OrgHeader),
+    request_body = IngestGitHubIssuesRequest,
+    responses((status = 200, description = "The number of threads stored.", body = ApiResponse<IngestGitHubIssuesResponse>))
+)]
+pub async fn ingest_github_issues_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Query(embed_params): Query<EmbedParams>,
+    headers: HeaderMap,
+    Json(payload): Json<IngestGitHubIssuesRequest>,
+) -> Result<Json<ApiResponse<IngestGitHubIssuesResponse>>, AppError> {
+    // 1. Ingest the threads through the plugin registry. The plugin reads them with the
+    // token of the request, or the configured one.
+    let source = json!({
+        "url": payload.url,
+        "kinds": payload.kinds,
+        "max_items": payload.max_items,
+        "auth_token": payload.auth_token,
+    });
+    let (result, debug_info) = run_ingest(
+        &app_state,
+        &user.0,
+        &headers,
+        &embed_params,
+        SOURCE_GITHUB_ISSUES,
+        source,
+    )
+    .await?;
+
+    // 2. Construct the final HTTP response.
+    let response = IngestGitHubIssuesResponse {
+        message: format!(
+            "Successfully ingested {} new or updated GitHub threads.",
+            result.documents_added
+        ),
+        ingested_threads: result.documents_added,
+    };
     Ok(wrap_response(response, debug_params, Some(debug_info)))
 }
 
 /// Handler for retrieving a consolidated Markdown file of examples for a specific repository version.
+#[utoipa::path(
+    get,
+    path = "/examples/{repo_name}/{version}",
+    tag = "examples",
+    params(GetVersionedExamplesPath, DebugParams),
+    responses((status = 200, description = "The examples of the version as Markdown.", body = ApiResponse<GetExamplesResponse>))
+)]
 pub async fn get_versioned_examples_handler(
     State(app_state): State<AppState>,
     Path(path): Path<GetVersionedExamplesPath>,
@@ -93,8 +153,8 @@ pub async fn get_versioned_examples_handler(
         .iter()
         .map(|ex| {
             format!(
-                "## `{}`\n**Source:** `{}` (`{}`)\n\n```rust\n{}\n```\n",
-                ex.example_handle, ex.source_file, ex.source_type, ex.content
+                "## `{}`\n**Source:** `{}` (`{}`)\n\n```{}\n{}\n```\n",
+                ex.example_handle, ex.source_file, ex.source_type, ex.language, ex.content
             )
         })
         .collect::<Vec<String>>()
@@ -109,6 +169,13 @@ pub async fn get_versioned_examples_handler(
 }
 
 /// Handler for retrieving examples for the latest version of a repository.
+#[utoipa::path(
+    get,
+    path = "/examples/{repo_name}",
+    tag = "examples",
+    params(GetLatestExamplesPath, DebugParams),
+    responses((status = 200, description = "The examples of the latest version as Markdown.", body = ApiResponse<GetExamplesResponse>))
+)]
 pub async fn get_latest_examples_handler(
     State(app_state): State<AppState>,
     Path(path): Path<GetLatestExamplesPath>,
@@ -155,8 +222,8 @@ pub async fn get_latest_examples_handler(
         .iter()
         .map(|ex| {
             format!(
-                "## `{}`\n**Source:** `{}` (`{}`)\n\n```rust\n{}\n```\n",
-                ex.example_handle, ex.source_file, ex.source_type, ex.content
+                "## `{}`\n**Source:** `{}` (`{}`)\n\n```{}\n{}\n```\n",
+                ex.example_handle, ex.source_file, ex.source_type, ex.language, ex.content
             )
         })
         .collect::<Vec<String>>()
@@ -171,6 +238,14 @@ pub async fn get_latest_examples_handler(
 }
 
 /// Handler for the RAG search endpoint for code examples.
+#[utoipa::path(
+    post,
+    path = "/search/examples",
+    tag = "examples",
+    params(DebugParams),
+    request_body = SearchExamplesRequest,
+    responses((status = 200, description = "The matching examples with their repository and version.", body = ApiResponse<SearchExamplesResponse>))
+)]
 pub async fn search_examples_handler(
     State(app_state): State<AppState>,
     _user: AuthenticatedUser,
@@ -208,6 +283,7 @@ pub async fn search_examples_handler(
         &storage_manager,
         &payload.query,
         &payload.repos,
+        &payload.languages,
         std::sync::Arc::from(ai_provider),
         embedding_api_url,
         embedding_model,
@@ -219,6 +295,7 @@ pub async fn search_examples_handler(
     let response = SearchExamplesResponse {
         results: search_results,
     };
-    let debug_info = json!({ "query": payload.query, "repos": payload.repos });
+    let debug_info =
+        json!({ "query": payload.query, "repos": payload.repos, "languages": payload.languages });
     Ok(wrap_response(response, debug_params, Some(debug_info)))
 }
@@ -1,42 +1,72 @@
-use anyrag::SearchResult;
+use anyrag_github::{ingest::types::ExampleSearchResult, issues::types::ThreadKind};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct IngestGitHubRequest {
     pub url: String,
     pub version: Option<String>,
+    /// An access token for a private repository, overriding the server's `GITHUB_TOKEN`.
+    #[serde(default)]
+    pub auth_token: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct IngestGitHubResponse {
     pub message: String,
     pub ingested_examples: usize,
     pub version: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
+pub struct IngestGitHubIssuesRequest {
+    pub url: String,
+    /// The kinds of threads to ingest. All kinds when omitted.
+    #[serde(default)]
+    pub kinds: Vec<ThreadKind>,
+    /// The number of threads of each kind to fetch, most recently updated first.
+    #[serde(default)]
+    pub max_items: Option<usize>,
+    /// An access token for the GraphQL API, overriding the server's `GITHUB_TOKEN`.
+    #[serde(default)]
+    pub auth_token: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IngestGitHubIssuesResponse {
+    pub message: String,
+    pub ingested_threads: usize,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
 pub struct GetVersionedExamplesPath {
     pub repo_name: String,
     pub version: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
 pub struct GetLatestExamplesPath {
     pub repo_name: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct GetExamplesResponse {
     pub content: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct SearchExamplesRequest {
     pub query: String,
     pub repos: Vec<String>,
+    /// Limits the search to examples in these languages (e.g. `["python", "go"]`).
+    #[serde(default)]
+    pub languages: Vec<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct SearchExamplesResponse {
-    pub results: Vec<SearchResult>,
+    /// The matching examples, each with the repository and version it belongs to.
+    pub results: Vec<ExampleSearchResult>,
 }
@@ -0,0 +1,71 @@
+use crate::auth::middleware::AuthenticatedUser;
+use crate::handlers::ingest::generic::run_ingest;
+use crate::handlers::{
+    wrap_response, ApiResponse, AppError, AppState, DebugParams, EmbedParams, OrgHeader,
+};
+use crate::ingestors::SOURCE_JIRA;
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct IngestJiraRequest {
+    pub jql: String,
+    /// Fetches only the issues updated since the last incremental sync of this query.
+    #[serde(default)]
+    pub incremental: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IngestJiraResponse {
+    pub message: String,
+    pub ingested_issues: usize,
+}
+
+/// Handler for ingesting Jira issues matching a JQL query using the `anyrag-jira` plugin.
+#[utoipa::path(
+    post,
+    path = "/ingest/jira",
+    tag = "ingest",
+    params(DebugParams, EmbedParams, OrgHeader),
+    request_body = IngestJiraRequest,
+    responses((status = 200, description = "The number of issues stored.", body = ApiResponse<IngestJiraResponse>))
+)]
+pub async fn ingest_jira_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Query(embed_params): Query<EmbedParams>,
+    headers: HeaderMap,
+    Json(payload): Json<IngestJiraRequest>,
+) -> Result<Json<ApiResponse<IngestJiraResponse>>, AppError> {
+    // 1. Ingest the source through the plugin registry.
+    let source = json!({
+        "jql": payload.jql,
+        "incremental": payload.incremental,
+    });
+    let (result, debug_info) = run_ingest(
+        &app_state,
+        &user.0,
+        &headers,
+        &embed_params,
+        SOURCE_JIRA,
+        source,
+    )
+    .await?;
+
+    // 2. Construct the final HTTP response.
+    let response = IngestJiraResponse {
+        message: format!(
+            "Successfully ingested {} new or updated Jira issues.",
+            result.documents_added
+        ),
+        ingested_issues: result.documents_added,
+    };
+    Ok(wrap_response(response, debug_params, Some(debug_info)))
+}
@@ -1,13 +1,24 @@
+#[cfg(feature = "discord")]
+pub mod discord;
+
 #[cfg(feature = "firebase")]
 pub mod firebase;
 #[cfg(feature = "firebase")]
 pub mod firebase_types;
 
+pub mod generic;
+
 #[cfg(feature = "github")]
 pub mod github;
 #[cfg(feature = "github")]
 pub mod github_types;
 
+#[cfg(feature = "jira")]
+pub mod jira;
+
+#[cfg(feature = "objectstore")]
+pub mod objectstore;
+
 #[cfg(feature = "pdf")]
 pub mod pdf;
 
@@ -17,6 +28,9 @@ pub mod rss;
 #[cfg(feature = "sheets")]
 pub mod sheet;
 
+#[cfg(feature = "slack")]
+pub mod slack;
+
 #[cfg(feature = "text")]
 pub mod text;
 
@@ -0,0 +1,108 @@
+use crate::auth::middleware::AuthenticatedUser;
+use crate::handlers::ingest::generic::run_ingest;
+use crate::handlers::{
+    wrap_response, ApiResponse, AppError, AppState, DebugParams, EmbedParams, OrgHeader,
+};
+use crate::ingestors::SOURCE_OBJECTSTORE;
+use anyrag::types::AppConfig;
+use anyrag_objectstore::client::{s3_endpoint, ObjectStoreClient, DEFAULT_REGION};
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct IngestObjectStoreRequest {
+    pub bucket: String,
+    #[serde(default)]
+    pub prefix: String,
+    /// Re-ingests every object, even those whose ETag is unchanged.
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IngestObjectStoreResponse {
+    pub message: String,
+    pub ingested_documents: usize,
+    /// The `ingested`, `unchanged`, `unsupported`, and `failed` objects of the run.
+    pub objects: Value,
+}
+
+/// Creates the client of the configured S3-compatible store.
+pub(crate) fn object_store_client(config: &AppConfig) -> ObjectStoreClient {
+    let region = config
+        .object_store_region
+        .as_deref()
+        .unwrap_or(DEFAULT_REGION);
+    let endpoint = config
+        .object_store_endpoint
+        .clone()
+        .unwrap_or_else(|| s3_endpoint(region));
+    let client = ObjectStoreClient::new(&endpoint, region);
+    match (
+        config.object_store_access_key_id.as_deref(),
+        config.object_store_secret_access_key.as_deref(),
+    ) {
+        (Some(access_key_id), Some(secret_access_key)) => {
+            client.with_credentials(access_key_id, secret_access_key)
+        }
+        _ => client,
+    }
+}
+
+/// Handler for ingesting the objects of an S3 or GCS bucket using the `anyrag-objectstore` plugin.
+#[utoipa::path(
+    post,
+    path = "/ingest/objectstore",
+    tag = "ingest",
+    params(DebugParams, EmbedParams, OrgHeader),
+    request_body = IngestObjectStoreRequest,
+    responses((status = 200, description = "The number of documents stored.", body = ApiResponse<IngestObjectStoreResponse>))
+)]
+pub async fn ingest_objectstore_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Query(embed_params): Query<EmbedParams>,
+    headers: HeaderMap,
+    Json(payload): Json<IngestObjectStoreRequest>,
+) -> Result<Json<ApiResponse<IngestObjectStoreResponse>>, AppError> {
+    // 1. Ingest the source through the plugin registry.
+    let source = json!({
+        "bucket": payload.bucket,
+        "prefix": payload.prefix,
+        "force": payload.force,
+    });
+    let (result, debug_info) = run_ingest(
+        &app_state,
+        &user.0,
+        &headers,
+        &embed_params,
+        SOURCE_OBJECTSTORE,
+        source,
+    )
+    .await?;
+
+    // 2. Construct the final HTTP response.
+    let objects: Value = result
+        .metadata
+        .as_deref()
+        .map(serde_json::from_str)
+        .transpose()?
+        .unwrap_or_default();
+    let failed = objects["failed"].as_array().map_or(0, Vec::len);
+    let response = IngestObjectStoreResponse {
+        message: format!(
+            "Ingested {} documents from bucket '{}' ({failed} objects failed).",
+            result.documents_added, payload.bucket
+        ),
+        ingested_documents: result.documents_added,
+        objects,
+    };
+    Ok(wrap_response(response, debug_params, Some(debug_info)))
+}
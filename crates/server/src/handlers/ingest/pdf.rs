@@ -1,10 +1,14 @@
 use crate::auth::middleware::AuthenticatedUser;
-use crate::handlers::{wrap_response, ApiResponse, AppError, AppState, DebugParams};
-use anyrag::ingest::IngestionPrompts;
-use anyrag::ingest::Ingestor;
-use anyrag_pdf::{PdfExtractor, PdfIngestor};
+use crate::handlers::ingest::generic::run_ingest;
+use crate::handlers::{
+    wrap_response, ApiResponse, AppError, AppState, DebugParams, EmbedParams, OrgHeader,
+};
+use crate::ingestors::SOURCE_PDF;
+use anyrag::ingest::ChunkingStrategy;
+use anyrag_pdf::PdfExtractor;
 use axum::{
     extract::{Query, State},
+    http::HeaderMap,
     Json,
 };
 use axum_extra::extract::Multipart;
@@ -13,20 +17,51 @@ use tracing::{info, warn};
 
 use base64::{engine::general_purpose, Engine as _};
 use serde_json::Value;
+use utoipa::ToSchema;
 
 // The ExtractorChoice is now defined in the `anyrag-pdf` crate as `PdfExtractor`.
 
+/// The multipart form of `POST /ingest/pdf`. The handler reads the parts as they
+/// arrive; this struct only documents them.
+#[derive(ToSchema)]
+pub struct IngestPdfForm {
+    /// The PDF to ingest. Either `file` or `url` is required.
+    #[schema(value_type = Option<String>, format = Binary)]
+    pub file: Option<Vec<u8>>,
+    /// The URL to download the PDF from.
+    pub url: Option<String>,
+    /// The text extractor: `local` (default) or `gemini`.
+    pub extractor: Option<String>,
+    /// A chunking strategy as JSON, e.g. `{"strategy": "sentence"}`.
+    pub chunking: Option<String>,
+    /// `false` stores the text of each page as it is instead of restructuring it with
+    /// the LLM.
+    pub restructure: Option<bool>,
+}
+
 /// Consolidated handler for ingesting a PDF from an upload or a URL.
+#[utoipa::path(
+    post,
+    path = "/ingest/pdf",
+    tag = "ingest",
+    params(DebugParams, EmbedParams, OrgHeader),
+    request_body(content = IngestPdfForm, content_type = "multipart/form-data"),
+    responses((status = 200, description = "The ingestion summary.", body = ApiResponse<Value>))
+)]
 pub async fn ingest_pdf_handler(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
     debug_params: Query<DebugParams>,
+    Query(embed_params): Query<EmbedParams>,
+    headers: HeaderMap,
     mut multipart: Multipart,
 ) -> Result<Json<ApiResponse<Value>>, AppError> {
-    let owner_id = Some(user.0.id);
+    let owner_id = Some(user.0.id.as_str());
     let mut pdf_data: Option<Vec<u8>> = None;
     let mut source_identifier: Option<String> = None;
     let mut extractor_choice = PdfExtractor::default();
+    let mut chunking: Option<ChunkingStrategy> = None;
+    let mut restructure = true;
 
     info!("PDF ingest request received.");
 
@@ -80,6 +115,19 @@ pub async fn ingest_pdf_handler(
                     })?;
                 info!("Extractor choice set to: {:?}", extractor_choice);
             }
+            "chunking" => {
+                let chunking_str = field.text().await.map_err(anyhow::Error::from)?;
+                chunking = Some(serde_json::from_str(&chunking_str).map_err(|e| {
+                    AppError::BadRequest(format!("Invalid chunking strategy: {e}"))
+                })?);
+                info!("Chunking strategy set to: {:?}", chunking);
+            }
+            "restructure" => {
+                let restructure_str = field.text().await.map_err(anyhow::Error::from)?;
+                restructure = restructure_str.trim().parse().map_err(|e| {
+                    AppError::Internal(anyhow::anyhow!("Invalid restructure flag: {e}"))
+                })?;
+            }
             _ => warn!("Ignoring unknown multipart field: {}", name),
         }
     }
@@ -95,62 +143,32 @@ pub async fn ingest_pdf_handler(
         ))
     })?;
 
-    // --- 2. Get dependencies from app state ---
-    let task_name = "knowledge_distillation";
-    let task_config = app_state.tasks.get(task_name).ok_or_else(|| {
-        AppError::Internal(anyhow::anyhow!(
-            "Configuration for task '{task_name}' not found."
-        ))
-    })?;
-    let provider_name = &task_config.provider;
-    let ai_provider = app_state.ai_providers.get(provider_name).ok_or_else(|| {
-        AppError::Internal(anyhow::anyhow!(
-            "Provider '{provider_name}' not found in providers map."
-        ))
-    })?;
-
-    let metadata_task_config = app_state
-        .tasks
-        .get("knowledge_metadata_extraction")
-        .ok_or_else(|| {
-            AppError::Internal(anyhow::anyhow!(
-                "Task 'knowledge_metadata_extraction' not found in config"
-            ))
-        })?;
-
-    let prompts = IngestionPrompts {
-        restructuring_system_prompt: &task_config.system_prompt,
-        metadata_extraction_system_prompt: &metadata_task_config.system_prompt,
-    };
-
-    // --- 3. Instantiate and call the ingestor plugin ---
-    let ingestor = PdfIngestor::new(&app_state.sqlite_provider.db, ai_provider.as_ref(), prompts);
+    // --- 2. Ingest the PDF through the plugin registry ---
     let pdf_data_base64 = general_purpose::STANDARD.encode(&pdf_data);
-
-    let source_json = json!({
+    let source = json!({
         "source_identifier": source_identifier,
         "pdf_data_base64": pdf_data_base64,
         "extractor": extractor_choice,
-    })
-    .to_string();
-
-    let ingest_result = ingestor
-        .ingest(&source_json, owner_id.as_deref())
-        .await
-        .map_err(|e| AppError::Internal(anyhow::anyhow!("PDF ingestion failed: {e}")))?;
-
-    // --- 4. Construct the response ---
+        "chunking": chunking,
+        "restructure": restructure,
+    });
+    let (ingest_result, mut debug_info) = run_ingest(
+        &app_state,
+        &user.0,
+        &headers,
+        &embed_params,
+        SOURCE_PDF,
+        source,
+    )
+    .await?;
+
+    // --- 3. Construct the response ---
     let response = json!({
         "message": "PDF ingestion pipeline completed successfully.".to_string(),
         "ingested_documents": ingest_result.documents_added,
     });
-
-    let debug_info = json!({
-        "source": source_identifier,
-        "size": pdf_data.len(),
-        "extractor": extractor_choice,
-        "owner_id": owner_id,
-    });
+    debug_info["size"] = json!(pdf_data.len());
+    debug_info["extractor"] = json!(extractor_choice);
 
     Ok(wrap_response(response, debug_params, Some(debug_info)))
 }
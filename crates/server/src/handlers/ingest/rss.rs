@@ -1,52 +1,83 @@
 use crate::auth::middleware::AuthenticatedUser;
-use crate::handlers::{wrap_response, ApiResponse, AppError, AppState, DebugParams};
-use anyrag::ingest::Ingestor;
-use anyrag_rss::RssIngestor;
+use crate::handlers::ingest::generic::run_ingest;
+use crate::handlers::{
+    wrap_response, ApiResponse, AppError, AppState, DebugParams, EmbedParams, OrgHeader,
+};
+use crate::ingestors::SOURCE_RSS;
+use anyrag_rss::{transcription::TranscriptionConfig, RssIngestor};
 use axum::{
     extract::{Query, State},
+    http::HeaderMap,
     Json,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tracing::info;
+use turso::Database;
+use utoipa::ToSchema;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct IngestRssRequest {
     pub url: String,
+    /// Stores each item's full article instead of its description.
+    #[serde(default)]
+    pub fetch_full_content: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct IngestRssResponse {
     pub message: String,
     pub ingested_articles: usize,
 }
 
+/// Creates the RSS ingestor, transcribing podcast episodes when a transcription
+/// endpoint is configured.
+pub(crate) fn rss_ingestor(app_state: &AppState, db: &Database) -> RssIngestor {
+    let ingestor = RssIngestor::new(db);
+    let Some(endpoint) = app_state.config.transcription_api_url.as_deref() else {
+        return ingestor;
+    };
+    let mut transcription = TranscriptionConfig::new(endpoint);
+    transcription.api_key = app_state.config.transcription_api_key.clone();
+    if let Some(model) = app_state.config.transcription_model.clone() {
+        transcription.model = model;
+    }
+    ingestor.with_transcription(transcription)
+}
+
 /// Handler for ingesting content from an RSS feed URL using the `anyrag-rss` plugin.
+#[utoipa::path(
+    post,
+    path = "/ingest/rss",
+    tag = "ingest",
+    params(DebugParams, EmbedParams, OrgHeader),
+    request_body = IngestRssRequest,
+    responses((status = 200, description = "The number of articles stored.", body = ApiResponse<IngestRssResponse>))
+)]
 pub async fn ingest_rss_handler(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
     debug_params: Query<DebugParams>,
+    Query(embed_params): Query<EmbedParams>,
+    headers: HeaderMap,
     Json(payload): Json<IngestRssRequest>,
 ) -> Result<Json<ApiResponse<IngestRssResponse>>, AppError> {
-    let owner_id = Some(user.0.id);
-    info!(
-        "User '{:?}' initiating RSS ingest for URL: {}",
-        owner_id, payload.url
-    );
+    // 1. Ingest the feed through the plugin registry, which transcribes podcasts if an
+    // endpoint is set.
+    let source = json!({
+        "url": payload.url,
+        "fetch_full_content": payload.fetch_full_content,
+    });
+    let (result, debug_info) = run_ingest(
+        &app_state,
+        &user.0,
+        &headers,
+        &embed_params,
+        SOURCE_RSS,
+        source,
+    )
+    .await?;
 
-    // 1. Instantiate the ingestor plugin.
-    let ingestor = RssIngestor::new(&app_state.sqlite_provider.db);
-
-    // 2. Serialize the source information into a JSON string for the generic ingest method.
-    let source_json = json!({ "url": payload.url }).to_string();
-
-    // 3. Call the generic ingest method from the trait.
-    let result = ingestor
-        .ingest(&source_json, owner_id.as_deref())
-        .await
-        .map_err(|e| AppError::Internal(anyhow::anyhow!("RSS ingestion failed: {e}")))?;
-
-    // 4. Construct the final HTTP response.
+    // 2. Construct the final HTTP response.
     let response = IngestRssResponse {
         message: format!(
             "Successfully ingested {} new articles from the RSS feed.",
@@ -54,8 +85,5 @@ pub async fn ingest_rss_handler(
         ),
         ingested_articles: result.documents_added,
     };
-
-    let debug_info =
-        json!({ "url": payload.url, "owner_id": owner_id, "ingested_ids": result.document_ids });
     Ok(wrap_response(response, debug_params, Some(debug_info)))
 }
@@ -1,101 +1,95 @@
 //! # Google Sheets Ingestion Handler
 //!
 //! This module provides the HTTP handler for ingesting data from a Google Sheet.
-//! It acts as a thin web layer, orchestrating the call to the `anyrag-sheets`
-//! crate through the generic `Ingestor` trait.
+//! It acts as a thin web layer, ingesting the sheet through the `anyrag-sheets`
+//! plugin of the registry.
 
 use crate::{
     auth::middleware::AuthenticatedUser,
-    handlers::{wrap_response, ApiResponse, AppError, AppState, DebugParams},
+    handlers::{
+        ingest::generic::run_ingest, wrap_response, ApiResponse, AppError, AppState, DebugParams,
+        EmbedParams, OrgHeader,
+    },
+    ingestors::SOURCE_SHEETS,
 };
-use anyrag::ingest::{IngestionPrompts, Ingestor};
-use anyrag_sheets::SheetsIngestor;
 use axum::{
     extract::{Query, State},
+    http::HeaderMap,
     Json,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tracing::info;
+use utoipa::ToSchema;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct IngestSheetRequest {
     pub url: String,
     #[serde(default)]
     pub gid: Option<String>,
+    /// `knowledge` (the default) restructures the sheet into documents; `table` copies
+    /// it into a typed SQL table for text-to-SQL queries.
+    #[serde(default)]
+    pub mode: Option<String>,
+    /// `false` stores the rows as CSV instead of restructuring them with the LLM.
+    #[serde(default)]
+    pub restructure: Option<bool>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct IngestSheetResponse {
     pub message: String,
     pub ingested_chunks: usize,
     pub document_ids: Vec<String>,
+    /// The table the sheet was copied into, in `table` mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table_name: Option<String>,
 }
 
 /// Handler for ingesting a Google Sheet using the `anyrag-sheets` plugin.
+#[utoipa::path(
+    post,
+    path = "/ingest/sheet",
+    tag = "ingest",
+    params(DebugParams, EmbedParams, OrgHeader),
+    request_body = IngestSheetRequest,
+    responses((status = 200, description = "The stored chunks.", body = ApiResponse<IngestSheetResponse>))
+)]
 pub async fn ingest_sheet_handler(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
     debug_params: Query<DebugParams>,
+    Query(embed_params): Query<EmbedParams>,
+    headers: HeaderMap,
     Json(payload): Json<IngestSheetRequest>,
 ) -> Result<Json<ApiResponse<IngestSheetResponse>>, AppError> {
-    let owner_id = Some(user.0.id);
-    info!(
-        "User '{:?}' initiating Sheet ingest for URL: {}",
-        owner_id, payload.url
-    );
-
-    // --- 1. Get dependencies from app state ---
-    let task_name = "knowledge_distillation";
-    let task_config = app_state.tasks.get(task_name).ok_or_else(|| {
-        AppError::Internal(anyhow::anyhow!("Task '{task_name}' not found in config"))
-    })?;
-    let provider_name = &task_config.provider;
-    let ai_provider = app_state.ai_providers.get(provider_name).ok_or_else(|| {
-        AppError::Internal(anyhow::anyhow!("Provider '{provider_name}' not found"))
-    })?;
-
-    let meta_task_config = app_state
-        .tasks
-        .get("knowledge_metadata_extraction")
-        .ok_or_else(|| {
-            AppError::Internal(anyhow::anyhow!(
-                "Task 'knowledge_metadata_extraction' not found in config"
-            ))
-        })?;
-
-    let prompts = IngestionPrompts {
-        restructuring_system_prompt: &task_config.system_prompt,
-        metadata_extraction_system_prompt: &meta_task_config.system_prompt,
-    };
-
-    // --- 2. Instantiate and call the ingestor plugin ---
-    let ingestor =
-        SheetsIngestor::new(&app_state.sqlite_provider.db, ai_provider.as_ref(), prompts);
-
-    let source_json = json!({
-        "url": payload.url,
-        "gid": payload.gid,
-    })
-    .to_string();
-
-    let ingest_result = ingestor
-        .ingest(&source_json, owner_id.as_deref())
-        .await
-        .map_err(|e| AppError::Internal(anyhow::anyhow!("Sheet ingestion failed: {e}")))?;
-
-    // --- 3. Construct the response ---
-    let debug_info = json!({
+    // --- 1. Ingest the sheet through the plugin registry ---
+    let source = json!({
         "url": payload.url,
         "gid": payload.gid,
-        "owner_id": owner_id,
-        "document_id": ingest_result.document_ids.first(),
+        "mode": payload.mode,
+        "restructure": payload.restructure.unwrap_or(true),
     });
+    let (ingest_result, debug_info) = run_ingest(
+        &app_state,
+        &user.0,
+        &headers,
+        &embed_params,
+        SOURCE_SHEETS,
+        source,
+    )
+    .await?;
 
+    // --- 2. Construct the response ---
+    let table_name = ingest_result
+        .metadata
+        .as_deref()
+        .and_then(|metadata| serde_json::from_str::<serde_json::Value>(metadata).ok())
+        .and_then(|metadata| metadata["table_name"].as_str().map(str::to_string));
     let response = IngestSheetResponse {
         message: "Sheet ingestion pipeline completed successfully.".to_string(),
         ingested_chunks: ingest_result.documents_added,
         document_ids: ingest_result.document_ids,
+        table_name,
     };
 
     Ok(wrap_response(response, debug_params, Some(debug_info)))
@@ -0,0 +1,71 @@
+use crate::auth::middleware::AuthenticatedUser;
+use crate::handlers::ingest::generic::run_ingest;
+use crate::handlers::{
+    wrap_response, ApiResponse, AppError, AppState, DebugParams, EmbedParams, OrgHeader,
+};
+use crate::ingestors::SOURCE_SLACK;
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct IngestSlackRequest {
+    pub channel_id: String,
+    /// Fetches only the messages posted since the last incremental sync of the channel.
+    #[serde(default)]
+    pub incremental: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IngestSlackResponse {
+    pub message: String,
+    pub ingested_threads: usize,
+}
+
+/// Handler for ingesting the history of a Slack channel using the `anyrag-slack` plugin.
+#[utoipa::path(
+    post,
+    path = "/ingest/slack",
+    tag = "ingest",
+    params(DebugParams, EmbedParams, OrgHeader),
+    request_body = IngestSlackRequest,
+    responses((status = 200, description = "The number of threads stored.", body = ApiResponse<IngestSlackResponse>))
+)]
+pub async fn ingest_slack_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Query(embed_params): Query<EmbedParams>,
+    headers: HeaderMap,
+    Json(payload): Json<IngestSlackRequest>,
+) -> Result<Json<ApiResponse<IngestSlackResponse>>, AppError> {
+    // 1. Ingest the source through the plugin registry.
+    let source = json!({
+        "channel_id": payload.channel_id,
+        "incremental": payload.incremental,
+    });
+    let (result, debug_info) = run_ingest(
+        &app_state,
+        &user.0,
+        &headers,
+        &embed_params,
+        SOURCE_SLACK,
+        source,
+    )
+    .await?;
+
+    // 2. Construct the final HTTP response.
+    let response = IngestSlackResponse {
+        message: format!(
+            "Successfully ingested {} threads from the Slack channel.",
+            result.documents_added
+        ),
+        ingested_threads: result.documents_added,
+    };
+    Ok(wrap_response(response, debug_params, Some(debug_info)))
+}
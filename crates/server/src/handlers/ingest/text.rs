@@ -1,62 +1,87 @@
 use crate::auth::middleware::AuthenticatedUser;
-use crate::handlers::{wrap_response, ApiResponse, AppError, AppState, DebugParams};
-use anyrag::ingest::Ingestor;
-use anyrag_text::TextIngestor;
+use crate::handlers::ingest::generic::run_ingest;
+use crate::handlers::{
+    wrap_response, ApiResponse, AppError, AppState, DebugParams, EmbedParams, OrgHeader,
+};
+use crate::ingestors::SOURCE_TEXT;
+use anyrag::ingest::ChunkingStrategy;
+use anyrag_text::{validate_chunk_config, DEFAULT_CHUNK_OVERLAP, DEFAULT_CHUNK_SIZE};
 use axum::{
     extract::{Query, State},
+    http::HeaderMap,
     Json,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tracing::info;
+use utoipa::ToSchema;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct IngestTextRequest {
     pub text: String,
     #[serde(default = "default_source")]
     pub source: String,
+    #[serde(default)]
+    pub chunk_size: Option<usize>,
+    #[serde(default)]
+    pub chunk_overlap: Option<usize>,
+    #[serde(default)]
+    pub chunking: Option<ChunkingStrategy>,
 }
 
 fn default_source() -> String {
     "text_input".to_string()
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct IngestTextResponse {
     pub message: String,
     pub ingested_chunks: usize,
 }
 
 /// Handler for ingesting raw text content using the `anyrag-text` plugin.
+#[utoipa::path(
+    post,
+    path = "/ingest/text",
+    tag = "ingest",
+    params(DebugParams, EmbedParams, OrgHeader),
+    request_body = IngestTextRequest,
+    responses((status = 200, description = "The number of chunks stored.", body = ApiResponse<IngestTextResponse>))
+)]
 pub async fn ingest_text_handler(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
     debug_params: Query<DebugParams>,
+    Query(embed_params): Query<EmbedParams>,
+    headers: HeaderMap,
     Json(payload): Json<IngestTextRequest>,
 ) -> Result<Json<ApiResponse<IngestTextResponse>>, AppError> {
-    let owner_id = Some(user.0.id);
-    info!(
-        "User '{:?}' sending text ingest request from source: {}",
-        owner_id, payload.source
-    );
-
-    // 1. Instantiate the ingestor plugin.
-    let ingestor = TextIngestor::new(&app_state.sqlite_provider.db);
+    // Reject invalid chunk settings up front so they surface as a client error.
+    validate_chunk_config(
+        payload.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE),
+        payload.chunk_overlap.unwrap_or(DEFAULT_CHUNK_OVERLAP),
+    )?;
 
-    // 2. Serialize the source information into a JSON string for the generic ingest method.
-    let source_json = json!({
+    // 1. Describe the source as the text plugin expects it.
+    let source = json!({
         "text": payload.text,
-        "source": payload.source
-    })
-    .to_string();
+        "source": payload.source,
+        "chunk_size": payload.chunk_size,
+        "chunk_overlap": payload.chunk_overlap,
+        "chunking": payload.chunking
+    });
 
-    // 3. Call the generic ingest method from the trait.
-    let result = ingestor
-        .ingest(&source_json, owner_id.as_deref())
-        .await
-        .map_err(|e| AppError::Internal(anyhow::anyhow!("Text ingestion failed: {e}")))?;
+    // 2. Ingest it through the plugin registry.
+    let (result, debug_info) = run_ingest(
+        &app_state,
+        &user.0,
+        &headers,
+        &embed_params,
+        SOURCE_TEXT,
+        source,
+    )
+    .await?;
 
-    // 4. Construct the final HTTP response.
+    // 3. Construct the final HTTP response.
     let message = if result.documents_added > 0 {
         format!(
             "Text ingestion successful. Stored {} new document chunks.",
@@ -70,11 +95,5 @@ pub async fn ingest_text_handler(
         message,
         ingested_chunks: result.documents_added,
     };
-    let debug_info = json!({
-        "source": result.source,
-        "chunks_created": result.documents_added,
-        "document_ids": result.document_ids,
-        "owner_id": owner_id,
-    });
     Ok(wrap_response(response, debug_params, Some(debug_info)))
 }
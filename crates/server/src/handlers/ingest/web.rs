@@ -1,89 +1,198 @@
 use crate::auth::middleware::AuthenticatedUser;
-use crate::handlers::{wrap_response, ApiResponse, AppError, AppState, DebugParams};
-use anyrag::ingest::{IngestionPrompts, Ingestor};
-use anyrag_web::{WebIngestStrategy, WebIngestor};
+use crate::handlers::ingest::generic::run_ingest;
+use crate::handlers::{
+    wrap_response, ApiResponse, AppError, AppState, DebugParams, EmbedParams, OrgHeader,
+};
+use crate::ingestors::SOURCE_WEB;
+use anyrag::ingest::ChunkingStrategy;
+use anyrag::types::AppConfig;
+use anyrag_web::{
+    crawl::CrawlOptions, images::ImageOptions, sitemap::SitemapOptions, WebIngestMetadata,
+    WebIngestStrategy,
+};
 use axum::{
     extract::{Query, State},
+    http::HeaderMap,
     Json,
 };
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use tracing::info;
+use serde_json::{json, Value};
+use utoipa::ToSchema;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct IngestWebRequest {
-    pub url: String,
+    /// The page to ingest. Exclusive with `sitemap_url`.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// A sitemap or sitemap index whose pages are all ingested.
+    #[serde(default)]
+    pub sitemap_url: Option<String>,
+    /// Filters the pages of `sitemap_url`.
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub sitemap: Option<SitemapOptions>,
+    #[serde(default)]
+    pub chunking: Option<ChunkingStrategy>,
+    /// `false` stores the page's markdown chunks as they are instead of restructuring
+    /// them with the LLM.
+    #[serde(default)]
+    pub restructure: Option<bool>,
+    /// Stores the page's HTML tables as SQLite tables for text-to-SQL.
+    #[serde(default)]
+    pub extract_tables: bool,
+    /// Follows same-site links from `url` and ingests every page reached.
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub crawl: Option<CrawlOptions>,
+    /// Keeps the page's images and stores them as `IMAGE` metadata of its documents.
+    /// With `{"caption": true}`, each is described by the model of the
+    /// `image_captioning` task.
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub images: Option<ImageOptions>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct IngestWebResponse {
     pub message: String,
     pub ingested_documents: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tables: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pages: Vec<String>,
+    /// Pages skipped because they did not change since their last ingestion.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unchanged: Vec<String>,
+}
+
+/// Returns the fetch strategy configured by `WEB_INGEST_STRATEGY`.
+pub(crate) fn web_ingest_strategy(config: &AppConfig) -> anyhow::Result<WebIngestStrategy<'_>> {
+    let strategy = match config.web_ingest_strategy.as_str() {
+        "jina" => WebIngestStrategy::Jina {
+            api_key: config.jina_api_key.as_deref(),
+        },
+        "headless" => WebIngestStrategy::Headless {
+            endpoint: config.headless_browser_url.as_deref().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "The 'headless' web ingest strategy requires HEADLESS_BROWSER_URL to be set."
+                )
+            })?,
+        },
+        _ => WebIngestStrategy::RawHtml,
+    };
+    Ok(strategy)
+}
+
+/// Lowers the `max_pages` of a crawl to the `max_crawl_pages` of the server and to the
+/// documents left in the user's monthly quota, as every page becomes a document.
+pub(crate) fn limit_crawl(
+    config: &AppConfig,
+    crawl: &Value,
+    remaining_documents: Option<u64>,
+) -> anyhow::Result<Value> {
+    let mut options: CrawlOptions = serde_json::from_value(crawl.clone())?;
+    options.max_pages = page_limit(
+        options.max_pages,
+        config.max_crawl_pages,
+        remaining_documents,
+    );
+    Ok(serde_json::to_value(options)?)
+}
+
+/// Lowers the `max_urls` of a sitemap to the `max_sitemap_urls` of the server and to
+/// the documents left in the user's monthly quota. A missing `sitemap` gets the
+/// default options.
+pub(crate) fn limit_sitemap(
+    config: &AppConfig,
+    sitemap: &Value,
+    remaining_documents: Option<u64>,
+) -> anyhow::Result<Value> {
+    let mut options: SitemapOptions = match sitemap {
+        Value::Null => SitemapOptions::default(),
+        sitemap => serde_json::from_value(sitemap.clone())?,
+    };
+    options.max_urls = page_limit(
+        options.max_urls,
+        config.max_sitemap_urls,
+        remaining_documents,
+    );
+    Ok(serde_json::to_value(options)?)
+}
+
+/// The smallest of the requested page count, the server's maximum and the quota left.
+fn page_limit(requested: usize, server_max: usize, remaining_documents: Option<u64>) -> usize {
+    let limit = requested.min(server_max);
+    match remaining_documents {
+        Some(remaining) => limit.min(usize::try_from(remaining).unwrap_or(usize::MAX)),
+        None => limit,
+    }
+}
+
+/// Returns the model and system prompt of the `image_captioning` task, which describes
+/// the images of web pages.
+pub(crate) fn image_captioning(
+    app_state: &AppState,
+) -> Option<(&dyn anyrag::providers::ai::AiProvider, &str)> {
+    let task_config = app_state.tasks.get("image_captioning")?;
+    let ai_provider = app_state.ai_providers.get(&task_config.provider)?;
+    Some((ai_provider.as_ref(), task_config.system_prompt.as_str()))
 }
 
 /// Handler for the knowledge base ingestion pipeline from a web URL.
+#[utoipa::path(
+    post,
+    path = "/ingest/web",
+    tag = "ingest",
+    params(DebugParams, EmbedParams, OrgHeader),
+    request_body = IngestWebRequest,
+    responses((status = 200, description = "The number of documents stored.", body = ApiResponse<IngestWebResponse>))
+)]
 pub async fn ingest_web_handler(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
     debug_params: Query<DebugParams>,
+    Query(embed_params): Query<EmbedParams>,
+    headers: HeaderMap,
     Json(payload): Json<IngestWebRequest>,
 ) -> Result<Json<ApiResponse<IngestWebResponse>>, AppError> {
-    let owner_id = Some(user.0.id);
-    info!(
-        "Received web ingest request for URL: {} by user {:?}",
-        payload.url, owner_id
-    );
-
-    // 1. Get necessary providers and prompts from app state
-    let task_name = "knowledge_distillation";
-    let task_config = app_state.tasks.get(task_name).ok_or_else(|| {
-        AppError::Internal(anyhow::anyhow!("Task '{task_name}' not found in config"))
-    })?;
-    let provider_name = &task_config.provider;
-    let ai_provider = app_state.ai_providers.get(provider_name).ok_or_else(|| {
-        AppError::Internal(anyhow::anyhow!("Provider '{provider_name}' not found"))
-    })?;
-
-    let meta_task_name = "knowledge_metadata_extraction";
-    let meta_task_config = app_state.tasks.get(meta_task_name).ok_or_else(|| {
-        AppError::Internal(anyhow::anyhow!(
-            "Task '{meta_task_name}' not found in config"
-        ))
-    })?;
-
-    let prompts = IngestionPrompts {
-        restructuring_system_prompt: &task_config.system_prompt,
-        metadata_extraction_system_prompt: &meta_task_config.system_prompt,
-    };
-
-    // 2. Instantiate the ingestor plugin
-    let ingestor = WebIngestor::new(&app_state.sqlite_provider.db, ai_provider.as_ref(), prompts);
-
-    // 3. Determine the strategy and serialize the source for the ingestor
-    let web_ingest_strategy = match app_state.config.web_ingest_strategy.as_str() {
-        "jina" => WebIngestStrategy::Jina {
-            api_key: app_state.config.jina_api_key.as_deref(),
-        },
-        _ => WebIngestStrategy::RawHtml,
-    };
-
-    let source_json = json!({
+    // 1. Ingest the pages through the plugin registry, which sets the fetch strategy
+    // and the image captioning configured for the server.
+    let source = json!({
         "url": payload.url,
-        "strategy": web_ingest_strategy,
-    })
-    .to_string();
-
-    // 4. Call the generic ingest method
-    let ingest_result = ingestor
-        .ingest(&source_json, owner_id.as_deref())
-        .await
-        .map_err(|e| AppError::Internal(anyhow::anyhow!("Web ingestion failed: {e}")))?;
+        "sitemap_url": payload.sitemap_url,
+        "sitemap": payload.sitemap.unwrap_or_default(),
+        "chunking": payload.chunking,
+        "restructure": payload.restructure.unwrap_or(true),
+        "extract_tables": payload.extract_tables,
+        "crawl": payload.crawl,
+        "images": payload.images,
+    });
+    let (ingest_result, debug_info) = run_ingest(
+        &app_state,
+        &user.0,
+        &headers,
+        &embed_params,
+        SOURCE_WEB,
+        source,
+    )
+    .await?;
 
-    // 5. Construct the response
+    // 2. Construct the response
+    let WebIngestMetadata {
+        tables,
+        pages,
+        unchanged,
+    } = ingest_result
+        .metadata
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<WebIngestMetadata>(m).ok())
+        .unwrap_or_default();
     let response = IngestWebResponse {
         message: "Knowledge ingestion pipeline completed successfully.".to_string(),
         ingested_documents: ingest_result.documents_added,
+        tables,
+        pages,
+        unchanged,
     };
-    let debug_info = json!({ "url": payload.url, "owner_id": owner_id });
     Ok(wrap_response(response, debug_params, Some(debug_info)))
 }
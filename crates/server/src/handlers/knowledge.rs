@@ -4,47 +4,83 @@
 //! including the main RAG search endpoint, embedding, exporting, and graph searches.
 
 use super::{
-    search::SearchRequest, wrap_response, AppError, AppState, DebugParams, PromptResponse,
+    search::SearchRequest, wrap_response, ApiResponse, AppError, AppState, DebugParams, OrgHeader,
+    PromptResponse,
+};
+use crate::{
+    auth::{middleware::AuthenticatedUser, org::org_context},
+    db_router::Tenant,
+    graph_extraction::fact_visibility,
+    moderation::moderate_answer,
 };
-use crate::auth::middleware::AuthenticatedUser;
 use anyrag::{
-    constants,
-    ingest::export_for_finetuning,
+    answer_cache::{
+        find_cached_answer, store_answer, AnswerCacheConfig, AnswerCacheScope, CachedAnswer,
+    },
+    context_budget::{fit_context, ContextBudget},
+    context_sanitization::{document_context, guard_system_prompt},
+    ingest::{
+        export_finetuning_dataset, generate_faqs, FaqGeneration, FinetuningExportOptions,
+        FinetuningFormat,
+    },
+    moderation::ModerationReport,
     providers::{ai::generate_embeddings_batch, db::sqlite::SqliteProvider},
-    search::{hybrid_search, HybridSearchOptions, HybridSearchPrompts},
+    search::{hybrid_search_with_details, HybridSearchOptions, HybridSearchPrompts},
     types::{ContentType, ExecutePromptOptions, PromptClientBuilder},
 };
 use axum::{
     extract::{Query, State},
+    http::{header, HeaderMap},
+    response::IntoResponse,
     Json,
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use core_access::{has_permission, permissions::ADMIN_DOCUMENTS};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::sync::Arc;
-use tracing::{error, info};
-use turso::params;
+use tracing::{error, info, warn};
+use turso::{params, Connection};
+use utoipa::{IntoParams, ToSchema};
 
 // --- API Payloads for Knowledge Base ---
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct EmbedNewRequest {
     pub limit: Option<usize>,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
 pub struct EmbedNewResponse {
     message: String,
     embedded_articles: usize,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct FaqGenerateRequest {
+    /// The documents to generate FAQs for. When empty, up to `limit` documents that
+    /// have no FAQs yet are picked.
+    #[serde(default)]
+    pub document_ids: Vec<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct FaqGenerateResponse {
+    message: String,
+    faqs_stored: usize,
+    duplicates_skipped: usize,
+    documents: Vec<FaqGeneration>,
+    failed_document_ids: Vec<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct KnowledgeGraphSearchRequest {
     pub subject: String,
     pub predicate: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct KnowledgeGraphSearchResponse {
     pub object: Option<String>,
 }
@@ -52,8 +88,17 @@ pub struct KnowledgeGraphSearchResponse {
 // --- Knowledge Base Handlers ---
 
 /// Handler for embedding new, unprocessed documents in the knowledge base.
+#[utoipa::path(
+    post,
+    path = "/embed/new",
+    tag = "knowledge",
+    params(DebugParams),
+    request_body = EmbedNewRequest,
+    responses((status = 200, description = "The number of documents embedded.", body = ApiResponse<EmbedNewResponse>))
+)]
 pub async fn embed_new_handler(
     State(app_state): State<AppState>,
+    user: AuthenticatedUser,
     debug_params: Query<DebugParams>,
     Json(payload): Json<EmbedNewRequest>,
 ) -> Result<Json<super::ApiResponse<EmbedNewResponse>>, AppError> {
@@ -65,13 +110,14 @@ pub async fn embed_new_handler(
     let model = &app_state.config.embedding.model_name;
     let api_key = app_state.config.embedding.api_key.as_deref();
 
-    let conn = app_state.sqlite_provider.db.connect()?;
+    let db = app_state.db_router.for_user(&user.0.id, None).await?;
+    let conn = db.db.connect()?;
     let sql = format!(
         "
         SELECT d.id, d.title, d.content
         FROM documents d
         LEFT JOIN document_embeddings de ON d.id = de.document_id
-        WHERE de.id IS NULL
+        WHERE de.id IS NULL AND d.deleted_at IS NULL
         LIMIT {limit}
     "
     );
@@ -162,44 +208,285 @@ pub async fn embed_new_handler(
     Ok(wrap_response(response, debug_params, Some(debug_info)))
 }
 
-/// Handler for exporting the knowledge base for fine-tuning.
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct KnowledgeExportQuery {
+    /// `openai` (default), `gemini`, or `alpaca`.
+    #[serde(default)]
+    #[param(inline)]
+    pub format: FinetuningFormat,
+    /// Exports the FAQs of this user's documents. Defaults to the current user, or to
+    /// every user for callers with the `admin:documents` permission.
+    pub owner_id: Option<String>,
+    /// Only exports the FAQs of documents whose link starts with this prefix.
+    pub source_prefix: Option<String>,
+    /// Only exports the FAQs of documents created at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Only exports the FAQs of documents created before this time.
+    pub until: Option<DateTime<Utc>>,
+    /// Only exports the FAQs whose question has at least this feedback score: the
+    /// number of positive ratings of prompts asking it minus the negative ones.
+    pub min_feedback_score: Option<i64>,
+}
+
+/// Handler for exporting the knowledge base as a fine-tuning dataset.
+///
+/// **Authorization**: Users can export their own documents. Users with the
+/// `admin:documents` permission can export the documents of any user, or all of them.
+#[utoipa::path(
+    get,
+    path = "/knowledge/export",
+    tag = "knowledge",
+    params(KnowledgeExportQuery),
+    responses((status = 200, description = "The FAQs of the knowledge base as fine-tuning JSONL.", body = String))
+)]
 pub async fn knowledge_export_handler(
     State(app_state): State<AppState>,
-) -> Result<String, AppError> {
-    info!("Received request to export knowledge base for fine-tuning.");
-    let jsonl_data = export_for_finetuning(&app_state.sqlite_provider.db)
+    user: AuthenticatedUser,
+    Query(query): Query<KnowledgeExportQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let current_user = user.0;
+    let owner_id = match (
+        query.owner_id,
+        has_permission(&current_user, ADMIN_DOCUMENTS),
+    ) {
+        (Some(owner_id), true) => Some(owner_id),
+        (None, true) => None,
+        (Some(owner_id), false) if owner_id != current_user.id => {
+            return Err(AppError::Forbidden(
+                "Forbidden: only your own documents can be exported.".to_string(),
+            ))
+        }
+        (_, false) => Some(current_user.id.clone()),
+    };
+    info!(
+        "User '{}' is exporting the fine-tuning dataset of {:?} as {}.",
+        current_user.id, owner_id, query.format
+    );
+
+    // A dataset of all owners reads every shard of a sharded knowledge base.
+    let databases = match &owner_id {
+        Some(owner_id) => vec![app_state.db_router.for_user(owner_id, None).await?],
+        None => app_state.db_router.knowledge_bases().await?,
+    };
+    let options = FinetuningExportOptions {
+        format: query.format,
+        owner_id,
+        source_prefix: query.source_prefix,
+        since: query.since,
+        until: query.until,
+        min_feedback_score: query.min_feedback_score,
+    };
+    let mut jsonl_data = String::new();
+    for db in &databases {
+        jsonl_data.push_str(&export_finetuning_dataset(&db.db, &options).await?);
+    }
+    let disposition = format!(
+        "attachment; filename=\"finetuning-{}.jsonl\"",
+        options.format
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        jsonl_data,
+    ))
+}
+
+/// Handler for distilling FAQs from the user's documents into `faq_items`.
+#[utoipa::path(
+    post,
+    path = "/knowledge/faq/generate",
+    tag = "knowledge",
+    params(DebugParams),
+    request_body = FaqGenerateRequest,
+    responses((status = 200, description = "The FAQs stored per document.", body = ApiResponse<FaqGenerateResponse>))
+)]
+pub async fn faq_generate_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Json(payload): Json<FaqGenerateRequest>,
+) -> Result<Json<ApiResponse<FaqGenerateResponse>>, AppError> {
+    let limit = payload.limit.unwrap_or(20);
+    let task_name = "faq_generation";
+    let task = app_state.tasks.get(task_name).ok_or_else(|| {
+        AppError::Internal(anyhow::anyhow!("Task '{task_name}' not found in config"))
+    })?;
+    let provider_name = &task.provider;
+    let ai_provider = app_state.ai_providers.get(provider_name).ok_or_else(|| {
+        AppError::Internal(anyhow::anyhow!("Provider '{provider_name}' not found"))
+    })?;
+
+    let db = app_state.db_router.for_user(&user.0.id, None).await?;
+    let conn = db.db.connect()?;
+    let document_ids = faq_document_ids(&conn, &user.0.id, &payload.document_ids, limit).await?;
+    info!("Generating FAQs for {} documents.", document_ids.len());
+
+    let mut documents = Vec::new();
+    let mut failed_document_ids: Vec<String> = payload
+        .document_ids
+        .iter()
+        .filter(|id| !document_ids.contains(id))
+        .cloned()
+        .collect();
+    for document_id in &document_ids {
+        match generate_faqs(
+            &conn,
+            ai_provider.as_ref(),
+            document_id,
+            &task.system_prompt,
+            &task.user_prompt,
+        )
         .await
-        .map_err(|e| AppError::Internal(anyhow::anyhow!("Knowledge export failed: {e}")))?;
-    Ok(jsonl_data)
+        {
+            Ok(generation) => documents.push(generation),
+            Err(e) => {
+                warn!("FAQ generation failed for document '{document_id}': {e}");
+                failed_document_ids.push(document_id.clone());
+            }
+        }
+    }
+
+    let faqs_stored = documents.iter().map(|d| d.stored).sum();
+    let duplicates_skipped = documents.iter().map(|d| d.duplicates).sum();
+    let response = FaqGenerateResponse {
+        message: format!(
+            "Stored {faqs_stored} FAQs from {} of {} documents.",
+            documents.len(),
+            document_ids.len()
+        ),
+        faqs_stored,
+        duplicates_skipped,
+        documents,
+        failed_document_ids,
+    };
+    let debug_info = json!({ "limit": limit, "task": task_name, "provider": provider_name });
+    Ok(wrap_response(response, debug_params, Some(debug_info)))
+}
+
+/// Returns the requested documents the user owns, or, when none are requested, up to
+/// `limit` of the user's documents that have no FAQs yet.
+async fn faq_document_ids(
+    conn: &Connection,
+    owner_id: &str,
+    requested_ids: &[String],
+    limit: usize,
+) -> Result<Vec<String>, AppError> {
+    let mut document_ids = Vec::new();
+    if requested_ids.is_empty() {
+        let sql = format!(
+            "
+            SELECT d.id
+            FROM documents d
+            LEFT JOIN faq_items f ON d.id = f.document_id
+            WHERE f.id IS NULL AND d.owner_id = ? AND d.deleted_at IS NULL
+            GROUP BY d.id
+            LIMIT {limit}
+        "
+        );
+        let mut rows = conn.query(&sql, params![owner_id]).await?;
+        while let Some(row) = rows.next().await? {
+            document_ids.push(row.get(0)?);
+        }
+        return Ok(document_ids);
+    }
+    for document_id in requested_ids {
+        let mut rows = conn
+            .query(
+                "SELECT id FROM documents WHERE id = ? AND owner_id = ? AND deleted_at IS NULL",
+                params![document_id.as_str(), owner_id],
+            )
+            .await?;
+        if rows.next().await?.is_some() && !document_ids.contains(document_id) {
+            document_ids.push(document_id.clone());
+        }
+    }
+    Ok(document_ids)
 }
 
 /// Handler for the primary RAG search endpoint against the knowledge base.
+#[utoipa::path(
+    post,
+    path = "/search/knowledge",
+    tag = "search",
+    params(DebugParams, OrgHeader),
+    request_body = SearchRequest,
+    responses((status = 200, description = "The answer grounded in the knowledge base.", body = ApiResponse<PromptResponse>))
+)]
 #[axum::debug_handler]
 pub async fn knowledge_search_handler(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
     debug_params: Query<DebugParams>,
+    headers: HeaderMap,
     Json(payload): Json<SearchRequest>,
 ) -> Result<Json<super::ApiResponse<PromptResponse>>, AppError> {
-    let owner_id = Some(user.0.id);
+    let org_id = org_context(&app_state, &user.0, &headers).await?;
     let limit = payload.limit.unwrap_or(5);
 
     // --- Dynamic DB Connection ---
-    let sqlite_provider = if let Some(db_name) = &payload.db {
-        let db_path = format!("{}/{}.db", constants::DB_DIR, db_name);
-        info!("Connecting to dynamic database: {}", db_path);
-        let provider = SqliteProvider::new(&db_path).await?;
-        provider.initialize_schema().await?;
-        Arc::new(provider)
-    } else {
-        app_state.sqlite_provider.clone()
+    let sqlite_provider = match &payload.db {
+        Some(db_name) => {
+            info!("Connecting to dynamic database: {}", db_name);
+            app_state.db_router.route(Tenant::Project(db_name)).await?
+        }
+        None => {
+            app_state
+                .db_router
+                .for_user(&user.0.id, org_id.as_deref())
+                .await?
+        }
     };
+    let owner_id = Some(user.0.id);
 
     info!(
         "User '{:?}' sending knowledge RAG search for query: '{}', limit: {}",
         owner_id, payload.query, limit
     );
 
+    // --- Serve near-duplicate questions from the answer cache ---
+    // Knowledge graph facts, model overrides and collections are not part of the cache
+    // key, so those requests are always answered afresh.
+    let cache_lookup = match &app_state.config.answer_cache {
+        Some(config)
+            if payload.model.is_none()
+                && payload.collection.is_none()
+                && !payload.use_knowledge_graph.unwrap_or(false) =>
+        {
+            let scope = AnswerCacheScope {
+                owner_id: owner_id.clone(),
+                org_id: org_id.clone(),
+                db: payload.db.clone(),
+                instruction: payload.instruction.clone().filter(|s| !s.is_empty()),
+            };
+            lookup_cached_answer(&app_state, &sqlite_provider, config, scope, &payload.query).await
+        }
+        _ => None,
+    };
+    if let Some((lookup, Some(cached))) = &cache_lookup {
+        info!(
+            "Answering from the answer cache (entry {}, similarity {:.3}).",
+            cached.id, cached.similarity
+        );
+        let (answer, moderation) = moderate_answer(&app_state, cached.answer.clone()).await;
+        let debug_info = json!({
+            "query": payload.query,
+            "answer_cache": { "hit": true, "entry": cached, "corpus_version": lookup.corpus_version },
+            "moderation": moderation,
+        });
+        return Ok(wrap_response(
+            PromptResponse {
+                text: Value::String(answer),
+                experiment_run: None,
+                moderation: moderation.filter(ModerationReport::is_reported),
+            },
+            debug_params,
+            Some(debug_info),
+        ));
+    }
+
     // --- Get AI provider for query analysis ---
     let task_name = "query_analysis";
     let task_config = app_state.tasks.get(task_name).ok_or_else(|| {
@@ -243,7 +530,9 @@ pub async fn knowledge_search_handler(
 
     let search_options = HybridSearchOptions {
         query_text: payload.query.clone(),
-        owner_id,
+        owner_id: owner_id.clone(),
+        org_id: org_id.clone(),
+        collection: payload.collection.clone(),
         limit,
         prompts: HybridSearchPrompts {
             analysis_system_prompt: &task_config.system_prompt,
@@ -257,24 +546,67 @@ pub async fn knowledge_search_handler(
         temporal_ranking_config,
     };
 
-    let search_results =
-        hybrid_search(sqlite_provider.clone(), ai_provider, search_options).await?;
+    let search_output =
+        hybrid_search_with_details(sqlite_provider.clone(), ai_provider, search_options).await?;
+    let temporal_constraint = search_output.temporal_constraint;
+    let search_results = search_output.results;
 
     let kg_fact = if payload.use_knowledge_graph.unwrap_or(false) {
         info!("Knowledge graph search is enabled for this request.");
+        let visibility =
+            fact_visibility(&sqlite_provider, owner_id.as_deref(), org_id.as_deref()).await?;
         let kg = app_state
             .knowledge_graph
             .read()
             .map_err(|_| AppError::Internal(anyhow::anyhow!("Failed to acquire KG read lock")))?;
 
         let predicate = "role";
-        kg.get_fact_as_of(&payload.query, predicate, Utc::now())
+        kg.get_fact_as_of(&payload.query, predicate, Utc::now(), &visibility)
             .ok()
             .flatten()
     } else {
         None
     };
 
+    // --- Get AI provider for RAG synthesis ---
+    let task_name = "rag_synthesis";
+    let task_config = app_state.tasks.get(task_name).ok_or_else(|| {
+        AppError::Internal(anyhow::anyhow!("Task '{task_name}' not found in config"))
+    })?;
+
+    let synthesis_provider_name = if let Some(model_name) = &payload.model {
+        info!("Model override requested for synthesis: {}", model_name);
+        // We can reuse the same logic as for the analysis provider
+        app_state
+            .config
+            .providers
+            .iter()
+            .find(|(_, p)| p.model_name == *model_name)
+            .map(|(name, _)| name)
+            .ok_or_else(|| {
+                AppError::Internal(anyhow::anyhow!(
+                    "Model '{model_name}' not found in any configured provider"
+                ))
+            })?
+    } else {
+        &task_config.provider
+    };
+    let synthesis_provider = app_state
+        .ai_providers
+        .get(synthesis_provider_name)
+        .ok_or_else(|| {
+            AppError::Internal(anyhow::anyhow!(
+                "Provider '{synthesis_provider_name}' not found"
+            ))
+        })?;
+
+    // --- Fit the retrieved context into the synthesis model's context window ---
+    let budgeted = fit_context(
+        ContextBudget::for_provider(&app_state.config, synthesis_provider_name),
+        search_results,
+    );
+    let search_results = budgeted.results;
+
     let mut context_parts = Vec::new();
 
     if let Some(fact) = kg_fact {
@@ -282,12 +614,11 @@ pub async fn knowledge_search_handler(
         context_parts.push(format!("Definitive Answer from Knowledge Graph: {fact}."));
     }
 
+    // --- Sanitize the retrieved documents before they reach the prompt ---
+    let sanitizer = app_state.context_sanitizer.as_deref();
+    let documents = document_context(&search_results, sanitizer);
     if !search_results.is_empty() {
-        let articles_context = search_results
-            .iter()
-            .map(|result| result.description.clone())
-            .collect::<Vec<String>>()
-            .join("\n\n---\n\n");
+        let articles_context = documents.context;
 
         if !context_parts.is_empty() {
             context_parts.push(format!(
@@ -302,11 +633,17 @@ pub async fn knowledge_search_handler(
 
     if context.is_empty() {
         let text = "I could not find any relevant information to answer your question.".to_string();
-        let debug_info =
-            json!({ "query": payload.query, "limit": limit, "status": "No results found" });
+        let debug_info = json!({
+            "query": payload.query,
+            "limit": limit,
+            "status": "No results found",
+            "temporal_constraint": temporal_constraint,
+        });
         return Ok(wrap_response(
             PromptResponse {
                 text: Value::String(text),
+                experiment_run: None,
+                moderation: None,
             },
             debug_params,
             Some(debug_info),
@@ -315,36 +652,6 @@ pub async fn knowledge_search_handler(
 
     info!("--> Synthesizing answer with context:\n{}", context);
 
-    // --- Get AI provider for RAG synthesis ---
-    let task_name = "rag_synthesis";
-    let task_config = app_state.tasks.get(task_name).ok_or_else(|| {
-        AppError::Internal(anyhow::anyhow!("Task '{task_name}' not found in config"))
-    })?;
-
-    let synthesis_provider = if let Some(model_name) = &payload.model {
-        info!("Model override requested for synthesis: {}", model_name);
-        // We can reuse the same logic as for the analysis provider
-        let provider_name = app_state
-            .config
-            .providers
-            .iter()
-            .find(|(_, p)| p.model_name == *model_name)
-            .map(|(name, _)| name)
-            .ok_or_else(|| {
-                AppError::Internal(anyhow::anyhow!(
-                    "Model '{model_name}' not found in any configured provider"
-                ))
-            })?;
-        app_state.ai_providers.get(provider_name).ok_or_else(|| {
-            AppError::Internal(anyhow::anyhow!("Provider '{provider_name}' not found"))
-        })?
-    } else {
-        let provider_name = &task_config.provider;
-        app_state.ai_providers.get(provider_name).ok_or_else(|| {
-            AppError::Internal(anyhow::anyhow!("Provider '{provider_name}' not found"))
-        })?
-    };
-
     // Manually combine prompt and instruction for the final synthesis step.
     // This is safer than modifying the library's prompt templates or logic.
     let final_prompt =
@@ -365,7 +672,10 @@ pub async fn knowledge_search_handler(
     };
 
     // Apply prompts from config
-    options.system_prompt_template = Some(task_config.system_prompt.clone());
+    options.system_prompt_template = Some(match sanitizer {
+        Some(_) => guard_system_prompt(&task_config.system_prompt),
+        None => task_config.system_prompt.clone(),
+    });
     options.user_prompt_template = Some(task_config.user_prompt.clone());
 
     let client = PromptClientBuilder::new()
@@ -374,28 +684,132 @@ pub async fn knowledge_search_handler(
         .build()?;
 
     let prompt_result = client.execute_prompt_with_options(options.clone()).await?;
+    let (answer, moderation) = moderate_answer(&app_state, prompt_result.text).await;
+    let blocked = moderation.as_ref().is_some_and(|report| report.blocked);
+
+    // A blocked answer is not cached, so that the question is answered afresh.
+    if let (Some((lookup, None)), false) = (&cache_lookup, blocked) {
+        if let Err(e) = store_answer(
+            &app_state.sqlite_provider.db,
+            &lookup.scope,
+            &lookup.corpus_version,
+            &payload.query,
+            &lookup.embedding,
+            &answer,
+            lookup.config,
+        )
+        .await
+        {
+            warn!("Failed to store the answer in the answer cache: {e}");
+        }
+    }
 
     let debug_info = if debug_params.debug.unwrap_or(false) {
         Some(json!({
             "options": options,
             "retrieved_context": context,
-            "final_candidate_count": search_results.len()
+            "final_candidate_count": search_results.len(),
+            "context_tokens": budgeted.used_tokens,
+            "dropped_context": budgeted.dropped,
+            "injection_detections": documents.detections,
+            "temporal_constraint": temporal_constraint,
+            "answer_cache": cache_lookup.as_ref().map(|(lookup, _)| json!({ "hit": false, "corpus_version": lookup.corpus_version })),
+            "moderation": moderation,
         }))
     } else {
         None
     };
     Ok(wrap_response(
         PromptResponse {
-            text: Value::String(prompt_result.text),
+            text: Value::String(answer),
+            experiment_run: None,
+            moderation: moderation.filter(ModerationReport::is_reported),
         },
         debug_params,
         debug_info,
     ))
 }
 
+/// A question looked up in the answer cache, kept to store its answer on a miss.
+struct AnswerCacheLookup<'a> {
+    config: &'a AnswerCacheConfig,
+    scope: AnswerCacheScope,
+    corpus_version: String,
+    embedding: Vec<f32>,
+}
+
+/// Looks a question up in the answer cache of the primary database. Returns `None`
+/// when the lookup fails, so that the question is answered without the cache.
+async fn lookup_cached_answer<'a>(
+    app_state: &AppState,
+    sqlite_provider: &SqliteProvider,
+    config: &'a AnswerCacheConfig,
+    scope: AnswerCacheScope,
+    question: &str,
+) -> Option<(AnswerCacheLookup<'a>, Option<CachedAnswer>)> {
+    let corpus_version = match sqlite_provider
+        .corpus_version(scope.owner_id.as_deref(), scope.org_id.as_deref())
+        .await
+    {
+        Ok(version) => version,
+        Err(e) => {
+            warn!("Skipping the answer cache, the corpus version is unknown: {e}");
+            return None;
+        }
+    };
+    let embedding = match generate_embeddings_batch(
+        &app_state.config.embedding.api_url,
+        &app_state.config.embedding.model_name,
+        &[question],
+        app_state.config.embedding.api_key.as_deref(),
+    )
+    .await
+    {
+        Ok(mut vectors) if !vectors.is_empty() => vectors.swap_remove(0),
+        Ok(_) => return None,
+        Err(e) => {
+            warn!("Skipping the answer cache, the question could not be embedded: {e}");
+            return None;
+        }
+    };
+
+    let cached = find_cached_answer(
+        &app_state.sqlite_provider.db,
+        &scope,
+        &corpus_version,
+        &embedding,
+        config,
+    )
+    .await
+    .unwrap_or_else(|e| {
+        warn!("Failed to read the answer cache: {e}");
+        None
+    });
+    let lookup = AnswerCacheLookup {
+        config,
+        scope,
+        corpus_version,
+        embedding,
+    };
+    Some((lookup, cached))
+}
+
 /// Handler for performing a direct search on the knowledge graph.
+///
+/// Facts extracted from documents are only found when one of those documents is
+/// visible to the caller.
+#[utoipa::path(
+    post,
+    path = "/search/knowledge_graph",
+    tag = "graph",
+    params(OrgHeader),
+    request_body = KnowledgeGraphSearchRequest,
+    responses((status = 200, description = "The object of the fact, if one is known.", body = ApiResponse<KnowledgeGraphSearchResponse>))
+)]
 pub async fn knowledge_graph_search_handler(
     State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    headers: HeaderMap,
     Json(payload): Json<KnowledgeGraphSearchRequest>,
 ) -> Result<Json<super::ApiResponse<KnowledgeGraphSearchResponse>>, AppError> {
     info!(
@@ -403,13 +817,25 @@ pub async fn knowledge_graph_search_handler(
         payload.subject, payload.predicate
     );
 
+    let org_id = org_context(&app_state, &user.0, &headers).await?;
+    let db = app_state
+        .db_router
+        .for_user(&user.0.id, org_id.as_deref())
+        .await?;
+    let visibility = fact_visibility(&db, Some(&user.0.id), org_id.as_deref()).await?;
+
     let object = {
         let kg = app_state
             .knowledge_graph
             .read()
             .map_err(|_| AppError::Internal(anyhow::anyhow!("Failed to acquire KG read lock")))?;
-        kg.get_fact_as_of(&payload.subject, &payload.predicate, Utc::now())
-            .map_err(|e| AppError::Internal(anyhow::anyhow!("Knowledge graph query failed: {e}")))?
+        kg.get_fact_as_of(
+            &payload.subject,
+            &payload.predicate,
+            Utc::now(),
+            &visibility,
+        )
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Knowledge graph query failed: {e}")))?
     };
 
     let response = KnowledgeGraphSearchResponse { object };
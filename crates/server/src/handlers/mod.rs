@@ -7,8 +7,13 @@
 // Sub-modules for different handler categories.
 pub mod admin_handlers;
 pub mod auth_handlers;
+pub mod backup_handlers;
+pub mod chat_handlers;
+pub mod collection_handlers;
 pub mod db_handlers;
 pub mod document_handlers;
+pub mod experiment_handlers;
+pub mod feedback_handlers;
 pub mod general;
 pub mod generation_handlers;
 pub mod generation_types;
@@ -16,27 +21,38 @@ pub mod generation_types;
 pub mod graph_handlers;
 pub mod ingest;
 pub mod knowledge;
+pub mod org_handlers;
 pub mod search;
+pub mod usage_handlers;
+pub mod ws_handlers;
 
 // Re-export all handlers from the sub-modules to make them easily accessible
 // to the router under a single `handlers::` path.
 pub use admin_handlers::*;
 pub use auth_handlers::*;
+pub use backup_handlers::*;
+pub use chat_handlers::*;
+pub use collection_handlers::*;
 pub use db_handlers::*;
 pub use document_handlers::*;
+pub use experiment_handlers::*;
+pub use feedback_handlers::*;
 pub use general::*;
 pub use generation_handlers::*;
 #[cfg(feature = "graph_db")]
 pub use graph_handlers::*;
 pub use ingest::*;
 pub use knowledge::*;
+pub use org_handlers::*;
 pub use search::*;
+pub use usage_handlers::*;
+pub use ws_handlers::*;
 
 // Shared items used by multiple handler modules.
 use super::{
     errors::AppError,
     state::AppState,
-    types::{ApiResponse, DebugParams},
+    types::{ApiResponse, DebugParams, EmbedParams, OrgHeader},
 };
 use axum::{extract::Query, Json};
 use serde_json::Value;
@@ -0,0 +1,199 @@
+//! # Organization Route Handlers
+//!
+//! This module contains handlers for creating organizations and managing their
+//! members. Members can share documents with an organization and search them by
+//! sending the `X-Org-Id` header with ingest and search requests.
+
+use crate::{
+    auth::middleware::AuthenticatedUser,
+    errors::AppError,
+    handlers::{wrap_response, ApiResponse, DebugParams},
+    state::AppState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use core_access::{
+    organizations::{
+        add_member, create_organization, list_members, list_user_organizations, remove_member,
+        require_membership, require_owner,
+    },
+    OrgMember, OrgRole, Organization, GUEST_USER_IDENTIFIER,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::info;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateOrgRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AddOrgMemberRequest {
+    pub user_id: String,
+    /// Defaults to `member`. Adding an existing member changes their role.
+    #[serde(default)]
+    pub role: Option<OrgRole>,
+}
+
+/// Rejects the guest user, who is shared by every anonymous caller and so cannot
+/// belong to an organization.
+fn reject_guest(user_id: &str) -> Result<(), AppError> {
+    let guest_user_id =
+        Uuid::new_v5(&Uuid::NAMESPACE_URL, GUEST_USER_IDENTIFIER.as_bytes()).to_string();
+    if user_id == guest_user_id {
+        return Err(AppError::Forbidden(
+            "Forbidden: the guest user cannot belong to an organization.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Handler for creating an organization. The requesting user becomes its owner.
+#[utoipa::path(
+    post,
+    path = "/orgs",
+    tag = "organizations",
+    params(DebugParams),
+    request_body = CreateOrgRequest,
+    responses((status = 200, description = "The new organization.", body = ApiResponse<Organization>))
+)]
+pub async fn create_org_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Json(payload): Json<CreateOrgRequest>,
+) -> Result<Json<ApiResponse<Organization>>, AppError> {
+    let current_user = user.0;
+    reject_guest(&current_user.id)?;
+
+    info!(
+        "User '{}' creating organization '{}'.",
+        current_user.id, payload.name
+    );
+    let org = create_organization(
+        &app_state.sqlite_provider.db,
+        &payload.name,
+        &current_user.id,
+    )
+    .await?;
+
+    let debug_info = json!({ "requesting_user_id": current_user.id, "org_id": org.id });
+    Ok(wrap_response(org, debug_params, Some(debug_info)))
+}
+
+/// Handler for listing the organizations the requesting user is a member of.
+#[utoipa::path(
+    get,
+    path = "/orgs",
+    tag = "organizations",
+    params(DebugParams),
+    responses((status = 200, description = "The organizations of the current user.", body = ApiResponse<Vec<Organization>>))
+)]
+pub async fn list_orgs_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+) -> Result<Json<ApiResponse<Vec<Organization>>>, AppError> {
+    let current_user = user.0;
+    let orgs = list_user_organizations(&app_state.sqlite_provider.db, &current_user.id).await?;
+    let debug_info = json!({ "requesting_user_id": current_user.id, "org_count": orgs.len() });
+    Ok(wrap_response(orgs, debug_params, Some(debug_info)))
+}
+
+/// Handler for listing the members of an organization.
+///
+/// **Authorization**: Requires membership of the organization.
+#[utoipa::path(
+    get,
+    path = "/orgs/{org_id}/members",
+    tag = "organizations",
+    params(DebugParams, ("org_id" = String, Path, description = "The organization.")),
+    responses((status = 200, description = "The members of the organization.", body = ApiResponse<Vec<OrgMember>>))
+)]
+pub async fn list_org_members_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(org_id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<OrgMember>>>, AppError> {
+    let db = &app_state.sqlite_provider.db;
+    require_membership(db, &org_id, &user.0.id).await?;
+    let members = list_members(db, &org_id).await?;
+    Ok(wrap_response(members, debug_params, None))
+}
+
+/// Handler for adding a user to an organization, or changing their role.
+///
+/// **Authorization**: Requires ownership of the organization.
+#[utoipa::path(
+    post,
+    path = "/orgs/{org_id}/members",
+    tag = "organizations",
+    params(DebugParams, ("org_id" = String, Path, description = "The organization.")),
+    request_body = AddOrgMemberRequest,
+    responses((status = 200, description = "The membership of the user.", body = ApiResponse<OrgMember>))
+)]
+pub async fn add_org_member_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path(org_id): Path<String>,
+    Json(payload): Json<AddOrgMemberRequest>,
+) -> Result<Json<ApiResponse<OrgMember>>, AppError> {
+    let current_user = user.0;
+    let db = &app_state.sqlite_provider.db;
+    require_owner(db, &org_id, &current_user.id).await?;
+    reject_guest(&payload.user_id)?;
+
+    let role = payload.role.unwrap_or(OrgRole::Member);
+    info!(
+        "User '{}' adding user '{}' to organization '{}' as '{}'.",
+        current_user.id, payload.user_id, org_id, role
+    );
+    let member = add_member(db, &org_id, &payload.user_id, role).await?;
+    Ok(wrap_response(member, debug_params, None))
+}
+
+/// Handler for removing a user from an organization.
+///
+/// **Authorization**: Requires ownership of the organization, except for members
+/// removing themselves.
+#[utoipa::path(
+    delete,
+    path = "/orgs/{org_id}/members/{user_id}",
+    tag = "organizations",
+    params(
+        DebugParams,
+        ("org_id" = String, Path, description = "The organization."),
+        ("user_id" = String, Path, description = "The member to remove.")
+    ),
+    responses((status = 200, description = "The removed membership.", body = ApiResponse<Value>))
+)]
+pub async fn remove_org_member_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+    Path((org_id, user_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<Value>>, AppError> {
+    let current_user = user.0;
+    let db = &app_state.sqlite_provider.db;
+    if current_user.id != user_id {
+        require_owner(db, &org_id, &current_user.id).await?;
+    }
+
+    info!(
+        "User '{}' removing user '{}' from organization '{}'.",
+        current_user.id, user_id, org_id
+    );
+    remove_member(db, &org_id, &user_id).await?;
+    Ok(wrap_response(
+        json!({ "org_id": org_id, "user_id": user_id }),
+        debug_params,
+        None,
+    ))
+}
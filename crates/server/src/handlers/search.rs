@@ -3,29 +3,34 @@
 //! This module contains all the Axum handlers for search-related endpoints,
 //! including vector, keyword, and hybrid search.
 
-use super::{wrap_response, ApiResponse, AppError, AppState, DebugParams};
-use crate::auth::middleware::AuthenticatedUser;
+use super::{wrap_response, ApiResponse, AppError, AppState, DebugParams, OrgHeader};
+use crate::auth::{middleware::AuthenticatedUser, org::org_context};
 use anyrag::{
     providers::{
         ai::generate_embeddings_batch,
-        db::storage::{KeywordSearch, VectorSearch},
+        db::{
+            sqlite::SqliteProvider,
+            storage::{CollectionSearch, KeywordSearch, VectorSearch},
+        },
     },
     rerank::{llm_rerank, reciprocal_rank_fusion},
-    search::SearchMode,
+    search::{generate_hypothetical_document, SearchMode},
     SearchResult,
 };
 use axum::{
     extract::{Query, State},
+    http::HeaderMap,
     Json,
 };
 use serde::Deserialize;
 use serde_json::json;
 use std::collections::HashMap;
 use tracing::info;
+use utoipa::ToSchema;
 
 // --- API Payloads for Search ---
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct SearchRequest {
     pub db: Option<String>,
     pub query: String,
@@ -36,18 +41,59 @@ pub struct SearchRequest {
     pub mode: SearchMode,
     #[serde(default)]
     pub use_knowledge_graph: Option<bool>,
+    /// A collection, by id or name, that the search is limited to.
+    #[serde(default)]
+    pub collection: Option<String>,
+}
+
+/// Resolves the collection of a search request to the ids of its documents visible to
+/// the owner, or `None` when the request has no collection.
+pub(crate) async fn collection_filter(
+    db: &SqliteProvider,
+    collection: Option<&str>,
+    owner_id: Option<&str>,
+    org_id: Option<&str>,
+) -> Result<Option<Vec<String>>, AppError> {
+    match collection {
+        Some(collection) => Ok(Some(
+            db.collection_document_ids(collection, owner_id, org_id)
+                .await?,
+        )),
+        None => Ok(None),
+    }
 }
 
 // --- Search Handlers ---
 
 /// Handler for performing a vector similarity search.
+#[utoipa::path(
+    post,
+    path = "/search/vector",
+    tag = "search",
+    params(DebugParams, OrgHeader),
+    request_body = SearchRequest,
+    responses((status = 200, description = "The documents closest to the query embedding.", body = ApiResponse<Vec<SearchResult>>))
+)]
 pub async fn vector_search_handler(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
     debug_params: Query<DebugParams>,
+    headers: HeaderMap,
     Json(payload): Json<SearchRequest>,
 ) -> Result<Json<ApiResponse<Vec<SearchResult>>>, AppError> {
+    let org_id = org_context(&app_state, &user.0, &headers).await?;
+    let db = app_state
+        .db_router
+        .for_user(&user.0.id, org_id.as_deref())
+        .await?;
     let owner_id = Some(user.0.id);
+    let document_ids = collection_filter(
+        &db,
+        payload.collection.as_deref(),
+        owner_id.as_deref(),
+        org_id.as_deref(),
+    )
+    .await?;
     info!("Received vector search for query: '{}'", payload.query);
     let limit = payload.limit.unwrap_or(10);
 
@@ -64,44 +110,96 @@ pub async fn vector_search_handler(
                 "Embedding API returned no vector for the query"
             ))
         })?;
-    let results = app_state
-        .sqlite_provider
-        .vector_search(query_vector, limit, owner_id.as_deref(), None)
+    let results = db
+        .vector_search(
+            query_vector,
+            limit,
+            owner_id.as_deref(),
+            org_id.as_deref(),
+            document_ids.as_deref(),
+        )
         .await?;
 
     info!("Vector search found {} results.", results.len());
 
-    let debug_info = json!({ "query": payload.query, "limit": limit, "owner_id": owner_id });
+    let debug_info = json!({ "query": payload.query, "limit": limit, "owner_id": owner_id, "org_id": org_id, "collection": payload.collection });
     Ok(wrap_response(results, debug_params, Some(debug_info)))
 }
 
 /// Handler for performing a keyword search.
+#[utoipa::path(
+    post,
+    path = "/search/keyword",
+    tag = "search",
+    params(DebugParams, OrgHeader),
+    request_body = SearchRequest,
+    responses((status = 200, description = "The documents matching the query keywords.", body = ApiResponse<Vec<SearchResult>>))
+)]
 pub async fn keyword_search_handler(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
     debug_params: Query<DebugParams>,
+    headers: HeaderMap,
     Json(payload): Json<SearchRequest>,
 ) -> Result<Json<ApiResponse<Vec<SearchResult>>>, AppError> {
+    let org_id = org_context(&app_state, &user.0, &headers).await?;
+    let db = app_state
+        .db_router
+        .for_user(&user.0.id, org_id.as_deref())
+        .await?;
     let owner_id = Some(user.0.id);
+    let document_ids = collection_filter(
+        &db,
+        payload.collection.as_deref(),
+        owner_id.as_deref(),
+        org_id.as_deref(),
+    )
+    .await?;
     info!("Received keyword search for query: '{}'", payload.query);
     let limit = payload.limit.unwrap_or(10);
-    let results = app_state
-        .sqlite_provider
-        .keyword_search(&payload.query, limit * 2, owner_id.as_deref(), None)
+    let results = db
+        .keyword_search(
+            &payload.query,
+            limit * 2,
+            owner_id.as_deref(),
+            org_id.as_deref(),
+            document_ids.as_deref(),
+        )
         .await?;
     info!("Keyword search found {} results.", results.len());
-    let debug_info = json!({ "query": payload.query, "limit": limit, "owner_id": owner_id });
+    let debug_info = json!({ "query": payload.query, "limit": limit, "owner_id": owner_id, "org_id": org_id, "collection": payload.collection });
     Ok(wrap_response(results, debug_params, Some(debug_info)))
 }
 
 /// Handler for performing a hybrid search (vector + keyword) with re-ranking.
+#[utoipa::path(
+    post,
+    path = "/search/hybrid",
+    tag = "search",
+    params(DebugParams, OrgHeader),
+    request_body = SearchRequest,
+    responses((status = 200, description = "The re-ranked documents of the vector and keyword searches.", body = ApiResponse<Vec<SearchResult>>))
+)]
 pub async fn hybrid_search_handler(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
     debug_params: Query<DebugParams>,
+    headers: HeaderMap,
     Json(payload): Json<SearchRequest>,
 ) -> Result<Json<ApiResponse<Vec<SearchResult>>>, AppError> {
+    let org_id = org_context(&app_state, &user.0, &headers).await?;
+    let db = app_state
+        .db_router
+        .for_user(&user.0.id, org_id.as_deref())
+        .await?;
     let owner_id = Some(user.0.id);
+    let document_ids = collection_filter(
+        &db,
+        payload.collection.as_deref(),
+        owner_id.as_deref(),
+        org_id.as_deref(),
+    )
+    .await?;
     info!(
         "Received hybrid search for query: '{}' with mode {:?}",
         payload.query, payload.mode
@@ -112,7 +210,30 @@ pub async fn hybrid_search_handler(
     let model = &app_state.config.embedding.model_name;
     let api_key = app_state.config.embedding.api_key.as_deref();
 
-    let query_vector = generate_embeddings_batch(api_url, model, &[&payload.query], api_key)
+    // In HyDE mode, embed an LLM-written hypothetical answer instead of the raw query.
+    let text_to_embed = match payload.mode {
+        SearchMode::Hyde => {
+            let task_name = "hyde_generation";
+            let task_config = app_state.tasks.get(task_name).ok_or_else(|| {
+                AppError::Internal(anyhow::anyhow!("Task '{task_name}' not found in config"))
+            })?;
+            let provider_name = &task_config.provider;
+            let hyde_provider = app_state.ai_providers.get(provider_name).ok_or_else(|| {
+                AppError::Internal(anyhow::anyhow!("Provider '{provider_name}' not found"))
+            })?;
+
+            generate_hypothetical_document(
+                hyde_provider.as_ref(),
+                &payload.query,
+                &task_config.system_prompt,
+                &task_config.user_prompt,
+            )
+            .await?
+        }
+        SearchMode::LlmReRank | SearchMode::Rrf => payload.query.clone(),
+    };
+
+    let query_vector = generate_embeddings_batch(api_url, model, &[&text_to_embed], api_key)
         .await?
         .into_iter()
         .next()
@@ -124,17 +245,19 @@ pub async fn hybrid_search_handler(
 
     // --- Stage 1: Fetch Candidates Concurrently ---
     let (vector_results, keyword_results) = tokio::join!(
-        app_state.sqlite_provider.vector_search(
+        db.vector_search(
             query_vector.clone(),
             limit * 2,
             owner_id.as_deref(),
-            None
+            org_id.as_deref(),
+            document_ids.as_deref()
         ),
-        app_state.sqlite_provider.keyword_search(
+        db.keyword_search(
             &payload.query,
             limit * 2,
             owner_id.as_deref(),
-            None
+            org_id.as_deref(),
+            document_ids.as_deref()
         )
     );
 
@@ -184,7 +307,9 @@ pub async fn hybrid_search_handler(
                 .map_err(|e| AppError::Internal(anyhow::anyhow!("LLM Reranking failed: {e}")))?
             }
         }
-        SearchMode::Rrf => reciprocal_rank_fusion(vec![vector_results, keyword_results]),
+        SearchMode::Rrf | SearchMode::Hyde => {
+            reciprocal_rank_fusion(vec![vector_results, keyword_results])
+        }
     };
 
     ranked_results.truncate(limit as usize);
@@ -194,7 +319,7 @@ pub async fn hybrid_search_handler(
         ranked_results.len()
     );
 
-    let debug_info = json!({ "query": payload.query, "limit": limit, "mode": payload.mode, "owner_id": owner_id });
+    let debug_info = json!({ "query": payload.query, "embedded_text": text_to_embed, "limit": limit, "mode": payload.mode, "owner_id": owner_id, "org_id": org_id, "collection": payload.collection });
     Ok(wrap_response(
         ranked_results,
         debug_params,
@@ -0,0 +1,69 @@
+//! # Usage Route Handlers
+//!
+//! This module contains the handler reporting what the current user consumed this
+//! month, against the quotas and rate limit the server enforces.
+
+use crate::{
+    auth::middleware::AuthenticatedUser,
+    errors::AppError,
+    handlers::{wrap_response, ApiResponse, DebugParams},
+    state::AppState,
+};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use core_access::{usage::get_usage, Usage};
+use serde::Serialize;
+use serde_json::json;
+use utoipa::ToSchema;
+
+/// The limits that apply to the current user. Unset limits are not enforced.
+#[derive(Serialize, ToSchema)]
+pub struct UsageLimits {
+    pub ai_calls_per_month: Option<u64>,
+    pub documents_per_month: Option<u64>,
+    pub requests_per_minute: Option<u32>,
+    pub burst: Option<u32>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UsageResponse {
+    pub usage: Usage,
+    pub limits: UsageLimits,
+}
+
+/// Handler for reporting the current user's usage this month and their limits.
+#[utoipa::path(
+    get,
+    path = "/me/usage",
+    tag = "auth",
+    params(DebugParams),
+    responses((status = 200, description = "The usage and limits of the current user.", body = ApiResponse<UsageResponse>))
+)]
+pub async fn get_usage_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    debug_params: Query<DebugParams>,
+) -> Result<Json<ApiResponse<UsageResponse>>, AppError> {
+    let current_user = user.0;
+    let usage = get_usage(&app_state.sqlite_provider.db, &current_user.id)
+        .await
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to load usage: {e}")))?;
+
+    let quotas = app_state.config.quotas.clone().unwrap_or_default();
+    let rate_limiter = app_state.rate_limiter.as_deref();
+    let limits = UsageLimits {
+        ai_calls_per_month: quotas.ai_calls_per_month,
+        documents_per_month: quotas.documents_per_month,
+        requests_per_minute: rate_limiter.map(|limiter| limiter.requests_per_minute()),
+        burst: rate_limiter.map(|limiter| limiter.burst()),
+    };
+
+    let debug_info = json!({ "requesting_user_id": current_user.id });
+    Ok(wrap_response(
+        UsageResponse { usage, limits },
+        debug_params,
+        Some(debug_info),
+    ))
+}
@@ -0,0 +1,396 @@
+//! # WebSocket Route Handlers
+//!
+//! This module contains the Axum handler for the `/ws` endpoint, which runs
+//! interactive RAG sessions over a WebSocket. Each connection is one conversation:
+//! the client sends queries, and the server streams the progress of the pipeline,
+//! the citations it retrieved, and the answer tokens as they are generated. A turn
+//! can be cancelled mid-generation, in which case it is not added to the history.
+//!
+//! Messages are JSON objects tagged by `type`; see `ClientMessage` and `ServerMessage`.
+
+use super::chat_handlers::{
+    task_with_provider, CHAT_HISTORY_LIMIT, CHAT_QUERY_REWRITE_TASK, QUERY_ANALYSIS_TASK,
+    RAG_SYNTHESIS_TASK,
+};
+use super::AppState;
+use crate::{auth::middleware::AuthenticatedUser, moderation::moderate_answer};
+use anyrag::{
+    context_budget::{fit_context, ContextBudget},
+    context_sanitization::{document_context, guard_system_prompt},
+    moderation::{ModerationAction, ModerationReport},
+    providers::db::sqlite::SqliteProvider,
+    search::{hybrid_search, HybridSearchOptions, HybridSearchPrompts},
+    types::{ContentType, ExecutePromptOptions, PromptClientBuilder},
+    ChatClient, ChatMessage, ChatRole,
+};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::{IntoResponse, Response},
+};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::{sync::mpsc, task::JoinHandle};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// The number of documents retrieved for a query when the client sends no `limit`.
+const DEFAULT_RETRIEVAL_LIMIT: u32 = 5;
+
+// --- WebSocket Protocol ---
+
+#[derive(Deserialize)]
+pub struct WsParams {
+    /// The chat session to continue. A new session is started when omitted.
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+/// A message sent by the client.
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Starts a turn. Only one turn runs at a time.
+    Query {
+        message: String,
+        #[serde(default)]
+        limit: Option<u32>,
+        /// A collection, by id or name, that the retrieval is limited to.
+        #[serde(default)]
+        collection: Option<String>,
+    },
+    /// Stops the running turn.
+    Cancel,
+}
+
+/// A message sent by the server.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Sent once when the connection opens.
+    Session { session_id: String },
+    /// A turn entered a new stage of the pipeline.
+    Progress { stage: TurnStage },
+    /// The documents the answer is grounded in.
+    Citations { citations: Vec<Citation> },
+    /// The next piece of the answer. Not sent when answers are moderated with the
+    /// `block` action, since a token cannot be withdrawn once sent.
+    Token { text: String },
+    /// The turn completed and was added to the conversation.
+    Done {
+        text: String,
+        /// The query rewritten as a standalone query for retrieval.
+        standalone_query: String,
+        /// The moderation verdict, when the answer was flagged or blocked.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        moderation: Option<ModerationReport>,
+    },
+    /// The running turn was stopped at the client's request.
+    Cancelled,
+    /// A message was rejected or the turn failed.
+    Error { message: String },
+}
+
+/// The stages of a turn, reported in this order.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnStage {
+    RewritingQuery,
+    Retrieving,
+    Generating,
+}
+
+/// A retrieved document.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Citation {
+    pub title: String,
+    pub link: String,
+    pub score: f64,
+}
+
+/// The fixed parts of the turns of one connection.
+#[derive(Clone)]
+struct Session {
+    app_state: AppState,
+    /// The database of the owner's documents and conversations.
+    db: Arc<SqliteProvider>,
+    owner_id: String,
+    session_id: String,
+    events: mpsc::UnboundedSender<ServerMessage>,
+}
+
+impl Session {
+    /// Queues a message for the client. A closed connection is noticed by the
+    /// receive loop, so a failed send is ignored here.
+    fn send(&self, message: ServerMessage) {
+        let _ = self.events.send(message);
+    }
+}
+
+// --- WebSocket Handlers ---
+
+/// Handler for upgrading a request to an interactive RAG session.
+pub async fn ws_handler(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(params): Query<WsParams>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let db = match app_state.db_router.for_user(&user.0.id, None).await {
+        Ok(db) => db,
+        Err(e) => return e.into_response(),
+    };
+    let owner_id = user.0.id;
+    let session_id = params
+        .session_id
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    info!("User '{owner_id}' opening WebSocket session '{session_id}'.");
+    ws.on_upgrade(move |socket| run_session(socket, app_state, db, owner_id, session_id))
+}
+
+/// Serves one connection until the client closes it.
+async fn run_session(
+    socket: WebSocket,
+    app_state: AppState,
+    db: Arc<SqliteProvider>,
+    owner_id: String,
+    session_id: String,
+) {
+    let (mut sink, mut stream) = socket.split();
+
+    // All outgoing messages go through one channel, so the running turn can report
+    // while this task keeps reading the client's messages.
+    let (events, mut outgoing) = mpsc::unbounded_channel::<ServerMessage>();
+    let writer = tokio::spawn(async move {
+        while let Some(message) = outgoing.recv().await {
+            let Ok(text) = serde_json::to_string(&message) else {
+                continue;
+            };
+            if sink.send(Message::Text(text.into())).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let session = Session {
+        app_state,
+        db,
+        owner_id,
+        session_id,
+        events,
+    };
+    session.send(ServerMessage::Session {
+        session_id: session.session_id.clone(),
+    });
+
+    let mut turn: Option<JoinHandle<()>> = None;
+    while let Some(Ok(message)) = stream.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            // Pings are answered by axum; other frames carry no queries.
+            _ => continue,
+        };
+        let client_message: ClientMessage = match serde_json::from_str(text.as_str()) {
+            Ok(client_message) => client_message,
+            Err(e) => {
+                session.send(ServerMessage::Error {
+                    message: format!("Invalid message: {e}"),
+                });
+                continue;
+            }
+        };
+
+        let running = turn.as_ref().is_some_and(|handle| !handle.is_finished());
+        match client_message {
+            ClientMessage::Query { .. } if running => session.send(ServerMessage::Error {
+                message: "A query is already running. Cancel it before sending another."
+                    .to_string(),
+            }),
+            ClientMessage::Query {
+                message,
+                limit,
+                collection,
+            } => {
+                let session = session.clone();
+                turn = Some(tokio::spawn(async move {
+                    if let Err(e) = run_turn(&session, message, limit, collection).await {
+                        warn!("WebSocket turn failed: {e:#}");
+                        session.send(ServerMessage::Error {
+                            message: e.to_string(),
+                        });
+                    }
+                }));
+            }
+            ClientMessage::Cancel if running => {
+                let Some(handle) = turn.take() else {
+                    continue;
+                };
+                // Waiting for the aborted task guarantees that no token of the
+                // cancelled turn follows the confirmation.
+                handle.abort();
+                let _ = handle.await;
+                info!("Cancelled a turn of session '{}'.", session.session_id);
+                session.send(ServerMessage::Cancelled);
+            }
+            ClientMessage::Cancel => session.send(ServerMessage::Error {
+                message: "No query is running.".to_string(),
+            }),
+        }
+    }
+
+    if let Some(handle) = turn {
+        handle.abort();
+    }
+    info!("WebSocket session '{}' closed.", session.session_id);
+    drop(session);
+    let _ = writer.await;
+}
+
+/// Answers one query of the conversation, streaming its progress to the client.
+async fn run_turn(
+    session: &Session,
+    message: String,
+    limit: Option<u32>,
+    collection: Option<String>,
+) -> anyhow::Result<()> {
+    let app_state = &session.app_state;
+    let owner_id = Some(session.owner_id.as_str());
+
+    // --- Resolve the follow-up against the conversation history ---
+    session.send(ServerMessage::Progress {
+        stage: TurnStage::RewritingQuery,
+    });
+    let (rewrite_task, rewrite_provider) = task_with_provider(app_state, CHAT_QUERY_REWRITE_TASK)?;
+    let chat_client = ChatClient::new(rewrite_provider, session.db.clone());
+    let history = chat_client
+        .load_history(&session.session_id, owner_id, CHAT_HISTORY_LIMIT)
+        .await?;
+    let standalone_query = chat_client
+        .rewrite_follow_up(
+            &history,
+            &message,
+            &rewrite_task.system_prompt,
+            &rewrite_task.user_prompt,
+        )
+        .await?;
+
+    // --- Retrieve context for the standalone query ---
+    session.send(ServerMessage::Progress {
+        stage: TurnStage::Retrieving,
+    });
+    let (analysis_task, analysis_provider) = task_with_provider(app_state, QUERY_ANALYSIS_TASK)?;
+    let search_options = HybridSearchOptions {
+        query_text: standalone_query.clone(),
+        owner_id: Some(session.owner_id.clone()),
+        org_id: None,
+        collection,
+        limit: limit.unwrap_or(DEFAULT_RETRIEVAL_LIMIT),
+        prompts: HybridSearchPrompts {
+            analysis_system_prompt: &analysis_task.system_prompt,
+            analysis_user_prompt_template: &analysis_task.user_prompt,
+        },
+        use_keyword_search: true,
+        use_vector_search: true,
+        embedding_api_url: &app_state.config.embedding.api_url,
+        embedding_model: &app_state.config.embedding.model_name,
+        embedding_api_key: app_state.config.embedding.api_key.as_deref(),
+        temporal_ranking_config: None,
+    };
+    let search_results = hybrid_search(
+        session.db.clone(),
+        Arc::from(analysis_provider),
+        search_options,
+    )
+    .await?;
+    let (synthesis_task, synthesis_provider) = task_with_provider(app_state, RAG_SYNTHESIS_TASK)?;
+    let search_results = fit_context(
+        ContextBudget::for_provider(&app_state.config, &synthesis_task.provider),
+        search_results,
+    )
+    .results;
+    session.send(ServerMessage::Citations {
+        citations: search_results
+            .iter()
+            .map(|result| Citation {
+                title: result.title.clone(),
+                link: result.link.clone(),
+                score: result.score,
+            })
+            .collect(),
+    });
+    let sanitizer = app_state.context_sanitizer.as_deref();
+    let context = document_context(&search_results, sanitizer).context;
+
+    // --- Stream the answer with the conversation history ---
+    session.send(ServerMessage::Progress {
+        stage: TurnStage::Generating,
+    });
+    let options = ExecutePromptOptions {
+        prompt: standalone_query.clone(),
+        content_type: Some(ContentType::Knowledge),
+        context: Some(context),
+        history: Some(history),
+        system_prompt_template: Some(match sanitizer {
+            Some(_) => guard_system_prompt(&synthesis_task.system_prompt),
+            None => synthesis_task.system_prompt.clone(),
+        }),
+        user_prompt_template: Some(synthesis_task.user_prompt.clone()),
+        ..Default::default()
+    };
+    let client = PromptClientBuilder::new()
+        .ai_provider(synthesis_provider.clone())
+        .storage_provider(Box::new(session.db.as_ref().clone()))
+        .build()?;
+    let (system_prompt, user_prompt) = client.build_prompts(&options).await?;
+
+    let (tokens, mut generated) = mpsc::unbounded_channel::<String>();
+    let generation = synthesis_provider.generate_stream(&system_prompt, &user_prompt, tokens);
+    let withhold_tokens = app_state.moderator.is_some()
+        && app_state
+            .config
+            .moderation
+            .as_ref()
+            .is_some_and(|config| config.action == ModerationAction::Block);
+    let forwarding = async {
+        while let Some(text) = generated.recv().await {
+            if !withhold_tokens {
+                session.send(ServerMessage::Token { text });
+            }
+        }
+    };
+    let (text, ()) = tokio::join!(generation, forwarding);
+    let (text, moderation) = moderate_answer(app_state, text?).await;
+
+    // --- Persist the completed turn ---
+    chat_client
+        .append_message(
+            &session.session_id,
+            owner_id,
+            &ChatMessage {
+                role: ChatRole::User,
+                content: message,
+            },
+        )
+        .await?;
+    chat_client
+        .append_message(
+            &session.session_id,
+            owner_id,
+            &ChatMessage {
+                role: ChatRole::Assistant,
+                content: text.clone(),
+            },
+        )
+        .await?;
+
+    session.send(ServerMessage::Done {
+        text,
+        standalone_query,
+        moderation: moderation.filter(ModerationReport::is_reported),
+    });
+    Ok(())
+}
@@ -0,0 +1,445 @@
+//! # Ingestor Registry
+//!
+//! This module maps the `source_type` of a `POST /ingest` request to the plugin that
+//! ingests it. The per-source routes, such as `/ingest/web`, ingest through the same
+//! plugins. Every plugin builds its `Ingestor` from the application state for each
+//! request, so the registry itself is built once at startup by `build_app_state`.
+//! Supporting a new source means registering its plugin here; no route is added.
+
+use crate::state::AppState;
+use anyrag::ingest::Ingestor;
+use serde_json::Value;
+use std::{collections::HashMap, sync::Arc};
+use turso::Database;
+
+pub const SOURCE_TEXT: &str = "text";
+pub const SOURCE_PDF: &str = "pdf";
+pub const SOURCE_WEB: &str = "web";
+pub const SOURCE_RSS: &str = "rss";
+pub const SOURCE_SHEETS: &str = "sheets";
+pub const SOURCE_GITHUB: &str = "github";
+pub const SOURCE_GITHUB_ISSUES: &str = "github_issues";
+pub const SOURCE_SLACK: &str = "slack";
+pub const SOURCE_DISCORD: &str = "discord";
+pub const SOURCE_JIRA: &str = "jira";
+pub const SOURCE_OBJECTSTORE: &str = "objectstore";
+pub const SOURCE_NOTION: &str = "notion";
+
+/// A source of content the ingestion endpoints can dispatch to.
+pub trait IngestorPlugin: Send + Sync {
+    /// Builds the ingestor for one request from the shared resources and the
+    /// configuration of the server. It stores documents in `db`, the database the
+    /// request is routed to. `source` is the prepared source of the request, for the
+    /// plugins whose client depends on it.
+    fn build<'a>(
+        &self,
+        app_state: &'a AppState,
+        db: &'a Database,
+        source: &Value,
+    ) -> anyhow::Result<Box<dyn Ingestor + 'a>>;
+
+    /// Completes the `source` sent by the client with settings only the server
+    /// decides, before it is passed to the ingestor. `remaining_documents` is how many
+    /// more documents the user may ingest this month, when a quota limits it. Returns
+    /// the source unchanged by default.
+    fn prepare_source(
+        &self,
+        _app_state: &AppState,
+        source: Value,
+        _remaining_documents: Option<u64>,
+    ) -> anyhow::Result<Value> {
+        Ok(source)
+    }
+}
+
+/// The ingestion plugins available to `POST /ingest`, keyed by source type.
+#[derive(Default, Clone)]
+pub struct IngestorRegistry {
+    plugins: HashMap<String, Arc<dyn IngestorPlugin>>,
+}
+
+impl IngestorRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry holding the plugins of every enabled feature.
+    pub fn with_enabled_plugins() -> Self {
+        #[allow(unused_mut)]
+        let mut registry = Self::new();
+        #[cfg(feature = "text")]
+        registry.register(SOURCE_TEXT, TextPlugin);
+        #[cfg(feature = "pdf")]
+        registry.register(SOURCE_PDF, PdfPlugin);
+        #[cfg(feature = "web")]
+        registry.register(SOURCE_WEB, WebPlugin);
+        #[cfg(feature = "rss")]
+        registry.register(SOURCE_RSS, RssPlugin);
+        #[cfg(feature = "sheets")]
+        registry.register(SOURCE_SHEETS, SheetsPlugin);
+        #[cfg(feature = "github")]
+        {
+            registry.register(SOURCE_GITHUB, GitHubPlugin);
+            registry.register(SOURCE_GITHUB_ISSUES, GitHubIssuesPlugin);
+        }
+        #[cfg(feature = "slack")]
+        registry.register(SOURCE_SLACK, SlackPlugin);
+        #[cfg(feature = "discord")]
+        registry.register(SOURCE_DISCORD, DiscordPlugin);
+        #[cfg(feature = "jira")]
+        registry.register(SOURCE_JIRA, JiraPlugin);
+        #[cfg(feature = "objectstore")]
+        registry.register(SOURCE_OBJECTSTORE, ObjectStorePlugin);
+        #[cfg(feature = "notion")]
+        registry.register(SOURCE_NOTION, NotionPlugin);
+        registry
+    }
+
+    /// Registers a plugin, replacing any plugin registered for the same source type.
+    pub fn register(&mut self, source_type: &str, plugin: impl IngestorPlugin + 'static) {
+        self.plugins
+            .insert(source_type.to_string(), Arc::new(plugin));
+    }
+
+    /// Returns the plugin registered for a source type.
+    pub fn get(&self, source_type: &str) -> Option<Arc<dyn IngestorPlugin>> {
+        self.plugins.get(source_type).cloned()
+    }
+
+    /// Returns the registered source types in alphabetical order.
+    pub fn source_types(&self) -> Vec<String> {
+        let mut source_types: Vec<String> = self.plugins.keys().cloned().collect();
+        source_types.sort();
+        source_types
+    }
+}
+
+/// Returns the AI provider and prompts of the knowledge distillation tasks, which the
+/// plugins that restructure content with an LLM share.
+#[cfg(any(
+    feature = "pdf",
+    feature = "web",
+    feature = "sheets",
+    feature = "objectstore",
+    feature = "notion"
+))]
+fn knowledge_ingestion(
+    app_state: &AppState,
+) -> anyhow::Result<(
+    &dyn anyrag::providers::ai::AiProvider,
+    anyrag::ingest::IngestionPrompts<'_>,
+)> {
+    let task_name = "knowledge_distillation";
+    let task_config = app_state
+        .tasks
+        .get(task_name)
+        .ok_or_else(|| anyhow::anyhow!("Task '{task_name}' not found in config"))?;
+    let meta_task_name = "knowledge_metadata_extraction";
+    let meta_task_config = app_state
+        .tasks
+        .get(meta_task_name)
+        .ok_or_else(|| anyhow::anyhow!("Task '{meta_task_name}' not found in config"))?;
+    let provider_name = &task_config.provider;
+    let ai_provider = app_state
+        .ai_providers
+        .get(provider_name)
+        .ok_or_else(|| anyhow::anyhow!("Provider '{provider_name}' not found"))?;
+
+    let prompts = anyrag::ingest::IngestionPrompts {
+        restructuring_system_prompt: &task_config.system_prompt,
+        metadata_extraction_system_prompt: &meta_task_config.system_prompt,
+    };
+    Ok((ai_provider.as_ref(), prompts))
+}
+
+// --- Plugins ---
+
+#[cfg(feature = "text")]
+struct TextPlugin;
+
+#[cfg(feature = "text")]
+impl IngestorPlugin for TextPlugin {
+    fn build<'a>(
+        &self,
+        _app_state: &'a AppState,
+        db: &'a Database,
+        _source: &Value,
+    ) -> anyhow::Result<Box<dyn Ingestor + 'a>> {
+        Ok(Box::new(anyrag_text::TextIngestor::new(db)))
+    }
+}
+
+#[cfg(feature = "pdf")]
+struct PdfPlugin;
+
+#[cfg(feature = "pdf")]
+impl IngestorPlugin for PdfPlugin {
+    fn build<'a>(
+        &self,
+        app_state: &'a AppState,
+        db: &'a Database,
+        _source: &Value,
+    ) -> anyhow::Result<Box<dyn Ingestor + 'a>> {
+        let (ai_provider, prompts) = knowledge_ingestion(app_state)?;
+        Ok(Box::new(
+            anyrag_pdf::PdfIngestor::new(db, ai_provider, prompts)
+                .with_concurrency(app_state.config.ingest_concurrency),
+        ))
+    }
+}
+
+#[cfg(feature = "web")]
+struct WebPlugin;
+
+#[cfg(feature = "web")]
+impl IngestorPlugin for WebPlugin {
+    fn build<'a>(
+        &self,
+        app_state: &'a AppState,
+        db: &'a Database,
+        _source: &Value,
+    ) -> anyhow::Result<Box<dyn Ingestor + 'a>> {
+        let (ai_provider, prompts) = knowledge_ingestion(app_state)?;
+        let mut ingestor = anyrag_web::WebIngestor::new(db, ai_provider, prompts)
+            .with_concurrency(app_state.config.ingest_concurrency);
+        if let Some((captioning_provider, captioning_prompt)) =
+            crate::handlers::ingest::web::image_captioning(app_state)
+        {
+            ingestor = ingestor.with_image_captioning(captioning_provider, captioning_prompt);
+        }
+        Ok(Box::new(ingestor))
+    }
+
+    /// The fetch strategy is a server setting; the one a client sends is replaced. The
+    /// pages of a crawl or a sitemap are limited by the server and by the user's
+    /// document quota.
+    fn prepare_source(
+        &self,
+        app_state: &AppState,
+        mut source: Value,
+        remaining_documents: Option<u64>,
+    ) -> anyhow::Result<Value> {
+        let strategy = crate::handlers::ingest::web::web_ingest_strategy(&app_state.config)?;
+        let object = source
+            .as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("The web source must be a JSON object"))?;
+        object.insert("strategy".to_string(), serde_json::to_value(strategy)?);
+        if let Some(crawl) = object.get("crawl").filter(|crawl| !crawl.is_null()) {
+            let crawl = crate::handlers::ingest::web::limit_crawl(
+                &app_state.config,
+                crawl,
+                remaining_documents,
+            )?;
+            object.insert("crawl".to_string(), crawl);
+        }
+        if object.get("sitemap_url").is_some_and(|url| !url.is_null()) {
+            let sitemap = object.get("sitemap").cloned().unwrap_or(Value::Null);
+            let sitemap = crate::handlers::ingest::web::limit_sitemap(
+                &app_state.config,
+                &sitemap,
+                remaining_documents,
+            )?;
+            object.insert("sitemap".to_string(), sitemap);
+        }
+        Ok(source)
+    }
+}
+
+#[cfg(feature = "rss")]
+struct RssPlugin;
+
+#[cfg(feature = "rss")]
+impl IngestorPlugin for RssPlugin {
+    fn build<'a>(
+        &self,
+        app_state: &'a AppState,
+        db: &'a Database,
+        _source: &Value,
+    ) -> anyhow::Result<Box<dyn Ingestor + 'a>> {
+        Ok(Box::new(crate::handlers::ingest::rss::rss_ingestor(
+            app_state, db,
+        )))
+    }
+}
+
+#[cfg(feature = "sheets")]
+struct SheetsPlugin;
+
+#[cfg(feature = "sheets")]
+impl IngestorPlugin for SheetsPlugin {
+    fn build<'a>(
+        &self,
+        app_state: &'a AppState,
+        db: &'a Database,
+        _source: &Value,
+    ) -> anyhow::Result<Box<dyn Ingestor + 'a>> {
+        let (ai_provider, prompts) = knowledge_ingestion(app_state)?;
+        Ok(Box::new(
+            anyrag_sheets::SheetsIngestor::new(db, ai_provider, prompts)
+                .with_concurrency(app_state.config.ingest_concurrency),
+        ))
+    }
+}
+
+#[cfg(feature = "github")]
+struct GitHubPlugin;
+
+#[cfg(feature = "github")]
+impl IngestorPlugin for GitHubPlugin {
+    fn build<'a>(
+        &self,
+        app_state: &'a AppState,
+        _db: &'a Database,
+        _source: &Value,
+    ) -> anyhow::Result<Box<dyn Ingestor + 'a>> {
+        let config = &app_state.config;
+        Ok(Box::new(
+            anyrag_github::GithubIngestor::new(
+                app_state.storage_manager.clone(),
+                Some(config.embedding.api_url.clone()),
+                Some(config.embedding.model_name.clone()),
+                config.embedding.api_key.clone(),
+            )
+            .with_auth_token(config.github_token.clone()),
+        ))
+    }
+}
+
+#[cfg(feature = "github")]
+struct GitHubIssuesPlugin;
+
+#[cfg(feature = "github")]
+impl IngestorPlugin for GitHubIssuesPlugin {
+    fn build<'a>(
+        &self,
+        app_state: &'a AppState,
+        db: &'a Database,
+        source: &Value,
+    ) -> anyhow::Result<Box<dyn Ingestor + 'a>> {
+        // A token sent with the source takes precedence over the configured one.
+        let token = source["auth_token"]
+            .as_str()
+            .or(app_state.config.github_token.as_deref())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "An auth_token or GITHUB_TOKEN is required to read issues and discussions."
+                )
+            })?;
+        Ok(Box::new(
+            anyrag_github::issues::ingestor::GitHubIssuesIngestor::new(
+                db,
+                anyrag_github::issues::client::GitHubGraphQlClient::new(token),
+            ),
+        ))
+    }
+}
+
+#[cfg(feature = "slack")]
+struct SlackPlugin;
+
+#[cfg(feature = "slack")]
+impl IngestorPlugin for SlackPlugin {
+    fn build<'a>(
+        &self,
+        app_state: &'a AppState,
+        db: &'a Database,
+        _source: &Value,
+    ) -> anyhow::Result<Box<dyn Ingestor + 'a>> {
+        let token = app_state
+            .config
+            .slack_bot_token
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("SLACK_BOT_TOKEN is not set."))?;
+        Ok(Box::new(anyrag_slack::SlackIngestor::new(db, token)))
+    }
+}
+
+#[cfg(feature = "discord")]
+struct DiscordPlugin;
+
+#[cfg(feature = "discord")]
+impl IngestorPlugin for DiscordPlugin {
+    fn build<'a>(
+        &self,
+        app_state: &'a AppState,
+        db: &'a Database,
+        _source: &Value,
+    ) -> anyhow::Result<Box<dyn Ingestor + 'a>> {
+        let token = app_state
+            .config
+            .discord_bot_token
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("DISCORD_BOT_TOKEN is not set."))?;
+        Ok(Box::new(anyrag_discord::DiscordIngestor::new(db, token)))
+    }
+}
+
+#[cfg(feature = "jira")]
+struct JiraPlugin;
+
+#[cfg(feature = "jira")]
+impl IngestorPlugin for JiraPlugin {
+    fn build<'a>(
+        &self,
+        app_state: &'a AppState,
+        db: &'a Database,
+        _source: &Value,
+    ) -> anyhow::Result<Box<dyn Ingestor + 'a>> {
+        let config = &app_state.config;
+        let (Some(base_url), Some(email), Some(api_token)) = (
+            config.jira_base_url.as_deref(),
+            config.jira_email.as_deref(),
+            config.jira_api_token.as_deref(),
+        ) else {
+            return Err(anyhow::anyhow!(
+                "JIRA_BASE_URL, JIRA_EMAIL, and JIRA_API_TOKEN must be set."
+            ));
+        };
+        Ok(Box::new(anyrag_jira::JiraIngestor::new(
+            db, base_url, email, api_token,
+        )))
+    }
+}
+
+#[cfg(feature = "objectstore")]
+struct ObjectStorePlugin;
+
+#[cfg(feature = "objectstore")]
+impl IngestorPlugin for ObjectStorePlugin {
+    fn build<'a>(
+        &self,
+        app_state: &'a AppState,
+        db: &'a Database,
+        _source: &Value,
+    ) -> anyhow::Result<Box<dyn Ingestor + 'a>> {
+        let (ai_provider, prompts) = knowledge_ingestion(app_state)?;
+        Ok(Box::new(anyrag_objectstore::ObjectStoreIngestor::new(
+            db,
+            ai_provider,
+            prompts,
+            crate::handlers::ingest::objectstore::object_store_client(&app_state.config),
+        )))
+    }
+}
+
+#[cfg(feature = "notion")]
+struct NotionPlugin;
+
+#[cfg(feature = "notion")]
+impl IngestorPlugin for NotionPlugin {
+    fn build<'a>(
+        &self,
+        app_state: &'a AppState,
+        db: &'a Database,
+        _source: &Value,
+    ) -> anyhow::Result<Box<dyn Ingestor + 'a>> {
+        // The Notion plugin reads NOTION_TOKEN and NOTION_VERSION itself. Without the
+        // knowledge tasks, only the `table` mode is available.
+        let ingestor = anyrag_notion::NotionIngestor::new();
+        Ok(Box::new(match knowledge_ingestion(app_state) {
+            Ok((ai_provider, prompts)) => ingestor.with_knowledge(db, ai_provider, prompts),
+            Err(_) => ingestor,
+        }))
+    }
+}
@@ -1,48 +1,107 @@
 pub mod auth;
 pub mod config;
+pub mod db_router;
+pub mod embedding;
 pub mod errors;
+pub mod graph_extraction;
 pub mod handlers;
+pub mod ingestors;
+pub mod metrics;
+pub mod moderation;
+pub mod openapi;
+pub mod redaction;
+pub mod route_retriever;
 
 pub mod router;
 pub mod state;
+pub mod summarization;
+pub mod telemetry;
+pub mod trash;
 pub mod types;
 
-use crate::{config::get_config, router::create_router, state::build_app_state};
+use crate::{
+    config::get_config, router::create_router, state::build_app_state, telemetry::init_tracing,
+    trash::spawn_trash_purge,
+};
 use anyrag::types::AppConfig;
 use std::net::SocketAddr;
 use tokio::net::TcpListener;
-use tracing::{debug, info};
-use tracing_subscriber::FmtSubscriber;
+use tracing::{debug, info, warn};
 
 /// Configures and runs the web server.
 ///
 /// This function initializes the application state, creates the router,
-/// and starts the Axum server.
+/// and starts the Axum server, along with the trash purge job if one is configured.
+/// On Ctrl-C or `SIGTERM`, the server stops accepting connections, finishes the
+/// requests in flight, and flushes the knowledge graph.
 pub async fn run(listener: TcpListener, config: AppConfig) -> anyhow::Result<()> {
     debug!(?config, "Server configuration loaded");
 
     let app_state = build_app_state(config).await?;
+    let knowledge_graph = app_state.knowledge_graph.clone();
+    spawn_trash_purge(&app_state);
     let app = create_router(app_state);
 
     info!("listening on {}", listener.local_addr()?);
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+
+    // The persistent graph is closed when its last reference is dropped; flush it first
+    // so that nothing written is left to RocksDB's recovery on the next start.
+    match knowledge_graph.read() {
+        Ok(kg) => {
+            if let Err(e) = kg.sync() {
+                warn!("Failed to flush the knowledge graph: {e}");
+            }
+        }
+        Err(_) => warn!("Failed to acquire KG read lock, the knowledge graph was not flushed."),
+    }
+    info!("Server stopped.");
 
     Ok(())
 }
 
+/// Resolves when the process is asked to stop, by Ctrl-C or `SIGTERM`.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl-C: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    info!("Shutting down.");
+}
+
 /// The library's main entry point.
 ///
-/// Sets up logging, configuration, and the TCP listener, then calls `run`.
+/// Sets up configuration, logging and tracing, and the TCP listener, then calls `run`.
 pub async fn start() -> anyhow::Result<()> {
     dotenvy::dotenv().ok();
 
-    let subscriber = FmtSubscriber::builder()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .compact()
-        .finish();
-    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
-
     let config = get_config(None)?;
+    // Kept until the server stops, so that the remaining spans are exported.
+    let _telemetry = init_tracing(config.telemetry.as_ref())?;
     let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
     let listener = TcpListener::bind(addr).await?;
     info!("Server listening on {}", addr);
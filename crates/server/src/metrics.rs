@@ -0,0 +1,152 @@
+//! # Metrics
+//!
+//! This module installs the Prometheus recorder that `/metrics` renders, and records
+//! the server's own metrics: requests per route, ingestion runs, and the size of the
+//! SQLite database. The AI provider and embedding metrics are recorded by the library.
+
+use anyrag::{metrics::status_label, providers::db::sqlite::SqliteProvider};
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::{sync::OnceLock, time::Instant};
+use tracing::debug;
+use turso::{Error as TursoError, Value};
+
+/// Requests served, labelled by `method`, `route` and `status`.
+pub const HTTP_REQUESTS_TOTAL: &str = "anyrag_http_requests_total";
+/// The time until the response headers are sent, labelled by `method` and `route`.
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "anyrag_http_request_duration_seconds";
+/// Ingestion runs of `POST /ingest`, labelled by `source_type` and `status`.
+pub const INGEST_RUNS_TOTAL: &str = "anyrag_ingest_runs_total";
+/// The duration of ingestion runs, labelled by `source_type`.
+pub const INGEST_DURATION_SECONDS: &str = "anyrag_ingest_duration_seconds";
+/// Documents added by ingestion runs, labelled by `source_type`.
+pub const INGEST_DOCUMENTS_TOTAL: &str = "anyrag_ingest_documents_total";
+/// The number of pages of the SQLite database.
+pub const SQLITE_PAGES: &str = "anyrag_sqlite_pages";
+/// The size of the SQLite database.
+pub const SQLITE_SIZE_BYTES: &str = "anyrag_sqlite_size_bytes";
+
+/// The content type of the Prometheus text exposition format.
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+const PAGE_COUNT_PRAGMA: &str = "PRAGMA page_count";
+const PAGE_SIZE_PRAGMA: &str = "PRAGMA page_size";
+/// The `route` label of requests that matched no route.
+pub(crate) const UNMATCHED_ROUTE: &str = "unmatched";
+/// Histogram buckets, from fast lookups to long ingestion runs.
+const DURATION_BUCKETS_SECS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0,
+];
+
+static PROMETHEUS_HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Returns the handle of the process-wide Prometheus recorder, installing it on the
+/// first call. If another recorder was installed first, the handle renders nothing.
+pub fn prometheus_handle() -> PrometheusHandle {
+    PROMETHEUS_HANDLE
+        .get_or_init(|| {
+            let recorder = PrometheusBuilder::new()
+                .set_buckets(DURATION_BUCKETS_SECS)
+                .expect("the duration buckets are not empty")
+                .build_recorder();
+            let handle = recorder.handle();
+            if metrics::set_global_recorder(recorder).is_err() {
+                debug!("A metrics recorder was already installed; /metrics will be empty.");
+            }
+            handle
+        })
+        .clone()
+}
+
+/// Middleware recording the count and latency of requests per route. Routes are
+/// labelled by their pattern, e.g. `/documents/{id}/share`, to bound the label values.
+pub async fn track_http_metrics(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE, MatchedPath::as_str)
+        .to_string();
+
+    let response = next.run(request).await;
+
+    histogram!(
+        HTTP_REQUEST_DURATION_SECONDS,
+        "method" => method.clone(),
+        "route" => route.clone()
+    )
+    .record(started.elapsed().as_secs_f64());
+    counter!(
+        HTTP_REQUESTS_TOTAL,
+        "method" => method,
+        "route" => route,
+        "status" => response.status().as_u16().to_string()
+    )
+    .increment(1);
+    response
+}
+
+/// Records an ingestion run that started at `started` and added `documents_added`.
+pub fn record_ingest<E>(source_type: &str, started: Instant, documents_added: Result<usize, E>) {
+    histogram!(INGEST_DURATION_SECONDS, "source_type" => source_type.to_string())
+        .record(started.elapsed().as_secs_f64());
+    counter!(
+        INGEST_RUNS_TOTAL,
+        "source_type" => source_type.to_string(),
+        "status" => status_label(&documents_added)
+    )
+    .increment(1);
+    if let Ok(documents_added) = documents_added {
+        counter!(INGEST_DOCUMENTS_TOTAL, "source_type" => source_type.to_string())
+            .increment(documents_added as u64);
+    }
+}
+
+/// Updates the SQLite gauges. The database opens connections on demand rather than
+/// pooling them, so its page count and size are what it reports.
+pub async fn record_database_stats(sqlite_provider: &SqliteProvider) {
+    let (Some(page_count), Some(page_size)) = (
+        read_pragma(sqlite_provider, PAGE_COUNT_PRAGMA).await,
+        read_pragma(sqlite_provider, PAGE_SIZE_PRAGMA).await,
+    ) else {
+        return;
+    };
+    gauge!(SQLITE_PAGES).set(page_count as f64);
+    gauge!(SQLITE_SIZE_BYTES).set((page_count * page_size) as f64);
+}
+
+/// Returns the size of a SQLite database in bytes, if it reports its page count and
+/// size.
+pub async fn database_size_bytes(sqlite_provider: &SqliteProvider) -> Option<i64> {
+    let page_count = read_pragma(sqlite_provider, PAGE_COUNT_PRAGMA).await?;
+    let page_size = read_pragma(sqlite_provider, PAGE_SIZE_PRAGMA).await?;
+    Some(page_count * page_size)
+}
+
+async fn read_pragma(sqlite_provider: &SqliteProvider, pragma: &str) -> Option<i64> {
+    let result: Result<Value, TursoError> = async {
+        let conn = sqlite_provider.db.connect()?;
+        let mut rows = conn.query(pragma, ()).await?;
+        match rows.next().await? {
+            Some(row) => row.get_value(0),
+            None => Ok(Value::Null),
+        }
+    }
+    .await;
+    match result {
+        Ok(Value::Integer(value)) => Some(value),
+        Ok(other) => {
+            debug!("'{pragma}' returned {other:?}, skipping the SQLite gauges.");
+            None
+        }
+        Err(e) => {
+            debug!("'{pragma}' failed, skipping the SQLite gauges: {e}");
+            None
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! # Answer and Ingestion Moderation
+//!
+//! With a `moderation` configuration, the final answers of the prompt, search and chat
+//! endpoints are checked by the configured moderation provider before they are
+//! returned, and handled by the configured policy. With `moderate_ingested`, the
+//! documents an ingestion stored are checked too: blocked documents are deleted, and
+//! the categories of flagged ones are recorded in `content_metadata` as `MODERATION`
+//! entries.
+//!
+//! A failure to moderate an ingested document is logged and never fails the ingestion.
+
+use crate::state::AppState;
+use anyrag::{
+    moderation::{moderate_text, ModerationAction, ModerationReport},
+    providers::{db::sqlite::SqliteProvider, moderation::ModerationProvider},
+};
+use tracing::{info, instrument, warn};
+use turso::{params, Connection};
+
+const SELECT_DOCUMENT_SQL: &str = "SELECT owner_id, content FROM documents WHERE id = ?";
+const DELETE_DOCUMENT_SQL: &str = "DELETE FROM documents WHERE id = ?";
+const INSERT_MODERATION_METADATA_SQL: &str = "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_subtype, metadata_value) VALUES (?, ?, 'MODERATION', ?, ?)";
+
+/// Moderates a final answer, if moderation is configured.
+///
+/// Returns the answer to send, which is the configured blocked message when the answer
+/// is blocked, and the moderation report.
+#[instrument(name = "moderation.answer", skip_all)]
+pub async fn moderate_answer(
+    app_state: &AppState,
+    answer: String,
+) -> (String, Option<ModerationReport>) {
+    let (Some(moderator), Some(config)) = (&app_state.moderator, &app_state.config.moderation)
+    else {
+        return (answer, None);
+    };
+    let (answer, report) = moderate_text(moderator.as_ref(), config, answer).await;
+    (answer, Some(report))
+}
+
+/// Moderates the given documents, if enabled. Returns the number of documents flagged.
+#[instrument(name = "ingest.moderate", skip_all, fields(documents = document_ids.len()))]
+pub async fn moderate_documents(
+    app_state: &AppState,
+    db: &SqliteProvider,
+    document_ids: &[String],
+) -> usize {
+    let (Some(moderator), Some(config)) = (&app_state.moderator, &app_state.config.moderation)
+    else {
+        return 0;
+    };
+    if !config.moderate_ingested || document_ids.is_empty() {
+        return 0;
+    }
+
+    let conn = match db.db.connect() {
+        Ok(conn) => conn,
+        Err(e) => {
+            warn!("Could not connect to moderate the ingested documents: {e}");
+            return 0;
+        }
+    };
+    let mut flagged = 0;
+    for document_id in document_ids {
+        match moderate_document(&conn, moderator.as_ref(), config.action, document_id).await {
+            Ok(true) => flagged += 1,
+            Ok(false) => {}
+            Err(e) => warn!("Moderation failed for document '{document_id}': {e}"),
+        }
+    }
+    info!("Moderation flagged {flagged} ingested documents.");
+    flagged
+}
+
+/// Moderates one document, returning whether it was flagged.
+async fn moderate_document(
+    conn: &Connection,
+    moderator: &dyn ModerationProvider,
+    action: ModerationAction,
+    document_id: &str,
+) -> Result<bool, anyhow::Error> {
+    let mut rows = conn
+        .query(SELECT_DOCUMENT_SQL, params![document_id])
+        .await?;
+    let Some(row) = rows.next().await? else {
+        return Ok(false);
+    };
+    let owner_id: Option<String> = row.get(0)?;
+    let content: String = row.get(1)?;
+
+    let verdict = moderator.moderate(&content).await?;
+    if !verdict.flagged {
+        return Ok(false);
+    }
+    warn!(
+        categories = ?verdict.categories,
+        action = ?action,
+        "Moderation flagged ingested document '{document_id}'."
+    );
+    match action {
+        ModerationAction::Block => {
+            conn.execute(DELETE_DOCUMENT_SQL, params![document_id])
+                .await?;
+        }
+        ModerationAction::Flag => {
+            let categories = if verdict.categories.is_empty() {
+                vec!["flagged".to_string()]
+            } else {
+                verdict.categories
+            };
+            for category in categories {
+                conn.execute(
+                    INSERT_MODERATION_METADATA_SQL,
+                    params![
+                        document_id,
+                        owner_id.clone(),
+                        category.to_uppercase(),
+                        "true"
+                    ],
+                )
+                .await?;
+            }
+        }
+        ModerationAction::Log => {}
+    }
+    Ok(true)
+}
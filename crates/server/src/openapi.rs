@@ -0,0 +1,317 @@
+//! # OpenAPI Specification
+//!
+//! This module assembles the OpenAPI document of the server from the `#[utoipa::path]`
+//! annotations of the handlers. The routes of optional features are described by
+//! their own documents and merged in when the feature is enabled, mirroring how
+//! `create_router` adds them. The result is served at `/openapi.json` and browsable
+//! with the Swagger UI at `/swagger-ui`.
+//! The `/ws` WebSocket endpoint is not included, as OpenAPI cannot describe it.
+
+use crate::{auth::middleware::API_KEY_HEADER, handlers, types::ErrorResponse};
+use utoipa::{
+    openapi::{
+        path::Operation,
+        security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+        ContentBuilder, Ref, ResponseBuilder,
+    },
+    Modify, OpenApi,
+};
+
+/// The route of the OpenAPI document.
+pub const OPENAPI_PATH: &str = "/openapi.json";
+/// The route of the Swagger UI.
+pub const SWAGGER_UI_PATH: &str = "/swagger-ui";
+/// The name of the JWT bearer security scheme. The `security` attributes below
+/// repeat it as a literal, since the macro does not accept constants.
+pub const BEARER_AUTH: &str = "bearer_auth";
+/// The name of the API key security scheme, repeated as a literal like `BEARER_AUTH`.
+pub const API_KEY_AUTH: &str = "api_key_auth";
+
+const ERROR_SCHEMA: &str = "ErrorResponse";
+const CLIENT_ERROR_STATUS: &str = "4XX";
+const SERVER_ERROR_STATUS: &str = "5XX";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "anyrag-server",
+        description = "Ingestion, search, and retrieval-augmented generation over your own data. \
+            Every successful JSON response is wrapped in an `ApiResponse` envelope, and every \
+            error is returned as an `ErrorResponse`."
+    ),
+    paths(
+        handlers::general::root,
+        handlers::general::health_check,
+        handlers::general::metrics_handler,
+        handlers::general::prompt_handler,
+        handlers::auth_handlers::google_login_handler,
+        handlers::auth_handlers::google_auth_callback_handler,
+        handlers::auth_handlers::oidc_login_handler,
+        handlers::auth_handlers::oidc_callback_handler,
+        handlers::auth_handlers::get_me_handler,
+        handlers::auth_handlers::refresh_handler,
+        handlers::auth_handlers::list_sessions_handler,
+        handlers::auth_handlers::revoke_session_handler,
+        handlers::auth_handlers::revoke_all_sessions_handler,
+        handlers::usage_handlers::get_usage_handler,
+        handlers::admin_handlers::get_users_handler,
+        handlers::admin_handlers::create_api_key_handler,
+        handlers::admin_handlers::list_api_keys_handler,
+        handlers::admin_handlers::get_api_key_handler,
+        handlers::admin_handlers::update_api_key_handler,
+        handlers::admin_handlers::delete_api_key_handler,
+        handlers::admin_handlers::purge_answer_cache_handler,
+        handlers::admin_handlers::knowledge_base_stats_handler,
+        handlers::backup_handlers::create_backup_handler,
+        handlers::backup_handlers::restore_backup_handler,
+        handlers::document_handlers::get_documents_handler,
+        handlers::document_handlers::export_documents_handler,
+        handlers::document_handlers::document_history_handler,
+        handlers::document_handlers::delete_document_handler,
+        handlers::document_handlers::list_trash_handler,
+        handlers::document_handlers::restore_document_handler,
+        handlers::document_handlers::share_document_handler,
+        handlers::document_handlers::list_document_shares_handler,
+        handlers::document_handlers::unshare_document_handler,
+        handlers::collection_handlers::create_collection_handler,
+        handlers::collection_handlers::list_collections_handler,
+        handlers::collection_handlers::get_collection_handler,
+        handlers::collection_handlers::update_collection_handler,
+        handlers::collection_handlers::delete_collection_handler,
+        handlers::collection_handlers::list_collection_documents_handler,
+        handlers::collection_handlers::add_collection_documents_handler,
+        handlers::collection_handlers::remove_collection_document_handler,
+        handlers::org_handlers::create_org_handler,
+        handlers::org_handlers::list_orgs_handler,
+        handlers::org_handlers::list_org_members_handler,
+        handlers::org_handlers::add_org_member_handler,
+        handlers::org_handlers::remove_org_member_handler,
+        handlers::chat_handlers::chat_handler,
+        handlers::db_handlers::db_query_handler,
+        handlers::db_handlers::list_schema_annotations_handler,
+        handlers::db_handlers::upsert_schema_annotation_handler,
+        handlers::db_handlers::delete_schema_annotation_handler,
+        handlers::experiment_handlers::experiment_summary_handler,
+        handlers::experiment_handlers::experiment_feedback_handler,
+        handlers::feedback_handlers::feedback_handler,
+        handlers::feedback_handlers::accept_feedback_handler,
+        handlers::generation_handlers::gen_text_handler,
+        handlers::knowledge::embed_new_handler,
+        handlers::knowledge::knowledge_export_handler,
+        handlers::knowledge::faq_generate_handler,
+        handlers::knowledge::knowledge_search_handler,
+        handlers::search::vector_search_handler,
+        handlers::search::keyword_search_handler,
+        handlers::search::hybrid_search_handler,
+        handlers::ingest::generic::ingest_handler,
+    ),
+    components(schemas(ErrorResponse)),
+    // Requests without a token are served as the guest user, so the scheme is optional.
+    security((), ("bearer_auth" = []), ("api_key_auth" = [])),
+    tags(
+        (name = "general", description = "Server status."),
+        (name = "auth", description = "Sign-in and the current user."),
+        (name = "admin", description = "Administration, restricted to the `root` role."),
+        (name = "documents", description = "The stored documents, their history and their trash."),
+        (name = "collections", description = "Named groups of documents that searches can be limited to."),
+        (name = "organizations", description = "Team workspaces that share documents between their members."),
+        (name = "prompt", description = "Text-to-SQL prompts and conversations."),
+        (name = "db", description = "Direct database access and schema annotations."),
+        (name = "experiments", description = "A/B experiments between task configurations."),
+        (name = "feedback", description = "Feedback on answers."),
+        (name = "generation", description = "Content generation from retrieved context."),
+        (name = "search", description = "Vector, keyword, hybrid, and knowledge base search."),
+        (name = "knowledge", description = "Embedding, exporting and distilling FAQs from the knowledge base."),
+        (name = "ingest", description = "Ingestion from external sources."),
+        (name = "examples", description = "Code examples ingested from GitHub repositories."),
+        (name = "graph", description = "The knowledge graph."),
+    )
+)]
+struct ApiDoc;
+
+#[cfg(feature = "text")]
+#[derive(OpenApi)]
+#[openapi(paths(handlers::ingest::text::ingest_text_handler))]
+struct TextApiDoc;
+
+#[cfg(feature = "pdf")]
+#[derive(OpenApi)]
+#[openapi(paths(handlers::ingest::pdf::ingest_pdf_handler))]
+struct PdfApiDoc;
+
+#[cfg(feature = "sheets")]
+#[derive(OpenApi)]
+#[openapi(paths(handlers::ingest::sheet::ingest_sheet_handler))]
+struct SheetsApiDoc;
+
+#[cfg(feature = "web")]
+#[derive(OpenApi)]
+#[openapi(paths(handlers::ingest::web::ingest_web_handler))]
+struct WebApiDoc;
+
+#[cfg(feature = "github")]
+#[derive(OpenApi)]
+#[openapi(paths(
+    handlers::ingest::github::ingest_github_handler,
+    handlers::ingest::github::ingest_github_issues_handler,
+    handlers::ingest::github::get_latest_examples_handler,
+    handlers::ingest::github::get_versioned_examples_handler,
+    handlers::ingest::github::search_examples_handler,
+))]
+struct GitHubApiDoc;
+
+#[cfg(feature = "rss")]
+#[derive(OpenApi)]
+#[openapi(paths(handlers::ingest::rss::ingest_rss_handler))]
+struct RssApiDoc;
+
+#[cfg(feature = "slack")]
+#[derive(OpenApi)]
+#[openapi(paths(handlers::ingest::slack::ingest_slack_handler))]
+struct SlackApiDoc;
+
+#[cfg(feature = "discord")]
+#[derive(OpenApi)]
+#[openapi(paths(handlers::ingest::discord::ingest_discord_handler))]
+struct DiscordApiDoc;
+
+#[cfg(feature = "jira")]
+#[derive(OpenApi)]
+#[openapi(paths(handlers::ingest::jira::ingest_jira_handler))]
+struct JiraApiDoc;
+
+#[cfg(feature = "objectstore")]
+#[derive(OpenApi)]
+#[openapi(paths(handlers::ingest::objectstore::ingest_objectstore_handler))]
+struct ObjectStoreApiDoc;
+
+#[cfg(feature = "firebase")]
+#[derive(OpenApi)]
+#[openapi(paths(handlers::ingest::firebase::ingest_firebase_handler))]
+struct FirebaseApiDoc;
+
+#[cfg(feature = "graph_db")]
+#[derive(OpenApi)]
+#[openapi(paths(
+    handlers::knowledge::knowledge_graph_search_handler,
+    handlers::graph_handlers::graph_build_handler,
+    handlers::graph_handlers::graph_neighbors_handler,
+    handlers::graph_handlers::graph_path_handler,
+    handlers::graph_handlers::graph_facts_handler,
+    handlers::graph_handlers::graph_compact_handler,
+    handlers::graph_handlers::graph_resolve_handler,
+    handlers::graph_handlers::graph_prompt_handler,
+))]
+struct GraphApiDoc;
+
+/// Returns the OpenAPI document of the routes enabled in this build.
+pub fn api_doc() -> utoipa::openapi::OpenApi {
+    let mut doc = ApiDoc::openapi();
+
+    #[cfg(feature = "text")]
+    doc.merge(TextApiDoc::openapi());
+    #[cfg(feature = "pdf")]
+    doc.merge(PdfApiDoc::openapi());
+    #[cfg(feature = "sheets")]
+    doc.merge(SheetsApiDoc::openapi());
+    #[cfg(feature = "web")]
+    doc.merge(WebApiDoc::openapi());
+    #[cfg(feature = "github")]
+    doc.merge(GitHubApiDoc::openapi());
+    #[cfg(feature = "rss")]
+    doc.merge(RssApiDoc::openapi());
+    #[cfg(feature = "slack")]
+    doc.merge(SlackApiDoc::openapi());
+    #[cfg(feature = "discord")]
+    doc.merge(DiscordApiDoc::openapi());
+    #[cfg(feature = "jira")]
+    doc.merge(JiraApiDoc::openapi());
+    #[cfg(feature = "objectstore")]
+    doc.merge(ObjectStoreApiDoc::openapi());
+    #[cfg(feature = "firebase")]
+    doc.merge(FirebaseApiDoc::openapi());
+    #[cfg(feature = "graph_db")]
+    doc.merge(GraphApiDoc::openapi());
+
+    // The modifiers run after the merges so they also cover the feature routes.
+    SecurityAddon.modify(&mut doc);
+    ErrorResponses.modify(&mut doc);
+    doc
+}
+
+/// Registers the JWT bearer scheme issued by the sign-in flow, and the API key scheme.
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            BEARER_AUTH,
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some(
+                        "A JWT whose `sub` identifies the user. Without a token, requests are \
+                         served as the guest user; an invalid or expired token is rejected \
+                         with `401`.",
+                    ))
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            API_KEY_AUTH,
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                API_KEY_HEADER,
+                "A key issued through `/admin/api-keys`, for services that cannot use the \
+                 sign-in flow. Requests act as the owner of the key, and are rejected with \
+                 `403` on routes outside its scopes.",
+            ))),
+        );
+    }
+}
+
+/// Documents the `ErrorResponse` body of failed requests on every operation.
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.post,
+                &mut item.put,
+                &mut item.delete,
+            ];
+            for operation in operations.into_iter().flatten() {
+                add_error_response(
+                    operation,
+                    CLIENT_ERROR_STATUS,
+                    "The request was invalid or unauthorized.",
+                );
+                add_error_response(
+                    operation,
+                    SERVER_ERROR_STATUS,
+                    "The server or an upstream service failed.",
+                );
+            }
+        }
+    }
+}
+
+fn add_error_response(operation: &mut Operation, status: &str, description: &str) {
+    let response = ResponseBuilder::new()
+        .description(description)
+        .content(
+            "application/json",
+            ContentBuilder::new()
+                .schema(Some(Ref::from_schema_name(ERROR_SCHEMA)))
+                .build(),
+        )
+        .build();
+    operation
+        .responses
+        .responses
+        .entry(status.to_string())
+        .or_insert(response.into());
+}
@@ -0,0 +1,181 @@
+//! # Ingestion Redaction
+//!
+//! With a `redaction` configuration, the documents an ingestion stored are redacted
+//! before anything else reads them: their title and content are masked with the
+//! configured detectors, and with the `pii_detection` task if `llm_detection` is
+//! enabled. The metadata the ingestor already extracted from the content, such as
+//! entities and keyphrases, is masked with the detectors too. The types of data found
+//! are recorded in `content_metadata` as `PII` entries, with the number of values
+//! masked.
+//!
+//! The content hash is left as it was computed from the original content, so that
+//! ingesting the same content again is still recognized as a duplicate. A failure here
+//! is logged and never fails the ingestion.
+
+use crate::state::AppState;
+use anyrag::{
+    providers::{ai::AiProvider, db::sqlite::SqliteProvider},
+    redaction::{Redaction, Redactor},
+    types::ResolvedTask,
+};
+use tracing::{info, instrument, warn};
+use turso::{params, Connection};
+
+/// The task personal data is detected with when `llm_detection` is enabled.
+const DETECTION_TASK: &str = "pii_detection";
+const SELECT_DOCUMENT_SQL: &str = "SELECT owner_id, title, content FROM documents WHERE id = ?";
+const UPDATE_DOCUMENT_SQL: &str = "UPDATE documents SET title = ?, content = ? WHERE id = ?";
+const SELECT_METADATA_SQL: &str = "SELECT id, metadata_value FROM content_metadata WHERE document_id = ? AND metadata_type != 'PII'";
+const UPDATE_METADATA_SQL: &str = "UPDATE content_metadata SET metadata_value = ? WHERE id = ?";
+const INSERT_PII_METADATA_SQL: &str = "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_subtype, metadata_value) VALUES (?, ?, 'PII', ?, ?)";
+
+/// Redacts the given documents, if enabled. Returns the number of documents in which
+/// personal data was found.
+#[instrument(name = "ingest.redact", skip_all, fields(documents = document_ids.len()))]
+pub async fn redact_documents(
+    app_state: &AppState,
+    db: &SqliteProvider,
+    document_ids: &[String],
+) -> usize {
+    let Some(redactor) = app_state.redactor.as_deref() else {
+        return 0;
+    };
+    if document_ids.is_empty() {
+        return 0;
+    }
+    let llm_detection = app_state
+        .config
+        .redaction
+        .as_ref()
+        .is_some_and(|redaction| redaction.llm_detection);
+    let detection = if llm_detection {
+        detection_task(app_state)
+    } else {
+        None
+    };
+
+    let conn = match db.db.connect() {
+        Ok(conn) => conn,
+        Err(e) => {
+            warn!("Could not connect to redact the ingested documents: {e}");
+            return 0;
+        }
+    };
+    let mut redacted = 0;
+    for document_id in document_ids {
+        match redact_document(&conn, redactor, detection, document_id).await {
+            Ok(true) => redacted += 1,
+            Ok(false) => {}
+            Err(e) => warn!("Redaction failed for document '{document_id}': {e}"),
+        }
+    }
+    info!("Redacted personal data from {redacted} ingested documents.");
+    redacted
+}
+
+/// Resolves the task and provider personal data is detected with.
+fn detection_task(app_state: &AppState) -> Option<(&ResolvedTask, &dyn AiProvider)> {
+    let Some(task) = app_state.tasks.get(DETECTION_TASK) else {
+        warn!("Task '{DETECTION_TASK}' not found in config, skipping LLM PII detection.");
+        return None;
+    };
+    let Some(ai_provider) = app_state.ai_providers.get(&task.provider) else {
+        warn!(
+            "Provider '{}' not found, skipping LLM PII detection.",
+            task.provider
+        );
+        return None;
+    };
+    Some((task, ai_provider.as_ref()))
+}
+
+/// Redacts one document, returning whether personal data was found in it.
+async fn redact_document(
+    conn: &Connection,
+    redactor: &Redactor,
+    detection: Option<(&ResolvedTask, &dyn AiProvider)>,
+    document_id: &str,
+) -> Result<bool, anyhow::Error> {
+    let mut rows = conn
+        .query(SELECT_DOCUMENT_SQL, params![document_id])
+        .await?;
+    let Some(row) = rows.next().await? else {
+        return Ok(false);
+    };
+    let owner_id: Option<String> = row.get(0)?;
+    let title: Option<String> = row.get(1)?;
+    let content: String = row.get(2)?;
+
+    let title = title.map(|title| redactor.redact(&title));
+    let content = match detection {
+        Some((task, ai_provider)) => {
+            redactor
+                .redact_with_llm(
+                    &content,
+                    ai_provider,
+                    &task.system_prompt,
+                    &task.user_prompt,
+                )
+                .await?
+        }
+        None => redactor.redact(&content),
+    };
+    let metadata = redact_metadata(conn, redactor, document_id).await?;
+    if !content.is_redacted()
+        && !title.as_ref().is_some_and(Redaction::is_redacted)
+        && metadata.is_empty()
+    {
+        return Ok(false);
+    }
+
+    let mut detected = content.detected.clone();
+    for redaction in title.iter().chain(&metadata) {
+        for (pii_type, count) in &redaction.detected {
+            *detected.entry(pii_type.clone()).or_default() += count;
+        }
+    }
+    conn.execute(
+        UPDATE_DOCUMENT_SQL,
+        params![title.map(|title| title.text), content.text, document_id],
+    )
+    .await?;
+    for (pii_type, count) in detected {
+        conn.execute(
+            INSERT_PII_METADATA_SQL,
+            params![document_id, owner_id.clone(), pii_type, count.to_string()],
+        )
+        .await?;
+    }
+    Ok(true)
+}
+
+/// Masks the personal data in the metadata values of a document, returning the
+/// redactions of the values that had some.
+async fn redact_metadata(
+    conn: &Connection,
+    redactor: &Redactor,
+    document_id: &str,
+) -> Result<Vec<Redaction>, anyhow::Error> {
+    let mut rows = conn
+        .query(SELECT_METADATA_SQL, params![document_id])
+        .await?;
+    let mut redacted = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let id: i64 = row.get(0)?;
+        let value: String = row.get(1)?;
+        let redaction = redactor.redact(&value);
+        if redaction.is_redacted() {
+            redacted.push((id, redaction));
+        }
+    }
+    drop(rows);
+
+    for (id, redaction) in &redacted {
+        conn.execute(UPDATE_METADATA_SQL, params![redaction.text.clone(), *id])
+            .await?;
+    }
+    Ok(redacted
+        .into_iter()
+        .map(|(_, redaction)| redaction)
+        .collect())
+}
@@ -0,0 +1,222 @@
+//! # Route Retriever
+//!
+//! Retrieves the context of the knowledge and graph routes for prompts routed by the
+//! executor: the documents of a hybrid search, or the facts of a knowledge graph query.
+
+use crate::{graph_extraction::fact_visibility, state::AppState};
+use anyrag::{
+    context_budget::{fit_context, ContextBudget},
+    context_sanitization::{document_context, ContextSanitizer},
+    graph::{nl_query::parse_graph_query, store::KnowledgeGraphStore},
+    providers::{ai::AiProvider, db::sqlite::SqliteProvider},
+    search::{hybrid_search, HybridSearchOptions, HybridSearchPrompts, TemporalRankingConfig},
+    types::{AppConfig, ResolvedTask},
+    PromptError, Route, RouteRetriever,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, RwLock},
+};
+use tracing::{info, warn};
+
+/// The number of documents a knowledge route answers from.
+const KNOWLEDGE_ROUTE_LIMIT: u32 = 5;
+const QUERY_ANALYSIS_TASK: &str = "query_analysis";
+const GRAPH_QUERY_TASK: &str = "graph_query_generation";
+const RAG_SYNTHESIS_TASK: &str = "rag_synthesis";
+
+/// Retrieves route context from the server's knowledge base and knowledge graph.
+///
+/// A retriever is created for each routed prompt, so that the knowledge route searches
+/// only the documents visible to the caller, and the graph route reads only their facts.
+pub struct ServerRouteRetriever {
+    /// The database the caller's requests are routed to.
+    pub sqlite_provider: Arc<SqliteProvider>,
+    pub ai_providers: Arc<HashMap<String, Box<dyn AiProvider>>>,
+    pub tasks: Arc<HashMap<String, ResolvedTask>>,
+    pub config: Arc<AppConfig>,
+    pub knowledge_graph: Arc<RwLock<KnowledgeGraphStore>>,
+    /// Sanitizes the retrieved documents, if `context_sanitization` is configured.
+    pub context_sanitizer: Option<Arc<ContextSanitizer>>,
+    /// The caller, and the organization the request acts within.
+    pub owner_id: Option<String>,
+    pub org_id: Option<String>,
+}
+
+impl fmt::Debug for ServerRouteRetriever {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServerRouteRetriever")
+            .field("sqlite_provider", &self.sqlite_provider)
+            .field("owner_id", &self.owner_id)
+            .field("org_id", &self.org_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ServerRouteRetriever {
+    /// Creates the retriever of a caller's prompt, answering from `db`, the database the
+    /// caller's requests are routed to.
+    pub fn for_caller(
+        app_state: &AppState,
+        db: Arc<SqliteProvider>,
+        owner_id: Option<String>,
+        org_id: Option<String>,
+    ) -> Self {
+        Self {
+            sqlite_provider: db,
+            ai_providers: app_state.ai_providers.clone(),
+            tasks: app_state.tasks.clone(),
+            config: app_state.config.clone(),
+            knowledge_graph: app_state.knowledge_graph.clone(),
+            context_sanitizer: app_state.context_sanitizer.clone(),
+            owner_id,
+            org_id,
+        }
+    }
+
+    fn task(&self, task_name: &str) -> Result<(&ResolvedTask, Box<dyn AiProvider>), PromptError> {
+        let task_config = self.tasks.get(task_name).ok_or_else(|| {
+            PromptError::StorageOperationFailed(format!(
+                "Configuration for task '{task_name}' not found."
+            ))
+        })?;
+        let provider = self
+            .ai_providers
+            .get(&task_config.provider)
+            .ok_or_else(|| {
+                PromptError::MissingAiProvider(format!(
+                    "Provider '{}' for task '{task_name}' not found in providers map.",
+                    task_config.provider
+                ))
+            })?;
+        Ok((task_config, provider.clone()))
+    }
+
+    async fn search_knowledge(
+        &self,
+        question: &str,
+        collection: Option<&str>,
+    ) -> Result<Option<String>, PromptError> {
+        let (task_config, provider) = self.task(QUERY_ANALYSIS_TASK)?;
+        let temporal_keywords: Vec<&str>;
+        let temporal_ranking_config = match &self.config.temporal_reasoning {
+            Some(config) => {
+                temporal_keywords = config.keywords.iter().map(|s| s.as_str()).collect();
+                Some(TemporalRankingConfig {
+                    keywords: &temporal_keywords,
+                    property_name: &config.property_name,
+                })
+            }
+            None => None,
+        };
+        let search_options = HybridSearchOptions {
+            query_text: question.to_string(),
+            owner_id: self.owner_id.clone(),
+            org_id: self.org_id.clone(),
+            collection: collection.map(str::to_string),
+            limit: KNOWLEDGE_ROUTE_LIMIT,
+            prompts: HybridSearchPrompts {
+                analysis_system_prompt: &task_config.system_prompt,
+                analysis_user_prompt_template: &task_config.user_prompt,
+            },
+            use_keyword_search: true,
+            use_vector_search: true,
+            embedding_api_url: &self.config.embedding.api_url,
+            embedding_model: &self.config.embedding.model_name,
+            embedding_api_key: self.config.embedding.api_key.as_deref(),
+            temporal_ranking_config,
+        };
+        let results = hybrid_search(
+            self.sqlite_provider.clone(),
+            Arc::from(provider),
+            search_options,
+        )
+        .await
+        .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?;
+        info!("Knowledge route found {} documents.", results.len());
+        // The knowledge route is answered by the `rag_synthesis` task, whose model's
+        // context window the documents must fit.
+        let budget = self
+            .tasks
+            .get(RAG_SYNTHESIS_TASK)
+            .and_then(|task| ContextBudget::for_provider(&self.config, &task.provider));
+        let results = fit_context(budget, results).results;
+        if results.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(
+            document_context(&results, self.context_sanitizer.as_deref()).context,
+        ))
+    }
+
+    async fn query_graph(&self, question: &str) -> Result<Option<String>, PromptError> {
+        let (task_config, provider) = self.task(GRAPH_QUERY_TASK)?;
+        let user_prompt = task_config
+            .user_prompt
+            .replace("{today}", &Utc::now().to_rfc3339())
+            .replace("{prompt}", question);
+        let response = provider
+            .generate(&task_config.system_prompt, &user_prompt)
+            .await?;
+        let Some(query) = parse_graph_query(&response) else {
+            info!("Graph route found no entity in the question.");
+            return Ok(None);
+        };
+
+        let visibility = fact_visibility(
+            &self.sqlite_provider,
+            self.owner_id.as_deref(),
+            self.org_id.as_deref(),
+        )
+        .await?;
+        let facts = match self.knowledge_graph.read() {
+            Ok(kg) => kg
+                .execute_graph_query(&query, &visibility)
+                .map_err(|e| PromptError::StorageOperationFailed(e.to_string()))?,
+            Err(_) => {
+                warn!("Failed to acquire KG read lock, answering without the graph.");
+                Vec::new()
+            }
+        };
+        info!("Graph route found {} facts.", facts.len());
+        if facts.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(format!(
+            "Facts from the Knowledge Graph:\n{}",
+            serde_json::to_string_pretty(&facts)?
+        )))
+    }
+}
+
+#[async_trait]
+impl RouteRetriever for ServerRouteRetriever {
+    fn supports(&self, route: Route) -> bool {
+        match route {
+            Route::Knowledge => true,
+            Route::Graph => self
+                .knowledge_graph
+                .read()
+                .ok()
+                .and_then(|kg| kg.entity_names().ok())
+                .is_some_and(|names| !names.is_empty()),
+            Route::Sql | Route::Direct => false,
+        }
+    }
+
+    async fn retrieve(
+        &self,
+        route: Route,
+        question: &str,
+        collection: Option<&str>,
+    ) -> Result<Option<String>, PromptError> {
+        match route {
+            Route::Knowledge => self.search_knowledge(question, collection).await,
+            Route::Graph => self.query_graph(question).await,
+            Route::Sql | Route::Direct => Ok(None),
+        }
+    }
+}
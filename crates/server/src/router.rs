@@ -1,27 +1,140 @@
-use super::{handlers, state::AppState};
+use super::{
+    auth::middleware::require_route_access,
+    handlers,
+    metrics::track_http_metrics,
+    openapi::{api_doc, OPENAPI_PATH, SWAGGER_UI_PATH},
+    state::AppState,
+    telemetry::{make_request_span, record_response},
+};
 use axum::extract::DefaultBodyLimit;
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, post},
     Router,
 };
 use tower_http::trace::TraceLayer;
+use utoipa_swagger_ui::SwaggerUi;
 
 /// Creates the Axum router with all the application routes.
 pub fn create_router(app_state: AppState) -> Router {
     let router = Router::new()
         .route("/", get(handlers::root))
         .route("/health", get(handlers::health_check))
+        .route("/metrics", get(handlers::metrics_handler))
         .route("/documents", get(handlers::get_documents_handler))
+        .route("/documents/export", get(handlers::export_documents_handler))
+        .route("/documents/trash", get(handlers::list_trash_handler))
+        .route("/documents/{id}", delete(handlers::delete_document_handler))
+        .route(
+            "/documents/{id}/restore",
+            post(handlers::restore_document_handler),
+        )
+        .route(
+            "/documents/{id}/history",
+            get(handlers::document_history_handler),
+        )
+        .route(
+            "/documents/{id}/share",
+            get(handlers::list_document_shares_handler).post(handlers::share_document_handler),
+        )
+        .route(
+            "/documents/{id}/share/{user_id}",
+            delete(handlers::unshare_document_handler),
+        )
+        .route(
+            "/collections",
+            get(handlers::list_collections_handler).post(handlers::create_collection_handler),
+        )
+        .route(
+            "/collections/{id}",
+            get(handlers::get_collection_handler)
+                .put(handlers::update_collection_handler)
+                .delete(handlers::delete_collection_handler),
+        )
+        .route(
+            "/collections/{id}/documents",
+            get(handlers::list_collection_documents_handler)
+                .post(handlers::add_collection_documents_handler),
+        )
+        .route(
+            "/collections/{id}/documents/{document_id}",
+            delete(handlers::remove_collection_document_handler),
+        )
         // --- OAuth 2.0 Authentication Routes ---
         .route("/auth/login/google", get(handlers::google_login_handler))
         .route(
             "/auth/callback/google",
             get(handlers::google_auth_callback_handler),
         )
+        .route("/auth/login/oidc", get(handlers::oidc_login_handler))
+        .route("/auth/callback/oidc", get(handlers::oidc_callback_handler))
         .route("/auth/me", get(handlers::get_me_handler))
+        .route("/me/usage", get(handlers::get_usage_handler))
+        .route("/auth/refresh", post(handlers::refresh_handler))
+        .route(
+            "/auth/sessions",
+            get(handlers::list_sessions_handler).delete(handlers::revoke_all_sessions_handler),
+        )
+        .route(
+            "/auth/sessions/{session_id}",
+            delete(handlers::revoke_session_handler),
+        )
         .route("/users", get(handlers::get_users_handler))
+        .route(
+            "/admin/api-keys",
+            get(handlers::list_api_keys_handler).post(handlers::create_api_key_handler),
+        )
+        .route(
+            "/admin/api-keys/{id}",
+            get(handlers::get_api_key_handler)
+                .put(handlers::update_api_key_handler)
+                .delete(handlers::delete_api_key_handler),
+        )
+        .route("/admin/stats", get(handlers::knowledge_base_stats_handler))
+        .route("/admin/backups", post(handlers::create_backup_handler))
+        .route(
+            "/admin/backups/restore",
+            post(handlers::restore_backup_handler),
+        )
+        .route(
+            "/admin/answer-cache",
+            delete(handlers::purge_answer_cache_handler),
+        )
+        .route(
+            "/orgs",
+            get(handlers::list_orgs_handler).post(handlers::create_org_handler),
+        )
+        .route(
+            "/orgs/{org_id}/members",
+            get(handlers::list_org_members_handler).post(handlers::add_org_member_handler),
+        )
+        .route(
+            "/orgs/{org_id}/members/{user_id}",
+            delete(handlers::remove_org_member_handler),
+        )
         .route("/prompt", post(handlers::prompt_handler))
+        .route("/chat", post(handlers::chat_handler))
+        .route("/ws", get(handlers::ws_handler))
         .route("/db/query", post(handlers::db_query_handler))
+        .route(
+            "/db/annotations",
+            get(handlers::list_schema_annotations_handler)
+                .post(handlers::upsert_schema_annotation_handler)
+                .delete(handlers::delete_schema_annotation_handler),
+        )
+        .route(
+            "/experiments/{name}/summary",
+            get(handlers::experiment_summary_handler),
+        )
+        .route(
+            "/experiments/runs/{run_id}/feedback",
+            post(handlers::experiment_feedback_handler),
+        )
+        .route("/feedback", post(handlers::feedback_handler))
+        .route(
+            "/feedback/{feedback_id}/accept",
+            post(handlers::accept_feedback_handler),
+        )
         .route("/gen/text", post(handlers::gen_text_handler))
         .route("/embed/new", post(handlers::embed_new_handler))
         .route("/search/vector", post(handlers::vector_search_handler))
@@ -31,7 +144,12 @@ pub fn create_router(app_state: AppState) -> Router {
             "/search/knowledge",
             post(handlers::knowledge_search_handler),
         )
-        .route("/knowledge/export", get(handlers::knowledge_export_handler));
+        .route("/knowledge/export", get(handlers::knowledge_export_handler))
+        .route(
+            "/knowledge/faq/generate",
+            post(handlers::faq_generate_handler),
+        )
+        .route("/ingest", post(handlers::ingest::generic::ingest_handler));
 
     // Conditionally add routes by re-binding the router variable.
     // This avoids the `unused_mut` warning when no features are enabled.
@@ -77,6 +195,10 @@ pub fn create_router(app_state: AppState) -> Router {
                 "/ingest/github",
                 post(handlers::ingest::github::ingest_github_handler),
             )
+            .route(
+                "/ingest/github/issues",
+                post(handlers::ingest::github::ingest_github_issues_handler),
+            )
             .route(
                 "/examples/{repo_name}",
                 get(handlers::ingest::github::get_latest_examples_handler),
@@ -99,6 +221,38 @@ pub fn create_router(app_state: AppState) -> Router {
         );
     }
 
+    #[cfg(feature = "slack")]
+    {
+        router = router.route(
+            "/ingest/slack",
+            post(handlers::ingest::slack::ingest_slack_handler),
+        );
+    }
+
+    #[cfg(feature = "discord")]
+    {
+        router = router.route(
+            "/ingest/discord",
+            post(handlers::ingest::discord::ingest_discord_handler),
+        );
+    }
+
+    #[cfg(feature = "jira")]
+    {
+        router = router.route(
+            "/ingest/jira",
+            post(handlers::ingest::jira::ingest_jira_handler),
+        );
+    }
+
+    #[cfg(feature = "objectstore")]
+    {
+        router = router.route(
+            "/ingest/objectstore",
+            post(handlers::ingest::objectstore::ingest_objectstore_handler),
+        );
+    }
+
     #[cfg(feature = "firebase")]
     {
         router = router.route(
@@ -114,10 +268,34 @@ pub fn create_router(app_state: AppState) -> Router {
                 "/search/knowledge_graph",
                 post(handlers::knowledge_graph_search_handler),
             )
-            .route("/graph/build", post(handlers::graph_build_handler));
+            .route("/search/graph", post(handlers::graph_prompt_handler))
+            .route("/graph/build", post(handlers::graph_build_handler))
+            .route("/graph/neighbors", post(handlers::graph_neighbors_handler))
+            .route("/graph/path", post(handlers::graph_path_handler))
+            .route("/graph/facts", post(handlers::graph_facts_handler))
+            .route(
+                "/admin/graph/compact",
+                post(handlers::graph_compact_handler),
+            )
+            .route(
+                "/admin/graph/resolve",
+                post(handlers::graph_resolve_handler),
+            );
     }
 
+    // The OpenAPI document and the Swagger UI that browses it.
+    let router = router.merge(SwaggerUi::new(SWAGGER_UI_PATH).url(OPENAPI_PATH, api_doc()));
+
     router
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            require_route_access,
+        ))
+        .layer(middleware::from_fn(track_http_metrics))
         .with_state(app_state)
-        .layer(TraceLayer::new_for_http())
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(make_request_span)
+                .on_response(record_response),
+        )
 }
@@ -5,21 +5,38 @@
 //! as the configuration, database connections, and instantiated AI provider clients,
 //! making them accessible to all request handlers.
 
+use crate::{
+    auth::{oidc::OidcClient, rate_limit::RateLimiter},
+    db_router::DatabaseRouter,
+    ingestors::IngestorRegistry,
+    metrics::prometheus_handle,
+};
 use anyrag::{
-    graph::types::MemoryKnowledgeGraph,
+    context_sanitization::ContextSanitizer,
+    graph::store::KnowledgeGraphStore,
+    moderation::ModerationProviderConfig,
     providers::{
-        ai::{gemini::GeminiProvider, local::LocalAiProvider, AiProvider},
+        ai::{
+            gemini::GeminiProvider, local::LocalAiProvider, AiProvider, MeteredAiProvider,
+            RedactingAiProvider,
+        },
         db::sqlite::SqliteProvider,
+        moderation::{ClassifierModerationProvider, ModerationProvider, OpenAiModerationProvider},
     },
+    redaction::Redactor,
     types::{AppConfig, ResolvedTask},
     AnyragExecutor,
 };
 use anyrag_github::ingest::storage::StorageManager;
+use metrics_exporter_prometheus::PrometheusHandle;
 use std::{
     collections::HashMap,
     sync::{Arc, RwLock},
 };
 
+/// The task a classifier moderation provider is prompted with.
+const MODERATION_TASK: &str = "content_moderation";
+
 /// The shared application state, accessible from all request handlers.
 #[derive(Clone)]
 pub struct AppState {
@@ -29,23 +46,49 @@ pub struct AppState {
     pub tasks: Arc<HashMap<String, ResolvedTask>>,
     /// The primary database provider for local storage and knowledge base.
     pub sqlite_provider: Arc<SqliteProvider>,
+    /// Routes requests to the database of their owner, organization or project.
+    pub db_router: Arc<DatabaseRouter>,
     /// A map of instantiated AI providers, keyed by their name from the config.
     pub ai_providers: Arc<HashMap<String, Box<dyn AiProvider>>>,
-    /// An in-memory knowledge graph for time-sensitive, precise data.
-    pub knowledge_graph: Arc<RwLock<MemoryKnowledgeGraph>>,
+    /// The knowledge graph for time-sensitive, precise data, kept in memory or
+    /// persisted as selected by the `graph` configuration.
+    pub knowledge_graph: Arc<RwLock<KnowledgeGraphStore>>,
     /// The core logic executor, which holds shared dependencies.
     pub executor: Arc<AnyragExecutor>,
     /// Manages databases for GitHub example ingestion and search.
     pub storage_manager: Arc<StorageManager>,
+    /// The ingestion plugins `POST /ingest` dispatches to, keyed by source type.
+    pub ingestors: Arc<IngestorRegistry>,
+    /// The OpenID Connect provider bearer tokens are verified with, if configured.
+    pub oidc: Option<Arc<OidcClient>>,
+    /// The per-caller request limits, if configured.
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// The Prometheus recorder `/metrics` renders.
+    pub metrics: PrometheusHandle,
+    /// Masks personal data in ingested documents, if the `redaction` configuration
+    /// enables it.
+    pub redactor: Option<Arc<Redactor>>,
+    /// Scans retrieved documents for instruction-like text before they are put into
+    /// RAG prompts, if `context_sanitization` is configured.
+    pub context_sanitizer: Option<Arc<ContextSanitizer>>,
+    /// Checks final answers and ingested documents, if `moderation` is configured.
+    pub moderator: Option<Arc<dyn ModerationProvider>>,
 }
 
 /// Builds the shared application state from the configuration.
 ///
 /// This function initializes all necessary services:
 /// - It instantiates an AI provider client for each entry in the `providers`
-///   section of the configuration.
-/// - It sets up the connection to the SQLite database.
-/// - It initializes an in-memory knowledge graph.
+///   section of the configuration, wrapped to record its metrics.
+/// - It compiles the redaction patterns, and wraps the providers of the tasks
+///   configured with `redact: true` to mask personal data in their prompts.
+/// - It compiles the patterns retrieved documents are sanitized with.
+/// - It instantiates the moderation provider, if configured.
+/// - It sets up the connection to the SQLite database, and the router to its shards.
+/// - It opens the knowledge graph, loading a persisted one from disk.
+/// - It registers the ingestion plugins of the enabled features.
+/// - It sets up the OpenID Connect client and the rate limiter, if configured.
+/// - It installs the Prometheus metrics recorder.
 pub async fn build_app_state(config: AppConfig) -> anyhow::Result<AppState> {
     // Create a map of AI provider instances from the configuration.
     let mut ai_providers = HashMap::new();
@@ -85,9 +128,14 @@ pub async fn build_app_state(config: AppConfig) -> anyhow::Result<AppState> {
                 ));
             }
         };
+        let provider: Box<dyn AiProvider> = Box::new(MeteredAiProvider::new(name, provider));
         ai_providers.insert(name.clone(), provider);
     }
 
+    let redactor = Arc::new(Redactor::new(
+        &config.redaction.clone().unwrap_or_default(),
+    )?);
+
     // Validate and resolve all tasks from the configuration.
     // The config loading ensures that all default tasks have their fields populated,
     // so we can safely unwrap them here. A panic here indicates a misconfiguration
@@ -104,6 +152,24 @@ pub async fn build_app_state(config: AppConfig) -> anyhow::Result<AppState> {
             anyhow::anyhow!("Resolved task '{name}' is missing required 'user_prompt' field")
         })?;
 
+        // Redacted tasks use a redacting copy of their provider, registered under its
+        // own name so that the other tasks of the provider are unaffected.
+        let provider = if task_config.redact {
+            let redacted_name = format!("{provider}+redacted");
+            if !ai_providers.contains_key(&redacted_name) {
+                let inner: Box<dyn AiProvider> =
+                    ai_providers.get(&provider).cloned().ok_or_else(|| {
+                        anyhow::anyhow!("Task '{name}' references unknown provider '{provider}'")
+                    })?;
+                let redacting: Box<dyn AiProvider> =
+                    Box::new(RedactingAiProvider::new(redactor.clone(), inner));
+                ai_providers.insert(redacted_name.clone(), redacting);
+            }
+            redacted_name
+        } else {
+            provider
+        };
+
         resolved_tasks.insert(
             name.clone(),
             ResolvedTask {
@@ -114,8 +180,61 @@ pub async fn build_app_state(config: AppConfig) -> anyhow::Result<AppState> {
         );
     }
 
+    // The moderation provider checks answers with a dedicated API or a classifier task.
+    let moderator: Option<Arc<dyn ModerationProvider>> = match config
+        .moderation
+        .as_ref()
+        .map(|moderation| &moderation.provider)
+    {
+        None => None,
+        Some(ModerationProviderConfig::OpenAi {
+            api_key,
+            api_url,
+            model,
+        }) => Some(Arc::new(OpenAiModerationProvider::new(
+            api_key.clone(),
+            api_url.clone(),
+            model.clone(),
+        )?)),
+        Some(ModerationProviderConfig::Classifier) => {
+            let task = resolved_tasks.get(MODERATION_TASK).ok_or_else(|| {
+                anyhow::anyhow!("Moderation requires the '{MODERATION_TASK}' task")
+            })?;
+            let provider = ai_providers.get(&task.provider).cloned().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Task '{MODERATION_TASK}' references unknown provider '{}'",
+                    task.provider
+                )
+            })?;
+            Some(Arc::new(ClassifierModerationProvider::new(
+                provider,
+                task.system_prompt.clone(),
+                task.user_prompt.clone(),
+            )))
+        }
+    };
+
+    // Every experiment must split traffic between two resolvable task configurations.
+    for (name, experiment) in &config.experiments {
+        for variant_task in [&experiment.variant_a, &experiment.variant_b] {
+            if !resolved_tasks.contains_key(variant_task) {
+                return Err(anyhow::anyhow!(
+                    "Experiment '{name}' references unknown task '{variant_task}'"
+                ));
+            }
+        }
+        if experiment.traffic_percent_b > 100 {
+            return Err(anyhow::anyhow!(
+                "Experiment '{name}' has 'traffic_percent_b' above 100"
+            ));
+        }
+    }
+
     // The provider for local ingestion, embedding, and searching.
-    let sqlite_provider = SqliteProvider::new(&config.db_url).await?;
+    let sqlite_provider = SqliteProvider::new(&config.db_url)
+        .await?
+        .with_query_limits(config.query_limits())
+        .with_sqlite_settings(config.sqlite_settings());
     tracing::info!(db_path = %config.db_url, "Initialized local storage provider (SQLite).");
     // Ensure the database schema is up-to-date on startup.
     sqlite_provider.initialize_schema().await?;
@@ -133,27 +252,62 @@ pub async fn build_app_state(config: AppConfig) -> anyhow::Result<AppState> {
     let storage_manager = StorageManager::new(db_dir.as_deref()).await?;
     let storage_manager_arc = Arc::new(storage_manager);
 
+    let knowledge_graph = KnowledgeGraphStore::open(config.graph.as_ref())?;
+
+    let oidc = config
+        .oidc
+        .clone()
+        .map(OidcClient::new)
+        .transpose()?
+        .map(Arc::new);
+    let rate_limiter = config
+        .rate_limit
+        .as_ref()
+        .map(|rate_limit| Arc::new(RateLimiter::new(rate_limit)));
+
+    let context_sanitizer = config
+        .context_sanitization
+        .as_ref()
+        .map(ContextSanitizer::new)
+        .transpose()?
+        .map(Arc::new);
+    let redaction_enabled = config
+        .redaction
+        .as_ref()
+        .is_some_and(|redaction| redaction.redact_ingested);
+
     // Wrap dependencies in Arcs for sharing.
     let sqlite_provider_arc = Arc::new(sqlite_provider);
     let ai_providers_arc = Arc::new(ai_providers);
     let tasks_arc = Arc::new(resolved_tasks);
     let config_arc = Arc::new(config);
+    let knowledge_graph_arc = Arc::new(RwLock::new(knowledge_graph));
 
-    // Create the core logic executor, passing shared dependencies.
+    // Create the core logic executor, passing shared dependencies. The retriever of
+    // routed prompts is attached for each caller by the prompt handler.
     let executor = AnyragExecutor::new(
         ai_providers_arc.clone(),
         sqlite_provider_arc.clone(),
         config_arc.clone(),
         tasks_arc.clone(),
     );
+    let db_router = DatabaseRouter::new(sqlite_provider_arc.clone(), &config_arc);
 
     Ok(AppState {
         config: config_arc,
         tasks: tasks_arc,
         sqlite_provider: sqlite_provider_arc,
+        db_router: Arc::new(db_router),
         ai_providers: ai_providers_arc,
-        knowledge_graph: Arc::new(RwLock::new(MemoryKnowledgeGraph::new_memory())),
+        knowledge_graph: knowledge_graph_arc,
         executor: Arc::new(executor),
         storage_manager: storage_manager_arc,
+        ingestors: Arc::new(IngestorRegistry::with_enabled_plugins()),
+        oidc,
+        rate_limiter,
+        metrics: prometheus_handle(),
+        redactor: redaction_enabled.then_some(redactor),
+        context_sanitizer,
+        moderator,
     })
 }
@@ -0,0 +1,156 @@
+//! # Ingestion Summarization
+//!
+//! With a `summarization` configuration, the sources an ingestion stored chunks of are
+//! summarized with the `document_summarization` task once the ingestion is done. Each
+//! source long enough gets one summary document, which replaces the previous one and is
+//! embedded right away, so that hybrid search can find it and expand it to the chunks.
+//!
+//! A failure to summarize a source is logged and never fails the ingestion. A summary
+//! that could not be embedded is embedded by the next `/embed/new` call.
+
+use crate::state::AppState;
+use anyrag::{
+    providers::{ai::generate_embeddings_batch, db::sqlite::SqliteProvider},
+    summarization::{is_summary_link, source_of, summarize_source},
+};
+use std::collections::BTreeSet;
+use tracing::{info, instrument, warn};
+use turso::{params, Connection};
+
+/// The task sources are summarized with.
+const SUMMARIZATION_TASK: &str = "document_summarization";
+const SELECT_SOURCE_SQL: &str = "SELECT owner_id, source_url FROM documents WHERE id = ?";
+const SELECT_SUMMARY_SQL: &str = "SELECT title, content FROM documents WHERE id = ?";
+const INSERT_EMBEDDING_SQL: &str =
+    "INSERT INTO document_embeddings (document_id, model_name, embedding) VALUES (?, ?, ?)";
+
+/// Summarizes the sources of the given documents, if enabled. Returns the number of
+/// summary documents stored.
+#[instrument(name = "ingest.summarize", skip_all, fields(documents = document_ids.len()))]
+pub async fn summarize_sources(
+    app_state: &AppState,
+    db: &SqliteProvider,
+    document_ids: &[String],
+) -> usize {
+    let Some(config) = &app_state.config.summarization else {
+        return 0;
+    };
+    if document_ids.is_empty() {
+        return 0;
+    }
+    let Some(task) = app_state.tasks.get(SUMMARIZATION_TASK) else {
+        warn!("Task '{SUMMARIZATION_TASK}' not found in config, skipping summarization.");
+        return 0;
+    };
+    let Some(ai_provider) = app_state.ai_providers.get(&task.provider) else {
+        warn!(
+            "Provider '{}' not found, skipping summarization.",
+            task.provider
+        );
+        return 0;
+    };
+
+    let mut conn = match db.db.connect() {
+        Ok(conn) => conn,
+        Err(e) => {
+            warn!("Could not connect to summarize the ingested sources: {e}");
+            return 0;
+        }
+    };
+    let sources = match document_sources(&conn, document_ids).await {
+        Ok(sources) => sources,
+        Err(e) => {
+            warn!("Could not load the sources of the ingested documents: {e}");
+            return 0;
+        }
+    };
+
+    let mut summarized = 0;
+    for (owner_id, source) in sources {
+        let summary_id = match summarize_source(
+            &mut conn,
+            ai_provider.as_ref(),
+            owner_id.as_deref(),
+            &source,
+            config,
+            &task.system_prompt,
+            &task.user_prompt,
+        )
+        .await
+        {
+            Ok(Some(summary_id)) => summary_id,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("Summarization failed for source '{source}': {e}");
+                continue;
+            }
+        };
+        summarized += 1;
+        if let Err(e) = embed_summary(app_state, &conn, &summary_id).await {
+            warn!("Could not embed the summary of source '{source}': {e}");
+        }
+    }
+    info!("Stored {summarized} summaries of ingested sources.");
+    summarized
+}
+
+/// Returns the distinct owners and sources of the given documents.
+async fn document_sources(
+    conn: &Connection,
+    document_ids: &[String],
+) -> Result<BTreeSet<(Option<String>, String)>, turso::Error> {
+    let mut sources = BTreeSet::new();
+    for document_id in document_ids {
+        let mut rows = conn
+            .query(SELECT_SOURCE_SQL, params![document_id.as_str()])
+            .await?;
+        let Some(row) = rows.next().await? else {
+            continue;
+        };
+        let owner_id: Option<String> = row.get(0)?;
+        let Some(source_url) = row.get::<Option<String>>(1)? else {
+            continue;
+        };
+        if !is_summary_link(&source_url) {
+            sources.insert((owner_id, source_of(&source_url).to_string()));
+        }
+    }
+    Ok(sources)
+}
+
+/// Embeds a summary document the way `/embed/new` embeds documents.
+async fn embed_summary(
+    app_state: &AppState,
+    conn: &Connection,
+    summary_id: &str,
+) -> Result<(), anyhow::Error> {
+    let mut rows = conn.query(SELECT_SUMMARY_SQL, params![summary_id]).await?;
+    let Some(row) = rows.next().await? else {
+        return Ok(());
+    };
+    let title: String = row.get(0)?;
+    let content: String = row.get(1)?;
+
+    let embedding = &app_state.config.embedding;
+    let text = format!("{title}. {content}");
+    let vector = generate_embeddings_batch(
+        &embedding.api_url,
+        &embedding.model_name,
+        &[text.as_str()],
+        embedding.api_key.as_deref(),
+    )
+    .await?
+    .pop()
+    .ok_or_else(|| anyhow::anyhow!("Embedding API returned no vector"))?;
+    let vector_bytes: Vec<u8> = vector.iter().flat_map(|x| x.to_ne_bytes()).collect();
+    conn.execute(
+        INSERT_EMBEDDING_SQL,
+        params![
+            summary_id,
+            embedding.model_name.as_str(),
+            vector_bytes.as_slice()
+        ],
+    )
+    .await?;
+    Ok(())
+}
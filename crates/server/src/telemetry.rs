@@ -0,0 +1,148 @@
+//! # Telemetry
+//!
+//! This module sets up the tracing subscriber. Spans and events are always logged;
+//! with a `telemetry` section in the configuration, spans are also exported to an
+//! OpenTelemetry collector over OTLP, continuing the traces of callers that send a
+//! W3C `traceparent` header.
+
+use crate::metrics::UNMATCHED_ROUTE;
+use anyrag::types::TelemetryConfig;
+use axum::{
+    extract::{MatchedPath, Request},
+    http::HeaderMap,
+    response::Response,
+};
+use opentelemetry::{
+    global,
+    propagation::Extractor,
+    trace::{TraceContextExt, TracerProvider as _},
+};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    trace::{Sampler, SdkTracerProvider},
+    Resource,
+};
+use std::time::Duration;
+use tracing::{debug, field::Empty, info_span, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{
+    filter::EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt, Layer,
+};
+
+/// The spans exported to the collector. The exporter's own HTTP/2 client is excluded,
+/// as exporting its spans would produce more of them.
+const EXPORT_FILTER: &str = "info,h2=off,hyper=off,tonic=off,tower=off,opentelemetry=off";
+const TRACER_NAME: &str = "anyrag-server";
+
+/// Flushes the spans not exported yet when dropped, e.g. when the server stops.
+pub struct TelemetryGuard {
+    tracer_provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        let Some(tracer_provider) = self.tracer_provider.take() else {
+            return;
+        };
+        if let Err(e) = tracer_provider.shutdown() {
+            eprintln!("Failed to flush the OpenTelemetry spans: {e}");
+        }
+    }
+}
+
+/// Installs the global tracing subscriber. Logging is filtered by `RUST_LOG`, while
+/// the export to the collector, if configured, includes every `info` span.
+pub fn init_tracing(config: Option<&TelemetryConfig>) -> anyhow::Result<TelemetryGuard> {
+    let fmt_layer = fmt::layer()
+        .compact()
+        .with_filter(EnvFilter::from_default_env());
+
+    let Some(config) = config else {
+        tracing_subscriber::registry().with(fmt_layer).try_init()?;
+        return Ok(TelemetryGuard {
+            tracer_provider: None,
+        });
+    };
+
+    let exporter = SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(&config.otlp_endpoint)
+        .build()?;
+    let tracer_provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+        .build();
+    let otel_layer = tracing_opentelemetry::layer()
+        .with_tracer(tracer_provider.tracer(TRACER_NAME))
+        .with_filter(EnvFilter::new(EXPORT_FILTER));
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    global::set_tracer_provider(tracer_provider.clone());
+    tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(otel_layer)
+        .try_init()?;
+
+    Ok(TelemetryGuard {
+        tracer_provider: Some(tracer_provider),
+    })
+}
+
+/// Creates the span of a request, named after its route, e.g. `GET /documents/{id}/share`.
+/// When the request carries a trace context, the span continues that trace.
+pub fn make_request_span(request: &Request) -> Span {
+    let method = request.method();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE, MatchedPath::as_str);
+    let span = info_span!(
+        "request",
+        otel.name = %format!("{method} {route}"),
+        otel.kind = "server",
+        http.request.method = %method,
+        http.route = route,
+        url.path = request.uri().path(),
+        http.response.status_code = Empty,
+    );
+
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    if parent.span().span_context().is_remote() {
+        span.set_parent(parent);
+    }
+    span
+}
+
+/// Records the status of the response on the request span.
+pub fn record_response(response: &Response, latency: Duration, span: &Span) {
+    let status = response.status().as_u16();
+    span.record("http.response.status_code", status);
+    debug!(
+        status,
+        latency_ms = latency.as_millis() as u64,
+        "finished processing request"
+    );
+}
+
+/// Reads the trace context from request headers.
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
@@ -0,0 +1,60 @@
+//! # Trash Purging
+//!
+//! `DELETE /documents/{id}` only moves a document to the trash. With a `trash`
+//! configuration, a background job purges the documents deleted longer ago than the
+//! retention, from the primary database and from every shard, at each purge interval.
+//!
+//! A failed purge is logged and retried at the next interval.
+
+use crate::state::AppState;
+use anyrag::ingest::{purge_trash, TrashConfig};
+use chrono::Utc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, instrument, warn};
+
+/// Starts purging the trash periodically, if a `trash` configuration is set.
+pub fn spawn_trash_purge(app_state: &AppState) -> Option<JoinHandle<()>> {
+    let config = app_state.config.trash.clone()?;
+    let app_state = app_state.clone();
+    info!(
+        "Purging documents deleted more than {} days ago every {} seconds.",
+        config.retention_days, config.purge_interval_secs
+    );
+    Some(tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(config.purge_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            purge_expired(&app_state, &config).await;
+        }
+    }))
+}
+
+/// Purges the documents deleted before the retention of every knowledge base. Returns
+/// the number of documents purged.
+#[instrument(name = "trash.purge", skip_all)]
+pub async fn purge_expired(app_state: &AppState, config: &TrashConfig) -> u64 {
+    let deleted_before = Utc::now() - chrono::Duration::days(i64::from(config.retention_days));
+    let databases = match app_state.db_router.knowledge_bases().await {
+        Ok(databases) => databases,
+        Err(e) => {
+            warn!("Could not open the knowledge bases to purge the trash: {e:?}");
+            return 0;
+        }
+    };
+
+    let mut purged = 0;
+    for db in databases {
+        let _permit = db.write_permit().await;
+        let result = match db.db.connect() {
+            Ok(conn) => purge_trash(&conn, deleted_before).await,
+            Err(e) => Err(e.into()),
+        };
+        match result {
+            Ok(count) => purged += count,
+            Err(e) => warn!("Failed to purge the trash: {e}"),
+        }
+    }
+    purged
+}
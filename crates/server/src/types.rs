@@ -1,18 +1,49 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct DebugParams {
+    /// Adds the intermediate steps of the request to the response as `debug`.
     pub debug: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize)]
+/// Whether an ingestion embeds the documents it stored.
+#[derive(Debug, Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EmbedParams {
+    /// Embeds the ingested documents with the configured embedding model. Defaults to
+    /// `true`; with `false`, they are left to `/embed/new`.
+    pub embed: Option<bool>,
+}
+
+/// The organization context header of search and ingest requests, for the API
+/// documentation. Handlers read it with `auth::org::org_context`.
+#[derive(Debug, Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Header)]
+pub struct OrgHeader {
+    /// An organization the user is a member of. Searches also cover the documents
+    /// shared with it, and ingested documents are shared with it.
+    #[serde(rename = "X-Org-Id")]
+    pub org_id: Option<String>,
+}
+
+/// The envelope of every successful JSON response.
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct ApiResponse<T> {
+    /// Diagnostic details, only present when `?debug=true` is requested.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub debug: Option<Value>,
     pub result: T,
 }
 
+/// The body of every error response.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
 // --- Ingestion Payloads ---
 
 #[derive(Deserialize)]
@@ -8,8 +8,8 @@ mod common;
 use anyhow::Result;
 use anyrag_server::types::ApiResponse;
 use axum::http::StatusCode;
-use common::{generate_jwt, TestApp};
-use core_access::get_or_create_user;
+use common::TestApp;
+use core_access::{get_or_create_user, permissions::set_role_permissions};
 use httpmock::Method;
 use serde_json::{json, Value};
 
@@ -36,7 +36,7 @@ async fn test_get_users_as_root_succeeds() -> Result<()> {
     let _ = get_or_create_user(db, root_user_identifier, Some("root")).await?;
 
     // Generate a token for the root user.
-    let token = generate_jwt(root_user_identifier)?;
+    let token = app.generate_jwt(root_user_identifier).await?;
 
     // --- 2. Act ---
     let response = app
@@ -77,7 +77,7 @@ async fn test_get_users_as_regular_user_fails() -> Result<()> {
         }));
     });
     let regular_user_identifier = "user@example.com";
-    let token = generate_jwt(regular_user_identifier)?;
+    let token = app.generate_jwt(regular_user_identifier).await?;
 
     // --- 2. Act ---
     let response = app
@@ -88,11 +88,10 @@ async fn test_get_users_as_regular_user_fails() -> Result<()> {
         .await?;
 
     // --- 3. Assert ---
-    // The current implementation returns a 500 Internal Server Error for this case.
-    // A 403 Forbidden would be more appropriate, but this test verifies the current behavior.
-    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    // The `user` role lacks the `admin:users` permission.
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
     let body: Value = response.json().await?;
-    assert_eq!(body["error"], "An internal server error occurred.");
+    assert!(body["error"].as_str().unwrap().contains("admin:users"));
 
     Ok(())
 }
@@ -123,9 +122,148 @@ async fn test_get_users_as_guest_fails() -> Result<()> {
         .await?;
 
     // --- 3. Assert ---
-    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
     let body: Value = response.json().await?;
-    assert_eq!(body["error"], "An internal server error occurred.");
+    assert!(body["error"].as_str().unwrap().contains("admin:users"));
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_knowledge_base_stats_as_root() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_knowledge_base_stats_as_root").await?;
+    let db = &app.app_state.sqlite_provider.db;
+    let root = get_or_create_user(db, "root@example.com", Some("root")).await?;
+    let conn = db.connect()?;
+    for (id, source_url) in [
+        ("web-1", "https://docs.example.com/refunds"),
+        ("web-2", "https://docs.example.com/shipping"),
+        ("pdf-1", "file:///tmp/handbook.pdf#chunk_0"),
+    ] {
+        conn.execute(
+            "INSERT INTO documents (id, owner_id, source_url, title, content) VALUES (?, ?, ?, ?, ?)",
+            turso::params![id, root.id.as_str(), source_url, id, "content"],
+        )
+        .await?;
+    }
+    conn.execute(
+        "INSERT INTO document_embeddings (document_id, model_name, embedding) VALUES ('web-1', 'mock-model', ?)",
+        turso::params![[0u8; 16].as_slice()],
+    )
+    .await?;
+    conn.execute(
+        "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_value) VALUES ('pdf-1', ?, 'KEYPHRASE', 'handbook')",
+        turso::params![root.id.as_str()],
+    )
+    .await?;
+    conn.execute(
+        "UPDATE documents SET deleted_at = CURRENT_TIMESTAMP WHERE id = 'web-2'",
+        (),
+    )
+    .await?;
+
+    // --- 2. Act ---
+    let response = app
+        .client
+        .get(format!("{}/admin/stats", app.address))
+        .bearer_auth(app.generate_jwt("root@example.com").await?)
+        .send()
+        .await?;
+
+    // --- 3. Assert ---
+    // The document in the trash is only counted as trashed.
+    assert_eq!(response.status(), StatusCode::OK);
+    let stats: Value = response.json::<ApiResponse<Value>>().await?.result;
+    assert_eq!(stats["documents"], 2);
+    assert_eq!(stats["trashed_documents"], 1);
+    assert_eq!(stats["embedded_documents"], 1);
+    assert_eq!(stats["embedding_coverage"], 0.5);
+    assert_eq!(stats["documents_with_metadata"], 1);
+    assert!(stats["database_size_bytes"].as_i64().unwrap_or_default() > 0);
+    let sources: Vec<&str> = stats["sources"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|source| source["group"].as_str().unwrap())
+        .collect();
+    assert_eq!(sources, vec!["docs.example.com", "file"]);
+    assert_eq!(stats["owners"][0]["group"], root.id.as_str());
+    assert_eq!(stats["owners"][0]["documents"], 2);
+
+    // A regular user lacks the `admin:stats` permission.
+    let response = app
+        .client
+        .get(format!("{}/admin/stats", app.address))
+        .bearer_auth(app.generate_jwt("user@example.com").await?)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_route_permissions_apply_to_every_listed_route() -> Result<()> {
+    // --- 1. Arrange: A role without any permission ---
+    let app = TestApp::spawn("test_route_permissions_apply_to_every_listed_route").await?;
+    let viewer = "viewer@example.com";
+    get_or_create_user(&app.app_state.sqlite_provider.db, viewer, Some("viewer")).await?;
+    let token = app.generate_jwt(viewer).await?;
+
+    // --- 2. Act & Assert: Routes whose handlers don't resolve the user are guarded too ---
+    let requests = [
+        app.client.post(format!("{}/db/query", app.address)),
+        app.client.get(format!("{}/db/annotations", app.address)),
+        app.client.post(format!("{}/db/annotations", app.address)),
+        app.client.delete(format!("{}/db/annotations", app.address)),
+        app.client
+            .get(format!("{}/experiments/baseline/summary", app.address)),
+        app.client.post(format!("{}/prompt", app.address)),
+        app.client.get(format!("{}/orgs", app.address)),
+    ];
+    for request in requests {
+        let response = request.bearer_auth(&token).json(&json!({})).send().await?;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_write_routes_require_the_ingest_permission() -> Result<()> {
+    // --- 1. Arrange: A role that may only read ---
+    let app = TestApp::spawn("test_write_routes_require_the_ingest_permission").await?;
+    let db = &app.app_state.sqlite_provider.db;
+    set_role_permissions(db, "reader", &["search:read".to_string()]).await?;
+    let reader = "reader@example.com";
+    get_or_create_user(db, reader, Some("reader")).await?;
+    let token = app.generate_jwt(reader).await?;
+
+    // --- 2. Act & Assert: Reads are allowed ---
+    let response = app
+        .client
+        .get(format!("{}/collections", app.address))
+        .bearer_auth(&token)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // --- 3. Act & Assert: Writes are not ---
+    let requests = [
+        app.client.post(format!("{}/collections", app.address)),
+        app.client.put(format!("{}/collections/c1", app.address)),
+        app.client.delete(format!("{}/collections/c1", app.address)),
+        app.client.post(format!("{}/orgs", app.address)),
+        app.client.post(format!("{}/orgs/o1/members", app.address)),
+        app.client.delete(format!("{}/documents/d1", app.address)),
+        app.client
+            .post(format!("{}/documents/d1/share", app.address)),
+        app.client
+            .post(format!("{}/knowledge/faq/generate", app.address)),
+    ];
+    for request in requests {
+        let response = request.bearer_auth(&token).json(&json!({})).send().await?;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+    Ok(())
+}
@@ -0,0 +1,144 @@
+//! # API Key Tests
+//!
+//! This file contains integration tests for the `/admin/api-keys` endpoints and for
+//! authenticating with the `X-Api-Key` header, including scope enforcement and
+//! last-used tracking.
+
+mod common;
+
+use anyhow::Result;
+use anyrag_server::types::ApiResponse;
+use axum::http::StatusCode;
+use common::TestApp;
+use core_access::get_or_create_user;
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn test_api_key_lifecycle_and_scopes() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_api_key_lifecycle_and_scopes").await?;
+    let db = &app.app_state.sqlite_provider.db;
+    let root_identifier = "root@example.com";
+    get_or_create_user(db, root_identifier, Some("root")).await?;
+    let service_user = get_or_create_user(db, "ingest-service@example.com", None).await?;
+    let root_token = app.generate_jwt(root_identifier).await?;
+
+    // --- 2. Act: Create an ingest-only key for the service user ---
+    let response = app
+        .client
+        .post(format!("{}/admin/api-keys", app.address))
+        .bearer_auth(&root_token)
+        .json(&json!({
+            "name": "ingest-bot",
+            "scopes": ["ingest"],
+            "owner_id": service_user.id
+        }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: ApiResponse<Value> = response.json().await?;
+    let key = body.result["key"].as_str().unwrap().to_string();
+    let key_id = body.result["api_key"]["id"].as_str().unwrap().to_string();
+    assert_eq!(body.result["api_key"]["scopes"], json!(["ingest"]));
+    assert!(body.result["api_key"]["last_used_at"].is_null());
+
+    // --- 3. Assert: The key authenticates as its owner ---
+    let response = app
+        .client
+        .get(format!("{}/auth/me", app.address))
+        .header("X-Api-Key", &key)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let me: Value = response.json().await?;
+    assert_eq!(me["id"], service_user.id);
+
+    // --- 4. Assert: Routes outside the key's scopes are forbidden ---
+    for path in ["/documents", "/admin/api-keys"] {
+        let response = app
+            .client
+            .get(format!("{}{path}", app.address))
+            .header("X-Api-Key", &key)
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN, "path: {path}");
+    }
+
+    // --- 5. Assert: The listing records the use and never reveals the key ---
+    let response = app
+        .client
+        .get(format!("{}/admin/api-keys", app.address))
+        .bearer_auth(&root_token)
+        .send()
+        .await?;
+    let body: ApiResponse<Value> = response.json().await?;
+    let keys = body.result.as_array().unwrap();
+    assert_eq!(keys.len(), 1);
+    assert!(!keys[0]["last_used_at"].is_null());
+    assert!(keys[0].get("key").is_none());
+    assert!(key.starts_with(keys[0]["key_prefix"].as_str().unwrap()));
+
+    // --- 6. Act: Grant the search scope, then revoke the key ---
+    let response = app
+        .client
+        .put(format!("{}/admin/api-keys/{key_id}", app.address))
+        .bearer_auth(&root_token)
+        .json(&json!({ "scopes": ["ingest", "search"] }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .client
+        .get(format!("{}/documents", app.address))
+        .header("X-Api-Key", &key)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .client
+        .delete(format!("{}/admin/api-keys/{key_id}", app.address))
+        .bearer_auth(&root_token)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // --- 7. Assert: A revoked key is rejected ---
+    let response = app
+        .client
+        .get(format!("{}/auth/me", app.address))
+        .header("X-Api-Key", &key)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_key_management_requires_root() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_api_key_management_requires_root").await?;
+    let token = app.generate_jwt("user@example.com").await?;
+
+    // --- 2. Act ---
+    let response = app
+        .client
+        .post(format!("{}/admin/api-keys", app.address))
+        .bearer_auth(token)
+        .json(&json!({ "name": "sneaky", "scopes": ["admin"] }))
+        .send()
+        .await?;
+
+    // --- 3. Assert ---
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .client
+        .get(format!("{}/auth/me", app.address))
+        .header("X-Api-Key", "ak_not-a-real-key")
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
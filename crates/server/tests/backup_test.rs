@@ -0,0 +1,129 @@
+//! # Backup Endpoint Tests
+//!
+//! This file contains integration tests for backing up the knowledge base and
+//! restoring the backup into a fresh instance.
+
+mod common;
+
+use anyhow::Result;
+use anyrag_server::types::ApiResponse;
+use axum::http::StatusCode;
+use common::TestApp;
+use core_access::get_or_create_user;
+use httpmock::MockServer;
+use serde_json::{json, Value};
+use std::{path::Path, sync::Arc};
+use tempfile::tempdir;
+use turso::Value as TursoValue;
+
+const ROOT_USER: &str = "backup-root@example.com";
+
+/// Spawns an app that keeps its backups in `backup_dir`, with a root user.
+async fn spawn_with_backup_dir(test_case_name: &str, backup_dir: &Path) -> Result<TestApp> {
+    let base = TestApp::spawn(test_case_name).await?;
+    let mut app_state = base.app_state.clone();
+    let mut config = (*app_state.config).clone();
+    config.backup_dir = Some(backup_dir.to_str().unwrap().to_string());
+    app_state.config = Arc::new(config);
+    get_or_create_user(&app_state.sqlite_provider.db, ROOT_USER, Some("root")).await?;
+    TestApp::spawn_with_state(app_state, MockServer::start()).await
+}
+
+async fn count_documents(app: &TestApp) -> Result<i64> {
+    let conn = app.app_state.sqlite_provider.db.connect()?;
+    let mut rows = conn.query("SELECT COUNT(*) FROM documents", ()).await?;
+    match rows.next().await?.map(|row| row.get_value(0)).transpose()? {
+        Some(TursoValue::Integer(count)) => Ok(count),
+        _ => Ok(0),
+    }
+}
+
+#[tokio::test]
+async fn test_backup_restores_into_a_fresh_instance() -> Result<()> {
+    // --- 1. Arrange: An instance with a document and its embedding ---
+    let backup_dir = tempdir()?;
+    let source = spawn_with_backup_dir("test_backup_source", backup_dir.path()).await?;
+    let conn = source.app_state.sqlite_provider.db.connect()?;
+    conn.execute(
+        "INSERT INTO documents (id, title, content) VALUES ('doc-1', 'Roadmap', 'Ship backups')",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "INSERT INTO document_embeddings (document_id, model_name, embedding) VALUES ('doc-1', 'mock-model', X'0000803F')",
+        (),
+    )
+    .await?;
+    let token = source.generate_jwt(ROOT_USER).await?;
+
+    // --- 2. Act: Back up the source instance ---
+    let response = source
+        .client
+        .post(format!("{}/admin/backups", source.address))
+        .bearer_auth(&token)
+        .json(&json!({}))
+        .send()
+        .await?;
+
+    // --- 3. Assert ---
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: ApiResponse<Value> = response.json().await?;
+    assert_eq!(body.result["rows"]["documents"], 1);
+    assert_eq!(body.result["rows"]["document_embeddings"], 1);
+    assert!(body.result["object_url"].is_null());
+    let name = body.result["name"].as_str().unwrap().to_string();
+    assert!(backup_dir.path().join(&name).is_file());
+
+    // --- 4. Act: Restore the backup into a fresh instance ---
+    let target = spawn_with_backup_dir("test_backup_target", backup_dir.path()).await?;
+    let target_token = target.generate_jwt(ROOT_USER).await?;
+    let response = target
+        .client
+        .post(format!("{}/admin/backups/restore", target.address))
+        .bearer_auth(&target_token)
+        .json(&json!({ "name": name }))
+        .send()
+        .await?;
+
+    // --- 5. Assert: The documents are restored, and only once ---
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(count_documents(&target).await?, 1);
+    let response = target
+        .client
+        .post(format!("{}/admin/backups/restore", target.address))
+        .bearer_auth(&target_token)
+        .json(&json!({ "name": name }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_backup_requires_the_backups_permission() -> Result<()> {
+    // --- 1. Arrange ---
+    let backup_dir = tempdir()?;
+    let app = spawn_with_backup_dir("test_backup_requires_permission", backup_dir.path()).await?;
+    let token = app.generate_jwt("backup-user@example.com").await?;
+
+    // --- 2. Act & Assert ---
+    let response = app
+        .client
+        .post(format!("{}/admin/backups", app.address))
+        .bearer_auth(&token)
+        .json(&json!({}))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let root_token = app.generate_jwt(ROOT_USER).await?;
+    let response = app
+        .client
+        .post(format!("{}/admin/backups/restore", app.address))
+        .bearer_auth(&root_token)
+        .json(&json!({ "name": "../anyrag.db" }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    Ok(())
+}
@@ -15,7 +15,7 @@
 
 use anyhow::Result;
 use anyrag::{
-    graph::types::MemoryKnowledgeGraph,
+    graph::store::KnowledgeGraphStore,
     providers::db::{sqlite::SqliteProvider, storage::Storage},
     types::{TableField, TableSchema},
 };
@@ -25,6 +25,7 @@ use anyrag_server::{
     state::{build_app_state, AppState},
 };
 use axum::serve;
+use core_access::{get_or_create_user, sessions::create_session};
 use httpmock::{prelude::*, Method};
 use jsonwebtoken::{encode, EncodingKey, Header};
 use reqwest::Client;
@@ -55,7 +56,7 @@ pub struct TestApp {
     pub db_path: PathBuf,
     pub github_db_dir: PathBuf,
     pub app_state: AppState,
-    pub knowledge_graph: Arc<RwLock<MemoryKnowledgeGraph>>,
+    pub knowledge_graph: Arc<RwLock<KnowledgeGraphStore>>,
     _db_file: Option<NamedTempFile>,
     _config_dir: Option<TempDir>,
     _github_db_dir: Option<TempDir>,
@@ -311,22 +312,40 @@ pub fn get_mock_schema() -> Arc<TableSchema> {
                 description: Some("A floating point value.".to_string()),
             },
         ],
+        foreign_keys: vec![],
     })
 }
 
-/// Generates a valid JWT for a given user identifier (subject).
-pub fn generate_jwt(sub: &str) -> Result<String> {
-    generate_jwt_with_expiry(sub, 3600)
+impl TestApp {
+    /// Starts a session for a given user identifier (subject) and returns a valid JWT of it.
+    pub async fn generate_jwt(&self, sub: &str) -> Result<String> {
+        self.generate_jwt_with_expiry(sub, 3600).await
+    }
+
+    /// Starts a session for a given user identifier (subject) and returns a JWT of it
+    /// with a custom expiration.
+    pub async fn generate_jwt_with_expiry(
+        &self,
+        sub: &str,
+        expires_in_secs: u64,
+    ) -> Result<String> {
+        let db = &self.app_state.sqlite_provider.db;
+        let user = get_or_create_user(db, sub, None).await?;
+        let new_session = create_session(db, &user, sub, chrono::Duration::days(1)).await?;
+        sign_jwt(sub, Some(new_session.session.id), expires_in_secs)
+    }
 }
 
-/// Generates a valid JWT for a given user identifier (subject) with a custom expiration.
-pub fn generate_jwt_with_expiry(sub: &str, expires_in_secs: u64) -> Result<String> {
+/// Signs a JWT with the shared secret. The server only accepts it while the session
+/// `sid` refers to is active.
+pub fn sign_jwt(sub: &str, sid: Option<String>, expires_in_secs: u64) -> Result<String> {
     let expiration = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + expires_in_secs;
     let user_id = Uuid::new_v5(&Uuid::NAMESPACE_URL, sub.as_bytes()).to_string();
     let claims = Claims {
         sub: sub.to_string(),
         exp: expiration as usize,
         user_id,
+        sid,
     };
     let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "a-secure-secret-key".to_string());
     let token = encode(
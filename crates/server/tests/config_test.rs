@@ -127,6 +127,36 @@ tasks:
     assert_eq!(provider.api_key, Some("my_secret_key".to_string()));
 }
 
+#[test]
+#[serial]
+fn test_telemetry_config_defaults() {
+    let _guards = [EnvVarGuard::clear("ANYRAG_EMBEDDING__API_URL")];
+    let fixture = TestFixture::new();
+    let yaml_content = r#"
+embedding:
+  api_url: "http://localhost:1234/v1/embeddings"
+  model_name: "test-embedding-model"
+telemetry:
+  otlp_endpoint: "http://localhost:4317"
+providers:
+  test_provider:
+    provider: "local"
+    api_url: "http://localhost:1234/v1/chat/completions"
+    model_name: "test-chat-model"
+tasks:
+  test_task: { provider: "test_provider", system_prompt: "sys", user_prompt: "user" }
+"#;
+    fixture.create_config_file(yaml_content);
+
+    let config =
+        get_config(Some(&fixture.config_path)).expect("Configuration should load successfully");
+
+    let telemetry = config.telemetry.expect("The telemetry section should load");
+    assert_eq!(telemetry.otlp_endpoint, "http://localhost:4317");
+    assert_eq!(telemetry.service_name, "anyrag-server");
+    assert_eq!(telemetry.sample_ratio, 1.0);
+}
+
 #[test]
 #[serial]
 fn test_env_var_override_and_defaults() {
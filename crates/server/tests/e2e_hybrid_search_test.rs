@@ -11,7 +11,7 @@ mod common;
 
 use anyhow::Result;
 use anyrag_server::types::ApiResponse;
-use common::{generate_jwt, TestApp, TestDataBuilder};
+use common::{TestApp, TestDataBuilder};
 use core_access::get_or_create_user;
 use httpmock::Method;
 use serde_json::{json, Value};
@@ -114,7 +114,7 @@ async fn test_e2e_multi_stage_hybrid_search() -> Result<()> {
     });
 
     // --- 3. Execute the search ---
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
     let response = app
         .client
         .post(format!("{}/search/knowledge", app.address))
@@ -9,7 +9,7 @@ mod common;
 use anyhow::Result;
 use anyrag_server::types::ApiResponse;
 use chrono::{Duration, Utc};
-use common::{generate_jwt, TestApp};
+use common::TestApp;
 use core_access::get_or_create_user;
 use httpmock::Method;
 use serde_json::{json, Value};
@@ -189,7 +189,7 @@ sections:
     });
 
     // --- 2. Act ---
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
     let response = app
         .client
         .post(format!("{}/search/knowledge", app.address))
@@ -349,7 +349,7 @@ sections:
     });
 
     // --- 5. Act & Assert ---
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
 
     // A. Call WITHOUT the Knowledge Graph
     let response_without_kg = app
@@ -102,6 +102,23 @@ async fn test_e2e_prompt_execution() -> Result<()> {
     let result = body["result"]["text"].as_str().unwrap();
 
     assert!(body["debug"].is_object(), "Debug field should be present");
+    let stages: Vec<&str> = body["debug"]["trace"]
+        .as_array()
+        .expect("The pipeline trace should be present")
+        .iter()
+        .map(|step| step["stage"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        stages,
+        [
+            "prompt_sent",
+            "ai_response",
+            "generated_sql",
+            "rows_returned",
+            "formatting_prompt"
+        ]
+    );
+    assert_eq!(body["debug"]["trace"][3]["detail"]["rows"], 1);
     assert!(
         result.contains("27,894"),
         "Response did not contain the expected result."
@@ -7,7 +7,7 @@ mod common;
 
 use anyhow::Result;
 use anyrag_server::types::ApiResponse;
-use common::{generate_jwt, TestApp};
+use common::TestApp;
 use httpmock::Method;
 use serde_json::{json, Value};
 
@@ -15,7 +15,7 @@ use serde_json::{json, Value};
 async fn test_hybrid_search_llm_and_rrf_modes() -> Result<()> {
     // --- 1. Arrange & Setup ---
     let app = TestApp::spawn("test_hybrid_search_llm_and_rrf_modes").await?;
-    let token = generate_jwt("search-test-user@example.com")?;
+    let token = app.generate_jwt("search-test-user@example.com").await?;
 
     // --- 2. Mock External Services ---
 
@@ -153,3 +153,112 @@ async fn test_hybrid_search_llm_and_rrf_modes() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_hybrid_search_hyde_mode_embeds_hypothetical_document() -> Result<()> {
+    // --- 1. Arrange & Setup ---
+    let app = TestApp::spawn("test_hybrid_search_hyde_mode").await?;
+    let token = app.generate_jwt("hyde-test-user@example.com").await?;
+
+    // --- 2. Mock External Services ---
+    let rss_content = r#"
+<rss version="2.0">
+<channel>
+  <title>Test Feed</title>
+  <link>http://mock.com/rss</link>
+  <description>A test feed for AnyRAG.</description>
+  <item>
+    <title>Learning Rust</title>
+    <link>http://mock.com/rust</link>
+    <description>Rust is a systems programming language.</description>
+  </item>
+  <item>
+    <title>Learning Go</title>
+    <link>http://mock.com/go</link>
+    <description>Go is another systems language.</description>
+  </item>
+</channel>
+</rss>
+"#;
+    let rss_mock = app.mock_server.mock(|when, then| {
+        when.method(Method::GET).path("/rss");
+        then.status(200).body(rss_content);
+    });
+
+    let doc_embedding_mock = app.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path("/test_hybrid_search_hyde_mode/v1/embeddings")
+            .body_contains("Learning Rust");
+        then.status(200).json_body(json!({
+            "data": [
+                { "embedding": [0.1, 0.2, 0.3] },
+                { "embedding": [0.4, 0.5, 0.6] }
+            ]
+        }));
+    });
+
+    // The LLM writes a hypothetical answer for the short query.
+    let hypothetical_document = "Go, also known as golang, is a statically typed systems language.";
+    let hyde_mock = app.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path("/test_hybrid_search_hyde_mode/v1/chat/completions")
+            .body_contains("used only for semantic search"); // Match the HyDE system prompt
+        then.status(200).json_body(json!({
+            "choices": [{
+                "message": { "role": "assistant", "content": hypothetical_document }
+            }]
+        }));
+    });
+
+    // The hypothetical document, not the raw query, must be embedded.
+    let hyde_embedding_mock = app.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path("/test_hybrid_search_hyde_mode/v1/embeddings")
+            .body_contains("statically typed systems language");
+        then.status(200)
+            .json_body(json!({ "data": [{ "embedding": [0.4, 0.5, 0.6] }] }));
+    });
+
+    // --- 3. Act: Ingest and Embed ---
+    app.client
+        .post(format!("{}/ingest/rss", app.address))
+        .bearer_auth(token.clone())
+        .json(&json!({ "url": app.mock_server.url("/rss") }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    app.client
+        .post(format!("{}/embed/new", app.address))
+        .json(&json!({ "limit": 2 }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    // --- 4. Act: Search in HyDE mode ---
+    let hyde_search_res = app
+        .client
+        .post(format!("{}/search/hybrid", app.address))
+        .bearer_auth(token.clone())
+        .json(&json!({
+            "query": "golang",
+            "mode": "hyde"
+        }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let hyde_body: ApiResponse<Value> = hyde_search_res.json().await?;
+    let hyde_results = hyde_body.result.as_array().unwrap();
+
+    // --- 5. Assert ---
+    assert_eq!(hyde_results.len(), 2);
+    assert_eq!(hyde_results[0]["title"], "Learning Go");
+
+    rss_mock.assert();
+    doc_embedding_mock.assert();
+    hyde_mock.assert();
+    hyde_embedding_mock.assert();
+
+    Ok(())
+}
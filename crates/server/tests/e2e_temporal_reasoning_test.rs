@@ -9,7 +9,7 @@ mod common;
 
 use anyhow::Result;
 use anyrag_server::types::ApiResponse;
-use common::{generate_jwt, TestApp, TestDataBuilder};
+use common::{TestApp, TestDataBuilder};
 use core_access::get_or_create_user;
 use httpmock::Method;
 use serde_json::{json, Value};
@@ -123,7 +123,7 @@ async fn test_knowledge_search_with_temporal_reasoning() -> Result<()> {
     });
 
     // --- 4. Act: Perform a RAG search with a temporal keyword ---
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
     let search_response = app
         .client
         .post(format!("{}/search/knowledge", app.address))
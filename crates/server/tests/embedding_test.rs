@@ -7,7 +7,7 @@
 mod common;
 
 use anyhow::Result;
-use common::{generate_jwt, TestApp};
+use common::TestApp;
 use httpmock::Method;
 use serde_json::json;
 
@@ -18,7 +18,7 @@ async fn test_embed_and_search_flow() -> Result<()> {
     // --- 1. Arrange ---
     let app = TestApp::spawn("test_embed_and_search_flow").await?;
     let user_identifier = "embed-test-user@example.com";
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
 
     let rss_mock = app.mock_server.mock(|when, then| {
         when.method(Method::GET).path("/rss");
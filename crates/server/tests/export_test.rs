@@ -0,0 +1,129 @@
+//! # Document Export Endpoint Tests
+//!
+//! This file contains integration tests for exporting documents, with their metadata
+//! and embeddings, scoped to the documents the caller may export.
+
+mod common;
+
+use anyhow::Result;
+use axum::http::StatusCode;
+use common::{TestApp, TestDataBuilder};
+use core_access::get_or_create_user;
+use serde_json::Value;
+
+const USER_A: &str = "export-user-a@example.com";
+const USER_B: &str = "export-user-b@example.com";
+
+/// Seeds one document with metadata and an embedding for each of two users.
+async fn seed_data(app: &TestApp) -> Result<(String, String)> {
+    let db = &app.app_state.sqlite_provider.db;
+    let user_a = get_or_create_user(db, USER_A, None).await?;
+    let user_b = get_or_create_user(db, USER_B, None).await?;
+
+    let builder = TestDataBuilder::new(app).await?;
+    builder
+        .add_document("doc_a", &user_a.id, "Doc A", "Owned by A.", None)
+        .await?
+        .add_metadata("doc_a", &user_a.id, "KEYPHRASE", "CONCEPT", "export")
+        .await?
+        .add_embedding("doc_a", vec![1.0, 0.0, 0.0])
+        .await?;
+    builder
+        .add_document("doc_b", &user_b.id, "Doc B", "Owned by B.", None)
+        .await?
+        .add_embedding("doc_b", vec![0.0, 1.0, 0.0])
+        .await?;
+    Ok((user_a.id, user_b.id))
+}
+
+#[tokio::test]
+async fn test_export_returns_only_own_documents_as_jsonl() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_export_returns_only_own_documents_as_jsonl").await?;
+    let (user_a_id, _) = seed_data(&app).await?;
+    let token = app.generate_jwt(USER_A).await?;
+
+    // --- 2. Act ---
+    let response = app
+        .client
+        .get(format!("{}/documents/export?format=jsonl", app.address))
+        .bearer_auth(token)
+        .send()
+        .await?;
+
+    // --- 3. Assert ---
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-type"].to_str()?,
+        "application/x-ndjson"
+    );
+    let body = response.text().await?;
+    let documents: Vec<Value> = body
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(documents.len(), 1);
+    assert_eq!(documents[0]["id"], "doc_a");
+    assert_eq!(documents[0]["owner_id"], user_a_id.as_str());
+    assert_eq!(documents[0]["metadata"][0]["metadata_value"], "export");
+    assert_eq!(
+        documents[0]["embeddings"][0]["vector"],
+        serde_json::json!([1.0, 0.0, 0.0])
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_of_another_users_documents_is_forbidden() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_export_of_another_users_documents_is_forbidden").await?;
+    let (_, user_b_id) = seed_data(&app).await?;
+    let token = app.generate_jwt(USER_A).await?;
+
+    // --- 2. Act ---
+    let response = app
+        .client
+        .get(format!(
+            "{}/documents/export?owner_id={user_b_id}",
+            app.address
+        ))
+        .bearer_auth(token)
+        .send()
+        .await?;
+
+    // --- 3. Assert ---
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_exports_documents_of_any_user() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_admin_exports_documents_of_any_user").await?;
+    let (_, user_b_id) = seed_data(&app).await?;
+    let root = "export-root@example.com";
+    get_or_create_user(&app.app_state.sqlite_provider.db, root, Some("root")).await?;
+    let token = app.generate_jwt(root).await?;
+
+    // --- 2. Act ---
+    let response = app
+        .client
+        .get(format!(
+            "{}/documents/export?owner_id={user_b_id}",
+            app.address
+        ))
+        .bearer_auth(token)
+        .send()
+        .await?;
+
+    // --- 3. Assert ---
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.text().await?;
+    let documents: Vec<Value> = body
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(documents.len(), 1);
+    assert_eq!(documents[0]["id"], "doc_b");
+    Ok(())
+}
@@ -12,7 +12,7 @@ use anyhow::Result;
 use anyrag::prompts::{knowledge, tasks};
 use anyrag_server::types::ApiResponse;
 use anyrag_test_utils::helpers::generate_test_pdf;
-use common::{generate_jwt, TestApp};
+use common::TestApp;
 use httpmock::Method;
 use serde_json::{json, Value};
 
@@ -23,7 +23,7 @@ async fn test_unified_pdf_ingestion_and_rag_workflow() -> Result<()> {
     // --- 1. Arrange & Setup ---
     let test_name = "test_unified_pdf_ingestion_and_rag_workflow";
     let app = TestApp::spawn(test_name).await?;
-    let token = generate_jwt("unified-ingest-user@example.com")?;
+    let token = app.generate_jwt("unified-ingest-user@example.com").await?;
 
     let pdf_content = "The magic word is AnyRAG. It is a powerful framework.";
     let pdf_data = generate_test_pdf(pdf_content)?;
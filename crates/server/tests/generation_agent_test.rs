@@ -11,7 +11,7 @@ use core_access::get_or_create_user;
 use httpmock::Method;
 use serde_json::{json, Value};
 
-use crate::common::{generate_jwt, TestApp, TestDataBuilder};
+use crate::common::{TestApp, TestDataBuilder};
 
 #[tokio::test]
 async fn test_gen_text_with_explicit_knowledge_search() -> Result<()> {
@@ -84,7 +84,7 @@ sections:
     });
 
     // --- 3. Execute the /gen/text request ---
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
     // Explicitly tell the handler to use knowledge_search, bypassing the agent.
     let payload = json!({
         "context_prompt": context_prompt,
@@ -172,7 +172,7 @@ async fn test_gen_tx_handler() -> Result<()> {
     });
 
     // --- 3. Execute the /gen/tx request ---
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
     let payload = json!({
         "context_prompt": "---\n\nCURRENT ON-CHAIN CONTEXT:\naccount_states:\n  RECIPIENT_USDC_ATA:\n    lamports: 2039280\n  USER_USDC_ATA:\n    lamports: 2039280\n  USER_WALLET_PUBKEY:\n    lamports: 1000000000\nkey_map:\n  RECIPIENT_USDC_ATA: 7aVgJrZvZ6wTayTR3CVYPqLCNBGw1pB5aUbaqx6RijYX\n  USER_USDC_ATA: 6nrJ4TdMSMz4omJQA6R5c3TDnfQ1UoBJ1ux7UGsB2pcv\n  USER_WALLET_PUBKEY: 3i7ijk5nAZwWzKvduAehYXJDu9SnLanEKyrtr9Ru382E\n\n\n---",
         "generation_prompt": "Please send 15 USDC from my token account (USER_USDC_ATA) to the recipient's token account (RECIPIENT_USDC_ATA). The mint is MOCK_USDC_MINT, and I am the authority (USER_WALLET_PUBKEY)."
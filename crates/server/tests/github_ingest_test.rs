@@ -9,7 +9,7 @@ mod common;
 use anyhow::Result;
 use anyrag_github::ingest::storage::StorageManager;
 use anyrag_server::types::ApiResponse;
-use common::{generate_jwt, TestApp};
+use common::TestApp;
 use httpmock::Method;
 use serde_json::{json, Value};
 use std::fs;
@@ -117,7 +117,7 @@ version = "0.1.0-test"
 
     let app = TestApp::spawn("test_github_ingestion_e2e_workflow").await?;
     let user_identifier = "github-ingest-user@example.com";
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
 
     // --- 2. Mock Services ---
     info!("Setting up mocks");
@@ -197,7 +197,7 @@ async fn test_get_examples_endpoint_success() -> Result<()> {
     // --- 1. Arrange & Setup ---
     let app = TestApp::spawn("test_get_examples_endpoint_success").await?;
     let user_identifier = "get-examples-user@example.com";
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
 
     let remote_repo_dir = tempdir()?;
     let remote_repo_path = remote_repo_dir.path();
@@ -317,7 +317,7 @@ async fn test_search_examples_e2e() -> Result<()> {
     // --- 1. Arrange & Setup ---
     let app = TestApp::spawn("test_search_examples_e2e").await?;
     let user_identifier = "search-examples-user@example.com";
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
 
     let remote_repo_dir = tempdir()?;
     let remote_repo_path = remote_repo_dir.path();
@@ -9,7 +9,7 @@ mod common;
 use anyhow::Result;
 use anyrag_github::ingest::storage::StorageManager;
 use anyrag_server::types::ApiResponse;
-use common::{generate_jwt, TestApp};
+use common::TestApp;
 use httpmock::Method;
 use serde_json::{json, Value};
 use std::fs;
@@ -94,7 +94,7 @@ async fn test_search_across_multiple_repos_e2e() -> Result<()> {
     // --- 1. Arrange & Setup ---
     let app = TestApp::spawn("test_search_across_multiple_repos_e2e").await?;
     let user_identifier = "github-search-user@example.com";
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
 
     // A. Mock the embedding API for the ingestion process. It will be called for each repo.
     let mut ingest_embedding_mock = app.mock_server.mock(|when, then| {
@@ -230,6 +230,11 @@ async fn test_search_across_multiple_repos_e2e() -> Result<()> {
         top_result["link"].as_str().unwrap().contains("src/db.rs"),
         "The result link should point to the correct file"
     );
+    assert_eq!(
+        top_result["repository"], repo_a_name,
+        "The result should name the repository it was found in"
+    );
+    assert_eq!(top_result["version"], "v1.0.0");
 
     Ok(())
 }
@@ -9,7 +9,8 @@ mod common;
 use anyhow::Result;
 use anyrag_server::types::ApiResponse;
 use chrono::{Duration, Utc};
-use common::TestApp;
+use common::{TestApp, TestDataBuilder};
+use core_access::get_or_create_user;
 use httpmock::Method;
 use serde_json::{json, Value};
 
@@ -85,3 +86,310 @@ async fn test_knowledge_graph_endpoint_e2e() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+#[cfg(feature = "graph_db")]
+async fn test_graph_query_endpoints_e2e() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_graph_query_endpoints_e2e").await?;
+    let now = Utc::now();
+    let past = (now - Duration::days(10), now - Duration::days(5));
+    let current = (now - Duration::days(1), now + Duration::days(1));
+    {
+        let mut kg = app
+            .knowledge_graph
+            .write()
+            .expect("Failed to get write lock on knowledge graph");
+        kg.add_fact("Alice", "works_at", "Acme", current.0, current.1)?;
+        kg.add_fact("Acme", "located_in", "Berlin", current.0, current.1)?;
+        kg.add_fact("Alice", "role", "Developer", past.0, past.1)?;
+    }
+
+    // --- 2. Act & Assert: neighbors ---
+    let response = app
+        .client
+        .post(format!("{}/graph/neighbors?debug=true", app.address))
+        .json(&json!({ "entity": "Alice" }))
+        .send()
+        .await?;
+    assert!(response.status().is_success());
+    let body: ApiResponse<Value> = response.json().await?;
+    assert_eq!(body.result["nodes"].as_array().unwrap().len(), 3);
+    assert_eq!(body.result["edges"].as_array().unwrap().len(), 2);
+    assert_eq!(body.debug.unwrap()["nodes"], 3);
+
+    // --- Shortest path ---
+    let response = app
+        .client
+        .post(format!("{}/graph/path", app.address))
+        .json(&json!({ "from": "Alice", "to": "Berlin" }))
+        .send()
+        .await?;
+    assert!(response.status().is_success());
+    let body: ApiResponse<Value> = response.json().await?;
+    assert_eq!(body.result["found"], true);
+    let names: Vec<&str> = body.result["path"]["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|node| node["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["Alice", "Acme", "Berlin"]);
+
+    // --- Facts as of a past date ---
+    let response = app
+        .client
+        .post(format!("{}/graph/facts", app.address))
+        .json(&json!({ "entity": "Alice", "as_of": now - Duration::days(7) }))
+        .send()
+        .await?;
+    assert!(response.status().is_success());
+    let body: ApiResponse<Value> = response.json().await?;
+    let facts = body.result["facts"].as_array().unwrap();
+    assert_eq!(facts.len(), 1, "Only the past role was valid then.");
+    assert_eq!(facts[0]["object"], "Developer");
+
+    // --- Unknown entity ---
+    let response = app
+        .client
+        .post(format!("{}/graph/neighbors", app.address))
+        .json(&json!({ "entity": "Nobody" }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "graph_db")]
+async fn test_graph_query_endpoints_hide_facts_of_private_documents_e2e() -> Result<()> {
+    use anyrag::graph::extraction::ExtractedFact;
+
+    // --- 1. Arrange: facts extracted from a document private to user A ---
+    let app =
+        TestApp::spawn("test_graph_query_endpoints_hide_facts_of_private_documents_e2e").await?;
+    let db = &app.app_state.sqlite_provider.db;
+    let user_a = get_or_create_user(db, "user_a@example.com", None).await?;
+    get_or_create_user(db, "user_b@example.com", None).await?;
+    TestDataBuilder::new(&app)
+        .await?
+        .add_document(
+            "private_doc",
+            &user_a.id,
+            "Salaries",
+            "Bob earns 100k.",
+            None,
+        )
+        .await?;
+    {
+        let mut kg = app
+            .knowledge_graph
+            .write()
+            .expect("Failed to get write lock on knowledge graph");
+        let facts = [ExtractedFact {
+            subject: "Bob".to_string(),
+            predicate: "salary".to_string(),
+            object: "100k".to_string(),
+            valid_from: None,
+            valid_to: None,
+        }];
+        assert_eq!(kg.add_document_facts("private_doc", &facts), 1);
+    }
+    let user_a_token = app.generate_jwt("user_a@example.com").await?;
+    let user_b_token = app.generate_jwt("user_b@example.com").await?;
+
+    // --- 2. Act & Assert: the owner of the document sees the fact ---
+    let response = app
+        .client
+        .post(format!("{}/graph/facts", app.address))
+        .bearer_auth(&user_a_token)
+        .json(&json!({ "entity": "Bob" }))
+        .send()
+        .await?;
+    assert!(response.status().is_success());
+    let body: ApiResponse<Value> = response.json().await?;
+    let facts = body.result["facts"].as_array().unwrap();
+    assert_eq!(facts.len(), 1);
+    assert_eq!(facts[0]["object"], "100k");
+    assert_eq!(facts[0]["source_documents"], json!(["private_doc"]));
+
+    // --- Another user does not find the entity at all ---
+    for (path, payload) in [
+        ("graph/facts", json!({ "entity": "Bob" })),
+        ("graph/neighbors", json!({ "entity": "Bob" })),
+    ] {
+        let response = app
+            .client
+            .post(format!("{}/{path}", app.address))
+            .bearer_auth(&user_b_token)
+            .json(&payload)
+            .send()
+            .await?;
+        assert_eq!(
+            response.status(),
+            reqwest::StatusCode::NOT_FOUND,
+            "{path} should not reveal the private fact."
+        );
+    }
+
+    // --- Nor a path through the private fact ---
+    let response = app
+        .client
+        .post(format!("{}/graph/path", app.address))
+        .bearer_auth(&user_b_token)
+        .json(&json!({ "from": "Bob", "to": "100k" }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "graph_db")]
+async fn test_graph_question_is_answered_from_facts_e2e() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_graph_question_is_answered_from_facts_e2e").await?;
+    let chat_path = "/test_graph_question_is_answered_from_facts_e2e/v1/chat/completions";
+    let query_mock = app.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(chat_path)
+            .body_contains("knowledge graph query planner");
+        then.status(200).json_body(json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": "{\"entity\": \"Alice\", \"predicate\": \"role\", \"as_of\": null}"
+                }
+            }]
+        }));
+    });
+    let answer_mock = app.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(chat_path)
+            .body_contains("Lead Developer");
+        then.status(200).json_body(json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": "Alice is a Lead Developer."
+                }
+            }]
+        }));
+    });
+
+    let now = Utc::now();
+    {
+        let mut kg = app
+            .knowledge_graph
+            .write()
+            .expect("Failed to get write lock on knowledge graph");
+        kg.add_fact(
+            "Alice",
+            "role",
+            "Lead Developer",
+            now - Duration::days(1),
+            now + Duration::days(1),
+        )?;
+    }
+
+    // --- 2. Act ---
+    let response = app
+        .client
+        .post(format!("{}/search/graph", app.address))
+        .json(&json!({ "query": "What is Alice's role?" }))
+        .send()
+        .await?;
+
+    // --- 3. Assert ---
+    assert!(response.status().is_success());
+    let body: ApiResponse<Value> = response.json().await?;
+    assert_eq!(body.result["text"], "Alice is a Lead Developer.");
+    query_mock.assert();
+    answer_mock.assert();
+
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "graph_db")]
+async fn test_graph_question_is_not_answered_from_private_facts_e2e() -> Result<()> {
+    use anyrag::graph::extraction::ExtractedFact;
+
+    // --- 1. Arrange: a fact extracted from a document private to user A ---
+    let app = TestApp::spawn("test_graph_question_is_not_answered_from_private_facts_e2e").await?;
+    let chat_path =
+        "/test_graph_question_is_not_answered_from_private_facts_e2e/v1/chat/completions";
+    let query_mock = app.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(chat_path)
+            .body_contains("knowledge graph query planner");
+        then.status(200).json_body(json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": "{\"entity\": \"Bob\", \"predicate\": \"salary\", \"as_of\": null}"
+                }
+            }]
+        }));
+    });
+    let answer_mock = app.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(chat_path)
+            .body_contains("100k");
+        then.status(200).json_body(json!({
+            "choices": [{
+                "message": { "role": "assistant", "content": "Bob earns 100k." }
+            }]
+        }));
+    });
+
+    let db = &app.app_state.sqlite_provider.db;
+    let user_a = get_or_create_user(db, "user_a@example.com", None).await?;
+    TestDataBuilder::new(&app)
+        .await?
+        .add_document(
+            "private_doc",
+            &user_a.id,
+            "Salaries",
+            "Bob earns 100k.",
+            None,
+        )
+        .await?;
+    {
+        let mut kg = app
+            .knowledge_graph
+            .write()
+            .expect("Failed to get write lock on knowledge graph");
+        let facts = [ExtractedFact {
+            subject: "Bob".to_string(),
+            predicate: "salary".to_string(),
+            object: "100k".to_string(),
+            valid_from: None,
+            valid_to: None,
+        }];
+        kg.add_document_facts("private_doc", &facts);
+    }
+    let user_b_token = app.generate_jwt("user_b@example.com").await?;
+
+    // --- 2. Act ---
+    let response = app
+        .client
+        .post(format!("{}/search/graph", app.address))
+        .bearer_auth(&user_b_token)
+        .json(&json!({ "query": "What is Bob's salary?" }))
+        .send()
+        .await?;
+
+    // --- 3. Assert: the private fact never reaches the answer synthesis ---
+    let body = response.text().await?;
+    assert!(
+        !body.contains("100k"),
+        "The answer leaked a private fact: {body}"
+    );
+    query_mock.assert();
+    answer_mock.assert_hits(0);
+
+    Ok(())
+}
@@ -8,7 +8,7 @@ mod common;
 
 use anyhow::Result;
 use anyrag_server::types::ApiResponse;
-use common::{generate_jwt, TestApp, TestDataBuilder};
+use common::{TestApp, TestDataBuilder};
 use core_access::get_or_create_user;
 use httpmock::Method;
 use serde_json::{json, Value};
@@ -130,7 +130,7 @@ sections:
     });
 
     // --- 5. Execute Hybrid RAG Search and Verify ---
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
     let search_res = app
         .client
         .post(format!("{}/search/knowledge", app.address))
@@ -0,0 +1,97 @@
+//! # Metrics Tests
+//!
+//! This file contains integration tests for the Prometheus `/metrics` endpoint and
+//! the request, embedding and SQLite metrics it exposes.
+
+mod common;
+
+use anyhow::Result;
+use anyrag_server::metrics::PROMETHEUS_CONTENT_TYPE;
+use axum::http::{header, StatusCode};
+use common::TestApp;
+use httpmock::Method;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_metrics_endpoint_reports_requests_and_embeddings() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_metrics_endpoint_reports_requests_and_embeddings").await?;
+    let embeddings_mock = app.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path("/test_metrics_endpoint_reports_requests_and_embeddings/v1/embeddings");
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .json_body(json!({ "data": [{ "embedding": [0.1, 0.2, 0.3, 0.4] }] }));
+    });
+
+    // --- 2. Act: Make a plain request and one that embeds the query ---
+    let response = app
+        .client
+        .get(format!("{}/health", app.address))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .client
+        .post(format!("{}/search/vector", app.address))
+        .bearer_auth(app.generate_jwt("metrics-user@example.com").await?)
+        .json(&json!({ "query": "What is on the roadmap?" }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    embeddings_mock.assert();
+
+    // --- 3. Assert: The metrics are rendered without authentication ---
+    let response = app
+        .client
+        .get(format!("{}/metrics", app.address))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        PROMETHEUS_CONTENT_TYPE
+    );
+    let body = response.text().await?;
+    assert!(body.contains("anyrag_http_requests_total"));
+    assert!(body.contains("anyrag_http_request_duration_seconds_bucket"));
+    assert!(body.contains(r#"route="/health""#));
+    assert!(body.contains(r#"route="/search/vector""#));
+    assert!(body.contains("anyrag_embedding_inputs_total"));
+    assert!(body.contains(r#"model="mock-embedding-model""#));
+    assert!(body.contains("anyrag_sqlite_size_bytes"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_metrics_report_ingestion_through_per_source_routes() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_metrics_report_ingestion_through_per_source_routes").await?;
+
+    // --- 2. Act: Ingest through the text route rather than `POST /ingest` ---
+    let response = app
+        .client
+        .post(format!("{}/ingest/text", app.address))
+        .bearer_auth(app.generate_jwt("metrics-ingest-user@example.com").await?)
+        .json(&json!({ "text": "Metrics cover every ingest route.", "source": "metrics" }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // --- 3. Assert: The run is recorded under its source type ---
+    let body = app
+        .client
+        .get(format!("{}/metrics", app.address))
+        .send()
+        .await?
+        .text()
+        .await?;
+    let recorded = |metric: &str| {
+        body.lines()
+            .any(|line| line.starts_with(metric) && line.contains(r#"source_type="text""#))
+    };
+    assert!(recorded("anyrag_ingest_runs_total"));
+    assert!(recorded("anyrag_ingest_duration_seconds_bucket"));
+    assert!(recorded("anyrag_ingest_documents_total"));
+    Ok(())
+}
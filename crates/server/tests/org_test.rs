@@ -0,0 +1,266 @@
+//! # Organization Tests
+//!
+//! This file contains integration tests for organizations. It verifies that documents
+//! ingested with an `X-Org-Id` header are shared with the organization, that its
+//! members can search them, and that users outside the organization can neither use
+//! it as their context nor manage its members.
+
+mod common;
+
+use anyhow::Result;
+use anyrag_server::types::ApiResponse;
+use axum::http::StatusCode;
+use common::TestApp;
+use core_access::get_or_create_user;
+use httpmock::Method;
+use serde_json::{json, Value};
+
+const ORG_ID_HEADER: &str = "X-Org-Id";
+
+/// Runs a keyword search as the given user, optionally within an organization.
+async fn keyword_search(
+    app: &TestApp,
+    token: &str,
+    org_id: Option<&str>,
+) -> Result<reqwest::Response> {
+    let mut request = app
+        .client
+        .post(format!("{}/search/keyword", app.address))
+        .bearer_auth(token)
+        .json(&json!({ "query": "quarterly roadmap" }));
+    if let Some(org_id) = org_id {
+        request = request.header(ORG_ID_HEADER, org_id);
+    }
+    Ok(request.send().await?)
+}
+
+#[tokio::test]
+async fn test_org_documents_are_shared_with_members() -> Result<()> {
+    // --- 1. Arrange ---
+    let test_case_name = "test_org_documents_are_shared_with_members";
+    let app = TestApp::spawn(test_case_name).await?;
+    // This test doesn't call the AI, but a placeholder mock is needed for stable app startup.
+    app.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(format!("/{test_case_name}/v1/chat/completions"));
+        then.status(200)
+            .json_body(json!({"choices": [{"message": {"role": "assistant", "content": "OK"}}]}));
+    });
+    let db = &app.app_state.sqlite_provider.db;
+    get_or_create_user(db, "alice@example.com", None).await?;
+    let bob = get_or_create_user(db, "bob@example.com", None).await?;
+    get_or_create_user(db, "carol@example.com", None).await?;
+    let alice_token = app.generate_jwt("alice@example.com").await?;
+    let bob_token = app.generate_jwt("bob@example.com").await?;
+    let carol_token = app.generate_jwt("carol@example.com").await?;
+
+    // --- 2. Act: Alice creates an organization with Bob and ingests into it ---
+    let response = app
+        .client
+        .post(format!("{}/orgs", app.address))
+        .bearer_auth(&alice_token)
+        .json(&json!({ "name": "Planning" }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: ApiResponse<Value> = response.json().await?;
+    let org_id = body.result["id"].as_str().unwrap().to_string();
+
+    let response = app
+        .client
+        .post(format!("{}/orgs/{org_id}/members", app.address))
+        .bearer_auth(&alice_token)
+        .json(&json!({ "user_id": bob.id }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: ApiResponse<Value> = response.json().await?;
+    assert_eq!(body.result["role"], "member");
+
+    let response = app
+        .client
+        .post(format!("{}/ingest", app.address))
+        .bearer_auth(&alice_token)
+        .header(ORG_ID_HEADER, &org_id)
+        .json(&json!({
+            "source_type": "text",
+            "source": { "text": "The quarterly roadmap is final.", "source": "org_test" }
+        }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // --- 3. Assert: Bob finds the document within the organization only ---
+    let response = keyword_search(&app, &bob_token, Some(&org_id)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: ApiResponse<Vec<Value>> = response.json().await?;
+    assert_eq!(body.result.len(), 1);
+    assert!(body.result[0]["description"]
+        .as_str()
+        .unwrap()
+        .contains("quarterly roadmap"));
+
+    let body: ApiResponse<Vec<Value>> =
+        keyword_search(&app, &bob_token, None).await?.json().await?;
+    assert!(body.result.is_empty());
+
+    let response = app
+        .client
+        .get(format!("{}/documents", app.address))
+        .bearer_auth(&bob_token)
+        .send()
+        .await?;
+    let body: ApiResponse<Vec<Value>> = response.json().await?;
+    assert_eq!(body.result.len(), 1);
+    assert_eq!(body.result[0]["org_id"], org_id.as_str());
+
+    // --- 4. Assert: Carol cannot use the organization as her context ---
+    let response = keyword_search(&app, &carol_token, Some(&org_id)).await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // --- 5. Act & Assert: A removed member loses access ---
+    let response = app
+        .client
+        .delete(format!("{}/orgs/{org_id}/members/{}", app.address, bob.id))
+        .bearer_auth(&alice_token)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = keyword_search(&app, &bob_token, Some(&org_id)).await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_org_membership_management_is_restricted() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_org_membership_management_is_restricted").await?;
+    let db = &app.app_state.sqlite_provider.db;
+    get_or_create_user(db, "owner@example.com", None).await?;
+    let member = get_or_create_user(db, "member@example.com", None).await?;
+    let owner_token = app.generate_jwt("owner@example.com").await?;
+    let member_token = app.generate_jwt("member@example.com").await?;
+
+    let response = app
+        .client
+        .post(format!("{}/orgs", app.address))
+        .bearer_auth(&owner_token)
+        .json(&json!({ "name": "Ops" }))
+        .send()
+        .await?;
+    let body: ApiResponse<Value> = response.json().await?;
+    let org_id = body.result["id"].as_str().unwrap().to_string();
+    app.client
+        .post(format!("{}/orgs/{org_id}/members", app.address))
+        .bearer_auth(&owner_token)
+        .json(&json!({ "user_id": member.id }))
+        .send()
+        .await?;
+
+    // --- 2. Act & Assert: Members cannot add others, and guests cannot create orgs ---
+    let response = app
+        .client
+        .post(format!("{}/orgs/{org_id}/members", app.address))
+        .bearer_auth(&member_token)
+        .json(&json!({ "user_id": member.id, "role": "owner" }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .client
+        .post(format!("{}/orgs", app.address))
+        .json(&json!({ "name": "Anonymous" }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // --- 3. Act & Assert: Members see the organization and may leave it ---
+    let response = app
+        .client
+        .get(format!("{}/orgs", app.address))
+        .bearer_auth(&member_token)
+        .send()
+        .await?;
+    let body: ApiResponse<Vec<Value>> = response.json().await?;
+    assert_eq!(body.result.len(), 1);
+    assert_eq!(body.result[0]["name"], "Ops");
+
+    let response = app
+        .client
+        .delete(format!(
+            "{}/orgs/{org_id}/members/{}",
+            app.address, member.id
+        ))
+        .bearer_auth(&member_token)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .client
+        .get(format!("{}/orgs/{org_id}/members", app.address))
+        .bearer_auth(&member_token)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_per_source_ingest_shares_documents_with_the_org() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_per_source_ingest_shares_documents_with_the_org").await?;
+    let db = &app.app_state.sqlite_provider.db;
+    get_or_create_user(db, "dana@example.com", None).await?;
+    let erin = get_or_create_user(db, "erin@example.com", None).await?;
+    get_or_create_user(db, "frank@example.com", None).await?;
+    let dana_token = app.generate_jwt("dana@example.com").await?;
+    let erin_token = app.generate_jwt("erin@example.com").await?;
+    let frank_token = app.generate_jwt("frank@example.com").await?;
+
+    let response = app
+        .client
+        .post(format!("{}/orgs", app.address))
+        .bearer_auth(&dana_token)
+        .json(&json!({ "name": "Research" }))
+        .send()
+        .await?;
+    let body: ApiResponse<Value> = response.json().await?;
+    let org_id = body.result["id"].as_str().unwrap().to_string();
+    app.client
+        .post(format!("{}/orgs/{org_id}/members", app.address))
+        .bearer_auth(&dana_token)
+        .json(&json!({ "user_id": erin.id }))
+        .send()
+        .await?;
+
+    // --- 2. Act: Dana ingests through the text route within the organization ---
+    let ingest_text = |token: &str| {
+        app.client
+            .post(format!("{}/ingest/text", app.address))
+            .bearer_auth(token)
+            .header(ORG_ID_HEADER, &org_id)
+            .json(&json!({ "text": "The quarterly roadmap is final.", "source": "org_text" }))
+            .send()
+    };
+    let response = ingest_text(&dana_token).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // --- 3. Assert: Erin sees the shared document, and Frank cannot ingest into it ---
+    let response = app
+        .client
+        .get(format!("{}/documents", app.address))
+        .bearer_auth(&erin_token)
+        .send()
+        .await?;
+    let body: ApiResponse<Vec<Value>> = response.json().await?;
+    assert_eq!(body.result.len(), 1);
+    assert_eq!(body.result[0]["org_id"], org_id.as_str());
+
+    let response = ingest_text(&frank_token).await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    Ok(())
+}
@@ -6,16 +6,23 @@
 //! 3. The search endpoint correctly filters results, allowing authenticated users to see
 //!    their own content plus guest content, while guest users see only guest content.
 //! 4. Requests with an invalid token are rejected.
+//! 5. A document shared with another user is visible to them until the share is revoked.
+//! 6. A prompt routed to the knowledge route answers from the caller's documents.
 
 mod common;
 
 use anyhow::Result;
+use anyrag::{
+    routing::{RouteConfig, RoutingConfig},
+    AnyragExecutor, Route,
+};
 use anyrag_server::types::ApiResponse;
 use axum::http::StatusCode;
-use common::{generate_jwt_with_expiry, TestApp, TestDataBuilder};
+use common::{TestApp, TestDataBuilder};
 use core_access::{get_or_create_user, GUEST_USER_IDENTIFIER};
-use httpmock::Method;
+use httpmock::{Method, MockServer};
 use serde_json::{json, Value};
+use std::sync::Arc;
 
 /// Seeds the database with documents owned by different users and the guest user.
 async fn seed_data(app: &TestApp) -> Result<()> {
@@ -135,7 +142,9 @@ async fn test_authenticated_user_a_sees_own_and_guest_content() -> Result<()> {
     });
 
     // --- 3. Execute search with a valid JWT for User A ---
-    let token = generate_jwt_with_expiry(user_a_identifier, 3600)?;
+    let token = app
+        .generate_jwt_with_expiry(user_a_identifier, 3600)
+        .await?;
     let response = app
         .client
         .post(format!("{}/search/knowledge", app.address))
@@ -251,7 +260,9 @@ async fn test_request_with_expired_token_is_rejected() -> Result<()> {
         then.status(200)
             .json_body(json!({"choices": [{"message": {"role": "assistant", "content": "OK"}}]}));
     });
-    let expired_token = generate_jwt_with_expiry("any-user@example.com", 0)?; // Expires immediately
+    let expired_token = app
+        .generate_jwt_with_expiry("any-user@example.com", 0)
+        .await?; // Expires immediately
     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await; // Ensure it's expired
 
     // --- 2. Act ---
@@ -315,7 +326,9 @@ async fn test_authenticated_user_b_sees_own_and_guest_content() -> Result<()> {
     });
 
     // --- 3. Execute search with a valid JWT for User B ---
-    let token = generate_jwt_with_expiry(user_b_identifier, 3600)?;
+    let token = app
+        .generate_jwt_with_expiry(user_b_identifier, 3600)
+        .await?;
     let response = app
         .client
         .post(format!("{}/search/knowledge", app.address))
@@ -332,3 +345,171 @@ async fn test_authenticated_user_b_sees_own_and_guest_content() -> Result<()> {
 
     Ok(())
 }
+
+/// Returns the titles of User B's keyword search results for "private".
+async fn user_b_keyword_titles(app: &TestApp) -> Result<Vec<String>> {
+    let token = app
+        .generate_jwt_with_expiry("user_b@example.com", 3600)
+        .await?;
+    let response: ApiResponse<Vec<Value>> = app
+        .client
+        .post(format!("{}/search/keyword", app.address))
+        .bearer_auth(token)
+        .json(&json!({ "query": "private" }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(response
+        .result
+        .iter()
+        .map(|result| result["title"].as_str().unwrap_or_default().to_string())
+        .collect())
+}
+
+#[tokio::test]
+async fn test_shared_document_is_visible_to_grantee() -> Result<()> {
+    // --- 1. Arrange & Setup ---
+    let app = TestApp::spawn("test_shared_document_is_visible_to_grantee").await?;
+    app.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path("/test_shared_document_is_visible_to_grantee/v1/chat/completions");
+        then.status(200)
+            .json_body(json!({"choices": [{"message": {"role": "assistant", "content": "OK"}}]}));
+    });
+    seed_data(&app).await?;
+    let db = &app.app_state.sqlite_provider.db;
+    let user_b = get_or_create_user(db, "user_b@example.com", None).await?;
+    let user_a_token = app
+        .generate_jwt_with_expiry("user_a@example.com", 3600)
+        .await?;
+    let user_b_token = app
+        .generate_jwt_with_expiry("user_b@example.com", 3600)
+        .await?;
+    assert!(!user_b_keyword_titles(&app)
+        .await?
+        .contains(&"Doc A".to_string()));
+
+    // --- 2. Act & Assert: Only the owner can share the document ---
+    let response = app
+        .client
+        .post(format!("{}/documents/doc_owned_by_a/share", app.address))
+        .bearer_auth(&user_b_token)
+        .json(&json!({ "user_ids": [user_b.id] }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .client
+        .post(format!("{}/documents/doc_owned_by_a/share", app.address))
+        .bearer_auth(&user_a_token)
+        .json(&json!({ "user_ids": [user_b.id] }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: ApiResponse<Vec<Value>> = response.json().await?;
+    assert_eq!(body.result.len(), 1);
+    assert_eq!(body.result[0]["user_id"], user_b.id);
+
+    // --- 3. Assert: User B now finds User A's document ---
+    assert!(user_b_keyword_titles(&app)
+        .await?
+        .contains(&"Doc A".to_string()));
+
+    // --- 4. Act & Assert: Revoking the share hides it again ---
+    let response = app
+        .client
+        .delete(format!(
+            "{}/documents/doc_owned_by_a/share/{}",
+            app.address, user_b.id
+        ))
+        .bearer_auth(&user_a_token)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!user_b_keyword_titles(&app)
+        .await?
+        .contains(&"Doc A".to_string()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_routed_prompt_answers_from_the_callers_documents() -> Result<()> {
+    // --- 1. Arrange: Route every prompt to the knowledge route ---
+    let test_case_name = "test_routed_prompt_answers_from_the_callers_documents";
+    let base = TestApp::spawn(test_case_name).await?;
+    seed_data(&base).await?;
+    let mut app_state = base.app_state.clone();
+    let mut config = (*app_state.config).clone();
+    config.routing = Some(RoutingConfig {
+        routes: vec![RouteConfig {
+            route: Route::Knowledge,
+            description: None,
+        }],
+        fallback: Route::Knowledge,
+    });
+    let config = Arc::new(config);
+    app_state.executor = Arc::new(AnyragExecutor::new(
+        app_state.ai_providers.clone(),
+        app_state.sqlite_provider.clone(),
+        config.clone(),
+        app_state.tasks.clone(),
+    ));
+    app_state.config = config;
+    let app = TestApp::spawn_with_state(app_state, MockServer::start()).await?;
+    let final_answer = "Found User A's private document.";
+
+    // --- 2. Mock External Services ---
+    // The configured providers still point at the mock server of the base app.
+    base.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(format!("/{test_case_name}/v1/chat/completions"))
+            .body_contains("expert query analyst");
+        then.status(200).json_body(json!({
+            "choices": [{"message": {"role": "assistant", "content": json!({
+                "entities": [], "keyphrases": ["searchable_topic"]
+            }).to_string()}}]
+        }));
+    });
+    base.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(format!("/{test_case_name}/v1/embeddings"));
+        then.status(200)
+            .json_body(json!({ "data": [{ "embedding": [0.5, 0.5, 0.5] }] }));
+    });
+    let rag_synthesis_mock = base.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(format!("/{test_case_name}/v1/chat/completions"))
+            .body_contains("strict, factual AI")
+            .body_contains("This document is private to User A.")
+            // It MUST NOT see User B's private content.
+            .matches(|req| {
+                !String::from_utf8_lossy(req.body.as_deref().unwrap_or_default())
+                    .contains("private to User B")
+            });
+        then.status(200).json_body(
+            json!({"choices": [{"message": {"role": "assistant", "content": final_answer}}]}),
+        );
+    });
+
+    // --- 3. Act: Prompt as User A ---
+    let token = app.generate_jwt("user_a@example.com").await?;
+    let response = app
+        .client
+        .post(format!("{}/prompt", app.address))
+        .bearer_auth(token)
+        .json(&json!({ "prompt": "Find all documents about the searchable topic" }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    // --- 4. Assert ---
+    let response_body: ApiResponse<Value> = response.json().await?;
+    assert_eq!(response_body.result["text"], final_answer);
+    rag_synthesis_mock.assert();
+
+    Ok(())
+}
@@ -7,7 +7,7 @@
 mod common;
 
 use anyhow::Result;
-use common::{generate_jwt, TestApp};
+use common::TestApp;
 use core_access::get_or_create_user;
 use httpmock::Method;
 use serde_json::json;
@@ -18,7 +18,7 @@ async fn test_ingest_endpoint_success() -> Result<()> {
     // --- Arrange ---
     let app = TestApp::spawn("test_ingest_endpoint_success").await?;
     let user_identifier = "ingest-test-user@example.com";
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
 
     // This test doesn't call the AI, but a placeholder mock is needed for stable app startup.
     app.mock_server.mock(|when, then| {
@@ -1,11 +1,14 @@
 //! # Server Endpoint Tests
 //!
 //! This file contains integration tests for the `anyrag-server` endpoints,
-//! including health checks and error handling for invalid input.
+//! including health checks and error handling for invalid input and expensive queries.
 
 mod common;
 
 use anyhow::Result;
+use anyrag::PromptError;
+use anyrag_server::errors::AppError;
+use axum::{http::StatusCode, response::IntoResponse};
 use common::TestApp;
 use httpmock::Method;
 use serde_json::json;
@@ -108,3 +111,50 @@ async fn test_prompt_handler_invalid_payload() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_openapi_document_describes_routes_and_auth() -> Result<()> {
+    // Arrange
+    let app = TestApp::spawn("test_openapi_document_describes_routes_and_auth").await?;
+
+    // Act
+    let response = app
+        .client
+        .get(format!("{}/openapi.json", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request to /openapi.json");
+
+    // Assert
+    assert!(response.status().is_success());
+    let doc: serde_json::Value = response.json().await?;
+    assert!(doc["openapi"].as_str().unwrap().starts_with("3."));
+    let paths = doc["paths"].as_object().unwrap();
+    assert!(paths.contains_key("/ingest"));
+    assert!(paths.contains_key("/search/hybrid"));
+    // The routes of enabled features are merged in.
+    assert!(paths.contains_key("/ingest/text"));
+    // Every operation documents the error envelope.
+    assert!(doc["paths"]["/ingest"]["post"]["responses"]["4XX"].is_object());
+    assert_eq!(
+        doc["components"]["securitySchemes"]["bearer_auth"]["scheme"],
+        "bearer"
+    );
+    let schemas = doc["components"]["schemas"].as_object().unwrap();
+    assert!(schemas.contains_key("ErrorResponse"));
+    assert!(schemas.contains_key("IngestRequest"));
+    Ok(())
+}
+
+#[test]
+fn test_queries_over_the_cost_limit_are_unprocessable() {
+    let error = AppError::from(PromptError::QueryCostExceeded {
+        estimated_bytes: 2_000_000,
+        max_bytes: 1_000_000,
+    });
+
+    assert_eq!(
+        error.into_response().status(),
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+}
@@ -0,0 +1,157 @@
+//! # Session Tests
+//!
+//! This file contains integration tests for sign-in sessions: exchanging refresh
+//! tokens at `/auth/refresh`, listing sessions, and revoking them so that their
+//! access tokens are rejected before they expire.
+
+mod common;
+
+use anyhow::Result;
+use anyrag_server::auth::session::start_session;
+use axum::http::StatusCode;
+use common::{sign_jwt, TestApp};
+use core_access::get_or_create_user;
+use serde_json::{json, Value};
+
+async fn get_me(app: &TestApp, token: &str) -> Result<reqwest::Response> {
+    Ok(app
+        .client
+        .get(format!("{}/auth/me", app.address))
+        .bearer_auth(token)
+        .send()
+        .await?)
+}
+
+async fn post_refresh(app: &TestApp, refresh_token: &str) -> Result<reqwest::Response> {
+    Ok(app
+        .client
+        .post(format!("{}/auth/refresh", app.address))
+        .json(&json!({ "refresh_token": refresh_token }))
+        .send()
+        .await?)
+}
+
+#[tokio::test]
+async fn test_refresh_and_revoke_session() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_refresh_and_revoke_session").await?;
+    let subject = "session-user@example.com";
+    let user = get_or_create_user(&app.app_state.sqlite_provider.db, subject, None).await?;
+    let tokens = start_session(&app.app_state, &user, subject).await.unwrap();
+    assert_eq!(
+        get_me(&app, &tokens.access_token).await?.status(),
+        StatusCode::OK
+    );
+
+    // --- 2. Act: Refresh the session ---
+    let response = post_refresh(&app, &tokens.refresh_token).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let refreshed: Value = response.json().await?;
+    let access_token = refreshed["access_token"].as_str().unwrap();
+    let refresh_token = refreshed["refresh_token"].as_str().unwrap();
+
+    // --- 3. Assert: The new tokens work, and the old refresh token is spent ---
+    assert_eq!(refreshed["session_id"], tokens.session_id);
+    let me: Value = get_me(&app, access_token).await?.json().await?;
+    assert_eq!(me["id"], user.id);
+    let response = post_refresh(&app, &tokens.refresh_token).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .client
+        .get(format!("{}/auth/sessions", app.address))
+        .bearer_auth(access_token)
+        .send()
+        .await?;
+    let sessions: Value = response.json().await?;
+    assert_eq!(sessions.as_array().unwrap().len(), 1);
+    assert_eq!(sessions[0]["id"], tokens.session_id);
+
+    // --- 4. Act: Revoke the session ---
+    let response = app
+        .client
+        .delete(format!(
+            "{}/auth/sessions/{}",
+            app.address, tokens.session_id
+        ))
+        .bearer_auth(access_token)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // --- 5. Assert: Its unexpired tokens are rejected from now on ---
+    for token in [access_token, tokens.access_token.as_str()] {
+        assert_eq!(
+            get_me(&app, token).await?.status(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+    let response = post_refresh(&app, refresh_token).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sessions_of_other_users_cannot_be_revoked() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_sessions_of_other_users_cannot_be_revoked").await?;
+    let db = &app.app_state.sqlite_provider.db;
+    let owner = get_or_create_user(db, "owner@example.com", None).await?;
+    let tokens = start_session(&app.app_state, &owner, "owner@example.com")
+        .await
+        .unwrap();
+
+    // --- 2. Act: Another user tries to revoke the session ---
+    let response = app
+        .client
+        .delete(format!(
+            "{}/auth/sessions/{}",
+            app.address, tokens.session_id
+        ))
+        .bearer_auth(app.generate_jwt("intruder@example.com").await?)
+        .send()
+        .await?;
+
+    // --- 3. Assert: The session is untouched ---
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(
+        get_me(&app, &tokens.access_token).await?.status(),
+        StatusCode::OK
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tokens_without_an_active_session_are_rejected() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_tokens_without_an_active_session_are_rejected").await?;
+    let subject = "sessionless-user@example.com";
+
+    // --- 2. Act & Assert: Tokens without a session, or of an unknown one, are rejected ---
+    for sid in [None, Some("unknown-session".to_string())] {
+        let response = get_me(&app, &sign_jwt(subject, sid, 3600)?).await?;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_concurrent_refreshes_with_the_same_token_rotate_once() -> Result<()> {
+    // --- 1. Arrange ---
+    let app = TestApp::spawn("test_concurrent_refreshes_with_the_same_token_rotate_once").await?;
+    let subject = "racing-user@example.com";
+    let user = get_or_create_user(&app.app_state.sqlite_provider.db, subject, None).await?;
+    let tokens = start_session(&app.app_state, &user, subject).await.unwrap();
+
+    // --- 2. Act ---
+    let (first, second) = tokio::join!(
+        post_refresh(&app, &tokens.refresh_token),
+        post_refresh(&app, &tokens.refresh_token)
+    );
+
+    // --- 3. Assert: Only one of the refreshes is granted ---
+    let mut statuses = vec![first?.status(), second?.status()];
+    statuses.sort();
+    assert_eq!(statuses, vec![StatusCode::OK, StatusCode::UNAUTHORIZED]);
+    Ok(())
+}
@@ -0,0 +1,199 @@
+//! # Database Sharding Tests
+//!
+//! This file contains integration tests for routing the documents of each owner to a
+//! SQLite file of their own, and for the router's cache of open databases.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::types::ShardingConfig;
+use anyrag_server::{
+    db_router::{DatabaseRouter, Tenant},
+    types::ApiResponse,
+};
+use axum::http::StatusCode;
+use common::TestApp;
+use core_access::get_or_create_user;
+use httpmock::MockServer;
+use serde_json::{json, Value};
+use std::{path::Path, sync::Arc};
+use tempfile::tempdir;
+use turso::Value as TursoValue;
+
+const USER_A: &str = "shard-user-a@example.com";
+const USER_B: &str = "shard-user-b@example.com";
+
+/// Spawns an app that keeps the documents of each owner in `shard_dir`.
+async fn spawn_sharded(test_case_name: &str, shard_dir: &Path, max_open: usize) -> Result<TestApp> {
+    let base = TestApp::spawn(test_case_name).await?;
+    let mut app_state = base.app_state.clone();
+    let mut config = (*app_state.config).clone();
+    config.sharding = Some(ShardingConfig {
+        dir: Some(shard_dir.to_str().unwrap().to_string()),
+        max_open,
+    });
+    app_state.db_router = Arc::new(DatabaseRouter::new(
+        app_state.sqlite_provider.clone(),
+        &config,
+    ));
+    app_state.config = Arc::new(config);
+    TestApp::spawn_with_state(app_state, MockServer::start()).await
+}
+
+async fn count_documents(db: &turso::Database) -> Result<i64> {
+    let conn = db.connect()?;
+    let mut rows = conn.query("SELECT COUNT(*) FROM documents", ()).await?;
+    match rows.next().await?.map(|row| row.get_value(0)).transpose()? {
+        Some(TursoValue::Integer(count)) => Ok(count),
+        _ => Ok(0),
+    }
+}
+
+#[tokio::test]
+async fn test_documents_of_each_owner_live_in_their_own_shard() -> Result<()> {
+    // --- 1. Arrange: A document in the shard of user A ---
+    let shard_dir = tempdir()?;
+    let app = spawn_sharded("test_documents_of_each_owner", shard_dir.path(), 8).await?;
+    let db = &app.app_state.sqlite_provider.db;
+    let user_a = get_or_create_user(db, USER_A, None).await?;
+    get_or_create_user(db, USER_B, None).await?;
+    let shard_a = app
+        .app_state
+        .db_router
+        .for_user(&user_a.id, None)
+        .await
+        .unwrap();
+    shard_a
+        .db
+        .connect()?
+        .execute(
+            "INSERT INTO documents (id, owner_id, title, content) VALUES ('doc-a', ?, 'Doc A', 'Owned by A.')",
+            turso::params![user_a.id.clone()],
+        )
+        .await?;
+
+    // --- 2. Act: List the documents of each user ---
+    let mut visible = Vec::new();
+    for user in [USER_A, USER_B] {
+        let response = app
+            .client
+            .get(format!("{}/documents", app.address))
+            .bearer_auth(app.generate_jwt(user).await?)
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: ApiResponse<Vec<Value>> = response.json().await?;
+        visible.push(body.result.len());
+    }
+
+    // --- 3. Assert ---
+    assert_eq!(visible, vec![1, 0]);
+    assert!(shard_dir
+        .path()
+        .join(format!("owner-{}.db", user_a.id))
+        .is_file());
+    assert_eq!(count_documents(db).await?, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sharing_a_document_across_shards() -> Result<()> {
+    // --- 1. Arrange: A document in the shard of user A, and users kept in the primary ---
+    let shard_dir = tempdir()?;
+    let app = spawn_sharded("test_sharing_across_shards", shard_dir.path(), 8).await?;
+    let db = &app.app_state.sqlite_provider.db;
+    let user_a = get_or_create_user(db, USER_A, None).await?;
+    let user_b = get_or_create_user(db, USER_B, None).await?;
+    let root = "shard-root@example.com";
+    get_or_create_user(db, root, Some("root")).await?;
+    let shard_a = app
+        .app_state
+        .db_router
+        .for_user(&user_a.id, None)
+        .await
+        .unwrap();
+    shard_a
+        .db
+        .connect()?
+        .execute(
+            "INSERT INTO documents (id, owner_id, title, content) VALUES ('doc-a', ?, 'Doc A', 'Owned by A.')",
+            turso::params![user_a.id.clone()],
+        )
+        .await?;
+
+    // --- 2. Act: User A shares the document with user B ---
+    let response = app
+        .client
+        .post(format!("{}/documents/doc-a/share", app.address))
+        .bearer_auth(app.generate_jwt(USER_A).await?)
+        .json(&json!({ "user_ids": [user_b.id.clone()] }))
+        .send()
+        .await?;
+
+    // --- 3. Assert: The share lives next to the document and user B sees it ---
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: ApiResponse<Vec<Value>> = response.json().await?;
+    assert_eq!(body.result.len(), 1);
+    assert_eq!(body.result[0]["user_id"], user_b.id);
+
+    let mut visible = Vec::new();
+    for user in [USER_B, root] {
+        let response = app
+            .client
+            .get(format!("{}/documents", app.address))
+            .bearer_auth(app.generate_jwt(user).await?)
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: ApiResponse<Vec<Value>> = response.json().await?;
+        visible.push(body.result.len());
+    }
+    assert_eq!(visible, vec![1, 1]);
+
+    let response = app
+        .client
+        .get(format!("{}/documents/doc-a/share", app.address))
+        .bearer_auth(app.generate_jwt(root).await?)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: ApiResponse<Vec<Value>> = response.json().await?;
+    assert_eq!(body.result.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_router_closes_the_least_recently_used_shard() -> Result<()> {
+    // --- 1. Arrange ---
+    let shard_dir = tempdir()?;
+    let app = spawn_sharded("test_router_closes_lru_shard", shard_dir.path(), 2).await?;
+    let router = &app.app_state.db_router;
+
+    // --- 2. Act: Open three shards, using the first again before the third ---
+    router.route(Tenant::Owner("a")).await.unwrap();
+    router.route(Tenant::Owner("b")).await.unwrap();
+    router.route(Tenant::Owner("a")).await.unwrap();
+    router.route(Tenant::Org("c")).await.unwrap();
+
+    // --- 3. Assert: Only two stay open, and closed shards reopen ---
+    assert_eq!(router.open_count().await, 2);
+    let reopened = router.route(Tenant::Owner("b")).await.unwrap();
+    assert_eq!(count_documents(&reopened.db).await?, 0);
+    assert!(shard_dir.path().join("org-c.db").is_file());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_router_rejects_names_outside_its_directory() -> Result<()> {
+    let shard_dir = tempdir()?;
+    let app = spawn_sharded("test_router_rejects_names", shard_dir.path(), 2).await?;
+
+    for tenant in [
+        Tenant::Owner("../primary"),
+        Tenant::Org(""),
+        Tenant::Project("../../etc/passwd"),
+    ] {
+        assert!(app.app_state.db_router.route(tenant).await.is_err());
+    }
+    Ok(())
+}
@@ -8,14 +8,16 @@
 mod common;
 
 use anyhow::Result;
-use anyrag::prompts::{knowledge, tasks};
+use anyrag::{
+    prompts::{knowledge, tasks},
+    redaction::{RedactionConfig, Redactor},
+};
 use common::TestApp;
-use httpmock::Method;
+use httpmock::{Method, MockServer};
 use serde_json::json;
+use std::sync::Arc;
 use turso::params;
 
-use crate::common::generate_jwt;
-
 #[tokio::test]
 async fn test_ingest_sheet_endpoint_success() -> Result<()> {
     // --- Arrange ---
@@ -23,7 +25,7 @@ async fn test_ingest_sheet_endpoint_success() -> Result<()> {
     let test_case_name = "test_ingest_sheet_endpoint_success";
     let app = TestApp::spawn(test_case_name).await?;
     let user_identifier = "ingest-sheet-user@example.com";
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
 
     // --- Mock Data ---
 
@@ -194,3 +196,171 @@ sections:
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_ingest_sheet_redacts_before_embedding() -> Result<()> {
+    // --- Arrange: Enable the redaction of ingested documents ---
+    let test_case_name = "test_ingest_sheet_redacts_before_embedding";
+    let base = TestApp::spawn(test_case_name).await?;
+    let mut app_state = base.app_state.clone();
+    app_state.redactor = Some(Arc::new(Redactor::new(&RedactionConfig::default())?));
+    let app = TestApp::spawn_with_state(app_state, MockServer::start()).await?;
+    let token = app.generate_jwt("redacted-sheet-user@example.com").await?;
+
+    let sheet_mock = app.mock_server.mock(|when, then| {
+        when.method(Method::GET)
+            .path("/spreadsheets/d/mock_sheet_id_pii/export")
+            .query_param("format", "csv");
+        then.status(200)
+            .header("Content-Type", "text/csv")
+            .body("name,email\nAlice,alice@example.com");
+    });
+    // The configured providers still point at the mock server of the base app.
+    let metadata_mock = base.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(format!("/{test_case_name}/v1/chat/completions"));
+        then.status(200).json_body(json!({
+            "choices": [{"message": {"role": "assistant", "content": "[]"}}]
+        }));
+    });
+    let embedding_mock = base.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(format!("/{test_case_name}/v1/embeddings"))
+            .body_contains("[REDACTED_EMAIL]");
+        then.status(200)
+            .json_body(json!({ "data": [{ "embedding": [0.1, 0.2, 0.3] }] }));
+    });
+
+    // --- Act: Store the sheet as it is ---
+    let payload = json!({
+        "url": format!("{}/spreadsheets/d/mock_sheet_id_pii/edit", app.mock_server.base_url()),
+        "restructure": false,
+    });
+    let response = app
+        .client
+        .post(format!("{}/ingest/sheet", app.address))
+        .bearer_auth(token)
+        .json(&payload)
+        .send()
+        .await?;
+
+    // --- Assert: The stored and embedded content is masked ---
+    assert!(
+        response.status().is_success(),
+        "Request failed with status: {}",
+        response.status()
+    );
+    let response_body: serde_json::Value = response.json().await?;
+    let doc_id = response_body["result"]["document_ids"][0]
+        .as_str()
+        .expect("Document ID is not a string");
+
+    let db = turso::Builder::new_local(app.db_path.to_str().unwrap())
+        .build()
+        .await?;
+    let conn = db.connect()?;
+    let stored_content: String = conn
+        .query(
+            "SELECT content FROM documents WHERE id = ?",
+            params![doc_id],
+        )
+        .await?
+        .next()
+        .await?
+        .expect("Document not found in DB")
+        .get(0)?;
+    assert!(stored_content.contains("[REDACTED_EMAIL]"));
+    assert!(!stored_content.contains("alice@example.com"));
+
+    sheet_mock.assert();
+    metadata_mock.assert();
+    embedding_mock.assert();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ingest_sheet_redacts_extracted_metadata() -> Result<()> {
+    // --- Arrange: Metadata is extracted from the content before it is redacted ---
+    let test_case_name = "test_ingest_sheet_redacts_extracted_metadata";
+    let base = TestApp::spawn(test_case_name).await?;
+    let mut app_state = base.app_state.clone();
+    app_state.redactor = Some(Arc::new(Redactor::new(&RedactionConfig::default())?));
+    let app = TestApp::spawn_with_state(app_state, MockServer::start()).await?;
+    let token = app
+        .generate_jwt("redacted-metadata-user@example.com")
+        .await?;
+
+    let sheet_mock = app.mock_server.mock(|when, then| {
+        when.method(Method::GET)
+            .path("/spreadsheets/d/mock_sheet_id_pii_metadata/export")
+            .query_param("format", "csv");
+        then.status(200)
+            .header("Content-Type", "text/csv")
+            .body("name,email\nAlice,alice@example.com");
+    });
+    let mock_metadata = json!([
+        { "type": "ENTITY", "subtype": "CONTACT", "value": "alice@example.com" },
+        { "type": "KEYPHRASE", "subtype": "CONCEPT", "value": "customer list" }
+    ]);
+    let metadata_mock = base.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(format!("/{test_case_name}/v1/chat/completions"));
+        then.status(200).json_body(json!({
+            "choices": [{"message": {"role": "assistant", "content": mock_metadata.to_string()}}]
+        }));
+    });
+
+    // --- Act ---
+    let payload = json!({
+        "url": format!(
+            "{}/spreadsheets/d/mock_sheet_id_pii_metadata/edit",
+            app.mock_server.base_url()
+        ),
+        "restructure": false,
+    });
+    let response = app
+        .client
+        .post(format!("{}/ingest/sheet", app.address))
+        .bearer_auth(token)
+        .json(&payload)
+        .send()
+        .await?;
+
+    // --- Assert: No metadata value keeps the address ---
+    assert!(
+        response.status().is_success(),
+        "Request failed with status: {}",
+        response.status()
+    );
+    let response_body: serde_json::Value = response.json().await?;
+    let doc_id = response_body["result"]["document_ids"][0]
+        .as_str()
+        .expect("Document ID is not a string");
+
+    let db = turso::Builder::new_local(app.db_path.to_str().unwrap())
+        .build()
+        .await?;
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT metadata_type, metadata_value FROM content_metadata WHERE document_id = ?",
+            params![doc_id],
+        )
+        .await?;
+    let mut metadata = Vec::new();
+    while let Some(row) = rows.next().await? {
+        metadata.push((row.get::<String>(0)?, row.get::<String>(1)?));
+    }
+    assert!(
+        metadata
+            .iter()
+            .all(|(_, value)| !value.contains("alice@example.com")),
+        "Metadata kept personal data: {metadata:?}"
+    );
+    assert!(metadata.contains(&("ENTITY".to_string(), "[REDACTED_EMAIL]".to_string())));
+    assert!(metadata.contains(&("KEYPHRASE".to_string(), "customer list".to_string())));
+
+    sheet_mock.assert();
+    metadata_mock.assert();
+    Ok(())
+}
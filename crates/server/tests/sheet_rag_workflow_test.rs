@@ -10,7 +10,7 @@ mod common;
 
 use anyhow::Result;
 use anyrag::prompts::{knowledge, tasks};
-use common::{generate_jwt, TestApp};
+use common::TestApp;
 use httpmock::Method;
 use serde_json::json;
 
@@ -21,7 +21,7 @@ async fn test_full_sheet_rag_workflow() -> Result<()> {
     let test_case_name = "test_full_sheet_rag_workflow";
     let app = TestApp::spawn(test_case_name).await?;
     let user_identifier = "rag-workflow-user@example.com";
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
 
     // --- Mock Data ---
 
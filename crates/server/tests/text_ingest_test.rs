@@ -12,15 +12,13 @@ use httpmock::Method;
 use serde_json::json;
 use turso::Value as TursoValue;
 
-use crate::common::generate_jwt;
-
 #[tokio::test]
 async fn test_ingest_text_endpoint_success() -> Result<()> {
     // --- Arrange ---
 
     let app = TestApp::spawn("test_ingest_text_endpoint_success").await?;
     let user_identifier = "ingest-text-user@example.com";
-    let token = generate_jwt(user_identifier)?;
+    let token = app.generate_jwt(user_identifier).await?;
 
     // This test doesn't call the AI, but a placeholder mock is needed for stable app startup.
     app.mock_server.mock(|when, then| {
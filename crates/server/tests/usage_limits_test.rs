@@ -0,0 +1,173 @@
+//! # Rate Limit and Quota Tests
+//!
+//! This file contains integration tests for the per-caller rate limit, the monthly
+//! usage quotas enforced by the authentication middleware, the page limits of web
+//! crawls, and the `/me/usage` report.
+
+mod common;
+
+use anyhow::Result;
+use anyrag::types::{QuotaConfig, RateLimitConfig};
+#[cfg(feature = "web")]
+use anyrag_server::ingestors::SOURCE_WEB;
+use anyrag_server::{auth::rate_limit::RateLimiter, types::ApiResponse};
+use axum::http::StatusCode;
+use common::TestApp;
+use httpmock::MockServer;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_requests_over_the_rate_limit_are_rejected() -> Result<()> {
+    // --- 1. Arrange: Allow a burst of two requests per caller ---
+    let base = TestApp::spawn("test_requests_over_the_rate_limit_are_rejected").await?;
+    let mut app_state = base.app_state.clone();
+    app_state.rate_limiter = Some(Arc::new(RateLimiter::new(&RateLimitConfig {
+        requests_per_minute: 1,
+        burst: Some(2),
+    })));
+    let app = TestApp::spawn_with_state(app_state, MockServer::start()).await?;
+    let token = app.generate_jwt("busy-user@example.com").await?;
+
+    // --- 2. Act ---
+    let mut statuses = Vec::new();
+    for _ in 0..3 {
+        let response = app
+            .client
+            .get(format!("{}/auth/me", app.address))
+            .bearer_auth(&token)
+            .send()
+            .await?;
+        statuses.push(response.status());
+    }
+
+    // --- 3. Assert: The third request is limited, but other callers are not ---
+    assert_eq!(
+        statuses,
+        [
+            StatusCode::OK,
+            StatusCode::OK,
+            StatusCode::TOO_MANY_REQUESTS
+        ]
+    );
+    let response = app
+        .client
+        .get(format!("{}/auth/me", app.address))
+        .bearer_auth(app.generate_jwt("other-user@example.com").await?)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_monthly_quotas_are_enforced_and_reported() -> Result<()> {
+    // --- 1. Arrange: One AI call and no ingested documents per month ---
+    let base = TestApp::spawn("test_monthly_quotas_are_enforced_and_reported").await?;
+    let mut config = (*base.app_state.config).clone();
+    config.quotas = Some(QuotaConfig {
+        ai_calls_per_month: Some(1),
+        documents_per_month: Some(0),
+    });
+    let mut app_state = base.app_state.clone();
+    app_state.config = Arc::new(config);
+    let app = TestApp::spawn_with_state(app_state, MockServer::start()).await?;
+    let token = app.generate_jwt("quota-user@example.com").await?;
+
+    // --- 2. Act: Make two AI calls ---
+    let mut statuses = Vec::new();
+    for _ in 0..2 {
+        let response = app
+            .client
+            .post(format!("{}/search/vector", app.address))
+            .bearer_auth(&token)
+            .json(&json!({ "query": "What is on the roadmap?" }))
+            .send()
+            .await?;
+        statuses.push(response.status());
+    }
+
+    // --- 3. Assert: Only the first call is admitted ---
+    assert_ne!(statuses[0], StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(statuses[1], StatusCode::TOO_MANY_REQUESTS);
+
+    let response = app
+        .client
+        .post(format!("{}/ingest", app.address))
+        .bearer_auth(&token)
+        .json(&json!({ "source_type": "text", "source": {} }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // --- 4. Assert: The usage report shows the call and the limits ---
+    let response = app
+        .client
+        .get(format!("{}/me/usage", app.address))
+        .bearer_auth(&token)
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: ApiResponse<Value> = response.json().await?;
+    assert_eq!(body.result["usage"]["ai_calls"], 1);
+    assert_eq!(body.result["usage"]["documents_ingested"], 0);
+    assert_eq!(body.result["limits"]["ai_calls_per_month"], 1);
+    assert_eq!(body.result["limits"]["documents_per_month"], 0);
+    assert!(body.result["limits"]["requests_per_minute"].is_null());
+    Ok(())
+}
+
+#[cfg(feature = "web")]
+#[tokio::test]
+async fn test_crawls_are_limited_by_the_server_and_the_document_quota() -> Result<()> {
+    // --- 1. Arrange: At most 10 crawled pages, and a quota of 3 documents a month ---
+    let base = TestApp::spawn("test_crawls_are_limited").await?;
+    let mut config = (*base.app_state.config).clone();
+    config.max_crawl_pages = 10;
+    let mut app_state = base.app_state.clone();
+    app_state.config = Arc::new(config);
+    let plugin = app_state.ingestors.get(SOURCE_WEB).unwrap();
+    let source = json!({ "url": "https://example.com", "crawl": { "max_pages": 1000 } });
+
+    // --- 2. Act ---
+    let unlimited = plugin.prepare_source(&app_state, source.clone(), None)?;
+    let within_quota = plugin.prepare_source(&app_state, source, Some(3))?;
+    let small = plugin.prepare_source(
+        &app_state,
+        json!({ "url": "https://example.com", "crawl": { "max_pages": 2 } }),
+        Some(3),
+    )?;
+
+    // --- 3. Assert: The smallest of the limits applies ---
+    assert_eq!(unlimited["crawl"]["max_pages"], 10);
+    assert_eq!(within_quota["crawl"]["max_pages"], 3);
+    assert_eq!(small["crawl"]["max_pages"], 2);
+    Ok(())
+}
+
+#[cfg(feature = "web")]
+#[tokio::test]
+async fn test_sitemaps_are_limited_by_the_server_and_the_document_quota() -> Result<()> {
+    // --- 1. Arrange: At most 10 pages per sitemap ---
+    let base = TestApp::spawn("test_sitemaps_are_limited").await?;
+    let mut config = (*base.app_state.config).clone();
+    config.max_sitemap_urls = 10;
+    let mut app_state = base.app_state.clone();
+    app_state.config = Arc::new(config);
+    let plugin = app_state.ingestors.get(SOURCE_WEB).unwrap();
+    let sitemap_url = "https://example.com/sitemap.xml";
+
+    // --- 2. Act: With the default options, and with a larger request ---
+    let defaults =
+        plugin.prepare_source(&app_state, json!({ "sitemap_url": sitemap_url }), None)?;
+    let within_quota = plugin.prepare_source(
+        &app_state,
+        json!({ "sitemap_url": sitemap_url, "sitemap": { "max_urls": 1000 } }),
+        Some(4),
+    )?;
+
+    // --- 3. Assert: The smallest of the limits applies ---
+    assert_eq!(defaults["sitemap"]["max_urls"], 10);
+    assert_eq!(within_quota["sitemap"]["max_urls"], 4);
+    Ok(())
+}
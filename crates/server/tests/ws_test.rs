@@ -0,0 +1,218 @@
+//! # WebSocket Session Tests
+//!
+//! This file contains integration tests for the `/ws` endpoint. It verifies that a
+//! query streams its progress, citations, and answer tokens before completing, and
+//! that a running turn can be cancelled without being added to the conversation.
+
+mod common;
+
+use anyhow::Result;
+use anyrag_server::handlers::{ServerMessage, TurnStage};
+use common::TestApp;
+use futures::{SinkExt, StreamExt};
+use httpmock::Method;
+use serde_json::json;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{client::IntoClientRequest, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+use turso::Value as TursoValue;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Opens an authenticated WebSocket session on the test server.
+async fn connect(app: &TestApp, identifier: &str) -> Result<Socket> {
+    let url = format!("{}/ws", app.address.replacen("http", "ws", 1));
+    let mut request = url.into_client_request()?;
+    request.headers_mut().insert(
+        "Authorization",
+        format!("Bearer {}", app.generate_jwt(identifier).await?).parse()?,
+    );
+    let (socket, _) = connect_async(request).await?;
+    Ok(socket)
+}
+
+async fn send(socket: &mut Socket, message: serde_json::Value) -> Result<()> {
+    socket
+        .send(Message::Text(message.to_string().into()))
+        .await?;
+    Ok(())
+}
+
+/// Reads the next server message, failing the test if none arrives in time.
+async fn next_message(socket: &mut Socket) -> Result<ServerMessage> {
+    loop {
+        let frame = tokio::time::timeout(Duration::from_secs(10), socket.next())
+            .await?
+            .expect("The socket closed unexpectedly.")?;
+        if let Message::Text(text) = frame {
+            return Ok(serde_json::from_str(text.as_str())?);
+        }
+    }
+}
+
+/// Mocks the query analysis and embedding calls of the retrieval stage.
+fn mock_retrieval(app: &TestApp, test_case_name: &str) {
+    app.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(format!("/{test_case_name}/v1/chat/completions"))
+            .body_contains("expert query analyst");
+        then.status(200).json_body(json!({
+            "choices": [{"message": {"role": "assistant", "content": json!({
+                "entities": [],
+                "keyphrases": ["widgets"]
+            }).to_string()}}]
+        }));
+    });
+    app.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(format!("/{test_case_name}/v1/embeddings"));
+        then.status(200)
+            .json_body(json!({ "data": [{ "embedding": vec![0.1, 0.2, 0.3, 0.4] }] }));
+    });
+}
+
+async fn count_conversation_messages(app: &TestApp) -> Result<i64> {
+    let db = turso::Builder::new_local(app.db_path.to_str().unwrap())
+        .build()
+        .await?;
+    let conn = db.connect()?;
+    let mut rows = conn.query("SELECT COUNT(*) FROM conversations", ()).await?;
+    let row = rows.next().await?.expect("Row is None");
+    match row.get_value(0)? {
+        TursoValue::Integer(i) => Ok(i),
+        other => panic!("Expected Integer, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_ws_query_streams_progress_and_tokens() -> Result<()> {
+    // --- Arrange ---
+    let test_case_name = "test_ws_query_streams_progress_and_tokens";
+    let app = TestApp::spawn(test_case_name).await?;
+    mock_retrieval(&app, test_case_name);
+
+    let synthesis_mock = app.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(format!("/{test_case_name}/v1/chat/completions"))
+            .body_contains("strict, factual AI")
+            .body_contains("\"stream\":true");
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(concat!(
+                "data: {\"choices\":[{\"delta\":{\"content\":\"Widgets \"}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"content\":\"spin.\"}}]}\n\n",
+                "data: [DONE]\n\n",
+            ));
+    });
+
+    let mut socket = connect(&app, "ws-user@example.com").await?;
+
+    // --- Act ---
+    let ServerMessage::Session { session_id } = next_message(&mut socket).await? else {
+        panic!("Expected the session to be announced first.");
+    };
+    assert!(!session_id.is_empty());
+    send(
+        &mut socket,
+        json!({ "type": "query", "message": "What do widgets do?" }),
+    )
+    .await?;
+
+    let mut messages = Vec::new();
+    loop {
+        let message = next_message(&mut socket).await?;
+        let done = matches!(message, ServerMessage::Done { .. });
+        messages.push(message);
+        if done {
+            break;
+        }
+    }
+
+    // --- Assert ---
+    assert_eq!(
+        messages,
+        vec![
+            ServerMessage::Progress {
+                stage: TurnStage::RewritingQuery
+            },
+            ServerMessage::Progress {
+                stage: TurnStage::Retrieving
+            },
+            ServerMessage::Citations { citations: vec![] },
+            ServerMessage::Progress {
+                stage: TurnStage::Generating
+            },
+            ServerMessage::Token {
+                text: "Widgets ".to_string()
+            },
+            ServerMessage::Token {
+                text: "spin.".to_string()
+            },
+            ServerMessage::Done {
+                text: "Widgets spin.".to_string(),
+                standalone_query: "What do widgets do?".to_string(),
+                moderation: None
+            },
+        ]
+    );
+    synthesis_mock.assert();
+    // The question and the answer were added to the conversation.
+    assert_eq!(count_conversation_messages(&app).await?, 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ws_cancel_stops_running_query() -> Result<()> {
+    // --- Arrange ---
+    let test_case_name = "test_ws_cancel_stops_running_query";
+    let app = TestApp::spawn(test_case_name).await?;
+    mock_retrieval(&app, test_case_name);
+
+    // The answer takes long enough for the cancellation to arrive mid-generation.
+    app.mock_server.mock(|when, then| {
+        when.method(Method::POST)
+            .path(format!("/{test_case_name}/v1/chat/completions"))
+            .body_contains("strict, factual AI");
+        then.status(200).delay(Duration::from_secs(5)).json_body(
+            json!({"choices": [{"message": {"role": "assistant", "content": "Too late."}}]}),
+        );
+    });
+
+    let mut socket = connect(&app, "ws-cancel-user@example.com").await?;
+    next_message(&mut socket).await?;
+
+    // --- Act ---
+    send(
+        &mut socket,
+        json!({ "type": "query", "message": "What do widgets do?" }),
+    )
+    .await?;
+    loop {
+        let message = next_message(&mut socket).await?;
+        if message
+            == (ServerMessage::Progress {
+                stage: TurnStage::Generating,
+            })
+        {
+            break;
+        }
+    }
+    send(&mut socket, json!({ "type": "cancel" })).await?;
+
+    // --- Assert ---
+    assert_eq!(next_message(&mut socket).await?, ServerMessage::Cancelled);
+    // A second cancellation has nothing to stop.
+    send(&mut socket, json!({ "type": "cancel" })).await?;
+    assert!(matches!(
+        next_message(&mut socket).await?,
+        ServerMessage::Error { .. }
+    ));
+    assert_eq!(count_conversation_messages(&app).await?, 0);
+
+    Ok(())
+}
@@ -3,23 +3,65 @@
 //! This crate provides the logic for ingesting data from Google Sheets as a self-contained
 //! plugin for the `anyrag` ecosystem. It implements the `Ingestor` trait from the
 //! core `anyrag` library.
+//!
+//! A source with a `gid` ingests that tab. Without one, the tabs of the spreadsheet are
+//! discovered through the Sheets API metadata endpoint and each is ingested as its own
+//! document, titled and tagged with the tab's name. When the tabs cannot be listed,
+//! such as for a spreadsheet the endpoint refuses without an API key, the first tab is
+//! ingested as before.
+//!
+//! A tab with more rows than one restructuring call can hold is split into batches of
+//! rows, each restructured and stored as its own document at the tab's URL with a
+//! `rows=` fragment, such as `...#gid=0&rows=2-201`. A parent document at the tab's URL
+//! indexes the batches, and each batch links back to it through its `parent_document`
+//! property.
+//!
+//! With `"mode": "table"`, a tab is instead copied into a typed SQL table named after
+//! the sheet, one row per sheet row, so it can be queried with text-to-SQL. No LLM is
+//! involved, and each ingestion replaces the table with the sheet's current rows.
 
 use anyhow::anyhow;
 use anyrag::{
+    constants::DEFAULT_INGEST_CONCURRENCY,
     ingest::{
-        knowledge::{extract_and_store_metadata, restructure_with_llm},
+        content_hash, embed_ingested_documents,
+        knowledge::RestructuringOutcome,
+        record_revision, source_url_prefix_pattern,
         traits::{IngestError, IngestionPrompts, IngestionResult, Ingestor},
+        KnowledgePipeline,
     },
-    providers::ai::AiProvider,
+    providers::{
+        ai::AiProvider,
+        db::sqlite::table_transfer::{import_table_from_csv, TableTransferError},
+    },
+    types::EmbeddingConfig,
 };
 use async_trait::async_trait;
 use regex::Regex;
 use serde::Deserialize;
+use serde_json::json;
 use thiserror::Error;
-use tracing::info;
-use turso::Database;
+use tracing::{info, warn};
+use turso::{params, Database};
 use uuid::Uuid;
 
+/// The environment variable holding the Google API key sent to the Sheets API metadata
+/// endpoint when discovering the tabs of a spreadsheet.
+pub const SHEETS_API_KEY_ENV: &str = "GOOGLE_SHEETS_API_KEY";
+/// The `content_metadata` property holding the name of the tab a document was ingested
+/// from.
+pub const TAB_PROPERTY: &str = "sheet_tab";
+/// The `content_metadata` property linking a batch of rows of a large sheet to the
+/// document indexing the whole sheet.
+pub const PARENT_DOCUMENT_PROPERTY: &str = "parent_document";
+
+const PROPERTY_METADATA_TYPE: &str = "PROPERTY";
+
+/// The most data rows sent to the LLM in one restructuring call. Larger sheets are split
+/// into batches of rows, each with the header row, restructured concurrently and stored
+/// as one document per batch.
+pub const ROWS_PER_CHUNK: usize = 200;
+
 // --- Error Definitions ---
 
 #[derive(Error, Debug, Clone)]
@@ -46,10 +88,83 @@ impl From<SheetError> for IngestError {
     }
 }
 
+/// A tab of a spreadsheet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SheetTab {
+    pub gid: String,
+    pub title: String,
+}
+
+#[derive(Deserialize)]
+struct SpreadsheetMetadata {
+    #[serde(default)]
+    sheets: Vec<SpreadsheetSheet>,
+}
+
+#[derive(Deserialize)]
+struct SpreadsheetSheet {
+    properties: SheetProperties,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SheetProperties {
+    sheet_id: i64,
+    title: String,
+}
+
 // --- Public Helper Functions ---
 
 /// Transforms a Google Sheet URL into a CSV export URL.
 pub fn construct_export_url(url_str: &str, gid: Option<&str>) -> Result<String, SheetError> {
+    let (base_url, spreadsheets_id) = parse_sheet_url(url_str, "https://docs.google.com")?;
+    let mut export_url = format!("{base_url}/spreadsheets/d/{spreadsheets_id}/export?format=csv");
+
+    if let Some(gid_val) = gid {
+        if !gid_val.is_empty() {
+            export_url.push_str(&format!("&gid={gid_val}"));
+        }
+    }
+
+    Ok(export_url)
+}
+
+/// Transforms a Google Sheet URL into the URL of its Sheets API metadata, which lists
+/// its tabs.
+pub fn construct_metadata_url(url_str: &str, api_key: Option<&str>) -> Result<String, SheetError> {
+    let (base_url, spreadsheets_id) = parse_sheet_url(url_str, "https://sheets.googleapis.com")?;
+    let mut metadata_url = format!(
+        "{base_url}/v4/spreadsheets/{spreadsheets_id}?fields=sheets.properties(sheetId,title)"
+    );
+    if let Some(key) = api_key.filter(|key| !key.is_empty()) {
+        metadata_url.push_str(&format!("&key={key}"));
+    }
+    Ok(metadata_url)
+}
+
+/// Lists the tabs of a spreadsheet, in their order in the spreadsheet.
+pub async fn discover_tabs(metadata_url: &str) -> Result<Vec<SheetTab>, SheetError> {
+    let response = reqwest::get(metadata_url).await?;
+    if !response.status().is_success() {
+        return Err(SheetError::Fetch(format!(
+            "Listing the tabs failed with status: {}",
+            response.status()
+        )));
+    }
+    let metadata: SpreadsheetMetadata = response.json().await?;
+    Ok(metadata
+        .sheets
+        .into_iter()
+        .map(|sheet| SheetTab {
+            gid: sheet.properties.sheet_id.to_string(),
+            title: sheet.properties.title,
+        })
+        .collect())
+}
+
+/// Splits a Google Sheet URL into the base URL of the service and the spreadsheet id.
+/// Local URLs, which tests serve sheets from, are their own base URL.
+fn parse_sheet_url(url_str: &str, google_base_url: &str) -> Result<(String, String), SheetError> {
     let parsed_url =
         reqwest::Url::parse(url_str).map_err(|e| SheetError::InvalidUrl(format!("{e}")))?;
 
@@ -68,17 +183,23 @@ pub fn construct_export_url(url_str: &str, gid: Option<&str>) -> Result<String,
         Some("127.0.0.1") | Some("localhost") => {
             format!("{}://{}", parsed_url.scheme(), parsed_url.authority())
         }
-        _ => "https://docs.google.com".to_string(),
+        _ => google_base_url.to_string(),
     };
-    let mut export_url = format!("{base_url}/spreadsheets/d/{spreadsheets_id}/export?format=csv");
-
-    if let Some(gid_val) = gid {
-        if !gid_val.is_empty() {
-            export_url.push_str(&format!("&gid={gid_val}"));
-        }
-    }
+    Ok((base_url, spreadsheets_id.to_string()))
+}
 
-    Ok(export_url)
+/// The name of the table a sheet tab is copied into in table mode: `sheet_` and the
+/// spreadsheet's id, followed by the `gid` of the tab when one is given.
+pub fn sheet_table_name(url_str: &str, gid: Option<&str>) -> Result<String, SheetError> {
+    let (_, spreadsheets_id) = parse_sheet_url(url_str, "https://docs.google.com")?;
+    let name = match gid.filter(|gid| !gid.is_empty()) {
+        Some(gid) => format!("sheet_{spreadsheets_id}_{gid}"),
+        None => format!("sheet_{spreadsheets_id}"),
+    };
+    Ok(name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect())
 }
 
 /// Downloads the content of a Google Sheet as a CSV string.
@@ -94,6 +215,44 @@ pub async fn download_csv(export_url: &str) -> Result<String, SheetError> {
     response.text().await.map_err(SheetError::from)
 }
 
+/// Splits CSV content into chunks of at most `rows_per_chunk` data rows, each starting
+/// with the header row. Content that fits in one chunk, or cannot be parsed as CSV, is
+/// returned unchanged as a single chunk.
+pub fn chunk_csv_rows(csv_content: &str, rows_per_chunk: usize) -> Vec<String> {
+    let whole = || vec![csv_content.to_string()];
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(csv_content.as_bytes());
+    let Ok(header) = reader.byte_headers().cloned() else {
+        return whole();
+    };
+    let Ok(records) = reader.byte_records().collect::<Result<Vec<_>, _>>() else {
+        return whole();
+    };
+    if records.len() <= rows_per_chunk {
+        return whole();
+    }
+
+    records
+        .chunks(rows_per_chunk.max(1))
+        .map(|batch| write_csv(&header, batch))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|_| whole())
+}
+
+/// Writes a header row and data rows back out as CSV.
+fn write_csv(header: &csv::ByteRecord, rows: &[csv::ByteRecord]) -> Result<String, csv::Error> {
+    let mut writer = csv::WriterBuilder::new()
+        .flexible(true)
+        .from_writer(Vec::new());
+    writer.write_byte_record(header)?;
+    for row in rows {
+        writer.write_byte_record(row)?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
 // --- Ingestor Implementation ---
 
 /// Defines the structure of the JSON string passed to the `ingest` method.
@@ -101,6 +260,72 @@ pub async fn download_csv(export_url: &str) -> Result<String, SheetError> {
 struct SheetSource {
     url: String,
     gid: Option<String>,
+    #[serde(default)]
+    mode: Option<SheetMode>,
+    /// With `false`, the rows are stored as CSV instead of being restructured by the
+    /// LLM. Only applies to the `knowledge` mode.
+    #[serde(default = "default_restructure")]
+    restructure: bool,
+}
+
+fn default_restructure() -> bool {
+    true
+}
+
+/// How a sheet is stored.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SheetMode {
+    /// Restructured by the LLM into knowledge documents.
+    #[default]
+    Knowledge,
+    /// Copied into a typed SQL table for text-to-SQL queries.
+    Table,
+}
+
+/// A sheet tab copied into a table by [`ingest_sheet_table`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SheetTable {
+    /// The name of the table, from [`sheet_table_name`].
+    pub table_name: String,
+    /// The columns of the table, from the sheet's header row.
+    pub columns: Vec<String>,
+    /// The number of rows stored.
+    pub rows: u64,
+}
+
+/// Copies a tab of a Google Sheet into a typed table, replacing the table an earlier
+/// ingestion of the tab made. Without a `gid`, the first tab is copied.
+///
+/// The column types are inferred from the first rows, as for an imported CSV file, so
+/// numeric columns can be compared and summed in SQL.
+pub async fn ingest_sheet_table(
+    db: &Database,
+    url: &str,
+    gid: Option<&str>,
+) -> Result<SheetTable, IngestError> {
+    let table_name = sheet_table_name(url, gid)?;
+    let export_url = construct_export_url(url, gid)?;
+    let csv_content = download_csv(&export_url).await?;
+
+    let conn = db.connect()?;
+    conn.execute(&format!("DROP TABLE IF EXISTS \"{table_name}\""), ())
+        .await?;
+    let summary = import_table_from_csv(db, &csv_content, &table_name)
+        .await
+        .map_err(|e| match e {
+            TableTransferError::Database(e) => IngestError::Database(e),
+            e => IngestError::Parse(format!("Failed to read sheet '{url}' as a table: {e}")),
+        })?;
+    info!(
+        "Copied {} rows of sheet '{url}' into table '{table_name}'",
+        summary.rows_inserted
+    );
+    Ok(SheetTable {
+        columns: csv_columns(&csv_content),
+        table_name,
+        rows: summary.rows_inserted,
+    })
 }
 
 /// The `Ingestor` implementation for Google Sheets.
@@ -108,6 +333,9 @@ pub struct SheetsIngestor<'a> {
     db: &'a Database,
     ai_provider: &'a dyn AiProvider,
     prompts: IngestionPrompts<'a>,
+    concurrency: usize,
+    rows_per_chunk: usize,
+    embedding: Option<&'a EmbeddingConfig>,
 }
 
 impl<'a> SheetsIngestor<'a> {
@@ -121,8 +349,35 @@ impl<'a> SheetsIngestor<'a> {
             db,
             ai_provider,
             prompts,
+            concurrency: DEFAULT_INGEST_CONCURRENCY,
+            rows_per_chunk: ROWS_PER_CHUNK,
+            embedding: None,
         }
     }
+
+    /// Sets how many batches of rows of a large sheet are restructured by the LLM at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the most data rows restructured, and stored, as one document. Defaults to
+    /// [`ROWS_PER_CHUNK`].
+    pub fn with_rows_per_chunk(mut self, rows_per_chunk: usize) -> Self {
+        self.rows_per_chunk = rows_per_chunk.max(1);
+        self
+    }
+
+    /// Embeds the documents of each ingestion with the given model once they are
+    /// stored. Copies of a tab into a table are not embedded.
+    pub fn with_embedding(mut self, config: &'a EmbeddingConfig) -> Self {
+        self.embedding = Some(config);
+        self
+    }
+
+    fn knowledge_pipeline(&self) -> KnowledgePipeline<'a> {
+        KnowledgePipeline::new(self.ai_provider, self.prompts).with_concurrency(self.concurrency)
+    }
 }
 
 #[async_trait]
@@ -132,6 +387,13 @@ impl Ingestor for SheetsIngestor<'_> {
     /// The `source` argument is expected to be a JSON string with a `url` key
     /// and an optional `gid` key, for example:
     /// `{"url": "https://docs.google.com/spreadsheets/d/...", "gid": "12345"}`.
+    /// Without a `gid`, every tab of a spreadsheet with more than one is ingested as a
+    /// separate document, stored under the sheet's URL with a `#gid=` fragment.
+    ///
+    /// With `"mode": "table"`, the tab is copied into a table by [`ingest_sheet_table`]
+    /// instead, and the result's metadata names the table, its columns and its rows.
+    /// With `"restructure": false`, the documents hold the sheet's CSV as it is, and only
+    /// their metadata is extracted by the LLM.
     async fn ingest(
         &self,
         source: &str,
@@ -140,71 +402,250 @@ impl Ingestor for SheetsIngestor<'_> {
         let sheet_source: SheetSource = serde_json::from_str(source)
             .map_err(|e| IngestError::Parse(format!("Failed to parse SheetSource JSON: {e}")))?;
 
+        let gid = sheet_source.gid.as_deref().filter(|gid| !gid.is_empty());
+        if sheet_source.mode == Some(SheetMode::Table) {
+            let table = ingest_sheet_table(self.db, &sheet_source.url, gid).await?;
+            return Ok(IngestionResult {
+                source: sheet_source.url,
+                documents_added: 0,
+                document_ids: vec![],
+                metadata: Some(
+                    json!({
+                        "table_name": table.table_name,
+                        "columns": table.columns,
+                        "rows": table.rows,
+                    })
+                    .to_string(),
+                ),
+            });
+        }
+        if gid.is_none() {
+            let api_key = std::env::var(SHEETS_API_KEY_ENV).ok();
+            let metadata_url = construct_metadata_url(&sheet_source.url, api_key.as_deref())?;
+            match discover_tabs(&metadata_url).await {
+                Ok(tabs) if tabs.len() > 1 => {
+                    let result = self
+                        .ingest_tabs(&sheet_source.url, &tabs, sheet_source.restructure, owner_id)
+                        .await?;
+                    self.embed_new_documents(&result).await;
+                    return Ok(result);
+                }
+                Ok(_) => {}
+                Err(e) => warn!(
+                    "Could not list the tabs of '{}', ingesting the first tab: {e}",
+                    sheet_source.url
+                ),
+            }
+        }
+
         // --- 1. Download CSV content from Google Sheet ---
-        let export_url = construct_export_url(&sheet_source.url, sheet_source.gid.as_deref())?;
+        let export_url = construct_export_url(&sheet_source.url, gid)?;
         let csv_content = download_csv(&export_url).await?;
 
+        let title = format!("Data from sheet: {}", sheet_source.url);
+        let result = self
+            .ingest_titled_csv(
+                &sheet_source.url,
+                &title,
+                &csv_content,
+                sheet_source.restructure,
+                owner_id,
+            )
+            .await?;
+        self.embed_new_documents(&result).await;
+        Ok(result)
+    }
+}
+
+impl SheetsIngestor<'_> {
+    /// Ingests CSV content that was already downloaded, storing it as one document
+    /// under `source_url`, or as a parent document and its batches of rows when it is
+    /// larger than one batch.
+    ///
+    /// This is the pipeline behind `ingest`, exposed for callers that fetch the CSV
+    /// themselves, such as the object-store ingestor.
+    pub async fn ingest_csv(
+        &self,
+        source_url: &str,
+        csv_content: &str,
+        owner_id: Option<&str>,
+    ) -> Result<IngestionResult, IngestError> {
+        let title = format!("Data from sheet: {source_url}");
+        let result = self
+            .ingest_titled_csv(source_url, &title, csv_content, true, owner_id)
+            .await?;
+        self.embed_new_documents(&result).await;
+        Ok(result)
+    }
+
+    /// Embeds the documents of `result` with the model set by `with_embedding`, if any.
+    async fn embed_new_documents(&self, result: &IngestionResult) {
+        if let Some(config) = self.embedding {
+            embed_ingested_documents(self.db, config, &result.document_ids).await;
+        }
+    }
+
+    /// Ingests each tab of a spreadsheet as its own document, titled with the tab's
+    /// name, which is also stored as its `sheet_tab` property.
+    async fn ingest_tabs(
+        &self,
+        sheet_url: &str,
+        tabs: &[SheetTab],
+        restructure: bool,
+        owner_id: Option<&str>,
+    ) -> Result<IngestionResult, IngestError> {
+        info!("Ingesting {} tabs of sheet: {sheet_url}", tabs.len());
+        let base_url = sheet_url.split('#').next().unwrap_or(sheet_url);
+        let mut document_ids = Vec::new();
+        let mut ingested_tabs = Vec::new();
+
+        for tab in tabs {
+            let export_url = construct_export_url(sheet_url, Some(&tab.gid))?;
+            let csv_content = download_csv(&export_url).await?;
+            let source_url = format!("{base_url}#gid={}", tab.gid);
+            let title = format!("Data from sheet tab '{}': {base_url}", tab.title);
+            let result = self
+                .ingest_titled_csv(&source_url, &title, &csv_content, restructure, owner_id)
+                .await?;
+
+            let conn = self.db.connect()?;
+            for document_id in &result.document_ids {
+                conn.execute(
+                    "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_subtype, metadata_value) VALUES (?, ?, ?, ?, ?)",
+                    params![
+                        document_id.as_str(),
+                        owner_id,
+                        PROPERTY_METADATA_TYPE,
+                        TAB_PROPERTY,
+                        tab.title.as_str()
+                    ],
+                )
+                .await?;
+            }
+            ingested_tabs.push(json!({
+                "gid": tab.gid,
+                "title": tab.title,
+                "document_id": result.document_ids.first(),
+            }));
+            document_ids.extend(result.document_ids);
+        }
+
+        Ok(IngestionResult {
+            documents_added: document_ids.len(),
+            source: sheet_url.to_string(),
+            document_ids,
+            metadata: Some(json!({ "tabs": ingested_tabs }).to_string()),
+        })
+    }
+
+    /// Ingests CSV content as one document under `source_url`, with `title` if the
+    /// document is new, or as one document per batch of rows when it has more rows than
+    /// one restructuring call holds. Without `restructure`, the CSV is stored as it is.
+    async fn ingest_titled_csv(
+        &self,
+        source_url: &str,
+        title: &str,
+        csv_content: &str,
+        restructure: bool,
+        owner_id: Option<&str>,
+    ) -> Result<IngestionResult, IngestError> {
+        let chunks = chunk_csv_rows(csv_content, self.rows_per_chunk);
+        if chunks.len() > 1 {
+            return self
+                .ingest_csv_in_batches(source_url, title, &chunks, restructure, owner_id)
+                .await;
+        }
+
         // --- 2. Create or Update Parent Document ---
         let conn = self.db.connect()?;
+        // Batches left from when the sheet was too large for one document.
+        conn.execute(
+            "DELETE FROM documents WHERE owner_id IS ? AND source_url LIKE ? ESCAPE '\\'",
+            params![
+                owner_id,
+                source_url_prefix_pattern(&rows_fragment_prefix(source_url))
+            ],
+        )
+        .await?;
         let document_id: String;
+        let existed: bool;
 
         if let Some(row) = conn
             .query(
-                "SELECT id FROM documents WHERE source_url = ?",
-                turso::params![sheet_source.url.clone()],
+                "SELECT id FROM documents WHERE owner_id IS ? AND source_url = ?",
+                turso::params![owner_id, source_url],
             )
             .await?
             .next()
             .await?
         {
             document_id = row.get(0)?;
+            existed = true;
         } else {
-            document_id =
-                Uuid::new_v5(&Uuid::NAMESPACE_URL, sheet_source.url.as_bytes()).to_string();
-            let title = format!("Data from sheet: {}", sheet_source.url);
+            existed = false;
+            document_id = sheet_document_id(owner_id, source_url);
             conn.execute(
-                "INSERT INTO documents (id, owner_id, source_url, title, content)
-                 VALUES (?, ?, ?, ?, ?)
+                "INSERT INTO documents (id, owner_id, source_url, title, content, content_hash)
+                 VALUES (?, ?, ?, ?, ?, ?)
                  ON CONFLICT(source_url) DO UPDATE SET
                  title = excluded.title,
-                 content = excluded.content",
+                 content = excluded.content,
+                 content_hash = excluded.content_hash",
                 turso::params![
                     document_id.clone(),
                     owner_id,
-                    sheet_source.url.clone(),
+                    source_url,
                     title,
-                    csv_content.clone() // Store raw CSV initially
+                    csv_content, // Store raw CSV initially
+                    content_hash(csv_content)
                 ],
             )
             .await?;
         }
 
         // --- 3. Restructure CSV to YAML using LLM ---
-        let structured_yaml = restructure_with_llm(
-            self.ai_provider,
-            &csv_content,
-            self.prompts.restructuring_system_prompt,
-        )
-        .await
-        .map_err(|e| IngestError::Internal(anyhow!("LLM restructuring failed: {e}")))?;
+        let (outcome, structured_yaml) = if restructure {
+            let restructured = self
+                .knowledge_pipeline()
+                .restructure(&chunks)
+                .await
+                .map_err(|e| IngestError::Internal(anyhow!("LLM restructuring failed: {e}")))?;
+            let outcome = restructured.outcome();
+            let structured_yaml = restructured.into_text().unwrap_or_else(|| {
+                warn!("LLM restructuring of sheet '{source_url}' resulted in empty content, keeping its CSV.");
+                csv_content.to_string()
+            });
+            (outcome, structured_yaml)
+        } else {
+            (Some(RestructuringOutcome::Skipped), csv_content.to_string())
+        };
 
         // --- 4. Update Document and Extract Metadata ---
+        // A sheet ingested before keeps its previous version; a new one only replaces
+        // the raw CSV it was created with.
+        if existed {
+            record_revision(&conn, source_url, &structured_yaml).await?;
+        }
         conn.execute(
-            "UPDATE documents SET content = ? WHERE id = ?",
-            turso::params![structured_yaml.clone(), document_id.clone()],
+            "UPDATE documents SET content = ?, content_hash = ? WHERE id = ?",
+            turso::params![
+                structured_yaml.clone(),
+                content_hash(&structured_yaml),
+                document_id.clone()
+            ],
         )
         .await?;
 
-        extract_and_store_metadata(
-            &conn,
-            self.ai_provider,
-            &document_id,
-            owner_id,
-            &structured_yaml,
-            self.prompts.metadata_extraction_system_prompt,
-        )
-        .await
-        .map_err(|e| IngestError::Internal(anyhow!("Metadata extraction failed: {e}")))?;
+        self.knowledge_pipeline()
+            .extract_metadata(&conn, &document_id, owner_id, &structured_yaml)
+            .await
+            .map_err(|e| IngestError::Internal(anyhow!("Metadata extraction failed: {e}")))?;
+        if let Some(outcome) = outcome {
+            self.knowledge_pipeline()
+                .record_outcome(&conn, &document_id, owner_id, outcome)
+                .await
+                .map_err(|e| IngestError::Internal(anyhow!("Metadata extraction failed: {e}")))?;
+        }
 
         info!(
             "Successfully ingested and processed Google Sheet as document ID: {}",
@@ -213,9 +654,239 @@ impl Ingestor for SheetsIngestor<'_> {
 
         Ok(IngestionResult {
             documents_added: 1, // The entire sheet is treated as one document.
-            source: sheet_source.url,
+            source: source_url.to_string(),
             document_ids: vec![document_id],
             metadata: None,
         })
     }
+
+    /// Ingests each batch of rows of a large sheet as its own document, and a parent
+    /// document under `source_url` that indexes them. Batches of an earlier ingestion
+    /// that no longer exist, such as after rows were deleted, are removed.
+    async fn ingest_csv_in_batches(
+        &self,
+        source_url: &str,
+        title: &str,
+        chunks: &[String],
+        restructure: bool,
+        owner_id: Option<&str>,
+    ) -> Result<IngestionResult, IngestError> {
+        info!(
+            "Ingesting sheet '{source_url}' as {} batches of rows",
+            chunks.len()
+        );
+        // How the content of each batch was obtained, and the content; `None` when the
+        // LLM returned nothing and the batch's CSV was kept.
+        let contents: Vec<(Option<RestructuringOutcome>, String)> = if restructure {
+            self.knowledge_pipeline()
+                .restructure_each(chunks)
+                .await
+                .map_err(|e| IngestError::Internal(anyhow!("LLM restructuring failed: {e}")))?
+                .into_iter()
+                .zip(chunks)
+                .map(|(restructured, chunk)| {
+                    let outcome = restructured.outcome();
+                    (
+                        outcome,
+                        restructured.into_text().unwrap_or_else(|| chunk.clone()),
+                    )
+                })
+                .collect()
+        } else {
+            chunks
+                .iter()
+                .map(|chunk| (Some(RestructuringOutcome::Skipped), chunk.clone()))
+                .collect()
+        };
+
+        let conn = self.db.connect()?;
+        let mut previous_batches = Vec::new();
+        let mut rows = conn
+            .query(
+                "SELECT source_url FROM documents WHERE owner_id IS ? AND source_url LIKE ? ESCAPE '\\'",
+                params![
+                    owner_id,
+                    source_url_prefix_pattern(&rows_fragment_prefix(source_url))
+                ],
+            )
+            .await?;
+        while let Some(row) = rows.next().await? {
+            previous_batches.push(row.get::<String>(0)?);
+        }
+
+        // The id, source URL, content and sheet rows of every stored batch.
+        let mut batches = Vec::new();
+        let mut outcomes = Vec::new();
+        // Row 1 of the sheet is the header, so data starts on row 2.
+        let mut next_row = 2;
+        for (chunk, (outcome, structured_yaml)) in chunks.iter().zip(contents) {
+            let first_row = next_row;
+            let last_row = first_row + csv_row_count(chunk).max(1) - 1;
+            next_row = last_row + 1;
+
+            let batch_url = rows_source_url(source_url, first_row, last_row);
+            let batch_id = sheet_document_id(owner_id, &batch_url);
+            record_revision(&conn, &batch_url, &structured_yaml).await?;
+            conn.execute(
+                "INSERT INTO documents (id, owner_id, source_url, title, content, content_hash)
+                 VALUES (?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(source_url) DO UPDATE SET
+                 title = excluded.title,
+                 content = excluded.content,
+                 content_hash = excluded.content_hash",
+                params![
+                    batch_id.clone(),
+                    owner_id,
+                    batch_url.clone(),
+                    format!("{title} (rows {first_row}-{last_row})"),
+                    structured_yaml.clone(),
+                    content_hash(&structured_yaml)
+                ],
+            )
+            .await?;
+            batches.push((batch_id, batch_url, structured_yaml, (first_row, last_row)));
+            outcomes.push(outcome);
+        }
+
+        for stale_url in previous_batches
+            .iter()
+            .filter(|url| !batches.iter().any(|(_, batch_url, _, _)| batch_url == *url))
+        {
+            conn.execute(
+                "DELETE FROM documents WHERE owner_id IS ? AND source_url = ?",
+                params![owner_id, stale_url.as_str()],
+            )
+            .await?;
+        }
+
+        // --- The parent document indexing the batches ---
+        let parent_id = sheet_document_id(owner_id, source_url);
+        let parts: Vec<_> = batches
+            .iter()
+            .map(|(_, batch_url, _, (first_row, last_row))| {
+                json!({ "rows": format!("{first_row}-{last_row}"), "source_url": batch_url })
+            })
+            .collect();
+        let index = json!({
+            "sheet": source_url,
+            "columns": csv_columns(&chunks[0]),
+            "rows": next_row - 2,
+            "parts": parts,
+        });
+        let index_yaml = serde_yaml::to_string(&index)
+            .map_err(|e| IngestError::Internal(anyhow!("Failed to write sheet index: {e}")))?;
+        record_revision(&conn, source_url, &index_yaml).await?;
+        conn.execute(
+            "INSERT INTO documents (id, owner_id, source_url, title, content, content_hash)
+             VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT(source_url) DO UPDATE SET
+             title = excluded.title,
+             content = excluded.content,
+             content_hash = excluded.content_hash",
+            params![
+                parent_id.clone(),
+                owner_id,
+                source_url,
+                title,
+                index_yaml.clone(),
+                content_hash(&index_yaml)
+            ],
+        )
+        .await?;
+        // The index has no metadata of its own; drop what a single-document ingestion
+        // of the sheet extracted.
+        conn.execute(
+            "DELETE FROM content_metadata WHERE document_id = ?",
+            params![parent_id.clone()],
+        )
+        .await?;
+
+        let documents: Vec<(String, String)> = batches
+            .iter()
+            .map(|(batch_id, _, content, _)| (batch_id.clone(), content.clone()))
+            .collect();
+        self.knowledge_pipeline()
+            .extract_metadata_of_each(&conn, &documents, owner_id)
+            .await
+            .map_err(|e| IngestError::Internal(anyhow!("Metadata extraction failed: {e}")))?;
+
+        // Stored after the extracted metadata, which replaces all rows of the document.
+        for ((batch_id, _, _, _), outcome) in batches.iter().zip(&outcomes) {
+            if let Some(outcome) = outcome {
+                self.knowledge_pipeline()
+                    .record_outcome(&conn, batch_id, owner_id, *outcome)
+                    .await
+                    .map_err(|e| {
+                        IngestError::Internal(anyhow!("Metadata extraction failed: {e}"))
+                    })?;
+            }
+            conn.execute(
+                "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_subtype, metadata_value) VALUES (?, ?, ?, ?, ?)",
+                params![
+                    batch_id.as_str(),
+                    owner_id,
+                    PROPERTY_METADATA_TYPE,
+                    PARENT_DOCUMENT_PROPERTY,
+                    parent_id.as_str()
+                ],
+            )
+            .await?;
+        }
+
+        info!(
+            "Ingested sheet '{source_url}' as document {parent_id} with {} batches of rows",
+            batches.len()
+        );
+        let document_ids: Vec<String> = std::iter::once(parent_id)
+            .chain(batches.into_iter().map(|(batch_id, _, _, _)| batch_id))
+            .collect();
+        Ok(IngestionResult {
+            documents_added: document_ids.len(),
+            source: source_url.to_string(),
+            document_ids,
+            metadata: None,
+        })
+    }
+}
+
+/// The id of the document an owner stores at `source_url`. Owners ingesting the same
+/// sheet each get documents of their own.
+fn sheet_document_id(owner_id: Option<&str>, source_url: &str) -> String {
+    Uuid::new_v5(
+        &Uuid::NAMESPACE_URL,
+        format!("{}:{source_url}", owner_id.unwrap_or_default()).as_bytes(),
+    )
+    .to_string()
+}
+
+/// The start of the source URL of every batch of rows of the sheet at `source_url`. A
+/// tab's URL already has a `#gid=` fragment, which the row range is added to.
+fn rows_fragment_prefix(source_url: &str) -> String {
+    let separator = if source_url.contains('#') { '&' } else { '#' };
+    format!("{source_url}{separator}rows=")
+}
+
+/// The source URL of the document holding rows `first_row` to `last_row` of a sheet, in
+/// the sheet's own row numbers.
+fn rows_source_url(source_url: &str, first_row: usize, last_row: usize) -> String {
+    format!("{}{first_row}-{last_row}", rows_fragment_prefix(source_url))
+}
+
+/// Counts the data rows of CSV content, not including the header row.
+fn csv_row_count(csv_content: &str) -> usize {
+    csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(csv_content.as_bytes())
+        .byte_records()
+        .count()
+}
+
+/// The column names in the header row of CSV content.
+fn csv_columns(csv_content: &str) -> Vec<String> {
+    csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(csv_content.as_bytes())
+        .headers()
+        .map(|header| header.iter().map(str::to_string).collect())
+        .unwrap_or_default()
 }
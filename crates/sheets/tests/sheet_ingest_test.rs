@@ -8,6 +8,11 @@ use httpmock::{Method, MockServer};
 use serde_json::json;
 use turso::{params, Value as TursoValue};
 
+/// A restructuring response with one section, titled `title`.
+fn section_yaml(title: &str) -> String {
+    format!("sections:\n  - title: {title}\n    faqs:\n      - question: q\n        answer: a\n")
+}
+
 #[tokio::test]
 async fn test_sheet_ingestion_yaml_workflow() -> Result<()> {
     // --- 1. Arrange & Setup ---
@@ -122,3 +127,387 @@ sections:
 
     Ok(())
 }
+
+#[test]
+fn test_chunk_csv_rows_repeats_header() {
+    let csv = "name,quote\nalice,\"hi, there\"\nbob,hello\ncarol,\"multi\nline\"\n";
+
+    assert_eq!(anyrag_sheets::chunk_csv_rows(csv, 3), vec![csv.to_string()]);
+
+    let chunks = anyrag_sheets::chunk_csv_rows(csv, 2);
+    assert_eq!(
+        chunks,
+        vec![
+            "name,quote\nalice,\"hi, there\"\nbob,hello\n".to_string(),
+            "name,quote\ncarol,\"multi\nline\"\n".to_string(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_sheet_without_gid_ingests_every_tab() -> Result<()> {
+    // --- 1. Arrange ---
+    let setup = TestSetup::new().await?;
+    let ai_provider = MockAiProvider::new();
+    let mock_server = MockServer::start();
+
+    let metadata_mock = mock_server.mock(|when, then| {
+        when.method(Method::GET)
+            .path("/v4/spreadsheets/mock_multi_tab_sheet");
+        then.status(200).json_body(json!({
+            "sheets": [
+                { "properties": { "sheetId": 0, "title": "Pricing" } },
+                { "properties": { "sheetId": 1789, "title": "Shipping" } }
+            ]
+        }));
+    });
+    let pricing_mock = mock_server.mock(|when, then| {
+        when.method(Method::GET)
+            .path("/spreadsheets/d/mock_multi_tab_sheet/export")
+            .query_param("gid", "0");
+        then.status(200).body("plan,price\nPro,10\n");
+    });
+    let shipping_mock = mock_server.mock(|when, then| {
+        when.method(Method::GET)
+            .path("/spreadsheets/d/mock_multi_tab_sheet/export")
+            .query_param("gid", "1789");
+        then.status(200).body("region,days\nEU,3\n");
+    });
+    // Each tab is restructured, then its metadata is extracted.
+    ai_provider.add_response("restructure", &section_yaml("Pro costs 10"));
+    ai_provider.add_response("metadata", "[]");
+    ai_provider.add_response("restructure", &section_yaml("EU ships in 3 days"));
+    ai_provider.add_response("metadata", "[]");
+
+    let prompts = IngestionPrompts {
+        restructuring_system_prompt:
+            anyrag::prompts::knowledge::KNOWLEDGE_RESTRUCTURING_SYSTEM_PROMPT,
+        metadata_extraction_system_prompt:
+            anyrag::prompts::tasks::KNOWLEDGE_METADATA_EXTRACTION_SYSTEM_PROMPT,
+    };
+    let ingestor = SheetsIngestor::new(&setup.db, &ai_provider, prompts);
+    let sheet_url = format!(
+        "{}/spreadsheets/d/mock_multi_tab_sheet/edit",
+        mock_server.base_url()
+    );
+
+    // --- 2. Act ---
+    let result = ingestor
+        .ingest(&json!({ "url": sheet_url }).to_string(), Some("sheet-user"))
+        .await?;
+
+    // --- 3. Assert ---
+    assert_eq!(result.documents_added, 2);
+    let metadata: serde_json::Value = serde_json::from_str(result.metadata.as_deref().unwrap())?;
+    assert_eq!(metadata["tabs"][1]["title"], "Shipping");
+    assert_eq!(metadata["tabs"][1]["gid"], "1789");
+
+    let conn = setup.db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT d.source_url, d.title, d.content, m.metadata_value FROM documents d
+             JOIN content_metadata m ON m.document_id = d.id AND m.metadata_subtype = 'sheet_tab'
+             ORDER BY d.source_url",
+            (),
+        )
+        .await?;
+    let pricing = rows.next().await?.expect("Expected the Pricing tab");
+    assert_eq!(pricing.get::<String>(0)?, format!("{sheet_url}#gid=0"));
+    assert!(pricing.get::<String>(1)?.contains("'Pricing'"));
+    assert!(pricing.get::<String>(2)?.contains("Pro costs 10"));
+    assert_eq!(pricing.get::<String>(3)?, "Pricing");
+    let shipping = rows.next().await?.expect("Expected the Shipping tab");
+    assert_eq!(shipping.get::<String>(0)?, format!("{sheet_url}#gid=1789"));
+    assert_eq!(shipping.get::<String>(3)?, "Shipping");
+    assert!(rows.next().await?.is_none());
+
+    metadata_mock.assert();
+    pricing_mock.assert();
+    shipping_mock.assert();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_large_sheet_is_stored_as_linked_batches_of_rows() -> Result<()> {
+    // --- 1. Arrange ---
+    let setup = TestSetup::new().await?;
+    let ai_provider = MockAiProvider::new();
+    let mock_server = MockServer::start();
+
+    let mut sheet_mock = mock_server.mock(|when, then| {
+        when.method(Method::GET)
+            .path("/spreadsheets/d/mock_large_sheet/export");
+        then.status(200)
+            .body("sku,stock\nA1,4\nA2,0\nA3,9\nA4,1\nA5,7\n");
+    });
+    // Each batch of two rows is restructured, then the metadata of each is extracted.
+    for batch in ["A1 and A2", "A3 and A4", "A5"] {
+        ai_provider.add_response("restructure", &section_yaml(batch));
+    }
+    for _ in 0..3 {
+        ai_provider.add_response("metadata", "[]");
+    }
+
+    let prompts = IngestionPrompts {
+        restructuring_system_prompt:
+            anyrag::prompts::knowledge::KNOWLEDGE_RESTRUCTURING_SYSTEM_PROMPT,
+        metadata_extraction_system_prompt:
+            anyrag::prompts::tasks::KNOWLEDGE_METADATA_EXTRACTION_SYSTEM_PROMPT,
+    };
+    let ingestor = SheetsIngestor::new(&setup.db, &ai_provider, prompts)
+        .with_concurrency(1)
+        .with_rows_per_chunk(2);
+    let sheet_url = format!(
+        "{}/spreadsheets/d/mock_large_sheet/edit#gid=0",
+        mock_server.base_url()
+    );
+    let source = json!({ "url": sheet_url }).to_string();
+
+    // --- 2. Act ---
+    let result = ingestor.ingest(&source, Some("sheet-user")).await?;
+
+    // --- 3. Assert ---
+    assert_eq!(result.documents_added, 4);
+    assert_eq!(ai_provider.get_calls().len(), 6);
+    let parent_id = result.document_ids[0].clone();
+
+    let conn = setup.db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT d.source_url, d.title, d.content FROM documents d
+             JOIN content_metadata m ON m.document_id = d.id
+             WHERE m.metadata_subtype = 'parent_document' AND m.metadata_value = ?
+             ORDER BY d.source_url",
+            params![parent_id.clone()],
+        )
+        .await?;
+    let mut batches = Vec::new();
+    while let Some(row) = rows.next().await? {
+        batches.push((
+            row.get::<String>(0)?,
+            row.get::<String>(1)?,
+            row.get::<String>(2)?,
+        ));
+    }
+    assert_eq!(batches.len(), 3);
+    assert_eq!(batches[0].0, format!("{sheet_url}&rows=2-3"));
+    assert!(batches[0].1.ends_with("(rows 2-3)"));
+    assert!(batches[0].2.contains("A1 and A2"));
+    assert_eq!(batches[2].0, format!("{sheet_url}&rows=6-6"));
+    assert!(batches[2].2.contains("A5"));
+
+    let parent_content: String = conn
+        .query(
+            "SELECT content FROM documents WHERE id = ?",
+            params![parent_id.clone()],
+        )
+        .await?
+        .next()
+        .await?
+        .expect("Expected the parent document")
+        .get(0)?;
+    assert!(parent_content.contains("rows: 5"), "{parent_content}");
+    assert!(parent_content.contains("- sku"), "{parent_content}");
+    assert!(parent_content.contains(&format!("{sheet_url}&rows=4-5")));
+
+    // --- 4. Act: the sheet shrinks to one batch ---
+    sheet_mock.delete();
+    mock_server.mock(|when, then| {
+        when.method(Method::GET)
+            .path("/spreadsheets/d/mock_large_sheet/export");
+        then.status(200).body("sku,stock\nA1,4\nA2,0\n");
+    });
+    ai_provider.add_response("restructure", &section_yaml("A1 and A2"));
+    ai_provider.add_response("metadata", "[]");
+    let result = ingestor.ingest(&source, Some("sheet-user")).await?;
+
+    // --- 5. Assert: the batches are gone ---
+    assert_eq!(result.document_ids, vec![parent_id]);
+    let remaining: i64 = conn
+        .query("SELECT COUNT(*) FROM documents", ())
+        .await?
+        .next()
+        .await?
+        .expect("Expected a count")
+        .get(0)?;
+    assert_eq!(remaining, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_table_mode_copies_the_sheet_into_a_typed_table() -> Result<()> {
+    // --- 1. Arrange ---
+    let setup = TestSetup::new().await?;
+    let ai_provider = MockAiProvider::new();
+    let mock_server = MockServer::start();
+
+    let mut sheet_mock = mock_server.mock(|when, then| {
+        when.method(Method::GET)
+            .path("/spreadsheets/d/mock-table-sheet/export")
+            .query_param("gid", "42");
+        then.status(200)
+            .body("sku,name,stock,price\nA1,Kettle,4,19.5\nA2,\"Teapot, blue\",0,7.25\n");
+    });
+    let prompts = IngestionPrompts {
+        restructuring_system_prompt:
+            anyrag::prompts::knowledge::KNOWLEDGE_RESTRUCTURING_SYSTEM_PROMPT,
+        metadata_extraction_system_prompt:
+            anyrag::prompts::tasks::KNOWLEDGE_METADATA_EXTRACTION_SYSTEM_PROMPT,
+    };
+    let ingestor = SheetsIngestor::new(&setup.db, &ai_provider, prompts);
+    let sheet_url = format!(
+        "{}/spreadsheets/d/mock-table-sheet/edit",
+        mock_server.base_url()
+    );
+    let source = json!({ "url": sheet_url, "gid": "42", "mode": "table" }).to_string();
+
+    // --- 2. Act ---
+    let result = ingestor.ingest(&source, Some("sheet-user")).await?;
+
+    // --- 3. Assert ---
+    assert_eq!(result.documents_added, 0);
+    let metadata: serde_json::Value = serde_json::from_str(result.metadata.as_deref().unwrap())?;
+    assert_eq!(metadata["table_name"], "sheet_mock_table_sheet_42");
+    assert_eq!(metadata["rows"], 2);
+    assert!(ai_provider.get_calls().is_empty());
+
+    let conn = setup.db.connect()?;
+    let row = conn
+        .query(
+            "SELECT SUM(stock), typeof(price), name FROM sheet_mock_table_sheet_42 WHERE sku = 'A2'",
+            (),
+        )
+        .await?
+        .next()
+        .await?
+        .expect("Expected the A2 row");
+    assert_eq!(row.get::<i64>(0)?, 0);
+    assert_eq!(row.get::<String>(1)?, "real");
+    assert_eq!(row.get::<String>(2)?, "Teapot, blue");
+
+    // --- 4. Act: ingesting again replaces the rows ---
+    sheet_mock.delete();
+    mock_server.mock(|when, then| {
+        when.method(Method::GET)
+            .path("/spreadsheets/d/mock-table-sheet/export");
+        then.status(200)
+            .body("sku,name,stock,price\nA3,Mug,40,3.5\n");
+    });
+    ingestor.ingest(&source, Some("sheet-user")).await?;
+
+    let skus: String = conn
+        .query(
+            "SELECT group_concat(sku) FROM sheet_mock_table_sheet_42",
+            (),
+        )
+        .await?
+        .next()
+        .await?
+        .expect("Expected a row")
+        .get(0)?;
+    assert_eq!(skus, "A3");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sheet_without_restructuring_keeps_its_csv() -> Result<()> {
+    // --- 1. Arrange ---
+    let setup = TestSetup::new().await?;
+    let ai_provider = MockAiProvider::new();
+    let mock_server = MockServer::start();
+
+    let csv_content = "sku,stock\nA1,4\nA2,0\n";
+    let sheet_mock = mock_server.mock(|when, then| {
+        when.method(Method::GET)
+            .path("/spreadsheets/d/mock-raw-sheet/export")
+            .query_param("gid", "7");
+        then.status(200).body(csv_content);
+    });
+    // Only the metadata is extracted by the LLM.
+    ai_provider.add_response("metadata", "[]");
+    let prompts = IngestionPrompts {
+        restructuring_system_prompt:
+            anyrag::prompts::knowledge::KNOWLEDGE_RESTRUCTURING_SYSTEM_PROMPT,
+        metadata_extraction_system_prompt:
+            anyrag::prompts::tasks::KNOWLEDGE_METADATA_EXTRACTION_SYSTEM_PROMPT,
+    };
+    let ingestor = SheetsIngestor::new(&setup.db, &ai_provider, prompts);
+    let sheet_url = format!(
+        "{}/spreadsheets/d/mock-raw-sheet/edit",
+        mock_server.base_url()
+    );
+    let source = json!({ "url": sheet_url, "gid": "7", "restructure": false }).to_string();
+
+    // --- 2. Act ---
+    let result = ingestor.ingest(&source, Some("sheet-user")).await?;
+
+    // --- 3. Assert ---
+    assert_eq!(result.documents_added, 1);
+    assert_eq!(ai_provider.get_calls().len(), 1);
+
+    let conn = setup.db.connect()?;
+    let row = conn
+        .query(
+            "SELECT d.content, m.metadata_value FROM documents d
+             JOIN content_metadata m ON m.document_id = d.id AND m.metadata_subtype = 'restructuring'
+             WHERE d.id = ?",
+            params![result.document_ids[0].clone()],
+        )
+        .await?
+        .next()
+        .await?
+        .expect("Expected the sheet document");
+    assert_eq!(row.get::<String>(0)?, csv_content);
+    assert_eq!(row.get::<String>(1)?, "skipped");
+    sheet_mock.assert();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sheet_ingested_by_two_owners_keeps_a_document_for_each() -> Result<()> {
+    // --- 1. Arrange ---
+    let setup = TestSetup::new().await?;
+    let ai_provider = MockAiProvider::new();
+    let mock_server = MockServer::start();
+
+    let csv_content = "sku,stock\nA1,4\nA2,0\n";
+    let sheet_mock = mock_server.mock(|when, then| {
+        when.method(Method::GET)
+            .path("/spreadsheets/d/mock_shared_sheet/export")
+            .query_param("gid", "0");
+        then.status(200).body(csv_content);
+    });
+    ai_provider.add_response("metadata", "[]");
+    let prompts = IngestionPrompts {
+        restructuring_system_prompt:
+            anyrag::prompts::knowledge::KNOWLEDGE_RESTRUCTURING_SYSTEM_PROMPT,
+        metadata_extraction_system_prompt:
+            anyrag::prompts::tasks::KNOWLEDGE_METADATA_EXTRACTION_SYSTEM_PROMPT,
+    };
+    let ingestor = SheetsIngestor::new(&setup.db, &ai_provider, prompts);
+    let sheet_url = format!(
+        "{}/spreadsheets/d/mock_shared_sheet/edit",
+        mock_server.base_url()
+    );
+    let source = json!({ "url": sheet_url, "gid": "0", "restructure": false }).to_string();
+
+    // --- 2. Act ---
+    let alice = ingestor.ingest(&source, Some("alice")).await?;
+    let bob = ingestor.ingest(&source, Some("bob")).await?;
+    // Ingesting again only replaces the owner's own document.
+    ingestor.ingest(&source, Some("alice")).await?;
+
+    // --- 3. Assert ---
+    assert_ne!(alice.document_ids, bob.document_ids);
+    let conn = setup.db.connect()?;
+    let mut rows = conn
+        .query("SELECT owner_id FROM documents ORDER BY owner_id", ())
+        .await?;
+    let mut owners = Vec::new();
+    while let Some(row) = rows.next().await? {
+        owners.push(row.get::<String>(0)?);
+    }
+    assert_eq!(owners, vec!["alice", "bob"]);
+    sheet_mock.assert_hits(3);
+    Ok(())
+}
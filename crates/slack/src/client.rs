@@ -0,0 +1,205 @@
+//! # Slack Web API Client
+//!
+//! A small client for the two `conversations.*` methods the ingestor needs. It follows
+//! cursor pagination and waits out `429 Too Many Requests` responses for as long as
+//! their `Retry-After` header asks.
+
+use crate::SlackIngestError;
+use reqwest::{header::RETRY_AFTER, Client, Response, StatusCode};
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// The base URL of the Slack Web API.
+pub const DEFAULT_SLACK_API_URL: &str = "https://slack.com/api";
+
+/// The number of messages requested per page, the maximum Slack recommends.
+const PAGE_LIMIT: &str = "200";
+/// How many rate-limited responses in a row are waited out before giving up.
+const MAX_RATE_LIMIT_RETRIES: usize = 5;
+/// The wait used when a rate-limited response has no usable `Retry-After` header.
+const DEFAULT_RETRY_AFTER_SECS: u64 = 1;
+
+const HISTORY_METHOD: &str = "conversations.history";
+const REPLIES_METHOD: &str = "conversations.replies";
+
+/// A message as returned by the `conversations.*` methods.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlackMessage {
+    /// The message timestamp, which is also its ID within the channel.
+    pub ts: String,
+    #[serde(default)]
+    pub user: Option<String>,
+    /// The display name of bot messages, which have no `user`.
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub subtype: Option<String>,
+    #[serde(default)]
+    pub thread_ts: Option<String>,
+    #[serde(default)]
+    pub reply_count: usize,
+}
+
+impl SlackMessage {
+    /// Returns whether this message is a reply inside another message's thread.
+    pub fn is_thread_reply(&self) -> bool {
+        self.thread_ts.as_deref().is_some_and(|ts| ts != self.ts)
+    }
+
+    /// Returns who wrote the message: the user ID, or the bot name.
+    pub fn author(&self) -> &str {
+        self.user
+            .as_deref()
+            .or(self.username.as_deref())
+            .unwrap_or("unknown")
+    }
+}
+
+#[derive(Deserialize)]
+struct ConversationsResponse {
+    ok: bool,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    messages: Vec<SlackMessage>,
+    #[serde(default)]
+    response_metadata: Option<ResponseMetadata>,
+}
+
+#[derive(Deserialize)]
+struct ResponseMetadata {
+    #[serde(default)]
+    next_cursor: String,
+}
+
+/// A Slack Web API client authenticated with a bot or user token.
+#[derive(Debug, Clone)]
+pub struct SlackClient {
+    http: Client,
+    base_url: String,
+    token: String,
+}
+
+impl SlackClient {
+    /// Creates a client for the API at `base_url`.
+    pub fn new(base_url: &str, token: &str) -> Self {
+        Self {
+            http: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+        }
+    }
+
+    /// Sends the requests to another API base URL, keeping the token.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Fetches every message of a channel, newest first, optionally only those posted
+    /// after the `oldest` timestamp.
+    pub async fn channel_history(
+        &self,
+        channel_id: &str,
+        oldest: Option<&str>,
+    ) -> Result<Vec<SlackMessage>, SlackIngestError> {
+        let mut query = vec![("channel", channel_id.to_string())];
+        if let Some(oldest) = oldest {
+            query.push(("oldest", oldest.to_string()));
+        }
+        self.paginate(HISTORY_METHOD, query).await
+    }
+
+    /// Fetches a thread, its parent message first.
+    pub async fn thread_replies(
+        &self,
+        channel_id: &str,
+        thread_ts: &str,
+    ) -> Result<Vec<SlackMessage>, SlackIngestError> {
+        let query = vec![
+            ("channel", channel_id.to_string()),
+            ("ts", thread_ts.to_string()),
+        ];
+        self.paginate(REPLIES_METHOD, query).await
+    }
+
+    /// Calls `method` page by page until the cursor runs out.
+    async fn paginate(
+        &self,
+        method: &str,
+        query: Vec<(&'static str, String)>,
+    ) -> Result<Vec<SlackMessage>, SlackIngestError> {
+        let mut messages = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut page_query = query.clone();
+            page_query.push(("limit", PAGE_LIMIT.to_string()));
+            if let Some(cursor) = &cursor {
+                page_query.push(("cursor", cursor.clone()));
+            }
+
+            let page = self.call(method, &page_query).await?;
+            messages.extend(page.messages);
+            cursor = page
+                .response_metadata
+                .map(|metadata| metadata.next_cursor)
+                .filter(|next| !next.is_empty());
+            if cursor.is_none() {
+                break;
+            }
+        }
+        info!("Fetched {} messages with {method}.", messages.len());
+        Ok(messages)
+    }
+
+    /// Calls `method` once, waiting and retrying while Slack rate-limits the request.
+    async fn call(
+        &self,
+        method: &str,
+        query: &[(&str, String)],
+    ) -> Result<ConversationsResponse, SlackIngestError> {
+        let url = format!("{}/{method}", self.base_url);
+        let mut retries = 0;
+        loop {
+            let response = self
+                .http
+                .get(&url)
+                .bearer_auth(&self.token)
+                .query(query)
+                .send()
+                .await?;
+
+            if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                if retries >= MAX_RATE_LIMIT_RETRIES {
+                    return Err(SlackIngestError::RateLimited(method.to_string()));
+                }
+                retries += 1;
+                let wait = retry_after(&response);
+                warn!("Slack rate-limited {method}, retrying in {wait:?} (attempt {retries}).");
+                tokio::time::sleep(wait).await;
+                continue;
+            }
+
+            let body: ConversationsResponse = response.error_for_status()?.json().await?;
+            if !body.ok {
+                let error = body.error.unwrap_or_default();
+                return Err(SlackIngestError::Api(format!("{method} failed: {error}")));
+            }
+            return Ok(body);
+        }
+    }
+}
+
+/// Reads how long Slack asks to wait from the `Retry-After` header, in seconds.
+fn retry_after(response: &Response) -> Duration {
+    let secs = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_RETRY_AFTER_SECS);
+    Duration::from_secs(secs)
+}
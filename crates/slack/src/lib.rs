@@ -0,0 +1,288 @@
+//! # `anyrag-slack`: Slack Ingestion Plugin
+//!
+//! This crate provides the logic for ingesting the history of Slack channels as a
+//! self-contained plugin for the `anyrag` ecosystem. It implements the `Ingestor` trait
+//! from the core `anyrag` library.
+//!
+//! Each top-level message becomes one document together with its thread replies, so a
+//! question and the answers it received are found and cited together.
+
+use anyhow::anyhow;
+use anyrag::ingest::{
+    content_hash, find_duplicate_document, record_revision, state_manager, IngestError,
+    IngestionResult, Ingestor,
+};
+use async_trait::async_trait;
+use chrono::DateTime;
+use client::{SlackClient, SlackMessage, DEFAULT_SLACK_API_URL};
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::info;
+use turso::{params, Database};
+use uuid::Uuid;
+
+pub mod client;
+
+/// The `project_id` under which `state_manager` keeps the last synced timestamp of
+/// each channel.
+const SYNC_STATE_PROJECT: &str = "slack";
+/// The base of the permalinks used as document source URLs.
+const ARCHIVE_BASE_URL: &str = "https://slack.com/archives";
+/// The maximum number of characters of a parent message used as the document title.
+const TITLE_MAX_CHARS: usize = 80;
+/// Message subtypes that carry no knowledge.
+const SKIPPED_SUBTYPES: [&str; 2] = ["channel_join", "channel_leave"];
+
+/// Custom error types for the Slack ingestion process.
+#[derive(Error, Debug)]
+pub enum SlackIngestError {
+    #[error("Database connection failed: {0}")]
+    Database(#[from] turso::Error),
+    #[error("Failed to call the Slack API: {0}")]
+    Fetch(#[from] reqwest::Error),
+    #[error("Slack API returned an error: {0}")]
+    Api(String),
+    #[error("Slack kept rate-limiting {0}, giving up")]
+    RateLimited(String),
+    #[error("Source deserialization failed: {0}")]
+    SourceDeserialization(#[from] serde_json::Error),
+    #[error("Failed to access the sync state: {0}")]
+    State(String),
+}
+
+/// A helper to convert the specific `SlackIngestError` into the generic `anyrag::ingest::IngestError`.
+impl From<SlackIngestError> for IngestError {
+    fn from(err: SlackIngestError) -> Self {
+        match err {
+            SlackIngestError::Database(e) => IngestError::Database(e),
+            SlackIngestError::Fetch(e) => IngestError::Fetch(e.to_string()),
+            SlackIngestError::Api(e) => IngestError::Fetch(e),
+            SlackIngestError::RateLimited(_) => IngestError::Fetch(err.to_string()),
+            SlackIngestError::SourceDeserialization(e) => {
+                IngestError::Parse(format!("Invalid source JSON for Slack ingest: {e}"))
+            }
+            SlackIngestError::State(e) => IngestError::Internal(anyhow!(e)),
+        }
+    }
+}
+
+/// Defines the structure of the JSON string passed to the `ingest` method.
+#[derive(Deserialize)]
+struct SlackSource {
+    channel_id: String,
+    /// Fetches only the messages posted since the last incremental sync of the channel.
+    #[serde(default)]
+    incremental: bool,
+}
+
+/// A document prepared from a Slack thread before it is stored.
+struct ThreadDocument {
+    source_url: String,
+    title: String,
+    content: String,
+}
+
+/// The `Ingestor` implementation for Slack channels.
+pub struct SlackIngestor {
+    db: Database,
+    client: SlackClient,
+}
+
+impl SlackIngestor {
+    /// Creates a new `SlackIngestor` authenticated with a bot token (`xoxb-...`) that has
+    /// the `channels:history` scope.
+    pub fn new(db: &Database, token: &str) -> Self {
+        Self {
+            db: db.clone(),
+            client: SlackClient::new(DEFAULT_SLACK_API_URL, token),
+        }
+    }
+
+    /// Points the ingestor at another Slack-compatible API, such as a mock server.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.client = self.client.with_base_url(base_url);
+        self
+    }
+}
+
+#[async_trait]
+impl Ingestor for SlackIngestor {
+    /// Fetches a channel's history and stores each thread as a document.
+    ///
+    /// The `source` argument is expected to be a JSON string with a `channel_id` key,
+    /// for example: `{"channel_id": "C0123456789"}`. With `"incremental": true`, only
+    /// messages newer than the last incremental sync are fetched. New replies to
+    /// threads started before that sync are picked up by a full sync.
+    async fn ingest(
+        &self,
+        source: &str,
+        owner_id: Option<&str>,
+    ) -> Result<IngestionResult, IngestError> {
+        let slack_source: SlackSource =
+            serde_json::from_str(source).map_err(SlackIngestError::from)?;
+        let channel_id = &slack_source.channel_id;
+
+        let oldest = match slack_source.incremental {
+            true => state_manager::read_last_timestamp(SYNC_STATE_PROJECT, channel_id)
+                .map_err(|e| SlackIngestError::State(e.to_string()))?,
+            false => None,
+        };
+
+        info!("Fetching Slack history of channel {channel_id} (oldest: {oldest:?}).");
+        let mut parents: Vec<SlackMessage> = self
+            .client
+            .channel_history(channel_id, oldest.as_deref())
+            .await?
+            .into_iter()
+            .filter(|message| !message.is_thread_reply() && is_knowledge(message))
+            .collect();
+        // Slack timestamps have a fixed number of digits, so they sort as strings.
+        parents.sort_by(|a, b| a.ts.cmp(&b.ts));
+
+        let Some(latest_ts) = parents.last().map(|message| message.ts.clone()) else {
+            info!("Slack channel {channel_id} has no new messages to ingest.");
+            return Ok(IngestionResult {
+                source: channel_id.to_string(),
+                ..Default::default()
+            });
+        };
+
+        // Threads are fetched before the transaction so it is not held open over the network.
+        let mut documents = Vec::new();
+        for parent in parents {
+            let messages = match parent.reply_count {
+                0 => vec![parent],
+                _ => self.client.thread_replies(channel_id, &parent.ts).await?,
+            };
+            let messages: Vec<SlackMessage> = messages.into_iter().filter(is_knowledge).collect();
+            if let Some(document) = thread_document(channel_id, &messages) {
+                documents.push(document);
+            }
+        }
+
+        let mut conn = self.db.connect().map_err(SlackIngestError::from)?;
+        let tx = conn.transaction().await.map_err(SlackIngestError::from)?;
+        let mut new_document_ids = Vec::new();
+
+        for document in documents {
+            let ThreadDocument {
+                source_url,
+                title,
+                content,
+            } = document;
+            let document_id = Uuid::new_v5(&Uuid::NAMESPACE_URL, source_url.as_bytes()).to_string();
+            let hash = content_hash(&content);
+            if find_duplicate_document(&tx, owner_id, &hash)
+                .await
+                .map_err(SlackIngestError::from)?
+                .is_some()
+            {
+                info!("Skipping unchanged Slack thread: {}", source_url);
+                continue;
+            }
+
+            // The `source_url` is the permalink of the thread's parent message, so a
+            // thread that gained replies replaces its previous version.
+            record_revision(&tx, &source_url, &content)
+                .await
+                .map_err(SlackIngestError::from)?;
+            let changes = tx
+                .execute(
+                    "INSERT INTO documents (id, owner_id, source_url, title, content, content_hash)
+                     VALUES (?, ?, ?, ?, ?, ?)
+                     ON CONFLICT(source_url) DO UPDATE SET
+                     title = excluded.title,
+                     content = excluded.content,
+                     content_hash = excluded.content_hash",
+                    params![
+                        document_id.clone(),
+                        owner_id,
+                        source_url,
+                        title,
+                        content,
+                        hash
+                    ],
+                )
+                .await
+                .map_err(SlackIngestError::from)?;
+
+            if changes > 0 {
+                new_document_ids.push(document_id);
+            }
+        }
+
+        tx.commit().await.map_err(SlackIngestError::from)?;
+
+        if slack_source.incremental {
+            state_manager::write_last_timestamp(SYNC_STATE_PROJECT, channel_id, &latest_ts)
+                .map_err(|e| SlackIngestError::State(e.to_string()))?;
+        }
+
+        info!(
+            "Ingested {} new threads from Slack channel {channel_id}.",
+            new_document_ids.len()
+        );
+
+        Ok(IngestionResult {
+            documents_added: new_document_ids.len(),
+            source: channel_id.to_string(),
+            document_ids: new_document_ids,
+            metadata: None,
+        })
+    }
+}
+
+/// Returns whether a message has content worth storing.
+fn is_knowledge(message: &SlackMessage) -> bool {
+    let skipped = message
+        .subtype
+        .as_deref()
+        .is_some_and(|subtype| SKIPPED_SUBTYPES.contains(&subtype));
+    !skipped && !message.text.trim().is_empty()
+}
+
+/// Builds the document of a thread from its messages, parent first.
+fn thread_document(channel_id: &str, messages: &[SlackMessage]) -> Option<ThreadDocument> {
+    let parent = messages.first()?;
+    let title: String = parent
+        .text
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .take(TITLE_MAX_CHARS)
+        .collect();
+    let content = messages
+        .iter()
+        .map(format_message)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Some(ThreadDocument {
+        source_url: permalink(channel_id, &parent.ts),
+        title,
+        content,
+    })
+}
+
+/// Returns the permalink of a message, e.g. `https://slack.com/archives/C123/p1700000000000100`.
+pub fn permalink(channel_id: &str, ts: &str) -> String {
+    format!("{ARCHIVE_BASE_URL}/{channel_id}/p{}", ts.replace('.', ""))
+}
+
+/// Formats a message as `[YYYY-MM-DD HH:MM UTC] author: text`.
+fn format_message(message: &SlackMessage) -> String {
+    let posted_at = message
+        .ts
+        .split('.')
+        .next()
+        .and_then(|secs| secs.parse::<i64>().ok())
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .map(|time| time.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| message.ts.clone());
+    format!(
+        "[{posted_at}] {}: {}",
+        message.author(),
+        message.text.trim()
+    )
+}
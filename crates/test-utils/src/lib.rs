@@ -19,10 +19,8 @@ impl TestSetup {
         let db = turso::Builder::new_local(":memory:").build().await?;
         let conn = db.connect()?;
 
-        // Initialize the schema using the shared SQL constants.
-        for statement in anyrag::providers::db::sqlite::sql::ALL_TABLE_CREATION_SQL {
-            conn.execute(statement, ()).await?;
-        }
+        // Initialize the schema with the application's migrations.
+        anyrag::providers::db::sqlite::migrations::run_migrations(&conn).await?;
 
         Ok(Self { db })
     }
@@ -82,6 +80,17 @@ impl AiProvider for MockAiProvider {
             ))
         }
     }
+    /// Answers from the same queue as `generate`, recording the image URL after the
+    /// user prompt.
+    async fn describe_image(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        image_url: &str,
+    ) -> Result<String, PromptError> {
+        self.generate(system_prompt, &format!("{user_prompt}\n{image_url}"))
+            .await
+    }
 }
 
 // --- Test-Specific Helpers ---
@@ -95,10 +104,12 @@ pub mod helpers {
 
     /// Generates a simple, single-page PDF with the given text content, compatible with printpdf v0.8.2.
     pub fn generate_test_pdf(text: &str) -> Result<Vec<u8>> {
+        generate_multi_page_test_pdf(&[text])
+    }
+
+    /// Generates a PDF with one page per entry of `pages`, each holding that text.
+    pub fn generate_multi_page_test_pdf(pages: &[&str]) -> Result<Vec<u8>> {
         let mut doc = PdfDocument::new("Test PDF");
-        let mut page = PdfPage::new(Mm(210.0), Mm(297.0), vec![]);
-        let layer_def = Layer::new("Layer 1");
-        let layer_id = doc.add_layer(&layer_def);
 
         // Get the font bytes for a built-in font and parse it.
         let font_bytes = BuiltinFont::Helvetica.get_subset_font().bytes;
@@ -106,31 +117,37 @@ pub mod helpers {
             .ok_or_else(|| anyhow::anyhow!("Failed to parse built-in font"))?;
         let font_id = doc.add_font(&font);
 
-        let ops = vec![
-            Op::BeginLayer {
-                layer_id: layer_id.clone(),
-            },
-            Op::SetFontSize {
-                size: Pt(12.0),
-                font: font_id.clone(),
-            },
-            Op::StartTextSection,
-            Op::SetTextMatrix {
-                matrix: TextMatrix::Translate(Mm(10.0).into(), Mm(280.0).into()),
-            },
-            Op::SetTextRenderingMode {
-                mode: TextRenderingMode::Fill,
-            },
-            Op::WriteText {
-                items: vec![TextItem::Text(text.to_string())],
-                font: font_id,
-            },
-            Op::EndTextSection,
-            Op::EndLayer { layer_id },
-        ];
-
-        page.ops = ops;
-        doc.pages.push(page);
+        for (index, text) in pages.iter().enumerate() {
+            let mut page = PdfPage::new(Mm(210.0), Mm(297.0), vec![]);
+            let layer_def = Layer::new(&format!("Layer {}", index + 1));
+            let layer_id = doc.add_layer(&layer_def);
+
+            let ops = vec![
+                Op::BeginLayer {
+                    layer_id: layer_id.clone(),
+                },
+                Op::SetFontSize {
+                    size: Pt(12.0),
+                    font: font_id.clone(),
+                },
+                Op::StartTextSection,
+                Op::SetTextMatrix {
+                    matrix: TextMatrix::Translate(Mm(10.0).into(), Mm(280.0).into()),
+                },
+                Op::SetTextRenderingMode {
+                    mode: TextRenderingMode::Fill,
+                },
+                Op::WriteText {
+                    items: vec![TextItem::Text(text.to_string())],
+                    font: font_id.clone(),
+                },
+                Op::EndTextSection,
+                Op::EndLayer { layer_id },
+            ];
+
+            page.ops = ops;
+            doc.pages.push(page);
+        }
 
         let mut warnings = Vec::new();
         let bytes = doc.save(&PdfSaveOptions::default(), &mut warnings);
@@ -6,19 +6,19 @@
 //! as a separate document.
 
 use anyhow::anyhow;
-use anyrag::ingest::{IngestError as AnyragIngestError, IngestionResult, Ingestor};
+pub use anyrag::ingest::chunking::{DEFAULT_CHUNK_OVERLAP, DEFAULT_CHUNK_SIZE};
+use anyrag::ingest::{
+    bulk_insert_documents,
+    chunking::{validate_chunk_sizes, ParagraphChunker},
+    Chunker, ChunkingStrategy, IngestError as AnyragIngestError, IngestionResult, Ingestor,
+    NewDocument,
+};
 use async_trait::async_trait;
 use serde::Deserialize;
 use thiserror::Error;
-use tracing::warn;
-use turso::{params, Connection, Database};
+use turso::{Connection, Database};
 use uuid::Uuid;
 
-/// The target maximum size for a single text chunk in characters.
-const CHUNK_SIZE_LIMIT: usize = 4096;
-/// The character overlap to include between consecutive chunks.
-const CHUNK_OVERLAP: usize = 200;
-
 /// Custom error types for the text ingestion process.
 #[derive(Error, Debug)]
 pub enum TextIngestError {
@@ -28,6 +28,8 @@ pub enum TextIngestError {
     Database(#[from] turso::Error),
     #[error("Source deserialization failed: {0}")]
     SourceDeserialization(#[from] serde_json::Error),
+    #[error("Invalid chunk configuration: {0}")]
+    InvalidChunkConfig(String),
 }
 
 /// A helper to convert the specific `TextIngestError` into the generic `anyrag::ingest::IngestError`.
@@ -41,6 +43,9 @@ impl From<TextIngestError> for AnyragIngestError {
             TextIngestError::SourceDeserialization(e) => {
                 AnyragIngestError::Internal(anyhow!("Failed to deserialize source JSON: {e}"))
             }
+            TextIngestError::InvalidChunkConfig(msg) => {
+                AnyragIngestError::Parse(format!("Invalid chunk configuration: {msg}"))
+            }
         }
     }
 }
@@ -50,6 +55,15 @@ impl From<TextIngestError> for AnyragIngestError {
 struct TextSource {
     text: String,
     source: String,
+    /// The maximum paragraph chunk size in characters. Defaults to `DEFAULT_CHUNK_SIZE`.
+    #[serde(default)]
+    chunk_size: Option<usize>,
+    /// The overlap between split chunks in characters. Defaults to `DEFAULT_CHUNK_OVERLAP`.
+    #[serde(default)]
+    chunk_overlap: Option<usize>,
+    /// The chunking strategy to use. Defaults to one chunk per paragraph.
+    #[serde(default)]
+    chunking: Option<ChunkingStrategy>,
 }
 
 /// The `Ingestor` implementation for raw text.
@@ -71,6 +85,9 @@ impl Ingestor for TextIngestor<'_> {
     /// The `source` argument is expected to be a JSON string with `text` and `source`
     /// keys, for example:
     /// `{"text": "This is the content.", "source": "manual_input"}`.
+    /// Optional `chunk_size` and `chunk_overlap` keys tune the default paragraph
+    /// chunking, and an optional `chunking` key selects another strategy instead,
+    /// for example `{"strategy": "sentence", "chunk_size": 1000}`.
     async fn ingest(
         &self,
         source: &str,
@@ -78,7 +95,26 @@ impl Ingestor for TextIngestor<'_> {
     ) -> Result<IngestionResult, AnyragIngestError> {
         let text_source: TextSource =
             serde_json::from_str(source).map_err(TextIngestError::from)?;
-        let chunks = chunk_text(&text_source.text)?;
+        let chunks = match &text_source.chunking {
+            Some(strategy) => {
+                if text_source.chunk_size.is_some() || text_source.chunk_overlap.is_some() {
+                    return Err(TextIngestError::InvalidChunkConfig(
+                        "set `chunk_size` and `chunk_overlap` inside `chunking` when a strategy is given"
+                            .to_string(),
+                    )
+                    .into());
+                }
+                let chunker = strategy
+                    .chunker()
+                    .map_err(|e| TextIngestError::InvalidChunkConfig(e.0))?;
+                chunk_text_with(&text_source.text, chunker.as_ref())?
+            }
+            None => chunk_text(
+                &text_source.text,
+                text_source.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE),
+                text_source.chunk_overlap.unwrap_or(DEFAULT_CHUNK_OVERLAP),
+            )?,
+        };
         let mut conn = self.db.connect().map_err(TextIngestError::from)?;
         let document_ids =
             ingest_chunks_as_documents(&mut conn, chunks, &text_source.source, owner_id).await?;
@@ -92,99 +128,64 @@ impl Ingestor for TextIngestor<'_> {
     }
 }
 
-/// Chunks a given text into smaller pieces based on paragraphs and size limits.
-pub fn chunk_text(text: &str) -> Result<Vec<String>, TextIngestError> {
-    let trimmed_text = text.trim();
-    if trimmed_text.is_empty() {
-        return Err(TextIngestError::EmptyContent);
-    }
-
-    let mut chunks = Vec::new();
-    let paragraphs = trimmed_text.split("\n\n");
+/// Checks that a chunk size and overlap can produce chunks.
+pub fn validate_chunk_config(
+    chunk_size: usize,
+    chunk_overlap: usize,
+) -> Result<(), TextIngestError> {
+    validate_chunk_sizes(chunk_size, chunk_overlap)
+        .map_err(|e| TextIngestError::InvalidChunkConfig(e.0))
+}
 
-    for paragraph in paragraphs {
-        let p_trimmed = paragraph.trim();
-        if p_trimmed.is_empty() {
-            continue;
-        }
+/// Chunks a given text into smaller pieces based on paragraphs and size limits.
+///
+/// Paragraphs longer than `chunk_size` characters are split into windows that
+/// overlap by `chunk_overlap` characters.
+pub fn chunk_text(
+    text: &str,
+    chunk_size: usize,
+    chunk_overlap: usize,
+) -> Result<Vec<String>, TextIngestError> {
+    validate_chunk_config(chunk_size, chunk_overlap)?;
+    chunk_text_with(
+        text,
+        &ParagraphChunker {
+            chunk_size,
+            chunk_overlap,
+        },
+    )
+}
 
-        if p_trimmed.chars().count() <= CHUNK_SIZE_LIMIT {
-            chunks.push(p_trimmed.to_string());
-        } else {
-            warn!(
-                "Paragraph exceeds chunk size limit ({} > {}). Splitting by character.",
-                p_trimmed.chars().count(),
-                CHUNK_SIZE_LIMIT
-            );
-            let mut sub_chunks = split_long_text(p_trimmed);
-            chunks.append(&mut sub_chunks);
-        }
+/// Chunks a given text with the given chunking strategy.
+pub fn chunk_text_with(text: &str, chunker: &dyn Chunker) -> Result<Vec<String>, TextIngestError> {
+    if text.trim().is_empty() {
+        return Err(TextIngestError::EmptyContent);
     }
-
-    Ok(chunks)
+    Ok(chunker.chunk(text))
 }
 
 /// Takes a vector of text chunks and ingests them into the `documents` table.
+///
+/// Chunks whose content the owner already has are skipped, so only the ids of
+/// newly stored documents are returned. The chunks are stored with
+/// `bulk_insert_documents`, which takes the write permit only for their transaction.
 pub async fn ingest_chunks_as_documents(
     conn: &mut Connection,
     chunks: Vec<String>,
     source_identifier: &str,
     owner_id: Option<&str>,
 ) -> Result<Vec<String>, TextIngestError> {
-    if chunks.is_empty() {
-        return Ok(Vec::new());
-    }
-
-    let tx = conn.transaction().await?;
-    let mut new_document_ids = Vec::new();
-
-    for (i, chunk) in chunks.iter().enumerate() {
-        let document_id = Uuid::new_v4().to_string();
-        // Create a unique source URL for each chunk to avoid collisions.
-        let source_url = format!("{source_identifier}#chunk_{i}");
-        let title: String = chunk.chars().take(80).collect();
-
-        tx.execute(
-            "INSERT INTO documents (id, owner_id, source_url, title, content)
-             VALUES (?, ?, ?, ?, ?)
-             ON CONFLICT(source_url) DO UPDATE SET
-             title = excluded.title,
-             content = excluded.content",
-            params![
-                document_id.clone(),
-                owner_id,
-                source_url,
-                title,
-                chunk.clone()
-            ],
-        )
-        .await?;
-        new_document_ids.push(document_id);
-    }
-
-    tx.commit().await?;
-
-    Ok(new_document_ids)
-}
-
-/// Splits a long string into chunks that are at most `CHUNK_SIZE_LIMIT` characters long.
-fn split_long_text(text: &str) -> Vec<String> {
-    let mut chunks = Vec::new();
-    let chars: Vec<char> = text.chars().collect();
-    let mut start = 0;
-
-    while start < chars.len() {
-        let end = std::cmp::min(start + CHUNK_SIZE_LIMIT, chars.len());
-        let chunk: String = chars[start..end].iter().collect();
-        chunks.push(chunk);
-
-        // Move the start for the next chunk, considering the overlap.
-        let next_start = start + CHUNK_SIZE_LIMIT - CHUNK_OVERLAP;
-        if next_start >= chars.len() || next_start <= start {
-            break;
-        }
-        start = next_start;
-    }
+    let documents = chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| NewDocument {
+            id: Uuid::new_v4().to_string(),
+            // Create a unique source URL for each chunk to avoid collisions.
+            source_url: format!("{source_identifier}#chunk_{i}"),
+            title: chunk.chars().take(80).collect(),
+            content: chunk,
+        })
+        .collect();
 
-    chunks
+    Ok(bulk_insert_documents(conn, owner_id, documents).await?)
 }
@@ -5,23 +5,26 @@
 //! independent of the main server.
 
 use anyhow::Result;
-use anyrag::ingest::Ingestor;
+use anyrag::ingest::{IngestError, Ingestor};
 use anyrag_test_utils::TestSetup;
-use anyrag_text::{chunk_text, ingest_chunks_as_documents, TextIngestError, TextIngestor};
+use anyrag_text::{
+    chunk_text, ingest_chunks_as_documents, TextIngestError, TextIngestor, DEFAULT_CHUNK_OVERLAP,
+    DEFAULT_CHUNK_SIZE,
+};
 use serde_json::json;
 
 // --- Unit Tests for chunk_text ---
 
 #[test]
 fn test_chunk_text_empty_input() {
-    let result = chunk_text("   ");
+    let result = chunk_text("   ", DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_OVERLAP);
     assert!(matches!(result, Err(TextIngestError::EmptyContent)));
 }
 
 #[test]
 fn test_chunk_text_single_short_paragraph() -> Result<()> {
     let text = "This is a single paragraph that is well under the chunk size limit.";
-    let chunks = chunk_text(text)?;
+    let chunks = chunk_text(text, DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_OVERLAP)?;
     assert_eq!(chunks.len(), 1);
     assert_eq!(chunks[0], text);
     Ok(())
@@ -30,7 +33,7 @@ fn test_chunk_text_single_short_paragraph() -> Result<()> {
 #[test]
 fn test_chunk_text_multiple_paragraphs() -> Result<()> {
     let text = "First paragraph.\n\nSecond paragraph.\n\nThird paragraph.";
-    let chunks = chunk_text(text)?;
+    let chunks = chunk_text(text, DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_OVERLAP)?;
     assert_eq!(chunks.len(), 3);
     assert_eq!(chunks[0], "First paragraph.");
     assert_eq!(chunks[1], "Second paragraph.");
@@ -43,7 +46,7 @@ fn test_chunk_text_long_paragraph_split() -> Result<()> {
     // CHUNK_SIZE_LIMIT is 4096, CHUNK_OVERLAP is 200.
     // 5000 chars should be split into two chunks.
     let long_text = "a".repeat(5000);
-    let chunks = chunk_text(&long_text)?;
+    let chunks = chunk_text(&long_text, DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_OVERLAP)?;
 
     assert_eq!(chunks.len(), 2);
     // First chunk should be exactly the limit.
@@ -57,6 +60,32 @@ fn test_chunk_text_long_paragraph_split() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_chunk_text_custom_size_and_overlap() -> Result<()> {
+    let chunks = chunk_text(&"c".repeat(250), 100, 10)?;
+
+    // Chunks start at 0, 90, and 180.
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(chunks[0].chars().count(), 100);
+    assert_eq!(chunks[2].chars().count(), 70);
+    Ok(())
+}
+
+#[test]
+fn test_chunk_text_rejects_invalid_config() {
+    let zero_size = chunk_text("Some text.", 0, 0);
+    assert!(matches!(
+        zero_size,
+        Err(TextIngestError::InvalidChunkConfig(_))
+    ));
+
+    let overlap_too_large = chunk_text("Some text.", 100, 100);
+    assert!(matches!(
+        overlap_too_large,
+        Err(TextIngestError::InvalidChunkConfig(_))
+    ));
+}
+
 // --- Integration Tests for database interaction ---
 
 #[tokio::test]
@@ -154,3 +183,63 @@ async fn test_text_ingestor_e2e() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_text_ingestor_skips_duplicate_chunks() -> Result<()> {
+    // --- Arrange ---
+    let setup = TestSetup::new().await?;
+    let ingestor = TextIngestor::new(&setup.db);
+    let owner_id = "dedup-user@test.com";
+    let text = "Shared paragraph.\n\nAnother shared paragraph.";
+    let first = json!({ "text": text, "source": "first_source" }).to_string();
+    let second =
+        json!({ "text": format!("{text}\n\nA new paragraph."), "source": "second_source" })
+            .to_string();
+
+    // --- Act ---
+    let first_result = ingestor.ingest(&first, Some(owner_id)).await?;
+    let second_result = ingestor.ingest(&second, Some(owner_id)).await?;
+    let other_owner_result = ingestor.ingest(&first, Some("other-user@test.com")).await?;
+
+    // --- Assert ---
+    assert_eq!(first_result.documents_added, 2);
+    // Only the new paragraph is stored; the other two already exist for this owner.
+    assert_eq!(second_result.documents_added, 1);
+    // Deduplication is scoped to the owner.
+    assert_eq!(other_owner_result.documents_added, 2);
+
+    let conn = setup.db.connect()?;
+    let count: i64 = conn
+        .query(
+            "SELECT COUNT(*) FROM documents WHERE owner_id = ?",
+            [owner_id],
+        )
+        .await?
+        .next()
+        .await?
+        .unwrap()
+        .get(0)?;
+    assert_eq!(count, 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_text_ingestor_rejects_a_strategy_that_cannot_produce_chunks() -> Result<()> {
+    // --- Arrange ---
+    let setup = TestSetup::new().await?;
+    let ingestor = TextIngestor::new(&setup.db);
+    let source = json!({
+        "text": "a".repeat(500),
+        "source": "overlapping_strategy",
+        "chunking": { "strategy": "fixed_size", "chunk_size": 100, "chunk_overlap": 100 }
+    })
+    .to_string();
+
+    // --- Act ---
+    let result = ingestor.ingest(&source, None).await;
+
+    // --- Assert: Nothing past the first window is silently dropped ---
+    assert!(matches!(result, Err(IngestError::Parse(_))));
+    Ok(())
+}
@@ -0,0 +1,236 @@
+//! # Website Crawling
+//!
+//! This module decides which pages a crawl visits: it normalizes URLs so each page is
+//! fetched once, keeps the crawl on the seed's host, applies the include and exclude
+//! patterns, and honors the site's `robots.txt`. The pages themselves are ingested by
+//! `WebIngestor` through the regular web pipeline.
+
+use crate::WebIngestError;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use url::Url;
+
+/// The default number of links followed away from the seed URL.
+pub const DEFAULT_MAX_DEPTH: usize = 2;
+/// The default maximum number of pages ingested by a single crawl.
+pub const DEFAULT_MAX_PAGES: usize = 20;
+
+/// The user agent whose `robots.txt` rules the crawler obeys, besides `*`.
+const ROBOTS_USER_AGENT: &str = "anyrag";
+/// The path of the robots exclusion file on every host.
+const ROBOTS_TXT_PATH: &str = "/robots.txt";
+/// The `robots.txt` user agent matching every crawler.
+const ROBOTS_WILDCARD_AGENT: &str = "*";
+
+/// Options of a crawl, as found in the `crawl` field of the web source JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CrawlOptions {
+    /// How many links away from the seed URL the crawl goes; `0` ingests the seed only.
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize,
+    /// The maximum number of pages ingested.
+    #[serde(default = "default_max_pages")]
+    pub max_pages: usize,
+    /// Regexes matched against absolute URLs; if any are given, a link must match one.
+    #[serde(default)]
+    pub include_patterns: Vec<String>,
+    /// Regexes matched against absolute URLs; links matching any of them are skipped.
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_pages: DEFAULT_MAX_PAGES,
+            include_patterns: Vec::new(),
+            exclude_patterns: Vec::new(),
+        }
+    }
+}
+
+fn default_max_depth() -> usize {
+    DEFAULT_MAX_DEPTH
+}
+
+fn default_max_pages() -> usize {
+    DEFAULT_MAX_PAGES
+}
+
+/// Decides which discovered links a crawl follows.
+#[derive(Debug, Clone)]
+pub struct CrawlScope {
+    seed: Url,
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+    robots: RobotsRules,
+}
+
+impl CrawlScope {
+    /// Compiles the patterns of `options` for a crawl starting at `seed`.
+    pub fn new(
+        seed: Url,
+        options: &CrawlOptions,
+        robots: RobotsRules,
+    ) -> Result<Self, WebIngestError> {
+        Ok(Self {
+            seed,
+            include: compile_patterns(&options.include_patterns)?,
+            exclude: compile_patterns(&options.exclude_patterns)?,
+            robots,
+        })
+    }
+
+    /// The `robots.txt` rules of the seed's host.
+    pub fn robots(&self) -> &RobotsRules {
+        &self.robots
+    }
+
+    /// Returns whether `url` is an HTTP(S) page on the seed's host that the patterns
+    /// and `robots.txt` allow.
+    pub fn allows(&self, url: &Url) -> bool {
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+        if url.host_str() != self.seed.host_str() {
+            return false;
+        }
+        let url_str = url.as_str();
+        if !self.include.is_empty() && !self.include.iter().any(|re| re.is_match(url_str)) {
+            return false;
+        }
+        if self.exclude.iter().any(|re| re.is_match(url_str)) {
+            return false;
+        }
+        self.robots.is_allowed(url.path())
+    }
+}
+
+/// Compiles URL patterns, reporting the first invalid one.
+pub(crate) fn compile_patterns(patterns: &[String]) -> Result<Vec<Regex>, WebIngestError> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Regex::new(pattern)
+                .map_err(|e| WebIngestError::InvalidCrawlPattern(format!("'{pattern}': {e}")))
+        })
+        .collect()
+}
+
+/// Returns the form of `url` used to tell pages apart: without its fragment and
+/// without a trailing slash, so `/docs/`, `/docs`, and `/docs#intro` are one page.
+pub fn normalize_url(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    let path = url.path().trim_end_matches('/').to_string();
+    match path.is_empty() {
+        true => url.set_path("/"),
+        false => url.set_path(&path),
+    }
+    url.to_string()
+}
+
+/// The `Allow` and `Disallow` rules of a `robots.txt` that apply to this crawler.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RobotsRules {
+    /// `(allowed, path prefix)` pairs.
+    rules: Vec<(bool, String)>,
+}
+
+impl RobotsRules {
+    /// Parses a `robots.txt`, keeping the rules of the group for this crawler's user
+    /// agent, or of the `*` group if there is no such group.
+    pub fn parse(robots_txt: &str) -> Self {
+        let mut groups: Vec<(Vec<String>, Vec<(bool, String)>)> = Vec::new();
+        let mut in_rules = false;
+
+        for line in robots_txt.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_lowercase().as_str() {
+                "user-agent" => {
+                    // A user agent after rules starts a new group.
+                    if in_rules || groups.is_empty() {
+                        groups.push((Vec::new(), Vec::new()));
+                        in_rules = false;
+                    }
+                    if let Some((agents, _)) = groups.last_mut() {
+                        agents.push(value.to_lowercase());
+                    }
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    // An empty `Disallow` allows everything and adds no rule.
+                    if value.is_empty() {
+                        continue;
+                    }
+                    if let Some((_, rules)) = groups.last_mut() {
+                        rules.push((key.trim().eq_ignore_ascii_case("allow"), value.to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let matching = |agent: &str| -> Vec<(bool, String)> {
+            groups
+                .iter()
+                .filter(|(agents, _)| agents.iter().any(|a| a == agent))
+                .flat_map(|(_, rules)| rules.iter().cloned())
+                .collect()
+        };
+        let rules = match groups
+            .iter()
+            .any(|(agents, _)| agents.iter().any(|a| a == ROBOTS_USER_AGENT))
+        {
+            true => matching(ROBOTS_USER_AGENT),
+            false => matching(ROBOTS_WILDCARD_AGENT),
+        };
+        Self { rules }
+    }
+
+    /// Returns whether `path` may be crawled. The longest matching rule wins, and an
+    /// `Allow` wins a tie.
+    pub fn is_allowed(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, prefix)| path.starts_with(prefix.as_str()))
+            .max_by_key(|(allowed, prefix)| (prefix.len(), *allowed))
+            .is_none_or(|(allowed, _)| *allowed)
+    }
+}
+
+/// Fetches and parses the `robots.txt` of the seed's host.
+///
+/// A missing or unreachable `robots.txt` allows everything.
+pub async fn fetch_robots_rules(seed: &Url) -> RobotsRules {
+    let Ok(robots_url) = seed.join(ROBOTS_TXT_PATH) else {
+        return RobotsRules::default();
+    };
+    let response = match reqwest::get(robots_url.as_str()).await {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => {
+            info!(
+                "No robots.txt at {robots_url} (status {}), crawling without restrictions.",
+                response.status()
+            );
+            return RobotsRules::default();
+        }
+        Err(e) => {
+            warn!("Failed to fetch {robots_url}: {e}. Crawling without restrictions.");
+            return RobotsRules::default();
+        }
+    };
+    match response.text().await {
+        Ok(body) => RobotsRules::parse(&body),
+        Err(e) => {
+            warn!("Failed to read {robots_url}: {e}. Crawling without restrictions.");
+            RobotsRules::default()
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! # Content-Change Detection
+//!
+//! Re-ingesting a page that has not changed would spend LLM tokens restructuring the
+//! same content again. This module remembers the `ETag` and `Last-Modified` headers and
+//! the content hash of every ingested page in the `web_sources` table, issues
+//! conditional requests with them, and lets the ingestor skip pages that are unchanged.
+
+use crate::WebIngestError;
+use reqwest::{
+    header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    StatusCode,
+};
+use tracing::info;
+use turso::{params, Database};
+
+/// The HTTP validators of a fetched page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+/// What is remembered about a page from its last ingestion.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WebSourceState {
+    pub validators: Validators,
+    /// The hash of the page's cleaned markdown, see `anyrag::ingest::content_hash`.
+    pub content_hash: Option<String>,
+}
+
+/// The result of a conditional request.
+#[derive(Debug)]
+pub enum ConditionalFetch {
+    /// The server answered `304 Not Modified`.
+    NotModified,
+    Modified {
+        body: String,
+        validators: Validators,
+    },
+}
+
+/// Loads what was remembered about `url` for an owner, if it was ingested before.
+///
+/// Nothing is returned once the page's documents are gone, so a deleted page is
+/// processed again instead of being reported as unchanged.
+pub async fn load_source_state(
+    db: &Database,
+    url: &str,
+    owner_id: Option<&str>,
+) -> Result<Option<WebSourceState>, WebIngestError> {
+    let conn = db.connect()?;
+    let mut rows = conn
+        .query(
+            "SELECT etag, last_modified, content_hash FROM web_sources ws
+             WHERE ws.source_url = ? AND ws.owner_id = ?
+             AND EXISTS (
+                SELECT 1 FROM documents d
+                WHERE d.source_url = ws.source_url AND COALESCE(d.owner_id, '') = ws.owner_id
+             )",
+            params![url, owner_id.unwrap_or_default()],
+        )
+        .await?;
+    let Some(row) = rows.next().await? else {
+        return Ok(None);
+    };
+    Ok(Some(WebSourceState {
+        validators: Validators {
+            etag: row.get(0)?,
+            last_modified: row.get(1)?,
+        },
+        content_hash: row.get(2)?,
+    }))
+}
+
+/// Remembers the validators and content hash of `url` for an owner.
+pub async fn save_source_state(
+    db: &Database,
+    url: &str,
+    owner_id: Option<&str>,
+    state: &WebSourceState,
+) -> Result<(), WebIngestError> {
+    let conn = db.connect()?;
+    conn.execute(
+        "INSERT INTO web_sources (source_url, owner_id, etag, last_modified, content_hash) VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT(source_url, owner_id) DO UPDATE SET
+            etag = excluded.etag,
+            last_modified = excluded.last_modified,
+            content_hash = excluded.content_hash,
+            fetched_at = CURRENT_TIMESTAMP",
+        params![
+            url,
+            owner_id.unwrap_or_default(),
+            state.validators.etag.clone(),
+            state.validators.last_modified.clone(),
+            state.content_hash.clone()
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Fetches `url`, sending the validators of its last fetch so the server can answer
+/// `304 Not Modified` instead of the body.
+pub async fn fetch_if_modified(
+    url: &str,
+    previous: Option<&Validators>,
+) -> Result<ConditionalFetch, WebIngestError> {
+    let mut request = reqwest::Client::new().get(url);
+    if let Some(etag) = previous.and_then(|v| v.etag.as_deref()) {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = previous.and_then(|v| v.last_modified.as_deref()) {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+    }
+
+    let response = request.send().await?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        info!("Server reports {url} as not modified.");
+        return Ok(ConditionalFetch::NotModified);
+    }
+    if !response.status().is_success() {
+        let status = response.status().as_u16();
+        let body = response.text().await.unwrap_or_default();
+        return Err(WebIngestError::Html(
+            anyrag_html::FetchError::Status { status, body }.to_string(),
+        ));
+    }
+
+    let header = |name: HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let validators = Validators {
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+    };
+    Ok(ConditionalFetch::Modified {
+        body: response.text().await?,
+        validators,
+    })
+}
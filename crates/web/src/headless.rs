@@ -0,0 +1,60 @@
+//! # Headless Browser Rendering
+//!
+//! Many documentation sites render their content with JavaScript and return an empty
+//! body to a plain HTTP request. This module connects to a running headless Chrome
+//! over the Chrome DevTools Protocol, lets it render the page, and returns the
+//! resulting HTML.
+
+use crate::WebIngestError;
+use chromiumoxide::Browser;
+use futures::StreamExt;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// The maximum time to wait for a page to load and render.
+const RENDER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Renders a page in the headless browser at `endpoint` and returns its HTML.
+///
+/// `endpoint` is the browser's DevTools address, either its WebSocket URL
+/// (`ws://host:9222/devtools/browser/...`) or its HTTP address (`http://host:9222`).
+pub async fn render_with_headless_browser(
+    endpoint: &str,
+    url: &str,
+) -> Result<String, WebIngestError> {
+    info!("Rendering '{url}' with the headless browser at '{endpoint}'.");
+    let (browser, mut handler) = Browser::connect(endpoint)
+        .await
+        .map_err(|e| WebIngestError::Headless(format!("Failed to connect to '{endpoint}': {e}")))?;
+
+    // The handler drives the DevTools connection and must be polled while the browser is used.
+    let handler_task = tokio::spawn(async move {
+        while let Some(event) = handler.next().await {
+            if let Err(e) = event {
+                warn!("Headless browser connection error: {e}");
+                break;
+            }
+        }
+    });
+
+    let result = tokio::time::timeout(RENDER_TIMEOUT, async {
+        let page = browser.new_page(url).await?;
+        page.wait_for_navigation().await?;
+        let html = page.content().await?;
+        page.close().await?;
+        Ok::<_, chromiumoxide::error::CdpError>(html)
+    })
+    .await;
+    handler_task.abort();
+
+    match result {
+        Ok(Ok(html)) => Ok(html),
+        Ok(Err(e)) => Err(WebIngestError::Headless(format!(
+            "Failed to render '{url}': {e}"
+        ))),
+        Err(_) => Err(WebIngestError::Headless(format!(
+            "Rendering '{url}' timed out after {}s.",
+            RENDER_TIMEOUT.as_secs()
+        ))),
+    }
+}
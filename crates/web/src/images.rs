@@ -0,0 +1,141 @@
+//! # Page Images
+//!
+//! This module keeps the images of an ingested page, which the markdown conversion
+//! would otherwise drop, so the diagrams of technical docs are not lost. Each image is
+//! stored as `IMAGE` metadata of the page's documents, with its absolute URL as the
+//! subtype and its description as the value: a caption written by a vision model when
+//! one was asked for, or else the figure caption or alt text from the page.
+
+use anyrag::{
+    prompts::tasks::{IMAGE_CAPTIONING_SYSTEM_PROMPT, IMAGE_CAPTIONING_USER_PROMPT},
+    providers::ai::AiProvider,
+};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tracing::warn;
+use turso::{params, Connection};
+use url::Url;
+
+/// The `metadata_type` of the image rows in `content_metadata`.
+pub const IMAGE_METADATA_TYPE: &str = "IMAGE";
+/// The HTML tag of images, kept in the markdown when images are stored.
+pub(crate) const IMAGE_TAG: &str = "img";
+
+/// How the images of a page are kept, from the `images` option of a web source.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+pub struct ImageOptions {
+    /// Describes each image with a vision model, the one set by
+    /// `WebIngestor::with_image_captioning` or else the ingestor's own.
+    #[serde(default)]
+    pub caption: bool,
+}
+
+/// An image of a page.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PageImage {
+    /// The absolute URL of the image.
+    pub url: String,
+    /// The `alt` text, if any.
+    pub alt: Option<String>,
+    /// The caption of the figure holding the image, if any.
+    pub caption: Option<String>,
+    /// The description written by a vision model, if the image was captioned.
+    pub generated_caption: Option<String>,
+}
+
+impl PageImage {
+    /// The text stored for the image: the generated caption, else the figure caption,
+    /// else the alt text, else the file name from its URL.
+    pub fn description(&self) -> String {
+        self.generated_caption
+            .clone()
+            .or_else(|| self.caption.clone())
+            .or_else(|| self.alt.clone())
+            .unwrap_or_else(|| {
+                let path = self.url.split(['?', '#']).next().unwrap_or(&self.url);
+                path.rsplit('/').next().unwrap_or(path).to_string()
+            })
+    }
+}
+
+/// Returns the images of a page's HTML with their URLs resolved against the page's.
+/// Inline `data:` images and repeated URLs are skipped.
+pub fn page_images(page_url: &str, html: &str) -> Vec<PageImage> {
+    let base = Url::parse(page_url).ok();
+    let mut seen = HashSet::new();
+    anyrag_html::extract_images(html)
+        .into_iter()
+        .filter_map(|image| {
+            let url = match &base {
+                Some(base) => base.join(&image.src).ok()?.to_string(),
+                None => image.src,
+            };
+            if url.starts_with("data:") || !seen.insert(url.clone()) {
+                return None;
+            }
+            Some(PageImage {
+                url,
+                alt: image.alt,
+                caption: image.caption,
+                generated_caption: None,
+            })
+        })
+        .collect()
+}
+
+/// Describes each image with `ai_provider`, at most `concurrency` at once. An image the
+/// model fails to describe keeps the descriptions from the page.
+pub async fn caption_images(
+    ai_provider: &dyn AiProvider,
+    system_prompt: Option<&str>,
+    images: &mut [PageImage],
+    concurrency: usize,
+) {
+    let system_prompt = system_prompt.unwrap_or(IMAGE_CAPTIONING_SYSTEM_PROMPT);
+    let captions: Vec<_> = stream::iter(images.iter())
+        .map(|image| async move {
+            let user_prompt = IMAGE_CAPTIONING_USER_PROMPT
+                .replace("{alt}", image.alt.as_deref().unwrap_or("(none)"));
+            ai_provider
+                .describe_image(system_prompt, &user_prompt, &image.url)
+                .await
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await;
+
+    for (image, caption) in images.iter_mut().zip(captions) {
+        match caption {
+            Ok(caption) if !caption.trim().is_empty() => {
+                image.generated_caption = Some(caption.trim().to_string());
+            }
+            Ok(_) => warn!("The model returned no caption for image {}", image.url),
+            Err(e) => warn!("Failed to caption image {}: {e}", image.url),
+        }
+    }
+}
+
+/// Stores the images as `IMAGE` metadata of a document. The metadata extraction of a
+/// document replaces all of its rows, so this runs after it.
+pub async fn store_image_metadata(
+    conn: &Connection,
+    document_id: &str,
+    owner_id: Option<&str>,
+    images: &[PageImage],
+) -> Result<(), turso::Error> {
+    for image in images {
+        conn.execute(
+            "INSERT INTO content_metadata (document_id, owner_id, metadata_type, metadata_subtype, metadata_value) VALUES (?, ?, ?, ?, ?)",
+            params![
+                document_id,
+                owner_id,
+                IMAGE_METADATA_TYPE,
+                image.url.as_str(),
+                image.description()
+            ],
+        )
+        .await?;
+    }
+    Ok(())
+}
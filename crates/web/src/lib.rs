@@ -4,20 +4,45 @@
 //! for the `anyrag` ecosystem. It implements the `Ingestor` trait.
 
 use anyrag::{
+    constants::DEFAULT_INGEST_CONCURRENCY,
     ingest::{
-        knowledge::{extract_and_store_metadata, restructure_with_llm, YamlContent},
-        IngestError, IngestionPrompts, IngestionResult, Ingestor,
+        chunk_without_restructuring, content_hash, embed_ingested_documents,
+        find_duplicate_document,
+        knowledge::{chunk_title, RestructuringOutcome, UNPARSED_CONTENT_TITLE},
+        source_url_prefix_pattern, ChunkingStrategy, IngestError, IngestionPrompts,
+        IngestionResult, Ingestor, InvalidChunkingError, KnowledgePipeline, Restructured,
     },
     providers::ai::AiProvider,
+    types::EmbeddingConfig,
     PromptError,
 };
 use async_trait::async_trait;
+use crawl::{fetch_robots_rules, normalize_url, CrawlOptions, CrawlScope};
+use freshness::{
+    fetch_if_modified, load_source_state, save_source_state, ConditionalFetch, Validators,
+    WebSourceState,
+};
+use images::{caption_images, page_images, store_image_metadata, ImageOptions, PageImage};
 use serde::{Deserialize, Serialize};
+use sitemap::{collect_sitemap_urls, SitemapOptions};
+use std::collections::{HashSet, VecDeque};
 use thiserror::Error;
 use tracing::{info, warn};
 use turso::{params, Database};
 use uuid::Uuid;
 
+pub mod crawl;
+pub mod freshness;
+pub mod headless;
+pub mod images;
+pub mod sitemap;
+pub mod tables;
+
+/// The HTML tag of tables, removed from the markdown once tables are extracted.
+const TABLE_TAG: &str = "table";
+/// URLs ending with this are fetched as markdown rather than HTML.
+const MARKDOWN_EXTENSION: &str = ".md";
+
 // --- Error Definitions ---
 
 #[derive(Error, Debug)]
@@ -38,6 +63,16 @@ pub enum WebIngestError {
     Internal(#[from] anyhow::Error),
     #[error("HTML processing error: {0}")]
     Html(String),
+    #[error("Headless browser rendering failed: {0}")]
+    Headless(String),
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+    #[error("Invalid crawl pattern {0}")]
+    InvalidCrawlPattern(String),
+    #[error("Invalid sitemap: {0}")]
+    Sitemap(String),
+    #[error(transparent)]
+    InvalidChunking(#[from] InvalidChunkingError),
 }
 
 impl From<WebIngestError> for IngestError {
@@ -48,6 +83,10 @@ impl From<WebIngestError> for IngestError {
                 IngestError::Parse(format!("Content unchanged for URL: {url}"))
             }
             WebIngestError::Fetch(e) => IngestError::Fetch(e.to_string()),
+            WebIngestError::InvalidUrl(_)
+            | WebIngestError::InvalidCrawlPattern(_)
+            | WebIngestError::Sitemap(_)
+            | WebIngestError::InvalidChunking(_) => IngestError::Parse(err.to_string()),
             _ => IngestError::Internal(anyhow::anyhow!(err.to_string())),
         }
     }
@@ -65,14 +104,76 @@ pub enum WebIngestStrategy<'a> {
         #[serde(borrow)]
         api_key: Option<&'a str>,
     },
+    /// Renders the page in a headless Chrome reachable at the DevTools `endpoint`,
+    /// so content produced by JavaScript is included.
+    Headless {
+        #[serde(borrow)]
+        endpoint: &'a str,
+    },
+}
+
+/// The extra information returned in `IngestionResult::metadata` as JSON.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct WebIngestMetadata {
+    /// The SQLite tables created from the page's HTML tables.
+    pub tables: Vec<String>,
+    /// The pages visited by a crawl, or ingested from a sitemap, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pages: Vec<String>,
+    /// The pages skipped because they did not change since their last ingestion.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unchanged: Vec<String>,
+}
+
+impl WebIngestMetadata {
+    fn is_empty(&self) -> bool {
+        self.tables.is_empty() && self.pages.is_empty() && self.unchanged.is_empty()
+    }
 }
 
 #[derive(Deserialize)]
 struct IngestSource<'a> {
-    url: &'a str,
+    /// The page to ingest, or the seed of a crawl. Exclusive with `sitemap_url`.
+    #[serde(default)]
+    url: Option<&'a str>,
+    /// A sitemap or sitemap index whose pages are all ingested.
+    #[serde(default)]
+    sitemap_url: Option<&'a str>,
+    /// Filters the pages of `sitemap_url`.
+    #[serde(default)]
+    sitemap: SitemapOptions,
     #[serde(default)]
     #[serde(borrow)]
     strategy: WebIngestStrategy<'a>,
+    /// Splits the fetched content before restructuring, one LLM call per chunk.
+    #[serde(default)]
+    chunking: Option<ChunkingStrategy>,
+    /// With `false`, the markdown is not restructured by the LLM: each chunk of it is
+    /// stored as its own document, as fetched.
+    #[serde(default = "default_restructure")]
+    restructure: bool,
+    /// Stores `<table>` elements as SQLite tables instead of flattening them into markdown.
+    #[serde(default)]
+    extract_tables: bool,
+    /// Follows same-site links from `url` and ingests every page reached.
+    #[serde(default)]
+    crawl: Option<CrawlOptions>,
+    /// Keeps the page's images in the markdown and stores them as `IMAGE` metadata of
+    /// its documents.
+    #[serde(default)]
+    images: Option<ImageOptions>,
+}
+
+fn default_restructure() -> bool {
+    true
+}
+
+/// The markdown of a fetched page, with the tables stored and the images found in it.
+#[derive(Debug, Default)]
+struct PageContent {
+    markdown: String,
+    tables: Vec<String>,
+    images: Vec<PageImage>,
 }
 
 // --- Core Pipeline Logic (Moved from anyrag-lib) ---
@@ -108,91 +209,210 @@ pub async fn fetch_web_content(
             let markdown = response.text().await.map_err(WebIngestError::Fetch)?;
             Ok(anyrag_html::clean_markdown_content(&markdown))
         }
+        WebIngestStrategy::Headless { endpoint } => {
+            let html = headless::render_with_headless_browser(endpoint, url).await?;
+            Ok(anyrag_html::html_to_clean_markdown(&html, None))
+        }
     }
 }
 
-async fn run_web_ingestion_pipeline(
+/// Fetches a page's raw HTML, rendering it in the headless browser for the
+/// `Headless` strategy.
+pub async fn fetch_page_html(
+    url: &str,
+    strategy: WebIngestStrategy<'_>,
+) -> Result<String, WebIngestError> {
+    match strategy {
+        WebIngestStrategy::Headless { endpoint } => {
+            headless::render_with_headless_browser(endpoint, url).await
+        }
+        _ => anyrag_html::fetch_html(url)
+            .await
+            .map_err(|e| WebIngestError::Html(e.to_string())),
+    }
+}
+
+/// Fetches a page's raw HTML, stores its tables as SQLite tables of `owner_id`, and
+/// returns the cleaned markdown of the rest of the page along with the names of the
+/// new tables.
+///
+/// The page is rendered in the headless browser for the `Headless` strategy and
+/// fetched as raw HTML otherwise.
+pub async fn fetch_web_content_with_tables(
     db: &Database,
-    ai_provider: &dyn AiProvider,
     url: &str,
     owner_id: Option<&str>,
-    prompts: IngestionPrompts<'_>,
-    web_ingest_strategy: WebIngestStrategy<'_>,
-) -> Result<Vec<String>, WebIngestError> {
-    // 1. Fetch and restructure content first.
-    let markdown_content = fetch_web_content(url, web_ingest_strategy).await?;
+    strategy: WebIngestStrategy<'_>,
+) -> Result<(String, Vec<String>), WebIngestError> {
+    info!("Fetching HTML and extracting tables from: {url}");
+    let html = fetch_page_html(url, strategy).await?;
+    store_tables_from_html(db, url, owner_id, &html).await
+}
 
-    let structured_yaml = restructure_with_llm(
-        ai_provider,
-        &markdown_content,
-        prompts.restructuring_system_prompt,
-    )
-    .await
-    .map_err(|e| WebIngestError::Internal(anyhow::anyhow!(e)))?;
+/// Stores the tables of already-fetched HTML as SQLite tables of `owner_id`, and
+/// returns the cleaned markdown of the rest of the page along with the names of the
+/// new tables.
+pub async fn store_tables_from_html(
+    db: &Database,
+    url: &str,
+    owner_id: Option<&str>,
+    html: &str,
+) -> Result<(String, Vec<String>), WebIngestError> {
+    let html_tables = anyrag_html::extract_tables(html);
+    let table_names = tables::store_html_tables(db, url, owner_id, &html_tables).await?;
 
-    if structured_yaml.trim().is_empty() {
-        warn!(
-            "LLM restructuring resulted in empty content for source: {}",
-            url
-        );
-        return Ok(vec![]);
-    }
+    // The tables are queryable on their own now, so keep them out of the markdown.
+    let remove_tags = [anyrag_html::DEFAULT_REMOVE_TAGS, &[TABLE_TAG]].concat();
+    let markdown = anyrag_html::html_to_clean_markdown(html, Some(&remove_tags));
+    Ok((markdown, table_names))
+}
 
-    let yaml_content: YamlContent = match serde_yaml::from_str(&structured_yaml) {
-        Ok(content) => content,
-        Err(e) => {
+async fn run_web_ingestion_pipeline(
+    db: &Database,
+    pipeline: &KnowledgePipeline<'_>,
+    url: &str,
+    markdown_content: String,
+    owner_id: Option<&str>,
+    chunking: Option<&ChunkingStrategy>,
+) -> Result<Vec<String>, WebIngestError> {
+    // 1. Restructure the fetched content first.
+    let chunks = match chunking {
+        Some(strategy) => strategy.chunker()?.chunk(&markdown_content),
+        None => vec![markdown_content],
+    };
+    let restructured = pipeline
+        .restructure(&chunks)
+        .await
+        .map_err(|e| WebIngestError::Internal(anyhow::anyhow!(e)))?;
+
+    let outcome = restructured.outcome();
+    // Use the title from the first section as the document title, or a fallback.
+    let (title, structured_yaml) = match restructured {
+        Restructured::Sections { content, yaml, .. } => {
+            let title = content
+                .sections
+                .first()
+                .map(|s| s.title.clone())
+                .unwrap_or_else(|| url.to_string());
+            (title, yaml)
+        }
+        Restructured::Unparsed(raw) => {
+            warn!("Failed to obtain valid structured YAML for source: {url}, storing it as-is.");
+            (UNPARSED_CONTENT_TITLE.to_string(), raw)
+        }
+        Restructured::Empty => {
             warn!(
-                "Failed to parse structured YAML for source: {}. Error: {}",
-                url, e
+                "LLM restructuring resulted in empty content for source: {}",
+                url
             );
-            // Even if parsing fails, we should store the raw structured YAML as a fallback.
-            let fallback_id = Uuid::new_v4().to_string();
-            let conn = db.connect()?;
-            conn.execute(
-                "INSERT INTO documents (id, owner_id, source_url, title, content) VALUES (?, ?, ?, ?, ?)",
-                params![fallback_id.clone(), owner_id, url, "Unparsed Content", structured_yaml],
-            ).await?;
-            return Ok(vec![fallback_id]);
+            return Ok(vec![]);
         }
     };
 
     // 2. Insert the entire structured content as a single document to enable versioning.
     let conn = db.connect()?;
     let doc_id = Uuid::new_v4().to_string();
-    // Use the title from the first section as the document title, or a fallback.
-    let title = yaml_content
-        .sections
-        .first()
-        .map(|s| s.title.clone())
-        .unwrap_or_else(|| url.to_string());
+    let hash = content_hash(&structured_yaml);
+    if find_duplicate_document(&conn, owner_id, &hash)
+        .await?
+        .is_some()
+    {
+        info!("Skipping duplicate content for source: {}", url);
+        return Ok(vec![]);
+    }
 
     conn.execute(
-        "INSERT INTO documents (id, owner_id, source_url, title, content) VALUES (?, ?, ?, ?, ?)",
+        "INSERT INTO documents (id, owner_id, source_url, title, content, content_hash) VALUES (?, ?, ?, ?, ?, ?)",
         params![
             doc_id.clone(),
             owner_id,
             url,
             title,
             structured_yaml.clone(),
+            hash,
         ],
     )
     .await?;
 
     // 3. Extract and store metadata for the new document.
-    extract_and_store_metadata(
-        &conn,
-        ai_provider,
-        &doc_id,
-        owner_id,
-        &structured_yaml,
-        prompts.metadata_extraction_system_prompt,
-    )
-    .await
-    .map_err(|e| WebIngestError::Internal(anyhow::anyhow!(e)))?;
+    pipeline
+        .extract_metadata(&conn, &doc_id, owner_id, &structured_yaml)
+        .await
+        .map_err(|e| WebIngestError::Internal(anyhow::anyhow!(e)))?;
+    if let Some(outcome) = outcome {
+        pipeline
+            .record_outcome(&conn, &doc_id, owner_id, outcome)
+            .await
+            .map_err(|e| WebIngestError::Internal(anyhow::anyhow!(e)))?;
+    }
 
     Ok(vec![doc_id])
 }
 
+/// Stores each chunk of a page's markdown as its own document, at the page's URL with
+/// a `#chunk_N` fragment, without restructuring it. The documents of an earlier
+/// ingestion of the page by the same owner are replaced.
+async fn store_unrestructured_page(
+    db: &Database,
+    pipeline: &KnowledgePipeline<'_>,
+    url: &str,
+    markdown_content: &str,
+    owner_id: Option<&str>,
+    chunking: Option<&ChunkingStrategy>,
+) -> Result<Vec<String>, WebIngestError> {
+    let chunks = chunk_without_restructuring(markdown_content, chunking)?;
+    if chunks.is_empty() {
+        warn!("No content to store for source: {url}");
+        return Ok(vec![]);
+    }
+
+    let conn = db.connect()?;
+    conn.execute(
+        "DELETE FROM documents WHERE owner_id IS ? AND (source_url = ? OR source_url LIKE ? ESCAPE '\\')",
+        params![owner_id, url, source_url_prefix_pattern(&format!("{url}#chunk_"))],
+    )
+    .await?;
+
+    let mut documents = Vec::new();
+    for (index, chunk) in chunks.into_iter().enumerate() {
+        let hash = content_hash(&chunk);
+        if find_duplicate_document(&conn, owner_id, &hash)
+            .await?
+            .is_some()
+        {
+            info!("Skipping duplicate chunk {index} of source: {url}");
+            continue;
+        }
+        let chunk_url = format!("{url}#chunk_{index}");
+        // Other owners may store the same page, so the id is derived from the owner too.
+        let doc_id = Uuid::new_v5(
+            &Uuid::NAMESPACE_URL,
+            format!("{}:{chunk_url}", owner_id.unwrap_or_default()).as_bytes(),
+        )
+        .to_string();
+        let title = chunk_title(&chunk).unwrap_or_else(|| url.to_string());
+        conn.execute(
+            "INSERT INTO documents (id, owner_id, source_url, title, content, content_hash) VALUES (?, ?, ?, ?, ?, ?)",
+            params![doc_id.clone(), owner_id, chunk_url, title, chunk.clone(), hash],
+        )
+        .await?;
+        documents.push((doc_id, chunk));
+    }
+
+    pipeline
+        .extract_metadata_of_each(&conn, &documents, owner_id)
+        .await
+        .map_err(|e| WebIngestError::Internal(anyhow::anyhow!(e)))?;
+    for (doc_id, _) in &documents {
+        pipeline
+            .record_outcome(&conn, doc_id, owner_id, RestructuringOutcome::Skipped)
+            .await
+            .map_err(|e| WebIngestError::Internal(anyhow::anyhow!(e)))?;
+    }
+
+    Ok(documents.into_iter().map(|(doc_id, _)| doc_id).collect())
+}
+
 // --- Ingestor Implementation ---
 
 /// The Ingestor implementation for public web URLs.
@@ -200,6 +420,9 @@ pub struct WebIngestor<'a> {
     db: &'a Database,
     ai_provider: &'a dyn AiProvider,
     prompts: IngestionPrompts<'a>,
+    concurrency: usize,
+    image_captioning: Option<(&'a dyn AiProvider, &'a str)>,
+    embedding: Option<&'a EmbeddingConfig>,
 }
 
 impl<'a> WebIngestor<'a> {
@@ -212,8 +435,370 @@ impl<'a> WebIngestor<'a> {
             db,
             ai_provider,
             prompts,
+            concurrency: DEFAULT_INGEST_CONCURRENCY,
+            image_captioning: None,
+            embedding: None,
         }
     }
+
+    /// Sets how many chunks of a page are restructured by the LLM at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the vision model, and its system prompt, that captions images for sources
+    /// with `"images": {"caption": true}`. Without it, the ingestor's own model is asked
+    /// with the default captioning prompt.
+    pub fn with_image_captioning(
+        mut self,
+        ai_provider: &'a dyn AiProvider,
+        system_prompt: &'a str,
+    ) -> Self {
+        self.image_captioning = Some((ai_provider, system_prompt));
+        self
+    }
+
+    /// Embeds the documents of each ingestion with the given model once they are
+    /// stored, instead of leaving them to `/embed/new`.
+    pub fn with_embedding(mut self, config: &'a EmbeddingConfig) -> Self {
+        self.embedding = Some(config);
+        self
+    }
+
+    /// Fetches a single page and returns its markdown with the tables and images
+    /// stored from it.
+    async fn fetch_page(
+        &self,
+        url: &str,
+        source: &IngestSource<'_>,
+        owner_id: Option<&str>,
+    ) -> Result<PageContent, WebIngestError> {
+        match source.strategy {
+            WebIngestStrategy::Jina { .. } => {
+                if source.extract_tables || source.images.is_some() {
+                    warn!("Table and image extraction are only supported by the raw_html and headless strategies, skipping them for: {url}");
+                }
+                Ok(PageContent {
+                    markdown: fetch_web_content(url, source.strategy).await?,
+                    ..Default::default()
+                })
+            }
+            _ if source.extract_tables || source.images.is_some() => {
+                let html = fetch_page_html(url, source.strategy).await?;
+                self.html_page_content(url, &html, source, owner_id).await
+            }
+            _ => Ok(PageContent {
+                markdown: fetch_web_content(url, source.strategy).await?,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Turns the already-fetched HTML of a crawled page into markdown, storing its
+    /// tables and images if requested. The Jina strategy fetches the markdown from the
+    /// reader.
+    async fn crawled_page_content(
+        &self,
+        url: &str,
+        html: &str,
+        source: &IngestSource<'_>,
+        owner_id: Option<&str>,
+    ) -> Result<PageContent, WebIngestError> {
+        match source.strategy {
+            WebIngestStrategy::Jina { .. } => Ok(PageContent {
+                markdown: fetch_web_content(url, source.strategy).await?,
+                ..Default::default()
+            }),
+            _ => self.html_page_content(url, html, source, owner_id).await,
+        }
+    }
+
+    /// Converts a page's HTML into markdown, storing its tables as SQLite tables and
+    /// collecting its images when the source asks for them.
+    async fn html_page_content(
+        &self,
+        url: &str,
+        html: &str,
+        source: &IngestSource<'_>,
+        owner_id: Option<&str>,
+    ) -> Result<PageContent, WebIngestError> {
+        let mut remove_tags = anyrag_html::DEFAULT_REMOVE_TAGS.to_vec();
+        let mut content = PageContent::default();
+        if source.extract_tables {
+            let html_tables = anyrag_html::extract_tables(html);
+            content.tables =
+                tables::store_html_tables(self.db, url, owner_id, &html_tables).await?;
+            // The tables are queryable on their own now, so keep them out of the markdown.
+            remove_tags.push(TABLE_TAG);
+        }
+        if source.images.is_some() {
+            content.images = page_images(url, html);
+            remove_tags.retain(|tag| *tag != images::IMAGE_TAG);
+        }
+        content.markdown = anyrag_html::html_to_clean_markdown(html, Some(&remove_tags));
+        Ok(content)
+    }
+
+    /// Captions the images of a page if the source asks for it, and stores them as
+    /// metadata of each of the page's new documents.
+    async fn store_page_images(
+        &self,
+        document_ids: &[String],
+        mut images: Vec<PageImage>,
+        source: &IngestSource<'_>,
+        owner_id: Option<&str>,
+    ) -> Result<(), WebIngestError> {
+        if images.is_empty() || document_ids.is_empty() {
+            return Ok(());
+        }
+        if source.images.is_some_and(|options| options.caption) {
+            let (ai_provider, system_prompt) = match self.image_captioning {
+                Some((ai_provider, system_prompt)) => (ai_provider, Some(system_prompt)),
+                None => (self.ai_provider, None),
+            };
+            caption_images(ai_provider, system_prompt, &mut images, self.concurrency).await;
+        }
+        let conn = self.db.connect()?;
+        for document_id in document_ids {
+            store_image_metadata(&conn, document_id, owner_id, &images).await?;
+        }
+        Ok(())
+    }
+
+    /// Fetches and ingests a single page, returning the new document ids and the
+    /// tables stored from it.
+    ///
+    /// With the `RawHtml` strategy the page is fetched with a conditional request, so a
+    /// page the server reports as not modified fails with `ContentUnchanged` before
+    /// anything is processed.
+    async fn ingest_page(
+        &self,
+        url: &str,
+        source: &IngestSource<'_>,
+        owner_id: Option<&str>,
+    ) -> Result<(Vec<String>, Vec<String>), WebIngestError> {
+        let previous = load_source_state(self.db, url, owner_id).await?;
+        let (content, validators) = match source.strategy {
+            WebIngestStrategy::RawHtml => {
+                let fetched =
+                    fetch_if_modified(url, previous.as_ref().map(|p| &p.validators)).await?;
+                let ConditionalFetch::Modified { body, validators } = fetched else {
+                    return Err(WebIngestError::ContentUnchanged(url.to_string()));
+                };
+                let content = match url.ends_with(MARKDOWN_EXTENSION) {
+                    true => PageContent {
+                        markdown: anyrag_html::clean_markdown_content(&body),
+                        ..Default::default()
+                    },
+                    false => self.html_page_content(url, &body, source, owner_id).await?,
+                };
+                (content, validators)
+            }
+            _ => (
+                self.fetch_page(url, source, owner_id).await?,
+                Validators::default(),
+            ),
+        };
+
+        let PageContent {
+            markdown,
+            tables,
+            images,
+        } = content;
+        let document_ids = self
+            .ingest_if_changed(url, markdown, source, owner_id, previous, validators)
+            .await?;
+        self.store_page_images(&document_ids, images, source, owner_id)
+            .await?;
+        Ok((document_ids, tables))
+    }
+
+    /// Runs the ingestion pipeline on a page's markdown unless it hashes the same as at
+    /// the last ingestion, in which case the LLM restructuring is skipped and
+    /// `ContentUnchanged` is returned. The page's validators and hash are remembered
+    /// either way.
+    async fn ingest_if_changed(
+        &self,
+        url: &str,
+        markdown_content: String,
+        source: &IngestSource<'_>,
+        owner_id: Option<&str>,
+        previous: Option<WebSourceState>,
+        validators: Validators,
+    ) -> Result<Vec<String>, WebIngestError> {
+        let hash = content_hash(&markdown_content);
+        let unchanged = previous.and_then(|p| p.content_hash).as_deref() == Some(hash.as_str());
+        let state = WebSourceState {
+            validators,
+            content_hash: Some(hash),
+        };
+        if unchanged {
+            save_source_state(self.db, url, owner_id, &state).await?;
+            info!("Content of {url} is unchanged, skipping restructuring.");
+            return Err(WebIngestError::ContentUnchanged(url.to_string()));
+        }
+
+        let pipeline = KnowledgePipeline::new(self.ai_provider, self.prompts)
+            .with_concurrency(self.concurrency);
+        let document_ids = if source.restructure {
+            run_web_ingestion_pipeline(
+                self.db,
+                &pipeline,
+                url,
+                markdown_content,
+                owner_id,
+                source.chunking.as_ref(),
+            )
+            .await?
+        } else {
+            store_unrestructured_page(
+                self.db,
+                &pipeline,
+                url,
+                &markdown_content,
+                owner_id,
+                source.chunking.as_ref(),
+            )
+            .await?
+        };
+        save_source_state(self.db, url, owner_id, &state).await?;
+        Ok(document_ids)
+    }
+
+    /// Ingests every page listed in a sitemap that passes the sitemap filters.
+    ///
+    /// Pages are ingested one at a time with progress logged; a page that fails is
+    /// logged and skipped so one broken link does not abort a large import.
+    async fn ingest_sitemap(
+        &self,
+        sitemap_url: &str,
+        source: &IngestSource<'_>,
+        owner_id: Option<&str>,
+    ) -> Result<(Vec<String>, WebIngestMetadata), WebIngestError> {
+        let urls = collect_sitemap_urls(sitemap_url, &source.sitemap).await?;
+        let total = urls.len();
+        let mut document_ids = Vec::new();
+        let mut metadata = WebIngestMetadata::default();
+
+        for (i, url) in urls.into_iter().enumerate() {
+            info!("[{}/{total}] Ingesting {url} from sitemap.", i + 1);
+            match self.ingest_page(&url, source, owner_id).await {
+                Ok((ids, tables)) => {
+                    document_ids.extend(ids);
+                    metadata.tables.extend(tables);
+                    metadata.pages.push(url);
+                }
+                Err(WebIngestError::ContentUnchanged(_)) => metadata.unchanged.push(url),
+                Err(e) => warn!("[{}/{total}] Failed to ingest {url}: {e}", i + 1),
+            }
+        }
+
+        info!(
+            "Sitemap {} ingested {} of {} pages and added {} documents.",
+            sitemap_url,
+            metadata.pages.len(),
+            total,
+            document_ids.len()
+        );
+        Ok((document_ids, metadata))
+    }
+
+    /// Ingests every page reachable from the seed URL within the crawl limits.
+    ///
+    /// Pages are visited breadth-first. A page that fails to fetch or ingest is logged
+    /// and skipped, except the seed itself, whose failure fails the crawl.
+    async fn crawl(
+        &self,
+        seed_url: &str,
+        source: &IngestSource<'_>,
+        options: &CrawlOptions,
+        owner_id: Option<&str>,
+    ) -> Result<(Vec<String>, WebIngestMetadata), WebIngestError> {
+        let seed = url::Url::parse(seed_url)
+            .map_err(|e| WebIngestError::InvalidUrl(format!("{seed_url}: {e}")))?;
+        let robots = fetch_robots_rules(&seed).await;
+        let scope = CrawlScope::new(seed.clone(), options, robots)?;
+
+        let mut queue = VecDeque::from([(seed.clone(), 0)]);
+        let mut seen = HashSet::from([normalize_url(&seed)]);
+        let mut document_ids = Vec::new();
+        let mut metadata = WebIngestMetadata::default();
+
+        while let Some((page_url, depth)) = queue.pop_front() {
+            if metadata.pages.len() >= options.max_pages {
+                break;
+            }
+            if !scope.robots().is_allowed(page_url.path()) {
+                info!("Skipping {page_url}, disallowed by robots.txt.");
+                continue;
+            }
+
+            info!("Crawling {page_url} (depth {depth}).");
+            let html = match fetch_page_html(page_url.as_str(), source.strategy).await {
+                Ok(html) => html,
+                Err(e) if depth == 0 => return Err(e),
+                Err(e) => {
+                    warn!("Failed to fetch crawled page {page_url}: {e}");
+                    continue;
+                }
+            };
+
+            if depth < options.max_depth {
+                for href in anyrag_html::extract_links(&html) {
+                    let Ok(link) = page_url.join(&href) else {
+                        continue;
+                    };
+                    if scope.allows(&link) && seen.insert(normalize_url(&link)) {
+                        queue.push_back((link, depth + 1));
+                    }
+                }
+            }
+
+            let page_ids = match self
+                .crawled_page_content(page_url.as_str(), &html, source, owner_id)
+                .await
+            {
+                Ok(content) => {
+                    metadata.tables.extend(content.tables);
+                    let previous = load_source_state(self.db, page_url.as_str(), owner_id).await?;
+                    match self
+                        .ingest_if_changed(
+                            page_url.as_str(),
+                            content.markdown,
+                            source,
+                            owner_id,
+                            previous,
+                            Validators::default(),
+                        )
+                        .await
+                    {
+                        Ok(ids) => self
+                            .store_page_images(&ids, content.images, source, owner_id)
+                            .await
+                            .map(|_| ids),
+                        Err(e) => Err(e),
+                    }
+                }
+                Err(e) => Err(e),
+            };
+            match page_ids {
+                Ok(ids) => document_ids.extend(ids),
+                Err(WebIngestError::ContentUnchanged(url)) => metadata.unchanged.push(url),
+                Err(e) if depth == 0 => return Err(e),
+                Err(e) => warn!("Failed to ingest crawled page {page_url}: {e}"),
+            }
+            metadata.pages.push(page_url.to_string());
+        }
+
+        info!(
+            "Crawl from {} visited {} pages and added {} documents.",
+            seed_url,
+            metadata.pages.len(),
+            document_ids.len()
+        );
+        Ok((document_ids, metadata))
+    }
 }
 
 #[async_trait]
@@ -226,21 +811,56 @@ impl<'a> Ingestor for WebIngestor<'a> {
         let ingest_source: IngestSource = serde_json::from_str(source)
             .map_err(|e| IngestError::Parse(format!("Invalid source JSON for web ingest: {e}")))?;
 
-        let document_ids = run_web_ingestion_pipeline(
-            self.db,
-            self.ai_provider,
-            ingest_source.url,
-            owner_id,
-            self.prompts,
-            ingest_source.strategy,
-        )
-        .await?;
+        let (url, document_ids, metadata) = match (ingest_source.url, ingest_source.sitemap_url) {
+            (Some(url), None) => {
+                let (document_ids, metadata) = match &ingest_source.crawl {
+                    Some(options) => self.crawl(url, &ingest_source, options, owner_id).await?,
+                    None => match self.ingest_page(url, &ingest_source, owner_id).await {
+                        Ok((document_ids, tables)) => {
+                            let metadata = WebIngestMetadata {
+                                tables,
+                                ..Default::default()
+                            };
+                            (document_ids, metadata)
+                        }
+                        Err(WebIngestError::ContentUnchanged(url)) => {
+                            let metadata = WebIngestMetadata {
+                                unchanged: vec![url],
+                                ..Default::default()
+                            };
+                            (vec![], metadata)
+                        }
+                        Err(e) => return Err(e.into()),
+                    },
+                };
+                (url, document_ids, metadata)
+            }
+            (None, Some(sitemap_url)) => {
+                let (document_ids, metadata) = self
+                    .ingest_sitemap(sitemap_url, &ingest_source, owner_id)
+                    .await?;
+                (sitemap_url, document_ids, metadata)
+            }
+            _ => {
+                return Err(IngestError::Parse(
+                    "Web ingest source needs exactly one of `url` or `sitemap_url`.".to_string(),
+                ))
+            }
+        };
+
+        if let Some(config) = self.embedding {
+            embed_ingested_documents(self.db, config, &document_ids).await;
+        }
 
+        let metadata = match metadata.is_empty() {
+            true => None,
+            false => Some(serde_json::to_string(&metadata).map_err(WebIngestError::from)?),
+        };
         Ok(IngestionResult {
-            source: ingest_source.url.to_string(),
+            source: url.to_string(),
             documents_added: document_ids.len(),
             document_ids,
-            metadata: None,
+            metadata,
         })
     }
 }
@@ -0,0 +1,241 @@
+//! # Sitemap Ingestion
+//!
+//! This module reads `sitemap.xml` files, following sitemap indexes to the sitemaps
+//! they list, and filters the URLs found by pattern and last modification date. It
+//! is the fastest way to load a documentation site: the site lists its own pages, so
+//! nothing has to be discovered by crawling. The pages are ingested by `WebIngestor`.
+
+use crate::{crawl::compile_patterns, WebIngestError};
+use chrono::NaiveDate;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tracing::{info, warn};
+
+/// The default maximum number of URLs ingested from a sitemap.
+pub const DEFAULT_MAX_URLS: usize = 500;
+
+/// How many sitemap indexes deep nested sitemaps are followed.
+const MAX_SITEMAP_DEPTH: usize = 3;
+/// The format of `modified_since`, and of the date part of `<lastmod>`.
+const DATE_FORMAT: &str = "%Y-%m-%d";
+/// The length of a `YYYY-MM-DD` date, the prefix of every W3C datetime.
+const DATE_LEN: usize = 10;
+
+/// The root element of a sitemap listing pages.
+const URLSET_TAG: &str = "urlset";
+/// The root element of a sitemap listing other sitemaps.
+const SITEMAP_INDEX_TAG: &str = "sitemapindex";
+/// The entry element of a `urlset`.
+const URL_TAG: &str = "url";
+/// The entry element of a `sitemapindex`.
+const SITEMAP_TAG: &str = "sitemap";
+const LOC_TAG: &str = "loc";
+const LASTMOD_TAG: &str = "lastmod";
+
+/// Filters applied to the URLs of a sitemap, as found in the `sitemap` field of the
+/// web source JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SitemapOptions {
+    /// Regexes matched against each URL; if any are given, a URL must match one.
+    #[serde(default)]
+    pub include_patterns: Vec<String>,
+    /// Regexes matched against each URL; URLs matching any of them are skipped.
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    /// Only URLs last modified on or after this `YYYY-MM-DD` date are ingested. URLs
+    /// without a `<lastmod>` are kept, since their age is unknown.
+    #[serde(default)]
+    pub modified_since: Option<String>,
+    /// The maximum number of URLs ingested.
+    #[serde(default = "default_max_urls")]
+    pub max_urls: usize,
+}
+
+impl Default for SitemapOptions {
+    fn default() -> Self {
+        Self {
+            include_patterns: Vec::new(),
+            exclude_patterns: Vec::new(),
+            modified_since: None,
+            max_urls: DEFAULT_MAX_URLS,
+        }
+    }
+}
+
+fn default_max_urls() -> usize {
+    DEFAULT_MAX_URLS
+}
+
+/// A `<url>` or `<sitemap>` entry of a sitemap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SitemapEntry {
+    pub loc: String,
+    pub lastmod: Option<String>,
+}
+
+/// A parsed sitemap file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sitemap {
+    /// A `<urlset>` listing pages.
+    UrlSet(Vec<SitemapEntry>),
+    /// A `<sitemapindex>` listing other sitemaps.
+    Index(Vec<SitemapEntry>),
+}
+
+/// Parses a sitemap or sitemap index. Entries without a `<loc>` are skipped.
+pub fn parse_sitemap(xml: &str) -> Result<Sitemap, WebIngestError> {
+    let document =
+        roxmltree::Document::parse(xml).map_err(|e| WebIngestError::Sitemap(e.to_string()))?;
+    let root = document.root_element();
+    let entry_tag = match root.tag_name().name() {
+        URLSET_TAG => URL_TAG,
+        SITEMAP_INDEX_TAG => SITEMAP_TAG,
+        other => {
+            return Err(WebIngestError::Sitemap(format!(
+            "Unexpected root element '{other}', expected '{URLSET_TAG}' or '{SITEMAP_INDEX_TAG}'."
+        )))
+        }
+    };
+
+    let entries = root
+        .children()
+        .filter(|node| node.tag_name().name() == entry_tag)
+        .filter_map(|node| {
+            Some(SitemapEntry {
+                loc: child_text(node, LOC_TAG)?,
+                lastmod: child_text(node, LASTMOD_TAG),
+            })
+        })
+        .collect();
+
+    match entry_tag {
+        URL_TAG => Ok(Sitemap::UrlSet(entries)),
+        _ => Ok(Sitemap::Index(entries)),
+    }
+}
+
+/// Returns the trimmed, non-empty text of the first `tag` child of `node`.
+fn child_text(node: roxmltree::Node<'_, '_>, tag: &str) -> Option<String> {
+    node.children()
+        .find(|child| child.tag_name().name() == tag)
+        .and_then(|child| child.text())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+/// Selects the sitemap entries to ingest.
+#[derive(Debug, Clone)]
+pub struct SitemapFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+    modified_since: Option<NaiveDate>,
+}
+
+impl SitemapFilter {
+    /// Compiles the patterns and parses the date of `options`.
+    pub fn new(options: &SitemapOptions) -> Result<Self, WebIngestError> {
+        let modified_since = options
+            .modified_since
+            .as_deref()
+            .map(|date| {
+                NaiveDate::parse_from_str(date, DATE_FORMAT).map_err(|e| {
+                    WebIngestError::Sitemap(format!(
+                        "Invalid modified_since '{date}', expected YYYY-MM-DD: {e}"
+                    ))
+                })
+            })
+            .transpose()?;
+        Ok(Self {
+            include: compile_patterns(&options.include_patterns)?,
+            exclude: compile_patterns(&options.exclude_patterns)?,
+            modified_since,
+        })
+    }
+
+    /// Returns whether the page of `entry` should be ingested.
+    pub fn accepts(&self, entry: &SitemapEntry) -> bool {
+        let loc = entry.loc.as_str();
+        if !self.include.is_empty() && !self.include.iter().any(|re| re.is_match(loc)) {
+            return false;
+        }
+        if self.exclude.iter().any(|re| re.is_match(loc)) {
+            return false;
+        }
+        let Some(since) = self.modified_since else {
+            return true;
+        };
+        match entry.lastmod.as_deref().and_then(lastmod_date) {
+            Some(date) => date >= since,
+            None => true,
+        }
+    }
+}
+
+/// Reads the date part of a W3C datetime like `2024-05-01T10:00:00+00:00`.
+fn lastmod_date(lastmod: &str) -> Option<NaiveDate> {
+    let date = lastmod.get(..DATE_LEN).unwrap_or(lastmod);
+    NaiveDate::parse_from_str(date, DATE_FORMAT).ok()
+}
+
+/// Fetches a sitemap, following sitemap indexes, and returns the page URLs that pass
+/// the filter, without duplicates and at most `max_urls` of them.
+///
+/// A nested sitemap that fails to load is logged and skipped; the top-level sitemap
+/// failing fails the whole call.
+pub async fn collect_sitemap_urls(
+    sitemap_url: &str,
+    options: &SitemapOptions,
+) -> Result<Vec<String>, WebIngestError> {
+    let filter = SitemapFilter::new(options)?;
+    let mut pending = vec![(sitemap_url.to_string(), 0)];
+    let mut seen_sitemaps = HashSet::new();
+    let mut seen_urls = HashSet::new();
+    let mut urls = Vec::new();
+
+    while let Some((url, depth)) = pending.pop() {
+        if !seen_sitemaps.insert(url.clone()) {
+            continue;
+        }
+        let sitemap = match fetch_sitemap(&url).await {
+            Ok(sitemap) => sitemap,
+            Err(e) if depth == 0 => return Err(e),
+            Err(e) => {
+                warn!("Skipping nested sitemap {url}: {e}");
+                continue;
+            }
+        };
+
+        match sitemap {
+            Sitemap::Index(sitemaps) if depth < MAX_SITEMAP_DEPTH => {
+                info!("Sitemap index {url} lists {} sitemaps.", sitemaps.len());
+                // Pushed in reverse so the sitemaps are read in document order.
+                pending.extend(sitemaps.into_iter().rev().map(|s| (s.loc, depth + 1)));
+            }
+            Sitemap::Index(_) => {
+                warn!("Not following sitemap index {url}, nested deeper than {MAX_SITEMAP_DEPTH}.");
+            }
+            Sitemap::UrlSet(entries) => {
+                for entry in entries {
+                    if urls.len() >= options.max_urls {
+                        break;
+                    }
+                    if filter.accepts(&entry) && seen_urls.insert(entry.loc.clone()) {
+                        urls.push(entry.loc);
+                    }
+                }
+            }
+        }
+    }
+
+    info!("Collected {} URLs from sitemap {sitemap_url}.", urls.len());
+    Ok(urls)
+}
+
+async fn fetch_sitemap(url: &str) -> Result<Sitemap, WebIngestError> {
+    info!("Fetching sitemap: {url}");
+    let response = reqwest::get(url).await?.error_for_status()?;
+    let body = response.text().await?;
+    parse_sitemap(&body)
+}
@@ -0,0 +1,197 @@
+//! # HTML Table Storage
+//!
+//! This module stores tables extracted from a web page as real SQLite tables, so
+//! tabular content like Wikipedia tables can be queried with text-to-SQL instead of
+//! being flattened into markdown.
+
+use crate::WebIngestError;
+use anyrag_html::HtmlTable;
+use tracing::info;
+use turso::{Database, Value as TursoValue};
+
+/// The prefix of every table created from a web page.
+const WEB_TABLE_PREFIX: &str = "web";
+/// The maximum length of the caption part of a table name.
+const MAX_CAPTION_SLUG_LEN: usize = 40;
+
+/// The SQLite type inferred for a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    Integer,
+    Real,
+    Text,
+}
+
+impl ColumnType {
+    fn as_sql(self) -> &'static str {
+        match self {
+            ColumnType::Integer => "INTEGER",
+            ColumnType::Real => "REAL",
+            ColumnType::Text => "TEXT",
+        }
+    }
+}
+
+/// Returns the SQLite table name for the `index`-th table of a page ingested by
+/// `owner_id`.
+///
+/// The name combines a short hash of the owner and URL, so re-ingesting a page replaces
+/// the owner's tables but never another owner's, with the caption so the model can
+/// tell tables apart.
+pub fn web_table_name(
+    url: &str,
+    owner_id: Option<&str>,
+    index: usize,
+    caption: Option<&str>,
+) -> String {
+    let key = format!("{}:{url}", owner_id.unwrap_or_default());
+    let digest = format!("{:x}", md5::compute(key.as_bytes()));
+    let base = format!("{WEB_TABLE_PREFIX}_{}_{index}", &digest[..8]);
+    match caption.map(sanitize_identifier).filter(|s| !s.is_empty()) {
+        Some(slug) => format!(
+            "{base}_{}",
+            slug.chars().take(MAX_CAPTION_SLUG_LEN).collect::<String>()
+        ),
+        None => base,
+    }
+}
+
+/// Creates one SQLite table per extracted table of a page ingested by `owner_id` and
+/// returns their names.
+///
+/// Existing tables with the same name, those of an earlier ingestion of the page by
+/// the same owner, are dropped first. Column types are inferred
+/// from the values: a column is `INTEGER` or `REAL` when every non-empty value parses
+/// as such (ignoring thousands separators), and `TEXT` otherwise.
+pub async fn store_html_tables(
+    db: &Database,
+    url: &str,
+    owner_id: Option<&str>,
+    tables: &[HtmlTable],
+) -> Result<Vec<String>, WebIngestError> {
+    let mut conn = db.connect()?;
+    let mut table_names = Vec::new();
+
+    for (index, table) in tables.iter().enumerate() {
+        let table_name = web_table_name(url, owner_id, index, table.caption.as_deref());
+        let columns = column_names(&table.headers);
+        let types: Vec<ColumnType> = (0..columns.len())
+            .map(|i| infer_column_type(table.rows.iter().map(|row| row[i].as_str())))
+            .collect();
+
+        let columns_def = columns
+            .iter()
+            .zip(&types)
+            .map(|(name, column_type)| format!("\"{name}\" {}", column_type.as_sql()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let insert_sql = format!(
+            "INSERT INTO \"{table_name}\" ({}) VALUES ({placeholders})",
+            columns
+                .iter()
+                .map(|c| format!("\"{c}\""))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        let tx = conn.transaction().await?;
+        tx.execute(&format!("DROP TABLE IF EXISTS \"{table_name}\""), ())
+            .await?;
+        tx.execute(
+            &format!("CREATE TABLE \"{table_name}\" ({columns_def})"),
+            (),
+        )
+        .await?;
+        for row in &table.rows {
+            let values: Vec<TursoValue> = row
+                .iter()
+                .zip(&types)
+                .map(|(value, column_type)| to_turso_value(value, *column_type))
+                .collect();
+            tx.execute(&insert_sql, values).await?;
+        }
+        tx.commit().await?;
+
+        info!(
+            "Stored table '{}' with {} rows from '{}'.",
+            table_name,
+            table.rows.len(),
+            url
+        );
+        table_names.push(table_name);
+    }
+
+    Ok(table_names)
+}
+
+/// Turns headers into unique SQL column names.
+fn column_names(headers: &[String]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::with_capacity(headers.len());
+    for (i, header) in headers.iter().enumerate() {
+        let mut name = sanitize_identifier(header);
+        if name.is_empty() {
+            name = format!("column_{}", i + 1);
+        }
+        let base = name.clone();
+        let mut suffix = 2;
+        while names.contains(&name) {
+            name = format!("{base}_{suffix}");
+            suffix += 1;
+        }
+        names.push(name);
+    }
+    names
+}
+
+/// Lowercases text and replaces runs of non-alphanumeric characters with `_`.
+fn sanitize_identifier(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+fn infer_column_type<'a>(values: impl Iterator<Item = &'a str>) -> ColumnType {
+    let mut column_type = ColumnType::Integer;
+    let mut has_value = false;
+    for value in values.map(normalize_number).filter(|v| !v.is_empty()) {
+        has_value = true;
+        if value.parse::<i64>().is_ok() {
+            continue;
+        }
+        if value.parse::<f64>().is_ok() {
+            column_type = ColumnType::Real;
+            continue;
+        }
+        return ColumnType::Text;
+    }
+    if !has_value {
+        return ColumnType::Text;
+    }
+    column_type
+}
+
+fn to_turso_value(value: &str, column_type: ColumnType) -> TursoValue {
+    if value.trim().is_empty() {
+        return TursoValue::Null;
+    }
+    let number = normalize_number(value);
+    match column_type {
+        ColumnType::Integer => number
+            .parse::<i64>()
+            .map(TursoValue::Integer)
+            .unwrap_or_else(|_| TursoValue::Text(value.to_string())),
+        ColumnType::Real => number
+            .parse::<f64>()
+            .map(TursoValue::Real)
+            .unwrap_or_else(|_| TursoValue::Text(value.to_string())),
+        ColumnType::Text => TursoValue::Text(value.to_string()),
+    }
+}
+
+/// Strips surrounding whitespace and thousands separators from a possible number.
+fn normalize_number(value: &str) -> String {
+    value.trim().replace(',', "")
+}
@@ -3,10 +3,27 @@
 //! This file contains tests for the web content fetching logic,
 //! specifically for the different `WebIngestStrategy` options.
 
-use anyrag_web::{fetch_web_content, WebIngestError, WebIngestStrategy};
+use anyrag::{
+    errors::PromptError,
+    providers::{ai::AiProvider, db::sqlite::SqliteProvider},
+};
+use anyrag_html::HtmlTable;
+use anyrag_web::{
+    crawl::{normalize_url, CrawlOptions, CrawlScope, RobotsRules},
+    fetch_web_content,
+    freshness::{
+        fetch_if_modified, load_source_state, save_source_state, ConditionalFetch, Validators,
+        WebSourceState,
+    },
+    images::{caption_images, page_images, store_image_metadata, PageImage},
+    sitemap::{collect_sitemap_urls, parse_sitemap, Sitemap, SitemapEntry, SitemapOptions},
+    tables::{store_html_tables, web_table_name},
+    WebIngestError, WebIngestStrategy,
+};
+use async_trait::async_trait;
 use std::sync::Once;
 use url::Url;
-use wiremock::matchers::{method, path};
+use wiremock::matchers::{header, method, path};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 static INIT: Once = Once::new();
@@ -87,3 +104,444 @@ async fn test_fetch_web_content_raw_html_error_status() {
         other => panic!("Expected Html error, but got {other:?}"),
     }
 }
+
+#[tokio::test]
+async fn test_store_html_tables() -> Result<(), Box<dyn std::error::Error>> {
+    // --- 1. Arrange ---
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    let url = "https://example.com/cities";
+    let tables = vec![HtmlTable {
+        caption: Some("Largest Cities".to_string()),
+        headers: vec![
+            "City".to_string(),
+            "Population".to_string(),
+            "Area (km2)".to_string(),
+        ],
+        rows: vec![
+            vec![
+                "Bangkok".to_string(),
+                "8,305,218".to_string(),
+                "1568.7".to_string(),
+            ],
+            vec![
+                "Chiang Mai".to_string(),
+                "127,240".to_string(),
+                String::new(),
+            ],
+        ],
+    }];
+
+    // --- 2. Act ---
+    let table_names = store_html_tables(&provider.db, url, Some("owner-a"), &tables).await?;
+    // Storing again replaces the table instead of appending rows.
+    store_html_tables(&provider.db, url, Some("owner-a"), &tables).await?;
+    // Another owner's tables of the same page are stored apart.
+    let other_names = store_html_tables(&provider.db, url, Some("owner-b"), &tables).await?;
+
+    // --- 3. Assert ---
+    assert_eq!(
+        table_names,
+        vec![web_table_name(
+            url,
+            Some("owner-a"),
+            0,
+            Some("Largest Cities")
+        )]
+    );
+    assert_ne!(table_names, other_names);
+    assert!(table_names[0].ends_with("_0_largest_cities"));
+
+    let conn = provider.db.connect()?;
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT city, population, area_km2 FROM \"{}\" ORDER BY population DESC",
+                table_names[0]
+            ),
+            (),
+        )
+        .await?;
+    let first = rows.next().await?.unwrap();
+    assert_eq!(first.get::<String>(0)?, "Bangkok");
+    assert_eq!(first.get::<i64>(1)?, 8_305_218);
+    assert_eq!(first.get::<f64>(2)?, 1568.7);
+    let second = rows.next().await?.unwrap();
+    assert_eq!(second.get::<i64>(1)?, 127_240);
+    assert!(rows.next().await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fetch_web_content_headless_unreachable_browser() {
+    // --- 1. Arrange ---
+    setup_tracing();
+    // Nothing listens on port 1, so connecting to the browser fails.
+    let strategy = WebIngestStrategy::Headless {
+        endpoint: "ws://127.0.0.1:1/devtools/browser/test",
+    };
+
+    // --- 2. Act ---
+    let result = fetch_web_content("https://example.com", strategy).await;
+
+    // --- 3. Assert ---
+    assert!(matches!(result, Err(WebIngestError::Headless(_))));
+}
+
+#[test]
+fn test_normalize_url() {
+    let normalize = |url: &str| normalize_url(&Url::parse(url).unwrap());
+
+    assert_eq!(
+        normalize("https://example.com/docs/"),
+        "https://example.com/docs"
+    );
+    assert_eq!(
+        normalize("https://example.com/docs#intro"),
+        "https://example.com/docs"
+    );
+    assert_eq!(normalize("https://example.com"), "https://example.com/");
+    // The query string identifies a different page.
+    assert_eq!(
+        normalize("https://example.com/search/?q=rag"),
+        "https://example.com/search?q=rag"
+    );
+}
+
+#[test]
+fn test_robots_rules() {
+    let robots_txt = r#"
+User-agent: googlebot
+Disallow: /
+
+User-agent: *
+Disallow: /private # internal pages
+Allow: /private/public
+Disallow:
+"#;
+    let rules = RobotsRules::parse(robots_txt);
+
+    assert!(rules.is_allowed("/docs"));
+    assert!(!rules.is_allowed("/private/notes"));
+    // The longer `Allow` wins over the shorter `Disallow`.
+    assert!(rules.is_allowed("/private/public/page"));
+
+    // A group for this crawler replaces the `*` group.
+    let rules =
+        RobotsRules::parse("User-agent: *\nDisallow: /\n\nUser-agent: anyrag\nDisallow: /tmp\n");
+    assert!(rules.is_allowed("/docs"));
+    assert!(!rules.is_allowed("/tmp/file"));
+}
+
+#[test]
+fn test_crawl_scope() {
+    let seed = Url::parse("https://example.com/guide/").unwrap();
+    let options = CrawlOptions {
+        include_patterns: vec!["/guide/".to_string()],
+        exclude_patterns: vec!["changelog".to_string()],
+        ..Default::default()
+    };
+    let robots = RobotsRules::parse("User-agent: *\nDisallow: /guide/drafts\n");
+    let scope = CrawlScope::new(seed, &options, robots).unwrap();
+    let allows = |url: &str| scope.allows(&Url::parse(url).unwrap());
+
+    assert!(allows("https://example.com/guide/setup"));
+    assert!(!allows("https://other.com/guide/setup"));
+    assert!(!allows("https://example.com/blog/post"));
+    assert!(!allows("https://example.com/guide/changelog"));
+    assert!(!allows("https://example.com/guide/drafts/next"));
+    assert!(!allows("mailto:team@example.com"));
+
+    let invalid = CrawlOptions {
+        include_patterns: vec!["(".to_string()],
+        ..Default::default()
+    };
+    let result = CrawlScope::new(
+        Url::parse("https://example.com").unwrap(),
+        &invalid,
+        RobotsRules::default(),
+    );
+    assert!(matches!(
+        result,
+        Err(WebIngestError::InvalidCrawlPattern(_))
+    ));
+}
+
+#[test]
+fn test_parse_sitemap() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>https://example.com/docs/intro</loc><lastmod>2024-05-01T10:00:00+00:00</lastmod></url>
+  <url><loc> https://example.com/docs/setup </loc></url>
+  <url><lastmod>2024-05-01</lastmod></url>
+</urlset>"#;
+
+    assert_eq!(
+        parse_sitemap(xml).unwrap(),
+        Sitemap::UrlSet(vec![
+            SitemapEntry {
+                loc: "https://example.com/docs/intro".to_string(),
+                lastmod: Some("2024-05-01T10:00:00+00:00".to_string()),
+            },
+            SitemapEntry {
+                loc: "https://example.com/docs/setup".to_string(),
+                lastmod: None,
+            },
+        ])
+    );
+    assert!(matches!(
+        parse_sitemap("<html><body></body></html>"),
+        Err(WebIngestError::Sitemap(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_collect_sitemap_urls_follows_index_and_filters() {
+    // --- 1. Arrange ---
+    setup_tracing();
+    let server = MockServer::start().await;
+    let base = server.uri();
+
+    let index = format!(
+        r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <sitemap><loc>{base}/sitemap-docs.xml</loc></sitemap>
+  <sitemap><loc>{base}/sitemap-missing.xml</loc></sitemap>
+</sitemapindex>"#
+    );
+    let docs = format!(
+        r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>{base}/docs/new</loc><lastmod>2024-06-01</lastmod></url>
+  <url><loc>{base}/docs/old</loc><lastmod>2023-01-01</lastmod></url>
+  <url><loc>{base}/docs/undated</loc></url>
+  <url><loc>{base}/docs/changelog</loc><lastmod>2024-06-01</lastmod></url>
+  <url><loc>{base}/blog/post</loc><lastmod>2024-06-01</lastmod></url>
+  <url><loc>{base}/docs/new</loc><lastmod>2024-06-01</lastmod></url>
+</urlset>"#
+    );
+    Mock::given(method("GET"))
+        .and(path("/sitemap.xml"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(index))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/sitemap-docs.xml"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(docs))
+        .mount(&server)
+        .await;
+
+    let options = SitemapOptions {
+        include_patterns: vec!["/docs/".to_string()],
+        exclude_patterns: vec!["changelog".to_string()],
+        modified_since: Some("2024-01-01".to_string()),
+        ..Default::default()
+    };
+
+    // --- 2. Act ---
+    let urls = collect_sitemap_urls(&format!("{base}/sitemap.xml"), &options)
+        .await
+        .unwrap();
+
+    // --- 3. Assert ---
+    // The missing nested sitemap is skipped, and the duplicate URL is listed once.
+    assert_eq!(
+        urls,
+        vec![format!("{base}/docs/new"), format!("{base}/docs/undated")]
+    );
+
+    let limited = SitemapOptions {
+        max_urls: 1,
+        ..Default::default()
+    };
+    let urls = collect_sitemap_urls(&format!("{base}/sitemap.xml"), &limited)
+        .await
+        .unwrap();
+    assert_eq!(urls.len(), 1);
+
+    let invalid_date = SitemapOptions {
+        modified_since: Some("last week".to_string()),
+        ..Default::default()
+    };
+    assert!(matches!(
+        collect_sitemap_urls(&format!("{base}/sitemap.xml"), &invalid_date).await,
+        Err(WebIngestError::Sitemap(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_fetch_if_modified_sends_validators() {
+    // --- 1. Arrange ---
+    setup_tracing();
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/page"))
+        .and(header("If-None-Match", "\"v1\""))
+        .respond_with(ResponseTemplate::new(304))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/page"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("ETag", "\"v1\"")
+                .insert_header("Last-Modified", "Wed, 01 May 2024 10:00:00 GMT")
+                .set_body_string("<html><body><p>Hello</p></body></html>"),
+        )
+        .mount(&server)
+        .await;
+    let url = format!("{}/page", server.uri());
+
+    // --- 2. Act ---
+    let first = fetch_if_modified(&url, None).await.unwrap();
+    let ConditionalFetch::Modified { body, validators } = first else {
+        panic!("Expected the first fetch to return the page.");
+    };
+    let second = fetch_if_modified(&url, Some(&validators)).await.unwrap();
+
+    // --- 3. Assert ---
+    assert!(body.contains("Hello"));
+    assert_eq!(
+        validators,
+        Validators {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: Some("Wed, 01 May 2024 10:00:00 GMT".to_string()),
+        }
+    );
+    assert!(matches!(second, ConditionalFetch::NotModified));
+}
+
+#[tokio::test]
+async fn test_web_source_state_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    // --- 1. Arrange ---
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let url = "https://example.com/page";
+    let state = WebSourceState {
+        validators: Validators {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+        },
+        content_hash: Some("abc".to_string()),
+    };
+
+    // --- 2. Act & 3. Assert ---
+    save_source_state(&provider.db, url, None, &state).await?;
+    // Without documents for the page, the remembered state is ignored.
+    assert_eq!(load_source_state(&provider.db, url, None).await?, None);
+
+    let conn = provider.db.connect()?;
+    conn.execute(
+        "INSERT INTO documents (id, owner_id, source_url, title, content) VALUES ('doc-1', NULL, ?, 'Page', 'content')",
+        turso::params![url],
+    )
+    .await?;
+    assert_eq!(
+        load_source_state(&provider.db, url, None).await?,
+        Some(state.clone())
+    );
+    // The state is kept per owner.
+    assert_eq!(
+        load_source_state(&provider.db, url, Some("user-1")).await?,
+        None
+    );
+
+    let updated = WebSourceState {
+        content_hash: Some("def".to_string()),
+        ..state
+    };
+    save_source_state(&provider.db, url, None, &updated).await?;
+    assert_eq!(
+        load_source_state(&provider.db, url, None).await?,
+        Some(updated)
+    );
+
+    Ok(())
+}
+
+/// A vision model that describes an image by its file name, and cannot see SVGs.
+#[derive(Debug, Clone)]
+struct FileNameVisionProvider;
+
+#[async_trait]
+impl AiProvider for FileNameVisionProvider {
+    async fn generate(&self, _system: &str, _user: &str) -> Result<String, PromptError> {
+        Ok(String::new())
+    }
+
+    async fn describe_image(
+        &self,
+        _system_prompt: &str,
+        _user_prompt: &str,
+        image_url: &str,
+    ) -> Result<String, PromptError> {
+        match image_url.ends_with(".svg") {
+            true => Err(PromptError::ImagesNotSupported),
+            false => Ok(format!(
+                "A diagram at {}",
+                image_url.rsplit('/').next().unwrap()
+            )),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_page_images_are_captioned_and_stored() -> Result<(), Box<dyn std::error::Error>> {
+    // --- 1. Arrange ---
+    setup_tracing();
+    let provider = SqliteProvider::new(":memory:").await?;
+    provider.initialize_schema().await?;
+    let html = r#"<html><body>
+        <figure><img src="img/flow.png" alt="Request flow"><figcaption>Figure 1</figcaption></figure>
+        <img src="/static/logo.svg" alt="Logo">
+        <img src="img/flow.png">
+        <img src="data:image/png;base64,AAAA">
+    </body></html>"#;
+
+    // --- 2. Act ---
+    let mut images = page_images("https://docs.example.com/guide/intro", html);
+    caption_images(&FileNameVisionProvider, None, &mut images, 2).await;
+
+    // --- 3. Assert ---
+    assert_eq!(
+        images,
+        vec![
+            PageImage {
+                url: "https://docs.example.com/guide/img/flow.png".to_string(),
+                alt: Some("Request flow".to_string()),
+                caption: Some("Figure 1".to_string()),
+                generated_caption: Some("A diagram at flow.png".to_string()),
+            },
+            PageImage {
+                url: "https://docs.example.com/static/logo.svg".to_string(),
+                alt: Some("Logo".to_string()),
+                caption: None,
+                generated_caption: None,
+            },
+        ]
+    );
+
+    let conn = provider.db.connect()?;
+    conn.execute(
+        "INSERT INTO documents (id, owner_id, source_url, title, content) VALUES ('doc-1', NULL, 'https://docs.example.com/guide/intro', 'Intro', 'content')",
+        (),
+    )
+    .await?;
+    store_image_metadata(&conn, "doc-1", None, &images).await?;
+    let mut rows = conn
+        .query(
+            "SELECT metadata_subtype, metadata_value FROM content_metadata WHERE document_id = 'doc-1' AND metadata_type = 'IMAGE' ORDER BY metadata_subtype",
+            (),
+        )
+        .await?;
+    let flow = rows.next().await?.expect("Expected the flow image");
+    assert_eq!(flow.get::<String>(1)?, "A diagram at flow.png");
+    let logo = rows.next().await?.expect("Expected the logo");
+    assert_eq!(
+        logo.get::<String>(0)?,
+        "https://docs.example.com/static/logo.svg"
+    );
+    assert_eq!(logo.get::<String>(1)?, "Logo");
+    Ok(())
+}